@@ -21,8 +21,11 @@ use std::time::Duration;
 use anyhow::anyhow;
 use flume::{Receiver, Sender};
 use log::{debug, error, warn};
+use openssl::x509::X509Ref;
 use tokio::runtime::Handle;
 
+#[cfg(not(any(feature = "vendored-aws-lc", feature = "vendored-boringssl")))]
+use g3_tls_cert::builder::OcspResponseBuilder;
 use g3_tls_cert::builder::{ServerCertBuilder, TlsServerCertBuilder};
 use g3_types::net::Host;
 
@@ -36,6 +39,8 @@ use crate::frontend::ResponseData;
 pub(crate) struct OpensslBackend {
     config: Arc<OpensslBackendConfig>,
     builder: ServerCertBuilder,
+    #[cfg(not(any(feature = "vendored-aws-lc", feature = "vendored-boringssl")))]
+    ocsp_builder: OcspResponseBuilder,
     stats: Arc<BackendStats>,
 }
 
@@ -48,6 +53,8 @@ impl OpensslBackend {
         Ok(OpensslBackend {
             config: Arc::clone(config),
             builder,
+            #[cfg(not(any(feature = "vendored-aws-lc", feature = "vendored-boringssl")))]
+            ocsp_builder: OcspResponseBuilder::default(),
             stats: Arc::clone(stats),
         })
     }
@@ -83,12 +90,29 @@ impl OpensslBackend {
             host: host.to_string(),
             cert: unsafe { String::from_utf8_unchecked(cert_pem) },
             key: unsafe { String::from_utf8_unchecked(key_pem) },
+            ocsp: self.build_ocsp(&cert)?,
             ttl: 300,
         };
         self.stats.add_request_ok();
         Ok(data)
     }
 
+    #[cfg(not(any(feature = "vendored-aws-lc", feature = "vendored-boringssl")))]
+    fn build_ocsp(&self, cert: &X509Ref) -> anyhow::Result<Option<Vec<u8>>> {
+        if !self.config.ocsp_stapling {
+            return Ok(None);
+        }
+        let ocsp = self
+            .ocsp_builder
+            .build_good(cert, &self.config.ca_cert, &self.config.ca_key)?;
+        Ok(Some(ocsp))
+    }
+
+    #[cfg(any(feature = "vendored-aws-lc", feature = "vendored-boringssl"))]
+    fn build_ocsp(&self, _cert: &X509Ref) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
     pub(crate) fn spawn(
         mut self,
         handle: &Handle,
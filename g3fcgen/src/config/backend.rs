@@ -34,12 +34,14 @@ pub(crate) struct OpensslBackendConfig {
     pub(crate) ca_cert: X509,
     pub(crate) ca_key: PKey<Private>,
     pub(crate) ca_cert_pem: Vec<u8>,
+    pub(crate) ocsp_stapling: bool,
     pub(crate) duration_stats: HistogramMetricsConfig,
 }
 
 pub(super) fn load_config(value: &Yaml) -> anyhow::Result<()> {
     if let Yaml::Hash(map) = value {
         let mut no_append_ca_cert = false;
+        let mut ocsp_stapling = false;
         let mut ca_cert_pem = Vec::new();
         let mut ca_cert: Option<X509> = None;
         let mut ca_key: Option<PKey<Private>> = None;
@@ -74,6 +76,17 @@ pub(super) fn load_config(value: &Yaml) -> anyhow::Result<()> {
                 no_append_ca_cert = g3_yaml::value::as_bool(v)?;
                 Ok(())
             }
+            "ocsp_stapling" => {
+                ocsp_stapling = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                #[cfg(any(feature = "vendored-aws-lc", feature = "vendored-boringssl"))]
+                if ocsp_stapling {
+                    return Err(anyhow!(
+                        "ocsp stapling is not supported with this ssl library"
+                    ));
+                }
+                Ok(())
+            }
             "duration_stats" | "duration_metrics" => {
                 duration_stats = g3_yaml::value::as_histogram_metrics_config(v).context(
                     format!("invalid histogram metrics config value for key {k}"),
@@ -98,6 +111,7 @@ pub(super) fn load_config(value: &Yaml) -> anyhow::Result<()> {
                 ca_cert,
                 ca_key,
                 ca_cert_pem,
+                ocsp_stapling,
                 duration_stats,
             }))
             .map_err(|_| anyhow!("duplicate backend config"))?;
@@ -28,12 +28,13 @@ pub(crate) struct ResponseData {
     pub(crate) host: String,
     pub(crate) cert: String,
     pub(crate) key: String,
+    pub(crate) ocsp: Option<Vec<u8>>,
     pub(crate) ttl: u32,
 }
 
 impl ResponseData {
     pub(crate) fn encode(&self) -> anyhow::Result<Vec<u8>> {
-        let map = vec![
+        let mut map = vec![
             (
                 ValueRef::String("host".into()),
                 ValueRef::String(self.host.as_str().into()),
@@ -51,6 +52,12 @@ impl ResponseData {
                 ValueRef::Integer(self.ttl.into()),
             ),
         ];
+        if let Some(ocsp) = &self.ocsp {
+            map.push((
+                ValueRef::String("ocsp".into()),
+                ValueRef::Binary(ocsp.as_slice()),
+            ));
+        }
         let mut buf = Vec::with_capacity(32);
         let v = ValueRef::Map(map);
         rmpv::encode::write_value_ref(&mut buf, &v)
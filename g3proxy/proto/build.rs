@@ -23,6 +23,8 @@ fn main() {
         .file("schema/resolver.capnp")
         .file("schema/escaper.capnp")
         .file("schema/server.capnp")
+        .file("schema/task.capnp")
+        .file("schema/bandwidth.capnp")
         .run()
         .unwrap();
 }
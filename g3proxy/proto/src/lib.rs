@@ -38,3 +38,11 @@ pub mod escaper_capnp {
 pub mod server_capnp {
     include!(concat!(env!("OUT_DIR"), "/server_capnp.rs"));
 }
+
+pub mod task_capnp {
+    include!(concat!(env!("OUT_DIR"), "/task_capnp.rs"));
+}
+
+pub mod bandwidth_capnp {
+    include!(concat!(env!("OUT_DIR"), "/bandwidth_capnp.rs"));
+}
@@ -0,0 +1,130 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UnixStream};
+
+use crate::config::audit::{ClamavPeer, ClamavServiceConfig};
+
+const INSTREAM_COMMAND: &[u8] = b"zINSTREAM\0";
+const INSTREAM_CHUNK_SIZE: usize = 16384;
+const RESPONSE_MAX_SIZE: u64 = 1024;
+
+/// Scan data by using the clamd INSTREAM command
+pub(crate) struct ClamavScanner {
+    config: Arc<ClamavServiceConfig>,
+}
+
+impl ClamavScanner {
+    pub(crate) fn new(config: Arc<ClamavServiceConfig>) -> Self {
+        ClamavScanner { config }
+    }
+
+    #[inline]
+    pub(crate) fn max_body_size(&self) -> usize {
+        self.config.max_body_size
+    }
+
+    #[inline]
+    pub(crate) fn bypass(&self) -> bool {
+        self.config.bypass
+    }
+
+    /// Scan the data, and return the signature name if virus found
+    pub(crate) async fn scan(&self, data: &[u8]) -> anyhow::Result<Option<String>> {
+        match &self.config.peer {
+            ClamavPeer::Tcp(addr) => {
+                let stream =
+                    tokio::time::timeout(self.config.connect_timeout, TcpStream::connect(addr))
+                        .await
+                        .map_err(|_| anyhow!("timeout to connect to clamd {addr}"))?
+                        .map_err(|e| anyhow!("failed to connect to clamd {addr}: {e}"))?;
+                self.scan_with_timeout(stream, data).await
+            }
+            ClamavPeer::Unix(path) => {
+                let stream =
+                    tokio::time::timeout(self.config.connect_timeout, UnixStream::connect(path))
+                        .await
+                        .map_err(|_| anyhow!("timeout to connect to clamd {}", path.display()))?
+                        .map_err(|e| {
+                            anyhow!("failed to connect to clamd {}: {e}", path.display())
+                        })?;
+                self.scan_with_timeout(stream, data).await
+            }
+        }
+    }
+
+    async fn scan_with_timeout<S>(&self, stream: S, data: &[u8]) -> anyhow::Result<Option<String>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        tokio::time::timeout(self.config.scan_timeout, instream_scan(stream, data))
+            .await
+            .map_err(|_| anyhow!("timeout to get clamd scan result"))?
+    }
+}
+
+async fn instream_scan<S>(mut stream: S, data: &[u8]) -> anyhow::Result<Option<String>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream
+        .write_all(INSTREAM_COMMAND)
+        .await
+        .map_err(|e| anyhow!("failed to send INSTREAM command: {e}"))?;
+    for chunk in data.chunks(INSTREAM_CHUNK_SIZE) {
+        stream
+            .write_all(&(chunk.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| anyhow!("failed to send chunk size: {e}"))?;
+        stream
+            .write_all(chunk)
+            .await
+            .map_err(|e| anyhow!("failed to send chunk data: {e}"))?;
+    }
+    stream
+        .write_all(&0u32.to_be_bytes())
+        .await
+        .map_err(|e| anyhow!("failed to send end chunk: {e}"))?;
+    stream
+        .flush()
+        .await
+        .map_err(|e| anyhow!("failed to flush data: {e}"))?;
+
+    let mut rsp = Vec::with_capacity(128);
+    (&mut stream)
+        .take(RESPONSE_MAX_SIZE)
+        .read_to_end(&mut rsp)
+        .await
+        .map_err(|e| anyhow!("failed to read scan result: {e}"))?;
+    parse_scan_result(&rsp)
+}
+
+fn parse_scan_result(rsp: &[u8]) -> anyhow::Result<Option<String>> {
+    let rsp = std::str::from_utf8(rsp).map_err(|e| anyhow!("invalid scan result: {e}"))?;
+    let rsp = rsp.trim_end_matches(['\0', '\n']);
+    let result = rsp.strip_prefix("stream:").unwrap_or(rsp).trim();
+    if result == "OK" {
+        Ok(None)
+    } else if let Some(name) = result.strip_suffix(" FOUND") {
+        Ok(Some(name.to_string()))
+    } else {
+        Err(anyhow!("clamd scan error: {result}"))
+    }
+}
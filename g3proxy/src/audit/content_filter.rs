@@ -0,0 +1,321 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use ahash::AHashSet;
+use memchr::memmem;
+use regex::RegexSet;
+
+use g3_types::acl::{AclAction, AclChildDomainRule, AclChildDomainRuleBuilder};
+use g3_types::net::Host;
+
+use crate::config::audit::ContentFilterConfig;
+
+struct UrlCategory {
+    name: Arc<str>,
+    exact_hosts: AHashSet<String>,
+    suffix_hosts: Option<AclChildDomainRule>,
+    url_regex: Option<RegexSet>,
+}
+
+impl UrlCategory {
+    fn matches(&self, host: &Host, url: &str) -> bool {
+        match host {
+            Host::Domain(domain) => {
+                if self.exact_hosts.contains(domain) {
+                    return true;
+                }
+                if let Some(rule) = &self.suffix_hosts {
+                    if rule.check(domain).0 {
+                        return true;
+                    }
+                }
+            }
+            Host::Ip(ip) => {
+                if self.exact_hosts.contains(&ip.to_string()) {
+                    return true;
+                }
+            }
+        }
+        self.url_regex
+            .as_ref()
+            .map(|set| set.is_match(url))
+            .unwrap_or(false)
+    }
+}
+
+pub(crate) struct ContentFilter {
+    url_categories: Vec<UrlCategory>,
+    blocked_content_types: Vec<String>,
+    blocked_body_keywords: Vec<memmem::Finder<'static>>,
+    body_inspect_max_size: usize,
+}
+
+impl ContentFilter {
+    pub(crate) fn new(config: &ContentFilterConfig) -> Self {
+        let mut url_categories = Vec::with_capacity(config.url_categories.len());
+        for c in &config.url_categories {
+            let suffix_hosts = if c.suffix_hosts.is_empty() {
+                None
+            } else {
+                let mut builder = AclChildDomainRuleBuilder::new(AclAction::Permit);
+                for domain in &c.suffix_hosts {
+                    builder.add_node(domain, AclAction::Forbid);
+                }
+                Some(builder.build())
+            };
+            url_categories.push(UrlCategory {
+                name: Arc::from(c.name.as_str()),
+                exact_hosts: c.exact_hosts.iter().cloned().collect(),
+                suffix_hosts,
+                url_regex: c.url_regex_set.clone(),
+            });
+        }
+
+        ContentFilter {
+            url_categories,
+            blocked_content_types: config.blocked_content_types.iter().cloned().collect(),
+            blocked_body_keywords: config
+                .blocked_body_keywords
+                .iter()
+                .map(|s| memmem::Finder::new(s.as_bytes()).into_owned())
+                .collect(),
+            body_inspect_max_size: config.body_inspect_max_size,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn has_url_rules(&self) -> bool {
+        !self.url_categories.is_empty()
+    }
+
+    #[inline]
+    pub(crate) fn has_body_rules(&self) -> bool {
+        !self.blocked_body_keywords.is_empty()
+    }
+
+    #[inline]
+    pub(crate) fn body_inspect_max_size(&self) -> usize {
+        self.body_inspect_max_size
+    }
+
+    /// Get the name of the first url category that matches the request
+    pub(crate) fn check_url(&self, host: &Host, path: &str) -> Option<Arc<str>> {
+        if self.url_categories.is_empty() {
+            return None;
+        }
+        let url = format!("{host}{path}");
+        self.url_categories
+            .iter()
+            .find(|c| c.matches(host, &url))
+            .map(|c| c.name.clone())
+    }
+
+    pub(crate) fn check_content_type(&self, value: &str) -> bool {
+        if self.blocked_content_types.is_empty() {
+            return false;
+        }
+        let mime = value
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let Some((main_type, _)) = mime.split_once('/') else {
+            return false;
+        };
+        self.blocked_content_types.iter().any(|rule| {
+            if let Some(prefix) = rule.strip_suffix("/*") {
+                prefix == main_type
+            } else {
+                rule.as_str() == mime
+            }
+        })
+    }
+
+    pub(crate) fn check_body(&self, body: &[u8]) -> bool {
+        self.blocked_body_keywords
+            .iter()
+            .any(|finder| finder.find(body).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+
+    use yaml_rust::YamlLoader;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!(
+            "g3proxy-content-filter-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn build_filter(doc: &str, dir: &Path) -> ContentFilter {
+        let v = YamlLoader::load_from_str(doc).unwrap();
+        let config = ContentFilterConfig::parse(&v[0], dir).unwrap();
+        ContentFilter::new(&config)
+    }
+
+    fn host(s: &str) -> Host {
+        Host::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn url_category() {
+        let dir = test_dir("url");
+        std::fs::write(
+            dir.join("exact.txt"),
+            "# exact hosts\n\nAds.Example.net\n10.0.0.1\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("suffix.txt"), ".tracker.example.org\n").unwrap();
+        std::fs::write(dir.join("regex.txt"), "/download/.*\\.exe$\n").unwrap();
+
+        let doc = r#"
+          url_categories:
+            - name: ads
+              exact: [doubleclick.example.com]
+              exact_file: exact.txt
+              suffix: [adserver.example.com]
+              suffix_file: suffix.txt
+            - name: malware
+              suffix: [example.com]
+              regex_file: regex.txt
+              regex:
+                - "^[^/]+/phish/"
+        "#;
+        let filter = build_filter(doc, &dir);
+        assert!(filter.has_url_rules());
+        assert!(!filter.has_body_rules());
+
+        let check = |h: &str, path: &str| filter.check_url(&host(h), path);
+
+        // exact hosts
+        assert_eq!(
+            check("doubleclick.example.com", "/").as_deref(),
+            Some("ads")
+        );
+        assert_eq!(check("ads.example.net", "/").as_deref(), Some("ads"));
+        assert_eq!(check("ADS.example.net", "/").as_deref(), Some("ads"));
+        assert_eq!(check("10.0.0.1", "/").as_deref(), Some("ads"));
+        assert_eq!(check("x.ads.example.net", "/"), None);
+        assert_eq!(check("10.0.0.2", "/"), None);
+
+        // suffix hosts match the domain itself and all its children
+        assert_eq!(check("adserver.example.com", "/").as_deref(), Some("ads"));
+        assert_eq!(
+            check("a.b.adserver.example.com", "/").as_deref(),
+            Some("ads")
+        );
+        assert_eq!(check("t.tracker.example.org", "/").as_deref(), Some("ads"));
+        assert_eq!(check("t.xtracker.example.org", "/"), None);
+        assert_eq!(check("example.org", "/"), None);
+
+        // the first matched category wins
+        assert_eq!(check("www.example.com", "/").as_deref(), Some("malware"));
+
+        // regex on host and path
+        assert_eq!(
+            check("files.example.net", "/download/setup.exe").as_deref(),
+            Some("malware")
+        );
+        assert_eq!(check("files.example.net", "/download/setup.exe.txt"), None);
+        assert_eq!(
+            check("login.example.net", "/phish/index.html").as_deref(),
+            Some("malware")
+        );
+        assert_eq!(check("login.example.net", "/a/phish/index.html"), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn no_url_rules() {
+        let filter = build_filter("body_inspect_max_size: 1024", &std::env::temp_dir());
+        assert!(!filter.has_url_rules());
+        assert_eq!(filter.body_inspect_max_size(), 1024);
+        assert_eq!(filter.check_url(&host("example.com"), "/"), None);
+        assert!(!filter.check_content_type("application/octet-stream"));
+        assert!(!filter.check_body(b"anything"));
+    }
+
+    #[test]
+    fn content_type() {
+        let doc = r#"
+          blocked_content_types:
+            - Application/X-MSDownload
+            - video/*
+        "#;
+        let filter = build_filter(doc, &std::env::temp_dir());
+
+        assert!(filter.check_content_type("application/x-msdownload"));
+        assert!(filter.check_content_type(" Application/X-MSDownload ; name=a.exe"));
+        assert!(filter.check_content_type("video/mp4"));
+        assert!(filter.check_content_type("VIDEO/webm; codecs=vp9"));
+
+        assert!(!filter.check_content_type("application/x-msdownload2"));
+        assert!(!filter.check_content_type("application/json"));
+        assert!(!filter.check_content_type("videos/mp4"));
+        assert!(!filter.check_content_type("video"));
+        assert!(!filter.check_content_type(""));
+    }
+
+    #[test]
+    fn body_keyword() {
+        let doc = r#"
+          blocked_body_keywords:
+            - "confidential"
+            - "EICAR-STANDARD"
+        "#;
+        let filter = build_filter(doc, &std::env::temp_dir());
+        assert!(filter.has_body_rules());
+        assert_eq!(filter.body_inspect_max_size(), 1 << 20);
+
+        assert!(filter.check_body(b"this is confidential data"));
+        assert!(filter.check_body(b"X5O!P%@AP[4\\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS"));
+        // keywords are case sensitive
+        assert!(!filter.check_body(b"this is Confidential data"));
+        assert!(!filter.check_body(b"confident"));
+        assert!(!filter.check_body(b""));
+    }
+
+    #[test]
+    fn invalid_config() {
+        let dir = std::env::temp_dir();
+        for doc in [
+            "url_categories: [{exact: [a.example.com]}]",
+            "url_categories: [{name: a, regex: ['(']}]",
+            "url_categories: [{name: a, exact_file: not-existed-file.txt}]",
+            "url_categories: [{name: a, unknown: b}]",
+            "blocked_content_types: [text]",
+            "blocked_body_keywords: ['']",
+            "[a]",
+        ] {
+            let v = YamlLoader::load_from_str(doc).unwrap();
+            assert!(ContentFilterConfig::parse(&v[0], &dir).is_err(), "{doc}");
+        }
+    }
+}
@@ -19,13 +19,23 @@ use std::sync::Arc;
 use slog::Logger;
 
 use g3_dpi::{
-    H1InterceptionConfig, H2InterceptionConfig, ProtocolInspectionConfig, ProtocolPortMap,
+    DnsInterceptionConfig, FtpInterceptionConfig, H1InterceptionConfig, H2InterceptionConfig,
+    ImapInterceptionConfig, Pop3InterceptionConfig, Protocol, ProtocolInspectionConfig,
+    ProtocolPortMap, SmtpInterceptionConfig, TlsClientFingerprint, WebSocketInterceptionConfig,
 };
 use g3_icap_client::reqmod::IcapReqmodClient;
 use g3_icap_client::respmod::IcapRespmodClient;
+use g3_types::acl::{AclAction, AclChildDomainRule, AclChildDomainRuleBuilder};
+use g3_types::net::{Host, HttpHeaderMap};
 
-use super::Auditor;
-use crate::config::audit::AuditorConfig;
+use super::{
+    Auditor, AuditorStats, ClamavScanner, ContentFilter, HarExporter, TlsBypassCache,
+    TlsInterceptionBypass, TrafficMirror,
+};
+use crate::config::audit::{
+    AuditorConfig, ProtocolPolicyAction, RespmodDecompressConfig, TlsEchPolicy,
+    TlsUpstreamCertErrorPolicy,
+};
 use crate::inspect::tls::TlsInterceptionContext;
 
 pub(crate) struct AuditHandle {
@@ -37,6 +47,14 @@ pub(crate) struct AuditHandle {
     intercept_logger: Logger,
     icap_reqmod_client: Option<IcapReqmodClient>,
     icap_respmod_client: Option<IcapRespmodClient>,
+    dns_blocked_domains: Option<AclChildDomainRule>,
+    content_filter: Option<Arc<ContentFilter>>,
+    clamav_scanner: Option<Arc<ClamavScanner>>,
+    har_exporter: Option<Arc<HarExporter>>,
+    tls_interception_bypass: Option<Arc<TlsInterceptionBypass>>,
+    tls_bypass_cache: Arc<TlsBypassCache>,
+    stats: Arc<AuditorStats>,
+    traffic_mirror: Option<Arc<TrafficMirror>>,
 }
 
 impl AuditHandle {
@@ -49,6 +67,16 @@ impl AuditHandle {
             .icap_respmod_service
             .as_ref()
             .map(|c| IcapRespmodClient::new(c.clone()));
+        let blocked_domains = &auditor.config.dns_interception.blocked_domains;
+        let dns_blocked_domains = if blocked_domains.is_empty() {
+            None
+        } else {
+            let mut builder = AclChildDomainRuleBuilder::new(AclAction::Permit);
+            for domain in blocked_domains {
+                builder.add_node(domain, AclAction::Forbid);
+            }
+            Some(builder.build())
+        };
         AuditHandle {
             auditor_config: auditor.config.clone(),
             server_tcp_portmap: auditor.server_tcp_portmap.clone(),
@@ -58,6 +86,14 @@ impl AuditHandle {
             intercept_logger: crate::log::intercept::get_logger(auditor.config.name()),
             icap_reqmod_client: icap_reqmod_service,
             icap_respmod_client: icap_respmod_service,
+            dns_blocked_domains,
+            content_filter: auditor.content_filter.clone(),
+            clamav_scanner: auditor.clamav_scanner.clone(),
+            har_exporter: auditor.har_exporter.clone(),
+            tls_interception_bypass: auditor.tls_interception_bypass.clone(),
+            tls_bypass_cache: auditor.tls_bypass_cache.clone(),
+            stats: auditor.stats.clone(),
+            traffic_mirror: auditor.traffic_mirror.clone(),
         }
     }
 
@@ -95,6 +131,48 @@ impl AuditHandle {
         self.tls_interception.clone()
     }
 
+    pub(crate) fn tls_fingerprint_blocked(&self, fingerprint: &TlsClientFingerprint) -> bool {
+        let config = &self.auditor_config;
+        config.tls_block_ja3.contains(fingerprint.ja3())
+            || config.tls_block_ja4.contains(fingerprint.ja4())
+    }
+
+    #[inline]
+    pub(crate) fn tls_interception_bypass(&self) -> Option<&TlsInterceptionBypass> {
+        self.tls_interception_bypass.as_deref()
+    }
+
+    #[inline]
+    pub(crate) fn tls_bypass_cache(&self) -> &TlsBypassCache {
+        &self.tls_bypass_cache
+    }
+
+    /// Check if TLS interception should be done for the ALPN protocol
+    pub(crate) fn tls_alpn_intercepted(&self, protocol: &[u8]) -> bool {
+        let set = &self.auditor_config.tls_intercept_alpn;
+        if set.is_empty() {
+            return true;
+        }
+        std::str::from_utf8(protocol)
+            .map(|s| set.contains(s))
+            .unwrap_or(false)
+    }
+
+    #[inline]
+    pub(crate) fn traffic_mirror(&self) -> Option<&Arc<TrafficMirror>> {
+        self.traffic_mirror.as_ref()
+    }
+
+    #[inline]
+    pub(crate) fn tls_ech_policy(&self) -> TlsEchPolicy {
+        self.auditor_config.tls_ech_policy
+    }
+
+    #[inline]
+    pub(crate) fn tls_upstream_cert_error_policy(&self) -> TlsUpstreamCertErrorPolicy {
+        self.auditor_config.tls_upstream_cert_error
+    }
+
     #[inline]
     pub(crate) fn log_uri_max_chars(&self) -> usize {
         self.auditor_config.log_uri_max_chars
@@ -110,6 +188,43 @@ impl AuditHandle {
         &self.auditor_config.h2_interception
     }
 
+    #[inline]
+    pub(crate) fn websocket_interception(&self) -> &WebSocketInterceptionConfig {
+        &self.auditor_config.websocket_interception
+    }
+
+    #[inline]
+    pub(crate) fn smtp_interception(&self) -> &SmtpInterceptionConfig {
+        &self.auditor_config.smtp_interception
+    }
+
+    #[inline]
+    pub(crate) fn imap_interception(&self) -> &ImapInterceptionConfig {
+        &self.auditor_config.imap_interception
+    }
+
+    #[inline]
+    pub(crate) fn pop3_interception(&self) -> &Pop3InterceptionConfig {
+        &self.auditor_config.pop3_interception
+    }
+
+    #[inline]
+    pub(crate) fn ftp_interception(&self) -> &FtpInterceptionConfig {
+        &self.auditor_config.ftp_interception
+    }
+
+    #[inline]
+    pub(crate) fn dns_interception(&self) -> &DnsInterceptionConfig {
+        &self.auditor_config.dns_interception
+    }
+
+    pub(crate) fn dns_domain_blocked(&self, domain: &str) -> bool {
+        self.dns_blocked_domains
+            .as_ref()
+            .map(|rule| rule.check(domain).0)
+            .unwrap_or(false)
+    }
+
     #[inline]
     pub(crate) fn icap_reqmod_client(&self) -> Option<&IcapReqmodClient> {
         self.icap_reqmod_client.as_ref()
@@ -120,6 +235,126 @@ impl AuditHandle {
         self.icap_respmod_client.as_ref()
     }
 
+    /// Check if the request should skip REQMOD according to its body size
+    pub(crate) fn icap_reqmod_bypassed(&self, content_length: Option<u64>) -> bool {
+        self.auditor_config
+            .icap_reqmod_body_limit
+            .map(|limit| limit.bypass(content_length))
+            .unwrap_or(false)
+    }
+
+    /// Check if the request should be blocked as its body is too large for REQMOD
+    pub(crate) fn icap_reqmod_body_blocked(&self, content_length: Option<u64>) -> bool {
+        self.auditor_config
+            .icap_reqmod_body_limit
+            .map(|limit| limit.block(content_length))
+            .unwrap_or(false)
+    }
+
+    /// Check if the response should skip RESPMOD according to its Content-Type and Content-Length
+    pub(crate) fn icap_respmod_bypassed(
+        &self,
+        content_type: Option<&str>,
+        content_length: Option<u64>,
+    ) -> bool {
+        if let Some(limit) = self.auditor_config.icap_respmod_body_limit {
+            if limit.bypass(content_length) {
+                return true;
+            }
+        }
+
+        let rules = &self.auditor_config.icap_respmod_bypass;
+        if rules.is_empty() {
+            return false;
+        }
+        let Some(content_type) = content_type else {
+            return false;
+        };
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        rules.iter().any(|r| r.matches(&mime, content_length))
+    }
+
+    /// Check if the response should be blocked as its body is too large for RESPMOD
+    pub(crate) fn icap_respmod_body_blocked(&self, content_length: Option<u64>) -> bool {
+        self.auditor_config
+            .icap_respmod_body_limit
+            .map(|limit| limit.block(content_length))
+            .unwrap_or(false)
+    }
+
+    #[inline]
+    pub(crate) fn icap_respmod_decompress(&self) -> Option<&RespmodDecompressConfig> {
+        self.auditor_config.icap_respmod_decompress.as_ref()
+    }
+
+    /// Check the protocol detected on the upstream port against the protocol policy
+    #[inline]
+    pub(crate) fn stats(&self) -> &Arc<AuditorStats> {
+        &self.stats
+    }
+
+    pub(crate) fn check_protocol_policy(
+        &self,
+        protocol: Protocol,
+        port: u16,
+    ) -> ProtocolPolicyAction {
+        let action = self
+            .auditor_config
+            .protocol_policy
+            .as_ref()
+            .map(|policy| policy.check(protocol, port))
+            .unwrap_or(ProtocolPolicyAction::Allow);
+        self.stats
+            .add_detected(protocol, action == ProtocolPolicyAction::Block);
+        action
+    }
+
+    #[inline]
+    pub(crate) fn content_filter(&self) -> Option<&ContentFilter> {
+        self.content_filter.as_deref()
+    }
+
+    /// Apply the http header rules to the request headers, return true if any header changed
+    pub(crate) fn rewrite_http_request_headers(
+        &self,
+        host: Option<&Host>,
+        path: &str,
+        headers: &mut HttpHeaderMap,
+    ) -> bool {
+        self.auditor_config
+            .http_request_header_rules
+            .iter()
+            .fold(false, |changed, r| r.apply(host, path, headers) || changed)
+    }
+
+    /// Apply the http header rules to the response headers, return true if any header changed
+    pub(crate) fn rewrite_http_response_headers(
+        &self,
+        host: Option<&Host>,
+        path: &str,
+        headers: &mut HttpHeaderMap,
+    ) -> bool {
+        self.auditor_config
+            .http_response_header_rules
+            .iter()
+            .fold(false, |changed, r| r.apply(host, path, headers) || changed)
+    }
+
+    #[inline]
+    pub(crate) fn clamav_scanner(&self) -> Option<&ClamavScanner> {
+        self.clamav_scanner.as_deref()
+    }
+
+    #[inline]
+    pub(crate) fn har_exporter(&self) -> Option<&HarExporter> {
+        self.har_exporter.as_deref()
+    }
+
     pub(crate) fn do_application_audit(&self) -> bool {
         use rand::distributions::Distribution;
 
@@ -0,0 +1,443 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use ahash::AHashMap;
+use base64::prelude::*;
+use http::Version;
+use log::{debug, warn};
+use serde_json::{json, Value};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use g3_types::net::HttpHeaderMap;
+
+use super::AuditorStats;
+use crate::config::audit::{HarExportConfig, HarExportSplit};
+
+const FLUSH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const FILE_CREATE_MAX_RETRY: usize = 1024;
+
+struct HarRecord {
+    task_id: Uuid,
+    entry: Value,
+}
+
+/// Export intercepted HTTP transactions as HAR files
+pub(crate) struct HarExporter {
+    sender: mpsc::Sender<HarRecord>,
+    body_max_size: usize,
+    stats: Arc<AuditorStats>,
+}
+
+impl HarExporter {
+    fn new(config: &HarExportConfig, stats: &Arc<AuditorStats>) -> (Self, HarFileWriter) {
+        let (sender, receiver) = mpsc::channel(config.queue_size);
+        let writer = HarFileWriter {
+            config: config.clone(),
+            receiver,
+            pending: AHashMap::new(),
+        };
+        let exporter = HarExporter {
+            sender,
+            body_max_size: config.body_max_size,
+            stats: stats.clone(),
+        };
+        (exporter, writer)
+    }
+
+    pub(crate) fn spawn(config: &HarExportConfig, stats: &Arc<AuditorStats>) -> Self {
+        let (exporter, writer) = HarExporter::new(config, stats);
+        tokio::spawn(writer.into_running());
+        exporter
+    }
+
+    #[inline]
+    pub(crate) fn body_max_size(&self) -> usize {
+        self.body_max_size
+    }
+
+    pub(crate) fn add_entry(&self, task_id: &Uuid, entry: Value) {
+        let record = HarRecord {
+            task_id: *task_id,
+            entry,
+        };
+        // drop the entry if the queue is full, so we won't block the proxy traffic
+        if self.sender.try_send(record).is_err() {
+            self.stats.add_har_export_dropped();
+            debug!("har export queue is full or closed, entry dropped");
+        }
+    }
+}
+
+pub(crate) fn har_version(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_09 => "HTTP/0.9",
+        Version::HTTP_10 => "HTTP/1.0",
+        Version::HTTP_11 => "HTTP/1.1",
+        Version::HTTP_2 => "HTTP/2",
+        Version::HTTP_3 => "HTTP/3",
+        _ => "",
+    }
+}
+
+pub(crate) fn har_headers(headers: &HttpHeaderMap) -> Value {
+    let mut list = Vec::new();
+    headers.for_each(|name, value| {
+        list.push(json!({
+            "name": value.original_name().unwrap_or(name.as_str()),
+            "value": value.to_str(),
+        }));
+    });
+    Value::Array(list)
+}
+
+/// Get the duration value in milliseconds
+pub(crate) fn har_time(dur: Duration) -> f64 {
+    dur.as_secs_f64() * 1000.0
+}
+
+/// Set the body text to a HAR content object
+pub(crate) fn har_set_content_text(content: &mut Value, body: &[u8]) {
+    match std::str::from_utf8(body) {
+        Ok(s) => {
+            content["text"] = json!(s);
+        }
+        Err(_) => {
+            content["text"] = json!(BASE64_STANDARD.encode(body));
+            content["encoding"] = json!("base64");
+        }
+    }
+}
+
+struct PendingFile {
+    path: PathBuf,
+    /// the encoded entries
+    entries: Vec<Vec<u8>>,
+    size: usize,
+    deadline: Instant,
+}
+
+impl PendingFile {
+    fn new(path: PathBuf, deadline: Instant) -> Self {
+        PendingFile {
+            path,
+            entries: Vec::new(),
+            size: 0,
+            deadline,
+        }
+    }
+
+    fn push(&mut self, entry: &Value) {
+        match serde_json::to_vec(entry) {
+            Ok(v) => {
+                self.size += v.len();
+                self.entries.push(v);
+            }
+            Err(e) => warn!(
+                "failed to encode har entry for {}: {e}",
+                self.path.display()
+            ),
+        }
+    }
+
+    fn is_full(&self, config: &HarExportConfig) -> bool {
+        self.entries.len() >= config.file_max_entries || self.size >= config.file_max_size
+    }
+
+    fn encode(&self) -> anyhow::Result<Vec<u8>> {
+        let creator = json!({
+            "name": crate::build::PKG_NAME,
+            "version": crate::build::VERSION,
+        });
+        let creator = serde_json::to_vec(&creator)?;
+
+        let mut buf = Vec::with_capacity(self.size + self.entries.len() + creator.len() + 64);
+        buf.extend_from_slice(b"{\"log\":{\"version\":\"1.2\",\"creator\":");
+        buf.extend_from_slice(&creator);
+        buf.extend_from_slice(b",\"entries\":[");
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                buf.push(b',');
+            }
+            buf.extend_from_slice(entry);
+        }
+        buf.extend_from_slice(b"]}}");
+        Ok(buf)
+    }
+}
+
+struct HarFileWriter {
+    config: HarExportConfig,
+    receiver: mpsc::Receiver<HarRecord>,
+    pending: AHashMap<String, PendingFile>,
+}
+
+impl HarFileWriter {
+    async fn into_running(mut self) {
+        let mut interval = tokio::time::interval(FLUSH_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                r = self.receiver.recv() => {
+                    match r {
+                        Some(record) => {
+                            if let Some(file) = self.add_record(record) {
+                                write_file(file).await;
+                            }
+                        }
+                        None => break,
+                    }
+                }
+                _ = interval.tick() => {
+                    self.flush_expired().await;
+                }
+            }
+        }
+
+        for (_, file) in self.pending.drain() {
+            write_file(file).await;
+        }
+    }
+
+    /// Add the record to the pending file, and return the file if it should be flushed early
+    fn add_record(&mut self, record: HarRecord) -> Option<PendingFile> {
+        let now = Instant::now();
+        let unix_now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let key = match self.config.split {
+            HarExportSplit::Task => {
+                let key = record.task_id.simple().to_string();
+                let deadline = now + self.config.task_idle_timeout;
+                let file = self.pending.entry(key.clone()).or_insert_with(|| {
+                    let name = format!("{}-{}.har", record.task_id, unix_now.as_millis());
+                    PendingFile::new(self.config.directory.join(name), deadline)
+                });
+                file.push(&record.entry);
+                file.deadline = deadline;
+                key
+            }
+            HarExportSplit::Window(window) => {
+                let window_secs = window.as_secs().max(1);
+                let start = unix_now.as_secs() - unix_now.as_secs() % window_secs;
+                let left = Duration::from_secs(start + window_secs) - unix_now;
+                let key = start.to_string();
+                let file = self.pending.entry(key.clone()).or_insert_with(|| {
+                    let path = self.config.directory.join(format!("{start}.har"));
+                    PendingFile::new(path, now + left)
+                });
+                file.push(&record.entry);
+                key
+            }
+        };
+
+        if self.pending.get(&key)?.is_full(&self.config) {
+            self.pending.remove(&key)
+        } else {
+            None
+        }
+    }
+
+    async fn flush_expired(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, f)| f.deadline <= now)
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in expired {
+            if let Some(file) = self.pending.remove(&key) {
+                write_file(file).await;
+            }
+        }
+    }
+}
+
+async fn write_file(file: PendingFile) {
+    if file.entries.is_empty() {
+        return;
+    }
+    let content = match file.encode() {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("failed to encode har file {}: {e}", file.path.display());
+            return;
+        }
+    };
+    let (mut f, path) = match create_unique_file(&file.path).await {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("failed to create har file {}: {e}", file.path.display());
+            return;
+        }
+    };
+    if let Err(e) = f.write_all(&content).await {
+        warn!("failed to write har file {}: {e}", path.display());
+        return;
+    }
+    if let Err(e) = f.flush().await {
+        warn!("failed to flush har file {}: {e}", path.display());
+    }
+}
+
+/// Create a new file at the path, or with a *-<seq>* suffix added to the file stem if it
+/// already exists, so files from early flushes or a reloaded auditor won't be overwritten
+async fn create_unique_file(path: &Path) -> io::Result<(File, PathBuf)> {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut path = path.to_path_buf();
+    for seq in 1..=FILE_CREATE_MAX_RETRY {
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+        {
+            Ok(f) => return Ok((f, path)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                path.set_file_name(format!("{stem}-{seq}.har"));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "too many files with the same name",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use g3_types::metrics::MetricsName;
+
+    fn test_config(directory: PathBuf) -> HarExportConfig {
+        let mut config = HarExportConfig::new(directory);
+        config.queue_size = 2;
+        config.file_max_entries = 3;
+        config
+    }
+
+    fn test_stats() -> Arc<AuditorStats> {
+        Arc::new(AuditorStats::new(&MetricsName::from_str("test").unwrap()))
+    }
+
+    fn test_record(task_id: Uuid) -> HarRecord {
+        HarRecord {
+            task_id,
+            entry: json!({"request": {"method": "GET"}}),
+        }
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("g3proxy-har-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn drop_on_full() {
+        let config = test_config(std::env::temp_dir());
+        let stats = test_stats();
+        let (exporter, writer) = HarExporter::new(&config, &stats);
+
+        let task_id = Uuid::new_v4();
+        for _ in 0..5 {
+            exporter.add_entry(&task_id, json!({}));
+        }
+        assert_eq!(stats.har_export_dropped(), 3);
+
+        drop(writer);
+        exporter.add_entry(&task_id, json!({}));
+        assert_eq!(stats.har_export_dropped(), 4);
+    }
+
+    #[test]
+    fn flush_on_max_entries() {
+        let config = test_config(std::env::temp_dir());
+        let stats = test_stats();
+        let (_exporter, mut writer) = HarExporter::new(&config, &stats);
+
+        let task_id = Uuid::new_v4();
+        assert!(writer.add_record(test_record(task_id)).is_none());
+        assert!(writer.add_record(test_record(task_id)).is_none());
+        let file = writer.add_record(test_record(task_id)).unwrap();
+        assert_eq!(file.entries.len(), 3);
+        assert!(writer.pending.is_empty());
+
+        assert!(writer.add_record(test_record(task_id)).is_none());
+        assert_eq!(writer.pending.len(), 1);
+    }
+
+    #[test]
+    fn flush_on_max_size() {
+        let mut config = test_config(std::env::temp_dir());
+        config.file_max_entries = usize::MAX;
+        config.file_max_size = 50;
+        let stats = test_stats();
+        let (_exporter, mut writer) = HarExporter::new(&config, &stats);
+
+        let task_id = Uuid::new_v4();
+        assert!(writer.add_record(test_record(task_id)).is_none());
+        let file = writer.add_record(test_record(task_id)).unwrap();
+        assert_eq!(file.entries.len(), 2);
+        assert!(file.size >= 50);
+    }
+
+    #[test]
+    fn encode() {
+        let mut file = PendingFile::new(PathBuf::from("test.har"), Instant::now());
+        file.push(&json!({"a": 1}));
+        file.push(&json!({"b": 2}));
+        let content = file.encode().unwrap();
+        let doc: Value = serde_json::from_slice(&content).unwrap();
+        assert_eq!(doc["log"]["version"], "1.2");
+        assert_eq!(doc["log"]["entries"], json!([{"a": 1}, {"b": 2}]));
+    }
+
+    #[tokio::test]
+    async fn unique_file_name() {
+        let dir = test_dir("unique");
+        let path = dir.join("100.har");
+
+        for _ in 0..3 {
+            let mut file = PendingFile::new(path.clone(), Instant::now());
+            file.push(&json!({}));
+            write_file(file).await;
+        }
+
+        assert!(dir.join("100.har").exists());
+        assert!(dir.join("100-1.har").exists());
+        assert!(dir.join("100-2.har").exists());
+        assert!(!dir.join("100-3.har").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -14,12 +14,13 @@
  * limitations under the License.
  */
 
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use anyhow::Context;
+use log::warn;
 
 use g3_dpi::ProtocolPortMap;
-use g3_icap_client::IcapServiceClient;
+use g3_icap_client::IcapServiceGroup;
 use g3_types::metrics::MetricsName;
 
 use crate::config::audit::AuditorConfig;
@@ -30,17 +31,47 @@ pub use ops::load_all;
 pub(crate) use ops::reload;
 
 mod registry;
-pub(crate) use registry::{get_names, get_or_insert_default};
+pub(crate) use registry::{foreach as foreach_auditor, get_names, get_or_insert_default};
 
 mod handle;
 pub(crate) use handle::AuditHandle;
 
+mod content_filter;
+pub(crate) use content_filter::ContentFilter;
+
+mod clamav;
+pub(crate) use clamav::ClamavScanner;
+
+pub(crate) mod har;
+use har::HarExporter;
+
+mod tls_bypass;
+pub(crate) use tls_bypass::{TlsBypassCache, TlsInterceptionBypass};
+
+mod traffic_mirror;
+pub(crate) use traffic_mirror::{TrafficMirror, TrafficMirrorDirection};
+
+mod stats;
+pub(crate) use stats::{
+    AuditIcapSnapshot, AuditInspectSnapshot, AuditInterceptionSnapshot, AuditProtocolSnapshot,
+    AuditorStats,
+};
+
 pub(crate) struct Auditor {
     config: Arc<AuditorConfig>,
     server_tcp_portmap: Arc<ProtocolPortMap>,
     client_tcp_portmap: Arc<ProtocolPortMap>,
-    icap_reqmod_service: Option<Arc<IcapServiceClient>>,
-    icap_respmod_service: Option<Arc<IcapServiceClient>>,
+    icap_reqmod_service: Option<Arc<IcapServiceGroup>>,
+    icap_respmod_service: Option<Arc<IcapServiceGroup>>,
+    content_filter: Option<Arc<ContentFilter>>,
+    clamav_scanner: Option<Arc<ClamavScanner>>,
+    har_exporter: Option<Arc<HarExporter>>,
+    tls_interception_bypass: Option<Arc<TlsInterceptionBypass>>,
+    tls_bypass_cache: Arc<TlsBypassCache>,
+    traffic_mirror: Option<Arc<TrafficMirror>>,
+    stats: Arc<AuditorStats>,
+    /// the handle shared by all users that select this auditor
+    user_handle: OnceLock<Option<Arc<AuditHandle>>>,
 }
 
 impl Auditor {
@@ -50,47 +81,110 @@ impl Auditor {
     }
 
     fn new_with_config(config: AuditorConfig) -> Arc<Self> {
+        let stats = Arc::new(AuditorStats::new(config.name()));
         let server_tcp_portmap = Arc::new(config.server_tcp_portmap.clone());
         let client_tcp_portmap = Arc::new(config.client_tcp_portmap.clone());
         let icap_reqmod_service = config
             .icap_reqmod_service
             .as_ref()
-            .map(|config| Arc::new(IcapServiceClient::new(config.clone())));
+            .map(|config| Arc::new(IcapServiceGroup::new(config.clone())));
         let icap_respmod_service = config
             .icap_respmod_service
             .as_ref()
-            .map(|config| Arc::new(IcapServiceClient::new(config.clone())));
+            .map(|config| Arc::new(IcapServiceGroup::new(config.clone())));
+        let content_filter = config
+            .content_filter
+            .as_ref()
+            .map(|config| Arc::new(ContentFilter::new(config)));
+        let clamav_scanner = config
+            .clamav_service
+            .as_ref()
+            .map(|config| Arc::new(ClamavScanner::new(config.clone())));
+        let har_exporter = config
+            .har_export
+            .as_ref()
+            .map(|config| Arc::new(HarExporter::spawn(config, &stats)));
+        let tls_interception_bypass = config
+            .tls_interception_bypass
+            .as_ref()
+            .map(|config| Arc::new(TlsInterceptionBypass::new(config)));
+        let traffic_mirror = config
+            .traffic_mirror
+            .as_ref()
+            .map(|config| Arc::new(TrafficMirror::spawn(config)));
         let auditor = Auditor {
             config: Arc::new(config),
             server_tcp_portmap,
             client_tcp_portmap,
             icap_reqmod_service,
             icap_respmod_service,
+            content_filter,
+            clamav_scanner,
+            har_exporter,
+            tls_interception_bypass,
+            tls_bypass_cache: Arc::new(TlsBypassCache::default()),
+            traffic_mirror,
+            stats,
+            user_handle: OnceLock::new(),
         };
         Arc::new(auditor)
     }
 
     fn reload(&self, config: AuditorConfig) -> Arc<Self> {
+        let stats = self.stats.clone();
         let server_tcp_portmap = Arc::new(config.server_tcp_portmap.clone());
         let client_tcp_portmap = Arc::new(config.client_tcp_portmap.clone());
         let icap_reqmod_service = config
             .icap_reqmod_service
             .as_ref()
-            .map(|config| Arc::new(IcapServiceClient::new(config.clone())));
+            .map(|config| Arc::new(IcapServiceGroup::new(config.clone())));
         let icap_respmod_service = config
             .icap_respmod_service
             .as_ref()
-            .map(|config| Arc::new(IcapServiceClient::new(config.clone())));
+            .map(|config| Arc::new(IcapServiceGroup::new(config.clone())));
+        let content_filter = config
+            .content_filter
+            .as_ref()
+            .map(|config| Arc::new(ContentFilter::new(config)));
+        let clamav_scanner = config
+            .clamav_service
+            .as_ref()
+            .map(|config| Arc::new(ClamavScanner::new(config.clone())));
+        let har_exporter = config
+            .har_export
+            .as_ref()
+            .map(|config| Arc::new(HarExporter::spawn(config, &stats)));
+        let tls_interception_bypass = config
+            .tls_interception_bypass
+            .as_ref()
+            .map(|config| Arc::new(TlsInterceptionBypass::new(config)));
+        let traffic_mirror = config
+            .traffic_mirror
+            .as_ref()
+            .map(|config| Arc::new(TrafficMirror::spawn(config)));
         let auditor = Auditor {
             config: Arc::new(config),
             server_tcp_portmap,
             client_tcp_portmap,
             icap_reqmod_service,
             icap_respmod_service,
+            content_filter,
+            clamav_scanner,
+            har_exporter,
+            tls_interception_bypass,
+            tls_bypass_cache: self.tls_bypass_cache.clone(),
+            traffic_mirror,
+            stats,
+            user_handle: OnceLock::new(),
         };
         Arc::new(auditor)
     }
 
+    #[inline]
+    pub(crate) fn stats(&self) -> &Arc<AuditorStats> {
+        &self.stats
+    }
+
     pub(crate) fn build_handle(&self) -> anyhow::Result<Arc<AuditHandle>> {
         let mut handle = AuditHandle::new(self);
 
@@ -103,14 +197,38 @@ impl Auditor {
                 .tls_interception_client
                 .build()
                 .context("failed to build tls client config")?;
+            let untrusted_cert_agent = match &self.config.tls_untrusted_cert_agent {
+                Some(config) => Some(
+                    config
+                        .spawn_cert_agent()
+                        .context("failed to spawn untrusted cert generator task")?,
+                ),
+                None => None,
+            };
             let ctx = TlsInterceptionContext::new(
                 cert_agent,
+                untrusted_cert_agent,
                 client_config,
-                self.config.tls_stream_dump,
+                self.config.tls_stream_dump.clone(),
             )?;
             handle.set_tls_interception(ctx);
         }
 
         Ok(Arc::new(handle))
     }
+
+    pub(crate) fn user_handle(&self) -> Option<Arc<AuditHandle>> {
+        self.user_handle
+            .get_or_init(|| match self.build_handle() {
+                Ok(handle) => Some(handle),
+                Err(e) => {
+                    warn!(
+                        "failed to build user audit handle for auditor {}: {e:?}",
+                        self.config.name()
+                    );
+                    None
+                }
+            })
+            .clone()
+    }
 }
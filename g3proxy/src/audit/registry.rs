@@ -42,6 +42,16 @@ pub(super) fn del(name: &MetricsName) {
     if let Some(_old_auditor) = ht.remove(name) {}
 }
 
+pub(crate) fn foreach<F>(mut f: F)
+where
+    F: FnMut(&MetricsName, &Arc<Auditor>),
+{
+    let ht = RUNTIME_AUDITOR_REGISTRY.lock().unwrap();
+    for (name, auditor) in ht.iter() {
+        f(name, auditor)
+    }
+}
+
 pub(crate) fn get_names() -> HashSet<MetricsName> {
     let mut names = HashSet::new();
     let ht = RUNTIME_AUDITOR_REGISTRY.lock().unwrap();
@@ -0,0 +1,217 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use ahash::AHashMap;
+
+use g3_dpi::Protocol;
+use g3_types::metrics::MetricsName;
+use g3_types::stats::StatId;
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct AuditProtocolSnapshot {
+    pub(crate) detected: u64,
+    pub(crate) blocked: u64,
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct AuditInspectSnapshot {
+    pub(crate) timeout: u64,
+    pub(crate) oversize: u64,
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct AuditIcapSnapshot {
+    pub(crate) adapted: u64,
+    pub(crate) no_modification: u64,
+    pub(crate) blocked: u64,
+    pub(crate) error: u64,
+    pub(crate) bypassed: u64,
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct AuditInterceptionSnapshot {
+    pub(crate) failed: u64,
+    pub(crate) bypassed: u64,
+    pub(crate) upstream_handshake: u64,
+    pub(crate) upstream_session_resumed: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct AuditIcapStats {
+    adapted: AtomicU64,
+    no_modification: AtomicU64,
+    blocked: AtomicU64,
+    error: AtomicU64,
+    bypassed: AtomicU64,
+}
+
+impl AuditIcapStats {
+    /// the message is modified by the icap server
+    pub(crate) fn add_adapted(&self) {
+        self.adapted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// the icap server replied with 204 or the original message is sent directly
+    pub(crate) fn add_no_modification(&self) {
+        self.no_modification.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// the icap server replied with a http error response
+    pub(crate) fn add_blocked(&self) {
+        self.blocked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_error(&self) {
+        self.error.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// the icap service is not available and the bypass is enabled
+    pub(crate) fn add_bypassed(&self) {
+        self.bypassed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> AuditIcapSnapshot {
+        AuditIcapSnapshot {
+            adapted: self.adapted.load(Ordering::Relaxed),
+            no_modification: self.no_modification.load(Ordering::Relaxed),
+            blocked: self.blocked.load(Ordering::Relaxed),
+            error: self.error.load(Ordering::Relaxed),
+            bypassed: self.bypassed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub(crate) struct AuditorStats {
+    id: StatId,
+    name: MetricsName,
+    protocols: Mutex<AHashMap<&'static str, AuditProtocolSnapshot>>,
+    inspect_timeout: AtomicU64,
+    inspect_oversize: AtomicU64,
+    icap_reqmod: AuditIcapStats,
+    icap_respmod: AuditIcapStats,
+    tls_interception_failed: AtomicU64,
+    tls_interception_bypassed: AtomicU64,
+    tls_upstream_handshake: AtomicU64,
+    tls_upstream_session_resumed: AtomicU64,
+    har_export_dropped: AtomicU64,
+}
+
+impl AuditorStats {
+    pub(super) fn new(name: &MetricsName) -> Self {
+        AuditorStats {
+            id: StatId::new(),
+            name: name.clone(),
+            protocols: Mutex::new(AHashMap::new()),
+            inspect_timeout: AtomicU64::new(0),
+            inspect_oversize: AtomicU64::new(0),
+            icap_reqmod: AuditIcapStats::default(),
+            icap_respmod: AuditIcapStats::default(),
+            tls_interception_failed: AtomicU64::new(0),
+            tls_interception_bypassed: AtomicU64::new(0),
+            tls_upstream_handshake: AtomicU64::new(0),
+            tls_upstream_session_resumed: AtomicU64::new(0),
+            har_export_dropped: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn stat_id(&self) -> StatId {
+        self.id
+    }
+
+    #[inline]
+    pub(crate) fn name(&self) -> &MetricsName {
+        &self.name
+    }
+
+    pub(crate) fn add_detected(&self, protocol: Protocol, blocked: bool) {
+        let mut map = self.protocols.lock().unwrap();
+        let stats = map.entry(protocol.as_str()).or_default();
+        stats.detected += 1;
+        if blocked {
+            stats.blocked += 1;
+        }
+    }
+
+    pub(crate) fn add_inspect_timeout(&self) {
+        self.inspect_timeout.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_inspect_oversize(&self) {
+        self.inspect_oversize.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn inspect_snapshot(&self) -> AuditInspectSnapshot {
+        AuditInspectSnapshot {
+            timeout: self.inspect_timeout.load(Ordering::Relaxed),
+            oversize: self.inspect_oversize.load(Ordering::Relaxed),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn icap_reqmod(&self) -> &AuditIcapStats {
+        &self.icap_reqmod
+    }
+
+    #[inline]
+    pub(crate) fn icap_respmod(&self) -> &AuditIcapStats {
+        &self.icap_respmod
+    }
+
+    pub(crate) fn add_tls_interception_failed(&self) {
+        self.tls_interception_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_tls_interception_bypassed(&self) {
+        self.tls_interception_bypassed
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// a successful tls handshake with the upstream server during interception
+    pub(crate) fn add_tls_upstream_handshake(&self, session_reused: bool) {
+        self.tls_upstream_handshake.fetch_add(1, Ordering::Relaxed);
+        if session_reused {
+            self.tls_upstream_session_resumed
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn tls_interception_snapshot(&self) -> AuditInterceptionSnapshot {
+        AuditInterceptionSnapshot {
+            failed: self.tls_interception_failed.load(Ordering::Relaxed),
+            bypassed: self.tls_interception_bypassed.load(Ordering::Relaxed),
+            upstream_handshake: self.tls_upstream_handshake.load(Ordering::Relaxed),
+            upstream_session_resumed: self.tls_upstream_session_resumed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// the HAR entry is dropped as the export queue is full
+    pub(crate) fn add_har_export_dropped(&self) {
+        self.har_export_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn har_export_dropped(&self) -> u64 {
+        self.har_export_dropped.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<(&'static str, AuditProtocolSnapshot)> {
+        let map = self.protocols.lock().unwrap();
+        map.iter().map(|(k, v)| (*k, *v)).collect()
+    }
+}
@@ -0,0 +1,152 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use ahash::{AHashMap, AHashSet};
+use ip_network_table::IpNetworkTable;
+use openssl::nid::Nid;
+use openssl::x509::X509Ref;
+use regex::RegexSet;
+
+use g3_types::acl::{AclAction, AclChildDomainRule, AclChildDomainRuleBuilder};
+use g3_types::net::{Host, UpstreamAddr};
+
+use crate::config::audit::TlsInterceptionBypassConfig;
+
+pub(crate) struct TlsInterceptionBypass {
+    exact_match_domain: AHashSet<String>,
+    exact_match_ipaddr: AHashSet<IpAddr>,
+    child_match_domain: Option<AclChildDomainRule>,
+    subnet_match_ipaddr: Option<IpNetworkTable<()>>,
+    cert_subject: Option<RegexSet>,
+    cert_bypass_cache_ttl: Duration,
+}
+
+impl TlsInterceptionBypass {
+    pub(crate) fn new(config: &TlsInterceptionBypassConfig) -> Self {
+        let child_match_domain = if config.child_match_domain.is_empty() {
+            None
+        } else {
+            let mut builder = AclChildDomainRuleBuilder::new(AclAction::Permit);
+            for domain in &config.child_match_domain {
+                builder.add_node(domain, AclAction::Forbid);
+            }
+            Some(builder.build())
+        };
+        let subnet_match_ipaddr = if config.subnet_match_ipaddr.is_empty() {
+            None
+        } else {
+            let mut table = IpNetworkTable::new();
+            for net in &config.subnet_match_ipaddr {
+                table.insert(*net, ());
+            }
+            Some(table)
+        };
+
+        TlsInterceptionBypass {
+            exact_match_domain: config.exact_match_domain.iter().cloned().collect(),
+            exact_match_ipaddr: config.exact_match_ipaddr.iter().copied().collect(),
+            child_match_domain,
+            subnet_match_ipaddr,
+            cert_subject: config.cert_subject_regex_set.clone(),
+            cert_bypass_cache_ttl: config.cert_bypass_cache_ttl,
+        }
+    }
+
+    /// Check if the interception should be bypassed for the target host,
+    /// which may be the SNI hostname or the upstream address
+    pub(crate) fn check_host(&self, host: &Host) -> bool {
+        match host {
+            Host::Domain(domain) => {
+                if self.exact_match_domain.contains(domain) {
+                    return true;
+                }
+                self.child_match_domain
+                    .as_ref()
+                    .map(|rule| rule.check(domain).0)
+                    .unwrap_or(false)
+            }
+            Host::Ip(ip) => {
+                if self.exact_match_ipaddr.contains(ip) {
+                    return true;
+                }
+                self.subnet_match_ipaddr
+                    .as_ref()
+                    .map(|table| table.longest_match(*ip).is_some())
+                    .unwrap_or(false)
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn cert_bypass_cache_ttl(&self) -> Duration {
+        self.cert_bypass_cache_ttl
+    }
+
+    /// Check if the upstream certificate matches the bypass rules
+    pub(crate) fn check_upstream_cert(&self, cert: &X509Ref) -> bool {
+        let Some(set) = &self.cert_subject else {
+            return false;
+        };
+
+        let matched = cert
+            .subject_name()
+            .entries_by_nid(Nid::COMMONNAME)
+            .filter_map(|entry| entry.data().as_utf8().ok())
+            .any(|cn| set.is_match(&cn));
+        if matched {
+            return true;
+        }
+        cert.subject_alt_names()
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|name| name.dnsname())
+                    .any(|name| set.is_match(name))
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// The upstream addresses for which the TLS interception should be bypassed,
+/// as found after the upstream handshake of previous connections
+#[derive(Default)]
+pub(crate) struct TlsBypassCache {
+    inner: Mutex<AHashMap<UpstreamAddr, Instant>>,
+}
+
+impl TlsBypassCache {
+    pub(crate) fn contains(&self, upstream: &UpstreamAddr) -> bool {
+        let mut cache = self.inner.lock().unwrap();
+        match cache.get(upstream) {
+            Some(expire) if *expire > Instant::now() => true,
+            Some(_) => {
+                cache.remove(upstream);
+                false
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn add(&self, upstream: &UpstreamAddr, ttl: Duration) {
+        let now = Instant::now();
+        let mut cache = self.inner.lock().unwrap();
+        cache.retain(|_, expire| *expire > now);
+        cache.insert(upstream.clone(), now + ttl);
+    }
+}
@@ -0,0 +1,250 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{self, IoSlice};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::SystemTime;
+
+use log::debug;
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio::net::{TcpStream, UdpSocket, UnixStream};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TryRecvError;
+use uuid::Uuid;
+
+use crate::config::audit::{TrafficMirrorConfig, TrafficMirrorPeer};
+
+const FRAME_VERSION: u8 = 1;
+const FRAME_HEADER_LEN: usize = 32;
+const STREAM_MAX_PAYLOAD_SIZE: usize = 16384;
+const UDP_MIN_PAYLOAD_SIZE: usize = 256;
+
+#[derive(Clone, Copy)]
+pub(crate) enum TrafficMirrorDirection {
+    ClientToRemote = 0,
+    RemoteToClient = 1,
+}
+
+/// Mirror cleartext stream data to an external collector.
+///
+/// Each frame has a 32 bytes header in network byte order:
+///   version(u8) | direction(u8) | header length(u16) | payload length(u32)
+///   | task id(16 bytes) | timestamp in microseconds since unix epoch(u64)
+pub(crate) struct TrafficMirror {
+    sender: mpsc::Sender<Vec<u8>>,
+    max_payload_size: usize,
+}
+
+impl TrafficMirror {
+    pub(crate) fn spawn(config: &TrafficMirrorConfig) -> Self {
+        let max_payload_size = match config.peer {
+            TrafficMirrorPeer::Udp(_) => config
+                .packet_size
+                .saturating_sub(FRAME_HEADER_LEN)
+                .max(UDP_MIN_PAYLOAD_SIZE),
+            _ => STREAM_MAX_PAYLOAD_SIZE,
+        };
+
+        let (sender, receiver) = mpsc::channel(config.queue_size);
+        let sinker = TrafficMirrorSinker {
+            config: config.clone(),
+            receiver,
+        };
+        tokio::spawn(sinker.into_running());
+
+        TrafficMirror {
+            sender,
+            max_payload_size,
+        }
+    }
+
+    fn send(&self, task_id: &Uuid, direction: TrafficMirrorDirection, data: &[u8]) {
+        let ts = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or_default();
+
+        for chunk in data.chunks(self.max_payload_size) {
+            let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + chunk.len());
+            frame.push(FRAME_VERSION);
+            frame.push(direction as u8);
+            frame.extend_from_slice(&(FRAME_HEADER_LEN as u16).to_be_bytes());
+            frame.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+            frame.extend_from_slice(task_id.as_bytes());
+            frame.extend_from_slice(&ts.to_be_bytes());
+            frame.extend_from_slice(chunk);
+            // drop the frame if the queue is full, so we won't block the proxy traffic
+            if self.sender.try_send(frame).is_err() {
+                debug!("traffic mirror queue is full or closed, frame dropped");
+            }
+        }
+    }
+
+    pub(crate) fn wrap_writer<W>(
+        self: &Arc<Self>,
+        writer: W,
+        task_id: Uuid,
+        direction: TrafficMirrorDirection,
+    ) -> TrafficMirrorWriter<W>
+    where
+        W: AsyncWrite,
+    {
+        TrafficMirrorWriter {
+            inner: writer,
+            mirror: self.clone(),
+            task_id,
+            direction,
+        }
+    }
+}
+
+pub(crate) struct TrafficMirrorWriter<W> {
+    inner: W,
+    mirror: Arc<TrafficMirror>,
+    task_id: Uuid,
+    direction: TrafficMirrorDirection,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for TrafficMirrorWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let nw = ready!(Pin::new(&mut self.inner).poll_write(cx, buf))?;
+        self.mirror.send(&self.task_id, self.direction, &buf[..nw]);
+        Poll::Ready(Ok(nw))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let nw = ready!(Pin::new(&mut self.inner).poll_write_vectored(cx, bufs))?;
+        let mut left = nw;
+        for buf in bufs {
+            if left == 0 {
+                break;
+            }
+            let len = buf.len().min(left);
+            self.mirror.send(&self.task_id, self.direction, &buf[..len]);
+            left -= len;
+        }
+        Poll::Ready(Ok(nw))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+enum SinkIo {
+    Stream(BufWriter<Box<dyn AsyncWrite + Send + Unpin>>),
+    Udp(UdpSocket),
+}
+
+impl SinkIo {
+    async fn connect(peer: &TrafficMirrorPeer) -> io::Result<Self> {
+        match peer {
+            TrafficMirrorPeer::Tcp(addr) => {
+                let stream = TcpStream::connect(addr).await?;
+                Ok(SinkIo::Stream(BufWriter::new(Box::new(stream))))
+            }
+            TrafficMirrorPeer::Unix(path) => {
+                let stream = UnixStream::connect(path).await?;
+                Ok(SinkIo::Stream(BufWriter::new(Box::new(stream))))
+            }
+            TrafficMirrorPeer::Udp(addr) => {
+                let socket = g3_socket::udp::new_std_socket_to(
+                    *addr,
+                    None,
+                    Default::default(),
+                    Default::default(),
+                )?;
+                socket.connect(addr)?;
+                Ok(SinkIo::Udp(UdpSocket::from_std(socket)?))
+            }
+        }
+    }
+
+    async fn send(&mut self, frame: &[u8]) -> io::Result<()> {
+        match self {
+            SinkIo::Stream(writer) => writer.write_all(frame).await,
+            SinkIo::Udp(socket) => socket.send(frame).await.map(|_| ()),
+        }
+    }
+
+    async fn flush(&mut self) -> io::Result<()> {
+        match self {
+            SinkIo::Stream(writer) => writer.flush().await,
+            SinkIo::Udp(_) => Ok(()),
+        }
+    }
+}
+
+struct TrafficMirrorSinker {
+    config: TrafficMirrorConfig,
+    receiver: mpsc::Receiver<Vec<u8>>,
+}
+
+impl TrafficMirrorSinker {
+    async fn into_running(mut self) {
+        loop {
+            let mut io = match SinkIo::connect(&self.config.peer).await {
+                Ok(io) => io,
+                Err(e) => {
+                    debug!("failed to connect to traffic mirror collector: {e}");
+                    // the frame will be dropped if we are not able to connect
+                    if let Err(TryRecvError::Disconnected) = self.receiver.try_recv() {
+                        break;
+                    }
+                    tokio::time::sleep(self.config.reconnect_wait).await;
+                    continue;
+                }
+            };
+
+            match self.send_all(&mut io).await {
+                Ok(_) => break,
+                Err(e) => {
+                    debug!("traffic mirror send error: {e}");
+                    tokio::time::sleep(self.config.reconnect_wait).await;
+                }
+            }
+        }
+    }
+
+    async fn send_all(&mut self, io: &mut SinkIo) -> io::Result<()> {
+        while let Some(frame) = self.receiver.recv().await {
+            io.send(&frame).await?;
+            while let Ok(frame) = self.receiver.try_recv() {
+                io.send(&frame).await?;
+            }
+            io.flush().await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use openssl::nid::Nid;
+use openssl::x509::{X509NameRef, X509Ref};
+
+use crate::config::auth::{ClientCertField, ClientCertUserRule};
+
+fn common_names(name: &X509NameRef) -> Vec<String> {
+    name.entries_by_nid(Nid::COMMONNAME)
+        .filter_map(|entry| entry.data().as_utf8().ok())
+        .map(|cn| cn.to_string())
+        .collect()
+}
+
+fn subject_alt_names(cert: &X509Ref) -> Vec<String> {
+    let Some(names) = cert.subject_alt_names() else {
+        return Vec::new();
+    };
+    names
+        .iter()
+        .filter_map(|name| name.dnsname().or(name.email()).or(name.uri()))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// find the user name and the matched value by the first matched rule.
+/// The matched value will be used as user name if no user is set in the rule,
+/// except for the issuer rule, which will use the subject CN instead.
+pub(super) fn match_user(rules: &[ClientCertUserRule], cert: &X509Ref) -> Option<(String, String)> {
+    let subject_cn = common_names(cert.subject_name());
+    let mut san = None;
+    let mut issuer_cn = None;
+
+    for rule in rules {
+        let values: &Vec<String> = match rule.field {
+            ClientCertField::CommonName => &subject_cn,
+            ClientCertField::SubjectAltName => san.get_or_insert_with(|| subject_alt_names(cert)),
+            ClientCertField::IssuerCommonName => {
+                issuer_cn.get_or_insert_with(|| common_names(cert.issuer_name()))
+            }
+        };
+        let Some(matched) = values.iter().find(|v| rule.is_match(v)) else {
+            continue;
+        };
+
+        let matched = match rule.field {
+            ClientCertField::IssuerCommonName => {
+                let Some(cn) = subject_cn.first() else {
+                    continue;
+                };
+                cn
+            }
+            _ => matched,
+        };
+        let user = rule.user.as_ref().unwrap_or(matched);
+        return Some((user.to_string(), matched.to_string()));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::{X509Builder, X509Name, X509};
+    use yaml_rust::YamlLoader;
+
+    fn name(cn: Option<&str>) -> X509Name {
+        let mut builder = X509Name::builder().unwrap();
+        builder
+            .append_entry_by_nid(Nid::ORGANIZATIONNAME, "Example")
+            .unwrap();
+        if let Some(cn) = cn {
+            builder.append_entry_by_nid(Nid::COMMONNAME, cn).unwrap();
+        }
+        builder.build()
+    }
+
+    fn build_cert(subject_cn: Option<&str>, issuer_cn: &str, dns: &[&str], email: &[&str]) -> X509 {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name(subject_cn)).unwrap();
+        builder.set_issuer_name(&name(Some(issuer_cn))).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        if !dns.is_empty() || !email.is_empty() {
+            let mut san = SubjectAlternativeName::new();
+            for v in dns {
+                san.dns(v);
+            }
+            for v in email {
+                san.email(v);
+            }
+            let ext = san.build(&builder.x509v3_context(None, None)).unwrap();
+            builder.append_extension(ext).unwrap();
+        }
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    fn rule(field: &str, pattern: &str, user: Option<&str>) -> ClientCertUserRule {
+        let mut doc = format!("{{field: {field}, pattern: '{pattern}'");
+        if let Some(user) = user {
+            doc.push_str(&format!(", user: {user}"));
+        }
+        doc.push('}');
+        let v = YamlLoader::load_from_str(&doc).unwrap();
+        ClientCertUserRule::parse_yaml(&v[0]).unwrap()
+    }
+
+    fn pair(user: &str, value: &str) -> Option<(String, String)> {
+        Some((user.to_string(), value.to_string()))
+    }
+
+    #[test]
+    fn common_name() {
+        let cert = build_cert(Some("alice"), "Example CA", &[], &[]);
+
+        let rules = [rule("cn", "*", None)];
+        assert_eq!(match_user(&rules, &cert), pair("alice", "alice"));
+
+        let rules = [rule("cn", "ali??", Some("staff"))];
+        assert_eq!(match_user(&rules, &cert), pair("staff", "alice"));
+
+        let rules = [rule("cn", "bob", None), rule("cn", "Alice", None)];
+        assert_eq!(match_user(&rules, &cert), None);
+
+        // no subject cn
+        let cert = build_cert(None, "Example CA", &["api.example.net"], &[]);
+        let rules = [rule("cn", "*", None)];
+        assert_eq!(match_user(&rules, &cert), None);
+    }
+
+    #[test]
+    fn subject_alt_name() {
+        let cert = build_cert(
+            Some("client"),
+            "Example CA",
+            &["api.svc.example.net", "api.example.org"],
+            &["ops@example.com"],
+        );
+
+        let rules = [rule("san", "*.svc.example.net", Some("service"))];
+        assert_eq!(
+            match_user(&rules, &cert),
+            pair("service", "api.svc.example.net")
+        );
+
+        // the first matched value is used
+        let rules = [rule("san", "api.*", None)];
+        assert_eq!(
+            match_user(&rules, &cert),
+            pair("api.svc.example.net", "api.svc.example.net")
+        );
+
+        let rules = [rule("san", "*@example.com", None)];
+        assert_eq!(
+            match_user(&rules, &cert),
+            pair("ops@example.com", "ops@example.com")
+        );
+
+        let rules = [
+            rule("san", "*.example.com", None),
+            rule("san", "svc.example.net", None),
+            rule("san", "client", None),
+        ];
+        assert_eq!(match_user(&rules, &cert), None);
+
+        // no san extension
+        let cert = build_cert(Some("client"), "Example CA", &[], &[]);
+        let rules = [rule("san", "*", None)];
+        assert_eq!(match_user(&rules, &cert), None);
+    }
+
+    #[test]
+    fn issuer() {
+        let cert = build_cert(Some("bob"), "Example Partner CA", &[], &[]);
+
+        // the subject cn is used as the matched value
+        let rules = [rule("issuer", "Example Partner CA", None)];
+        assert_eq!(match_user(&rules, &cert), pair("bob", "bob"));
+
+        let rules = [rule("issuer", "* Partner CA", Some("partner"))];
+        assert_eq!(match_user(&rules, &cert), pair("partner", "bob"));
+
+        let rules = [rule("issuer", "Example CA", Some("partner"))];
+        assert_eq!(match_user(&rules, &cert), None);
+
+        // skip to the next rule if no subject cn
+        let cert = build_cert(None, "Example Partner CA", &["api.example.net"], &[]);
+        let rules = [
+            rule("issuer", "Example Partner CA", Some("partner")),
+            rule("san", "*", None),
+        ];
+        assert_eq!(
+            match_user(&rules, &cert),
+            pair("api.example.net", "api.example.net")
+        );
+    }
+
+    #[test]
+    fn precedence() {
+        let cert = build_cert(
+            Some("api.svc.example.net"),
+            "Example Partner CA",
+            &["api.svc.example.net"],
+            &[],
+        );
+
+        // the first matched rule wins, even if a later one is more specific
+        let rules = [
+            rule("cn", "*", Some("any")),
+            rule("cn", "api.svc.example.net", Some("api")),
+        ];
+        assert_eq!(
+            match_user(&rules, &cert),
+            pair("any", "api.svc.example.net")
+        );
+
+        let rules = [
+            rule("cn", "api.svc.example.net", Some("api")),
+            rule("cn", "*", Some("any")),
+        ];
+        assert_eq!(
+            match_user(&rules, &cert),
+            pair("api", "api.svc.example.net")
+        );
+
+        // rules on different fields are also checked in order
+        let rules = [
+            rule("issuer", "Other CA", Some("other")),
+            rule("san", "*.svc.example.net", Some("service")),
+            rule("issuer", "Example Partner CA", Some("partner")),
+        ];
+        assert_eq!(
+            match_user(&rules, &cert),
+            pair("service", "api.svc.example.net")
+        );
+
+        // the wildcard rule is used as the fallback
+        let rules = [
+            rule("cn", "web.svc.example.net", Some("web")),
+            rule("cn", "*.svc.example.net", Some("service")),
+        ];
+        assert_eq!(
+            match_user(&rules, &cert),
+            pair("service", "api.svc.example.net")
+        );
+
+        assert_eq!(match_user(&[], &cert), None);
+    }
+}
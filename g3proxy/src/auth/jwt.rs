@@ -0,0 +1,260 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+use base64::prelude::*;
+use openssl::bn::BigNum;
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::rsa::Padding;
+use openssl::sign::{RsaPssSaltlen, Signer, Verifier};
+use serde_json::{Map, Value};
+
+use crate::config::auth::{JwtAlgorithm, JwtAuthConfig, JwtVerifyKey};
+
+pub(crate) struct JwtClaims {
+    pub(crate) username: String,
+    pub(crate) groups: Vec<String>,
+}
+
+fn decode_json(s: &str) -> anyhow::Result<Map<String, Value>> {
+    let data = BASE64_URL_SAFE_NO_PAD
+        .decode(s)
+        .map_err(|e| anyhow!("invalid base64url encoding: {e}"))?;
+    match serde_json::from_slice(&data) {
+        Ok(Value::Object(map)) => Ok(map),
+        Ok(_) => Err(anyhow!("not a json object")),
+        Err(e) => Err(anyhow!("invalid json: {e}")),
+    }
+}
+
+/// verify the signature and the registered claims of the token, and return the mapped claims
+pub(crate) fn verify_token(config: &JwtAuthConfig, token: &str) -> anyhow::Result<JwtClaims> {
+    let mut parts = token.split('.');
+    let (Some(header), Some(payload), Some(signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(anyhow!("invalid jws compact serialization"));
+    };
+
+    let header_map = decode_json(header).map_err(|e| anyhow!("invalid header: {e}"))?;
+    let alg = match header_map.get("alg") {
+        Some(Value::String(s)) => s
+            .parse::<JwtAlgorithm>()
+            .map_err(|_| anyhow!("unsupported algorithm {s}"))?,
+        _ => return Err(anyhow!("no valid alg found in header")),
+    };
+    let kid = match header_map.get("kid") {
+        Some(Value::String(s)) => Some(s.as_str()),
+        _ => None,
+    };
+    let signature = BASE64_URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|e| anyhow!("invalid signature encoding: {e}"))?;
+
+    let signed_len = header.len() + 1 + payload.len();
+    let signed_data = &token.as_bytes()[..signed_len];
+    let mut verified = false;
+    for key in config.keys.iter().filter(|k| k.matches(kid, alg)) {
+        if verify_signature(&key.key, alg, signed_data, &signature).unwrap_or(false) {
+            verified = true;
+            break;
+        }
+    }
+    if !verified {
+        return Err(anyhow!("signature verification failed"));
+    }
+
+    let claims = decode_json(payload).map_err(|e| anyhow!("invalid payload: {e}"))?;
+    check_claims(config, &claims)?;
+
+    let username = match claims.get(&config.username_claim) {
+        Some(Value::String(s)) if !s.is_empty() => s.to_string(),
+        _ => return Err(anyhow!("no valid {} claim found", config.username_claim)),
+    };
+    let groups = match claims.get(&config.groups_claim) {
+        Some(Value::String(s)) => vec![s.to_string()],
+        Some(Value::Array(seq)) => seq
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    Ok(JwtClaims { username, groups })
+}
+
+fn check_claims(config: &JwtAuthConfig, claims: &Map<String, Value>) -> anyhow::Result<()> {
+    let now = chrono::Utc::now().timestamp();
+    let leeway = config.leeway.as_secs() as i64;
+
+    let Some(exp) = claims.get("exp").and_then(|v| v.as_i64()) else {
+        return Err(anyhow!("no valid exp claim found"));
+    };
+    if now > exp.saturating_add(leeway) {
+        return Err(anyhow!("token has been expired"));
+    }
+    if let Some(nbf) = claims.get("nbf") {
+        let Some(nbf) = nbf.as_i64() else {
+            return Err(anyhow!("invalid nbf claim"));
+        };
+        if now < nbf.saturating_sub(leeway) {
+            return Err(anyhow!("token is not yet valid"));
+        }
+    }
+
+    if let Some(issuer) = &config.issuer {
+        match claims.get("iss") {
+            Some(Value::String(s)) if s == issuer => {}
+            _ => return Err(anyhow!("iss claim mismatch")),
+        }
+    }
+    if let Some(audience) = &config.audience {
+        let matched = match claims.get("aud") {
+            Some(Value::String(s)) => s == audience,
+            Some(Value::Array(seq)) => seq.iter().any(|v| v.as_str() == Some(audience)),
+            _ => false,
+        };
+        if !matched {
+            return Err(anyhow!("aud claim mismatch"));
+        }
+    }
+    Ok(())
+}
+
+fn verify_signature(
+    key: &JwtVerifyKey,
+    alg: JwtAlgorithm,
+    data: &[u8],
+    signature: &[u8],
+) -> anyhow::Result<bool> {
+    let digest = match alg {
+        JwtAlgorithm::HS256 | JwtAlgorithm::RS256 | JwtAlgorithm::PS256 | JwtAlgorithm::ES256 => {
+            MessageDigest::sha256()
+        }
+        JwtAlgorithm::HS384 | JwtAlgorithm::RS384 | JwtAlgorithm::PS384 | JwtAlgorithm::ES384 => {
+            MessageDigest::sha384()
+        }
+        JwtAlgorithm::HS512 | JwtAlgorithm::RS512 | JwtAlgorithm::PS512 | JwtAlgorithm::ES512 => {
+            MessageDigest::sha512()
+        }
+    };
+
+    match key {
+        JwtVerifyKey::Hmac(secret) => {
+            let pkey = PKey::hmac(secret)?;
+            let mut signer = Signer::new(digest, &pkey)?;
+            signer.update(data)?;
+            let mac = signer.sign_to_vec()?;
+            Ok(mac.len() == signature.len() && openssl::memcmp::eq(&mac, signature))
+        }
+        JwtVerifyKey::Public(pkey) => match alg {
+            JwtAlgorithm::ES256 | JwtAlgorithm::ES384 | JwtAlgorithm::ES512 => {
+                // the signature is the concatenation of R and S, see RFC 7518 section 3.4
+                if signature.is_empty() || signature.len() % 2 != 0 {
+                    return Ok(false);
+                }
+                let (r, s) = signature.split_at(signature.len() / 2);
+                let sig = EcdsaSig::from_private_components(
+                    BigNum::from_slice(r)?,
+                    BigNum::from_slice(s)?,
+                )?;
+                let der = sig.to_der()?;
+                let mut verifier = Verifier::new(digest, pkey)?;
+                verifier.update(data)?;
+                Ok(verifier.verify(&der)?)
+            }
+            JwtAlgorithm::PS256 | JwtAlgorithm::PS384 | JwtAlgorithm::PS512 => {
+                let mut verifier = Verifier::new(digest, pkey)?;
+                verifier.set_rsa_padding(Padding::PKCS1_PSS)?;
+                verifier.set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)?;
+                verifier.update(data)?;
+                Ok(verifier.verify(signature)?)
+            }
+            _ => {
+                let mut verifier = Verifier::new(digest, pkey)?;
+                verifier.update(data)?;
+                Ok(verifier.verify(signature)?)
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::{Yaml, YamlLoader};
+
+    fn hmac_config() -> JwtAuthConfig {
+        let docs = YamlLoader::load_from_str("keys:\n  - secret: secret\n").unwrap();
+        let Yaml::Hash(map) = &docs[0] else {
+            unreachable!()
+        };
+        JwtAuthConfig::parse_yaml(map, None).unwrap()
+    }
+
+    fn sign_hs256(header: &str, payload: &str) -> String {
+        let header = BASE64_URL_SAFE_NO_PAD.encode(header);
+        let payload = BASE64_URL_SAFE_NO_PAD.encode(payload);
+        let data = format!("{header}.{payload}");
+        let pkey = PKey::hmac(b"secret").unwrap();
+        let mut signer = Signer::new(MessageDigest::sha256(), &pkey).unwrap();
+        signer.update(data.as_bytes()).unwrap();
+        let sig = BASE64_URL_SAFE_NO_PAD.encode(signer.sign_to_vec().unwrap());
+        format!("{data}.{sig}")
+    }
+
+    #[test]
+    fn hs256() {
+        let config = hmac_config();
+        let exp = chrono::Utc::now().timestamp() + 300;
+        let token = sign_hs256(
+            r#"{"alg":"HS256","typ":"JWT"}"#,
+            &format!(r#"{{"sub":"alice","groups":["dev","ops"],"exp":{exp}}}"#),
+        );
+        let claims = verify_token(&config, &token).unwrap();
+        assert_eq!(claims.username, "alice");
+        assert_eq!(claims.groups, ["dev", "ops"]);
+
+        let parts: Vec<&str> = token.split('.').collect();
+        let payload = BASE64_URL_SAFE_NO_PAD.encode(format!(r#"{{"sub":"bob","exp":{exp}}}"#));
+        let bad_token = format!("{}.{payload}.{}", parts[0], parts[2]);
+        assert!(verify_token(&config, &bad_token).is_err());
+    }
+
+    #[test]
+    fn expired() {
+        let config = hmac_config();
+        let exp = chrono::Utc::now().timestamp() - 3600;
+        let token = sign_hs256(
+            r#"{"alg":"HS256"}"#,
+            &format!(r#"{{"sub":"alice","exp":{exp}}}"#),
+        );
+        assert!(verify_token(&config, &token).is_err());
+
+        let token = sign_hs256(r#"{"alg":"HS256"}"#, r#"{"sub":"alice"}"#);
+        assert!(verify_token(&config, &token).is_err());
+    }
+
+    #[test]
+    fn alg_none() {
+        let config = hmac_config();
+        let header = BASE64_URL_SAFE_NO_PAD.encode(r#"{"alg":"none"}"#);
+        let payload = BASE64_URL_SAFE_NO_PAD.encode(r#"{"sub":"alice","exp":9999999999}"#);
+        let token = format!("{header}.{payload}.");
+        assert!(verify_token(&config, &token).is_err());
+    }
+}
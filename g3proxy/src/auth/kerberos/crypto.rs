@@ -0,0 +1,418 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use openssl::symm::{Cipher, Crypter, Mode};
+
+pub(super) const ETYPE_AES128_CTS_HMAC_SHA1_96: i32 = 17;
+pub(super) const ETYPE_AES256_CTS_HMAC_SHA1_96: i32 = 18;
+
+const BLOCK_SIZE: usize = 16;
+const CONFOUNDER_SIZE: usize = 16;
+const HMAC_SIZE: usize = 12;
+
+pub(super) fn is_supported(etype: i32) -> bool {
+    matches!(
+        etype,
+        ETYPE_AES128_CTS_HMAC_SHA1_96 | ETYPE_AES256_CTS_HMAC_SHA1_96
+    )
+}
+
+fn block_cipher(etype: i32, key: &[u8]) -> anyhow::Result<Cipher> {
+    let (cipher, key_len) = match etype {
+        ETYPE_AES128_CTS_HMAC_SHA1_96 => (Cipher::aes_128_ecb(), 16),
+        ETYPE_AES256_CTS_HMAC_SHA1_96 => (Cipher::aes_256_ecb(), 32),
+        _ => return Err(anyhow!("unsupported encryption type {etype}")),
+    };
+    if key.len() != key_len {
+        return Err(anyhow!(
+            "invalid key length {} for encryption type {etype}",
+            key.len()
+        ));
+    }
+    Ok(cipher)
+}
+
+struct BlockCrypter {
+    crypter: Crypter,
+}
+
+impl BlockCrypter {
+    fn new(cipher: Cipher, mode: Mode, key: &[u8]) -> anyhow::Result<Self> {
+        let mut crypter = Crypter::new(cipher, mode, key, None)?;
+        crypter.pad(false);
+        Ok(BlockCrypter { crypter })
+    }
+
+    fn process(&mut self, block: &[u8]) -> anyhow::Result<[u8; BLOCK_SIZE]> {
+        let mut out = [0u8; BLOCK_SIZE * 2];
+        let len = self.crypter.update(block, &mut out)?;
+        if len != BLOCK_SIZE {
+            return Err(anyhow!("unexpected block output length {len}"));
+        }
+        let mut r = [0u8; BLOCK_SIZE];
+        r.copy_from_slice(&out[..BLOCK_SIZE]);
+        Ok(r)
+    }
+}
+
+/// the n-fold operation, see RFC 3961 section 5.1
+fn n_fold(input: &[u8], out_len: usize) -> Vec<u8> {
+    let in_len = input.len();
+    let (mut a, mut b) = (out_len, in_len);
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    let lcm = out_len * in_len / a;
+    let in_bits = in_len << 3;
+
+    let mut out = vec![0u8; out_len];
+    let mut byte = 0usize;
+    for i in (0..lcm).rev() {
+        let msbit =
+            ((in_bits - 1) + ((in_bits + 13) * (i / in_len)) + ((in_len - (i % in_len)) << 3))
+                % in_bits;
+        let hi = input[((in_len - 1) - (msbit >> 3)) % in_len] as usize;
+        let lo = input[(in_len - (msbit >> 3)) % in_len] as usize;
+        byte += (((hi << 8) | lo) >> ((msbit & 7) + 1)) & 0xff;
+        byte += out[i % out_len] as usize;
+        out[i % out_len] = (byte & 0xff) as u8;
+        byte >>= 8;
+    }
+    if byte != 0 {
+        for v in out.iter_mut().rev() {
+            byte += *v as usize;
+            *v = (byte & 0xff) as u8;
+            byte >>= 8;
+        }
+    }
+    out
+}
+
+/// the DK function for AES, see RFC 3961 section 5.1 and RFC 3962 section 6
+fn dk(cipher: Cipher, key: &[u8], constant: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut crypter = BlockCrypter::new(cipher, Mode::Encrypt, key)?;
+    let mut block = [0u8; BLOCK_SIZE];
+    block.copy_from_slice(&n_fold(constant, BLOCK_SIZE));
+    let mut derived = Vec::with_capacity(key.len() + BLOCK_SIZE);
+    while derived.len() < key.len() {
+        block = crypter.process(&block)?;
+        derived.extend_from_slice(&block);
+    }
+    derived.truncate(key.len());
+    Ok(derived)
+}
+
+fn derive_key(cipher: Cipher, key: &[u8], usage: u32, kind: u8) -> anyhow::Result<Vec<u8>> {
+    let mut constant = [0u8; 5];
+    constant[..4].copy_from_slice(&usage.to_be_bytes());
+    constant[4] = kind;
+    dk(cipher, key, &constant)
+}
+
+/// AES CBC mode with ciphertext stealing and zero IV, see RFC 3962 section 5
+fn cts_decrypt(cipher: Cipher, key: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if data.len() < BLOCK_SIZE {
+        return Err(anyhow!("too short cipher data"));
+    }
+    let mut crypter = BlockCrypter::new(cipher, Mode::Decrypt, key)?;
+    if data.len() == BLOCK_SIZE {
+        return Ok(crypter.process(data)?.to_vec());
+    }
+
+    let block_count = data.len().div_ceil(BLOCK_SIZE);
+    let last_len = data.len() - (block_count - 1) * BLOCK_SIZE;
+    let mut plain = Vec::with_capacity(data.len());
+
+    let mut prev = [0u8; BLOCK_SIZE];
+    for block in data[..(block_count - 2) * BLOCK_SIZE].chunks(BLOCK_SIZE) {
+        let p = crypter.process(block)?;
+        plain.extend(p.iter().zip(prev.iter()).map(|(a, b)| a ^ b));
+        prev.copy_from_slice(block);
+    }
+
+    let second_last = &data[(block_count - 2) * BLOCK_SIZE..(block_count - 1) * BLOCK_SIZE];
+    let last = &data[(block_count - 1) * BLOCK_SIZE..];
+    let x = crypter.process(second_last)?;
+    let mut stolen = [0u8; BLOCK_SIZE];
+    stolen[..last_len].copy_from_slice(last);
+    stolen[last_len..].copy_from_slice(&x[last_len..]);
+    let p = crypter.process(&stolen)?;
+    plain.extend(p.iter().zip(prev.iter()).map(|(a, b)| a ^ b));
+    plain.extend(x[..last_len].iter().zip(last.iter()).map(|(a, b)| a ^ b));
+    Ok(plain)
+}
+
+#[cfg(test)]
+fn cts_encrypt(cipher: Cipher, key: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if data.len() < BLOCK_SIZE {
+        return Err(anyhow!("too short plain data"));
+    }
+    let mut crypter = BlockCrypter::new(cipher, Mode::Encrypt, key)?;
+    if data.len() == BLOCK_SIZE {
+        return Ok(crypter.process(data)?.to_vec());
+    }
+
+    let block_count = data.len().div_ceil(BLOCK_SIZE);
+    let last_len = data.len() - (block_count - 1) * BLOCK_SIZE;
+    let mut encrypted = Vec::with_capacity(data.len());
+
+    let mut prev = [0u8; BLOCK_SIZE];
+    for block in data[..(block_count - 1) * BLOCK_SIZE].chunks(BLOCK_SIZE) {
+        let mut input = [0u8; BLOCK_SIZE];
+        input
+            .iter_mut()
+            .zip(block.iter().zip(prev.iter()))
+            .for_each(|(v, (a, b))| *v = a ^ b);
+        prev = crypter.process(&input)?;
+        encrypted.extend_from_slice(&prev);
+    }
+
+    let mut input = prev;
+    input
+        .iter_mut()
+        .zip(data[(block_count - 1) * BLOCK_SIZE..].iter())
+        .for_each(|(v, a)| *v ^= a);
+    let last = crypter.process(&input)?;
+    // swap the last two blocks and truncate the original second last one
+    encrypted.truncate((block_count - 2) * BLOCK_SIZE);
+    encrypted.extend_from_slice(&last);
+    encrypted.extend_from_slice(&prev[..last_len]);
+    Ok(encrypted)
+}
+
+/// encrypt with a fixed confounder, only used to generate test data
+#[cfg(test)]
+pub(super) fn encrypt(
+    etype: i32,
+    key: &[u8],
+    usage: u32,
+    confounder: &[u8; CONFOUNDER_SIZE],
+    data: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let cipher = block_cipher(etype, key)?;
+    let ke = derive_key(cipher, key, usage, 0xaa)?;
+    let ki = derive_key(cipher, key, usage, 0x55)?;
+
+    let mut plain = Vec::with_capacity(CONFOUNDER_SIZE + data.len());
+    plain.extend_from_slice(confounder);
+    plain.extend_from_slice(data);
+    let mut encrypted = cts_encrypt(cipher, &ke, &plain)?;
+
+    let ki = PKey::hmac(&ki)?;
+    let mut signer = Signer::new(MessageDigest::sha1(), &ki)?;
+    signer.update(&plain)?;
+    let mac = signer.sign_to_vec()?;
+    encrypted.extend_from_slice(&mac[..HMAC_SIZE]);
+    Ok(encrypted)
+}
+
+/// decrypt the cipher data for aes-cts-hmac-sha1-96 types, see RFC 3962
+pub(super) fn decrypt(etype: i32, key: &[u8], usage: u32, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let cipher = block_cipher(etype, key)?;
+    if data.len() < CONFOUNDER_SIZE + HMAC_SIZE {
+        return Err(anyhow!("too short encrypted data"));
+    }
+
+    let ke = derive_key(cipher, key, usage, 0xaa)?;
+    let ki = derive_key(cipher, key, usage, 0x55)?;
+
+    let (cipher_data, mac) = data.split_at(data.len() - HMAC_SIZE);
+    let plain = cts_decrypt(cipher, &ke, cipher_data)?;
+
+    let ki = PKey::hmac(&ki)?;
+    let mut signer = Signer::new(MessageDigest::sha1(), &ki)?;
+    signer.update(&plain)?;
+    let expected = signer.sign_to_vec()?;
+    if !openssl::memcmp::eq(&expected[..HMAC_SIZE], mac) {
+        return Err(anyhow!("integrity check failed"));
+    }
+
+    Ok(plain[CONFOUNDER_SIZE..].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn fold() {
+        assert_eq!(n_fold(b"012345", 8), hex("be072631276b1955"));
+        assert_eq!(n_fold(b"password", 7), hex("78a07b6caf85fa"));
+        assert_eq!(
+            n_fold(b"Rough Consensus, and Running Code", 8),
+            hex("bb6ed30870b7f0e0")
+        );
+        assert_eq!(
+            n_fold(b"password", 21),
+            hex("59e4a8ca7c0385c3c37b3f6d2000247cb6e6bd5b3e")
+        );
+        assert_eq!(
+            n_fold(b"kerberos", 16),
+            hex("6b65726265726f737b9b5b2b93132b93")
+        );
+    }
+
+    #[test]
+    fn derive() {
+        let key = hex("42263c6e89f4fc28b8df68ee09799f15");
+        let cipher = Cipher::aes_128_ecb();
+        assert_eq!(
+            derive_key(cipher, &key, 2, 0xaa).unwrap(),
+            hex("5b14fc4e250e14ddf9dccf1af6674f53")
+        );
+        assert_eq!(
+            derive_key(cipher, &key, 2, 0x55).unwrap(),
+            hex("4ed31063621684f09ae8d89991af3e8f")
+        );
+    }
+
+    #[test]
+    fn string_to_key() {
+        // test vectors from RFC 3962 appendix B, with pass phrase "password"
+        // and salt "ATHENA.MIT.EDUraeburn"
+        let cases: [(usize, &str, &str); 3] = [
+            (
+                1,
+                "42263c6e89f4fc28b8df68ee09799f15",
+                "fe697b52bc0d3ce14432ba036a92e65bbb52280990a2fa27883998d72af30161",
+            ),
+            (
+                2,
+                "c651bf29e2300ac27fa469d693bdda13",
+                "a2e16d16b36069c135d5e9d2e25f896102685618b95914b467c67622225824ff",
+            ),
+            (
+                1200,
+                "4c01cd46d632d01e6dbe230a01ed642a",
+                "55a6ac740ad17b4846941051e1e8b0a7548d93b0ab30a8bc3ff16280382b8c2a",
+            ),
+        ];
+        for (iter, aes128, aes256) in cases {
+            for (cipher, expected) in [
+                (Cipher::aes_128_ecb(), aes128),
+                (Cipher::aes_256_ecb(), aes256),
+            ] {
+                let mut tkey = vec![0u8; cipher.key_len()];
+                openssl::pkcs5::pbkdf2_hmac(
+                    b"password",
+                    b"ATHENA.MIT.EDUraeburn",
+                    iter,
+                    MessageDigest::sha1(),
+                    &mut tkey,
+                )
+                .unwrap();
+                assert_eq!(dk(cipher, &tkey, b"kerberos").unwrap(), hex(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn cts() {
+        // test vectors from RFC 3962 appendix B
+        let key = b"chicken teriyaki";
+        let cipher = Cipher::aes_128_ecb();
+        let cases: [(&[u8], &str); 6] = [
+            (b"I would like the ", "c6353568f2bf8cb4d8a580362da7ff7f97"),
+            (
+                b"I would like the General Gau's ",
+                "fc00783e0efdb2c1d445d4c8eff7ed2297687268d6ecccc0c07b25e25ecfe5",
+            ),
+            (
+                b"I would like the General Gau's C",
+                "39312523a78662d5be7fcbcc98ebf5a897687268d6ecccc0c07b25e25ecfe584",
+            ),
+            (
+                b"I would like the General Gau's Chicken, please,",
+                "97687268d6ecccc0c07b25e25ecfe584b3fffd940c16a18c1b5549d2f838029e\
+                 39312523a78662d5be7fcbcc98ebf5",
+            ),
+            (
+                b"I would like the General Gau's Chicken, please, ",
+                "97687268d6ecccc0c07b25e25ecfe5849dad8bbb96c4cdc03bc103e1a194bbd8\
+                 39312523a78662d5be7fcbcc98ebf5a8",
+            ),
+            (
+                b"I would like the General Gau's Chicken, please, and wonton soup.",
+                "97687268d6ecccc0c07b25e25ecfe58439312523a78662d5be7fcbcc98ebf5a8\
+                 4807efe836ee89a526730dbc2f7bc8409dad8bbb96c4cdc03bc103e1a194bbd8",
+            ),
+        ];
+        for (plain, encrypted) in cases {
+            let encrypted = hex(encrypted);
+            assert_eq!(cts_encrypt(cipher, key, plain).unwrap(), encrypted);
+            assert_eq!(cts_decrypt(cipher, key, &encrypted).unwrap(), plain);
+        }
+        assert!(cts_decrypt(cipher, key, &[0u8; 15]).is_err());
+    }
+
+    #[test]
+    fn decrypt_aes() {
+        let key: Vec<u8> = (0u8..16).collect();
+        let data = hex("f155e5cbea7b905067598099acd6fcbb81a8038a4496c6af710678dc00ddf2c1924a334273957828afd40befc3");
+        let plain = decrypt(ETYPE_AES128_CTS_HMAC_SHA1_96, &key, 2, &data).unwrap();
+        assert_eq!(plain, b"hello kerberos!!!");
+        assert!(decrypt(ETYPE_AES128_CTS_HMAC_SHA1_96, &key, 3, &data).is_err());
+
+        let key: Vec<u8> = (0u8..32).collect();
+        let data = hex("9e97ecdb2486725211257684b1a2d2f3ca308e8377176d4f36501b4dbb6e84cc84efed5cf7ad0874bcd8e2b653");
+        let plain = decrypt(ETYPE_AES256_CTS_HMAC_SHA1_96, &key, 2, &data).unwrap();
+        assert_eq!(plain, b"hello kerberos!!!");
+    }
+
+    #[test]
+    fn encrypt_aes() {
+        let confounder = [0x11u8; CONFOUNDER_SIZE];
+        let key: Vec<u8> = (0u8..16).collect();
+        let data = encrypt(
+            ETYPE_AES128_CTS_HMAC_SHA1_96,
+            &key,
+            2,
+            &confounder,
+            b"hello kerberos!!!",
+        )
+        .unwrap();
+        assert_eq!(data, hex("f155e5cbea7b905067598099acd6fcbb81a8038a4496c6af710678dc00ddf2c1924a334273957828afd40befc3"));
+
+        let key: Vec<u8> = (0u8..32).collect();
+        let data = encrypt(
+            ETYPE_AES256_CTS_HMAC_SHA1_96,
+            &key,
+            11,
+            &confounder,
+            b"hello kerberos!!!",
+        )
+        .unwrap();
+        assert_eq!(data, hex("cf28e36f9861ab28056b5dc0bbd5677571b1c7d5f61c48244dbbb0571f2ca920982fd9bab80a67ba163f003c18"));
+        let plain = decrypt(ETYPE_AES256_CTS_HMAC_SHA1_96, &key, 11, &data).unwrap();
+        assert_eq!(plain, b"hello kerberos!!!");
+
+        // tampered data should fail the integrity check
+        let mut data = data;
+        data[20] ^= 0x01;
+        assert!(decrypt(ETYPE_AES256_CTS_HMAC_SHA1_96, &key, 11, &data).is_err());
+    }
+}
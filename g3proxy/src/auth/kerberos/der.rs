@@ -0,0 +1,166 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+
+pub(super) const TAG_INTEGER: u8 = 0x02;
+pub(super) const TAG_BIT_STRING: u8 = 0x03;
+pub(super) const TAG_OCTET_STRING: u8 = 0x04;
+pub(super) const TAG_OID: u8 = 0x06;
+pub(super) const TAG_GENERAL_STRING: u8 = 0x1b;
+pub(super) const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub(super) const TAG_SEQUENCE: u8 = 0x30;
+
+#[inline]
+pub(super) const fn application(n: u8) -> u8 {
+    0x60 | n
+}
+
+#[inline]
+pub(super) const fn context(n: u8) -> u8 {
+    0xa0 | n
+}
+
+pub(super) struct DerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Self {
+        DerReader { data }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub(super) fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    pub(super) fn left(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub(super) fn read_tlv(&mut self) -> anyhow::Result<(u8, &'a [u8])> {
+        let Some((&tag, left)) = self.data.split_first() else {
+            return Err(anyhow!("no more der data"));
+        };
+        if tag & 0x1f == 0x1f {
+            return Err(anyhow!("unsupported multi-byte der tag"));
+        }
+        let Some((&l, mut left)) = left.split_first() else {
+            return Err(anyhow!("no length for der tag {tag:#04x}"));
+        };
+        let len = if l & 0x80 == 0 {
+            l as usize
+        } else {
+            let n = (l & 0x7f) as usize;
+            if n == 0 || n > 4 || left.len() < n {
+                return Err(anyhow!("invalid length for der tag {tag:#04x}"));
+            }
+            let len = left[..n]
+                .iter()
+                .fold(0usize, |acc, b| (acc << 8) | (*b as usize));
+            left = &left[n..];
+            len
+        };
+        if left.len() < len {
+            return Err(anyhow!("truncated value for der tag {tag:#04x}"));
+        }
+        let (value, left) = left.split_at(len);
+        self.data = left;
+        Ok((tag, value))
+    }
+
+    pub(super) fn read_expected(&mut self, tag: u8) -> anyhow::Result<&'a [u8]> {
+        let (t, value) = self.read_tlv()?;
+        if t != tag {
+            return Err(anyhow!("expected der tag {tag:#04x} but got {t:#04x}"));
+        }
+        Ok(value)
+    }
+
+    /// read the value of an explicit tagged field
+    pub(super) fn read_explicit(&mut self, n: u8, tag: u8) -> anyhow::Result<&'a [u8]> {
+        let value = self.read_expected(context(n))?;
+        let mut inner = DerReader::new(value);
+        let value = inner.read_expected(tag)?;
+        if !inner.is_empty() {
+            return Err(anyhow!("trailing data in der field [{n}]"));
+        }
+        Ok(value)
+    }
+
+    /// skip the explicit tagged field if present, return the value if found
+    pub(super) fn read_optional(&mut self, n: u8, tag: u8) -> anyhow::Result<Option<&'a [u8]>> {
+        if self.peek_tag() == Some(context(n)) {
+            self.read_explicit(n, tag).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub(super) fn read_explicit_integer(&mut self, n: u8) -> anyhow::Result<i64> {
+        let value = self.read_explicit(n, TAG_INTEGER)?;
+        decode_integer(value)
+    }
+
+    pub(super) fn read_explicit_string(&mut self, n: u8) -> anyhow::Result<String> {
+        let value = self.read_explicit(n, TAG_GENERAL_STRING)?;
+        std::str::from_utf8(value)
+            .map(|s| s.to_string())
+            .map_err(|_| anyhow!("invalid utf-8 string in der field [{n}]"))
+    }
+
+    /// skip all fields with context tag less than n
+    pub(super) fn skip_to(&mut self, n: u8) -> anyhow::Result<()> {
+        while let Some(tag) = self.peek_tag() {
+            if tag & 0xe0 == 0xa0 && tag & 0x1f < n {
+                self.read_tlv()?;
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn decode_integer(value: &[u8]) -> anyhow::Result<i64> {
+    if value.is_empty() || value.len() > 8 {
+        return Err(anyhow!("invalid integer value length {}", value.len()));
+    }
+    let init = if value[0] & 0x80 != 0 { -1i64 } else { 0 };
+    Ok(value.iter().fold(init, |acc, b| (acc << 8) | (*b as i64)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit() {
+        // [1] INTEGER 5, [2] GeneralString "ab"
+        let data = [
+            0xa1, 0x03, 0x02, 0x01, 0x05, 0xa2, 0x04, 0x1b, 0x02, b'a', b'b',
+        ];
+        let mut reader = DerReader::new(&data);
+        assert!(reader.read_optional(0, TAG_INTEGER).unwrap().is_none());
+        assert_eq!(reader.read_explicit_integer(1).unwrap(), 5);
+        assert_eq!(reader.read_explicit_string(2).unwrap(), "ab");
+        assert!(reader.is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+
+pub(super) struct KeytabEntry {
+    pub(super) realm: String,
+    /// the name components joined by '/'
+    pub(super) name: String,
+    pub(super) kvno: u32,
+    pub(super) etype: i32,
+    pub(super) key: Vec<u8>,
+}
+
+impl KeytabEntry {
+    pub(super) fn principal(&self) -> String {
+        format!("{}@{}", self.name, self.realm)
+    }
+}
+
+struct KeytabReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> KeytabReader<'a> {
+    fn read_bytes(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(anyhow!("unexpected end of data"));
+        }
+        let (v, left) = self.data.split_at(len);
+        self.data = left;
+        Ok(v)
+    }
+
+    fn read_u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> anyhow::Result<u16> {
+        let b = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn read_u32(&mut self) -> anyhow::Result<u32> {
+        let b = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn read_counted_string(&mut self) -> anyhow::Result<String> {
+        let len = self.read_u16()? as usize;
+        let b = self.read_bytes(len)?;
+        std::str::from_utf8(b)
+            .map(|s| s.to_string())
+            .map_err(|_| anyhow!("invalid utf-8 string"))
+    }
+
+    fn read_entry(&mut self) -> anyhow::Result<KeytabEntry> {
+        let component_count = self.read_u16()?;
+        let realm = self.read_counted_string()?;
+        let mut components = Vec::with_capacity(component_count as usize);
+        for _ in 0..component_count {
+            components.push(self.read_counted_string()?);
+        }
+        let _name_type = self.read_u32()?;
+        let _timestamp = self.read_u32()?;
+        let kvno8 = self.read_u8()?;
+        let etype = self.read_u16()? as i32;
+        let key_len = self.read_u16()? as usize;
+        let key = self.read_bytes(key_len)?.to_vec();
+        // the 32 bit kvno is optional
+        let kvno = if self.data.len() >= 4 {
+            match self.read_u32()? {
+                0 => kvno8 as u32,
+                n => n,
+            }
+        } else {
+            kvno8 as u32
+        };
+        Ok(KeytabEntry {
+            realm,
+            name: components.join("/"),
+            kvno,
+            etype,
+            key,
+        })
+    }
+}
+
+/// parse the keytab file in MIT format version 2
+pub(super) fn parse(data: &[u8]) -> anyhow::Result<Vec<KeytabEntry>> {
+    let mut reader = KeytabReader { data };
+    let version = reader.read_u16().context("no version found")?;
+    if version != 0x0502 {
+        return Err(anyhow!("unsupported keytab version {version:#06x}"));
+    }
+
+    let mut entries = Vec::new();
+    while !reader.data.is_empty() {
+        let size = reader.read_u32().context("no entry size found")? as i32;
+        if size == 0 {
+            break;
+        }
+        let len = size.unsigned_abs() as usize;
+        let data = reader
+            .read_bytes(len)
+            .context(format!("truncated entry #{}", entries.len()))?;
+        if size < 0 {
+            // deleted entry
+            continue;
+        }
+        let mut entry_reader = KeytabReader { data };
+        let entry = entry_reader
+            .read_entry()
+            .context(format!("invalid entry #{}", entries.len()))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+pub(super) fn load(path: &Path) -> anyhow::Result<Vec<KeytabEntry>> {
+    let data = std::fs::read(path)
+        .map_err(|e| anyhow!("failed to read keytab file {}: {e}", path.display()))?;
+    parse(&data).context(format!("invalid keytab file {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_entry() {
+        let mut data = vec![0x05, 0x02];
+        let mut entry = Vec::new();
+        entry.extend_from_slice(&2u16.to_be_bytes());
+        entry.extend_from_slice(&11u16.to_be_bytes());
+        entry.extend_from_slice(b"EXAMPLE.COM");
+        entry.extend_from_slice(&4u16.to_be_bytes());
+        entry.extend_from_slice(b"HTTP");
+        entry.extend_from_slice(&17u16.to_be_bytes());
+        entry.extend_from_slice(b"proxy.example.com");
+        entry.extend_from_slice(&1u32.to_be_bytes()); // name type
+        entry.extend_from_slice(&0u32.to_be_bytes()); // timestamp
+        entry.push(3); // kvno
+        entry.extend_from_slice(&18u16.to_be_bytes());
+        entry.extend_from_slice(&32u16.to_be_bytes());
+        entry.extend_from_slice(&[0x01; 32]);
+        data.extend_from_slice(&(entry.len() as u32).to_be_bytes());
+        data.extend_from_slice(&entry);
+        // a deleted entry
+        data.extend_from_slice(&(-4i32).to_be_bytes());
+        data.extend_from_slice(&[0; 4]);
+
+        let entries = parse(&data).unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.principal(), "HTTP/proxy.example.com@EXAMPLE.COM");
+        assert_eq!(entry.kvno, 3);
+        assert_eq!(entry.etype, 18);
+        assert_eq!(entry.key.len(), 32);
+    }
+}
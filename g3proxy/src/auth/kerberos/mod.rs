@@ -0,0 +1,687 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::{Arc, Mutex};
+
+use ahash::AHashMap;
+use anyhow::{anyhow, Context};
+use chrono::{NaiveDateTime, Utc};
+
+use crate::config::auth::KerberosAuthConfig;
+
+mod crypto;
+mod der;
+mod keytab;
+
+use der::{DerReader, TAG_GENERALIZED_TIME, TAG_GENERAL_STRING, TAG_INTEGER, TAG_OCTET_STRING};
+use keytab::KeytabEntry;
+
+const OID_SPNEGO: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x02];
+const OID_KRB5: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x12, 0x01, 0x02, 0x02];
+const OID_MS_KRB5: &[u8] = &[0x2a, 0x86, 0x48, 0x82, 0xf7, 0x12, 0x01, 0x02, 0x02];
+
+const TAG_GSS_TOKEN: u8 = der::application(0);
+const TAG_TICKET: u8 = der::application(1);
+const TAG_AUTHENTICATOR: u8 = der::application(2);
+const TAG_ENC_TICKET_PART: u8 = der::application(3);
+const TAG_AP_REQ: u8 = der::application(14);
+
+const KRB5_TOK_ID_AP_REQ: [u8; 2] = [0x01, 0x00];
+
+const KEY_USAGE_TICKET: u32 = 2;
+const KEY_USAGE_AP_REQ_AUTHENTICATOR: u32 = 11;
+
+const MAX_REPLAY_CACHE_COUNT: usize = 1 << 20;
+
+struct EncryptedData<'a> {
+    etype: i32,
+    kvno: Option<u32>,
+    cipher: &'a [u8],
+}
+
+impl<'a> EncryptedData<'a> {
+    fn parse(data: &'a [u8]) -> anyhow::Result<Self> {
+        let mut reader = DerReader::new(data);
+        let etype = reader.read_explicit_integer(0)? as i32;
+        let kvno = match reader.read_optional(1, TAG_INTEGER)? {
+            Some(v) => Some(der::decode_integer(v)? as u32),
+            None => None,
+        };
+        let cipher = reader.read_explicit(2, TAG_OCTET_STRING)?;
+        Ok(EncryptedData {
+            etype,
+            kvno,
+            cipher,
+        })
+    }
+}
+
+fn parse_principal_name(data: &[u8]) -> anyhow::Result<String> {
+    let mut reader = DerReader::new(data);
+    let _name_type = reader.read_explicit_integer(0)?;
+    let seq = reader.read_explicit(1, der::TAG_SEQUENCE)?;
+    let mut reader = DerReader::new(seq);
+    let mut components = Vec::new();
+    while !reader.is_empty() {
+        let v = reader.read_expected(TAG_GENERAL_STRING)?;
+        let s = std::str::from_utf8(v).map_err(|_| anyhow!("invalid principal name"))?;
+        components.push(s);
+    }
+    if components.is_empty() {
+        return Err(anyhow!("empty principal name"));
+    }
+    Ok(components.join("/"))
+}
+
+fn parse_kerberos_time(data: &[u8]) -> anyhow::Result<i64> {
+    // KerberosTime should always be in the form of YYYYMMDDHHMMSSZ
+    if data.len() != 15 {
+        return Err(anyhow!("invalid kerberos time length"));
+    }
+    let s = std::str::from_utf8(data).map_err(|_| anyhow!("invalid kerberos time"))?;
+    let datetime = NaiveDateTime::parse_from_str(s, "%Y%m%d%H%M%SZ")
+        .map_err(|e| anyhow!("invalid kerberos time {s}: {e}"))?;
+    Ok(datetime.and_utc().timestamp())
+}
+
+/// get the kerberos AP-REQ from the GSS-API / SPNEGO token
+fn extract_ap_req(token: &[u8], allow_spnego: bool) -> anyhow::Result<&[u8]> {
+    if token.starts_with(b"NTLMSSP\0") {
+        return Err(anyhow!("NTLM is not supported"));
+    }
+
+    let mut reader = DerReader::new(token);
+    let value = reader
+        .read_expected(TAG_GSS_TOKEN)
+        .context("invalid gss-api token")?;
+    let mut reader = DerReader::new(value);
+    let oid = reader.read_expected(der::TAG_OID)?;
+    let inner = reader.left();
+    if oid == OID_KRB5 || oid == OID_MS_KRB5 {
+        if inner.len() < 2 || inner[..2] != KRB5_TOK_ID_AP_REQ {
+            return Err(anyhow!("the kerberos token is not an AP-REQ"));
+        }
+        Ok(&inner[2..])
+    } else if oid == OID_SPNEGO && allow_spnego {
+        // NegotiationToken ::= CHOICE { negTokenInit [0] NegTokenInit, ... }
+        let mut reader = DerReader::new(inner);
+        let value = reader
+            .read_expected(der::context(0))
+            .context("not a spnego NegTokenInit")?;
+        let value = DerReader::new(value).read_expected(der::TAG_SEQUENCE)?;
+        let mut reader = DerReader::new(value);
+        reader.skip_to(2)?;
+        let Some(mech_token) = reader.read_optional(2, TAG_OCTET_STRING)? else {
+            return Err(anyhow!("no mech token found in spnego NegTokenInit"));
+        };
+        extract_ap_req(mech_token, false)
+    } else {
+        Err(anyhow!("unsupported gss-api mechanism"))
+    }
+}
+
+pub(crate) struct KerberosAuth {
+    config: Arc<KerberosAuthConfig>,
+    keys: Vec<KeytabEntry>,
+    replay_cache: Mutex<AHashMap<[u8; 32], i64>>,
+}
+
+impl KerberosAuth {
+    pub(crate) fn new(config: Arc<KerberosAuthConfig>) -> anyhow::Result<Self> {
+        let keys = keytab::load(&config.keytab)?;
+        KerberosAuth::with_keys(config, keys)
+    }
+
+    fn with_keys(
+        config: Arc<KerberosAuthConfig>,
+        mut keys: Vec<KeytabEntry>,
+    ) -> anyhow::Result<Self> {
+        keys.retain(|k| crypto::is_supported(k.etype));
+        if let Some(principal) = &config.service_principal {
+            keys.retain(|k| k.principal().eq(principal));
+        }
+        if keys.is_empty() {
+            return Err(anyhow!(
+                "no usable key found in keytab file {}",
+                config.keytab.display()
+            ));
+        }
+        Ok(KerberosAuth {
+            config,
+            keys,
+            replay_cache: Mutex::new(AHashMap::new()),
+        })
+    }
+
+    #[inline]
+    pub(crate) fn config(&self) -> &Arc<KerberosAuthConfig> {
+        &self.config
+    }
+
+    /// verify the Negotiate token, and return the client principal
+    pub(crate) fn verify(&self, token: &[u8]) -> anyhow::Result<String> {
+        self.verify_at(token, Utc::now().timestamp())
+    }
+
+    fn verify_at(&self, token: &[u8], now: i64) -> anyhow::Result<String> {
+        let ap_req = extract_ap_req(token, true)?;
+
+        let value = DerReader::new(ap_req)
+            .read_expected(TAG_AP_REQ)
+            .context("invalid AP-REQ")?;
+        let value = DerReader::new(value).read_expected(der::TAG_SEQUENCE)?;
+        let mut reader = DerReader::new(value);
+        let pvno = reader.read_explicit_integer(0)?;
+        let msg_type = reader.read_explicit_integer(1)?;
+        if pvno != 5 || msg_type != 14 {
+            return Err(anyhow!("invalid AP-REQ pvno {pvno} or msg-type {msg_type}"));
+        }
+        let _ap_options = reader.read_explicit(2, der::TAG_BIT_STRING)?;
+        let ticket = reader.read_explicit(3, TAG_TICKET)?;
+        let authenticator = reader.read_explicit(4, der::TAG_SEQUENCE)?;
+        let authenticator = EncryptedData::parse(authenticator).context("invalid authenticator")?;
+
+        // Ticket
+        let value = DerReader::new(ticket).read_expected(der::TAG_SEQUENCE)?;
+        let mut reader = DerReader::new(value);
+        let _tkt_vno = reader.read_explicit_integer(0)?;
+        let server_realm = reader.read_explicit_string(1)?;
+        let server_name = reader.read_explicit(2, der::TAG_SEQUENCE)?;
+        let server_name = parse_principal_name(server_name)?;
+        let enc_part = reader.read_explicit(3, der::TAG_SEQUENCE)?;
+        let enc_part = EncryptedData::parse(enc_part).context("invalid ticket enc-part")?;
+
+        let ticket_part = self.decrypt_ticket(&server_realm, &server_name, &enc_part)?;
+
+        // EncTicketPart
+        let value = DerReader::new(&ticket_part)
+            .read_expected(TAG_ENC_TICKET_PART)
+            .context("invalid EncTicketPart")?;
+        let value = DerReader::new(value).read_expected(der::TAG_SEQUENCE)?;
+        let mut reader = DerReader::new(value);
+        let _flags = reader.read_explicit(0, der::TAG_BIT_STRING)?;
+        let session_key = reader.read_explicit(1, der::TAG_SEQUENCE)?;
+        let mut key_reader = DerReader::new(session_key);
+        let session_key_type = key_reader.read_explicit_integer(0)? as i32;
+        let session_key = key_reader.read_explicit(1, TAG_OCTET_STRING)?;
+        let client_realm = reader.read_explicit_string(2)?;
+        let client_name = reader.read_explicit(3, der::TAG_SEQUENCE)?;
+        let client_name = parse_principal_name(client_name)?;
+        reader.skip_to(5)?;
+        let auth_time = parse_kerberos_time(reader.read_explicit(5, TAG_GENERALIZED_TIME)?)?;
+        let start_time = match reader.read_optional(6, TAG_GENERALIZED_TIME)? {
+            Some(v) => parse_kerberos_time(v)?,
+            None => auth_time,
+        };
+        let end_time = parse_kerberos_time(reader.read_explicit(7, TAG_GENERALIZED_TIME)?)?;
+
+        let skew = self.config.max_clock_skew.as_secs() as i64;
+        if now < start_time - skew {
+            return Err(anyhow!("ticket is not yet valid"));
+        }
+        if now > end_time + skew {
+            return Err(anyhow!("ticket has been expired"));
+        }
+        if !self.config.realms.is_empty() && !self.config.realms.contains(&client_realm) {
+            return Err(anyhow!("client realm {client_realm} is not allowed"));
+        }
+
+        // Authenticator
+        if authenticator.etype != session_key_type {
+            return Err(anyhow!("authenticator etype mismatch with the session key"));
+        }
+        let authenticator_data = crypto::decrypt(
+            session_key_type,
+            session_key,
+            KEY_USAGE_AP_REQ_AUTHENTICATOR,
+            authenticator.cipher,
+        )
+        .context("failed to decrypt authenticator")?;
+        let value = DerReader::new(&authenticator_data)
+            .read_expected(TAG_AUTHENTICATOR)
+            .context("invalid Authenticator")?;
+        let value = DerReader::new(value).read_expected(der::TAG_SEQUENCE)?;
+        let mut reader = DerReader::new(value);
+        let _authenticator_vno = reader.read_explicit_integer(0)?;
+        let realm = reader.read_explicit_string(1)?;
+        let name = reader.read_explicit(2, der::TAG_SEQUENCE)?;
+        let name = parse_principal_name(name)?;
+        if realm != client_realm || name != client_name {
+            return Err(anyhow!("client principal mismatch in authenticator"));
+        }
+        reader.skip_to(4)?;
+        let _cusec = reader.read_explicit_integer(4)?;
+        let client_time = parse_kerberos_time(reader.read_explicit(5, TAG_GENERALIZED_TIME)?)?;
+        if (now - client_time).abs() > skew {
+            return Err(anyhow!("clock skew too great"));
+        }
+
+        self.check_replay(authenticator.cipher, client_time + skew, now)?;
+
+        Ok(format!("{client_name}@{client_realm}"))
+    }
+
+    fn decrypt_ticket(
+        &self,
+        server_realm: &str,
+        server_name: &str,
+        enc_part: &EncryptedData,
+    ) -> anyhow::Result<Vec<u8>> {
+        if let Some(principal) = &self.config.service_principal {
+            let ticket_principal = format!("{server_name}@{server_realm}");
+            if ticket_principal.ne(principal) {
+                return Err(anyhow!(
+                    "the ticket is issued for {ticket_principal} but not {principal}"
+                ));
+            }
+        }
+
+        let mut keys: Vec<&KeytabEntry> = self
+            .keys
+            .iter()
+            .filter(|k| k.etype == enc_part.etype)
+            .filter(|k| k.realm == server_realm && k.name == server_name)
+            .collect();
+        if keys.is_empty() {
+            return Err(anyhow!(
+                "no key found for {server_name}@{server_realm} with etype {}",
+                enc_part.etype
+            ));
+        }
+        if let Some(kvno) = enc_part.kvno {
+            // try the key with the matched kvno first
+            keys.sort_by_key(|k| k.kvno != kvno);
+        }
+
+        for key in keys {
+            if let Ok(data) =
+                crypto::decrypt(key.etype, &key.key, KEY_USAGE_TICKET, enc_part.cipher)
+            {
+                return Ok(data);
+            }
+        }
+        Err(anyhow!(
+            "failed to decrypt ticket for {server_name}@{server_realm}"
+        ))
+    }
+
+    fn check_replay(&self, authenticator: &[u8], expire: i64, now: i64) -> anyhow::Result<()> {
+        let digest = openssl::sha::sha256(authenticator);
+        let mut cache = self.replay_cache.lock().unwrap();
+        if cache.len() >= MAX_REPLAY_CACHE_COUNT {
+            cache.retain(|_, v| *v >= now);
+            if cache.len() >= MAX_REPLAY_CACHE_COUNT {
+                return Err(anyhow!("replay cache is full"));
+            }
+        }
+        match cache.get(&digest) {
+            Some(v) if *v >= now => Err(anyhow!("replayed authenticator")),
+            _ => {
+                cache.insert(digest, expire);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REALM: &str = "EXAMPLE.COM";
+    const SERVICE: &[&str] = &["HTTP", "proxy.example.com"];
+    const CLIENT: &[&str] = &["alice"];
+    const NOW: &[u8] = b"20240102030405Z";
+
+    fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+        let mut v = vec![tag];
+        let len = value.len();
+        if len < 0x80 {
+            v.push(len as u8);
+        } else if len < 0x100 {
+            v.extend_from_slice(&[0x81, len as u8]);
+        } else {
+            v.push(0x82);
+            v.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        v.extend_from_slice(value);
+        v
+    }
+
+    fn explicit(n: u8, tag: u8, value: &[u8]) -> Vec<u8> {
+        tlv(der::context(n), &tlv(tag, value))
+    }
+
+    fn explicit_integer(n: u8, v: u8) -> Vec<u8> {
+        explicit(n, TAG_INTEGER, &[v])
+    }
+
+    fn principal_name(n: u8, components: &[&str]) -> Vec<u8> {
+        let mut names = Vec::new();
+        for c in components {
+            names.extend(tlv(TAG_GENERAL_STRING, c.as_bytes()));
+        }
+        let mut v = explicit_integer(0, 1);
+        v.extend(explicit(1, der::TAG_SEQUENCE, &names));
+        explicit(n, der::TAG_SEQUENCE, &v)
+    }
+
+    fn encrypted_data(n: u8, kvno: u8, cipher: &[u8]) -> Vec<u8> {
+        let mut v = explicit_integer(0, crypto::ETYPE_AES256_CTS_HMAC_SHA1_96 as u8);
+        v.extend(explicit_integer(1, kvno));
+        v.extend(explicit(2, TAG_OCTET_STRING, cipher));
+        explicit(n, der::TAG_SEQUENCE, &v)
+    }
+
+    struct TicketBuilder {
+        service_key: Vec<u8>,
+        session_key: Vec<u8>,
+        service: &'static [&'static str],
+        start_time: &'static str,
+        end_time: &'static str,
+        client_time: &'static str,
+        confounder: u8,
+    }
+
+    impl Default for TicketBuilder {
+        fn default() -> Self {
+            TicketBuilder {
+                service_key: vec![0x5a; 32],
+                session_key: vec![0xa5; 32],
+                service: SERVICE,
+                start_time: "20240102000000Z",
+                end_time: "20240102100000Z",
+                client_time: "20240102030400Z",
+                confounder: 0x11,
+            }
+        }
+    }
+
+    impl TicketBuilder {
+        fn build(&self) -> Vec<u8> {
+            let etype = crypto::ETYPE_AES256_CTS_HMAC_SHA1_96;
+
+            // EncTicketPart
+            let mut session_key = explicit_integer(0, etype as u8);
+            session_key.extend(explicit(1, TAG_OCTET_STRING, &self.session_key));
+            let mut v = explicit(0, der::TAG_BIT_STRING, &[0, 0, 0, 0, 0]);
+            v.extend(explicit(1, der::TAG_SEQUENCE, &session_key));
+            v.extend(explicit(2, TAG_GENERAL_STRING, REALM.as_bytes()));
+            v.extend(principal_name(3, CLIENT));
+            let mut transited = explicit_integer(0, 1);
+            transited.extend(explicit(1, TAG_OCTET_STRING, &[]));
+            v.extend(explicit(4, der::TAG_SEQUENCE, &transited));
+            v.extend(explicit(
+                5,
+                TAG_GENERALIZED_TIME,
+                self.start_time.as_bytes(),
+            ));
+            v.extend(explicit(
+                6,
+                TAG_GENERALIZED_TIME,
+                self.start_time.as_bytes(),
+            ));
+            v.extend(explicit(7, TAG_GENERALIZED_TIME, self.end_time.as_bytes()));
+            let ticket_part = tlv(TAG_ENC_TICKET_PART, &tlv(der::TAG_SEQUENCE, &v));
+            let ticket_cipher = crypto::encrypt(
+                etype,
+                &self.service_key,
+                KEY_USAGE_TICKET,
+                &[self.confounder; 16],
+                &ticket_part,
+            )
+            .unwrap();
+
+            // Ticket
+            let mut v = explicit_integer(0, 5);
+            v.extend(explicit(1, TAG_GENERAL_STRING, REALM.as_bytes()));
+            v.extend(principal_name(2, self.service));
+            v.extend(encrypted_data(3, 2, &ticket_cipher));
+            let ticket = tlv(der::TAG_SEQUENCE, &v);
+
+            // Authenticator
+            let mut v = explicit_integer(0, 5);
+            v.extend(explicit(1, TAG_GENERAL_STRING, REALM.as_bytes()));
+            v.extend(principal_name(2, CLIENT));
+            v.extend(explicit_integer(4, 0));
+            v.extend(explicit(
+                5,
+                TAG_GENERALIZED_TIME,
+                self.client_time.as_bytes(),
+            ));
+            let authenticator = tlv(TAG_AUTHENTICATOR, &tlv(der::TAG_SEQUENCE, &v));
+            let authenticator_cipher = crypto::encrypt(
+                etype,
+                &self.session_key,
+                KEY_USAGE_AP_REQ_AUTHENTICATOR,
+                &[self.confounder; 16],
+                &authenticator,
+            )
+            .unwrap();
+
+            // AP-REQ
+            let mut v = explicit_integer(0, 5);
+            v.extend(explicit_integer(1, 14));
+            v.extend(explicit(2, der::TAG_BIT_STRING, &[0, 0, 0, 0, 0]));
+            v.extend(explicit(3, TAG_TICKET, &ticket));
+            v.extend(encrypted_data(4, 0, &authenticator_cipher));
+            let ap_req = tlv(TAG_AP_REQ, &tlv(der::TAG_SEQUENCE, &v));
+
+            let mut krb5_token = tlv(der::TAG_OID, OID_KRB5);
+            krb5_token.extend_from_slice(&KRB5_TOK_ID_AP_REQ);
+            krb5_token.extend(ap_req);
+            tlv(TAG_GSS_TOKEN, &krb5_token)
+        }
+    }
+
+    fn keytab_data(service: &[&str], key: &[u8]) -> Vec<u8> {
+        let mut entry = Vec::new();
+        entry.extend_from_slice(&(service.len() as u16).to_be_bytes());
+        entry.extend_from_slice(&(REALM.len() as u16).to_be_bytes());
+        entry.extend_from_slice(REALM.as_bytes());
+        for c in service {
+            entry.extend_from_slice(&(c.len() as u16).to_be_bytes());
+            entry.extend_from_slice(c.as_bytes());
+        }
+        entry.extend_from_slice(&1u32.to_be_bytes()); // name type
+        entry.extend_from_slice(&0u32.to_be_bytes()); // timestamp
+        entry.push(2); // kvno
+        entry.extend_from_slice(&(crypto::ETYPE_AES256_CTS_HMAC_SHA1_96 as u16).to_be_bytes());
+        entry.extend_from_slice(&(key.len() as u16).to_be_bytes());
+        entry.extend_from_slice(key);
+
+        let mut data = vec![0x05, 0x02];
+        data.extend_from_slice(&(entry.len() as u32).to_be_bytes());
+        data.extend_from_slice(&entry);
+        data
+    }
+
+    fn new_auth(config: KerberosAuthConfig, service: &[&str], key: &[u8]) -> KerberosAuth {
+        let keys = keytab::parse(&keytab_data(service, key)).unwrap();
+        KerberosAuth::with_keys(Arc::new(config), keys).unwrap()
+    }
+
+    fn now() -> i64 {
+        parse_kerberos_time(NOW).unwrap()
+    }
+
+    #[test]
+    fn kerberos_time() {
+        let t = parse_kerberos_time(b"20240102030405Z").unwrap();
+        assert_eq!(t, 1704164645);
+        assert!(parse_kerberos_time(b"2024010203040Z").is_err());
+    }
+
+    #[test]
+    fn gss_token() {
+        let ap_req = [TAG_AP_REQ, 0x00];
+        let mut krb5_token = vec![der::TAG_OID, OID_KRB5.len() as u8];
+        krb5_token.extend_from_slice(OID_KRB5);
+        krb5_token.extend_from_slice(&KRB5_TOK_ID_AP_REQ);
+        krb5_token.extend_from_slice(&ap_req);
+        let mut gss_token = vec![TAG_GSS_TOKEN, krb5_token.len() as u8];
+        gss_token.extend_from_slice(&krb5_token);
+        assert_eq!(extract_ap_req(&gss_token, true).unwrap(), ap_req);
+
+        // NegTokenInit with mechTypes and mechToken
+        let mut mech_types = vec![der::TAG_SEQUENCE, OID_KRB5.len() as u8 + 2];
+        mech_types.extend_from_slice(&[der::TAG_OID, OID_KRB5.len() as u8]);
+        mech_types.extend_from_slice(OID_KRB5);
+        let mut seq = vec![der::context(0), mech_types.len() as u8];
+        seq.extend_from_slice(&mech_types);
+        seq.extend_from_slice(&[der::context(2), gss_token.len() as u8 + 2]);
+        seq.extend_from_slice(&[TAG_OCTET_STRING, gss_token.len() as u8]);
+        seq.extend_from_slice(&gss_token);
+        let mut init = vec![der::context(0), seq.len() as u8 + 2, der::TAG_SEQUENCE];
+        init.push(seq.len() as u8);
+        init.extend_from_slice(&seq);
+        let mut spnego_token = vec![der::TAG_OID, OID_SPNEGO.len() as u8];
+        spnego_token.extend_from_slice(OID_SPNEGO);
+        spnego_token.extend_from_slice(&init);
+        let mut token = vec![TAG_GSS_TOKEN, spnego_token.len() as u8];
+        token.extend_from_slice(&spnego_token);
+        assert_eq!(extract_ap_req(&token, true).unwrap(), ap_req);
+        assert!(extract_ap_req(&token, false).is_err());
+
+        assert!(extract_ap_req(b"NTLMSSP\0\x01\x00\x00\x00", true).is_err());
+    }
+
+    #[test]
+    fn verify_ap_req() {
+        let auth = new_auth(KerberosAuthConfig::default(), SERVICE, &[0x5a; 32]);
+        let token = TicketBuilder::default().build();
+        assert_eq!(auth.verify_at(&token, now()).unwrap(), "alice@EXAMPLE.COM");
+
+        let config = KerberosAuthConfig {
+            realms: vec!["OTHER.COM".to_string()],
+            ..Default::default()
+        };
+        let auth = new_auth(config, SERVICE, &[0x5a; 32]);
+        let e = auth.verify_at(&token, now()).unwrap_err();
+        assert_eq!(e.to_string(), "client realm EXAMPLE.COM is not allowed");
+    }
+
+    #[test]
+    fn verify_wrong_key() {
+        let auth = new_auth(KerberosAuthConfig::default(), SERVICE, &[0x5b; 32]);
+        let token = TicketBuilder::default().build();
+        let e = auth.verify_at(&token, now()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "failed to decrypt ticket for HTTP/proxy.example.com@EXAMPLE.COM"
+        );
+    }
+
+    #[test]
+    fn verify_ticket_time() {
+        let auth = new_auth(KerberosAuthConfig::default(), SERVICE, &[0x5a; 32]);
+
+        let token = TicketBuilder {
+            end_time: "20240102020000Z",
+            ..Default::default()
+        }
+        .build();
+        let e = auth.verify_at(&token, now()).unwrap_err();
+        assert_eq!(e.to_string(), "ticket has been expired");
+
+        let token = TicketBuilder {
+            start_time: "20240102040000Z",
+            ..Default::default()
+        }
+        .build();
+        let e = auth.verify_at(&token, now()).unwrap_err();
+        assert_eq!(e.to_string(), "ticket is not yet valid");
+
+        // still valid within the allowed clock skew
+        let token = TicketBuilder {
+            end_time: "20240102030300Z",
+            ..Default::default()
+        }
+        .build();
+        assert!(auth.verify_at(&token, now()).is_ok());
+    }
+
+    #[test]
+    fn verify_clock_skew() {
+        let auth = new_auth(KerberosAuthConfig::default(), SERVICE, &[0x5a; 32]);
+
+        let token = TicketBuilder {
+            client_time: "20240102025000Z",
+            ..Default::default()
+        }
+        .build();
+        let e = auth.verify_at(&token, now()).unwrap_err();
+        assert_eq!(e.to_string(), "clock skew too great");
+
+        let token = TicketBuilder {
+            client_time: "20240102031800Z",
+            ..Default::default()
+        }
+        .build();
+        let e = auth.verify_at(&token, now()).unwrap_err();
+        assert_eq!(e.to_string(), "clock skew too great");
+    }
+
+    #[test]
+    fn verify_replay() {
+        let auth = new_auth(KerberosAuthConfig::default(), SERVICE, &[0x5a; 32]);
+
+        let token = TicketBuilder::default().build();
+        assert!(auth.verify_at(&token, now()).is_ok());
+        let e = auth.verify_at(&token, now()).unwrap_err();
+        assert_eq!(e.to_string(), "replayed authenticator");
+
+        // a new authenticator with the same ticket
+        let token = TicketBuilder {
+            confounder: 0x22,
+            ..Default::default()
+        }
+        .build();
+        assert!(auth.verify_at(&token, now()).is_ok());
+    }
+
+    #[test]
+    fn verify_service_principal() {
+        let config = KerberosAuthConfig {
+            service_principal: Some("HTTP/proxy.example.com@EXAMPLE.COM".to_string()),
+            ..Default::default()
+        };
+        let auth = new_auth(config, SERVICE, &[0x5a; 32]);
+        let token = TicketBuilder::default().build();
+        assert_eq!(auth.verify_at(&token, now()).unwrap(), "alice@EXAMPLE.COM");
+
+        // a ticket for another service encrypted with the same key
+        let token = TicketBuilder {
+            service: &["HTTP", "other.example.com"],
+            ..Default::default()
+        }
+        .build();
+        let e = auth.verify_at(&token, now()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "the ticket is issued for HTTP/other.example.com@EXAMPLE.COM \
+             but not HTTP/proxy.example.com@EXAMPLE.COM"
+        );
+
+        // the same is rejected without the service principal config
+        let auth = new_auth(KerberosAuthConfig::default(), SERVICE, &[0x5a; 32]);
+        let e = auth.verify_at(&token, now()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "no key found for HTTP/other.example.com@EXAMPLE.COM with etype 18"
+        );
+    }
+}
@@ -22,12 +22,15 @@ use anyhow::anyhow;
 use arc_swap::ArcSwap;
 use chrono::Utc;
 use futures_util::future::AbortHandle;
-use log::{info, warn};
+use log::{debug, info, warn};
 use nix::NixPath;
+use openssl::x509::X509Ref;
 
+use g3_types::auth::UserAuthError;
 use g3_types::metrics::MetricsName;
 
 use crate::config::auth::UserGroupConfig;
+use crate::serve::alive_task::AliveTaskFilter;
 
 mod ops;
 pub use ops::load_all;
@@ -45,12 +48,28 @@ pub(crate) use user::{User, UserContext};
 mod stats;
 pub(crate) use stats::{
     UserForbiddenSnapshot, UserForbiddenStats, UserRequestSnapshot, UserRequestStats,
-    UserSiteDurationRecorder, UserSiteDurationStats, UserSiteStats, UserTrafficSnapshot,
-    UserTrafficStats, UserUpstreamTrafficSnapshot, UserUpstreamTrafficStats,
+    UserSiteDurationRecorder, UserSiteDurationStats, UserSiteEmitControl, UserSiteStats,
+    UserTrafficSnapshot, UserTrafficStats, UserUpstreamTrafficSnapshot, UserUpstreamTrafficStats,
 };
 
 mod source;
 
+mod radius;
+use radius::RadiusAuth;
+
+mod jwt;
+
+mod kerberos;
+use kerberos::KerberosAuth;
+
+mod quota;
+use quota::UserTrafficQuota;
+
+mod password_cache;
+use password_cache::PasswordCache;
+
+mod cert_user;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(crate) enum UserType {
     Static,
@@ -74,7 +93,13 @@ pub(crate) struct UserGroup {
     dynamic_users: Arc<ArcSwap<AHashMap<String, Arc<User>>>>,
     /// the dynamic job is for both dynamic fetch and expire check
     dynamic_job_handler: Option<AbortHandle>,
+    /// the quota job is for traffic quota accounting and persistence
+    quota_job_handler: Option<AbortHandle>,
     anonymous_user: Option<Arc<User>>,
+    radius: Option<Arc<RadiusAuth>>,
+    radius_user: Option<Arc<User>>,
+    kerberos: Option<Arc<KerberosAuth>>,
+    password_cache: Option<Arc<PasswordCache>>,
 }
 
 impl Drop for UserGroup {
@@ -82,17 +107,26 @@ impl Drop for UserGroup {
         if let Some(handler) = self.dynamic_job_handler.take() {
             handler.abort();
         }
+        if let Some(handler) = self.quota_job_handler.take() {
+            handler.abort();
+        }
     }
 }
 
 impl UserGroup {
     fn new_without_users(config: UserGroupConfig) -> Self {
+        let password_cache = PasswordCache::new(&config).map(Arc::new);
         UserGroup {
             config: Arc::new(config),
             static_users: Arc::new(AHashMap::new()),
             dynamic_users: Arc::new(ArcSwap::from_pointee(AHashMap::new())),
             dynamic_job_handler: None,
+            quota_job_handler: None,
             anonymous_user: None,
+            radius: None,
+            radius_user: None,
+            kerberos: None,
+            password_cache,
         }
     }
 
@@ -113,6 +147,19 @@ impl UserGroup {
             .anonymous_user
             .as_ref()
             .map(|user_config| User::new(config.name(), user_config, &datetime_now));
+        let radius_user = config
+            .radius_user
+            .as_ref()
+            .map(|user_config| User::new(config.name(), user_config, &datetime_now));
+        let radius = config
+            .radius
+            .as_ref()
+            .map(|radius_config| RadiusAuth::new(Arc::clone(radius_config)));
+        let kerberos = config
+            .kerberos
+            .as_ref()
+            .map(|kerberos_config| KerberosAuth::new(Arc::clone(kerberos_config)))
+            .transpose()?;
 
         let mut group = Self::new_without_users(config);
         group.static_users = Arc::new(users);
@@ -136,12 +183,20 @@ impl UserGroup {
         }
 
         group.anonymous_user = anonymous_user.map(Arc::new);
+        group.radius_user = radius_user.map(Arc::new);
+        group.radius = radius.map(Arc::new);
+        group.kerberos = kerberos.map(Arc::new);
 
         group.dynamic_job_handler = Some(source::new_job(
             &group.config,
             &group.static_users,
             &group.dynamic_users,
         ));
+        group.quota_job_handler = Some(quota::new_job(
+            &group.config,
+            &group.static_users,
+            &group.dynamic_users,
+        ));
 
         Ok(Arc::new(group))
     }
@@ -164,6 +219,24 @@ impl UserGroup {
                 .map(|old| old.new_for_reload(user_config, &datetime_now))
                 .unwrap_or_else(|| User::new(config.name(), user_config, &datetime_now))
         });
+        let radius_user = config.radius_user.as_ref().map(|user_config| {
+            self.radius_user
+                .as_ref()
+                .map(|old| old.new_for_reload(user_config, &datetime_now))
+                .unwrap_or_else(|| User::new(config.name(), user_config, &datetime_now))
+        });
+        let radius = config.radius.as_ref().map(|radius_config| {
+            match &self.radius {
+                // keep the success cache if the radius config is not changed
+                Some(old) if old.config().as_ref() == radius_config.as_ref() => Arc::clone(old),
+                _ => Arc::new(RadiusAuth::new(Arc::clone(radius_config))),
+            }
+        });
+        let kerberos = config
+            .kerberos
+            .as_ref()
+            .map(|kerberos_config| KerberosAuth::new(Arc::clone(kerberos_config)))
+            .transpose()?;
 
         let mut dynamic_users = AHashMap::new();
         if self.config.dynamic_source.is_some() && config.dynamic_source.is_some() {
@@ -181,12 +254,20 @@ impl UserGroup {
         }
 
         group.anonymous_user = anonymous_user.map(Arc::new);
+        group.radius_user = radius_user.map(Arc::new);
+        group.radius = radius;
+        group.kerberos = kerberos.map(Arc::new);
 
         group.dynamic_job_handler = Some(source::new_job(
             &group.config,
             &group.static_users,
             &group.dynamic_users,
         ));
+        group.quota_job_handler = Some(quota::new_job(
+            &group.config,
+            &group.static_users,
+            &group.dynamic_users,
+        ));
 
         Ok(Arc::new(group))
     }
@@ -202,7 +283,8 @@ impl UserGroup {
             .map(|u| (Arc::clone(u), UserType::Anonymous))
     }
 
-    pub(crate) fn get_user(&self, username: &str) -> Option<(Arc<User>, UserType)> {
+    /// get the static or dynamic user by name, without falling back to the radius or anonymous user
+    pub(crate) fn get_named_user(&self, username: &str) -> Option<(Arc<User>, UserType)> {
         if let Some(user) = self.static_users.get(username) {
             return Some((Arc::clone(user), UserType::Static));
         }
@@ -214,9 +296,146 @@ impl UserGroup {
             }
         }
 
+        None
+    }
+
+    pub(crate) fn get_user(&self, username: &str) -> Option<(Arc<User>, UserType)> {
+        if let Some(r) = self.get_named_user(username) {
+            return Some(r);
+        }
+
+        if self.radius.is_some() {
+            if let Some(user) = &self.radius_user {
+                return Some((Arc::clone(user), UserType::Dynamic));
+            }
+        }
+
         self.get_anonymous_user()
     }
 
+    /// get the user by the bearer token, the anonymous user will be used if jwt is not configured.
+    /// The returned username is the one in the claims.
+    pub(crate) fn get_user_by_token(
+        &self,
+        token: &str,
+    ) -> Result<(Arc<User>, UserType, Option<String>), UserAuthError> {
+        let Some(jwt_config) = &self.config.jwt else {
+            return self
+                .get_anonymous_user()
+                .map(|(user, user_type)| (user, user_type, None))
+                .ok_or(UserAuthError::NoUserSupplied);
+        };
+
+        let claims = jwt::verify_token(jwt_config, token).map_err(|e| {
+            debug!(
+                "invalid jwt token for user-group {}: {e}",
+                self.config.name()
+            );
+            UserAuthError::TokenNotMatch
+        })?;
+
+        if let Some((user, user_type)) = self.get_named_user(&claims.username) {
+            return Ok((user, user_type, Some(claims.username)));
+        }
+        for group in &claims.groups {
+            if let Some(name) = jwt_config.group_users.get(group) {
+                if let Some((user, user_type)) = self.get_named_user(name) {
+                    return Ok((user, user_type, Some(claims.username)));
+                }
+            }
+        }
+        Err(UserAuthError::NoSuchUser)
+    }
+
+    #[inline]
+    pub(crate) fn allow_negotiate(&self) -> bool {
+        self.kerberos.is_some()
+    }
+
+    /// get the user by the Negotiate token, the anonymous user will be used if kerberos is not configured.
+    /// The returned username is the client principal.
+    pub(crate) fn get_user_by_negotiate(
+        &self,
+        token: &[u8],
+    ) -> Result<(Arc<User>, UserType, Option<String>), UserAuthError> {
+        let Some(kerberos) = &self.kerberos else {
+            return self
+                .get_anonymous_user()
+                .map(|(user, user_type)| (user, user_type, None))
+                .ok_or(UserAuthError::NoUserSupplied);
+        };
+
+        let principal = kerberos.verify(token).map_err(|e| {
+            debug!(
+                "invalid negotiate token for user-group {}: {e:?}",
+                self.config.name()
+            );
+            UserAuthError::TokenNotMatch
+        })?;
+
+        let config = kerberos.config();
+        let username = match config.principal_users.get(&principal) {
+            Some(name) => name.as_str(),
+            None if config.strip_realm => principal
+                .rsplit_once('@')
+                .map(|(name, _)| name)
+                .unwrap_or(&principal),
+            None => principal.as_str(),
+        };
+        match self.get_named_user(username) {
+            Some((user, user_type)) => Ok((user, user_type, Some(principal))),
+            None => Err(UserAuthError::NoSuchUser),
+        }
+    }
+
+    /// get the user by the client certificate, using the first matched rule.
+    /// The returned username is the matched certificate attribute value.
+    pub(crate) fn get_user_by_client_cert(
+        &self,
+        cert: &X509Ref,
+    ) -> Option<(Arc<User>, UserType, Option<String>)> {
+        if self.config.client_cert_users.is_empty() {
+            return None;
+        }
+        let (username, matched) = cert_user::match_user(&self.config.client_cert_users, cert)?;
+        match self.get_named_user(&username) {
+            Some((user, user_type)) => Some((user, user_type, Some(matched))),
+            None => {
+                debug!(
+                    "no user {username} found in user-group {} for client cert {matched}",
+                    self.config.name()
+                );
+                None
+            }
+        }
+    }
+
+    /// check the password of the user, it will be verified by the radius server if configured,
+    /// or the local verification result will be cached if enabled
+    pub(crate) async fn check_password(
+        &self,
+        user_ctx: &UserContext,
+        password: &str,
+    ) -> Result<(), UserAuthError> {
+        if let Some(radius) = &self.radius {
+            if user_ctx.user_type() != UserType::Anonymous {
+                let username = user_ctx.raw_user_name().unwrap_or(user_ctx.user_name());
+                let verified = radius.verify(username, password).await;
+                return user_ctx.check_external_auth(verified);
+            }
+        }
+        if let Some(cache) = &self.password_cache {
+            let verified = cache.verify(user_ctx, password);
+            return user_ctx.check_external_auth(verified);
+        }
+        user_ctx.check_password(password)
+    }
+
+    #[inline]
+    pub(crate) fn name(&self) -> &MetricsName {
+        self.config.name()
+    }
+
     pub(crate) fn foreach_user<F>(&self, mut f: F)
     where
         F: FnMut(&str, &Arc<User>),
@@ -253,6 +472,66 @@ impl UserGroup {
         dynamic_users.keys().map(|k| k.to_string()).collect()
     }
 
+    pub(crate) fn traffic_quota_status(&self, username: &str) -> anyhow::Result<String> {
+        let Some((user, _)) = self.get_named_user(username) else {
+            return Err(anyhow!("no user {username} found"));
+        };
+        let Some((config, quota)) = user.traffic_quota() else {
+            return Err(anyhow!("no traffic quota set for user {username}"));
+        };
+        Ok(quota.status(config))
+    }
+
+    pub(crate) fn set_user_blocked(&self, username: &str, blocked: bool) -> anyhow::Result<()> {
+        let Some((user, _)) = self.get_named_user(username) else {
+            return Err(anyhow!("no user {username} found"));
+        };
+        user.set_ctl_blocked(blocked);
+        Ok(())
+    }
+
+    pub(crate) fn reset_traffic_quota(&self, username: &str) -> anyhow::Result<()> {
+        let Some((user, _)) = self.get_named_user(username) else {
+            return Err(anyhow!("no user {username} found"));
+        };
+        let Some((_, quota)) = user.traffic_quota() else {
+            return Err(anyhow!("no traffic quota set for user {username}"));
+        };
+        quota.reset();
+        Ok(())
+    }
+
+    /// flush the cached password and close all alive tasks of the user,
+    /// and return the count of closed tasks
+    pub(crate) fn expire_user_sessions(&self, username: &str) -> usize {
+        if let Some(cache) = &self.password_cache {
+            cache.flush(Some(username));
+        }
+        let filter = AliveTaskFilter {
+            server: None,
+            user_group: Some(self.name().clone()),
+            user: Some(username.to_string()),
+        };
+        crate::serve::alive_task::kill_matched(&filter)
+    }
+
+    pub(crate) fn password_cache_status(&self) -> anyhow::Result<String> {
+        match &self.password_cache {
+            Some(cache) => Ok(cache.status()),
+            None => Err(anyhow!("password cache is not enabled")),
+        }
+    }
+
+    pub(crate) fn flush_password_cache(&self, username: Option<&str>) -> anyhow::Result<()> {
+        match &self.password_cache {
+            Some(cache) => {
+                cache.flush(username);
+                Ok(())
+            }
+            None => Err(anyhow!("password cache is not enabled")),
+        }
+    }
+
     pub(crate) async fn publish_dynamic_users(&self, contents: &str) -> anyhow::Result<()> {
         let doc = serde_json::Value::from_str(contents)
             .map_err(|e| anyhow!("the published contents is not valid json: {e}",))?;
@@ -275,3 +554,33 @@ impl UserGroup {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    #[tokio::test]
+    async fn get_named_user() {
+        let docs = YamlLoader::load_from_str(
+            "
+name: g1
+static_users:
+  - name: a
+anonymous_user:
+  name: anonymous
+",
+        )
+        .unwrap();
+        let mut config = UserGroupConfig::new(None);
+        config.parse(docs[0].as_hash().unwrap()).unwrap();
+        let group = UserGroup::new_with_config(config).await.unwrap();
+
+        let (_, user_type) = group.get_named_user("a").unwrap();
+        assert_eq!(user_type, UserType::Static);
+        assert!(group.get_named_user("b").is_none());
+
+        let (_, user_type) = group.get_user("b").unwrap();
+        assert_eq!(user_type, UserType::Anonymous);
+    }
+}
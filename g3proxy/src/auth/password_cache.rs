@@ -0,0 +1,127 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use ahash::AHashMap;
+
+use super::{User, UserContext};
+use crate::config::auth::UserGroupConfig;
+
+const MAX_CACHE_COUNT: usize = 65536;
+
+struct CachedVerification {
+    user: Weak<User>,
+    password_digest: [u8; 32],
+    verified: bool,
+    expire: Instant,
+}
+
+pub(super) struct PasswordCache {
+    ttl: Duration,
+    failed_ttl: Duration,
+    cache: Mutex<AHashMap<String, CachedVerification>>,
+    hit: AtomicU64,
+    miss: AtomicU64,
+}
+
+impl PasswordCache {
+    pub(super) fn new(config: &UserGroupConfig) -> Option<Self> {
+        if config.password_cache_ttl.is_zero() && config.password_cache_failed_ttl.is_zero() {
+            return None;
+        }
+        Some(PasswordCache {
+            ttl: config.password_cache_ttl,
+            failed_ttl: config.password_cache_failed_ttl,
+            cache: Mutex::new(AHashMap::new()),
+            hit: AtomicU64::new(0),
+            miss: AtomicU64::new(0),
+        })
+    }
+
+    /// verify the password of the user, recent results will be served from the cache
+    pub(super) fn verify(&self, user_ctx: &UserContext, password: &str) -> bool {
+        let password_digest = openssl::sha::sha256(password.as_bytes());
+        if let Some(verified) = self.check_cache(user_ctx, &password_digest) {
+            self.hit.fetch_add(1, Ordering::Relaxed);
+            return verified;
+        }
+        self.miss.fetch_add(1, Ordering::Relaxed);
+
+        let verified = user_ctx.user_config().check_password(password);
+        self.add_cache(user_ctx, password_digest, verified);
+        verified
+    }
+
+    fn check_cache(&self, user_ctx: &UserContext, password_digest: &[u8; 32]) -> Option<bool> {
+        let cache = self.cache.lock().unwrap();
+        let c = cache.get(user_ctx.user_name())?;
+        // the cache is invalid if the user has been reloaded
+        if c.expire > Instant::now()
+            && Weak::as_ptr(&c.user) == Arc::as_ptr(user_ctx.user())
+            && openssl::memcmp::eq(&c.password_digest, password_digest)
+        {
+            Some(c.verified)
+        } else {
+            None
+        }
+    }
+
+    fn add_cache(&self, user_ctx: &UserContext, password_digest: [u8; 32], verified: bool) {
+        let ttl = if verified { self.ttl } else { self.failed_ttl };
+        if ttl.is_zero() {
+            return;
+        }
+        let now = Instant::now();
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHE_COUNT {
+            cache.retain(|_, c| c.expire > now);
+            if cache.len() >= MAX_CACHE_COUNT {
+                return;
+            }
+        }
+        cache.insert(
+            user_ctx.user_name().to_string(),
+            CachedVerification {
+                user: Arc::downgrade(user_ctx.user()),
+                password_digest,
+                verified,
+                expire: now + ttl,
+            },
+        );
+    }
+
+    pub(super) fn flush(&self, username: Option<&str>) {
+        let mut cache = self.cache.lock().unwrap();
+        match username {
+            Some(name) => {
+                cache.remove(name);
+            }
+            None => cache.clear(),
+        }
+    }
+
+    pub(super) fn status(&self) -> String {
+        let cached = self.cache.lock().unwrap().len();
+        format!(
+            "hit: {}, miss: {}, cached: {cached}",
+            self.hit.load(Ordering::Relaxed),
+            self.miss.load(Ordering::Relaxed)
+        )
+    }
+}
@@ -0,0 +1,469 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use ahash::AHashMap;
+use anyhow::anyhow;
+use arc_swap::ArcSwap;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use futures_util::future::{AbortHandle, Abortable};
+use log::warn;
+use serde_json::Value;
+
+use g3_types::metrics::MetricsName;
+
+use super::{User, UserGroupConfig};
+use crate::config::auth::{TrafficQuotaStoreConfig, UserTrafficQuotaConfig};
+
+mod store;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct TrafficQuotaRecord {
+    day: NaiveDate,
+    daily_used: u64,
+    monthly_used: u64,
+}
+
+impl TrafficQuotaRecord {
+    fn new(day: NaiveDate) -> Self {
+        TrafficQuotaRecord {
+            day,
+            daily_used: 0,
+            monthly_used: 0,
+        }
+    }
+
+    fn in_same_month(&self, day: NaiveDate) -> bool {
+        self.day.year() == day.year() && self.day.month() == day.month()
+    }
+
+    fn roll_to(&mut self, day: NaiveDate) {
+        if self.day == day {
+            return;
+        }
+        if !self.in_same_month(day) {
+            self.monthly_used = 0;
+        }
+        self.daily_used = 0;
+        self.day = day;
+    }
+
+    fn add(&mut self, size: u64) {
+        self.daily_used = self.daily_used.saturating_add(size);
+        self.monthly_used = self.monthly_used.saturating_add(size);
+    }
+
+    fn merge(&mut self, other: &TrafficQuotaRecord) {
+        if self.day == other.day {
+            self.daily_used = self.daily_used.saturating_add(other.daily_used);
+        }
+        if self.in_same_month(other.day) {
+            self.monthly_used = self.monthly_used.saturating_add(other.monthly_used);
+        }
+    }
+
+    fn as_json(&self) -> Value {
+        serde_json::json!({
+            "day": self.day.to_string(),
+            "daily_used": self.daily_used,
+            "monthly_used": self.monthly_used,
+        })
+    }
+
+    fn parse_json(v: &Value) -> anyhow::Result<Self> {
+        let Value::Object(map) = v else {
+            return Err(anyhow!("json value type for record should be 'map'"));
+        };
+        let mut day = None;
+        let mut record = TrafficQuotaRecord::new(NaiveDate::default());
+        for (k, v) in map {
+            match g3_json::key::normalize(k).as_str() {
+                "day" => {
+                    let s = g3_json::value::as_string(v)?;
+                    let d = NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+                        .map_err(|e| anyhow!("invalid date value for key {k}: {e}"))?;
+                    day = Some(d);
+                }
+                "daily_used" => {
+                    record.daily_used = v
+                        .as_u64()
+                        .ok_or_else(|| anyhow!("invalid u64 value for key {k}"))?;
+                }
+                "monthly_used" => {
+                    record.monthly_used = v
+                        .as_u64()
+                        .ok_or_else(|| anyhow!("invalid u64 value for key {k}"))?;
+                }
+                _ => {}
+            }
+        }
+        let Some(day) = day else {
+            return Err(anyhow!("no day set"));
+        };
+        record.day = day;
+        Ok(record)
+    }
+}
+
+struct TrafficQuotaState {
+    record: TrafficQuotaRecord,
+    last_total: u64,
+    restored: bool,
+}
+
+pub(crate) struct UserTrafficQuota {
+    state: Mutex<TrafficQuotaState>,
+    exceeded: AtomicBool,
+}
+
+impl UserTrafficQuota {
+    pub(super) fn new(
+        config: &UserTrafficQuotaConfig,
+        datetime_now: &DateTime<Utc>,
+        current_total: u64,
+    ) -> Self {
+        let day = config.timezone.date(datetime_now);
+        UserTrafficQuota {
+            state: Mutex::new(TrafficQuotaState {
+                record: TrafficQuotaRecord::new(day),
+                last_total: current_total,
+                restored: false,
+            }),
+            exceeded: AtomicBool::new(false),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_exceeded(&self) -> bool {
+        self.exceeded.load(Ordering::Relaxed)
+    }
+
+    /// merge the persisted record, only the first call takes effect
+    fn restore(&self, stored: Option<&TrafficQuotaRecord>) {
+        let mut state = self.state.lock().unwrap();
+        if state.restored {
+            return;
+        }
+        state.restored = true;
+        if let Some(record) = stored {
+            state.record.merge(record);
+        }
+    }
+
+    fn update(&self, config: &UserTrafficQuotaConfig, total: u64, datetime_now: &DateTime<Utc>) {
+        let day = config.timezone.date(datetime_now);
+        let mut state = self.state.lock().unwrap();
+        let delta = total.saturating_sub(state.last_total);
+        state.last_total = total;
+        state.record.roll_to(day);
+        state.record.add(delta);
+
+        let exceeded = config
+            .daily
+            .is_some_and(|quota| state.record.daily_used >= quota)
+            || config
+                .monthly
+                .is_some_and(|quota| state.record.monthly_used >= quota);
+        self.exceeded.store(exceeded, Ordering::Relaxed);
+    }
+
+    /// clear the used traffic of the current day and month
+    pub(super) fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.record.daily_used = 0;
+        state.record.monthly_used = 0;
+        self.exceeded.store(false, Ordering::Relaxed);
+    }
+
+    /// get the record for persistence, only after the persisted one has been restored
+    fn record(&self) -> Option<TrafficQuotaRecord> {
+        let state = self.state.lock().unwrap();
+        if state.restored {
+            Some(state.record.clone())
+        } else {
+            None
+        }
+    }
+
+    pub(super) fn status(&self, config: &UserTrafficQuotaConfig) -> String {
+        let record = self.state.lock().unwrap().record.clone();
+        let mut s = String::new();
+        let _ = writeln!(s, "day: {}", record.day);
+        let mut add_line = |name: &str, used: u64, quota: Option<u64>| {
+            if let Some(quota) = quota {
+                let _ = writeln!(
+                    s,
+                    "{name}: used {used}, quota {quota}, remaining {}",
+                    quota.saturating_sub(used)
+                );
+            } else {
+                let _ = writeln!(s, "{name}: used {used}, no quota");
+            }
+        };
+        add_line("daily", record.daily_used, config.daily);
+        add_line("monthly", record.monthly_used, config.monthly);
+        let _ = write!(s, "exceeded: {}", self.is_exceeded());
+        s
+    }
+}
+
+/// Sync the used traffic of users with the persisted records
+struct TrafficQuotaSyncer {
+    group_name: MetricsName,
+    store: Option<Arc<TrafficQuotaStoreConfig>>,
+    /// the persisted records, only used for restore
+    stored_records: Option<AHashMap<String, TrafficQuotaRecord>>,
+}
+
+impl TrafficQuotaSyncer {
+    fn new(group_name: MetricsName, store: Option<Arc<TrafficQuotaStoreConfig>>) -> Self {
+        let stored_records = if store.is_some() {
+            None
+        } else {
+            Some(AHashMap::new())
+        };
+        TrafficQuotaSyncer {
+            group_name,
+            store,
+            stored_records,
+        }
+    }
+
+    /// load the persisted records, will retry at the next sync if failed
+    async fn load(&mut self) {
+        if self.stored_records.is_some() {
+            return;
+        }
+        if let Some(store) = &self.store {
+            match store::load(store).await {
+                Ok(records) => self.stored_records = Some(records),
+                Err(e) => warn!(
+                    "failed to load traffic quota records for user-group {}: {e:?}",
+                    self.group_name
+                ),
+            }
+        }
+    }
+
+    /// update the quota with the current total traffic, and return the record to persist.
+    /// Nothing will be returned before the persisted records are loaded, so the store won't
+    /// be overwritten by the partial counters
+    fn update(
+        &self,
+        name: &str,
+        config: &UserTrafficQuotaConfig,
+        quota: &UserTrafficQuota,
+        total: u64,
+        datetime_now: &DateTime<Utc>,
+    ) -> Option<TrafficQuotaRecord> {
+        if let Some(stored) = &self.stored_records {
+            quota.restore(stored.get(name));
+        }
+        quota.update(config, total, datetime_now);
+        quota.record()
+    }
+
+    async fn save(&self, records: &[(String, TrafficQuotaRecord)]) {
+        if records.is_empty() {
+            return;
+        }
+        if let Some(store) = &self.store {
+            if let Err(e) = store::save(store, records).await {
+                warn!(
+                    "failed to save traffic quota records for user-group {}: {e:?}",
+                    self.group_name
+                );
+            }
+        }
+    }
+}
+
+pub(super) fn new_job(
+    group_config: &Arc<UserGroupConfig>,
+    static_users: &Arc<AHashMap<String, Arc<User>>>,
+    dynamic_users_container: &Arc<ArcSwap<AHashMap<String, Arc<User>>>>,
+) -> AbortHandle {
+    let group_config = Arc::clone(group_config);
+    let static_users = Arc::clone(static_users);
+    let dynamic_users_container = Arc::clone(dynamic_users_container);
+
+    let f = async move {
+        let mut syncer = TrafficQuotaSyncer::new(
+            group_config.name().clone(),
+            group_config.traffic_quota_store.clone(),
+        );
+
+        let mut interval = tokio::time::interval(group_config.traffic_quota_sync_interval);
+        loop {
+            interval.tick().await; // will tick immediately for the first time
+
+            syncer.load().await;
+
+            let datetime_now = Utc::now();
+            let mut records = Vec::new();
+            let mut update_user = |name: &str, user: &Arc<User>| {
+                let Some((config, quota)) = user.traffic_quota() else {
+                    return;
+                };
+                let total = user.traffic_total_bytes();
+                if let Some(record) = syncer.update(name, config, quota, total, &datetime_now) {
+                    records.push((name.to_string(), record));
+                }
+            };
+            for (name, user) in static_users.iter() {
+                update_user(name, user);
+            }
+            for (name, user) in dynamic_users_container.load().iter() {
+                update_user(name, user);
+            }
+
+            syncer.save(&records).await;
+        }
+    };
+
+    let (abort_handle, abort_registration) = AbortHandle::new_pair();
+    let future = Abortable::new(f, abort_registration);
+    tokio::spawn(future);
+    abort_handle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roll_and_merge() {
+        let day = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let mut record = TrafficQuotaRecord::new(day);
+        record.add(100);
+
+        let stored = TrafficQuotaRecord {
+            day: NaiveDate::from_ymd_opt(2024, 1, 30).unwrap(),
+            daily_used: 10,
+            monthly_used: 50,
+        };
+        record.merge(&stored);
+        assert_eq!(record.daily_used, 100);
+        assert_eq!(record.monthly_used, 150);
+
+        record.roll_to(NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+        assert_eq!(record.daily_used, 0);
+        assert_eq!(record.monthly_used, 0);
+
+        let v = record.as_json();
+        let parsed = TrafficQuotaRecord::parse_json(&v).unwrap();
+        assert_eq!(parsed, record);
+    }
+
+    fn quota_config(daily: u64) -> UserTrafficQuotaConfig {
+        UserTrafficQuotaConfig {
+            daily: Some(daily),
+            ..Default::default()
+        }
+    }
+
+    fn file_syncer(name: &str) -> (TrafficQuotaSyncer, std::path::PathBuf) {
+        let mut path = std::env::temp_dir();
+        path.push(format!("g3proxy-quota-sync-{name}-{}", std::process::id()));
+        std::fs::write(&path, "").unwrap();
+        let store = Arc::new(TrafficQuotaStoreConfig::File(path.clone()));
+        let syncer = TrafficQuotaSyncer::new(MetricsName::default(), Some(store));
+        (syncer, path)
+    }
+
+    #[tokio::test]
+    async fn sync_no_store() {
+        let config = quota_config(1000);
+        let now = Utc::now();
+        let quota = UserTrafficQuota::new(&config, &now, 100);
+
+        let mut syncer = TrafficQuotaSyncer::new(MetricsName::default(), None);
+        syncer.load().await;
+        let record = syncer.update("a", &config, &quota, 600, &now).unwrap();
+        assert_eq!(record.daily_used, 500);
+        assert!(!quota.is_exceeded());
+
+        let record = syncer.update("a", &config, &quota, 1100, &now).unwrap();
+        assert_eq!(record.daily_used, 1000);
+        assert!(quota.is_exceeded());
+    }
+
+    #[tokio::test]
+    async fn sync_restore() {
+        let config = quota_config(1000);
+        let now = Utc::now();
+        let (mut syncer, path) = file_syncer("restore");
+
+        let quota = UserTrafficQuota::new(&config, &now, 0);
+        syncer.load().await;
+        let record = syncer.update("a", &config, &quota, 600, &now).unwrap();
+        syncer.save(&[("a".to_string(), record)]).await;
+
+        // restart with a new syncer and new counters
+        let store = Arc::new(TrafficQuotaStoreConfig::File(path.clone()));
+        let mut syncer = TrafficQuotaSyncer::new(MetricsName::default(), Some(store));
+        let quota = UserTrafficQuota::new(&config, &now, 0);
+        syncer.load().await;
+        let record = syncer.update("a", &config, &quota, 300, &now).unwrap();
+        assert_eq!(record.daily_used, 900);
+        assert_eq!(record.monthly_used, 900);
+        assert!(!quota.is_exceeded());
+
+        // the stored one should only be merged once
+        let record = syncer.update("a", &config, &quota, 400, &now).unwrap();
+        assert_eq!(record.daily_used, 1000);
+        assert!(quota.is_exceeded());
+
+        // users not in the store start from zero
+        let other = UserTrafficQuota::new(&config, &now, 0);
+        let record = syncer.update("b", &config, &other, 10, &now).unwrap();
+        assert_eq!(record.daily_used, 10);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn sync_load_failed() {
+        let config = quota_config(1000);
+        let now = Utc::now();
+        let (mut syncer, path) = file_syncer("load-failed");
+        std::fs::write(&path, "{corrupt").unwrap();
+
+        let quota = UserTrafficQuota::new(&config, &now, 0);
+        syncer.load().await;
+        // the counter still works, but nothing should be persisted
+        assert!(syncer.update("a", &config, &quota, 100, &now).is_none());
+        assert!(syncer.update("a", &config, &quota, 2000, &now).is_none());
+        assert!(quota.is_exceeded());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{corrupt");
+
+        // retry at the next sync
+        let stored = TrafficQuotaRecord {
+            day: config.timezone.date(&now),
+            daily_used: 50,
+            monthly_used: 50,
+        };
+        let contents = serde_json::json!({"a": stored.as_json()}).to_string();
+        std::fs::write(&path, contents).unwrap();
+        syncer.load().await;
+        let record = syncer.update("a", &config, &quota, 2000, &now).unwrap();
+        assert_eq!(record.daily_used, 2050);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
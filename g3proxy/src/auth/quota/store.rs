@@ -0,0 +1,282 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use ahash::AHashMap;
+use anyhow::{anyhow, Context};
+use redis::AsyncCommands;
+use serde_json::Value;
+
+use super::TrafficQuotaRecord;
+use crate::config::auth::{TrafficQuotaRedisStore, TrafficQuotaStoreConfig};
+
+pub(super) async fn load(
+    config: &TrafficQuotaStoreConfig,
+) -> anyhow::Result<AHashMap<String, TrafficQuotaRecord>> {
+    match config {
+        TrafficQuotaStoreConfig::File(path) => load_file(path).await,
+        TrafficQuotaStoreConfig::Redis(store) => load_redis(store).await,
+    }
+}
+
+pub(super) async fn save(
+    config: &TrafficQuotaStoreConfig,
+    records: &[(String, TrafficQuotaRecord)],
+) -> anyhow::Result<()> {
+    match config {
+        TrafficQuotaStoreConfig::File(path) => save_file(path, records).await,
+        TrafficQuotaStoreConfig::Redis(store) => save_redis(store, records).await,
+    }
+}
+
+async fn load_file(path: &Path) -> anyhow::Result<AHashMap<String, TrafficQuotaRecord>> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| anyhow!("failed to read file {}: {e}", path.display()))?;
+    let mut records = AHashMap::new();
+    if contents.trim().is_empty() {
+        return Ok(records);
+    }
+
+    let doc = Value::from_str(&contents)
+        .map_err(|e| anyhow!("file {} is not valid json: {e}", path.display()))?;
+    let Value::Object(map) = doc else {
+        return Err(anyhow!("json value type in file should be 'map'"));
+    };
+    for (user, v) in map.iter() {
+        let record = TrafficQuotaRecord::parse_json(v)
+            .context(format!("invalid traffic quota record for user {user}"))?;
+        records.insert(user.to_string(), record);
+    }
+    Ok(records)
+}
+
+async fn save_file(path: &Path, records: &[(String, TrafficQuotaRecord)]) -> anyhow::Result<()> {
+    let mut map = serde_json::Map::with_capacity(records.len());
+    for (user, record) in records {
+        map.insert(user.to_string(), record.as_json());
+    }
+    let contents = Value::Object(map).to_string();
+
+    let mut tmp_path = OsString::from(path.as_os_str());
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    // we should avoid corrupt write at process exit
+    let r = crate::control::run_protected_io(async {
+        tokio::fs::write(&tmp_path, contents).await?;
+        tokio::fs::rename(&tmp_path, path).await
+    })
+    .await;
+    match r {
+        Some(Err(e)) => Err(anyhow!("failed to write file {}: {e}", path.display())),
+        _ => Ok(()),
+    }
+}
+
+async fn connect_to_redis(store: &TrafficQuotaRedisStore) -> anyhow::Result<impl AsyncCommands> {
+    let client =
+        redis::Client::open(store).map_err(|e| anyhow!("redis client open failed: {e}"))?;
+    match tokio::time::timeout(store.connect_timeout, client.get_async_connection()).await {
+        Ok(Ok(con)) => Ok(con),
+        Ok(Err(e)) => Err(anyhow!("connect failed: {e}")),
+        Err(_) => Err(anyhow!("connect timeout")),
+    }
+}
+
+async fn load_redis(
+    store: &TrafficQuotaRedisStore,
+) -> anyhow::Result<AHashMap<String, TrafficQuotaRecord>> {
+    let mut con = connect_to_redis(store).await?;
+    let values: HashMap<String, String> =
+        match tokio::time::timeout(store.read_timeout, con.hgetall(&store.hash_key)).await {
+            Ok(Ok(v)) => v,
+            Ok(Err(e)) => return Err(anyhow!("failed to get hash {}: {e}", store.hash_key)),
+            Err(_) => return Err(anyhow!("timeout to get hash {}", store.hash_key)),
+        };
+
+    let mut records = AHashMap::with_capacity(values.len());
+    for (user, s) in values {
+        let v =
+            Value::from_str(&s).map_err(|e| anyhow!("invalid json value for user {user}: {e}"))?;
+        let record = TrafficQuotaRecord::parse_json(&v)
+            .context(format!("invalid traffic quota record for user {user}"))?;
+        records.insert(user, record);
+    }
+    Ok(records)
+}
+
+fn redis_save_pipeline(
+    store: &TrafficQuotaRedisStore,
+    records: &[(String, TrafficQuotaRecord)],
+) -> redis::Pipeline {
+    let mut pipe = redis::pipe();
+    pipe.atomic().cmd("HMSET").arg(&store.hash_key);
+    for (user, record) in records {
+        pipe.arg(user).arg(record.as_json().to_string());
+    }
+    pipe.ignore();
+    if let Some(ttl) = store.ttl {
+        // refresh the ttl at each save, so only the unused hash will expire
+        pipe.cmd("EXPIRE")
+            .arg(&store.hash_key)
+            .arg(ttl.as_secs())
+            .ignore();
+    }
+    pipe
+}
+
+async fn save_redis(
+    store: &TrafficQuotaRedisStore,
+    records: &[(String, TrafficQuotaRecord)],
+) -> anyhow::Result<()> {
+    let pipe = redis_save_pipeline(store, records);
+
+    let mut con = connect_to_redis(store).await?;
+    match tokio::time::timeout(store.read_timeout, pipe.query_async::<_, ()>(&mut con)).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(anyhow!("failed to set hash {}: {e}", store.hash_key)),
+        Err(_) => Err(anyhow!("timeout to set hash {}", store.hash_key)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::fmt::Write;
+    use std::time::Duration;
+
+    fn test_path(name: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("g3proxy-quota-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn record(daily_used: u64, monthly_used: u64) -> TrafficQuotaRecord {
+        TrafficQuotaRecord {
+            day: NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+            daily_used,
+            monthly_used,
+        }
+    }
+
+    #[tokio::test]
+    async fn file_persist_reload() {
+        let path = test_path("reload");
+        std::fs::write(&path, "").unwrap();
+        assert!(load_file(&path).await.unwrap().is_empty());
+
+        let records = vec![
+            ("a".to_string(), record(10, 100)),
+            ("b".to_string(), record(20, 200)),
+        ];
+        save_file(&path, &records).await.unwrap();
+
+        let loaded = load_file(&path).await.unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get("a"), Some(&record(10, 100)));
+        assert_eq!(loaded.get("b"), Some(&record(20, 200)));
+
+        // the whole file is rewritten at each save
+        let records = vec![("a".to_string(), record(30, 120))];
+        save_file(&path, &records).await.unwrap();
+        let loaded = load_file(&path).await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.get("a"), Some(&record(30, 120)));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn file_corrupt() {
+        let path = test_path("corrupt");
+        assert!(load_file(&path).await.is_err());
+
+        std::fs::write(&path, r#"{"a": {"day": "2024-03-05", "daily_used": 1"#).unwrap();
+        assert!(load_file(&path).await.is_err());
+
+        std::fs::write(&path, "[]").unwrap();
+        assert!(load_file(&path).await.is_err());
+
+        std::fs::write(&path, r#"{"a": {"daily_used": 1}}"#).unwrap();
+        assert!(load_file(&path).await.is_err());
+
+        std::fs::write(&path, r#"{"a": {"day": "2024-13-05", "daily_used": 1}}"#).unwrap();
+        assert!(load_file(&path).await.is_err());
+
+        std::fs::write(&path, r#"{"a": {"day": "2024-03-05", "daily_used": -1}}"#).unwrap();
+        assert!(load_file(&path).await.is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    fn redis_store(doc: &str) -> TrafficQuotaRedisStore {
+        let docs = yaml_rust::YamlLoader::load_from_str(doc).unwrap();
+        let config = TrafficQuotaStoreConfig::parse_yaml(&docs[0], &std::env::temp_dir()).unwrap();
+        let TrafficQuotaStoreConfig::Redis(store) = config else {
+            panic!("not a redis store");
+        };
+        store
+    }
+
+    fn packed(args: &[&str]) -> Vec<u8> {
+        let mut buf = format!("*{}\r\n", args.len());
+        for arg in args {
+            let _ = write!(buf, "${}\r\n{arg}\r\n", arg.len());
+        }
+        buf.into_bytes()
+    }
+
+    #[test]
+    fn redis_pipeline() {
+        let records = vec![
+            ("a".to_string(), record(10, 100)),
+            ("b".to_string(), record(20, 200)),
+        ];
+        let a = records[0].1.as_json().to_string();
+        let b = records[1].1.as_json().to_string();
+
+        let store = redis_store("type: redis\naddr: 127.0.0.1\nhash_key: quota\n");
+        assert!(store.ttl.is_none());
+        let mut expected = packed(&["MULTI"]);
+        expected.extend(packed(&["HMSET", "quota", "a", &a, "b", &b]));
+        expected.extend(packed(&["EXEC"]));
+        assert_eq!(
+            redis_save_pipeline(&store, &records).get_packed_pipeline(),
+            expected
+        );
+
+        let store = redis_store("type: redis\naddr: 127.0.0.1\nhash_key: quota\nttl: 1h\n");
+        assert_eq!(store.ttl, Some(Duration::from_secs(3600)));
+        let mut expected = packed(&["MULTI"]);
+        expected.extend(packed(&["HMSET", "quota", "a", &a, "b", &b]));
+        expected.extend(packed(&["EXPIRE", "quota", "3600"]));
+        expected.extend(packed(&["EXEC"]));
+        assert_eq!(
+            redis_save_pipeline(&store, &records).get_packed_pipeline(),
+            expected
+        );
+
+        let store = redis_store("type: redis\naddr: 127.0.0.1\nhash_key: quota\nttl: 0\n");
+        assert!(store.ttl.is_none());
+    }
+}
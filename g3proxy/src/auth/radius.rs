@@ -0,0 +1,497 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use ahash::AHashMap;
+use anyhow::anyhow;
+use log::warn;
+use openssl::hash::{Hasher, MessageDigest};
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use tokio::net::UdpSocket;
+
+use crate::config::auth::{RadiusAuthConfig, RadiusAuthMethod};
+
+const CODE_ACCESS_REQUEST: u8 = 1;
+const CODE_ACCESS_ACCEPT: u8 = 2;
+const CODE_ACCESS_REJECT: u8 = 3;
+const CODE_ACCESS_CHALLENGE: u8 = 11;
+
+const ATTR_USER_NAME: u8 = 1;
+const ATTR_USER_PASSWORD: u8 = 2;
+const ATTR_CHAP_PASSWORD: u8 = 3;
+const ATTR_NAS_IDENTIFIER: u8 = 32;
+const ATTR_CHAP_CHALLENGE: u8 = 60;
+const ATTR_MESSAGE_AUTHENTICATOR: u8 = 80;
+
+const HEADER_LEN: usize = 20;
+const MAX_PACKET_LEN: usize = 4096;
+const MAX_PASSWORD_LEN: usize = 128;
+const MAX_CACHE_COUNT: usize = 65536;
+
+struct CachedAuth {
+    password_digest: [u8; 32],
+    expire: Instant,
+}
+
+pub(crate) struct RadiusAuth {
+    config: Arc<RadiusAuthConfig>,
+    cache: Mutex<AHashMap<String, CachedAuth>>,
+}
+
+impl RadiusAuth {
+    pub(crate) fn new(config: Arc<RadiusAuthConfig>) -> Self {
+        RadiusAuth {
+            config,
+            cache: Mutex::new(AHashMap::new()),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn config(&self) -> &Arc<RadiusAuthConfig> {
+        &self.config
+    }
+
+    /// verify the username and password, recent successes will be served from the local cache
+    pub(crate) async fn verify(&self, username: &str, password: &str) -> bool {
+        let password_digest = openssl::sha::sha256(password.as_bytes());
+        if self.check_cache(username, &password_digest) {
+            return true;
+        }
+
+        let mut last_err = None;
+        for _ in 0..=self.config.retries {
+            match tokio::time::timeout(self.config.timeout, self.send_request(username, password))
+                .await
+            {
+                Ok(Ok(accepted)) => {
+                    if accepted {
+                        self.add_cache(username, password_digest);
+                    }
+                    return accepted;
+                }
+                Ok(Err(e)) => last_err = Some(e),
+                Err(_) => last_err = Some(anyhow!("timed out")),
+            }
+        }
+        if let Some(e) = last_err {
+            warn!(
+                "radius auth for user {username} to server {} failed: {e:?}",
+                self.config.server
+            );
+        }
+        false
+    }
+
+    fn check_cache(&self, username: &str, password_digest: &[u8; 32]) -> bool {
+        if self.config.cache_ttl.is_zero() {
+            return false;
+        }
+        let cache = self.cache.lock().unwrap();
+        cache.get(username).is_some_and(|c| {
+            c.expire > Instant::now() && openssl::memcmp::eq(&c.password_digest, password_digest)
+        })
+    }
+
+    fn add_cache(&self, username: &str, password_digest: [u8; 32]) {
+        if self.config.cache_ttl.is_zero() {
+            return;
+        }
+        let now = Instant::now();
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHE_COUNT {
+            cache.retain(|_, c| c.expire > now);
+            if cache.len() >= MAX_CACHE_COUNT {
+                return;
+            }
+        }
+        cache.insert(
+            username.to_string(),
+            CachedAuth {
+                password_digest,
+                expire: now + self.config.cache_ttl,
+            },
+        );
+    }
+
+    async fn send_request(&self, username: &str, password: &str) -> anyhow::Result<bool> {
+        let identifier = rand::random::<u8>();
+        let authenticator = rand::random::<[u8; 16]>();
+        let request = self.build_request(identifier, &authenticator, username, password)?;
+
+        let server = self.config.server;
+        let bind_addr = match server {
+            SocketAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+            SocketAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+        };
+        let socket = UdpSocket::bind(bind_addr)
+            .await
+            .map_err(|e| anyhow!("failed to bind udp socket: {e}"))?;
+        socket
+            .connect(server)
+            .await
+            .map_err(|e| anyhow!("failed to connect udp socket: {e}"))?;
+        socket
+            .send(&request)
+            .await
+            .map_err(|e| anyhow!("failed to send request: {e}"))?;
+
+        let mut buf = vec![0u8; MAX_PACKET_LEN];
+        loop {
+            let len = socket
+                .recv(&mut buf)
+                .await
+                .map_err(|e| anyhow!("failed to recv response: {e}"))?;
+            let rsp = &buf[..len];
+            if rsp.len() >= HEADER_LEN && rsp[1] != identifier {
+                // not the response for this request
+                continue;
+            }
+            return self.parse_response(rsp, identifier, &authenticator);
+        }
+    }
+
+    fn build_request(
+        &self,
+        identifier: u8,
+        authenticator: &[u8; 16],
+        username: &str,
+        password: &str,
+    ) -> anyhow::Result<Vec<u8>> {
+        let secret = self.config.secret.as_bytes();
+        if username.is_empty() || username.len() > 253 {
+            return Err(anyhow!("invalid username length {}", username.len()));
+        }
+        if password.len() > MAX_PASSWORD_LEN {
+            return Err(anyhow!("too long password"));
+        }
+
+        let mut packet = Vec::with_capacity(256);
+        packet.push(CODE_ACCESS_REQUEST);
+        packet.push(identifier);
+        packet.extend_from_slice(&[0, 0]); // length, set later
+        packet.extend_from_slice(authenticator);
+
+        // add the message authenticator as the first attribute, see RFC 3579 and BlastRADIUS
+        push_attr(&mut packet, ATTR_MESSAGE_AUTHENTICATOR, &[0u8; 16]);
+        push_attr(&mut packet, ATTR_USER_NAME, username.as_bytes());
+        match self.config.method {
+            RadiusAuthMethod::Pap => {
+                let encrypted = encrypt_password(secret, authenticator, password.as_bytes())?;
+                push_attr(&mut packet, ATTR_USER_PASSWORD, &encrypted);
+            }
+            RadiusAuthMethod::Chap => {
+                let chap_id = rand::random::<u8>();
+                let challenge = rand::random::<[u8; 16]>();
+                let mut h = Hasher::new(MessageDigest::md5())?;
+                h.update(&[chap_id])?;
+                h.update(password.as_bytes())?;
+                h.update(&challenge)?;
+                let digest = h.finish()?;
+                let mut value = Vec::with_capacity(17);
+                value.push(chap_id);
+                value.extend_from_slice(&digest);
+                push_attr(&mut packet, ATTR_CHAP_PASSWORD, &value);
+                push_attr(&mut packet, ATTR_CHAP_CHALLENGE, &challenge);
+            }
+        }
+        if !self.config.nas_identifier.is_empty() {
+            push_attr(
+                &mut packet,
+                ATTR_NAS_IDENTIFIER,
+                self.config.nas_identifier.as_bytes(),
+            );
+        }
+
+        let len = packet.len() as u16;
+        packet[2..4].copy_from_slice(&len.to_be_bytes());
+        let mac = hmac_md5(secret, &packet)?;
+        packet[HEADER_LEN + 2..HEADER_LEN + 18].copy_from_slice(&mac);
+        Ok(packet)
+    }
+
+    fn parse_response(
+        &self,
+        rsp: &[u8],
+        identifier: u8,
+        req_authenticator: &[u8; 16],
+    ) -> anyhow::Result<bool> {
+        let secret = self.config.secret.as_bytes();
+        if rsp.len() < HEADER_LEN {
+            return Err(anyhow!("too short response"));
+        }
+        if rsp[1] != identifier {
+            return Err(anyhow!("mismatched response identifier {}", rsp[1]));
+        }
+        let len = u16::from_be_bytes([rsp[2], rsp[3]]) as usize;
+        if len < HEADER_LEN || len > rsp.len() {
+            return Err(anyhow!("invalid response length {len}"));
+        }
+        let rsp = &rsp[..len];
+
+        let mut h = Hasher::new(MessageDigest::md5())?;
+        h.update(&rsp[..4])?;
+        h.update(req_authenticator)?;
+        h.update(&rsp[HEADER_LEN..])?;
+        h.update(secret)?;
+        let digest = h.finish()?;
+        if !openssl::memcmp::eq(&digest, &rsp[4..HEADER_LEN]) {
+            return Err(anyhow!("invalid response authenticator"));
+        }
+
+        // verify the message authenticator, which is required by default, see BlastRADIUS
+        let mut found_message_authenticator = false;
+        let mut offset = HEADER_LEN;
+        while offset + 2 <= rsp.len() {
+            let attr_type = rsp[offset];
+            let attr_len = rsp[offset + 1] as usize;
+            if attr_len < 2 || offset + attr_len > rsp.len() {
+                return Err(anyhow!("invalid attribute length"));
+            }
+            if attr_type == ATTR_MESSAGE_AUTHENTICATOR {
+                if attr_len != 18 {
+                    return Err(anyhow!("invalid message authenticator length"));
+                }
+                let mut data = rsp.to_vec();
+                data[4..HEADER_LEN].copy_from_slice(req_authenticator);
+                data[offset + 2..offset + 18].fill(0);
+                let mac = hmac_md5(secret, &data)?;
+                if !openssl::memcmp::eq(&mac, &rsp[offset + 2..offset + 18]) {
+                    return Err(anyhow!("invalid message authenticator"));
+                }
+                found_message_authenticator = true;
+            }
+            offset += attr_len;
+        }
+        if !found_message_authenticator && self.config.require_message_authenticator {
+            return Err(anyhow!("no message authenticator found in response"));
+        }
+
+        match rsp[0] {
+            CODE_ACCESS_ACCEPT => Ok(true),
+            CODE_ACCESS_REJECT | CODE_ACCESS_CHALLENGE => Ok(false),
+            code => Err(anyhow!("unexpected response code {code}")),
+        }
+    }
+}
+
+fn push_attr(packet: &mut Vec<u8>, attr_type: u8, value: &[u8]) {
+    packet.push(attr_type);
+    packet.push((value.len() + 2) as u8);
+    packet.extend_from_slice(value);
+}
+
+fn hmac_md5(key: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::md5(), &key)?;
+    signer.update(data)?;
+    Ok(signer.sign_to_vec()?)
+}
+
+/// encrypt the User-Password attribute, see RFC 2865 section 5.2
+fn encrypt_password(
+    secret: &[u8],
+    authenticator: &[u8; 16],
+    password: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let padded_len = password.len().max(1).next_multiple_of(16);
+    let mut result = password.to_vec();
+    result.resize(padded_len, 0);
+
+    let mut prev: [u8; 16] = *authenticator;
+    for chunk in result.chunks_mut(16) {
+        let mut h = Hasher::new(MessageDigest::md5())?;
+        h.update(secret)?;
+        h.update(&prev)?;
+        let b = h.finish()?;
+        for (c, k) in chunk.iter_mut().zip(b.iter()) {
+            *c ^= *k;
+        }
+        prev.copy_from_slice(chunk);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "xyzzy5461";
+    const IDENTIFIER: u8 = 0x7f;
+    const REQ_AUTHENTICATOR: [u8; 16] = [
+        0x0f, 0x40, 0x3f, 0x94, 0x73, 0x97, 0x80, 0x57, 0xbd, 0x83, 0xd5, 0xcb, 0x98, 0xf4, 0x22,
+        0x7a,
+    ];
+
+    fn new_auth(require_message_authenticator: bool) -> RadiusAuth {
+        let config = RadiusAuthConfig {
+            secret: SECRET.to_string(),
+            require_message_authenticator,
+            ..Default::default()
+        };
+        RadiusAuth::new(Arc::new(config))
+    }
+
+    fn build_response(code: u8, message_authenticator: bool) -> Vec<u8> {
+        let mut packet = vec![code, IDENTIFIER, 0, 0];
+        packet.extend_from_slice(&REQ_AUTHENTICATOR);
+        if message_authenticator {
+            push_attr(&mut packet, ATTR_MESSAGE_AUTHENTICATOR, &[0u8; 16]);
+        }
+        push_attr(&mut packet, 18, b"Hello");
+        let len = packet.len() as u16;
+        packet[2..4].copy_from_slice(&len.to_be_bytes());
+
+        if message_authenticator {
+            let mac = hmac_md5(SECRET.as_bytes(), &packet).unwrap();
+            packet[HEADER_LEN + 2..HEADER_LEN + 18].copy_from_slice(&mac);
+        }
+
+        let mut h = Hasher::new(MessageDigest::md5()).unwrap();
+        h.update(&packet).unwrap();
+        h.update(SECRET.as_bytes()).unwrap();
+        let digest = h.finish().unwrap();
+        packet[4..HEADER_LEN].copy_from_slice(&digest);
+        packet
+    }
+
+    /// update the response authenticator after the packet is modified
+    fn resign_response(packet: &mut [u8]) {
+        let mut data = packet.to_vec();
+        data[4..HEADER_LEN].copy_from_slice(&REQ_AUTHENTICATOR);
+        let mut h = Hasher::new(MessageDigest::md5()).unwrap();
+        h.update(&data).unwrap();
+        h.update(SECRET.as_bytes()).unwrap();
+        let digest = h.finish().unwrap();
+        packet[4..HEADER_LEN].copy_from_slice(&digest);
+    }
+
+    #[test]
+    fn parse_valid_response() {
+        let auth = new_auth(true);
+
+        let rsp = build_response(CODE_ACCESS_ACCEPT, true);
+        assert!(auth
+            .parse_response(&rsp, IDENTIFIER, &REQ_AUTHENTICATOR)
+            .unwrap());
+
+        let rsp = build_response(CODE_ACCESS_REJECT, true);
+        assert!(!auth
+            .parse_response(&rsp, IDENTIFIER, &REQ_AUTHENTICATOR)
+            .unwrap());
+
+        let rsp = build_response(CODE_ACCESS_CHALLENGE, true);
+        assert!(!auth
+            .parse_response(&rsp, IDENTIFIER, &REQ_AUTHENTICATOR)
+            .unwrap());
+    }
+
+    #[test]
+    fn parse_missing_message_authenticator() {
+        let auth = new_auth(true);
+        let rsp = build_response(CODE_ACCESS_ACCEPT, false);
+        assert!(auth
+            .parse_response(&rsp, IDENTIFIER, &REQ_AUTHENTICATOR)
+            .is_err());
+        let rsp = build_response(CODE_ACCESS_REJECT, false);
+        assert!(auth
+            .parse_response(&rsp, IDENTIFIER, &REQ_AUTHENTICATOR)
+            .is_err());
+
+        let auth = new_auth(false);
+        let rsp = build_response(CODE_ACCESS_ACCEPT, false);
+        assert!(auth
+            .parse_response(&rsp, IDENTIFIER, &REQ_AUTHENTICATOR)
+            .unwrap());
+    }
+
+    #[test]
+    fn parse_bad_message_authenticator() {
+        let mut rsp = build_response(CODE_ACCESS_ACCEPT, true);
+        rsp[HEADER_LEN + 2] ^= 0xff;
+        resign_response(&mut rsp);
+
+        // should always be checked if present
+        let auth = new_auth(true);
+        assert!(auth
+            .parse_response(&rsp, IDENTIFIER, &REQ_AUTHENTICATOR)
+            .is_err());
+        let auth = new_auth(false);
+        assert!(auth
+            .parse_response(&rsp, IDENTIFIER, &REQ_AUTHENTICATOR)
+            .is_err());
+    }
+
+    #[test]
+    fn parse_bad_response_authenticator() {
+        let auth = new_auth(true);
+
+        let mut rsp = build_response(CODE_ACCESS_ACCEPT, true);
+        rsp[4] ^= 0xff;
+        assert!(auth
+            .parse_response(&rsp, IDENTIFIER, &REQ_AUTHENTICATOR)
+            .is_err());
+
+        // signed with a different request authenticator
+        let rsp = build_response(CODE_ACCESS_ACCEPT, true);
+        let mut req_authenticator = REQ_AUTHENTICATOR;
+        req_authenticator[0] ^= 0xff;
+        assert!(auth
+            .parse_response(&rsp, IDENTIFIER, &req_authenticator)
+            .is_err());
+
+        // attribute changed
+        let mut rsp = build_response(CODE_ACCESS_ACCEPT, true);
+        let last = rsp.len() - 1;
+        rsp[last] ^= 0xff;
+        assert!(auth
+            .parse_response(&rsp, IDENTIFIER, &REQ_AUTHENTICATOR)
+            .is_err());
+    }
+
+    #[test]
+    fn parse_mismatched_identifier() {
+        let auth = new_auth(true);
+        let rsp = build_response(CODE_ACCESS_ACCEPT, true);
+        assert!(auth
+            .parse_response(&rsp, IDENTIFIER + 1, &REQ_AUTHENTICATOR)
+            .is_err());
+        assert!(auth
+            .parse_response(&rsp[..HEADER_LEN - 1], IDENTIFIER, &REQ_AUTHENTICATOR)
+            .is_err());
+    }
+
+    #[test]
+    fn password_encrypt() {
+        let secret = b"xyzzy5461";
+        let authenticator = [
+            0x0f, 0x40, 0x3f, 0x94, 0x73, 0x97, 0x80, 0x57, 0xbd, 0x83, 0xd5, 0xcb, 0x98, 0xf4,
+            0x22, 0x7a,
+        ];
+        // the example in RFC 2865 section 7.1
+        let encrypted = encrypt_password(secret, &authenticator, b"arctangent").unwrap();
+        assert_eq!(
+            encrypted,
+            [
+                0x0d, 0xbe, 0x70, 0x8d, 0x93, 0xd4, 0x13, 0xce, 0x31, 0x96, 0xe4, 0x3f, 0x78, 0x2a,
+                0x0a, 0xee
+            ]
+        );
+    }
+}
@@ -24,11 +24,11 @@ use ip_network_table::IpNetworkTable;
 use radix_trie::Trie;
 
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
-use g3_types::net::{Host, UpstreamAddr};
-use g3_types::resolve::ResolveStrategy;
+use g3_types::net::{Host, StreamDelayConfig, UpstreamAddr};
+use g3_types::resolve::{ResolveRedirectionValue, ResolveStrategy};
 
 use super::stats::{UserSiteDurationRecorder, UserSiteStats};
-use super::{UserSiteDurationStats, UserType};
+use super::{UserRequestStats, UserSiteDurationStats, UserType};
 use crate::config::auth::UserSiteConfig;
 
 struct DurationValue {
@@ -41,36 +41,49 @@ pub(super) struct UserSite {
     config: Arc<UserSiteConfig>,
     stats: Arc<UserSiteStats>,
     duration_recorder: Arc<Mutex<AHashMap<String, DurationValue>>>,
+    resolve_static: Option<ResolveRedirectionValue>,
+}
+
+fn build_resolve_static(config: &UserSiteConfig) -> Option<ResolveRedirectionValue> {
+    if config.resolve_static.is_empty() {
+        return None;
+    }
+    let (ip4, ip6) = config.resolve_static.iter().partition(|ip| ip.is_ipv4());
+    Some(ResolveRedirectionValue::Ip((ip4, ip6)))
 }
 
 impl UserSite {
     fn new(config: &Arc<UserSiteConfig>, user: &str, user_group: &MetricsName) -> Self {
         UserSite {
             config: Arc::clone(config),
-            stats: Arc::new(UserSiteStats::new(user, user_group, &config.id)),
+            stats: Arc::new(UserSiteStats::new(user, user_group, config)),
             duration_recorder: Arc::new(Mutex::new(AHashMap::new())),
+            resolve_static: build_resolve_static(config),
         }
     }
 
     fn new_for_reload(&self, config: &Arc<UserSiteConfig>) -> Self {
+        self.stats.update_emit_control(config);
         if self.config.duration_stats != config.duration_stats {
             UserSite {
                 config: Arc::clone(config),
                 stats: self.stats.clone(),
                 duration_recorder: Arc::new(Mutex::new(AHashMap::new())),
+                resolve_static: build_resolve_static(config),
             }
         } else {
             UserSite {
                 config: Arc::clone(config),
                 stats: self.stats.clone(),
                 duration_recorder: self.duration_recorder.clone(),
+                resolve_static: build_resolve_static(config),
             }
         }
     }
 
     #[inline]
-    pub(super) fn emit_stats(&self) -> bool {
-        self.config.emit_stats
+    pub(super) fn need_stats(&self) -> bool {
+        self.config.need_stats()
     }
 
     #[inline]
@@ -83,6 +96,21 @@ impl UserSite {
         self.config.resolve_strategy
     }
 
+    #[inline]
+    pub(super) fn resolve_static(&self) -> Option<&ResolveRedirectionValue> {
+        self.resolve_static.as_ref()
+    }
+
+    #[inline]
+    pub(super) fn resolver(&self) -> Option<&MetricsName> {
+        self.config.resolver.as_ref()
+    }
+
+    #[inline]
+    pub(super) fn tcp_stream_delay(&self) -> Option<StreamDelayConfig> {
+        self.config.tcp_stream_delay
+    }
+
     pub(crate) fn fetch_duration_recorder(
         &self,
         user_type: UserType,
@@ -114,7 +142,11 @@ impl UserSite {
         drop(map);
 
         if let Some(stats) = new_stats {
-            crate::stat::user_site::push_duration_stats(stats, &self.config.id);
+            crate::stat::user_site::push_duration_stats(
+                stats,
+                &self.config.id,
+                self.stats.emit_control(),
+            );
         }
 
         recorder
@@ -221,6 +253,32 @@ impl UserSites {
         })
     }
 
+    /// register the site level stats for the server in advance,
+    /// so the metrics will be emitted even if no request matched the site yet
+    pub(super) fn register_stats(
+        &self,
+        user_type: UserType,
+        server: &MetricsName,
+        server_extra_tags: &Arc<ArcSwapOption<StaticMetricsTags>>,
+    ) {
+        for site in self.all_sites.values() {
+            if site.need_stats() {
+                site.stats
+                    .fetch_request_stats(user_type, server, server_extra_tags);
+                site.stats
+                    .fetch_traffic_stats(user_type, server, server_extra_tags);
+            }
+        }
+    }
+
+    pub(super) fn register_stats_as(&self, user_stats: &UserRequestStats) {
+        self.register_stats(
+            user_stats.raw_user_type(),
+            user_stats.server(),
+            user_stats.shared_server_extra_tags(),
+        );
+    }
+
     pub(super) fn fetch_site(&self, ups: &UpstreamAddr) -> Option<Arc<UserSite>> {
         match ups.host() {
             Host::Ip(ip) => {
@@ -0,0 +1,450 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use base64::prelude::*;
+use http::{header, Method};
+use log::{debug, warn};
+use nix::NixPath;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{Id, PKey, Public};
+use openssl::sign::Verifier;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use url::Url;
+
+use g3_http::client::HttpForwardRemoteResponse;
+use g3_http::HttpBodyReader;
+use g3_types::fs::ConfigFileFormat;
+
+use crate::config::auth::source::http::UserDynamicHttpSource;
+use crate::config::auth::UserConfig;
+
+/// Validators returned by the server for the last applied payload.
+#[derive(Default)]
+pub(super) struct HttpFetchState {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+struct HttpFetchResult {
+    body: Vec<u8>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Fetch users from the http server, `None` will be returned if not modified.
+pub(super) async fn fetch_records(
+    source: &Arc<UserDynamicHttpSource>,
+    cache: &Path,
+    state: &mut HttpFetchState,
+) -> anyhow::Result<Option<Vec<UserConfig>>> {
+    let validators = if source.conditional {
+        Some(&*state)
+    } else {
+        None
+    };
+    let fut = async {
+        let Some(r) = fetch_url(source, &source.url, validators).await? else {
+            return Ok(None);
+        };
+        if let (Some(sig_url), Some(key)) = (&source.signature_url, &source.signature_key) {
+            let Some(sig) = fetch_url(source, sig_url, None).await? else {
+                return Err(anyhow!("unexpected not modified response for {sig_url}"));
+            };
+            verify_signature(key, &r.body, &sig.body)?;
+        }
+        Ok(Some(r))
+    };
+    let Some(r) = tokio::time::timeout(source.fetch_timeout, fut)
+        .await
+        .map_err(|_| anyhow!("timed out to fetch users from {}", source.url))??
+    else {
+        debug!("users from {} not modified", source.url);
+        return Ok(None);
+    };
+
+    let users = parse_content(source, &r.body)?;
+
+    if !cache.is_empty() {
+        // we should avoid corrupt write at process exit
+        if let Some(Err(e)) =
+            crate::control::run_protected_io(tokio::fs::write(cache, &r.body)).await
+        {
+            warn!(
+                "failed to cache dynamic users to file {} ({e:?}),\
+                 this may lead to auth error during restart",
+                cache.display()
+            );
+        }
+    }
+
+    state.etag = r.etag;
+    state.last_modified = r.last_modified;
+    Ok(Some(users))
+}
+
+fn parse_content(source: &UserDynamicHttpSource, body: &[u8]) -> anyhow::Result<Vec<UserConfig>> {
+    let contents = std::str::from_utf8(body)
+        .map_err(|e| anyhow!("invalid utf-8 content from {}: {e}", source.url))?;
+    if contents.is_empty() {
+        return Ok(Vec::new());
+    }
+    match source.format {
+        ConfigFileFormat::Yaml => {
+            let docs = yaml_rust::YamlLoader::load_from_str(contents)
+                .map_err(|e| anyhow!("invalid yaml content from {}: {e}", source.url))?;
+            crate::config::auth::source::cache::parse_yaml(&docs)
+        }
+        ConfigFileFormat::Json => {
+            let doc = serde_json::Value::from_str(contents)
+                .map_err(|e| anyhow!("invalid json content from {}: {e}", source.url))?;
+            crate::config::auth::source::cache::parse_json(&doc)
+        }
+    }
+}
+
+fn verify_signature(key: &PKey<Public>, data: &[u8], sig: &[u8]) -> anyhow::Result<()> {
+    // the signature file may be either raw binary or base64 encoded
+    let sig = std::str::from_utf8(sig)
+        .ok()
+        .and_then(|s| BASE64_STANDARD.decode(s.trim()).ok())
+        .unwrap_or_else(|| sig.to_vec());
+
+    let verified = match key.id() {
+        Id::ED25519 | Id::ED448 => {
+            let mut verifier = Verifier::new_without_digest(key)
+                .map_err(|e| anyhow!("failed to create signature verifier: {e}"))?;
+            verifier.verify_oneshot(&sig, data)
+        }
+        _ => {
+            let mut verifier = Verifier::new(MessageDigest::sha256(), key)
+                .map_err(|e| anyhow!("failed to create signature verifier: {e}"))?;
+            verifier.verify_oneshot(&sig, data)
+        }
+    }
+    .map_err(|e| anyhow!("failed to verify signature: {e}"))?;
+    if verified {
+        Ok(())
+    } else {
+        Err(anyhow!("signature mismatch"))
+    }
+}
+
+async fn fetch_url(
+    source: &UserDynamicHttpSource,
+    url: &Url,
+    validators: Option<&HttpFetchState>,
+) -> anyhow::Result<Option<HttpFetchResult>> {
+    let server = &source.server;
+    let tcp_stream = tokio::time::timeout(
+        source.connect_timeout,
+        TcpStream::connect((server.host_str().as_ref(), server.port())),
+    )
+    .await
+    .map_err(|_| anyhow!("timed out to connect to http server {server}"))?
+    .map_err(|e| anyhow!("failed to connect to http server {server}: {e}"))?;
+
+    if let Some(tls_client) = &source.tls_client {
+        let Some(tls_name) = source.tls_name.clone() else {
+            return Err(anyhow!("no tls server name set"));
+        };
+        let tls_connect =
+            TlsConnector::from(tls_client.driver.clone()).connect(tls_name, tcp_stream);
+        let tls_stream = tokio::time::timeout(tls_client.handshake_timeout, tls_connect)
+            .await
+            .map_err(|_| anyhow!("tls handshake with http server {server} timed out"))?
+            .map_err(|e| anyhow!("tls handshake with http server {server} failed: {e}"))?;
+        send_request(source, url, validators, tls_stream).await
+    } else {
+        send_request(source, url, validators, tcp_stream).await
+    }
+}
+
+fn build_request(url: &Url, validators: Option<&HttpFetchState>) -> Vec<u8> {
+    let path = &url[url::Position::BeforePath..url::Position::AfterQuery];
+    let host = &url[url::Position::BeforeHost..url::Position::AfterPort];
+
+    let mut buf = Vec::with_capacity(512);
+    buf.extend_from_slice(format!("GET {path} HTTP/1.1\r\nHost: {host}\r\n").as_bytes());
+    buf.extend_from_slice(b"Connection: close\r\n");
+    if !url.username().is_empty() {
+        let username = percent_encoding::percent_decode_str(url.username()).decode_utf8_lossy();
+        let password = url
+            .password()
+            .map(|s| percent_encoding::percent_decode_str(s).decode_utf8_lossy())
+            .unwrap_or_default();
+        let token = BASE64_STANDARD.encode(format!("{username}:{password}"));
+        buf.extend_from_slice(format!("Authorization: Basic {token}\r\n").as_bytes());
+    }
+    if let Some(state) = validators {
+        if let Some(etag) = &state.etag {
+            buf.extend_from_slice(format!("If-None-Match: {etag}\r\n").as_bytes());
+        }
+        if let Some(last_modified) = &state.last_modified {
+            buf.extend_from_slice(format!("If-Modified-Since: {last_modified}\r\n").as_bytes());
+        }
+    }
+    buf.extend_from_slice(b"\r\n");
+    buf
+}
+
+async fn send_request<S>(
+    source: &UserDynamicHttpSource,
+    url: &Url,
+    validators: Option<&HttpFetchState>,
+    mut stream: S,
+) -> anyhow::Result<Option<HttpFetchResult>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let req = build_request(url, validators);
+    stream
+        .write_all(&req)
+        .await
+        .map_err(|e| anyhow!("failed to send request to {url}: {e}"))?;
+    stream
+        .flush()
+        .await
+        .map_err(|e| anyhow!("failed to send request to {url}: {e}"))?;
+
+    let mut reader = BufReader::new(stream);
+    let rsp =
+        HttpForwardRemoteResponse::parse(&mut reader, &Method::GET, false, source.max_header_size)
+            .await
+            .map_err(|e| anyhow!("failed to recv response from {url}: {e}"))?;
+    match rsp.code {
+        200 => {}
+        304 if validators.is_some() => return Ok(None),
+        code => return Err(anyhow!("unexpected response code {code} from {url}")),
+    }
+
+    let get_header = |name| {
+        rsp.end_to_end_headers
+            .get(name)
+            .map(|v| v.to_str().to_string())
+    };
+    let etag = get_header(header::ETAG);
+    let last_modified = get_header(header::LAST_MODIFIED);
+
+    let mut body = Vec::new();
+    if let Some(body_type) = rsp.body_type(&Method::GET) {
+        let body_reader = HttpBodyReader::new(&mut reader, body_type, 1024);
+        body_reader
+            .take(source.max_body_size as u64 + 1)
+            .read_to_end(&mut body)
+            .await
+            .map_err(|e| anyhow!("failed to read response body from {url}: {e}"))?;
+        if body.len() > source.max_body_size {
+            return Err(anyhow!("too large response body from {url}"));
+        }
+    }
+
+    Ok(Some(HttpFetchResult {
+        body,
+        etag,
+        last_modified,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::pkey::Private;
+    use openssl::rsa::Rsa;
+    use openssl::sign::Signer;
+    use tokio::net::TcpListener;
+
+    use g3_types::net::UpstreamAddr;
+
+    const USERS: &[u8] = b"[]";
+
+    fn public_key(key: &PKey<Private>) -> PKey<Public> {
+        let pem = key.public_key_to_pem().unwrap();
+        PKey::public_key_from_pem(&pem).unwrap()
+    }
+
+    fn sign(key: &PKey<Private>, data: &[u8]) -> Vec<u8> {
+        match key.id() {
+            Id::ED25519 | Id::ED448 => {
+                let mut signer = Signer::new_without_digest(key).unwrap();
+                signer.sign_oneshot_to_vec(data).unwrap()
+            }
+            _ => {
+                let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+                signer.update(data).unwrap();
+                signer.sign_to_vec().unwrap()
+            }
+        }
+    }
+
+    fn ed25519_key() -> PKey<Private> {
+        PKey::generate_ed25519().unwrap()
+    }
+
+    fn rsa_key() -> PKey<Private> {
+        PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap()
+    }
+
+    fn ec_key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn check_key(key: PKey<Private>) {
+        let data = b"test users content";
+        let public = public_key(&key);
+        let sig = sign(&key, data);
+
+        verify_signature(&public, data, &sig).unwrap();
+        let encoded = BASE64_STANDARD.encode(&sig);
+        verify_signature(&public, data, encoded.as_bytes()).unwrap();
+        let encoded = format!("{encoded}\n");
+        verify_signature(&public, data, encoded.as_bytes()).unwrap();
+
+        assert!(verify_signature(&public, b"other users content", &sig).is_err());
+        let other_sig = sign(&key, b"other users content");
+        assert!(verify_signature(&public, data, &other_sig).is_err());
+    }
+
+    #[test]
+    fn verify_ed25519() {
+        check_key(ed25519_key());
+    }
+
+    #[test]
+    fn verify_rsa_sha256() {
+        check_key(rsa_key());
+    }
+
+    #[test]
+    fn verify_ec_sha256() {
+        check_key(ec_key());
+    }
+
+    #[test]
+    fn verify_key_mismatch() {
+        let data = b"test users content";
+        let sig = sign(&ed25519_key(), data);
+        let public = public_key(&ed25519_key());
+        assert!(verify_signature(&public, data, &sig).is_err());
+
+        let sig = sign(&rsa_key(), data);
+        let public = public_key(&ec_key());
+        assert!(verify_signature(&public, data, &sig).is_err());
+    }
+
+    async fn run_server(listener: TcpListener, sig: Vec<u8>) {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut req = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !req.ends_with(b"\r\n\r\n") {
+                let nr = stream.read(&mut buf).await.unwrap();
+                if nr == 0 {
+                    break;
+                }
+                req.extend_from_slice(&buf[..nr]);
+            }
+            let req = String::from_utf8(req).unwrap();
+            let path = req.split(' ').nth(1).unwrap();
+            let body = if path.ends_with(".sig") {
+                sig.as_slice()
+            } else {
+                USERS
+            };
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(header.as_bytes()).await.unwrap();
+            stream.write_all(body).await.unwrap();
+            stream.shutdown().await.unwrap();
+        }
+    }
+
+    async fn start_server(sig: Vec<u8>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(run_server(listener, sig));
+        addr
+    }
+
+    fn test_source(addr: SocketAddr, key: PKey<Public>) -> Arc<UserDynamicHttpSource> {
+        let url = Url::parse(&format!("http://{addr}/users.json")).unwrap();
+        let signature_url = Url::parse(&format!("http://{addr}/users.json.sig")).unwrap();
+        Arc::new(UserDynamicHttpSource {
+            server: UpstreamAddr::try_from(&url).unwrap(),
+            url,
+            tls_client: None,
+            tls_name: None,
+            format: ConfigFileFormat::Json,
+            conditional: true,
+            signature_url: Some(signature_url),
+            signature_key: Some(key),
+            max_header_size: 4096,
+            max_body_size: 4096,
+            connect_timeout: Duration::from_secs(5),
+            fetch_timeout: Duration::from_secs(10),
+        })
+    }
+
+    fn test_cache(name: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("g3proxy-http-users-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn fetch_verified() {
+        let key = ed25519_key();
+        let addr = start_server(sign(&key, USERS)).await;
+        let source = test_source(addr, public_key(&key));
+        let cache = test_cache("verified");
+
+        let mut state = HttpFetchState::default();
+        let users = fetch_records(&source, &cache, &mut state).await.unwrap();
+        assert!(users.unwrap().is_empty());
+        assert_eq!(state.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(std::fs::read(&cache).unwrap(), USERS);
+        let _ = std::fs::remove_file(&cache);
+    }
+
+    #[tokio::test]
+    async fn fetch_mismatch() {
+        let key = ed25519_key();
+        let addr = start_server(sign(&key, b"other users content")).await;
+        let source = test_source(addr, public_key(&key));
+        let cache = test_cache("mismatch");
+
+        let mut state = HttpFetchState::default();
+        assert!(fetch_records(&source, &cache, &mut state).await.is_err());
+        assert!(state.etag.is_none());
+        assert!(state.last_modified.is_none());
+        assert!(!cache.exists());
+    }
+}
@@ -0,0 +1,192 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+pub(super) const TAG_BOOLEAN: u8 = 0x01;
+pub(super) const TAG_INTEGER: u8 = 0x02;
+pub(super) const TAG_OCTET_STRING: u8 = 0x04;
+pub(super) const TAG_ENUMERATED: u8 = 0x0a;
+pub(super) const TAG_SEQUENCE: u8 = 0x30;
+pub(super) const TAG_SET: u8 = 0x31;
+
+const MAX_MESSAGE_SIZE: usize = 16 << 20;
+
+fn encode_length(len: usize, buf: &mut Vec<u8>) {
+    if len < 0x80 {
+        buf.push(len as u8);
+    } else {
+        let bytes = (len as u64).to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        buf.push(0x80 | (bytes.len() - skip) as u8);
+        buf.extend_from_slice(&bytes[skip..]);
+    }
+}
+
+pub(super) fn encode_tlv(tag: u8, content: &[u8], buf: &mut Vec<u8>) {
+    buf.push(tag);
+    encode_length(content.len(), buf);
+    buf.extend_from_slice(content);
+}
+
+pub(super) fn encode_integer(tag: u8, value: i64, buf: &mut Vec<u8>) {
+    let bytes = value.to_be_bytes();
+    let mut skip = 0;
+    // keep the minimal two's complement encoding
+    while skip < bytes.len() - 1 {
+        let (b, next) = (bytes[skip], bytes[skip + 1]);
+        if (b == 0x00 && next & 0x80 == 0) || (b == 0xff && next & 0x80 != 0) {
+            skip += 1;
+        } else {
+            break;
+        }
+    }
+    encode_tlv(tag, &bytes[skip..], buf);
+}
+
+pub(super) fn encode_boolean(value: bool, buf: &mut Vec<u8>) {
+    encode_tlv(TAG_BOOLEAN, &[if value { 0xff } else { 0x00 }], buf);
+}
+
+pub(super) struct BerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BerReader<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Self {
+        BerReader { data }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub(super) fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    pub(super) fn read_tlv(&mut self) -> anyhow::Result<(u8, &'a [u8])> {
+        let Some((&tag, left)) = self.data.split_first() else {
+            return Err(anyhow!("no more ber data"));
+        };
+        let Some((&l, mut left)) = left.split_first() else {
+            return Err(anyhow!("no length for ber tag {tag:#04x}"));
+        };
+        let len = if l & 0x80 == 0 {
+            l as usize
+        } else {
+            let n = (l & 0x7f) as usize;
+            if n == 0 || n > 4 || left.len() < n {
+                return Err(anyhow!("invalid length for ber tag {tag:#04x}"));
+            }
+            let len = left[..n]
+                .iter()
+                .fold(0usize, |acc, b| (acc << 8) | (*b as usize));
+            left = &left[n..];
+            len
+        };
+        if left.len() < len {
+            return Err(anyhow!("truncated value for ber tag {tag:#04x}"));
+        }
+        let (value, left) = left.split_at(len);
+        self.data = left;
+        Ok((tag, value))
+    }
+
+    pub(super) fn read_expected(&mut self, tag: u8) -> anyhow::Result<&'a [u8]> {
+        let (t, value) = self.read_tlv()?;
+        if t != tag {
+            return Err(anyhow!("expected ber tag {tag:#04x} but got {t:#04x}"));
+        }
+        Ok(value)
+    }
+
+    pub(super) fn read_integer(&mut self, tag: u8) -> anyhow::Result<i64> {
+        let value = self.read_expected(tag)?;
+        if value.is_empty() || value.len() > 8 {
+            return Err(anyhow!("invalid integer value length {}", value.len()));
+        }
+        let init = if value[0] & 0x80 != 0 { -1i64 } else { 0 };
+        Ok(value.iter().fold(init, |acc, b| (acc << 8) | (*b as i64)))
+    }
+
+    pub(super) fn read_string(&mut self) -> anyhow::Result<String> {
+        let value = self.read_expected(TAG_OCTET_STRING)?;
+        Ok(String::from_utf8_lossy(value).to_string())
+    }
+}
+
+/// read a full LDAPMessage and return the content of the outer sequence
+pub(super) async fn read_message<R>(reader: &mut R) -> anyhow::Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin,
+{
+    let tag = reader.read_u8().await?;
+    if tag != TAG_SEQUENCE {
+        return Err(anyhow!("invalid ldap message tag {tag:#04x}"));
+    }
+    let l = reader.read_u8().await?;
+    let len = if l & 0x80 == 0 {
+        l as usize
+    } else {
+        let n = (l & 0x7f) as usize;
+        if n == 0 || n > 4 {
+            return Err(anyhow!("invalid ldap message length"));
+        }
+        let mut len = 0usize;
+        for _ in 0..n {
+            len = (len << 8) | (reader.read_u8().await? as usize);
+        }
+        len
+    };
+    if len > MAX_MESSAGE_SIZE {
+        return Err(anyhow!("too large ldap message size {len}"));
+    }
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf).await?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer() {
+        for v in [0i64, 1, 127, 128, 256, -1, -128, -129, i64::MAX, i64::MIN] {
+            let mut buf = Vec::new();
+            encode_integer(TAG_INTEGER, v, &mut buf);
+            let mut reader = BerReader::new(&buf);
+            assert_eq!(reader.read_integer(TAG_INTEGER).unwrap(), v);
+            assert!(reader.is_empty());
+        }
+
+        let mut buf = Vec::new();
+        encode_integer(TAG_INTEGER, 128, &mut buf);
+        assert_eq!(buf, [0x02, 0x02, 0x00, 0x80]);
+    }
+
+    #[test]
+    fn long_length() {
+        let content = vec![0x41u8; 300];
+        let mut buf = Vec::new();
+        encode_tlv(TAG_OCTET_STRING, &content, &mut buf);
+        assert_eq!(&buf[..4], &[0x04, 0x82, 0x01, 0x2c]);
+        let mut reader = BerReader::new(&buf);
+        assert_eq!(reader.read_expected(TAG_OCTET_STRING).unwrap(), content);
+    }
+}
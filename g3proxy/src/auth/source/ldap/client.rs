@@ -0,0 +1,357 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use super::ber::{self, BerReader};
+use crate::config::auth::source::ldap::{LdapFilter, LdapSubstringsFilter};
+
+const TAG_BIND_REQUEST: u8 = 0x60;
+const TAG_BIND_RESPONSE: u8 = 0x61;
+const TAG_UNBIND_REQUEST: u8 = 0x42;
+const TAG_SEARCH_REQUEST: u8 = 0x63;
+const TAG_SEARCH_RESULT_ENTRY: u8 = 0x64;
+const TAG_SEARCH_RESULT_DONE: u8 = 0x65;
+const TAG_SEARCH_RESULT_REFERENCE: u8 = 0x73;
+const TAG_CONTROLS: u8 = 0xa0;
+const TAG_AUTH_SIMPLE: u8 = 0x80;
+
+const TAG_FILTER_AND: u8 = 0xa0;
+const TAG_FILTER_OR: u8 = 0xa1;
+const TAG_FILTER_NOT: u8 = 0xa2;
+const TAG_FILTER_EQUALITY: u8 = 0xa3;
+const TAG_FILTER_SUBSTRINGS: u8 = 0xa4;
+const TAG_FILTER_GREATER_OR_EQUAL: u8 = 0xa5;
+const TAG_FILTER_LESS_OR_EQUAL: u8 = 0xa6;
+const TAG_FILTER_PRESENT: u8 = 0x87;
+const TAG_FILTER_APPROX_MATCH: u8 = 0xa8;
+const TAG_SUBSTRING_INITIAL: u8 = 0x80;
+const TAG_SUBSTRING_ANY: u8 = 0x81;
+const TAG_SUBSTRING_FINAL: u8 = 0x82;
+
+const LDAP_VERSION: i64 = 3;
+const SEARCH_SCOPE_WHOLE_SUBTREE: i64 = 2;
+const DEREF_ALIASES_NEVER: i64 = 0;
+
+const CONTROL_PAGED_RESULTS: &[u8] = b"1.2.840.113556.1.4.319";
+
+pub(super) struct LdapEntry {
+    pub(super) dn: String,
+    pub(super) attributes: Vec<(String, Vec<Vec<u8>>)>,
+}
+
+impl LdapEntry {
+    pub(super) fn get(&self, attr: &str) -> Option<&[Vec<u8>]> {
+        self.attributes
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(attr))
+            .map(|(_, values)| values.as_slice())
+    }
+}
+
+pub(super) struct LdapSearchParams<'a> {
+    pub(super) base_dn: &'a str,
+    pub(super) filter: &'a LdapFilter,
+    pub(super) attributes: &'a [&'a str],
+    pub(super) page_size: u32,
+}
+
+pub(super) struct LdapConnection<S> {
+    stream: BufReader<S>,
+    message_id: i64,
+}
+
+impl<S> LdapConnection<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    pub(super) fn new(stream: S) -> Self {
+        LdapConnection {
+            stream: BufReader::new(stream),
+            message_id: 0,
+        }
+    }
+
+    async fn send_message(&mut self, op: &[u8], controls: Option<&[u8]>) -> anyhow::Result<i64> {
+        self.message_id += 1;
+        let mut content = Vec::with_capacity(op.len() + 16);
+        ber::encode_integer(ber::TAG_INTEGER, self.message_id, &mut content);
+        content.extend_from_slice(op);
+        if let Some(controls) = controls {
+            ber::encode_tlv(TAG_CONTROLS, controls, &mut content);
+        }
+        let mut buf = Vec::with_capacity(content.len() + 8);
+        ber::encode_tlv(ber::TAG_SEQUENCE, &content, &mut buf);
+
+        let writer = self.stream.get_mut();
+        writer
+            .write_all(&buf)
+            .await
+            .map_err(|e| anyhow!("failed to send ldap message: {e}"))?;
+        writer
+            .flush()
+            .await
+            .map_err(|e| anyhow!("failed to flush ldap message: {e}"))?;
+        Ok(self.message_id)
+    }
+
+    async fn recv_message(&mut self, message_id: i64) -> anyhow::Result<Vec<u8>> {
+        let msg = ber::read_message(&mut self.stream)
+            .await
+            .map_err(|e| anyhow!("failed to read ldap message: {e}"))?;
+        let mut reader = BerReader::new(&msg);
+        let id = reader.read_integer(ber::TAG_INTEGER)?;
+        if id == 0 {
+            // unsolicited notification, such as notice of disconnection
+            return Err(anyhow!("unsolicited notification received from server"));
+        }
+        if id != message_id {
+            return Err(anyhow!(
+                "unexpected message id {id}, the expected one is {message_id}"
+            ));
+        }
+        Ok(msg)
+    }
+
+    pub(super) async fn simple_bind(&mut self, dn: &str, password: &str) -> anyhow::Result<()> {
+        let mut content = Vec::new();
+        ber::encode_integer(ber::TAG_INTEGER, LDAP_VERSION, &mut content);
+        ber::encode_tlv(ber::TAG_OCTET_STRING, dn.as_bytes(), &mut content);
+        ber::encode_tlv(TAG_AUTH_SIMPLE, password.as_bytes(), &mut content);
+        let mut op = Vec::new();
+        ber::encode_tlv(TAG_BIND_REQUEST, &content, &mut op);
+
+        let id = self.send_message(&op, None).await?;
+        let msg = self.recv_message(id).await?;
+        let mut reader = BerReader::new(&msg);
+        reader.read_tlv()?; // message id
+        let result = reader.read_expected(TAG_BIND_RESPONSE)?;
+        check_result(result).context("bind failed")
+    }
+
+    pub(super) async fn search(
+        &mut self,
+        params: &LdapSearchParams<'_>,
+    ) -> anyhow::Result<Vec<LdapEntry>> {
+        let mut op = Vec::new();
+        encode_search_request(params, &mut op);
+
+        let mut entries = Vec::new();
+        let mut cookie = Vec::new();
+        loop {
+            let controls = if params.page_size > 0 {
+                let mut controls = Vec::new();
+                encode_paged_results_control(params.page_size, &cookie, &mut controls);
+                Some(controls)
+            } else {
+                None
+            };
+            let id = self.send_message(&op, controls.as_deref()).await?;
+
+            loop {
+                let msg = self.recv_message(id).await?;
+                let mut reader = BerReader::new(&msg);
+                reader.read_tlv()?; // message id
+                let (tag, value) = reader.read_tlv()?;
+                match tag {
+                    TAG_SEARCH_RESULT_ENTRY => {
+                        let entry = parse_search_entry(value).context("invalid search entry")?;
+                        entries.push(entry);
+                    }
+                    TAG_SEARCH_RESULT_REFERENCE => {} // referrals are not followed
+                    TAG_SEARCH_RESULT_DONE => {
+                        check_result(value).context("search failed")?;
+                        cookie = if reader.peek_tag() == Some(TAG_CONTROLS) {
+                            let controls = reader.read_expected(TAG_CONTROLS)?;
+                            parse_paged_results_cookie(controls)?
+                        } else {
+                            Vec::new()
+                        };
+                        break;
+                    }
+                    _ => return Err(anyhow!("unexpected search response tag {tag:#04x}")),
+                }
+            }
+
+            if params.page_size == 0 || cookie.is_empty() {
+                return Ok(entries);
+            }
+        }
+    }
+
+    pub(super) async fn unbind(&mut self) {
+        let mut op = Vec::new();
+        ber::encode_tlv(TAG_UNBIND_REQUEST, &[], &mut op);
+        let _ = self.send_message(&op, None).await;
+        let _ = self.stream.get_mut().shutdown().await;
+    }
+}
+
+fn check_result(data: &[u8]) -> anyhow::Result<()> {
+    let mut reader = BerReader::new(data);
+    let code = reader.read_integer(ber::TAG_ENUMERATED)?;
+    if code == 0 {
+        return Ok(());
+    }
+    let _matched_dn = reader.read_string()?;
+    let message = reader.read_string()?;
+    Err(anyhow!("ldap result code {code}: {message}"))
+}
+
+fn encode_search_request(params: &LdapSearchParams<'_>, buf: &mut Vec<u8>) {
+    let mut content = Vec::new();
+    ber::encode_tlv(
+        ber::TAG_OCTET_STRING,
+        params.base_dn.as_bytes(),
+        &mut content,
+    );
+    ber::encode_integer(
+        ber::TAG_ENUMERATED,
+        SEARCH_SCOPE_WHOLE_SUBTREE,
+        &mut content,
+    );
+    ber::encode_integer(ber::TAG_ENUMERATED, DEREF_ALIASES_NEVER, &mut content);
+    ber::encode_integer(ber::TAG_INTEGER, 0, &mut content); // size limit
+    ber::encode_integer(ber::TAG_INTEGER, 0, &mut content); // time limit
+    ber::encode_boolean(false, &mut content); // types only
+    encode_filter(params.filter, &mut content);
+    let mut attributes = Vec::new();
+    for attr in params.attributes {
+        ber::encode_tlv(ber::TAG_OCTET_STRING, attr.as_bytes(), &mut attributes);
+    }
+    ber::encode_tlv(ber::TAG_SEQUENCE, &attributes, &mut content);
+
+    ber::encode_tlv(TAG_SEARCH_REQUEST, &content, buf);
+}
+
+fn encode_filter(filter: &LdapFilter, buf: &mut Vec<u8>) {
+    let encode_ava = |tag: u8, attr: &str, value: &[u8], buf: &mut Vec<u8>| {
+        let mut content = Vec::new();
+        ber::encode_tlv(ber::TAG_OCTET_STRING, attr.as_bytes(), &mut content);
+        ber::encode_tlv(ber::TAG_OCTET_STRING, value, &mut content);
+        ber::encode_tlv(tag, &content, buf);
+    };
+
+    match filter {
+        LdapFilter::And(list) | LdapFilter::Or(list) => {
+            let tag = if matches!(filter, LdapFilter::And(_)) {
+                TAG_FILTER_AND
+            } else {
+                TAG_FILTER_OR
+            };
+            let mut content = Vec::new();
+            for f in list {
+                encode_filter(f, &mut content);
+            }
+            ber::encode_tlv(tag, &content, buf);
+        }
+        LdapFilter::Not(f) => {
+            let mut content = Vec::new();
+            encode_filter(f, &mut content);
+            ber::encode_tlv(TAG_FILTER_NOT, &content, buf);
+        }
+        LdapFilter::Equality(attr, value) => encode_ava(TAG_FILTER_EQUALITY, attr, value, buf),
+        LdapFilter::Substrings(f) => encode_substrings_filter(f, buf),
+        LdapFilter::GreaterOrEqual(attr, value) => {
+            encode_ava(TAG_FILTER_GREATER_OR_EQUAL, attr, value, buf)
+        }
+        LdapFilter::LessOrEqual(attr, value) => {
+            encode_ava(TAG_FILTER_LESS_OR_EQUAL, attr, value, buf)
+        }
+        LdapFilter::Present(attr) => ber::encode_tlv(TAG_FILTER_PRESENT, attr.as_bytes(), buf),
+        LdapFilter::ApproxMatch(attr, value) => {
+            encode_ava(TAG_FILTER_APPROX_MATCH, attr, value, buf)
+        }
+    }
+}
+
+fn encode_substrings_filter(filter: &LdapSubstringsFilter, buf: &mut Vec<u8>) {
+    let mut substrings = Vec::new();
+    if let Some(initial) = &filter.initial {
+        ber::encode_tlv(TAG_SUBSTRING_INITIAL, initial, &mut substrings);
+    }
+    for any in &filter.any {
+        ber::encode_tlv(TAG_SUBSTRING_ANY, any, &mut substrings);
+    }
+    if let Some(last) = &filter.last {
+        ber::encode_tlv(TAG_SUBSTRING_FINAL, last, &mut substrings);
+    }
+
+    let mut content = Vec::new();
+    ber::encode_tlv(ber::TAG_OCTET_STRING, filter.attr.as_bytes(), &mut content);
+    ber::encode_tlv(ber::TAG_SEQUENCE, &substrings, &mut content);
+    ber::encode_tlv(TAG_FILTER_SUBSTRINGS, &content, buf);
+}
+
+fn encode_paged_results_control(size: u32, cookie: &[u8], buf: &mut Vec<u8>) {
+    let mut value = Vec::new();
+    ber::encode_integer(ber::TAG_INTEGER, size as i64, &mut value);
+    ber::encode_tlv(ber::TAG_OCTET_STRING, cookie, &mut value);
+    let mut control_value = Vec::new();
+    ber::encode_tlv(ber::TAG_SEQUENCE, &value, &mut control_value);
+
+    let mut control = Vec::new();
+    ber::encode_tlv(ber::TAG_OCTET_STRING, CONTROL_PAGED_RESULTS, &mut control);
+    ber::encode_tlv(ber::TAG_OCTET_STRING, &control_value, &mut control);
+    ber::encode_tlv(ber::TAG_SEQUENCE, &control, buf);
+}
+
+fn parse_paged_results_cookie(controls: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut reader = BerReader::new(controls);
+    while !reader.is_empty() {
+        let control = reader.read_expected(ber::TAG_SEQUENCE)?;
+        let mut reader = BerReader::new(control);
+        let oid = reader.read_expected(ber::TAG_OCTET_STRING)?;
+        if oid != CONTROL_PAGED_RESULTS {
+            continue;
+        }
+        if reader.peek_tag() == Some(ber::TAG_BOOLEAN) {
+            reader.read_tlv()?;
+        }
+        let value = reader.read_expected(ber::TAG_OCTET_STRING)?;
+        let mut reader = BerReader::new(value);
+        let value = reader.read_expected(ber::TAG_SEQUENCE)?;
+        let mut reader = BerReader::new(value);
+        let _size = reader.read_integer(ber::TAG_INTEGER)?;
+        let cookie = reader.read_expected(ber::TAG_OCTET_STRING)?;
+        return Ok(cookie.to_vec());
+    }
+    Ok(Vec::new())
+}
+
+fn parse_search_entry(data: &[u8]) -> anyhow::Result<LdapEntry> {
+    let mut reader = BerReader::new(data);
+    let dn = reader.read_string()?;
+    let attr_list = reader.read_expected(ber::TAG_SEQUENCE)?;
+
+    let mut attributes = Vec::new();
+    let mut reader = BerReader::new(attr_list);
+    while !reader.is_empty() {
+        let attr = reader.read_expected(ber::TAG_SEQUENCE)?;
+        let mut reader = BerReader::new(attr);
+        let name = reader.read_string()?;
+        let value_set = reader.read_expected(ber::TAG_SET)?;
+        let mut values = Vec::new();
+        let mut reader = BerReader::new(value_set);
+        while !reader.is_empty() {
+            let value = reader.read_expected(ber::TAG_OCTET_STRING)?;
+            values.push(value.to_vec());
+        }
+        attributes.push((name, values));
+    }
+    Ok(LdapEntry { dn, attributes })
+}
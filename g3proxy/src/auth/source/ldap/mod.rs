@@ -0,0 +1,192 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use log::warn;
+use nix::NixPath;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use yaml_rust::{yaml, Yaml, YamlEmitter};
+
+use crate::config::auth::source::ldap::UserDynamicLdapSource;
+use crate::config::auth::UserConfig;
+
+mod ber;
+
+mod client;
+use client::{LdapConnection, LdapEntry, LdapSearchParams};
+
+pub(super) async fn fetch_records(
+    source: &Arc<UserDynamicLdapSource>,
+    cache: &Path,
+) -> anyhow::Result<Vec<UserConfig>> {
+    let entries = tokio::time::timeout(source.fetch_timeout, fetch_entries(source))
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "timed out to fetch users from ldap server {}",
+                source.server
+            )
+        })??;
+
+    let mut users = Vec::with_capacity(entries.len());
+    let mut docs = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let Some(map) = convert_entry(source, &entry) else {
+            continue;
+        };
+        match UserConfig::parse_yaml(&map) {
+            Ok(user) => {
+                users.push(user);
+                docs.push(Yaml::Hash(map));
+            }
+            Err(e) => warn!("invalid user config in ldap entry {}: {e:?}", entry.dn),
+        }
+    }
+
+    if !cache.is_empty() {
+        let mut contents = String::new();
+        let mut emitter = YamlEmitter::new(&mut contents);
+        emitter
+            .dump(&Yaml::Array(docs))
+            .map_err(|e| anyhow!("failed to encode dynamic users: {e:?}"))?;
+        // we should avoid corrupt write at process exit
+        if let Some(Err(e)) =
+            crate::control::run_protected_io(tokio::fs::write(cache, contents)).await
+        {
+            warn!(
+                "failed to cache dynamic users to file {} ({e:?}),\
+                 this may lead to auth error during restart",
+                cache.display()
+            );
+        }
+    }
+
+    Ok(users)
+}
+
+async fn fetch_entries(source: &UserDynamicLdapSource) -> anyhow::Result<Vec<LdapEntry>> {
+    let server = &source.server;
+    let tcp_stream = tokio::time::timeout(
+        source.connect_timeout,
+        TcpStream::connect((server.host_str().as_ref(), server.port())),
+    )
+    .await
+    .map_err(|_| anyhow!("timed out to connect to ldap server {server}"))?
+    .map_err(|e| anyhow!("failed to connect to ldap server {server}: {e}"))?;
+
+    if let Some(tls_client) = &source.tls_client {
+        let Some(tls_name) = source.tls_name.clone() else {
+            return Err(anyhow!("no tls server name set"));
+        };
+        let tls_connect =
+            TlsConnector::from(tls_client.driver.clone()).connect(tls_name, tcp_stream);
+        let tls_stream = tokio::time::timeout(tls_client.handshake_timeout, tls_connect)
+            .await
+            .map_err(|_| anyhow!("tls handshake with ldap server {server} timed out"))?
+            .map_err(|e| anyhow!("tls handshake with ldap server {server} failed: {e}"))?;
+        search_users(source, tls_stream).await
+    } else {
+        search_users(source, tcp_stream).await
+    }
+}
+
+async fn search_users<S>(
+    source: &UserDynamicLdapSource,
+    stream: S,
+) -> anyhow::Result<Vec<LdapEntry>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut connection = LdapConnection::new(stream);
+    connection
+        .simple_bind(&source.bind_dn, &source.bind_password)
+        .await?;
+
+    let mut attributes = Vec::with_capacity(source.attributes.len() + 1);
+    attributes.push(source.username_attribute.as_str());
+    for attr in source.attributes.keys() {
+        attributes.push(attr.as_str());
+    }
+    let params = LdapSearchParams {
+        base_dn: &source.base_dn,
+        filter: &source.filter,
+        attributes: &attributes,
+        page_size: source.page_size,
+    };
+    let entries = connection
+        .search(&params)
+        .await
+        .context(format!("failed to search in {}", source.base_dn))?;
+
+    connection.unbind().await;
+    Ok(entries)
+}
+
+fn convert_entry(source: &UserDynamicLdapSource, entry: &LdapEntry) -> Option<yaml::Hash> {
+    let Some(name) = entry
+        .get(&source.username_attribute)
+        .and_then(|values| values.first())
+        .and_then(|v| std::str::from_utf8(v).ok())
+    else {
+        warn!(
+            "no valid {} attribute found in ldap entry {}",
+            source.username_attribute, entry.dn
+        );
+        return None;
+    };
+
+    let mut map = source.user_template.clone();
+    map.insert(
+        Yaml::String("name".to_string()),
+        Yaml::String(name.to_string()),
+    );
+    for (attr, key) in &source.attributes {
+        let Some(values) = entry.get(attr) else {
+            continue;
+        };
+        let mut seq = Vec::with_capacity(values.len());
+        for v in values {
+            let Ok(s) = std::str::from_utf8(v) else {
+                warn!(
+                    "non utf-8 value found for attribute {attr} in ldap entry {}",
+                    entry.dn
+                );
+                return None;
+            };
+            let s = if key == "token" {
+                // RFC 3112 style crypt hash
+                s.strip_prefix("{CRYPT}")
+                    .or_else(|| s.strip_prefix("{crypt}"))
+                    .unwrap_or(s)
+            } else {
+                s
+            };
+            seq.push(Yaml::String(s.to_string()));
+        }
+        let value = if seq.len() == 1 {
+            seq.pop().unwrap()
+        } else {
+            Yaml::Array(seq)
+        };
+        map.insert(Yaml::String(key.to_string()), value);
+    }
+    Some(map)
+}
@@ -15,6 +15,7 @@
  */
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use ahash::AHashMap;
 use arc_swap::ArcSwap;
@@ -25,6 +26,9 @@ use log::warn;
 use super::{User, UserGroupConfig};
 use crate::config::auth::{UserConfig, UserDynamicSource};
 
+mod http;
+mod ldap;
+
 #[cfg(feature = "lua")]
 mod lua;
 
@@ -37,6 +41,16 @@ pub(super) async fn load_initial_users(
 ) -> anyhow::Result<AHashMap<String, Arc<User>>> {
     let r = match source {
         UserDynamicSource::File(config) => config.fetch_records().await?,
+        UserDynamicSource::Http(config) => {
+            config
+                .fetch_cached_records(&group_config.dynamic_cache)
+                .await?
+        }
+        UserDynamicSource::Ldap(config) => {
+            config
+                .fetch_cached_records(&group_config.dynamic_cache)
+                .await?
+        }
         #[cfg(feature = "lua")]
         UserDynamicSource::Lua(config) => {
             config
@@ -75,22 +89,13 @@ pub(super) fn new_job(
     let f = async move {
         let mut interval = tokio::time::interval(group_config.refresh_interval);
         interval.tick().await; // will tick immediately
+        let mut http_state = http::HttpFetchState::default();
         loop {
             let new_dynamic_config: Option<Vec<UserConfig>> =
                 if let Some(source) = &group_config.dynamic_source {
-                    let r = match source {
-                        UserDynamicSource::File(config) => config.fetch_records().await,
-                        #[cfg(feature = "lua")]
-                        UserDynamicSource::Lua(config) => {
-                            lua::fetch_records(config, &group_config.dynamic_cache).await
-                        }
-                        #[cfg(feature = "python")]
-                        UserDynamicSource::Python(config) => {
-                            python::fetch_records(config, &group_config.dynamic_cache).await
-                        }
-                    };
+                    let r = fetch_dynamic_users(&group_config, source, &mut http_state).await;
                     match r {
-                        Ok(users) => Some(users),
+                        Ok(users) => users,
                         Err(e) => {
                             warn!(
                                 "failed to fetch dynamic user for group {}: {e:?}",
@@ -121,6 +126,11 @@ pub(super) fn new_job(
             check_static_users(&datetime_now, &static_users);
 
             interval.tick().await;
+            if !group_config.refresh_jitter.is_zero() {
+                // spread the fetch requests from different instances
+                let jitter = fastrand::u64(0..group_config.refresh_jitter.as_millis() as u64);
+                tokio::time::sleep(Duration::from_millis(jitter)).await;
+            }
         }
     };
 
@@ -130,6 +140,26 @@ pub(super) fn new_job(
     abort_handle
 }
 
+async fn fetch_dynamic_users(
+    group_config: &UserGroupConfig,
+    source: &UserDynamicSource,
+    http_state: &mut http::HttpFetchState,
+) -> anyhow::Result<Option<Vec<UserConfig>>> {
+    let cache = &group_config.dynamic_cache;
+    let r = match source {
+        UserDynamicSource::File(config) => config.fetch_records().await?,
+        UserDynamicSource::Http(config) => {
+            return http::fetch_records(config, cache, http_state).await;
+        }
+        UserDynamicSource::Ldap(config) => ldap::fetch_records(config, cache).await?,
+        #[cfg(feature = "lua")]
+        UserDynamicSource::Lua(config) => lua::fetch_records(config, cache).await?,
+        #[cfg(feature = "python")]
+        UserDynamicSource::Python(config) => python::fetch_records(config, cache).await?,
+    };
+    Ok(Some(r))
+}
+
 pub(super) fn publish_dynamic_users(
     group_config: &UserGroupConfig,
     dynamic_config: Vec<UserConfig>,
@@ -41,6 +41,9 @@ pub(crate) struct UserForbiddenStats {
     dest_denied: AtomicU64,
     ip_blocked: AtomicU64,
     ua_blocked: AtomicU64,
+    time_blocked: AtomicU64,
+    quota_exceeded: AtomicU64,
+    conn_limited: AtomicU64,
     log_skipped: AtomicU64,
 }
 
@@ -56,6 +59,9 @@ pub(crate) struct UserForbiddenSnapshot {
     pub(crate) dest_denied: u64,
     pub(crate) ip_blocked: u64,
     pub(crate) ua_blocked: u64,
+    pub(crate) time_blocked: u64,
+    pub(crate) quota_exceeded: u64,
+    pub(crate) conn_limited: u64,
     pub(crate) log_skipped: u64,
 }
 
@@ -84,6 +90,9 @@ impl UserForbiddenStats {
             dest_denied: Default::default(),
             ip_blocked: Default::default(),
             ua_blocked: Default::default(),
+            time_blocked: Default::default(),
+            quota_exceeded: Default::default(),
+            conn_limited: Default::default(),
             log_skipped: Default::default(),
         }
     }
@@ -130,6 +139,9 @@ impl UserForbiddenStats {
             dest_denied: self.dest_denied.load(Ordering::Relaxed),
             ip_blocked: self.ip_blocked.load(Ordering::Relaxed),
             ua_blocked: self.ua_blocked.load(Ordering::Relaxed),
+            time_blocked: self.time_blocked.load(Ordering::Relaxed),
+            quota_exceeded: self.quota_exceeded.load(Ordering::Relaxed),
+            conn_limited: self.conn_limited.load(Ordering::Relaxed),
             log_skipped: self.log_skipped.load(Ordering::Relaxed),
         }
     }
@@ -174,6 +186,18 @@ impl UserForbiddenStats {
         self.ua_blocked.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub(crate) fn add_time_blocked(&self) {
+        self.time_blocked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_quota_exceeded(&self) {
+        self.quota_exceeded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_conn_limited(&self) {
+        self.conn_limited.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub(crate) fn add_log_skipped(&self) {
         self.log_skipped.fetch_add(1, Ordering::Relaxed);
     }
@@ -26,7 +26,7 @@ pub(crate) use traffic::{
 };
 
 mod site;
-pub(crate) use site::UserSiteStats;
+pub(crate) use site::{UserSiteEmitControl, UserSiteStats};
 
 mod duration;
 pub(crate) use duration::{UserSiteDurationRecorder, UserSiteDurationStats};
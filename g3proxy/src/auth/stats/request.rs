@@ -97,11 +97,21 @@ impl UserRequestStats {
         self.user_type.as_str()
     }
 
+    #[inline]
+    pub(crate) fn raw_user_type(&self) -> UserType {
+        self.user_type
+    }
+
     #[inline]
     pub(crate) fn server(&self) -> &MetricsName {
         &self.server
     }
 
+    #[inline]
+    pub(crate) fn shared_server_extra_tags(&self) -> &Arc<ArcSwapOption<StaticMetricsTags>> {
+        &self.server_extra_tags
+    }
+
     pub(crate) fn server_extra_tags(&self) -> Option<Arc<StaticMetricsTags>> {
         let guard = self.server_extra_tags.load();
         (*guard).as_ref().cloned()
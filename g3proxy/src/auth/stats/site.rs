@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 use ahash::AHashMap;
@@ -23,28 +24,75 @@ use g3_types::metrics::{MetricsName, StaticMetricsTags};
 
 use super::{UserRequestStats, UserTrafficStats, UserUpstreamTrafficStats};
 use crate::auth::UserType;
+use crate::config::auth::UserSiteConfig;
+
+/// control how the site level stats should be emitted, which may be changed at reload
+#[derive(Default)]
+pub(crate) struct UserSiteEmitControl {
+    emit_stats: AtomicBool,
+    rollup_site: AtomicBool,
+    rollup_user_group: AtomicBool,
+}
+
+impl UserSiteEmitControl {
+    fn update(&self, config: &UserSiteConfig) {
+        self.emit_stats.store(config.emit_stats, Ordering::Relaxed);
+        self.rollup_site
+            .store(config.rollup_stats.site, Ordering::Relaxed);
+        self.rollup_user_group
+            .store(config.rollup_stats.user_group, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn emit_stats(&self) -> bool {
+        self.emit_stats.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub(crate) fn rollup_site(&self) -> bool {
+        self.rollup_site.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub(crate) fn rollup_user_group(&self) -> bool {
+        self.rollup_user_group.load(Ordering::Relaxed)
+    }
+}
 
 pub(crate) struct UserSiteStats {
     user: String,
     user_group: MetricsName,
     site_id: MetricsName,
+    emit_control: Arc<UserSiteEmitControl>,
     pub(crate) request: Mutex<AHashMap<String, Arc<UserRequestStats>>>,
     pub(crate) client_io: Mutex<AHashMap<String, Arc<UserTrafficStats>>>,
     pub(crate) remote_io: Mutex<AHashMap<String, Arc<UserUpstreamTrafficStats>>>,
 }
 
 impl UserSiteStats {
-    pub(crate) fn new(user: &str, user_group: &MetricsName, site_id: &MetricsName) -> Self {
+    pub(crate) fn new(user: &str, user_group: &MetricsName, config: &UserSiteConfig) -> Self {
+        let emit_control = Arc::new(UserSiteEmitControl::default());
+        emit_control.update(config);
         UserSiteStats {
             user: user.to_string(),
             user_group: user_group.clone(),
-            site_id: site_id.clone(),
+            site_id: config.id.clone(),
+            emit_control,
             request: Mutex::new(AHashMap::new()),
             client_io: Mutex::new(AHashMap::new()),
             remote_io: Mutex::new(AHashMap::new()),
         }
     }
 
+    pub(crate) fn update_emit_control(&self, config: &UserSiteConfig) {
+        self.emit_control.update(config);
+    }
+
+    #[inline]
+    pub(crate) fn emit_control(&self) -> &Arc<UserSiteEmitControl> {
+        &self.emit_control
+    }
+
     #[inline]
     pub(crate) fn user_group(&self) -> &MetricsName {
         &self.user_group
@@ -81,7 +129,7 @@ impl UserSiteStats {
         drop(map);
 
         if let Some(stats) = new_stats {
-            crate::stat::user_site::push_request_stats(stats, &self.site_id);
+            crate::stat::user_site::push_request_stats(stats, &self.site_id, &self.emit_control);
         }
 
         stats
@@ -113,7 +161,7 @@ impl UserSiteStats {
         drop(map);
 
         if let Some(stats) = new_stats {
-            crate::stat::user_site::push_traffic_stats(stats, &self.site_id);
+            crate::stat::user_site::push_traffic_stats(stats, &self.site_id, &self.emit_control);
         }
 
         stats
@@ -145,7 +193,11 @@ impl UserSiteStats {
         drop(map);
 
         if let Some(stats) = new_stats {
-            crate::stat::user_site::push_upstream_traffic_stats(stats, &self.site_id);
+            crate::stat::user_site::push_upstream_traffic_stats(
+                stats,
+                &self.site_id,
+                &self.emit_control,
+            );
         }
 
         stats
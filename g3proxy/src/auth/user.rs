@@ -31,14 +31,18 @@ use g3_types::acl_set::AclDstHostRuleSet;
 use g3_types::auth::UserAuthError;
 use g3_types::limit::{GaugeSemaphore, GaugeSemaphorePermit};
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
-use g3_types::net::{HttpHeaderMap, ProxyRequestType, UpstreamAddr};
-use g3_types::resolve::{ResolveRedirection, ResolveStrategy};
+use g3_types::net::{
+    HttpHeaderMap, ProxyRequestType, StreamDelayConfig, TcpSockSpeedLimitConfig,
+    UdpSockSpeedLimitConfig, UpstreamAddr,
+};
+use g3_types::resolve::{ResolveRedirection, ResolveRedirectionValue, ResolveStrategy};
 
 use super::{
     UserForbiddenStats, UserRequestStats, UserSite, UserSiteDurationRecorder, UserSiteStats,
-    UserSites, UserTrafficStats, UserType, UserUpstreamTrafficStats,
+    UserSites, UserTrafficQuota, UserTrafficStats, UserType, UserUpstreamTrafficStats,
 };
-use crate::config::auth::{UserAuditConfig, UserConfig};
+use crate::audit::AuditHandle;
+use crate::config::auth::{UserAuditConfig, UserConfig, UserTrafficQuotaConfig};
 
 pub(crate) struct User {
     config: Arc<UserConfig>,
@@ -46,6 +50,8 @@ pub(crate) struct User {
     started: Instant,
     is_expired: AtomicBool,
     is_blocked: Arc<AtomicBool>,
+    /// blocked by ctl command, kept across reload
+    ctl_blocked: Arc<AtomicBool>,
     request_rate_limit: Option<Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>>,
     tcp_conn_rate_limit: Option<Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>>,
     ingress_net_filter: Option<Arc<AclNetworkRule>>,
@@ -57,7 +63,9 @@ pub(crate) struct User {
     io_stats: Arc<Mutex<AHashMap<String, Arc<UserTrafficStats>>>>,
     upstream_io_stats: Arc<Mutex<AHashMap<String, Arc<UserUpstreamTrafficStats>>>>,
     req_alive_sem: GaugeSemaphore,
+    conn_alive_sem: GaugeSemaphore,
     explicit_sites: UserSites,
+    traffic_quota: Option<Arc<UserTrafficQuota>>,
 }
 
 impl User {
@@ -113,12 +121,18 @@ impl User {
 
         let explicit_sites = UserSites::new(config.explicit_sites.values(), config.name(), group);
 
+        let traffic_quota = config
+            .traffic_quota
+            .as_ref()
+            .map(|quota| Arc::new(UserTrafficQuota::new(quota, datetime_now, 0)));
+
         let mut user = User {
             config: Arc::clone(config),
             group: group.clone(),
             started: Instant::now(),
             is_expired,
             is_blocked,
+            ctl_blocked: Arc::new(AtomicBool::new(false)),
             request_rate_limit,
             tcp_conn_rate_limit,
             ingress_net_filter: None,
@@ -130,7 +144,9 @@ impl User {
             io_stats: Arc::new(Mutex::new(AHashMap::new())),
             upstream_io_stats: Arc::new(Mutex::new(AHashMap::new())),
             req_alive_sem: GaugeSemaphore::new(config.request_alive_max),
+            conn_alive_sem: GaugeSemaphore::new(config.connection_alive_max),
             explicit_sites,
+            traffic_quota,
         };
         user.update_ingress_net_filter();
         user.update_dst_host_filter();
@@ -217,12 +233,24 @@ impl User {
             &self.group,
         );
 
+        // always keep the used traffic of the old user
+        let traffic_quota = config.traffic_quota.as_ref().map(|quota| {
+            self.traffic_quota.clone().unwrap_or_else(|| {
+                Arc::new(UserTrafficQuota::new(
+                    quota,
+                    datetime_now,
+                    self.traffic_total_bytes(),
+                ))
+            })
+        });
+
         let mut user = User {
             config: Arc::clone(config),
             group: self.group.clone(),
             started: self.started,
             is_expired,
             is_blocked,
+            ctl_blocked: Arc::clone(&self.ctl_blocked),
             request_rate_limit,
             tcp_conn_rate_limit,
             ingress_net_filter: None,
@@ -234,7 +262,9 @@ impl User {
             io_stats: Arc::clone(&self.io_stats),
             upstream_io_stats: Arc::clone(&self.upstream_io_stats),
             req_alive_sem: self.req_alive_sem.new_updated(config.request_alive_max),
+            conn_alive_sem: self.conn_alive_sem.new_updated(config.connection_alive_max),
             explicit_sites,
+            traffic_quota,
         };
         if self
             .config
@@ -251,12 +281,24 @@ impl User {
             user.dst_host_filter = self.dst_host_filter.clone();
         }
         user.update_resolve_redirection();
+        for stats in user.all_request_stats() {
+            user.explicit_sites.register_stats_as(&stats);
+        }
         user
     }
 
     /// for user blocked check in idle checking
     pub(crate) fn is_blocked(&self) -> bool {
-        self.is_blocked.load(Ordering::Relaxed)
+        self.is_blocked.load(Ordering::Relaxed) || self.ctl_blocked.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn set_ctl_blocked(&self, blocked: bool) {
+        self.ctl_blocked.store(blocked, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn group(&self) -> &MetricsName {
+        &self.group
     }
 
     #[inline]
@@ -284,6 +326,13 @@ impl User {
             forbid_stats.add_auth_failed();
             return Err(UserAuthError::TokenNotMatch);
         }
+        self.check_auth_status(forbid_stats)
+    }
+
+    fn check_auth_status(
+        &self,
+        forbid_stats: &Arc<UserForbiddenStats>,
+    ) -> Result<(), UserAuthError> {
         if self.is_expired() {
             forbid_stats.add_user_expired();
             return Err(UserAuthError::ExpiredUser);
@@ -292,6 +341,10 @@ impl User {
             forbid_stats.add_user_blocked();
             return Err(UserAuthError::BlockedUser(duration));
         }
+        if self.ctl_blocked.load(Ordering::Relaxed) {
+            forbid_stats.add_user_blocked();
+            return Err(UserAuthError::BlockedUser(Duration::ZERO));
+        }
         Ok(())
     }
 
@@ -329,17 +382,30 @@ impl User {
         server: &MetricsName,
         server_extra_tags: &Arc<ArcSwapOption<StaticMetricsTags>>,
     ) -> Arc<UserRequestStats> {
+        let mut is_new = false;
+
         let mut map = self.req_stats.lock().unwrap();
-        let stats = map.entry(server.to_string()).or_insert_with(|| {
-            Arc::new(UserRequestStats::new(
-                &self.group,
-                self.config.name(),
-                user_type,
-                server,
-                server_extra_tags,
-            ))
-        });
-        Arc::clone(stats)
+        let stats = map
+            .entry(server.to_string())
+            .or_insert_with(|| {
+                is_new = true;
+                Arc::new(UserRequestStats::new(
+                    &self.group,
+                    self.config.name(),
+                    user_type,
+                    server,
+                    server_extra_tags,
+                ))
+            })
+            .clone();
+        drop(map);
+
+        if is_new {
+            self.explicit_sites
+                .register_stats(user_type, server, server_extra_tags);
+        }
+
+        stats
     }
 
     pub(crate) fn all_request_stats(&self) -> Vec<Arc<UserRequestStats>> {
@@ -379,6 +445,41 @@ impl User {
         all_stats
     }
 
+    /// (in_bytes, out_bytes) summed over all servers
+    pub(crate) fn traffic_io_bytes(&self) -> (u64, u64) {
+        let map = self.io_stats.lock().unwrap();
+        map.values()
+            .map(|stats| stats.io.io_bytes())
+            .fold((0u64, 0u64), |(i, o), (in_bytes, out_bytes)| {
+                (i.wrapping_add(in_bytes), o.wrapping_add(out_bytes))
+            })
+    }
+
+    pub(super) fn traffic_total_bytes(&self) -> u64 {
+        let map = self.io_stats.lock().unwrap();
+        map.values()
+            .map(|stats| stats.io.total_bytes())
+            .fold(0u64, u64::wrapping_add)
+    }
+
+    pub(super) fn traffic_quota(
+        &self,
+    ) -> Option<(&UserTrafficQuotaConfig, &Arc<UserTrafficQuota>)> {
+        let config = self.config.traffic_quota.as_ref()?;
+        let quota = self.traffic_quota.as_ref()?;
+        Some((config, quota))
+    }
+
+    /// get the throttle config if the traffic quota is exceeded
+    fn traffic_quota_throttle(&self) -> Option<&UserTrafficQuotaConfig> {
+        let (config, quota) = self.traffic_quota()?;
+        if config.throttle() && quota.is_exceeded() {
+            Some(config)
+        } else {
+            None
+        }
+    }
+
     fn fetch_upstream_traffic_stats(
         &self,
         user_type: UserType,
@@ -439,6 +540,26 @@ impl User {
         Ok(())
     }
 
+    fn check_time_window(&self, forbid_stats: &Arc<UserForbiddenStats>) -> Result<(), ()> {
+        if let Some(time_window) = &self.config.time_window {
+            if !time_window.is_allowed(&Utc::now()) {
+                forbid_stats.add_time_blocked();
+                return Err(());
+            }
+        }
+        Ok(())
+    }
+
+    fn check_traffic_quota(&self, forbid_stats: &Arc<UserForbiddenStats>) -> Result<(), ()> {
+        if let Some((config, quota)) = self.traffic_quota() {
+            if !config.throttle() && quota.is_exceeded() {
+                forbid_stats.add_quota_exceeded();
+                return Err(());
+            }
+        }
+        Ok(())
+    }
+
     fn acquire_request_semaphore(
         &self,
         forbid_stats: &Arc<UserForbiddenStats>,
@@ -448,6 +569,15 @@ impl User {
         })
     }
 
+    fn acquire_connection_semaphore(
+        &self,
+        forbid_stats: &Arc<UserForbiddenStats>,
+    ) -> Result<GaugeSemaphorePermit, ()> {
+        self.conn_alive_sem.try_acquire().map_err(|_| {
+            forbid_stats.add_conn_limited();
+        })
+    }
+
     fn check_proxy_request(
         &self,
         request: ProxyRequestType,
@@ -597,7 +727,7 @@ impl UserContext {
         ups: &UpstreamAddr,
     ) {
         if let Some(user_site) = self.user.explicit_sites.fetch_site(ups) {
-            if user_site.emit_stats() {
+            if user_site.need_stats() {
                 let user_site_stats = user_site.stats().clone();
                 self.site_req_stats = Some(user_site_stats.fetch_request_stats(
                     self.user_type,
@@ -636,6 +766,21 @@ impl UserContext {
         &self.user.config
     }
 
+    /// get the audit handle for this user, the server one will be used if no auditor is set
+    pub(crate) fn audit_handle(
+        &self,
+        server_handle: Option<&Arc<AuditHandle>>,
+    ) -> Option<Arc<AuditHandle>> {
+        let audit = &self.user.config.audit;
+        if !audit.enable {
+            return None;
+        }
+        match &audit.auditor {
+            Some(name) => crate::audit::get_or_insert_default(name).user_handle(),
+            None => server_handle.cloned(),
+        }
+    }
+
     pub(crate) fn resolve_strategy(&self) -> Option<ResolveStrategy> {
         self.user_site
             .as_ref()
@@ -643,6 +788,24 @@ impl UserContext {
             .or(self.user.config.resolve_strategy)
     }
 
+    pub(crate) fn site_resolve_static(&self) -> Option<ResolveRedirectionValue> {
+        self.user_site
+            .as_ref()
+            .and_then(|s| s.resolve_static())
+            .cloned()
+    }
+
+    pub(crate) fn site_resolver(&self) -> Option<&MetricsName> {
+        self.user_site.as_ref().and_then(|s| s.resolver())
+    }
+
+    pub(crate) fn tcp_stream_delay(&self) -> Option<StreamDelayConfig> {
+        self.user_site
+            .as_ref()
+            .and_then(|s| s.tcp_stream_delay())
+            .or(self.user.config.tcp_stream_delay)
+    }
+
     #[inline]
     pub(crate) fn forbidden_stats(&self) -> &Arc<UserForbiddenStats> {
         &self.forbid_stats
@@ -722,6 +885,25 @@ impl UserContext {
         self.user.check_password(password, &self.forbid_stats)
     }
 
+    /// check the user status after the password has been verified by an external backend or cache
+    pub(crate) fn check_external_auth(&self, verified: bool) -> Result<(), UserAuthError> {
+        if !verified {
+            self.forbid_stats.add_auth_failed();
+            return Err(UserAuthError::TokenNotMatch);
+        }
+        self.check_auth_status()
+    }
+
+    #[inline]
+    pub(crate) fn check_auth_status(&self) -> Result<(), UserAuthError> {
+        self.user.check_auth_status(&self.forbid_stats)
+    }
+
+    #[inline]
+    pub(crate) fn user_type(&self) -> UserType {
+        self.user_type
+    }
+
     #[inline]
     pub(crate) fn skip_log(&self) -> bool {
         self.user.skip_log(&self.forbid_stats)
@@ -733,11 +915,42 @@ impl UserContext {
             .check_rate_limit(self.reused_client_connection, &self.forbid_stats)
     }
 
+    #[inline]
+    pub(crate) fn check_time_window(&self) -> Result<(), ()> {
+        self.user.check_time_window(&self.forbid_stats)
+    }
+
+    #[inline]
+    pub(crate) fn check_traffic_quota(&self) -> Result<(), ()> {
+        self.user.check_traffic_quota(&self.forbid_stats)
+    }
+
+    pub(crate) fn tcp_sock_speed_limit(&self) -> TcpSockSpeedLimitConfig {
+        let limit = self.user.config.tcp_sock_speed_limit;
+        match self.user.traffic_quota_throttle() {
+            Some(quota) => limit.shrink_as_smaller(&quota.throttle_tcp_sock_speed_limit),
+            None => limit,
+        }
+    }
+
+    pub(crate) fn udp_sock_speed_limit(&self) -> UdpSockSpeedLimitConfig {
+        let limit = self.user.config.udp_sock_speed_limit;
+        match self.user.traffic_quota_throttle() {
+            Some(quota) => limit.shrink_as_smaller(&quota.throttle_udp_sock_speed_limit),
+            None => limit,
+        }
+    }
+
     #[inline]
     pub(crate) fn acquire_request_semaphore(&self) -> Result<GaugeSemaphorePermit, ()> {
         self.user.acquire_request_semaphore(&self.forbid_stats)
     }
 
+    #[inline]
+    pub(crate) fn acquire_connection_semaphore(&self) -> Result<GaugeSemaphorePermit, ()> {
+        self.user.acquire_connection_semaphore(&self.forbid_stats)
+    }
+
     #[inline]
     pub(crate) fn check_proxy_request(&self, request: ProxyRequestType) -> AclAction {
         self.user.check_proxy_request(request, &self.forbid_stats)
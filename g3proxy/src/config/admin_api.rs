@@ -0,0 +1,98 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+static GLOBAL_ADMIN_API_CONFIG: OnceLock<AdminApiConfig> = OnceLock::new();
+
+pub(crate) struct AdminApiConfig {
+    pub(crate) listen: SocketAddr,
+    pub(crate) token: String,
+    pub(crate) req_header_max_size: usize,
+    pub(crate) recv_timeout: Duration,
+}
+
+impl Default for AdminApiConfig {
+    fn default() -> Self {
+        AdminApiConfig {
+            listen: SocketAddr::from(([127, 0, 0, 1], 2998)),
+            token: String::new(),
+            req_header_max_size: 8192,
+            recv_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl AdminApiConfig {
+    fn parse(map: &yaml_rust::yaml::Hash) -> anyhow::Result<Self> {
+        let mut config = AdminApiConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| config.set(k, v))?;
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "listen" => {
+                self.listen = g3_yaml::value::as_env_sockaddr(v)
+                    .context(format!("invalid socket address value for key {k}"))?;
+                Ok(())
+            }
+            "token" | "auth_token" => {
+                self.token = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                Ok(())
+            }
+            "req_header_max_size" => {
+                self.req_header_max_size = g3_yaml::humanize::as_usize(v)
+                    .context(format!("invalid humanize usize value for key {k}"))?;
+                Ok(())
+            }
+            "recv_timeout" => {
+                self.recv_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.token.is_empty() {
+            return Err(anyhow!("no auth token set"));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn load(v: &Yaml) -> anyhow::Result<()> {
+    let config = match v {
+        Yaml::Hash(map) => AdminApiConfig::parse(map)?,
+        _ => return Err(anyhow!("invalid value type")),
+    };
+    GLOBAL_ADMIN_API_CONFIG
+        .set(config)
+        .map_err(|_| anyhow!("admin api config has already been set"))
+}
+
+pub(crate) fn get_global_config() -> Option<&'static AdminApiConfig> {
+    GLOBAL_ADMIN_API_CONFIG.get()
+}
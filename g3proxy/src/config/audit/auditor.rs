@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use std::collections::BTreeSet;
 use std::sync::Arc;
 
 use anyhow::{anyhow, Context};
@@ -21,15 +22,24 @@ use rand::distributions::Bernoulli;
 use yaml_rust::{yaml, Yaml};
 
 use g3_dpi::{
-    H1InterceptionConfig, H2InterceptionConfig, ProtocolInspectionConfig, ProtocolPortMap,
+    DnsInterceptionConfig, FtpInterceptionConfig, H1InterceptionConfig, H2InterceptionConfig,
+    ImapInterceptionConfig, Pop3InterceptionConfig, ProtocolInspectionConfig, ProtocolPortMap,
+    SmtpInterceptionConfig, WebSocketInterceptionConfig,
 };
-use g3_icap_client::IcapServiceConfig;
+use g3_icap_client::IcapServiceGroupConfig;
 use g3_tls_cert::agent::CertAgentConfig;
 use g3_types::metrics::MetricsName;
 use g3_types::net::OpensslInterceptionClientConfigBuilder;
 use g3_udpdump::StreamDumpConfig;
 use g3_yaml::YamlDocPosition;
 
+use super::{
+    ClamavServiceConfig, ContentFilterConfig, HarExportConfig, HttpHeaderRule,
+    HttpHeaderRuleDirection, IcapBodyLimit, ProtocolPolicyConfig, RespmodBypassRule,
+    RespmodDecompressConfig, TlsEchPolicy, TlsInterceptionBypassConfig, TlsUpstreamCertErrorAction,
+    TlsUpstreamCertErrorPolicy, TrafficMirrorConfig,
+};
+
 #[derive(Clone)]
 pub(crate) struct AuditorConfig {
     name: MetricsName,
@@ -37,14 +47,38 @@ pub(crate) struct AuditorConfig {
     pub(crate) protocol_inspection: ProtocolInspectionConfig,
     pub(crate) server_tcp_portmap: ProtocolPortMap,
     pub(crate) client_tcp_portmap: ProtocolPortMap,
+    pub(crate) protocol_policy: Option<ProtocolPolicyConfig>,
     pub(crate) tls_cert_agent: Option<CertAgentConfig>,
+    pub(crate) tls_untrusted_cert_agent: Option<CertAgentConfig>,
     pub(crate) tls_interception_client: OpensslInterceptionClientConfigBuilder,
     pub(crate) tls_stream_dump: Option<StreamDumpConfig>,
+    pub(crate) tls_block_ja3: BTreeSet<String>,
+    pub(crate) tls_block_ja4: BTreeSet<String>,
+    pub(crate) tls_interception_bypass: Option<Arc<TlsInterceptionBypassConfig>>,
+    pub(crate) tls_upstream_cert_error: TlsUpstreamCertErrorPolicy,
+    pub(crate) tls_intercept_alpn: BTreeSet<String>,
+    pub(crate) tls_ech_policy: TlsEchPolicy,
     pub(crate) log_uri_max_chars: usize,
     pub(crate) h1_interception: H1InterceptionConfig,
     pub(crate) h2_interception: H2InterceptionConfig,
-    pub(crate) icap_reqmod_service: Option<Arc<IcapServiceConfig>>,
-    pub(crate) icap_respmod_service: Option<Arc<IcapServiceConfig>>,
+    pub(crate) websocket_interception: WebSocketInterceptionConfig,
+    pub(crate) smtp_interception: SmtpInterceptionConfig,
+    pub(crate) imap_interception: ImapInterceptionConfig,
+    pub(crate) pop3_interception: Pop3InterceptionConfig,
+    pub(crate) ftp_interception: FtpInterceptionConfig,
+    pub(crate) dns_interception: DnsInterceptionConfig,
+    pub(crate) icap_reqmod_service: Option<Arc<IcapServiceGroupConfig>>,
+    pub(crate) icap_respmod_service: Option<Arc<IcapServiceGroupConfig>>,
+    pub(crate) icap_respmod_bypass: Vec<RespmodBypassRule>,
+    pub(crate) icap_reqmod_body_limit: Option<IcapBodyLimit>,
+    pub(crate) icap_respmod_body_limit: Option<IcapBodyLimit>,
+    pub(crate) icap_respmod_decompress: Option<RespmodDecompressConfig>,
+    pub(crate) content_filter: Option<Arc<ContentFilterConfig>>,
+    pub(crate) http_request_header_rules: Vec<HttpHeaderRule>,
+    pub(crate) http_response_header_rules: Vec<HttpHeaderRule>,
+    pub(crate) clamav_service: Option<Arc<ClamavServiceConfig>>,
+    pub(crate) har_export: Option<HarExportConfig>,
+    pub(crate) traffic_mirror: Option<TrafficMirrorConfig>,
     pub(crate) application_audit_ratio: Bernoulli,
 }
 
@@ -64,14 +98,38 @@ impl AuditorConfig {
             protocol_inspection: Default::default(),
             server_tcp_portmap: ProtocolPortMap::tcp_server(),
             client_tcp_portmap: ProtocolPortMap::tcp_client(),
+            protocol_policy: None,
             tls_cert_agent: None,
+            tls_untrusted_cert_agent: None,
             tls_interception_client: Default::default(),
             tls_stream_dump: None,
+            tls_block_ja3: BTreeSet::new(),
+            tls_block_ja4: BTreeSet::new(),
+            tls_interception_bypass: None,
+            tls_upstream_cert_error: Default::default(),
+            tls_intercept_alpn: BTreeSet::new(),
+            tls_ech_policy: TlsEchPolicy::default(),
             log_uri_max_chars: 1024,
             h1_interception: Default::default(),
             h2_interception: Default::default(),
+            websocket_interception: Default::default(),
+            smtp_interception: Default::default(),
+            imap_interception: Default::default(),
+            pop3_interception: Default::default(),
+            ftp_interception: Default::default(),
+            dns_interception: Default::default(),
             icap_reqmod_service: None,
             icap_respmod_service: None,
+            icap_respmod_bypass: Vec::new(),
+            icap_reqmod_body_limit: None,
+            icap_respmod_body_limit: None,
+            icap_respmod_decompress: None,
+            content_filter: None,
+            http_request_header_rules: Vec::new(),
+            http_response_header_rules: Vec::new(),
+            clamav_service: None,
+            har_export: None,
+            traffic_mirror: None,
             application_audit_ratio: Bernoulli::new(1.0).unwrap(),
         }
     }
@@ -94,6 +152,18 @@ impl AuditorConfig {
         if self.name.is_empty() {
             return Err(anyhow!("name is not set"));
         }
+        if self.tls_upstream_cert_error.action == TlsUpstreamCertErrorAction::ForgeUntrusted
+            && self.tls_untrusted_cert_agent.is_none()
+        {
+            return Err(anyhow!(
+                "untrusted tls cert generator is required to forge untrusted certificates"
+            ));
+        }
+        if self.clamav_service.is_some() && self.icap_respmod_service.is_some() {
+            return Err(anyhow!(
+                "clamav service and icap respmod service can not be set at the same time"
+            ));
+        }
 
         Ok(())
     }
@@ -120,6 +190,12 @@ impl AuditorConfig {
                 g3_yaml::value::update_protocol_portmap(&mut self.client_tcp_portmap, v)
                     .context(format!("invalid protocol portmap value for key {k}"))
             }
+            "protocol_policy" => {
+                let policy = ProtocolPolicyConfig::parse(v)
+                    .context(format!("invalid protocol policy config value for key {k}"))?;
+                self.protocol_policy = Some(policy);
+                Ok(())
+            }
             "tls_cert_agent" | "tls_cert_generator" => {
                 let agent = g3_yaml::value::as_tls_cert_agent_config(v).context(format!(
                     "invalid tls cert generator config value for key {k}"
@@ -127,6 +203,13 @@ impl AuditorConfig {
                 self.tls_cert_agent = Some(agent);
                 Ok(())
             }
+            "tls_untrusted_cert_agent" | "tls_untrusted_cert_generator" => {
+                let agent = g3_yaml::value::as_tls_cert_agent_config(v).context(format!(
+                    "invalid tls cert generator config value for key {k}"
+                ))?;
+                self.tls_untrusted_cert_agent = Some(agent);
+                Ok(())
+            }
             "tls_interception_client" => {
                 let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
                 let builder =
@@ -143,6 +226,51 @@ impl AuditorConfig {
                 self.tls_stream_dump = Some(dump);
                 Ok(())
             }
+            "tls_block_ja3" => {
+                for s in g3_yaml::value::as_list(v, g3_yaml::value::as_string)
+                    .context(format!("invalid string list value for key {k}"))?
+                {
+                    self.tls_block_ja3.insert(s.to_ascii_lowercase());
+                }
+                Ok(())
+            }
+            "tls_block_ja4" => {
+                for s in g3_yaml::value::as_list(v, g3_yaml::value::as_string)
+                    .context(format!("invalid string list value for key {k}"))?
+                {
+                    self.tls_block_ja4.insert(s.to_ascii_lowercase());
+                }
+                Ok(())
+            }
+            "tls_interception_bypass" | "tls_bypass" => {
+                let bypass = TlsInterceptionBypassConfig::parse(v).context(format!(
+                    "invalid tls interception bypass config value for key {k}"
+                ))?;
+                self.tls_interception_bypass = Some(Arc::new(bypass));
+                Ok(())
+            }
+            "tls_upstream_cert_error" | "tls_upstream_cert_error_policy" => {
+                self.tls_upstream_cert_error = TlsUpstreamCertErrorPolicy::parse(v).context(
+                    format!("invalid tls upstream cert error policy value for key {k}"),
+                )?;
+                Ok(())
+            }
+            "tls_intercept_alpn" | "tls_interception_alpn" => {
+                for s in g3_yaml::value::as_list(v, g3_yaml::value::as_string)
+                    .context(format!("invalid string list value for key {k}"))?
+                {
+                    if s.is_empty() || s.len() > 255 {
+                        return Err(anyhow!("invalid alpn protocol {s} for key {k}"));
+                    }
+                    self.tls_intercept_alpn.insert(s);
+                }
+                Ok(())
+            }
+            "tls_ech_policy" => {
+                self.tls_ech_policy = TlsEchPolicy::parse(v)
+                    .context(format!("invalid tls ech policy value for key {k}"))?;
+                Ok(())
+            }
             "log_uri_max_chars" | "uri_log_max_chars" => {
                 self.log_uri_max_chars = g3_yaml::value::as_usize(v)
                     .context(format!("invalid usize value for key {k}"))?;
@@ -158,20 +286,125 @@ impl AuditorConfig {
                     .context(format!("invalid h1 interception value for key {k}"))?;
                 Ok(())
             }
+            "websocket_interception" => {
+                self.websocket_interception =
+                    g3_yaml::value::as_websocket_interception_config(v)
+                        .context(format!("invalid websocket interception value for key {k}"))?;
+                Ok(())
+            }
+            "smtp_interception" => {
+                self.smtp_interception = g3_yaml::value::as_smtp_interception_config(v)
+                    .context(format!("invalid smtp interception value for key {k}"))?;
+                Ok(())
+            }
+            "imap_interception" => {
+                self.imap_interception = g3_yaml::value::as_imap_interception_config(v)
+                    .context(format!("invalid imap interception value for key {k}"))?;
+                Ok(())
+            }
+            "pop3_interception" => {
+                self.pop3_interception = g3_yaml::value::as_pop3_interception_config(v)
+                    .context(format!("invalid pop3 interception value for key {k}"))?;
+                Ok(())
+            }
+            "ftp_interception" => {
+                self.ftp_interception = g3_yaml::value::as_ftp_interception_config(v)
+                    .context(format!("invalid ftp interception value for key {k}"))?;
+                Ok(())
+            }
+            "dns_interception" => {
+                self.dns_interception = g3_yaml::value::as_dns_interception_config(v)
+                    .context(format!("invalid dns interception value for key {k}"))?;
+                Ok(())
+            }
             "icap_reqmod_service" => {
-                let service = g3_yaml::value::as_icap_reqmod_service_config(v).context(format!(
-                    "invalid icap reqmod service config value for key {k}"
-                ))?;
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let service =
+                    g3_yaml::value::as_icap_reqmod_service_group_config(v, Some(lookup_dir))
+                        .context(format!(
+                            "invalid icap reqmod service config value for key {k}"
+                        ))?;
                 self.icap_reqmod_service = Some(Arc::new(service));
                 Ok(())
             }
             "icap_respmod_service" => {
-                let service = g3_yaml::value::as_icap_respmod_service_config(v).context(
-                    format!("invalid icap respmod service config value for key {k}"),
-                )?;
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let service =
+                    g3_yaml::value::as_icap_respmod_service_group_config(v, Some(lookup_dir))
+                        .context(format!(
+                            "invalid icap respmod service config value for key {k}"
+                        ))?;
                 self.icap_respmod_service = Some(Arc::new(service));
                 Ok(())
             }
+            "icap_respmod_bypass" => {
+                self.icap_respmod_bypass = g3_yaml::value::as_list(v, RespmodBypassRule::parse)
+                    .context(format!(
+                        "invalid respmod bypass rule list value for key {k}"
+                    ))?;
+                Ok(())
+            }
+            "icap_reqmod_body_limit" => {
+                let limit = IcapBodyLimit::parse(v)
+                    .context(format!("invalid icap body limit value for key {k}"))?;
+                self.icap_reqmod_body_limit = Some(limit);
+                Ok(())
+            }
+            "icap_respmod_body_limit" => {
+                let limit = IcapBodyLimit::parse(v)
+                    .context(format!("invalid icap body limit value for key {k}"))?;
+                self.icap_respmod_body_limit = Some(limit);
+                Ok(())
+            }
+            "icap_respmod_decompress" => {
+                self.icap_respmod_decompress = RespmodDecompressConfig::parse(v).context(
+                    format!("invalid icap respmod decompress config value for key {k}"),
+                )?;
+                Ok(())
+            }
+            "content_filter" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let filter = ContentFilterConfig::parse(v, lookup_dir)
+                    .context(format!("invalid content filter config value for key {k}"))?;
+                self.content_filter = Some(Arc::new(filter));
+                Ok(())
+            }
+            "http_header_rules" | "http_header_rule" => {
+                let rules = g3_yaml::value::as_list(v, HttpHeaderRule::parse)
+                    .context(format!("invalid http header rule list value for key {k}"))?;
+                self.http_request_header_rules.clear();
+                self.http_response_header_rules.clear();
+                for rule in rules {
+                    match rule.direction() {
+                        HttpHeaderRuleDirection::Request => {
+                            self.http_request_header_rules.push(rule)
+                        }
+                        HttpHeaderRuleDirection::Response => {
+                            self.http_response_header_rules.push(rule)
+                        }
+                    }
+                }
+                Ok(())
+            }
+            "clamav_service" | "clamd_service" => {
+                let service = ClamavServiceConfig::parse(v)
+                    .context(format!("invalid clamav service config value for key {k}"))?;
+                self.clamav_service = Some(Arc::new(service));
+                Ok(())
+            }
+            "har_export" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let export = HarExportConfig::parse(v, lookup_dir)
+                    .context(format!("invalid har export config value for key {k}"))?;
+                self.har_export = Some(export);
+                Ok(())
+            }
+            "traffic_mirror" => {
+                let mirror = TrafficMirrorConfig::parse(v)
+                    .context(format!("invalid traffic mirror config value for key {k}"))?;
+                self.traffic_mirror = Some(mirror);
+                Ok(())
+            }
             "application_audit_ratio" => {
                 self.application_audit_ratio = g3_yaml::value::as_random_ratio(v)
                     .context(format!("invalid random ratio value for key {k}"))?;
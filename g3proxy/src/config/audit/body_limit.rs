@@ -0,0 +1,83 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+#[derive(Clone, Copy)]
+pub(crate) struct IcapBodyLimit {
+    max_size: u64,
+    block: bool,
+}
+
+impl IcapBodyLimit {
+    pub(crate) fn parse(v: &Yaml) -> anyhow::Result<Self> {
+        match v {
+            Yaml::Hash(map) => {
+                let mut limit = IcapBodyLimit {
+                    max_size: 0,
+                    block: false,
+                };
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "max_size" => {
+                        limit.max_size = g3_yaml::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?
+                            as u64;
+                        Ok(())
+                    }
+                    "action" => {
+                        let action = g3_yaml::value::as_string(v)
+                            .context(format!("invalid string value for key {k}"))?;
+                        limit.block = match action.to_lowercase().as_str() {
+                            "bypass" | "skip" => false,
+                            "block" | "deny" => true,
+                            _ => return Err(anyhow!("invalid action {action} for key {k}")),
+                        };
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+                if limit.max_size == 0 {
+                    return Err(anyhow!("max_size is not set"));
+                }
+                Ok(limit)
+            }
+            _ => {
+                let max_size = g3_yaml::humanize::as_usize(v)
+                    .context("invalid humanize usize value for body limit")?;
+                Ok(IcapBodyLimit {
+                    max_size: max_size as u64,
+                    block: false,
+                })
+            }
+        }
+    }
+
+    #[inline]
+    fn exceeded(&self, body_size: Option<u64>) -> bool {
+        body_size.map(|size| size > self.max_size).unwrap_or(false)
+    }
+
+    /// Check if the adaptation should be skipped for the body size
+    pub(crate) fn bypass(&self, body_size: Option<u64>) -> bool {
+        !self.block && self.exceeded(body_size)
+    }
+
+    /// Check if the task should be blocked for the body size
+    pub(crate) fn block(&self, body_size: Option<u64>) -> bool {
+        self.block && self.exceeded(body_size)
+    }
+}
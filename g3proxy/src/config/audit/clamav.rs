@@ -0,0 +1,115 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+const DEFAULT_MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(4);
+const DEFAULT_SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum ClamavPeer {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ClamavServiceConfig {
+    pub(crate) peer: ClamavPeer,
+    pub(crate) max_body_size: usize,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) scan_timeout: Duration,
+    pub(crate) bypass: bool,
+}
+
+impl ClamavServiceConfig {
+    fn new(peer: ClamavPeer) -> Self {
+        ClamavServiceConfig {
+            peer,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            scan_timeout: DEFAULT_SCAN_TIMEOUT,
+            bypass: false,
+        }
+    }
+
+    pub(crate) fn parse(v: &Yaml) -> anyhow::Result<Self> {
+        match v {
+            Yaml::Hash(map) => {
+                let mut peer = None;
+                let mut config = ClamavServiceConfig::new(ClamavPeer::Unix(PathBuf::new()));
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "tcp" | "tcp_peer" => {
+                        let addr = g3_yaml::value::as_env_sockaddr(v)
+                            .context(format!("invalid socket address value for key {k}"))?;
+                        peer = Some(ClamavPeer::Tcp(addr));
+                        Ok(())
+                    }
+                    "unix" | "unix_path" => {
+                        let path = g3_yaml::value::as_absolute_path(v)
+                            .context(format!("invalid absolute path value for key {k}"))?;
+                        peer = Some(ClamavPeer::Unix(path));
+                        Ok(())
+                    }
+                    "max_body_size" => {
+                        config.max_body_size = g3_yaml::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?;
+                        Ok(())
+                    }
+                    "connect_timeout" => {
+                        config.connect_timeout = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    "scan_timeout" => {
+                        config.scan_timeout = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    "bypass" => {
+                        config.bypass = g3_yaml::value::as_bool(v)?;
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+                let Some(peer) = peer else {
+                    return Err(anyhow!("no clamd address set"));
+                };
+                config.peer = peer;
+                Ok(config)
+            }
+            Yaml::String(s) => {
+                let peer = if s.starts_with('/') {
+                    ClamavPeer::Unix(PathBuf::from(s))
+                } else {
+                    let addr = SocketAddr::from_str(s)
+                        .map_err(|e| anyhow!("invalid socket address {s}: {e}"))?;
+                    ClamavPeer::Tcp(addr)
+                };
+                Ok(ClamavServiceConfig::new(peer))
+            }
+            _ => Err(anyhow!(
+                "yaml value type for 'clamav service config' should be 'map' or 'str'"
+            )),
+        }
+    }
+}
@@ -0,0 +1,213 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeSet;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use regex::{Regex, RegexSet};
+use yaml_rust::{yaml, Yaml};
+
+const DEFAULT_BODY_INSPECT_MAX_SIZE: usize = 1 << 20; // 1MB
+
+#[derive(Clone, Default)]
+pub(crate) struct UrlCategoryConfig {
+    pub(crate) name: String,
+    pub(crate) exact_hosts: BTreeSet<String>,
+    pub(crate) suffix_hosts: BTreeSet<String>,
+    url_regex: Vec<String>,
+    pub(crate) url_regex_set: Option<RegexSet>,
+}
+
+impl UrlCategoryConfig {
+    fn parse(map: &yaml::Hash, lookup_dir: &Path) -> anyhow::Result<Self> {
+        let mut config = UrlCategoryConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| config.set(k, v, lookup_dir))?;
+        if config.name.is_empty() {
+            return Err(anyhow!("name is not set"));
+        }
+        if !config.url_regex.is_empty() {
+            let set = RegexSet::new(&config.url_regex)
+                .map_err(|e| anyhow!("failed to build url regex set: {e}"))?;
+            config.url_regex_set = Some(set);
+        }
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml, lookup_dir: &Path) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "name" => {
+                self.name = g3_yaml::value::as_string(v)?;
+                Ok(())
+            }
+            "exact" => {
+                for host in g3_yaml::value::as_list(v, g3_yaml::value::as_domain)
+                    .context(format!("invalid domain list value for key {k}"))?
+                {
+                    self.exact_hosts.insert(host);
+                }
+                Ok(())
+            }
+            "exact_file" => {
+                for line in load_list_file(v, lookup_dir)
+                    .context(format!("failed to load list file for key {k}"))?
+                {
+                    self.exact_hosts.insert(line.to_lowercase());
+                }
+                Ok(())
+            }
+            "suffix" => {
+                for host in g3_yaml::value::as_list(v, g3_yaml::value::as_domain)
+                    .context(format!("invalid domain list value for key {k}"))?
+                {
+                    self.suffix_hosts
+                        .insert(host.trim_start_matches('.').to_string());
+                }
+                Ok(())
+            }
+            "suffix_file" => {
+                for line in load_list_file(v, lookup_dir)
+                    .context(format!("failed to load list file for key {k}"))?
+                {
+                    self.suffix_hosts
+                        .insert(line.trim_start_matches('.').to_lowercase());
+                }
+                Ok(())
+            }
+            "regex" => {
+                for s in g3_yaml::value::as_list(v, g3_yaml::value::as_string)
+                    .context(format!("invalid string list value for key {k}"))?
+                {
+                    self.add_url_regex(s)
+                        .context(format!("invalid value for key {k}"))?;
+                }
+                Ok(())
+            }
+            "regex_file" => {
+                for line in load_list_file(v, lookup_dir)
+                    .context(format!("failed to load list file for key {k}"))?
+                {
+                    self.add_url_regex(line)
+                        .context(format!("invalid line in file for key {k}"))?;
+                }
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn add_url_regex(&mut self, s: String) -> anyhow::Result<()> {
+        Regex::new(&s).map_err(|e| anyhow!("invalid regex {s}: {e}"))?;
+        self.url_regex.push(s);
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ContentFilterConfig {
+    pub(crate) url_categories: Vec<UrlCategoryConfig>,
+    pub(crate) blocked_content_types: BTreeSet<String>,
+    pub(crate) blocked_body_keywords: BTreeSet<String>,
+    pub(crate) body_inspect_max_size: usize,
+}
+
+impl Default for ContentFilterConfig {
+    fn default() -> Self {
+        ContentFilterConfig {
+            url_categories: Vec::new(),
+            blocked_content_types: BTreeSet::new(),
+            blocked_body_keywords: BTreeSet::new(),
+            body_inspect_max_size: DEFAULT_BODY_INSPECT_MAX_SIZE,
+        }
+    }
+}
+
+impl ContentFilterConfig {
+    pub(crate) fn parse(v: &Yaml, lookup_dir: &Path) -> anyhow::Result<Self> {
+        if let Yaml::Hash(map) = v {
+            let mut config = ContentFilterConfig::default();
+            g3_yaml::foreach_kv(map, |k, v| config.set(k, v, lookup_dir))?;
+            Ok(config)
+        } else {
+            Err(anyhow!(
+                "yaml value type for 'content filter config' should be 'map'"
+            ))
+        }
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml, lookup_dir: &Path) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "url_categories" | "url_category" => {
+                let categories = g3_yaml::value::as_list(v, |v| {
+                    if let Yaml::Hash(map) = v {
+                        UrlCategoryConfig::parse(map, lookup_dir)
+                    } else {
+                        Err(anyhow!(
+                            "yaml value type for 'url category' should be 'map'"
+                        ))
+                    }
+                })
+                .context(format!("invalid url category list value for key {k}"))?;
+                self.url_categories.extend(categories);
+                Ok(())
+            }
+            "blocked_content_types" | "blocked_content_type" => {
+                for s in g3_yaml::value::as_list(v, g3_yaml::value::as_string)
+                    .context(format!("invalid string list value for key {k}"))?
+                {
+                    if !s.contains('/') {
+                        return Err(anyhow!("invalid content type {s} for key {k}"));
+                    }
+                    self.blocked_content_types.insert(s.to_lowercase());
+                }
+                Ok(())
+            }
+            "blocked_body_keywords" | "blocked_body_keyword" => {
+                for s in g3_yaml::value::as_list(v, g3_yaml::value::as_string)
+                    .context(format!("invalid string list value for key {k}"))?
+                {
+                    if s.is_empty() {
+                        return Err(anyhow!("empty body keyword is not allowed for key {k}"));
+                    }
+                    self.blocked_body_keywords.insert(s);
+                }
+                Ok(())
+            }
+            "body_inspect_max_size" => {
+                self.body_inspect_max_size = g3_yaml::humanize::as_usize(v)
+                    .context(format!("invalid humanize usize value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+}
+
+fn load_list_file(v: &Yaml, lookup_dir: &Path) -> anyhow::Result<Vec<String>> {
+    let (file, path) = g3_yaml::value::as_file(v, Some(lookup_dir))?;
+    let mut list = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line =
+            line.map_err(|e| anyhow!("failed to read line {i} of {}: {e}", path.display()))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        list.push(line.to_string());
+    }
+    Ok(list)
+}
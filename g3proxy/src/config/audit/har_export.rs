@@ -0,0 +1,142 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+const DEFAULT_TASK_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_QUEUE_SIZE: usize = 1024;
+const DEFAULT_FILE_MAX_ENTRIES: usize = 4096;
+const DEFAULT_FILE_MAX_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum HarExportSplit {
+    /// one file for each task
+    Task,
+    /// one file for each time window
+    Window(Duration),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct HarExportConfig {
+    pub(crate) directory: PathBuf,
+    pub(crate) split: HarExportSplit,
+    pub(crate) task_idle_timeout: Duration,
+    pub(crate) body_max_size: usize,
+    pub(crate) queue_size: usize,
+    pub(crate) file_max_entries: usize,
+    pub(crate) file_max_size: usize,
+}
+
+impl HarExportConfig {
+    pub(crate) fn new(directory: PathBuf) -> Self {
+        HarExportConfig {
+            directory,
+            split: HarExportSplit::Task,
+            task_idle_timeout: DEFAULT_TASK_IDLE_TIMEOUT,
+            body_max_size: 0,
+            queue_size: DEFAULT_QUEUE_SIZE,
+            file_max_entries: DEFAULT_FILE_MAX_ENTRIES,
+            file_max_size: DEFAULT_FILE_MAX_SIZE,
+        }
+    }
+
+    pub(crate) fn parse(v: &Yaml, lookup_dir: &Path) -> anyhow::Result<Self> {
+        match v {
+            Yaml::Hash(map) => {
+                let mut directory = None;
+                let mut split = HarExportSplit::Task;
+                let mut task_idle_timeout = DEFAULT_TASK_IDLE_TIMEOUT;
+                let mut body_max_size = 0;
+                let mut queue_size = DEFAULT_QUEUE_SIZE;
+                let mut file_max_entries = DEFAULT_FILE_MAX_ENTRIES;
+                let mut file_max_size = DEFAULT_FILE_MAX_SIZE;
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "directory" | "dir" => {
+                        let dir = g3_yaml::value::as_dir_path(v, lookup_dir, true)
+                            .context(format!("invalid directory path value for key {k}"))?;
+                        directory = Some(dir);
+                        Ok(())
+                    }
+                    "window" | "time_window" => {
+                        let window = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        if window.is_zero() {
+                            return Err(anyhow!("zero time window is not allowed"));
+                        }
+                        split = HarExportSplit::Window(window);
+                        Ok(())
+                    }
+                    "task_idle_timeout" => {
+                        task_idle_timeout = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    "body_max_size" => {
+                        body_max_size = g3_yaml::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?;
+                        Ok(())
+                    }
+                    "queue_size" => {
+                        queue_size = g3_yaml::value::as_usize(v)?;
+                        Ok(())
+                    }
+                    "file_max_entries" => {
+                        file_max_entries = g3_yaml::value::as_usize(v)?;
+                        Ok(())
+                    }
+                    "file_max_size" => {
+                        file_max_size = g3_yaml::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?;
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+                let Some(directory) = directory else {
+                    return Err(anyhow!("no directory set"));
+                };
+                if queue_size == 0 {
+                    return Err(anyhow!("invalid zero queue size"));
+                }
+                if file_max_entries == 0 {
+                    return Err(anyhow!("invalid zero file max entries"));
+                }
+                if file_max_size == 0 {
+                    return Err(anyhow!("invalid zero file max size"));
+                }
+                Ok(HarExportConfig {
+                    directory,
+                    split,
+                    task_idle_timeout,
+                    body_max_size,
+                    queue_size,
+                    file_max_entries,
+                    file_max_size,
+                })
+            }
+            Yaml::String(_) => {
+                let directory = g3_yaml::value::as_dir_path(v, lookup_dir, true)?;
+                Ok(HarExportConfig::new(directory))
+            }
+            _ => Err(anyhow!(
+                "yaml value type for 'har export config' should be 'map' or 'str'"
+            )),
+        }
+    }
+}
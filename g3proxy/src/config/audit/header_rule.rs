@@ -0,0 +1,491 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use http::HeaderName;
+use regex::Regex;
+use yaml_rust::{yaml, Yaml};
+
+use g3_types::net::{Host, HttpHeaderMap, HttpHeaderValue};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum HttpHeaderRuleDirection {
+    Request,
+    Response,
+}
+
+#[derive(Clone)]
+enum HttpHeaderRuleAction {
+    Add(HttpHeaderValue),
+    Set(HttpHeaderValue),
+    Remove,
+    Replace(Regex, String),
+}
+
+#[derive(Clone)]
+pub(crate) struct HttpHeaderRule {
+    direction: HttpHeaderRuleDirection,
+    name: HeaderName,
+    action: HttpHeaderRuleAction,
+    exact_hosts: Vec<String>,
+    suffix_hosts: Vec<String>,
+    path_regex: Option<Regex>,
+}
+
+impl HttpHeaderRule {
+    pub(crate) fn parse(v: &Yaml) -> anyhow::Result<Self> {
+        if let Yaml::Hash(map) = v {
+            HttpHeaderRule::parse_map(map)
+        } else {
+            Err(anyhow!(
+                "yaml value type for 'http header rule' should be 'map'"
+            ))
+        }
+    }
+
+    fn parse_map(map: &yaml::Hash) -> anyhow::Result<Self> {
+        let mut direction = HttpHeaderRuleDirection::Request;
+        let mut name: Option<HeaderName> = None;
+        let mut action = String::new();
+        let mut value: Option<String> = None;
+        let mut regex: Option<Regex> = None;
+        let mut exact_hosts = Vec::new();
+        let mut suffix_hosts = Vec::new();
+        let mut path_regex = None;
+
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "direction" => {
+                let s = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                direction = match s.to_lowercase().as_str() {
+                    "request" | "req" => HttpHeaderRuleDirection::Request,
+                    "response" | "rsp" => HttpHeaderRuleDirection::Response,
+                    _ => return Err(anyhow!("invalid direction {s} for key {k}")),
+                };
+                Ok(())
+            }
+            "name" | "header" => {
+                let header = g3_yaml::value::as_http_header_name(v)
+                    .context(format!("invalid http header name value for key {k}"))?;
+                name = Some(header);
+                Ok(())
+            }
+            "action" => {
+                action = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?
+                    .to_lowercase();
+                Ok(())
+            }
+            "value" => {
+                value = Some(
+                    g3_yaml::value::as_string(v)
+                        .context(format!("invalid string value for key {k}"))?,
+                );
+                Ok(())
+            }
+            "regex" => {
+                let s = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                let r =
+                    Regex::new(&s).map_err(|e| anyhow!("invalid regex {s} for key {k}: {e}"))?;
+                regex = Some(r);
+                Ok(())
+            }
+            "host" => {
+                for host in g3_yaml::value::as_list(v, g3_yaml::value::as_domain)
+                    .context(format!("invalid domain list value for key {k}"))?
+                {
+                    if let Some(suffix) = host.strip_prefix('.') {
+                        suffix_hosts.push(suffix.to_string());
+                    } else {
+                        exact_hosts.push(host);
+                    }
+                }
+                Ok(())
+            }
+            "path" | "path_regex" => {
+                let s = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                let r =
+                    Regex::new(&s).map_err(|e| anyhow!("invalid regex {s} for key {k}: {e}"))?;
+                path_regex = Some(r);
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        let Some(name) = name else {
+            return Err(anyhow!("no header name set"));
+        };
+        check_header_name(&name)?;
+
+        let parse_value = |value: Option<String>| -> anyhow::Result<HttpHeaderValue> {
+            let Some(value) = value else {
+                return Err(anyhow!("no value set for action {action}"));
+            };
+            HttpHeaderValue::from_str(&value).map_err(|_| anyhow!("invalid header value {value}"))
+        };
+        let action = match action.as_str() {
+            "add" | "append" => HttpHeaderRuleAction::Add(parse_value(value)?),
+            "set" => HttpHeaderRuleAction::Set(parse_value(value)?),
+            "remove" | "delete" => HttpHeaderRuleAction::Remove,
+            "replace" | "regex_replace" => {
+                let Some(regex) = regex else {
+                    return Err(anyhow!("no regex set for action {action}"));
+                };
+                HttpHeaderRuleAction::Replace(regex, value.unwrap_or_default())
+            }
+            "" => return Err(anyhow!("no action set")),
+            _ => return Err(anyhow!("invalid action {action}")),
+        };
+
+        Ok(HttpHeaderRule {
+            direction,
+            name,
+            action,
+            exact_hosts,
+            suffix_hosts,
+            path_regex,
+        })
+    }
+
+    #[inline]
+    pub(crate) fn direction(&self) -> HttpHeaderRuleDirection {
+        self.direction
+    }
+
+    fn host_matches(&self, host: Option<&Host>) -> bool {
+        if self.exact_hosts.is_empty() && self.suffix_hosts.is_empty() {
+            return true;
+        }
+        let Some(Host::Domain(domain)) = host else {
+            return false;
+        };
+        let domain = domain.to_lowercase();
+        if self.exact_hosts.iter().any(|h| domain.eq(h)) {
+            return true;
+        }
+        self.suffix_hosts.iter().any(|suffix| {
+            domain.eq(suffix)
+                || domain
+                    .strip_suffix(suffix.as_str())
+                    .map(|prefix| prefix.ends_with('.'))
+                    .unwrap_or(false)
+        })
+    }
+
+    /// Apply the rule to the headers, return true if the headers changed
+    pub(crate) fn apply(
+        &self,
+        host: Option<&Host>,
+        path: &str,
+        headers: &mut HttpHeaderMap,
+    ) -> bool {
+        if !self.host_matches(host) {
+            return false;
+        }
+        if let Some(regex) = &self.path_regex {
+            if !regex.is_match(path) {
+                return false;
+            }
+        }
+
+        match &self.action {
+            HttpHeaderRuleAction::Add(value) => {
+                headers.append(self.name.clone(), value.clone());
+                true
+            }
+            HttpHeaderRuleAction::Set(value) => {
+                headers.insert(self.name.clone(), value.clone());
+                true
+            }
+            HttpHeaderRuleAction::Remove => headers.remove(&self.name).is_some(),
+            HttpHeaderRuleAction::Replace(regex, replacement) => {
+                let mut changed = false;
+                let mut new_values = Vec::new();
+                for value in headers.get_all(&self.name) {
+                    let s = value.to_str();
+                    if regex.is_match(s) {
+                        let new = regex.replace_all(s, replacement.as_str());
+                        match HttpHeaderValue::from_str(&new) {
+                            Ok(mut v) => {
+                                if let Some(name) = value.original_name() {
+                                    v.set_original_name(name);
+                                }
+                                new_values.push(v);
+                                changed = true;
+                            }
+                            Err(_) => new_values.push(value.clone()),
+                        }
+                    } else {
+                        new_values.push(value.clone());
+                    }
+                }
+                if changed {
+                    headers.remove(&self.name);
+                    for v in new_values {
+                        headers.append(self.name.clone(), v);
+                    }
+                }
+                changed
+            }
+        }
+    }
+}
+
+fn check_header_name(name: &HeaderName) -> anyhow::Result<()> {
+    // these headers are used to handle the message framing and the connection
+    match name.as_str() {
+        "connection" | "content-length" | "host" | "keep-alive" | "proxy-connection" | "te"
+        | "trailer" | "transfer-encoding" | "upgrade" => {
+            Err(anyhow!("header {name} is not allowed to be changed"))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use g3_http::server::HttpTransparentRequest;
+    use yaml_rust::YamlLoader;
+
+    fn rule(doc: &str) -> HttpHeaderRule {
+        let v = YamlLoader::load_from_str(doc).unwrap();
+        HttpHeaderRule::parse(&v[0]).unwrap()
+    }
+
+    fn header_map(headers: &[(&str, &str)]) -> HttpHeaderMap {
+        let mut map = HttpHeaderMap::default();
+        for (name, value) in headers {
+            let mut v = HttpHeaderValue::from_str(value).unwrap();
+            v.set_original_name(name);
+            map.append(HeaderName::from_str(name).unwrap(), v);
+        }
+        map
+    }
+
+    fn values<'a>(headers: &'a HttpHeaderMap, name: &str) -> Vec<&'a str> {
+        headers.get_all(name).iter().map(|v| v.to_str()).collect()
+    }
+
+    fn host(s: &str) -> Host {
+        Host::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn add() {
+        let r = rule("{name: X-Trace-Id, action: add, value: abc}");
+        assert_eq!(r.direction(), HttpHeaderRuleDirection::Request);
+
+        let mut headers = header_map(&[("X-Trace-Id", "123")]);
+        assert!(r.apply(None, "/", &mut headers));
+        assert_eq!(values(&headers, "x-trace-id"), ["123", "abc"]);
+
+        let mut headers = HttpHeaderMap::default();
+        assert!(r.apply(None, "/", &mut headers));
+        assert_eq!(values(&headers, "x-trace-id"), ["abc"]);
+    }
+
+    #[test]
+    fn set() {
+        let r = rule("{direction: response, name: Cache-Control, action: set, value: no-store}");
+        assert_eq!(r.direction(), HttpHeaderRuleDirection::Response);
+
+        let mut headers = header_map(&[
+            ("Cache-Control", "max-age=60"),
+            ("Cache-Control", "public"),
+            ("Content-Type", "text/html"),
+        ]);
+        assert!(r.apply(None, "/", &mut headers));
+        assert_eq!(values(&headers, "cache-control"), ["no-store"]);
+        assert_eq!(values(&headers, "content-type"), ["text/html"]);
+    }
+
+    #[test]
+    fn remove() {
+        let r = rule("{name: X-Tracking-Id, action: remove}");
+
+        let mut headers = header_map(&[
+            ("X-Tracking-Id", "1"),
+            ("Accept", "*/*"),
+            ("X-Tracking-Id", "2"),
+        ]);
+        assert!(r.apply(None, "/", &mut headers));
+        assert!(values(&headers, "x-tracking-id").is_empty());
+        assert_eq!(values(&headers, "accept"), ["*/*"]);
+
+        // nothing changed if the header is absent
+        assert!(!r.apply(None, "/", &mut headers));
+    }
+
+    #[test]
+    fn replace() {
+        let r = rule(
+            r#"{name: User-Agent, action: replace, regex: "Chrome/[0-9.]+", value: "Chrome/0"}"#,
+        );
+
+        let mut headers = header_map(&[
+            ("User-Agent", "Mozilla/5.0 Chrome/120.0.1 Safari/537.36"),
+            ("User-Agent", "curl/8.0"),
+        ]);
+        assert!(r.apply(None, "/", &mut headers));
+        assert_eq!(
+            values(&headers, "user-agent"),
+            ["Mozilla/5.0 Chrome/0 Safari/537.36", "curl/8.0"]
+        );
+        // the original header name is kept
+        let value = headers.get("user-agent").unwrap();
+        assert_eq!(value.original_name(), Some("User-Agent"));
+
+        // nothing changed if no value matches
+        let mut headers = header_map(&[("User-Agent", "curl/8.0")]);
+        assert!(!r.apply(None, "/", &mut headers));
+        assert_eq!(values(&headers, "user-agent"), ["curl/8.0"]);
+
+        // capture groups and empty replacement
+        let r = rule(r#"{name: Cookie, action: replace, regex: "(^|; )_ga=[^;]*"}"#);
+        let mut headers = header_map(&[("Cookie", "a=1; _ga=GA1.2; b=2")]);
+        assert!(r.apply(None, "/", &mut headers));
+        assert_eq!(values(&headers, "cookie"), ["a=1; b=2"]);
+
+        // the value is kept if the replaced one is not a valid header value
+        let r = rule(r#"{name: X-Note, action: replace, regex: "a", value: "\r\n"}"#);
+        let mut headers = header_map(&[("X-Note", "abc")]);
+        assert!(!r.apply(None, "/", &mut headers));
+        assert_eq!(values(&headers, "x-note"), ["abc"]);
+    }
+
+    #[test]
+    fn host_condition() {
+        let r = rule(
+            "{name: X-Trace-Id, action: set, value: abc, host: [www.example.net, .example.com]}",
+        );
+
+        let check = |host: Option<&Host>| {
+            let mut headers = HttpHeaderMap::default();
+            let changed = r.apply(host, "/", &mut headers);
+            assert_eq!(changed, headers.contains_key("x-trace-id"));
+            changed
+        };
+
+        assert!(check(Some(&host("www.example.net"))));
+        assert!(check(Some(&host("WWW.Example.Net"))));
+        assert!(!check(Some(&host("example.net"))));
+        assert!(!check(Some(&host("a.www.example.net"))));
+
+        assert!(check(Some(&host("example.com"))));
+        assert!(check(Some(&host("a.b.example.com"))));
+        assert!(!check(Some(&host("badexample.com"))));
+        assert!(!check(Some(&host("example.com.cn"))));
+
+        assert!(!check(Some(&host("192.0.2.1"))));
+        assert!(!check(None));
+
+        // no host condition
+        let r = rule("{name: X-Trace-Id, action: set, value: abc}");
+        let mut headers = HttpHeaderMap::default();
+        assert!(r.apply(Some(&host("192.0.2.1")), "/", &mut headers));
+    }
+
+    #[test]
+    fn path_condition() {
+        let r = rule(r#"{name: X-Api, action: add, value: "1", path: "^/api/"}"#);
+
+        let mut headers = HttpHeaderMap::default();
+        assert!(!r.apply(None, "/index.html", &mut headers));
+        assert!(!r.apply(None, "/v1/api/", &mut headers));
+        assert!(headers.is_empty());
+
+        assert!(r.apply(None, "/api/users?id=1", &mut headers));
+        assert_eq!(values(&headers, "x-api"), ["1"]);
+    }
+
+    #[test]
+    fn invalid() {
+        for doc in [
+            "{action: remove}",
+            "{name: X-A}",
+            "{name: X-A, action: move}",
+            "{name: X-A, action: add}",
+            "{name: X-A, action: set}",
+            "{name: X-A, action: replace, value: b}",
+            "{name: X-A, action: replace, regex: '('}",
+            "{name: X-A, action: add, value: \"a\\nb\"}",
+            "{name: X-A, action: remove, direction: both}",
+            "{name: X-A, action: remove, path: '('}",
+            "{name: X-A, action: remove, unknown: a}",
+            "{name: Content-Length, action: remove}",
+            "{name: Transfer-Encoding, action: set, value: chunked}",
+            "{name: Host, action: set, value: example.com}",
+            "{name: Connection, action: remove}",
+            "[a]",
+        ] {
+            let v = YamlLoader::load_from_str(doc).unwrap();
+            assert!(HttpHeaderRule::parse(&v[0]).is_err(), "{doc}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rewrite_request() {
+        let data = b"GET /api/list?a=1 HTTP/1.1\r\n\
+            Host: www.example.com\r\n\
+            User-Agent: Mozilla/5.0 Chrome/120.0\r\n\
+            X-Tracking-Id: 123\r\n\
+            Accept: */*\r\n\r\n";
+        let mut reader = &data[..];
+        let (mut req, _) = HttpTransparentRequest::parse(&mut reader, 4096)
+            .await
+            .unwrap();
+
+        let rules = [
+            rule("{name: X-Tracking-Id, action: remove}"),
+            rule("{name: X-Trace-Id, action: add, value: t-1, host: [.example.com], path: ^/api/}"),
+            rule("{name: X-Other, action: add, value: o-1, host: [.example.net]}"),
+            rule(
+                r#"{name: User-Agent, action: replace, regex: "Chrome/[0-9.]+", value: "Chrome/0"}"#,
+            ),
+        ];
+        let host = req.host.as_ref().map(|v| v.host());
+        let path = req.uri.path_and_query().map(|v| v.as_str()).unwrap_or("/");
+        let mut headers = req.end_to_end_headers.clone();
+        let changed = rules.iter().fold(false, |changed, r| {
+            r.apply(host, path, &mut headers) || changed
+        });
+        assert!(changed);
+        req.end_to_end_headers = headers;
+
+        let head = String::from_utf8(req.serialize_for_origin()).unwrap();
+        let mut lines = head.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines[0], "GET /api/list?a=1 HTTP/1.1");
+        assert_eq!(lines.pop(), Some(""));
+        assert_eq!(lines.pop(), Some(""));
+        let mut header_lines = lines[1..].to_vec();
+        header_lines.sort();
+        assert_eq!(
+            header_lines,
+            [
+                "Accept: */*",
+                "Connection: Keep-Alive",
+                "Host: www.example.com",
+                "User-Agent: Mozilla/5.0 Chrome/0",
+                "x-trace-id: t-1",
+            ]
+        );
+    }
+}
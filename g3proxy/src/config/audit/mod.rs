@@ -27,13 +27,51 @@ pub(crate) use registry::{clear, get_all};
 mod auditor;
 pub(crate) use auditor::AuditorConfig;
 
+mod content_filter;
+pub(crate) use content_filter::ContentFilterConfig;
+
+mod header_rule;
+pub(crate) use header_rule::{HttpHeaderRule, HttpHeaderRuleDirection};
+
+mod clamav;
+pub(crate) use clamav::{ClamavPeer, ClamavServiceConfig};
+
+mod har_export;
+pub(crate) use har_export::{HarExportConfig, HarExportSplit};
+
+mod protocol_policy;
+pub(crate) use protocol_policy::{ProtocolPolicyAction, ProtocolPolicyConfig};
+
+mod respmod_bypass;
+pub(crate) use respmod_bypass::RespmodBypassRule;
+
+mod respmod_decompress;
+pub(crate) use respmod_decompress::RespmodDecompressConfig;
+
+mod body_limit;
+pub(crate) use body_limit::IcapBodyLimit;
+
+mod tls_bypass;
+pub(crate) use tls_bypass::TlsInterceptionBypassConfig;
+
+mod tls_cert_error;
+pub(crate) use tls_cert_error::{TlsUpstreamCertErrorAction, TlsUpstreamCertErrorPolicy};
+
+mod tls_ech;
+pub(crate) use tls_ech::TlsEchPolicy;
+
+mod traffic_mirror;
+pub(crate) use traffic_mirror::{TrafficMirrorConfig, TrafficMirrorPeer};
+
 pub(crate) fn load_all(v: &Yaml, conf_dir: &Path) -> anyhow::Result<()> {
     let parser = HybridParser::new(conf_dir, g3_daemon::opts::config_file_extension());
-    parser.foreach_map(v, |map, position| {
-        let auditor = load_auditor(map, position)?;
-        registry::add(auditor, false)?;
-        Ok(())
-    })
+    parser.foreach_map(v, load_one)
+}
+
+/// load and add one auditor
+pub(crate) fn load_one(map: &yaml::Hash, position: Option<YamlDocPosition>) -> anyhow::Result<()> {
+    let auditor = load_auditor(map, position)?;
+    registry::add(auditor, false)
 }
 
 pub(crate) fn load_at_position(position: &YamlDocPosition) -> anyhow::Result<AuditorConfig> {
@@ -0,0 +1,388 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::{yaml, Yaml};
+
+use g3_dpi::Protocol;
+use g3_types::net::Ports;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ProtocolPolicyAction {
+    Allow,
+    Log,
+    Block,
+}
+
+impl ProtocolPolicyAction {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            ProtocolPolicyAction::Allow => "allow",
+            ProtocolPolicyAction::Log => "log",
+            ProtocolPolicyAction::Block => "block",
+        }
+    }
+}
+
+impl FromStr for ProtocolPolicyAction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "allow" | "permit" => Ok(ProtocolPolicyAction::Allow),
+            "log" | "allow_log" | "permit_log" => Ok(ProtocolPolicyAction::Log),
+            "block" | "deny" | "forbid" => Ok(ProtocolPolicyAction::Block),
+            _ => Err(()),
+        }
+    }
+}
+
+fn as_policy_action(v: &Yaml) -> anyhow::Result<ProtocolPolicyAction> {
+    let s = g3_yaml::value::as_string(v)?;
+    ProtocolPolicyAction::from_str(&s).map_err(|_| anyhow!("invalid protocol policy action {s}"))
+}
+
+fn as_protocol_name(v: &Yaml) -> anyhow::Result<&'static str> {
+    let s = g3_yaml::value::as_string(v)?;
+    let protocol = Protocol::from_str(&s).map_err(|_| anyhow!("unsupported protocol {s}"))?;
+    Ok(protocol.as_str())
+}
+
+#[derive(Clone)]
+struct ProtocolPolicyRule {
+    protocols: BTreeSet<&'static str>,
+    ports: Option<Ports>,
+    action: ProtocolPolicyAction,
+}
+
+impl ProtocolPolicyRule {
+    fn parse(v: &Yaml) -> anyhow::Result<Self> {
+        let Yaml::Hash(map) = v else {
+            return Err(anyhow!(
+                "yaml value type for 'protocol policy rule' should be 'map'"
+            ));
+        };
+
+        let mut protocols = BTreeSet::new();
+        let mut ports = None;
+        let mut action = None;
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "protocol" | "protocols" => {
+                for p in g3_yaml::value::as_list(v, as_protocol_name)
+                    .context(format!("invalid protocol list value for key {k}"))?
+                {
+                    protocols.insert(p);
+                }
+                Ok(())
+            }
+            "port" | "ports" => {
+                let p = g3_yaml::value::as_ports(v)
+                    .context(format!("invalid ports value for key {k}"))?;
+                ports = Some(p);
+                Ok(())
+            }
+            "action" => {
+                let a = as_policy_action(v).context(format!("invalid action value for key {k}"))?;
+                action = Some(a);
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        if protocols.is_empty() {
+            return Err(anyhow!("no protocol set"));
+        }
+        let Some(action) = action else {
+            return Err(anyhow!("no action set"));
+        };
+        Ok(ProtocolPolicyRule {
+            protocols,
+            ports,
+            action,
+        })
+    }
+
+    fn matches(&self, protocol: &str, port: u16) -> bool {
+        if !self.protocols.contains(protocol) {
+            return false;
+        }
+        self.ports
+            .as_ref()
+            .map(|ports| ports.contains(port))
+            .unwrap_or(true)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ProtocolPolicyConfig {
+    default_action: ProtocolPolicyAction,
+    rules: Vec<ProtocolPolicyRule>,
+}
+
+impl Default for ProtocolPolicyConfig {
+    fn default() -> Self {
+        ProtocolPolicyConfig {
+            default_action: ProtocolPolicyAction::Allow,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl ProtocolPolicyConfig {
+    pub(crate) fn parse(v: &Yaml) -> anyhow::Result<Self> {
+        match v {
+            Yaml::Hash(map) => ProtocolPolicyConfig::parse_map(map),
+            Yaml::Array(_) => {
+                let rules = g3_yaml::value::as_list(v, ProtocolPolicyRule::parse)?;
+                Ok(ProtocolPolicyConfig {
+                    default_action: ProtocolPolicyAction::Allow,
+                    rules,
+                })
+            }
+            _ => Err(anyhow!(
+                "yaml value type for 'protocol policy config' should be 'map' or 'seq'"
+            )),
+        }
+    }
+
+    fn parse_map(map: &yaml::Hash) -> anyhow::Result<Self> {
+        let mut config = ProtocolPolicyConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "default" | "default_action" => {
+                config.default_action =
+                    as_policy_action(v).context(format!("invalid action value for key {k}"))?;
+                Ok(())
+            }
+            "rules" | "rule" => {
+                config.rules = g3_yaml::value::as_list(v, ProtocolPolicyRule::parse).context(
+                    format!("invalid protocol policy rule list value for key {k}"),
+                )?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+        Ok(config)
+    }
+
+    /// Get the action for the detected protocol on the upstream port
+    pub(crate) fn check(&self, protocol: Protocol, port: u16) -> ProtocolPolicyAction {
+        let protocol = protocol.as_str();
+        self.rules
+            .iter()
+            .find(|r| r.matches(protocol, port))
+            .map(|r| r.action)
+            .unwrap_or(self.default_action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(doc: &str) -> anyhow::Result<ProtocolPolicyConfig> {
+        let v = YamlLoader::load_from_str(doc).unwrap();
+        ProtocolPolicyConfig::parse(&v[0])
+    }
+
+    #[test]
+    fn resolve() {
+        let doc = r#"
+          default: log
+          rules:
+            - protocol: [ssh, bittorrent]
+              port: 443
+              action: block
+            - protocol: ssh
+              port: "22, 2200-2299"
+              action: allow
+            - protocol: [ssh, unknown]
+              action: deny
+            - protocol: [http, h2]
+              action: permit
+        "#;
+        let config = parse(doc).unwrap();
+
+        // port specific rules
+        assert_eq!(
+            config.check(Protocol::Ssh, 443),
+            ProtocolPolicyAction::Block
+        );
+        assert_eq!(
+            config.check(Protocol::BitTorrentOverTcp, 443),
+            ProtocolPolicyAction::Block
+        );
+        assert_eq!(config.check(Protocol::Ssh, 22), ProtocolPolicyAction::Allow);
+        assert_eq!(
+            config.check(Protocol::Ssh, 2250),
+            ProtocolPolicyAction::Allow
+        );
+
+        // the first matched rule wins
+        assert_eq!(
+            config.check(Protocol::Ssh, 2300),
+            ProtocolPolicyAction::Block
+        );
+        assert_eq!(
+            config.check(Protocol::Unknown, 443),
+            ProtocolPolicyAction::Block
+        );
+
+        // protocol aliases
+        assert_eq!(
+            config.check(Protocol::Http1, 443),
+            ProtocolPolicyAction::Allow
+        );
+        assert_eq!(
+            config.check(Protocol::Http2, 8443),
+            ProtocolPolicyAction::Allow
+        );
+
+        // fallback to the default action
+        assert_eq!(
+            config.check(Protocol::BitTorrentOverTcp, 6881),
+            ProtocolPolicyAction::Log
+        );
+        assert_eq!(
+            config.check(Protocol::SshLegacy, 443),
+            ProtocolPolicyAction::Log
+        );
+        assert_eq!(
+            config.check(Protocol::TlsModern, 443),
+            ProtocolPolicyAction::Log
+        );
+    }
+
+    #[test]
+    fn shared_protocol_name() {
+        // the protocol variants with the same name are matched by the same rule
+        let config = parse("[{protocol: [rtmp, bittorrent], action: block}]").unwrap();
+        assert_eq!(
+            config.check(Protocol::RtmpOverTcp, 1935),
+            ProtocolPolicyAction::Block
+        );
+        assert_eq!(
+            config.check(Protocol::RtmpOverHttp, 80),
+            ProtocolPolicyAction::Block
+        );
+        assert_eq!(
+            config.check(Protocol::BitTorrentOverUtp, 6881),
+            ProtocolPolicyAction::Block
+        );
+        assert_eq!(config.check(Protocol::Ssh, 22), ProtocolPolicyAction::Allow);
+    }
+
+    #[test]
+    fn default_config() {
+        let config = ProtocolPolicyConfig::default();
+        assert_eq!(
+            config.check(Protocol::Ssh, 443),
+            ProtocolPolicyAction::Allow
+        );
+        assert_eq!(
+            config.check(Protocol::Unknown, 80),
+            ProtocolPolicyAction::Allow
+        );
+
+        let config = parse("default: block").unwrap();
+        assert_eq!(
+            config.check(Protocol::Http1, 80),
+            ProtocolPolicyAction::Block
+        );
+    }
+
+    #[test]
+    fn all_protocols() {
+        // all the protocol names are allowed to be used in the rules
+        for protocol in [
+            Protocol::Unknown,
+            Protocol::SslLegacy,
+            Protocol::TlsLegacy,
+            Protocol::TlsModern,
+            Protocol::TlsTlcp,
+            Protocol::Http1,
+            Protocol::Http2,
+            Protocol::Http3,
+            Protocol::Smtp,
+            Protocol::SshLegacy,
+            Protocol::Ssh,
+            Protocol::FtpControl,
+            Protocol::Pop3,
+            Protocol::Nntp,
+            Protocol::Nnsp,
+            Protocol::Imap,
+            Protocol::Rtsp,
+            Protocol::Mqtt,
+            Protocol::Stomp,
+            Protocol::Smpp,
+            Protocol::RtmpOverTcp,
+            Protocol::RtmpOverHttp,
+            Protocol::Nats,
+            Protocol::BitTorrentOverTcp,
+            Protocol::BitTorrentOverUtp,
+            Protocol::Rdp,
+            Protocol::Mysql,
+            Protocol::Postgres,
+            Protocol::Redis,
+            Protocol::Websocket,
+            Protocol::Dns,
+        ] {
+            let doc = format!("[{{protocol: {}, action: block}}]", protocol.as_str());
+            let config = parse(&doc).unwrap();
+            assert_eq!(config.check(protocol, 1), ProtocolPolicyAction::Block);
+        }
+    }
+
+    #[test]
+    fn action() {
+        for (s, action) in [
+            ("allow", ProtocolPolicyAction::Allow),
+            ("Permit", ProtocolPolicyAction::Allow),
+            ("log", ProtocolPolicyAction::Log),
+            ("allow_log", ProtocolPolicyAction::Log),
+            ("permit_log", ProtocolPolicyAction::Log),
+            ("block", ProtocolPolicyAction::Block),
+            ("deny", ProtocolPolicyAction::Block),
+            ("FORBID", ProtocolPolicyAction::Block),
+        ] {
+            assert_eq!(ProtocolPolicyAction::from_str(s), Ok(action));
+        }
+        assert!(ProtocolPolicyAction::from_str("drop").is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        for doc in [
+            "[{action: block}]",
+            "[{protocol: ssh}]",
+            "[{protocol: [], action: block}]",
+            "[{protocol: telnet, action: block}]",
+            "[{protocol: ssh, action: drop}]",
+            "[{protocol: ssh, port: 70000, action: block}]",
+            "[{protocol: ssh, port: '443-80', action: block}]",
+            "[{protocol: ssh, action: block, unknown: 1}]",
+            "{default: drop}",
+            "{rules: [ssh]}",
+            "{unknown: 1}",
+            "ssh",
+        ] {
+            assert!(parse(doc).is_err(), "{doc}");
+        }
+    }
+}
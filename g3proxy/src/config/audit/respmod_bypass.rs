@@ -0,0 +1,84 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+#[derive(Clone)]
+pub(crate) struct RespmodBypassRule {
+    content_type: String,
+    min_size: u64,
+}
+
+impl RespmodBypassRule {
+    fn new(content_type: &str) -> anyhow::Result<Self> {
+        if !content_type.contains('/') {
+            return Err(anyhow!("invalid content type {content_type}"));
+        }
+        Ok(RespmodBypassRule {
+            content_type: content_type.to_ascii_lowercase(),
+            min_size: 0,
+        })
+    }
+
+    pub(crate) fn parse(v: &Yaml) -> anyhow::Result<Self> {
+        match v {
+            Yaml::String(s) => RespmodBypassRule::new(s),
+            Yaml::Hash(map) => {
+                let mut content_type = String::new();
+                let mut min_size = 0;
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "content_type" => {
+                        content_type = g3_yaml::value::as_string(v)
+                            .context(format!("invalid string value for key {k}"))?;
+                        Ok(())
+                    }
+                    "min_size" => {
+                        min_size = g3_yaml::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?;
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+                let mut rule = RespmodBypassRule::new(&content_type)?;
+                rule.min_size = min_size as u64;
+                Ok(rule)
+            }
+            _ => Err(anyhow!(
+                "yaml value type for 'respmod bypass rule' should be 'string' or 'map'"
+            )),
+        }
+    }
+
+    /// Check if the response should skip RESPMOD.
+    /// The `mime` should be in lower case, and without any parameters.
+    pub(crate) fn matches(&self, mime: &str, content_length: Option<u64>) -> bool {
+        let type_matched = if let Some(main_type) = self.content_type.strip_suffix("/*") {
+            mime.split('/').next() == Some(main_type)
+        } else {
+            self.content_type == mime
+        };
+        if !type_matched {
+            return false;
+        }
+        if self.min_size == 0 {
+            return true;
+        }
+        content_length
+            .map(|len| len >= self.min_size)
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,73 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+const DEFAULT_MAX_BODY_SIZE: usize = 4 * 1024 * 1024;
+const DEFAULT_MAX_DECODED_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct RespmodDecompressConfig {
+    pub(crate) max_body_size: usize,
+    pub(crate) max_decoded_size: usize,
+}
+
+impl Default for RespmodDecompressConfig {
+    fn default() -> Self {
+        RespmodDecompressConfig {
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            max_decoded_size: DEFAULT_MAX_DECODED_SIZE,
+        }
+    }
+}
+
+impl RespmodDecompressConfig {
+    pub(crate) fn parse(v: &Yaml) -> anyhow::Result<Option<Self>> {
+        match v {
+            Yaml::Hash(map) => {
+                let mut config = RespmodDecompressConfig::default();
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "max_body_size" | "max_encoded_size" => {
+                        config.max_body_size = g3_yaml::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?;
+                        Ok(())
+                    }
+                    "max_decoded_size" => {
+                        config.max_decoded_size = g3_yaml::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?;
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+                if config.max_body_size == 0 || config.max_decoded_size == 0 {
+                    return Err(anyhow!("zero size limit is not allowed"));
+                }
+                Ok(Some(config))
+            }
+            Yaml::Boolean(enable) => {
+                if *enable {
+                    Ok(Some(RespmodDecompressConfig::default()))
+                } else {
+                    Ok(None)
+                }
+            }
+            _ => Err(anyhow!(
+                "invalid value type for icap respmod decompress config"
+            )),
+        }
+    }
+}
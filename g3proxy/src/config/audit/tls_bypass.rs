@@ -0,0 +1,118 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use ip_network::IpNetwork;
+use regex::{Regex, RegexSet};
+use yaml_rust::Yaml;
+
+use g3_types::net::Host;
+
+const DEFAULT_CERT_BYPASS_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+#[derive(Clone)]
+pub(crate) struct TlsInterceptionBypassConfig {
+    pub(crate) exact_match_domain: BTreeSet<String>,
+    pub(crate) exact_match_ipaddr: BTreeSet<IpAddr>,
+    pub(crate) child_match_domain: BTreeSet<String>,
+    pub(crate) subnet_match_ipaddr: BTreeSet<IpNetwork>,
+    cert_subject_regex: Vec<String>,
+    pub(crate) cert_subject_regex_set: Option<RegexSet>,
+    pub(crate) cert_bypass_cache_ttl: Duration,
+}
+
+impl Default for TlsInterceptionBypassConfig {
+    fn default() -> Self {
+        TlsInterceptionBypassConfig {
+            exact_match_domain: BTreeSet::new(),
+            exact_match_ipaddr: BTreeSet::new(),
+            child_match_domain: BTreeSet::new(),
+            subnet_match_ipaddr: BTreeSet::new(),
+            cert_subject_regex: Vec::new(),
+            cert_subject_regex_set: None,
+            cert_bypass_cache_ttl: DEFAULT_CERT_BYPASS_CACHE_TTL,
+        }
+    }
+}
+
+impl TlsInterceptionBypassConfig {
+    pub(crate) fn parse(v: &Yaml) -> anyhow::Result<Self> {
+        if let Yaml::Hash(map) = v {
+            let mut config = TlsInterceptionBypassConfig::default();
+            g3_yaml::foreach_kv(map, |k, v| config.set(k, v))?;
+            if !config.cert_subject_regex.is_empty() {
+                let set = RegexSet::new(&config.cert_subject_regex)
+                    .map_err(|e| anyhow!("failed to build cert subject regex set: {e}"))?;
+                config.cert_subject_regex_set = Some(set);
+            }
+            Ok(config)
+        } else {
+            Err(anyhow!(
+                "yaml value type for 'tls interception bypass config' should be 'map'"
+            ))
+        }
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "exact_match" => {
+                let hosts = g3_yaml::value::as_list(v, g3_yaml::value::as_host)
+                    .context(format!("invalid host list value for key {k}"))?;
+                for host in hosts {
+                    match host {
+                        Host::Domain(domain) => self.exact_match_domain.insert(domain),
+                        Host::Ip(ip) => self.exact_match_ipaddr.insert(ip),
+                    };
+                }
+                Ok(())
+            }
+            "child_match" => {
+                let domains = g3_yaml::value::as_list(v, g3_yaml::value::as_domain)
+                    .context(format!("invalid domain list value for key {k}"))?;
+                for domain in domains {
+                    self.child_match_domain.insert(domain);
+                }
+                Ok(())
+            }
+            "subnet_match" => {
+                let nets = g3_yaml::value::as_list(v, g3_yaml::value::as_ip_network)
+                    .context(format!("invalid ip network list value for key {k}"))?;
+                for net in nets {
+                    self.subnet_match_ipaddr.insert(net);
+                }
+                Ok(())
+            }
+            "cert_subject_match" | "cert_subject" => {
+                for s in g3_yaml::value::as_list(v, g3_yaml::value::as_string)
+                    .context(format!("invalid string list value for key {k}"))?
+                {
+                    Regex::new(&s).map_err(|e| anyhow!("invalid regex {s} for key {k}: {e}"))?;
+                    self.cert_subject_regex.push(s);
+                }
+                Ok(())
+            }
+            "cert_bypass_cache_ttl" => {
+                self.cert_bypass_cache_ttl = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+const DEFAULT_BYPASS_CACHE_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TlsUpstreamCertErrorAction {
+    /// close the connection
+    Block,
+    /// close the connection, and tunnel later connections to the same upstream,
+    /// the current one can not be tunneled as the client hello has already been consumed
+    BlockAndBypassLater,
+    /// intercept with fake certificates signed by the untrusted CA
+    ForgeUntrusted,
+}
+
+impl FromStr for TlsUpstreamCertErrorAction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match g3_yaml::key::normalize(s).as_str() {
+            "block" | "deny" => Ok(TlsUpstreamCertErrorAction::Block),
+            "block_and_bypass_later" | "bypass_later" => {
+                Ok(TlsUpstreamCertErrorAction::BlockAndBypassLater)
+            }
+            "forge_untrusted" | "untrusted" => Ok(TlsUpstreamCertErrorAction::ForgeUntrusted),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TlsUpstreamCertErrorPolicy {
+    pub(crate) action: TlsUpstreamCertErrorAction,
+    pub(crate) bypass_cache_ttl: Duration,
+}
+
+impl Default for TlsUpstreamCertErrorPolicy {
+    fn default() -> Self {
+        TlsUpstreamCertErrorPolicy {
+            action: TlsUpstreamCertErrorAction::Block,
+            bypass_cache_ttl: DEFAULT_BYPASS_CACHE_TTL,
+        }
+    }
+}
+
+impl TlsUpstreamCertErrorPolicy {
+    pub(crate) fn parse(v: &Yaml) -> anyhow::Result<Self> {
+        let mut policy = TlsUpstreamCertErrorPolicy::default();
+        match v {
+            Yaml::String(s) => {
+                policy.action = TlsUpstreamCertErrorAction::from_str(s)
+                    .map_err(|_| anyhow!("invalid action {s}"))?;
+            }
+            Yaml::Hash(map) => {
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "action" => {
+                        let s = g3_yaml::value::as_string(v)
+                            .context(format!("invalid string value for key {k}"))?;
+                        policy.action = TlsUpstreamCertErrorAction::from_str(&s)
+                            .map_err(|_| anyhow!("invalid action value {s} for key {k}"))?;
+                        Ok(())
+                    }
+                    "bypass_cache_ttl" => {
+                        policy.bypass_cache_ttl = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+            }
+            _ => {
+                return Err(anyhow!(
+                    "yaml value type for 'cert error policy' should be 'string' or 'map'"
+                ));
+            }
+        }
+        Ok(policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_action() {
+        let policy = TlsUpstreamCertErrorPolicy::parse(&Yaml::String("deny".to_string())).unwrap();
+        assert_eq!(policy.action, TlsUpstreamCertErrorAction::Block);
+
+        let docs = yaml_rust::YamlLoader::load_from_str(
+            "{action: block_and_bypass_later, bypass_cache_ttl: 1m}",
+        )
+        .unwrap();
+        let policy = TlsUpstreamCertErrorPolicy::parse(&docs[0]).unwrap();
+        assert_eq!(
+            policy.action,
+            TlsUpstreamCertErrorAction::BlockAndBypassLater
+        );
+        assert_eq!(policy.bypass_cache_ttl, Duration::from_secs(60));
+
+        // the current connection will not be tunneled, so no plain bypass action
+        assert!(TlsUpstreamCertErrorPolicy::parse(&Yaml::String("bypass".to_string())).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use yaml_rust::Yaml;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum TlsEchPolicy {
+    /// continue the interception without ECH, as the upstream ClientHello is always rebuilt
+    #[default]
+    Strip,
+    /// tunnel the stream without interception
+    Bypass,
+    /// close the connection
+    Block,
+}
+
+impl TlsEchPolicy {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            TlsEchPolicy::Strip => "strip",
+            TlsEchPolicy::Bypass => "bypass",
+            TlsEchPolicy::Block => "block",
+        }
+    }
+
+    pub(crate) fn parse(v: &Yaml) -> anyhow::Result<Self> {
+        if let Yaml::String(s) = v {
+            TlsEchPolicy::from_str(s).map_err(|_| anyhow!("invalid tls ech policy {s}"))
+        } else {
+            Err(anyhow!(
+                "yaml value type for 'tls ech policy' should be 'string'"
+            ))
+        }
+    }
+}
+
+impl FromStr for TlsEchPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match g3_yaml::key::normalize(s).as_str() {
+            "strip" | "intercept" => Ok(TlsEchPolicy::Strip),
+            "bypass" | "tunnel" => Ok(TlsEchPolicy::Bypass),
+            "block" | "deny" => Ok(TlsEchPolicy::Block),
+            _ => Err(()),
+        }
+    }
+}
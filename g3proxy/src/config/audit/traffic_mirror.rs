@@ -0,0 +1,116 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+const DEFAULT_QUEUE_SIZE: usize = 4096;
+const DEFAULT_PACKET_SIZE: usize = 1400;
+const DEFAULT_RECONNECT_WAIT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum TrafficMirrorPeer {
+    Tcp(SocketAddr),
+    Udp(SocketAddr),
+    Unix(PathBuf),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct TrafficMirrorConfig {
+    pub(crate) peer: TrafficMirrorPeer,
+    pub(crate) queue_size: usize,
+    pub(crate) packet_size: usize,
+    pub(crate) reconnect_wait: Duration,
+}
+
+impl TrafficMirrorConfig {
+    fn new(peer: TrafficMirrorPeer) -> Self {
+        TrafficMirrorConfig {
+            peer,
+            queue_size: DEFAULT_QUEUE_SIZE,
+            packet_size: DEFAULT_PACKET_SIZE,
+            reconnect_wait: DEFAULT_RECONNECT_WAIT,
+        }
+    }
+
+    pub(crate) fn parse(v: &Yaml) -> anyhow::Result<Self> {
+        match v {
+            Yaml::Hash(map) => {
+                let mut peer = None;
+                let mut queue_size = DEFAULT_QUEUE_SIZE;
+                let mut packet_size = DEFAULT_PACKET_SIZE;
+                let mut reconnect_wait = DEFAULT_RECONNECT_WAIT;
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "tcp" | "tcp_peer" => {
+                        let addr = g3_yaml::value::as_env_sockaddr(v)
+                            .context(format!("invalid socket address value for key {k}"))?;
+                        peer = Some(TrafficMirrorPeer::Tcp(addr));
+                        Ok(())
+                    }
+                    "udp" | "udp_peer" => {
+                        let addr = g3_yaml::value::as_env_sockaddr(v)
+                            .context(format!("invalid socket address value for key {k}"))?;
+                        peer = Some(TrafficMirrorPeer::Udp(addr));
+                        Ok(())
+                    }
+                    "unix" | "unix_path" => {
+                        let path = g3_yaml::value::as_absolute_path(v)
+                            .context(format!("invalid absolute path value for key {k}"))?;
+                        peer = Some(TrafficMirrorPeer::Unix(path));
+                        Ok(())
+                    }
+                    "queue_size" => {
+                        queue_size = g3_yaml::value::as_usize(v)?;
+                        Ok(())
+                    }
+                    "packet_size" => {
+                        packet_size = g3_yaml::value::as_usize(v)?;
+                        Ok(())
+                    }
+                    "reconnect_wait" => {
+                        reconnect_wait = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+                let Some(peer) = peer else {
+                    return Err(anyhow!("no collector address set"));
+                };
+                if queue_size == 0 {
+                    return Err(anyhow!("invalid zero queue size"));
+                }
+                Ok(TrafficMirrorConfig {
+                    peer,
+                    queue_size,
+                    packet_size,
+                    reconnect_wait,
+                })
+            }
+            Yaml::String(_) => {
+                let addr = g3_yaml::value::as_env_sockaddr(v)?;
+                Ok(TrafficMirrorConfig::new(TrafficMirrorPeer::Tcp(addr)))
+            }
+            _ => Err(anyhow!(
+                "yaml value type for 'traffic mirror config' should be 'map' or 'str'"
+            )),
+        }
+    }
+}
@@ -24,6 +24,20 @@ impl UserAuditConfig {
         if let Value::Object(map) = v {
             for (k, v) in map {
                 match g3_json::key::normalize(k).as_str() {
+                    "enable" | "enable_audit" => {
+                        self.enable = g3_json::value::as_bool(v)
+                            .context(format!("invalid bool value for key {k}"))?;
+                    }
+                    "auditor" => {
+                        let name = g3_json::value::as_metrics_name(v)
+                            .context(format!("invalid metrics name value for key {k}"))?;
+                        self.auditor = Some(name);
+                    }
+                    "tls_interception" | "enable_tls_interception" => {
+                        let enable = g3_json::value::as_bool(v)
+                            .context(format!("invalid bool value for key {k}"))?;
+                        self.tls_interception = Some(enable);
+                    }
                     "enable_protocol_inspection" => {
                         self.enable_protocol_inspection = g3_json::value::as_bool(v)
                             .context(format!("invalid bool value for key {k}"))?;
@@ -16,16 +16,34 @@
 
 use rand::distributions::{Bernoulli, Distribution};
 
+use g3_types::metrics::MetricsName;
+
 mod json;
 mod yaml;
 
-#[derive(Default, Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct UserAuditConfig {
+    pub(crate) enable: bool,
+    pub(crate) auditor: Option<MetricsName>,
+    pub(crate) tls_interception: Option<bool>,
     pub(crate) enable_protocol_inspection: bool,
     pub(crate) prohibit_unknown_protocol: bool,
     application_audit_ratio: Option<Bernoulli>,
 }
 
+impl Default for UserAuditConfig {
+    fn default() -> Self {
+        UserAuditConfig {
+            enable: true,
+            auditor: None,
+            tls_interception: None,
+            enable_protocol_inspection: false,
+            prohibit_unknown_protocol: false,
+            application_audit_ratio: None,
+        }
+    }
+}
+
 impl UserAuditConfig {
     pub(crate) fn do_application_audit(&self) -> Option<bool> {
         if let Some(ratio) = &self.application_audit_ratio {
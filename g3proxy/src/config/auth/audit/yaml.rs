@@ -23,6 +23,20 @@ impl UserAuditConfig {
     pub(crate) fn parse_yaml(&mut self, v: &Yaml) -> anyhow::Result<()> {
         if let Yaml::Hash(map) = v {
             g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                "enable" | "enable_audit" => {
+                    self.enable = g3_yaml::value::as_bool(v)?;
+                    Ok(())
+                }
+                "auditor" => {
+                    let name = g3_yaml::value::as_metrics_name(v)
+                        .context(format!("invalid metrics name value for key {k}"))?;
+                    self.auditor = Some(name);
+                    Ok(())
+                }
+                "tls_interception" | "enable_tls_interception" => {
+                    self.tls_interception = Some(g3_yaml::value::as_bool(v)?);
+                    Ok(())
+                }
                 "enable_protocol_inspection" => {
                     self.enable_protocol_inspection = g3_yaml::value::as_bool(v)?;
                     Ok(())
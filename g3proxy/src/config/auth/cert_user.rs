@@ -0,0 +1,211 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ClientCertField {
+    CommonName,
+    SubjectAltName,
+    IssuerCommonName,
+}
+
+impl FromStr for ClientCertField {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cn" | "common_name" | "subject_cn" => Ok(ClientCertField::CommonName),
+            "san" | "subject_alt_name" => Ok(ClientCertField::SubjectAltName),
+            "issuer" | "issuer_cn" => Ok(ClientCertField::IssuerCommonName),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ClientCertUserRule {
+    pub(crate) field: ClientCertField,
+    pattern: String,
+    pub(crate) user: Option<String>,
+}
+
+impl ClientCertUserRule {
+    pub(crate) fn parse_yaml(v: &Yaml) -> anyhow::Result<Self> {
+        if let Yaml::Hash(map) = v {
+            let mut field = None;
+            let mut pattern = "*".to_string();
+            let mut user = None;
+            g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                "field" | "match" => {
+                    let s = g3_yaml::value::as_string(v)?;
+                    let f = ClientCertField::from_str(&s)
+                        .map_err(|_| anyhow!("invalid client cert field {s}"))?;
+                    field = Some(f);
+                    Ok(())
+                }
+                "pattern" => {
+                    pattern = g3_yaml::value::as_string(v)
+                        .context(format!("invalid string value for key {k}"))?;
+                    Ok(())
+                }
+                "user" | "username" => {
+                    let name = g3_yaml::value::as_string(v)
+                        .context(format!("invalid string value for key {k}"))?;
+                    user = Some(name);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+            let Some(field) = field else {
+                return Err(anyhow!("no client cert field set"));
+            };
+            Ok(ClientCertUserRule {
+                field,
+                pattern,
+                user,
+            })
+        } else {
+            Err(anyhow!(
+                "yaml value type for 'client cert user rule' should be 'map'"
+            ))
+        }
+    }
+
+    /// match the value with the wildcard pattern, '*' for any sequence and '?' for any single char
+    pub(crate) fn is_match(&self, value: &str) -> bool {
+        let p = self.pattern.as_bytes();
+        let v = value.as_bytes();
+
+        let mut pi = 0;
+        let mut vi = 0;
+        let mut star: Option<(usize, usize)> = None;
+        while vi < v.len() {
+            if pi < p.len() && (p[pi] == b'?' || p[pi] == v[vi]) {
+                pi += 1;
+                vi += 1;
+            } else if pi < p.len() && p[pi] == b'*' {
+                star = Some((pi, vi));
+                pi += 1;
+            } else if let Some((sp, sv)) = star {
+                pi = sp + 1;
+                vi = sv + 1;
+                star = Some((sp, sv + 1));
+            } else {
+                return false;
+            }
+        }
+        p[pi..].iter().all(|c| *c == b'*')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn rule(doc: &str) -> ClientCertUserRule {
+        let v = YamlLoader::load_from_str(doc).unwrap();
+        ClientCertUserRule::parse_yaml(&v[0]).unwrap()
+    }
+
+    fn pattern(p: &str) -> ClientCertUserRule {
+        ClientCertUserRule {
+            field: ClientCertField::CommonName,
+            pattern: p.to_string(),
+            user: None,
+        }
+    }
+
+    #[test]
+    fn wildcard() {
+        let r = pattern("*");
+        assert!(r.is_match("alice"));
+        assert!(r.is_match(""));
+
+        let r = pattern("alice");
+        assert!(r.is_match("alice"));
+        assert!(!r.is_match("Alice"));
+        assert!(!r.is_match("alice2"));
+        assert!(!r.is_match("malice"));
+
+        let r = pattern("*.svc.example.net");
+        assert!(r.is_match("api.svc.example.net"));
+        assert!(r.is_match("a.b.svc.example.net"));
+        assert!(!r.is_match("svc.example.net"));
+        assert!(!r.is_match("api.svc.example.net.cn"));
+        assert!(!r.is_match("api.svc-example.net"));
+
+        let r = pattern("user-??");
+        assert!(r.is_match("user-01"));
+        assert!(!r.is_match("user-1"));
+        assert!(!r.is_match("user-001"));
+
+        let r = pattern("*@*.example.com");
+        assert!(r.is_match("bob@mail.example.com"));
+        assert!(r.is_match("a@b@c.example.com"));
+        assert!(!r.is_match("bob@example.com"));
+
+        let r = pattern("a*b*c");
+        assert!(r.is_match("abc"));
+        assert!(r.is_match("aXbYbZc"));
+        assert!(r.is_match("abcbc"));
+        assert!(!r.is_match("abcb"));
+        assert!(!r.is_match("ac"));
+
+        let r = pattern("**");
+        assert!(r.is_match(""));
+        assert!(r.is_match("any"));
+
+        let r = pattern("");
+        assert!(r.is_match(""));
+        assert!(!r.is_match("a"));
+    }
+
+    #[test]
+    fn parse() {
+        let r = rule("{field: cn}");
+        assert_eq!(r.field, ClientCertField::CommonName);
+        assert_eq!(r.pattern, "*");
+        assert_eq!(r.user, None);
+
+        let r = rule("{match: SAN, pattern: '*.svc.example.net', user: service}");
+        assert_eq!(r.field, ClientCertField::SubjectAltName);
+        assert_eq!(r.pattern, "*.svc.example.net");
+        assert_eq!(r.user.as_deref(), Some("service"));
+
+        let r = rule("{field: issuer_cn, pattern: Example CA, username: partner}");
+        assert_eq!(r.field, ClientCertField::IssuerCommonName);
+        assert_eq!(r.user.as_deref(), Some("partner"));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for doc in [
+            "{pattern: '*'}",
+            "{field: email}",
+            "{field: cn, unknown: 1}",
+            "{field: cn, user: [a]}",
+            "cn",
+        ] {
+            let v = YamlLoader::load_from_str(doc).unwrap();
+            assert!(ClientCertUserRule::parse_yaml(&v[0]).is_err(), "{doc}");
+        }
+    }
+}
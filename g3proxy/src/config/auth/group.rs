@@ -25,9 +25,13 @@ use yaml_rust::{yaml, Yaml};
 use g3_types::metrics::MetricsName;
 use g3_yaml::YamlDocPosition;
 
-use super::{UserConfig, UserDynamicSource};
+use super::{
+    ClientCertUserRule, JwtAuthConfig, KerberosAuthConfig, RadiusAuthConfig,
+    TrafficQuotaStoreConfig, UserConfig, UserDynamicSource,
+};
 
 const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_TRAFFIC_QUOTA_SYNC_INTERVAL: Duration = Duration::from_secs(10);
 
 #[derive(Clone)]
 pub(crate) struct UserGroupConfig {
@@ -37,7 +41,17 @@ pub(crate) struct UserGroupConfig {
     pub(crate) dynamic_source: Option<UserDynamicSource>,
     pub(crate) dynamic_cache: PathBuf,
     pub(crate) refresh_interval: Duration,
+    pub(crate) refresh_jitter: Duration,
     pub(crate) anonymous_user: Option<Arc<UserConfig>>,
+    pub(crate) radius: Option<Arc<RadiusAuthConfig>>,
+    pub(crate) radius_user: Option<Arc<UserConfig>>,
+    pub(crate) jwt: Option<Arc<JwtAuthConfig>>,
+    pub(crate) kerberos: Option<Arc<KerberosAuthConfig>>,
+    pub(crate) client_cert_users: Vec<ClientCertUserRule>,
+    pub(crate) traffic_quota_store: Option<Arc<TrafficQuotaStoreConfig>>,
+    pub(crate) traffic_quota_sync_interval: Duration,
+    pub(crate) password_cache_ttl: Duration,
+    pub(crate) password_cache_failed_ttl: Duration,
 }
 
 impl UserGroupConfig {
@@ -49,6 +63,11 @@ impl UserGroupConfig {
         self.position.clone()
     }
 
+    /// check if the user may be found in this group, dynamic users can only be checked at runtime
+    pub(crate) fn may_have_user(&self, username: &str) -> bool {
+        self.dynamic_source.is_some() || self.static_users.contains_key(username)
+    }
+
     pub(crate) fn empty(name: &MetricsName) -> Self {
         UserGroupConfig {
             name: name.clone(),
@@ -57,7 +76,17 @@ impl UserGroupConfig {
             dynamic_source: None,
             dynamic_cache: PathBuf::default(),
             refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            refresh_jitter: Duration::ZERO,
             anonymous_user: None,
+            radius: None,
+            radius_user: None,
+            jwt: None,
+            kerberos: None,
+            client_cert_users: Vec::new(),
+            traffic_quota_store: None,
+            traffic_quota_sync_interval: DEFAULT_TRAFFIC_QUOTA_SYNC_INTERVAL,
+            password_cache_ttl: Duration::ZERO,
+            password_cache_failed_ttl: Duration::ZERO,
         }
     }
 
@@ -69,7 +98,17 @@ impl UserGroupConfig {
             dynamic_source: None,
             dynamic_cache: PathBuf::default(),
             refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            refresh_jitter: Duration::ZERO,
             anonymous_user: None,
+            radius: None,
+            radius_user: None,
+            jwt: None,
+            kerberos: None,
+            client_cert_users: Vec::new(),
+            traffic_quota_store: None,
+            traffic_quota_sync_interval: DEFAULT_TRAFFIC_QUOTA_SYNC_INTERVAL,
+            password_cache_ttl: Duration::ZERO,
+            password_cache_failed_ttl: Duration::ZERO,
         }
     }
 
@@ -83,6 +122,9 @@ impl UserGroupConfig {
         if self.name.is_empty() {
             return Err(anyhow!("name is not set"));
         }
+        if self.radius_user.is_some() && self.radius.is_none() {
+            return Err(anyhow!("radius user is set but no radius config found"));
+        }
 
         Ok(())
     }
@@ -134,6 +176,21 @@ impl UserGroupConfig {
                     .context(format!("invalid duration value for key {k}"))?;
                 Ok(())
             }
+            "refresh_jitter" => {
+                self.refresh_jitter = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid duration value for key {k}"))?;
+                Ok(())
+            }
+            "password_cache_ttl" => {
+                self.password_cache_ttl = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid duration value for key {k}"))?;
+                Ok(())
+            }
+            "password_cache_failed_ttl" => {
+                self.password_cache_failed_ttl = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid duration value for key {k}"))?;
+                Ok(())
+            }
             "anonymous_user" => {
                 if let Yaml::Hash(map) = v {
                     let mut user = UserConfig::parse_yaml(map)?;
@@ -144,7 +201,102 @@ impl UserGroupConfig {
                     Err(anyhow!("invalid hash value for key {k}"))
                 }
             }
+            "radius" => {
+                if let Yaml::Hash(map) = v {
+                    let radius = RadiusAuthConfig::parse_yaml(map)
+                        .context(format!("invalid radius config value for key {k}"))?;
+                    self.radius = Some(Arc::new(radius));
+                    Ok(())
+                } else {
+                    Err(anyhow!("invalid hash value for key {k}"))
+                }
+            }
+            "radius_user" => {
+                if let Yaml::Hash(map) = v {
+                    let mut user = UserConfig::parse_yaml(map)?;
+                    user.set_no_password();
+                    self.radius_user = Some(Arc::new(user));
+                    Ok(())
+                } else {
+                    Err(anyhow!("invalid hash value for key {k}"))
+                }
+            }
+            "jwt" => {
+                if let Yaml::Hash(map) = v {
+                    let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                    let jwt = JwtAuthConfig::parse_yaml(map, Some(lookup_dir))
+                        .context(format!("invalid jwt auth config value for key {k}"))?;
+                    self.jwt = Some(Arc::new(jwt));
+                    Ok(())
+                } else {
+                    Err(anyhow!("invalid hash value for key {k}"))
+                }
+            }
+            "kerberos" => {
+                if let Yaml::Hash(map) = v {
+                    let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                    let kerberos = KerberosAuthConfig::parse_yaml(map, lookup_dir)
+                        .context(format!("invalid kerberos auth config value for key {k}"))?;
+                    self.kerberos = Some(Arc::new(kerberos));
+                    Ok(())
+                } else {
+                    Err(anyhow!("invalid hash value for key {k}"))
+                }
+            }
+            "client_cert_users" | "client_cert_user_rules" => {
+                self.client_cert_users = g3_yaml::value::as_list(v, ClientCertUserRule::parse_yaml)
+                    .context(format!("invalid client cert user rules value for key {k}"))?;
+                Ok(())
+            }
+            "traffic_quota_store" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let store = TrafficQuotaStoreConfig::parse_yaml(v, lookup_dir).context(format!(
+                    "invalid traffic quota store config value for key {k}"
+                ))?;
+                self.traffic_quota_store = Some(Arc::new(store));
+                Ok(())
+            }
+            "traffic_quota_sync_interval" => {
+                self.traffic_quota_sync_interval = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid duration value for key {k}"))?;
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(s: &str) -> UserGroupConfig {
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        let mut config = UserGroupConfig::new(None);
+        config.parse(docs[0].as_hash().unwrap()).unwrap();
+        config
+    }
+
+    #[test]
+    fn may_have_user() {
+        let config = parse(
+            "
+name: g1
+static_users:
+  - name: a
+anonymous_user:
+  name: anonymous
+",
+        );
+        assert!(config.may_have_user("a"));
+        assert!(!config.may_have_user("b"));
+        assert!(!config.may_have_user("anonymous"));
+
+        let mut config = config;
+        let source = Yaml::String("http://127.0.0.1/users".to_string());
+        config.dynamic_source =
+            Some(UserDynamicSource::parse_config(&source, std::path::Path::new("/")).unwrap());
+        assert!(config.may_have_user("b"));
+    }
+}
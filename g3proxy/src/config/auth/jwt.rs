@@ -0,0 +1,381 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use base64::prelude::*;
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::nid::Nid;
+use openssl::pkey::{Id, PKey, Public};
+use openssl::rsa::Rsa;
+use yaml_rust::{yaml, Yaml};
+
+const MAX_KEY_FILE_SIZE: u64 = 1_000_000; // 1MB
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum JwtAlgorithm {
+    HS256,
+    HS384,
+    HS512,
+    RS256,
+    RS384,
+    RS512,
+    PS256,
+    PS384,
+    PS512,
+    ES256,
+    ES384,
+    ES512,
+}
+
+impl FromStr for JwtAlgorithm {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "HS256" => Ok(JwtAlgorithm::HS256),
+            "HS384" => Ok(JwtAlgorithm::HS384),
+            "HS512" => Ok(JwtAlgorithm::HS512),
+            "RS256" => Ok(JwtAlgorithm::RS256),
+            "RS384" => Ok(JwtAlgorithm::RS384),
+            "RS512" => Ok(JwtAlgorithm::RS512),
+            "PS256" => Ok(JwtAlgorithm::PS256),
+            "PS384" => Ok(JwtAlgorithm::PS384),
+            "PS512" => Ok(JwtAlgorithm::PS512),
+            "ES256" => Ok(JwtAlgorithm::ES256),
+            "ES384" => Ok(JwtAlgorithm::ES384),
+            "ES512" => Ok(JwtAlgorithm::ES512),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) enum JwtVerifyKey {
+    Hmac(Vec<u8>),
+    Public(PKey<Public>),
+}
+
+impl JwtVerifyKey {
+    fn support(&self, alg: JwtAlgorithm) -> bool {
+        match self {
+            JwtVerifyKey::Hmac(_) => matches!(
+                alg,
+                JwtAlgorithm::HS256 | JwtAlgorithm::HS384 | JwtAlgorithm::HS512
+            ),
+            JwtVerifyKey::Public(key) => match key.id() {
+                Id::RSA => matches!(
+                    alg,
+                    JwtAlgorithm::RS256
+                        | JwtAlgorithm::RS384
+                        | JwtAlgorithm::RS512
+                        | JwtAlgorithm::PS256
+                        | JwtAlgorithm::PS384
+                        | JwtAlgorithm::PS512
+                ),
+                Id::EC => matches!(
+                    alg,
+                    JwtAlgorithm::ES256 | JwtAlgorithm::ES384 | JwtAlgorithm::ES512
+                ),
+                _ => false,
+            },
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct JwtKey {
+    pub(crate) kid: Option<String>,
+    pub(crate) alg: Option<JwtAlgorithm>,
+    pub(crate) key: JwtVerifyKey,
+}
+
+impl JwtKey {
+    pub(crate) fn matches(&self, kid: Option<&str>, alg: JwtAlgorithm) -> bool {
+        if let (Some(k1), Some(k2)) = (&self.kid, kid) {
+            if k1 != k2 {
+                return false;
+            }
+        }
+        if let Some(key_alg) = self.alg {
+            if key_alg != alg {
+                return false;
+            }
+        }
+        self.key.support(alg)
+    }
+
+    fn parse_yaml(map: &yaml::Hash, lookup_dir: Option<&Path>) -> anyhow::Result<Self> {
+        let mut kid = None;
+        let mut alg = None;
+        let mut key = None;
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "kid" | "key_id" => {
+                kid = Some(g3_yaml::value::as_string(v)?);
+                Ok(())
+            }
+            "alg" | "algorithm" => {
+                let s = g3_yaml::value::as_string(v)?;
+                let a = JwtAlgorithm::from_str(&s.to_uppercase())
+                    .map_err(|_| anyhow!("unsupported jwt algorithm {s}"))?;
+                alg = Some(a);
+                Ok(())
+            }
+            "secret" => {
+                let s = g3_yaml::value::as_string(v)?;
+                key = Some(JwtVerifyKey::Hmac(s.into_bytes()));
+                Ok(())
+            }
+            "public_key" => {
+                let pkey = as_public_key(v, lookup_dir)
+                    .context(format!("invalid public key value for key {k}"))?;
+                key = Some(JwtVerifyKey::Public(pkey));
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        let Some(key) = key else {
+            return Err(anyhow!("no secret or public key set"));
+        };
+        if let Some(alg) = alg {
+            if !key.support(alg) {
+                return Err(anyhow!("the key can not be used for algorithm {alg:?}"));
+            }
+        }
+        Ok(JwtKey { kid, alg, key })
+    }
+
+    fn parse_jwk(map: &serde_json::Map<String, serde_json::Value>) -> anyhow::Result<Option<Self>> {
+        let get_str = |name: &str| -> anyhow::Result<Option<&str>> {
+            match map.get(name) {
+                Some(serde_json::Value::String(s)) => Ok(Some(s.as_str())),
+                Some(_) => Err(anyhow!("invalid string value for field {name}")),
+                None => Ok(None),
+            }
+        };
+        let get_bytes = |name: &str| -> anyhow::Result<Vec<u8>> {
+            let s = get_str(name)?.ok_or_else(|| anyhow!("no field {name} found"))?;
+            BASE64_URL_SAFE_NO_PAD
+                .decode(s)
+                .map_err(|e| anyhow!("invalid base64url value for field {name}: {e}"))
+        };
+
+        if let Some(u) = get_str("use")? {
+            if u != "sig" {
+                return Ok(None);
+            }
+        }
+        let kid = get_str("kid")?.map(|s| s.to_string());
+        let alg = match get_str("alg")? {
+            Some(s) => match JwtAlgorithm::from_str(s) {
+                Ok(alg) => Some(alg),
+                Err(_) => return Ok(None),
+            },
+            None => None,
+        };
+
+        let key = match get_str("kty")? {
+            Some("RSA") => {
+                let n = BigNum::from_slice(&get_bytes("n")?)?;
+                let e = BigNum::from_slice(&get_bytes("e")?)?;
+                let rsa = Rsa::from_public_components(n, e)?;
+                JwtVerifyKey::Public(PKey::from_rsa(rsa)?)
+            }
+            Some("EC") => {
+                let nid = match get_str("crv")? {
+                    Some("P-256") => Nid::X9_62_PRIME256V1,
+                    Some("P-384") => Nid::SECP384R1,
+                    Some("P-521") => Nid::SECP521R1,
+                    _ => return Ok(None),
+                };
+                let group = EcGroup::from_curve_name(nid)?;
+                let x = BigNum::from_slice(&get_bytes("x")?)?;
+                let y = BigNum::from_slice(&get_bytes("y")?)?;
+                let ec = EcKey::from_public_key_affine_coordinates(&group, &x, &y)?;
+                ec.check_key()?;
+                JwtVerifyKey::Public(PKey::from_ec_key(ec)?)
+            }
+            Some("oct") => JwtVerifyKey::Hmac(get_bytes("k")?),
+            Some(_) => return Ok(None),
+            None => return Err(anyhow!("no kty field found")),
+        };
+        if let Some(alg) = alg {
+            if !key.support(alg) {
+                return Err(anyhow!("the key can not be used for algorithm {alg:?}"));
+            }
+        }
+        Ok(Some(JwtKey { kid, alg, key }))
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct JwtAuthConfig {
+    pub(crate) keys: Vec<JwtKey>,
+    pub(crate) issuer: Option<String>,
+    pub(crate) audience: Option<String>,
+    pub(crate) username_claim: String,
+    pub(crate) groups_claim: String,
+    pub(crate) group_users: HashMap<String, String>,
+    pub(crate) leeway: Duration,
+}
+
+impl Default for JwtAuthConfig {
+    fn default() -> Self {
+        JwtAuthConfig {
+            keys: Vec::new(),
+            issuer: None,
+            audience: None,
+            username_claim: "sub".to_string(),
+            groups_claim: "groups".to_string(),
+            group_users: HashMap::new(),
+            leeway: Duration::from_secs(60),
+        }
+    }
+}
+
+impl JwtAuthConfig {
+    pub(crate) fn parse_yaml(map: &yaml::Hash, lookup_dir: Option<&Path>) -> anyhow::Result<Self> {
+        let mut config = JwtAuthConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| config.set(k, v, lookup_dir))?;
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml, lookup_dir: Option<&Path>) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "keys" | "key" => {
+                if let Yaml::Array(seq) = v {
+                    for (i, v) in seq.iter().enumerate() {
+                        let key = self
+                            .parse_key(v, lookup_dir)
+                            .context(format!("invalid jwt key value for {k}#{i}"))?;
+                        self.keys.push(key);
+                    }
+                } else {
+                    let key = self
+                        .parse_key(v, lookup_dir)
+                        .context(format!("invalid jwt key value for key {k}"))?;
+                    self.keys.push(key);
+                }
+                Ok(())
+            }
+            "jwks_file" | "jwks" => {
+                let keys = load_jwks_file(v, lookup_dir)
+                    .context(format!("invalid jwks file value for key {k}"))?;
+                self.keys.extend(keys);
+                Ok(())
+            }
+            "issuer" | "iss" => {
+                self.issuer = Some(g3_yaml::value::as_string(v)?);
+                Ok(())
+            }
+            "audience" | "aud" => {
+                self.audience = Some(g3_yaml::value::as_string(v)?);
+                Ok(())
+            }
+            "username_claim" => {
+                self.username_claim = g3_yaml::value::as_string(v)?;
+                Ok(())
+            }
+            "groups_claim" => {
+                self.groups_claim = g3_yaml::value::as_string(v)?;
+                Ok(())
+            }
+            "group_users" | "group_user_map" => {
+                self.group_users = g3_yaml::value::as_hashmap(
+                    v,
+                    g3_yaml::value::as_string,
+                    g3_yaml::value::as_string,
+                )
+                .context(format!("invalid group to user map value for key {k}"))?;
+                Ok(())
+            }
+            "leeway" => {
+                self.leeway = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn parse_key(&self, v: &Yaml, lookup_dir: Option<&Path>) -> anyhow::Result<JwtKey> {
+        if let Yaml::Hash(map) = v {
+            JwtKey::parse_yaml(map, lookup_dir)
+        } else {
+            Err(anyhow!("yaml value type for jwt key should be 'map'"))
+        }
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.keys.is_empty() {
+            return Err(anyhow!("no jwt verify key set"));
+        }
+        if self.username_claim.is_empty() {
+            return Err(anyhow!("empty username claim"));
+        }
+        Ok(())
+    }
+}
+
+fn read_file(v: &Yaml, lookup_dir: Option<&Path>) -> anyhow::Result<String> {
+    let (file, path) = g3_yaml::value::as_file(v, lookup_dir).context("invalid file")?;
+    let mut contents = String::new();
+    file.take(MAX_KEY_FILE_SIZE)
+        .read_to_string(&mut contents)
+        .map_err(|e| anyhow!("failed to read contents of file {}: {e}", path.display()))?;
+    Ok(contents)
+}
+
+pub(super) fn as_public_key(v: &Yaml, lookup_dir: Option<&Path>) -> anyhow::Result<PKey<Public>> {
+    if let Yaml::String(s) = v {
+        if s.trim_start().starts_with("--") {
+            return PKey::public_key_from_pem(s.as_bytes())
+                .map_err(|e| anyhow!("invalid public key string: {e}"));
+        }
+    }
+
+    let contents = read_file(v, lookup_dir)?;
+    PKey::public_key_from_pem(contents.as_bytes())
+        .map_err(|e| anyhow!("invalid public key file: {e}"))
+}
+
+fn load_jwks_file(v: &Yaml, lookup_dir: Option<&Path>) -> anyhow::Result<Vec<JwtKey>> {
+    let contents = read_file(v, lookup_dir)?;
+    let doc =
+        serde_json::Value::from_str(&contents).map_err(|e| anyhow!("invalid json content: {e}"))?;
+    let Some(serde_json::Value::Array(keys)) = doc.get("keys") else {
+        return Err(anyhow!("no valid keys array found"));
+    };
+
+    let mut jwt_keys = Vec::with_capacity(keys.len());
+    for (i, v) in keys.iter().enumerate() {
+        let serde_json::Value::Object(map) = v else {
+            return Err(anyhow!("invalid json object value for key #{i}"));
+        };
+        if let Some(key) = JwtKey::parse_jwk(map).context(format!("invalid jwk for key #{i}"))? {
+            jwt_keys.push(key);
+        }
+    }
+    Ok(jwt_keys)
+}
@@ -0,0 +1,99 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::{yaml, Yaml};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct KerberosAuthConfig {
+    pub(crate) keytab: PathBuf,
+    pub(crate) service_principal: Option<String>,
+    pub(crate) max_clock_skew: Duration,
+    pub(crate) realms: Vec<String>,
+    pub(crate) strip_realm: bool,
+    pub(crate) principal_users: HashMap<String, String>,
+}
+
+impl Default for KerberosAuthConfig {
+    fn default() -> Self {
+        KerberosAuthConfig {
+            keytab: PathBuf::new(),
+            service_principal: None,
+            max_clock_skew: Duration::from_secs(300),
+            realms: Vec::new(),
+            strip_realm: true,
+            principal_users: HashMap::new(),
+        }
+    }
+}
+
+impl KerberosAuthConfig {
+    pub(super) fn parse_yaml(map: &yaml::Hash, lookup_dir: &Path) -> anyhow::Result<Self> {
+        let mut config = KerberosAuthConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| config.set(k, v, lookup_dir))?;
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml, lookup_dir: &Path) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "keytab" | "keytab_file" => {
+                self.keytab = g3_yaml::value::as_file_path(v, lookup_dir, false)
+                    .context(format!("invalid file path value for key {k}"))?;
+                Ok(())
+            }
+            "service_principal" | "principal" => {
+                self.service_principal = Some(g3_yaml::value::as_string(v)?);
+                Ok(())
+            }
+            "max_clock_skew" => {
+                self.max_clock_skew = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "realms" | "realm" => {
+                self.realms = g3_yaml::value::as_list(v, g3_yaml::value::as_string)
+                    .context(format!("invalid string list value for key {k}"))?;
+                Ok(())
+            }
+            "strip_realm" => {
+                self.strip_realm = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
+            "principal_users" | "principal_user_map" => {
+                self.principal_users = g3_yaml::value::as_hashmap(
+                    v,
+                    g3_yaml::value::as_string,
+                    g3_yaml::value::as_string,
+                )
+                .context(format!("invalid principal to user map value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.keytab.as_os_str().is_empty() {
+            return Err(anyhow!("no keytab file set"));
+        }
+        Ok(())
+    }
+}
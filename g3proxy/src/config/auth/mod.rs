@@ -30,12 +30,32 @@ pub(crate) use site::UserSiteConfig;
 mod audit;
 pub(crate) use audit::UserAuditConfig;
 
+mod time_window;
+pub(crate) use time_window::{TimeWindowZone, UserTimeWindowConfig};
+
+mod traffic_quota;
+pub(crate) use traffic_quota::{
+    TrafficQuotaRedisStore, TrafficQuotaStoreConfig, UserTrafficQuotaConfig,
+};
+
 mod user;
 pub(crate) use user::UserConfig;
 
 mod group;
 pub(crate) use group::UserGroupConfig;
 
+mod radius;
+pub(crate) use radius::{RadiusAuthConfig, RadiusAuthMethod};
+
+mod jwt;
+pub(crate) use jwt::{JwtAlgorithm, JwtAuthConfig, JwtVerifyKey};
+
+mod kerberos;
+pub(crate) use kerberos::KerberosAuthConfig;
+
+mod cert_user;
+pub(crate) use cert_user::{ClientCertField, ClientCertUserRule};
+
 pub(crate) mod source;
 pub(crate) use source::UserDynamicSource;
 
@@ -44,11 +64,13 @@ pub(crate) use registry::{clear, get_all};
 
 pub(crate) fn load_all(v: &Yaml, conf_dir: &Path) -> anyhow::Result<()> {
     let parser = HybridParser::new(conf_dir, g3_daemon::opts::config_file_extension());
-    parser.foreach_map(v, |map, position| {
-        let group = load_user_group(map, position)?;
-        registry::add(group, false)?;
-        Ok(())
-    })
+    parser.foreach_map(v, load_one)
+}
+
+/// load and add one user group
+pub(crate) fn load_one(map: &yaml::Hash, position: Option<YamlDocPosition>) -> anyhow::Result<()> {
+    let group = load_user_group(map, position)?;
+    registry::add(group, false)
 }
 
 pub(crate) fn load_at_position(position: &YamlDocPosition) -> anyhow::Result<UserGroupConfig> {
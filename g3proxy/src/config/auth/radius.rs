@@ -0,0 +1,125 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::{yaml, Yaml};
+
+const DEFAULT_RADIUS_PORT: u16 = 1812;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum RadiusAuthMethod {
+    Pap,
+    Chap,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct RadiusAuthConfig {
+    pub(crate) server: SocketAddr,
+    pub(crate) secret: String,
+    pub(crate) method: RadiusAuthMethod,
+    pub(crate) nas_identifier: String,
+    pub(crate) timeout: Duration,
+    pub(crate) retries: usize,
+    pub(crate) cache_ttl: Duration,
+    pub(crate) require_message_authenticator: bool,
+}
+
+impl Default for RadiusAuthConfig {
+    fn default() -> Self {
+        RadiusAuthConfig {
+            server: SocketAddr::from(([127, 0, 0, 1], DEFAULT_RADIUS_PORT)),
+            secret: String::new(),
+            method: RadiusAuthMethod::Pap,
+            nas_identifier: "g3proxy".to_string(),
+            timeout: Duration::from_secs(3),
+            retries: 2,
+            cache_ttl: Duration::from_secs(60),
+            require_message_authenticator: true,
+        }
+    }
+}
+
+impl RadiusAuthConfig {
+    pub(super) fn parse_yaml(map: &yaml::Hash) -> anyhow::Result<Self> {
+        let mut config = RadiusAuthConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| config.set(k, v))?;
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "server" | "address" | "addr" => {
+                self.server = g3_yaml::value::as_sockaddr(v)
+                    .or_else(|_| {
+                        g3_yaml::value::as_ipaddr(v)
+                            .map(|ip| SocketAddr::new(ip, DEFAULT_RADIUS_PORT))
+                    })
+                    .context(format!("invalid socket address value for key {k}"))?;
+                Ok(())
+            }
+            "secret" | "shared_secret" => {
+                self.secret = g3_yaml::value::as_string(v)?;
+                Ok(())
+            }
+            "method" | "auth_method" => {
+                let method = g3_yaml::value::as_string(v)?;
+                self.method = match g3_yaml::key::normalize(&method).as_str() {
+                    "pap" => RadiusAuthMethod::Pap,
+                    "chap" => RadiusAuthMethod::Chap,
+                    _ => return Err(anyhow!("unsupported radius auth method {method}")),
+                };
+                Ok(())
+            }
+            "nas_identifier" => {
+                self.nas_identifier = g3_yaml::value::as_string(v)?;
+                Ok(())
+            }
+            "timeout" => {
+                self.timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "retries" | "retry" => {
+                self.retries = g3_yaml::value::as_usize(v)?;
+                Ok(())
+            }
+            "cache_ttl" => {
+                self.cache_ttl = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "require_message_authenticator" => {
+                self.require_message_authenticator = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.secret.is_empty() {
+            return Err(anyhow!("no shared secret is set"));
+        }
+        if self.nas_identifier.len() > 253 {
+            return Err(anyhow!("too long nas identifier"));
+        }
+        Ok(())
+    }
+}
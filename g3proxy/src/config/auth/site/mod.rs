@@ -16,18 +16,58 @@
 
 use std::collections::BTreeSet;
 use std::net::IpAddr;
+use std::str::FromStr;
 
 use anyhow::anyhow;
 use ip_network::IpNetwork;
 
 use g3_histogram::HistogramMetricsConfig;
 use g3_types::metrics::MetricsName;
-use g3_types::net::Host;
+use g3_types::net::{Host, StreamDelayConfig};
 use g3_types::resolve::ResolveStrategy;
 
 mod json;
 mod yaml;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum UserSiteRollupLevel {
+    /// aggregate all users of this site into one series
+    Site,
+    /// aggregate all users of the same user group into one series
+    UserGroup,
+}
+
+impl FromStr for UserSiteRollupLevel {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "site" => Ok(UserSiteRollupLevel::Site),
+            "user_group" | "group" => Ok(UserSiteRollupLevel::UserGroup),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub(crate) struct UserSiteRollupConfig {
+    pub(crate) site: bool,
+    pub(crate) user_group: bool,
+}
+
+impl UserSiteRollupConfig {
+    pub(crate) fn enabled(&self) -> bool {
+        self.site || self.user_group
+    }
+
+    fn add_level(&mut self, level: UserSiteRollupLevel) {
+        match level {
+            UserSiteRollupLevel::Site => self.site = true,
+            UserSiteRollupLevel::UserGroup => self.user_group = true,
+        }
+    }
+}
+
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
 pub(crate) struct UserSiteConfig {
     pub(crate) id: MetricsName,
@@ -36,7 +76,11 @@ pub(crate) struct UserSiteConfig {
     pub(crate) subnet_match_ipaddr: BTreeSet<IpNetwork>,
     pub(crate) child_match_domain: BTreeSet<String>,
     pub(crate) emit_stats: bool,
+    pub(crate) rollup_stats: UserSiteRollupConfig,
     pub(crate) resolve_strategy: Option<ResolveStrategy>,
+    pub(crate) resolve_static: Vec<IpAddr>,
+    pub(crate) resolver: Option<MetricsName>,
+    pub(crate) tcp_stream_delay: Option<StreamDelayConfig>,
     pub(crate) duration_stats: HistogramMetricsConfig,
 }
 
@@ -48,6 +92,11 @@ impl UserSiteConfig {
         Ok(())
     }
 
+    /// whether we need to collect site level stats
+    pub(crate) fn need_stats(&self) -> bool {
+        self.emit_stats || self.rollup_stats.enabled()
+    }
+
     fn add_exact_host(&mut self, host: Host) {
         match host {
             Host::Domain(domain) => self.exact_match_domain.insert(domain),
@@ -14,10 +14,12 @@
  * limitations under the License.
  */
 
+use std::str::FromStr;
+
 use anyhow::{anyhow, Context};
 use yaml_rust::Yaml;
 
-use super::UserSiteConfig;
+use super::{UserSiteConfig, UserSiteRollupLevel};
 
 impl UserSiteConfig {
     pub(crate) fn parse_yaml(v: &Yaml) -> anyhow::Result<Self> {
@@ -67,6 +69,18 @@ impl UserSiteConfig {
                     .context(format!("invalid bool value for key {k}"))?;
                 Ok(())
             }
+            "rollup_stats" | "rollup_metrics" => {
+                let levels = g3_yaml::value::as_list(v, |v| {
+                    let s = g3_yaml::value::as_string(v)?;
+                    UserSiteRollupLevel::from_str(&s)
+                        .map_err(|_| anyhow!("invalid rollup level {s}"))
+                })
+                .context(format!("invalid rollup level list value for key {k}"))?;
+                for level in levels {
+                    self.rollup_stats.add_level(level);
+                }
+                Ok(())
+            }
             "duration_stats" | "duration_metrics" => {
                 self.duration_stats = g3_yaml::value::as_histogram_metrics_config(v).context(
                     format!("invalid histogram metrics config value for key {k}"),
@@ -79,6 +93,23 @@ impl UserSiteConfig {
                 self.resolve_strategy = Some(strategy);
                 Ok(())
             }
+            "resolve_static" | "static_addresses" => {
+                self.resolve_static = g3_yaml::value::as_list(v, g3_yaml::value::as_ipaddr)
+                    .context(format!("invalid ip address list value for key {k}"))?;
+                Ok(())
+            }
+            "resolver" => {
+                let name = g3_yaml::value::as_metrics_name(v)
+                    .context(format!("invalid metrics name value for key {k}"))?;
+                self.resolver = Some(name);
+                Ok(())
+            }
+            "tcp_stream_delay" | "tcp_delay" => {
+                let delay = g3_yaml::value::as_stream_delay_config(v)
+                    .context(format!("invalid stream delay config value for key {k}"))?;
+                self.tcp_stream_delay = Some(delay);
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
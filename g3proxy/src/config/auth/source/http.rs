@@ -0,0 +1,216 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use nix::NixPath;
+use openssl::pkey::{PKey, Public};
+use rustls::ServerName;
+use url::Url;
+use yaml_rust::{yaml, Yaml};
+
+use g3_types::fs::ConfigFileFormat;
+use g3_types::net::{RustlsClientConfig, RustlsClientConfigBuilder, UpstreamAddr};
+
+use super::file::UserDynamicFileSource;
+use crate::config::auth::UserConfig;
+
+const CONFIG_KEY_SOURCE_URL: &str = "url";
+
+#[derive(Clone)]
+pub(crate) struct UserDynamicHttpSource {
+    pub(crate) url: Url,
+    pub(crate) server: UpstreamAddr,
+    pub(crate) tls_client: Option<RustlsClientConfig>,
+    pub(crate) tls_name: Option<ServerName>,
+    pub(crate) format: ConfigFileFormat,
+    pub(crate) conditional: bool,
+    pub(crate) signature_url: Option<Url>,
+    pub(crate) signature_key: Option<PKey<Public>>,
+    pub(crate) max_header_size: usize,
+    pub(crate) max_body_size: usize,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) fetch_timeout: Duration,
+}
+
+impl UserDynamicHttpSource {
+    fn new(url: Url) -> anyhow::Result<Self> {
+        let server = UpstreamAddr::try_from(&url)?;
+        let format = url
+            .path_segments()
+            .and_then(|s| s.last())
+            .and_then(|s| s.rsplit_once('.'))
+            .and_then(|(_, ext)| ConfigFileFormat::from_str(ext).ok())
+            .unwrap_or(ConfigFileFormat::Json);
+        Ok(UserDynamicHttpSource {
+            url,
+            server,
+            tls_client: None,
+            tls_name: None,
+            format,
+            conditional: true,
+            signature_url: None,
+            signature_key: None,
+            max_header_size: 64 * 1024,
+            max_body_size: 64 * 1024 * 1024,
+            connect_timeout: Duration::from_secs(10),
+            fetch_timeout: Duration::from_secs(30),
+        })
+    }
+
+    pub(super) fn parse_map(map: &yaml::Hash, lookup_dir: &Path) -> anyhow::Result<Self> {
+        let v = g3_yaml::hash_get_required(map, CONFIG_KEY_SOURCE_URL)?;
+        let url = g3_yaml::value::as_url(v)
+            .context(format!("invalid url value for key {CONFIG_KEY_SOURCE_URL}"))?;
+        let mut config = UserDynamicHttpSource::new(url)?;
+
+        g3_yaml::foreach_kv(map, |k, v| {
+            config
+                .set(k, v, lookup_dir)
+                .context(format!("failed to parse key {k}"))
+        })?;
+
+        config.check()?;
+        Ok(config)
+    }
+
+    pub(super) fn parse_url(url: &Url) -> anyhow::Result<Self> {
+        let mut config = UserDynamicHttpSource::new(url.clone())?;
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml, lookup_dir: &Path) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            super::CONFIG_KEY_SOURCE_TYPE => Ok(()),
+            CONFIG_KEY_SOURCE_URL => Ok(()),
+            "tls_client" | "tls" => {
+                let builder = g3_yaml::value::as_rustls_client_config_builder(v, Some(lookup_dir))
+                    .context(format!(
+                        "invalid rustls tls client config value for key {k}"
+                    ))?;
+                let tls_client = builder
+                    .build()
+                    .context(format!("failed to build tls client config for key {k}"))?;
+                self.tls_client = Some(tls_client);
+                Ok(())
+            }
+            "tls_name" => {
+                let name = g3_yaml::value::as_rustls_server_name(v)
+                    .context(format!("invalid tls server name value for key {k}"))?;
+                self.tls_name = Some(name);
+                Ok(())
+            }
+            "format" => {
+                self.format = g3_yaml::value::as_config_file_format(v)
+                    .context(format!("invalid config file format value for key {k}"))?;
+                Ok(())
+            }
+            "conditional" | "conditional_fetch" => {
+                self.conditional = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
+            "signature_url" => {
+                let url =
+                    g3_yaml::value::as_url(v).context(format!("invalid url value for key {k}"))?;
+                self.signature_url = Some(url);
+                Ok(())
+            }
+            "signature_key" | "signature_public_key" => {
+                let key = crate::config::auth::jwt::as_public_key(v, Some(lookup_dir))
+                    .context(format!("invalid public key value for key {k}"))?;
+                self.signature_key = Some(key);
+                Ok(())
+            }
+            "max_header_size" => {
+                self.max_header_size = g3_yaml::humanize::as_usize(v)
+                    .context(format!("invalid humanize usize value for key {k}"))?;
+                Ok(())
+            }
+            "max_body_size" => {
+                self.max_body_size = g3_yaml::humanize::as_usize(v)
+                    .context(format!("invalid humanize usize value for key {k}"))?;
+                Ok(())
+            }
+            "connect_timeout" => {
+                self.connect_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "fetch_timeout" | "timeout" => {
+                self.fetch_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&mut self) -> anyhow::Result<()> {
+        match self.url.scheme() {
+            "http" => {}
+            "https" => {
+                if self.tls_client.is_none() {
+                    let tls_client = RustlsClientConfigBuilder::default()
+                        .build()
+                        .context("failed to build default tls client config")?;
+                    self.tls_client = Some(tls_client);
+                }
+            }
+            s => return Err(anyhow!("unsupported url scheme {s}")),
+        }
+        if self.tls_client.is_some() && self.tls_name.is_none() {
+            let tls_name = ServerName::try_from(self.server.host())
+                .map_err(|e| anyhow!("invalid tls server name: {e}"))?;
+            self.tls_name = Some(tls_name);
+        }
+        if self.signature_key.is_some() {
+            if self.signature_url.is_none() {
+                let sig_url = Url::parse(&format!("{}.sig", self.url.as_str()))
+                    .map_err(|e| anyhow!("unable to get default signature url: {e}"))?;
+                self.signature_url = Some(sig_url);
+            }
+        } else if self.signature_url.is_some() {
+            return Err(anyhow!("signature url is set but no signature key found"));
+        }
+        if let Some(sig_url) = &self.signature_url {
+            let sig_server = UpstreamAddr::try_from(sig_url)?;
+            if sig_url.scheme() != self.url.scheme() || sig_server != self.server {
+                return Err(anyhow!(
+                    "the signature url should be on the same server as the user url"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn fetch_cached_records(
+        &self,
+        cache: &Path,
+    ) -> anyhow::Result<Vec<UserConfig>> {
+        if cache.is_empty() {
+            return Ok(Vec::new());
+        }
+        let file_source = UserDynamicFileSource {
+            path: cache.to_path_buf(),
+            format: self.format.clone(),
+        };
+        file_source.fetch_records().await
+    }
+}
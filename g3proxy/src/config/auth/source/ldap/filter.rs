@@ -0,0 +1,258 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+/// The search filter defined in RFC 4515
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum LdapFilter {
+    And(Vec<LdapFilter>),
+    Or(Vec<LdapFilter>),
+    Not(Box<LdapFilter>),
+    Equality(String, Vec<u8>),
+    Substrings(LdapSubstringsFilter),
+    GreaterOrEqual(String, Vec<u8>),
+    LessOrEqual(String, Vec<u8>),
+    Present(String),
+    ApproxMatch(String, Vec<u8>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct LdapSubstringsFilter {
+    pub(crate) attr: String,
+    pub(crate) initial: Option<Vec<u8>>,
+    pub(crate) any: Vec<Vec<u8>>,
+    pub(crate) last: Option<Vec<u8>>,
+}
+
+impl Default for LdapFilter {
+    fn default() -> Self {
+        LdapFilter::Present("objectClass".to_string())
+    }
+}
+
+impl FromStr for LdapFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if !s.starts_with('(') {
+            // allow to omit the outer parentheses
+            return parse_item(s);
+        }
+        let (filter, left) = parse_filter(s)?;
+        if !left.is_empty() {
+            return Err(anyhow!("unexpected trailing data {left}"));
+        }
+        Ok(filter)
+    }
+}
+
+fn parse_filter(s: &str) -> anyhow::Result<(LdapFilter, &str)> {
+    let Some(s) = s.strip_prefix('(') else {
+        return Err(anyhow!("no opening parenthesis found"));
+    };
+    let (filter, s) = match s.chars().next() {
+        Some('&') => {
+            let (list, s) = parse_filter_list(&s[1..])?;
+            (LdapFilter::And(list), s)
+        }
+        Some('|') => {
+            let (list, s) = parse_filter_list(&s[1..])?;
+            (LdapFilter::Or(list), s)
+        }
+        Some('!') => {
+            let (filter, s) = parse_filter(&s[1..])?;
+            (LdapFilter::Not(Box::new(filter)), s)
+        }
+        Some(_) => {
+            let Some(p) = s.find(')') else {
+                return Err(anyhow!("no closing parenthesis found"));
+            };
+            (parse_item(&s[..p])?, &s[p..])
+        }
+        None => return Err(anyhow!("empty filter")),
+    };
+    match s.strip_prefix(')') {
+        Some(left) => Ok((filter, left)),
+        None => Err(anyhow!("no closing parenthesis found")),
+    }
+}
+
+fn parse_filter_list(mut s: &str) -> anyhow::Result<(Vec<LdapFilter>, &str)> {
+    let mut list = Vec::new();
+    while s.starts_with('(') {
+        let (filter, left) = parse_filter(s)?;
+        list.push(filter);
+        s = left;
+    }
+    if list.is_empty() {
+        return Err(anyhow!("empty filter list"));
+    }
+    Ok((list, s))
+}
+
+fn parse_item(s: &str) -> anyhow::Result<LdapFilter> {
+    let Some(p) = s.find('=') else {
+        return Err(anyhow!("no '=' found in filter item {s}"));
+    };
+    let value = &s[p + 1..];
+    let (attr, filter_type) = match s[..p].as_bytes().last() {
+        Some(b'~') => (&s[..p - 1], Some('~')),
+        Some(b'>') => (&s[..p - 1], Some('>')),
+        Some(b'<') => (&s[..p - 1], Some('<')),
+        _ => (&s[..p], None),
+    };
+    check_attr(attr)?;
+    let attr = attr.to_string();
+
+    match filter_type {
+        Some('~') => Ok(LdapFilter::ApproxMatch(attr, unescape(value)?)),
+        Some('>') => Ok(LdapFilter::GreaterOrEqual(attr, unescape(value)?)),
+        Some('<') => Ok(LdapFilter::LessOrEqual(attr, unescape(value)?)),
+        _ => {
+            if value == "*" {
+                return Ok(LdapFilter::Present(attr));
+            }
+            if !value.contains('*') {
+                return Ok(LdapFilter::Equality(attr, unescape(value)?));
+            }
+
+            let mut parts = value.split('*').collect::<Vec<_>>();
+            let last = parts.pop().filter(|s| !s.is_empty()).map(unescape);
+            let initial = parts.first().filter(|s| !s.is_empty()).map(|s| unescape(s));
+            let mut any = Vec::new();
+            for s in parts.iter().skip(1) {
+                if s.is_empty() {
+                    return Err(anyhow!(
+                        "empty substring found in filter item value {value}"
+                    ));
+                }
+                any.push(unescape(s)?);
+            }
+            Ok(LdapFilter::Substrings(LdapSubstringsFilter {
+                attr,
+                initial: initial.transpose()?,
+                any,
+                last: last.transpose()?,
+            }))
+        }
+    }
+}
+
+fn check_attr(attr: &str) -> anyhow::Result<()> {
+    if attr.is_empty() {
+        return Err(anyhow!("empty attribute description"));
+    }
+    if !attr
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ';'))
+    {
+        return Err(anyhow!("invalid attribute description {attr}"));
+    }
+    Ok(())
+}
+
+fn unescape(s: &str) -> anyhow::Result<Vec<u8>> {
+    let b = s.as_bytes();
+    let mut value = Vec::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len() {
+        match b[i] {
+            b'\\' => {
+                let Some(hex) = b.get(i + 1..i + 3) else {
+                    return Err(anyhow!("invalid escape sequence in value {s}"));
+                };
+                let (Some(h), Some(l)) = (hex_value(hex[0]), hex_value(hex[1])) else {
+                    return Err(anyhow!("invalid escape sequence in value {s}"));
+                };
+                value.push((h << 4) | l);
+                i += 3;
+            }
+            b'(' | b')' | b'*' => {
+                return Err(anyhow!("unescaped special char found in value {s}"));
+            }
+            c => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+    Ok(value)
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_simple() {
+        let f = LdapFilter::from_str("(uid=test)").unwrap();
+        assert_eq!(f, LdapFilter::Equality("uid".to_string(), b"test".to_vec()));
+
+        let f = LdapFilter::from_str("objectClass=*").unwrap();
+        assert_eq!(f, LdapFilter::Present("objectClass".to_string()));
+
+        let f = LdapFilter::from_str("(cn=a\\2ab*c*d)").unwrap();
+        assert_eq!(
+            f,
+            LdapFilter::Substrings(LdapSubstringsFilter {
+                attr: "cn".to_string(),
+                initial: Some(b"a*b".to_vec()),
+                any: vec![b"c".to_vec()],
+                last: Some(b"d".to_vec()),
+            })
+        );
+
+        assert!(LdapFilter::from_str("(uid=a(b)").is_err());
+        assert!(LdapFilter::from_str("(=a)").is_err());
+    }
+
+    #[test]
+    fn parse_nested() {
+        let f = LdapFilter::from_str("(&(objectClass=person)(!(uidNumber<=1000))(|(ou=a)(ou=b)))")
+            .unwrap();
+        assert_eq!(
+            f,
+            LdapFilter::And(vec![
+                LdapFilter::Equality("objectClass".to_string(), b"person".to_vec()),
+                LdapFilter::Not(Box::new(LdapFilter::LessOrEqual(
+                    "uidNumber".to_string(),
+                    b"1000".to_vec()
+                ))),
+                LdapFilter::Or(vec![
+                    LdapFilter::Equality("ou".to_string(), b"a".to_vec()),
+                    LdapFilter::Equality("ou".to_string(), b"b".to_vec()),
+                ]),
+            ])
+        );
+
+        assert!(LdapFilter::from_str("(&)").is_err());
+        assert!(LdapFilter::from_str("(&(a=b)").is_err());
+        assert!(LdapFilter::from_str("(a=b))").is_err());
+    }
+}
@@ -0,0 +1,219 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use nix::NixPath;
+use rustls::ServerName;
+use yaml_rust::{yaml, Yaml};
+
+use g3_types::fs::ConfigFileFormat;
+use g3_types::net::{RustlsClientConfig, UpstreamAddr};
+
+use super::file::UserDynamicFileSource;
+use crate::config::auth::UserConfig;
+
+mod filter;
+pub(crate) use filter::{LdapFilter, LdapSubstringsFilter};
+
+const LDAP_DEFAULT_PORT: u16 = 389;
+const LDAPS_DEFAULT_PORT: u16 = 636;
+
+#[derive(Clone)]
+pub(crate) struct UserDynamicLdapSource {
+    pub(crate) server: UpstreamAddr,
+    pub(crate) tls_client: Option<RustlsClientConfig>,
+    pub(crate) tls_name: Option<ServerName>,
+    pub(crate) bind_dn: String,
+    pub(crate) bind_password: String,
+    pub(crate) base_dn: String,
+    pub(crate) filter: LdapFilter,
+    pub(crate) page_size: u32,
+    pub(crate) username_attribute: String,
+    pub(crate) attributes: BTreeMap<String, String>,
+    pub(crate) user_template: yaml::Hash,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) fetch_timeout: Duration,
+}
+
+impl Default for UserDynamicLdapSource {
+    fn default() -> Self {
+        UserDynamicLdapSource {
+            server: UpstreamAddr::empty(),
+            tls_client: None,
+            tls_name: None,
+            bind_dn: String::new(),
+            bind_password: String::new(),
+            base_dn: String::new(),
+            filter: LdapFilter::default(),
+            page_size: 500,
+            username_attribute: "uid".to_string(),
+            attributes: BTreeMap::new(),
+            user_template: yaml::Hash::new(),
+            connect_timeout: Duration::from_secs(10),
+            fetch_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl UserDynamicLdapSource {
+    pub(super) fn parse_map(map: &yaml::Hash, lookup_dir: &Path) -> anyhow::Result<Self> {
+        let mut config = UserDynamicLdapSource::default();
+
+        g3_yaml::foreach_kv(map, |k, v| config.set(k, v, lookup_dir))?;
+
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml, lookup_dir: &Path) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            super::CONFIG_KEY_SOURCE_TYPE => Ok(()),
+            "server" | "address" | "addr" => {
+                self.server = g3_yaml::value::as_upstream_addr(v, 0)
+                    .context(format!("invalid upstream address value for key {k}"))?;
+                Ok(())
+            }
+            "tls_client" | "tls" => {
+                let builder = g3_yaml::value::as_rustls_client_config_builder(v, Some(lookup_dir))
+                    .context(format!(
+                        "invalid rustls tls client config value for key {k}"
+                    ))?;
+                let tls_client = builder
+                    .build()
+                    .context(format!("failed to build tls client config for key {k}"))?;
+                self.tls_client = Some(tls_client);
+                Ok(())
+            }
+            "tls_name" => {
+                let name = g3_yaml::value::as_rustls_server_name(v)
+                    .context(format!("invalid tls server name value for key {k}"))?;
+                self.tls_name = Some(name);
+                Ok(())
+            }
+            "bind_dn" | "bind_username" => {
+                self.bind_dn = g3_yaml::value::as_string(v)?;
+                Ok(())
+            }
+            "bind_password" => {
+                self.bind_password = g3_yaml::value::as_string(v)?;
+                Ok(())
+            }
+            "base_dn" | "search_base" => {
+                self.base_dn = g3_yaml::value::as_string(v)?;
+                Ok(())
+            }
+            "filter" | "search_filter" => {
+                let s = g3_yaml::value::as_string(v)?;
+                self.filter = LdapFilter::from_str(&s)
+                    .context(format!("invalid ldap search filter value for key {k}"))?;
+                Ok(())
+            }
+            "page_size" => {
+                self.page_size = g3_yaml::value::as_u32(v)?;
+                Ok(())
+            }
+            "username_attribute" | "name_attribute" => {
+                self.username_attribute = g3_yaml::value::as_string(v)?;
+                Ok(())
+            }
+            "attributes" | "attribute_map" => {
+                if let Yaml::Hash(map) = v {
+                    g3_yaml::foreach_kv(map, |attr, v| {
+                        let key = g3_yaml::value::as_string(v).context(format!(
+                            "invalid user config key value for attribute {attr}"
+                        ))?;
+                        self.attributes.insert(attr.to_string(), key);
+                        Ok(())
+                    })
+                } else {
+                    Err(anyhow!("invalid map value for key {k}"))
+                }
+            }
+            "user_template" | "template" => {
+                if let Yaml::Hash(map) = v {
+                    // make sure the template itself is valid
+                    let mut user = map.clone();
+                    user.insert(
+                        Yaml::String("name".to_string()),
+                        Yaml::String("template".to_string()),
+                    );
+                    UserConfig::parse_yaml(&user)
+                        .context(format!("invalid user config value for key {k}"))?;
+                    self.user_template = map.clone();
+                    Ok(())
+                } else {
+                    Err(anyhow!("invalid map value for key {k}"))
+                }
+            }
+            "connect_timeout" => {
+                self.connect_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "fetch_timeout" | "timeout" => {
+                self.fetch_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&mut self) -> anyhow::Result<()> {
+        if self.server.is_empty() {
+            return Err(anyhow!("no server address is set"));
+        }
+        if self.server.port() == 0 {
+            let port = if self.tls_client.is_some() {
+                LDAPS_DEFAULT_PORT
+            } else {
+                LDAP_DEFAULT_PORT
+            };
+            self.server.set_port(port);
+        }
+        if self.tls_client.is_some() && self.tls_name.is_none() {
+            let tls_name = ServerName::try_from(self.server.host())
+                .map_err(|e| anyhow!("invalid tls server name: {e}"))?;
+            self.tls_name = Some(tls_name);
+        }
+        if self.base_dn.is_empty() {
+            return Err(anyhow!("no base dn is set"));
+        }
+        if self.username_attribute.is_empty() {
+            return Err(anyhow!("no username attribute is set"));
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn fetch_cached_records(
+        &self,
+        cache: &Path,
+    ) -> anyhow::Result<Vec<UserConfig>> {
+        if cache.is_empty() {
+            return Ok(Vec::new());
+        }
+        let file_source = UserDynamicFileSource {
+            path: cache.to_path_buf(),
+            format: ConfigFileFormat::Yaml,
+        };
+        file_source.fetch_records().await
+    }
+}
@@ -0,0 +1,111 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+
+use super::{TimeWindow, TimeWindowZone, UserTimeWindowConfig};
+
+impl TimeWindow {
+    fn parse_json(v: &Value) -> anyhow::Result<Self> {
+        if let Value::Object(map) = v {
+            let mut window = TimeWindow::default();
+            for (k, v) in map {
+                window.set_json(k, v)?;
+            }
+            window.check()?;
+            Ok(window)
+        } else {
+            Err(anyhow!("json value type for 'time window' should be 'map'"))
+        }
+    }
+
+    fn set_json(&mut self, k: &str, v: &Value) -> anyhow::Result<()> {
+        match g3_json::key::normalize(k).as_str() {
+            "days" | "weekdays" => match v {
+                Value::String(s) => self
+                    .set_days(s)
+                    .context(format!("invalid days of week value for key {k}")),
+                Value::Array(seq) => {
+                    self.days = 0;
+                    for (i, v) in seq.iter().enumerate() {
+                        let s = g3_json::value::as_string(v)?;
+                        self.add_days(&s)
+                            .context(format!("invalid days of week value for {k}#{i}"))?;
+                    }
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid value type for key {k}")),
+            },
+            "time" | "hours" => {
+                let s = g3_json::value::as_string(v)?;
+                self.set_time_range(&s)
+                    .context(format!("invalid time range value for key {k}"))
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+}
+
+impl UserTimeWindowConfig {
+    pub(crate) fn parse_json(v: &Value) -> anyhow::Result<Self> {
+        let mut config = UserTimeWindowConfig::default();
+        match v {
+            Value::Object(map) => {
+                for (k, v) in map {
+                    config.set_json(k, v)?;
+                }
+            }
+            Value::Array(_) => config.set_windows_json(v)?,
+            _ => {
+                return Err(anyhow!(
+                    "json value type for 'user time window' should be 'map' or 'array'"
+                ))
+            }
+        }
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set_json(&mut self, k: &str, v: &Value) -> anyhow::Result<()> {
+        match g3_json::key::normalize(k).as_str() {
+            "timezone" | "tz" => {
+                let s = g3_json::value::as_string(v)?;
+                self.zone = s
+                    .parse::<TimeWindowZone>()
+                    .context(format!("invalid timezone value for key {k}"))?;
+                Ok(())
+            }
+            "windows" | "window" => self
+                .set_windows_json(v)
+                .context(format!("invalid time window value for key {k}")),
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn set_windows_json(&mut self, v: &Value) -> anyhow::Result<()> {
+        if let Value::Array(seq) = v {
+            for (i, v) in seq.iter().enumerate() {
+                let window =
+                    TimeWindow::parse_json(v).context(format!("invalid value for #{i}"))?;
+                self.windows.push(window);
+            }
+        } else {
+            let window = TimeWindow::parse_json(v)?;
+            self.windows.push(window);
+        }
+        Ok(())
+    }
+}
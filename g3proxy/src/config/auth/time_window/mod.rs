@@ -0,0 +1,260 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDate, Timelike, Utc, Weekday};
+
+mod json;
+mod yaml;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum TimeWindowZone {
+    #[default]
+    Utc,
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl FromStr for TimeWindowZone {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utc" | "z" => Ok(TimeWindowZone::Utc),
+            "local" => Ok(TimeWindowZone::Local),
+            _ => {
+                let offset = FixedOffset::from_str(s)
+                    .map_err(|e| anyhow!("invalid timezone offset {s}: {e}"))?;
+                Ok(TimeWindowZone::Fixed(offset))
+            }
+        }
+    }
+}
+
+impl TimeWindowZone {
+    pub(crate) fn date(&self, now: &DateTime<Utc>) -> NaiveDate {
+        match self {
+            TimeWindowZone::Utc => now.date_naive(),
+            TimeWindowZone::Local => now.with_timezone(&Local).date_naive(),
+            TimeWindowZone::Fixed(offset) => now.with_timezone(offset).date_naive(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TimeWindow {
+    /// bit 0 for Monday, bit 6 for Sunday
+    days: u8,
+    /// minutes since midnight, inclusive
+    start: u32,
+    /// minutes since midnight, exclusive, may be less than start for overnight windows
+    end: u32,
+}
+
+impl Default for TimeWindow {
+    fn default() -> Self {
+        TimeWindow {
+            days: 0x7f,
+            start: 0,
+            end: MINUTES_PER_DAY,
+        }
+    }
+}
+
+impl TimeWindow {
+    fn has_day(&self, day: Weekday) -> bool {
+        self.days & (1 << day.num_days_from_monday()) != 0
+    }
+
+    fn contains(&self, day: Weekday, minute: u32) -> bool {
+        if self.start < self.end {
+            self.has_day(day) && minute >= self.start && minute < self.end
+        } else {
+            // overnight window, the part after midnight belongs to the previous day
+            (self.has_day(day) && minute >= self.start)
+                || (self.has_day(day.pred()) && minute < self.end)
+        }
+    }
+
+    fn set_days(&mut self, s: &str) -> anyhow::Result<()> {
+        let mut days = 0u8;
+        for part in s.split(',') {
+            days |= parse_days(part.trim())?;
+        }
+        self.days = days;
+        Ok(())
+    }
+
+    fn add_days(&mut self, s: &str) -> anyhow::Result<()> {
+        self.days |= parse_days(s.trim())?;
+        Ok(())
+    }
+
+    fn set_time_range(&mut self, s: &str) -> anyhow::Result<()> {
+        let Some((start, end)) = s.split_once('-') else {
+            return Err(anyhow!("time range should be in format 'HH:MM-HH:MM'"));
+        };
+        let start = parse_minute(start.trim()).context("invalid start time")?;
+        let end = parse_minute(end.trim()).context("invalid end time")?;
+        if start == end {
+            return Err(anyhow!("start time and end time should not be the same"));
+        }
+        if start == MINUTES_PER_DAY {
+            return Err(anyhow!("start time should be less than 24:00"));
+        }
+        self.start = start;
+        self.end = end;
+        Ok(())
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.days == 0 {
+            return Err(anyhow!("no days of week set"));
+        }
+        Ok(())
+    }
+}
+
+fn parse_weekday(s: &str) -> anyhow::Result<Weekday> {
+    Weekday::from_str(s).map_err(|_| anyhow!("invalid day of week {s}"))
+}
+
+fn parse_days(s: &str) -> anyhow::Result<u8> {
+    match s.to_lowercase().as_str() {
+        "*" | "all" | "everyday" => return Ok(0x7f),
+        "weekday" | "weekdays" => return Ok(0x1f),
+        "weekend" | "weekends" => return Ok(0x60),
+        _ => {}
+    }
+    let (first, last) = match s.split_once('-') {
+        Some((first, last)) => (parse_weekday(first.trim())?, parse_weekday(last.trim())?),
+        None => {
+            let day = parse_weekday(s)?;
+            (day, day)
+        }
+    };
+    let mut days = 0u8;
+    let mut day = first;
+    loop {
+        days |= 1 << day.num_days_from_monday();
+        if day == last {
+            break;
+        }
+        day = day.succ();
+    }
+    Ok(days)
+}
+
+fn parse_minute(s: &str) -> anyhow::Result<u32> {
+    let (h, m) = s.split_once(':').unwrap_or((s, "0"));
+    let h = u32::from_str(h).map_err(|e| anyhow!("invalid hour value {h}: {e}"))?;
+    let m = u32::from_str(m).map_err(|e| anyhow!("invalid minute value {m}: {e}"))?;
+    if m >= 60 {
+        return Err(anyhow!("minute value should be less than 60"));
+    }
+    let minute = h * 60 + m;
+    if minute > MINUTES_PER_DAY {
+        return Err(anyhow!("time should not be later than 24:00"));
+    }
+    Ok(minute)
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct UserTimeWindowConfig {
+    zone: TimeWindowZone,
+    windows: Vec<TimeWindow>,
+}
+
+impl UserTimeWindowConfig {
+    fn check(&self) -> anyhow::Result<()> {
+        if self.windows.is_empty() {
+            return Err(anyhow!("no time window set"));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_allowed(&self, now: &DateTime<Utc>) -> bool {
+        let (day, minute) = match self.zone {
+            TimeWindowZone::Utc => (now.weekday(), now.hour() * 60 + now.minute()),
+            TimeWindowZone::Local => {
+                let t = now.with_timezone(&Local);
+                (t.weekday(), t.hour() * 60 + t.minute())
+            }
+            TimeWindowZone::Fixed(offset) => {
+                let t = now.with_timezone(&offset);
+                (t.weekday(), t.hour() * 60 + t.minute())
+            }
+        };
+        self.windows.iter().any(|w| w.contains(day, minute))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn window(days: &str, time: &str) -> TimeWindow {
+        let mut w = TimeWindow::default();
+        w.set_days(days).unwrap();
+        w.set_time_range(time).unwrap();
+        w
+    }
+
+    #[test]
+    fn business_hours() {
+        let config = UserTimeWindowConfig {
+            zone: TimeWindowZone::from_str("+08:00").unwrap(),
+            windows: vec![window("mon-fri", "09:00-18:00")],
+        };
+        // 2024-01-01 is a Monday
+        let t = Utc.with_ymd_and_hms(2024, 1, 1, 1, 0, 0).unwrap();
+        assert!(config.is_allowed(&t));
+        let t = Utc.with_ymd_and_hms(2024, 1, 1, 0, 59, 0).unwrap();
+        assert!(!config.is_allowed(&t));
+        let t = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        assert!(!config.is_allowed(&t));
+        let t = Utc.with_ymd_and_hms(2024, 1, 6, 2, 0, 0).unwrap();
+        assert!(!config.is_allowed(&t));
+    }
+
+    #[test]
+    fn overnight() {
+        let config = UserTimeWindowConfig {
+            zone: TimeWindowZone::Utc,
+            windows: vec![window("fri", "22:00-06:00")],
+        };
+        let t = Utc.with_ymd_and_hms(2024, 1, 5, 23, 0, 0).unwrap();
+        assert!(config.is_allowed(&t));
+        let t = Utc.with_ymd_and_hms(2024, 1, 6, 5, 59, 0).unwrap();
+        assert!(config.is_allowed(&t));
+        let t = Utc.with_ymd_and_hms(2024, 1, 6, 23, 0, 0).unwrap();
+        assert!(!config.is_allowed(&t));
+        let t = Utc.with_ymd_and_hms(2024, 1, 5, 5, 0, 0).unwrap();
+        assert!(!config.is_allowed(&t));
+    }
+
+    #[test]
+    fn days() {
+        assert_eq!(parse_days("sat-mon").unwrap(), 0x61);
+        assert_eq!(parse_days("weekend").unwrap(), 0x60);
+        assert_eq!(parse_days("Wednesday").unwrap(), 0x04);
+        assert!(parse_days("xyz").is_err());
+    }
+}
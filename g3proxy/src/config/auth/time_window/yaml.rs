@@ -0,0 +1,134 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use super::{TimeWindow, TimeWindowZone, UserTimeWindowConfig};
+
+impl TimeWindow {
+    fn parse_yaml(v: &Yaml) -> anyhow::Result<Self> {
+        if let Yaml::Hash(map) = v {
+            let mut window = TimeWindow::default();
+            g3_yaml::foreach_kv(map, |k, v| window.set_yaml(k, v))?;
+            window.check()?;
+            Ok(window)
+        } else {
+            Err(anyhow!("yaml value type for 'time window' should be 'map'"))
+        }
+    }
+
+    fn set_yaml(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "days" | "weekdays" => match v {
+                Yaml::String(s) => self
+                    .set_days(s)
+                    .context(format!("invalid days of week value for key {k}")),
+                Yaml::Array(seq) => {
+                    self.days = 0;
+                    for (i, v) in seq.iter().enumerate() {
+                        let s = g3_yaml::value::as_string(v)?;
+                        self.add_days(&s)
+                            .context(format!("invalid days of week value for {k}#{i}"))?;
+                    }
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid value type for key {k}")),
+            },
+            "time" | "hours" => {
+                let s = g3_yaml::value::as_string(v)?;
+                self.set_time_range(&s)
+                    .context(format!("invalid time range value for key {k}"))
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+}
+
+impl UserTimeWindowConfig {
+    pub(crate) fn parse_yaml(v: &Yaml) -> anyhow::Result<Self> {
+        let mut config = UserTimeWindowConfig::default();
+        match v {
+            Yaml::Hash(map) => g3_yaml::foreach_kv(map, |k, v| config.set_yaml(k, v))?,
+            Yaml::Array(_) => config.set_windows_yaml(v)?,
+            _ => {
+                return Err(anyhow!(
+                    "yaml value type for 'user time window' should be 'map' or 'seq'"
+                ))
+            }
+        }
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set_yaml(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "timezone" | "tz" => {
+                let s = g3_yaml::value::as_string(v)?;
+                self.zone = s
+                    .parse::<TimeWindowZone>()
+                    .context(format!("invalid timezone value for key {k}"))?;
+                Ok(())
+            }
+            "windows" | "window" => self
+                .set_windows_yaml(v)
+                .context(format!("invalid time window value for key {k}")),
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn set_windows_yaml(&mut self, v: &Yaml) -> anyhow::Result<()> {
+        if let Yaml::Array(seq) = v {
+            for (i, v) in seq.iter().enumerate() {
+                let window =
+                    TimeWindow::parse_yaml(v).context(format!("invalid value for #{i}"))?;
+                self.windows.push(window);
+            }
+        } else {
+            let window = TimeWindow::parse_yaml(v)?;
+            self.windows.push(window);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn parse() {
+        let doc = r#"
+          timezone: "+08:00"
+          windows:
+            - days: mon-fri
+              time: "09:00-18:00"
+            - days: [sat]
+              time: "10:00-12:00"
+        "#;
+        let v = YamlLoader::load_from_str(doc).unwrap();
+        let config = UserTimeWindowConfig::parse_yaml(&v[0]).unwrap();
+        assert_eq!(config.windows.len(), 2);
+        assert_eq!(config.windows[0].days, 0x1f);
+        assert_eq!(config.windows[1].start, 600);
+
+        let doc = "[{days: sun}]";
+        let v = YamlLoader::load_from_str(doc).unwrap();
+        let config = UserTimeWindowConfig::parse_yaml(&v[0]).unwrap();
+        assert_eq!(config.zone, TimeWindowZone::Utc);
+        assert_eq!(config.windows[0].end, 1440);
+    }
+}
@@ -0,0 +1,77 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use g3_types::net::{TcpSockSpeedLimitConfig, UdpSockSpeedLimitConfig};
+
+use super::TimeWindowZone;
+
+mod json;
+mod yaml;
+
+mod store;
+pub(crate) use store::{TrafficQuotaRedisStore, TrafficQuotaStoreConfig};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum TrafficQuotaExceedAction {
+    #[default]
+    Block,
+    Throttle,
+}
+
+impl FromStr for TrafficQuotaExceedAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "block" | "deny" | "forbid" => Ok(TrafficQuotaExceedAction::Block),
+            "throttle" | "limit" => Ok(TrafficQuotaExceedAction::Throttle),
+            _ => Err(anyhow!("invalid traffic quota exceed action {s}")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct UserTrafficQuotaConfig {
+    pub(crate) daily: Option<u64>,
+    pub(crate) monthly: Option<u64>,
+    pub(crate) timezone: TimeWindowZone,
+    pub(crate) exceed_action: TrafficQuotaExceedAction,
+    pub(crate) throttle_tcp_sock_speed_limit: TcpSockSpeedLimitConfig,
+    pub(crate) throttle_udp_sock_speed_limit: UdpSockSpeedLimitConfig,
+}
+
+impl UserTrafficQuotaConfig {
+    fn check(&self) -> anyhow::Result<()> {
+        if self.daily.is_none() && self.monthly.is_none() {
+            return Err(anyhow!("neither daily nor monthly quota is set"));
+        }
+        if self.exceed_action == TrafficQuotaExceedAction::Throttle
+            && self.throttle_tcp_sock_speed_limit == TcpSockSpeedLimitConfig::default()
+            && self.throttle_udp_sock_speed_limit == UdpSockSpeedLimitConfig::default()
+        {
+            return Err(anyhow!("no throttle speed limit set for throttle action"));
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn throttle(&self) -> bool {
+        self.exceed_action == TrafficQuotaExceedAction::Throttle
+    }
+}
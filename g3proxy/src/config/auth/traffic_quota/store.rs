@@ -0,0 +1,161 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use redis::{ConnectionAddr, ConnectionInfo, IntoConnectionInfo, RedisConnectionInfo, RedisResult};
+use yaml_rust::{yaml, Yaml};
+
+use g3_types::net::UpstreamAddr;
+
+const CONFIG_KEY_STORE_TYPE: &str = "type";
+
+const REDIS_DEFAULT_PORT: u16 = 6379;
+const REDIS_DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REDIS_DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum TrafficQuotaStoreConfig {
+    File(PathBuf),
+    Redis(TrafficQuotaRedisStore),
+}
+
+impl TrafficQuotaStoreConfig {
+    pub(crate) fn parse_yaml(v: &Yaml, lookup_dir: &Path) -> anyhow::Result<Self> {
+        match v {
+            Yaml::Hash(map) => {
+                let store_type = g3_yaml::hash_get_required_str(map, CONFIG_KEY_STORE_TYPE)?;
+                match g3_yaml::key::normalize(store_type).as_str() {
+                    "file" => {
+                        let v = g3_yaml::hash_get_required(map, "path")?;
+                        let path = g3_yaml::value::as_file_path(v, lookup_dir, true)
+                            .context("invalid file path value for key path")?;
+                        Ok(TrafficQuotaStoreConfig::File(path))
+                    }
+                    "redis" => {
+                        let store = TrafficQuotaRedisStore::parse_map(map)?;
+                        Ok(TrafficQuotaStoreConfig::Redis(store))
+                    }
+                    _ => Err(anyhow!("unsupported traffic quota store type {store_type}")),
+                }
+            }
+            Yaml::String(_) => {
+                let path = g3_yaml::value::as_file_path(v, lookup_dir, true)
+                    .context("invalid file path value")?;
+                Ok(TrafficQuotaStoreConfig::File(path))
+            }
+            _ => Err(anyhow!(
+                "yaml value type for 'traffic quota store' should be 'map' or 'string'"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TrafficQuotaRedisStore {
+    addr: UpstreamAddr,
+    db: i64,
+    username: Option<String>,
+    password: Option<String>,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) read_timeout: Duration,
+    pub(crate) hash_key: String,
+    pub(crate) ttl: Option<Duration>,
+}
+
+impl TrafficQuotaRedisStore {
+    fn parse_map(map: &yaml::Hash) -> anyhow::Result<Self> {
+        let v = g3_yaml::hash_get_required(map, "addr")?;
+        let addr = g3_yaml::value::as_upstream_addr(v, REDIS_DEFAULT_PORT)
+            .context("invalid upstream addr value for key addr")?;
+        let mut store = TrafficQuotaRedisStore {
+            addr,
+            db: 0,
+            username: None,
+            password: None,
+            connect_timeout: REDIS_DEFAULT_CONNECT_TIMEOUT,
+            read_timeout: REDIS_DEFAULT_READ_TIMEOUT,
+            hash_key: String::new(),
+            ttl: None,
+        };
+
+        g3_yaml::foreach_kv(map, |k, v| {
+            store.set(k, v).context(format!("failed to parse key {k}"))
+        })?;
+
+        if store.hash_key.is_empty() {
+            return Err(anyhow!("no hash key set"));
+        }
+        Ok(store)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            CONFIG_KEY_STORE_TYPE => Ok(()),
+            "addr" => Ok(()),
+            "db" => {
+                self.db =
+                    g3_yaml::value::as_i64(v).context(format!("invalid int value for key {k}"))?;
+                Ok(())
+            }
+            "username" => {
+                let username = g3_yaml::value::as_string(v)?;
+                self.username = Some(username);
+                Ok(())
+            }
+            "password" => {
+                let password = g3_yaml::value::as_string(v)?;
+                self.password = Some(password);
+                Ok(())
+            }
+            "connect_timeout" => {
+                self.connect_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "read_timeout" => {
+                self.read_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "hash_key" | "key" => {
+                self.hash_key = g3_yaml::value::as_string(v)?;
+                Ok(())
+            }
+            "ttl" | "expire" => {
+                let ttl = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                self.ttl = if ttl.as_secs() == 0 { None } else { Some(ttl) };
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+}
+
+impl IntoConnectionInfo for &TrafficQuotaRedisStore {
+    fn into_connection_info(self) -> RedisResult<ConnectionInfo> {
+        Ok(ConnectionInfo {
+            addr: ConnectionAddr::Tcp(self.addr.host().to_string(), self.addr.port()),
+            redis: RedisConnectionInfo {
+                db: self.db,
+                username: self.username.clone(),
+                password: self.password.clone(),
+            },
+        })
+    }
+}
@@ -0,0 +1,77 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use super::{TrafficQuotaExceedAction, UserTrafficQuotaConfig};
+use crate::config::auth::TimeWindowZone;
+
+impl UserTrafficQuotaConfig {
+    pub(crate) fn parse_yaml(v: &Yaml) -> anyhow::Result<Self> {
+        if let Yaml::Hash(map) = v {
+            let mut config = UserTrafficQuotaConfig::default();
+            g3_yaml::foreach_kv(map, |k, v| config.set_yaml(k, v))?;
+            config.check()?;
+            Ok(config)
+        } else {
+            Err(anyhow!(
+                "yaml value type for 'user traffic quota' should be 'map'"
+            ))
+        }
+    }
+
+    fn set_yaml(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "daily" => {
+                let size = g3_yaml::humanize::as_usize(v)
+                    .context(format!("invalid humanize usize value for key {k}"))?;
+                self.daily = Some(size as u64);
+                Ok(())
+            }
+            "monthly" => {
+                let size = g3_yaml::humanize::as_usize(v)
+                    .context(format!("invalid humanize usize value for key {k}"))?;
+                self.monthly = Some(size as u64);
+                Ok(())
+            }
+            "timezone" | "tz" => {
+                let s = g3_yaml::value::as_string(v)?;
+                self.timezone = s
+                    .parse::<TimeWindowZone>()
+                    .context(format!("invalid timezone value for key {k}"))?;
+                Ok(())
+            }
+            "exceed_action" | "action" => {
+                let s = g3_yaml::value::as_string(v)?;
+                self.exceed_action = s
+                    .parse::<TrafficQuotaExceedAction>()
+                    .context(format!("invalid exceed action value for key {k}"))?;
+                Ok(())
+            }
+            "throttle_tcp_sock_speed_limit" | "throttle_tcp" => {
+                self.throttle_tcp_sock_speed_limit = g3_yaml::value::as_tcp_sock_speed_limit(v)
+                    .context(format!("invalid tcp socket speed limit value for key {k}"))?;
+                Ok(())
+            }
+            "throttle_udp_sock_speed_limit" | "throttle_udp" => {
+                self.throttle_udp_sock_speed_limit = g3_yaml::value::as_udp_sock_speed_limit(v)
+                    .context(format!("invalid udp socket speed limit value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+}
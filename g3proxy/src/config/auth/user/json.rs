@@ -21,7 +21,9 @@ use serde_json::{Map, Value};
 
 use g3_types::route::EgressPathSelection;
 
-use super::{PasswordToken, UserConfig, UserSiteConfig};
+use super::{
+    PasswordToken, UserConfig, UserSiteConfig, UserTimeWindowConfig, UserTrafficQuotaConfig,
+};
 
 impl UserConfig {
     pub(crate) fn parse_json(map: &Map<String, Value>) -> anyhow::Result<Self> {
@@ -57,6 +59,18 @@ impl UserConfig {
                 self.block_and_delay = Some(delay);
                 Ok(())
             }
+            "time_window" | "access_time_window" => {
+                let config = UserTimeWindowConfig::parse_json(v)
+                    .context(format!("invalid user time window value for key {k}"))?;
+                self.time_window = Some(config);
+                Ok(())
+            }
+            "traffic_quota" => {
+                let config = UserTrafficQuotaConfig::parse_json(v)
+                    .context(format!("invalid user traffic quota value for key {k}"))?;
+                self.traffic_quota = Some(config);
+                Ok(())
+            }
             "tcp_connect" => {
                 let config = g3_json::value::as_tcp_connect_config(v)
                     .context(format!("invalid tcp connect config value for key {k}"))?;
@@ -73,6 +87,12 @@ impl UserConfig {
                     .context(format!("invalid udp socket speed limit value for key {k}"))?;
                 Ok(())
             }
+            "tcp_stream_delay" | "tcp_delay" => {
+                let delay = g3_json::value::as_stream_delay_config(v)
+                    .context(format!("invalid stream delay config value for key {k}"))?;
+                self.tcp_stream_delay = Some(delay);
+                Ok(())
+            }
             "tcp_remote_keepalive" => {
                 self.tcp_remote_keepalive = g3_json::value::as_tcp_keepalive_config(v)
                     .context(format!("invalid tcp keepalive config value for key {k}"))?;
@@ -124,6 +144,11 @@ impl UserConfig {
                     .context(format!("invalid usize value for key {k}"))?;
                 Ok(())
             }
+            "connection_max_alive" | "connection_alive_max" => {
+                self.connection_alive_max = g3_json::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
             "ingress_network_filter" | "ingress_net_filter" => {
                 let filter = g3_json::value::acl::as_ingress_network_rule_builder(v).context(
                     format!("invalid ingress network acl rule value for key {k}"),
@@ -207,6 +232,12 @@ impl UserConfig {
                 .audit
                 .parse_json(v)
                 .context(format!("invalid user audit config value for key {k}")),
+            "escaper" => {
+                let name = g3_json::value::as_metrics_name(v)
+                    .context(format!("invalid metrics name value for key {k}"))?;
+                self.escaper = Some(name);
+                Ok(())
+            }
             "egress_path" => {
                 self.egress_path_selection = Arc::new(EgressPathSelection::JsonValue(v.clone()));
                 Ok(())
@@ -28,13 +28,15 @@ use g3_types::acl_set::AclDstHostRuleSetBuilder;
 use g3_types::limit::RateLimitQuotaConfig;
 use g3_types::metrics::MetricsName;
 use g3_types::net::{
-    HttpKeepAliveConfig, TcpConnectConfig, TcpKeepAliveConfig, TcpMiscSockOpts,
+    HttpKeepAliveConfig, StreamDelayConfig, TcpConnectConfig, TcpKeepAliveConfig, TcpMiscSockOpts,
     TcpSockSpeedLimitConfig, UdpMiscSockOpts, UdpSockSpeedLimitConfig,
 };
 use g3_types::resolve::{ResolveRedirectionBuilder, ResolveStrategy};
 use g3_types::route::EgressPathSelection;
 
-use super::{PasswordToken, UserAuditConfig, UserSiteConfig};
+use super::{
+    PasswordToken, UserAuditConfig, UserSiteConfig, UserTimeWindowConfig, UserTrafficQuotaConfig,
+};
 
 mod json;
 mod yaml;
@@ -46,6 +48,8 @@ pub(crate) struct UserConfig {
     expire_datetime: Option<DateTime<Utc>>,
     pub(crate) audit: UserAuditConfig,
     pub(crate) block_and_delay: Option<Duration>,
+    pub(crate) time_window: Option<UserTimeWindowConfig>,
+    pub(crate) traffic_quota: Option<UserTrafficQuotaConfig>,
     pub(crate) tcp_connect: Option<TcpConnectConfig>,
     pub(crate) tcp_remote_keepalive: TcpKeepAliveConfig,
     tcp_remote_misc_opts: Option<TcpMiscSockOpts>,
@@ -54,10 +58,12 @@ pub(crate) struct UserConfig {
     udp_client_misc_opts: Option<UdpMiscSockOpts>,
     pub(crate) http_upstream_keepalive: HttpKeepAliveConfig,
     pub(crate) request_alive_max: usize,
+    pub(crate) connection_alive_max: usize,
     pub(crate) request_rate_limit: Option<RateLimitQuotaConfig>,
     pub(crate) tcp_conn_rate_limit: Option<RateLimitQuotaConfig>,
     pub(crate) tcp_sock_speed_limit: TcpSockSpeedLimitConfig,
     pub(crate) udp_sock_speed_limit: UdpSockSpeedLimitConfig,
+    pub(crate) tcp_stream_delay: Option<StreamDelayConfig>,
     pub(crate) log_rate_limit: Option<RateLimitQuotaConfig>,
     pub(crate) log_uri_max_chars: Option<usize>,
     pub(crate) ingress_net_filter: Option<AclNetworkRuleBuilder>,
@@ -70,6 +76,7 @@ pub(crate) struct UserConfig {
     pub(crate) task_idle_max_count: i32,
     pub(crate) socks_use_udp_associate: bool,
     pub(crate) egress_path_selection: Arc<EgressPathSelection>,
+    pub(crate) escaper: Option<MetricsName>,
     pub(crate) explicit_sites: BTreeMap<MetricsName, Arc<UserSiteConfig>>,
 }
 
@@ -81,6 +88,8 @@ impl Default for UserConfig {
             expire_datetime: None,
             audit: UserAuditConfig::default(),
             block_and_delay: None,
+            time_window: None,
+            traffic_quota: None,
             tcp_connect: None,
             tcp_remote_keepalive: Default::default(),
             tcp_remote_misc_opts: None,
@@ -89,10 +98,12 @@ impl Default for UserConfig {
             udp_client_misc_opts: None,
             http_upstream_keepalive: Default::default(),
             request_alive_max: 0,
+            connection_alive_max: 0,
             request_rate_limit: None,
             tcp_conn_rate_limit: None,
             tcp_sock_speed_limit: Default::default(),
             udp_sock_speed_limit: Default::default(),
+            tcp_stream_delay: None,
             log_rate_limit: None,
             log_uri_max_chars: None,
             ingress_net_filter: None,
@@ -105,6 +116,7 @@ impl Default for UserConfig {
             task_idle_max_count: 1,
             socks_use_udp_associate: false,
             egress_path_selection: Arc::new(EgressPathSelection::Default),
+            escaper: None,
             explicit_sites: BTreeMap::new(),
         }
     }
@@ -22,7 +22,9 @@ use yaml_rust::{yaml, Yaml};
 
 use g3_types::route::EgressPathSelection;
 
-use super::{PasswordToken, UserConfig, UserSiteConfig};
+use super::{
+    PasswordToken, UserConfig, UserSiteConfig, UserTimeWindowConfig, UserTrafficQuotaConfig,
+};
 
 impl UserConfig {
     pub(crate) fn parse_yaml(map: &yaml::Hash) -> anyhow::Result<Self> {
@@ -56,6 +58,18 @@ impl UserConfig {
                 self.block_and_delay = Some(delay);
                 Ok(())
             }
+            "time_window" | "access_time_window" => {
+                let config = UserTimeWindowConfig::parse_yaml(v)
+                    .context(format!("invalid user time window value for key {k}"))?;
+                self.time_window = Some(config);
+                Ok(())
+            }
+            "traffic_quota" => {
+                let config = UserTrafficQuotaConfig::parse_yaml(v)
+                    .context(format!("invalid user traffic quota value for key {k}"))?;
+                self.traffic_quota = Some(config);
+                Ok(())
+            }
             "tcp_connect" => {
                 let config = g3_yaml::value::as_tcp_connect_config(v)
                     .context(format!("invalid tcp connect config value for key {k}"))?;
@@ -72,6 +86,12 @@ impl UserConfig {
                     .context(format!("invalid udp socket speed limit value for key {k}"))?;
                 Ok(())
             }
+            "tcp_stream_delay" | "tcp_delay" => {
+                let delay = g3_yaml::value::as_stream_delay_config(v)
+                    .context(format!("invalid stream delay config value for key {k}"))?;
+                self.tcp_stream_delay = Some(delay);
+                Ok(())
+            }
             "tcp_remote_keepalive" => {
                 self.tcp_remote_keepalive = g3_yaml::value::as_tcp_keepalive_config(v)
                     .context(format!("invalid tcp keepalive config value for key {k}"))?;
@@ -123,6 +143,11 @@ impl UserConfig {
                     .context(format!("invalid usize value for key {k}"))?;
                 Ok(())
             }
+            "connection_max_alive" | "connection_alive_max" => {
+                self.connection_alive_max = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
             "ingress_network_filter" | "ingress_net_filter" => {
                 let filter = g3_yaml::value::acl::as_ingress_network_rule_builder(v).context(
                     format!("invalid ingress network acl rule value for key {k}"),
@@ -206,6 +231,12 @@ impl UserConfig {
                 .audit
                 .parse_yaml(v)
                 .context(format!("invalid user audit config value for key {k}")),
+            "escaper" => {
+                let name = g3_yaml::value::as_metrics_name(v)
+                    .context(format!("invalid metrics name value for key {k}"))?;
+                self.escaper = Some(name);
+                Ok(())
+            }
             "egress_path" => {
                 if let Yaml::String(s) = v {
                     let v = serde_json::Value::from_str(s)
@@ -0,0 +1,94 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+static GLOBAL_CERT_WATCH_CONFIG: OnceLock<CertWatchConfig> = OnceLock::new();
+
+pub(crate) struct CertWatchConfig {
+    pub(crate) interval: Duration,
+    pub(crate) debounce: Duration,
+}
+
+impl Default for CertWatchConfig {
+    fn default() -> Self {
+        CertWatchConfig {
+            interval: Duration::from_secs(60),
+            debounce: Duration::from_secs(2),
+        }
+    }
+}
+
+impl CertWatchConfig {
+    fn parse(map: &yaml_rust::yaml::Hash) -> anyhow::Result<Self> {
+        let mut config = CertWatchConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| config.set(k, v))?;
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "interval" | "poll_interval" => {
+                self.interval = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "debounce" => {
+                self.debounce = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.interval.is_zero() {
+            return Err(anyhow!("poll interval should not be zero"));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn load(v: &Yaml) -> anyhow::Result<()> {
+    let config = match v {
+        Yaml::Hash(map) => CertWatchConfig::parse(map)?,
+        Yaml::Boolean(true) => CertWatchConfig::default(),
+        Yaml::Boolean(false) => return Ok(()),
+        _ => {
+            let interval = g3_yaml::humanize::as_duration(v)
+                .context("invalid map or humanize duration value")?;
+            let config = CertWatchConfig {
+                interval,
+                ..Default::default()
+            };
+            config.check()?;
+            config
+        }
+    };
+    GLOBAL_CERT_WATCH_CONFIG
+        .set(config)
+        .map_err(|_| anyhow!("cert watch config has already been set"))
+}
+
+pub(crate) fn get_global_config() -> Option<&'static CertWatchConfig> {
+    GLOBAL_CERT_WATCH_CONFIG.get()
+}
@@ -0,0 +1,623 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+use yaml_rust::{yaml, ScanError, Yaml};
+
+use g3_types::metrics::MetricsName;
+use g3_yaml::{HybridParser, YamlDocPosition};
+
+use super::server::AnyServerConfig;
+
+struct KeyLines {
+    key: String,
+    line: usize,
+    items: Vec<usize>,
+}
+
+struct DocLines {
+    start: usize,
+    keys: Vec<KeyLines>,
+}
+
+/// the line numbers of the top level keys, and the items in their sequence values, of each doc
+#[derive(Default)]
+struct LineIndex {
+    docs: Vec<DocLines>,
+    // true for sequence and false for mapping
+    stack: Vec<bool>,
+    in_value: bool,
+}
+
+impl LineIndex {
+    fn parse(content: &str) -> Result<Self, ScanError> {
+        let mut index = LineIndex::default();
+        let mut parser = Parser::new(content.chars());
+        parser.load(&mut index, true)?;
+        Ok(index)
+    }
+
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        LineIndex::parse(&content).map_err(|e| anyhow!("{e}"))
+    }
+
+    fn doc_line(&self, doc: usize) -> Option<usize> {
+        self.docs.get(doc).map(|d| d.start)
+    }
+
+    fn key(&self, doc: usize, key: &str) -> Option<&KeyLines> {
+        self.docs.get(doc)?.keys.iter().find(|k| k.key == key)
+    }
+
+    fn key_line(&self, doc: usize, key: &str) -> Option<usize> {
+        self.key(doc, key).map(|k| k.line)
+    }
+
+    fn item_line(&self, doc: usize, key: &str, item: usize) -> Option<usize> {
+        self.key(doc, key)?.items.get(item).copied()
+    }
+
+    fn node_start(&mut self, line: usize, scalar: Option<&str>) {
+        match self.stack.as_slice() {
+            [false] if !self.in_value => {
+                if let (Some(doc), Some(key)) = (self.docs.last_mut(), scalar) {
+                    doc.keys.push(KeyLines {
+                        key: key.to_string(),
+                        line,
+                        items: Vec::new(),
+                    });
+                }
+            }
+            [false, true] => {
+                if let Some(k) = self.docs.last_mut().and_then(|d| d.keys.last_mut()) {
+                    k.items.push(line);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn node_end(&mut self) {
+        if self.stack.len() == 1 {
+            self.in_value = !self.in_value;
+        }
+    }
+}
+
+impl MarkedEventReceiver for LineIndex {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        let line = mark.line();
+        match ev {
+            Event::DocumentStart => {
+                self.docs.push(DocLines {
+                    start: line,
+                    keys: Vec::new(),
+                });
+                self.stack.clear();
+                self.in_value = false;
+            }
+            Event::MappingStart(_) => {
+                self.node_start(line, None);
+                self.stack.push(false);
+            }
+            Event::SequenceStart(_) => {
+                self.node_start(line, None);
+                self.stack.push(true);
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+                self.node_end();
+            }
+            Event::Scalar(s, ..) => {
+                self.node_start(line, Some(&s));
+                self.node_end();
+            }
+            Event::Alias(_) => {
+                self.node_start(line, None);
+                self.node_end();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn location(path: &Path, line: Option<usize>) -> String {
+    match line {
+        Some(line) => format!("{}:{line}", path.display()),
+        None => path.display().to_string(),
+    }
+}
+
+fn position_location(position: &YamlDocPosition) -> String {
+    let line = LineIndex::load(&position.path)
+        .ok()
+        .and_then(|index| index.doc_line(position.index));
+    match line {
+        Some(line) => location(&position.path, Some(line)),
+        None => position.to_string(),
+    }
+}
+
+struct DocRef<'a> {
+    path: &'a Path,
+    lines: &'a LineIndex,
+    index: usize,
+}
+
+impl DocRef<'_> {
+    fn location(&self, line: Option<usize>) -> String {
+        location(self.path, line.or_else(|| self.lines.doc_line(self.index)))
+    }
+}
+
+struct Checker {
+    conf_dir: PathBuf,
+    list: RefCell<Vec<String>>,
+}
+
+impl Checker {
+    fn new(conf_dir: &Path) -> Self {
+        Checker {
+            conf_dir: conf_dir.to_path_buf(),
+            list: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn push(&self, msg: String) {
+        self.list.borrow_mut().push(msg);
+    }
+
+    /// load all the docs in the file, and continue with the next key or entry if failed
+    fn check_file(&self, path: &Path, allow_include: bool) {
+        let lines = match LineIndex::load(path) {
+            Ok(lines) => lines,
+            Err(e) => {
+                self.push(format!("{}: {e:#}", path.display()));
+                return;
+            }
+        };
+
+        let r = g3_yaml::foreach_doc(path, |index, doc| {
+            let doc_ref = DocRef {
+                path,
+                lines: &lines,
+                index,
+            };
+            let Yaml::Hash(map) = doc else {
+                self.push(format!(
+                    "{}: yaml doc root should be hash",
+                    doc_ref.location(None)
+                ));
+                return Ok(());
+            };
+            g3_yaml::foreach_kv(map, |k, v| {
+                self.check_kv(&doc_ref, k, v, allow_include);
+                Ok(())
+            })
+        });
+        if let Err(e) = r {
+            self.push(format!("{}: {e:#}", path.display()));
+        }
+    }
+
+    fn check_kv(&self, doc: &DocRef, k: &str, v: &Yaml, allow_include: bool) {
+        match g3_yaml::key::normalize(k).as_str() {
+            "escaper" => self.check_entries("escaper", super::escaper::load_one, doc, k, v),
+            "server" => self.check_entries("server", super::server::load_one, doc, k, v),
+            "resolver" => self.check_entries("resolver", super::resolver::load_one, doc, k, v),
+            "user" | "user_group" => {
+                self.check_entries("user group", super::auth::load_one, doc, k, v)
+            }
+            "auditor" => self.check_entries("auditor", super::audit::load_one, doc, k, v),
+            "include" if allow_include => match super::included_files(v, &self.conf_dir) {
+                Ok(files) => {
+                    for file in files {
+                        self.check_file(&file, false);
+                    }
+                }
+                Err(e) => self.push(format!(
+                    "{}: invalid value for key {k}: {e:#}",
+                    doc.location(doc.lines.key_line(doc.index, k))
+                )),
+            },
+            _ => {
+                if let Err(e) = super::load_kv(k, v, &self.conf_dir, allow_include) {
+                    self.push(format!(
+                        "{}: {e:#}",
+                        doc.location(doc.lines.key_line(doc.index, k))
+                    ));
+                }
+            }
+        }
+    }
+
+    fn check_entries<F>(&self, kind: &str, load: F, doc: &DocRef, k: &str, v: &Yaml)
+    where
+        F: Fn(&yaml::Hash, Option<YamlDocPosition>) -> anyhow::Result<()>,
+    {
+        let items = match v {
+            Yaml::Array(seq) => seq
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (v, doc.lines.item_line(doc.index, k, i)))
+                .collect::<Vec<_>>(),
+            _ => vec![(v, doc.lines.key_line(doc.index, k))],
+        };
+
+        let parser = HybridParser::new(&self.conf_dir, g3_daemon::opts::config_file_extension());
+        for (v, line) in items {
+            let at = doc.location(line);
+            match v {
+                Yaml::Hash(map) => self.check_entry(kind, &load, map, None, &at),
+                Yaml::String(_) => {
+                    let r = parser.foreach_map(v, |map, position| {
+                        self.check_entry(kind, &load, map, position, &at);
+                        Ok(())
+                    });
+                    if let Err(e) = r {
+                        self.push(format!("{at}: {e:#}"));
+                    }
+                }
+                _ => self.push(format!("{at}: {kind} value should be a path or a map")),
+            }
+        }
+    }
+
+    fn check_entry<F>(
+        &self,
+        kind: &str,
+        load: &F,
+        map: &yaml::Hash,
+        position: Option<YamlDocPosition>,
+        at: &str,
+    ) where
+        F: Fn(&yaml::Hash, Option<YamlDocPosition>) -> anyhow::Result<()>,
+    {
+        let name = g3_yaml::hash_get_required_str(map, "name")
+            .unwrap_or("<unnamed>")
+            .to_string();
+        let at = match &position {
+            Some(p) => position_location(p),
+            None => at.to_string(),
+        };
+        if let Err(e) = load(map, position) {
+            self.push(format!("{at}: invalid {kind} {name}: {e:#}"));
+        }
+    }
+
+    fn check_store(&self) {
+        let r = super::store::foreach_doc(false, |map| {
+            g3_yaml::foreach_kv(map, |k, v| {
+                if let Err(e) = super::load_kv(k, v, &self.conf_dir, false) {
+                    self.push(format!("config store: {e:#}"));
+                }
+                Ok(())
+            })
+        });
+        if let Err(e) = r {
+            self.push(format!("config store: {e:#}"));
+        }
+    }
+
+    fn add(&self, kind: &str, name: &MetricsName, position: Option<YamlDocPosition>, msg: &str) {
+        match position {
+            Some(p) => self.push(format!("{}: {kind} {name}: {msg}", position_location(&p))),
+            None => self.push(format!("{kind} {name}: {msg}")),
+        }
+    }
+
+    fn check_ref(
+        &self,
+        kind: &str,
+        name: &MetricsName,
+        position: &Option<YamlDocPosition>,
+        ref_kind: &str,
+        ref_name: &MetricsName,
+        all: &HashSet<MetricsName>,
+    ) -> bool {
+        if ref_name.is_empty() || all.contains(ref_name) {
+            return true;
+        }
+        self.add(
+            kind,
+            name,
+            position.clone(),
+            &format!("{ref_kind} {ref_name} is not existed"),
+        );
+        false
+    }
+
+    /// check the cross references between all the loaded configs
+    fn check_references(&self) {
+        let auditors: HashSet<MetricsName> = super::audit::get_all()
+            .iter()
+            .map(|c| c.name().clone())
+            .collect();
+        let all_user_group = super::auth::get_all();
+        let user_groups: HashSet<MetricsName> =
+            all_user_group.iter().map(|c| c.name().clone()).collect();
+
+        let all_resolver = super::resolver::get_all();
+        let resolvers: HashSet<MetricsName> =
+            all_resolver.iter().map(|c| c.name().clone()).collect();
+        let mut resolver_ok = true;
+        for c in &all_resolver {
+            for v in c.dependent_resolver().unwrap_or_default() {
+                resolver_ok &= self.check_ref(
+                    "resolver",
+                    c.name(),
+                    &c.position(),
+                    "resolver",
+                    &v,
+                    &resolvers,
+                );
+            }
+        }
+        if resolver_ok {
+            if let Err(e) = super::resolver::get_all_sorted() {
+                self.push(format!("{e}"));
+            }
+        }
+
+        let all_escaper = super::escaper::get_all();
+        let escapers: HashSet<MetricsName> = all_escaper.iter().map(|c| c.name().clone()).collect();
+        let mut escaper_ok = true;
+        for c in &all_escaper {
+            let position = c.position();
+            for v in c.dependent_escaper().unwrap_or_default() {
+                escaper_ok &=
+                    self.check_ref("escaper", c.name(), &position, "escaper", &v, &escapers);
+            }
+            self.check_ref(
+                "escaper",
+                c.name(),
+                &position,
+                "resolver",
+                c.resolver(),
+                &resolvers,
+            );
+        }
+        if escaper_ok {
+            if let Err(e) = super::escaper::get_all_sorted() {
+                self.push(format!("{e}"));
+            }
+        }
+
+        let all_server = super::server::get_all();
+        let servers: HashSet<MetricsName> = all_server.iter().map(|c| c.name().clone()).collect();
+        let mut server_ok = true;
+        for c in &all_server {
+            let position = c.position();
+            for v in c.dependent_server().unwrap_or_default() {
+                server_ok &= self.check_ref("server", c.name(), &position, "server", &v, &servers);
+            }
+            self.check_ref(
+                "server",
+                c.name(),
+                &position,
+                "escaper",
+                c.escaper(),
+                &escapers,
+            );
+            self.check_ref(
+                "server",
+                c.name(),
+                &position,
+                "user group",
+                c.user_group(),
+                &user_groups,
+            );
+            self.check_ref(
+                "server",
+                c.name(),
+                &position,
+                "auditor",
+                c.auditor(),
+                &auditors,
+            );
+            if let AnyServerConfig::SocksProxy(s) = c.as_ref() {
+                if let Some(user) = &s.auth_free_user {
+                    let found = all_user_group
+                        .iter()
+                        .find(|g| g.name() == c.user_group())
+                        .map(|g| g.may_have_user(user));
+                    if found == Some(false) {
+                        self.add(
+                            "server",
+                            c.name(),
+                            position.clone(),
+                            &format!(
+                                "auth free user {user} is not existed in user group {}",
+                                c.user_group()
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+        if server_ok {
+            if let Err(e) = super::server::get_all_sorted() {
+                self.push(format!("{e}"));
+            }
+        }
+    }
+}
+
+/// load the config in the same way as the daemon, and return all the problems found,
+/// including the cross references between the loaded entries
+pub fn check_all() -> anyhow::Result<Vec<String>> {
+    let config_file =
+        g3_daemon::opts::config_file().ok_or_else(|| anyhow!("no config file set"))?;
+    let conf_dir =
+        g3_daemon::opts::config_dir().ok_or_else(|| anyhow!("no valid config dir has been set"))?;
+
+    let checker = Checker::new(conf_dir);
+    checker.check_file(config_file, true);
+    checker.check_store();
+    checker.check_references();
+    Ok(checker.list.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_index() {
+        let content = "\
+runtime:
+  thread_number: 2
+server:
+  - name: a
+    type: dummy_close
+  - b.yaml
+---
+resolver:
+  - name: r
+";
+        let index = LineIndex::parse(content).unwrap();
+        assert_eq!(index.doc_line(0), Some(1));
+        assert_eq!(index.key_line(0, "runtime"), Some(1));
+        assert_eq!(index.key_line(0, "server"), Some(3));
+        assert_eq!(index.key_line(0, "thread_number"), None);
+        assert_eq!(index.item_line(0, "server", 0), Some(4));
+        assert_eq!(index.item_line(0, "server", 1), Some(6));
+        assert_eq!(index.item_line(0, "server", 2), None);
+        assert_eq!(index.item_line(0, "runtime", 0), None);
+
+        assert_eq!(index.doc_line(1), Some(7));
+        assert_eq!(index.key_line(1, "resolver"), Some(8));
+        assert_eq!(index.item_line(1, "resolver", 0), Some(9));
+        assert_eq!(index.key_line(1, "server"), None);
+        assert_eq!(index.doc_line(2), None);
+
+        let e = LineIndex::parse("runtime:\n  thread_number: 2: 3\nserver: []\n")
+            .err()
+            .unwrap();
+        assert_eq!(e.marker().line(), 2);
+    }
+
+    #[test]
+    fn collect_all() {
+        let dir = std::env::temp_dir().join(format!("g3proxy-check-all-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.canonicalize().unwrap();
+
+        let main = dir.join("main.yaml");
+        std::fs::write(
+            &main,
+            "\
+server:
+  - name: check-all-ok
+    type: dummy_close
+  - name: check-all-bad-key
+    type: dummy_close
+    listen: 127.0.0.1:1080
+  - type: dummy_close
+  - servers.yaml
+include: inc.yaml
+",
+        )
+        .unwrap();
+        let servers = dir.join("servers.yaml");
+        std::fs::write(
+            &servers,
+            "\
+name: check-all-file
+type: dummy_close
+---
+name: check-all-file-bad
+type: dummy_close
+foo: bar
+",
+        )
+        .unwrap();
+        let inc = dir.join("inc.yaml");
+        std::fs::write(&inc, "server:\n  - name: a: b\n  - name: c\n").unwrap();
+
+        let checker = Checker::new(&dir);
+        checker.check_file(&main, true);
+        let list = checker.list.into_inner();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(list.len(), 4, "{list:?}");
+        let main = main.display();
+        assert!(list[0].starts_with(&format!("{main}:4: invalid server check-all-bad-key: ")));
+        assert!(list[0].contains("invalid key listen"));
+        assert!(list[1].starts_with(&format!("{main}:7: invalid server <unnamed>: ")));
+        assert!(list[1].contains("name is not set"));
+        let servers = servers.display();
+        assert!(list[2].starts_with(&format!("{servers}:3: invalid server check-all-file-bad: ")));
+        assert!(list[2].contains("invalid key foo"));
+        assert!(list[3].starts_with(&format!("{}: ", inc.display())));
+        assert!(list[3].contains("line 2"));
+    }
+
+    fn load_str<F>(load_one: F, s: &str)
+    where
+        F: Fn(&yaml::Hash, Option<YamlDocPosition>) -> anyhow::Result<()>,
+    {
+        let docs = yaml_rust::YamlLoader::load_from_str(s).unwrap();
+        load_one(docs[0].as_hash().unwrap(), None).unwrap();
+    }
+
+    #[test]
+    fn auth_free_user_reference() {
+        load_str(
+            super::super::auth::load_one,
+            "
+name: check-ref-users
+static_users:
+  - name: a
+",
+        );
+        for (name, user) in [("check-ref-socks-ok", "a"), ("check-ref-socks-bad", "b")] {
+            load_str(
+                super::super::server::load_one,
+                &format!(
+                    "
+name: {name}
+type: socks_proxy
+escaper: default
+user_group: check-ref-users
+auth_free_networks: [127.0.0.1/32]
+auth_free_user: {user}
+"
+                ),
+            );
+        }
+
+        let checker = Checker::new(&std::env::temp_dir());
+        checker.check_references();
+        let list = checker.list.into_inner();
+        let found = list
+            .iter()
+            .filter(|s| s.contains("auth free user"))
+            .collect::<Vec<_>>();
+        assert_eq!(found.len(), 1, "{list:?}");
+        assert_eq!(
+            found[0],
+            "server check-ref-socks-bad: auth free user b is not existed in user group check-ref-users"
+        );
+    }
+}
@@ -15,6 +15,7 @@
  */
 
 use std::net::IpAddr;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use anyhow::{anyhow, Context};
@@ -22,8 +23,12 @@ use ascii::AsciiString;
 use yaml_rust::{yaml, Yaml};
 
 use g3_types::acl::{AclAction, AclNetworkRuleBuilder};
+use g3_types::collection::SelectivePickPolicy;
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
-use g3_types::net::{HappyEyeballsConfig, TcpKeepAliveConfig, TcpMiscSockOpts, UdpMiscSockOpts};
+use g3_types::net::{
+    HappyEyeballsConfig, Nat64Prefix, TcpBindConfig, TcpKeepAliveConfig, TcpMiscSockOpts,
+    UdpMiscSockOpts,
+};
 use g3_types::resolve::{QueryStrategy, ResolveRedirectionBuilder, ResolveStrategy};
 use g3_yaml::YamlDocPosition;
 
@@ -31,6 +36,26 @@ use super::{AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, GeneralEsc
 
 const ESCAPER_CONFIG_TYPE: &str = "DirectFixed";
 
+/// the key used by rendezvous / jump hash when selecting bind ip
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum BindIpHashKey {
+    #[default]
+    ClientIp,
+    UpstreamHost,
+}
+
+impl FromStr for BindIpHashKey {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match g3_yaml::key::normalize(s).as_str() {
+            "client_ip" | "client" => Ok(BindIpHashKey::ClientIp),
+            "upstream_host" | "host" => Ok(BindIpHashKey::UpstreamHost),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq)]
 pub(crate) struct DirectFixedEscaperConfig {
     pub(crate) name: MetricsName,
@@ -38,8 +63,11 @@ pub(crate) struct DirectFixedEscaperConfig {
     pub(crate) shared_logger: Option<AsciiString>,
     pub(crate) bind4: Vec<IpAddr>,
     pub(crate) bind6: Vec<IpAddr>,
+    pub(crate) bind_ip_pick_policy: SelectivePickPolicy,
+    pub(crate) bind_ip_hash_key: BindIpHashKey,
     pub(crate) no_ipv4: bool,
     pub(crate) no_ipv6: bool,
+    pub(crate) nat64_prefix: Option<Nat64Prefix>,
     pub(crate) resolver: MetricsName,
     pub(crate) resolve_strategy: ResolveStrategy,
     pub(crate) resolve_redirection: Option<ResolveRedirectionBuilder>,
@@ -48,6 +76,7 @@ pub(crate) struct DirectFixedEscaperConfig {
     pub(crate) happy_eyeballs: HappyEyeballsConfig,
     pub(crate) tcp_keepalive: TcpKeepAliveConfig,
     pub(crate) tcp_misc_opts: TcpMiscSockOpts,
+    pub(crate) tcp_bind: TcpBindConfig,
     pub(crate) udp_misc_opts: UdpMiscSockOpts,
     pub(crate) enable_path_selection: bool,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
@@ -61,8 +90,11 @@ impl DirectFixedEscaperConfig {
             shared_logger: None,
             bind4: Vec::new(),
             bind6: Vec::new(),
+            bind_ip_pick_policy: SelectivePickPolicy::Random,
+            bind_ip_hash_key: BindIpHashKey::default(),
             no_ipv4: false,
             no_ipv6: false,
+            nat64_prefix: None,
             resolver: MetricsName::default(),
             resolve_strategy: Default::default(),
             resolve_redirection: None,
@@ -71,6 +103,7 @@ impl DirectFixedEscaperConfig {
             happy_eyeballs: Default::default(),
             tcp_keepalive: Default::default(),
             tcp_misc_opts: Default::default(),
+            tcp_bind: Default::default(),
             udp_misc_opts: Default::default(),
             enable_path_selection: false,
             extra_metrics_tags: None,
@@ -107,6 +140,13 @@ impl DirectFixedEscaperConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "tcp_connect_duration_stats" | "tcp_connect_duration_metrics" => {
+                let config = g3_yaml::value::as_histogram_metrics_config(v).context(format!(
+                    "invalid histogram metrics config value for key {k}"
+                ))?;
+                self.general.tcp_connect_duration_stats = Some(config);
+                Ok(())
+            }
             "bind_ip" => {
                 let ips = g3_yaml::value::as_list(v, g3_yaml::value::as_ipaddr)
                     .context(format!("invalid ip address list value for key {k}"))?;
@@ -115,6 +155,17 @@ impl DirectFixedEscaperConfig {
                 }
                 Ok(())
             }
+            "bind_ip_pick_policy" => {
+                self.bind_ip_pick_policy = g3_yaml::value::as_selective_pick_policy(v)
+                    .context(format!("invalid selective pick policy value for key {k}"))?;
+                Ok(())
+            }
+            "bind_ip_hash_key" => {
+                let s = g3_yaml::value::as_string(v)?;
+                self.bind_ip_hash_key = BindIpHashKey::from_str(&s)
+                    .map_err(|_| anyhow!("invalid bind ip hash key {s}"))?;
+                Ok(())
+            }
             "resolver" => {
                 self.resolver = g3_yaml::value::as_metrics_name(v)?;
                 Ok(())
@@ -144,6 +195,18 @@ impl DirectFixedEscaperConfig {
                     .context(format!("invalid tcp conn socket limit value for key {k}"))?;
                 Ok(())
             }
+            "tcp_all_upload_speed_limit" => {
+                let limit = g3_yaml::value::as_global_stream_speed_limit(v)
+                    .context(format!("invalid global speed limit value for key {k}"))?;
+                self.general.tcp_all_upload_speed_limit = Some(limit);
+                Ok(())
+            }
+            "tcp_all_download_speed_limit" => {
+                let limit = g3_yaml::value::as_global_stream_speed_limit(v)
+                    .context(format!("invalid global speed limit value for key {k}"))?;
+                self.general.tcp_all_download_speed_limit = Some(limit);
+                Ok(())
+            }
             "udp_sock_speed_limit" | "udp_relay_speed_limit" | "udp_relay_limit" => {
                 self.general.udp_sock_speed_limit = g3_yaml::value::as_udp_sock_speed_limit(v)
                     .context(format!("invalid udp socket speed limit value for key {k}"))?;
@@ -159,6 +222,17 @@ impl DirectFixedEscaperConfig {
                     .context(format!("invalid tcp misc sock opts value for key {k}"))?;
                 Ok(())
             }
+            "tcp_bind_no_port" => {
+                let enable = g3_yaml::value::as_bool(v)?;
+                self.tcp_bind.set_bind_no_port(enable);
+                Ok(())
+            }
+            "tcp_bind_port_range" => {
+                let range = g3_yaml::value::as_port_range(v)
+                    .context(format!("invalid port range value for key {k}"))?;
+                self.tcp_bind.set_port_range(range);
+                Ok(())
+            }
             "udp_misc_opts" => {
                 self.udp_misc_opts = g3_yaml::value::as_udp_misc_sock_opts(v)
                     .context(format!("invalid udp misc sock opts value for key {k}"))?;
@@ -172,6 +246,12 @@ impl DirectFixedEscaperConfig {
                 self.no_ipv6 = g3_yaml::value::as_bool(v)?;
                 Ok(())
             }
+            "nat64_prefix" => {
+                let prefix = g3_yaml::value::as_nat64_prefix(v)
+                    .context(format!("invalid nat64 prefix value for key {k}"))?;
+                self.nat64_prefix = Some(prefix);
+                Ok(())
+            }
             "tcp_connect" => {
                 self.general.tcp_connect = g3_yaml::value::as_tcp_connect_config(v)
                     .context(format!("invalid tcp connect value for key {k}"))?;
@@ -196,13 +276,18 @@ impl DirectFixedEscaperConfig {
         if self.no_ipv4 && self.no_ipv6 {
             return Err(anyhow!("both ipv4 and ipv6 are disabled"));
         }
+        if self.nat64_prefix.is_some() && self.no_ipv6 {
+            return Err(anyhow!("nat64 prefix is set but ipv6 is disabled"));
+        }
+        // A records are still needed to synthesize ipv6 addresses if nat64 is enabled
+        let no_ipv4_query = self.no_ipv4 && self.nat64_prefix.is_none();
         self.resolve_strategy
-            .update_query_strategy(self.no_ipv4, self.no_ipv6)
+            .update_query_strategy(no_ipv4_query, self.no_ipv6)
             .context("found incompatible resolver strategy".to_string())?;
 
         if !self.no_ipv4 && !self.no_ipv6 {
             match self.resolve_strategy.query {
-                QueryStrategy::Ipv4Only => self.no_ipv6 = true,
+                QueryStrategy::Ipv4Only if self.nat64_prefix.is_none() => self.no_ipv6 = true,
                 QueryStrategy::Ipv6Only => self.no_ipv4 = true,
                 _ => {}
             }
@@ -24,13 +24,18 @@ use slog::Logger;
 use yaml_rust::{yaml, Yaml};
 
 use g3_daemon::config::sort_nodes_in_dependency_graph;
+use g3_histogram::HistogramMetricsConfig;
 use g3_types::metrics::MetricsName;
-use g3_types::net::{TcpConnectConfig, TcpSockSpeedLimitConfig, UdpSockSpeedLimitConfig};
+use g3_types::net::{
+    GlobalStreamSpeedLimitConfig, TcpConnectConfig, TcpSockSpeedLimitConfig,
+    UdpSockSpeedLimitConfig,
+};
 use g3_yaml::{HybridParser, YamlDocPosition};
 
 pub(crate) mod direct_fixed;
 pub(crate) mod direct_float;
 pub(crate) mod dummy_deny;
+pub(crate) mod peer_quarantine;
 pub(crate) mod proxy_float;
 pub(crate) mod proxy_http;
 pub(crate) mod proxy_https;
@@ -47,7 +52,7 @@ pub(crate) mod route_upstream;
 pub(crate) mod trick_float;
 
 mod registry;
-pub(crate) use registry::clear;
+pub(crate) use registry::{clear, get_all};
 
 mod verify;
 use verify::EscaperConfigVerifier;
@@ -91,6 +96,9 @@ pub(crate) struct GeneralEscaperConfig {
     pub(crate) tcp_sock_speed_limit: TcpSockSpeedLimitConfig,
     pub(crate) udp_sock_speed_limit: UdpSockSpeedLimitConfig,
     pub(crate) tcp_connect: TcpConnectConfig,
+    pub(crate) tcp_all_upload_speed_limit: Option<GlobalStreamSpeedLimitConfig>,
+    pub(crate) tcp_all_download_speed_limit: Option<GlobalStreamSpeedLimitConfig>,
+    pub(crate) tcp_connect_duration_stats: Option<HistogramMetricsConfig>,
 }
 
 #[derive(Clone)]
@@ -177,21 +185,24 @@ impl AnyEscaperConfig {
 
 pub(crate) fn load_all(v: &Yaml, conf_dir: &Path) -> anyhow::Result<()> {
     let parser = HybridParser::new(conf_dir, g3_daemon::opts::config_file_extension());
-    parser.foreach_map(v, |map, position| {
-        let escaper = load_escaper(map, position)?;
-        if let Some(old_escaper) = registry::add(escaper) {
-            Err(anyhow!(
-                "escaper with name {} already exists",
-                old_escaper.name()
-            ))
-        } else {
-            Ok(())
-        }
-    })?;
+    parser.foreach_map(v, load_one)?;
     check_dependency()?;
     Ok(())
 }
 
+/// load and add one escaper, the dependency is not checked
+pub(crate) fn load_one(map: &yaml::Hash, position: Option<YamlDocPosition>) -> anyhow::Result<()> {
+    let escaper = load_escaper(map, position)?;
+    if let Some(old_escaper) = registry::add(escaper) {
+        Err(anyhow!(
+            "escaper with name {} already exists",
+            old_escaper.name()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 pub(crate) fn load_at_position(position: &YamlDocPosition) -> anyhow::Result<AnyEscaperConfig> {
     let doc = g3_yaml::load_doc(position)?;
     if let Yaml::Hash(map) = doc {
@@ -0,0 +1,144 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct PeerQuarantineConfig {
+    pub(crate) failure_threshold: usize,
+    pub(crate) cooldown: Duration,
+    pub(crate) max_cooldown: Duration,
+}
+
+impl Default for PeerQuarantineConfig {
+    fn default() -> Self {
+        PeerQuarantineConfig {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(10),
+            max_cooldown: Duration::from_secs(300),
+        }
+    }
+}
+
+impl PeerQuarantineConfig {
+    /// get the cooldown time for the nth (starting from 1) continuous quarantine
+    pub(crate) fn cooldown_for(&self, n: u32) -> Duration {
+        let shift = n.saturating_sub(1).min(16);
+        self.cooldown
+            .saturating_mul(1 << shift)
+            .min(self.max_cooldown)
+    }
+
+    fn check(&mut self) -> anyhow::Result<()> {
+        if self.failure_threshold == 0 {
+            return Err(anyhow!("failure threshold should not be zero"));
+        }
+        if self.cooldown.is_zero() {
+            return Err(anyhow!("cooldown should not be zero"));
+        }
+        if self.max_cooldown < self.cooldown {
+            self.max_cooldown = self.cooldown;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn parse(value: &Yaml) -> anyhow::Result<Option<Self>> {
+        match value {
+            Yaml::Boolean(enable) => Ok(enable.then(PeerQuarantineConfig::default)),
+            Yaml::Hash(map) => {
+                let mut config = PeerQuarantineConfig::default();
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "failure_threshold" | "max_failures" => {
+                        config.failure_threshold = g3_yaml::value::as_usize(v)
+                            .context(format!("invalid usize value for key {k}"))?;
+                        Ok(())
+                    }
+                    "cooldown" => {
+                        config.cooldown = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    "max_cooldown" => {
+                        config.max_cooldown = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+                config.check()?;
+                Ok(Some(config))
+            }
+            _ => Err(anyhow!(
+                "yaml value type for 'peer quarantine config' should be 'boolean' or 'map'"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(doc: &str) -> anyhow::Result<Option<PeerQuarantineConfig>> {
+        let v = YamlLoader::load_from_str(doc).unwrap();
+        PeerQuarantineConfig::parse(&v[0])
+    }
+
+    #[test]
+    fn parse_bool() {
+        assert_eq!(
+            parse("true").unwrap(),
+            Some(PeerQuarantineConfig::default())
+        );
+        assert_eq!(parse("false").unwrap(), None);
+        assert!(parse("1").is_err());
+    }
+
+    #[test]
+    fn parse_map() {
+        let config = parse("{max_failures: 5, cooldown: 30s, max_cooldown: 10m}")
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.failure_threshold, 5);
+        assert_eq!(config.cooldown, Duration::from_secs(30));
+        assert_eq!(config.max_cooldown, Duration::from_secs(600));
+
+        // max cooldown should be no less than cooldown
+        let config = parse("{failure_threshold: 1, cooldown: 10m}")
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.failure_threshold, 1);
+        assert_eq!(config.max_cooldown, Duration::from_secs(600));
+
+        assert!(parse("{failure_threshold: 0}").is_err());
+        assert!(parse("{cooldown: 0}").is_err());
+        assert!(parse("{cooldown_time: 10s}").is_err());
+    }
+
+    #[test]
+    fn cooldown() {
+        let config = PeerQuarantineConfig::default();
+        assert_eq!(config.cooldown_for(1), Duration::from_secs(10));
+        assert_eq!(config.cooldown_for(2), Duration::from_secs(20));
+        assert_eq!(config.cooldown_for(5), Duration::from_secs(160));
+        assert_eq!(config.cooldown_for(6), Duration::from_secs(300));
+        assert_eq!(config.cooldown_for(u32::MAX), Duration::from_secs(300));
+    }
+}
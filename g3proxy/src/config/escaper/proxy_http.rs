@@ -26,12 +26,13 @@ use g3_types::auth::{Password, Username};
 use g3_types::collection::SelectivePickPolicy;
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::net::{
-    HappyEyeballsConfig, Host, HttpForwardCapability, ProxyProtocolVersion, TcpKeepAliveConfig,
-    TcpMiscSockOpts, WeightedUpstreamAddr,
+    ConnectionPoolConfig, HappyEyeballsConfig, Host, HttpForwardCapability, ProxyProtocolVersion,
+    TcpKeepAliveConfig, TcpMiscSockOpts, WeightedUpstreamAddr,
 };
-use g3_types::resolve::{QueryStrategy, ResolveStrategy};
+use g3_types::resolve::{QueryStrategy, ResolveRedirectionBuilder, ResolveStrategy};
 use g3_yaml::YamlDocPosition;
 
+use super::peer_quarantine::PeerQuarantineConfig;
 use super::{AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, GeneralEscaperConfig};
 
 const ESCAPER_CONFIG_TYPE: &str = "ProxyHttp";
@@ -51,9 +52,11 @@ pub(crate) struct ProxyHttpEscaperConfig {
     pub(crate) no_ipv6: bool,
     pub(crate) resolver: MetricsName,
     pub(crate) resolve_strategy: ResolveStrategy,
+    pub(crate) resolve_redirection: Option<ResolveRedirectionBuilder>,
     pub(crate) general: GeneralEscaperConfig,
     pub(crate) happy_eyeballs: HappyEyeballsConfig,
     pub(crate) http_forward_capability: HttpForwardCapability,
+    pub(crate) http_forward_connection_pool: Option<ConnectionPoolConfig>,
     pub(crate) tcp_keepalive: TcpKeepAliveConfig,
     pub(crate) tcp_misc_opts: TcpMiscSockOpts,
     pub(crate) http_connect_rsp_hdr_max_size: usize,
@@ -61,6 +64,7 @@ pub(crate) struct ProxyHttpEscaperConfig {
     pub(crate) pass_proxy_userid: bool,
     pub(crate) use_proxy_protocol: Option<ProxyProtocolVersion>,
     pub(crate) peer_negotiation_timeout: Duration,
+    pub(crate) peer_quarantine: Option<PeerQuarantineConfig>,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
 }
 
@@ -80,9 +84,11 @@ impl ProxyHttpEscaperConfig {
             no_ipv6: false,
             resolver: MetricsName::default(),
             resolve_strategy: Default::default(),
+            resolve_redirection: None,
             general: Default::default(),
             happy_eyeballs: Default::default(),
             http_forward_capability: Default::default(),
+            http_forward_connection_pool: None,
             tcp_keepalive: Default::default(),
             tcp_misc_opts: Default::default(),
             http_connect_rsp_hdr_max_size: 4096,
@@ -90,6 +96,7 @@ impl ProxyHttpEscaperConfig {
             pass_proxy_userid: false,
             use_proxy_protocol: None,
             peer_negotiation_timeout: Duration::from_secs(10),
+            peer_quarantine: None,
             extra_metrics_tags: None,
         }
     }
@@ -124,6 +131,13 @@ impl ProxyHttpEscaperConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "tcp_connect_duration_stats" | "tcp_connect_duration_metrics" => {
+                let config = g3_yaml::value::as_histogram_metrics_config(v).context(format!(
+                    "invalid histogram metrics config value for key {k}"
+                ))?;
+                self.general.tcp_connect_duration_stats = Some(config);
+                Ok(())
+            }
             "proxy_addr" => {
                 self.proxy_nodes = g3_yaml::value::as_list(v, |v| {
                     g3_yaml::value::as_weighted_upstream_addr(v, 3128)
@@ -165,16 +179,44 @@ impl ProxyHttpEscaperConfig {
                 self.resolve_strategy = g3_yaml::value::as_resolve_strategy(v)?;
                 Ok(())
             }
+            "resolve_redirection" => {
+                let redirect = g3_yaml::value::as_resolve_redirection_builder(v)
+                    .context(format!("invalid resolve redirection value for key {k}"))?;
+                self.resolve_redirection = Some(redirect);
+                Ok(())
+            }
             "tcp_sock_speed_limit" | "tcp_conn_speed_limit" | "tcp_conn_limit" | "conn_limit" => {
                 self.general.tcp_sock_speed_limit = g3_yaml::value::as_tcp_sock_speed_limit(v)
                     .context(format!("invalid tcp socket speed limit value for key {k}"))?;
                 Ok(())
             }
+            "tcp_all_upload_speed_limit" => {
+                let limit = g3_yaml::value::as_global_stream_speed_limit(v)
+                    .context(format!("invalid global speed limit value for key {k}"))?;
+                self.general.tcp_all_upload_speed_limit = Some(limit);
+                Ok(())
+            }
+            "tcp_all_download_speed_limit" => {
+                let limit = g3_yaml::value::as_global_stream_speed_limit(v)
+                    .context(format!("invalid global speed limit value for key {k}"))?;
+                self.general.tcp_all_download_speed_limit = Some(limit);
+                Ok(())
+            }
             "http_forward_capability" => {
                 self.http_forward_capability = g3_yaml::value::as_http_forward_capability(v)
                     .context(format!("invalid http forward capability value for key {k}"))?;
                 Ok(())
             }
+            "http_forward_connection_pool" => {
+                if let Yaml::Boolean(enable) = v {
+                    self.http_forward_connection_pool = enable.then(ConnectionPoolConfig::default);
+                } else {
+                    let config = g3_yaml::value::as_connection_pool_config(v)
+                        .context(format!("invalid connection pool config value for key {k}"))?;
+                    self.http_forward_connection_pool = Some(config);
+                }
+                Ok(())
+            }
             "tcp_keepalive" => {
                 self.tcp_keepalive = g3_yaml::value::as_tcp_keepalive_config(v)
                     .context(format!("invalid tcp keepalive config value for key {k}"))?;
@@ -224,6 +266,11 @@ impl ProxyHttpEscaperConfig {
                     .context(format!("invalid humanize duration value for key {k}"))?;
                 Ok(())
             }
+            "peer_quarantine" => {
+                self.peer_quarantine = PeerQuarantineConfig::parse(v)
+                    .context(format!("invalid peer quarantine config value for key {k}"))?;
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
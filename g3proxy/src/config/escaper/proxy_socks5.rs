@@ -30,9 +30,10 @@ use g3_types::net::{
     HappyEyeballsConfig, Host, SocksAuth, TcpKeepAliveConfig, TcpMiscSockOpts, UdpMiscSockOpts,
     WeightedUpstreamAddr,
 };
-use g3_types::resolve::{QueryStrategy, ResolveStrategy};
+use g3_types::resolve::{QueryStrategy, ResolveRedirectionBuilder, ResolveStrategy};
 use g3_yaml::YamlDocPosition;
 
+use super::peer_quarantine::PeerQuarantineConfig;
 use super::{AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, GeneralEscaperConfig};
 
 const ESCAPER_CONFIG_TYPE: &str = "ProxySocks5";
@@ -52,6 +53,7 @@ pub(crate) struct ProxySocks5EscaperConfig {
     pub(crate) no_ipv6: bool,
     pub(crate) resolver: MetricsName,
     pub(crate) resolve_strategy: ResolveStrategy,
+    pub(crate) resolve_redirection: Option<ResolveRedirectionBuilder>,
     pub(crate) general: GeneralEscaperConfig,
     pub(crate) happy_eyeballs: HappyEyeballsConfig,
     pub(crate) tcp_keepalive: TcpKeepAliveConfig,
@@ -59,6 +61,7 @@ pub(crate) struct ProxySocks5EscaperConfig {
     pub(crate) udp_misc_opts: UdpMiscSockOpts,
     pub(crate) auth_info: SocksAuth,
     pub(crate) peer_negotiation_timeout: Duration,
+    pub(crate) peer_quarantine: Option<PeerQuarantineConfig>,
     transmute_udp_peer_ip: Option<AHashMap<IpAddr, IpAddr>>,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
 }
@@ -79,6 +82,7 @@ impl ProxySocks5EscaperConfig {
             no_ipv6: false,
             resolver: MetricsName::default(),
             resolve_strategy: Default::default(),
+            resolve_redirection: None,
             general: Default::default(),
             happy_eyeballs: Default::default(),
             tcp_keepalive: TcpKeepAliveConfig::default_enabled(),
@@ -86,6 +90,7 @@ impl ProxySocks5EscaperConfig {
             udp_misc_opts: Default::default(),
             auth_info: SocksAuth::None,
             peer_negotiation_timeout: Duration::from_secs(10),
+            peer_quarantine: None,
             transmute_udp_peer_ip: None,
             extra_metrics_tags: None,
         }
@@ -121,6 +126,13 @@ impl ProxySocks5EscaperConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "tcp_connect_duration_stats" | "tcp_connect_duration_metrics" => {
+                let config = g3_yaml::value::as_histogram_metrics_config(v).context(format!(
+                    "invalid histogram metrics config value for key {k}"
+                ))?;
+                self.general.tcp_connect_duration_stats = Some(config);
+                Ok(())
+            }
             "proxy_addr" => {
                 self.proxy_nodes = g3_yaml::value::as_list(v, |v| {
                     g3_yaml::value::as_weighted_upstream_addr(v, 1080)
@@ -162,11 +174,29 @@ impl ProxySocks5EscaperConfig {
                 self.resolve_strategy = g3_yaml::value::as_resolve_strategy(v)?;
                 Ok(())
             }
+            "resolve_redirection" => {
+                let redirect = g3_yaml::value::as_resolve_redirection_builder(v)
+                    .context(format!("invalid resolve redirection value for key {k}"))?;
+                self.resolve_redirection = Some(redirect);
+                Ok(())
+            }
             "tcp_sock_speed_limit" | "tcp_conn_speed_limit" | "tcp_conn_limit" | "conn_limit" => {
                 self.general.tcp_sock_speed_limit = g3_yaml::value::as_tcp_sock_speed_limit(v)
                     .context(format!("invalid tcp socket speed limit value for key {k}"))?;
                 Ok(())
             }
+            "tcp_all_upload_speed_limit" => {
+                let limit = g3_yaml::value::as_global_stream_speed_limit(v)
+                    .context(format!("invalid global speed limit value for key {k}"))?;
+                self.general.tcp_all_upload_speed_limit = Some(limit);
+                Ok(())
+            }
+            "tcp_all_download_speed_limit" => {
+                let limit = g3_yaml::value::as_global_stream_speed_limit(v)
+                    .context(format!("invalid global speed limit value for key {k}"))?;
+                self.general.tcp_all_download_speed_limit = Some(limit);
+                Ok(())
+            }
             "udp_sock_speed_limit"
             | "udp_relay_speed_limit"
             | "udp_relay_limit"
@@ -213,6 +243,11 @@ impl ProxySocks5EscaperConfig {
                     .context(format!("invalid humanize duration value for key {k}"))?;
                 Ok(())
             }
+            "peer_quarantine" => {
+                self.peer_quarantine = PeerQuarantineConfig::parse(v)
+                    .context(format!("invalid peer quarantine config value for key {k}"))?;
+                Ok(())
+            }
             "transmute_udp_peer_ip" => {
                 if let Yaml::Hash(_) = v {
                     let map = g3_yaml::value::as_hashmap(
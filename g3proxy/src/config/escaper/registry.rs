@@ -43,7 +43,7 @@ pub(super) fn del(name: &MetricsName) {
     ht.remove(name);
 }
 
-pub(super) fn get_all() -> Vec<Arc<AnyEscaperConfig>> {
+pub(crate) fn get_all() -> Vec<Arc<AnyEscaperConfig>> {
     let mut vec = Vec::new();
     let ht = INITIAL_ESCAPER_CONFIG_REGISTRY.lock().unwrap();
     for v in ht.values() {
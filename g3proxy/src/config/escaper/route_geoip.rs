@@ -23,7 +23,7 @@ use yaml_rust::{yaml, Yaml};
 
 use g3_geoip::{ContinentCode, IsoCountryCode};
 use g3_types::metrics::MetricsName;
-use g3_types::resolve::ResolveStrategy;
+use g3_types::resolve::{ResolveRedirectionBuilder, ResolveStrategy};
 use g3_yaml::YamlDocPosition;
 
 use super::{AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, EscaperConfigVerifier};
@@ -36,6 +36,7 @@ pub(crate) struct RouteGeoIpEscaperConfig {
     position: Option<YamlDocPosition>,
     pub(crate) resolver: MetricsName,
     pub(crate) resolve_strategy: ResolveStrategy,
+    pub(crate) resolve_redirection: Option<ResolveRedirectionBuilder>,
     pub(crate) resolution_delay: Duration,
     pub(crate) lpm_rules: BTreeMap<MetricsName, BTreeSet<IpNetwork>>,
     pub(crate) asn_rules: BTreeMap<MetricsName, BTreeSet<u32>>,
@@ -51,6 +52,7 @@ impl RouteGeoIpEscaperConfig {
             position,
             resolver: MetricsName::default(),
             resolve_strategy: Default::default(),
+            resolve_redirection: None,
             resolution_delay: Duration::from_millis(50),
             lpm_rules: BTreeMap::new(),
             asn_rules: BTreeMap::new(),
@@ -87,6 +89,12 @@ impl RouteGeoIpEscaperConfig {
                 self.resolve_strategy = g3_yaml::value::as_resolve_strategy(v)?;
                 Ok(())
             }
+            "resolve_redirection" => {
+                let redirect = g3_yaml::value::as_resolve_redirection_builder(v)
+                    .context(format!("invalid resolve redirection value for key {k}"))?;
+                self.resolve_redirection = Some(redirect);
+                Ok(())
+            }
             "resolution_delay" => {
                 self.resolution_delay = g3_yaml::humanize::as_duration(v)
                     .context(format!("invalid humanize duration value for key {k}"))?;
@@ -16,9 +16,11 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 use std::net::IpAddr;
+use std::path::Path;
 
 use anyhow::{anyhow, Context};
 use ip_network::IpNetwork;
+use regex::RegexSet;
 use yaml_rust::{yaml, Yaml};
 
 use g3_types::metrics::MetricsName;
@@ -29,6 +31,33 @@ use super::{AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, EscaperCon
 
 const ESCAPER_CONFIG_TYPE: &str = "RouteUpstream";
 
+/// Regex rules for a next escaper. Each regex is anchored at both ends, so it should match the whole domain.
+#[derive(Clone)]
+pub(crate) struct RegexMatchRule {
+    pub(crate) next: MetricsName,
+    pub(crate) regex_set: RegexSet,
+}
+
+impl RegexMatchRule {
+    fn new(next: MetricsName, all_regex: &[String]) -> anyhow::Result<Self> {
+        let regex_set = RegexSet::new(all_regex.iter().map(|r| format!("^(?:{r})$")))
+            .map_err(|e| anyhow!("invalid regex value: {e}"))?;
+        Ok(RegexMatchRule { next, regex_set })
+    }
+
+    pub(crate) fn is_match(&self, domain: &str) -> bool {
+        self.regex_set.is_match(domain)
+    }
+}
+
+impl PartialEq for RegexMatchRule {
+    fn eq(&self, other: &Self) -> bool {
+        self.next == other.next && self.regex_set.patterns() == other.regex_set.patterns()
+    }
+}
+
+impl Eq for RegexMatchRule {}
+
 #[derive(Clone, Eq, PartialEq)]
 pub(crate) struct RouteUpstreamEscaperConfig {
     pub(crate) name: MetricsName,
@@ -38,6 +67,7 @@ pub(crate) struct RouteUpstreamEscaperConfig {
     pub(crate) subnet_match_ipaddr: BTreeMap<MetricsName, BTreeSet<IpNetwork>>,
     pub(crate) radix_match_domain: BTreeMap<MetricsName, BTreeSet<String>>,
     pub(crate) child_match_domain: BTreeMap<MetricsName, BTreeSet<String>>,
+    pub(crate) regex_match_domain: Vec<RegexMatchRule>,
     pub(crate) default_next: MetricsName,
 }
 
@@ -51,6 +81,7 @@ impl RouteUpstreamEscaperConfig {
             subnet_match_ipaddr: BTreeMap::new(),
             radix_match_domain: BTreeMap::new(),
             child_match_domain: BTreeMap::new(),
+            regex_match_domain: Vec::new(),
             default_next: MetricsName::default(),
         }
     }
@@ -104,6 +135,16 @@ impl RouteUpstreamEscaperConfig {
             "child_match" | "child_rules" => {
                 RouteUpstreamEscaperConfig::foreach_rule(k, v, |map| self.add_child_match(map))
             }
+            "regex_match" | "regex_rules" => {
+                RouteUpstreamEscaperConfig::foreach_rule(k, v, |map| self.add_regex_match(map))
+            }
+            "rule_files" | "rule_file" => {
+                let lookup_dir =
+                    g3_daemon::config::get_lookup_dir(self.position.as_ref())?.to_path_buf();
+                RouteUpstreamEscaperConfig::foreach_rule(k, v, |map| {
+                    self.add_rule_file(map, &lookup_dir)
+                })
+            }
             "default_next" => {
                 self.default_next = g3_yaml::value::as_metrics_name(v)?;
                 Ok(())
@@ -289,6 +330,142 @@ impl RouteUpstreamEscaperConfig {
         }
         Ok(())
     }
+    fn add_regex_match(&mut self, map: &yaml::Hash) -> anyhow::Result<()> {
+        let mut escaper = MetricsName::default();
+        let mut all_regex = Vec::<String>::new();
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "next" | "escaper" => {
+                escaper = g3_yaml::value::as_metrics_name(v)?;
+                Ok(())
+            }
+            "regexes" | "regex" => {
+                if let Yaml::Array(seq) = v {
+                    for (i, v) in seq.iter().enumerate() {
+                        let regex = g3_yaml::value::as_string(v)
+                            .context(format!("invalid regex string value for {k}:{i}"))?;
+                        all_regex.push(regex);
+                    }
+                    Ok(())
+                } else {
+                    Err(anyhow!("invalid array value for key {k}"))
+                }
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+        if escaper.is_empty() {
+            return Err(anyhow!("no next escaper set"));
+        }
+        self.push_regex_match(escaper, all_regex)
+    }
+
+    fn push_regex_match(
+        &mut self,
+        escaper: MetricsName,
+        all_regex: Vec<String>,
+    ) -> anyhow::Result<()> {
+        if !all_regex.is_empty() {
+            let rule = RegexMatchRule::new(escaper, &all_regex)?;
+            self.regex_match_domain.push(rule);
+        }
+        Ok(())
+    }
+
+    fn add_rule_file(&mut self, map: &yaml::Hash, lookup_dir: &Path) -> anyhow::Result<()> {
+        let mut escaper = MetricsName::default();
+        let mut match_type = String::new();
+        let mut path = None;
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "next" | "escaper" => {
+                escaper = g3_yaml::value::as_metrics_name(v)?;
+                Ok(())
+            }
+            "type" | "match" => {
+                match_type = g3_yaml::value::as_string(v)?.to_lowercase();
+                Ok(())
+            }
+            "path" | "file" => {
+                let file = g3_yaml::value::as_file_path(v, lookup_dir, false)
+                    .context(format!("invalid file path value for key {k}"))?;
+                path = Some(file);
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+        if escaper.is_empty() {
+            return Err(anyhow!("no next escaper set"));
+        }
+        let Some(path) = path else {
+            return Err(anyhow!("no rule file path set"));
+        };
+
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("failed to read rule file {}: {e}", path.display()))?;
+        // one rule per line, empty lines and lines starting with '#' are ignored
+        let rules = content
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| Yaml::String(line.to_string()));
+
+        match match_type.as_str() {
+            "exact" => {
+                for (i, v) in rules.enumerate() {
+                    match g3_yaml::value::as_host(&v)
+                        .context(format!("invalid host value for rule #{i}"))?
+                    {
+                        Host::Ip(ip) => {
+                            self.exact_match_ipaddr
+                                .entry(escaper.clone())
+                                .or_default()
+                                .insert(ip);
+                        }
+                        Host::Domain(domain) => {
+                            self.exact_match_domain
+                                .entry(escaper.clone())
+                                .or_default()
+                                .insert(domain);
+                        }
+                    }
+                }
+            }
+            "subnet" => {
+                let set = self.subnet_match_ipaddr.entry(escaper).or_default();
+                for (i, v) in rules.enumerate() {
+                    let subnet = g3_yaml::value::as_ip_network(&v)
+                        .context(format!("invalid subnet value for rule #{i}"))?;
+                    set.insert(subnet);
+                }
+            }
+            "child" => {
+                let set = self.child_match_domain.entry(escaper).or_default();
+                for (i, v) in rules.enumerate() {
+                    let domain = g3_yaml::value::as_domain(&v)
+                        .context(format!("invalid domain value for rule #{i}"))?;
+                    set.insert(domain);
+                }
+            }
+            "radix" | "suffix" => {
+                let set = self.radix_match_domain.entry(escaper).or_default();
+                for (i, v) in rules.enumerate() {
+                    let domain = g3_yaml::value::as_domain(&v)
+                        .context(format!("invalid domain suffix value for rule #{i}"))?;
+                    set.insert(domain);
+                }
+            }
+            "regex" => {
+                let mut all_regex = Vec::new();
+                for v in rules {
+                    if let Yaml::String(s) = v {
+                        all_regex.push(s);
+                    }
+                }
+                self.push_regex_match(escaper, all_regex)?;
+            }
+            "" => return Err(anyhow!("no rule match type set")),
+            _ => return Err(anyhow!("unsupported rule match type {match_type}")),
+        }
+        Ok(())
+    }
 }
 
 impl EscaperConfig for RouteUpstreamEscaperConfig {
@@ -339,6 +516,233 @@ impl EscaperConfig for RouteUpstreamEscaperConfig {
         for key in self.child_match_domain.keys() {
             set.insert(key.clone());
         }
+        for rule in &self.regex_match_domain {
+            set.insert(rule.next.clone());
+        }
         Some(set)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+    use yaml_rust::YamlLoader;
+
+    fn parse(doc: &str) -> anyhow::Result<RouteUpstreamEscaperConfig> {
+        let docs = YamlLoader::load_from_str(doc).unwrap();
+        RouteUpstreamEscaperConfig::parse(docs[0].as_hash().unwrap(), None)
+    }
+
+    fn rule_map(doc: &str) -> yaml::Hash {
+        let docs = YamlLoader::load_from_str(doc).unwrap();
+        docs[0].as_hash().unwrap().clone()
+    }
+
+    fn rule_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "g3proxy-route-upstream-{name}-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn regex_match() {
+        let config = parse(
+            r#"
+name: route
+default_next: default
+regex_match:
+  - next: video
+    regexes:
+      - 'video[0-9]+\.example\.net'
+      - 'cdn\..*'
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.regex_match_domain.len(), 1);
+        let rule = &config.regex_match_domain[0];
+        assert_eq!(rule.next.as_str(), "video");
+        assert!(rule.is_match("video01.example.net"));
+        assert!(rule.is_match("cdn.example.org"));
+
+        // the regexes are anchored
+        assert!(!rule.is_match("video01.example.net.evil.org"));
+        assert!(!rule.is_match("www.video01.example.net"));
+        assert!(!rule.is_match("www.cdn.example.org"));
+
+        let set = config.dependent_escaper().unwrap();
+        assert!(set.contains(&rule.next));
+    }
+
+    #[test]
+    fn regex_match_alternation() {
+        // the alternation should not escape from the anchor
+        let config = parse(
+            r#"
+name: route
+default_next: default
+regex_match:
+  - next: a
+    regex:
+      - 'a\.example\.net|b\.example\.net'
+"#,
+        )
+        .unwrap();
+        let rule = &config.regex_match_domain[0];
+        assert!(rule.is_match("a.example.net"));
+        assert!(rule.is_match("b.example.net"));
+        assert!(!rule.is_match("a.example.net.org"));
+        assert!(!rule.is_match("www.b.example.net"));
+    }
+
+    #[test]
+    fn regex_match_invalid() {
+        assert!(parse(
+            r#"
+name: route
+default_next: default
+regex_match:
+  - next: a
+    regexes:
+      - 'a(b'
+"#,
+        )
+        .is_err());
+        assert!(parse(
+            r#"
+name: route
+default_next: default
+regex_match:
+  - regexes:
+      - 'a'
+"#,
+        )
+        .is_err());
+        assert!(parse(
+            r#"
+name: route
+default_next: default
+regex_match:
+  - next: a
+    regexes: 'a'
+"#,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn regex_match_eq() {
+        let doc = r#"
+name: route
+default_next: default
+regex_match:
+  - next: a
+    regexes:
+      - 'a\.example\.net'
+"#;
+        let old = parse(doc).unwrap();
+        let new = parse(doc).unwrap();
+        assert!(old == new);
+
+        let new = parse(
+            r#"
+name: route
+default_next: default
+regex_match:
+  - next: a
+    regexes:
+      - 'b\.example\.net'
+"#,
+        )
+        .unwrap();
+        assert!(old != new);
+    }
+
+    #[test]
+    fn rule_file() {
+        let dir = rule_dir("all");
+        std::fs::write(
+            dir.join("exact.txt"),
+            "# exact rules\nwww.example.net\n\n  192.168.1.1  \n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("subnet.txt"), "10.0.0.0/8\n").unwrap();
+        std::fs::write(dir.join("child.txt"), "example.org\n").unwrap();
+        std::fs::write(dir.join("radix.txt"), "example.com\n").unwrap();
+        std::fs::write(
+            dir.join("regex.txt"),
+            "# regex rules\nvideo[0-9]+\\.example\\.net\n",
+        )
+        .unwrap();
+
+        let mut config = RouteUpstreamEscaperConfig::new(None);
+        for match_type in ["exact", "subnet", "child", "radix", "regex"] {
+            let map = rule_map(&format!(
+                "next: {match_type}\ntype: {match_type}\npath: {match_type}.txt\n"
+            ));
+            config.add_rule_file(&map, &dir).unwrap();
+        }
+
+        let exact = MetricsName::from_str("exact").unwrap();
+        let domains = config.exact_match_domain.get(&exact).unwrap();
+        assert_eq!(domains.len(), 1);
+        assert!(domains.contains("www.example.net"));
+        let ips = config.exact_match_ipaddr.get(&exact).unwrap();
+        assert!(ips.contains(&IpAddr::from_str("192.168.1.1").unwrap()));
+
+        let subnet = MetricsName::from_str("subnet").unwrap();
+        let nets = config.subnet_match_ipaddr.get(&subnet).unwrap();
+        assert!(nets.contains(&IpNetwork::from_str("10.0.0.0/8").unwrap()));
+
+        let child = MetricsName::from_str("child").unwrap();
+        assert!(config
+            .child_match_domain
+            .get(&child)
+            .unwrap()
+            .contains("example.org"));
+
+        let radix = MetricsName::from_str("radix").unwrap();
+        assert!(config
+            .radix_match_domain
+            .get(&radix)
+            .unwrap()
+            .contains("example.com"));
+
+        assert_eq!(config.regex_match_domain.len(), 1);
+        let rule = &config.regex_match_domain[0];
+        assert_eq!(rule.next.as_str(), "regex");
+        assert!(rule.is_match("video1.example.net"));
+        assert!(!rule.is_match("www.video1.example.net"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rule_file_invalid() {
+        let dir = rule_dir("invalid");
+        std::fs::write(dir.join("subnet.txt"), "10.0.0.0/8\nnot-a-subnet\n").unwrap();
+        std::fs::write(dir.join("regex.txt"), "a(b\n").unwrap();
+
+        let mut config = RouteUpstreamEscaperConfig::new(None);
+        let map = rule_map("next: a\ntype: subnet\npath: subnet.txt\n");
+        assert!(config.add_rule_file(&map, &dir).is_err());
+        let map = rule_map("next: a\ntype: regex\npath: regex.txt\n");
+        assert!(config.add_rule_file(&map, &dir).is_err());
+        let map = rule_map("next: a\ntype: unknown\npath: subnet.txt\n");
+        assert!(config.add_rule_file(&map, &dir).is_err());
+        let map = rule_map("next: a\npath: subnet.txt\n");
+        assert!(config.add_rule_file(&map, &dir).is_err());
+        let map = rule_map("next: a\ntype: subnet\n");
+        assert!(config.add_rule_file(&map, &dir).is_err());
+        let map = rule_map("type: subnet\npath: subnet.txt\n");
+        assert!(config.add_rule_file(&map, &dir).is_err());
+        let map = rule_map("next: a\ntype: subnet\npath: missing.txt\n");
+        assert!(config.add_rule_file(&map, &dir).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -14,9 +14,9 @@
  * limitations under the License.
  */
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use yaml_rust::{yaml, Yaml};
 
 mod graphviz;
@@ -28,12 +28,21 @@ pub use mermaid::mermaid_graph;
 mod plantuml;
 pub use plantuml::plantuml_graph;
 
+mod check;
+pub use check::check_all;
+
+pub(crate) mod admin_api;
 pub(crate) mod audit;
 pub(crate) mod auth;
+pub(crate) mod cert_watch;
 pub(crate) mod escaper;
 pub(crate) mod log;
+pub(crate) mod partial;
 pub(crate) mod resolver;
+pub(crate) mod sandbox;
 pub(crate) mod server;
+pub(crate) mod store;
+pub(crate) mod trace;
 
 #[cfg(feature = "geoip")]
 mod geoip;
@@ -44,9 +53,12 @@ pub fn load() -> anyhow::Result<&'static Path> {
 
     // allow multiple docs, and treat them as the same
     g3_yaml::foreach_doc(config_file, |_, doc| match doc {
-        Yaml::Hash(map) => load_doc(map),
+        Yaml::Hash(map) => load_doc(map, true),
         _ => Err(anyhow!("yaml doc root should be hash")),
     })?;
+    // the docs in config store should be in the same format as the included files
+    store::foreach_doc(false, |map| load_doc(map, false))?;
+    partial::record_loaded();
 
     Ok(config_file)
 }
@@ -70,18 +82,56 @@ fn reload_blocking() -> anyhow::Result<()> {
     if let Some(conf_file) = g3_daemon::opts::config_file() {
         // allow multiple docs, and treat them as the same
         g3_yaml::foreach_doc(conf_file, |_, doc| match doc {
-            Yaml::Hash(map) => reload_doc(map),
+            Yaml::Hash(map) => reload_doc(map, true),
             _ => Err(anyhow!("yaml doc root should be hash")),
         })?;
+        store::foreach_doc(true, |map| reload_doc(map, false))?;
+        partial::record_loaded();
     }
     Ok(())
 }
 
-fn reload_doc(map: &yaml::Hash) -> anyhow::Result<()> {
+/// get all the files set in the include value
+fn included_files(v: &Yaml, conf_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let paths = match v {
+        Yaml::String(s) => vec![s.as_str()],
+        Yaml::Array(seq) => seq
+            .iter()
+            .map(|v| match v {
+                Yaml::String(s) => Ok(s.as_str()),
+                _ => Err(anyhow!("the include path should be a string")),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?,
+        _ => return Err(anyhow!("invalid value type for include")),
+    };
+    let mut files = Vec::new();
+    for path in paths {
+        files.extend(g3_yaml::resolve_files(conf_dir, path)?);
+    }
+    Ok(files)
+}
+
+/// load all the docs in the included files, which should be in the same format as the main conf
+fn foreach_included_doc<F>(v: &Yaml, conf_dir: &Path, f: F) -> anyhow::Result<()>
+where
+    F: Fn(&yaml::Hash) -> anyhow::Result<()>,
+{
+    for file in included_files(v, conf_dir)? {
+        g3_yaml::foreach_doc(&file, |_, doc| match doc {
+            Yaml::Hash(map) => f(map),
+            _ => Err(anyhow!("yaml doc root should be hash")),
+        })
+        .context(format!("failed to load included file {}", file.display()))?;
+    }
+    Ok(())
+}
+
+fn reload_doc(map: &yaml::Hash, allow_include: bool) -> anyhow::Result<()> {
     let conf_dir =
         g3_daemon::opts::config_dir().ok_or_else(|| anyhow!("no valid config dir has been set"))?;
     g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
-        "runtime" | "worker" | "log" | "stat" | "controller" => Ok(()),
+        "runtime" | "worker" | "log" | "stat" | "controller" | "trace" | "admin_api"
+        | "cert_watch" | "sandbox" | "config_store" => Ok(()),
         #[cfg(feature = "geoip")]
         "geoip_db" => geoip::load(v, conf_dir),
         "escaper" => escaper::load_all(v, conf_dir),
@@ -89,20 +139,32 @@ fn reload_doc(map: &yaml::Hash) -> anyhow::Result<()> {
         "resolver" => resolver::load_all(v, conf_dir),
         "user" | "user_group" => auth::load_all(v, conf_dir),
         "auditor" => audit::load_all(v, conf_dir),
+        "include" if allow_include => {
+            foreach_included_doc(v, conf_dir, |map| reload_doc(map, false))
+        }
         _ => Ok(()),
     })?;
     Ok(())
 }
 
-fn load_doc(map: &yaml::Hash) -> anyhow::Result<()> {
+fn load_doc(map: &yaml::Hash, allow_include: bool) -> anyhow::Result<()> {
     let conf_dir =
         g3_daemon::opts::config_dir().ok_or_else(|| anyhow!("no valid config dir has been set"))?;
-    g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+    g3_yaml::foreach_kv(map, |k, v| load_kv(k, v, conf_dir, allow_include))
+}
+
+fn load_kv(k: &str, v: &Yaml, conf_dir: &Path, allow_include: bool) -> anyhow::Result<()> {
+    match g3_yaml::key::normalize(k).as_str() {
         "runtime" => g3_daemon::runtime::config::load(v),
         "worker" => g3_daemon::runtime::config::load_worker(v),
         "log" => log::load(v, conf_dir),
         "stat" => g3_daemon::stat::config::load(v, crate::build::PKG_NAME),
         "controller" => g3_daemon::control::config::load(v),
+        "trace" => trace::load(v).context(format!("invalid value for key {k}")),
+        "admin_api" => admin_api::load(v).context(format!("invalid value for key {k}")),
+        "cert_watch" => cert_watch::load(v).context(format!("invalid value for key {k}")),
+        "sandbox" => sandbox::load(v).context(format!("invalid value for key {k}")),
+        "config_store" => store::load(v, conf_dir).context(format!("invalid value for key {k}")),
         #[cfg(feature = "geoip")]
         "geoip_db" => geoip::load(v, conf_dir),
         "escaper" => escaper::load_all(v, conf_dir),
@@ -110,7 +172,9 @@ fn load_doc(map: &yaml::Hash) -> anyhow::Result<()> {
         "resolver" => resolver::load_all(v, conf_dir),
         "user" | "user_group" => auth::load_all(v, conf_dir),
         "auditor" => audit::load_all(v, conf_dir),
+        "include" if allow_include => foreach_included_doc(v, conf_dir, |map| load_doc(map, false))
+            .context(format!("invalid value for key {k}")),
+        "include" => Err(anyhow!("nested include is not supported")),
         _ => Err(anyhow!("invalid key {k} in main conf")),
-    })?;
-    Ok(())
+    }
 }
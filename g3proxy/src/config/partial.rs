@@ -0,0 +1,194 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use once_cell::sync::Lazy;
+use yaml_rust::{yaml, Yaml};
+
+use g3_yaml::{HybridParser, YamlDocPosition};
+
+static LOADED_ENTRIES: Lazy<Mutex<HashMap<(EntryKind, String), yaml::Hash>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum EntryKind {
+    Server,
+    Escaper,
+    Auditor,
+    Resolver,
+    UserGroup,
+}
+
+impl EntryKind {
+    pub(crate) fn parse(s: &str) -> anyhow::Result<Self> {
+        match g3_yaml::key::normalize(s).as_str() {
+            "server" => Ok(EntryKind::Server),
+            "escaper" => Ok(EntryKind::Escaper),
+            "auditor" => Ok(EntryKind::Auditor),
+            "resolver" => Ok(EntryKind::Resolver),
+            "user_group" | "user" => Ok(EntryKind::UserGroup),
+            _ => Err(anyhow!("unsupported entry type {s}")),
+        }
+    }
+
+    fn from_conf_key(k: &str) -> Option<Self> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "server" => Some(EntryKind::Server),
+            "escaper" => Some(EntryKind::Escaper),
+            "auditor" => Some(EntryKind::Auditor),
+            "resolver" => Some(EntryKind::Resolver),
+            "user" | "user_group" => Some(EntryKind::UserGroup),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::Server => "server",
+            EntryKind::Escaper => "escaper",
+            EntryKind::Auditor => "auditor",
+            EntryKind::Resolver => "resolver",
+            EntryKind::UserGroup => "user group",
+        }
+    }
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single named entry found in the on-disk config
+pub(crate) struct OnDiskEntry {
+    map: yaml::Hash,
+    pub(crate) position: Option<YamlDocPosition>,
+}
+
+impl OnDiskEntry {
+    /// get the changed top level keys compared to the one recorded when it's loaded,
+    /// None will be returned if no record found
+    pub(crate) fn diff_loaded(&self, kind: EntryKind, name: &str) -> Option<Vec<String>> {
+        let ht = LOADED_ENTRIES.lock().unwrap();
+        let old = ht.get(&(kind, name.to_string()))?;
+        Some(diff_map(old, &self.map))
+    }
+
+    /// record this as the loaded one
+    pub(crate) fn mark_loaded(self, kind: EntryKind, name: &str) {
+        let mut ht = LOADED_ENTRIES.lock().unwrap();
+        ht.insert((kind, name.to_string()), self.map);
+    }
+}
+
+fn key_str(k: &Yaml) -> String {
+    match k {
+        Yaml::String(s) => s.to_string(),
+        _ => format!("{k:?}"),
+    }
+}
+
+fn diff_map(old: &yaml::Hash, new: &yaml::Hash) -> Vec<String> {
+    let mut changes = Vec::new();
+    for (k, v) in new {
+        match old.get(k) {
+            Some(old_v) if old_v == v => {}
+            Some(_) => changes.push(format!("~{}", key_str(k))),
+            None => changes.push(format!("+{}", key_str(k))),
+        }
+    }
+    for k in old.keys() {
+        if !new.contains_key(k) {
+            changes.push(format!("-{}", key_str(k)));
+        }
+    }
+    changes
+}
+
+type EntryVisitor<'a> =
+    RefCell<&'a mut dyn FnMut(EntryKind, &str, &yaml::Hash, Option<YamlDocPosition>)>;
+
+fn walk_doc(
+    map: &yaml::Hash,
+    conf_dir: &Path,
+    parser: &HybridParser,
+    allow_include: bool,
+    f: &EntryVisitor<'_>,
+) -> anyhow::Result<()> {
+    g3_yaml::foreach_kv(map, |k, v| {
+        if let Some(kind) = EntryKind::from_conf_key(k) {
+            parser.foreach_map(v, |map, position| {
+                let name = g3_yaml::hash_get_required_str(map, "name")?;
+                (f.borrow_mut())(kind, name, map, position);
+                Ok(())
+            })
+        } else if allow_include && g3_yaml::key::normalize(k) == "include" {
+            super::foreach_included_doc(v, conf_dir, |map| {
+                walk_doc(map, conf_dir, parser, false, f)
+            })
+        } else {
+            Ok(())
+        }
+    })
+}
+
+fn foreach_entry<F>(mut f: F) -> anyhow::Result<()>
+where
+    F: FnMut(EntryKind, &str, &yaml::Hash, Option<YamlDocPosition>),
+{
+    let conf_file = g3_daemon::opts::config_file().ok_or_else(|| anyhow!("no config file set"))?;
+    let conf_dir =
+        g3_daemon::opts::config_dir().ok_or_else(|| anyhow!("no valid config dir has been set"))?;
+    let parser = HybridParser::new(conf_dir, g3_daemon::opts::config_file_extension());
+    let f: EntryVisitor<'_> = RefCell::new(&mut f);
+
+    g3_yaml::foreach_doc(conf_file, |_, doc| match doc {
+        Yaml::Hash(map) => walk_doc(map, conf_dir, &parser, true, &f),
+        _ => Err(anyhow!("yaml doc root should be hash")),
+    })
+}
+
+/// find the named entry in the on-disk config
+pub(crate) fn find_on_disk(kind: EntryKind, name: &str) -> anyhow::Result<Option<OnDiskEntry>> {
+    let mut found = None;
+    foreach_entry(|k, n, map, position| {
+        if k == kind && n == name {
+            found = Some(OnDiskEntry {
+                map: map.clone(),
+                position,
+            });
+        }
+    })?;
+    Ok(found)
+}
+
+/// record all the entries in the just loaded config
+pub(super) fn record_loaded() {
+    let mut entries = HashMap::new();
+    if foreach_entry(|kind, name, map, _| {
+        entries.insert((kind, name.to_string()), map.clone());
+    })
+    .is_ok()
+    {
+        *LOADED_ENTRIES.lock().unwrap() = entries;
+    }
+}
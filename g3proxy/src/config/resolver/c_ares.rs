@@ -151,6 +151,41 @@ impl CAresResolverConfig {
                 self.runtime.protective_query_timeout = g3_yaml::humanize::as_duration(v)?;
                 Ok(())
             }
+            "static_hosts" => {
+                self.runtime.static_hosts =
+                    super::static_hosts::as_static_hosts_config(v, self.position.as_ref())
+                        .context(format!("invalid static hosts config value for key {k}"))?;
+                Ok(())
+            }
+            "deny_list" => {
+                self.runtime.deny_list = super::deny_list::as_deny_list_config(v)
+                    .context(format!("invalid deny list config value for key {k}"))?;
+                Ok(())
+            }
+            "nxdomain_negative_ttl" => {
+                let ttl = g3_yaml::value::as_u32(v)?;
+                self.runtime.nxdomain_negative_ttl = Some(ttl);
+                Ok(())
+            }
+            "servfail_negative_ttl" => {
+                let ttl = g3_yaml::value::as_u32(v)?;
+                self.runtime.servfail_negative_ttl = Some(ttl);
+                Ok(())
+            }
+            "serve_stale" | "serve_stale_time" => {
+                let time = g3_yaml::humanize::as_duration(v)?;
+                self.runtime.serve_stale = if time.is_zero() { None } else { Some(time) };
+                Ok(())
+            }
+            "prefetch_min_hits" => {
+                let hits = g3_yaml::value::as_u32(v)?;
+                self.runtime.prefetch_min_hits = if hits == 0 { None } else { Some(hits) };
+                Ok(())
+            }
+            "prefetch_ahead" => {
+                self.runtime.prefetch_ahead = g3_yaml::humanize::as_duration(v)?;
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
@@ -26,6 +26,7 @@ use super::hickory;
 
 use super::deny_all;
 use super::fail_over;
+use super::split;
 
 pub(super) const CONFIG_KEY_RESOLVER_TYPE: &str = "type";
 pub(super) const CONFIG_KEY_RESOLVER_NAME: &str = "name";
@@ -53,6 +54,7 @@ pub(crate) enum AnyResolverConfig {
     Hickory(hickory::HickoryResolverConfig),
     DenyAll(deny_all::DenyAllResolverConfig),
     FailOver(fail_over::FailOverResolverConfig),
+    Split(split::SplitResolverConfig),
 }
 
 macro_rules! impl_transparent0 {
@@ -65,6 +67,7 @@ macro_rules! impl_transparent0 {
                 AnyResolverConfig::Hickory(r) => r.$f(),
                 AnyResolverConfig::DenyAll(r) => r.$f(),
                 AnyResolverConfig::FailOver(r) => r.$f(),
+                AnyResolverConfig::Split(r) => r.$f(),
             }
         }
     };
@@ -80,6 +83,7 @@ macro_rules! impl_transparent1 {
                 AnyResolverConfig::Hickory(r) => r.$f(p),
                 AnyResolverConfig::DenyAll(r) => r.$f(p),
                 AnyResolverConfig::FailOver(r) => r.$f(p),
+                AnyResolverConfig::Split(r) => r.$f(p),
             }
         }
     };
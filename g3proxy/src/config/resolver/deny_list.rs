@@ -0,0 +1,48 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use g3_resolver::ResolverDenyListConfig;
+
+pub(super) fn as_deny_list_config(v: &Yaml) -> anyhow::Result<ResolverDenyListConfig> {
+    let mut config = ResolverDenyListConfig::default();
+    match v {
+        Yaml::String(_) => add_domain(&mut config, v)?,
+        Yaml::Array(seq) => {
+            for (i, v) in seq.iter().enumerate() {
+                add_domain(&mut config, v).context(format!("invalid domain value for #{i}"))?;
+            }
+        }
+        _ => return Err(anyhow!("invalid yaml value type for deny list config")),
+    }
+    Ok(config)
+}
+
+fn add_domain(config: &mut ResolverDenyListConfig, v: &Yaml) -> anyhow::Result<()> {
+    let Yaml::String(s) = v else {
+        return Err(anyhow!("the yaml value type should be string"));
+    };
+    if let Some(parent) = s.strip_prefix("*.") {
+        let domain = g3_yaml::value::as_domain(&Yaml::String(parent.to_string()))?;
+        config.add_child(&domain);
+    } else {
+        let domain = g3_yaml::value::as_domain(v)?;
+        config.add_exact(&domain);
+    }
+    Ok(())
+}
@@ -22,7 +22,7 @@ use anyhow::{anyhow, Context};
 use yaml_rust::{yaml, Yaml};
 
 use g3_resolver::driver::hickory::HickoryDriverConfig;
-use g3_resolver::{AnyResolveDriverConfig, ResolverRuntimeConfig};
+use g3_resolver::{AnyResolveDriverConfig, ClientSubnetConfig, ResolverRuntimeConfig};
 use g3_types::metrics::MetricsName;
 use g3_yaml::YamlDocPosition;
 
@@ -77,6 +77,11 @@ impl HickoryResolverConfig {
         self.driver.get_encryption().map(|c| c.summary())
     }
 
+    #[inline]
+    pub(crate) fn get_client_subnet(&self) -> Option<&ClientSubnetConfig> {
+        self.driver.get_client_subnet()
+    }
+
     pub(crate) fn parse(
         map: &yaml::Hash,
         position: Option<YamlDocPosition>,
@@ -114,6 +119,14 @@ impl HickoryResolverConfig {
                 self.driver.set_encryption(config);
                 Ok(())
             }
+            "client_subnet" | "ecs" => {
+                if let Some(config) = as_client_subnet_config(v)
+                    .context(format!("invalid client subnet config value for key {k}"))?
+                {
+                    self.driver.set_client_subnet(config);
+                }
+                Ok(())
+            }
             "each_timeout" => {
                 let timeout = g3_yaml::humanize::as_duration(v)
                     .context(format!("invalid humanize duration value for key {k}"))?;
@@ -158,6 +171,41 @@ impl HickoryResolverConfig {
                 self.runtime.protective_query_timeout = g3_yaml::humanize::as_duration(v)?;
                 Ok(())
             }
+            "static_hosts" => {
+                self.runtime.static_hosts =
+                    super::static_hosts::as_static_hosts_config(v, self.position.as_ref())
+                        .context(format!("invalid static hosts config value for key {k}"))?;
+                Ok(())
+            }
+            "deny_list" => {
+                self.runtime.deny_list = super::deny_list::as_deny_list_config(v)
+                    .context(format!("invalid deny list config value for key {k}"))?;
+                Ok(())
+            }
+            "nxdomain_negative_ttl" => {
+                let ttl = g3_yaml::value::as_u32(v)?;
+                self.runtime.nxdomain_negative_ttl = Some(ttl);
+                Ok(())
+            }
+            "servfail_negative_ttl" => {
+                let ttl = g3_yaml::value::as_u32(v)?;
+                self.runtime.servfail_negative_ttl = Some(ttl);
+                Ok(())
+            }
+            "serve_stale" | "serve_stale_time" => {
+                let time = g3_yaml::humanize::as_duration(v)?;
+                self.runtime.serve_stale = if time.is_zero() { None } else { Some(time) };
+                Ok(())
+            }
+            "prefetch_min_hits" => {
+                let hits = g3_yaml::value::as_u32(v)?;
+                self.runtime.prefetch_min_hits = if hits == 0 { None } else { Some(hits) };
+                Ok(())
+            }
+            "prefetch_ahead" => {
+                self.runtime.prefetch_ahead = g3_yaml::humanize::as_duration(v)?;
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
@@ -196,6 +244,11 @@ impl HickoryResolverConfig {
         if self.driver.is_unspecified() {
             return Err(anyhow!("no dns server has been set"));
         }
+        if self.driver.get_client_subnet().is_some() && self.driver.get_encryption().is_some() {
+            return Err(anyhow!(
+                "client subnet can not be used together with encryption"
+            ));
+        }
 
         Ok(())
     }
@@ -231,3 +284,24 @@ impl super::ResolverConfig for HickoryResolverConfig {
         None
     }
 }
+
+fn as_client_subnet_config(v: &Yaml) -> anyhow::Result<Option<ClientSubnetConfig>> {
+    if let Yaml::Hash(map) = v {
+        let mut config = ClientSubnetConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "ipv4_prefix" => {
+                let prefix = g3_yaml::value::as_u8(v)?;
+                config.set_ipv4_prefix(prefix)
+            }
+            "ipv6_prefix" => {
+                let prefix = g3_yaml::value::as_u8(v)?;
+                config.set_ipv6_prefix(prefix)
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+        Ok(Some(config))
+    } else {
+        let enable = g3_yaml::value::as_bool(v)?;
+        Ok(enable.then(ClientSubnetConfig::default))
+    }
+}
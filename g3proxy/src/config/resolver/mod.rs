@@ -33,33 +33,39 @@ pub(crate) mod hickory;
 
 pub(crate) mod deny_all;
 pub(crate) mod fail_over;
+pub(crate) mod split;
 
 mod config;
+mod deny_list;
+mod static_hosts;
 
 pub(crate) use config::{AnyResolverConfig, ResolverConfig, ResolverConfigDiffAction};
 
 use config::{CONFIG_KEY_RESOLVER_NAME, CONFIG_KEY_RESOLVER_TYPE};
 
 mod registry;
-pub(crate) use registry::clear;
+pub(crate) use registry::{clear, get_all};
 
 pub(crate) fn load_all(v: &Yaml, conf_dir: &Path) -> anyhow::Result<()> {
     let parser = HybridParser::new(conf_dir, g3_daemon::opts::config_file_extension());
-    parser.foreach_map(v, |map, position| {
-        let resolver = load_resolver(map, position)?;
-        if let Some(old) = registry::add(resolver) {
-            Err(anyhow!(
-                "resolver with name {} has already been added",
-                old.name()
-            ))
-        } else {
-            Ok(())
-        }
-    })?;
+    parser.foreach_map(v, load_one)?;
     check_dependency()?;
     Ok(())
 }
 
+/// load and add one resolver, the dependency is not checked
+pub(crate) fn load_one(map: &yaml::Hash, position: Option<YamlDocPosition>) -> anyhow::Result<()> {
+    let resolver = load_resolver(map, position)?;
+    if let Some(old) = registry::add(resolver) {
+        Err(anyhow!(
+            "resolver with name {} has already been added",
+            old.name()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 pub(crate) fn load_at_position(position: &YamlDocPosition) -> anyhow::Result<AnyResolverConfig> {
     let doc = g3_yaml::load_doc(position)?;
     if let Yaml::Hash(map) = doc {
@@ -109,6 +115,11 @@ fn load_resolver(
                 .context("failed to load this FailOver resolver")?;
             Ok(AnyResolverConfig::FailOver(resolver))
         }
+        "split" | "split_dns" | "splitdns" => {
+            let resolver = split::SplitResolverConfig::parse(map, position)
+                .context("failed to load this Split resolver")?;
+            Ok(AnyResolverConfig::Split(resolver))
+        }
         _ => Err(anyhow!("unsupported resolver type {resolver_type}")),
     }
 }
@@ -43,7 +43,7 @@ pub(super) fn del(name: &MetricsName) {
     ht.remove(name);
 }
 
-pub(super) fn get_all() -> Vec<Arc<AnyResolverConfig>> {
+pub(crate) fn get_all() -> Vec<Arc<AnyResolverConfig>> {
     let mut vec = Vec::new();
     let ht = INITIAL_RESOLVER_CONFIG_REGISTRY.lock().unwrap();
     for v in ht.values() {
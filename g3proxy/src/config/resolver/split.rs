@@ -0,0 +1,181 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, Context};
+use yaml_rust::{yaml, Yaml};
+
+use g3_types::metrics::MetricsName;
+use g3_yaml::YamlDocPosition;
+
+use super::{AnyResolverConfig, ResolverConfig, ResolverConfigDiffAction};
+
+const RESOLVER_CONFIG_TYPE: &str = "split";
+
+#[derive(Clone, Eq, PartialEq)]
+pub(crate) struct SplitResolverConfig {
+    position: Option<YamlDocPosition>,
+    name: MetricsName,
+    pub(crate) child_match_domain: BTreeMap<MetricsName, BTreeSet<String>>,
+    pub(crate) default_next: MetricsName,
+}
+
+impl SplitResolverConfig {
+    fn new(position: Option<YamlDocPosition>) -> Self {
+        SplitResolverConfig {
+            position,
+            name: MetricsName::default(),
+            child_match_domain: BTreeMap::new(),
+            default_next: MetricsName::default(),
+        }
+    }
+
+    pub(crate) fn parse(
+        map: &yaml::Hash,
+        position: Option<YamlDocPosition>,
+    ) -> anyhow::Result<Self> {
+        let mut resolver = Self::new(position);
+
+        g3_yaml::foreach_kv(map, |k, v| resolver.set(k, v))?;
+
+        resolver.check()?;
+        Ok(resolver)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            super::CONFIG_KEY_RESOLVER_TYPE => Ok(()),
+            super::CONFIG_KEY_RESOLVER_NAME => {
+                self.name = g3_yaml::value::as_metrics_name(v)?;
+                Ok(())
+            }
+            "rules" | "child_match" | "child_rules" => {
+                if let Yaml::Array(seq) = v {
+                    for (i, rule) in seq.iter().enumerate() {
+                        if let Yaml::Hash(map) = rule {
+                            self.add_child_match(map)
+                                .context(format!("failed to parse rule {k}#{i}"))?;
+                        } else {
+                            return Err(anyhow!("invalid value type for {k}#{i}"));
+                        }
+                    }
+                    Ok(())
+                } else {
+                    Err(anyhow!("invalid array value for key {k}"))
+                }
+            }
+            "default_next" | "default" => {
+                self.default_next = g3_yaml::value::as_metrics_name(v)?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn add_child_match(&mut self, map: &yaml::Hash) -> anyhow::Result<()> {
+        let mut next = MetricsName::default();
+        let mut all_domain = BTreeSet::<String>::new();
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "next" | "resolver" => {
+                next = g3_yaml::value::as_metrics_name(v)?;
+                Ok(())
+            }
+            "domains" | "domain" | "suffixes" | "suffix" => {
+                if let Yaml::Array(seq) = v {
+                    for (i, v) in seq.iter().enumerate() {
+                        let domain = g3_yaml::value::as_domain(v)
+                            .context(format!("invalid domain suffix value for {k}:{i}"))?;
+                        all_domain.insert(domain);
+                    }
+                    Ok(())
+                } else {
+                    let domain = g3_yaml::value::as_domain(v)
+                        .context(format!("invalid domain suffix value for key {k}"))?;
+                    all_domain.insert(domain);
+                    Ok(())
+                }
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+        if next.is_empty() {
+            return Err(anyhow!("no next resolver set"));
+        }
+        if all_domain.is_empty() {
+            return Err(anyhow!("no domain suffix set for next resolver {next}"));
+        }
+        if let Some(_old) = self.child_match_domain.insert(next.clone(), all_domain) {
+            return Err(anyhow!("found multiple entries for next resolver {next}"));
+        }
+        Ok(())
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.name.is_empty() {
+            return Err(anyhow!("name is not set"));
+        }
+        if self.default_next.is_empty() {
+            return Err(anyhow!("no default next resolver set"));
+        }
+
+        let mut all_domain = BTreeSet::new();
+        for domains in self.child_match_domain.values() {
+            for domain in domains {
+                if !all_domain.insert(domain.as_str()) {
+                    return Err(anyhow!("found duplicated domain suffix {domain}"));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ResolverConfig for SplitResolverConfig {
+    fn name(&self) -> &MetricsName {
+        &self.name
+    }
+
+    fn position(&self) -> Option<YamlDocPosition> {
+        self.position.clone()
+    }
+
+    fn resolver_type(&self) -> &'static str {
+        RESOLVER_CONFIG_TYPE
+    }
+
+    fn diff_action(&self, new: &AnyResolverConfig) -> ResolverConfigDiffAction {
+        let new = match new {
+            AnyResolverConfig::Split(new) => new,
+            _ => return ResolverConfigDiffAction::SpawnNew,
+        };
+
+        if self.eq(new) {
+            return ResolverConfigDiffAction::NoAction;
+        }
+
+        ResolverConfigDiffAction::Update
+    }
+
+    fn dependent_resolver(&self) -> Option<BTreeSet<MetricsName>> {
+        let mut set = BTreeSet::new();
+        for name in self.child_match_domain.keys() {
+            set.insert(name.clone());
+        }
+        set.insert(self.default_next.clone());
+        Some(set)
+    }
+}
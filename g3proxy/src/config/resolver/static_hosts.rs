@@ -0,0 +1,95 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use g3_resolver::ResolverStaticHostsConfig;
+use g3_yaml::YamlDocPosition;
+
+pub(super) fn as_static_hosts_config(
+    v: &Yaml,
+    position: Option<&YamlDocPosition>,
+) -> anyhow::Result<ResolverStaticHostsConfig> {
+    let mut config = ResolverStaticHostsConfig::default();
+    match v {
+        Yaml::Hash(map) => {
+            g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                "records" | "record" => {
+                    if let Yaml::Hash(map) = v {
+                        g3_yaml::foreach_kv(map, |k, v| {
+                            let (wildcard, name) = match k.strip_prefix("*.") {
+                                Some(s) => (true, s),
+                                None => (false, k),
+                            };
+                            let domain = g3_yaml::value::as_domain(&Yaml::String(name.to_string()))
+                                .context(format!("invalid domain {k}"))?;
+                            let domain = if wildcard {
+                                format!("*.{domain}")
+                            } else {
+                                domain
+                            };
+                            add_records(&mut config, &domain, v)
+                                .context(format!("invalid ip address value for domain {k}"))
+                        })
+                    } else {
+                        Err(anyhow!("invalid map value for key {k}"))
+                    }
+                }
+                "hosts_file" | "file" => {
+                    let lookup_dir = g3_daemon::config::get_lookup_dir(position)?;
+                    let path = g3_yaml::value::as_file_path(v, lookup_dir, false)
+                        .context(format!("invalid file path value for key {k}"))?;
+                    config.set_hosts_file(path);
+                    Ok(())
+                }
+                "check_interval" | "file_check_interval" => {
+                    let interval = g3_yaml::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_file_check_interval(interval);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+        }
+        Yaml::String(_) => {
+            let lookup_dir = g3_daemon::config::get_lookup_dir(position)?;
+            let path = g3_yaml::value::as_file_path(v, lookup_dir, false)
+                .context("invalid hosts file path value")?;
+            config.set_hosts_file(path);
+        }
+        _ => return Err(anyhow!("invalid yaml value type for static hosts config")),
+    }
+    config.check()?;
+    Ok(config)
+}
+
+fn add_records(
+    config: &mut ResolverStaticHostsConfig,
+    domain: &str,
+    v: &Yaml,
+) -> anyhow::Result<()> {
+    if let Yaml::Array(seq) = v {
+        for (i, v) in seq.iter().enumerate() {
+            let ip = g3_yaml::value::as_ipaddr(v).context(format!("invalid value for #{i}"))?;
+            config.add_record(domain, ip);
+        }
+    } else {
+        let ip = g3_yaml::value::as_ipaddr(v)?;
+        config.add_record(domain, ip);
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Context};
+use nix::unistd::{Group, User};
+use yaml_rust::Yaml;
+
+static GLOBAL_SANDBOX_CONFIG: OnceLock<SandboxConfig> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SeccompDenyAction {
+    /// return EPERM to the caller
+    Errno,
+    /// kill the whole process
+    Kill,
+    /// only log the syscall and allow it, for testing
+    Log,
+}
+
+impl FromStr for SeccompDenyAction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "errno" | "eperm" => Ok(SeccompDenyAction::Errno),
+            "kill" => Ok(SeccompDenyAction::Kill),
+            "log" => Ok(SeccompDenyAction::Log),
+            _ => Err(()),
+        }
+    }
+}
+
+pub(crate) struct SandboxConfig {
+    pub(crate) seccomp: bool,
+    pub(crate) seccomp_deny_action: SeccompDenyAction,
+    pub(crate) landlock: bool,
+    pub(crate) read_paths: Vec<PathBuf>,
+    pub(crate) write_paths: Vec<PathBuf>,
+    pub(crate) user: Option<User>,
+    pub(crate) group: Option<Group>,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        SandboxConfig {
+            seccomp: true,
+            seccomp_deny_action: SeccompDenyAction::Errno,
+            landlock: true,
+            read_paths: Vec::new(),
+            write_paths: Vec::new(),
+            user: None,
+            group: None,
+        }
+    }
+}
+
+impl SandboxConfig {
+    fn parse(map: &yaml_rust::yaml::Hash) -> anyhow::Result<Self> {
+        let mut config = SandboxConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| config.set(k, v))?;
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "seccomp" => {
+                self.seccomp = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
+            "seccomp_deny_action" | "seccomp_action" => {
+                let s = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                self.seccomp_deny_action = SeccompDenyAction::from_str(&s)
+                    .map_err(|_| anyhow!("invalid seccomp deny action {s}"))?;
+                Ok(())
+            }
+            "landlock" => {
+                self.landlock = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
+            "read_paths" | "read_path" => {
+                self.read_paths = g3_yaml::value::as_list(v, g3_yaml::value::as_absolute_path)
+                    .context(format!("invalid absolute path list value for key {k}"))?;
+                Ok(())
+            }
+            "write_paths" | "write_path" => {
+                self.write_paths = g3_yaml::value::as_list(v, g3_yaml::value::as_absolute_path)
+                    .context(format!("invalid absolute path list value for key {k}"))?;
+                Ok(())
+            }
+            "user" => {
+                let name = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                let user = User::from_name(&name)
+                    .map_err(|e| anyhow!("failed to get user {name}: {e}"))?
+                    .ok_or_else(|| anyhow!("no user named {name} found"))?;
+                self.user = Some(user);
+                Ok(())
+            }
+            "group" => {
+                let name = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                let group = Group::from_name(&name)
+                    .map_err(|e| anyhow!("failed to get group {name}: {e}"))?
+                    .ok_or_else(|| anyhow!("no group named {name} found"))?;
+                self.group = Some(group);
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+}
+
+pub(crate) fn load(v: &Yaml) -> anyhow::Result<()> {
+    let config = match v {
+        Yaml::Hash(map) => SandboxConfig::parse(map)?,
+        Yaml::Boolean(true) => SandboxConfig::default(),
+        Yaml::Boolean(false) => return Ok(()),
+        _ => return Err(anyhow!("invalid value type")),
+    };
+    GLOBAL_SANDBOX_CONFIG
+        .set(config)
+        .map_err(|_| anyhow!("sandbox config has already been set"))
+}
+
+pub(crate) fn get_global_config() -> Option<&'static SandboxConfig> {
+    GLOBAL_SANDBOX_CONFIG.get()
+}
@@ -24,6 +24,7 @@ use http::HeaderName;
 use yaml_rust::{yaml, Yaml};
 
 use g3_ftp_client::FtpClientConfig;
+use g3_histogram::HistogramMetricsConfig;
 use g3_io_ext::LimitedCopyConfig;
 use g3_types::acl::{AclExactPortRule, AclNetworkRuleBuilder};
 use g3_types::acl_set::AclDstHostRuleSetBuilder;
@@ -93,11 +94,13 @@ pub(crate) struct HttpProxyServerConfig {
     pub(crate) body_line_max_len: usize,
     pub(crate) http_forward_upstream_keepalive: HttpKeepAliveConfig,
     pub(crate) http_forward_mark_upstream: bool,
+    pub(crate) http_forward_stale_retry: usize,
     pub(crate) echo_chained_info: bool,
     pub(crate) untrusted_read_limit: Option<TcpSockSpeedLimitConfig>,
     pub(crate) egress_path_selection_header: Option<HeaderName>,
     pub(crate) steal_forwarded_for: bool,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) duration_stats: Option<HistogramMetricsConfig>,
 }
 
 impl HttpProxyServerConfig {
@@ -135,11 +138,13 @@ impl HttpProxyServerConfig {
             body_line_max_len: 8192,
             http_forward_upstream_keepalive: Default::default(),
             http_forward_mark_upstream: false,
+            http_forward_stale_retry: 1,
             echo_chained_info: false,
             untrusted_read_limit: None,
             egress_path_selection_header: None,
             steal_forwarded_for: false,
             extra_metrics_tags: None,
+            duration_stats: None,
         }
     }
 
@@ -185,6 +190,13 @@ impl HttpProxyServerConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "duration_stats" | "duration_metrics" => {
+                let config = g3_yaml::value::as_histogram_metrics_config(v).context(format!(
+                    "invalid histogram metrics config value for key {k}"
+                ))?;
+                self.duration_stats = Some(config);
+                Ok(())
+            }
             "listen" => {
                 let config = g3_yaml::value::as_tcp_listen_config(v)
                     .context(format!("invalid tcp listen config value for key {k}"))?;
@@ -267,6 +279,12 @@ impl HttpProxyServerConfig {
                 self.tcp_copy.set_yield_size(yield_size);
                 Ok(())
             }
+            "tcp_copy_pipeline_depth" => {
+                let depth = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                self.tcp_copy.set_pipeline_depth(depth);
+                Ok(())
+            }
             "tcp_misc_opts" => {
                 self.tcp_misc_opts = g3_yaml::value::as_tcp_misc_sock_opts(v)
                     .context(format!("invalid tcp misc sock opts value for key {k}"))?;
@@ -341,6 +359,11 @@ impl HttpProxyServerConfig {
                 self.http_forward_mark_upstream = g3_yaml::value::as_bool(v)?;
                 Ok(())
             }
+            "http_forward_stale_retry" => {
+                self.http_forward_stale_retry = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
             "echo_chained_info" => {
                 self.echo_chained_info = g3_yaml::value::as_bool(v)?;
                 Ok(())
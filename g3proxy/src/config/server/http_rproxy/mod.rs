@@ -21,6 +21,7 @@ use anyhow::{anyhow, Context};
 use ascii::AsciiString;
 use yaml_rust::{yaml, Yaml};
 
+use g3_histogram::HistogramMetricsConfig;
 use g3_io_ext::LimitedCopyConfig;
 use g3_types::acl::AclNetworkRuleBuilder;
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
@@ -88,6 +89,7 @@ pub(crate) struct HttpRProxyServerConfig {
     pub(crate) untrusted_read_limit: Option<TcpSockSpeedLimitConfig>,
     pub(crate) append_forwarded_for: HttpForwardedHeaderType,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) duration_stats: Option<HistogramMetricsConfig>,
     pub(crate) hosts: HostMatch<Arc<HttpHostConfig>>,
     pub(crate) enable_tls_server: bool,
     pub(crate) global_tls_server: Option<RustlsServerConfigBuilder>,
@@ -124,6 +126,7 @@ impl HttpRProxyServerConfig {
             untrusted_read_limit: None,
             append_forwarded_for: HttpForwardedHeaderType::default(),
             extra_metrics_tags: None,
+            duration_stats: None,
             hosts: Default::default(),
             enable_tls_server: false,
             global_tls_server: None,
@@ -169,6 +172,13 @@ impl HttpRProxyServerConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "duration_stats" | "duration_metrics" => {
+                let config = g3_yaml::value::as_histogram_metrics_config(v).context(format!(
+                    "invalid histogram metrics config value for key {k}"
+                ))?;
+                self.duration_stats = Some(config);
+                Ok(())
+            }
             "listen" => {
                 let config = g3_yaml::value::as_tcp_listen_config(v)
                     .context(format!("invalid tcp listen config value for key {k}"))?;
@@ -214,6 +224,12 @@ impl HttpRProxyServerConfig {
                 self.tcp_copy.set_yield_size(yield_size);
                 Ok(())
             }
+            "tcp_copy_pipeline_depth" => {
+                let depth = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                self.tcp_copy.set_pipeline_depth(depth);
+                Ok(())
+            }
             "tcp_misc_opts" => {
                 self.tcp_misc_opts = g3_yaml::value::as_tcp_misc_sock_opts(v)
                     .context(format!("invalid tcp misc sock opts value for key {k}"))?;
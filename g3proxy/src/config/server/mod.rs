@@ -54,8 +54,10 @@ pub(crate) mod tcp_stream;
 pub(crate) mod tcp_tproxy;
 pub(crate) mod tls_stream;
 
+pub(crate) mod test_echo;
+
 mod registry;
-pub(crate) use registry::clear;
+pub(crate) use registry::{clear, get_all};
 
 const CONFIG_KEY_SERVER_TYPE: &str = "type";
 const CONFIG_KEY_SERVER_NAME: &str = "name";
@@ -150,6 +152,7 @@ pub(crate) enum AnyServerConfig {
     SocksProxy(Box<socks_proxy::SocksProxyServerConfig>),
     HttpProxy(Box<http_proxy::HttpProxyServerConfig>),
     HttpRProxy(Box<http_rproxy::HttpRProxyServerConfig>),
+    TestEcho(test_echo::TestEchoServerConfig),
 }
 
 macro_rules! impl_transparent0 {
@@ -176,6 +179,7 @@ macro_rules! impl_transparent0 {
                 AnyServerConfig::SocksProxy(s) => s.$f(),
                 AnyServerConfig::HttpProxy(s) => s.$f(),
                 AnyServerConfig::HttpRProxy(s) => s.$f(),
+                AnyServerConfig::TestEcho(s) => s.$f(),
             }
         }
     };
@@ -205,6 +209,7 @@ macro_rules! impl_transparent1 {
                 AnyServerConfig::SocksProxy(s) => s.$f(p),
                 AnyServerConfig::HttpProxy(s) => s.$f(p),
                 AnyServerConfig::HttpRProxy(s) => s.$f(p),
+                AnyServerConfig::TestEcho(s) => s.$f(p),
             }
         }
     };
@@ -224,15 +229,17 @@ impl AnyServerConfig {
 
 pub(crate) fn load_all(v: &Yaml, conf_dir: &Path) -> anyhow::Result<()> {
     let parser = HybridParser::new(conf_dir, g3_daemon::opts::config_file_extension());
-    parser.foreach_map(v, |map, position| {
-        let server = load_server(map, position)?;
-        registry::add(server, false)?;
-        Ok(())
-    })?;
+    parser.foreach_map(v, load_one)?;
     check_dependency()?;
     Ok(())
 }
 
+/// load and add one server, the dependency is not checked
+pub(crate) fn load_one(map: &yaml::Hash, position: Option<YamlDocPosition>) -> anyhow::Result<()> {
+    let server = load_server(map, position)?;
+    registry::add(server, false)
+}
+
 pub(crate) fn load_at_position(position: &YamlDocPosition) -> anyhow::Result<AnyServerConfig> {
     let doc = g3_yaml::load_doc(position)?;
     if let Yaml::Hash(map) = doc {
@@ -324,6 +331,11 @@ fn load_server(
                 .context("failed to load this HttpRProxy server")?;
             Ok(AnyServerConfig::HttpRProxy(Box::new(server)))
         }
+        "test_echo" | "testecho" => {
+            let server = test_echo::TestEchoServerConfig::parse(map, position)
+                .context("failed to load this TestEcho server")?;
+            Ok(AnyServerConfig::TestEcho(server))
+        }
         _ => Err(anyhow!("unsupported server type {}", server_type)),
     }
 }
@@ -50,7 +50,7 @@ pub(super) fn add(server: AnyServerConfig, replace: bool) -> anyhow::Result<()>
     }
 }
 
-pub(super) fn get_all() -> Vec<Arc<AnyServerConfig>> {
+pub(crate) fn get_all() -> Vec<Arc<AnyServerConfig>> {
     let mut vec = Vec::new();
     let ht = INITIAL_SERVER_CONFIG_REGISTRY.lock().unwrap();
     for v in ht.values() {
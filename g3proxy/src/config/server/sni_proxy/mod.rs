@@ -22,6 +22,7 @@ use ascii::AsciiString;
 use yaml_rust::{yaml, Yaml};
 
 use g3_dpi::{ProtocolInspectionConfig, ProtocolPortMap};
+use g3_histogram::HistogramMetricsConfig;
 use g3_io_ext::LimitedCopyConfig;
 use g3_types::acl::AclNetworkRuleBuilder;
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
@@ -57,6 +58,7 @@ pub(crate) struct SniProxyServerConfig {
     pub(crate) server_tcp_portmap: ProtocolPortMap,
     pub(crate) client_tcp_portmap: ProtocolPortMap,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) duration_stats: Option<HistogramMetricsConfig>,
     pub(crate) allowed_sites: Option<HostMatch<Arc<SniHostConfig>>>,
 }
 
@@ -82,6 +84,7 @@ impl SniProxyServerConfig {
             server_tcp_portmap: ProtocolPortMap::tcp_server(),
             client_tcp_portmap: ProtocolPortMap::tcp_client(),
             extra_metrics_tags: None,
+            duration_stats: None,
             allowed_sites: None,
         }
     }
@@ -124,6 +127,13 @@ impl SniProxyServerConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "duration_stats" | "duration_metrics" => {
+                let config = g3_yaml::value::as_histogram_metrics_config(v).context(format!(
+                    "invalid histogram metrics config value for key {k}"
+                ))?;
+                self.duration_stats = Some(config);
+                Ok(())
+            }
             "listen" => {
                 let config = g3_yaml::value::as_tcp_listen_config(v)
                     .context(format!("invalid tcp listen config value for key {k}"))?;
@@ -158,6 +168,12 @@ impl SniProxyServerConfig {
                 self.tcp_copy.set_yield_size(yield_size);
                 Ok(())
             }
+            "tcp_copy_pipeline_depth" => {
+                let depth = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                self.tcp_copy.set_pipeline_depth(depth);
+                Ok(())
+            }
             "tcp_misc_opts" => {
                 self.tcp_misc_opts = g3_yaml::value::as_tcp_misc_sock_opts(v)
                     .context(format!("invalid tcp misc sock opts value for key {k}"))?;
@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use std::collections::BTreeSet;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::Duration;
@@ -21,15 +22,17 @@ use std::time::Duration;
 use ahash::AHashMap;
 use anyhow::{anyhow, Context};
 use ascii::AsciiString;
+use ip_network::IpNetwork;
 use yaml_rust::{yaml, Yaml};
 
+use g3_histogram::HistogramMetricsConfig;
 use g3_io_ext::{LimitedCopyConfig, LimitedUdpRelayConfig};
 use g3_types::acl::{AclExactPortRule, AclNetworkRuleBuilder};
 use g3_types::acl_set::AclDstHostRuleSetBuilder;
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::net::{
     PortRange, SocketBufferConfig, TcpListenConfig, TcpMiscSockOpts, TcpSockSpeedLimitConfig,
-    UdpMiscSockOpts, UdpSockSpeedLimitConfig,
+    UdpMiscSockOpts, UdpPacingConfig, UdpSockSpeedLimitConfig,
 };
 use g3_yaml::YamlDocPosition;
 
@@ -66,10 +69,13 @@ pub(crate) struct SocksProxyServerConfig {
     pub(crate) escaper: MetricsName,
     pub(crate) auditor: MetricsName,
     pub(crate) user_group: MetricsName,
+    pub(crate) auth_free_networks: BTreeSet<IpNetwork>,
+    pub(crate) auth_free_user: Option<String>,
     pub(crate) shared_logger: Option<AsciiString>,
     pub(crate) listen: Option<TcpListenConfig>,
     pub(crate) listen_in_worker: bool,
     pub(crate) use_udp_associate: bool,
+    pub(crate) udp_quic_sni_check: bool,
     pub(crate) udp_bind4: Vec<IpAddr>,
     pub(crate) udp_bind6: Vec<IpAddr>,
     pub(crate) udp_bind_port_range: Option<PortRange>,
@@ -84,10 +90,12 @@ pub(crate) struct SocksProxyServerConfig {
     pub(crate) task_idle_max_count: i32,
     pub(crate) tcp_copy: LimitedCopyConfig,
     pub(crate) udp_relay: LimitedUdpRelayConfig,
+    pub(crate) udp_relay_pacing: Option<UdpPacingConfig>,
     pub(crate) tcp_misc_opts: TcpMiscSockOpts,
     pub(crate) udp_misc_opts: UdpMiscSockOpts,
     pub(crate) transmute_udp_echo_ip: Option<AHashMap<IpAddr, IpAddr>>,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) duration_stats: Option<HistogramMetricsConfig>,
 }
 
 impl SocksProxyServerConfig {
@@ -98,10 +106,13 @@ impl SocksProxyServerConfig {
             escaper: MetricsName::default(),
             auditor: MetricsName::default(),
             user_group: MetricsName::default(),
+            auth_free_networks: BTreeSet::new(),
+            auth_free_user: None,
             shared_logger: None,
             listen: None,
             listen_in_worker: false,
             use_udp_associate: false,
+            udp_quic_sni_check: false,
             udp_bind4: Vec::new(),
             udp_bind6: Vec::new(),
             udp_bind_port_range: None,
@@ -116,10 +127,12 @@ impl SocksProxyServerConfig {
             task_idle_max_count: 1,
             tcp_copy: Default::default(),
             udp_relay: Default::default(),
+            udp_relay_pacing: None,
             tcp_misc_opts: Default::default(),
             udp_misc_opts: Default::default(),
             transmute_udp_echo_ip: None,
             extra_metrics_tags: None,
+            duration_stats: None,
         }
     }
 
@@ -154,6 +167,17 @@ impl SocksProxyServerConfig {
                 self.user_group = g3_yaml::value::as_metrics_name(v)?;
                 Ok(())
             }
+            "auth_free_networks" | "auth_free_network" => {
+                let networks = g3_yaml::value::as_list(v, g3_yaml::value::as_ip_network)
+                    .context(format!("invalid list of ip network value for key {k}"))?;
+                self.auth_free_networks.extend(networks);
+                Ok(())
+            }
+            "auth_free_user" => {
+                let name = g3_yaml::value::as_string(v)?;
+                self.auth_free_user = Some(name);
+                Ok(())
+            }
             "shared_logger" => {
                 let name = g3_yaml::value::as_ascii(v)?;
                 self.shared_logger = Some(name);
@@ -165,6 +189,13 @@ impl SocksProxyServerConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "duration_stats" | "duration_metrics" => {
+                let config = g3_yaml::value::as_histogram_metrics_config(v).context(format!(
+                    "invalid histogram metrics config value for key {k}"
+                ))?;
+                self.duration_stats = Some(config);
+                Ok(())
+            }
             "listen" => {
                 let config = g3_yaml::value::as_tcp_listen_config(v)
                     .context(format!("invalid tcp listen config value for key {k}"))?;
@@ -179,6 +210,10 @@ impl SocksProxyServerConfig {
                 self.use_udp_associate = g3_yaml::value::as_bool(v)?;
                 Ok(())
             }
+            "udp_quic_sni_check" => {
+                self.udp_quic_sni_check = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
             "udp_bind_ipv4" => {
                 self.udp_bind4 = g3_yaml::value::as_list(v, |v| {
                     let ip4 = g3_yaml::value::as_ipv4addr(v)?;
@@ -248,6 +283,12 @@ impl SocksProxyServerConfig {
                 self.tcp_copy.set_yield_size(yield_size);
                 Ok(())
             }
+            "tcp_copy_pipeline_depth" => {
+                let depth = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                self.tcp_copy.set_pipeline_depth(depth);
+                Ok(())
+            }
             "udp_relay_packet_size" => {
                 let packet_size = g3_yaml::humanize::as_usize(v)
                     .context(format!("invalid humanize usize value for key {k}"))?;
@@ -265,6 +306,12 @@ impl SocksProxyServerConfig {
                 self.udp_relay.set_batch_size(batch_size);
                 Ok(())
             }
+            "udp_relay_pacing" => {
+                let pacing = g3_yaml::value::as_udp_pacing_config(v)
+                    .context(format!("invalid udp pacing config value for key {k}"))?;
+                self.udp_relay_pacing = Some(pacing);
+                Ok(())
+            }
             "tcp_misc_opts" => {
                 self.tcp_misc_opts = g3_yaml::value::as_tcp_misc_sock_opts(v)
                     .context(format!("invalid tcp misc sock opts value for key {k}"))?;
@@ -315,6 +362,11 @@ impl SocksProxyServerConfig {
         if self.escaper.is_empty() {
             return Err(anyhow!("escaper is not set"));
         }
+        if !self.auth_free_networks.is_empty() && self.auth_free_user.is_none() {
+            return Err(anyhow!(
+                "auth_free_user should be set if auth_free_networks is not empty"
+            ));
+        }
         if self.task_idle_check_duration > IDLE_CHECK_MAXIMUM_DURATION {
             self.task_idle_check_duration = IDLE_CHECK_MAXIMUM_DURATION;
         }
@@ -21,6 +21,7 @@ use anyhow::{anyhow, Context};
 use ascii::AsciiString;
 use yaml_rust::{yaml, Yaml};
 
+use g3_histogram::HistogramMetricsConfig;
 use g3_io_ext::LimitedCopyConfig;
 use g3_types::acl::AclNetworkRuleBuilder;
 use g3_types::collection::SelectivePickPolicy;
@@ -55,6 +56,7 @@ pub(crate) struct TcpStreamServerConfig {
     pub(crate) tcp_copy: LimitedCopyConfig,
     pub(crate) tcp_misc_opts: TcpMiscSockOpts,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) duration_stats: Option<HistogramMetricsConfig>,
 }
 
 impl TcpStreamServerConfig {
@@ -78,6 +80,7 @@ impl TcpStreamServerConfig {
             tcp_copy: Default::default(),
             tcp_misc_opts: Default::default(),
             extra_metrics_tags: None,
+            duration_stats: None,
         }
     }
 
@@ -119,6 +122,13 @@ impl TcpStreamServerConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "duration_stats" | "duration_metrics" => {
+                let config = g3_yaml::value::as_histogram_metrics_config(v).context(format!(
+                    "invalid histogram metrics config value for key {k}"
+                ))?;
+                self.duration_stats = Some(config);
+                Ok(())
+            }
             "listen" => {
                 let config = g3_yaml::value::as_tcp_listen_config(v)
                     .context(format!("invalid tcp listen config value for key {k}"))?;
@@ -190,6 +200,12 @@ impl TcpStreamServerConfig {
                 self.tcp_copy.set_yield_size(yield_size);
                 Ok(())
             }
+            "tcp_copy_pipeline_depth" => {
+                let depth = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                self.tcp_copy.set_pipeline_depth(depth);
+                Ok(())
+            }
             "tcp_misc_opts" => {
                 self.tcp_misc_opts = g3_yaml::value::as_tcp_misc_sock_opts(v)
                     .context(format!("invalid tcp misc sock opts value for key {k}"))?;
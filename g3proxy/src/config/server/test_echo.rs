@@ -0,0 +1,227 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use yaml_rust::{yaml, Yaml};
+
+use g3_types::metrics::MetricsName;
+use g3_types::net::{TcpListenConfig, UdpListenConfig};
+use g3_yaml::YamlDocPosition;
+
+use super::ServerConfig;
+use crate::config::server::{AnyServerConfig, ServerConfigDiffAction};
+
+const SERVER_CONFIG_TYPE: &str = "TestEcho";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum TestEchoMode {
+    /// echo back all data received
+    Echo,
+    /// act as a simple http/1.x origin server
+    Http,
+}
+
+/// A hidden server for hermetic integration tests, not intended for production use.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct TestEchoServerConfig {
+    name: MetricsName,
+    position: Option<YamlDocPosition>,
+    pub(crate) listen: Option<TcpListenConfig>,
+    pub(crate) listen_in_worker: bool,
+    pub(crate) udp_listen: Option<UdpListenConfig>,
+    pub(crate) mode: TestEchoMode,
+    pub(crate) http_req_header_max_size: usize,
+    pub(crate) http_response_body: Option<String>,
+}
+
+impl TestEchoServerConfig {
+    fn new(position: Option<YamlDocPosition>) -> Self {
+        TestEchoServerConfig {
+            name: MetricsName::default(),
+            position,
+            listen: None,
+            listen_in_worker: false,
+            udp_listen: None,
+            mode: TestEchoMode::Echo,
+            http_req_header_max_size: 65536,
+            http_response_body: None,
+        }
+    }
+
+    pub(crate) fn parse(
+        map: &yaml::Hash,
+        position: Option<YamlDocPosition>,
+    ) -> anyhow::Result<Self> {
+        let mut server = TestEchoServerConfig::new(position);
+
+        g3_yaml::foreach_kv(map, |k, v| server.set(k, v))?;
+
+        server.check()?;
+        Ok(server)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            super::CONFIG_KEY_SERVER_TYPE => Ok(()),
+            super::CONFIG_KEY_SERVER_NAME => {
+                self.name = g3_yaml::value::as_metrics_name(v)?;
+                Ok(())
+            }
+            "listen" => {
+                let config = g3_yaml::value::as_tcp_listen_config(v)
+                    .context(format!("invalid tcp listen config value for key {k}"))?;
+                self.listen = Some(config);
+                Ok(())
+            }
+            "listen_in_worker" => {
+                self.listen_in_worker = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
+            "udp_listen" => {
+                let config = g3_yaml::value::as_udp_listen_config(v)
+                    .context(format!("invalid udp listen config value for key {k}"))?;
+                self.udp_listen = Some(config);
+                Ok(())
+            }
+            "mode" => {
+                let mode = g3_yaml::value::as_string(v)?;
+                self.mode = match g3_yaml::key::normalize(&mode).as_str() {
+                    "echo" => TestEchoMode::Echo,
+                    "http" => TestEchoMode::Http,
+                    _ => return Err(anyhow!("unsupported test echo mode {mode}")),
+                };
+                Ok(())
+            }
+            "http_req_header_max_size" => {
+                self.http_req_header_max_size = g3_yaml::humanize::as_usize(v)
+                    .context(format!("invalid humanize usize value for key {k}"))?;
+                Ok(())
+            }
+            "http_response_body" => {
+                let body = g3_yaml::value::as_string(v)?;
+                self.http_response_body = Some(body);
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.name.is_empty() {
+            return Err(anyhow!("name is not set"));
+        }
+        if self.listen.is_none() && self.udp_listen.is_none() {
+            return Err(anyhow!("neither listen nor udp_listen is set"));
+        }
+        Ok(())
+    }
+}
+
+impl ServerConfig for TestEchoServerConfig {
+    fn name(&self) -> &MetricsName {
+        &self.name
+    }
+
+    fn position(&self) -> Option<YamlDocPosition> {
+        self.position.clone()
+    }
+
+    fn server_type(&self) -> &'static str {
+        SERVER_CONFIG_TYPE
+    }
+
+    fn escaper(&self) -> &MetricsName {
+        Default::default()
+    }
+
+    fn user_group(&self) -> &MetricsName {
+        Default::default()
+    }
+
+    fn auditor(&self) -> &MetricsName {
+        Default::default()
+    }
+
+    fn diff_action(&self, new: &AnyServerConfig) -> ServerConfigDiffAction {
+        let new = match new {
+            AnyServerConfig::TestEcho(config) => config,
+            _ => return ServerConfigDiffAction::SpawnNew,
+        };
+
+        if self.eq(new) {
+            return ServerConfigDiffAction::NoAction;
+        }
+
+        ServerConfigDiffAction::ReloadAndRespawn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(doc: &str) -> anyhow::Result<TestEchoServerConfig> {
+        let docs = YamlLoader::load_from_str(doc).unwrap();
+        TestEchoServerConfig::parse(docs[0].as_hash().unwrap(), None)
+    }
+
+    #[test]
+    fn parse_echo() {
+        let config =
+            parse("name: echo\nlisten: 127.0.0.1:9000\nudp_listen: 127.0.0.1:9000\n").unwrap();
+        assert_eq!(config.name().as_str(), "echo");
+        assert_eq!(config.mode, TestEchoMode::Echo);
+        assert!(config.listen.is_some());
+        assert!(config.udp_listen.is_some());
+        assert!(config.http_response_body.is_none());
+    }
+
+    #[test]
+    fn parse_http() {
+        let config = parse(
+            "name: origin\nlisten: 127.0.0.1:9080\nmode: HTTP\nhttp_response_body: ok\nhttp_req_header_max_size: 4KiB\n",
+        )
+        .unwrap();
+        assert_eq!(config.mode, TestEchoMode::Http);
+        assert_eq!(config.http_req_header_max_size, 4096);
+        assert_eq!(config.http_response_body.as_deref(), Some("ok"));
+        assert!(config.udp_listen.is_none());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse("listen: 127.0.0.1:9000\n").is_err());
+        assert!(parse("name: echo\n").is_err());
+        assert!(parse("name: echo\nlisten: 127.0.0.1:9000\nmode: smtp\n").is_err());
+        assert!(parse("name: echo\nlisten: 127.0.0.1:9000\nunknown: 1\n").is_err());
+    }
+
+    #[test]
+    fn diff_action() {
+        let old = parse("name: echo\nlisten: 127.0.0.1:9000\n").unwrap();
+        let new = parse("name: echo\nlisten: 127.0.0.1:9000\n").unwrap();
+        assert!(matches!(
+            old.diff_action(&AnyServerConfig::TestEcho(new)),
+            ServerConfigDiffAction::NoAction
+        ));
+        let new = parse("name: echo\nlisten: 127.0.0.1:9000\nmode: http\n").unwrap();
+        assert!(matches!(
+            old.diff_action(&AnyServerConfig::TestEcho(new)),
+            ServerConfigDiffAction::ReloadAndRespawn
+        ));
+    }
+}
@@ -0,0 +1,211 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use base64::prelude::*;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde_json::Value;
+
+use super::{http, ConfigStoreConfig, StoreEntry};
+
+const KEY_PATH_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'?')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+pub(super) fn fetch(
+    config: &ConfigStoreConfig,
+    wait_index: Option<u64>,
+) -> anyhow::Result<(Vec<StoreEntry>, u64)> {
+    let path = kv_path(config, wait_index);
+    let extra_timeout = if wait_index.is_some() {
+        config.watch_interval
+    } else {
+        Duration::ZERO
+    };
+
+    let mut headers = Vec::with_capacity(1);
+    if let Some(token) = &config.token {
+        headers.push(("X-Consul-Token", token.as_str()));
+    }
+    let rsp = http::request(config, "GET", &path, &headers, None, extra_timeout)?;
+
+    let index = rsp
+        .header("X-Consul-Index")
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or_default();
+    match rsp.status {
+        200 => {}
+        404 => return Ok((Vec::new(), index)),
+        n => return Err(anyhow!("consul kv api returned status code {n}")),
+    }
+    let entries = parse_kv_response(&rsp.body)?;
+    Ok((entries, index))
+}
+
+fn kv_path(config: &ConfigStoreConfig, wait_index: Option<u64>) -> String {
+    let mut path = format!(
+        "/v1/kv/{}?recurse=true",
+        utf8_percent_encode(config.prefix.trim_start_matches('/'), KEY_PATH_ENCODE_SET)
+    );
+    if let Some(index) = wait_index {
+        // use blocking query, see https://developer.hashicorp.com/consul/api-docs/features/blocking
+        let wait = config.watch_interval.as_secs().max(1);
+        path.push_str(&format!("&index={index}&wait={wait}s"));
+    }
+    path
+}
+
+fn parse_kv_response(body: &[u8]) -> anyhow::Result<Vec<StoreEntry>> {
+    let doc =
+        serde_json::from_slice::<Value>(body).map_err(|e| anyhow!("invalid json response: {e}"))?;
+    let Value::Array(items) = doc else {
+        return Err(anyhow!("the json response should be an array"));
+    };
+    let mut entries = Vec::with_capacity(items.len());
+    for item in items {
+        let key = item
+            .get("Key")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("no valid Key field found in kv pair"))?;
+        // folders and empty keys have null value
+        let Some(value) = item.get("Value").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let value = BASE64_STANDARD
+            .decode(value)
+            .map_err(|e| anyhow!("invalid base64 value for key {key}: {e}"))?;
+        let value =
+            String::from_utf8(value).context(format!("the value of key {key} is not utf-8"))?;
+        entries.push(StoreEntry {
+            key: key.to_string(),
+            value,
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::store::ConfigStoreBackend;
+
+    #[test]
+    fn path() {
+        let mut config = ConfigStoreConfig::new(ConfigStoreBackend::Consul);
+        config.prefix = "/g3 proxy/".to_string();
+        assert_eq!(kv_path(&config, None), "/v1/kv/g3%20proxy/?recurse=true");
+
+        config.watch_interval = Duration::from_secs(30);
+        assert_eq!(
+            kv_path(&config, Some(5)),
+            "/v1/kv/g3%20proxy/?recurse=true&index=5&wait=30s"
+        );
+
+        config.watch_interval = Duration::from_millis(500);
+        assert_eq!(
+            kv_path(&config, Some(0)),
+            "/v1/kv/g3%20proxy/?recurse=true&index=0&wait=1s"
+        );
+    }
+
+    #[test]
+    fn kv_response() {
+        let body = br#"[
+            {"LockIndex": 0, "Key": "g3/", "Flags": 0, "Value": null, "CreateIndex": 10, "ModifyIndex": 10},
+            {"LockIndex": 0, "Key": "g3/b", "Flags": 0, "Value": "eTogMg==", "CreateIndex": 11, "ModifyIndex": 12},
+            {"LockIndex": 0, "Key": "g3/a", "Flags": 0, "Value": "eDogMQ==", "CreateIndex": 13, "ModifyIndex": 13}
+        ]"#;
+        let entries = parse_kv_response(body).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key, "g3/b");
+        assert_eq!(entries[0].value, "y: 2");
+        assert_eq!(entries[1].key, "g3/a");
+        assert_eq!(entries[1].value, "x: 1");
+
+        assert!(parse_kv_response(b"[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn kv_response_invalid() {
+        assert!(parse_kv_response(b"not json").is_err());
+        assert!(parse_kv_response(br#"{"Key": "g3/a"}"#).is_err());
+        assert!(parse_kv_response(br#"[{"Value": "eDogMQ=="}]"#).is_err());
+        assert!(parse_kv_response(br#"[{"Key": "g3/a", "Value": "!!"}]"#).is_err());
+        assert!(parse_kv_response(br#"[{"Key": "g3/a", "Value": "/w=="}]"#).is_err());
+    }
+
+    #[test]
+    fn fetch_sorted() {
+        let body = r#"[{"Key":"g3/b","Value":"eTogMg=="},{"Key":"g3/a","Value":"eDogMQ=="}]"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nX-Consul-Index: 12\r\n\r\n{body}"
+        );
+        let (mut config, server) = super::http::test_server(
+            ConfigStoreBackend::Consul,
+            response.into_bytes(),
+            Duration::ZERO,
+        );
+        config.token = Some("secret".to_string());
+
+        let (entries, index) = config.fetch(Some(10)).unwrap();
+        assert_eq!(index, 12);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key, "g3/a");
+        assert_eq!(entries[1].key, "g3/b");
+
+        let req = server.join().unwrap();
+        assert!(req.starts_with("GET /v1/kv/g3/?recurse=true&index=10&wait=30s HTTP/1.0\r\n"));
+        assert!(req.contains("X-Consul-Token: secret\r\n"));
+    }
+
+    #[test]
+    fn fetch_not_found() {
+        let response = "HTTP/1.1 404 Not Found\r\nX-Consul-Index: 7\r\n\r\n";
+        let (config, server) = super::http::test_server(
+            ConfigStoreBackend::Consul,
+            response.as_bytes().to_vec(),
+            Duration::ZERO,
+        );
+
+        let (entries, index) = config.fetch(None).unwrap();
+        assert!(entries.is_empty());
+        assert_eq!(index, 7);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn fetch_error() {
+        let response = "HTTP/1.1 403 Forbidden\r\n\r\n";
+        let (config, server) = super::http::test_server(
+            ConfigStoreBackend::Consul,
+            response.as_bytes().to_vec(),
+            Duration::ZERO,
+        );
+
+        assert!(config.fetch(None).is_err());
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,335 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{BufRead, ErrorKind};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+use base64::prelude::*;
+use serde_json::Value;
+
+use super::{http, ConfigStoreConfig, StoreEntry};
+
+/// get the range end for all keys with the given prefix
+fn prefix_range_end(prefix: &[u8]) -> Vec<u8> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < 0xff {
+            end.push(last + 1);
+            return end;
+        }
+    }
+    // all bytes are 0xff, use "\0" to get all keys >= prefix
+    vec![0]
+}
+
+fn json_str_field<'a>(v: &'a Value, name: &str) -> Option<&'a str> {
+    v.get(name).and_then(|v| v.as_str())
+}
+
+fn auth_headers(config: &ConfigStoreConfig) -> Vec<(&'static str, &str)> {
+    let mut headers = Vec::with_capacity(1);
+    if let Some(token) = &config.token {
+        headers.push(("Authorization", token.as_str()));
+    }
+    headers
+}
+
+pub(super) fn fetch(config: &ConfigStoreConfig) -> anyhow::Result<(Vec<StoreEntry>, u64)> {
+    let prefix = config.prefix.as_bytes();
+    let body = serde_json::json!({
+        "key": BASE64_STANDARD.encode(prefix),
+        "range_end": BASE64_STANDARD.encode(prefix_range_end(prefix)),
+    })
+    .to_string();
+
+    let headers = auth_headers(config);
+    // use the grpc gateway json api, see https://etcd.io/docs/v3.5/dev-guide/api_grpc_gateway/
+    let rsp = http::request(
+        config,
+        "POST",
+        "/v3/kv/range",
+        &headers,
+        Some(body.as_bytes()),
+        Duration::ZERO,
+    )?;
+    if rsp.status != 200 {
+        return Err(anyhow!("etcd kv api returned status code {}", rsp.status));
+    }
+    parse_range_response(&rsp.body)
+}
+
+fn parse_range_response(body: &[u8]) -> anyhow::Result<(Vec<StoreEntry>, u64)> {
+    let doc =
+        serde_json::from_slice::<Value>(body).map_err(|e| anyhow!("invalid json response: {e}"))?;
+    // int64 values are encoded as string in the json api
+    let revision = doc
+        .get("header")
+        .and_then(|v| json_str_field(v, "revision"))
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or_default();
+
+    let mut entries = Vec::new();
+    if let Some(Value::Array(kvs)) = doc.get("kvs") {
+        for kv in kvs {
+            let key = json_str_field(kv, "key")
+                .ok_or_else(|| anyhow!("no valid key field found in kv pair"))?;
+            let key = BASE64_STANDARD
+                .decode(key)
+                .map_err(|e| anyhow!("invalid base64 key: {e}"))?;
+            let key = String::from_utf8(key).map_err(|_| anyhow!("the key is not utf-8"))?;
+            // the value field is omitted if empty
+            let Some(value) = json_str_field(kv, "value") else {
+                continue;
+            };
+            let value = BASE64_STANDARD
+                .decode(value)
+                .map_err(|e| anyhow!("invalid base64 value for key {key}: {e}"))?;
+            let value =
+                String::from_utf8(value).context(format!("the value of key {key} is not utf-8"))?;
+            entries.push(StoreEntry { key, value });
+        }
+    }
+    Ok((entries, revision))
+}
+
+/// Watch the changes after `revision` by using the streaming watch api.
+///
+/// Returns false if no change happened in the watch interval.
+pub(super) fn watch(config: &ConfigStoreConfig, revision: u64) -> anyhow::Result<bool> {
+    let body = watch_request(config.prefix.as_bytes(), revision);
+
+    let headers = auth_headers(config);
+    // the watch responses are sent as newline delimited json objects
+    let (rsp, mut reader) =
+        http::request_stream(config, "POST", "/v3/watch", &headers, Some(body.as_bytes()))?;
+    if rsp.status != 200 {
+        return Err(anyhow!(
+            "etcd watch api returned status code {}",
+            rsp.status
+        ));
+    }
+
+    let deadline = Instant::now() + config.watch_interval;
+    let mut line = Vec::with_capacity(1024);
+    loop {
+        let Some(left) = deadline.checked_duration_since(Instant::now()) else {
+            return Ok(false);
+        };
+        reader
+            .get_ref()
+            .set_read_timeout(left.max(Duration::from_millis(1)))?;
+
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => return Err(anyhow!("etcd watch stream closed")),
+            Ok(_) => {}
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Ok(false)
+            }
+            Err(e) => return Err(anyhow!("failed to read etcd watch response: {e}")),
+        }
+        if line.iter().all(|c| c.is_ascii_whitespace()) {
+            continue;
+        }
+        if check_watch_response(&line)? {
+            return Ok(true);
+        }
+    }
+}
+
+/// the watch create request for changes after `revision`
+fn watch_request(prefix: &[u8], revision: u64) -> String {
+    serde_json::json!({
+        "create_request": {
+            "key": BASE64_STANDARD.encode(prefix),
+            "range_end": BASE64_STANDARD.encode(prefix_range_end(prefix)),
+            "start_revision": revision.wrapping_add(1).to_string(),
+        }
+    })
+    .to_string()
+}
+
+/// Check a line in the watch response stream, and return true if there are changes
+fn check_watch_response(line: &[u8]) -> anyhow::Result<bool> {
+    let doc = serde_json::from_slice::<Value>(line)
+        .map_err(|e| anyhow!("invalid json watch response: {e}"))?;
+    if let Some(e) = doc.get("error") {
+        return Err(anyhow!("etcd watch api returned error: {e}"));
+    }
+    let Some(result) = doc.get("result") else {
+        return Ok(false);
+    };
+    if result.get("canceled").and_then(|v| v.as_bool()) == Some(true) {
+        // the revision may have been compacted, fetch all again
+        return Ok(true);
+    }
+    match result.get("events") {
+        Some(Value::Array(events)) => Ok(!events.is_empty()),
+        _ => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::store::ConfigStoreBackend;
+
+    #[test]
+    fn range_end() {
+        assert_eq!(prefix_range_end(b"/g3/"), b"/g30".to_vec());
+        assert_eq!(prefix_range_end(&[0x61, 0xff]), vec![0x62]);
+        assert_eq!(prefix_range_end(&[0xff, 0xff]), vec![0x00]);
+    }
+
+    #[test]
+    fn range_response() {
+        let body = br#"{
+            "header": {"cluster_id": "1", "member_id": "2", "revision": "42", "raft_term": "3"},
+            "kvs": [
+                {"key": "L2czL2I=", "create_revision": "5", "mod_revision": "40", "version": "2", "value": "eTogMg=="},
+                {"key": "L2czL2Rpci8=", "create_revision": "6", "mod_revision": "6", "version": "1"},
+                {"key": "L2czL2E=", "create_revision": "4", "mod_revision": "42", "version": "3", "value": "eDogMQ=="}
+            ],
+            "count": "3"
+        }"#;
+        let (entries, revision) = parse_range_response(body).unwrap();
+        assert_eq!(revision, 42);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key, "/g3/b");
+        assert_eq!(entries[0].value, "y: 2");
+        assert_eq!(entries[1].key, "/g3/a");
+        assert_eq!(entries[1].value, "x: 1");
+
+        let body = br#"{"header": {"revision": "7"}}"#;
+        let (entries, revision) = parse_range_response(body).unwrap();
+        assert!(entries.is_empty());
+        assert_eq!(revision, 7);
+    }
+
+    #[test]
+    fn range_response_invalid() {
+        assert!(parse_range_response(b"not json").is_err());
+        assert!(parse_range_response(br#"{"kvs": [{"value": "eDogMQ=="}]}"#).is_err());
+        assert!(parse_range_response(br#"{"kvs": [{"key": "!!", "value": "eDogMQ=="}]}"#).is_err());
+        assert!(parse_range_response(br#"{"kvs": [{"key": "L2czL2E=", "value": "!!"}]}"#).is_err());
+    }
+
+    #[test]
+    fn watch_create_request() {
+        let body = watch_request(b"/g3/", 42);
+        let doc = serde_json::from_str::<Value>(&body).unwrap();
+        let req = doc.get("create_request").unwrap();
+        assert_eq!(json_str_field(req, "key"), Some("L2czLw=="));
+        assert_eq!(json_str_field(req, "range_end"), Some("L2czMA=="));
+        assert_eq!(json_str_field(req, "start_revision"), Some("43"));
+    }
+
+    #[test]
+    fn watch_response() {
+        let created = br#"{"result":{"header":{"revision":"42"},"created":true}}"#;
+        assert!(!check_watch_response(created).unwrap());
+
+        let progress = br#"{"result":{"header":{"revision":"43"},"events":[]}}"#;
+        assert!(!check_watch_response(progress).unwrap());
+
+        let changed = br#"{"result":{"header":{"revision":"43"},"events":[{"kv":{"key":"L2czL2E=","mod_revision":"43"}}]}}"#;
+        assert!(check_watch_response(changed).unwrap());
+
+        let deleted =
+            br#"{"result":{"header":{"revision":"44"},"events":[{"type":"DELETE","kv":{"key":"L2czL2E="}}]}}"#;
+        assert!(check_watch_response(deleted).unwrap());
+
+        let canceled =
+            br#"{"result":{"header":{"revision":"50"},"canceled":true,"compact_revision":"45"}}"#;
+        assert!(check_watch_response(canceled).unwrap());
+
+        let error = br#"{"error":{"grpc_code":16,"http_code":401,"message":"invalid auth token"}}"#;
+        assert!(check_watch_response(error).is_err());
+
+        assert!(check_watch_response(b"{").is_err());
+    }
+
+    #[test]
+    fn fetch_sorted() {
+        let body = r#"{"header":{"revision":"42"},"kvs":[{"key":"L2czL2I=","value":"eTogMg=="},{"key":"L2czL2E=","value":"eDogMQ=="}]}"#;
+        let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{body}");
+        let (config, server) = super::http::test_server(
+            ConfigStoreBackend::Etcd,
+            response.into_bytes(),
+            Duration::ZERO,
+        );
+
+        let (entries, revision) = config.fetch(None).unwrap();
+        assert_eq!(revision, 42);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key, "/g3/a");
+        assert_eq!(entries[1].key, "/g3/b");
+
+        let req = server.join().unwrap();
+        assert!(req.starts_with("POST /v3/kv/range HTTP/1.0\r\n"));
+        assert!(req.contains(r#""range_end":"L2czMA==""#));
+    }
+
+    #[test]
+    fn watch_changed() {
+        let response = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n\
+            {\"result\":{\"header\":{\"revision\":\"10\"},\"created\":true}}\n\
+            \n\
+            {\"result\":{\"header\":{\"revision\":\"11\"},\"events\":[{\"kv\":{\"key\":\"L2czL2E=\"}}]}}\n";
+        let (mut config, server) = super::http::test_server(
+            ConfigStoreBackend::Etcd,
+            response.as_bytes().to_vec(),
+            Duration::from_secs(1),
+        );
+        config.watch_interval = Duration::from_secs(5);
+
+        assert!(config.watch(10).unwrap());
+
+        let req = server.join().unwrap();
+        assert!(req.starts_with("POST /v3/watch HTTP/1.0\r\n"));
+        assert!(req.contains(r#""start_revision":"11""#));
+    }
+
+    #[test]
+    fn watch_unchanged() {
+        let response = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n\
+            {\"result\":{\"header\":{\"revision\":\"10\"},\"created\":true}}\n";
+        let (mut config, server) = super::http::test_server(
+            ConfigStoreBackend::Etcd,
+            response.as_bytes().to_vec(),
+            Duration::from_secs(1),
+        );
+        config.watch_interval = Duration::from_millis(200);
+
+        assert!(!config.watch(10).unwrap());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn watch_closed() {
+        let response = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n";
+        let (mut config, server) = super::http::test_server(
+            ConfigStoreBackend::Etcd,
+            response.as_bytes().to_vec(),
+            Duration::ZERO,
+        );
+        config.watch_interval = Duration::from_secs(5);
+
+        assert!(config.watch(10).is_err());
+        server.join().unwrap();
+    }
+}
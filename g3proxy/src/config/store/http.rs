@@ -0,0 +1,292 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use rustls::{ClientConnection, StreamOwned};
+
+use super::ConfigStoreConfig;
+
+const RESPONSE_MAX_SIZE: u64 = 16 * 1024 * 1024;
+const RESPONSE_HEADER_MAX_SIZE: u64 = 64 * 1024;
+
+pub(super) enum Stream {
+    Plain(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+impl Stream {
+    fn tcp(&self) -> &TcpStream {
+        match self {
+            Stream::Plain(s) => s,
+            Stream::Tls(s) => &s.sock,
+        }
+    }
+
+    pub(super) fn set_read_timeout(&self, timeout: Duration) -> anyhow::Result<()> {
+        self.tcp()
+            .set_read_timeout(Some(timeout))
+            .context("failed to set read timeout")
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(s) => s.read(buf),
+            Stream::Tls(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(s) => s.write(buf),
+            Stream::Tls(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Plain(s) => s.flush(),
+            Stream::Tls(s) => s.flush(),
+        }
+    }
+}
+
+pub(super) struct Response {
+    pub(super) status: u16,
+    headers: Vec<(String, String)>,
+    pub(super) body: Vec<u8>,
+}
+
+impl Response {
+    pub(super) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn parse_header(header: &[u8]) -> anyhow::Result<Self> {
+        let header =
+            std::str::from_utf8(header).map_err(|e| anyhow!("invalid response header: {e}"))?;
+
+        let mut lines = header.split("\r\n");
+        let status_line = lines.next().unwrap_or_default();
+        let mut parts = status_line.splitn(3, ' ');
+        match parts.next() {
+            Some(v) if v.starts_with("HTTP/1.") => {}
+            _ => return Err(anyhow!("invalid response status line: {status_line}")),
+        }
+        let status = parts
+            .next()
+            .and_then(|s| s.parse::<u16>().ok())
+            .ok_or_else(|| anyhow!("invalid response status line: {status_line}"))?;
+
+        let mut headers = Vec::new();
+        for line in lines {
+            if let Some((k, v)) = line.split_once(':') {
+                headers.push((k.trim().to_string(), v.trim().to_string()));
+            }
+        }
+
+        Ok(Response {
+            status,
+            headers,
+            body: Vec::new(),
+        })
+    }
+
+    fn read_header<R: BufRead>(reader: &mut R) -> anyhow::Result<Self> {
+        let mut buf = Vec::with_capacity(1024);
+        let mut limited = reader.take(RESPONSE_HEADER_MAX_SIZE);
+        loop {
+            let len = limited
+                .read_until(b'\n', &mut buf)
+                .map_err(|e| anyhow!("failed to read response header: {e}"))?;
+            if len == 0 {
+                return Err(anyhow!("no complete response header found"));
+            }
+            if buf.ends_with(b"\r\n\r\n") {
+                return Response::parse_header(&buf[..buf.len() - 4]);
+            }
+        }
+    }
+}
+
+fn connect(config: &ConfigStoreConfig) -> anyhow::Result<Stream> {
+    let peer = config.endpoint.to_string();
+    let addrs = peer
+        .to_socket_addrs()
+        .map_err(|e| anyhow!("failed to resolve {peer}: {e}"))?;
+
+    let mut last_err = anyhow!("no address resolved for {peer}");
+    let mut stream = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, config.connect_timeout) {
+            Ok(s) => {
+                stream = Some(s);
+                break;
+            }
+            Err(e) => last_err = anyhow!("failed to connect to {addr}: {e}"),
+        }
+    }
+    let mut stream = stream.ok_or(last_err)?;
+    stream
+        .set_write_timeout(Some(config.request_timeout))
+        .context("failed to set write timeout")?;
+
+    let Some(tls_client) = &config.tls_client else {
+        return Ok(Stream::Plain(stream));
+    };
+    let Some(tls_name) = config.tls_name.clone() else {
+        return Err(anyhow!("no tls server name set"));
+    };
+    let mut conn = ClientConnection::new(tls_client.driver.clone(), tls_name)
+        .map_err(|e| anyhow!("failed to create tls client connection: {e}"))?;
+    stream
+        .set_read_timeout(Some(tls_client.handshake_timeout))
+        .context("failed to set read timeout")?;
+    while conn.is_handshaking() {
+        conn.complete_io(&mut stream)
+            .map_err(|e| anyhow!("tls handshake with {peer} failed: {e}"))?;
+    }
+    Ok(Stream::Tls(Box::new(StreamOwned::new(conn, stream))))
+}
+
+/// Send a HTTP/1.0 request, so the response body is always terminated by connection close
+fn send_request(
+    config: &ConfigStoreConfig,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: Option<&[u8]>,
+) -> anyhow::Result<Stream> {
+    let mut stream = connect(config)?;
+    stream.set_read_timeout(config.request_timeout)?;
+
+    let peer = &config.endpoint;
+    let mut req =
+        format!("{method} {path} HTTP/1.0\r\nHost: {peer}\r\nAccept: application/json\r\n");
+    for (name, value) in headers {
+        req.push_str(&format!("{name}: {value}\r\n"));
+    }
+    if let Some(body) = body {
+        req.push_str("Content-Type: application/json\r\n");
+        req.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    req.push_str("\r\n");
+    let mut req = req.into_bytes();
+    if let Some(body) = body {
+        req.extend_from_slice(body);
+    }
+    stream
+        .write_all(&req)
+        .map_err(|e| anyhow!("failed to send request: {e}"))?;
+    stream
+        .flush()
+        .map_err(|e| anyhow!("failed to send request: {e}"))?;
+    Ok(stream)
+}
+
+pub(super) fn request(
+    config: &ConfigStoreConfig,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: Option<&[u8]>,
+    extra_timeout: Duration,
+) -> anyhow::Result<Response> {
+    let stream = send_request(config, method, path, headers, body)?;
+    stream.set_read_timeout(config.request_timeout + extra_timeout)?;
+
+    let mut reader = BufReader::new(stream);
+    let mut rsp = Response::read_header(&mut reader)?;
+    reader
+        .take(RESPONSE_MAX_SIZE)
+        .read_to_end(&mut rsp.body)
+        .map_err(|e| anyhow!("failed to read response body: {e}"))?;
+    Ok(rsp)
+}
+
+/// Send the request and return the reader for the streaming response body
+pub(super) fn request_stream(
+    config: &ConfigStoreConfig,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: Option<&[u8]>,
+) -> anyhow::Result<(Response, BufReader<Stream>)> {
+    let stream = send_request(config, method, path, headers, body)?;
+    let mut reader = BufReader::new(stream);
+    let rsp = Response::read_header(&mut reader)?;
+    Ok((rsp, reader))
+}
+
+/// Start a http server that accepts one request and sends back `response`, then keeps the
+/// connection open for `hold` before closing it. The received request can be got by joining
+/// the returned handle.
+#[cfg(test)]
+pub(super) fn test_server(
+    backend: super::ConfigStoreBackend,
+    response: Vec<u8>,
+    hold: Duration,
+) -> (ConfigStoreConfig, std::thread::JoinHandle<String>) {
+    use std::net::{IpAddr, Ipv4Addr, TcpListener};
+
+    use g3_types::net::UpstreamAddr;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut req = String::new();
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(v) = line.strip_prefix("Content-Length:") {
+                content_length = v.trim().parse::<usize>().unwrap();
+            }
+            req.push_str(&line);
+            if line == "\r\n" || line.is_empty() {
+                break;
+            }
+        }
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).unwrap();
+        req.push_str(std::str::from_utf8(&body).unwrap());
+
+        let mut stream = reader.into_inner();
+        stream.write_all(&response).unwrap();
+        stream.flush().unwrap();
+        std::thread::sleep(hold);
+        req
+    });
+
+    let mut config = ConfigStoreConfig::new(backend);
+    config.endpoint = UpstreamAddr::from_ip_and_port(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+    config.prefix = "/g3/".to_string();
+    config.connect_timeout = Duration::from_secs(5);
+    config.request_timeout = Duration::from_secs(5);
+    (config, handle)
+}
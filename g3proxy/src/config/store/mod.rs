@@ -0,0 +1,278 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use log::warn;
+use rustls::ServerName;
+use yaml_rust::{yaml, Yaml, YamlLoader};
+
+use g3_types::net::{RustlsClientConfig, UpstreamAddr};
+
+mod consul;
+mod etcd;
+mod http;
+
+static GLOBAL_CONFIG_STORE_CONFIG: OnceLock<ConfigStoreConfig> = OnceLock::new();
+
+/// the entries fetched at the last successful load, sorted by key
+static LOADED_ENTRIES: Mutex<Vec<StoreEntry>> = Mutex::new(Vec::new());
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ConfigStoreBackend {
+    Etcd,
+    Consul,
+}
+
+impl ConfigStoreBackend {
+    fn default_port(&self) -> u16 {
+        match self {
+            ConfigStoreBackend::Etcd => 2379,
+            ConfigStoreBackend::Consul => 8500,
+        }
+    }
+}
+
+impl FromStr for ConfigStoreBackend {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "etcd" | "etcd3" => Ok(ConfigStoreBackend::Etcd),
+            "consul" => Ok(ConfigStoreBackend::Consul),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct StoreEntry {
+    pub(crate) key: String,
+    pub(crate) value: String,
+}
+
+pub(crate) struct ConfigStoreConfig {
+    pub(crate) backend: ConfigStoreBackend,
+    pub(crate) endpoint: UpstreamAddr,
+    pub(crate) tls_client: Option<RustlsClientConfig>,
+    pub(crate) tls_name: Option<ServerName>,
+    pub(crate) prefix: String,
+    pub(crate) token: Option<String>,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) request_timeout: Duration,
+    pub(crate) watch: bool,
+    pub(crate) watch_interval: Duration,
+}
+
+impl ConfigStoreConfig {
+    fn new(backend: ConfigStoreBackend) -> Self {
+        ConfigStoreConfig {
+            backend,
+            endpoint: UpstreamAddr::empty(),
+            tls_client: None,
+            tls_name: None,
+            prefix: String::new(),
+            token: None,
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(30),
+            watch: true,
+            watch_interval: Duration::from_secs(30),
+        }
+    }
+
+    fn parse(map: &yaml::Hash, lookup_dir: &Path) -> anyhow::Result<Self> {
+        let backend = g3_yaml::hash_get_required_str(map, "type")?;
+        let backend = ConfigStoreBackend::from_str(backend)
+            .map_err(|_| anyhow!("unsupported config store type {backend}"))?;
+
+        let mut config = ConfigStoreConfig::new(backend);
+        g3_yaml::foreach_kv(map, |k, v| config.set(k, v, lookup_dir))?;
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml, lookup_dir: &Path) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "type" => Ok(()),
+            "endpoint" | "address" => {
+                self.endpoint = g3_yaml::value::as_upstream_addr(v, self.backend.default_port())
+                    .context(format!("invalid upstream addr value for key {k}"))?;
+                Ok(())
+            }
+            "tls_client" | "tls" => {
+                let builder = g3_yaml::value::as_rustls_client_config_builder(v, Some(lookup_dir))
+                    .context(format!(
+                        "invalid rustls tls client config value for key {k}"
+                    ))?;
+                let tls_client = builder
+                    .build()
+                    .context(format!("failed to build tls client config for key {k}"))?;
+                self.tls_client = Some(tls_client);
+                Ok(())
+            }
+            "tls_name" => {
+                let name = g3_yaml::value::as_rustls_server_name(v)
+                    .context(format!("invalid tls server name value for key {k}"))?;
+                self.tls_name = Some(name);
+                Ok(())
+            }
+            "prefix" | "key" => {
+                self.prefix = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                Ok(())
+            }
+            "token" => {
+                let token = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                self.token = Some(token);
+                Ok(())
+            }
+            "connect_timeout" => {
+                self.connect_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "request_timeout" => {
+                self.request_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "watch" => {
+                self.watch = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
+            "watch_interval" => {
+                self.watch_interval = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&mut self) -> anyhow::Result<()> {
+        if self.endpoint.is_empty() {
+            return Err(anyhow!("endpoint is not set"));
+        }
+        if self.tls_client.is_some() && self.tls_name.is_none() {
+            let tls_name = ServerName::try_from(self.endpoint.host())
+                .map_err(|e| anyhow!("invalid tls server name: {e}"))?;
+            self.tls_name = Some(tls_name);
+        }
+        if self.prefix.is_empty() {
+            return Err(anyhow!("prefix is not set"));
+        }
+        if self.connect_timeout.is_zero() || self.request_timeout.is_zero() {
+            return Err(anyhow!("timeout value should not be zero"));
+        }
+        if self.watch_interval.is_zero() {
+            return Err(anyhow!("watch interval should not be zero"));
+        }
+        Ok(())
+    }
+
+    /// Fetch all the entries under the prefix, sorted by key.
+    ///
+    /// If `wait_index` is set, the request will block until changes happen or the watch
+    /// interval elapsed, if the backend supports blocking queries. The returned index should be
+    /// used in the next call, or in the call to `watch`.
+    pub(crate) fn fetch(&self, wait_index: Option<u64>) -> anyhow::Result<(Vec<StoreEntry>, u64)> {
+        let (mut entries, index) = match self.backend {
+            ConfigStoreBackend::Etcd => etcd::fetch(self)?,
+            ConfigStoreBackend::Consul => consul::fetch(self, wait_index)?,
+        };
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok((entries, index))
+    }
+
+    /// Wait for changes after the index returned by `fetch`, if the backend supports watch streams.
+    ///
+    /// Returns false if no change happened in the watch interval.
+    pub(crate) fn watch(&self, index: u64) -> anyhow::Result<bool> {
+        match self.backend {
+            ConfigStoreBackend::Etcd => etcd::watch(self, index),
+            ConfigStoreBackend::Consul => Ok(true),
+        }
+    }
+}
+
+pub(crate) fn load(v: &Yaml, conf_dir: &Path) -> anyhow::Result<()> {
+    let config = match v {
+        Yaml::Hash(map) => ConfigStoreConfig::parse(map, conf_dir)?,
+        _ => return Err(anyhow!("invalid value type")),
+    };
+    GLOBAL_CONFIG_STORE_CONFIG
+        .set(config)
+        .map_err(|_| anyhow!("config store config has already been set"))
+}
+
+pub(crate) fn get_global_config() -> Option<&'static ConfigStoreConfig> {
+    GLOBAL_CONFIG_STORE_CONFIG.get()
+}
+
+pub(crate) fn loaded_entries() -> Vec<StoreEntry> {
+    LOADED_ENTRIES.lock().unwrap().clone()
+}
+
+/// Fetch the entries from the config store and call `f` for every yaml doc in them.
+///
+/// If `fallback` is set, the entries used in the last load will be used if the fetch failed.
+pub(super) fn foreach_doc<F>(fallback: bool, f: F) -> anyhow::Result<()>
+where
+    F: Fn(&yaml::Hash) -> anyhow::Result<()>,
+{
+    let Some(config) = get_global_config() else {
+        return Ok(());
+    };
+
+    let entries = match config.fetch(None) {
+        Ok((entries, _)) => entries,
+        Err(e) if fallback => {
+            warn!(
+                "failed to fetch config from {:?} store: {e:?}",
+                config.backend
+            );
+            warn!("will use the entries fetched in the last load");
+            LOADED_ENTRIES.lock().unwrap().clone()
+        }
+        Err(e) => {
+            return Err(e.context(format!(
+                "failed to fetch config from {:?} store",
+                config.backend
+            )))
+        }
+    };
+
+    for entry in &entries {
+        let docs = YamlLoader::load_from_str(&entry.value)
+            .map_err(|e| anyhow!("invalid yaml value for store key {}: {e}", entry.key))?;
+        for doc in &docs {
+            match doc {
+                Yaml::Hash(map) => f(map),
+                Yaml::Null => Ok(()),
+                _ => Err(anyhow!("yaml doc root should be hash")),
+            }
+            .context(format!("failed to load store key {}", entry.key))?;
+        }
+    }
+
+    *LOADED_ENTRIES.lock().unwrap() = entries;
+    Ok(())
+}
@@ -0,0 +1,144 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use url::Url;
+use yaml_rust::Yaml;
+
+static GLOBAL_TRACE_CONFIG: OnceLock<TraceExportConfig> = OnceLock::new();
+
+const DEFAULT_TRACES_PATH: &str = "/v1/traces";
+
+pub(crate) struct TraceExportConfig {
+    pub(crate) endpoint: Url,
+    pub(crate) service_name: String,
+    pub(crate) queue_size: usize,
+    pub(crate) batch_size: usize,
+    pub(crate) flush_interval: Duration,
+    pub(crate) timeout: Duration,
+    pub(crate) propagate: bool,
+}
+
+impl Default for TraceExportConfig {
+    fn default() -> Self {
+        TraceExportConfig {
+            endpoint: Url::parse("http://127.0.0.1:4318/v1/traces").unwrap(),
+            service_name: crate::build::PKG_NAME.to_string(),
+            queue_size: 65536,
+            batch_size: 512,
+            flush_interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(5),
+            propagate: true,
+        }
+    }
+}
+
+impl TraceExportConfig {
+    fn parse(map: &yaml_rust::yaml::Hash) -> anyhow::Result<Self> {
+        let mut config = TraceExportConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| config.set(k, v))?;
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "endpoint" | "url" => {
+                self.endpoint =
+                    g3_yaml::value::as_url(v).context(format!("invalid url value for key {k}"))?;
+                Ok(())
+            }
+            "service_name" => {
+                self.service_name = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                Ok(())
+            }
+            "queue_size" => {
+                self.queue_size = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            "batch_size" => {
+                self.batch_size = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            "flush_interval" => {
+                self.flush_interval = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "timeout" => {
+                self.timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "propagate" | "propagate_context" => {
+                self.propagate = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&mut self) -> anyhow::Result<()> {
+        if self.endpoint.scheme() != "http" {
+            return Err(anyhow!(
+                "unsupported scheme {} in endpoint url, only http is supported",
+                self.endpoint.scheme()
+            ));
+        }
+        if self.endpoint.host_str().is_none() {
+            return Err(anyhow!("no host found in endpoint url"));
+        }
+        if self.endpoint.path().is_empty() || self.endpoint.path() == "/" {
+            self.endpoint.set_path(DEFAULT_TRACES_PATH);
+        }
+        if self.queue_size == 0 {
+            self.queue_size = 1;
+        }
+        if self.batch_size == 0 {
+            self.batch_size = 1;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn load(v: &Yaml) -> anyhow::Result<()> {
+    let config = match v {
+        Yaml::Hash(map) => TraceExportConfig::parse(map)?,
+        Yaml::String(_) => {
+            let mut config = TraceExportConfig {
+                endpoint: g3_yaml::value::as_url(v).context("invalid endpoint url value")?,
+                ..Default::default()
+            };
+            config.check()?;
+            config
+        }
+        _ => return Err(anyhow!("invalid value type")),
+    };
+    GLOBAL_TRACE_CONFIG
+        .set(config)
+        .map_err(|_| anyhow!("trace config has already been set"))
+}
+
+pub(crate) fn get_global_config() -> Option<&'static TraceExportConfig> {
+    GLOBAL_TRACE_CONFIG.get()
+}
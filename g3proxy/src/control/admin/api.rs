@@ -0,0 +1,300 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashSet;
+
+use http::{Method, StatusCode};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use g3_types::metrics::MetricsName;
+
+use crate::control::bridge;
+use crate::serve::alive_task::{AliveTaskFilter, AliveTaskHandle};
+
+type ApiResult = (StatusCode, Value);
+
+fn ok(notice: &str) -> ApiResult {
+    (StatusCode::OK, json!({ "ok": notice }))
+}
+
+pub(super) fn error<T: AsRef<str>>(status: StatusCode, reason: T) -> ApiResult {
+    (status, json!({ "error": reason.as_ref() }))
+}
+
+fn operation_result(r: anyhow::Result<()>) -> ApiResult {
+    match r {
+        Ok(_) => ok("success"),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")),
+    }
+}
+
+fn name_list(set: HashSet<MetricsName>) -> ApiResult {
+    let mut names: Vec<&str> = set.iter().map(|name| name.as_str()).collect();
+    names.sort_unstable();
+    (StatusCode::OK, json!(names))
+}
+
+pub(super) async fn handle(method: &Method, path: &str, query: Option<&str>) -> ApiResult {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match (method, segments.as_slice()) {
+        (&Method::GET, ["v1", "status"]) => status(),
+        (&Method::POST, ["v1", "offline"]) => operation_result(bridge::offline().await),
+        (&Method::POST, ["v1", "reload", kind, name]) => reload(kind, name).await,
+        (&Method::GET, ["v1", "user-groups"]) => name_list(crate::auth::get_names()),
+        (&Method::GET, ["v1", "user-groups", name, "users"]) => list_users(name),
+        (&Method::GET, ["v1", "resolvers"]) => name_list(crate::resolve::get_names()),
+        (&Method::GET, ["v1", "auditors"]) => name_list(crate::audit::get_names()),
+        (&Method::GET, ["v1", "escapers"]) => name_list(crate::escape::get_names()),
+        (&Method::GET, ["v1", "servers"]) => name_list(crate::serve::get_names()),
+        (&Method::POST, ["v1", "servers", "force-quit-offline"]) => {
+            crate::serve::force_quit_offline_servers();
+            ok("success")
+        }
+        (&Method::POST, ["v1", "servers", name, "force-quit-offline"]) => {
+            let name = unsafe { MetricsName::from_str_unchecked(name) };
+            crate::serve::force_quit_offline_server(&name);
+            ok("success")
+        }
+        (&Method::GET, ["v1", "tasks"]) => list_tasks(query),
+        (&Method::POST, ["v1", "tasks", "kill"]) => kill_tasks(query),
+        (&Method::POST, ["v1", "tasks", id, "kill"]) => kill_task(id),
+        _ => error(StatusCode::NOT_FOUND, format!("no api for {method} {path}")),
+    }
+}
+
+fn status() -> ApiResult {
+    let alive_tasks = crate::serve::alive_task::list(&AliveTaskFilter::default()).len();
+    let v = json!({
+        "version": crate::build::VERSION,
+        "pid": std::process::id(),
+        "daemon_group": crate::opts::daemon_group(),
+        "alive_tasks": alive_tasks,
+    });
+    (StatusCode::OK, v)
+}
+
+async fn reload(kind: &str, name: &str) -> ApiResult {
+    let name = name.to_string();
+    let r = match kind {
+        "user-group" => bridge::reload_user_group(name, None).await,
+        "resolver" => bridge::reload_resolver(name, None).await,
+        "auditor" => bridge::reload_auditor(name, None).await,
+        "escaper" => bridge::reload_escaper(name, None).await,
+        "server" => bridge::reload_server(name, None).await,
+        _ => {
+            return error(
+                StatusCode::NOT_FOUND,
+                format!("unsupported reload type {kind}"),
+            )
+        }
+    };
+    operation_result(r)
+}
+
+fn list_users(name: &str) -> ApiResult {
+    let name = unsafe { MetricsName::from_str_unchecked(name) };
+    if !crate::auth::get_names().contains(&name) {
+        return error(StatusCode::NOT_FOUND, format!("no user group {name} found"));
+    }
+    let user_group = crate::auth::get_or_insert_default(&name);
+    let v = json!({
+        "static": user_group.all_static_users(),
+        "dynamic": user_group.all_dynamic_users(),
+    });
+    (StatusCode::OK, v)
+}
+
+fn parse_task_filter(query: Option<&str>) -> AliveTaskFilter {
+    let mut filter = AliveTaskFilter::default();
+    let Some(query) = query else {
+        return filter;
+    };
+    for (k, v) in url::form_urlencoded::parse(query.as_bytes()) {
+        if v.is_empty() {
+            continue;
+        }
+        match k.as_ref() {
+            "server" => filter.server = Some(unsafe { MetricsName::from_str_unchecked(&v) }),
+            "user_group" => {
+                filter.user_group = Some(unsafe { MetricsName::from_str_unchecked(&v) })
+            }
+            "user" => filter.user = Some(v.into_owned()),
+            _ => {}
+        }
+    }
+    filter
+}
+
+fn task_json(task: &AliveTaskHandle) -> Value {
+    let mut v = json!({
+        "id": task.id.simple().to_string(),
+        "server": task.server.as_str(),
+        "client_addr": task.client_addr().to_string(),
+        "upstream": task.upstream.to_string(),
+        "start_at": task.start_at.to_rfc3339(),
+        "alive_millis": task.time_elapsed().as_millis() as u64,
+    });
+    if let Some(user_group) = &task.user_group {
+        v["user_group"] = json!(user_group.as_str());
+    }
+    if let Some(user) = &task.user {
+        v["user"] = json!(user);
+    }
+    if let Some(io_stats) = task.io_stats() {
+        v["client_read_bytes"] = json!(io_stats.client_read_bytes());
+        v["client_write_bytes"] = json!(io_stats.client_write_bytes());
+    }
+    v
+}
+
+fn list_tasks(query: Option<&str>) -> ApiResult {
+    let filter = parse_task_filter(query);
+    let tasks: Vec<Value> = crate::serve::alive_task::list(&filter)
+        .iter()
+        .map(|task| task_json(task))
+        .collect();
+    (StatusCode::OK, Value::Array(tasks))
+}
+
+fn kill_task(id: &str) -> ApiResult {
+    let Ok(uuid) = Uuid::parse_str(id) else {
+        return error(StatusCode::BAD_REQUEST, format!("invalid task id {id}"));
+    };
+    match crate::serve::alive_task::kill(&uuid) {
+        Ok(_) => ok("success"),
+        Err(e) => error(
+            StatusCode::NOT_FOUND,
+            format!("failed to kill task {id}: {e}"),
+        ),
+    }
+}
+
+fn kill_tasks(query: Option<&str>) -> ApiResult {
+    let filter = parse_task_filter(query);
+    if filter.is_empty() {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "server, user_group or user should be set in the query",
+        );
+    }
+    let count = crate::serve::alive_task::kill_matched(&filter);
+    ok(&format!("{count} tasks killed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn status() {
+        let (status, v) = handle(&Method::GET, "/v1/status", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(v["version"], crate::build::VERSION);
+        assert_eq!(v["pid"], std::process::id());
+        assert!(v["alive_tasks"].is_u64());
+
+        let (status, _) = handle(&Method::GET, "//v1//status/", None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn name_lists() {
+        for path in [
+            "/v1/user-groups",
+            "/v1/resolvers",
+            "/v1/auditors",
+            "/v1/escapers",
+            "/v1/servers",
+        ] {
+            let (status, v) = handle(&Method::GET, path, None).await;
+            assert_eq!(status, StatusCode::OK, "{path}");
+            assert!(v.is_array(), "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_path() {
+        for path in ["/", "/v1", "/v2/status", "/v1/status/more", "/v1/unknown"] {
+            let (status, v) = handle(&Method::GET, path, None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{path}");
+            assert!(v["error"].is_string(), "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn wrong_method() {
+        let (status, _) = handle(&Method::POST, "/v1/status", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = handle(&Method::GET, "/v1/offline", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = handle(&Method::DELETE, "/v1/tasks", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn reload_unsupported() {
+        let (status, v) = handle(&Method::POST, "/v1/reload/unknown/test", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(v["error"], "unsupported reload type unknown");
+    }
+
+    #[tokio::test]
+    async fn users_of_unknown_group() {
+        let (status, v) = handle(&Method::GET, "/v1/user-groups/not-exist/users", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(v["error"], "no user group not-exist found");
+    }
+
+    #[tokio::test]
+    async fn tasks() {
+        let (status, v) = handle(&Method::GET, "/v1/tasks", Some("server=not-exist")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(v, json!([]));
+
+        let (status, _) = handle(&Method::POST, "/v1/tasks/kill", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = handle(&Method::POST, "/v1/tasks/kill", Some("server=")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, v) = handle(&Method::POST, "/v1/tasks/kill", Some("user=not-exist")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(v["ok"], "0 tasks killed");
+
+        let (status, _) = handle(&Method::POST, "/v1/tasks/bad-id/kill", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let id = Uuid::new_v4().simple().to_string();
+        let (status, _) = handle(&Method::POST, &format!("/v1/tasks/{id}/kill"), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn task_filter() {
+        let filter = parse_task_filter(None);
+        assert!(filter.is_empty());
+
+        let filter = parse_task_filter(Some("server=s1&user_group=g1&user=u%201&other=x"));
+        assert_eq!(filter.server.as_ref().unwrap().as_str(), "s1");
+        assert_eq!(filter.user_group.as_ref().unwrap().as_str(), "g1");
+        assert_eq!(filter.user.as_deref(), Some("u 1"));
+
+        let filter = parse_task_filter(Some("server=&user="));
+        assert!(filter.is_empty());
+    }
+}
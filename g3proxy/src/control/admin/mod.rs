@@ -0,0 +1,197 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::Write;
+use std::net::SocketAddr;
+
+use anyhow::anyhow;
+use http::StatusCode;
+use log::{debug, warn};
+use serde_json::Value;
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use g3_daemon::upgrade::ListenFdGuard;
+use g3_http::HttpTransparentRequest;
+
+use crate::config::admin_api::AdminApiConfig;
+
+mod api;
+
+/// spawn the admin api listener on the current runtime if it's configured
+pub async fn spawn_listener() -> anyhow::Result<()> {
+    let Some(config) = crate::config::admin_api::get_global_config() else {
+        return Ok(());
+    };
+
+    let listener = match g3_daemon::upgrade::take_tcp_listener(config.listen) {
+        Some(listener) => TcpListener::from_std(listener)
+            .map_err(|e| anyhow!("failed to use inherited listener {}: {e}", config.listen))?,
+        None => TcpListener::bind(config.listen)
+            .await
+            .map_err(|e| anyhow!("failed to listen on {}: {e}", config.listen))?,
+    };
+    let fd_guard = ListenFdGuard::new_tcp(&listener, config.listen);
+    tokio::spawn(async move {
+        let _fd_guard = fd_guard;
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer_addr)) => {
+                    tokio::spawn(handle_connection(stream, peer_addr, config));
+                }
+                Err(e) => warn!("admin api: failed to accept: {e}"),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn check_token(req: &HttpTransparentRequest, token: &str) -> bool {
+    let Some(value) = req.end_to_end_headers.get(http::header::AUTHORIZATION) else {
+        return false;
+    };
+    let Some(given) = value.to_str().strip_prefix("Bearer ") else {
+        return false;
+    };
+    let given = given.trim().as_bytes();
+    let token = token.as_bytes();
+    // compare in constant time for equal length tokens
+    given.len() == token.len()
+        && given
+            .iter()
+            .zip(token.iter())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    peer_addr: SocketAddr,
+    config: &'static AdminApiConfig,
+) {
+    let (clt_r, mut clt_w) = stream.into_split();
+    let mut clt_r = BufReader::new(clt_r);
+
+    let req = match tokio::time::timeout(
+        config.recv_timeout,
+        HttpTransparentRequest::parse(&mut clt_r, config.req_header_max_size),
+    )
+    .await
+    {
+        Ok(Ok((req, _))) => req,
+        Ok(Err(e)) => {
+            debug!("admin api: invalid request from {peer_addr}: {e}");
+            return;
+        }
+        Err(_) => {
+            debug!("admin api: timed out to read request from {peer_addr}");
+            return;
+        }
+    };
+
+    // no request body is needed by the apis, and the connection will always be closed
+    let (status, body) = if check_token(&req, &config.token) {
+        api::handle(&req.method, req.uri.path(), req.uri.query()).await
+    } else {
+        api::error(StatusCode::UNAUTHORIZED, "invalid auth token")
+    };
+    debug!(
+        "admin api: {} {} from {peer_addr} -> {}",
+        req.method,
+        req.uri.path(),
+        status.as_u16()
+    );
+
+    let _ = send_response(&mut clt_w, status, &body).await;
+}
+
+async fn send_response<W>(writer: &mut W, status: StatusCode, body: &Value) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let body = serde_json::to_vec(body).unwrap_or_default();
+
+    let mut rsp_head = Vec::with_capacity(128);
+    let _ = write!(
+        rsp_head,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or_default(),
+        body.len()
+    );
+    writer.write_all(&rsp_head).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await?;
+    writer.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn parse_request(data: &[u8]) -> HttpTransparentRequest {
+        let mut reader = data;
+        let (req, _) = HttpTransparentRequest::parse(&mut reader, 4096)
+            .await
+            .unwrap();
+        req
+    }
+
+    #[tokio::test]
+    async fn token_valid() {
+        let req = parse_request(
+            b"GET /v1/status HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer abc123\r\n\r\n",
+        )
+        .await;
+        assert!(check_token(&req, "abc123"));
+    }
+
+    #[tokio::test]
+    async fn token_missing() {
+        let req = parse_request(b"GET /v1/status HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+        assert!(!check_token(&req, "abc123"));
+    }
+
+    #[tokio::test]
+    async fn token_wrong() {
+        let req = parse_request(
+            b"GET /v1/status HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer abc124\r\n\r\n",
+        )
+        .await;
+        assert!(!check_token(&req, "abc123"));
+
+        let req = parse_request(
+            b"GET /v1/status HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer abc\r\n\r\n",
+        )
+        .await;
+        assert!(!check_token(&req, "abc123"));
+
+        let req = parse_request(
+            b"GET /v1/status HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer \r\n\r\n",
+        )
+        .await;
+        assert!(!check_token(&req, "abc123"));
+    }
+
+    #[tokio::test]
+    async fn token_wrong_scheme() {
+        let req = parse_request(
+            b"GET /v1/status HTTP/1.1\r\nHost: localhost\r\nAuthorization: Basic abc123\r\n\r\n",
+        )
+        .await;
+        assert!(!check_token(&req, "abc123"));
+    }
+}
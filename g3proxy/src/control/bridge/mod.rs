@@ -14,8 +14,14 @@
  * limitations under the License.
  */
 
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
 use anyhow::anyhow;
 
+use crate::config::partial::EntryKind;
+
 mod reload;
 pub(super) use reload::{
     reload_auditor, reload_escaper, reload_resolver, reload_server, reload_user_group,
@@ -29,3 +35,164 @@ pub(crate) async fn offline() -> anyhow::Result<()> {
         .map_err(|e| anyhow!("failed to spawn reload task: {e}"))?;
     Ok(())
 }
+
+/// run a new process of the current executable in check config mode,
+/// so the loaded config won't be affected
+pub(crate) async fn check_config(file: Option<PathBuf>) -> anyhow::Result<String> {
+    let file = match file {
+        Some(file) => file,
+        None => g3_daemon::opts::config_file()
+            .ok_or_else(|| anyhow!("no config file set"))?
+            .to_path_buf(),
+    };
+    let exe = std::env::current_exe().map_err(|e| anyhow!("failed to get current exe: {e}"))?;
+    let args = check_config_args(
+        &file,
+        crate::opts::daemon_group(),
+        &g3_daemon::opts::control_dir(),
+        g3_yaml::env_interpolation_enabled(),
+    );
+
+    let output = tokio::task::spawn_blocking(move || Command::new(exe).args(args).output())
+        .await
+        .map_err(|e| anyhow!("failed to join check task: {e}"))?
+        .map_err(|e| anyhow!("failed to run check process: {e}"))?;
+
+    let mut msg = String::from_utf8_lossy(&output.stdout).into_owned();
+    msg.push_str(&String::from_utf8_lossy(&output.stderr));
+    let msg = msg.trim_end().to_string();
+    if output.status.success() {
+        Ok(msg)
+    } else {
+        Err(anyhow!("{msg}"))
+    }
+}
+
+/// keep the group name and control dir of the running daemon,
+/// so the config will be loaded in the same way as it
+fn check_config_args(
+    file: &Path,
+    group: &str,
+    control_dir: &Path,
+    env_interpolation: bool,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec![
+        "--check-config".into(),
+        "--config-file".into(),
+        file.into(),
+        "--control-dir".into(),
+        control_dir.into(),
+    ];
+    if !group.is_empty() {
+        args.push("--group-name".into());
+        args.push(group.into());
+    }
+    if env_interpolation {
+        args.push("--env-interpolation".into());
+    }
+    args
+}
+
+/// reload exactly one named entry from the on-disk config, and report the changed keys
+pub(crate) async fn partial_reload(kind: &str, name: String) -> anyhow::Result<String> {
+    let kind = EntryKind::parse(kind)?;
+    let name2 = name.clone();
+    let entry =
+        tokio::task::spawn_blocking(move || crate::config::partial::find_on_disk(kind, &name2))
+            .await
+            .map_err(|e| anyhow!("failed to join config scan task: {e}"))??
+            .ok_or_else(|| anyhow!("no {kind} {name} found in the on-disk config"))?;
+    let Some(position) = entry.position.clone() else {
+        return Err(anyhow!(
+            "{kind} {name} is defined inline in the main conf file, only a full reload is supported"
+        ));
+    };
+
+    let changes = entry.diff_loaded(kind, &name);
+    if let Some(changes) = &changes {
+        if changes.is_empty() {
+            return Ok(format!("{kind} {name} not changed"));
+        }
+    }
+
+    match kind {
+        EntryKind::Server => reload_server(name.clone(), Some(position.clone())).await,
+        EntryKind::Escaper => reload_escaper(name.clone(), Some(position.clone())).await,
+        EntryKind::Auditor => reload_auditor(name.clone(), Some(position.clone())).await,
+        EntryKind::Resolver => reload_resolver(name.clone(), Some(position.clone())).await,
+        EntryKind::UserGroup => reload_user_group(name.clone(), Some(position.clone())).await,
+    }?;
+    entry.mark_loaded(kind, &name);
+
+    match changes {
+        Some(changes) => Ok(format!(
+            "{kind} {name} reloaded from {position}, changed keys: {}",
+            changes.join(" ")
+        )),
+        None => Ok(format!(
+            "{kind} {name} reloaded from {position}, no previous record to compare"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_args() {
+        let args = check_config_args(
+            Path::new("/etc/g3proxy/new.yaml"),
+            "edge",
+            Path::new("/run/g3proxy"),
+            false,
+        );
+        assert_eq!(
+            args,
+            [
+                "--check-config",
+                "--config-file",
+                "/etc/g3proxy/new.yaml",
+                "--control-dir",
+                "/run/g3proxy",
+                "--group-name",
+                "edge",
+            ]
+        );
+
+        let args = check_config_args(
+            Path::new("/etc/g3proxy/main.yaml"),
+            "",
+            Path::new("/run/g3proxy"),
+            false,
+        );
+        assert_eq!(
+            args,
+            [
+                "--check-config",
+                "--config-file",
+                "/etc/g3proxy/main.yaml",
+                "--control-dir",
+                "/run/g3proxy",
+            ]
+        );
+
+        let args = check_config_args(
+            Path::new("/etc/g3proxy/main.yaml"),
+            "",
+            Path::new("/run/g3proxy"),
+            true,
+        );
+        assert_eq!(
+            args,
+            [
+                "--check-config",
+                "--config-file",
+                "/etc/g3proxy/main.yaml",
+                "--control-dir",
+                "/run/g3proxy",
+                "--env-interpolation",
+            ]
+        );
+    }
+}
@@ -0,0 +1,141 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use g3_types::metrics::MetricsName;
+
+use g3proxy_proto::bandwidth_capnp::bandwidth_rate;
+
+const DEFAULT_SAMPLE_WINDOW: Duration = Duration::from_secs(1);
+const MAX_SAMPLE_WINDOW: Duration = Duration::from_secs(10);
+
+pub(super) struct BandwidthRate {
+    group: Option<MetricsName>,
+    name: String,
+    in_bytes_per_sec: u64,
+    out_bytes_per_sec: u64,
+}
+
+impl BandwidthRate {
+    pub(super) fn set_builder(&self, mut builder: bandwidth_rate::Builder<'_>) {
+        if let Some(group) = &self.group {
+            builder.set_group(group.as_str());
+        }
+        builder.set_name(self.name.as_str());
+        builder.set_in_bytes_per_sec(self.in_bytes_per_sec);
+        builder.set_out_bytes_per_sec(self.out_bytes_per_sec);
+    }
+}
+
+fn sample_window(secs: u32) -> Duration {
+    if secs == 0 {
+        DEFAULT_SAMPLE_WINDOW
+    } else {
+        Duration::from_secs(secs as u64).min(MAX_SAMPLE_WINDOW)
+    }
+}
+
+fn calc_rates<K: Eq + Hash>(
+    begin: HashMap<K, (u64, u64)>,
+    end: HashMap<K, (u64, u64)>,
+    elapsed: Duration,
+) -> Vec<(K, u64, u64)> {
+    let millis = elapsed.as_millis().max(1) as u64;
+    let mut rates = Vec::new();
+    for (k, (end_in, end_out)) in end {
+        let Some((begin_in, begin_out)) = begin.get(&k) else {
+            continue;
+        };
+        // the counters may go backward if some stats got dropped in the window
+        let in_rate = end_in.saturating_sub(*begin_in).saturating_mul(1000) / millis;
+        let out_rate = end_out.saturating_sub(*begin_out).saturating_mul(1000) / millis;
+        if in_rate > 0 || out_rate > 0 {
+            rates.push((k, in_rate, out_rate));
+        }
+    }
+    rates
+}
+
+fn user_io_bytes() -> HashMap<(MetricsName, String), (u64, u64)> {
+    let mut map = HashMap::new();
+    for group in crate::auth::get_all_groups() {
+        group.foreach_user(|name, user| {
+            map.insert(
+                (group.name().clone(), name.to_string()),
+                user.traffic_io_bytes(),
+            );
+        });
+    }
+    map
+}
+
+fn escaper_io_bytes() -> HashMap<MetricsName, (u64, u64)> {
+    let mut map = HashMap::new();
+    crate::escape::foreach_escaper(|name, escaper| {
+        let Some(stats) = escaper.get_escape_stats() else {
+            return;
+        };
+        let mut in_bytes = 0u64;
+        let mut out_bytes = 0u64;
+        if let Some(tcp) = stats.tcp_io_snapshot() {
+            in_bytes = in_bytes.wrapping_add(tcp.in_bytes);
+            out_bytes = out_bytes.wrapping_add(tcp.out_bytes);
+        }
+        if let Some(udp) = stats.udp_io_snapshot() {
+            in_bytes = in_bytes.wrapping_add(udp.in_bytes);
+            out_bytes = out_bytes.wrapping_add(udp.out_bytes);
+        }
+        map.insert(name.clone(), (in_bytes, out_bytes));
+    });
+    map
+}
+
+pub(super) async fn sample_user(window: u32) -> Vec<BandwidthRate> {
+    let begin = user_io_bytes();
+    let time_begin = Instant::now();
+    tokio::time::sleep(sample_window(window)).await;
+    let end = user_io_bytes();
+
+    calc_rates(begin, end, time_begin.elapsed())
+        .into_iter()
+        .map(|((group, name), in_rate, out_rate)| BandwidthRate {
+            group: Some(group),
+            name,
+            in_bytes_per_sec: in_rate,
+            out_bytes_per_sec: out_rate,
+        })
+        .collect()
+}
+
+pub(super) async fn sample_escaper(window: u32) -> Vec<BandwidthRate> {
+    let begin = escaper_io_bytes();
+    let time_begin = Instant::now();
+    tokio::time::sleep(sample_window(window)).await;
+    let end = escaper_io_bytes();
+
+    calc_rates(begin, end, time_begin.elapsed())
+        .into_iter()
+        .map(|(name, in_rate, out_rate)| BandwidthRate {
+            group: None,
+            name: name.to_string(),
+            in_bytes_per_sec: in_rate,
+            out_bytes_per_sec: out_rate,
+        })
+        .collect()
+}
@@ -51,4 +51,17 @@ impl escaper_control::Server for EscaperControlImpl {
             Ok(())
         })
     }
+
+    fn list_quarantined_peers(
+        &mut self,
+        _params: escaper_control::ListQuarantinedPeersParams,
+        mut results: escaper_control::ListQuarantinedPeersResults,
+    ) -> Promise<(), capnp::Error> {
+        let v = self.escaper.quarantined_peers();
+        let mut builder = results.get().init_result(v.len() as u32);
+        for (i, peer) in v.iter().enumerate() {
+            builder.set(i as u32, peer);
+        }
+        Promise::ok(())
+    }
 }
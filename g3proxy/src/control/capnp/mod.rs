@@ -20,9 +20,11 @@ mod common;
 use common::set_operation_result;
 mod proc;
 
+mod bandwidth;
 mod escaper;
 mod resolver;
 mod server;
+mod task;
 mod user_group;
 
 pub fn stop_working_thread() {
@@ -14,8 +14,12 @@
  * limitations under the License.
  */
 
+use std::path::PathBuf;
+
+use anyhow::anyhow;
 use capnp::capability::Promise;
 use capnp_rpc::pry;
+use uuid::Uuid;
 
 use g3_types::metrics::MetricsName;
 
@@ -256,6 +260,127 @@ impl proc_control::Server for ProcControlImpl {
         results.get().init_result().set_ok("success");
         Promise::ok(())
     }
+
+    fn list_task(
+        &mut self,
+        params: proc_control::ListTaskParams,
+        mut results: proc_control::ListTaskResults,
+    ) -> Promise<(), capnp::Error> {
+        let filter = pry!(pry!(params.get()).get_filter());
+        let filter = pry!(super::task::parse_task_filter(filter));
+        let tasks = crate::serve::alive_task::list(&filter);
+        let mut builder = results.get().init_result(tasks.len() as u32);
+        for (i, task) in tasks.iter().enumerate() {
+            super::task::set_task_info(builder.reborrow().get(i as u32), task);
+        }
+        Promise::ok(())
+    }
+
+    fn kill_task(
+        &mut self,
+        params: proc_control::KillTaskParams,
+        mut results: proc_control::KillTaskResults,
+    ) -> Promise<(), capnp::Error> {
+        let id = pry!(pry!(pry!(params.get()).get_id()).to_str());
+        let r = Uuid::parse_str(id)
+            .map_err(|e| anyhow!("invalid task id {id}: {e}"))
+            .and_then(|id| {
+                crate::serve::alive_task::kill(&id)
+                    .map_err(|e| anyhow!("failed to kill task {id}: {e}"))
+            });
+        set_operation_result(results.get().init_result(), r);
+        Promise::ok(())
+    }
+
+    fn kill_tasks(
+        &mut self,
+        params: proc_control::KillTasksParams,
+        mut results: proc_control::KillTasksResults,
+    ) -> Promise<(), capnp::Error> {
+        let filter = pry!(pry!(params.get()).get_filter());
+        let filter = pry!(super::task::parse_task_filter(filter));
+        if filter.is_empty() {
+            let mut ev = results.get().init_result().init_err();
+            ev.set_code(-1);
+            ev.set_reason("server or user should be set in the filter");
+            return Promise::ok(());
+        }
+        let count = crate::serve::alive_task::kill_matched(&filter);
+        results
+            .get()
+            .init_result()
+            .set_ok(format!("{count} tasks killed").as_str());
+        Promise::ok(())
+    }
+
+    fn query_user_bandwidth(
+        &mut self,
+        params: proc_control::QueryUserBandwidthParams,
+        mut results: proc_control::QueryUserBandwidthResults,
+    ) -> Promise<(), capnp::Error> {
+        let window = pry!(params.get()).get_window();
+        Promise::from_future(async move {
+            let rates = super::bandwidth::sample_user(window).await;
+            let mut builder = results.get().init_result(rates.len() as u32);
+            for (i, rate) in rates.iter().enumerate() {
+                rate.set_builder(builder.reborrow().get(i as u32));
+            }
+            Ok(())
+        })
+    }
+
+    fn check_config(
+        &mut self,
+        params: proc_control::CheckConfigParams,
+        mut results: proc_control::CheckConfigResults,
+    ) -> Promise<(), capnp::Error> {
+        let file = pry!(pry!(pry!(params.get()).get_file()).to_str());
+        let file = if file.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(file))
+        };
+        Promise::from_future(async move {
+            match crate::control::bridge::check_config(file).await {
+                Ok(msg) => results.get().init_result().set_ok(msg.as_str()),
+                Err(e) => set_operation_result(results.get().init_result(), Err(e)),
+            }
+            Ok(())
+        })
+    }
+
+    fn partial_reload(
+        &mut self,
+        params: proc_control::PartialReloadParams,
+        mut results: proc_control::PartialReloadResults,
+    ) -> Promise<(), capnp::Error> {
+        let params = pry!(params.get());
+        let kind = pry!(pry!(params.get_kind()).to_string());
+        let name = pry!(pry!(params.get_name()).to_string());
+        Promise::from_future(async move {
+            match crate::control::bridge::partial_reload(&kind, name).await {
+                Ok(msg) => results.get().init_result().set_ok(msg.as_str()),
+                Err(e) => set_operation_result(results.get().init_result(), Err(e)),
+            }
+            Ok(())
+        })
+    }
+
+    fn query_escaper_bandwidth(
+        &mut self,
+        params: proc_control::QueryEscaperBandwidthParams,
+        mut results: proc_control::QueryEscaperBandwidthResults,
+    ) -> Promise<(), capnp::Error> {
+        let window = pry!(params.get()).get_window();
+        Promise::from_future(async move {
+            let rates = super::bandwidth::sample_escaper(window).await;
+            let mut builder = results.get().init_result(rates.len() as u32);
+            for (i, rate) in rates.iter().enumerate() {
+                rate.set_builder(builder.reborrow().get(i as u32));
+            }
+            Ok(())
+        })
+    }
 }
 
 fn set_fetch_result<'a, T>(
@@ -21,10 +21,11 @@ use capnp::capability::Promise;
 use capnp_rpc::pry;
 
 use g3_types::metrics::MetricsName;
-use g3_types::resolve::{QueryStrategy as ResolveQueryStrategy, ResolveStrategy};
+use g3_types::resolve::{QueryStrategy as ResolveQueryStrategy, ResolvePickState, ResolveStrategy};
 
 use g3proxy_proto::resolver_capnp::{resolver_control, QueryStrategy};
 
+use super::set_operation_result;
 use crate::resolve::{ArcIntegratedResolverHandle, HappyEyeballsResolveJob};
 
 pub(super) struct ResolverControlImpl {
@@ -55,8 +56,10 @@ impl resolver_control::Server for ResolverControlImpl {
         let resolver_handler = Arc::clone(&self.resolver_handler);
 
         Promise::from_future(async move {
+            let pick_state = Arc::new(ResolvePickState::default());
             let mut job = match HappyEyeballsResolveJob::new_dyn(
                 resolver_strategy,
+                &pick_state,
                 &resolver_handler,
                 &domain,
             ) {
@@ -87,6 +90,78 @@ impl resolver_control::Server for ResolverControlImpl {
             Ok(())
         })
     }
+
+    fn dump_cache(
+        &mut self,
+        _params: resolver_control::DumpCacheParams,
+        mut results: resolver_control::DumpCacheResults,
+    ) -> Promise<(), capnp::Error> {
+        let Some(handle) = self.resolver_handler.clone_inner() else {
+            return Promise::err(capnp::Error::failed(
+                "cache is not supported on this resolver".to_string(),
+            ));
+        };
+
+        Promise::from_future(async move {
+            let records = handle
+                .dump_cache()
+                .await
+                .map_err(|e| capnp::Error::failed(format!("failed to dump cache: {e}")))?;
+            let mut builder = results.get().init_result(records.len() as u32);
+            for (i, r) in records.iter().enumerate() {
+                let mut entry = builder.reborrow().get(i as u32);
+                entry.set_domain(r.record.domain.as_str());
+                entry.set_query_type(r.query_type.as_str());
+                entry.set_ttl_remaining(r.ttl_remaining.as_secs() as u32);
+                entry.set_stale(r.stale);
+                entry.set_hits(r.hits);
+                if let Some(subnet) = &r.subnet {
+                    entry.set_client_subnet(subnet.to_string().as_str());
+                }
+                match &r.record.result {
+                    Ok(ips) => {
+                        let mut ips_builder = entry.init_ip(ips.len() as u32);
+                        for (j, ip) in ips.iter().enumerate() {
+                            ips_builder.set(j as u32, ip.to_string().as_str());
+                        }
+                    }
+                    Err(e) => entry.set_err(e.to_string().as_str()),
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn flush_cache(
+        &mut self,
+        params: resolver_control::FlushCacheParams,
+        mut results: resolver_control::FlushCacheResults,
+    ) -> Promise<(), capnp::Error> {
+        let Some(handle) = self.resolver_handler.clone_inner() else {
+            return Promise::err(capnp::Error::failed(
+                "cache is not supported on this resolver".to_string(),
+            ));
+        };
+        let domains = pry!(pry!(params.get()).get_domains());
+        let mut domain_list = Vec::with_capacity(domains.len() as usize);
+        for domain in domains.iter() {
+            domain_list.push(pry!(pry!(domain).to_string()));
+        }
+
+        Promise::from_future(async move {
+            match handle.flush_cache(domain_list).await {
+                Ok(count) => results
+                    .get()
+                    .init_result()
+                    .set_ok(format!("{count} records flushed").as_str()),
+                Err(e) => set_operation_result(
+                    results.get().init_result(),
+                    Err(anyhow::anyhow!("failed to flush cache: {e}")),
+                ),
+            }
+            Ok(())
+        })
+    }
 }
 
 fn get_resolver_strategy(q: QueryStrategy) -> ResolveStrategy {
@@ -98,6 +173,6 @@ fn get_resolver_strategy(q: QueryStrategy) -> ResolveStrategy {
     };
     ResolveStrategy {
         query: qs,
-        pick: Default::default(),
+        ..Default::default()
     }
 }
@@ -0,0 +1,51 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use g3_types::metrics::MetricsName;
+
+use g3proxy_proto::task_capnp::{task_filter, task_info};
+
+use crate::serve::alive_task::{AliveTaskFilter, AliveTaskHandle};
+
+pub(super) fn parse_task_filter(r: task_filter::Reader<'_>) -> capnp::Result<AliveTaskFilter> {
+    let mut filter = AliveTaskFilter::default();
+    let server = r.get_server()?.to_str()?;
+    if !server.is_empty() {
+        filter.server = Some(unsafe { MetricsName::from_str_unchecked(server) });
+    }
+    let user = r.get_user()?.to_str()?;
+    if !user.is_empty() {
+        filter.user = Some(user.to_string());
+    }
+    Ok(filter)
+}
+
+pub(super) fn set_task_info(mut builder: task_info::Builder<'_>, task: &AliveTaskHandle) {
+    builder.set_id(task.id.simple().to_string().as_str());
+    builder.set_server(task.server.as_str());
+    if let Some(user) = &task.user {
+        builder.set_user(user.as_str());
+    }
+    builder.set_client_addr(task.client_addr().to_string().as_str());
+    builder.set_upstream(task.upstream.to_string().as_str());
+    builder.set_start_at(task.start_at.to_rfc3339().as_str());
+    builder.set_alive_millis(task.time_elapsed().as_millis() as u64);
+    if let Some(io_stats) = task.io_stats() {
+        let mut io_builder = builder.init_io();
+        io_builder.set_client_read_bytes(io_stats.client_read_bytes());
+        io_builder.set_client_write_bytes(io_stats.client_write_bytes());
+    }
+}
@@ -78,4 +78,98 @@ impl user_group_control::Server for UserGroupControlImpl {
             Ok(())
         })
     }
+
+    fn query_traffic_quota(
+        &mut self,
+        params: user_group_control::QueryTrafficQuotaParams,
+        mut results: user_group_control::QueryTrafficQuotaResults,
+    ) -> Promise<(), capnp::Error> {
+        let user = pry!(pry!(pry!(params.get()).get_user()).to_str());
+        let mut builder = results.get().init_result();
+        match self.user_group.traffic_quota_status(user) {
+            Ok(status) => builder.set_ok(status.as_str()),
+            Err(e) => {
+                let mut ev = builder.init_err();
+                ev.set_code(-1);
+                ev.set_reason(format!("{e:?}").as_str());
+            }
+        }
+        Promise::ok(())
+    }
+
+    fn query_password_cache(
+        &mut self,
+        _params: user_group_control::QueryPasswordCacheParams,
+        mut results: user_group_control::QueryPasswordCacheResults,
+    ) -> Promise<(), capnp::Error> {
+        let mut builder = results.get().init_result();
+        match self.user_group.password_cache_status() {
+            Ok(status) => builder.set_ok(status.as_str()),
+            Err(e) => {
+                let mut ev = builder.init_err();
+                ev.set_code(-1);
+                ev.set_reason(format!("{e:?}").as_str());
+            }
+        }
+        Promise::ok(())
+    }
+
+    fn flush_password_cache(
+        &mut self,
+        params: user_group_control::FlushPasswordCacheParams,
+        mut results: user_group_control::FlushPasswordCacheResults,
+    ) -> Promise<(), capnp::Error> {
+        let user = pry!(pry!(pry!(params.get()).get_user()).to_str());
+        let user = if user.is_empty() { None } else { Some(user) };
+        let r = self.user_group.flush_password_cache(user);
+        set_operation_result(results.get().init_result(), r);
+        Promise::ok(())
+    }
+
+    fn block_user(
+        &mut self,
+        params: user_group_control::BlockUserParams,
+        mut results: user_group_control::BlockUserResults,
+    ) -> Promise<(), capnp::Error> {
+        let user = pry!(pry!(pry!(params.get()).get_user()).to_str());
+        let r = self.user_group.set_user_blocked(user, true);
+        set_operation_result(results.get().init_result(), r);
+        Promise::ok(())
+    }
+
+    fn unblock_user(
+        &mut self,
+        params: user_group_control::UnblockUserParams,
+        mut results: user_group_control::UnblockUserResults,
+    ) -> Promise<(), capnp::Error> {
+        let user = pry!(pry!(pry!(params.get()).get_user()).to_str());
+        let r = self.user_group.set_user_blocked(user, false);
+        set_operation_result(results.get().init_result(), r);
+        Promise::ok(())
+    }
+
+    fn reset_traffic_quota(
+        &mut self,
+        params: user_group_control::ResetTrafficQuotaParams,
+        mut results: user_group_control::ResetTrafficQuotaResults,
+    ) -> Promise<(), capnp::Error> {
+        let user = pry!(pry!(pry!(params.get()).get_user()).to_str());
+        let r = self.user_group.reset_traffic_quota(user);
+        set_operation_result(results.get().init_result(), r);
+        Promise::ok(())
+    }
+
+    fn expire_user_sessions(
+        &mut self,
+        params: user_group_control::ExpireUserSessionsParams,
+        mut results: user_group_control::ExpireUserSessionsResults,
+    ) -> Promise<(), capnp::Error> {
+        let user = pry!(pry!(pry!(params.get()).get_user()).to_str());
+        let count = self.user_group.expire_user_sessions(user);
+        results
+            .get()
+            .init_result()
+            .set_ok(format!("{count} tasks closed").as_str());
+        Promise::ok(())
+    }
 }
@@ -0,0 +1,446 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use log::{info, warn};
+use yaml_rust::Yaml;
+
+use g3_types::metrics::MetricsName;
+use g3_yaml::YamlDocPosition;
+
+use crate::config::cert_watch::CertWatchConfig;
+
+/// yaml keys whose value may be a certificate or private key file path
+const CERT_FILE_KEYS: &[&str] = &[
+    "certificate",
+    "cert",
+    "private_key",
+    "key",
+    "ca_certificate",
+    "ca_cert",
+    "client_auth_certificate",
+    "client_auth_cert",
+    "server_auth_certificate",
+    "server_auth_cert",
+    "enc_certificate",
+    "enc_cert",
+    "enc_private_key",
+    "enc_key",
+    "sign_certificate",
+    "sign_cert",
+    "sign_private_key",
+    "sign_key",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum WatchTarget {
+    Server,
+    Escaper,
+}
+
+#[derive(PartialEq, Eq)]
+struct FileStamp {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    fn new(path: PathBuf) -> Self {
+        // follow symlinks, so atomic symlink swaps are detected as well
+        match std::fs::metadata(&path) {
+            Ok(meta) => FileStamp {
+                modified: meta.modified().ok(),
+                len: meta.len(),
+                path,
+            },
+            Err(_) => FileStamp {
+                path,
+                modified: None,
+                len: 0,
+            },
+        }
+    }
+}
+
+type Snapshot = HashMap<(WatchTarget, MetricsName), Vec<FileStamp>>;
+
+fn collect_files(v: &Yaml, lookup_dir: &Path, is_cert_value: bool, files: &mut BTreeSet<PathBuf>) {
+    match v {
+        Yaml::Hash(map) => {
+            for (k, v) in map {
+                let is_cert_key = k
+                    .as_str()
+                    .map(|k| CERT_FILE_KEYS.contains(&g3_yaml::key::normalize(k).as_str()))
+                    .unwrap_or(false);
+                collect_files(v, lookup_dir, is_cert_key, files);
+            }
+        }
+        Yaml::Array(seq) => {
+            for v in seq {
+                collect_files(v, lookup_dir, is_cert_value, files);
+            }
+        }
+        Yaml::String(s) if is_cert_value => {
+            if s.trim_start().starts_with("--") {
+                // inline pem content
+                return;
+            }
+            let path = lookup_dir.join(s);
+            if path.is_file() {
+                files.insert(path);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn load_stamps(position: &YamlDocPosition) -> anyhow::Result<Vec<FileStamp>> {
+    let doc = g3_yaml::load_doc(position)?;
+    let lookup_dir = g3_daemon::config::get_lookup_dir(Some(position))?;
+    let mut files = BTreeSet::new();
+    collect_files(&doc, lookup_dir, false, &mut files);
+    Ok(files.into_iter().map(FileStamp::new).collect())
+}
+
+fn take_snapshot() -> Snapshot {
+    let mut snapshot = HashMap::new();
+
+    let mut add = |target: WatchTarget, name: &MetricsName, position: Option<YamlDocPosition>| {
+        let Some(position) = position else {
+            return;
+        };
+        match load_stamps(&position) {
+            Ok(stamps) => {
+                if !stamps.is_empty() {
+                    snapshot.insert((target, name.clone()), stamps);
+                }
+            }
+            Err(e) => warn!("cert watch: failed to scan config at {position}: {e:?}"),
+        }
+    };
+
+    for c in crate::config::server::get_all() {
+        add(WatchTarget::Server, c.name(), c.position());
+    }
+    for c in crate::config::escaper::get_all() {
+        add(WatchTarget::Escaper, c.name(), c.position());
+    }
+
+    snapshot
+}
+
+async fn take_snapshot_blocking() -> Snapshot {
+    tokio::task::spawn_blocking(take_snapshot)
+        .await
+        .unwrap_or_default()
+}
+
+/// entries newly added in the snapshot are not included, as they have just been loaded
+fn changed_entries<'a>(old: &Snapshot, new: &'a Snapshot) -> Vec<&'a (WatchTarget, MetricsName)> {
+    new.iter()
+        .filter(|(key, stamps)| {
+            old.get(*key)
+                .map(|old_stamps| old_stamps != *stamps)
+                .unwrap_or(false)
+        })
+        .map(|(key, _)| key)
+        .collect()
+}
+
+async fn reload_changed(old: &Snapshot, new: &Snapshot) {
+    for (target, name) in changed_entries(old, new) {
+        let r = match target {
+            WatchTarget::Server => {
+                info!("cert watch: certificate files changed, reloading server {name}");
+                super::bridge::reload_server(name.to_string(), None).await
+            }
+            WatchTarget::Escaper => {
+                info!("cert watch: certificate files changed, reloading escaper {name}");
+                super::bridge::reload_escaper(name.to_string(), None).await
+            }
+        };
+        if let Err(e) = r {
+            warn!("cert watch: failed to reload {name}: {e:?}");
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct DirNotifier {
+    stream: inotify::EventStream<[u8; 4096]>,
+    watched: BTreeSet<PathBuf>,
+}
+
+#[cfg(target_os = "linux")]
+impl DirNotifier {
+    fn new() -> anyhow::Result<Self> {
+        use anyhow::anyhow;
+
+        let inotify = inotify::Inotify::init()
+            .map_err(|e| anyhow!("failed to init inotify instance: {e}"))?;
+        let stream = inotify.into_event_stream([0u8; 4096])?;
+        Ok(DirNotifier {
+            stream,
+            watched: BTreeSet::new(),
+        })
+    }
+
+    fn update(&mut self, snapshot: &Snapshot) {
+        use inotify::WatchMask;
+
+        let mask = WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::CREATE;
+        for stamp in snapshot.values().flatten() {
+            let Some(dir) = stamp.path.parent() else {
+                continue;
+            };
+            if self.watched.contains(dir) {
+                continue;
+            }
+            match self.stream.watches().add(dir, mask) {
+                Ok(_) => {
+                    self.watched.insert(dir.to_path_buf());
+                }
+                Err(e) => warn!("cert watch: failed to watch dir {}: {e}", dir.display()),
+            }
+        }
+    }
+
+    async fn changed(&mut self) {
+        use std::future::poll_fn;
+
+        use futures_util::StreamExt;
+
+        match poll_fn(|cx| self.stream.poll_next_unpin(cx)).await {
+            Some(Ok(_)) => {}
+            Some(Err(e)) => warn!("cert watch: inotify watch failed: {e}"),
+            None => std::future::pending::<()>().await,
+        }
+    }
+}
+
+async fn run(config: &'static CertWatchConfig) {
+    let mut snapshot = take_snapshot_blocking().await;
+
+    #[cfg(target_os = "linux")]
+    let mut notifier = match DirNotifier::new() {
+        Ok(mut n) => {
+            n.update(&snapshot);
+            Some(n)
+        }
+        Err(e) => {
+            warn!("cert watch: fallback to polling only: {e:?}");
+            None
+        }
+    };
+
+    loop {
+        #[cfg(target_os = "linux")]
+        {
+            let notified = async {
+                match notifier.as_mut() {
+                    Some(n) => n.changed().await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(config.interval) => {}
+                _ = notified => {
+                    // wait for the related cert and key files to be all written
+                    tokio::time::sleep(config.debounce).await;
+                }
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        tokio::time::sleep(config.interval).await;
+
+        let new_snapshot = take_snapshot_blocking().await;
+        reload_changed(&snapshot, &new_snapshot).await;
+        #[cfg(target_os = "linux")]
+        if let Some(n) = notifier.as_mut() {
+            n.update(&new_snapshot);
+        }
+        snapshot = new_snapshot;
+    }
+}
+
+/// watch the certificate and key files used by servers and escapers,
+/// and reload the related entries if any of them changed
+pub fn spawn() {
+    if let Some(config) = crate::config::cert_watch::get_global_config() {
+        tokio::spawn(run(config));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    struct TestDir {
+        path: PathBuf,
+    }
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("g3proxy-cert-watch-{}-{name}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            TestDir { path }
+        }
+
+        fn write(&self, name: &str, content: &str) -> PathBuf {
+            let path = self.path.join(name);
+            std::fs::write(&path, content).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+
+    #[test]
+    fn scan_config() {
+        let dir = TestDir::new("scan");
+        for name in [
+            "server.crt",
+            "server.key",
+            "ca.crt",
+            "enc.crt",
+            "enc.key",
+            "access.log",
+        ] {
+            dir.write(name, "test");
+        }
+
+        let config = r#"
+            name: test
+            type: https_proxy
+            log_file: access.log
+            tls_server:
+              certificate: server.crt
+              private-key: server.key
+              ca_cert: ca.crt
+            tls_client:
+              certificate: |
+                --BEGIN CERTIFICATE--
+                --END CERTIFICATE--
+              ca_certificate: missing.crt
+            tlcp_cert_pairs:
+              - enc_cert: enc.crt
+                enc_key: enc.key
+            "#;
+        let docs = yaml_rust::YamlLoader::load_from_str(config).unwrap();
+
+        let mut files = BTreeSet::new();
+        collect_files(&docs[0], &dir.path, false, &mut files);
+        let expected: BTreeSet<PathBuf> =
+            ["ca.crt", "enc.crt", "enc.key", "server.crt", "server.key"]
+                .into_iter()
+                .map(|name| dir.path.join(name))
+                .collect();
+        assert_eq!(files, expected);
+    }
+
+    #[test]
+    fn file_stamp() {
+        let dir = TestDir::new("stamp");
+        let path = dir.write("server.crt", "cert v1");
+        let stamp = FileStamp::new(path.clone());
+        assert!(stamp.modified.is_some());
+        assert!(FileStamp::new(path.clone()) == stamp);
+
+        // same length and mtime, so it's treated as unchanged
+        dir.write("server.crt", "cert v2");
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(stamp.modified.unwrap())
+            .unwrap();
+        assert!(FileStamp::new(path.clone()) == stamp);
+
+        // mtime changed
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(stamp.modified.unwrap() + Duration::from_secs(1))
+            .unwrap();
+        assert!(FileStamp::new(path.clone()) != stamp);
+
+        // content length changed
+        dir.write("server.crt", "new cert");
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(stamp.modified.unwrap())
+            .unwrap();
+        assert!(FileStamp::new(path.clone()) != stamp);
+
+        // removed
+        std::fs::remove_file(&path).unwrap();
+        assert!(FileStamp::new(path) != stamp);
+    }
+
+    fn build_snapshot(entries: &[(WatchTarget, &str, &PathBuf)]) -> Snapshot {
+        entries
+            .iter()
+            .map(|(target, name, path)| {
+                let name = MetricsName::from_str(name).unwrap();
+                ((*target, name), vec![FileStamp::new(path.to_path_buf())])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn changed_snapshot() {
+        let dir = TestDir::new("snapshot");
+        let server_cert = dir.write("server.crt", "server cert");
+        let escaper_cert = dir.write("escaper.crt", "escaper cert");
+
+        let old = build_snapshot(&[(WatchTarget::Server, "server", &server_cert)]);
+        let new = build_snapshot(&[
+            (WatchTarget::Server, "server", &server_cert),
+            (WatchTarget::Escaper, "escaper", &escaper_cert),
+        ]);
+        // no change, and the newly added escaper should not be reloaded
+        assert!(changed_entries(&old, &new).is_empty());
+
+        let old = new;
+        dir.write("escaper.crt", "new escaper cert");
+        let new = build_snapshot(&[
+            (WatchTarget::Server, "server", &server_cert),
+            (WatchTarget::Escaper, "escaper", &escaper_cert),
+        ]);
+        let escaper = (
+            WatchTarget::Escaper,
+            MetricsName::from_str("escaper").unwrap(),
+        );
+        assert_eq!(changed_entries(&old, &new), vec![&escaper]);
+        assert!(changed_entries(&new, &new).is_empty());
+    }
+}
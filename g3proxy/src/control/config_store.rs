@@ -0,0 +1,109 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use log::{info, warn};
+
+use crate::config::store::{ConfigStoreBackend, ConfigStoreConfig, StoreEntry};
+
+async fn fetch(
+    config: &'static ConfigStoreConfig,
+    wait_index: Option<u64>,
+) -> anyhow::Result<(Vec<StoreEntry>, u64)> {
+    tokio::task::spawn_blocking(move || config.fetch(wait_index))
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to join fetch task: {e}"))?
+}
+
+/// wait until there are changes after `index`
+async fn watch(config: &'static ConfigStoreConfig, index: u64) -> anyhow::Result<()> {
+    loop {
+        let changed = tokio::task::spawn_blocking(move || config.watch(index))
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to join watch task: {e}"))??;
+        if changed {
+            return Ok(());
+        }
+    }
+}
+
+/// Get the index for the next consul blocking query.
+///
+/// The index should be reset if it goes backwards, see
+/// https://developer.hashicorp.com/consul/api-docs/features/blocking
+fn next_wait_index(last: Option<u64>, index: u64) -> u64 {
+    match last {
+        Some(last) if index < last => 0,
+        _ => index,
+    }
+}
+
+async fn run(config: &'static ConfigStoreConfig) {
+    let mut last_entries = crate::config::store::loaded_entries();
+    let mut wait_index: Option<u64> = None;
+
+    loop {
+        match fetch(config, wait_index).await {
+            Ok((entries, index)) => {
+                if entries != last_entries {
+                    info!("config store changed, reloading");
+                    crate::signal::do_reload().await;
+                    last_entries = entries;
+                }
+                if index > 0 {
+                    match config.backend {
+                        ConfigStoreBackend::Consul => {
+                            wait_index = Some(next_wait_index(wait_index, index));
+                            continue;
+                        }
+                        ConfigStoreBackend::Etcd => match watch(config, index).await {
+                            Ok(_) => continue,
+                            Err(e) => {
+                                warn!("failed to watch {:?} config store: {e:?}", config.backend)
+                            }
+                        },
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("failed to watch {:?} config store: {e:?}", config.backend);
+                wait_index = None;
+            }
+        }
+        tokio::time::sleep(config.watch_interval).await;
+    }
+}
+
+pub fn spawn() {
+    if let Some(config) = crate::config::store::get_global_config() {
+        if config.watch {
+            tokio::spawn(run(config));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_index() {
+        assert_eq!(next_wait_index(None, 10), 10);
+        assert_eq!(next_wait_index(Some(10), 10), 10);
+        assert_eq!(next_wait_index(Some(10), 12), 12);
+        assert_eq!(next_wait_index(Some(12), 10), 0);
+        assert_eq!(next_wait_index(Some(0), 5), 5);
+    }
+}
@@ -18,8 +18,12 @@ use std::future::Future;
 
 use tokio::sync::Mutex;
 
+pub mod admin;
 mod bridge;
 pub mod capnp;
+pub mod cert_watch;
+pub mod config_store;
+pub mod upgrade;
 
 mod local;
 pub use local::{DaemonController, UniqueController};
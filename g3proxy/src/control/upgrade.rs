@@ -0,0 +1,100 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+use log::{info, warn};
+use serde_json::Value;
+
+use g3_daemon::upgrade::{UpgradeListener, UpgradeSession};
+use g3_types::metrics::MetricsName;
+
+/// collect the published data of all escapers, which will be handed over to the new process
+fn export_state() -> Vec<u8> {
+    let mut map = serde_json::Map::new();
+    crate::escape::foreach_escaper(|name, escaper| {
+        if let Some(data) = escaper.published_data() {
+            map.insert(name.to_string(), Value::String(data));
+        }
+    });
+    Value::Object(map).to_string().into_bytes()
+}
+
+async fn import_state(state: &[u8]) {
+    if state.is_empty() {
+        return;
+    }
+    let map = match serde_json::from_slice::<Value>(state) {
+        Ok(Value::Object(map)) => map,
+        Ok(_) => {
+            warn!("upgrade: invalid state data, json map expected");
+            return;
+        }
+        Err(e) => {
+            warn!("upgrade: invalid state data: {e}");
+            return;
+        }
+    };
+
+    for (name, data) in map {
+        let Value::String(data) = data else {
+            continue;
+        };
+        let name = unsafe { MetricsName::from_unchecked(name) };
+        let r = match crate::escape::get_escaper(&name) {
+            Ok(escaper) => escaper.publish(data).await,
+            Err(e) => Err(e),
+        };
+        match r {
+            Ok(_) => info!("upgrade: restored published data for escaper {name}"),
+            Err(e) => warn!("upgrade: failed to restore published data for escaper {name}: {e:?}"),
+        }
+    }
+}
+
+/// listen for the new process to take over, this daemon will go offline after that
+pub fn spawn_listener() -> anyhow::Result<()> {
+    let listener = UpgradeListener::create(crate::opts::daemon_group())?;
+    tokio::spawn(async move {
+        listener.serve(export_state).await;
+        drop(listener);
+        info!("upgrade: the new process is ready, going offline");
+        super::DaemonController::abort().await;
+    });
+    Ok(())
+}
+
+/// take over the listen sockets and state from the running process of the same daemon group
+pub async fn take_over() -> anyhow::Result<UpgradeSession> {
+    let daemon_group = crate::opts::daemon_group();
+    tokio::task::spawn_blocking(move || UpgradeSession::take_over(daemon_group))
+        .await
+        .map_err(|e| anyhow!("failed to join take over task: {e}"))?
+}
+
+/// restore the state sent by the old process, this should be called after all escapers loaded
+pub async fn restore(session: &UpgradeSession) {
+    import_state(session.state()).await;
+}
+
+/// notify the old process to go offline, this should be called after all servers spawned
+pub async fn finish(session: UpgradeSession) -> anyhow::Result<()> {
+    session.finish().await?;
+    let closed = g3_daemon::upgrade::release_inherited();
+    if closed > 0 {
+        info!("upgrade: closed {closed} unused inherited listen sockets");
+    }
+    Ok(())
+}
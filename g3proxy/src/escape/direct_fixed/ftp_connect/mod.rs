@@ -42,18 +42,22 @@ impl DirectFixedEscaper {
         let wrapper_stats = Arc::new(wrapper_stats);
 
         let limit_config = &self.config.general.tcp_sock_speed_limit;
-        let r = LimitedReader::new(
+        let mut r = LimitedReader::new(
             r,
             limit_config.shift_millis,
             limit_config.max_south,
             wrapper_stats.clone() as _,
         );
-        let w = LimitedWriter::new(
+        r.set_burst_limit(limit_config.burst_south);
+        self.tcp_global_limiter.apply_to_reader(&mut r);
+        let mut w = LimitedWriter::new(
             w,
             limit_config.shift_millis,
             limit_config.max_north,
             wrapper_stats as _,
         );
+        w.set_burst_limit(limit_config.burst_north);
+        self.tcp_global_limiter.apply_to_writer(&mut w);
 
         Ok(Box::new(AggregatedIo {
             reader: r,
@@ -79,18 +83,22 @@ impl DirectFixedEscaper {
         let wrapper_stats = Arc::new(wrapper_stats);
 
         let limit_config = &self.config.general.tcp_sock_speed_limit;
-        let r = LimitedReader::new(
+        let mut r = LimitedReader::new(
             r,
             limit_config.shift_millis,
             limit_config.max_south,
             wrapper_stats.clone() as _,
         );
-        let w = LimitedWriter::new(
+        r.set_burst_limit(limit_config.burst_south);
+        self.tcp_global_limiter.apply_to_reader(&mut r);
+        let mut w = LimitedWriter::new(
             w,
             limit_config.shift_millis,
             limit_config.max_north,
             wrapper_stats as _,
         );
+        w.set_burst_limit(limit_config.burst_north);
+        self.tcp_global_limiter.apply_to_writer(&mut w);
 
         Ok(Box::new(AggregatedIo {
             reader: r,
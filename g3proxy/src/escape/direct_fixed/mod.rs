@@ -26,14 +26,19 @@ use g3_daemon::stat::remote::ArcTcpConnectionTaskRemoteStats;
 use g3_resolver::ResolveError;
 use g3_socket::util::AddressFamily;
 use g3_types::acl::AclNetworkRule;
+use g3_types::collection::{
+    SelectiveHash, SelectiveItem, SelectivePickPolicy, SelectiveVec, SelectiveVecBuilder,
+    WeightedValue,
+};
 use g3_types::metrics::MetricsName;
 use g3_types::net::{Host, OpensslClientConfig, UpstreamAddr};
-use g3_types::resolve::{ResolveRedirection, ResolveStrategy};
-use g3_types::route::EgressPathSelection;
+use g3_types::resolve::{ResolvePickState, ResolveRedirection, ResolveStrategy};
 
-use super::{ArcEscaper, ArcEscaperStats, Escaper, EscaperInternal, EscaperStats};
+use super::{
+    ArcEscaper, ArcEscaperStats, Escaper, EscaperInternal, EscaperStats, GlobalTcpLimiter,
+};
 use crate::auth::UserUpstreamTrafficStats;
-use crate::config::escaper::direct_fixed::DirectFixedEscaperConfig;
+use crate::config::escaper::direct_fixed::{BindIpHashKey, DirectFixedEscaperConfig};
 use crate::config::escaper::{AnyEscaperConfig, EscaperConfig};
 use crate::module::ftp_over_http::{
     AnyFtpConnectContextParam, ArcFtpTaskRemoteControlStats, ArcFtpTaskRemoteTransferStats,
@@ -68,15 +73,62 @@ pub(super) struct DirectFixedEscaper {
     config: Arc<DirectFixedEscaperConfig>,
     stats: Arc<DirectFixedEscaperStats>,
     resolver_handle: ArcIntegratedResolverHandle,
+    resolve_pick_state: Arc<ResolvePickState>,
     egress_net_filter: Arc<AclNetworkRule>,
     resolve_redirection: Option<ResolveRedirection>,
+    bind4_nodes: Option<SelectiveVec<WeightedValue<IpAddr>>>,
+    bind6_nodes: Option<SelectiveVec<WeightedValue<IpAddr>>>,
+    tcp_global_limiter: GlobalTcpLimiter,
     escape_logger: Logger,
 }
 
+fn build_bind_nodes(ips: &[IpAddr]) -> Option<SelectiveVec<WeightedValue<IpAddr>>> {
+    let mut builder = SelectiveVecBuilder::with_capacity(ips.len());
+    for ip in ips {
+        builder.insert(WeightedValue::new(*ip));
+    }
+    builder.build()
+}
+
+/// the key for rendezvous / jump hash when selecting bind ip
+#[derive(Hash)]
+pub(super) enum BindHashKey<'a> {
+    ClientIp(IpAddr),
+    UpstreamHost(&'a Host),
+}
+
+impl<'a> BindHashKey<'a> {
+    /// the client ip will be used if there is no upstream host, such as udp associate
+    pub(super) fn new(key: BindIpHashKey, client_ip: IpAddr, ups_host: Option<&'a Host>) -> Self {
+        match (key, ups_host) {
+            (BindIpHashKey::UpstreamHost, Some(host)) => BindHashKey::UpstreamHost(host),
+            _ => BindHashKey::ClientIp(client_ip),
+        }
+    }
+}
+
+pub(super) fn pick_bind<'a, T>(
+    nodes: &'a SelectiveVec<T>,
+    pick_policy: SelectivePickPolicy,
+    key: &BindHashKey,
+) -> &'a T
+where
+    T: SelectiveItem + SelectiveHash,
+{
+    match pick_policy {
+        SelectivePickPolicy::Random => nodes.pick_random(),
+        SelectivePickPolicy::Serial => nodes.pick_serial(),
+        SelectivePickPolicy::RoundRobin => nodes.pick_round_robin(),
+        SelectivePickPolicy::Rendezvous => nodes.pick_rendezvous(key),
+        SelectivePickPolicy::JumpHash => nodes.pick_jump(key),
+    }
+}
+
 impl DirectFixedEscaper {
     fn new_obj(
         config: DirectFixedEscaperConfig,
         stats: Arc<DirectFixedEscaperStats>,
+        tcp_global_limiter: Option<&GlobalTcpLimiter>,
     ) -> anyhow::Result<ArcEscaper> {
         let resolver_handle = crate::resolve::get_handle(config.resolver())?;
         let egress_net_filter = Arc::new(config.egress_net_filter.build());
@@ -86,16 +138,30 @@ impl DirectFixedEscaper {
             .as_ref()
             .map(|builder| builder.build());
 
+        let bind4_nodes = build_bind_nodes(&config.bind4);
+        let bind6_nodes = build_bind_nodes(&config.bind6);
+
+        let tcp_global_limiter = match tcp_global_limiter {
+            Some(limiter) => limiter.reload(&config.general),
+            None => GlobalTcpLimiter::new(&config.general),
+        };
         let escape_logger = config.get_escape_logger();
 
         stats.set_extra_tags(config.extra_metrics_tags.clone());
+        stats
+            .tcp
+            .set_connect_duration_config(config.general.tcp_connect_duration_stats.as_ref());
 
         let escaper = DirectFixedEscaper {
             config: Arc::new(config),
             stats,
             resolver_handle,
+            resolve_pick_state: Arc::new(ResolvePickState::default()),
             egress_net_filter,
             resolve_redirection,
+            bind4_nodes,
+            bind6_nodes,
+            tcp_global_limiter,
             escape_logger,
         };
 
@@ -104,42 +170,52 @@ impl DirectFixedEscaper {
 
     pub(super) fn prepare_initial(config: DirectFixedEscaperConfig) -> anyhow::Result<ArcEscaper> {
         let stats = Arc::new(DirectFixedEscaperStats::new(config.name()));
-        DirectFixedEscaper::new_obj(config, stats)
+        DirectFixedEscaper::new_obj(config, stats, None)
     }
 
     fn prepare_reload(
         config: AnyEscaperConfig,
         stats: Arc<DirectFixedEscaperStats>,
+        tcp_global_limiter: &GlobalTcpLimiter,
     ) -> anyhow::Result<ArcEscaper> {
         if let AnyEscaperConfig::DirectFixed(config) = config {
-            DirectFixedEscaper::new_obj(*config, stats)
+            DirectFixedEscaper::new_obj(*config, stats, Some(tcp_global_limiter))
         } else {
             Err(anyhow!("invalid escaper config type"))
         }
     }
 
-    fn get_bind_random(
+    fn nat64_synthesize(&self, ip: IpAddr) -> IpAddr {
+        match (ip, &self.config.nat64_prefix) {
+            (IpAddr::V4(ip4), Some(prefix)) => IpAddr::V6(prefix.synthesize(ip4)),
+            _ => ip,
+        }
+    }
+
+    fn get_bind_ip(
         &self,
         family: AddressFamily,
-        path_selection: &EgressPathSelection,
+        task_notes: &ServerTaskNotes,
+        ups_host: Option<&Host>,
     ) -> Option<IpAddr> {
-        let vec = match family {
-            AddressFamily::Ipv4 => &self.config.bind4,
-            AddressFamily::Ipv6 => &self.config.bind6,
+        let (vec, nodes) = match family {
+            AddressFamily::Ipv4 => (&self.config.bind4, &self.bind4_nodes),
+            AddressFamily::Ipv6 => (&self.config.bind6, &self.bind6_nodes),
         };
-        match vec.len() {
-            0 => None,
-            1 => Some(vec[0]),
-            n => {
-                if self.config.enable_path_selection {
-                    if let Some(i) = path_selection.select_by_index(n) {
-                        return Some(vec[i]);
-                    }
-                }
-
-                fastrand::choice(vec).copied()
+        let nodes = nodes.as_ref()?;
+        if vec.len() > 1 && self.config.enable_path_selection {
+            if let Some(i) = task_notes.egress_path_selection.select_by_index(vec.len()) {
+                return Some(vec[i]);
             }
         }
+
+        let key = BindHashKey::new(
+            self.config.bind_ip_hash_key,
+            task_notes.client_ip(),
+            ups_host,
+        );
+        let node = pick_bind(nodes, self.config.bind_ip_pick_policy, &key);
+        Some(*node.inner())
     }
 
     fn get_resolve_strategy(&self, task_notes: &ServerTaskNotes) -> ResolveStrategy {
@@ -154,18 +230,41 @@ impl DirectFixedEscaper {
         }
     }
 
+    fn get_resolver_handle(
+        &self,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<ArcIntegratedResolverHandle, ResolveError> {
+        match task_notes.user_ctx().and_then(|ctx| ctx.site_resolver()) {
+            Some(name) => crate::resolve::get_handle(name)
+                .map_err(|e| ResolveError::UnexpectedError(format!("user site resolver: {e}"))),
+            None => Ok(self.resolver_handle.clone()),
+        }
+    }
+
     fn resolve_happy(
         &self,
         domain: &str,
         strategy: ResolveStrategy,
         task_notes: &ServerTaskNotes,
     ) -> Result<HappyEyeballsResolveJob, ResolveError> {
+        let resolver_handle = self.get_resolver_handle(task_notes)?;
+
         if let Some(user_ctx) = task_notes.user_ctx() {
+            if let Some(v) = user_ctx.site_resolve_static() {
+                return HappyEyeballsResolveJob::new_redirected(
+                    strategy,
+                    &self.resolve_pick_state,
+                    &resolver_handle,
+                    v,
+                );
+            }
+
             if let Some(redirect) = user_ctx.user().resolve_redirection() {
                 if let Some(v) = redirect.query_value(domain) {
                     return HappyEyeballsResolveJob::new_redirected(
                         strategy,
-                        &self.resolver_handle,
+                        &self.resolve_pick_state,
+                        &resolver_handle,
                         v,
                     );
                 }
@@ -174,24 +273,47 @@ impl DirectFixedEscaper {
 
         if let Some(redirect) = &self.resolve_redirection {
             if let Some(v) = redirect.query_value(domain) {
-                return HappyEyeballsResolveJob::new_redirected(strategy, &self.resolver_handle, v);
+                return HappyEyeballsResolveJob::new_redirected(
+                    strategy,
+                    &self.resolve_pick_state,
+                    &resolver_handle,
+                    v,
+                );
             }
         }
 
-        HappyEyeballsResolveJob::new_dyn(strategy, &self.resolver_handle, domain)
+        HappyEyeballsResolveJob::new_dyn_for_client(
+            strategy,
+            &self.resolve_pick_state,
+            &resolver_handle,
+            domain,
+            Some(task_notes.client_ip()),
+        )
     }
 
     async fn resolve_best(
         &self,
         domain: &str,
         strategy: ResolveStrategy,
+        resolver_handle: &ArcIntegratedResolverHandle,
+    ) -> Result<IpAddr, ResolveError> {
+        let resolver_job = HappyEyeballsResolveJob::new_dyn(
+            strategy,
+            &self.resolve_pick_state,
+            resolver_handle,
+            domain,
+        )?;
+        self.resolve_job_get_best(resolver_job).await
+    }
+
+    async fn resolve_job_get_best(
+        &self,
+        mut resolver_job: HappyEyeballsResolveJob,
     ) -> Result<IpAddr, ResolveError> {
-        let mut resolver_job =
-            HappyEyeballsResolveJob::new_dyn(strategy, &self.resolver_handle, domain)?;
         let ips = resolver_job
             .get_r1_or_first(self.config.happy_eyeballs.resolution_delay(), usize::MAX)
             .await?;
-        strategy.pick_best(ips).ok_or_else(|| {
+        resolver_job.pick_best(ips).ok_or_else(|| {
             ResolveError::UnexpectedError("no upstream ip can be selected".to_string())
         })
     }
@@ -200,10 +322,14 @@ impl DirectFixedEscaper {
         &self,
         redirect_result: Host,
         resolve_strategy: ResolveStrategy,
+        resolver_handle: &ArcIntegratedResolverHandle,
     ) -> Result<IpAddr, ResolveError> {
         match redirect_result {
             Host::Ip(ip) => Ok(ip),
-            Host::Domain(new) => self.resolve_best(&new, resolve_strategy).await,
+            Host::Domain(new) => {
+                self.resolve_best(&new, resolve_strategy, resolver_handle)
+                    .await
+            }
         }
     }
 
@@ -216,11 +342,26 @@ impl DirectFixedEscaper {
         match ups.host() {
             Host::Ip(ip) => Ok(SocketAddr::new(*ip, ups.port())),
             Host::Domain(domain) => {
+                let resolver_handle = self.get_resolver_handle(task_notes)?;
+
                 if let Some(user_ctx) = task_notes.user_ctx() {
+                    if let Some(v) = user_ctx.site_resolve_static() {
+                        let resolver_job = HappyEyeballsResolveJob::new_redirected(
+                            resolve_strategy,
+                            &self.resolve_pick_state,
+                            &resolver_handle,
+                            v,
+                        )?;
+                        return self
+                            .resolve_job_get_best(resolver_job)
+                            .await
+                            .map(|ip| SocketAddr::new(ip, ups.port()));
+                    }
+
                     if let Some(redirect) = user_ctx.user().resolve_redirection() {
                         if let Some(v) = redirect.query_first(domain, resolve_strategy.query) {
                             return self
-                                .redirect_get_best(v, resolve_strategy)
+                                .redirect_get_best(v, resolve_strategy, &resolver_handle)
                                 .await
                                 .map(|ip| SocketAddr::new(ip, ups.port()));
                         }
@@ -230,13 +371,15 @@ impl DirectFixedEscaper {
                 if let Some(redirect) = &self.resolve_redirection {
                     if let Some(v) = redirect.query_first(domain, resolve_strategy.query) {
                         return self
-                            .redirect_get_best(v, resolve_strategy)
+                            .redirect_get_best(v, resolve_strategy, &resolver_handle)
                             .await
                             .map(|ip| SocketAddr::new(ip, ups.port()));
                     }
                 }
 
-                let ip = self.resolve_best(domain, resolve_strategy).await?;
+                let ip = self
+                    .resolve_best(domain, resolve_strategy, &resolver_handle)
+                    .await?;
                 Ok(SocketAddr::new(ip, ups.port()))
             }
         }
@@ -360,7 +503,7 @@ impl EscaperInternal for DirectFixedEscaper {
 
     async fn _lock_safe_reload(&self, config: AnyEscaperConfig) -> anyhow::Result<ArcEscaper> {
         let stats = Arc::clone(&self.stats);
-        DirectFixedEscaper::prepare_reload(config, stats)
+        DirectFixedEscaper::prepare_reload(config, stats, &self.tcp_global_limiter)
     }
 
     async fn _check_out_next_escaper(
@@ -438,3 +581,87 @@ impl EscaperInternal for DirectFixedEscaper {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    fn bind_nodes() -> SelectiveVec<WeightedValue<IpAddr>> {
+        let ips: Vec<IpAddr> = (1..=4).map(|i| IpAddr::from([192, 168, 0, i])).collect();
+        build_bind_nodes(&ips).unwrap()
+    }
+
+    #[test]
+    fn pick_round_robin() {
+        let nodes = bind_nodes();
+        let key = BindHashKey::ClientIp(IpAddr::from([10, 0, 0, 1]));
+        let picked: Vec<IpAddr> = (0..8)
+            .map(|_| *pick_bind(&nodes, SelectivePickPolicy::RoundRobin, &key).inner())
+            .collect();
+        assert_eq!(picked[..4], picked[4..]);
+        assert_eq!(picked[..4].iter().collect::<HashSet<_>>().len(), 4);
+    }
+
+    #[test]
+    fn pick_by_client_ip() {
+        let nodes = bind_nodes();
+        let host = Host::from_str("www.example.com").unwrap();
+
+        for policy in [
+            SelectivePickPolicy::Rendezvous,
+            SelectivePickPolicy::JumpHash,
+        ] {
+            let mut picked = HashSet::new();
+            for i in 0..64 {
+                let client_ip = IpAddr::from([10, 0, 0, i]);
+                let key = BindHashKey::new(BindIpHashKey::ClientIp, client_ip, Some(&host));
+                let ip = *pick_bind(&nodes, policy, &key).inner();
+                // sticky for the same client ip
+                for _ in 0..4 {
+                    assert_eq!(*pick_bind(&nodes, policy, &key).inner(), ip);
+                }
+                picked.insert(ip);
+            }
+            // spread over different client ips
+            assert!(picked.len() > 1);
+        }
+    }
+
+    #[test]
+    fn pick_by_upstream_host() {
+        let nodes = bind_nodes();
+        let host = Host::from_str("www.example.com").unwrap();
+
+        for policy in [
+            SelectivePickPolicy::Rendezvous,
+            SelectivePickPolicy::JumpHash,
+        ] {
+            let key = BindHashKey::new(
+                BindIpHashKey::UpstreamHost,
+                IpAddr::from([10, 0, 0, 1]),
+                Some(&host),
+            );
+            let ip = *pick_bind(&nodes, policy, &key).inner();
+            // the same upstream host from any client ip
+            for i in 2..64 {
+                let key = BindHashKey::new(
+                    BindIpHashKey::UpstreamHost,
+                    IpAddr::from([10, 0, 0, i]),
+                    Some(&host),
+                );
+                assert_eq!(*pick_bind(&nodes, policy, &key).inner(), ip);
+            }
+
+            // fallback to client ip if no upstream host
+            let client_ip = IpAddr::from([10, 0, 0, 1]);
+            let key = BindHashKey::new(BindIpHashKey::UpstreamHost, client_ip, None);
+            let expected = BindHashKey::new(BindIpHashKey::ClientIp, client_ip, Some(&host));
+            assert_eq!(
+                pick_bind(&nodes, policy, &key).inner(),
+                pick_bind(&nodes, policy, &expected).inner()
+            );
+        }
+    }
+}
@@ -19,13 +19,14 @@ use std::sync::Arc;
 use arc_swap::ArcSwapOption;
 
 use g3_daemon::stat::remote::TcpConnectionTaskRemoteStats;
+use g3_histogram::HistogramStats;
 use g3_io_ext::{LimitedReaderStats, LimitedWriterStats};
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::stats::{StatId, TcpIoSnapshot, UdpIoSnapshot};
 
 use crate::escape::{
     EscaperForbiddenSnapshot, EscaperForbiddenStats, EscaperInterfaceStats, EscaperInternalStats,
-    EscaperStats, EscaperTcpStats, EscaperUdpStats,
+    EscaperStats, EscaperTcpConnectErrorSnapshot, EscaperTcpStats, EscaperUdpStats,
 };
 use crate::module::ftp_over_http::{FtpTaskRemoteControlStats, FtpTaskRemoteTransferStats};
 use crate::module::http_forward::HttpForwardTaskRemoteStats;
@@ -106,6 +107,15 @@ impl EscaperStats for DirectFixedEscaperStats {
         Some(self.tcp.io.snapshot())
     }
 
+    #[inline]
+    fn tcp_connect_duration_stats(&self) -> Option<Arc<HistogramStats>> {
+        self.tcp.connect_duration_stats()
+    }
+
+    fn tcp_connect_error_snapshot(&self) -> Option<EscaperTcpConnectErrorSnapshot> {
+        Some(self.tcp.connect_error_snapshot())
+    }
+
     #[inline]
     fn udp_io_snapshot(&self) -> Option<UdpIoSnapshot> {
         Some(self.udp.io.snapshot())
@@ -83,7 +83,7 @@ impl DirectFixedEscaper {
         self.handle_udp_target_ip_acl_action(action, task_notes)?;
 
         let family = AddressFamily::from(&peer_addr);
-        let bind_ip = self.get_bind_random(family, &task_notes.egress_path_selection);
+        let bind_ip = self.get_bind_ip(family, task_notes, Some(upstream.host()));
         udp_notes.bind = bind_ip;
 
         let misc_opts = if let Some(user_ctx) = task_notes.user_ctx() {
@@ -111,20 +111,28 @@ impl DirectFixedEscaper {
         let wrapper_stats = Arc::new(wrapper_stats);
 
         let (recv, send) = g3_io_ext::split_udp(socket);
-        let recv = LimitedUdpRecv::new(
+        let mut recv = LimitedUdpRecv::new(
             recv,
             self.config.general.udp_sock_speed_limit.shift_millis,
             self.config.general.udp_sock_speed_limit.max_south_packets,
             self.config.general.udp_sock_speed_limit.max_south_bytes,
             wrapper_stats.clone() as _,
         );
-        let send = LimitedUdpSend::new(
+        recv.set_burst_limit(
+            self.config.general.udp_sock_speed_limit.burst_south_packets,
+            self.config.general.udp_sock_speed_limit.burst_south_bytes,
+        );
+        let mut send = LimitedUdpSend::new(
             send,
             self.config.general.udp_sock_speed_limit.shift_millis,
             self.config.general.udp_sock_speed_limit.max_north_packets,
             self.config.general.udp_sock_speed_limit.max_north_bytes,
             wrapper_stats as _,
         );
+        send.set_burst_limit(
+            self.config.general.udp_sock_speed_limit.burst_north_packets,
+            self.config.general.udp_sock_speed_limit.burst_north_bytes,
+        );
 
         Ok((
             Box::new(DirectUdpConnectRemoteRecv::new(recv)),
@@ -53,6 +53,7 @@ impl DirectFixedEscaper {
             &self.egress_net_filter,
             &self.resolver_handle,
             self.config.resolve_strategy,
+            &self.resolve_pick_state,
         );
 
         if !self.config.no_ipv4 {
@@ -86,7 +87,7 @@ impl DirectFixedEscaper {
         ),
         UdpRelaySetupError,
     > {
-        let bind_ip = self.get_bind_random(family, &task_notes.egress_path_selection);
+        let bind_ip = self.get_bind_ip(family, task_notes, None);
 
         let misc_opts = if let Some(user_ctx) = task_notes.user_ctx() {
             user_ctx
@@ -105,20 +106,28 @@ impl DirectFixedEscaper {
         let socket = UdpSocket::from_std(socket).map_err(UdpRelaySetupError::SetupSocketFailed)?;
 
         let (recv, send) = g3_io_ext::split_udp(socket);
-        let recv = LimitedUdpRecv::new(
+        let mut recv = LimitedUdpRecv::new(
             recv,
             self.config.general.udp_sock_speed_limit.shift_millis,
             self.config.general.udp_sock_speed_limit.max_south_packets,
             self.config.general.udp_sock_speed_limit.max_south_bytes,
             stats.clone() as _,
         );
-        let send = LimitedUdpSend::new(
+        recv.set_burst_limit(
+            self.config.general.udp_sock_speed_limit.burst_south_packets,
+            self.config.general.udp_sock_speed_limit.burst_south_bytes,
+        );
+        let mut send = LimitedUdpSend::new(
             send,
             self.config.general.udp_sock_speed_limit.shift_millis,
             self.config.general.udp_sock_speed_limit.max_north_packets,
             self.config.general.udp_sock_speed_limit.max_north_bytes,
             stats.clone() as _,
         );
+        send.set_burst_limit(
+            self.config.general.udp_sock_speed_limit.burst_north_packets,
+            self.config.general.udp_sock_speed_limit.burst_north_bytes,
+        );
 
         Ok((bind_addr, recv, send))
     }
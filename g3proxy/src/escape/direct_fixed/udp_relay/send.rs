@@ -31,7 +31,7 @@ use g3_io_ext::{SendMsgHdr, UdpRelayPacket};
 use g3_resolver::{ResolveError, ResolveLocalError};
 use g3_types::acl::{AclAction, AclNetworkRule};
 use g3_types::net::{Host, UpstreamAddr};
-use g3_types::resolve::ResolveStrategy;
+use g3_types::resolve::{ResolvePickState, ResolveStrategy};
 
 use super::DirectFixedEscaperStats;
 use crate::auth::UserContext;
@@ -48,6 +48,7 @@ pub(crate) struct DirectUdpRelayRemoteSend<T> {
     checked_egress_ip: Option<IpAddr>,
     resolver_handle: ArcIntegratedResolverHandle,
     resolve_strategy: ResolveStrategy,
+    resolve_pick_state: Arc<ResolvePickState>,
     resolver_job: Option<ArriveFirstResolveJob>,
     resolve_retry_domain: Option<String>,
     resolved_port: u16,
@@ -61,6 +62,7 @@ impl<T> DirectUdpRelayRemoteSend<T> {
         egress_net_filter: &Arc<AclNetworkRule>,
         resolver_handle: &ArcIntegratedResolverHandle,
         resolve_strategy: ResolveStrategy,
+        resolve_pick_state: &Arc<ResolvePickState>,
     ) -> Self {
         DirectUdpRelayRemoteSend {
             escaper_stats: Arc::clone(escaper_stats),
@@ -73,6 +75,7 @@ impl<T> DirectUdpRelayRemoteSend<T> {
             checked_egress_ip: None,
             resolver_handle: Arc::clone(resolver_handle),
             resolve_strategy,
+            resolve_pick_state: Arc::clone(resolve_pick_state),
             resolver_job: None,
             resolve_retry_domain: None,
             resolved_port: 0,
@@ -136,6 +139,7 @@ where
                                     let resolver_job = ArriveFirstResolveJob::new(
                                         &self.resolver_handle,
                                         self.resolve_strategy,
+                                        &self.resolve_pick_state,
                                         &domain,
                                     )?;
                                     self.resolver_job = Some(resolver_job);
@@ -168,6 +172,7 @@ where
                 let resolver_job = ArriveFirstResolveJob::new(
                     &self.resolver_handle,
                     self.resolve_strategy,
+                    &self.resolve_pick_state,
                     domain,
                 )?;
                 self.resolver_job = Some(resolver_job);
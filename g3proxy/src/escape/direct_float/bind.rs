@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use std::hash::{Hash, Hasher};
 use std::net::IpAddr;
 
 use ahash::AHashMap;
@@ -24,8 +25,13 @@ use serde_json::Value;
 use tokio::time::Instant;
 
 use g3_socket::util::AddressFamily;
+use g3_types::collection::{
+    SelectiveHash, SelectiveItem, SelectivePickPolicy, SelectiveVec, SelectiveVecBuilder,
+};
 use g3_types::net::EgressInfo;
 
+use crate::escape::direct_fixed::{pick_bind, BindHashKey};
+
 const CONFIG_KEY_IP: &str = "ip";
 
 #[derive(Clone, Debug)]
@@ -70,6 +76,30 @@ impl DirectFloatBindIp {
         }
     }
 
+    fn to_json(&self, id: Option<&str>) -> Value {
+        let mut map = serde_json::Map::new();
+        map.insert(
+            CONFIG_KEY_IP.to_string(),
+            Value::String(self.ip.to_string()),
+        );
+        if let Some(id) = id {
+            map.insert("id".to_string(), Value::String(id.to_string()));
+        }
+        if let Some(expire) = &self.expire_datetime {
+            map.insert("expire".to_string(), Value::String(expire.to_rfc3339()));
+        }
+        if let Some(isp) = &self.egress_info.isp {
+            map.insert("isp".to_string(), Value::String(isp.to_string()));
+        }
+        if let Some(ip) = &self.egress_info.ip {
+            map.insert("eip".to_string(), Value::String(ip.to_string()));
+        }
+        if let Some(area) = &self.egress_info.area {
+            map.insert("area".to_string(), Value::String(area.to_string()));
+        }
+        Value::Object(map)
+    }
+
     fn parse_json(
         value: &Value,
         instant_now: Instant,
@@ -137,6 +167,18 @@ impl DirectFloatBindIp {
     }
 }
 
+impl SelectiveItem for DirectFloatBindIp {
+    fn weight(&self) -> f64 {
+        1.0
+    }
+}
+
+impl SelectiveHash for DirectFloatBindIp {
+    fn selective_hash<H: Hasher>(&self, state: &mut H) {
+        self.ip.hash(state);
+    }
+}
+
 pub(super) fn parse_records(records: &[Value], family: AddressFamily) -> anyhow::Result<BindSet> {
     let mut bind_set = BindSet::default();
 
@@ -162,6 +204,7 @@ pub(super) fn parse_records(records: &[Value], family: AddressFamily) -> anyhow:
         }
     }
 
+    bind_set.build_selective();
     Ok(bind_set)
 }
 
@@ -187,6 +230,7 @@ pub(super) fn parse_record(
 pub(super) struct BindSet {
     unnamed: Vec<DirectFloatBindIp>,
     named: AHashMap<String, DirectFloatBindIp>,
+    selective: Option<SelectiveVec<DirectFloatBindIp>>,
 }
 
 impl BindSet {
@@ -208,6 +252,24 @@ impl BindSet {
             .cloned()
     }
 
+    fn build_selective(&mut self) {
+        let mut builder = SelectiveVecBuilder::with_capacity(self.unnamed.len() + self.named.len());
+        for bind in self.unnamed.iter().chain(self.named.values()) {
+            builder.insert(bind.clone());
+        }
+        self.selective = builder.build();
+    }
+
+    pub(super) fn select_bind(
+        &self,
+        pick_policy: SelectivePickPolicy,
+        key: &BindHashKey,
+    ) -> Option<DirectFloatBindIp> {
+        let nodes = self.selective.as_ref()?;
+        let bind = pick_bind(nodes, pick_policy, key);
+        Some(bind.clone())
+    }
+
     pub(super) fn select_again(&self, ip: IpAddr) -> Option<DirectFloatBindIp> {
         self.unnamed
             .iter()
@@ -230,4 +292,122 @@ impl BindSet {
     pub(super) fn select_named_bind(&self, id: &str) -> Option<DirectFloatBindIp> {
         self.named.get(id).cloned()
     }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.unnamed.is_empty() && self.named.is_empty()
+    }
+
+    /// convert back to the publish records, the expired ones will be skipped
+    pub(super) fn to_records(&self) -> Vec<Value> {
+        let unnamed = self
+            .unnamed
+            .iter()
+            .filter(|v| !v.is_expired())
+            .map(|v| v.to_json(None));
+        let named = self
+            .named
+            .iter()
+            .filter(|(_, v)| !v.is_expired())
+            .map(|(id, v)| v.to_json(Some(id)));
+        unnamed.chain(named).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn bind_set(ips: &[&str]) -> BindSet {
+        let records: Vec<Value> = ips
+            .iter()
+            .enumerate()
+            .map(|(i, ip)| {
+                if i % 2 == 0 {
+                    Value::String(ip.to_string())
+                } else {
+                    serde_json::json!({"ip": ip, "id": format!("bind-{i}")})
+                }
+            })
+            .collect();
+        parse_records(&records, AddressFamily::Ipv4).unwrap()
+    }
+
+    #[test]
+    fn select_empty() {
+        let set = bind_set(&[]);
+        let key = BindHashKey::ClientIp(IpAddr::from([10, 0, 0, 1]));
+        assert!(set.select_bind(SelectivePickPolicy::Random, &key).is_none());
+        assert!(set
+            .select_bind(SelectivePickPolicy::Rendezvous, &key)
+            .is_none());
+    }
+
+    #[test]
+    fn select_round_robin() {
+        let set = bind_set(&["192.168.0.1", "192.168.0.2", "192.168.0.3"]);
+        let key = BindHashKey::ClientIp(IpAddr::from([10, 0, 0, 1]));
+        let picked: Vec<IpAddr> = (0..6)
+            .map(|_| {
+                set.select_bind(SelectivePickPolicy::RoundRobin, &key)
+                    .unwrap()
+                    .ip
+            })
+            .collect();
+        assert_eq!(picked[..3], picked[3..]);
+        assert_eq!(picked[..3].iter().collect::<HashSet<_>>().len(), 3);
+    }
+
+    #[test]
+    fn select_rendezvous() {
+        let ips = ["192.168.0.1", "192.168.0.2", "192.168.0.3", "192.168.0.4"];
+        let set = bind_set(&ips);
+        let host = "www.example.com".parse().unwrap();
+
+        let mut picked = HashSet::new();
+        for i in 0..64 {
+            let key = BindHashKey::ClientIp(IpAddr::from([10, 0, 0, i]));
+            let bind = set
+                .select_bind(SelectivePickPolicy::Rendezvous, &key)
+                .unwrap();
+            let again = set
+                .select_bind(SelectivePickPolicy::Rendezvous, &key)
+                .unwrap();
+            assert_eq!(bind.ip, again.ip);
+            picked.insert(bind.ip);
+
+            // still the same if other binds are removed
+            let left: Vec<&str> = ips
+                .iter()
+                .copied()
+                .filter(|ip| ip.parse::<IpAddr>().unwrap() == bind.ip || ip.ends_with(".1"))
+                .collect();
+            let bind2 = bind_set(&left)
+                .select_bind(SelectivePickPolicy::Rendezvous, &key)
+                .unwrap();
+            assert_eq!(bind.ip, bind2.ip);
+        }
+        assert!(picked.len() > 1);
+
+        let key = BindHashKey::UpstreamHost(&host);
+        let bind = set
+            .select_bind(SelectivePickPolicy::Rendezvous, &key)
+            .unwrap();
+        for _ in 0..8 {
+            let again = set
+                .select_bind(SelectivePickPolicy::Rendezvous, &key)
+                .unwrap();
+            assert_eq!(bind.ip, again.ip);
+        }
+    }
+
+    #[test]
+    fn skip_other_family() {
+        let set = bind_set(&["192.168.0.1", "2001:db8::1"]);
+        let key = BindHashKey::ClientIp(IpAddr::from([10, 0, 0, 1]));
+        for _ in 0..4 {
+            let bind = set.select_bind(SelectivePickPolicy::Random, &key).unwrap();
+            assert_eq!(bind.ip, IpAddr::from([192, 168, 0, 1]));
+        }
+    }
 }
@@ -52,19 +52,23 @@ impl DirectFloatEscaper {
         r_wrapper_stats.push_user_io_stats(user_stats);
 
         let limit_config = &self.config.general.tcp_sock_speed_limit;
-        let ups_r = LimitedBufReader::new(
+        let mut ups_r = LimitedBufReader::new(
             ups_r,
             limit_config.shift_millis,
             limit_config.max_south,
             self.stats.clone() as _,
             Arc::new(r_wrapper_stats) as _,
         );
-        let ups_w = LimitedWriter::new(
+        ups_r.set_burst_limit(limit_config.burst_south);
+        self.tcp_global_limiter.apply_to_buf_reader(&mut ups_r);
+        let mut ups_w = LimitedWriter::new(
             ups_w,
             limit_config.shift_millis,
             limit_config.max_north,
             Arc::new(w_wrapper_stats) as _,
         );
+        ups_w.set_burst_limit(limit_config.burst_north);
+        self.tcp_global_limiter.apply_to_writer(&mut ups_w);
 
         let writer = DirectFloatHttpForwardWriter::new(ups_w, Some(Arc::clone(&self.stats)), bind);
         let reader = DirectFloatHttpForwardReader::new(ups_r);
@@ -33,15 +33,16 @@ use g3_socket::util::AddressFamily;
 use g3_types::acl::AclNetworkRule;
 use g3_types::metrics::MetricsName;
 use g3_types::net::{Host, OpensslClientConfig, UpstreamAddr};
-use g3_types::resolve::{ResolveRedirection, ResolveStrategy};
+use g3_types::resolve::{ResolvePickState, ResolveRedirection, ResolveStrategy};
 
 use super::{
     ArcEscaper, ArcEscaperInternalStats, ArcEscaperStats, Escaper, EscaperInternal, EscaperStats,
+    GlobalTcpLimiter,
 };
 use crate::auth::UserUpstreamTrafficStats;
 use crate::config::escaper::direct_float::DirectFloatEscaperConfig;
 use crate::config::escaper::{AnyEscaperConfig, EscaperConfig};
-use crate::escape::direct_fixed::DirectFixedEscaperStats;
+use crate::escape::direct_fixed::{BindHashKey, DirectFixedEscaperStats};
 use crate::module::ftp_over_http::{
     AnyFtpConnectContextParam, ArcFtpTaskRemoteControlStats, ArcFtpTaskRemoteTransferStats,
     BoxFtpConnectContext, BoxFtpRemoteConnection, DirectFtpConnectContext,
@@ -77,10 +78,12 @@ pub(super) struct DirectFloatEscaper {
     config: Arc<DirectFloatEscaperConfig>,
     stats: Arc<DirectFixedEscaperStats>,
     resolver_handle: ArcIntegratedResolverHandle,
+    resolve_pick_state: Arc<ResolvePickState>,
     egress_net_filter: Arc<AclNetworkRule>,
     resolve_redirection: Option<ResolveRedirection>,
     bind_v4: ArcSwap<BindSet>,
     bind_v6: ArcSwap<BindSet>,
+    tcp_global_limiter: GlobalTcpLimiter,
     escape_logger: Logger,
 }
 
@@ -90,6 +93,7 @@ impl DirectFloatEscaper {
         stats: Arc<DirectFixedEscaperStats>,
         bind_v4: Option<Arc<BindSet>>,
         bind_v6: Option<Arc<BindSet>>,
+        tcp_global_limiter: Option<&GlobalTcpLimiter>,
     ) -> anyhow::Result<ArcEscaper> {
         let resolver_handle = crate::resolve::get_handle(config.resolver())?;
         let egress_net_filter = Arc::new(config.egress_net_filter.build());
@@ -99,6 +103,10 @@ impl DirectFloatEscaper {
             .as_ref()
             .map(|builder| builder.build());
 
+        let tcp_global_limiter = match tcp_global_limiter {
+            Some(limiter) => limiter.reload(&config.general),
+            None => GlobalTcpLimiter::new(&config.general),
+        };
         let escape_logger = config.get_escape_logger();
 
         let config = Arc::new(config);
@@ -135,15 +143,20 @@ impl DirectFloatEscaper {
         };
 
         stats.set_extra_tags(config.extra_metrics_tags.clone());
+        stats
+            .tcp
+            .set_connect_duration_config(config.general.tcp_connect_duration_stats.as_ref());
 
         let escaper = DirectFloatEscaper {
             config,
             stats,
             resolver_handle,
+            resolve_pick_state: Arc::new(ResolvePickState::default()),
             egress_net_filter,
             resolve_redirection,
             bind_v4: ArcSwap::new(bind_v4),
             bind_v6: ArcSwap::new(bind_v6),
+            tcp_global_limiter,
             escape_logger,
         };
 
@@ -154,7 +167,7 @@ impl DirectFloatEscaper {
         config: DirectFloatEscaperConfig,
     ) -> anyhow::Result<ArcEscaper> {
         let stats = Arc::new(DirectFixedEscaperStats::new(config.name()));
-        DirectFloatEscaper::new_obj(config, stats, None, None).await
+        DirectFloatEscaper::new_obj(config, stats, None, None, None).await
     }
 
     async fn prepare_reload(
@@ -162,9 +175,11 @@ impl DirectFloatEscaper {
         stats: Arc<DirectFixedEscaperStats>,
         bind_v4: Option<Arc<BindSet>>,
         bind_v6: Option<Arc<BindSet>>,
+        tcp_global_limiter: &GlobalTcpLimiter,
     ) -> anyhow::Result<ArcEscaper> {
         if let AnyEscaperConfig::DirectFloat(config) = config {
-            DirectFloatEscaper::new_obj(*config, stats, bind_v4, bind_v6).await
+            DirectFloatEscaper::new_obj(*config, stats, bind_v4, bind_v6, Some(tcp_global_limiter))
+                .await
         } else {
             Err(anyhow!("invalid escaper config type"))
         }
@@ -233,13 +248,23 @@ impl DirectFloatEscaper {
         }
     }
 
-    fn select_bind_from_escaper(&self, family: AddressFamily) -> anyhow::Result<DirectFloatBindIp> {
+    fn select_bind_from_escaper(
+        &self,
+        family: AddressFamily,
+        task_notes: &ServerTaskNotes,
+        ups_host: Option<&Host>,
+    ) -> anyhow::Result<DirectFloatBindIp> {
         let bind_set = match family {
             AddressFamily::Ipv4 => self.bind_v4.load(),
             AddressFamily::Ipv6 => self.bind_v6.load(),
         };
+        let key = BindHashKey::new(
+            self.config.bind_ip_hash_key,
+            task_notes.client_ip(),
+            ups_host,
+        );
         bind_set
-            .select_random_bind()
+            .select_bind(self.config.bind_ip_pick_policy, &key)
             .ok_or_else(|| anyhow!("no {family} bind IP available at escaper level"))
     }
 
@@ -296,10 +321,18 @@ impl DirectFloatEscaper {
         }
     }
 
+    fn nat64_synthesize(&self, ip: IpAddr) -> IpAddr {
+        match (ip, &self.config.nat64_prefix) {
+            (IpAddr::V4(ip4), Some(prefix)) => IpAddr::V6(prefix.synthesize(ip4)),
+            _ => ip,
+        }
+    }
+
     fn select_bind(
         &self,
         family: AddressFamily,
         task_notes: &ServerTaskNotes,
+        ups_host: Option<&Host>,
     ) -> anyhow::Result<DirectFloatBindIp> {
         if let Some(v) = task_notes
             .egress_path_selection
@@ -307,7 +340,7 @@ impl DirectFloatEscaper {
         {
             self.select_bind_from_egress_path(family, v)
         } else {
-            self.select_bind_from_escaper(family)
+            self.select_bind_from_escaper(family, task_notes, ups_host)
         }
     }
 
@@ -323,18 +356,41 @@ impl DirectFloatEscaper {
         }
     }
 
+    fn get_resolver_handle(
+        &self,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<ArcIntegratedResolverHandle, ResolveError> {
+        match task_notes.user_ctx().and_then(|ctx| ctx.site_resolver()) {
+            Some(name) => crate::resolve::get_handle(name)
+                .map_err(|e| ResolveError::UnexpectedError(format!("user site resolver: {e}"))),
+            None => Ok(self.resolver_handle.clone()),
+        }
+    }
+
     fn resolve_happy(
         &self,
         domain: &str,
         strategy: ResolveStrategy,
         task_notes: &ServerTaskNotes,
     ) -> Result<HappyEyeballsResolveJob, ResolveError> {
+        let resolver_handle = self.get_resolver_handle(task_notes)?;
+
         if let Some(user_ctx) = task_notes.user_ctx() {
+            if let Some(v) = user_ctx.site_resolve_static() {
+                return HappyEyeballsResolveJob::new_redirected(
+                    strategy,
+                    &self.resolve_pick_state,
+                    &resolver_handle,
+                    v,
+                );
+            }
+
             if let Some(redirect) = user_ctx.user().resolve_redirection() {
                 if let Some(v) = redirect.query_value(domain) {
                     return HappyEyeballsResolveJob::new_redirected(
                         strategy,
-                        &self.resolver_handle,
+                        &self.resolve_pick_state,
+                        &resolver_handle,
                         v,
                     );
                 }
@@ -343,24 +399,47 @@ impl DirectFloatEscaper {
 
         if let Some(redirect) = &self.resolve_redirection {
             if let Some(v) = redirect.query_value(domain) {
-                return HappyEyeballsResolveJob::new_redirected(strategy, &self.resolver_handle, v);
+                return HappyEyeballsResolveJob::new_redirected(
+                    strategy,
+                    &self.resolve_pick_state,
+                    &resolver_handle,
+                    v,
+                );
             }
         }
 
-        HappyEyeballsResolveJob::new_dyn(strategy, &self.resolver_handle, domain)
+        HappyEyeballsResolveJob::new_dyn_for_client(
+            strategy,
+            &self.resolve_pick_state,
+            &resolver_handle,
+            domain,
+            Some(task_notes.client_ip()),
+        )
     }
 
     async fn resolve_best(
         &self,
         domain: &str,
         strategy: ResolveStrategy,
+        resolver_handle: &ArcIntegratedResolverHandle,
+    ) -> Result<IpAddr, ResolveError> {
+        let resolver_job = HappyEyeballsResolveJob::new_dyn(
+            strategy,
+            &self.resolve_pick_state,
+            resolver_handle,
+            domain,
+        )?;
+        self.resolve_job_get_best(resolver_job).await
+    }
+
+    async fn resolve_job_get_best(
+        &self,
+        mut resolver_job: HappyEyeballsResolveJob,
     ) -> Result<IpAddr, ResolveError> {
-        let mut resolver_job =
-            HappyEyeballsResolveJob::new_dyn(strategy, &self.resolver_handle, domain)?;
         let ips = resolver_job
             .get_r1_or_first(self.config.happy_eyeballs.resolution_delay(), usize::MAX)
             .await?;
-        strategy.pick_best(ips).ok_or_else(|| {
+        resolver_job.pick_best(ips).ok_or_else(|| {
             ResolveError::UnexpectedError("no upstream ip can be selected".to_string())
         })
     }
@@ -369,10 +448,14 @@ impl DirectFloatEscaper {
         &self,
         redirect_result: Host,
         resolve_strategy: ResolveStrategy,
+        resolver_handle: &ArcIntegratedResolverHandle,
     ) -> Result<IpAddr, ResolveError> {
         match redirect_result {
             Host::Ip(ip) => Ok(ip),
-            Host::Domain(new) => self.resolve_best(&new, resolve_strategy).await,
+            Host::Domain(new) => {
+                self.resolve_best(&new, resolve_strategy, resolver_handle)
+                    .await
+            }
         }
     }
 
@@ -385,11 +468,26 @@ impl DirectFloatEscaper {
         match ups.host() {
             Host::Ip(ip) => Ok(SocketAddr::new(*ip, ups.port())),
             Host::Domain(domain) => {
+                let resolver_handle = self.get_resolver_handle(task_notes)?;
+
                 if let Some(user_ctx) = task_notes.user_ctx() {
+                    if let Some(v) = user_ctx.site_resolve_static() {
+                        let resolver_job = HappyEyeballsResolveJob::new_redirected(
+                            resolve_strategy,
+                            &self.resolve_pick_state,
+                            &resolver_handle,
+                            v,
+                        )?;
+                        return self
+                            .resolve_job_get_best(resolver_job)
+                            .await
+                            .map(|ip| SocketAddr::new(ip, ups.port()));
+                    }
+
                     if let Some(redirect) = user_ctx.user().resolve_redirection() {
                         if let Some(v) = redirect.query_first(domain, resolve_strategy.query) {
                             return self
-                                .redirect_get_best(v, resolve_strategy)
+                                .redirect_get_best(v, resolve_strategy, &resolver_handle)
                                 .await
                                 .map(|ip| SocketAddr::new(ip, ups.port()));
                         }
@@ -399,13 +497,15 @@ impl DirectFloatEscaper {
                 if let Some(redirect) = &self.resolve_redirection {
                     if let Some(v) = redirect.query_first(domain, resolve_strategy.query) {
                         return self
-                            .redirect_get_best(v, resolve_strategy)
+                            .redirect_get_best(v, resolve_strategy, &resolver_handle)
                             .await
                             .map(|ip| SocketAddr::new(ip, ups.port()));
                     }
                 }
 
-                let ip = self.resolve_best(domain, resolve_strategy).await?;
+                let ip = self
+                    .resolve_best(domain, resolve_strategy, &resolver_handle)
+                    .await?;
                 Ok(SocketAddr::new(ip, ups.port()))
             }
         }
@@ -440,6 +540,10 @@ impl Escaper for DirectFloatEscaper {
         publish::publish_records(&self.config, &self.bind_v4, &self.bind_v6, data).await
     }
 
+    fn published_data(&self) -> Option<String> {
+        publish::published_data(&self.bind_v4, &self.bind_v6)
+    }
+
     async fn tcp_setup_connection<'a>(
         &'a self,
         tcp_notes: &'a mut TcpConnectTaskNotes,
@@ -535,7 +639,14 @@ impl EscaperInternal for DirectFloatEscaper {
         let bind_v4 = self.bind_v4.load_full();
         let bind_v6 = self.bind_v6.load_full();
 
-        DirectFloatEscaper::prepare_reload(config, stats, Some(bind_v4), Some(bind_v6)).await
+        DirectFloatEscaper::prepare_reload(
+            config,
+            stats,
+            Some(bind_v4),
+            Some(bind_v6),
+            &self.tcp_global_limiter,
+        )
+        .await
     }
 
     async fn _check_out_next_escaper(
@@ -139,3 +139,19 @@ pub(super) async fn publish_records(
         Err(anyhow!("the input data should be json map"))
     }
 }
+
+pub(super) fn published_data(
+    v4_container: &ArcSwap<BindSet>,
+    v6_container: &ArcSwap<BindSet>,
+) -> Option<String> {
+    let bind_v4 = v4_container.load();
+    let bind_v6 = v6_container.load();
+    if bind_v4.is_empty() && bind_v6.is_empty() {
+        return None;
+    }
+
+    let mut map = serde_json::Map::new();
+    map.insert("ipv4".to_string(), Value::Array(bind_v4.to_records()));
+    map.insert("ipv6".to_string(), Value::Array(bind_v6.to_records()));
+    Some(Value::Object(map).to_string())
+}
@@ -16,6 +16,7 @@
 
 use std::io;
 use std::net::{IpAddr, SocketAddr};
+use std::os::fd::AsRawFd;
 use std::sync::Arc;
 
 use tokio::net::{TcpSocket, TcpStream};
@@ -26,7 +27,9 @@ use g3_daemon::stat::remote::ArcTcpConnectionTaskRemoteStats;
 use g3_io_ext::{LimitedReader, LimitedWriter};
 use g3_socket::util::AddressFamily;
 use g3_types::acl::AclAction;
-use g3_types::net::{ConnectError, Host, TcpConnectConfig, TcpKeepAliveConfig, TcpMiscSockOpts};
+use g3_types::net::{
+    ConnectError, Host, Nat64Prefix, TcpConnectConfig, TcpKeepAliveConfig, TcpMiscSockOpts,
+};
 
 use super::{DirectFloatBindIp, DirectFloatEscaper};
 use crate::log::escape::tcp_connect::EscapeLogForTcpConnect;
@@ -68,12 +71,19 @@ impl DirectFloatEscaper {
     fn prepare_connect_socket(
         &self,
         peer_ip: IpAddr,
+        nat64: bool,
         bind_ip: Option<IpAddr>,
+        ups_host: &Host,
         task_notes: &ServerTaskNotes,
         keepalive: &TcpKeepAliveConfig,
         misc_opts: &TcpMiscSockOpts,
-    ) -> Result<(TcpSocket, DirectFloatBindIp), TcpConnectError> {
-        match peer_ip {
+    ) -> Result<(TcpSocket, DirectFloatBindIp, IpAddr), TcpConnectError> {
+        let connect_ip = if nat64 {
+            self.nat64_synthesize(peer_ip)
+        } else {
+            peer_ip
+        };
+        match connect_ip {
             IpAddr::V4(_) => {
                 if self.config.no_ipv4 {
                     return Err(TcpConnectError::ForbiddenAddressFamily);
@@ -93,14 +103,20 @@ impl DirectFloatEscaper {
             self.select_bind_again(ip, task_notes)
                 .map_err(TcpConnectError::EscaperNotUsable)?
         } else {
-            self.select_bind(AddressFamily::from(&peer_ip), task_notes)
+            self.select_bind(AddressFamily::from(&connect_ip), task_notes, Some(ups_host))
                 .map_err(TcpConnectError::EscaperNotUsable)?
         };
 
-        let sock =
-            g3_socket::tcp::new_socket_to(peer_ip, Some(bind.ip), keepalive, misc_opts, true)
-                .map_err(TcpConnectError::SetupSocketFailed)?;
-        Ok((sock, bind))
+        let sock = g3_socket::tcp::new_socket_to_with_bind(
+            connect_ip,
+            Some(bind.ip),
+            &self.config.tcp_bind,
+            keepalive,
+            misc_opts,
+            true,
+        )
+        .map_err(TcpConnectError::SetupSocketFailed)?;
+        Ok((sock, bind, connect_ip))
     }
 
     async fn fixed_try_connect(
@@ -112,14 +128,16 @@ impl DirectFloatEscaper {
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
     ) -> Result<(TcpStream, DirectFloatBindIp), TcpConnectError> {
-        let (sock, bind) = self.prepare_connect_socket(
+        let (sock, bind, connect_ip) = self.prepare_connect_socket(
             peer_ip,
+            true,
             tcp_notes.bind,
+            tcp_notes.upstream.host(),
             task_notes,
             &keepalive,
             &tcp_misc_opts,
         )?;
-        let peer = SocketAddr::new(peer_ip, tcp_notes.upstream.port());
+        let peer = SocketAddr::new(connect_ip, tcp_notes.upstream.port());
         tcp_notes.next = Some(peer);
         tcp_notes.bind = Some(bind.ip);
         tcp_notes.expire = bind.expire_datetime;
@@ -134,10 +152,14 @@ impl DirectFloatEscaper {
                 tcp_notes.duration = instant_now.elapsed();
 
                 self.stats.tcp.add_connection_established();
+                self.stats.tcp.record_connect_duration(tcp_notes.duration);
                 let local_addr = ups_stream
                     .local_addr()
                     .map_err(TcpConnectError::SetupSocketFailed)?;
                 tcp_notes.local = Some(local_addr);
+                if tcp_misc_opts.max_segment_size.is_some() {
+                    tcp_notes.mss = g3_socket::tcp::get_raw_mss(ups_stream.as_raw_fd()).ok();
+                }
                 tcp_notes.chained.target_addr = Some(peer);
                 tcp_notes.chained.outgoing_addr = Some(local_addr);
                 Ok((ups_stream, bind))
@@ -181,12 +203,25 @@ impl DirectFloatEscaper {
         task_notes: &ServerTaskNotes,
     ) -> Result<(TcpStream, DirectFloatBindIp), TcpConnectError> {
         let max_tries_each_family = tcp_connect_config.max_tries();
+        let resolve_start = Instant::now();
         let mut ips = resolver_job
             .get_r1_or_first(
                 self.config.happy_eyeballs.resolution_delay(),
                 max_tries_each_family,
             )
             .await?;
+        let mut resolver_r2_done = false;
+        let nat64 = if self.config.nat64_prefix.is_some() {
+            // all results are needed to decide whether to synthesize
+            if let Ok(ips2) = resolver_job.get_r2_or_never(max_tries_each_family).await {
+                self.merge_ip_list(0, &mut ips, ips2);
+            }
+            resolver_r2_done = true;
+            Nat64Prefix::prepare_resolved(&mut ips)
+        } else {
+            false
+        };
+        task_notes.trace_span("resolve", resolve_start);
         let port = tcp_notes.upstream.port();
 
         let mut c_set = JoinSet::new();
@@ -199,8 +234,8 @@ impl DirectFloatEscaper {
 
         let mut spawn_new_connection = true;
         let mut running_connection = 0;
-        let mut resolver_r2_done = false;
         let each_timeout = tcp_connect_config.each_timeout();
+        let rtt_state = resolver_job.connect_rtt_state();
 
         tcp_notes.tries = 0;
         let instant_now = Instant::now();
@@ -209,20 +244,32 @@ impl DirectFloatEscaper {
         loop {
             if spawn_new_connection {
                 if let Some(ip) = ips.pop() {
-                    let (sock, bind) = self.prepare_connect_socket(
+                    let (sock, bind, connect_ip) = self.prepare_connect_socket(
                         ip,
+                        nat64,
                         tcp_notes.bind,
+                        tcp_notes.upstream.host(),
                         task_notes,
                         &keepalive,
                         &tcp_misc_opts,
                     )?;
-                    let peer = SocketAddr::new(ip, port);
+                    let peer = SocketAddr::new(connect_ip, port);
                     running_connection += 1;
                     spawn_new_connection = false;
                     tcp_notes.tries += 1;
                     self.stats.tcp.add_connection_attempted();
+                    let rtt_state = rtt_state.clone();
                     c_set.spawn(async move {
-                        match tokio::time::timeout(each_timeout, sock.connect(peer)).await {
+                        let connect_start = Instant::now();
+                        let r = tokio::time::timeout(each_timeout, sock.connect(peer)).await;
+                        if let Some(state) = rtt_state {
+                            let rtt = match &r {
+                                Ok(Ok(_)) => connect_start.elapsed(),
+                                _ => each_timeout,
+                            };
+                            state.record_connect_rtt(ip, rtt);
+                        }
+                        match r {
                             Ok(Ok(stream)) => (Ok(stream), peer, bind),
                             Ok(Err(e)) => (
                                 Err(TcpConnectError::ConnectFailed(ConnectError::from(e))),
@@ -254,10 +301,15 @@ impl DirectFloatEscaper {
                                 match r.0 {
                                     Ok(ups_stream) => {
                                         self.stats.tcp.add_connection_established();
+                                        self.stats.tcp.record_connect_duration(tcp_notes.duration);
                                         let local_addr = ups_stream
                                             .local_addr()
                                             .map_err(TcpConnectError::SetupSocketFailed)?;
                                         tcp_notes.local = Some(local_addr);
+                                        if tcp_misc_opts.max_segment_size.is_some() {
+                                            let raw_fd = ups_stream.as_raw_fd();
+                                            tcp_notes.mss = g3_socket::tcp::get_raw_mss(raw_fd).ok();
+                                        }
                                         tcp_notes.chained.target_addr = Some(peer_addr);
                                         tcp_notes.chained.outgoing_addr = Some(local_addr);
                                         return Ok((ups_stream, bind));
@@ -330,6 +382,18 @@ impl DirectFloatEscaper {
         &'a self,
         tcp_notes: &'a mut TcpConnectTaskNotes,
         task_notes: &'a ServerTaskNotes,
+    ) -> Result<(TcpStream, DirectFloatBindIp), TcpConnectError> {
+        let r = self.do_tcp_connect_to(tcp_notes, task_notes).await;
+        if let Err(e) = &r {
+            self.stats.tcp.add_connect_error(e);
+        }
+        r
+    }
+
+    async fn do_tcp_connect_to<'a>(
+        &'a self,
+        tcp_notes: &'a mut TcpConnectTaskNotes,
+        task_notes: &'a ServerTaskNotes,
     ) -> Result<(TcpStream, DirectFloatBindIp), TcpConnectError> {
         let mut tcp_connect_config = self.config.general.tcp_connect;
 
@@ -384,6 +448,21 @@ impl DirectFloatEscaper {
         new_tcp_notes: &'a mut TcpConnectTaskNotes,
         old_tcp_notes: &'a TcpConnectTaskNotes,
         task_notes: &'a ServerTaskNotes,
+    ) -> Result<(TcpStream, DirectFloatBindIp), TcpConnectError> {
+        let r = self
+            .do_tcp_connect_to_again(new_tcp_notes, old_tcp_notes, task_notes)
+            .await;
+        if let Err(e) = &r {
+            self.stats.tcp.add_connect_error(e);
+        }
+        r
+    }
+
+    async fn do_tcp_connect_to_again<'a>(
+        &'a self,
+        new_tcp_notes: &'a mut TcpConnectTaskNotes,
+        old_tcp_notes: &'a TcpConnectTaskNotes,
+        task_notes: &'a ServerTaskNotes,
     ) -> Result<(TcpStream, DirectFloatBindIp), TcpConnectError> {
         new_tcp_notes.bind = old_tcp_notes.bind;
 
@@ -438,7 +517,11 @@ impl DirectFloatEscaper {
                     let mut resolve_strategy = self.get_resolve_strategy(task_notes);
                     match new_tcp_notes.bind {
                         Some(IpAddr::V4(_)) => resolve_strategy.query_v4only(),
-                        Some(IpAddr::V6(_)) => resolve_strategy.query_v6only(),
+                        // A records are still needed if nat64 is enabled
+                        Some(IpAddr::V6(_)) if self.config.nat64_prefix.is_none() => {
+                            resolve_strategy.query_v6only()
+                        }
+                        Some(IpAddr::V6(_)) => {}
                         None => {}
                     }
 
@@ -471,18 +554,22 @@ impl DirectFloatEscaper {
         let wrapper_stats = Arc::new(wrapper_stats);
 
         let limit_config = &self.config.general.tcp_sock_speed_limit;
-        let r = LimitedReader::new(
+        let mut r = LimitedReader::new(
             r,
             limit_config.shift_millis,
             limit_config.max_south,
             wrapper_stats.clone() as _,
         );
-        let w = LimitedWriter::new(
+        r.set_burst_limit(limit_config.burst_south);
+        self.tcp_global_limiter.apply_to_reader(&mut r);
+        let mut w = LimitedWriter::new(
             w,
             limit_config.shift_millis,
             limit_config.max_north,
             wrapper_stats as _,
         );
+        w.set_burst_limit(limit_config.burst_north);
+        self.tcp_global_limiter.apply_to_writer(&mut w);
 
         Ok((Box::new(r), Box::new(w)))
     }
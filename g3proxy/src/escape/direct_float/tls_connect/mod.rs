@@ -53,18 +53,22 @@ impl DirectFloatEscaper {
 
         // set limit config and add escaper stats, do not count in task stats
         let limit_config = &self.config.general.tcp_sock_speed_limit;
-        let ups_r = LimitedReader::new(
+        let mut ups_r = LimitedReader::new(
             ups_r,
             limit_config.shift_millis,
             limit_config.max_south,
             self.stats.clone() as _,
         );
-        let ups_w = LimitedWriter::new(
+        ups_r.set_burst_limit(limit_config.burst_south);
+        self.tcp_global_limiter.apply_to_reader(&mut ups_r);
+        let mut ups_w = LimitedWriter::new(
             ups_w,
             limit_config.shift_millis,
             limit_config.max_north,
             self.stats.clone() as _,
         );
+        ups_w.set_burst_limit(limit_config.burst_north);
+        self.tcp_global_limiter.apply_to_writer(&mut ups_w);
 
         let ssl = tls_config
             .build_ssl(tls_name, tcp_notes.upstream.port())
@@ -90,7 +94,9 @@ impl DirectFloatEscaper {
                     tls_application,
                 }
                 .log(&self.escape_logger, &e);
-                Err(TcpConnectError::UpstreamTlsHandshakeFailed(e))
+                let e = TcpConnectError::UpstreamTlsHandshakeFailed(e);
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
             }
             Err(_) => {
                 let e = anyhow!("upstream tls handshake timed out");
@@ -102,7 +108,9 @@ impl DirectFloatEscaper {
                     tls_application,
                 }
                 .log(&self.escape_logger, &e);
-                Err(TcpConnectError::UpstreamTlsHandshakeTimeout)
+                let e = TcpConnectError::UpstreamTlsHandshakeTimeout;
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
             }
         }
     }
@@ -81,7 +81,7 @@ impl DirectFloatEscaper {
 
         let family = AddressFamily::from(&peer_addr);
         let bind = self
-            .select_bind(family, task_notes)
+            .select_bind(family, task_notes, Some(upstream.host()))
             .map_err(UdpConnectError::EscaperNotUsable)?;
         udp_notes.bind = Some(bind.ip);
 
@@ -114,20 +114,28 @@ impl DirectFloatEscaper {
         let wrapper_stats = Arc::new(wrapper_stats);
 
         let (recv, send) = g3_io_ext::split_udp(socket);
-        let recv = LimitedUdpRecv::new(
+        let mut recv = LimitedUdpRecv::new(
             recv,
             self.config.general.udp_sock_speed_limit.shift_millis,
             self.config.general.udp_sock_speed_limit.max_south_packets,
             self.config.general.udp_sock_speed_limit.max_south_bytes,
             wrapper_stats.clone() as _,
         );
-        let send = LimitedUdpSend::new(
+        recv.set_burst_limit(
+            self.config.general.udp_sock_speed_limit.burst_south_packets,
+            self.config.general.udp_sock_speed_limit.burst_south_bytes,
+        );
+        let mut send = LimitedUdpSend::new(
             send,
             self.config.general.udp_sock_speed_limit.shift_millis,
             self.config.general.udp_sock_speed_limit.max_north_packets,
             self.config.general.udp_sock_speed_limit.max_north_bytes,
             wrapper_stats as _,
         );
+        send.set_burst_limit(
+            self.config.general.udp_sock_speed_limit.burst_north_packets,
+            self.config.general.udp_sock_speed_limit.burst_north_bytes,
+        );
 
         Ok((
             Box::new(DirectUdpConnectRemoteRecv::new(recv)),
@@ -0,0 +1,91 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use g3_io_ext::{GlobalStreamLimiter, LimitedBufReader, LimitedReader, LimitedWriter};
+use g3_types::net::GlobalStreamSpeedLimitConfig;
+
+use crate::config::escaper::GeneralEscaperConfig;
+
+/// Speed limiters shared by all tcp connections of the same escaper
+#[derive(Default)]
+pub(super) struct GlobalTcpLimiter {
+    upload: Option<Arc<GlobalStreamLimiter>>,
+    download: Option<Arc<GlobalStreamLimiter>>,
+}
+
+impl GlobalTcpLimiter {
+    pub(super) fn new(config: &GeneralEscaperConfig) -> Self {
+        GlobalTcpLimiter {
+            upload: config
+                .tcp_all_upload_speed_limit
+                .map(|c| Arc::new(GlobalStreamLimiter::new(c))),
+            download: config
+                .tcp_all_download_speed_limit
+                .map(|c| Arc::new(GlobalStreamLimiter::new(c))),
+        }
+    }
+
+    /// Build the limiters for the reloaded escaper. The existing ones will be reused,
+    /// as they may still be used by the connections created by the old escaper.
+    pub(super) fn reload(&self, config: &GeneralEscaperConfig) -> Self {
+        GlobalTcpLimiter {
+            upload: reload_limiter(self.upload.as_ref(), config.tcp_all_upload_speed_limit),
+            download: reload_limiter(self.download.as_ref(), config.tcp_all_download_speed_limit),
+        }
+    }
+
+    pub(super) fn apply_to_reader<R>(&self, reader: &mut LimitedReader<R>) {
+        if let Some(limiter) = &self.download {
+            reader.set_global_limit(limiter.clone());
+        }
+    }
+
+    pub(super) fn apply_to_buf_reader<R>(&self, reader: &mut LimitedBufReader<R>)
+    where
+        R: AsyncRead,
+    {
+        if let Some(limiter) = &self.download {
+            reader.set_global_limit(limiter.clone());
+        }
+    }
+
+    pub(super) fn apply_to_writer<W>(&self, writer: &mut LimitedWriter<W>)
+    where
+        W: AsyncWrite,
+    {
+        if let Some(limiter) = &self.upload {
+            writer.set_global_limit(limiter.clone());
+        }
+    }
+}
+
+fn reload_limiter(
+    old: Option<&Arc<GlobalStreamLimiter>>,
+    config: Option<GlobalStreamSpeedLimitConfig>,
+) -> Option<Arc<GlobalStreamLimiter>> {
+    let config = config?;
+    match old {
+        Some(limiter) => {
+            limiter.reload(config);
+            Some(limiter.clone())
+        }
+        None => Some(Arc::new(GlobalStreamLimiter::new(config))),
+    }
+}
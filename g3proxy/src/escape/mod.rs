@@ -45,11 +45,18 @@ use crate::serve::ServerTaskNotes;
 mod registry;
 pub(crate) use registry::{foreach as foreach_escaper, get_names, get_or_insert_default};
 
+mod global_limit;
+use global_limit::GlobalTcpLimiter;
+
+mod peer_health;
+use peer_health::PeerHealthTracker;
+
 mod stats;
 pub(crate) use stats::{
     ArcEscaperInternalStats, ArcEscaperStats, EscaperForbiddenSnapshot, EscaperForbiddenStats,
-    EscaperInterfaceStats, EscaperInternalStats, EscaperStats, EscaperTcpStats, EscaperUdpStats,
-    RouteEscaperSnapshot, RouteEscaperStats,
+    EscaperInterfaceStats, EscaperInternalStats, EscaperPeerQuarantineSnapshot, EscaperStats,
+    EscaperTcpConnectErrorSnapshot, EscaperTcpStats, EscaperTlsSessionSnapshot,
+    EscaperTlsSessionStats, EscaperUdpStats, RouteEscaperSnapshot, RouteEscaperStats,
 };
 
 mod direct_fixed;
@@ -93,6 +100,16 @@ pub(crate) trait EscaperInternal {
         HttpForwardCapability::default()
     }
 
+    /// get the next proxy node that will be used for http forward connections,
+    /// which is used as the key for pooled connections
+    fn _select_http_forward_peer(
+        &self,
+        _task_notes: &ServerTaskNotes,
+        _upstream: &UpstreamAddr,
+    ) -> Option<UpstreamAddr> {
+        None
+    }
+
     async fn _check_out_next_escaper(
         &self,
         task_notes: &ServerTaskNotes,
@@ -148,6 +165,17 @@ pub(crate) trait Escaper: EscaperInternal {
 
     async fn publish(&self, data: String) -> anyhow::Result<()>;
 
+    /// get the current published data, in the same format as the input of `publish`,
+    /// so it can be handed over to the new process on upgrade
+    fn published_data(&self) -> Option<String> {
+        None
+    }
+
+    /// list the next proxy peers that are temporarily removed from selection
+    fn quarantined_peers(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     async fn tcp_setup_connection<'a>(
         &'a self,
         tcp_notes: &'a mut TcpConnectTaskNotes,
@@ -190,6 +218,13 @@ pub(crate) trait Escaper: EscaperInternal {
 
 pub(crate) type ArcEscaper = Arc<dyn Escaper + Send + Sync>;
 
+#[derive(Hash)]
+struct ConsistentKey<'a> {
+    client_ip: IpAddr,
+    user: Option<&'a str>,
+    host: &'a Host,
+}
+
 pub(crate) trait EscaperExt: Escaper {
     fn select_consistent<'a, T>(
         &'a self,
@@ -201,13 +236,6 @@ pub(crate) trait EscaperExt: Escaper {
     where
         T: SelectiveItem + SelectiveHash,
     {
-        #[derive(Hash)]
-        struct ConsistentKey<'a> {
-            client_ip: IpAddr,
-            user: Option<&'a str>,
-            host: &'a Host,
-        }
-
         match pick_policy {
             SelectivePickPolicy::Random => nodes.pick_random(),
             SelectivePickPolicy::Serial => nodes.pick_serial(),
@@ -230,4 +258,42 @@ pub(crate) trait EscaperExt: Escaper {
             }
         }
     }
+
+    /// select a node that is not quarantined, the consistent one will be returned
+    /// if all nodes are quarantined
+    fn select_consistent_healthy<'a, T, F>(
+        &'a self,
+        nodes: &'a SelectiveVec<T>,
+        pick_policy: SelectivePickPolicy,
+        task_notes: &'a ServerTaskNotes,
+        host: &'a Host,
+        is_quarantined: F,
+    ) -> &'a T
+    where
+        T: SelectiveItem + SelectiveHash,
+        F: Fn(&T) -> bool,
+    {
+        let node = self.select_consistent(nodes, pick_policy, task_notes, host);
+        if !is_quarantined(node) {
+            return node;
+        }
+
+        let candidates = match pick_policy {
+            SelectivePickPolicy::Random => nodes.pick_random_n(usize::MAX),
+            SelectivePickPolicy::Serial => nodes.pick_serial_n(usize::MAX),
+            SelectivePickPolicy::RoundRobin => nodes.pick_round_robin_n(usize::MAX),
+            SelectivePickPolicy::Rendezvous | SelectivePickPolicy::JumpHash => {
+                let key = ConsistentKey {
+                    client_ip: task_notes.client_ip(),
+                    user: task_notes.raw_user_name(),
+                    host,
+                };
+                nodes.pick_rendezvous_n(&key, usize::MAX)
+            }
+        };
+        candidates
+            .into_iter()
+            .find(|v| !is_quarantined(v))
+            .unwrap_or(node)
+    }
 }
@@ -0,0 +1,263 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use ahash::AHashMap;
+
+use g3_types::net::UpstreamAddr;
+
+use super::EscaperPeerQuarantineSnapshot;
+use crate::config::escaper::peer_quarantine::PeerQuarantineConfig;
+use crate::module::tcp_connect::TcpConnectError;
+
+#[derive(Default)]
+struct PeerHealthState {
+    failures: usize,
+    quarantined: u32,
+    until: Option<Instant>,
+}
+
+impl PeerHealthState {
+    fn in_quarantine(&self, now: Instant) -> bool {
+        self.until.map(|until| until > now).unwrap_or(false)
+    }
+}
+
+#[derive(Default)]
+struct PeerHealthInner {
+    config: Option<PeerQuarantineConfig>,
+    peers: AHashMap<UpstreamAddr, PeerHealthState>,
+}
+
+/// Passive failure tracking of next proxy peers.
+/// It's kept in escaper stats so the state will be kept after reload.
+#[derive(Default)]
+pub(super) struct PeerHealthTracker {
+    inner: Mutex<PeerHealthInner>,
+    quarantine_total: AtomicU64,
+}
+
+impl PeerHealthTracker {
+    pub(super) fn set_config(&self, config: Option<PeerQuarantineConfig>) {
+        let mut inner = self.inner.lock().unwrap();
+        if config.is_none() {
+            inner.peers.clear();
+        }
+        inner.config = config;
+    }
+
+    pub(super) fn is_quarantined(&self, peer: &UpstreamAddr) -> bool {
+        self.is_quarantined_at(peer, Instant::now())
+    }
+
+    fn is_quarantined_at(&self, peer: &UpstreamAddr, now: Instant) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .peers
+            .get(peer)
+            .map(|s| s.in_quarantine(now))
+            .unwrap_or(false)
+    }
+
+    pub(super) fn add_success(&self, peer: &UpstreamAddr) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.peers.remove(peer);
+    }
+
+    pub(super) fn add_failure(&self, peer: &UpstreamAddr) {
+        self.add_failure_at(peer, Instant::now())
+    }
+
+    fn add_failure_at(&self, peer: &UpstreamAddr, now: Instant) {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(config) = inner.config else {
+            return;
+        };
+        let state = inner.peers.entry(peer.clone()).or_default();
+        if state.in_quarantine(now) {
+            // the task may be started before the peer is put in quarantine
+            return;
+        }
+        state.failures += 1;
+        if state.failures >= config.failure_threshold {
+            // a single failure after the cooldown will put it back in quarantine
+            state.failures = config.failure_threshold - 1;
+            state.quarantined = state.quarantined.saturating_add(1);
+            state.until = Some(now + config.cooldown_for(state.quarantined));
+            self.quarantine_total.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Track the result of the connect or negotiation with the peer.
+    /// Only the errors caused by the peer itself will be counted as failures.
+    pub(super) fn check_result<T>(&self, peer: &UpstreamAddr, r: &Result<T, TcpConnectError>) {
+        match r {
+            Ok(_) => self.add_success(peer),
+            Err(e) => {
+                if e.is_peer_failure() {
+                    self.add_failure(peer);
+                }
+            }
+        }
+    }
+
+    pub(super) fn quarantined_peers(&self) -> Vec<String> {
+        let now = Instant::now();
+        let inner = self.inner.lock().unwrap();
+        let mut list = Vec::with_capacity(inner.peers.len());
+        for (peer, state) in inner.peers.iter() {
+            if let Some(until) = state.until {
+                if until > now {
+                    let left = until - now;
+                    list.push(format!(
+                        "{peer}: quarantined {} times, {}s left",
+                        state.quarantined,
+                        left.as_secs() + 1
+                    ));
+                }
+            }
+        }
+        list.sort();
+        list
+    }
+
+    pub(super) fn snapshot(&self) -> Option<EscaperPeerQuarantineSnapshot> {
+        let now = Instant::now();
+        let inner = self.inner.lock().unwrap();
+        if inner.config.is_none() {
+            return None;
+        }
+        let current = inner
+            .peers
+            .values()
+            .filter(|s| s.in_quarantine(now))
+            .count();
+        Some(EscaperPeerQuarantineSnapshot {
+            total: self.quarantine_total.load(Ordering::Relaxed),
+            current: current as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn tracker() -> PeerHealthTracker {
+        let tracker = PeerHealthTracker::default();
+        tracker.set_config(Some(PeerQuarantineConfig {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(10),
+            max_cooldown: Duration::from_secs(15),
+        }));
+        tracker
+    }
+
+    #[test]
+    fn threshold() {
+        let tracker = tracker();
+        let peer = UpstreamAddr::from_ip_and_port("192.168.1.1".parse().unwrap(), 1080);
+        let now = Instant::now();
+
+        tracker.add_failure_at(&peer, now);
+        tracker.add_failure_at(&peer, now);
+        assert!(!tracker.is_quarantined_at(&peer, now));
+        // a success will reset the failure count
+        tracker.add_success(&peer);
+        tracker.add_failure_at(&peer, now);
+        tracker.add_failure_at(&peer, now);
+        assert!(!tracker.is_quarantined_at(&peer, now));
+        tracker.add_failure_at(&peer, now);
+        assert!(tracker.is_quarantined_at(&peer, now));
+
+        let other = UpstreamAddr::from_ip_and_port("192.168.1.2".parse().unwrap(), 1080);
+        assert!(!tracker.is_quarantined_at(&other, now));
+
+        let snapshot = tracker.snapshot().unwrap();
+        assert_eq!(snapshot.total, 1);
+        assert_eq!(snapshot.current, 1);
+    }
+
+    #[test]
+    fn quarantine_expire() {
+        let tracker = tracker();
+        let peer = UpstreamAddr::from_ip_and_port("192.168.1.1".parse().unwrap(), 1080);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            tracker.add_failure_at(&peer, now);
+        }
+        // failures of tasks started before the quarantine are ignored
+        tracker.add_failure_at(&peer, now + Duration::from_secs(5));
+        assert!(tracker.is_quarantined_at(&peer, now + Duration::from_secs(9)));
+        assert!(!tracker.is_quarantined_at(&peer, now + Duration::from_secs(10)));
+
+        // a single failure after the cooldown will put it back with a longer cooldown
+        let now = now + Duration::from_secs(10);
+        tracker.add_failure_at(&peer, now);
+        assert!(tracker.is_quarantined_at(&peer, now + Duration::from_secs(14)));
+        assert!(!tracker.is_quarantined_at(&peer, now + Duration::from_secs(15)));
+        assert_eq!(tracker.snapshot().unwrap().total, 2);
+
+        // a success will clear the state
+        tracker.add_success(&peer);
+        let now = now + Duration::from_secs(15);
+        tracker.add_failure_at(&peer, now);
+        assert!(!tracker.is_quarantined_at(&peer, now));
+    }
+
+    #[test]
+    fn check_result() {
+        let tracker = tracker();
+        let peer = UpstreamAddr::from_ip_and_port("192.168.1.1".parse().unwrap(), 1080);
+
+        for _ in 0..3 {
+            tracker.check_result::<()>(&peer, &Err(TcpConnectError::NegotiationAuthFailed));
+            tracker.check_result::<()>(
+                &peer,
+                &Err(TcpConnectError::NegotiationRejected("403".to_string())),
+            );
+        }
+        assert!(!tracker.is_quarantined(&peer));
+
+        for _ in 0..3 {
+            tracker.check_result::<()>(&peer, &Err(TcpConnectError::NegotiationPeerTimeout));
+        }
+        assert!(tracker.is_quarantined(&peer));
+    }
+
+    #[test]
+    fn disabled() {
+        let tracker = tracker();
+        let peer = UpstreamAddr::from_ip_and_port("192.168.1.1".parse().unwrap(), 1080);
+        for _ in 0..3 {
+            tracker.add_failure(&peer);
+        }
+        assert!(tracker.is_quarantined(&peer));
+
+        tracker.set_config(None);
+        assert!(!tracker.is_quarantined(&peer));
+        for _ in 0..3 {
+            tracker.add_failure(&peer);
+        }
+        assert!(!tracker.is_quarantined(&peer));
+        assert!(tracker.snapshot().is_none());
+    }
+}
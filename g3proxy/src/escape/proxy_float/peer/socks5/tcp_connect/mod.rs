@@ -116,18 +116,20 @@ impl ProxyFloatSocks5Peer {
         let (r, w) = stream.into_split();
 
         let limit_config = &self.shared_config.tcp_sock_speed_limit;
-        let r = LimitedReader::new(
+        let mut r = LimitedReader::new(
             r,
             limit_config.shift_millis,
             limit_config.max_south,
             self.escaper_stats.clone() as _,
         );
-        let w = LimitedWriter::new(
+        r.set_burst_limit(limit_config.burst_south);
+        let mut w = LimitedWriter::new(
             w,
             limit_config.shift_millis,
             limit_config.max_north,
             self.escaper_stats.clone() as _,
         );
+        w.set_burst_limit(limit_config.burst_north);
 
         Ok((r, w))
     }
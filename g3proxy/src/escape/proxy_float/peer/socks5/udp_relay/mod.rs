@@ -47,20 +47,28 @@ impl ProxyFloatSocks5Peer {
         let wrapper_stats = Arc::new(wrapper_stats);
 
         let (recv, send) = g3_io_ext::split_udp(udp_socket);
-        let recv = LimitedUdpRecv::new(
+        let mut recv = LimitedUdpRecv::new(
             recv,
             self.udp_sock_speed_limit.shift_millis,
             self.udp_sock_speed_limit.max_south_packets,
             self.udp_sock_speed_limit.max_south_bytes,
             wrapper_stats.clone() as _,
         );
-        let send = LimitedUdpSend::new(
+        recv.set_burst_limit(
+            self.udp_sock_speed_limit.burst_south_packets,
+            self.udp_sock_speed_limit.burst_south_bytes,
+        );
+        let mut send = LimitedUdpSend::new(
             send,
             self.udp_sock_speed_limit.shift_millis,
             self.udp_sock_speed_limit.max_north_packets,
             self.udp_sock_speed_limit.max_north_bytes,
             wrapper_stats as _,
         );
+        send.set_burst_limit(
+            self.udp_sock_speed_limit.burst_north_packets,
+            self.udp_sock_speed_limit.burst_north_bytes,
+        );
 
         let recv = ProxySocks5UdpRelayRemoteRecv::new(
             recv,
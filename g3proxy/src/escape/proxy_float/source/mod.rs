@@ -99,6 +99,7 @@ pub(super) fn new_job(
     peers_container: Arc<ArcSwap<PeerSet>>,
     tls_config: Option<Arc<OpensslClientConfig>>,
 ) -> anyhow::Result<AbortHandle> {
+    let task_name = format!("escaper {} peer fetch", config.name);
     let f = async move {
         let mut interval = tokio::time::interval(config.refresh_interval);
         interval.tick().await; // will tick immediately
@@ -138,6 +139,6 @@ pub(super) fn new_job(
 
     let (abort_handle, abort_registration) = AbortHandle::new_pair();
     let future = Abortable::new(f, abort_registration);
-    tokio::spawn(future);
+    g3_daemon::runtime::spawn_named(&task_name, future);
     Ok(abort_handle)
 }
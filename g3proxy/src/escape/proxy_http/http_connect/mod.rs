@@ -47,7 +47,7 @@ impl ProxyHttpEscaper {
         ),
         TcpConnectError,
     > {
-        let (r, mut w) = self.tcp_new_connection(tcp_notes, task_notes).await?;
+        let (peer, r, mut w) = self.tcp_new_connection(tcp_notes, task_notes).await?;
 
         let mut req =
             HttpConnectRequest::new(&tcp_notes.upstream, &self.config.append_http_headers);
@@ -59,13 +59,18 @@ impl ProxyHttpEscaper {
             }
         }
 
-        req.send(&mut w)
-            .await
-            .map_err(TcpConnectError::NegotiationWriteFailed)?;
-
         let mut r = BufReader::new(r);
-        let _ =
-            HttpConnectResponse::recv(&mut r, self.config.http_connect_rsp_hdr_max_size).await?;
+        let negotiation = match req.send(&mut w).await {
+            Ok(_) => HttpConnectResponse::recv(&mut r, self.config.http_connect_rsp_hdr_max_size)
+                .await
+                .map_err(TcpConnectError::from),
+            Err(e) => Err(TcpConnectError::NegotiationWriteFailed(e)),
+        };
+        self.stats.peer_health.check_result(&peer, &negotiation);
+        if let Err(e) = &negotiation {
+            self.stats.tcp.add_connect_error(e);
+        }
+        let _ = negotiation?;
 
         // TODO detect and set outgoing_addr and target_addr for supported remote proxies
 
@@ -83,12 +88,20 @@ impl ProxyHttpEscaper {
         ),
         TcpConnectError,
     > {
-        tokio::time::timeout(
+        match tokio::time::timeout(
             self.config.peer_negotiation_timeout,
             self.http_connect_tcp_connect_to(tcp_notes, task_notes),
         )
         .await
-        .map_err(|_| TcpConnectError::NegotiationPeerTimeout)?
+        {
+            Ok(r) => r,
+            Err(_) => {
+                if let Some(peer) = &tcp_notes.next_peer {
+                    self.stats.peer_health.add_failure(peer);
+                }
+                Err(TcpConnectError::NegotiationPeerTimeout)
+            }
+        }
     }
 
     pub(super) async fn http_connect_new_tcp_connection<'a>(
@@ -165,7 +178,9 @@ impl ProxyHttpEscaper {
                     tls_application,
                 }
                 .log(&self.escape_logger, &e);
-                Err(TcpConnectError::UpstreamTlsHandshakeFailed(e))
+                let e = TcpConnectError::UpstreamTlsHandshakeFailed(e);
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
             }
             Err(_) => {
                 let e = anyhow!("upstream tls handshake timed out");
@@ -177,7 +192,9 @@ impl ProxyHttpEscaper {
                     tls_application,
                 }
                 .log(&self.escape_logger, &e);
-                Err(TcpConnectError::UpstreamTlsHandshakeTimeout)
+                let e = TcpConnectError::UpstreamTlsHandshakeTimeout;
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
             }
         }
     }
@@ -41,7 +41,8 @@ impl ProxyHttpEscaper {
         task_notes: &'a ServerTaskNotes,
         task_stats: ArcHttpForwardTaskRemoteStats,
     ) -> Result<BoxHttpForwardConnection, TcpConnectError> {
-        let (ups_r, mut ups_w) = self.tcp_new_connection(tcp_notes, task_notes).await?;
+        let (peer, ups_r, mut ups_w) = self.tcp_new_connection(tcp_notes, task_notes).await?;
+        self.stats.peer_health.add_success(&peer);
 
         // add task and user stats
         let mut w_wrapper_stats = HttpForwardRemoteWrapperStats::new(&self.stats, &task_stats);
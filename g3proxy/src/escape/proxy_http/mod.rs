@@ -28,8 +28,12 @@ use g3_types::metrics::MetricsName;
 use g3_types::net::{
     Host, HttpForwardCapability, OpensslClientConfig, UpstreamAddr, WeightedUpstreamAddr,
 };
+use g3_types::resolve::{ResolvePickState, ResolveRedirection};
 
-use super::{ArcEscaper, ArcEscaperStats, Escaper, EscaperExt, EscaperInternal, EscaperStats};
+use super::{
+    ArcEscaper, ArcEscaperStats, Escaper, EscaperExt, EscaperInternal, EscaperStats,
+    GlobalTcpLimiter,
+};
 use crate::auth::UserUpstreamTrafficStats;
 use crate::config::escaper::proxy_http::ProxyHttpEscaperConfig;
 use crate::config::escaper::{AnyEscaperConfig, EscaperConfig};
@@ -40,7 +44,7 @@ use crate::module::ftp_over_http::{
 };
 use crate::module::http_forward::{
     ArcHttpForwardTaskRemoteStats, BoxHttpForwardConnection, BoxHttpForwardContext,
-    ProxyHttpForwardContext,
+    HttpForwardConnectionPool, ProxyHttpForwardContext,
 };
 use crate::module::tcp_connect::{TcpConnectError, TcpConnectResult, TcpConnectTaskNotes};
 use crate::module::udp_connect::{
@@ -64,6 +68,10 @@ pub(super) struct ProxyHttpEscaper {
     stats: Arc<ProxyHttpEscaperStats>,
     proxy_nodes: SelectiveVec<WeightedUpstreamAddr>,
     resolver_handle: Option<ArcIntegratedResolverHandle>,
+    resolve_pick_state: Arc<ResolvePickState>,
+    resolve_redirection: Option<ResolveRedirection>,
+    tcp_global_limiter: GlobalTcpLimiter,
+    http_forward_pool: Option<Arc<HttpForwardConnectionPool>>,
     escape_logger: Logger,
 }
 
@@ -71,6 +79,7 @@ impl ProxyHttpEscaper {
     fn new_obj(
         config: ProxyHttpEscaperConfig,
         stats: Arc<ProxyHttpEscaperStats>,
+        tcp_global_limiter: Option<&GlobalTcpLimiter>,
     ) -> anyhow::Result<ArcEscaper> {
         let mut nodes_builder = SelectiveVecBuilder::new();
         for node in &config.proxy_nodes {
@@ -80,6 +89,13 @@ impl ProxyHttpEscaper {
             .build()
             .ok_or_else(|| anyhow!("no next proxy node set"))?;
 
+        let tcp_global_limiter = match tcp_global_limiter {
+            Some(limiter) => limiter.reload(&config.general),
+            None => GlobalTcpLimiter::new(&config.general),
+        };
+        let http_forward_pool = config
+            .http_forward_connection_pool
+            .map(|c| Arc::new(HttpForwardConnectionPool::new(c)));
         let escape_logger = config.get_escape_logger();
 
         let resolver = config.resolver();
@@ -88,14 +104,26 @@ impl ProxyHttpEscaper {
         } else {
             Some(crate::resolve::get_handle(resolver)?)
         };
+        let resolve_redirection = config
+            .resolve_redirection
+            .as_ref()
+            .map(|builder| builder.build());
 
         stats.set_extra_tags(config.extra_metrics_tags.clone());
+        stats
+            .tcp
+            .set_connect_duration_config(config.general.tcp_connect_duration_stats.as_ref());
+        stats.peer_health.set_config(config.peer_quarantine);
 
         let escaper = ProxyHttpEscaper {
             config: Arc::new(config),
             stats,
             proxy_nodes,
             resolver_handle,
+            resolve_pick_state: Arc::new(ResolvePickState::default()),
+            resolve_redirection,
+            tcp_global_limiter,
+            http_forward_pool,
             escape_logger,
         };
 
@@ -104,15 +132,16 @@ impl ProxyHttpEscaper {
 
     pub(super) fn prepare_initial(config: ProxyHttpEscaperConfig) -> anyhow::Result<ArcEscaper> {
         let stats = Arc::new(ProxyHttpEscaperStats::new(config.name()));
-        ProxyHttpEscaper::new_obj(config, stats)
+        ProxyHttpEscaper::new_obj(config, stats, None)
     }
 
     fn prepare_reload(
         config: AnyEscaperConfig,
         stats: Arc<ProxyHttpEscaperStats>,
+        tcp_global_limiter: &GlobalTcpLimiter,
     ) -> anyhow::Result<ArcEscaper> {
         if let AnyEscaperConfig::ProxyHttp(config) = config {
-            ProxyHttpEscaper::new_obj(*config, stats)
+            ProxyHttpEscaper::new_obj(*config, stats, Some(tcp_global_limiter))
         } else {
             Err(anyhow!("invalid escaper config type"))
         }
@@ -123,18 +152,34 @@ impl ProxyHttpEscaper {
         task_notes: &'a ServerTaskNotes,
         target_host: &'a Host,
     ) -> &'a UpstreamAddr {
-        self.select_consistent(
+        self.select_consistent_healthy(
             &self.proxy_nodes,
             self.config.proxy_pick_policy,
             task_notes,
             target_host,
+            |node| self.stats.peer_health.is_quarantined(node.inner()),
         )
         .inner()
     }
 
     fn resolve_happy(&self, domain: &str) -> Result<HappyEyeballsResolveJob, ResolveError> {
         if let Some(resolver_handle) = &self.resolver_handle {
-            HappyEyeballsResolveJob::new_dyn(self.config.resolve_strategy, resolver_handle, domain)
+            if let Some(redirect) = &self.resolve_redirection {
+                if let Some(v) = redirect.query_value(domain) {
+                    return HappyEyeballsResolveJob::new_redirected(
+                        self.config.resolve_strategy,
+                        &self.resolve_pick_state,
+                        resolver_handle,
+                        v,
+                    );
+                }
+            }
+            HappyEyeballsResolveJob::new_dyn(
+                self.config.resolve_strategy,
+                &self.resolve_pick_state,
+                resolver_handle,
+                domain,
+            )
         } else {
             Err(ResolveLocalError::NoResolverSet.into())
         }
@@ -171,6 +216,10 @@ impl Escaper for ProxyHttpEscaper {
         Err(anyhow!("not implemented"))
     }
 
+    fn quarantined_peers(&self) -> Vec<String> {
+        self.stats.peer_health.quarantined_peers()
+    }
+
     async fn tcp_setup_connection<'a>(
         &'a self,
         tcp_notes: &'a mut TcpConnectTaskNotes,
@@ -222,7 +271,11 @@ impl Escaper for ProxyHttpEscaper {
     }
 
     fn new_http_forward_context(&self, escaper: ArcEscaper) -> BoxHttpForwardContext {
-        let ctx = ProxyHttpForwardContext::new(Arc::clone(&self.stats) as _, escaper);
+        let ctx = ProxyHttpForwardContext::new(
+            Arc::clone(&self.stats) as _,
+            escaper,
+            self.http_forward_pool.clone(),
+        );
         Box::new(ctx)
     }
 
@@ -261,7 +314,15 @@ impl EscaperInternal for ProxyHttpEscaper {
 
     async fn _lock_safe_reload(&self, config: AnyEscaperConfig) -> anyhow::Result<ArcEscaper> {
         let stats = Arc::clone(&self.stats);
-        ProxyHttpEscaper::prepare_reload(config, stats)
+        ProxyHttpEscaper::prepare_reload(config, stats, &self.tcp_global_limiter)
+    }
+
+    fn _select_http_forward_peer(
+        &self,
+        task_notes: &ServerTaskNotes,
+        upstream: &UpstreamAddr,
+    ) -> Option<UpstreamAddr> {
+        Some(self.get_next_proxy(task_notes, upstream.host()).clone())
     }
 
     #[inline]
@@ -19,11 +19,15 @@ use std::sync::Arc;
 use arc_swap::ArcSwapOption;
 
 use g3_daemon::stat::remote::TcpConnectionTaskRemoteStats;
+use g3_histogram::HistogramStats;
 use g3_io_ext::{LimitedReaderStats, LimitedWriterStats};
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::stats::{StatId, TcpIoSnapshot};
 
-use crate::escape::{EscaperInterfaceStats, EscaperInternalStats, EscaperStats, EscaperTcpStats};
+use crate::escape::{
+    EscaperInterfaceStats, EscaperInternalStats, EscaperPeerQuarantineSnapshot, EscaperStats,
+    EscaperTcpConnectErrorSnapshot, EscaperTcpStats, PeerHealthTracker,
+};
 use crate::module::http_forward::HttpForwardTaskRemoteStats;
 
 pub(crate) struct ProxyHttpEscaperStats {
@@ -32,6 +36,7 @@ pub(crate) struct ProxyHttpEscaperStats {
     extra_metrics_tags: Arc<ArcSwapOption<StaticMetricsTags>>,
     pub(super) interface: EscaperInterfaceStats,
     pub(super) tcp: EscaperTcpStats,
+    pub(super) peer_health: PeerHealthTracker,
 }
 
 impl ProxyHttpEscaperStats {
@@ -42,6 +47,7 @@ impl ProxyHttpEscaperStats {
             extra_metrics_tags: Arc::new(ArcSwapOption::new(None)),
             interface: EscaperInterfaceStats::default(),
             tcp: EscaperTcpStats::default(),
+            peer_health: PeerHealthTracker::default(),
         }
     }
 
@@ -94,6 +100,19 @@ impl EscaperStats for ProxyHttpEscaperStats {
     fn tcp_io_snapshot(&self) -> Option<TcpIoSnapshot> {
         Some(self.tcp.io.snapshot())
     }
+
+    #[inline]
+    fn tcp_connect_duration_stats(&self) -> Option<Arc<HistogramStats>> {
+        self.tcp.connect_duration_stats()
+    }
+
+    fn tcp_connect_error_snapshot(&self) -> Option<EscaperTcpConnectErrorSnapshot> {
+        Some(self.tcp.connect_error_snapshot())
+    }
+
+    fn peer_quarantine_snapshot(&self) -> Option<EscaperPeerQuarantineSnapshot> {
+        self.peer_health.snapshot()
+    }
 }
 
 impl LimitedReaderStats for ProxyHttpEscaperStats {
@@ -22,7 +22,7 @@ use tokio::task::JoinSet;
 use tokio::time::Instant;
 
 use g3_io_ext::{LimitedReader, LimitedWriter};
-use g3_types::net::{ConnectError, Host, ProxyProtocolEncoder};
+use g3_types::net::{ConnectError, Host, ProxyProtocolEncoder, UpstreamAddr};
 
 use super::ProxyHttpEscaper;
 use crate::log::escape::tcp_connect::EscapeLogForTcpConnect;
@@ -85,6 +85,7 @@ impl ProxyHttpEscaper {
                 tcp_notes.duration = instant_now.elapsed();
 
                 self.stats.tcp.add_connection_established();
+                self.stats.tcp.record_connect_duration(tcp_notes.duration);
                 let local_addr = ups_stream
                     .local_addr()
                     .map_err(TcpConnectError::SetupSocketFailed)?;
@@ -192,6 +193,7 @@ impl ProxyHttpEscaper {
                                 match r.0 {
                                     Ok(ups_stream) => {
                                         self.stats.tcp.add_connection_established();
+                                        self.stats.tcp.record_connect_duration(tcp_notes.duration);
                                         let local_addr = ups_stream
                                             .local_addr()
                                             .map_err(TcpConnectError::SetupSocketFailed)?;
@@ -267,10 +269,12 @@ impl ProxyHttpEscaper {
         &'a self,
         tcp_notes: &'a mut TcpConnectTaskNotes,
         task_notes: &'a ServerTaskNotes,
-    ) -> Result<TcpStream, TcpConnectError> {
-        let peer_proxy = self.get_next_proxy(task_notes, tcp_notes.upstream.host());
+    ) -> Result<(UpstreamAddr, TcpStream), TcpConnectError> {
+        let peer_proxy = self
+            .get_next_proxy(task_notes, tcp_notes.upstream.host())
+            .clone();
 
-        match peer_proxy.host() {
+        let r = match peer_proxy.host() {
             Host::Ip(ip) => {
                 self.fixed_try_connect(
                     SocketAddr::new(*ip, peer_proxy.port()),
@@ -279,11 +283,26 @@ impl ProxyHttpEscaper {
                 )
                 .await
             }
-            Host::Domain(domain) => {
-                let resolver_job = self.resolve_happy(domain)?;
+            Host::Domain(domain) => match self.resolve_happy(domain) {
+                Ok(resolver_job) => {
+                    self.happy_try_connect(resolver_job, peer_proxy.port(), tcp_notes, task_notes)
+                        .await
+                }
+                Err(e) => Err(e.into()),
+            },
+        };
 
-                self.happy_try_connect(resolver_job, peer_proxy.port(), tcp_notes, task_notes)
-                    .await
+        match r {
+            Ok(stream) => {
+                tcp_notes.next_peer = Some(peer_proxy.clone());
+                Ok((peer_proxy, stream))
+            }
+            Err(e) => {
+                if e.is_peer_failure() {
+                    self.stats.peer_health.add_failure(&peer_proxy);
+                }
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
             }
         }
     }
@@ -294,27 +313,32 @@ impl ProxyHttpEscaper {
         task_notes: &'a ServerTaskNotes,
     ) -> Result<
         (
+            UpstreamAddr,
             LimitedReader<tcp::OwnedReadHalf>,
             LimitedWriter<tcp::OwnedWriteHalf>,
         ),
         TcpConnectError,
     > {
-        let stream = self.tcp_connect_to(tcp_notes, task_notes).await?;
+        let (peer, stream) = self.tcp_connect_to(tcp_notes, task_notes).await?;
         let (r, w) = stream.into_split();
 
         let limit_config = &self.config.general.tcp_sock_speed_limit;
-        let r = LimitedReader::new(
+        let mut r = LimitedReader::new(
             r,
             limit_config.shift_millis,
             limit_config.max_south,
             self.stats.clone() as _,
         );
+        r.set_burst_limit(limit_config.burst_south);
+        self.tcp_global_limiter.apply_to_reader(&mut r);
         let mut w = LimitedWriter::new(
             w,
             limit_config.shift_millis,
             limit_config.max_north,
             self.stats.clone() as _,
         );
+        w.set_burst_limit(limit_config.burst_north);
+        self.tcp_global_limiter.apply_to_writer(&mut w);
 
         if let Some(version) = self.config.use_proxy_protocol {
             let mut encoder = ProxyProtocolEncoder::new(version);
@@ -326,6 +350,6 @@ impl ProxyHttpEscaper {
                 .map_err(TcpConnectError::ProxyProtocolWriteFailed)?;
         }
 
-        Ok((r, w))
+        Ok((peer, r, w))
     }
 }
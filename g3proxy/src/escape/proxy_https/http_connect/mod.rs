@@ -55,8 +55,13 @@ impl ProxyHttpsEscaper {
             .map_err(TcpConnectError::NegotiationWriteFailed)?;
 
         let mut r = BufReader::new(r);
-        let _ =
-            HttpConnectResponse::recv(&mut r, self.config.http_connect_rsp_hdr_max_size).await?;
+        if let Err(e) =
+            HttpConnectResponse::recv(&mut r, self.config.http_connect_rsp_hdr_max_size).await
+        {
+            let e = TcpConnectError::from(e);
+            self.stats.tcp.add_connect_error(&e);
+            return Err(e);
+        }
 
         // TODO detect and set outgoing_addr and target_addr for supported remote proxies
 
@@ -143,7 +148,9 @@ impl ProxyHttpsEscaper {
                     tls_application,
                 }
                 .log(&self.escape_logger, &e);
-                Err(TcpConnectError::UpstreamTlsHandshakeFailed(e))
+                let e = TcpConnectError::UpstreamTlsHandshakeFailed(e);
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
             }
             Err(_) => {
                 let e = anyhow!("upstream tls handshake timed out");
@@ -155,7 +162,9 @@ impl ProxyHttpsEscaper {
                     tls_application,
                 }
                 .log(&self.escape_logger, &e);
-                Err(TcpConnectError::UpstreamTlsHandshakeTimeout)
+                let e = TcpConnectError::UpstreamTlsHandshakeTimeout;
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
             }
         }
     }
@@ -28,8 +28,12 @@ use g3_types::metrics::MetricsName;
 use g3_types::net::{
     Host, HttpForwardCapability, OpensslClientConfig, UpstreamAddr, WeightedUpstreamAddr,
 };
+use g3_types::resolve::{ResolvePickState, ResolveRedirection};
 
-use super::{ArcEscaper, ArcEscaperStats, Escaper, EscaperExt, EscaperInternal, EscaperStats};
+use super::{
+    ArcEscaper, ArcEscaperStats, Escaper, EscaperExt, EscaperInternal, EscaperStats,
+    GlobalTcpLimiter,
+};
 use crate::auth::UserUpstreamTrafficStats;
 use crate::config::escaper::proxy_https::ProxyHttpsEscaperConfig;
 use crate::config::escaper::{AnyEscaperConfig, EscaperConfig};
@@ -40,7 +44,7 @@ use crate::module::ftp_over_http::{
 };
 use crate::module::http_forward::{
     ArcHttpForwardTaskRemoteStats, BoxHttpForwardConnection, BoxHttpForwardContext,
-    ProxyHttpForwardContext,
+    HttpForwardConnectionPool, ProxyHttpForwardContext,
 };
 use crate::module::tcp_connect::{TcpConnectError, TcpConnectResult, TcpConnectTaskNotes};
 use crate::module::udp_connect::{
@@ -66,6 +70,10 @@ pub(super) struct ProxyHttpsEscaper {
     proxy_nodes: SelectiveVec<WeightedUpstreamAddr>,
     tls_config: OpensslClientConfig,
     resolver_handle: Option<ArcIntegratedResolverHandle>,
+    resolve_pick_state: Arc<ResolvePickState>,
+    resolve_redirection: Option<ResolveRedirection>,
+    tcp_global_limiter: GlobalTcpLimiter,
+    http_forward_pool: Option<Arc<HttpForwardConnectionPool>>,
     escape_logger: Logger,
 }
 
@@ -73,6 +81,7 @@ impl ProxyHttpsEscaper {
     fn new_obj(
         config: ProxyHttpsEscaperConfig,
         stats: Arc<ProxyHttpsEscaperStats>,
+        tcp_global_limiter: Option<&GlobalTcpLimiter>,
     ) -> anyhow::Result<ArcEscaper> {
         let mut nodes_builder = SelectiveVecBuilder::new();
         for node in &config.proxy_nodes {
@@ -87,6 +96,13 @@ impl ProxyHttpsEscaper {
             .build()
             .context("failed to build tls config")?;
 
+        let tcp_global_limiter = match tcp_global_limiter {
+            Some(limiter) => limiter.reload(&config.general),
+            None => GlobalTcpLimiter::new(&config.general),
+        };
+        let http_forward_pool = config
+            .http_forward_connection_pool
+            .map(|c| Arc::new(HttpForwardConnectionPool::new(c)));
         let escape_logger = config.get_escape_logger();
 
         let resolver = config.resolver();
@@ -95,8 +111,16 @@ impl ProxyHttpsEscaper {
         } else {
             Some(crate::resolve::get_handle(resolver)?)
         };
+        let resolve_redirection = config
+            .resolve_redirection
+            .as_ref()
+            .map(|builder| builder.build());
 
         stats.set_extra_tags(config.extra_metrics_tags.clone());
+        stats
+            .tcp
+            .set_connect_duration_config(config.general.tcp_connect_duration_stats.as_ref());
+        stats.peer_health.set_config(config.peer_quarantine);
 
         let escaper = ProxyHttpsEscaper {
             config: Arc::new(config),
@@ -104,6 +128,10 @@ impl ProxyHttpsEscaper {
             proxy_nodes,
             tls_config,
             resolver_handle,
+            resolve_pick_state: Arc::new(ResolvePickState::default()),
+            resolve_redirection,
+            tcp_global_limiter,
+            http_forward_pool,
             escape_logger,
         };
         Ok(Arc::new(escaper))
@@ -111,15 +139,16 @@ impl ProxyHttpsEscaper {
 
     pub(super) fn prepare_initial(config: ProxyHttpsEscaperConfig) -> anyhow::Result<ArcEscaper> {
         let stats = Arc::new(ProxyHttpsEscaperStats::new(config.name()));
-        ProxyHttpsEscaper::new_obj(config, stats)
+        ProxyHttpsEscaper::new_obj(config, stats, None)
     }
 
     fn prepare_reload(
         config: AnyEscaperConfig,
         stats: Arc<ProxyHttpsEscaperStats>,
+        tcp_global_limiter: &GlobalTcpLimiter,
     ) -> anyhow::Result<ArcEscaper> {
         if let AnyEscaperConfig::ProxyHttps(config) = config {
-            ProxyHttpsEscaper::new_obj(*config, stats)
+            ProxyHttpsEscaper::new_obj(*config, stats, Some(tcp_global_limiter))
         } else {
             Err(anyhow!("invalid escaper config type"))
         }
@@ -130,18 +159,34 @@ impl ProxyHttpsEscaper {
         task_notes: &'a ServerTaskNotes,
         target_host: &'a Host,
     ) -> &'a UpstreamAddr {
-        self.select_consistent(
+        self.select_consistent_healthy(
             &self.proxy_nodes,
             self.config.proxy_pick_policy,
             task_notes,
             target_host,
+            |node| self.stats.peer_health.is_quarantined(node.inner()),
         )
         .inner()
     }
 
     fn resolve_happy(&self, domain: &str) -> Result<HappyEyeballsResolveJob, ResolveError> {
         if let Some(resolver_handle) = &self.resolver_handle {
-            HappyEyeballsResolveJob::new_dyn(self.config.resolve_strategy, resolver_handle, domain)
+            if let Some(redirect) = &self.resolve_redirection {
+                if let Some(v) = redirect.query_value(domain) {
+                    return HappyEyeballsResolveJob::new_redirected(
+                        self.config.resolve_strategy,
+                        &self.resolve_pick_state,
+                        resolver_handle,
+                        v,
+                    );
+                }
+            }
+            HappyEyeballsResolveJob::new_dyn(
+                self.config.resolve_strategy,
+                &self.resolve_pick_state,
+                resolver_handle,
+                domain,
+            )
         } else {
             Err(ResolveLocalError::NoResolverSet.into())
         }
@@ -178,6 +223,10 @@ impl Escaper for ProxyHttpsEscaper {
         Err(anyhow!("not implemented"))
     }
 
+    fn quarantined_peers(&self) -> Vec<String> {
+        self.stats.peer_health.quarantined_peers()
+    }
+
     async fn tcp_setup_connection<'a>(
         &'a self,
         tcp_notes: &'a mut TcpConnectTaskNotes,
@@ -229,7 +278,11 @@ impl Escaper for ProxyHttpsEscaper {
     }
 
     fn new_http_forward_context(&self, escaper: ArcEscaper) -> BoxHttpForwardContext {
-        let ctx = ProxyHttpForwardContext::new(Arc::clone(&self.stats) as _, escaper);
+        let ctx = ProxyHttpForwardContext::new(
+            Arc::clone(&self.stats) as _,
+            escaper,
+            self.http_forward_pool.clone(),
+        );
         Box::new(ctx)
     }
 
@@ -268,7 +321,15 @@ impl EscaperInternal for ProxyHttpsEscaper {
 
     async fn _lock_safe_reload(&self, config: AnyEscaperConfig) -> anyhow::Result<ArcEscaper> {
         let stats = Arc::clone(&self.stats);
-        ProxyHttpsEscaper::prepare_reload(config, stats)
+        ProxyHttpsEscaper::prepare_reload(config, stats, &self.tcp_global_limiter)
+    }
+
+    fn _select_http_forward_peer(
+        &self,
+        task_notes: &ServerTaskNotes,
+        upstream: &UpstreamAddr,
+    ) -> Option<UpstreamAddr> {
+        Some(self.get_next_proxy(task_notes, upstream.host()).clone())
     }
 
     #[inline]
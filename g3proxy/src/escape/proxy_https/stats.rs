@@ -18,11 +18,16 @@ use std::sync::Arc;
 
 use arc_swap::ArcSwapOption;
 
+use g3_histogram::HistogramStats;
 use g3_io_ext::{LimitedReaderStats, LimitedWriterStats};
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::stats::{StatId, TcpIoSnapshot};
 
-use crate::escape::{EscaperInterfaceStats, EscaperInternalStats, EscaperStats, EscaperTcpStats};
+use crate::escape::{
+    EscaperInterfaceStats, EscaperInternalStats, EscaperPeerQuarantineSnapshot, EscaperStats,
+    EscaperTcpConnectErrorSnapshot, EscaperTcpStats, EscaperTlsSessionSnapshot,
+    EscaperTlsSessionStats, PeerHealthTracker,
+};
 
 pub(crate) struct ProxyHttpsEscaperStats {
     name: MetricsName,
@@ -30,6 +35,8 @@ pub(crate) struct ProxyHttpsEscaperStats {
     extra_metrics_tags: Arc<ArcSwapOption<StaticMetricsTags>>,
     pub(super) interface: EscaperInterfaceStats,
     pub(super) tcp: EscaperTcpStats,
+    pub(super) tls_session: EscaperTlsSessionStats,
+    pub(super) peer_health: PeerHealthTracker,
 }
 
 impl ProxyHttpsEscaperStats {
@@ -40,6 +47,8 @@ impl ProxyHttpsEscaperStats {
             extra_metrics_tags: Arc::new(ArcSwapOption::new(None)),
             interface: EscaperInterfaceStats::default(),
             tcp: EscaperTcpStats::default(),
+            tls_session: EscaperTlsSessionStats::default(),
+            peer_health: PeerHealthTracker::default(),
         }
     }
 
@@ -92,6 +101,23 @@ impl EscaperStats for ProxyHttpsEscaperStats {
     fn tcp_io_snapshot(&self) -> Option<TcpIoSnapshot> {
         Some(self.tcp.io.snapshot())
     }
+
+    #[inline]
+    fn tcp_connect_duration_stats(&self) -> Option<Arc<HistogramStats>> {
+        self.tcp.connect_duration_stats()
+    }
+
+    fn tcp_connect_error_snapshot(&self) -> Option<EscaperTcpConnectErrorSnapshot> {
+        Some(self.tcp.connect_error_snapshot())
+    }
+
+    fn tls_session_snapshot(&self) -> Option<EscaperTlsSessionSnapshot> {
+        Some(self.tls_session.snapshot())
+    }
+
+    fn peer_quarantine_snapshot(&self) -> Option<EscaperPeerQuarantineSnapshot> {
+        self.peer_health.snapshot()
+    }
 }
 
 impl LimitedReaderStats for ProxyHttpsEscaperStats {
@@ -85,6 +85,7 @@ impl ProxyHttpsEscaper {
                 tcp_notes.duration = instant_now.elapsed();
 
                 self.stats.tcp.add_connection_established();
+                self.stats.tcp.record_connect_duration(tcp_notes.duration);
                 let local_addr = ups_stream
                     .local_addr()
                     .map_err(TcpConnectError::SetupSocketFailed)?;
@@ -192,6 +193,7 @@ impl ProxyHttpsEscaper {
                                 match r.0 {
                                     Ok(ups_stream) => {
                                         self.stats.tcp.add_connection_established();
+                                        self.stats.tcp.record_connect_duration(tcp_notes.duration);
                                         let local_addr = ups_stream
                                             .local_addr()
                                             .map_err(TcpConnectError::SetupSocketFailed)?;
@@ -272,24 +274,37 @@ impl ProxyHttpsEscaper {
             .get_next_proxy(task_notes, tcp_notes.upstream.host())
             .clone();
 
-        let stream = match peer_proxy.host() {
+        let r = match peer_proxy.host() {
             Host::Ip(ip) => {
                 self.fixed_try_connect(
                     SocketAddr::new(*ip, peer_proxy.port()),
                     tcp_notes,
                     task_notes,
                 )
-                .await?
-            }
-            Host::Domain(domain) => {
-                let resolver_job = self.resolve_happy(domain)?;
-
-                self.happy_try_connect(resolver_job, peer_proxy.port(), tcp_notes, task_notes)
-                    .await?
+                .await
             }
+            Host::Domain(domain) => match self.resolve_happy(domain) {
+                Ok(resolver_job) => {
+                    self.happy_try_connect(resolver_job, peer_proxy.port(), tcp_notes, task_notes)
+                        .await
+                }
+                Err(e) => Err(e.into()),
+            },
         };
 
-        Ok((peer_proxy, stream))
+        match r {
+            Ok(stream) => {
+                tcp_notes.next_peer = Some(peer_proxy.clone());
+                Ok((peer_proxy, stream))
+            }
+            Err(e) => {
+                if e.is_peer_failure() {
+                    self.stats.peer_health.add_failure(&peer_proxy);
+                }
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
+            }
+        }
     }
 
     pub(super) async fn tcp_new_connection<'a>(
@@ -308,18 +323,22 @@ impl ProxyHttpsEscaper {
         let (r, w) = stream.into_split();
 
         let limit_config = &self.config.general.tcp_sock_speed_limit;
-        let r = LimitedReader::new(
+        let mut r = LimitedReader::new(
             r,
             limit_config.shift_millis,
             limit_config.max_south,
             self.stats.clone() as _,
         );
+        r.set_burst_limit(limit_config.burst_south);
+        self.tcp_global_limiter.apply_to_reader(&mut r);
         let mut w = LimitedWriter::new(
             w,
             limit_config.shift_millis,
             limit_config.max_north,
             self.stats.clone() as _,
         );
+        w.set_burst_limit(limit_config.burst_north);
+        self.tcp_global_limiter.apply_to_writer(&mut w);
 
         if let Some(version) = self.config.use_proxy_protocol {
             let mut encoder = ProxyProtocolEncoder::new(version);
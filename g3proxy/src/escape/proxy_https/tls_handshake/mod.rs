@@ -47,8 +47,16 @@ impl ProxyHttpsEscaper {
         )
         .map_err(|e| TcpConnectError::InternalTlsClientError(anyhow::Error::new(e)))?;
 
-        match tokio::time::timeout(self.tls_config.handshake_timeout, connector.connect()).await {
+        let handshake = match tokio::time::timeout(
+            self.tls_config.handshake_timeout,
+            connector.connect(),
+        )
+        .await
+        {
             Ok(Ok(stream)) => {
+                self.stats
+                    .tls_session
+                    .add_handshake(stream.ssl().session_reused());
                 let (r, w) = tokio::io::split(stream);
                 Ok((r, w))
             }
@@ -62,7 +70,9 @@ impl ProxyHttpsEscaper {
                     tls_application: TlsApplication::HttpProxy,
                 }
                 .log(&self.escape_logger, &e);
-                Err(TcpConnectError::PeerTlsHandshakeFailed(e))
+                let e = TcpConnectError::PeerTlsHandshakeFailed(e);
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
             }
             Err(_) => {
                 let e = anyhow!("peer tls handshake timed out");
@@ -74,8 +84,12 @@ impl ProxyHttpsEscaper {
                     tls_application: TlsApplication::HttpProxy,
                 }
                 .log(&self.escape_logger, &e);
-                Err(TcpConnectError::PeerTlsHandshakeTimeout)
+                let e = TcpConnectError::PeerTlsHandshakeTimeout;
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
             }
-        }
+        };
+        self.stats.peer_health.check_result(&peer, &handshake);
+        handshake
     }
 }
@@ -26,10 +26,11 @@ use g3_resolver::{ResolveError, ResolveLocalError};
 use g3_types::collection::{SelectiveVec, SelectiveVecBuilder};
 use g3_types::metrics::MetricsName;
 use g3_types::net::{Host, OpensslClientConfig, UpstreamAddr, WeightedUpstreamAddr};
+use g3_types::resolve::{ResolvePickState, ResolveRedirection};
 
 use super::{
     ArcEscaper, ArcEscaperInternalStats, ArcEscaperStats, Escaper, EscaperExt, EscaperInternal,
-    EscaperStats,
+    EscaperStats, GlobalTcpLimiter,
 };
 use crate::auth::UserUpstreamTrafficStats;
 use crate::config::escaper::proxy_socks5::ProxySocks5EscaperConfig;
@@ -67,6 +68,9 @@ pub(super) struct ProxySocks5Escaper {
     stats: Arc<ProxySocks5EscaperStats>,
     proxy_nodes: SelectiveVec<WeightedUpstreamAddr>,
     resolver_handle: Option<ArcIntegratedResolverHandle>,
+    resolve_pick_state: Arc<ResolvePickState>,
+    resolve_redirection: Option<ResolveRedirection>,
+    tcp_global_limiter: GlobalTcpLimiter,
     escape_logger: Logger,
 }
 
@@ -74,6 +78,7 @@ impl ProxySocks5Escaper {
     fn new_obj(
         config: ProxySocks5EscaperConfig,
         stats: Arc<ProxySocks5EscaperStats>,
+        tcp_global_limiter: Option<&GlobalTcpLimiter>,
     ) -> anyhow::Result<ArcEscaper> {
         let mut nodes_builder = SelectiveVecBuilder::new();
         for node in &config.proxy_nodes {
@@ -83,6 +88,10 @@ impl ProxySocks5Escaper {
             .build()
             .ok_or_else(|| anyhow!("no next proxy node set"))?;
 
+        let tcp_global_limiter = match tcp_global_limiter {
+            Some(limiter) => limiter.reload(&config.general),
+            None => GlobalTcpLimiter::new(&config.general),
+        };
         let escape_logger = config.get_escape_logger();
 
         let resolver = config.resolver();
@@ -91,14 +100,25 @@ impl ProxySocks5Escaper {
         } else {
             Some(crate::resolve::get_handle(resolver)?)
         };
+        let resolve_redirection = config
+            .resolve_redirection
+            .as_ref()
+            .map(|builder| builder.build());
 
         stats.set_extra_tags(config.extra_metrics_tags.clone());
+        stats
+            .tcp
+            .set_connect_duration_config(config.general.tcp_connect_duration_stats.as_ref());
+        stats.peer_health.set_config(config.peer_quarantine);
 
         let escaper = ProxySocks5Escaper {
             config: Arc::new(config),
             stats,
             proxy_nodes,
             resolver_handle,
+            resolve_pick_state: Arc::new(ResolvePickState::default()),
+            resolve_redirection,
+            tcp_global_limiter,
             escape_logger,
         };
 
@@ -107,15 +127,16 @@ impl ProxySocks5Escaper {
 
     pub(super) fn prepare_initial(config: ProxySocks5EscaperConfig) -> anyhow::Result<ArcEscaper> {
         let stats = Arc::new(ProxySocks5EscaperStats::new(config.name()));
-        ProxySocks5Escaper::new_obj(config, stats)
+        ProxySocks5Escaper::new_obj(config, stats, None)
     }
 
     fn prepare_reload(
         config: AnyEscaperConfig,
         stats: Arc<ProxySocks5EscaperStats>,
+        tcp_global_limiter: &GlobalTcpLimiter,
     ) -> anyhow::Result<ArcEscaper> {
         if let AnyEscaperConfig::ProxySocks5(config) = config {
-            ProxySocks5Escaper::new_obj(config, stats)
+            ProxySocks5Escaper::new_obj(config, stats, Some(tcp_global_limiter))
         } else {
             Err(anyhow!("invalid escaper config type"))
         }
@@ -126,18 +147,34 @@ impl ProxySocks5Escaper {
         task_notes: &'a ServerTaskNotes,
         target_host: &'a Host,
     ) -> &'a UpstreamAddr {
-        self.select_consistent(
+        self.select_consistent_healthy(
             &self.proxy_nodes,
             self.config.proxy_pick_policy,
             task_notes,
             target_host,
+            |node| self.stats.peer_health.is_quarantined(node.inner()),
         )
         .inner()
     }
 
     fn resolve_happy(&self, domain: &str) -> Result<HappyEyeballsResolveJob, ResolveError> {
         if let Some(resolver_handle) = &self.resolver_handle {
-            HappyEyeballsResolveJob::new_dyn(self.config.resolve_strategy, resolver_handle, domain)
+            if let Some(redirect) = &self.resolve_redirection {
+                if let Some(v) = redirect.query_value(domain) {
+                    return HappyEyeballsResolveJob::new_redirected(
+                        self.config.resolve_strategy,
+                        &self.resolve_pick_state,
+                        resolver_handle,
+                        v,
+                    );
+                }
+            }
+            HappyEyeballsResolveJob::new_dyn(
+                self.config.resolve_strategy,
+                &self.resolve_pick_state,
+                resolver_handle,
+                domain,
+            )
         } else {
             Err(ResolveLocalError::NoResolverSet.into())
         }
@@ -174,6 +211,10 @@ impl Escaper for ProxySocks5Escaper {
         Err(anyhow!("not implemented"))
     }
 
+    fn quarantined_peers(&self) -> Vec<String> {
+        self.stats.peer_health.quarantined_peers()
+    }
+
     async fn tcp_setup_connection<'a>(
         &'a self,
         tcp_notes: &'a mut TcpConnectTaskNotes,
@@ -266,7 +307,7 @@ impl EscaperInternal for ProxySocks5Escaper {
 
     async fn _lock_safe_reload(&self, config: AnyEscaperConfig) -> anyhow::Result<ArcEscaper> {
         let stats = Arc::clone(&self.stats);
-        ProxySocks5Escaper::prepare_reload(config, stats)
+        ProxySocks5Escaper::prepare_reload(config, stats, &self.tcp_global_limiter)
     }
 
     async fn _check_out_next_escaper(
@@ -50,14 +50,20 @@ impl ProxySocks5Escaper {
         ),
         TcpConnectError,
     > {
-        let (mut r, mut w) = self.tcp_new_connection(tcp_notes, task_notes).await?;
-        let outgoing_addr = v5::client::socks5_connect_to(
+        let (peer, mut r, mut w) = self.tcp_new_connection(tcp_notes, task_notes).await?;
+        let negotiation = v5::client::socks5_connect_to(
             &mut r,
             &mut w,
             &self.config.auth_info,
             &tcp_notes.upstream,
         )
-        .await?;
+        .await
+        .map_err(TcpConnectError::from);
+        self.stats.peer_health.check_result(&peer, &negotiation);
+        if let Err(e) = &negotiation {
+            self.stats.tcp.add_connect_error(e);
+        }
+        let outgoing_addr = negotiation?;
         tcp_notes.chained.outgoing_addr = Some(outgoing_addr);
         // we can not determine the real upstream addr that the proxy choose to connect to
 
@@ -75,12 +81,20 @@ impl ProxySocks5Escaper {
         ),
         TcpConnectError,
     > {
-        tokio::time::timeout(
+        match tokio::time::timeout(
             self.config.peer_negotiation_timeout,
             self.socks5_connect_tcp_connect_to(tcp_notes, task_notes),
         )
         .await
-        .map_err(|_| TcpConnectError::NegotiationPeerTimeout)?
+        {
+            Ok(r) => r,
+            Err(_) => {
+                if let Some(peer) = &tcp_notes.next_peer {
+                    self.stats.peer_health.add_failure(peer);
+                }
+                Err(TcpConnectError::NegotiationPeerTimeout)
+            }
+        }
     }
 
     /// setup udp associate with remote proxy
@@ -99,7 +113,7 @@ impl ProxySocks5Escaper {
         ),
         io::Error,
     > {
-        let (mut r, mut w) = self
+        let (peer, mut r, mut w) = self
             .tcp_new_connection(tcp_notes, task_notes)
             .await
             .map_err(io::Error::other)?;
@@ -117,10 +131,15 @@ impl ProxySocks5Escaper {
         };
         let send_udp_addr = SocketAddr::new(send_udp_ip, 0);
 
-        let peer_udp_addr =
+        let negotiation =
             v5::client::socks5_udp_associate(&mut r, &mut w, &self.config.auth_info, send_udp_addr)
                 .await
-                .map_err(io::Error::other)?;
+                .map_err(TcpConnectError::from);
+        self.stats.peer_health.check_result(&peer, &negotiation);
+        if let Err(e) = &negotiation {
+            self.stats.tcp.add_connect_error(e);
+        }
+        let peer_udp_addr = negotiation.map_err(io::Error::other)?;
         let peer_udp_addr = self
             .config
             .transmute_udp_peer_addr(peer_udp_addr, peer_tcp_addr.ip());
@@ -175,12 +194,23 @@ impl ProxySocks5Escaper {
         ),
         io::Error,
     > {
-        tokio::time::timeout(
+        match tokio::time::timeout(
             self.config.peer_negotiation_timeout,
             self.socks5_udp_associate(buf_conf, tcp_notes, task_notes),
         )
         .await
-        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "peer negotiation timeout"))?
+        {
+            Ok(r) => r,
+            Err(_) => {
+                if let Some(peer) = &tcp_notes.next_peer {
+                    self.stats.peer_health.add_failure(peer);
+                }
+                Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "peer negotiation timeout",
+                ))
+            }
+        }
     }
 
     pub(super) async fn socks5_new_tcp_connection<'a>(
@@ -244,7 +274,9 @@ impl ProxySocks5Escaper {
                     tls_application,
                 }
                 .log(&self.escape_logger, &e);
-                Err(TcpConnectError::UpstreamTlsHandshakeFailed(e))
+                let e = TcpConnectError::UpstreamTlsHandshakeFailed(e);
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
             }
             Err(_) => {
                 let e = anyhow!("upstream tls handshake timed out");
@@ -256,7 +288,9 @@ impl ProxySocks5Escaper {
                     tls_application,
                 }
                 .log(&self.escape_logger, &e);
-                Err(TcpConnectError::UpstreamTlsHandshakeTimeout)
+                let e = TcpConnectError::UpstreamTlsHandshakeTimeout;
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
             }
         }
     }
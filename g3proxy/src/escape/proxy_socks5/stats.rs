@@ -19,12 +19,14 @@ use std::sync::Arc;
 use arc_swap::ArcSwapOption;
 
 use g3_daemon::stat::remote::TcpConnectionTaskRemoteStats;
+use g3_histogram::HistogramStats;
 use g3_io_ext::{LimitedReaderStats, LimitedWriterStats};
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::stats::{StatId, TcpIoSnapshot, UdpIoSnapshot};
 
 use crate::escape::{
-    EscaperInterfaceStats, EscaperInternalStats, EscaperStats, EscaperTcpStats, EscaperUdpStats,
+    EscaperInterfaceStats, EscaperInternalStats, EscaperPeerQuarantineSnapshot, EscaperStats,
+    EscaperTcpConnectErrorSnapshot, EscaperTcpStats, EscaperUdpStats, PeerHealthTracker,
 };
 use crate::module::http_forward::HttpForwardTaskRemoteStats;
 use crate::module::udp_connect::UdpConnectTaskRemoteStats;
@@ -37,6 +39,7 @@ pub(super) struct ProxySocks5EscaperStats {
     pub(super) interface: EscaperInterfaceStats,
     pub(super) udp: EscaperUdpStats,
     pub(super) tcp: EscaperTcpStats,
+    pub(super) peer_health: PeerHealthTracker,
 }
 
 impl ProxySocks5EscaperStats {
@@ -48,6 +51,7 @@ impl ProxySocks5EscaperStats {
             interface: EscaperInterfaceStats::default(),
             udp: EscaperUdpStats::default(),
             tcp: EscaperTcpStats::default(),
+            peer_health: PeerHealthTracker::default(),
         }
     }
 
@@ -101,9 +105,22 @@ impl EscaperStats for ProxySocks5EscaperStats {
         Some(self.tcp.io.snapshot())
     }
 
+    #[inline]
+    fn tcp_connect_duration_stats(&self) -> Option<Arc<HistogramStats>> {
+        self.tcp.connect_duration_stats()
+    }
+
+    fn tcp_connect_error_snapshot(&self) -> Option<EscaperTcpConnectErrorSnapshot> {
+        Some(self.tcp.connect_error_snapshot())
+    }
+
     fn udp_io_snapshot(&self) -> Option<UdpIoSnapshot> {
         Some(self.udp.io.snapshot())
     }
+
+    fn peer_quarantine_snapshot(&self) -> Option<EscaperPeerQuarantineSnapshot> {
+        self.peer_health.snapshot()
+    }
 }
 
 impl LimitedReaderStats for ProxySocks5EscaperStats {
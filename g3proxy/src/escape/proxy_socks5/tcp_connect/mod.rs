@@ -21,7 +21,7 @@ use tokio::task::JoinSet;
 use tokio::time::Instant;
 
 use g3_io_ext::{LimitedReader, LimitedWriter};
-use g3_types::net::{ConnectError, Host};
+use g3_types::net::{ConnectError, Host, UpstreamAddr};
 
 use super::ProxySocks5Escaper;
 use crate::log::escape::tcp_connect::EscapeLogForTcpConnect;
@@ -84,6 +84,7 @@ impl ProxySocks5Escaper {
                 tcp_notes.duration = instant_now.elapsed();
 
                 self.stats.tcp.add_connection_established();
+                self.stats.tcp.record_connect_duration(tcp_notes.duration);
                 let local_addr = ups_stream
                     .local_addr()
                     .map_err(TcpConnectError::SetupSocketFailed)?;
@@ -191,6 +192,7 @@ impl ProxySocks5Escaper {
                                 match r.0 {
                                     Ok(ups_stream) => {
                                         self.stats.tcp.add_connection_established();
+                                        self.stats.tcp.record_connect_duration(tcp_notes.duration);
                                         let local_addr = ups_stream
                                             .local_addr()
                                             .map_err(TcpConnectError::SetupSocketFailed)?;
@@ -266,10 +268,12 @@ impl ProxySocks5Escaper {
         &'a self,
         tcp_notes: &'a mut TcpConnectTaskNotes,
         task_notes: &'a ServerTaskNotes,
-    ) -> Result<TcpStream, TcpConnectError> {
-        let peer_proxy = self.get_next_proxy(task_notes, tcp_notes.upstream.host());
+    ) -> Result<(UpstreamAddr, TcpStream), TcpConnectError> {
+        let peer_proxy = self
+            .get_next_proxy(task_notes, tcp_notes.upstream.host())
+            .clone();
 
-        match peer_proxy.host() {
+        let r = match peer_proxy.host() {
             Host::Ip(ip) => {
                 self.fixed_try_connect(
                     SocketAddr::new(*ip, peer_proxy.port()),
@@ -278,11 +282,26 @@ impl ProxySocks5Escaper {
                 )
                 .await
             }
-            Host::Domain(domain) => {
-                let resolver_job = self.resolve_happy(domain)?;
+            Host::Domain(domain) => match self.resolve_happy(domain) {
+                Ok(resolver_job) => {
+                    self.happy_try_connect(resolver_job, peer_proxy.port(), tcp_notes, task_notes)
+                        .await
+                }
+                Err(e) => Err(e.into()),
+            },
+        };
 
-                self.happy_try_connect(resolver_job, peer_proxy.port(), tcp_notes, task_notes)
-                    .await
+        match r {
+            Ok(stream) => {
+                tcp_notes.next_peer = Some(peer_proxy.clone());
+                Ok((peer_proxy, stream))
+            }
+            Err(e) => {
+                if e.is_peer_failure() {
+                    self.stats.peer_health.add_failure(&peer_proxy);
+                }
+                self.stats.tcp.add_connect_error(&e);
+                Err(e)
             }
         }
     }
@@ -293,28 +312,33 @@ impl ProxySocks5Escaper {
         task_notes: &'a ServerTaskNotes,
     ) -> Result<
         (
+            UpstreamAddr,
             LimitedReader<tcp::OwnedReadHalf>,
             LimitedWriter<tcp::OwnedWriteHalf>,
         ),
         TcpConnectError,
     > {
-        let stream = self.tcp_connect_to(tcp_notes, task_notes).await?;
+        let (peer, stream) = self.tcp_connect_to(tcp_notes, task_notes).await?;
         let (r, w) = stream.into_split();
 
         let limit_config = &self.config.general.tcp_sock_speed_limit;
-        let r = LimitedReader::new(
+        let mut r = LimitedReader::new(
             r,
             limit_config.shift_millis,
             limit_config.max_south,
             self.stats.clone() as _,
         );
-        let w = LimitedWriter::new(
+        r.set_burst_limit(limit_config.burst_south);
+        self.tcp_global_limiter.apply_to_reader(&mut r);
+        let mut w = LimitedWriter::new(
             w,
             limit_config.shift_millis,
             limit_config.max_north,
             self.stats.clone() as _,
         );
+        w.set_burst_limit(limit_config.burst_north);
+        self.tcp_global_limiter.apply_to_writer(&mut w);
 
-        Ok((r, w))
+        Ok((peer, r, w))
     }
 }
@@ -58,20 +58,28 @@ impl ProxySocks5Escaper {
         let wrapper_stats = Arc::new(wrapper_stats);
 
         let (recv, send) = g3_io_ext::split_udp(udp_socket);
-        let recv = LimitedUdpRecv::new(
+        let mut recv = LimitedUdpRecv::new(
             recv,
             self.config.general.udp_sock_speed_limit.shift_millis,
             self.config.general.udp_sock_speed_limit.max_south_packets,
             self.config.general.udp_sock_speed_limit.max_south_bytes,
             wrapper_stats.clone() as _,
         );
-        let send = LimitedUdpSend::new(
+        recv.set_burst_limit(
+            self.config.general.udp_sock_speed_limit.burst_south_packets,
+            self.config.general.udp_sock_speed_limit.burst_south_bytes,
+        );
+        let mut send = LimitedUdpSend::new(
             send,
             self.config.general.udp_sock_speed_limit.shift_millis,
             self.config.general.udp_sock_speed_limit.max_north_packets,
             self.config.general.udp_sock_speed_limit.max_north_bytes,
             wrapper_stats as _,
         );
+        send.set_burst_limit(
+            self.config.general.udp_sock_speed_limit.burst_north_packets,
+            self.config.general.udp_sock_speed_limit.burst_north_bytes,
+        );
 
         let recv = ProxySocks5UdpConnectRemoteRecv::new(recv, tcp_close_receiver);
         let send = ProxySocks5UdpConnectRemoteSend::new(send, upstream);
@@ -30,6 +30,7 @@ use g3_geoip::{ContinentCode, IsoCountryCode};
 use g3_resolver::ResolveError;
 use g3_types::metrics::MetricsName;
 use g3_types::net::{Host, OpensslClientConfig, UpstreamAddr};
+use g3_types::resolve::{ResolvePickState, ResolveRedirection};
 
 use super::{ArcEscaper, Escaper, EscaperInternal, RouteEscaperStats};
 use crate::config::escaper::route_geoip::RouteGeoIpEscaperConfig;
@@ -56,6 +57,8 @@ pub(super) struct RouteGeoIpEscaper {
     config: RouteGeoIpEscaperConfig,
     stats: Arc<RouteEscaperStats>,
     resolver_handle: ArcIntegratedResolverHandle,
+    resolve_pick_state: Arc<ResolvePickState>,
+    resolve_redirection: Option<ResolveRedirection>,
     next_table: BTreeMap<MetricsName, ArcEscaper>,
     lpm_table: IpNetworkTable<ArcEscaper>,
     asn_table: FxHashMap<u32, ArcEscaper>,
@@ -74,6 +77,10 @@ impl RouteGeoIpEscaper {
         stats: Arc<RouteEscaperStats>,
     ) -> anyhow::Result<ArcEscaper> {
         let resolver_handle = crate::resolve::get_handle(config.resolver())?;
+        let resolve_redirection = config
+            .resolve_redirection
+            .as_ref()
+            .map(|builder| builder.build());
 
         let mut next_table = BTreeMap::new();
         if let Some(escapers) = config.dependent_escaper() {
@@ -127,6 +134,8 @@ impl RouteGeoIpEscaper {
             config,
             stats,
             resolver_handle,
+            resolve_pick_state: Arc::new(ResolvePickState::default()),
+            resolve_redirection,
             next_table,
             lpm_table,
             asn_table,
@@ -162,15 +171,29 @@ impl RouteGeoIpEscaper {
         match ups {
             Host::Ip(ip) => Ok(*ip),
             Host::Domain(domain) => {
-                let mut resolver_job = HappyEyeballsResolveJob::new_dyn(
-                    self.config.resolve_strategy,
-                    &self.resolver_handle,
-                    domain,
-                )?;
+                let redirected = self
+                    .resolve_redirection
+                    .as_ref()
+                    .and_then(|redirect| redirect.query_value(domain));
+                let mut resolver_job = if let Some(v) = redirected {
+                    HappyEyeballsResolveJob::new_redirected(
+                        self.config.resolve_strategy,
+                        &self.resolve_pick_state,
+                        &self.resolver_handle,
+                        v,
+                    )?
+                } else {
+                    HappyEyeballsResolveJob::new_dyn(
+                        self.config.resolve_strategy,
+                        &self.resolve_pick_state,
+                        &self.resolver_handle,
+                        domain,
+                    )?
+                };
                 let v = resolver_job
                     .get_r1_or_first(self.config.resolution_delay, usize::MAX)
                     .await?;
-                self.config.resolve_strategy.pick_best(v).ok_or_else(|| {
+                resolver_job.pick_best(v).ok_or_else(|| {
                     ResolveError::UnexpectedError(
                         "resolver job return ok but with no ip can be selected".to_string(),
                     )
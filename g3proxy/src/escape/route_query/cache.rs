@@ -115,8 +115,8 @@ pub(super) async fn spawn(config: &Arc<RouteQueryEscaperConfig>) -> anyhow::Resu
         g3_io_ext::spawn_effective_cache(config.cache_request_batch_count);
     let query_runtime = QueryRuntime::new(config, socket, query_handle);
 
-    tokio::spawn(query_runtime);
-    tokio::spawn(cache_runtime);
+    g3_daemon::runtime::spawn_named(&format!("escaper {} query", config.name), query_runtime);
+    g3_daemon::runtime::spawn_named(&format!("escaper {} cache", config.name), cache_runtime);
 
     Ok(CacheHandle {
         inner: cache_handle,
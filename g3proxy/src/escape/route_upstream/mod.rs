@@ -29,7 +29,7 @@ use g3_types::metrics::MetricsName;
 use g3_types::net::{Host, OpensslClientConfig, UpstreamAddr};
 
 use super::{ArcEscaper, Escaper, EscaperInternal, RouteEscaperStats};
-use crate::config::escaper::route_upstream::RouteUpstreamEscaperConfig;
+use crate::config::escaper::route_upstream::{RegexMatchRule, RouteUpstreamEscaperConfig};
 use crate::config::escaper::{AnyEscaperConfig, EscaperConfig};
 use crate::module::ftp_over_http::{
     AnyFtpConnectContextParam, ArcFtpTaskRemoteControlStats, ArcFtpTaskRemoteTransferStats,
@@ -59,6 +59,7 @@ pub(super) struct RouteUpstreamEscaper {
     child_match_domain: Trie<String, ArcEscaper>,
     do_radix_match: bool,
     radix_match_domain: Trie<String, ArcEscaper>,
+    regex_match_domain: Vec<(RegexMatchRule, ArcEscaper)>,
     default_next: ArcEscaper,
 }
 
@@ -120,6 +121,12 @@ impl RouteUpstreamEscaper {
             }
         }
 
+        let mut regex_match_domain = Vec::with_capacity(config.regex_match_domain.len());
+        for rule in &config.regex_match_domain {
+            let next = &next_table.get(&rule.next).unwrap();
+            regex_match_domain.push((rule.clone(), Arc::clone(next)));
+        }
+
         let escaper = RouteUpstreamEscaper {
             config,
             stats,
@@ -131,6 +138,7 @@ impl RouteUpstreamEscaper {
             child_match_domain,
             do_radix_match,
             radix_match_domain,
+            regex_match_domain,
             default_next,
         };
 
@@ -192,6 +200,12 @@ impl RouteUpstreamEscaper {
             }
         }
 
+        for (rule, escaper) in &self.regex_match_domain {
+            if rule.is_match(host) {
+                return Arc::clone(escaper);
+            }
+        }
+
         Arc::clone(&self.default_next)
     }
 
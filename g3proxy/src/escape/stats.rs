@@ -16,12 +16,18 @@
 
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use arc_swap::ArcSwapOption;
 
+use g3_histogram::{HistogramMetricsConfig, HistogramRecorder, HistogramStats};
+use g3_types::ext::DurationExt;
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
+use g3_types::net::ConnectError;
 use g3_types::stats::{StatId, TcpIoSnapshot, TcpIoStats, UdpIoSnapshot, UdpIoStats};
 
+use crate::module::tcp_connect::TcpConnectError;
+
 pub(crate) trait EscaperInternalStats {
     fn add_http_forward_request_attempted(&self);
     fn add_https_forward_request_attempted(&self);
@@ -51,6 +57,22 @@ pub(crate) trait EscaperStats: EscaperInternalStats {
     fn forbidden_snapshot(&self) -> Option<EscaperForbiddenSnapshot> {
         None
     }
+
+    fn tls_session_snapshot(&self) -> Option<EscaperTlsSessionSnapshot> {
+        None
+    }
+
+    fn peer_quarantine_snapshot(&self) -> Option<EscaperPeerQuarantineSnapshot> {
+        None
+    }
+
+    fn tcp_connect_duration_stats(&self) -> Option<Arc<HistogramStats>> {
+        None
+    }
+
+    fn tcp_connect_error_snapshot(&self) -> Option<EscaperTcpConnectErrorSnapshot> {
+        None
+    }
 }
 
 pub(crate) type ArcEscaperInternalStats = Arc<dyn EscaperInternalStats + Send + Sync>;
@@ -78,6 +100,40 @@ impl EscaperForbiddenStats {
     }
 }
 
+#[derive(Default)]
+pub(crate) struct EscaperTlsSessionSnapshot {
+    pub(crate) handshake: u64,
+    pub(crate) resumed: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct EscaperTlsSessionStats {
+    handshake: AtomicU64,
+    resumed: AtomicU64,
+}
+
+impl EscaperTlsSessionStats {
+    pub(crate) fn add_handshake(&self, session_reused: bool) {
+        self.handshake.fetch_add(1, Ordering::Relaxed);
+        if session_reused {
+            self.resumed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> EscaperTlsSessionSnapshot {
+        EscaperTlsSessionSnapshot {
+            handshake: self.handshake.load(Ordering::Relaxed),
+            resumed: self.resumed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct EscaperPeerQuarantineSnapshot {
+    pub(crate) total: u64,
+    pub(crate) current: u64,
+}
+
 #[derive(Default)]
 pub(crate) struct EscaperInterfaceStats {
     tcp_connect_attempted: AtomicU64,
@@ -159,10 +215,67 @@ impl EscaperInterfaceStats {
     }
 }
 
+#[derive(Default)]
+pub(crate) struct EscaperTcpConnectErrorSnapshot {
+    pub(crate) resolve_failed: u64,
+    pub(crate) connect_timeout: u64,
+    pub(crate) connect_refused: u64,
+    pub(crate) connect_failed: u64,
+    pub(crate) peer_auth_failed: u64,
+    pub(crate) tls_handshake_failed: u64,
+}
+
+#[derive(Default)]
+struct EscaperTcpConnectErrorStats {
+    resolve_failed: AtomicU64,
+    connect_timeout: AtomicU64,
+    connect_refused: AtomicU64,
+    connect_failed: AtomicU64,
+    peer_auth_failed: AtomicU64,
+    tls_handshake_failed: AtomicU64,
+}
+
+impl EscaperTcpConnectErrorStats {
+    fn add(&self, e: &TcpConnectError) {
+        let counter = match e {
+            TcpConnectError::ResolveFailed(_) => &self.resolve_failed,
+            TcpConnectError::TimeoutByRule
+            | TcpConnectError::ConnectFailed(ConnectError::TimedOut) => &self.connect_timeout,
+            TcpConnectError::ConnectFailed(
+                ConnectError::ConnectionRefused | ConnectError::ConnectionReset,
+            ) => &self.connect_refused,
+            TcpConnectError::ConnectFailed(_) | TcpConnectError::NoAddressConnected => {
+                &self.connect_failed
+            }
+            TcpConnectError::NegotiationAuthFailed => &self.peer_auth_failed,
+            TcpConnectError::PeerTlsHandshakeTimeout
+            | TcpConnectError::PeerTlsHandshakeFailed(_)
+            | TcpConnectError::UpstreamTlsHandshakeTimeout
+            | TcpConnectError::UpstreamTlsHandshakeFailed(_) => &self.tls_handshake_failed,
+            _ => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> EscaperTcpConnectErrorSnapshot {
+        EscaperTcpConnectErrorSnapshot {
+            resolve_failed: self.resolve_failed.load(Ordering::Relaxed),
+            connect_timeout: self.connect_timeout.load(Ordering::Relaxed),
+            connect_refused: self.connect_refused.load(Ordering::Relaxed),
+            connect_failed: self.connect_failed.load(Ordering::Relaxed),
+            peer_auth_failed: self.peer_auth_failed.load(Ordering::Relaxed),
+            tls_handshake_failed: self.tls_handshake_failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
 #[derive(Default)]
 pub(crate) struct EscaperTcpStats {
     connection_attempted: AtomicU64,
     connection_established: AtomicU64,
+    connect_error: EscaperTcpConnectErrorStats,
+    connect_duration_recorder: ArcSwapOption<HistogramRecorder<u64>>,
+    connect_duration_stats: ArcSwapOption<HistogramStats>,
     pub(crate) io: TcpIoStats,
 }
 
@@ -182,6 +295,39 @@ impl EscaperTcpStats {
     pub(crate) fn get_connection_established(&self) -> u64 {
         self.connection_established.load(Ordering::Relaxed)
     }
+
+    /// count the error by category, errors that are not caused by the remote side will be ignored
+    pub(crate) fn add_connect_error(&self, e: &TcpConnectError) {
+        self.connect_error.add(e);
+    }
+
+    pub(crate) fn connect_error_snapshot(&self) -> EscaperTcpConnectErrorSnapshot {
+        self.connect_error.snapshot()
+    }
+
+    pub(crate) fn set_connect_duration_config(&self, config: Option<&HistogramMetricsConfig>) {
+        match config {
+            Some(config) => {
+                let (r, s) = config.build_spawned(g3_daemon::runtime::main_handle().cloned());
+                self.connect_duration_recorder.store(Some(Arc::new(r)));
+                self.connect_duration_stats.store(Some(s));
+            }
+            None => {
+                self.connect_duration_recorder.store(None);
+                self.connect_duration_stats.store(None);
+            }
+        }
+    }
+
+    pub(crate) fn record_connect_duration(&self, dur: Duration) {
+        if let Some(recorder) = &*self.connect_duration_recorder.load() {
+            let _ = recorder.record(dur.as_nanos_u64());
+        }
+    }
+
+    pub(crate) fn connect_duration_stats(&self) -> Option<Arc<HistogramStats>> {
+        self.connect_duration_stats.load_full()
+    }
 }
 
 #[derive(Default)]
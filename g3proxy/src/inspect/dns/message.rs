@@ -0,0 +1,240 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+const HEADER_SIZE: usize = 12;
+
+const FLAG_QR: u16 = 0x8000;
+const FLAG_OPCODE_MASK: u16 = 0x7800;
+const FLAG_RD: u16 = 0x0100;
+const FLAG_RA: u16 = 0x0080;
+const RCODE_NXDOMAIN: u16 = 3;
+
+pub(super) struct DnsQuery {
+    id: u16,
+    flags: u16,
+    pub(super) qname: String,
+    pub(super) qtype: u16,
+    question_end: usize,
+}
+
+impl DnsQuery {
+    /// parse a query message that contains exactly one question
+    pub(super) fn parse(msg: &[u8]) -> Result<Self, &'static str> {
+        if msg.len() < HEADER_SIZE {
+            return Err("too short header");
+        }
+        let id = u16::from_be_bytes([msg[0], msg[1]]);
+        let flags = u16::from_be_bytes([msg[2], msg[3]]);
+        if flags & FLAG_QR != 0 {
+            return Err("not a query");
+        }
+        let qd_count = u16::from_be_bytes([msg[4], msg[5]]);
+        if qd_count != 1 {
+            return Err("unsupported question count");
+        }
+
+        let mut qname = String::with_capacity(64);
+        let mut offset = HEADER_SIZE;
+        loop {
+            let Some(len) = msg.get(offset) else {
+                return Err("truncated qname");
+            };
+            let len = *len as usize;
+            offset += 1;
+            if len == 0 {
+                break;
+            }
+            if len & 0xC0 != 0 {
+                return Err("unsupported label type in qname");
+            }
+            let Some(label) = msg.get(offset..offset + len) else {
+                return Err("truncated qname");
+            };
+            if !qname.is_empty() {
+                qname.push('.');
+            }
+            for c in label {
+                qname.push(c.to_ascii_lowercase() as char);
+            }
+            offset += len;
+        }
+        if qname.is_empty() {
+            qname.push('.');
+        }
+
+        let Some(qtype) = msg.get(offset..offset + 4) else {
+            return Err("truncated question");
+        };
+        let qtype = u16::from_be_bytes([qtype[0], qtype[1]]);
+
+        Ok(DnsQuery {
+            id,
+            flags,
+            qname,
+            qtype,
+            question_end: offset + 4,
+        })
+    }
+
+    /// build a NXDOMAIN response with the length prefix used over stream transports
+    pub(super) fn build_nxdomain(&self, msg: &[u8]) -> Vec<u8> {
+        let question = &msg[HEADER_SIZE..self.question_end];
+        let rsp_len = HEADER_SIZE + question.len();
+
+        let mut rsp = Vec::with_capacity(rsp_len + 2);
+        rsp.extend_from_slice(&(rsp_len as u16).to_be_bytes());
+        rsp.extend_from_slice(&self.id.to_be_bytes());
+        let flags = FLAG_QR
+            | (self.flags & FLAG_OPCODE_MASK)
+            | (self.flags & FLAG_RD)
+            | FLAG_RA
+            | RCODE_NXDOMAIN;
+        rsp.extend_from_slice(&flags.to_be_bytes());
+        rsp.extend_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        rsp.extend_from_slice(question);
+        rsp
+    }
+}
+
+pub(super) fn qtype_name(qtype: u16) -> String {
+    let s = match qtype {
+        1 => "A",
+        2 => "NS",
+        5 => "CNAME",
+        6 => "SOA",
+        12 => "PTR",
+        15 => "MX",
+        16 => "TXT",
+        28 => "AAAA",
+        33 => "SRV",
+        64 => "SVCB",
+        65 => "HTTPS",
+        255 => "ANY",
+        _ => return format!("TYPE{qtype}"),
+    };
+    s.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUESTION: &[u8] = &[
+        0x03, b'w', b'w', b'w', 0x07, b'E', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o',
+        b'm', 0x00, // qname
+        0x00, 0x01, // qtype A
+        0x00, 0x01, // qclass IN
+    ];
+
+    fn query(flags: u16, qd_count: u16, question: &[u8]) -> Vec<u8> {
+        let mut msg = vec![0x12, 0x34];
+        msg.extend_from_slice(&flags.to_be_bytes());
+        msg.extend_from_slice(&qd_count.to_be_bytes());
+        // no answer and authority, one additional
+        msg.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x01]);
+        msg.extend_from_slice(question);
+        // edns opt record
+        msg.extend_from_slice(&[
+            0x00, 0x00, 0x29, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ]);
+        msg
+    }
+
+    #[test]
+    fn parse_query() {
+        let msg = query(0x0100, 1, QUESTION);
+        let q = DnsQuery::parse(&msg).unwrap();
+        assert_eq!(q.id, 0x1234);
+        assert_eq!(q.qname, "www.example.com");
+        assert_eq!(q.qtype, 1);
+        assert_eq!(q.question_end, HEADER_SIZE + QUESTION.len());
+
+        let msg = query(0x0100, 1, &[0x00, 0x00, 0x02, 0x00, 0x01]);
+        let q = DnsQuery::parse(&msg).unwrap();
+        assert_eq!(q.qname, ".");
+        assert_eq!(q.qtype, 2);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(
+            DnsQuery::parse(&[0x12, 0x34, 0x01, 0x00]).err(),
+            Some("too short header")
+        );
+
+        let msg = query(0x8180, 1, QUESTION);
+        assert_eq!(DnsQuery::parse(&msg).err(), Some("not a query"));
+
+        let msg = query(0x0100, 0, QUESTION);
+        assert_eq!(
+            DnsQuery::parse(&msg).err(),
+            Some("unsupported question count")
+        );
+        let msg = query(0x0100, 2, QUESTION);
+        assert_eq!(
+            DnsQuery::parse(&msg).err(),
+            Some("unsupported question count")
+        );
+
+        // compression pointer in qname
+        let msg = query(0x0100, 1, &[0xc0, 0x0c, 0x00, 0x01, 0x00, 0x01]);
+        assert_eq!(
+            DnsQuery::parse(&msg).err(),
+            Some("unsupported label type in qname")
+        );
+
+        let mut msg = query(0x0100, 1, &[]);
+        msg.truncate(HEADER_SIZE);
+        msg.extend_from_slice(&QUESTION[..10]);
+        assert_eq!(DnsQuery::parse(&msg).err(), Some("truncated qname"));
+
+        let mut msg = query(0x0100, 1, &[]);
+        msg.truncate(HEADER_SIZE);
+        msg.extend_from_slice(&QUESTION[..19]);
+        assert_eq!(DnsQuery::parse(&msg).err(), Some("truncated question"));
+    }
+
+    #[test]
+    fn nxdomain() {
+        let msg = query(0x0100, 1, QUESTION);
+        let q = DnsQuery::parse(&msg).unwrap();
+        let rsp = q.build_nxdomain(&msg);
+
+        let mut expected = vec![
+            0x00, 0x21, // length prefix
+            0x12, 0x34, // id
+            0x81, 0x83, // QR RD RA NXDOMAIN
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        // the additional section is not copied
+        expected.extend_from_slice(QUESTION);
+        assert_eq!(rsp, expected);
+
+        // keep the opcode and do not set RD if not desired
+        let msg = query(0x0800, 1, QUESTION);
+        let q = DnsQuery::parse(&msg).unwrap();
+        let rsp = q.build_nxdomain(&msg);
+        assert_eq!(&rsp[4..6], &[0x88, 0x83]);
+    }
+
+    #[test]
+    fn qtype() {
+        assert_eq!(qtype_name(1), "A");
+        assert_eq!(qtype_name(28), "AAAA");
+        assert_eq!(qtype_name(65), "HTTPS");
+        assert_eq!(qtype_name(99), "TYPE99");
+    }
+}
@@ -0,0 +1,305 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::time::Duration;
+
+use slog::slog_info;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use g3_dpi::{DnsInterceptionConfig, Protocol};
+use g3_io_ext::FlexBufReader;
+use g3_slog_types::{LtUpstreamAddr, LtUuid};
+use g3_types::net::UpstreamAddr;
+
+use crate::config::server::ServerConfig;
+use crate::inspect::{BoxAsyncRead, BoxAsyncWrite, InterceptionError, StreamInspectContext};
+use crate::serve::ServerTaskResult;
+
+mod error;
+pub(crate) use error::DnsInterceptionError;
+
+mod message;
+use message::DnsQuery;
+
+macro_rules! intercept_log {
+    ($obj:tt, $($args:tt)+) => {
+        slog_info!($obj.ctx.intercept_logger(), $($args)+;
+            "intercept_type" => "DnsStream",
+            "task_id" => LtUuid($obj.ctx.server_task_id()),
+            "depth" => $obj.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr(&$obj.upstream),
+        )
+    };
+}
+
+enum ReadableSide {
+    Client,
+    Upstream,
+    ClientClosed,
+    UpstreamClosed,
+    Idle,
+}
+
+struct DnsInterceptIo {
+    clt_r: FlexBufReader<BoxAsyncRead>,
+    clt_w: BoxAsyncWrite,
+    ups_r: FlexBufReader<BoxAsyncRead>,
+    ups_w: BoxAsyncWrite,
+}
+
+pub(crate) struct DnsInterceptObject<SC: ServerConfig> {
+    io: Option<DnsInterceptIo>,
+    ctx: StreamInspectContext<SC>,
+    upstream: UpstreamAddr,
+    config: DnsInterceptionConfig,
+}
+
+impl<SC: ServerConfig> DnsInterceptObject<SC> {
+    pub(crate) fn new(ctx: StreamInspectContext<SC>, upstream: UpstreamAddr) -> Self {
+        let config = ctx.dns_interception().clone();
+        DnsInterceptObject {
+            io: None,
+            ctx,
+            upstream,
+            config,
+        }
+    }
+
+    pub(crate) fn set_io(
+        &mut self,
+        clt_r: FlexBufReader<BoxAsyncRead>,
+        clt_w: BoxAsyncWrite,
+        ups_r: FlexBufReader<BoxAsyncRead>,
+        ups_w: BoxAsyncWrite,
+    ) {
+        let io = DnsInterceptIo {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        };
+        self.io = Some(io);
+    }
+
+    pub(crate) async fn intercept(mut self) -> ServerTaskResult<()> {
+        match self.do_intercept().await {
+            Ok(_) => {
+                intercept_log!(self, "finished");
+                Ok(())
+            }
+            Err(e) => {
+                intercept_log!(self, "{e}");
+                Err(InterceptionError::Dns(e).into_server_task_error(Protocol::Dns))
+            }
+        }
+    }
+
+    async fn do_intercept(&mut self) -> Result<(), DnsInterceptionError> {
+        let mut io = self.io.take().unwrap();
+        let mut clt_closed = false;
+
+        loop {
+            self.check_cancel()?;
+
+            match self.wait_readable(&mut io, clt_closed).await? {
+                ReadableSide::Client => {
+                    let msg = read_message(&mut io.clt_r, self.config.message_read_timeout)
+                        .await
+                        .map_err(DnsInterceptionError::ClientReadFailed)?
+                        .ok_or(DnsInterceptionError::ClientReadTimeout)?;
+                    self.handle_query(&mut io, msg).await?;
+                }
+                ReadableSide::Upstream => {
+                    let msg = read_message(&mut io.ups_r, self.config.message_read_timeout)
+                        .await
+                        .map_err(DnsInterceptionError::UpstreamReadFailed)?
+                        .ok_or(DnsInterceptionError::UpstreamReadTimeout)?;
+                    write_message(&mut io.clt_w, &msg)
+                        .await
+                        .map_err(DnsInterceptionError::ClientWriteFailed)?;
+                }
+                ReadableSide::ClientClosed => {
+                    // let the upstream finish the pending responses
+                    clt_closed = true;
+                    io.ups_w
+                        .shutdown()
+                        .await
+                        .map_err(DnsInterceptionError::UpstreamWriteFailed)?;
+                }
+                ReadableSide::UpstreamClosed | ReadableSide::Idle => return Ok(()),
+            }
+        }
+    }
+
+    async fn handle_query(
+        &self,
+        io: &mut DnsInterceptIo,
+        msg: Vec<u8>,
+    ) -> Result<(), DnsInterceptionError> {
+        let query =
+            DnsQuery::parse(&msg[2..]).map_err(DnsInterceptionError::InvalidQueryMessage)?;
+        let blocked = self.ctx.dns_domain_blocked(&query.qname);
+        if self.config.log_queries || blocked {
+            intercept_log!(self, "dns query";
+                "qname" => &query.qname,
+                "qtype" => message::qtype_name(query.qtype),
+                "blocked" => blocked,
+            );
+        }
+
+        if blocked {
+            let rsp = query.build_nxdomain(&msg[2..]);
+            io.clt_w
+                .write_all(&rsp)
+                .await
+                .map_err(DnsInterceptionError::ClientWriteFailed)?;
+            io.clt_w
+                .flush()
+                .await
+                .map_err(DnsInterceptionError::ClientWriteFailed)
+        } else {
+            write_message(&mut io.ups_w, &msg)
+                .await
+                .map_err(DnsInterceptionError::UpstreamWriteFailed)
+        }
+    }
+
+    async fn wait_readable(
+        &self,
+        io: &mut DnsInterceptIo,
+        clt_closed: bool,
+    ) -> Result<ReadableSide, DnsInterceptionError> {
+        let DnsInterceptIo { clt_r, ups_r, .. } = io;
+        let wait = async {
+            tokio::select! {
+                biased;
+
+                r = ups_r.fill_buf() => {
+                    r.map(|b| {
+                        if b.is_empty() {
+                            ReadableSide::UpstreamClosed
+                        } else {
+                            ReadableSide::Upstream
+                        }
+                    })
+                    .map_err(DnsInterceptionError::UpstreamReadFailed)
+                }
+                r = clt_r.fill_buf(), if !clt_closed => {
+                    r.map(|b| {
+                        if b.is_empty() {
+                            ReadableSide::ClientClosed
+                        } else {
+                            ReadableSide::Client
+                        }
+                    })
+                    .map_err(DnsInterceptionError::ClientReadFailed)
+                }
+            }
+        };
+        match tokio::time::timeout(self.config.idle_timeout, wait).await {
+            Ok(r) => r,
+            Err(_) => Ok(ReadableSide::Idle),
+        }
+    }
+
+    fn check_cancel(&self) -> Result<(), DnsInterceptionError> {
+        if self.ctx.belongs_to_blocked_user() {
+            return Err(DnsInterceptionError::CanceledAsUserBlocked);
+        }
+        if self.ctx.server_quit_policy.force_quit() {
+            return Err(DnsInterceptionError::CanceledAsServerQuit);
+        }
+        Ok(())
+    }
+}
+
+/// read a length prefixed message, the returned buffer contains the length prefix
+async fn read_message<R>(reader: &mut R, timeout: Duration) -> io::Result<Option<Vec<u8>>>
+where
+    R: AsyncRead + Unpin,
+{
+    let read = async {
+        let mut len_buf = [0u8; 2];
+        reader.read_exact(&mut len_buf).await?;
+        let len = u16::from_be_bytes(len_buf) as usize;
+        let mut msg = vec![0u8; len + 2];
+        msg[0..2].copy_from_slice(&len_buf);
+        reader.read_exact(&mut msg[2..]).await?;
+        Ok(msg)
+    };
+    match tokio::time::timeout(timeout, read).await {
+        Ok(r) => r.map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+async fn write_message<W>(writer: &mut W, msg: &[u8]) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    writer.write_all(msg).await?;
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn read_messages() {
+        let data: &[u8] = &[0x00, 0x03, 0x01, 0x02, 0x03, 0x00, 0x00, 0x00, 0x01, 0xff];
+        let mut reader = data;
+        let timeout = Duration::from_secs(1);
+
+        let msg = read_message(&mut reader, timeout).await.unwrap().unwrap();
+        assert_eq!(msg, [0x00, 0x03, 0x01, 0x02, 0x03]);
+        let msg = read_message(&mut reader, timeout).await.unwrap().unwrap();
+        assert_eq!(msg, [0x00, 0x00]);
+        let msg = read_message(&mut reader, timeout).await.unwrap().unwrap();
+        assert_eq!(msg, [0x00, 0x01, 0xff]);
+
+        let e = read_message(&mut reader, timeout).await.unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[tokio::test]
+    async fn read_truncated() {
+        let data: &[u8] = &[0x00, 0x04, 0x01, 0x02];
+        let mut reader = data;
+        let e = read_message(&mut reader, Duration::from_secs(1))
+            .await
+            .unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[tokio::test]
+    async fn read_timeout() {
+        let (mut reader, mut writer) = tokio::io::duplex(64);
+        writer.write_all(&[0x00, 0x04, 0x01]).await.unwrap();
+        let r = read_message(&mut reader, Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert!(r.is_none());
+    }
+
+    #[tokio::test]
+    async fn forward_message() {
+        let mut buf = Vec::new();
+        write_message(&mut buf, &[0x00, 0x01, 0xff]).await.unwrap();
+        assert_eq!(buf, [0x00, 0x01, 0xff]);
+    }
+}
@@ -28,6 +28,16 @@ pub(crate) enum InterceptionError {
     H1(super::http::H1InterceptionError),
     #[error("http2: {0}")]
     H2(super::http::H2InterceptionError),
+    #[error("smtp: {0}")]
+    Smtp(super::smtp::SmtpInterceptionError),
+    #[error("imap: {0}")]
+    Imap(super::imap::ImapInterceptionError),
+    #[error("pop3: {0}")]
+    Pop3(super::pop3::Pop3InterceptionError),
+    #[error("ftp: {0}")]
+    Ftp(super::ftp::FtpInterceptionError),
+    #[error("dns: {0}")]
+    Dns(super::dns::DnsInterceptionError),
 }
 
 impl InterceptionError {
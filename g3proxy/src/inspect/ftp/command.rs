@@ -0,0 +1,171 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::str::FromStr;
+
+pub(super) struct FtpCommandLine<'a> {
+    pub(super) verb: String,
+    pub(super) args: &'a str,
+}
+
+impl<'a> FtpCommandLine<'a> {
+    pub(super) fn parse(line: &'a [u8]) -> Self {
+        let line = std::str::from_utf8(line).unwrap_or_default().trim_end();
+        match line.split_once(' ') {
+            Some((verb, args)) => FtpCommandLine {
+                verb: verb.to_ascii_uppercase(),
+                args,
+            },
+            None => FtpCommandLine {
+                verb: line.to_ascii_uppercase(),
+                args: "",
+            },
+        }
+    }
+
+    /// check if this command will use the data connection, see RFC 959 and RFC 3659
+    pub(super) fn use_data_channel(&self) -> bool {
+        matches!(
+            self.verb.as_str(),
+            "RETR" | "STOR" | "STOU" | "APPE" | "LIST" | "NLST" | "MLSD"
+        )
+    }
+}
+
+/// get the server address in the 227 reply to PASV
+///
+/// The format is like `227 Entering Passive Mode (h1,h2,h3,h4,p1,p2)`,
+/// but the parentheses may be absent.
+pub(super) fn parse_pasv_reply(line: &[u8]) -> Option<SocketAddr> {
+    let line = std::str::from_utf8(line).ok()?;
+    let text = line.get(4..)?;
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let text = &text[start..];
+    let end = text
+        .find(|c: char| !c.is_ascii_digit() && c != ',')
+        .unwrap_or(text.len());
+
+    let mut numbers = [0u8; 6];
+    let mut iter = text[..end].split(',');
+    for n in numbers.iter_mut() {
+        *n = u8::from_str(iter.next()?).ok()?;
+    }
+    if iter.next().is_some() {
+        return None;
+    }
+    let ip = Ipv4Addr::new(numbers[0], numbers[1], numbers[2], numbers[3]);
+    let port = u16::from_be_bytes([numbers[4], numbers[5]]);
+    Some(SocketAddr::V4(SocketAddrV4::new(ip, port)))
+}
+
+/// get the server port in the 229 reply to EPSV
+///
+/// The format is like `229 Entering Extended Passive Mode (|||port|)`, see RFC 2428.
+pub(super) fn parse_epsv_reply(line: &[u8]) -> Option<u16> {
+    let line = std::str::from_utf8(line).ok()?;
+    let start = line.find('(')?;
+    let text = &line[start + 1..];
+    let end = text.find(')')?;
+    let text = &text[..end];
+    let delimiter = text.chars().next()?;
+    let mut iter = text.split(delimiter);
+    // the net-prt and the net-addr should be empty
+    let _ = iter.next();
+    let _ = iter.next();
+    let _ = iter.next();
+    u16::from_str(iter.next()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command() {
+        let cmd = FtpCommandLine::parse(b"user anonymous\r\n");
+        assert_eq!(cmd.verb, "USER");
+        assert_eq!(cmd.args, "anonymous");
+
+        let cmd = FtpCommandLine::parse(b"RETR dir/file name.txt\r\n");
+        assert_eq!(cmd.verb, "RETR");
+        assert_eq!(cmd.args, "dir/file name.txt");
+
+        let cmd = FtpCommandLine::parse(b"pasv\r\n");
+        assert_eq!(cmd.verb, "PASV");
+        assert_eq!(cmd.args, "");
+    }
+
+    #[test]
+    fn data_channel() {
+        for verb in ["RETR", "STOR", "STOU", "APPE", "LIST", "NLST", "MLSD"] {
+            let line = format!("{verb} a\r\n");
+            assert!(FtpCommandLine::parse(line.as_bytes()).use_data_channel());
+        }
+        for verb in [
+            "USER", "PASS", "CWD", "PASV", "EPSV", "PORT", "MLST", "SIZE",
+        ] {
+            let line = format!("{verb} a\r\n");
+            assert!(!FtpCommandLine::parse(line.as_bytes()).use_data_channel());
+        }
+    }
+
+    #[test]
+    fn pasv_reply() {
+        assert_eq!(
+            parse_pasv_reply(b"227 Entering Passive Mode (192,168,1,2,195,80).\r\n"),
+            Some(SocketAddr::from_str("192.168.1.2:50000").unwrap())
+        );
+        assert_eq!(
+            parse_pasv_reply(b"227 Entering Passive Mode 10,0,0,1,0,21\r\n"),
+            Some(SocketAddr::from_str("10.0.0.1:21").unwrap())
+        );
+        assert_eq!(
+            parse_pasv_reply(b"227 =127,0,0,1,4,1\r\n"),
+            Some(SocketAddr::from_str("127.0.0.1:1025").unwrap())
+        );
+
+        assert!(
+            parse_pasv_reply(b"227 Entering Passive Mode (192,168,1,256,195,80)\r\n").is_none()
+        );
+        assert!(parse_pasv_reply(b"227 Entering Passive Mode (192,168,1,2,195)\r\n").is_none());
+        assert!(
+            parse_pasv_reply(b"227 Entering Passive Mode (192,168,1,2,195,80,1)\r\n").is_none()
+        );
+        assert!(parse_pasv_reply(b"227 Entering Passive Mode (192,168,,2,195,80)\r\n").is_none());
+        assert!(parse_pasv_reply(b"227 Entering Passive Mode\r\n").is_none());
+        assert!(parse_pasv_reply(b"227").is_none());
+    }
+
+    #[test]
+    fn epsv_reply() {
+        assert_eq!(
+            parse_epsv_reply(b"229 Entering Extended Passive Mode (|||6446|)\r\n"),
+            Some(6446)
+        );
+        assert_eq!(
+            parse_epsv_reply(b"229 Entering Extended Passive Mode (!!!21!)\r\n"),
+            Some(21)
+        );
+
+        assert!(parse_epsv_reply(b"229 Entering Extended Passive Mode (|||70000|)\r\n").is_none());
+        assert!(parse_epsv_reply(b"229 Entering Extended Passive Mode (|||port|)\r\n").is_none());
+        assert!(parse_epsv_reply(b"229 Entering Extended Passive Mode (||6446|)\r\n").is_none());
+        assert!(parse_epsv_reply(b"229 Entering Extended Passive Mode |||6446|\r\n").is_none());
+        assert!(parse_epsv_reply(b"229 Entering Extended Passive Mode (|||6446|\r\n").is_none());
+        assert!(parse_epsv_reply(b"229 Entering Extended Passive Mode ()\r\n").is_none());
+    }
+}
@@ -0,0 +1,308 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::io;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use slog::slog_info;
+use tokio::io::{AsyncRead, ReadBuf};
+use uuid::Uuid;
+
+use g3_dpi::Protocol;
+use g3_slog_types::{LtUpstreamAddr, LtUuid};
+use g3_types::net::UpstreamAddr;
+
+use crate::config::server::ServerConfig;
+use crate::inspect::{BoxAsyncRead, BoxAsyncWrite, StreamInspectContext};
+use crate::log::inspect::{stream::StreamInspectLog, InspectSource};
+use crate::serve::ServerTaskResult;
+
+struct ExpectedDataChannel {
+    expire: Instant,
+    transfer: Arc<FtpDataTransfer>,
+}
+
+static PASSIVE_DATA_CHANNELS: Lazy<Mutex<HashMap<(IpAddr, UpstreamAddr), ExpectedDataChannel>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub(crate) struct FtpDataTransfer {
+    control_task_id: Uuid,
+    upload_size: AtomicU64,
+    download_size: AtomicU64,
+}
+
+impl FtpDataTransfer {
+    pub(super) fn new(control_task_id: Uuid) -> Self {
+        FtpDataTransfer {
+            control_task_id,
+            upload_size: AtomicU64::new(0),
+            download_size: AtomicU64::new(0),
+        }
+    }
+
+    pub(super) fn upload_size(&self) -> u64 {
+        self.upload_size.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn download_size(&self) -> u64 {
+        self.download_size.load(Ordering::Relaxed)
+    }
+}
+
+/// expect the data connection to the passive server address from the same client
+pub(super) fn expect_passive_channel(
+    client_ip: IpAddr,
+    upstreams: Vec<UpstreamAddr>,
+    transfer: &Arc<FtpDataTransfer>,
+    timeout: Duration,
+) {
+    let now = Instant::now();
+    let expire = now + timeout;
+    let mut channels = PASSIVE_DATA_CHANNELS.lock().unwrap();
+    channels.retain(|_, v| v.expire > now);
+    for upstream in upstreams {
+        channels.insert(
+            (client_ip, upstream),
+            ExpectedDataChannel {
+                expire,
+                transfer: transfer.clone(),
+            },
+        );
+    }
+}
+
+pub(super) fn cancel_passive_channel(transfer: &Arc<FtpDataTransfer>) {
+    let mut channels = PASSIVE_DATA_CHANNELS.lock().unwrap();
+    channels.retain(|_, v| !Arc::ptr_eq(&v.transfer, transfer));
+}
+
+/// take the expected data connection, which should be used only once
+pub(crate) fn take_passive_channel(
+    client_ip: IpAddr,
+    upstream: &UpstreamAddr,
+) -> Option<Arc<FtpDataTransfer>> {
+    let mut channels = PASSIVE_DATA_CHANNELS.lock().unwrap();
+    let channel = channels.remove(&(client_ip, upstream.clone()))?;
+    channels.retain(|_, v| !Arc::ptr_eq(&v.transfer, &channel.transfer));
+    if channel.expire > Instant::now() {
+        Some(channel.transfer)
+    } else {
+        None
+    }
+}
+
+struct FtpDataCountReader<R> {
+    inner: R,
+    counter: Arc<FtpDataTransfer>,
+    upload: bool,
+}
+
+impl<R> AsyncRead for FtpDataCountReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let offset = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        let nr = (buf.filled().len() - offset) as u64;
+        if self.upload {
+            self.counter.upload_size.fetch_add(nr, Ordering::Relaxed);
+        } else {
+            self.counter.download_size.fetch_add(nr, Ordering::Relaxed);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// transit the data connection of a passive mode transfer, the data size will be
+/// recorded for the log of the control connection
+pub(crate) async fn transit_passive_channel<SC>(
+    mut ctx: StreamInspectContext<SC>,
+    upstream: &UpstreamAddr,
+    transfer: Arc<FtpDataTransfer>,
+    clt_r: BoxAsyncRead,
+    clt_w: BoxAsyncWrite,
+    ups_r: BoxAsyncRead,
+    ups_w: BoxAsyncWrite,
+) -> ServerTaskResult<()>
+where
+    SC: ServerConfig + Send + Sync + 'static,
+{
+    ctx.increase_inspection_depth();
+    StreamInspectLog::new(&ctx).log(InspectSource::FtpPassive, Protocol::Unknown);
+
+    let clt_r = FtpDataCountReader {
+        inner: clt_r,
+        counter: transfer.clone(),
+        upload: true,
+    };
+    let ups_r = FtpDataCountReader {
+        inner: ups_r,
+        counter: transfer.clone(),
+        upload: false,
+    };
+    let r = ctx.transit_transparent(clt_r, clt_w, ups_r, ups_w).await;
+
+    slog_info!(ctx.intercept_logger(), "finished";
+        "intercept_type" => "FtpDataChannel",
+        "task_id" => LtUuid(ctx.server_task_id()),
+        "depth" => ctx.inspection_depth,
+        "upstream" => LtUpstreamAddr(upstream),
+        "control_task_id" => LtUuid(&transfer.control_task_id),
+        "upload_size" => transfer.upload_size(),
+        "download_size" => transfer.download_size(),
+    );
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    use tokio::io::AsyncReadExt;
+
+    // use different client ips in each test, as the channels are stored globally
+    fn client_ip(n: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(192, 0, 2, n))
+    }
+
+    fn upstream(s: &str) -> UpstreamAddr {
+        UpstreamAddr::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn take_once() {
+        let ip = client_ip(1);
+        let transfer = Arc::new(FtpDataTransfer::new(Uuid::new_v4()));
+        expect_passive_channel(
+            ip,
+            vec![
+                upstream("10.0.0.1:50000"),
+                upstream("ftp.example.net:50000"),
+            ],
+            &transfer,
+            Duration::from_secs(60),
+        );
+
+        let found = take_passive_channel(ip, &upstream("ftp.example.net:50000")).unwrap();
+        assert!(Arc::ptr_eq(&found, &transfer));
+        // all the addresses of the same transfer should be removed
+        assert!(take_passive_channel(ip, &upstream("10.0.0.1:50000")).is_none());
+        assert!(take_passive_channel(ip, &upstream("ftp.example.net:50000")).is_none());
+    }
+
+    #[test]
+    fn mismatch() {
+        let ip = client_ip(2);
+        let transfer = Arc::new(FtpDataTransfer::new(Uuid::new_v4()));
+        expect_passive_channel(
+            ip,
+            vec![upstream("10.0.0.2:50000")],
+            &transfer,
+            Duration::from_secs(60),
+        );
+
+        assert!(take_passive_channel(client_ip(3), &upstream("10.0.0.2:50000")).is_none());
+        assert!(take_passive_channel(ip, &upstream("10.0.0.2:50001")).is_none());
+        assert!(take_passive_channel(ip, &upstream("10.0.0.3:50000")).is_none());
+        assert!(take_passive_channel(ip, &upstream("10.0.0.2:50000")).is_some());
+    }
+
+    #[test]
+    fn cancel() {
+        let ip = client_ip(4);
+        let transfer = Arc::new(FtpDataTransfer::new(Uuid::new_v4()));
+        expect_passive_channel(
+            ip,
+            vec![upstream("10.0.0.4:50000")],
+            &transfer,
+            Duration::from_secs(60),
+        );
+        cancel_passive_channel(&transfer);
+        assert!(take_passive_channel(ip, &upstream("10.0.0.4:50000")).is_none());
+    }
+
+    #[test]
+    fn replace() {
+        let ip = client_ip(5);
+        let old = Arc::new(FtpDataTransfer::new(Uuid::new_v4()));
+        let new = Arc::new(FtpDataTransfer::new(Uuid::new_v4()));
+        expect_passive_channel(
+            ip,
+            vec![upstream("10.0.0.5:50000")],
+            &old,
+            Duration::from_secs(60),
+        );
+        expect_passive_channel(
+            ip,
+            vec![upstream("10.0.0.5:50000")],
+            &new,
+            Duration::from_secs(60),
+        );
+        let found = take_passive_channel(ip, &upstream("10.0.0.5:50000")).unwrap();
+        assert!(Arc::ptr_eq(&found, &new));
+    }
+
+    #[test]
+    fn expired() {
+        let ip = client_ip(6);
+        let transfer = Arc::new(FtpDataTransfer::new(Uuid::new_v4()));
+        expect_passive_channel(
+            ip,
+            vec![upstream("10.0.0.6:50000")],
+            &transfer,
+            Duration::ZERO,
+        );
+        assert!(take_passive_channel(ip, &upstream("10.0.0.6:50000")).is_none());
+    }
+
+    #[tokio::test]
+    async fn count_size() {
+        let transfer = Arc::new(FtpDataTransfer::new(Uuid::new_v4()));
+
+        let mut upload = FtpDataCountReader {
+            inner: &b"stor data"[..],
+            counter: transfer.clone(),
+            upload: true,
+        };
+        let mut buf = Vec::new();
+        upload.read_to_end(&mut buf).await.unwrap();
+
+        let mut download = FtpDataCountReader {
+            inner: &b"retr"[..],
+            counter: transfer.clone(),
+            upload: false,
+        };
+        buf.clear();
+        download.read_to_end(&mut buf).await.unwrap();
+
+        assert_eq!(transfer.upload_size(), 9);
+        assert_eq!(transfer.download_size(), 4);
+    }
+}
@@ -0,0 +1,53 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub(crate) enum FtpInterceptionError {
+    #[error("read from client failed: {0:?}")]
+    ClientReadFailed(io::Error),
+    #[error("write to client failed: {0:?}")]
+    ClientWriteFailed(io::Error),
+    #[error("read from upstream failed: {0:?}")]
+    UpstreamReadFailed(io::Error),
+    #[error("write to upstream failed: {0:?}")]
+    UpstreamWriteFailed(io::Error),
+    #[error("closed by client")]
+    ClosedByClient,
+    #[error("closed by upstream")]
+    ClosedByUpstream,
+    #[error("too long command line, should be less than {0}")]
+    TooLongCommandLine(usize),
+    #[error("too long response line, should be less than {0}")]
+    TooLongResponseLine(usize),
+    #[error("too many response lines")]
+    TooManyResponseLines,
+    #[error("invalid response line")]
+    InvalidResponseLine,
+    #[error("timeout to read from client")]
+    ClientReadTimeout,
+    #[error("timeout to receive upstream response")]
+    UpstreamResponseTimeout,
+    #[error("unexpected pipelined data after AUTH TLS")]
+    UnexpectedDataAfterAuthTls,
+    #[error("canceled as user blocked")]
+    CanceledAsUserBlocked,
+    #[error("canceled as server quit")]
+    CanceledAsServerQuit,
+}
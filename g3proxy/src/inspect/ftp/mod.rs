@@ -0,0 +1,543 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use slog::slog_info;
+use tokio::io::{AsyncBufRead, AsyncWriteExt};
+
+use g3_dpi::{FtpInterceptionConfig, Protocol};
+use g3_io_ext::{FlexBufReader, LimitedBufReadExt, OnceBufReader};
+use g3_slog_types::{LtUpstreamAddr, LtUuid};
+use g3_types::net::UpstreamAddr;
+
+use crate::config::server::ServerConfig;
+use crate::inspect::{
+    BoxAsyncRead, BoxAsyncWrite, InterceptionError, StreamInspectContext, StreamInspection,
+};
+use crate::log::inspect::{stream::StreamInspectLog, InspectSource};
+use crate::serve::ServerTaskResult;
+
+mod error;
+pub(crate) use error::FtpInterceptionError;
+
+mod command;
+use command::FtpCommandLine;
+
+mod data;
+use data::FtpDataTransfer;
+pub(crate) use data::{take_passive_channel, transit_passive_channel};
+
+const REPLY_MAX_LINES: usize = 128;
+
+macro_rules! intercept_log {
+    ($obj:tt, $($args:tt)+) => {
+        slog_info!($obj.ctx.intercept_logger(), $($args)+;
+            "intercept_type" => "FtpConnection",
+            "task_id" => LtUuid($obj.ctx.server_task_id()),
+            "depth" => $obj.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr(&$obj.upstream),
+        )
+    };
+}
+
+struct FtpReply {
+    code: u16,
+    raw: Vec<u8>,
+}
+
+struct FtpInterceptIo {
+    clt_r: FlexBufReader<BoxAsyncRead>,
+    clt_w: BoxAsyncWrite,
+    ups_r: FlexBufReader<BoxAsyncRead>,
+    ups_w: BoxAsyncWrite,
+}
+
+pub(crate) struct FtpInterceptObject<SC: ServerConfig> {
+    io: Option<FtpInterceptIo>,
+    ctx: StreamInspectContext<SC>,
+    upstream: UpstreamAddr,
+    config: FtpInterceptionConfig,
+    from_starttls: bool,
+    ftp_user: String,
+    data_transfer: Option<Arc<FtpDataTransfer>>,
+}
+
+impl<SC: ServerConfig> FtpInterceptObject<SC> {
+    pub(crate) fn new(ctx: StreamInspectContext<SC>, upstream: UpstreamAddr) -> Self {
+        let config = ctx.ftp_interception().clone();
+        FtpInterceptObject {
+            io: None,
+            ctx,
+            upstream,
+            config,
+            from_starttls: false,
+            ftp_user: String::new(),
+            data_transfer: None,
+        }
+    }
+
+    /// the server greeting has already been sent before AUTH TLS
+    pub(crate) fn set_from_starttls(&mut self) {
+        self.from_starttls = true;
+    }
+
+    pub(crate) fn set_io(
+        &mut self,
+        clt_r: FlexBufReader<BoxAsyncRead>,
+        clt_w: BoxAsyncWrite,
+        ups_r: FlexBufReader<BoxAsyncRead>,
+        ups_w: BoxAsyncWrite,
+    ) {
+        let io = FtpInterceptIo {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        };
+        self.io = Some(io);
+    }
+
+    fn expect_passive_channel(&mut self, upstreams: Vec<UpstreamAddr>) {
+        self.cancel_passive_channel();
+        let transfer = Arc::new(FtpDataTransfer::new(*self.ctx.server_task_id()));
+        data::expect_passive_channel(
+            self.ctx.task_notes.client_addr.ip(),
+            upstreams,
+            &transfer,
+            self.config.data_channel_wait_timeout,
+        );
+        self.data_transfer = Some(transfer);
+    }
+
+    fn cancel_passive_channel(&mut self) {
+        if let Some(transfer) = self.data_transfer.take() {
+            data::cancel_passive_channel(&transfer);
+        }
+    }
+}
+
+impl<SC> FtpInterceptObject<SC>
+where
+    SC: ServerConfig + Send + Sync + 'static,
+{
+    pub(crate) async fn intercept(mut self) -> ServerTaskResult<Option<StreamInspection<SC>>> {
+        let r = self.do_intercept().await;
+        self.cancel_passive_channel();
+        match r {
+            Ok(v) => {
+                intercept_log!(self, "finished");
+                Ok(v)
+            }
+            Err(e) => {
+                intercept_log!(self, "{e}");
+                Err(InterceptionError::Ftp(e).into_server_task_error(Protocol::FtpControl))
+            }
+        }
+    }
+
+    async fn do_intercept(&mut self) -> Result<Option<StreamInspection<SC>>, FtpInterceptionError> {
+        let mut io = self.io.take().unwrap();
+
+        if !self.from_starttls {
+            loop {
+                let reply = self
+                    .recv_reply(&mut io.ups_r, self.config.greeting_timeout)
+                    .await?;
+                send_to_client(&mut io.clt_w, &reply.raw).await?;
+                match reply.code {
+                    120 => {} // service ready in nnn minutes
+                    220 => break,
+                    _ => return Ok(None),
+                }
+            }
+        }
+
+        let mut line = Vec::with_capacity(256);
+        loop {
+            self.check_cancel()?;
+
+            line.clear();
+            self.read_client_line(&mut io.clt_r, &mut line).await?;
+            let cmd = FtpCommandLine::parse(&line);
+            match cmd.verb.as_str() {
+                "USER" => {
+                    let reply = self.relay_command(&mut io, &line).await?;
+                    if reply.code / 100 != 5 {
+                        self.ftp_user = cmd.args.to_string();
+                    }
+                }
+                "AUTH" => {
+                    let reply = self.relay_command(&mut io, &line).await?;
+                    if reply.code == 234 {
+                        return self.start_tls(io).map(Some);
+                    }
+                }
+                "PASV" => {
+                    // the data connection should be expected before the reply is sent to client
+                    let reply = self.send_command(&mut io, &line).await?;
+                    if reply.code == 227 {
+                        if let Some(addr) = command::parse_pasv_reply(&reply.raw) {
+                            // clients may connect to the control host if the address is private
+                            let upstreams = vec![
+                                UpstreamAddr::from(addr),
+                                UpstreamAddr::new(self.upstream.host().clone(), addr.port()),
+                            ];
+                            self.expect_passive_channel(upstreams);
+                        }
+                    }
+                    send_to_client(&mut io.clt_w, &reply.raw).await?;
+                }
+                "EPSV" => {
+                    let reply = self.send_command(&mut io, &line).await?;
+                    if reply.code == 229 {
+                        if let Some(port) = command::parse_epsv_reply(&reply.raw) {
+                            let upstreams =
+                                vec![UpstreamAddr::new(self.upstream.host().clone(), port)];
+                            self.expect_passive_channel(upstreams);
+                        }
+                    }
+                    send_to_client(&mut io.clt_w, &reply.raw).await?;
+                }
+                "PORT" | "EPRT" => {
+                    // the data connection in active mode will not be correlated
+                    self.cancel_passive_channel();
+                    self.relay_command(&mut io, &line).await?;
+                }
+                "QUIT" => {
+                    self.relay_command(&mut io, &line).await?;
+                    return Ok(None);
+                }
+                _ => {
+                    if cmd.use_data_channel() {
+                        self.relay_transfer(&mut io, &line, &cmd).await?;
+                    } else {
+                        self.relay_command(&mut io, &line).await?;
+                    }
+                }
+            }
+        }
+    }
+
+    fn log_transfer(&self, cmd: &FtpCommandLine<'_>, reply_code: u16) {
+        let (passive, upload_size, download_size) = match &self.data_transfer {
+            Some(transfer) => (true, transfer.upload_size(), transfer.download_size()),
+            None => (false, 0, 0),
+        };
+        slog_info!(self.ctx.intercept_logger(), "ftp transfer";
+            "intercept_type" => "FtpConnection",
+            "task_id" => LtUuid(self.ctx.server_task_id()),
+            "depth" => self.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr(&self.upstream),
+            "ftp_user" => &self.ftp_user,
+            "command" => &cmd.verb,
+            "path" => cmd.args,
+            "reply_code" => reply_code,
+            "passive" => passive,
+            "upload_size" => upload_size,
+            "download_size" => download_size,
+        );
+    }
+
+    fn check_cancel(&self) -> Result<(), FtpInterceptionError> {
+        if self.ctx.belongs_to_blocked_user() {
+            return Err(FtpInterceptionError::CanceledAsUserBlocked);
+        }
+        if self.ctx.server_quit_policy.force_quit() {
+            return Err(FtpInterceptionError::CanceledAsServerQuit);
+        }
+        Ok(())
+    }
+
+    fn start_tls(
+        &mut self,
+        io: FtpInterceptIo,
+    ) -> Result<StreamInspection<SC>, FtpInterceptionError> {
+        let FtpInterceptIo {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        } = io;
+        if !clt_r.buffer().is_empty() || !ups_r.buffer().is_empty() {
+            // no data should be sent before the TLS handshake, see RFC 4217 section 4
+            return Err(FtpInterceptionError::UnexpectedDataAfterAuthTls);
+        }
+        let clt_r = clt_r.into_inner();
+        let ups_r = ups_r.into_inner();
+
+        let mut ctx = self.ctx.clone();
+        ctx.increase_inspection_depth();
+        if let Some(tls_interception) = ctx.tls_interception() {
+            StreamInspectLog::new(&ctx).log(InspectSource::StartTls, Protocol::TlsModern);
+            let mut tls_obj = crate::inspect::tls::TlsInterceptObject::new(
+                ctx,
+                self.upstream.clone(),
+                tls_interception,
+            );
+            tls_obj.set_io(OnceBufReader::with_no_buf(clt_r), clt_w, ups_r, ups_w);
+            tls_obj.set_inner_protocol(Protocol::FtpControl);
+            Ok(StreamInspection::TlsModern(tls_obj))
+        } else {
+            StreamInspectLog::new(&ctx).log(InspectSource::StartTls, Protocol::Unknown);
+            let mut stream_obj =
+                crate::inspect::stream::StreamInspectObject::new(ctx, self.upstream.clone());
+            stream_obj.set_io(clt_r, clt_w, ups_r, ups_w);
+            Ok(StreamInspection::StreamUnknown(stream_obj))
+        }
+    }
+
+    async fn read_client_line(
+        &self,
+        clt_r: &mut FlexBufReader<BoxAsyncRead>,
+        line: &mut Vec<u8>,
+    ) -> Result<(), FtpInterceptionError> {
+        let max_size = self.config.command_line_max_size;
+        match tokio::time::timeout(
+            self.config.command_wait_timeout,
+            clt_r.limited_read_until(b'\n', max_size, line),
+        )
+        .await
+        {
+            Ok(Ok((found, nr))) => {
+                if found {
+                    Ok(())
+                } else if nr < max_size {
+                    Err(FtpInterceptionError::ClosedByClient)
+                } else {
+                    Err(FtpInterceptionError::TooLongCommandLine(max_size))
+                }
+            }
+            Ok(Err(e)) => Err(FtpInterceptionError::ClientReadFailed(e)),
+            Err(_) => Err(FtpInterceptionError::ClientReadTimeout),
+        }
+    }
+
+    async fn recv_reply(
+        &self,
+        ups_r: &mut FlexBufReader<BoxAsyncRead>,
+        timeout: Duration,
+    ) -> Result<FtpReply, FtpInterceptionError> {
+        let max_size = self.config.response_line_max_size;
+        match tokio::time::timeout(timeout, read_reply(ups_r, max_size)).await {
+            Ok(r) => r,
+            Err(_) => Err(FtpInterceptionError::UpstreamResponseTimeout),
+        }
+    }
+
+    async fn send_command(
+        &self,
+        io: &mut FtpInterceptIo,
+        line: &[u8],
+    ) -> Result<FtpReply, FtpInterceptionError> {
+        send_to_upstream(&mut io.ups_w, line).await?;
+        self.recv_reply(&mut io.ups_r, self.config.response_wait_timeout)
+            .await
+    }
+
+    async fn relay_command(
+        &self,
+        io: &mut FtpInterceptIo,
+        line: &[u8],
+    ) -> Result<FtpReply, FtpInterceptionError> {
+        send_to_upstream(&mut io.ups_w, line).await?;
+        let mut reply = self
+            .recv_reply(&mut io.ups_r, self.config.response_wait_timeout)
+            .await?;
+        send_to_client(&mut io.clt_w, &reply.raw).await?;
+        while reply.code / 100 == 1 {
+            // relay the final reply after the preliminary reply
+            reply = self
+                .recv_reply(&mut io.ups_r, self.config.transfer_end_wait_timeout)
+                .await?;
+            send_to_client(&mut io.clt_w, &reply.raw).await?;
+        }
+        Ok(reply)
+    }
+
+    async fn relay_transfer(
+        &mut self,
+        io: &mut FtpInterceptIo,
+        line: &[u8],
+        cmd: &FtpCommandLine<'_>,
+    ) -> Result<(), FtpInterceptionError> {
+        let reply = self.relay_command(io, line).await?;
+        self.log_transfer(cmd, reply.code);
+        // each passive data connection is used for only one transfer
+        self.cancel_passive_channel();
+        Ok(())
+    }
+}
+
+/// read a complete (maybe multi-line) reply, see RFC 959 section 4.2
+async fn read_reply<R>(ups_r: &mut R, max_size: usize) -> Result<FtpReply, FtpInterceptionError>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut raw = Vec::with_capacity(256);
+    let mut code: Option<u16> = None;
+    for _ in 0..REPLY_MAX_LINES {
+        let offset = raw.len();
+        let (found, nr) = ups_r
+            .limited_read_until(b'\n', max_size, &mut raw)
+            .await
+            .map_err(FtpInterceptionError::UpstreamReadFailed)?;
+        if !found {
+            return if nr < max_size {
+                Err(FtpInterceptionError::ClosedByUpstream)
+            } else {
+                Err(FtpInterceptionError::TooLongResponseLine(max_size))
+            };
+        }
+
+        let line = &raw[offset..];
+        let line_code = if line.len() >= 4 && line[..3].iter().all(|c| c.is_ascii_digit()) {
+            Some(
+                line[..3]
+                    .iter()
+                    .fold(0u16, |acc, c| acc * 10 + (*c - b'0') as u16),
+            )
+        } else {
+            None
+        };
+        match code {
+            Some(code) => {
+                // the multi-line reply ends with the line starting with the same code, see RFC 959 section 4.2
+                if line_code == Some(code) && line[3] == b' ' {
+                    return Ok(FtpReply { code, raw });
+                }
+            }
+            None => {
+                let Some(line_code) = line_code else {
+                    return Err(FtpInterceptionError::InvalidResponseLine);
+                };
+                if line[3] != b'-' {
+                    return Ok(FtpReply {
+                        code: line_code,
+                        raw,
+                    });
+                }
+                code = Some(line_code);
+            }
+        }
+    }
+    Err(FtpInterceptionError::TooManyResponseLines)
+}
+
+async fn send_to_client(
+    clt_w: &mut BoxAsyncWrite,
+    data: &[u8],
+) -> Result<(), FtpInterceptionError> {
+    clt_w
+        .write_all(data)
+        .await
+        .map_err(FtpInterceptionError::ClientWriteFailed)?;
+    clt_w
+        .flush()
+        .await
+        .map_err(FtpInterceptionError::ClientWriteFailed)
+}
+
+async fn send_to_upstream(
+    ups_w: &mut BoxAsyncWrite,
+    data: &[u8],
+) -> Result<(), FtpInterceptionError> {
+    ups_w
+        .write_all(data)
+        .await
+        .map_err(FtpInterceptionError::UpstreamWriteFailed)?;
+    ups_w
+        .flush()
+        .await
+        .map_err(FtpInterceptionError::UpstreamWriteFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read(data: &[u8], max_size: usize) -> Result<FtpReply, FtpInterceptionError> {
+        let mut reader = data;
+        read_reply(&mut reader, max_size).await
+    }
+
+    #[tokio::test]
+    async fn single_line_reply() {
+        let rsp = read(b"220 FTP server ready\r\n", 1024).await.unwrap();
+        assert_eq!(rsp.code, 220);
+        assert_eq!(rsp.raw, b"220 FTP server ready\r\n");
+    }
+
+    #[tokio::test]
+    async fn multi_line_reply() {
+        // example in RFC 959 section 4.2
+        const DATA: &[u8] = b"123-First line\r\nSecond line\r\n  234 A line beginning with numbers\r\n123 The last line\r\n";
+        let mut reader = DATA.repeat(2);
+        reader.extend_from_slice(b"226 done\r\n");
+        let mut reader = reader.as_slice();
+
+        let rsp = read_reply(&mut reader, 1024).await.unwrap();
+        assert_eq!(rsp.code, 123);
+        assert_eq!(rsp.raw, DATA);
+        let rsp = read_reply(&mut reader, 1024).await.unwrap();
+        assert_eq!(rsp.code, 123);
+        let rsp = read_reply(&mut reader, 1024).await.unwrap();
+        assert_eq!(rsp.code, 226);
+    }
+
+    #[tokio::test]
+    async fn multi_line_end() {
+        // the end line should have the same code and a space after it
+        const DATA: &[u8] = b"211-Features:\r\n MDTM\r\n211-more\r\n200 not end\r\n211 End\r\n";
+        let rsp = read(DATA, 1024).await.unwrap();
+        assert_eq!(rsp.code, 211);
+        assert_eq!(rsp.raw, DATA);
+    }
+
+    #[tokio::test]
+    async fn invalid_reply() {
+        let r = read(b"22 ready\r\n", 1024).await;
+        assert!(matches!(r, Err(FtpInterceptionError::InvalidResponseLine)));
+
+        let r = read(b"ready\r\n", 1024).await;
+        assert!(matches!(r, Err(FtpInterceptionError::InvalidResponseLine)));
+    }
+
+    #[tokio::test]
+    async fn incomplete_reply() {
+        let r = read(b"220 ready", 1024).await;
+        assert!(matches!(r, Err(FtpInterceptionError::ClosedByUpstream)));
+
+        let r = read(b"211-Features:\r\n MDTM\r\n", 1024).await;
+        assert!(matches!(r, Err(FtpInterceptionError::ClosedByUpstream)));
+    }
+
+    #[tokio::test]
+    async fn too_long_reply() {
+        let r = read(b"220 a very long reply line\r\n", 8).await;
+        assert!(matches!(
+            r,
+            Err(FtpInterceptionError::TooLongResponseLine(8))
+        ));
+
+        let mut data = b"211-start\r\n".to_vec();
+        data.extend_from_slice(&b" line\r\n".repeat(REPLY_MAX_LINES));
+        data.extend_from_slice(b"211 end\r\n");
+        let r = read(&data, 1024).await;
+        assert!(matches!(r, Err(FtpInterceptionError::TooManyResponseLines)));
+    }
+}
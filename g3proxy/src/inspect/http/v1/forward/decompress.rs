@@ -0,0 +1,57 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::Read;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+#[derive(Clone, Copy)]
+pub(super) enum ContentCoding {
+    Gzip,
+    Deflate,
+    Brotli,
+}
+
+impl ContentCoding {
+    /// Get the supported content coding from the Content-Encoding header value
+    pub(super) fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(ContentCoding::Gzip),
+            "deflate" => Some(ContentCoding::Deflate),
+            "br" => Some(ContentCoding::Brotli),
+            _ => None,
+        }
+    }
+
+    /// Decode the body, None will be returned if the decode failed or the decoded size is too large
+    pub(super) fn decode(self, body: &[u8], max_decoded_size: usize) -> Option<Vec<u8>> {
+        let reader: Box<dyn Read + '_> = match self {
+            ContentCoding::Gzip => Box::new(GzDecoder::new(body)),
+            ContentCoding::Deflate => Box::new(ZlibDecoder::new(body)),
+            ContentCoding::Brotli => Box::new(brotli::Decompressor::new(body, 4096)),
+        };
+
+        let mut decoded = Vec::with_capacity((body.len() * 4).min(max_decoded_size));
+        reader
+            .take(max_decoded_size as u64 + 1)
+            .read_to_end(&mut decoded)
+            .ok()?;
+        if decoded.len() > max_decoded_size {
+            return None;
+        }
+        Some(decoded)
+    }
+}
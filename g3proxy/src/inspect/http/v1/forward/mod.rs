@@ -20,8 +20,9 @@ use anyhow::anyhow;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use futures_util::FutureExt;
+use serde_json::{json, Value};
 use slog::slog_info;
-use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::time::Instant;
 
 use g3_http::client::HttpTransparentResponse;
@@ -40,13 +41,20 @@ use g3_slog_types::{LtDateTime, LtDuration, LtHttpMethod, LtHttpUri, LtUuid};
 use g3_types::net::HttpHeaderMap;
 
 use super::{HttpRequest, HttpRequestIo, HttpResponseIo};
+use crate::audit::har;
+use crate::audit::{ClamavScanner, ContentFilter};
 use crate::config::server::ServerConfig;
 use crate::inspect::StreamInspectContext;
 use crate::module::http_forward::HttpProxyClientResponse;
-use crate::serve::{ServerIdleChecker, ServerTaskError, ServerTaskResult};
+use crate::serve::{
+    ServerIdleChecker, ServerTaskError, ServerTaskForbiddenError, ServerTaskResult,
+};
 
 mod adaptation;
 
+mod decompress;
+use decompress::ContentCoding;
+
 macro_rules! intercept_log {
     ($obj:tt, $($args:tt)+) => {
         slog_info!($obj.ctx.intercept_logger(), $($args)+;
@@ -128,6 +136,7 @@ pub(super) struct H1ForwardTask<'a, SC: ServerConfig> {
     send_error_response: bool,
     should_close: bool,
     http_notes: HttpForwardTaskNotes,
+    har_response: Option<Value>,
 }
 
 impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
@@ -145,9 +154,75 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
             send_error_response: true,
             should_close,
             http_notes,
+            har_response: None,
         }
     }
 
+    /// Export the HAR entry of this transaction if enabled
+    pub(super) fn export_har(&mut self) {
+        let Some(exporter) = self.ctx.audit_handle.har_exporter() else {
+            return;
+        };
+
+        let url = match &self.req.host {
+            Some(host) if self.req.uri.scheme().is_none() => {
+                format!("http://{}{}", host, self.req.uri)
+            }
+            _ => self.req.uri.to_string(),
+        };
+        let req_body_size = match self.req.body_type() {
+            Some(HttpBodyType::ContentLength(size)) => size as i64,
+            Some(_) => -1,
+            None => 0,
+        };
+        let response = self.har_response.take().unwrap_or_else(|| {
+            json!({
+                "status": self.http_notes.rsp_status,
+                "statusText": "",
+                "httpVersion": har::har_version(self.req.version),
+                "cookies": [],
+                "headers": [],
+                "content": {"size": 0, "mimeType": ""},
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": -1,
+            })
+        });
+
+        let notes = &self.http_notes;
+        let send = notes
+            .dur_req_send_all
+            .saturating_sub(notes.dur_req_send_hdr);
+        let wait = notes
+            .dur_rsp_recv_hdr
+            .saturating_sub(notes.dur_req_send_all);
+        let receive = notes
+            .dur_rsp_recv_all
+            .saturating_sub(notes.dur_rsp_recv_hdr);
+        let entry = json!({
+            "startedDateTime": notes.receive_datetime.to_rfc3339(),
+            "time": har::har_time(notes.dur_rsp_recv_all),
+            "request": {
+                "method": self.req.method.as_str(),
+                "url": url,
+                "httpVersion": har::har_version(self.req.version),
+                "cookies": [],
+                "headers": har::har_headers(&self.req.end_to_end_headers),
+                "queryString": [],
+                "headersSize": -1,
+                "bodySize": req_body_size,
+            },
+            "response": response,
+            "cache": {},
+            "timings": {
+                "send": har::har_time(send),
+                "wait": har::har_time(wait),
+                "receive": har::har_time(receive),
+            },
+        });
+        exporter.add_entry(self.ctx.server_task_id(), entry);
+    }
+
     #[inline]
     pub(super) fn should_close(&self) -> bool {
         self.should_close
@@ -179,6 +254,27 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
         }
     }
 
+    pub(super) async fn reply_blocked<CW>(&mut self, category: &str, clt_w: &mut CW)
+    where
+        CW: AsyncWrite + Unpin,
+    {
+        self.should_close = true;
+        let e = ServerTaskError::ForbiddenByRule(ServerTaskForbiddenError::ContentBlocked);
+        self.reply_task_err(&e, clt_w).await;
+        intercept_log!(self, "blocked by url category {category}");
+    }
+
+    pub(super) async fn reply_body_too_large<CW>(&mut self, clt_w: &mut CW)
+    where
+        CW: AsyncWrite + Unpin,
+    {
+        // the request body will not be read
+        self.should_close = true;
+        let e = ServerTaskError::ForbiddenByRule(ServerTaskForbiddenError::BodyTooLarge);
+        self.reply_task_err(&e, clt_w).await;
+        intercept_log!(self, "{e}");
+    }
+
     pub(super) async fn forward_without_body<UR, CW>(&mut self, rsp_io: &mut HttpResponseIo<UR, CW>)
     where
         UR: AsyncRead + Unpin,
@@ -223,9 +319,12 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
                 adapter
             }
             Err(e) => {
+                let icap_stats = self.ctx.audit_handle.stats().icap_reqmod();
                 if reqmod_client.bypass() {
+                    icap_stats.add_bypassed();
                     self.forward_with_io(req_io, rsp_io).await;
                 } else {
+                    icap_stats.add_error();
                     let e = ServerTaskError::InternalAdapterError(e);
                     self.reply_task_err(&e, &mut rsp_io.clt_w).await;
                     intercept_log!(self, "{e:?}");
@@ -340,18 +439,28 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
                     }
                 }
                 r = &mut adaptation_fut => {
+                    let icap_stats = self.ctx.audit_handle.stats().icap_reqmod();
                     match r {
                         Ok(ReqmodAdaptationEndState::OriginalTransferred) => {
+                            icap_stats.add_no_modification();
                             break;
                         }
                         Ok(ReqmodAdaptationEndState::AdaptedTransferred(_r)) => {
                             // TODO add log for adapted request?
+                            icap_stats.add_adapted();
                             break;
                         }
                         Ok(ReqmodAdaptationEndState::HttpErrResponse(rsp, rsp_recv_body)) => {
+                            icap_stats.add_blocked();
                             return self.send_adaptation_error_response(&mut rsp_io.clt_w, rsp, rsp_recv_body).await;
                         }
-                        Err(e) => return Err(e.into()),
+                        Err(e) => {
+                            let e = ServerTaskError::from(e);
+                            if matches!(e, ServerTaskError::InternalAdapterError(_)) {
+                                icap_stats.add_error();
+                            }
+                            return Err(e);
+                        }
                     }
                 }
             }
@@ -623,7 +732,7 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
     async fn send_response<UR, CW>(
         &mut self,
         mut rsp: HttpTransparentResponse,
-        rsp_head: Bytes,
+        mut rsp_head: Bytes,
         rsp_io: &mut HttpResponseIo<UR, CW>,
         adaptation_respond_shared_headers: Option<HttpHeaderMap>,
     ) -> ServerTaskResult<()>
@@ -641,7 +750,117 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
         self.http_notes.rsp_status = 0;
         self.http_notes.mark_rsp_recv_hdr();
 
-        if let Some(respmod) = self.ctx.audit_handle.icap_respmod_client() {
+        let audit_handle = self.ctx.audit_handle.clone();
+        let host = self.req.host.as_ref().map(|v| v.host());
+        let path = self
+            .req
+            .uri
+            .path_and_query()
+            .map(|v| v.as_str())
+            .unwrap_or("/");
+        if audit_handle.rewrite_http_response_headers(host, path, &mut rsp.end_to_end_headers) {
+            rsp_head = Bytes::from(rsp.serialize());
+        }
+        let content_type = rsp
+            .end_to_end_headers
+            .get(http::header::CONTENT_TYPE)
+            .map(|v| v.to_str());
+        let content_length = match rsp.body_type(&self.req.method) {
+            Some(HttpBodyType::ContentLength(size)) => Some(size),
+            _ => None,
+        };
+        if audit_handle.har_exporter().is_some() {
+            let body_size = match rsp.body_type(&self.req.method) {
+                Some(HttpBodyType::ContentLength(size)) => size as i64,
+                Some(_) => -1,
+                None => 0,
+            };
+            self.har_response = Some(json!({
+                "status": rsp.code,
+                "statusText": rsp.reason,
+                "httpVersion": har::har_version(rsp.version),
+                "cookies": [],
+                "headers": har::har_headers(&rsp.end_to_end_headers),
+                "content": {
+                    "size": body_size.max(0),
+                    "mimeType": content_type.unwrap_or_default(),
+                },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": body_size,
+            }));
+        }
+        let respmod_client = audit_handle
+            .icap_respmod_client()
+            .filter(|_| !audit_handle.icap_respmod_bypassed(content_type, content_length));
+        if respmod_client.is_some() && audit_handle.icap_respmod_body_blocked(content_length) {
+            // the response body will not be read
+            self.should_close = true;
+            return Err(ServerTaskError::ForbiddenByRule(
+                ServerTaskForbiddenError::BodyTooLarge,
+            ));
+        }
+
+        if let Some(filter) = audit_handle.content_filter() {
+            if let Some(content_type) = content_type {
+                if filter.check_content_type(content_type) {
+                    // the response body will not be read
+                    self.should_close = true;
+                    return Err(ServerTaskError::ForbiddenByRule(
+                        ServerTaskForbiddenError::ContentBlocked,
+                    ));
+                }
+            }
+        }
+
+        // body check is left to the icap server if respmod is enabled
+        if let (None, Some(size)) = (&respmod_client, content_length) {
+            let filter = audit_handle
+                .content_filter()
+                .filter(|f| f.has_body_rules() && size <= f.body_inspect_max_size() as u64);
+            let scanner = audit_handle
+                .clamav_scanner()
+                .filter(|s| size <= s.max_body_size() as u64);
+            let har_record_body = audit_handle
+                .har_exporter()
+                .map(|e| size <= e.body_max_size() as u64)
+                .unwrap_or(false);
+            if filter.is_some() || scanner.is_some() || har_record_body {
+                return self
+                    .send_response_after_body_check(
+                        rsp_head,
+                        size as usize,
+                        rsp_io,
+                        filter,
+                        scanner,
+                        har_record_body,
+                    )
+                    .await;
+            }
+        }
+
+        if let Some(respmod) = respmod_client {
+            // decode the body so the icap server can inspect the plain content
+            let mut buffered_body = None;
+            if let (Some(config), Some(size)) =
+                (audit_handle.icap_respmod_decompress(), content_length)
+            {
+                let coding = rsp
+                    .end_to_end_headers
+                    .get(http::header::CONTENT_ENCODING)
+                    .and_then(|v| ContentCoding::parse(v.to_str()));
+                if let Some(coding) = coding.filter(|_| size <= config.max_body_size as u64) {
+                    let body = self.recv_response_body(size as usize, rsp_io).await?;
+                    match coding.decode(&body, config.max_decoded_size) {
+                        Some(decoded) => {
+                            rsp.set_identity_content(decoded.len() as u64);
+                            buffered_body = Some(decoded);
+                        }
+                        None => buffered_body = Some(body),
+                    }
+                }
+            }
+
             match respmod
                 .h1_adapter(
                     self.ctx.server_config.limited_copy_config(),
@@ -660,9 +879,25 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
                         adapter.set_client_username(username);
                     }
                     adapter.set_respond_shared_headers(adaptation_respond_shared_headers);
-                    let r = self
-                        .send_response_with_adaptation(rsp, rsp_io, adapter, &mut adaptation_state)
-                        .await;
+                    let r = if let Some(body) = &buffered_body {
+                        self.send_response_with_adaptation(
+                            rsp,
+                            &mut body.as_slice(),
+                            &mut rsp_io.clt_w,
+                            adapter,
+                            &mut adaptation_state,
+                        )
+                        .await
+                    } else {
+                        self.send_response_with_adaptation(
+                            rsp,
+                            &mut rsp_io.ups_r,
+                            &mut rsp_io.clt_w,
+                            adapter,
+                            &mut adaptation_state,
+                        )
+                        .await
+                    };
                     if !adaptation_state.clt_write_finished || !adaptation_state.ups_read_finished {
                         self.should_close = true;
                     }
@@ -673,11 +908,21 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
                     return r;
                 }
                 Err(e) => {
+                    let icap_stats = self.ctx.audit_handle.stats().icap_respmod();
                     if !respmod.bypass() {
+                        icap_stats.add_error();
                         return Err(ServerTaskError::InternalAdapterError(e));
                     }
+                    icap_stats.add_bypassed();
                 }
             }
+
+            if let Some(body) = buffered_body {
+                let head = rsp.serialize();
+                return self
+                    .send_response_with_body(&head, &body, &mut rsp_io.clt_w)
+                    .await;
+            }
         }
 
         self.send_response_without_adaptation(rsp, rsp_head, rsp_io)
@@ -687,33 +932,37 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
     async fn send_response_with_adaptation<UR, CW>(
         &mut self,
         rsp: HttpTransparentResponse,
-        rsp_io: &mut HttpResponseIo<UR, CW>,
+        ups_r: &mut UR,
+        clt_w: &mut CW,
         icap_adapter: HttpResponseAdapter<ServerIdleChecker>,
         adaptation_state: &mut RespmodAdaptationRunState,
     ) -> ServerTaskResult<()>
     where
-        UR: AsyncRead + Unpin,
+        UR: AsyncBufRead + Unpin,
         CW: AsyncWrite + Send + Unpin,
     {
+        let icap_stats = self.ctx.audit_handle.stats().icap_respmod();
         match icap_adapter
-            .xfer(
-                adaptation_state,
-                self.req,
-                &rsp,
-                &mut rsp_io.ups_r,
-                &mut rsp_io.clt_w,
-            )
+            .xfer(adaptation_state, self.req, &rsp, ups_r, clt_w)
             .await
         {
             Ok(RespmodAdaptationEndState::OriginalTransferred) => {
+                icap_stats.add_no_modification();
                 self.http_notes.rsp_status = rsp.code;
                 Ok(())
             }
             Ok(RespmodAdaptationEndState::AdaptedTransferred(adapted_rsp)) => {
+                icap_stats.add_adapted();
                 self.http_notes.rsp_status = adapted_rsp.code;
                 Ok(())
             }
-            Err(e) => Err(e.into()),
+            Err(e) => {
+                let e = ServerTaskError::from(e);
+                if matches!(e, ServerTaskError::InternalAdapterError(_)) {
+                    icap_stats.add_error();
+                }
+                Err(e)
+            }
         }
     }
 
@@ -747,6 +996,111 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
         }
     }
 
+    async fn send_response_after_body_check<UR, CW>(
+        &mut self,
+        rsp_head: Bytes,
+        body_size: usize,
+        rsp_io: &mut HttpResponseIo<UR, CW>,
+        filter: Option<&ContentFilter>,
+        scanner: Option<&ClamavScanner>,
+        har_record_body: bool,
+    ) -> ServerTaskResult<()>
+    where
+        UR: AsyncRead + Unpin,
+        CW: AsyncWrite + Unpin,
+    {
+        let body = self.recv_response_body(body_size, rsp_io).await?;
+        if har_record_body {
+            if let Some(rsp) = &mut self.har_response {
+                har::har_set_content_text(&mut rsp["content"], &body);
+            }
+        }
+        if let Some(filter) = filter {
+            if filter.check_body(&body) {
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::ContentBlocked,
+                ));
+            }
+        }
+        if let Some(scanner) = scanner {
+            match scanner.scan(&body).await {
+                Ok(Some(name)) => {
+                    intercept_log!(self, "virus {name} found by clamav");
+                    return Err(ServerTaskError::ForbiddenByRule(
+                        ServerTaskForbiddenError::ContentBlocked,
+                    ));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    if !scanner.bypass() {
+                        return Err(ServerTaskError::InternalAdapterError(e));
+                    }
+                }
+            }
+        }
+
+        self.send_response_with_body(&rsp_head, &body, &mut rsp_io.clt_w)
+            .await
+    }
+
+    async fn recv_response_body<UR, CW>(
+        &mut self,
+        body_size: usize,
+        rsp_io: &mut HttpResponseIo<UR, CW>,
+    ) -> ServerTaskResult<Vec<u8>>
+    where
+        UR: AsyncRead + Unpin,
+        CW: AsyncWrite + Unpin,
+    {
+        let mut body = vec![0u8; body_size];
+        match tokio::time::timeout(
+            self.ctx.h1_interception().rsp_head_recv_timeout,
+            rsp_io.ups_r.read_exact(&mut body),
+        )
+        .await
+        {
+            Ok(Ok(_)) => {
+                self.http_notes.mark_rsp_recv_all();
+                Ok(body)
+            }
+            Ok(Err(e)) => {
+                self.should_close = true;
+                Err(ServerTaskError::UpstreamReadFailed(e))
+            }
+            Err(_) => {
+                self.should_close = true;
+                Err(ServerTaskError::UpstreamAppTimeout(
+                    "timeout to read response body",
+                ))
+            }
+        }
+    }
+
+    async fn send_response_with_body<CW>(
+        &mut self,
+        head: &[u8],
+        body: &[u8],
+        clt_w: &mut CW,
+    ) -> ServerTaskResult<()>
+    where
+        CW: AsyncWrite + Unpin,
+    {
+        self.send_error_response = false;
+        self.http_notes.rsp_status = self.http_notes.origin_status;
+        clt_w
+            .write_all(head)
+            .await
+            .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+        clt_w
+            .write_all(body)
+            .await
+            .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+        clt_w
+            .flush()
+            .await
+            .map_err(ServerTaskError::ClientTcpWriteFailed)
+    }
+
     async fn send_response_header<CW>(
         &mut self,
         clt_w: &mut CW,
@@ -22,6 +22,7 @@ use slog::slog_info;
 use tokio::io::{AsyncRead, AsyncWrite};
 
 use g3_dpi::Protocol;
+use g3_http::HttpBodyType;
 use g3_io_ext::FlexBufReader;
 use g3_slog_types::LtUuid;
 
@@ -162,11 +163,20 @@ where
                 HttpRecvRequest::RequestWithoutIo(r) => {
                     let mut forward_task = H1ForwardTask::new(self.ctx.clone(), &r, self.req_id);
                     forward_task.forward_without_body(&mut rsp_io).await;
+                    forward_task.export_har();
                     pipeline_stats.del_task();
                     if forward_task.should_close() {
                         req_acceptor.close();
                     }
                 }
+                HttpRecvRequest::RequestBlocked(r, category) => {
+                    let mut forward_task = H1ForwardTask::new(self.ctx.clone(), &r, self.req_id);
+                    forward_task
+                        .reply_blocked(&category, &mut rsp_io.clt_w)
+                        .await;
+                    forward_task.export_har();
+                    req_acceptor.close();
+                }
                 HttpRecvRequest::RequestWithIO(r, mut req_io, io_sender) => {
                     if r.inner.method == Method::CONNECT {
                         let mut connect_task = H1ConnectTask::new(self.ctx.clone(), r, self.req_id);
@@ -204,13 +214,26 @@ where
                     } else {
                         let mut forward_task =
                             H1ForwardTask::new(self.ctx.clone(), &r, self.req_id);
-                        if let Some(reqmod_client) = self.ctx.audit_handle.icap_reqmod_client() {
-                            forward_task
-                                .adapt_with_io(&mut req_io, &mut rsp_io, reqmod_client)
-                                .await;
+                        let content_length = match r.inner.body_type() {
+                            Some(HttpBodyType::ContentLength(size)) => Some(size),
+                            _ => None,
+                        };
+                        let audit_handle = &self.ctx.audit_handle;
+                        let reqmod_client = audit_handle
+                            .icap_reqmod_client()
+                            .filter(|_| !audit_handle.icap_reqmod_bypassed(content_length));
+                        if let Some(reqmod_client) = reqmod_client {
+                            if audit_handle.icap_reqmod_body_blocked(content_length) {
+                                forward_task.reply_body_too_large(&mut rsp_io.clt_w).await;
+                            } else {
+                                forward_task
+                                    .adapt_with_io(&mut req_io, &mut rsp_io, reqmod_client)
+                                    .await;
+                            }
                         } else {
                             forward_task.forward_with_io(&mut req_io, &mut rsp_io).await;
                         }
+                        forward_task.export_har();
                         pipeline_stats.del_task();
                         if forward_task.should_close() {
                             req_acceptor.close();
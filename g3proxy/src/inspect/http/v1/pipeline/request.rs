@@ -17,12 +17,14 @@
 use std::sync::Arc;
 use std::time::Duration;
 
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio::sync::mpsc;
 use tokio::time::Instant;
 
 use g3_http::server::{HttpRequestParseError, HttpTransparentRequest};
+use g3_http::HttpBodyType;
 use g3_io_ext::LimitedBufReadExt;
 
 use super::{H1InterceptionError, HttpRequestIo, PipelineStats};
@@ -46,6 +48,7 @@ pub(crate) enum HttpRecvRequest<R: AsyncRead, W: AsyncWrite> {
         mpsc::Sender<HttpRequestIo<R, W>>,
     ),
     RequestWithoutIo(HttpRequest),
+    RequestBlocked(HttpRequest, Arc<str>),
 }
 
 pub(crate) struct HttpRequestAcceptor<R: AsyncRead, W: AsyncWrite> {
@@ -172,7 +175,35 @@ where
                             req.disable_keep_alive();
                         }
 
-                        if self.ctx.audit_handle.icap_reqmod_client().is_some() {
+                        if let Some(category) = self.check_url_category(&req) {
+                            req.disable_keep_alive();
+                            let recv_req = HttpRecvRequest::RequestBlocked(
+                                HttpRequest {
+                                    inner: req,
+                                    time_received,
+                                    datetime_received,
+                                    dur_req_send_hdr: Duration::ZERO,
+                                },
+                                category,
+                            );
+                            let _ = self.send_request.send(recv_req).await;
+                            // no more request should be read after a blocked one
+                            break;
+                        }
+
+                        let head_bytes = if self.rewrite_request_headers(&mut req) {
+                            Bytes::from(req.serialize_for_origin())
+                        } else {
+                            head_bytes
+                        };
+
+                        let content_length = match req.body_type() {
+                            Some(HttpBodyType::ContentLength(size)) => Some(size),
+                            _ => None,
+                        };
+                        if self.ctx.audit_handle.icap_reqmod_client().is_some()
+                            && !self.ctx.audit_handle.icap_reqmod_bypassed(content_length)
+                        {
                             // skip the fast send of header if audit is needed
                             let recv_req = HttpRecvRequest::RequestWithIO(
                                 HttpRequest {
@@ -260,4 +291,22 @@ where
             }
         }
     }
+
+    fn check_url_category(&self, req: &HttpTransparentRequest) -> Option<Arc<str>> {
+        let filter = self.ctx.audit_handle.content_filter()?;
+        if !filter.has_url_rules() {
+            return None;
+        }
+        let host = req.host.as_ref()?;
+        let path = req.uri.path_and_query().map(|v| v.as_str()).unwrap_or("/");
+        filter.check_url(host.host(), path)
+    }
+
+    fn rewrite_request_headers(&self, req: &mut HttpTransparentRequest) -> bool {
+        let host = req.host.as_ref().map(|v| v.host());
+        let path = req.uri.path_and_query().map(|v| v.as_str()).unwrap_or("/");
+        self.ctx
+            .audit_handle
+            .rewrite_http_request_headers(host, path, &mut req.end_to_end_headers)
+    }
 }
@@ -120,6 +120,8 @@ pub(crate) enum H2StreamTransferError {
     Idle(Duration, i32),
     #[error("push wait error: {0}")]
     PushWaitError(h2::Error),
+    #[error("body too large for adaptation")]
+    BodyTooLarge,
 }
 
 impl H2StreamTransferError {
@@ -134,6 +136,7 @@ impl H2StreamTransferError {
             H2StreamTransferError::InvalidHostHeader => StatusCode::BAD_REQUEST,
             H2StreamTransferError::ResponseHeadRecvFailed(_) => StatusCode::BAD_GATEWAY,
             H2StreamTransferError::ResponseHeadRecvTimeout => StatusCode::GATEWAY_TIMEOUT,
+            H2StreamTransferError::BodyTooLarge => StatusCode::FORBIDDEN,
             _ => return None,
         };
         let rsp = Response::builder()
@@ -247,7 +247,26 @@ where
         self.send_error_response = true;
         let ups_req = Request::from_parts(parts, ());
 
-        if let Some(reqmod) = self.ctx.audit_handle.icap_reqmod_client() {
+        let content_length = ups_req
+            .headers()
+            .get(http::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        let reqmod_client = self
+            .ctx
+            .audit_handle
+            .icap_reqmod_client()
+            .filter(|_| !self.ctx.audit_handle.icap_reqmod_bypassed(content_length));
+        if reqmod_client.is_some()
+            && self
+                .ctx
+                .audit_handle
+                .icap_reqmod_body_blocked(content_length)
+        {
+            return Err(H2StreamTransferError::BodyTooLarge);
+        }
+
+        if let Some(reqmod) = reqmod_client {
             match reqmod
                 .h2_adapter(
                     self.ctx.server_config.limited_copy_config(),
@@ -288,9 +307,12 @@ where
                     return r;
                 }
                 Err(e) => {
+                    let icap_stats = self.ctx.audit_handle.stats().icap_reqmod();
                     if !reqmod.bypass() {
+                        icap_stats.add_error();
                         return Err(H2StreamTransferError::InternalAdapterError(e));
                     }
+                    icap_stats.add_bypassed();
                 }
             }
         }
@@ -315,6 +337,11 @@ where
             .await
         {
             Ok(ReqmodAdaptationEndState::OriginalTransferred(ups_rsp)) => {
+                self.ctx
+                    .audit_handle
+                    .stats()
+                    .icap_reqmod()
+                    .add_no_modification();
                 self.send_response(
                     orig_req,
                     ups_rsp,
@@ -324,6 +351,7 @@ where
                 .await
             }
             Ok(ReqmodAdaptationEndState::AdaptedTransferred(_http_req, ups_rsp)) => {
+                self.ctx.audit_handle.stats().icap_reqmod().add_adapted();
                 self.send_response(
                     orig_req,
                     ups_rsp,
@@ -333,10 +361,17 @@ where
                 .await
             }
             Ok(ReqmodAdaptationEndState::HttpErrResponse(err_rsp, recv_body)) => {
+                self.ctx.audit_handle.stats().icap_reqmod().add_blocked();
                 self.send_adaptation_error_response(clt_send_rsp, err_rsp, recv_body)
                     .await
             }
-            Err(e) => Err(e.into()),
+            Err(e) => {
+                let e = H2StreamTransferError::from(e);
+                if matches!(e, H2StreamTransferError::InternalAdapterError(_)) {
+                    self.ctx.audit_handle.stats().icap_reqmod().add_error();
+                }
+                Err(e)
+            }
         }
     }
 
@@ -586,7 +621,31 @@ where
 
         self.http_notes.origin_status = clt_rsp.status().as_u16();
 
-        if let Some(respmod) = self.ctx.audit_handle.icap_respmod_client() {
+        let content_type = clt_rsp
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok());
+        let content_length = clt_rsp
+            .headers()
+            .get(http::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        let respmod_client = self.ctx.audit_handle.icap_respmod_client().filter(|_| {
+            !self
+                .ctx
+                .audit_handle
+                .icap_respmod_bypassed(content_type, content_length)
+        });
+        if respmod_client.is_some()
+            && self
+                .ctx
+                .audit_handle
+                .icap_respmod_body_blocked(content_length)
+        {
+            return Err(H2StreamTransferError::BodyTooLarge);
+        }
+
+        if let Some(respmod) = respmod_client {
             match respmod
                 .h2_adapter(
                     self.ctx.server_config.limited_copy_config(),
@@ -625,9 +684,12 @@ where
                     return r;
                 }
                 Err(e) => {
+                    let icap_stats = self.ctx.audit_handle.stats().icap_respmod();
                     if !respmod.bypass() {
+                        icap_stats.add_error();
                         return Err(H2StreamTransferError::InternalAdapterError(e));
                     }
+                    icap_stats.add_bypassed();
                 }
             }
         }
@@ -646,19 +708,28 @@ where
         adaptation_state: &mut RespmodAdaptationRunState,
     ) -> Result<(), H2StreamTransferError> {
         let rsp_code = clt_rsp.status().as_u16();
+        let icap_stats = self.ctx.audit_handle.stats().icap_respmod();
         match icap_adapter
             .xfer(adaptation_state, ups_req, clt_rsp, ups_body, clt_send_rsp)
             .await
         {
             Ok(RespmodAdaptationEndState::OriginalTransferred) => {
+                icap_stats.add_no_modification();
                 self.http_notes.rsp_status = rsp_code;
                 Ok(())
             }
             Ok(RespmodAdaptationEndState::AdaptedTransferred(_rsp)) => {
+                icap_stats.add_adapted();
                 self.http_notes.rsp_status = rsp_code;
                 Ok(())
             }
-            Err(e) => Err(e.into()),
+            Err(e) => {
+                let e = H2StreamTransferError::from(e);
+                if matches!(e, H2StreamTransferError::InternalAdapterError(_)) {
+                    icap_stats.add_error();
+                }
+                Err(e)
+            }
         }
     }
 
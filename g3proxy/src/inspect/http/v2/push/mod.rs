@@ -179,7 +179,31 @@ impl<SC: ServerConfig> H2PushTask<SC> {
 
         self.http_notes.origin_status = clt_rsp.status().as_u16();
 
-        if let Some(respmod) = self.ctx.audit_handle.icap_respmod_client() {
+        let content_type = clt_rsp
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok());
+        let content_length = clt_rsp
+            .headers()
+            .get(http::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        let respmod_client = self.ctx.audit_handle.icap_respmod_client().filter(|_| {
+            !self
+                .ctx
+                .audit_handle
+                .icap_respmod_bypassed(content_type, content_length)
+        });
+        if respmod_client.is_some()
+            && self
+                .ctx
+                .audit_handle
+                .icap_respmod_body_blocked(content_length)
+        {
+            return Err(H2StreamTransferError::BodyTooLarge);
+        }
+
+        if let Some(respmod) = respmod_client {
             match respmod
                 .h2_adapter(
                     self.ctx.server_config.limited_copy_config(),
@@ -213,9 +237,12 @@ impl<SC: ServerConfig> H2PushTask<SC> {
                     return r;
                 }
                 Err(e) => {
+                    let icap_stats = self.ctx.audit_handle.stats().icap_respmod();
                     if !respmod.bypass() {
+                        icap_stats.add_error();
                         return Err(H2StreamTransferError::InternalAdapterError(e));
                     }
+                    icap_stats.add_bypassed();
                 }
             }
         }
@@ -233,6 +260,7 @@ impl<SC: ServerConfig> H2PushTask<SC> {
         adaptation_state: &mut RespmodAdaptationRunState,
     ) -> Result<(), H2StreamTransferError> {
         let rsp_code = clt_rsp.status().as_u16();
+        let icap_stats = self.ctx.audit_handle.stats().icap_respmod();
         match icap_adapter
             .xfer(
                 adaptation_state,
@@ -244,14 +272,22 @@ impl<SC: ServerConfig> H2PushTask<SC> {
             .await
         {
             Ok(RespmodAdaptationEndState::OriginalTransferred) => {
+                icap_stats.add_no_modification();
                 self.http_notes.rsp_status = rsp_code;
                 Ok(())
             }
             Ok(RespmodAdaptationEndState::AdaptedTransferred(_rsp)) => {
+                icap_stats.add_adapted();
                 self.http_notes.rsp_status = rsp_code;
                 Ok(())
             }
-            Err(e) => Err(e.into()),
+            Err(e) => {
+                let e = H2StreamTransferError::from(e);
+                if matches!(e, H2StreamTransferError::InternalAdapterError(_)) {
+                    icap_stats.add_error();
+                }
+                Err(e)
+            }
         }
     }
 
@@ -0,0 +1,271 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+pub(super) struct ImapCommandLine<'a> {
+    pub(super) tag: &'a str,
+    pub(super) command: String,
+    pub(super) args: &'a str,
+}
+
+impl<'a> ImapCommandLine<'a> {
+    pub(super) fn parse(line: &'a [u8]) -> Option<Self> {
+        let line = std::str::from_utf8(line).ok()?.trim_end();
+        let (tag, left) = line.split_once(' ')?;
+        let (command, args) = left.split_once(' ').unwrap_or((left, ""));
+        let mut command = command.to_ascii_uppercase();
+        let mut args = args;
+        if command == "UID" {
+            // UID COPY / UID FETCH / UID STORE / ...
+            let (sub_command, sub_args) = args.split_once(' ').unwrap_or((args, ""));
+            command.push(' ');
+            command.push_str(&sub_command.to_ascii_uppercase());
+            args = sub_args;
+        }
+        Some(ImapCommandLine { tag, command, args })
+    }
+
+    /// get the mailbox name in the first argument, literal mailbox names are not supported
+    pub(super) fn mailbox(&self) -> &'a str {
+        let args = self.args;
+        if let Some(left) = args.strip_prefix('"') {
+            let mut escaped = false;
+            for (i, c) in left.char_indices() {
+                match c {
+                    '\\' if !escaped => escaped = true,
+                    '"' if !escaped => return &left[..i],
+                    _ => escaped = false,
+                }
+            }
+            ""
+        } else if args.starts_with('{') {
+            ""
+        } else {
+            args.split(' ').next().unwrap_or_default()
+        }
+    }
+
+    pub(super) fn is_fetch(&self) -> bool {
+        matches!(self.command.as_str(), "FETCH" | "UID FETCH")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum ImapLiteral {
+    /// the synchronizing literal, the sender should wait for the continuation response
+    Sync(u64),
+    /// the non-synchronizing literal, see RFC 7888
+    NonSync(u64),
+}
+
+impl ImapLiteral {
+    /// get the literal at the end of the line
+    pub(super) fn parse(line: &[u8]) -> Option<Self> {
+        let line = line
+            .strip_suffix(b"\r\n")
+            .or_else(|| line.strip_suffix(b"\n"))?;
+        let line = line.strip_suffix(b"}")?;
+        let p = line.iter().rposition(|c| *c == b'{')?;
+        let spec = std::str::from_utf8(&line[p + 1..]).ok()?;
+        match spec.strip_suffix('+') {
+            Some(s) => u64::from_str(s).ok().map(ImapLiteral::NonSync),
+            None => u64::from_str(spec).ok().map(ImapLiteral::Sync),
+        }
+    }
+
+    pub(super) fn size(&self) -> u64 {
+        match self {
+            ImapLiteral::Sync(n) => *n,
+            ImapLiteral::NonSync(n) => *n,
+        }
+    }
+}
+
+/// get the line without the trailing literal spec
+pub(super) fn strip_literal(line: &[u8]) -> &[u8] {
+    match line.iter().rposition(|c| *c == b'{') {
+        Some(p) => &line[..p],
+        None => line,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum ImapResponseKind {
+    Untagged,
+    Continuation,
+    Tagged,
+}
+
+impl ImapResponseKind {
+    pub(super) fn parse(line: &[u8], tag: &str) -> Option<Self> {
+        if line.starts_with(b"* ") {
+            Some(ImapResponseKind::Untagged)
+        } else if line.starts_with(b"+") {
+            Some(ImapResponseKind::Continuation)
+        } else if line.len() > tag.len()
+            && line.starts_with(tag.as_bytes())
+            && line[tag.len()] == b' '
+        {
+            Some(ImapResponseKind::Tagged)
+        } else {
+            None
+        }
+    }
+}
+
+/// check if the status in the tagged response is OK
+pub(super) fn is_tagged_ok(line: &[u8], tag: &str) -> bool {
+    let left = &line[tag.len() + 1..];
+    left.len() >= 2 && left[..2].eq_ignore_ascii_case(b"OK")
+}
+
+/// check if the untagged response is a FETCH response
+pub(super) fn is_fetch_response(line: &[u8]) -> bool {
+    let mut iter = line[2..].split(|c| *c == b' ');
+    let _ = iter.next();
+    iter.next()
+        .map(|s| s.eq_ignore_ascii_case(b"FETCH"))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command() {
+        let cmd = ImapCommandLine::parse(b"a001 login user pass\r\n").unwrap();
+        assert_eq!(cmd.tag, "a001");
+        assert_eq!(cmd.command, "LOGIN");
+        assert_eq!(cmd.args, "user pass");
+
+        let cmd = ImapCommandLine::parse(b"a002 NOOP\r\n").unwrap();
+        assert_eq!(cmd.tag, "a002");
+        assert_eq!(cmd.command, "NOOP");
+        assert_eq!(cmd.args, "");
+
+        let cmd = ImapCommandLine::parse(b"a003 uid fetch 1:* (FLAGS)\r\n").unwrap();
+        assert_eq!(cmd.command, "UID FETCH");
+        assert_eq!(cmd.args, "1:* (FLAGS)");
+        assert!(cmd.is_fetch());
+
+        let cmd = ImapCommandLine::parse(b"a004 FETCH 1 BODY[]\r\n").unwrap();
+        assert!(cmd.is_fetch());
+
+        let cmd = ImapCommandLine::parse(b"a005 UID STORE 1 +FLAGS (\\Seen)\r\n").unwrap();
+        assert_eq!(cmd.command, "UID STORE");
+        assert!(!cmd.is_fetch());
+
+        assert!(ImapCommandLine::parse(b"NOOP\r\n").is_none());
+        assert!(ImapCommandLine::parse(b"a006 \xff\r\n").is_none());
+    }
+
+    #[test]
+    fn mailbox() {
+        let cmd = ImapCommandLine::parse(b"a001 APPEND INBOX (\\Seen) {310}\r\n").unwrap();
+        assert_eq!(cmd.mailbox(), "INBOX");
+
+        let cmd = ImapCommandLine::parse(b"a002 APPEND \"Sent Items\" {310}\r\n").unwrap();
+        assert_eq!(cmd.mailbox(), "Sent Items");
+
+        let cmd = ImapCommandLine::parse(b"a003 APPEND \"a \\\"b\\\" c\" {310}\r\n").unwrap();
+        assert_eq!(cmd.mailbox(), "a \\\"b\\\" c");
+
+        let cmd = ImapCommandLine::parse(b"a004 APPEND \"unterminated {310}\r\n").unwrap();
+        assert_eq!(cmd.mailbox(), "");
+
+        let cmd = ImapCommandLine::parse(b"a005 APPEND {5}\r\n").unwrap();
+        assert_eq!(cmd.mailbox(), "");
+    }
+
+    #[test]
+    fn literal() {
+        assert_eq!(
+            ImapLiteral::parse(b"a001 APPEND INBOX {310}\r\n"),
+            Some(ImapLiteral::Sync(310))
+        );
+        assert_eq!(
+            ImapLiteral::parse(b"a001 APPEND INBOX {310+}\r\n"),
+            Some(ImapLiteral::NonSync(310))
+        );
+        assert_eq!(
+            ImapLiteral::parse(b"* 1 FETCH (BODY[] {42}\n"),
+            Some(ImapLiteral::Sync(42))
+        );
+        assert_eq!(ImapLiteral::Sync(310).size(), 310);
+        assert_eq!(ImapLiteral::NonSync(12).size(), 12);
+
+        assert!(ImapLiteral::parse(b"a001 APPEND INBOX {310}").is_none());
+        assert!(ImapLiteral::parse(b"a001 APPEND INBOX {310} x\r\n").is_none());
+        assert!(ImapLiteral::parse(b"a001 APPEND INBOX {abc}\r\n").is_none());
+        assert!(ImapLiteral::parse(b"a001 APPEND INBOX {-1}\r\n").is_none());
+        assert!(ImapLiteral::parse(b"a001 NOOP\r\n").is_none());
+    }
+
+    #[test]
+    fn strip() {
+        assert_eq!(
+            strip_literal(b"a001 APPEND INBOX {310}\r\n"),
+            b"a001 APPEND INBOX "
+        );
+        assert_eq!(strip_literal(b"a001 NOOP\r\n"), b"a001 NOOP\r\n");
+    }
+
+    #[test]
+    fn response_kind() {
+        assert_eq!(
+            ImapResponseKind::parse(b"* 1 EXISTS\r\n", "a001"),
+            Some(ImapResponseKind::Untagged)
+        );
+        assert_eq!(
+            ImapResponseKind::parse(b"+ Ready for literal data\r\n", "a001"),
+            Some(ImapResponseKind::Continuation)
+        );
+        assert_eq!(
+            ImapResponseKind::parse(b"+\r\n", "a001"),
+            Some(ImapResponseKind::Continuation)
+        );
+        assert_eq!(
+            ImapResponseKind::parse(b"a001 OK done\r\n", "a001"),
+            Some(ImapResponseKind::Tagged)
+        );
+        assert_eq!(ImapResponseKind::parse(b"a0012 OK done\r\n", "a001"), None);
+        assert_eq!(ImapResponseKind::parse(b"a002 OK done\r\n", "a001"), None);
+        assert_eq!(ImapResponseKind::parse(b"a001", "a001"), None);
+    }
+
+    #[test]
+    fn tagged_ok() {
+        assert!(is_tagged_ok(b"a001 OK APPEND completed\r\n", "a001"));
+        assert!(is_tagged_ok(b"a001 ok\r\n", "a001"));
+        assert!(!is_tagged_ok(
+            b"a001 NO [TRYCREATE] no such mailbox\r\n",
+            "a001"
+        ));
+        assert!(!is_tagged_ok(b"a001 BAD command unknown\r\n", "a001"));
+        assert!(!is_tagged_ok(b"a001 O", "a001"));
+    }
+
+    #[test]
+    fn fetch_response() {
+        assert!(is_fetch_response(b"* 12 FETCH (FLAGS (\\Seen))\r\n"));
+        assert!(is_fetch_response(b"* 1 fetch (BODY[] {42}\r\n"));
+        assert!(!is_fetch_response(b"* 23 EXISTS\r\n"));
+        assert!(!is_fetch_response(b"* OK [UIDVALIDITY 3857529045]\r\n"));
+        assert!(!is_fetch_response(b"* 1 FETCHED\r\n"));
+    }
+}
@@ -0,0 +1,775 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::Write;
+use std::time::Duration;
+
+use slog::slog_info;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+use g3_dpi::{ImapInterceptionConfig, Protocol};
+use g3_icap_client::reqmod::imap::{ImapAdaptationEndState, ImapMessageEnvelope};
+use g3_icap_client::reqmod::IcapReqmodClient;
+use g3_io_ext::{FlexBufReader, LimitedBufReadExt, OnceBufReader};
+use g3_slog_types::{LtUpstreamAddr, LtUuid};
+use g3_types::net::UpstreamAddr;
+
+use crate::config::server::ServerConfig;
+use crate::inspect::{
+    BoxAsyncRead, BoxAsyncWrite, InterceptionError, StreamInspectContext, StreamInspection,
+};
+use crate::log::inspect::{stream::StreamInspectLog, InspectSource};
+use crate::serve::ServerTaskResult;
+
+mod error;
+pub(crate) use error::ImapInterceptionError;
+
+mod command;
+use command::{ImapCommandLine, ImapLiteral, ImapResponseKind};
+
+macro_rules! intercept_log {
+    ($obj:tt, $($args:tt)+) => {
+        slog_info!($obj.ctx.intercept_logger(), $($args)+;
+            "intercept_type" => "ImapConnection",
+            "task_id" => LtUuid($obj.ctx.server_task_id()),
+            "depth" => $obj.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr(&$obj.upstream),
+        )
+    };
+}
+
+#[derive(Default)]
+struct ImapFetchStats {
+    count: usize,
+    size: u64,
+}
+
+struct ImapInterceptIo {
+    clt_r: FlexBufReader<BoxAsyncRead>,
+    clt_w: BoxAsyncWrite,
+    ups_r: FlexBufReader<BoxAsyncRead>,
+    ups_w: BoxAsyncWrite,
+}
+
+pub(crate) struct ImapInterceptObject<SC: ServerConfig> {
+    io: Option<ImapInterceptIo>,
+    ctx: StreamInspectContext<SC>,
+    upstream: UpstreamAddr,
+    config: ImapInterceptionConfig,
+    from_starttls: bool,
+    mailbox: String,
+}
+
+impl<SC: ServerConfig> ImapInterceptObject<SC> {
+    pub(crate) fn new(ctx: StreamInspectContext<SC>, upstream: UpstreamAddr) -> Self {
+        let config = ctx.imap_interception().clone();
+        ImapInterceptObject {
+            io: None,
+            ctx,
+            upstream,
+            config,
+            from_starttls: false,
+            mailbox: String::new(),
+        }
+    }
+
+    /// the server greeting has already been sent before STARTTLS
+    pub(crate) fn set_from_starttls(&mut self) {
+        self.from_starttls = true;
+    }
+
+    pub(crate) fn set_io(
+        &mut self,
+        clt_r: FlexBufReader<BoxAsyncRead>,
+        clt_w: BoxAsyncWrite,
+        ups_r: FlexBufReader<BoxAsyncRead>,
+        ups_w: BoxAsyncWrite,
+    ) {
+        let io = ImapInterceptIo {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        };
+        self.io = Some(io);
+    }
+}
+
+impl<SC> ImapInterceptObject<SC>
+where
+    SC: ServerConfig + Send + Sync + 'static,
+{
+    pub(crate) async fn intercept(mut self) -> ServerTaskResult<Option<StreamInspection<SC>>> {
+        match self.do_intercept().await {
+            Ok(v) => {
+                intercept_log!(self, "finished");
+                Ok(v)
+            }
+            Err(e) => {
+                intercept_log!(self, "{e}");
+                Err(InterceptionError::Imap(e).into_server_task_error(Protocol::Imap))
+            }
+        }
+    }
+
+    async fn do_intercept(
+        &mut self,
+    ) -> Result<Option<StreamInspection<SC>>, ImapInterceptionError> {
+        let mut io = self.io.take().unwrap();
+
+        if !self.from_starttls {
+            let mut rsp = Vec::with_capacity(256);
+            self.read_upstream_line(&mut io.ups_r, &mut rsp, self.config.greeting_timeout)
+                .await?;
+            send_to_client(&mut io.clt_w, &rsp).await?;
+            if rsp.starts_with(b"* BYE") {
+                return Ok(None);
+            }
+        }
+
+        let reqmod_client = self.ctx.audit_handle.icap_reqmod_client().cloned();
+        let mut line = Vec::with_capacity(512);
+        loop {
+            self.check_cancel()?;
+
+            line.clear();
+            self.read_client_line(&mut io.clt_r, &mut line).await?;
+            let cmd =
+                ImapCommandLine::parse(&line).ok_or(ImapInterceptionError::InvalidCommandLine)?;
+            let literal = ImapLiteral::parse(&line);
+
+            match cmd.command.as_str() {
+                "APPEND" => {
+                    if let (Some(client), Some(literal)) = (&reqmod_client, literal) {
+                        self.adapt_append(&mut io, client, &cmd, &line, literal)
+                            .await?;
+                    } else {
+                        let mut stats = ImapFetchStats::default();
+                        self.relay_command(&mut io, &line, cmd.tag, &mut stats)
+                            .await?;
+                        let size = literal.map(|l| l.size()).unwrap_or_default();
+                        self.log_append(cmd.mailbox(), size);
+                    }
+                }
+                "SELECT" | "EXAMINE" => {
+                    let mut stats = ImapFetchStats::default();
+                    if self
+                        .relay_command(&mut io, &line, cmd.tag, &mut stats)
+                        .await?
+                    {
+                        self.mailbox = cmd.mailbox().to_string();
+                    } else {
+                        self.mailbox.clear();
+                    }
+                }
+                "CLOSE" | "UNSELECT" => {
+                    let mut stats = ImapFetchStats::default();
+                    self.relay_command(&mut io, &line, cmd.tag, &mut stats)
+                        .await?;
+                    self.mailbox.clear();
+                }
+                "STARTTLS" => {
+                    let mut stats = ImapFetchStats::default();
+                    if self
+                        .relay_command(&mut io, &line, cmd.tag, &mut stats)
+                        .await?
+                    {
+                        return self.start_tls(io).map(Some);
+                    }
+                }
+                "COMPRESS" => {
+                    let mut stats = ImapFetchStats::default();
+                    if self
+                        .relay_command(&mut io, &line, cmd.tag, &mut stats)
+                        .await?
+                    {
+                        // the following data will be compressed, see RFC 4978
+                        return Ok(Some(self.transit_unknown(io)));
+                    }
+                }
+                "LOGOUT" => {
+                    let mut stats = ImapFetchStats::default();
+                    self.relay_command(&mut io, &line, cmd.tag, &mut stats)
+                        .await?;
+                    return Ok(None);
+                }
+                _ => {
+                    let mut stats = ImapFetchStats::default();
+                    self.relay_command(&mut io, &line, cmd.tag, &mut stats)
+                        .await?;
+                    if cmd.is_fetch() {
+                        self.log_fetch(&cmd, &stats);
+                    }
+                }
+            }
+        }
+    }
+
+    fn log_fetch(&self, cmd: &ImapCommandLine<'_>, stats: &ImapFetchStats) {
+        slog_info!(self.ctx.intercept_logger(), "mail fetched";
+            "intercept_type" => "ImapConnection",
+            "task_id" => LtUuid(self.ctx.server_task_id()),
+            "depth" => self.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr(&self.upstream),
+            "mailbox" => &self.mailbox,
+            "command" => &cmd.command,
+            "args" => cmd.args,
+            "count" => stats.count,
+            "size" => stats.size,
+        );
+    }
+
+    fn log_append(&self, mailbox: &str, size: u64) {
+        slog_info!(self.ctx.intercept_logger(), "mail appended";
+            "intercept_type" => "ImapConnection",
+            "task_id" => LtUuid(self.ctx.server_task_id()),
+            "depth" => self.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr(&self.upstream),
+            "mailbox" => mailbox,
+            "size" => size,
+        );
+    }
+
+    fn check_cancel(&self) -> Result<(), ImapInterceptionError> {
+        if self.ctx.belongs_to_blocked_user() {
+            return Err(ImapInterceptionError::CanceledAsUserBlocked);
+        }
+        if self.ctx.server_quit_policy.force_quit() {
+            return Err(ImapInterceptionError::CanceledAsServerQuit);
+        }
+        Ok(())
+    }
+
+    fn start_tls(
+        &mut self,
+        io: ImapInterceptIo,
+    ) -> Result<StreamInspection<SC>, ImapInterceptionError> {
+        let ImapInterceptIo {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        } = io;
+        if !clt_r.buffer().is_empty() || !ups_r.buffer().is_empty() {
+            // no data should be sent before the TLS handshake, see RFC 9051 section 6.2.1
+            return Err(ImapInterceptionError::UnexpectedDataAfterStartTls);
+        }
+        let clt_r = clt_r.into_inner();
+        let ups_r = ups_r.into_inner();
+
+        let mut ctx = self.ctx.clone();
+        ctx.increase_inspection_depth();
+        if let Some(tls_interception) = ctx.tls_interception() {
+            StreamInspectLog::new(&ctx).log(InspectSource::StartTls, Protocol::TlsModern);
+            let mut tls_obj = crate::inspect::tls::TlsInterceptObject::new(
+                ctx,
+                self.upstream.clone(),
+                tls_interception,
+            );
+            tls_obj.set_io(OnceBufReader::with_no_buf(clt_r), clt_w, ups_r, ups_w);
+            tls_obj.set_inner_protocol(Protocol::Imap);
+            Ok(StreamInspection::TlsModern(tls_obj))
+        } else {
+            StreamInspectLog::new(&ctx).log(InspectSource::StartTls, Protocol::Unknown);
+            let mut stream_obj =
+                crate::inspect::stream::StreamInspectObject::new(ctx, self.upstream.clone());
+            stream_obj.set_io(clt_r, clt_w, ups_r, ups_w);
+            Ok(StreamInspection::StreamUnknown(stream_obj))
+        }
+    }
+
+    fn transit_unknown(&self, io: ImapInterceptIo) -> StreamInspection<SC> {
+        let ImapInterceptIo {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        } = io;
+        let mut stream_obj = crate::inspect::stream::StreamInspectObject::new(
+            self.ctx.clone(),
+            self.upstream.clone(),
+        );
+        stream_obj.set_io(Box::new(clt_r), clt_w, Box::new(ups_r), ups_w);
+        StreamInspection::StreamUnknown(stream_obj)
+    }
+
+    async fn read_client_line(
+        &self,
+        clt_r: &mut FlexBufReader<BoxAsyncRead>,
+        line: &mut Vec<u8>,
+    ) -> Result<(), ImapInterceptionError> {
+        let max_size = self.config.command_line_max_size;
+        match tokio::time::timeout(
+            self.config.command_wait_timeout,
+            clt_r.limited_read_until(b'\n', max_size, line),
+        )
+        .await
+        {
+            Ok(Ok((found, nr))) => {
+                if found {
+                    Ok(())
+                } else if nr < max_size {
+                    Err(ImapInterceptionError::ClosedByClient)
+                } else {
+                    Err(ImapInterceptionError::TooLongCommandLine(max_size))
+                }
+            }
+            Ok(Err(e)) => Err(ImapInterceptionError::ClientReadFailed(e)),
+            Err(_) => Err(ImapInterceptionError::ClientReadTimeout),
+        }
+    }
+
+    async fn read_upstream_line(
+        &self,
+        ups_r: &mut FlexBufReader<BoxAsyncRead>,
+        line: &mut Vec<u8>,
+        timeout: Duration,
+    ) -> Result<(), ImapInterceptionError> {
+        let max_size = self.config.response_line_max_size;
+        match tokio::time::timeout(timeout, ups_r.limited_read_until(b'\n', max_size, line)).await {
+            Ok(Ok((found, nr))) => {
+                if found {
+                    Ok(())
+                } else if nr < max_size {
+                    Err(ImapInterceptionError::ClosedByUpstream)
+                } else {
+                    Err(ImapInterceptionError::TooLongResponseLine(max_size))
+                }
+            }
+            Ok(Err(e)) => Err(ImapInterceptionError::UpstreamReadFailed(e)),
+            Err(_) => Err(ImapInterceptionError::UpstreamResponseTimeout),
+        }
+    }
+
+    /// read the client literal data, the data will be dropped if no buf is set
+    async fn read_client_literal(
+        &self,
+        clt_r: &mut FlexBufReader<BoxAsyncRead>,
+        size: u64,
+        mut buf: Option<&mut Vec<u8>>,
+    ) -> Result<(), ImapInterceptionError> {
+        let mut left = size;
+        while left > 0 {
+            let data = match tokio::time::timeout(
+                self.config.command_wait_timeout,
+                clt_r.fill_buf(),
+            )
+            .await
+            {
+                Ok(Ok(data)) => data,
+                Ok(Err(e)) => return Err(ImapInterceptionError::ClientReadFailed(e)),
+                Err(_) => return Err(ImapInterceptionError::ClientReadTimeout),
+            };
+            if data.is_empty() {
+                return Err(ImapInterceptionError::ClosedByClient);
+            }
+            let n = (data.len() as u64).min(left) as usize;
+            if let Some(buf) = &mut buf {
+                buf.extend_from_slice(&data[..n]);
+            }
+            clt_r.consume(n);
+            left -= n as u64;
+        }
+        Ok(())
+    }
+
+    async fn forward_client_literal(
+        &self,
+        io: &mut ImapInterceptIo,
+        size: u64,
+    ) -> Result<(), ImapInterceptionError> {
+        let mut left = size;
+        while left > 0 {
+            let data =
+                match tokio::time::timeout(self.config.command_wait_timeout, io.clt_r.fill_buf())
+                    .await
+                {
+                    Ok(Ok(data)) => data,
+                    Ok(Err(e)) => return Err(ImapInterceptionError::ClientReadFailed(e)),
+                    Err(_) => return Err(ImapInterceptionError::ClientReadTimeout),
+                };
+            if data.is_empty() {
+                return Err(ImapInterceptionError::ClosedByClient);
+            }
+            let n = (data.len() as u64).min(left) as usize;
+            io.ups_w
+                .write_all(&data[..n])
+                .await
+                .map_err(ImapInterceptionError::UpstreamWriteFailed)?;
+            io.clt_r.consume(n);
+            left -= n as u64;
+        }
+        Ok(())
+    }
+
+    async fn forward_upstream_literal(
+        &self,
+        io: &mut ImapInterceptIo,
+        size: u64,
+    ) -> Result<(), ImapInterceptionError> {
+        let mut left = size;
+        while left > 0 {
+            let data =
+                match tokio::time::timeout(self.config.response_wait_timeout, io.ups_r.fill_buf())
+                    .await
+                {
+                    Ok(Ok(data)) => data,
+                    Ok(Err(e)) => return Err(ImapInterceptionError::UpstreamReadFailed(e)),
+                    Err(_) => return Err(ImapInterceptionError::UpstreamResponseTimeout),
+                };
+            if data.is_empty() {
+                return Err(ImapInterceptionError::ClosedByUpstream);
+            }
+            let n = (data.len() as u64).min(left) as usize;
+            io.clt_w
+                .write_all(&data[..n])
+                .await
+                .map_err(ImapInterceptionError::ClientWriteFailed)?;
+            io.ups_r.consume(n);
+            left -= n as u64;
+        }
+        Ok(())
+    }
+
+    /// wait for new data from the client or the upstream, return true if it's from the client
+    async fn wait_readable(&self, io: &mut ImapInterceptIo) -> Result<bool, ImapInterceptionError> {
+        let ImapInterceptIo { clt_r, ups_r, .. } = io;
+        let wait = async {
+            tokio::select! {
+                biased;
+
+                r = ups_r.fill_buf() => {
+                    r.map(|_| false).map_err(ImapInterceptionError::UpstreamReadFailed)
+                }
+                r = clt_r.fill_buf() => {
+                    r.map(|_| true).map_err(ImapInterceptionError::ClientReadFailed)
+                }
+            }
+        };
+        match tokio::time::timeout(self.config.command_wait_timeout, wait).await {
+            Ok(r) => r,
+            Err(_) => Err(ImapInterceptionError::ClientReadTimeout),
+        }
+    }
+
+    /// send the command to upstream and relay the responses, return true if the tagged status is OK
+    async fn relay_command(
+        &self,
+        io: &mut ImapInterceptIo,
+        line: &[u8],
+        tag: &str,
+        stats: &mut ImapFetchStats,
+    ) -> Result<bool, ImapInterceptionError> {
+        let mut seg = line.to_vec();
+        loop {
+            send_to_upstream(&mut io.ups_w, &seg).await?;
+            let Some(literal) = ImapLiteral::parse(&seg) else {
+                break;
+            };
+            if matches!(literal, ImapLiteral::Sync(_)) {
+                if let Some(ok) = self.wait_continuation(io, tag, true, stats).await? {
+                    // the literal is rejected by upstream
+                    return Ok(ok);
+                }
+            }
+            self.forward_client_literal(io, literal.size()).await?;
+            seg.clear();
+            self.read_client_line(&mut io.clt_r, &mut seg).await?;
+        }
+
+        self.relay_responses(io, tag, stats).await
+    }
+
+    /// wait for the continuation response, or the tagged response if the command is rejected
+    async fn wait_continuation(
+        &self,
+        io: &mut ImapInterceptIo,
+        tag: &str,
+        relay: bool,
+        stats: &mut ImapFetchStats,
+    ) -> Result<Option<bool>, ImapInterceptionError> {
+        let mut rsp = Vec::with_capacity(256);
+        loop {
+            rsp.clear();
+            self.read_upstream_line(&mut io.ups_r, &mut rsp, self.config.response_wait_timeout)
+                .await?;
+            match ImapResponseKind::parse(&rsp, tag) {
+                Some(ImapResponseKind::Untagged) => {
+                    self.relay_untagged(io, &mut rsp, stats).await?;
+                }
+                Some(ImapResponseKind::Continuation) => {
+                    if relay {
+                        send_to_client(&mut io.clt_w, &rsp).await?;
+                    }
+                    return Ok(None);
+                }
+                Some(ImapResponseKind::Tagged) => {
+                    send_to_client(&mut io.clt_w, &rsp).await?;
+                    return Ok(Some(command::is_tagged_ok(&rsp, tag)));
+                }
+                None => return Err(ImapInterceptionError::InvalidResponseLine),
+            }
+        }
+    }
+
+    /// relay responses until the tagged response, return true if the tagged status is OK
+    async fn relay_responses(
+        &self,
+        io: &mut ImapInterceptIo,
+        tag: &str,
+        stats: &mut ImapFetchStats,
+    ) -> Result<bool, ImapInterceptionError> {
+        let mut rsp = Vec::with_capacity(256);
+        let mut wait_client = false;
+        loop {
+            let timeout = if wait_client {
+                // the client may send more data after the continuation response, like
+                // the client response in AUTHENTICATE or the DONE in IDLE
+                if self.wait_readable(io).await? {
+                    rsp.clear();
+                    self.read_client_line(&mut io.clt_r, &mut rsp).await?;
+                    send_to_upstream(&mut io.ups_w, &rsp).await?;
+                    wait_client = false;
+                    continue;
+                }
+                self.config.command_wait_timeout
+            } else {
+                self.config.response_wait_timeout
+            };
+
+            rsp.clear();
+            self.read_upstream_line(&mut io.ups_r, &mut rsp, timeout)
+                .await?;
+            match ImapResponseKind::parse(&rsp, tag) {
+                Some(ImapResponseKind::Untagged) => {
+                    self.relay_untagged(io, &mut rsp, stats).await?;
+                }
+                Some(ImapResponseKind::Continuation) => {
+                    send_to_client(&mut io.clt_w, &rsp).await?;
+                    wait_client = true;
+                }
+                Some(ImapResponseKind::Tagged) => {
+                    send_to_client(&mut io.clt_w, &rsp).await?;
+                    return Ok(command::is_tagged_ok(&rsp, tag));
+                }
+                None => return Err(ImapInterceptionError::InvalidResponseLine),
+            }
+        }
+    }
+
+    async fn relay_untagged(
+        &self,
+        io: &mut ImapInterceptIo,
+        line: &mut Vec<u8>,
+        stats: &mut ImapFetchStats,
+    ) -> Result<(), ImapInterceptionError> {
+        let is_fetch = command::is_fetch_response(line);
+        if is_fetch {
+            stats.count += 1;
+        }
+        loop {
+            send_to_client(&mut io.clt_w, line).await?;
+            let Some(literal) = ImapLiteral::parse(line) else {
+                return Ok(());
+            };
+            if is_fetch {
+                stats.size += literal.size();
+            }
+            self.forward_upstream_literal(io, literal.size()).await?;
+            line.clear();
+            self.read_upstream_line(&mut io.ups_r, line, self.config.response_wait_timeout)
+                .await?;
+        }
+    }
+
+    /// drain the non-synchronizing literals sent by the client after a rejected command
+    async fn drain_client_literals(
+        &self,
+        io: &mut ImapInterceptIo,
+        mut literal: ImapLiteral,
+    ) -> Result<(), ImapInterceptionError> {
+        let mut line = Vec::with_capacity(256);
+        while let ImapLiteral::NonSync(size) = literal {
+            self.read_client_literal(&mut io.clt_r, size, None).await?;
+            line.clear();
+            self.read_client_line(&mut io.clt_r, &mut line).await?;
+            match ImapLiteral::parse(&line) {
+                Some(l) => literal = l,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    async fn adapt_append(
+        &self,
+        io: &mut ImapInterceptIo,
+        reqmod_client: &IcapReqmodClient,
+        cmd: &ImapCommandLine<'_>,
+        line: &[u8],
+        literal: ImapLiteral,
+    ) -> Result<(), ImapInterceptionError> {
+        let tag = cmd.tag;
+        let size = literal.size();
+        if size > self.config.adaptation_message_max_size as u64 {
+            self.drain_client_literals(io, literal).await?;
+            let rsp =
+                format!("{tag} NO [TOOBIG] Message size exceeds the content filter limit\r\n");
+            return send_to_client(&mut io.clt_w, rsp.as_bytes()).await;
+        }
+
+        // the APPEND command will be sent to upstream after the adaptation of the message
+        if matches!(literal, ImapLiteral::Sync(_)) {
+            send_to_client(&mut io.clt_w, b"+ Ready for literal data\r\n").await?;
+        }
+        let mut message = Vec::with_capacity(size as usize);
+        self.read_client_literal(&mut io.clt_r, size, Some(&mut message))
+            .await?;
+        let mut line_end = Vec::with_capacity(16);
+        self.read_client_line(&mut io.clt_r, &mut line_end).await?;
+        if let Some(literal) = ImapLiteral::parse(&line_end) {
+            // MULTIAPPEND is not supported
+            self.drain_client_literals(io, literal).await?;
+            let rsp = format!(
+                "{tag} NO [CANNOT] Multiple messages are not supported by the content filter\r\n"
+            );
+            return send_to_client(&mut io.clt_w, rsp.as_bytes()).await;
+        }
+        self.log_append(cmd.mailbox(), size);
+
+        let Some(message) = self.adapt_message(io, reqmod_client, cmd, message).await? else {
+            return Ok(());
+        };
+
+        let mut append = command::strip_literal(line).to_vec();
+        let _ = write!(append, "{{{}}}\r\n", message.len());
+        send_to_upstream(&mut io.ups_w, &append).await?;
+        let mut stats = ImapFetchStats::default();
+        if self
+            .wait_continuation(io, tag, false, &mut stats)
+            .await?
+            .is_some()
+        {
+            // the tagged response has been sent to client
+            return Ok(());
+        }
+        io.ups_w
+            .write_all(&message)
+            .await
+            .map_err(ImapInterceptionError::UpstreamWriteFailed)?;
+        send_to_upstream(&mut io.ups_w, &line_end).await?;
+        self.relay_responses(io, tag, &mut stats).await?;
+        Ok(())
+    }
+
+    /// return the message to be sent to upstream, or None if the client has been replied
+    async fn adapt_message(
+        &self,
+        io: &mut ImapInterceptIo,
+        reqmod_client: &IcapReqmodClient,
+        cmd: &ImapCommandLine<'_>,
+        message: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, ImapInterceptionError> {
+        let tag = cmd.tag;
+        let icap_stats = self.ctx.audit_handle.stats().icap_reqmod();
+        let adapter = match reqmod_client
+            .imap_message_adapter(self.config.command_line_max_size)
+            .await
+        {
+            Ok(mut adapter) => {
+                adapter.set_client_addr(self.ctx.task_notes.client_addr);
+                if let Some(username) = self.ctx.raw_user_name() {
+                    adapter.set_client_username(username);
+                }
+                adapter
+            }
+            Err(e) => {
+                if reqmod_client.bypass() {
+                    icap_stats.add_bypassed();
+                    return Ok(Some(message));
+                }
+                icap_stats.add_error();
+                intercept_log!(self, "failed to get icap adapter: {e:?}");
+                let rsp =
+                    format!("{tag} NO [UNAVAILABLE] Content filter temporarily unavailable\r\n");
+                send_to_client(&mut io.clt_w, rsp.as_bytes()).await?;
+                return Ok(None);
+            }
+        };
+
+        let envelope = ImapMessageEnvelope {
+            upstream: &self.upstream,
+            mailbox: cmd.mailbox(),
+        };
+        match adapter.xfer(&envelope, &message).await {
+            Ok(ImapAdaptationEndState::OriginalMessage) => {
+                icap_stats.add_no_modification();
+                Ok(Some(message))
+            }
+            Ok(ImapAdaptationEndState::AdaptedMessage(adapted)) => {
+                icap_stats.add_adapted();
+                Ok(Some(adapted))
+            }
+            Ok(ImapAdaptationEndState::Blocked(rsp)) => {
+                icap_stats.add_blocked();
+                intercept_log!(self, "mail message blocked by icap server: {}", rsp.status);
+                let rsp = format!("{tag} NO Message rejected by content filter\r\n");
+                send_to_client(&mut io.clt_w, rsp.as_bytes()).await?;
+                Ok(None)
+            }
+            Err(e) => {
+                if reqmod_client.bypass() {
+                    icap_stats.add_bypassed();
+                    return Ok(Some(message));
+                }
+                icap_stats.add_error();
+                intercept_log!(self, "mail message adaptation failed: {e}");
+                let rsp =
+                    format!("{tag} NO [UNAVAILABLE] Content filter temporarily unavailable\r\n");
+                send_to_client(&mut io.clt_w, rsp.as_bytes()).await?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+async fn send_to_client(
+    clt_w: &mut BoxAsyncWrite,
+    data: &[u8],
+) -> Result<(), ImapInterceptionError> {
+    clt_w
+        .write_all(data)
+        .await
+        .map_err(ImapInterceptionError::ClientWriteFailed)?;
+    clt_w
+        .flush()
+        .await
+        .map_err(ImapInterceptionError::ClientWriteFailed)
+}
+
+async fn send_to_upstream(
+    ups_w: &mut BoxAsyncWrite,
+    data: &[u8],
+) -> Result<(), ImapInterceptionError> {
+    ups_w
+        .write_all(data)
+        .await
+        .map_err(ImapInterceptionError::UpstreamWriteFailed)?;
+    ups_w
+        .flush()
+        .await
+        .map_err(ImapInterceptionError::UpstreamWriteFailed)
+}
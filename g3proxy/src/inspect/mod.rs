@@ -22,12 +22,17 @@ use tokio::io::{AsyncRead, AsyncWrite};
 use uuid::Uuid;
 
 use g3_daemon::server::ServerQuitPolicy;
-use g3_dpi::{H1InterceptionConfig, H2InterceptionConfig, MaybeProtocol, ProtocolInspector};
+use g3_dpi::{
+    DnsInterceptionConfig, FtpInterceptionConfig, H1InterceptionConfig, H2InterceptionConfig,
+    ImapInterceptionConfig, MaybeProtocol, Pop3InterceptionConfig, Protocol, ProtocolInspector,
+    SmtpInterceptionConfig, WebSocketInterceptionConfig,
+};
 
-use crate::audit::AuditHandle;
+use crate::audit::{AuditHandle, TrafficMirrorDirection};
 use crate::auth::{User, UserForbiddenStats};
 use crate::config::server::ServerConfig;
 use crate::serve::{ArcServerStats, ServerIdleChecker, ServerTaskNotes};
+use crate::stat::types::{TrafficProtocolCell, TrafficProtocolHint};
 
 mod error;
 pub(crate) use error::InterceptionError;
@@ -38,6 +43,11 @@ pub(crate) mod tls;
 use tls::TlsInterceptionContext;
 
 pub(crate) mod http;
+mod dns;
+mod ftp;
+mod imap;
+mod pop3;
+mod smtp;
 mod websocket;
 
 #[derive(Clone)]
@@ -79,6 +89,8 @@ pub(crate) struct StreamInspectContext<SC: ServerConfig> {
     server_quit_policy: Arc<ServerQuitPolicy>,
     task_notes: StreamInspectTaskNotes,
     inspection_depth: usize,
+    traffic_mirrored: bool,
+    traffic_protocol: Option<Arc<TrafficProtocolCell>>,
 
     task_max_idle_count: i32,
 }
@@ -92,6 +104,8 @@ impl<SC: ServerConfig> Clone for StreamInspectContext<SC> {
             server_quit_policy: self.server_quit_policy.clone(),
             task_notes: self.task_notes.clone(),
             inspection_depth: self.inspection_depth,
+            traffic_mirrored: self.traffic_mirrored,
+            traffic_protocol: self.traffic_protocol.clone(),
             task_max_idle_count: self.task_max_idle_count,
         }
     }
@@ -117,10 +131,23 @@ impl<SC: ServerConfig> StreamInspectContext<SC> {
             server_quit_policy,
             task_notes: StreamInspectTaskNotes::from(task_notes),
             inspection_depth: 0,
+            traffic_mirrored: false,
+            traffic_protocol: None,
             task_max_idle_count,
         }
     }
 
+    /// set the protocol hint for user traffic stats, which will be set by the first inspection
+    pub(crate) fn set_traffic_protocol(&mut self, protocol: Arc<TrafficProtocolCell>) {
+        self.traffic_protocol = Some(protocol);
+    }
+
+    fn resolve_traffic_protocol(&self, protocol: Protocol) {
+        if let Some(cell) = &self.traffic_protocol {
+            cell.resolve(TrafficProtocolHint::from_inspection(protocol));
+        }
+    }
+
     fn user(&self) -> Option<&Arc<User>> {
         self.task_notes.user_ctx.as_ref().map(|cx| &cx.user)
     }
@@ -190,11 +217,37 @@ impl<SC: ServerConfig> StreamInspectContext<SC> {
         self.inspection_depth += 1;
     }
 
-    #[inline]
+    fn mirror_io(
+        &mut self,
+        clt_w: BoxAsyncWrite,
+        ups_w: BoxAsyncWrite,
+    ) -> (BoxAsyncWrite, BoxAsyncWrite) {
+        if self.traffic_mirrored {
+            return (clt_w, ups_w);
+        }
+        let Some(mirror) = self.audit_handle.traffic_mirror() else {
+            return (clt_w, ups_w);
+        };
+        self.traffic_mirrored = true;
+
+        let task_id = self.task_notes.task_id;
+        let clt_w = mirror.wrap_writer(clt_w, task_id, TrafficMirrorDirection::RemoteToClient);
+        let ups_w = mirror.wrap_writer(ups_w, task_id, TrafficMirrorDirection::ClientToRemote);
+        (Box::new(clt_w), Box::new(ups_w))
+    }
+
     pub(crate) fn tls_interception(&self) -> Option<TlsInterceptionContext> {
+        if self.user_tls_interception() == Some(false) {
+            return None;
+        }
         self.audit_handle.tls_interception()
     }
 
+    /// the user level tls interception config, which will override the auditor one
+    fn user_tls_interception(&self) -> Option<bool> {
+        self.user().and_then(|user| user.audit().tls_interception)
+    }
+
     fn log_uri_max_chars(&self) -> usize {
         self.task_notes
             .user_ctx
@@ -213,6 +266,41 @@ impl<SC: ServerConfig> StreamInspectContext<SC> {
         self.audit_handle.h2_interception()
     }
 
+    #[inline]
+    fn websocket_interception(&self) -> &WebSocketInterceptionConfig {
+        self.audit_handle.websocket_interception()
+    }
+
+    #[inline]
+    fn smtp_interception(&self) -> &SmtpInterceptionConfig {
+        self.audit_handle.smtp_interception()
+    }
+
+    #[inline]
+    fn imap_interception(&self) -> &ImapInterceptionConfig {
+        self.audit_handle.imap_interception()
+    }
+
+    #[inline]
+    fn pop3_interception(&self) -> &Pop3InterceptionConfig {
+        self.audit_handle.pop3_interception()
+    }
+
+    #[inline]
+    fn ftp_interception(&self) -> &FtpInterceptionConfig {
+        self.audit_handle.ftp_interception()
+    }
+
+    #[inline]
+    fn dns_interception(&self) -> &DnsInterceptionConfig {
+        self.audit_handle.dns_interception()
+    }
+
+    #[inline]
+    fn dns_domain_blocked(&self, domain: &str) -> bool {
+        self.audit_handle.dns_domain_blocked(domain)
+    }
+
     #[inline]
     fn task_max_idle_count(&self) -> i32 {
         self.task_max_idle_count
@@ -235,6 +323,11 @@ pub(crate) enum StreamInspection<SC: ServerConfig> {
     H1(http::H1InterceptObject<SC>),
     H2(http::H2InterceptObject<SC>),
     Websocket(websocket::H1WebsocketInterceptObject<SC>),
+    Smtp(smtp::SmtpInterceptObject<SC>),
+    Imap(imap::ImapInterceptObject<SC>),
+    Pop3(pop3::Pop3InterceptObject<SC>),
+    Ftp(ftp::FtpInterceptObject<SC>),
+    Dns(dns::DnsInterceptObject<SC>),
 }
 
 type BoxAsyncRead = Box<dyn AsyncRead + Send + Unpin + 'static>;
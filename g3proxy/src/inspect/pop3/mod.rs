@@ -0,0 +1,493 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use slog::slog_info;
+use tokio::io::AsyncWriteExt;
+
+use g3_dpi::{Pop3InterceptionConfig, Protocol};
+use g3_io_ext::{FlexBufReader, LimitedBufReadExt, OnceBufReader};
+use g3_slog_types::{LtUpstreamAddr, LtUuid};
+use g3_types::net::UpstreamAddr;
+
+use crate::config::server::ServerConfig;
+use crate::inspect::{
+    BoxAsyncRead, BoxAsyncWrite, InterceptionError, StreamInspectContext, StreamInspection,
+};
+use crate::log::inspect::{stream::StreamInspectLog, InspectSource};
+use crate::serve::ServerTaskResult;
+
+mod error;
+pub(crate) use error::Pop3InterceptionError;
+
+const DATA_WRITE_BUFFER_SIZE: usize = 16384;
+
+macro_rules! intercept_log {
+    ($obj:tt, $($args:tt)+) => {
+        slog_info!($obj.ctx.intercept_logger(), $($args)+;
+            "intercept_type" => "Pop3Connection",
+            "task_id" => LtUuid($obj.ctx.server_task_id()),
+            "depth" => $obj.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr(&$obj.upstream),
+        )
+    };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pop3ResponseStatus {
+    Ok,
+    Err,
+    Continue,
+}
+
+impl Pop3ResponseStatus {
+    fn parse(line: &[u8]) -> Option<Self> {
+        if line.starts_with(b"+OK") {
+            Some(Pop3ResponseStatus::Ok)
+        } else if line.starts_with(b"-ERR") {
+            Some(Pop3ResponseStatus::Err)
+        } else if line.starts_with(b"+ ") || line.starts_with(b"+\r\n") {
+            Some(Pop3ResponseStatus::Continue)
+        } else {
+            None
+        }
+    }
+}
+
+struct Pop3CommandLine<'a> {
+    verb: String,
+    args: &'a str,
+}
+
+impl<'a> Pop3CommandLine<'a> {
+    fn parse(line: &'a [u8]) -> Self {
+        let line = std::str::from_utf8(line).unwrap_or_default().trim_end();
+        match line.split_once(' ') {
+            Some((verb, args)) => Pop3CommandLine {
+                verb: verb.to_ascii_uppercase(),
+                args: args.trim(),
+            },
+            None => Pop3CommandLine {
+                verb: line.to_ascii_uppercase(),
+                args: "",
+            },
+        }
+    }
+
+    /// check if the +OK response of this command is multi-line, see RFC 1939 and RFC 2449
+    fn has_multi_line_response(&self) -> bool {
+        match self.verb.as_str() {
+            "CAPA" | "RETR" | "TOP" => true,
+            "LIST" | "UIDL" | "AUTH" => self.args.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+struct Pop3InterceptIo {
+    clt_r: FlexBufReader<BoxAsyncRead>,
+    clt_w: BoxAsyncWrite,
+    ups_r: FlexBufReader<BoxAsyncRead>,
+    ups_w: BoxAsyncWrite,
+}
+
+pub(crate) struct Pop3InterceptObject<SC: ServerConfig> {
+    io: Option<Pop3InterceptIo>,
+    ctx: StreamInspectContext<SC>,
+    upstream: UpstreamAddr,
+    config: Pop3InterceptionConfig,
+    from_starttls: bool,
+}
+
+impl<SC: ServerConfig> Pop3InterceptObject<SC> {
+    pub(crate) fn new(ctx: StreamInspectContext<SC>, upstream: UpstreamAddr) -> Self {
+        let config = ctx.pop3_interception().clone();
+        Pop3InterceptObject {
+            io: None,
+            ctx,
+            upstream,
+            config,
+            from_starttls: false,
+        }
+    }
+
+    /// the server greeting has already been sent before STLS
+    pub(crate) fn set_from_starttls(&mut self) {
+        self.from_starttls = true;
+    }
+
+    pub(crate) fn set_io(
+        &mut self,
+        clt_r: FlexBufReader<BoxAsyncRead>,
+        clt_w: BoxAsyncWrite,
+        ups_r: FlexBufReader<BoxAsyncRead>,
+        ups_w: BoxAsyncWrite,
+    ) {
+        let io = Pop3InterceptIo {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        };
+        self.io = Some(io);
+    }
+}
+
+impl<SC> Pop3InterceptObject<SC>
+where
+    SC: ServerConfig + Send + Sync + 'static,
+{
+    pub(crate) async fn intercept(mut self) -> ServerTaskResult<Option<StreamInspection<SC>>> {
+        match self.do_intercept().await {
+            Ok(v) => {
+                intercept_log!(self, "finished");
+                Ok(v)
+            }
+            Err(e) => {
+                intercept_log!(self, "{e}");
+                Err(InterceptionError::Pop3(e).into_server_task_error(Protocol::Pop3))
+            }
+        }
+    }
+
+    async fn do_intercept(
+        &mut self,
+    ) -> Result<Option<StreamInspection<SC>>, Pop3InterceptionError> {
+        let mut io = self.io.take().unwrap();
+
+        if !self.from_starttls {
+            let mut rsp = Vec::with_capacity(128);
+            self.recv_response(&mut io.ups_r, &mut rsp, self.config.greeting_timeout)
+                .await?;
+            send_to_client(&mut io.clt_w, &rsp).await?;
+        }
+
+        let mut line = Vec::with_capacity(128);
+        let mut rsp = Vec::with_capacity(128);
+        let mut auth_continue = false;
+        loop {
+            self.check_cancel()?;
+
+            line.clear();
+            if auth_continue {
+                // this is the client response in the AUTH exchange, not a command
+                self.read_client_line(&mut io.clt_r, &mut line, self.config.data_line_max_size)
+                    .await?;
+                let status = self.relay_command(&mut io, &line, &mut rsp).await?;
+                auth_continue = status == Pop3ResponseStatus::Continue;
+                continue;
+            }
+
+            self.read_client_line(&mut io.clt_r, &mut line, self.config.command_line_max_size)
+                .await?;
+            let cmd = Pop3CommandLine::parse(&line);
+            let status = self.relay_command(&mut io, &line, &mut rsp).await?;
+            match status {
+                Pop3ResponseStatus::Ok => {
+                    if cmd.has_multi_line_response() {
+                        let size = self.relay_multi_line_data(&mut io).await?;
+                        if matches!(cmd.verb.as_str(), "RETR" | "TOP") {
+                            self.log_retrieve(&cmd, size);
+                        }
+                    }
+                    match cmd.verb.as_str() {
+                        "STLS" => return self.start_tls(io).map(Some),
+                        "QUIT" => return Ok(None),
+                        _ => {}
+                    }
+                }
+                Pop3ResponseStatus::Err => {}
+                Pop3ResponseStatus::Continue => auth_continue = cmd.verb == "AUTH",
+            }
+        }
+    }
+
+    fn log_retrieve(&self, cmd: &Pop3CommandLine<'_>, size: usize) {
+        slog_info!(self.ctx.intercept_logger(), "mail retrieved";
+            "intercept_type" => "Pop3Connection",
+            "task_id" => LtUuid(self.ctx.server_task_id()),
+            "depth" => self.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr(&self.upstream),
+            "command" => &cmd.verb,
+            "args" => cmd.args,
+            "size" => size,
+        );
+    }
+
+    fn check_cancel(&self) -> Result<(), Pop3InterceptionError> {
+        if self.ctx.belongs_to_blocked_user() {
+            return Err(Pop3InterceptionError::CanceledAsUserBlocked);
+        }
+        if self.ctx.server_quit_policy.force_quit() {
+            return Err(Pop3InterceptionError::CanceledAsServerQuit);
+        }
+        Ok(())
+    }
+
+    fn start_tls(
+        &mut self,
+        io: Pop3InterceptIo,
+    ) -> Result<StreamInspection<SC>, Pop3InterceptionError> {
+        let Pop3InterceptIo {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        } = io;
+        if !clt_r.buffer().is_empty() || !ups_r.buffer().is_empty() {
+            // no data should be sent before the TLS handshake, see RFC 2595 section 4
+            return Err(Pop3InterceptionError::UnexpectedDataAfterStls);
+        }
+        let clt_r = clt_r.into_inner();
+        let ups_r = ups_r.into_inner();
+
+        let mut ctx = self.ctx.clone();
+        ctx.increase_inspection_depth();
+        if let Some(tls_interception) = ctx.tls_interception() {
+            StreamInspectLog::new(&ctx).log(InspectSource::StartTls, Protocol::TlsModern);
+            let mut tls_obj = crate::inspect::tls::TlsInterceptObject::new(
+                ctx,
+                self.upstream.clone(),
+                tls_interception,
+            );
+            tls_obj.set_io(OnceBufReader::with_no_buf(clt_r), clt_w, ups_r, ups_w);
+            tls_obj.set_inner_protocol(Protocol::Pop3);
+            Ok(StreamInspection::TlsModern(tls_obj))
+        } else {
+            StreamInspectLog::new(&ctx).log(InspectSource::StartTls, Protocol::Unknown);
+            let mut stream_obj =
+                crate::inspect::stream::StreamInspectObject::new(ctx, self.upstream.clone());
+            stream_obj.set_io(clt_r, clt_w, ups_r, ups_w);
+            Ok(StreamInspection::StreamUnknown(stream_obj))
+        }
+    }
+
+    async fn read_client_line(
+        &self,
+        clt_r: &mut FlexBufReader<BoxAsyncRead>,
+        line: &mut Vec<u8>,
+        max_size: usize,
+    ) -> Result<(), Pop3InterceptionError> {
+        match tokio::time::timeout(
+            self.config.command_wait_timeout,
+            clt_r.limited_read_until(b'\n', max_size, line),
+        )
+        .await
+        {
+            Ok(Ok((found, nr))) => {
+                if found {
+                    Ok(())
+                } else if nr < max_size {
+                    Err(Pop3InterceptionError::ClosedByClient)
+                } else {
+                    Err(Pop3InterceptionError::TooLongCommandLine(max_size))
+                }
+            }
+            Ok(Err(e)) => Err(Pop3InterceptionError::ClientReadFailed(e)),
+            Err(_) => Err(Pop3InterceptionError::ClientReadTimeout),
+        }
+    }
+
+    async fn recv_response(
+        &self,
+        ups_r: &mut FlexBufReader<BoxAsyncRead>,
+        rsp: &mut Vec<u8>,
+        timeout: Duration,
+    ) -> Result<Pop3ResponseStatus, Pop3InterceptionError> {
+        let max_size = self.config.response_line_max_size;
+        rsp.clear();
+        let (found, nr) =
+            match tokio::time::timeout(timeout, ups_r.limited_read_until(b'\n', max_size, rsp))
+                .await
+            {
+                Ok(Ok(r)) => r,
+                Ok(Err(e)) => return Err(Pop3InterceptionError::UpstreamReadFailed(e)),
+                Err(_) => return Err(Pop3InterceptionError::UpstreamResponseTimeout),
+            };
+        if !found {
+            return if nr < max_size {
+                Err(Pop3InterceptionError::ClosedByUpstream)
+            } else {
+                Err(Pop3InterceptionError::TooLongResponseLine(max_size))
+            };
+        }
+
+        Pop3ResponseStatus::parse(rsp).ok_or(Pop3InterceptionError::InvalidResponseLine)
+    }
+
+    async fn relay_command(
+        &self,
+        io: &mut Pop3InterceptIo,
+        line: &[u8],
+        rsp: &mut Vec<u8>,
+    ) -> Result<Pop3ResponseStatus, Pop3InterceptionError> {
+        send_to_upstream(&mut io.ups_w, line).await?;
+        let status = self
+            .recv_response(&mut io.ups_r, rsp, self.config.response_wait_timeout)
+            .await?;
+        send_to_client(&mut io.clt_w, rsp).await?;
+        Ok(status)
+    }
+
+    /// relay the data lines of the multi-line response, return the size of the data
+    async fn relay_multi_line_data(
+        &self,
+        io: &mut Pop3InterceptIo,
+    ) -> Result<usize, Pop3InterceptionError> {
+        let max_size = self.config.data_line_max_size;
+        let mut pending = Vec::with_capacity(DATA_WRITE_BUFFER_SIZE);
+        let mut total = 0usize;
+        let mut line_start = true;
+        loop {
+            let offset = pending.len();
+            let (found, nr) = match tokio::time::timeout(
+                self.config.response_wait_timeout,
+                io.ups_r.limited_read_until(b'\n', max_size, &mut pending),
+            )
+            .await
+            {
+                Ok(Ok(r)) => r,
+                Ok(Err(e)) => return Err(Pop3InterceptionError::UpstreamReadFailed(e)),
+                Err(_) => return Err(Pop3InterceptionError::UpstreamResponseTimeout),
+            };
+            if !found && nr < max_size {
+                return Err(Pop3InterceptionError::ClosedByUpstream);
+            }
+
+            let end = line_start && found && matches!(&pending[offset..], b".\r\n" | b".\n");
+            if !end {
+                total += nr;
+            }
+            if end || pending.len() >= DATA_WRITE_BUFFER_SIZE {
+                io.clt_w
+                    .write_all(&pending)
+                    .await
+                    .map_err(Pop3InterceptionError::ClientWriteFailed)?;
+                pending.clear();
+            }
+            if end {
+                break;
+            }
+            line_start = found;
+        }
+        io.clt_w
+            .flush()
+            .await
+            .map_err(Pop3InterceptionError::ClientWriteFailed)?;
+        Ok(total)
+    }
+}
+
+async fn send_to_client(
+    clt_w: &mut BoxAsyncWrite,
+    data: &[u8],
+) -> Result<(), Pop3InterceptionError> {
+    clt_w
+        .write_all(data)
+        .await
+        .map_err(Pop3InterceptionError::ClientWriteFailed)?;
+    clt_w
+        .flush()
+        .await
+        .map_err(Pop3InterceptionError::ClientWriteFailed)
+}
+
+async fn send_to_upstream(
+    ups_w: &mut BoxAsyncWrite,
+    data: &[u8],
+) -> Result<(), Pop3InterceptionError> {
+    ups_w
+        .write_all(data)
+        .await
+        .map_err(Pop3InterceptionError::UpstreamWriteFailed)?;
+    ups_w
+        .flush()
+        .await
+        .map_err(Pop3InterceptionError::UpstreamWriteFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command() {
+        let cmd = Pop3CommandLine::parse(b"retr 1\r\n");
+        assert_eq!(cmd.verb, "RETR");
+        assert_eq!(cmd.args, "1");
+
+        let cmd = Pop3CommandLine::parse(b"TOP 2  10 \r\n");
+        assert_eq!(cmd.verb, "TOP");
+        assert_eq!(cmd.args, "2  10");
+
+        let cmd = Pop3CommandLine::parse(b"STLS\r\n");
+        assert_eq!(cmd.verb, "STLS");
+        assert_eq!(cmd.args, "");
+
+        let cmd = Pop3CommandLine::parse(b"quit\n");
+        assert_eq!(cmd.verb, "QUIT");
+        assert_eq!(cmd.args, "");
+    }
+
+    #[test]
+    fn multi_line_response() {
+        let check = |line: &[u8]| Pop3CommandLine::parse(line).has_multi_line_response();
+
+        assert!(check(b"CAPA\r\n"));
+        assert!(check(b"RETR 1\r\n"));
+        assert!(check(b"TOP 1 10\r\n"));
+        assert!(check(b"LIST\r\n"));
+        assert!(check(b"UIDL\r\n"));
+        assert!(check(b"AUTH\r\n"));
+
+        assert!(!check(b"LIST 1\r\n"));
+        assert!(!check(b"UIDL 1\r\n"));
+        assert!(!check(b"AUTH PLAIN\r\n"));
+        assert!(!check(b"STAT\r\n"));
+        assert!(!check(b"DELE 1\r\n"));
+        assert!(!check(b"USER test\r\n"));
+        assert!(!check(b"STLS\r\n"));
+    }
+
+    #[test]
+    fn response_status() {
+        assert_eq!(
+            Pop3ResponseStatus::parse(b"+OK POP3 server ready\r\n"),
+            Some(Pop3ResponseStatus::Ok)
+        );
+        assert_eq!(
+            Pop3ResponseStatus::parse(b"+OK\r\n"),
+            Some(Pop3ResponseStatus::Ok)
+        );
+        assert_eq!(
+            Pop3ResponseStatus::parse(b"-ERR no such message\r\n"),
+            Some(Pop3ResponseStatus::Err)
+        );
+        assert_eq!(
+            Pop3ResponseStatus::parse(b"+ \r\n"),
+            Some(Pop3ResponseStatus::Continue)
+        );
+        assert_eq!(
+            Pop3ResponseStatus::parse(b"+\r\n"),
+            Some(Pop3ResponseStatus::Continue)
+        );
+        assert_eq!(Pop3ResponseStatus::parse(b"+ok\r\n"), None);
+        assert_eq!(Pop3ResponseStatus::parse(b"OK\r\n"), None);
+        assert_eq!(Pop3ResponseStatus::parse(b"* OK\r\n"), None);
+        assert_eq!(Pop3ResponseStatus::parse(b""), None);
+    }
+}
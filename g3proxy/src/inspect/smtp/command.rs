@@ -0,0 +1,175 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+pub(super) struct SmtpCommandLine<'a> {
+    pub(super) verb: String,
+    pub(super) param: &'a [u8],
+}
+
+impl<'a> SmtpCommandLine<'a> {
+    pub(super) fn parse(line: &'a [u8]) -> Self {
+        let line = trim_line_end(line);
+        match line.iter().position(|c| *c == b' ') {
+            Some(p) => SmtpCommandLine {
+                verb: String::from_utf8_lossy(&line[..p]).to_ascii_uppercase(),
+                param: &line[p + 1..],
+            },
+            None => SmtpCommandLine {
+                verb: String::from_utf8_lossy(line).to_ascii_uppercase(),
+                param: &[],
+            },
+        }
+    }
+
+    /// get the path in MAIL FROM:<path> or RCPT TO:<path>
+    pub(super) fn path(&self, key: &str) -> Option<String> {
+        let param = std::str::from_utf8(self.param).ok()?;
+        let (k, v) = param.split_once(':')?;
+        if !k.trim().eq_ignore_ascii_case(key) {
+            return None;
+        }
+        let v = v.trim_start();
+        if let Some(v) = v.strip_prefix('<') {
+            let (path, _) = v.split_once('>')?;
+            Some(path.to_string())
+        } else {
+            v.split_ascii_whitespace().next().map(|s| s.to_string())
+        }
+    }
+
+    /// get the chunk size and last flag in BDAT command
+    pub(super) fn bdat_chunk(&self) -> Option<(u64, bool)> {
+        let param = std::str::from_utf8(self.param).ok()?;
+        let mut iter = param.split_ascii_whitespace();
+        let size = u64::from_str(iter.next()?).ok()?;
+        match iter.next() {
+            Some(s) if s.eq_ignore_ascii_case("LAST") => Some((size, true)),
+            Some(_) => None,
+            None => Some((size, false)),
+        }
+    }
+}
+
+fn trim_line_end(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+pub(super) fn is_end_of_data(line: &[u8]) -> bool {
+    line == b".\r\n" || line == b".\n"
+}
+
+pub(super) fn dot_stuff(message: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(message.len() + 64);
+    let mut line_start = true;
+    for b in message {
+        if line_start && *b == b'.' {
+            data.push(b'.');
+        }
+        data.push(*b);
+        line_start = *b == b'\n';
+    }
+    if !line_start {
+        data.extend_from_slice(b"\r\n");
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command() {
+        let cmd = SmtpCommandLine::parse(b"ehlo client.example.net\r\n");
+        assert_eq!(cmd.verb, "EHLO");
+        assert_eq!(cmd.param, b"client.example.net");
+
+        let cmd = SmtpCommandLine::parse(b"QUIT\r\n");
+        assert_eq!(cmd.verb, "QUIT");
+        assert!(cmd.param.is_empty());
+
+        let cmd = SmtpCommandLine::parse(b"StartTLS\n");
+        assert_eq!(cmd.verb, "STARTTLS");
+        assert!(cmd.param.is_empty());
+
+        let cmd = SmtpCommandLine::parse(b"AUTH PLAIN dGVzdAB0ZXN0AHRlc3Q=\r\n");
+        assert_eq!(cmd.verb, "AUTH");
+        assert_eq!(cmd.param, b"PLAIN dGVzdAB0ZXN0AHRlc3Q=");
+    }
+
+    #[test]
+    fn path() {
+        let cmd = SmtpCommandLine::parse(b"MAIL FROM:<a@example.net> SIZE=1024 BODY=8BITMIME\r\n");
+        assert_eq!(cmd.path("FROM").unwrap(), "a@example.net");
+        assert!(cmd.path("TO").is_none());
+
+        let cmd = SmtpCommandLine::parse(b"MAIL from: <a@example.net>\r\n");
+        assert_eq!(cmd.path("FROM").unwrap(), "a@example.net");
+
+        let cmd = SmtpCommandLine::parse(b"MAIL FROM:<>\r\n");
+        assert_eq!(cmd.path("FROM").unwrap(), "");
+
+        let cmd = SmtpCommandLine::parse(b"RCPT TO:b@example.net NOTIFY=NEVER\r\n");
+        assert_eq!(cmd.path("TO").unwrap(), "b@example.net");
+
+        let cmd = SmtpCommandLine::parse(b"RCPT TO:<b@example.net\r\n");
+        assert!(cmd.path("TO").is_none());
+
+        let cmd = SmtpCommandLine::parse(b"RCPT b@example.net\r\n");
+        assert!(cmd.path("TO").is_none());
+    }
+
+    #[test]
+    fn bdat_chunk() {
+        let cmd = SmtpCommandLine::parse(b"BDAT 1000\r\n");
+        assert_eq!(cmd.bdat_chunk(), Some((1000, false)));
+
+        let cmd = SmtpCommandLine::parse(b"BDAT 0 LAST\r\n");
+        assert_eq!(cmd.bdat_chunk(), Some((0, true)));
+
+        let cmd = SmtpCommandLine::parse(b"bdat 86 last\r\n");
+        assert_eq!(cmd.bdat_chunk(), Some((86, true)));
+
+        let cmd = SmtpCommandLine::parse(b"BDAT\r\n");
+        assert!(cmd.bdat_chunk().is_none());
+
+        let cmd = SmtpCommandLine::parse(b"BDAT -1\r\n");
+        assert!(cmd.bdat_chunk().is_none());
+
+        let cmd = SmtpCommandLine::parse(b"BDAT 100 MORE\r\n");
+        assert!(cmd.bdat_chunk().is_none());
+    }
+
+    #[test]
+    fn end_of_data() {
+        assert!(is_end_of_data(b".\r\n"));
+        assert!(is_end_of_data(b".\n"));
+        assert!(!is_end_of_data(b"..\r\n"));
+        assert!(!is_end_of_data(b". \r\n"));
+        assert!(!is_end_of_data(b"."));
+    }
+
+    #[test]
+    fn dot_stuffing() {
+        assert_eq!(dot_stuff(b"a\r\n.b\r\n..\r\n"), b"a\r\n..b\r\n...\r\n");
+        assert_eq!(dot_stuff(b".\r\nend"), b"..\r\nend\r\n");
+        assert_eq!(dot_stuff(b"a.b\r\n"), b"a.b\r\n");
+        assert!(dot_stuff(b"").is_empty());
+    }
+}
@@ -0,0 +1,812 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use slog::slog_info;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt};
+
+use g3_dpi::{Protocol, SmtpInterceptionConfig};
+use g3_icap_client::reqmod::smtp::{SmtpAdaptationEndState, SmtpMessageEnvelope};
+use g3_icap_client::reqmod::IcapReqmodClient;
+use g3_io_ext::{FlexBufReader, LimitedBufReadExt, OnceBufReader};
+use g3_slog_types::{LtUpstreamAddr, LtUuid};
+use g3_types::net::UpstreamAddr;
+
+use crate::config::server::ServerConfig;
+use crate::inspect::{
+    BoxAsyncRead, BoxAsyncWrite, InterceptionError, StreamInspectContext, StreamInspection,
+};
+use crate::log::inspect::{stream::StreamInspectLog, InspectSource};
+use crate::serve::ServerTaskResult;
+
+mod error;
+pub(crate) use error::SmtpInterceptionError;
+
+mod command;
+use command::SmtpCommandLine;
+
+mod transaction;
+use transaction::SmtpTransaction;
+
+const RESPONSE_MAX_LINES: usize = 128;
+const DATA_WRITE_BUFFER_SIZE: usize = 16384;
+
+macro_rules! intercept_log {
+    ($obj:tt, $($args:tt)+) => {
+        slog_info!($obj.ctx.intercept_logger(), $($args)+;
+            "intercept_type" => "SmtpConnection",
+            "task_id" => LtUuid($obj.ctx.server_task_id()),
+            "depth" => $obj.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr(&$obj.upstream),
+        )
+    };
+}
+
+struct SmtpResponse {
+    code: u16,
+    raw: Vec<u8>,
+}
+
+struct SmtpInterceptIo {
+    clt_r: FlexBufReader<BoxAsyncRead>,
+    clt_w: BoxAsyncWrite,
+    ups_r: FlexBufReader<BoxAsyncRead>,
+    ups_w: BoxAsyncWrite,
+}
+
+pub(crate) struct SmtpInterceptObject<SC: ServerConfig> {
+    io: Option<SmtpInterceptIo>,
+    ctx: StreamInspectContext<SC>,
+    upstream: UpstreamAddr,
+    config: SmtpInterceptionConfig,
+    from_starttls: bool,
+    transaction: SmtpTransaction,
+    bdat_message: Vec<u8>,
+    bdat_too_large: bool,
+}
+
+impl<SC: ServerConfig> SmtpInterceptObject<SC> {
+    pub(crate) fn new(ctx: StreamInspectContext<SC>, upstream: UpstreamAddr) -> Self {
+        let config = ctx.smtp_interception().clone();
+        SmtpInterceptObject {
+            io: None,
+            ctx,
+            upstream,
+            config,
+            from_starttls: false,
+            transaction: SmtpTransaction::default(),
+            bdat_message: Vec::new(),
+            bdat_too_large: false,
+        }
+    }
+
+    /// the server greeting has already been sent before STARTTLS
+    pub(crate) fn set_from_starttls(&mut self) {
+        self.from_starttls = true;
+    }
+
+    pub(crate) fn set_io(
+        &mut self,
+        clt_r: FlexBufReader<BoxAsyncRead>,
+        clt_w: BoxAsyncWrite,
+        ups_r: FlexBufReader<BoxAsyncRead>,
+        ups_w: BoxAsyncWrite,
+    ) {
+        let io = SmtpInterceptIo {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        };
+        self.io = Some(io);
+    }
+}
+
+impl<SC> SmtpInterceptObject<SC>
+where
+    SC: ServerConfig + Send + Sync + 'static,
+{
+    pub(crate) async fn intercept(mut self) -> ServerTaskResult<Option<StreamInspection<SC>>> {
+        match self.do_intercept().await {
+            Ok(v) => {
+                intercept_log!(self, "finished");
+                Ok(v)
+            }
+            Err(e) => {
+                intercept_log!(self, "{e}");
+                Err(InterceptionError::Smtp(e).into_server_task_error(Protocol::Smtp))
+            }
+        }
+    }
+
+    async fn do_intercept(
+        &mut self,
+    ) -> Result<Option<StreamInspection<SC>>, SmtpInterceptionError> {
+        let mut io = self.io.take().unwrap();
+
+        if !self.from_starttls {
+            let rsp = self
+                .recv_response(&mut io.ups_r, self.config.greeting_timeout)
+                .await?;
+            send_to_client(&mut io.clt_w, &rsp.raw).await?;
+        }
+
+        let reqmod_client = self.ctx.audit_handle.icap_reqmod_client().cloned();
+        let mut line = Vec::with_capacity(512);
+        let mut auth_continue = false;
+        loop {
+            self.check_cancel()?;
+
+            line.clear();
+            self.read_client_line(&mut io.clt_r, &mut line).await?;
+            if auth_continue {
+                // this is the client response in the AUTH exchange, not a command
+                let rsp = self.relay_command(&mut io, &line).await?;
+                auth_continue = rsp.code == 334;
+                continue;
+            }
+
+            let cmd = SmtpCommandLine::parse(&line);
+            match cmd.verb.as_str() {
+                "EHLO" | "HELO" | "RSET" => {
+                    self.reset_transaction();
+                    self.relay_command(&mut io, &line).await?;
+                }
+                "MAIL" => {
+                    self.reset_transaction();
+                    self.transaction.start(&cmd);
+                    self.relay_command(&mut io, &line).await?;
+                }
+                "RCPT" => {
+                    let rsp = self.relay_command(&mut io, &line).await?;
+                    self.transaction.add_recipient(&cmd, rsp.code);
+                }
+                "AUTH" => {
+                    let rsp = self.relay_command(&mut io, &line).await?;
+                    auth_continue = rsp.code == 334;
+                }
+                "DATA" => {
+                    if let Some(client) = &reqmod_client {
+                        self.adapt_data(&mut io, client).await?;
+                    } else {
+                        self.relay_data(&mut io, &line).await?;
+                    }
+                }
+                "BDAT" => {
+                    let (size, last) = cmd
+                        .bdat_chunk()
+                        .ok_or(SmtpInterceptionError::InvalidBdatCommand)?;
+                    if let Some(client) = &reqmod_client {
+                        self.adapt_bdat(&mut io, client, size, last).await?;
+                    } else {
+                        self.relay_bdat(&mut io, &line, size, last).await?;
+                    }
+                }
+                "STARTTLS" => {
+                    let rsp = self.relay_command(&mut io, &line).await?;
+                    if rsp.code == 220 {
+                        return self.start_tls(io).map(Some);
+                    }
+                }
+                "QUIT" => {
+                    self.relay_command(&mut io, &line).await?;
+                    return Ok(None);
+                }
+                _ => {
+                    self.relay_command(&mut io, &line).await?;
+                }
+            }
+        }
+    }
+
+    fn reset_transaction(&mut self) {
+        self.transaction.reset();
+        self.bdat_message.clear();
+        self.bdat_too_large = false;
+    }
+
+    fn check_cancel(&self) -> Result<(), SmtpInterceptionError> {
+        if self.ctx.belongs_to_blocked_user() {
+            return Err(SmtpInterceptionError::CanceledAsUserBlocked);
+        }
+        if self.ctx.server_quit_policy.force_quit() {
+            return Err(SmtpInterceptionError::CanceledAsServerQuit);
+        }
+        Ok(())
+    }
+
+    fn start_tls(
+        &mut self,
+        io: SmtpInterceptIo,
+    ) -> Result<StreamInspection<SC>, SmtpInterceptionError> {
+        let SmtpInterceptIo {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        } = io;
+        if !clt_r.buffer().is_empty() || !ups_r.buffer().is_empty() {
+            // no data should be sent before the TLS handshake, see RFC 3207
+            return Err(SmtpInterceptionError::UnexpectedDataAfterStartTls);
+        }
+        let clt_r = clt_r.into_inner();
+        let ups_r = ups_r.into_inner();
+
+        let mut ctx = self.ctx.clone();
+        ctx.increase_inspection_depth();
+        if let Some(tls_interception) = ctx.tls_interception() {
+            StreamInspectLog::new(&ctx).log(InspectSource::StartTls, Protocol::TlsModern);
+            let mut tls_obj = crate::inspect::tls::TlsInterceptObject::new(
+                ctx,
+                self.upstream.clone(),
+                tls_interception,
+            );
+            tls_obj.set_io(OnceBufReader::with_no_buf(clt_r), clt_w, ups_r, ups_w);
+            tls_obj.set_inner_protocol(Protocol::Smtp);
+            Ok(StreamInspection::TlsModern(tls_obj))
+        } else {
+            StreamInspectLog::new(&ctx).log(InspectSource::StartTls, Protocol::Unknown);
+            let mut stream_obj =
+                crate::inspect::stream::StreamInspectObject::new(ctx, self.upstream.clone());
+            stream_obj.set_io(clt_r, clt_w, ups_r, ups_w);
+            Ok(StreamInspection::StreamUnknown(stream_obj))
+        }
+    }
+
+    async fn read_client_line(
+        &self,
+        clt_r: &mut FlexBufReader<BoxAsyncRead>,
+        line: &mut Vec<u8>,
+    ) -> Result<(), SmtpInterceptionError> {
+        let max_size = self.config.command_line_max_size;
+        match tokio::time::timeout(
+            self.config.command_wait_timeout,
+            clt_r.limited_read_until(b'\n', max_size, line),
+        )
+        .await
+        {
+            Ok(Ok((found, nr))) => {
+                if found {
+                    Ok(())
+                } else if nr < max_size {
+                    Err(SmtpInterceptionError::ClosedByClient)
+                } else {
+                    Err(SmtpInterceptionError::TooLongCommandLine(max_size))
+                }
+            }
+            Ok(Err(e)) => Err(SmtpInterceptionError::ClientReadFailed(e)),
+            Err(_) => Err(SmtpInterceptionError::ClientReadTimeout),
+        }
+    }
+
+    /// read a line in the mail data, return true if the line is complete
+    async fn read_data_line(
+        &self,
+        clt_r: &mut FlexBufReader<BoxAsyncRead>,
+        line: &mut Vec<u8>,
+    ) -> Result<bool, SmtpInterceptionError> {
+        let max_size = self.config.data_line_max_size;
+        match tokio::time::timeout(
+            self.config.command_wait_timeout,
+            clt_r.limited_read_until(b'\n', max_size, line),
+        )
+        .await
+        {
+            Ok(Ok((found, nr))) => {
+                if found {
+                    Ok(true)
+                } else if nr < max_size {
+                    Err(SmtpInterceptionError::ClosedByClient)
+                } else {
+                    Ok(false)
+                }
+            }
+            Ok(Err(e)) => Err(SmtpInterceptionError::ClientReadFailed(e)),
+            Err(_) => Err(SmtpInterceptionError::ClientReadTimeout),
+        }
+    }
+
+    /// read exactly `size` bytes of BDAT chunk data, the data will be dropped if no buf is set
+    async fn read_chunk_data(
+        &self,
+        clt_r: &mut FlexBufReader<BoxAsyncRead>,
+        size: u64,
+        mut buf: Option<&mut Vec<u8>>,
+    ) -> Result<(), SmtpInterceptionError> {
+        let mut left = size;
+        while left > 0 {
+            let data = match tokio::time::timeout(
+                self.config.command_wait_timeout,
+                clt_r.fill_buf(),
+            )
+            .await
+            {
+                Ok(Ok(data)) => data,
+                Ok(Err(e)) => return Err(SmtpInterceptionError::ClientReadFailed(e)),
+                Err(_) => return Err(SmtpInterceptionError::ClientReadTimeout),
+            };
+            if data.is_empty() {
+                return Err(SmtpInterceptionError::ClosedByClient);
+            }
+            let n = (data.len() as u64).min(left) as usize;
+            if let Some(buf) = &mut buf {
+                buf.extend_from_slice(&data[..n]);
+            }
+            clt_r.consume(n);
+            left -= n as u64;
+        }
+        Ok(())
+    }
+
+    async fn recv_response(
+        &self,
+        ups_r: &mut FlexBufReader<BoxAsyncRead>,
+        timeout: Duration,
+    ) -> Result<SmtpResponse, SmtpInterceptionError> {
+        let max_size = self.config.response_line_max_size;
+        match tokio::time::timeout(timeout, read_response(ups_r, max_size)).await {
+            Ok(r) => r,
+            Err(_) => Err(SmtpInterceptionError::UpstreamResponseTimeout),
+        }
+    }
+
+    async fn relay_command(
+        &self,
+        io: &mut SmtpInterceptIo,
+        line: &[u8],
+    ) -> Result<SmtpResponse, SmtpInterceptionError> {
+        send_to_upstream(&mut io.ups_w, line).await?;
+        let rsp = self
+            .recv_response(&mut io.ups_r, self.config.response_wait_timeout)
+            .await?;
+        send_to_client(&mut io.clt_w, &rsp.raw).await?;
+        Ok(rsp)
+    }
+
+    async fn relay_data(
+        &self,
+        io: &mut SmtpInterceptIo,
+        line: &[u8],
+    ) -> Result<(), SmtpInterceptionError> {
+        send_to_upstream(&mut io.ups_w, line).await?;
+        let rsp = self
+            .recv_response(&mut io.ups_r, self.config.data_initiation_timeout)
+            .await?;
+        send_to_client(&mut io.clt_w, &rsp.raw).await?;
+        if rsp.code != 354 {
+            return Ok(());
+        }
+
+        let mut pending = Vec::with_capacity(DATA_WRITE_BUFFER_SIZE);
+        let mut line_start = true;
+        loop {
+            let offset = pending.len();
+            let found = self.read_data_line(&mut io.clt_r, &mut pending).await?;
+            let end = line_start && found && command::is_end_of_data(&pending[offset..]);
+            if end || pending.len() >= DATA_WRITE_BUFFER_SIZE {
+                io.ups_w
+                    .write_all(&pending)
+                    .await
+                    .map_err(SmtpInterceptionError::UpstreamWriteFailed)?;
+                pending.clear();
+            }
+            if end {
+                break;
+            }
+            line_start = found;
+        }
+        io.ups_w
+            .flush()
+            .await
+            .map_err(SmtpInterceptionError::UpstreamWriteFailed)?;
+
+        let rsp = self
+            .recv_response(&mut io.ups_r, self.config.data_termination_timeout)
+            .await?;
+        send_to_client(&mut io.clt_w, &rsp.raw).await
+    }
+
+    async fn relay_bdat(
+        &self,
+        io: &mut SmtpInterceptIo,
+        line: &[u8],
+        size: u64,
+        last: bool,
+    ) -> Result<(), SmtpInterceptionError> {
+        io.ups_w
+            .write_all(line)
+            .await
+            .map_err(SmtpInterceptionError::UpstreamWriteFailed)?;
+
+        let mut left = size;
+        while left > 0 {
+            let data =
+                match tokio::time::timeout(self.config.command_wait_timeout, io.clt_r.fill_buf())
+                    .await
+                {
+                    Ok(Ok(data)) => data,
+                    Ok(Err(e)) => return Err(SmtpInterceptionError::ClientReadFailed(e)),
+                    Err(_) => return Err(SmtpInterceptionError::ClientReadTimeout),
+                };
+            if data.is_empty() {
+                return Err(SmtpInterceptionError::ClosedByClient);
+            }
+            let n = (data.len() as u64).min(left) as usize;
+            io.ups_w
+                .write_all(&data[..n])
+                .await
+                .map_err(SmtpInterceptionError::UpstreamWriteFailed)?;
+            io.clt_r.consume(n);
+            left -= n as u64;
+        }
+        io.ups_w
+            .flush()
+            .await
+            .map_err(SmtpInterceptionError::UpstreamWriteFailed)?;
+
+        let timeout = if last {
+            self.config.data_termination_timeout
+        } else {
+            self.config.response_wait_timeout
+        };
+        let rsp = self.recv_response(&mut io.ups_r, timeout).await?;
+        send_to_client(&mut io.clt_w, &rsp.raw).await
+    }
+
+    async fn adapt_data(
+        &mut self,
+        io: &mut SmtpInterceptIo,
+        reqmod_client: &IcapReqmodClient,
+    ) -> Result<(), SmtpInterceptionError> {
+        // the DATA command will be sent to upstream after the adaptation of the whole message
+        send_to_client(
+            &mut io.clt_w,
+            b"354 Start mail input; end with <CRLF>.<CRLF>\r\n",
+        )
+        .await?;
+
+        let max_size = self.config.adaptation_message_max_size;
+        let mut message = Vec::new();
+        let mut too_large = false;
+        let mut line = Vec::with_capacity(1024);
+        let mut line_start = true;
+        loop {
+            line.clear();
+            let found = self.read_data_line(&mut io.clt_r, &mut line).await?;
+            if line_start && found && command::is_end_of_data(&line) {
+                break;
+            }
+            if !too_large {
+                let data = if line_start && line.first() == Some(&b'.') {
+                    // remove the leading dot added by the client, see RFC 5321 section 4.5.2
+                    &line[1..]
+                } else {
+                    &line[..]
+                };
+                if message.len() + data.len() > max_size {
+                    too_large = true;
+                    message = Vec::new();
+                } else {
+                    message.extend_from_slice(data);
+                }
+            }
+            line_start = found;
+        }
+        if too_large {
+            return send_to_client(
+                &mut io.clt_w,
+                b"552 5.3.4 Message size exceeds the content filter limit\r\n",
+            )
+            .await;
+        }
+
+        let Some(message) = self.adapt_message(io, reqmod_client, message).await? else {
+            return Ok(());
+        };
+
+        send_to_upstream(&mut io.ups_w, b"DATA\r\n").await?;
+        let rsp = self
+            .recv_response(&mut io.ups_r, self.config.data_initiation_timeout)
+            .await?;
+        if rsp.code != 354 {
+            // use the upstream response as the final reply of the mail data
+            return send_to_client(&mut io.clt_w, &rsp.raw).await;
+        }
+
+        let data = command::dot_stuff(&message);
+        io.ups_w
+            .write_all(&data)
+            .await
+            .map_err(SmtpInterceptionError::UpstreamWriteFailed)?;
+        send_to_upstream(&mut io.ups_w, b".\r\n").await?;
+        let rsp = self
+            .recv_response(&mut io.ups_r, self.config.data_termination_timeout)
+            .await?;
+        send_to_client(&mut io.clt_w, &rsp.raw).await
+    }
+
+    async fn adapt_bdat(
+        &mut self,
+        io: &mut SmtpInterceptIo,
+        reqmod_client: &IcapReqmodClient,
+        size: u64,
+        last: bool,
+    ) -> Result<(), SmtpInterceptionError> {
+        let max_size = self.config.adaptation_message_max_size as u64;
+        if !self.bdat_too_large && self.bdat_message.len() as u64 + size <= max_size {
+            let mut buf = std::mem::take(&mut self.bdat_message);
+            let r = self
+                .read_chunk_data(&mut io.clt_r, size, Some(&mut buf))
+                .await;
+            self.bdat_message = buf;
+            r?;
+        } else {
+            self.bdat_too_large = true;
+            self.bdat_message = Vec::new();
+            self.read_chunk_data(&mut io.clt_r, size, None).await?;
+        }
+
+        if !last {
+            // the chunks will be sent to upstream after the adaptation of the whole message
+            let msg = format!("250 2.0.0 {size} octets received\r\n");
+            return send_to_client(&mut io.clt_w, msg.as_bytes()).await;
+        }
+
+        let message = std::mem::take(&mut self.bdat_message);
+        if std::mem::take(&mut self.bdat_too_large) {
+            return send_to_client(
+                &mut io.clt_w,
+                b"552 5.3.4 Message size exceeds the content filter limit\r\n",
+            )
+            .await;
+        }
+
+        let Some(message) = self.adapt_message(io, reqmod_client, message).await? else {
+            return Ok(());
+        };
+
+        let cmd = format!("BDAT {} LAST\r\n", message.len());
+        io.ups_w
+            .write_all(cmd.as_bytes())
+            .await
+            .map_err(SmtpInterceptionError::UpstreamWriteFailed)?;
+        send_to_upstream(&mut io.ups_w, &message).await?;
+        let rsp = self
+            .recv_response(&mut io.ups_r, self.config.data_termination_timeout)
+            .await?;
+        send_to_client(&mut io.clt_w, &rsp.raw).await
+    }
+
+    /// return the message to be sent to upstream, or None if the client has been replied
+    async fn adapt_message(
+        &self,
+        io: &mut SmtpInterceptIo,
+        reqmod_client: &IcapReqmodClient,
+        message: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, SmtpInterceptionError> {
+        let icap_stats = self.ctx.audit_handle.stats().icap_reqmod();
+        let adapter = match reqmod_client
+            .smtp_message_adapter(self.config.data_line_max_size)
+            .await
+        {
+            Ok(mut adapter) => {
+                adapter.set_client_addr(self.ctx.task_notes.client_addr);
+                if let Some(username) = self.ctx.raw_user_name() {
+                    adapter.set_client_username(username);
+                }
+                adapter
+            }
+            Err(e) => {
+                if reqmod_client.bypass() {
+                    icap_stats.add_bypassed();
+                    return Ok(Some(message));
+                }
+                icap_stats.add_error();
+                intercept_log!(self, "failed to get icap adapter: {e:?}");
+                send_to_client(
+                    &mut io.clt_w,
+                    b"451 4.3.0 Content filter temporarily unavailable\r\n",
+                )
+                .await?;
+                return Ok(None);
+            }
+        };
+
+        let envelope = SmtpMessageEnvelope {
+            upstream: &self.upstream,
+            mail_from: &self.transaction.mail_from,
+            rcpt_to: &self.transaction.rcpt_to,
+        };
+        match adapter.xfer(&envelope, &message).await {
+            Ok(SmtpAdaptationEndState::OriginalMessage) => {
+                icap_stats.add_no_modification();
+                Ok(Some(message))
+            }
+            Ok(SmtpAdaptationEndState::AdaptedMessage(adapted)) => {
+                icap_stats.add_adapted();
+                Ok(Some(adapted))
+            }
+            Ok(SmtpAdaptationEndState::Blocked(rsp)) => {
+                icap_stats.add_blocked();
+                intercept_log!(self, "mail message blocked by icap server: {}", rsp.status);
+                send_to_client(
+                    &mut io.clt_w,
+                    b"554 5.7.1 Message rejected by content filter\r\n",
+                )
+                .await?;
+                Ok(None)
+            }
+            Err(e) => {
+                if reqmod_client.bypass() {
+                    icap_stats.add_bypassed();
+                    return Ok(Some(message));
+                }
+                icap_stats.add_error();
+                intercept_log!(self, "mail message adaptation failed: {e}");
+                send_to_client(
+                    &mut io.clt_w,
+                    b"451 4.3.0 Content filter temporarily unavailable\r\n",
+                )
+                .await?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// read a complete (maybe multiline) reply, see RFC 5321 section 4.2.1
+async fn read_response<R>(
+    ups_r: &mut R,
+    max_size: usize,
+) -> Result<SmtpResponse, SmtpInterceptionError>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut raw = Vec::with_capacity(256);
+    for _ in 0..RESPONSE_MAX_LINES {
+        let offset = raw.len();
+        let (found, nr) = ups_r
+            .limited_read_until(b'\n', max_size, &mut raw)
+            .await
+            .map_err(SmtpInterceptionError::UpstreamReadFailed)?;
+        if !found {
+            return if nr < max_size {
+                Err(SmtpInterceptionError::ClosedByUpstream)
+            } else {
+                Err(SmtpInterceptionError::TooLongResponseLine(max_size))
+            };
+        }
+
+        let line = &raw[offset..];
+        if line.len() < 4 || !line[..3].iter().all(|c| c.is_ascii_digit()) {
+            return Err(SmtpInterceptionError::InvalidResponseLine);
+        }
+        let code = line[..3]
+            .iter()
+            .fold(0u16, |acc, c| acc * 10 + (*c - b'0') as u16);
+        if line[3] != b'-' {
+            return Ok(SmtpResponse { code, raw });
+        }
+    }
+    Err(SmtpInterceptionError::TooManyResponseLines)
+}
+
+async fn send_to_client(
+    clt_w: &mut BoxAsyncWrite,
+    data: &[u8],
+) -> Result<(), SmtpInterceptionError> {
+    clt_w
+        .write_all(data)
+        .await
+        .map_err(SmtpInterceptionError::ClientWriteFailed)?;
+    clt_w
+        .flush()
+        .await
+        .map_err(SmtpInterceptionError::ClientWriteFailed)
+}
+
+async fn send_to_upstream(
+    ups_w: &mut BoxAsyncWrite,
+    data: &[u8],
+) -> Result<(), SmtpInterceptionError> {
+    ups_w
+        .write_all(data)
+        .await
+        .map_err(SmtpInterceptionError::UpstreamWriteFailed)?;
+    ups_w
+        .flush()
+        .await
+        .map_err(SmtpInterceptionError::UpstreamWriteFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read(data: &[u8], max_size: usize) -> Result<SmtpResponse, SmtpInterceptionError> {
+        let mut reader = data;
+        read_response(&mut reader, max_size).await
+    }
+
+    #[tokio::test]
+    async fn single_line_reply() {
+        let rsp = read(b"220 mx.example.net ESMTP ready\r\n", 1024)
+            .await
+            .unwrap();
+        assert_eq!(rsp.code, 220);
+        assert_eq!(rsp.raw, b"220 mx.example.net ESMTP ready\r\n");
+
+        let rsp = read(b"354\r\n", 1024).await.unwrap();
+        assert_eq!(rsp.code, 354);
+    }
+
+    #[tokio::test]
+    async fn multi_line_reply() {
+        const DATA: &[u8] =
+            b"250-mx.example.net\r\n250-PIPELINING\r\n250-STARTTLS\r\n250 CHUNKING\r\n";
+        let mut reader =
+            &b"250-mx.example.net\r\n250-PIPELINING\r\n250-STARTTLS\r\n250 CHUNKING\r\n221 bye\r\n"
+                [..];
+        let rsp = read_response(&mut reader, 1024).await.unwrap();
+        assert_eq!(rsp.code, 250);
+        assert_eq!(rsp.raw, DATA);
+
+        // the next reply should be left in the reader
+        let rsp = read_response(&mut reader, 1024).await.unwrap();
+        assert_eq!(rsp.code, 221);
+    }
+
+    #[tokio::test]
+    async fn invalid_reply() {
+        let r = read(b"25 ok\r\n", 1024).await;
+        assert!(matches!(r, Err(SmtpInterceptionError::InvalidResponseLine)));
+
+        let r = read(b"2x0 ok\r\n", 1024).await;
+        assert!(matches!(r, Err(SmtpInterceptionError::InvalidResponseLine)));
+
+        let r = read(b"250-first\r\nnot a reply\r\n", 1024).await;
+        assert!(matches!(r, Err(SmtpInterceptionError::InvalidResponseLine)));
+    }
+
+    #[tokio::test]
+    async fn incomplete_reply() {
+        let r = read(b"250 ok", 1024).await;
+        assert!(matches!(r, Err(SmtpInterceptionError::ClosedByUpstream)));
+
+        let r = read(b"250-first\r\n", 1024).await;
+        assert!(matches!(r, Err(SmtpInterceptionError::ClosedByUpstream)));
+
+        let r = read(b"", 1024).await;
+        assert!(matches!(r, Err(SmtpInterceptionError::ClosedByUpstream)));
+    }
+
+    #[tokio::test]
+    async fn too_long_reply() {
+        let r = read(b"250 a very long reply line\r\n", 8).await;
+        assert!(matches!(
+            r,
+            Err(SmtpInterceptionError::TooLongResponseLine(8))
+        ));
+
+        let data = b"250-line\r\n".repeat(RESPONSE_MAX_LINES + 1);
+        let r = read(&data, 1024).await;
+        assert!(matches!(
+            r,
+            Err(SmtpInterceptionError::TooManyResponseLines)
+        ));
+    }
+}
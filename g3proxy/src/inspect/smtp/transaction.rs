@@ -0,0 +1,96 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::SmtpCommandLine;
+
+/// The envelope of the current mail transaction, see RFC 5321 section 3.3
+#[derive(Default)]
+pub(super) struct SmtpTransaction {
+    pub(super) mail_from: String,
+    pub(super) rcpt_to: Vec<String>,
+}
+
+impl SmtpTransaction {
+    pub(super) fn reset(&mut self) {
+        self.mail_from.clear();
+        self.rcpt_to.clear();
+    }
+
+    /// start a new transaction by the MAIL command
+    pub(super) fn start(&mut self, cmd: &SmtpCommandLine) {
+        self.reset();
+        self.mail_from = cmd.path("FROM").unwrap_or_default();
+    }
+
+    /// add the recipient in the RCPT command if it has been accepted by the server
+    pub(super) fn add_recipient(&mut self, cmd: &SmtpCommandLine, rsp_code: u16) {
+        if rsp_code / 100 != 2 {
+            return;
+        }
+        if let Some(path) = cmd.path("TO") {
+            self.rcpt_to.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope() {
+        let mut t = SmtpTransaction::default();
+        t.start(&SmtpCommandLine::parse(
+            b"MAIL FROM:<a@example.net> SIZE=100\r\n",
+        ));
+        assert_eq!(t.mail_from, "a@example.net");
+
+        t.add_recipient(&SmtpCommandLine::parse(b"RCPT TO:<b@example.net>\r\n"), 250);
+        t.add_recipient(&SmtpCommandLine::parse(b"RCPT TO:<c@example.net>\r\n"), 550);
+        t.add_recipient(&SmtpCommandLine::parse(b"RCPT TO:<d@example.net>\r\n"), 451);
+        t.add_recipient(&SmtpCommandLine::parse(b"RCPT TO:<e@example.net>\r\n"), 251);
+        assert_eq!(t.rcpt_to, ["b@example.net", "e@example.net"]);
+
+        // a new MAIL command starts a new transaction
+        t.start(&SmtpCommandLine::parse(b"MAIL FROM:<>\r\n"));
+        assert_eq!(t.mail_from, "");
+        assert!(t.rcpt_to.is_empty());
+    }
+
+    #[test]
+    fn reset() {
+        let mut t = SmtpTransaction::default();
+        t.start(&SmtpCommandLine::parse(b"MAIL FROM:<a@example.net>\r\n"));
+        t.add_recipient(&SmtpCommandLine::parse(b"RCPT TO:<b@example.net>\r\n"), 250);
+        t.reset();
+        assert!(t.mail_from.is_empty());
+        assert!(t.rcpt_to.is_empty());
+    }
+
+    #[test]
+    fn invalid_path() {
+        let mut t = SmtpTransaction::default();
+        t.start(&SmtpCommandLine::parse(b"MAIL TO:<a@example.net>\r\n"));
+        assert!(t.mail_from.is_empty());
+
+        t.add_recipient(
+            &SmtpCommandLine::parse(b"RCPT FROM:<b@example.net>\r\n"),
+            250,
+        );
+        t.add_recipient(&SmtpCommandLine::parse(b"RCPT TO:<c@example.net\r\n"), 250);
+        assert!(t.rcpt_to.is_empty());
+    }
+}
@@ -273,6 +273,37 @@ where
                 StreamInspection::Websocket(websocket) => {
                     return websocket.intercept().await;
                 }
+                StreamInspection::Smtp(smtp) => match smtp.intercept().await? {
+                    Some(new_obj) => {
+                        obj = new_obj;
+                        inspector.reset_state();
+                    }
+                    None => break,
+                },
+                StreamInspection::Imap(imap) => match imap.intercept().await? {
+                    Some(new_obj) => {
+                        obj = new_obj;
+                        inspector.reset_state();
+                    }
+                    None => break,
+                },
+                StreamInspection::Pop3(pop3) => match pop3.intercept().await? {
+                    Some(new_obj) => {
+                        obj = new_obj;
+                        inspector.reset_state();
+                    }
+                    None => break,
+                },
+                StreamInspection::Ftp(ftp) => match ftp.intercept().await? {
+                    Some(new_obj) => {
+                        obj = new_obj;
+                        inspector.reset_state();
+                    }
+                    None => break,
+                },
+                StreamInspection::Dns(dns) => {
+                    return dns.intercept().await;
+                }
                 StreamInspection::End => break,
             }
         }
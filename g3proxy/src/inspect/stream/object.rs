@@ -21,11 +21,12 @@ use g3_dpi::{Protocol, ProtocolInspector};
 use g3_io_ext::{FlexBufReader, OnceBufReader};
 use g3_types::net::UpstreamAddr;
 
+use crate::config::audit::ProtocolPolicyAction;
 use crate::config::server::ServerConfig;
 use crate::inspect::{BoxAsyncRead, BoxAsyncWrite, StreamInspectContext, StreamInspection};
 use crate::log::inspect::stream::StreamInspectLog;
 use crate::log::inspect::InspectSource;
-use crate::serve::{ServerTaskError, ServerTaskResult};
+use crate::serve::{ServerTaskError, ServerTaskForbiddenError, ServerTaskResult};
 
 enum InitialDataSource {
     Client,
@@ -90,11 +91,29 @@ where
     ) -> ServerTaskResult<StreamInspection<SC>> {
         let StreamInspectIo {
             mut clt_r,
-            clt_w,
+            mut clt_w,
             mut ups_r,
-            ups_w,
+            mut ups_w,
         } = self.io.take().unwrap();
 
+        if let Some(transfer) = crate::inspect::ftp::take_passive_channel(
+            self.ctx.task_notes.client_addr.ip(),
+            &self.upstream,
+        ) {
+            self.ctx.resolve_traffic_protocol(Protocol::Unknown);
+            crate::inspect::ftp::transit_passive_channel(
+                self.ctx,
+                &self.upstream,
+                transfer,
+                clt_r,
+                clt_w,
+                ups_r,
+                ups_w,
+            )
+            .await?;
+            return Ok(StreamInspection::End);
+        }
+
         let inspect_buffer_size = self.ctx.protocol_inspection().data0_buffer_size();
         let mut clt_r_buf = BytesMut::with_capacity(inspect_buffer_size);
         let mut ups_r_buf = BytesMut::with_capacity(inspect_buffer_size);
@@ -107,7 +126,12 @@ where
         {
             Ok(Ok(s)) => s,
             Ok(Err(e)) => return Err(e),
-            Err(_) => return Ok(StreamInspection::StreamUnknown(self)),
+            Err(_) => {
+                // no data has been read, so it's safe to reuse the io
+                self.ctx.resolve_traffic_protocol(Protocol::Unknown);
+                self.set_io(clt_r, clt_w, ups_r, ups_w);
+                return Ok(StreamInspection::StreamUnknown(self));
+            }
         };
 
         let protocol = match tokio::time::timeout(
@@ -125,11 +149,52 @@ where
         {
             Ok(Ok(p)) => p,
             Ok(Err(e)) => return Err(e),
-            Err(_) => return Ok(StreamInspection::StreamUnknown(self)),
+            Err(_) => {
+                // fallback to transparent copy with the buffered data
+                self.ctx.audit_handle.stats().add_inspect_timeout();
+                Protocol::Unknown
+            }
         };
 
+        self.ctx.resolve_traffic_protocol(protocol);
         self.ctx.increase_inspection_depth();
-        StreamInspectLog::new(&self.ctx).log(InspectSource::StreamInspection, protocol);
+        let policy_action = self
+            .ctx
+            .audit_handle
+            .check_protocol_policy(protocol, self.upstream.port());
+        match policy_action {
+            ProtocolPolicyAction::Allow => {
+                StreamInspectLog::new(&self.ctx).log(InspectSource::StreamInspection, protocol)
+            }
+            ProtocolPolicyAction::Log => StreamInspectLog::new(&self.ctx).log_with_action(
+                InspectSource::StreamInspection,
+                protocol,
+                policy_action,
+            ),
+            ProtocolPolicyAction::Block => {
+                StreamInspectLog::new(&self.ctx).log_with_action(
+                    InspectSource::StreamInspection,
+                    protocol,
+                    policy_action,
+                );
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::ProtocolBlocked,
+                ));
+            }
+        }
+        if matches!(
+            protocol,
+            Protocol::Http1
+                | Protocol::Http2
+                | Protocol::Smtp
+                | Protocol::Imap
+                | Protocol::Pop3
+                | Protocol::FtpControl
+                | Protocol::Dns
+        ) {
+            // only mirror the cleartext protocols that we can intercept
+            (clt_w, ups_w) = self.ctx.mirror_io(clt_w, ups_w);
+        }
         match protocol {
             Protocol::Unknown => {
                 self.ctx
@@ -168,6 +233,61 @@ where
                 h2_obj.set_io(OnceBufReader::new(clt_r, clt_r_buf), clt_w, ups_r, ups_w);
                 return Ok(StreamInspection::H2(h2_obj));
             }
+            Protocol::Smtp => {
+                let mut smtp_obj =
+                    crate::inspect::smtp::SmtpInterceptObject::new(self.ctx, self.upstream);
+                smtp_obj.set_io(
+                    FlexBufReader::with_bytes(clt_r_buf, clt_r),
+                    clt_w,
+                    FlexBufReader::with_bytes(ups_r_buf, ups_r),
+                    ups_w,
+                );
+                return Ok(StreamInspection::Smtp(smtp_obj));
+            }
+            Protocol::Imap => {
+                let mut imap_obj =
+                    crate::inspect::imap::ImapInterceptObject::new(self.ctx, self.upstream);
+                imap_obj.set_io(
+                    FlexBufReader::with_bytes(clt_r_buf, clt_r),
+                    clt_w,
+                    FlexBufReader::with_bytes(ups_r_buf, ups_r),
+                    ups_w,
+                );
+                return Ok(StreamInspection::Imap(imap_obj));
+            }
+            Protocol::Pop3 => {
+                let mut pop3_obj =
+                    crate::inspect::pop3::Pop3InterceptObject::new(self.ctx, self.upstream);
+                pop3_obj.set_io(
+                    FlexBufReader::with_bytes(clt_r_buf, clt_r),
+                    clt_w,
+                    FlexBufReader::with_bytes(ups_r_buf, ups_r),
+                    ups_w,
+                );
+                return Ok(StreamInspection::Pop3(pop3_obj));
+            }
+            Protocol::FtpControl => {
+                let mut ftp_obj =
+                    crate::inspect::ftp::FtpInterceptObject::new(self.ctx, self.upstream);
+                ftp_obj.set_io(
+                    FlexBufReader::with_bytes(clt_r_buf, clt_r),
+                    clt_w,
+                    FlexBufReader::with_bytes(ups_r_buf, ups_r),
+                    ups_w,
+                );
+                return Ok(StreamInspection::Ftp(ftp_obj));
+            }
+            Protocol::Dns => {
+                let mut dns_obj =
+                    crate::inspect::dns::DnsInterceptObject::new(self.ctx, self.upstream);
+                dns_obj.set_io(
+                    FlexBufReader::with_bytes(clt_r_buf, clt_r),
+                    clt_w,
+                    FlexBufReader::with_bytes(ups_r_buf, ups_r),
+                    ups_w,
+                );
+                return Ok(StreamInspection::Dns(dns_obj));
+            }
             _ => {}
         }
 
@@ -235,6 +355,7 @@ where
     where
         CR: AsyncRead + Unpin,
     {
+        let max_buffer_size = self.ctx.protocol_inspection().data0_max_buffer_size();
         loop {
             match inspector.check_client_initial_data(
                 self.ctx.protocol_inspection(),
@@ -246,6 +367,13 @@ where
                     if clt_r_buf.remaining() == 0 {
                         return Ok(Protocol::Unknown);
                     }
+                    if clt_r_buf.len() >= max_buffer_size {
+                        self.ctx
+                            .audit_handle
+                            .stats()
+                            .add_inspect_oversize();
+                        return Ok(Protocol::Unknown);
+                    }
                     match clt_r.read_buf(clt_r_buf).await {
                         Ok(0) => return Err(ServerTaskError::ClosedByClient),
                         Ok(_) => {}
@@ -265,6 +393,7 @@ where
     where
         UR: AsyncRead + Unpin,
     {
+        let max_buffer_size = self.ctx.protocol_inspection().data0_max_buffer_size();
         loop {
             match inspector.check_server_initial_data(
                 self.ctx.protocol_inspection(),
@@ -276,6 +405,13 @@ where
                     if ups_r_buf.remaining() == 0 {
                         return Ok(Protocol::Unknown);
                     }
+                    if ups_r_buf.len() >= max_buffer_size {
+                        self.ctx
+                            .audit_handle
+                            .stats()
+                            .add_inspect_oversize();
+                        return Ok(Protocol::Unknown);
+                    }
                     match ups_r.read_buf(ups_r_buf).await {
                         Ok(0) => return Err(ServerTaskError::ClosedByUpstream),
                         Ok(_) => {}
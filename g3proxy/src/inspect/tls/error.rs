@@ -30,4 +30,12 @@ pub(crate) enum TlsInterceptionError {
     UpstreamHandshakeFailed(anyhow::Error),
     #[error("no fake cert generated: {0:?}")]
     NoFakeCertGenerated(anyhow::Error),
+    #[error("client fingerprint blocked")]
+    ClientFingerprintBlocked,
+    #[error("client encrypted hello blocked")]
+    ClientEchBlocked,
+    #[error("upstream cert verify failed: {0}")]
+    UpstreamCertVerifyFailed(&'static str),
+    #[error("interception bypassed by upstream cert")]
+    UpstreamCertBypassed,
 }
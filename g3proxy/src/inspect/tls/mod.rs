@@ -14,19 +14,26 @@
  * limitations under the License.
  */
 
+use std::str::FromStr;
 use std::sync::Arc;
 
 use anyhow::anyhow;
+use bytes::BytesMut;
+use openssl::stack::StackRef;
+use openssl::x509::{X509NameRef, X509};
 use slog::slog_info;
+use tokio::io::AsyncReadExt;
 use tokio::runtime::Handle;
 
+use g3_dpi::{Protocol, TlsClientFingerprint, TlsClientHello, TlsClientHelloParseError};
 use g3_io_ext::OnceBufReader;
 use g3_slog_types::{LtUpstreamAddr, LtUuid};
 use g3_tls_cert::agent::CertAgentHandle;
-use g3_types::net::{OpensslInterceptionClientConfig, UpstreamAddr};
+use g3_types::net::{Host, OpensslInterceptionClientConfig, UpstreamAddr};
 use g3_udpdump::{StreamDumpConfig, StreamDumper};
 
 use super::{BoxAsyncRead, BoxAsyncWrite, StreamInspectContext};
+use crate::config::audit::TlsEchPolicy;
 use crate::config::server::ServerConfig;
 
 mod error;
@@ -34,9 +41,37 @@ pub(crate) use error::TlsInterceptionError;
 
 mod modern;
 
+const CLIENT_HELLO_MAX_SIZE: usize = 16384 + 5;
+
+fn format_x509_name(name: &X509NameRef) -> String {
+    name.entries()
+        .filter_map(|entry| {
+            let key = entry.object().nid().short_name().ok()?;
+            let value = entry.data().as_utf8().ok()?;
+            Some(format!("{key}={value}"))
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn format_cert_chain(chain: &StackRef<X509>) -> String {
+    chain
+        .iter()
+        .map(|cert| {
+            format!(
+                "subject: {}, issuer: {}",
+                format_x509_name(cert.subject_name()),
+                format_x509_name(cert.issuer_name())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 #[derive(Clone)]
 pub(crate) struct TlsInterceptionContext {
     cert_agent: Arc<CertAgentHandle>,
+    untrusted_cert_agent: Option<Arc<CertAgentHandle>>,
     client_config: Arc<OpensslInterceptionClientConfig>,
     stream_dumper: Arc<Vec<StreamDumper>>,
 }
@@ -44,34 +79,43 @@ pub(crate) struct TlsInterceptionContext {
 impl TlsInterceptionContext {
     pub(crate) fn new(
         cert_agent: CertAgentHandle,
+        untrusted_cert_agent: Option<CertAgentHandle>,
         client_config: OpensslInterceptionClientConfig,
         dump_config: Option<StreamDumpConfig>,
     ) -> anyhow::Result<Self> {
         let mut stream_dumper = Vec::new();
         if let Some(dump) = dump_config {
-            g3_daemon::runtime::worker::foreach(|h| {
-                let dumper = StreamDumper::new(dump, &h.handle).map_err(|e| {
-                    anyhow!("failed to create tls stream dumper in worker {}: {e}", h.id)
-                })?;
+            if dump.file.is_some() {
+                // all streams should be written to the same pcapng file
+                let dumper = StreamDumper::new(&dump, &Handle::current())
+                    .map_err(|e| anyhow!("failed to create tls stream pcapng dumper: {e}"))?;
                 stream_dumper.push(dumper);
-                Ok::<(), anyhow::Error>(())
-            })?;
-
-            if stream_dumper.is_empty() {
-                let dump_count =
-                    g3_daemon::runtime::config::get_runtime_config().intended_thread_number();
-                let handle = Handle::current();
-                for i in 0..dump_count {
-                    let dumper = StreamDumper::new(dump, &handle).map_err(|e| {
-                        anyhow!("failed to create tls stream dumper #{i} in main runtime: {e}")
+            } else {
+                g3_daemon::runtime::worker::foreach(|h| {
+                    let dumper = StreamDumper::new(&dump, &h.handle).map_err(|e| {
+                        anyhow!("failed to create tls stream dumper in worker {}: {e}", h.id)
                     })?;
                     stream_dumper.push(dumper);
+                    Ok::<(), anyhow::Error>(())
+                })?;
+
+                if stream_dumper.is_empty() {
+                    let dump_count =
+                        g3_daemon::runtime::config::get_runtime_config().intended_thread_number();
+                    let handle = Handle::current();
+                    for i in 0..dump_count {
+                        let dumper = StreamDumper::new(&dump, &handle).map_err(|e| {
+                            anyhow!("failed to create tls stream dumper #{i} in main runtime: {e}")
+                        })?;
+                        stream_dumper.push(dumper);
+                    }
                 }
             }
         }
 
         Ok(TlsInterceptionContext {
             cert_agent: Arc::new(cert_agent),
+            untrusted_cert_agent: untrusted_cert_agent.map(Arc::new),
             client_config: Arc::new(client_config),
             stream_dumper: Arc::new(stream_dumper),
         })
@@ -104,6 +148,13 @@ pub(crate) struct TlsInterceptObject<SC: ServerConfig> {
     ctx: StreamInspectContext<SC>,
     upstream: UpstreamAddr,
     tls_interception: TlsInterceptionContext,
+    inner_protocol: Option<Protocol>,
+    client_fingerprint: Option<TlsClientFingerprint>,
+    client_sni: Option<Host>,
+    client_alpn: Vec<Vec<u8>>,
+    client_ech_action: Option<TlsEchPolicy>,
+    upstream_cert_error: Option<String>,
+    upstream_cert_chain: Option<String>,
 }
 
 macro_rules! intercept_log {
@@ -113,6 +164,11 @@ macro_rules! intercept_log {
             "task_id" => LtUuid($obj.ctx.server_task_id()),
             "depth" => $obj.ctx.inspection_depth,
             "upstream" => LtUpstreamAddr(&$obj.upstream),
+            "tls_ja3" => $obj.client_fingerprint.as_ref().map(|v| v.ja3()),
+            "tls_ja4" => $obj.client_fingerprint.as_ref().map(|v| v.ja4()),
+            "tls_ech" => $obj.client_ech_action.map(|v| v.as_str()),
+            "upstream_cert_error" => $obj.upstream_cert_error.as_deref(),
+            "upstream_cert_chain" => $obj.upstream_cert_chain.as_deref(),
         )
     };
 }
@@ -128,9 +184,21 @@ impl<SC: ServerConfig> TlsInterceptObject<SC> {
             ctx,
             upstream,
             tls_interception: tls,
+            inner_protocol: None,
+            client_fingerprint: None,
+            client_sni: None,
+            client_alpn: Vec::new(),
+            client_ech_action: None,
+            upstream_cert_error: None,
+            upstream_cert_chain: None,
         }
     }
 
+    /// set the protocol expected inside TLS, which will be used if no ALPN is negotiated
+    pub(crate) fn set_inner_protocol(&mut self, protocol: Protocol) {
+        self.inner_protocol = Some(protocol);
+    }
+
     pub(crate) fn set_io(
         &mut self,
         clt_r: OnceBufReader<BoxAsyncRead>,
@@ -147,10 +215,105 @@ impl<SC: ServerConfig> TlsInterceptObject<SC> {
         self.io = Some(io);
     }
 
+    /// Read the full ClientHello message and compute the client fingerprint.
+    ///
+    /// The data read will be put back to the returned reader.
+    async fn read_client_hello(
+        &mut self,
+        mut clt_r: OnceBufReader<BoxAsyncRead>,
+    ) -> Result<OnceBufReader<BoxAsyncRead>, TlsInterceptionError> {
+        let mut buf = BytesMut::with_capacity(CLIENT_HELLO_MAX_SIZE);
+        if let Some(data) = clt_r.take_buf() {
+            buf.extend_from_slice(&data);
+        }
+        let mut clt_r = clt_r.into_inner();
+
+        loop {
+            match TlsClientHello::parse_records(&buf) {
+                Ok(hello) => {
+                    self.client_fingerprint = Some(TlsClientFingerprint::new(&hello));
+                    self.client_sni = hello
+                        .server_name
+                        .as_ref()
+                        .and_then(|name| Host::from_str(name).ok());
+                    if hello.has_ech() {
+                        self.client_ech_action = Some(self.ctx.audit_handle.tls_ech_policy());
+                    }
+                    self.client_alpn = hello.alpn_protocols;
+                    break;
+                }
+                Err(TlsClientHelloParseError::NeedMoreData) => {
+                    if buf.len() >= CLIENT_HELLO_MAX_SIZE {
+                        break;
+                    }
+                    match clt_r.read_buf(&mut buf).await {
+                        Ok(0) => {
+                            return Err(TlsInterceptionError::ClientHandshakeFailed(anyhow!(
+                                "connection closed while reading client hello msg"
+                            )))
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            return Err(TlsInterceptionError::ClientHandshakeFailed(anyhow!(
+                                "read client hello msg failed: {e:?}"
+                            )))
+                        }
+                    }
+                }
+                // let the tls handshake to report the error
+                Err(_) => break,
+            }
+        }
+
+        Ok(OnceBufReader::new(clt_r, buf))
+    }
+
+    /// Check if the interception should be bypassed, and the stream should be tunneled as is
+    fn interception_bypassed(&self) -> bool {
+        let audit_handle = &self.ctx.audit_handle;
+        if self.client_ech_action == Some(TlsEchPolicy::Bypass) {
+            return true;
+        }
+        if !self.client_alpn.is_empty()
+            && !self
+                .client_alpn
+                .iter()
+                .any(|p| audit_handle.tls_alpn_intercepted(p))
+        {
+            return true;
+        }
+        // the bypass rules will be skipped if interception is forced for this user
+        if self.ctx.user_tls_interception() != Some(true) {
+            if let Some(bypass) = audit_handle.tls_interception_bypass() {
+                if bypass.check_host(self.upstream.host()) {
+                    return true;
+                }
+                if let Some(sni) = &self.client_sni {
+                    if bypass.check_host(sni) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        // the upstream host will be set to the SNI hostname before the upstream handshake
+        match &self.client_sni {
+            Some(sni) => {
+                let upstream = UpstreamAddr::new(sni.clone(), self.upstream.port());
+                audit_handle.tls_bypass_cache().contains(&upstream)
+            }
+            None => audit_handle.tls_bypass_cache().contains(&self.upstream),
+        }
+    }
+
     fn log_ok(&self) {
         intercept_log!(self, "ok");
     }
 
+    fn log_bypass(&self) {
+        intercept_log!(self, "bypassed");
+    }
+
     fn log_err(&self, e: &TlsInterceptionError) {
         intercept_log!(self, "{e}");
     }
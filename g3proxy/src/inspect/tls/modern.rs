@@ -18,6 +18,8 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use anyhow::anyhow;
+use openssl::ssl::SslVerifyMode;
+use openssl::x509::X509VerifyResult;
 use tokio::io::{AsyncRead, AsyncWrite};
 
 use g3_dpi::{Protocol, ProtocolInspector};
@@ -27,6 +29,7 @@ use g3_types::net::{AlpnProtocol, Host};
 use g3_udpdump::ExportedPduDissectorHint;
 
 use super::{TlsInterceptIo, TlsInterceptObject, TlsInterceptionError};
+use crate::config::audit::{TlsEchPolicy, TlsUpstreamCertErrorAction};
 use crate::config::server::ServerConfig;
 use crate::inspect::{InterceptionError, StreamInspection};
 use crate::log::inspect::{stream::StreamInspectLog, InspectSource};
@@ -40,18 +43,71 @@ where
         mut self,
         inspector: &mut ProtocolInspector,
     ) -> ServerTaskResult<StreamInspection<SC>> {
+        if let Err(e) = self.check_client_hello().await {
+            self.ctx.audit_handle.stats().add_tls_interception_failed();
+            self.log_err(&e);
+            return Err(InterceptionError::Tls(e).into_server_task_error(Protocol::TlsModern));
+        }
+
+        if self.interception_bypassed() {
+            self.ctx
+                .audit_handle
+                .stats()
+                .add_tls_interception_bypassed();
+            self.log_bypass();
+            let TlsInterceptIo {
+                clt_r,
+                clt_w,
+                ups_r,
+                ups_w,
+            } = self.io.take().unwrap();
+            self.ctx
+                .transit_transparent(clt_r, clt_w, ups_r, ups_w)
+                .await?;
+            return Ok(StreamInspection::End);
+        }
+
         match self.do_intercept_modern(inspector).await {
             Ok(obj) => {
                 self.log_ok();
                 Ok(obj)
             }
             Err(e) => {
+                let stats = self.ctx.audit_handle.stats();
+                if matches!(e, TlsInterceptionError::UpstreamCertBypassed) {
+                    // only later connections will be bypassed
+                    stats.add_tls_interception_bypassed();
+                } else {
+                    stats.add_tls_interception_failed();
+                }
                 self.log_err(&e);
                 Err(InterceptionError::Tls(e).into_server_task_error(Protocol::TlsModern))
             }
         }
     }
 
+    async fn check_client_hello(&mut self) -> Result<(), TlsInterceptionError> {
+        let mut io = self.io.take().unwrap();
+
+        // also use upstream timeout config for client handshake
+        let handshake_timeout = self.tls_interception.client_config.handshake_timeout;
+
+        io.clt_r = tokio::time::timeout(handshake_timeout, self.read_client_hello(io.clt_r))
+            .await
+            .map_err(|_| TlsInterceptionError::ClientHandshakeTimeout)??;
+        self.io = Some(io);
+
+        if let Some(fingerprint) = &self.client_fingerprint {
+            if self.ctx.audit_handle.tls_fingerprint_blocked(fingerprint) {
+                return Err(TlsInterceptionError::ClientFingerprintBlocked);
+            }
+        }
+        if self.client_ech_action == Some(TlsEchPolicy::Block) {
+            return Err(TlsInterceptionError::ClientEchBlocked);
+        }
+        Ok(())
+    }
+
     async fn do_intercept_modern(
         &mut self,
         inspector: &mut ProtocolInspector,
@@ -63,14 +119,14 @@ where
             ups_w,
         } = self.io.take().unwrap();
 
+        // also use upstream timeout config for client handshake
+        let handshake_timeout = self.tls_interception.client_config.handshake_timeout;
+
         let acceptor = rustls::server::Acceptor::default();
         let clt_io = AggregatedIo::new(clt_r, clt_w);
 
         let lazy_acceptor = tokio_rustls::LazyConfigAcceptor::new(acceptor, clt_io);
 
-        // also use upstream timeout config for client handshake
-        let handshake_timeout = self.tls_interception.client_config.handshake_timeout;
-
         let client_handshake = tokio::time::timeout(handshake_timeout, lazy_acceptor)
             .await
             .map_err(|_| TlsInterceptionError::ClientHandshakeTimeout)?
@@ -88,23 +144,33 @@ where
                 self.upstream.set_host(host);
             }
         }
-        let ups_ssl = self
+        let audit_handle = self.ctx.audit_handle.clone();
+        let mut ups_ssl = self
             .tls_interception
             .client_config
-            .build_ssl(sni_hostname, &self.upstream, client_hello.alpn())
+            .build_ssl(
+                sni_hostname,
+                &self.upstream,
+                client_hello
+                    .alpn()
+                    .map(|iter| iter.filter(|p| audit_handle.tls_alpn_intercepted(p))),
+            )
             .map_err(|e| {
                 TlsInterceptionError::UpstreamPrepareFailed(anyhow!(
                     "failed to build ssl context: {e}"
                 ))
             })?;
+        // the verify result will be checked after handshake, according to the cert error policy
+        ups_ssl.set_verify(SslVerifyMode::NONE);
 
         // fetch fake server cert early in the background
         let tls_interception = self.tls_interception.clone();
         let cert_domain = sni_hostname
             .map(|v| v.to_string())
             .unwrap_or_else(|| self.upstream.host().to_string());
-        let clt_cert_handle =
-            tokio::spawn(async move { tls_interception.cert_agent.fetch(cert_domain).await });
+        let fetch_domain = cert_domain.clone();
+        let mut clt_cert_handle =
+            tokio::spawn(async move { tls_interception.cert_agent.fetch(fetch_domain).await });
 
         // handshake with upstream server
         let ups_tls_connector = SslConnector::new(ups_ssl, AggregatedIo::new(ups_r, ups_w))
@@ -123,10 +189,53 @@ where
             })?;
 
         let ups_ssl = ups_tls_stream.ssl();
+        audit_handle
+            .stats()
+            .add_tls_upstream_handshake(ups_ssl.session_reused());
+        // the client hello has been consumed, so only later connections can be bypassed
+        if let Some(bypass) = audit_handle.tls_interception_bypass() {
+            if let Some(cert) = ups_ssl.peer_certificate() {
+                if bypass.check_upstream_cert(&cert) {
+                    audit_handle
+                        .tls_bypass_cache()
+                        .add(&self.upstream, bypass.cert_bypass_cache_ttl());
+                    return Err(TlsInterceptionError::UpstreamCertBypassed);
+                }
+            }
+        }
+        let verify_result = ups_ssl.verify_result();
+        if verify_result != X509VerifyResult::OK {
+            let error = verify_result.error_string();
+            self.upstream_cert_error = Some(error.to_string());
+            self.upstream_cert_chain = ups_ssl.peer_cert_chain().map(super::format_cert_chain);
+
+            let policy = audit_handle.tls_upstream_cert_error_policy();
+            match policy.action {
+                TlsUpstreamCertErrorAction::Block => {
+                    return Err(TlsInterceptionError::UpstreamCertVerifyFailed(error));
+                }
+                TlsUpstreamCertErrorAction::BlockAndBypassLater => {
+                    // the client hello has been consumed, so only later connections can be bypassed
+                    audit_handle
+                        .tls_bypass_cache()
+                        .add(&self.upstream, policy.bypass_cache_ttl);
+                    return Err(TlsInterceptionError::UpstreamCertBypassed);
+                }
+                TlsUpstreamCertErrorAction::ForgeUntrusted => {
+                    let Some(cert_agent) = self.tls_interception.untrusted_cert_agent.clone()
+                    else {
+                        return Err(TlsInterceptionError::UpstreamCertVerifyFailed(error));
+                    };
+                    clt_cert_handle.abort();
+                    clt_cert_handle =
+                        tokio::spawn(async move { cert_agent.fetch(cert_domain).await });
+                }
+            }
+        }
         let selected_alpn_protocol = ups_ssl.selected_alpn_protocol();
 
         // fetch fake server cert
-        let (clt_cert, clt_key) = clt_cert_handle
+        let fake_cert = clt_cert_handle
             .await
             .map_err(|e| {
                 TlsInterceptionError::NoFakeCertGenerated(anyhow!(
@@ -143,7 +252,12 @@ where
         let mut clt_server_config = rustls::ServerConfig::builder()
             .with_safe_defaults()
             .with_no_client_auth()
-            .with_single_cert(clt_cert, clt_key)
+            .with_single_cert_with_ocsp_and_sct(
+                fake_cert.certs,
+                fake_cert.key,
+                fake_cert.ocsp,
+                Vec::new(),
+            )
             .map_err(|e| {
                 TlsInterceptionError::ClientHandshakeFailed(anyhow!(
                     "failed to build client tls config: {e:?}"
@@ -161,6 +275,11 @@ where
         } else {
             false
         };
+        if !has_alpn {
+            if let Some(p) = self.inner_protocol {
+                protocol = p;
+            }
+        }
         let clt_tls_stream = tokio::time::timeout(
             handshake_timeout,
             client_handshake.into_stream(Arc::new(clt_server_config)),
@@ -183,10 +302,15 @@ where
             } else {
                 ExportedPduDissectorHint::TlsPort(self.upstream.port())
             };
+            let comment = match &self.client_sni {
+                Some(sni) => format!("task_id: {}, sni: {sni}", self.ctx.server_task_id()),
+                None => format!("task_id: {}", self.ctx.server_task_id()),
+            };
             let (clt_w, ups_w) = stream_dumper.wrap_io(
                 self.ctx.task_notes.client_addr,
                 self.ctx.task_notes.server_addr,
                 dissector_hint,
+                Some(comment),
                 clt_w,
                 ups_w,
             );
@@ -214,15 +338,18 @@ where
     {
         let mut ctx = self.ctx.clone();
         ctx.increase_inspection_depth();
+        // the decrypted stream is a new stream layer
+        ctx.traffic_mirrored = false;
+        let (clt_w, ups_w) = ctx.mirror_io(Box::new(clt_w), Box::new(ups_w));
         StreamInspectLog::new(&ctx).log(InspectSource::TlsAlpn, protocol);
         match protocol {
             Protocol::Http1 => {
                 let mut h1_obj = crate::inspect::http::H1InterceptObject::new(ctx);
                 h1_obj.set_io(
                     FlexBufReader::new(Box::new(clt_r)),
-                    Box::new(clt_w),
+                    clt_w,
                     Box::new(ups_r),
-                    Box::new(ups_w),
+                    ups_w,
                 );
                 StreamInspection::H1(h1_obj)
             }
@@ -230,21 +357,75 @@ where
                 let mut h2_obj = crate::inspect::http::H2InterceptObject::new(ctx);
                 h2_obj.set_io(
                     OnceBufReader::with_no_buf(Box::new(clt_r)),
-                    Box::new(clt_w),
+                    clt_w,
                     Box::new(ups_r),
-                    Box::new(ups_w),
+                    ups_w,
                 );
                 StreamInspection::H2(h2_obj)
             }
+            Protocol::Smtp => {
+                let mut smtp_obj =
+                    crate::inspect::smtp::SmtpInterceptObject::new(ctx, self.upstream.clone());
+                smtp_obj.set_from_starttls();
+                smtp_obj.set_io(
+                    FlexBufReader::new(Box::new(clt_r)),
+                    clt_w,
+                    FlexBufReader::new(Box::new(ups_r)),
+                    ups_w,
+                );
+                StreamInspection::Smtp(smtp_obj)
+            }
+            Protocol::Imap => {
+                let mut imap_obj =
+                    crate::inspect::imap::ImapInterceptObject::new(ctx, self.upstream.clone());
+                imap_obj.set_from_starttls();
+                imap_obj.set_io(
+                    FlexBufReader::new(Box::new(clt_r)),
+                    clt_w,
+                    FlexBufReader::new(Box::new(ups_r)),
+                    ups_w,
+                );
+                StreamInspection::Imap(imap_obj)
+            }
+            Protocol::Pop3 => {
+                let mut pop3_obj =
+                    crate::inspect::pop3::Pop3InterceptObject::new(ctx, self.upstream.clone());
+                pop3_obj.set_from_starttls();
+                pop3_obj.set_io(
+                    FlexBufReader::new(Box::new(clt_r)),
+                    clt_w,
+                    FlexBufReader::new(Box::new(ups_r)),
+                    ups_w,
+                );
+                StreamInspection::Pop3(pop3_obj)
+            }
+            Protocol::FtpControl => {
+                let mut ftp_obj =
+                    crate::inspect::ftp::FtpInterceptObject::new(ctx, self.upstream.clone());
+                ftp_obj.set_from_starttls();
+                ftp_obj.set_io(
+                    FlexBufReader::new(Box::new(clt_r)),
+                    clt_w,
+                    FlexBufReader::new(Box::new(ups_r)),
+                    ups_w,
+                );
+                StreamInspection::Ftp(ftp_obj)
+            }
+            Protocol::Dns => {
+                let mut dns_obj =
+                    crate::inspect::dns::DnsInterceptObject::new(ctx, self.upstream.clone());
+                dns_obj.set_io(
+                    FlexBufReader::new(Box::new(clt_r)),
+                    clt_w,
+                    FlexBufReader::new(Box::new(ups_r)),
+                    ups_w,
+                );
+                StreamInspection::Dns(dns_obj)
+            }
             _ => {
                 let mut stream_obj =
                     crate::inspect::stream::StreamInspectObject::new(ctx, self.upstream.clone());
-                stream_obj.set_io(
-                    Box::new(clt_r),
-                    Box::new(clt_w),
-                    Box::new(ups_r),
-                    Box::new(ups_w),
-                );
+                stream_obj.set_io(Box::new(clt_r), clt_w, Box::new(ups_r), ups_w);
                 if has_alpn {
                     // Just treat it as unknown. Unknown protocol should be forbidden if needed.
                     StreamInspection::StreamUnknown(stream_obj)
@@ -0,0 +1,478 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use slog::{slog_info, Logger};
+use tokio::io::{AsyncRead, ReadBuf};
+use uuid::Uuid;
+
+use g3_dpi::WebSocketInterceptionConfig;
+use g3_slog_types::{LtUpstreamAddr, LtUuid};
+use g3_types::net::UpstreamAddr;
+
+use crate::config::server::ServerConfig;
+use crate::inspect::StreamInspectContext;
+
+const FRAME_HEADER_MAX_SIZE: usize = 14;
+
+#[derive(Clone, Copy)]
+pub(super) enum WebSocketDirection {
+    ClientToServer,
+    ServerToClient,
+}
+
+impl WebSocketDirection {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WebSocketDirection::ClientToServer => "c2s",
+            WebSocketDirection::ServerToClient => "s2c",
+        }
+    }
+}
+
+fn opcode_name(opcode: u8) -> &'static str {
+    match opcode {
+        0x1 => "text",
+        0x2 => "binary",
+        0x8 => "close",
+        0x9 => "ping",
+        0xA => "pong",
+        _ => "reserved",
+    }
+}
+
+fn frame_header_size(hdr: &[u8]) -> usize {
+    if hdr.len() < 2 {
+        return 2;
+    }
+    let mut size = 2;
+    match hdr[1] & 0x7F {
+        126 => size += 2,
+        127 => size += 8,
+        _ => {}
+    }
+    if hdr[1] & 0x80 != 0 {
+        // masking key
+        size += 4;
+    }
+    size
+}
+
+fn frame_payload_len(hdr: &[u8]) -> u64 {
+    match hdr[1] & 0x7F {
+        126 => u16::from_be_bytes([hdr[2], hdr[3]]) as u64,
+        127 => {
+            let mut len = [0u8; 8];
+            len.copy_from_slice(&hdr[2..10]);
+            u64::from_be_bytes(len)
+        }
+        n => n as u64,
+    }
+}
+
+/// the max payload size of control frames
+const CONTROL_FRAME_MAX_PAYLOAD_SIZE: u64 = 125;
+
+struct WebSocketMessage {
+    opcode: u8,
+    size: u64,
+    frames: usize,
+}
+
+enum FrameParseState {
+    Header {
+        buf: [u8; FRAME_HEADER_MAX_SIZE],
+        len: usize,
+    },
+    Payload {
+        left: u64,
+    },
+}
+
+impl Default for FrameParseState {
+    fn default() -> Self {
+        FrameParseState::Header {
+            buf: [0u8; FRAME_HEADER_MAX_SIZE],
+            len: 0,
+        }
+    }
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[derive(Default)]
+struct WebSocketFrameParser {
+    max_message_size: Option<u64>,
+    state: FrameParseState,
+    message: Option<WebSocketMessage>,
+}
+
+impl WebSocketFrameParser {
+    fn new(max_message_size: Option<u64>) -> Self {
+        WebSocketFrameParser {
+            max_message_size,
+            ..Default::default()
+        }
+    }
+
+    /// parse the frames in `data`, which may end in the middle of a frame,
+    /// `on_message` will be called with (opcode, size, frames) for each completed message
+    fn feed<F>(&mut self, mut data: &[u8], mut on_message: F) -> io::Result<()>
+    where
+        F: FnMut(u8, u64, usize),
+    {
+        while !data.is_empty() {
+            match &mut self.state {
+                FrameParseState::Payload { left } => {
+                    let n = (*left).min(data.len() as u64);
+                    *left -= n;
+                    data = &data[n as usize..];
+                    if *left == 0 {
+                        self.state = FrameParseState::default();
+                    }
+                }
+                FrameParseState::Header { buf, len } => {
+                    let need = frame_header_size(&buf[..*len]);
+                    let n = (need - *len).min(data.len());
+                    buf[*len..*len + n].copy_from_slice(&data[..n]);
+                    *len += n;
+                    data = &data[n..];
+                    if *len < need || frame_header_size(&buf[..*len]) > *len {
+                        continue;
+                    }
+
+                    let fin = buf[0] & 0x80 != 0;
+                    let opcode = buf[0] & 0x0F;
+                    let payload_len = frame_payload_len(&buf[..*len]);
+                    self.state = if payload_len > 0 {
+                        FrameParseState::Payload { left: payload_len }
+                    } else {
+                        FrameParseState::default()
+                    };
+                    self.handle_frame(fin, opcode, payload_len, &mut on_message)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_frame<F>(
+        &mut self,
+        fin: bool,
+        opcode: u8,
+        payload_len: u64,
+        on_message: &mut F,
+    ) -> io::Result<()>
+    where
+        F: FnMut(u8, u64, usize),
+    {
+        if opcode & 0x08 != 0 {
+            // control frames can not be fragmented, and may be injected in fragmented messages
+            if !fin {
+                return Err(invalid_data("fragmented websocket control frame"));
+            }
+            if payload_len > CONTROL_FRAME_MAX_PAYLOAD_SIZE {
+                return Err(invalid_data("too large websocket control frame"));
+            }
+            on_message(opcode, payload_len, 1);
+            return Ok(());
+        }
+
+        let message = if opcode == 0 {
+            let Some(message) = &mut self.message else {
+                return Err(invalid_data("unexpected websocket continuation frame"));
+            };
+            message.size = message.size.saturating_add(payload_len);
+            message.frames += 1;
+            message
+        } else {
+            self.message.insert(WebSocketMessage {
+                opcode,
+                size: payload_len,
+                frames: 1,
+            })
+        };
+
+        if let Some(max_size) = self.max_message_size {
+            if message.size > max_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "websocket message size {} exceeds the limit {max_size}",
+                        message.size
+                    ),
+                ));
+            }
+        }
+
+        if fin {
+            if let Some(message) = self.message.take() {
+                on_message(message.opcode, message.size, message.frames);
+            }
+        }
+        Ok(())
+    }
+}
+
+struct WebSocketMessageLogger {
+    logger: Option<Logger>,
+    intercept_type: &'static str,
+    task_id: Uuid,
+    depth: usize,
+    upstream: UpstreamAddr,
+    direction: WebSocketDirection,
+}
+
+impl WebSocketMessageLogger {
+    fn log_message(&self, opcode: u8, size: u64, frames: usize) {
+        if let Some(logger) = &self.logger {
+            slog_info!(logger, "websocket message";
+                "intercept_type" => self.intercept_type,
+                "task_id" => LtUuid(&self.task_id),
+                "depth" => self.depth,
+                "upstream" => LtUpstreamAddr(&self.upstream),
+                "direction" => self.direction.as_str(),
+                "opcode" => opcode_name(opcode),
+                "size" => size,
+                "frames" => frames,
+            );
+        }
+    }
+}
+
+pub(super) struct WebSocketFrameInspector {
+    logger: WebSocketMessageLogger,
+    parser: WebSocketFrameParser,
+}
+
+impl WebSocketFrameInspector {
+    pub(super) fn new<SC: ServerConfig>(
+        ctx: &StreamInspectContext<SC>,
+        config: &WebSocketInterceptionConfig,
+        intercept_type: &'static str,
+        upstream: &UpstreamAddr,
+        direction: WebSocketDirection,
+    ) -> Self {
+        WebSocketFrameInspector {
+            logger: WebSocketMessageLogger {
+                logger: config.log_messages.then(|| ctx.intercept_logger().clone()),
+                intercept_type,
+                task_id: *ctx.server_task_id(),
+                depth: ctx.inspection_depth,
+                upstream: upstream.clone(),
+                direction,
+            },
+            parser: WebSocketFrameParser::new(config.max_message_size.map(|v| v as u64)),
+        }
+    }
+
+    fn feed(&mut self, data: &[u8]) -> io::Result<()> {
+        let logger = &self.logger;
+        self.parser.feed(data, |opcode, size, frames| {
+            logger.log_message(opcode, size, frames)
+        })
+    }
+}
+
+pub(super) struct WebSocketFrameInspectReader<R> {
+    inner: R,
+    inspector: WebSocketFrameInspector,
+}
+
+impl<R> WebSocketFrameInspectReader<R> {
+    pub(super) fn new(inner: R, inspector: WebSocketFrameInspector) -> Self {
+        WebSocketFrameInspectReader { inner, inspector }
+    }
+}
+
+impl<R> AsyncRead for WebSocketFrameInspectReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let offset = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        let data = &buf.filled()[offset..];
+        Poll::Ready(self.inspector.feed(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// build a frame header, the masking key will be added if `masked`
+    fn header(fin: bool, opcode: u8, len: u64, masked: bool) -> Vec<u8> {
+        let mut hdr = vec![(u8::from(fin) << 7) | opcode];
+        let mask_bit = if masked { 0x80 } else { 0x00 };
+        if len < 126 {
+            hdr.push(mask_bit | len as u8);
+        } else if len <= u16::MAX as u64 {
+            hdr.push(mask_bit | 126);
+            hdr.extend_from_slice(&(len as u16).to_be_bytes());
+        } else {
+            hdr.push(mask_bit | 127);
+            hdr.extend_from_slice(&len.to_be_bytes());
+        }
+        if masked {
+            hdr.extend_from_slice(&[0x37, 0xfa, 0x21, 0x3d]);
+        }
+        hdr
+    }
+
+    fn frame(fin: bool, opcode: u8, len: usize, masked: bool) -> Vec<u8> {
+        let mut data = header(fin, opcode, len as u64, masked);
+        data.resize(data.len() + len, 0x61);
+        data
+    }
+
+    fn feed_all(
+        parser: &mut WebSocketFrameParser,
+        data: &[u8],
+    ) -> io::Result<Vec<(u8, u64, usize)>> {
+        let mut messages = Vec::new();
+        parser.feed(data, |opcode, size, frames| {
+            messages.push((opcode, size, frames))
+        })?;
+        Ok(messages)
+    }
+
+    #[test]
+    fn header_size() {
+        assert_eq!(frame_header_size(&[]), 2);
+        assert_eq!(frame_header_size(&[0x81]), 2);
+
+        assert_eq!(frame_header_size(&header(true, 1, 125, false)), 2);
+        assert_eq!(frame_header_size(&header(true, 1, 125, true)), 6);
+        assert_eq!(frame_header_size(&header(true, 1, 126, false)), 4);
+        assert_eq!(frame_header_size(&header(true, 1, 65535, true)), 8);
+        assert_eq!(frame_header_size(&header(true, 1, 65536, false)), 10);
+        assert_eq!(frame_header_size(&header(true, 1, 65536, true)), 14);
+
+        // only the first 2 bytes are needed
+        assert_eq!(frame_header_size(&[0x82, 0xFF]), 14);
+    }
+
+    #[test]
+    fn payload_len() {
+        for len in [0, 1, 125, 126, 127, 65535, 65536, u32::MAX as u64 + 1] {
+            for masked in [false, true] {
+                let hdr = header(true, 2, len, masked);
+                assert_eq!(frame_payload_len(&hdr), len);
+            }
+        }
+    }
+
+    #[test]
+    fn feed_lengths() {
+        let mut parser = WebSocketFrameParser::new(None);
+        for (len, masked) in [
+            (0, false),
+            (5, true),
+            (125, false),
+            (126, true),
+            (300, false),
+            (65535, true),
+            (65536, false),
+            (70000, true),
+        ] {
+            let data = frame(true, 2, len, masked);
+            let messages = feed_all(&mut parser, &data).unwrap();
+            assert_eq!(messages, vec![(2, len as u64, 1)]);
+        }
+    }
+
+    #[test]
+    fn feed_split() {
+        let mut data = frame(true, 1, 300, true);
+        data.extend(frame(true, 2, 70000, false));
+        data.extend(frame(true, 9, 4, true));
+
+        for chunk_size in [1, 2, 3, 7, 13, 1000] {
+            let mut parser = WebSocketFrameParser::new(None);
+            let mut messages = Vec::new();
+            for chunk in data.chunks(chunk_size) {
+                messages.extend(feed_all(&mut parser, chunk).unwrap());
+            }
+            assert_eq!(messages, vec![(1, 300, 1), (2, 70000, 1), (9, 4, 1)]);
+        }
+    }
+
+    #[test]
+    fn feed_fragmented() {
+        let mut parser = WebSocketFrameParser::new(None);
+        let mut data = frame(false, 1, 100, true);
+        // control frames may be injected in the middle
+        data.extend(frame(true, 9, 10, true));
+        data.extend(frame(false, 0, 200, true));
+        data.extend(frame(true, 0, 300, true));
+        let messages = feed_all(&mut parser, &data).unwrap();
+        assert_eq!(messages, vec![(9, 10, 1), (1, 600, 3)]);
+
+        let mut parser = WebSocketFrameParser::new(None);
+        let data = frame(true, 0, 10, false);
+        assert!(feed_all(&mut parser, &data).is_err());
+    }
+
+    #[test]
+    fn feed_control() {
+        let mut parser = WebSocketFrameParser::new(None);
+        let data = frame(true, 8, 125, true);
+        assert_eq!(feed_all(&mut parser, &data).unwrap(), vec![(8, 125, 1)]);
+
+        let mut parser = WebSocketFrameParser::new(None);
+        let data = frame(true, 9, 126, true);
+        assert!(feed_all(&mut parser, &data).is_err());
+
+        let mut parser = WebSocketFrameParser::new(None);
+        let data = frame(true, 0xA, 65536, false);
+        assert!(feed_all(&mut parser, &data).is_err());
+
+        let mut parser = WebSocketFrameParser::new(None);
+        let data = frame(false, 9, 10, true);
+        assert!(feed_all(&mut parser, &data).is_err());
+
+        let mut parser = WebSocketFrameParser::new(None);
+        let mut data = frame(false, 1, 10, true);
+        data.extend(frame(false, 8, 2, true));
+        assert!(feed_all(&mut parser, &data).is_err());
+    }
+
+    #[test]
+    fn feed_max_message_size() {
+        let mut parser = WebSocketFrameParser::new(Some(1000));
+        let data = frame(true, 2, 1000, true);
+        assert_eq!(feed_all(&mut parser, &data).unwrap(), vec![(2, 1000, 1)]);
+
+        let mut data = frame(false, 2, 600, true);
+        data.extend(frame(true, 0, 600, true));
+        assert!(feed_all(&mut parser, &data).is_err());
+
+        // the header is enough to detect the oversize
+        let mut parser = WebSocketFrameParser::new(Some(1000));
+        let data = header(true, 2, 1001, true);
+        assert!(feed_all(&mut parser, &data).is_err());
+    }
+}
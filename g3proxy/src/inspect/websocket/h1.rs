@@ -19,6 +19,7 @@ use slog::slog_info;
 use g3_slog_types::{LtUpstreamAddr, LtUuid};
 use g3_types::net::UpstreamAddr;
 
+use super::frame::{WebSocketDirection, WebSocketFrameInspectReader, WebSocketFrameInspector};
 use crate::config::server::ServerConfig;
 use crate::inspect::{BoxAsyncRead, BoxAsyncWrite, StreamInspectContext};
 use crate::serve::ServerTaskResult;
@@ -90,6 +91,34 @@ impl<SC: ServerConfig> H1WebsocketInterceptObject<SC> {
             ups_w,
         } = self.io.take().unwrap();
 
+        let config = self.ctx.websocket_interception();
+        if config.inspect_frames() {
+            let clt_inspector = WebSocketFrameInspector::new(
+                &self.ctx,
+                config,
+                "H1Websocket",
+                &self.upstream,
+                WebSocketDirection::ClientToServer,
+            );
+            let ups_inspector = WebSocketFrameInspector::new(
+                &self.ctx,
+                config,
+                "H1Websocket",
+                &self.upstream,
+                WebSocketDirection::ServerToClient,
+            );
+            return crate::inspect::stream::transit_transparent(
+                WebSocketFrameInspectReader::new(clt_r, clt_inspector),
+                clt_w,
+                WebSocketFrameInspectReader::new(ups_r, ups_inspector),
+                ups_w,
+                &self.ctx.server_config,
+                &self.ctx.server_quit_policy,
+                self.ctx.user(),
+            )
+            .await;
+        }
+
         crate::inspect::stream::transit_transparent(
             clt_r,
             clt_w,
@@ -22,6 +22,7 @@ use g3_h2::{H2StreamReader, H2StreamWriter};
 use g3_slog_types::{LtUpstreamAddr, LtUuid};
 use g3_types::net::UpstreamAddr;
 
+use super::frame::{WebSocketDirection, WebSocketFrameInspectReader, WebSocketFrameInspector};
 use crate::config::server::ServerConfig;
 use crate::inspect::StreamInspectContext;
 use crate::serve::ServerTaskResult;
@@ -75,6 +76,34 @@ impl<SC: ServerConfig> H2WebsocketInterceptObject<SC> {
         let ups_r = H2StreamReader::new(ups_r);
         let ups_w = H2StreamWriter::new(ups_w);
 
+        let config = self.ctx.websocket_interception();
+        if config.inspect_frames() {
+            let clt_inspector = WebSocketFrameInspector::new(
+                &self.ctx,
+                config,
+                "H2Websocket",
+                &self.upstream,
+                WebSocketDirection::ClientToServer,
+            );
+            let ups_inspector = WebSocketFrameInspector::new(
+                &self.ctx,
+                config,
+                "H2Websocket",
+                &self.upstream,
+                WebSocketDirection::ServerToClient,
+            );
+            return crate::inspect::stream::transit_transparent(
+                WebSocketFrameInspectReader::new(clt_r, clt_inspector),
+                clt_w,
+                WebSocketFrameInspectReader::new(ups_r, ups_inspector),
+                ups_w,
+                &self.ctx.server_config,
+                &self.ctx.server_quit_policy,
+                self.ctx.user(),
+            )
+            .await;
+        }
+
         crate::inspect::stream::transit_transparent(
             clt_r,
             clt_w,
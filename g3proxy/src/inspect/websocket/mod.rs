@@ -14,6 +14,8 @@
  * limitations under the License.
  */
 
+mod frame;
+
 mod h1;
 pub(crate) use h1::H1WebsocketInterceptObject;
 
@@ -21,9 +21,12 @@ pub mod control;
 pub mod escape;
 pub mod opts;
 pub mod resolve;
+#[cfg(target_os = "linux")]
+pub mod sandbox;
 pub mod serve;
 pub mod signal;
 pub mod stat;
+pub mod trace;
 
 mod build;
 mod inspect;
@@ -25,6 +25,8 @@ pub(crate) enum InspectSource {
     TlsAlpn,
     H2ExtendedConnect,
     HttpUpgrade,
+    StartTls,
+    FtpPassive,
 }
 
 impl InspectSource {
@@ -34,6 +36,8 @@ impl InspectSource {
             InspectSource::TlsAlpn => "tls alpn",
             InspectSource::H2ExtendedConnect => "h2 extended connect",
             InspectSource::HttpUpgrade => "http upgrade",
+            InspectSource::StartTls => "starttls",
+            InspectSource::FtpPassive => "ftp passive",
         }
     }
 }
@@ -20,6 +20,7 @@ use g3_dpi::Protocol;
 use g3_slog_types::LtUuid;
 
 use super::InspectSource;
+use crate::config::audit::ProtocolPolicyAction;
 use crate::config::server::ServerConfig;
 use crate::inspect::StreamInspectContext;
 
@@ -40,4 +41,19 @@ impl<'a, SC: ServerConfig> StreamInspectLog<'a, SC> {
             "protocol" => protocol.as_str(),
         )
     }
+
+    pub(crate) fn log_with_action(
+        &self,
+        source: InspectSource,
+        protocol: Protocol,
+        action: ProtocolPolicyAction,
+    ) {
+        slog_info!(self.ctx.inspect_logger(), "";
+            "task_id" => LtUuid(self.ctx.server_task_id()),
+            "depth" => self.ctx.current_inspection_depth(),
+            "source" => source.as_str(),
+            "protocol" => protocol.as_str(),
+            "policy_action" => action.as_str(),
+        )
+    }
 }
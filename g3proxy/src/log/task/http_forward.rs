@@ -58,6 +58,7 @@ impl TaskLogForHttpForward<'_> {
             "escaper" => self.tcp_notes.escaper.as_str(),
             "next_bind_ip" => self.tcp_notes.bind.map(LtIpAddr),
             "next_bound_addr" => self.tcp_notes.local,
+            "next_mss" => self.tcp_notes.mss,
             "next_peer_addr" => self.tcp_notes.next,
             "next_expire" => self.tcp_notes.expire.as_ref().map(LtDateTime),
             "tcp_connect_tries" => self.tcp_notes.tries,
@@ -50,9 +50,12 @@ impl TaskLogForTcpConnect<'_> {
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
             "upstream" => LtUpstreamAddr(&self.tcp_notes.upstream),
+            "tls_ja3" => self.task_notes.client_tls_fingerprint.as_ref().map(|v| v.ja3()),
+            "tls_ja4" => self.task_notes.client_tls_fingerprint.as_ref().map(|v| v.ja4()),
             "escaper" => self.tcp_notes.escaper.as_str(),
             "next_bind_ip" => self.tcp_notes.bind.map(LtIpAddr),
             "next_bound_addr" => self.tcp_notes.local,
+            "next_mss" => self.tcp_notes.mss,
             "next_peer_addr" => self.tcp_notes.next,
             "next_expire" => self.tcp_notes.expire.as_ref().map(LtDateTime),
             "tcp_connect_tries" => self.tcp_notes.tries,
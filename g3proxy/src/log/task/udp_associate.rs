@@ -40,6 +40,8 @@ pub(crate) struct TaskLogForUdpAssociate<'a> {
     pub(crate) remote_rd_packets: u64,
     pub(crate) remote_wr_bytes: u64,
     pub(crate) remote_wr_packets: u64,
+    /// (dropped packets, max queued packets)
+    pub(crate) client_wr_pacing: Option<(u64, usize)>,
 }
 
 impl TaskLogForUdpAssociate<'_> {
@@ -74,6 +76,8 @@ impl TaskLogForUdpAssociate<'_> {
             "r_rd_packets" => self.remote_rd_packets,
             "r_wr_bytes" => self.remote_wr_bytes,
             "r_wr_packets" => self.remote_wr_packets,
+            "c_wr_pacing_drop" => self.client_wr_pacing.map(|v| v.0),
+            "c_wr_pacing_queue" => self.client_wr_pacing.map(|v| v.1),
         )
     }
 }
@@ -19,7 +19,7 @@ extern "C" {
     // ...
 }
 
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use log::{debug, error, info};
 
 use g3proxy::opts::ProcArgs;
@@ -39,6 +39,19 @@ fn main() -> anyhow::Result<()> {
     let _log_guard = g3_daemon::log::process::setup(&proc_args.daemon_config)
         .context("failed to setup logger")?;
 
+    if proc_args.check_config {
+        // all the problems should be reported, so do not stop at the first failed entry
+        let problems = g3proxy::config::check_all().context("failed to check config")?;
+        if problems.is_empty() {
+            println!("config is ok");
+            return Ok(());
+        }
+        for s in &problems {
+            println!("{s}");
+        }
+        return Err(anyhow!("{} problems found in config", problems.len()));
+    }
+
     let config_file = g3proxy::config::load()
         .context(format!("failed to load config, opts: {:?}", &proc_args))?;
     debug!("loaded config from {}", config_file.display());
@@ -66,6 +79,13 @@ fn main() -> anyhow::Result<()> {
     // enter daemon mode after config loaded
     g3_daemon::daemonize::check_enter(&proc_args.daemon_config)?;
 
+    // the landlock rules only apply to threads spawned after this
+    #[cfg(target_os = "linux")]
+    g3proxy::sandbox::restrict_paths().context("failed to apply sandbox")?;
+
+    #[cfg(feature = "tokio-console")]
+    g3_daemon::runtime::init_console();
+
     let stat_join = if let Some(stat_config) = g3_daemon::stat::config::get_global_stat_config() {
         Some(
             g3proxy::stat::spawn_working_threads(stat_config)
@@ -74,9 +94,16 @@ fn main() -> anyhow::Result<()> {
     } else {
         None
     };
+    let trace_join =
+        g3proxy::trace::spawn_export_thread().context("failed to start trace export thread")?;
 
     let ret = tokio_run(&proc_args);
 
+    if let Some(handle) = trace_join {
+        g3proxy::trace::stop_export_thread();
+        let _ = handle.join();
+    }
+
     if let Some(handlers) = stat_join {
         g3proxy::stat::stop_working_threads();
         for handle in handlers {
@@ -93,6 +120,16 @@ fn main() -> anyhow::Result<()> {
     }
 }
 
+fn spawn_daemon_controller() -> anyhow::Result<()> {
+    let daemon_ctl =
+        g3proxy::control::DaemonController::start().context("failed to start daemon controller")?;
+    tokio::spawn(async move {
+        daemon_ctl.await;
+    });
+    g3proxy::control::upgrade::spawn_listener().context("failed to start upgrade listener")?;
+    Ok(())
+}
+
 fn tokio_run(args: &ProcArgs) -> anyhow::Result<()> {
     let rt = g3_daemon::runtime::config::get_runtime_config()
         .start()
@@ -107,13 +144,21 @@ fn tokio_run(args: &ProcArgs) -> anyhow::Result<()> {
         let unique_ctl = g3proxy::control::UniqueController::start()
             .context("failed to start unique controller")?;
 
-        if args.daemon_config.need_daemon_controller() {
-            let daemon_ctl = g3proxy::control::DaemonController::start()
-                .context("failed to start daemon controller")?;
-            tokio::spawn(async move {
-                daemon_ctl.await;
-            });
-        }
+        let upgrade_session = if args.upgrade {
+            let session = g3proxy::control::upgrade::take_over()
+                .await
+                .context("failed to take over from the running daemon")?;
+            Some(session)
+        } else {
+            if args.daemon_config.need_daemon_controller() {
+                spawn_daemon_controller()?;
+            }
+            None
+        };
+
+        g3proxy::control::admin::spawn_listener()
+            .await
+            .context("failed to start admin api listener")?;
 
         g3proxy::signal::setup_and_spawn().context("failed to setup signal handler")?;
         g3proxy::resolve::spawn_all()
@@ -122,6 +167,9 @@ fn tokio_run(args: &ProcArgs) -> anyhow::Result<()> {
         g3proxy::escape::load_all()
             .await
             .context("failed to load all escapers")?;
+        if let Some(session) = &upgrade_session {
+            g3proxy::control::upgrade::restore(session).await;
+        }
         g3proxy::auth::load_all()
             .await
             .context("failed to load all user groups")?;
@@ -135,6 +183,21 @@ fn tokio_run(args: &ProcArgs) -> anyhow::Result<()> {
         g3proxy::serve::spawn_all()
             .await
             .context("failed to spawn all servers")?;
+        g3proxy::control::cert_watch::spawn();
+        g3proxy::control::config_store::spawn();
+
+        if let Some(session) = upgrade_session {
+            g3proxy::control::upgrade::finish(session)
+                .await
+                .context("failed to finish upgrade")?;
+            if args.daemon_config.need_daemon_controller() {
+                spawn_daemon_controller()?;
+            }
+        }
+
+        // all the listening sockets have been bound now
+        #[cfg(target_os = "linux")]
+        g3proxy::sandbox::apply().context("failed to apply sandbox")?;
 
         unique_ctl.await;
 
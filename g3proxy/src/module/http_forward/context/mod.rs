@@ -47,6 +47,9 @@ pub(crate) trait HttpForwardContext {
     ) -> HttpForwardCapability;
 
     fn prepare_connection(&mut self, ups: &UpstreamAddr, is_tls: bool);
+    /// set the tls settings that will be used for the https connection,
+    /// this should be called after `prepare_connection` when pooled connections may be used
+    fn prepare_tls_connection(&mut self, _tls_config: &OpensslClientConfig, _tls_name: &Host) {}
     async fn get_alive_connection<'a>(
         &'a mut self,
         task_notes: &'a ServerTaskNotes,
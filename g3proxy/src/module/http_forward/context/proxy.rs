@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -24,7 +25,7 @@ use g3_types::net::{Host, HttpForwardCapability, OpensslClientConfig, UpstreamAd
 use crate::escape::{ArcEscaper, ArcEscaperInternalStats};
 use crate::module::http_forward::{
     ArcHttpForwardTaskRemoteStats, BoxHttpForwardConnection, HttpConnectionEofPoller,
-    HttpForwardContext,
+    HttpForwardConnectionPool, HttpForwardContext, HttpForwardPoolKey, HttpForwardTlsKey,
 };
 use crate::module::tcp_connect::{TcpConnectError, TcpConnectTaskNotes};
 use crate::serve::ServerTaskNotes;
@@ -34,19 +35,57 @@ pub(crate) struct ProxyHttpForwardContext {
     stats: ArcEscaperInternalStats,
     tcp_notes: TcpConnectTaskNotes,
     last_is_tls: bool,
+    last_tls: Option<HttpForwardTlsKey>,
     last_connection: Option<(Instant, HttpConnectionEofPoller)>,
+    pool: Option<Arc<HttpForwardConnectionPool>>,
+    pool_for_tls: bool,
+    pool_tls: Option<HttpForwardTlsKey>,
+    conn_created: Instant,
 }
 
 impl ProxyHttpForwardContext {
-    pub(crate) fn new(stats: ArcEscaperInternalStats, escaper: ArcEscaper) -> Self {
+    pub(crate) fn new(
+        stats: ArcEscaperInternalStats,
+        escaper: ArcEscaper,
+        pool: Option<Arc<HttpForwardConnectionPool>>,
+    ) -> Self {
         ProxyHttpForwardContext {
             escaper,
             stats,
             tcp_notes: TcpConnectTaskNotes::empty(),
             last_is_tls: false,
+            last_tls: None,
             last_connection: None,
+            pool,
+            pool_for_tls: false,
+            pool_tls: None,
+            conn_created: Instant::now(),
         }
     }
+
+    async fn fetch_pooled_connection(
+        &mut self,
+        task_notes: &ServerTaskNotes,
+        idle_expire: Duration,
+    ) -> Option<BoxHttpForwardConnection> {
+        let pool = self.pool.as_ref()?;
+        let tls = if self.pool_for_tls {
+            // the tls settings are required to reuse pooled tls connections
+            Some(self.pool_tls.as_ref()?)
+        } else {
+            None
+        };
+        let peer = self
+            .escaper
+            ._select_http_forward_peer(task_notes, &self.tcp_notes.upstream)?;
+        let key = HttpForwardPoolKey::new(peer, &self.tcp_notes.upstream, tls);
+        let pooled = pool.fetch(&key, idle_expire).await?;
+        self.tcp_notes.fill_generated(&pooled.tcp_notes);
+        self.last_is_tls = self.pool_for_tls;
+        self.last_tls = tls.cloned();
+        self.conn_created = pooled.created;
+        Some(pooled.connection)
+    }
 }
 
 #[async_trait]
@@ -60,6 +99,8 @@ impl HttpForwardContext for ProxyHttpForwardContext {
     }
 
     fn prepare_connection(&mut self, ups: &UpstreamAddr, is_tls: bool) {
+        self.pool_for_tls = is_tls;
+        self.pool_tls = None;
         if is_tls {
             self.stats.add_https_forward_request_attempted();
             if !self.last_is_tls || self.tcp_notes.upstream.ne(ups) {
@@ -88,6 +129,10 @@ impl HttpForwardContext for ProxyHttpForwardContext {
         }
     }
 
+    fn prepare_tls_connection(&mut self, tls_config: &OpensslClientConfig, tls_name: &Host) {
+        self.pool_tls = Some(HttpForwardTlsKey::new(tls_config, tls_name));
+    }
+
     async fn get_alive_connection<'a>(
         &'a mut self,
         task_notes: &'a ServerTaskNotes,
@@ -104,17 +149,26 @@ impl HttpForwardContext for ProxyHttpForwardContext {
             })
             .unwrap_or_default();
 
-        let (instant, eof_poller) = self.last_connection.take()?;
-        if instant.elapsed() < idle_expire {
-            let mut connection = eof_poller.recv_conn().await?;
-            connection
-                .0
-                .update_stats(&task_stats, all_user_stats.clone());
-            connection.1.update_stats(&task_stats, all_user_stats);
-            Some(connection)
-        } else {
-            None
-        }
+        let mut connection = match self.last_connection.take() {
+            Some((instant, eof_poller)) if instant.elapsed() < idle_expire => {
+                match eof_poller.recv_conn().await {
+                    Some(c) => c,
+                    None => {
+                        self.fetch_pooled_connection(task_notes, idle_expire)
+                            .await?
+                    }
+                }
+            }
+            _ => {
+                self.fetch_pooled_connection(task_notes, idle_expire)
+                    .await?
+            }
+        };
+        connection
+            .0
+            .update_stats(&task_stats, all_user_stats.clone());
+        connection.1.update_stats(&task_stats, all_user_stats);
+        Some(connection)
     }
 
     async fn make_new_http_connection<'a>(
@@ -123,6 +177,8 @@ impl HttpForwardContext for ProxyHttpForwardContext {
         task_stats: ArcHttpForwardTaskRemoteStats,
     ) -> Result<BoxHttpForwardConnection, TcpConnectError> {
         self.last_is_tls = false;
+        self.last_tls = None;
+        self.conn_created = Instant::now();
         self.escaper
             ._new_http_forward_connection(&mut self.tcp_notes, task_notes, task_stats)
             .await
@@ -136,6 +192,8 @@ impl HttpForwardContext for ProxyHttpForwardContext {
         tls_name: &'a Host,
     ) -> Result<BoxHttpForwardConnection, TcpConnectError> {
         self.last_is_tls = true;
+        self.last_tls = Some(HttpForwardTlsKey::new(tls_config, tls_name));
+        self.conn_created = Instant::now();
         self.escaper
             ._new_https_forward_connection(
                 &mut self.tcp_notes,
@@ -148,6 +206,22 @@ impl HttpForwardContext for ProxyHttpForwardContext {
     }
 
     fn save_alive_connection(&mut self, c: BoxHttpForwardConnection) {
+        if let Some(pool) = &self.pool {
+            // connections with unknown peer node will be closed
+            if let Some(peer) = &self.tcp_notes.next_peer {
+                let tls = if self.last_is_tls {
+                    match &self.last_tls {
+                        Some(tls) => Some(tls),
+                        None => return,
+                    }
+                } else {
+                    None
+                };
+                let key = HttpForwardPoolKey::new(peer.clone(), &self.tcp_notes.upstream, tls);
+                pool.save(key, self.conn_created, &self.tcp_notes, c);
+            }
+            return;
+        }
         let eof_poller = HttpConnectionEofPoller::spawn(c);
         self.last_connection = Some((Instant::now(), eof_poller));
     }
@@ -16,6 +16,7 @@
 
 mod connection;
 mod context;
+mod pool;
 mod response;
 mod stats;
 mod task;
@@ -29,6 +30,7 @@ pub(crate) use context::{
     BoxHttpForwardContext, DirectHttpForwardContext, FailoverHttpForwardContext,
     HttpForwardContext, ProxyHttpForwardContext, RouteHttpForwardContext,
 };
+pub(crate) use pool::{HttpForwardConnectionPool, HttpForwardPoolKey, HttpForwardTlsKey};
 pub(crate) use response::HttpProxyClientResponse;
 pub(crate) use stats::{
     ArcHttpForwardTaskRemoteStats, HttpForwardRemoteWrapperStats, HttpForwardTaskRemoteStats,
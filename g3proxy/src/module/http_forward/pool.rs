@@ -0,0 +1,380 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use g3_types::net::{ConnectionPoolConfig, Host, OpensslClientConfig, UpstreamAddr};
+
+use super::{BoxHttpForwardConnection, HttpConnectionEofPoller};
+use crate::module::tcp_connect::TcpConnectTaskNotes;
+
+/// The tls client settings used to create a tls connection to the upstream
+#[derive(Clone)]
+pub(crate) struct HttpForwardTlsKey {
+    // hold the config, so it won't be compared with a new one allocated at the same place
+    config: OpensslClientConfig,
+    name: Host,
+}
+
+impl HttpForwardTlsKey {
+    pub(crate) fn new(config: &OpensslClientConfig, name: &Host) -> Self {
+        HttpForwardTlsKey {
+            config: config.clone(),
+            name: name.clone(),
+        }
+    }
+}
+
+impl PartialEq for HttpForwardTlsKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.config.ptr_eq(&other.config)
+    }
+}
+
+impl Eq for HttpForwardTlsKey {}
+
+impl Hash for HttpForwardTlsKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl fmt::Debug for HttpForwardTlsKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpForwardTlsKey")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub(crate) enum HttpForwardPoolKey {
+    /// plain http connections to the next proxy node, which can be used for any upstream
+    Http { peer: UpstreamAddr },
+    /// tls connections to the upstream through the next proxy node
+    Https {
+        peer: UpstreamAddr,
+        upstream: UpstreamAddr,
+        tls: HttpForwardTlsKey,
+    },
+}
+
+impl HttpForwardPoolKey {
+    pub(crate) fn new(
+        peer: UpstreamAddr,
+        upstream: &UpstreamAddr,
+        tls: Option<&HttpForwardTlsKey>,
+    ) -> Self {
+        if let Some(tls) = tls {
+            HttpForwardPoolKey::Https {
+                peer,
+                upstream: upstream.clone(),
+                tls: tls.clone(),
+            }
+        } else {
+            HttpForwardPoolKey::Http { peer }
+        }
+    }
+}
+
+pub(crate) struct PooledHttpForwardConnection {
+    pub(crate) created: Instant,
+    pub(crate) tcp_notes: TcpConnectTaskNotes,
+    pub(crate) connection: BoxHttpForwardConnection,
+}
+
+struct IdleConnection<T = HttpConnectionEofPoller> {
+    created: Instant,
+    idle_since: Instant,
+    tcp_notes: TcpConnectTaskNotes,
+    eof_poller: T,
+}
+
+impl<T> IdleConnection<T> {
+    fn is_alive(&self, config: &ConnectionPoolConfig, idle_expire: Duration) -> bool {
+        let idle = self.idle_since.elapsed();
+        if idle >= idle_expire {
+            return false;
+        }
+        config.is_alive(self.created.elapsed(), idle)
+    }
+}
+
+struct IdleQueues<K, T = HttpConnectionEofPoller> {
+    queues: HashMap<K, VecDeque<IdleConnection<T>>>,
+    total: usize,
+}
+
+impl<K: Clone + Hash + Eq, T> IdleQueues<K, T> {
+    fn new() -> Self {
+        IdleQueues {
+            queues: HashMap::new(),
+            total: 0,
+        }
+    }
+
+    fn push(&mut self, config: &ConnectionPoolConfig, key: K, idle_conn: IdleConnection<T>) {
+        // drop all expired connections, and remove the peers that have no idle connections
+        let mut total = 0;
+        self.queues.retain(|_, queue| {
+            queue.retain(|c| c.is_alive(config, config.idle_timeout()));
+            total += queue.len();
+            !queue.is_empty()
+        });
+        self.total = total;
+
+        // the dropped eof poller will close the connection
+        let queue = self.queues.entry(key).or_default();
+        queue.push_back(idle_conn);
+        if queue.len() > config.max_idle_count() {
+            queue.pop_front();
+        } else {
+            self.total += 1;
+        }
+
+        while self.total > config.max_total_idle_count() {
+            if !self.evict_oldest() {
+                break;
+            }
+        }
+    }
+
+    fn evict_oldest(&mut self) -> bool {
+        let Some(key) = self
+            .queues
+            .iter()
+            .filter_map(|(k, queue)| queue.front().map(|c| (k, c.idle_since)))
+            .min_by_key(|(_, idle_since)| *idle_since)
+            .map(|(k, _)| k.clone())
+        else {
+            return false;
+        };
+        self.pop_front(&key);
+        true
+    }
+
+    fn pop_front(&mut self, key: &K) -> Option<IdleConnection<T>> {
+        self.pop_with(key, |queue| queue.pop_front())
+    }
+
+    fn pop_back(&mut self, key: &K) -> Option<IdleConnection<T>> {
+        self.pop_with(key, |queue| queue.pop_back())
+    }
+
+    fn pop_with<F>(&mut self, key: &K, pop: F) -> Option<IdleConnection<T>>
+    where
+        F: FnOnce(&mut VecDeque<IdleConnection<T>>) -> Option<IdleConnection<T>>,
+    {
+        let queue = self.queues.get_mut(key)?;
+        let c = pop(queue);
+        if queue.is_empty() {
+            self.queues.remove(key);
+        }
+        if c.is_some() {
+            self.total -= 1;
+        }
+        c
+    }
+}
+
+/// Idle http forward connections that can be shared by all tasks of the same escaper
+pub(crate) struct HttpForwardConnectionPool {
+    config: ConnectionPoolConfig,
+    idle: Mutex<IdleQueues<HttpForwardPoolKey>>,
+}
+
+impl HttpForwardConnectionPool {
+    pub(crate) fn new(config: ConnectionPoolConfig) -> Self {
+        HttpForwardConnectionPool {
+            config,
+            idle: Mutex::new(IdleQueues::new()),
+        }
+    }
+
+    pub(crate) fn save(
+        &self,
+        key: HttpForwardPoolKey,
+        created: Instant,
+        tcp_notes: &TcpConnectTaskNotes,
+        connection: BoxHttpForwardConnection,
+    ) {
+        if !self.config.is_alive(created.elapsed(), Duration::ZERO) {
+            return;
+        }
+
+        let idle_conn = IdleConnection {
+            created,
+            idle_since: Instant::now(),
+            tcp_notes: tcp_notes.clone(),
+            eof_poller: HttpConnectionEofPoller::spawn(connection),
+        };
+
+        let mut idle = self.idle.lock().unwrap();
+        idle.push(&self.config, key, idle_conn);
+    }
+
+    pub(crate) async fn fetch(
+        &self,
+        key: &HttpForwardPoolKey,
+        idle_expire: Duration,
+    ) -> Option<PooledHttpForwardConnection> {
+        loop {
+            let idle_conn = {
+                let mut idle = self.idle.lock().unwrap();
+                idle.pop_back(key)?
+            };
+
+            if !idle_conn.is_alive(&self.config, idle_expire) {
+                continue;
+            }
+            if let Some(connection) = idle_conn.eof_poller.recv_conn().await {
+                return Some(PooledHttpForwardConnection {
+                    created: idle_conn.created,
+                    tcp_notes: idle_conn.tcp_notes,
+                    connection,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use g3_types::net::OpensslClientConfigBuilder;
+
+    fn idle_conn(id: usize, idle: Duration) -> IdleConnection<usize> {
+        let now = Instant::now();
+        IdleConnection {
+            created: now - idle,
+            idle_since: now - idle,
+            tcp_notes: TcpConnectTaskNotes::empty(),
+            eof_poller: id,
+        }
+    }
+
+    fn pool_config(max_idle: usize, max_total_idle: usize) -> ConnectionPoolConfig {
+        let mut config = ConnectionPoolConfig::default();
+        config.set_max_idle_count(max_idle);
+        config.set_max_total_idle_count(max_total_idle);
+        config
+    }
+
+    #[test]
+    fn max_idle_per_key() {
+        let config = pool_config(2, 16);
+        let mut queues = IdleQueues::new();
+
+        queues.push(&config, "a", idle_conn(1, Duration::ZERO));
+        queues.push(&config, "a", idle_conn(2, Duration::ZERO));
+        queues.push(&config, "a", idle_conn(3, Duration::ZERO));
+        queues.push(&config, "b", idle_conn(4, Duration::ZERO));
+        assert_eq!(queues.total, 3);
+
+        assert_eq!(queues.pop_back(&"a").unwrap().eof_poller, 3);
+        assert_eq!(queues.pop_back(&"a").unwrap().eof_poller, 2);
+        assert!(queues.pop_back(&"a").is_none());
+        assert_eq!(queues.total, 1);
+    }
+
+    #[test]
+    fn max_total_idle() {
+        let config = pool_config(4, 3);
+        let mut queues = IdleQueues::new();
+
+        queues.push(&config, "a", idle_conn(1, Duration::from_secs(3)));
+        queues.push(&config, "b", idle_conn(2, Duration::from_secs(2)));
+        queues.push(&config, "b", idle_conn(3, Duration::from_secs(1)));
+        queues.push(&config, "c", idle_conn(4, Duration::ZERO));
+        assert_eq!(queues.total, 3);
+        assert!(!queues.queues.contains_key("a"));
+
+        queues.push(&config, "c", idle_conn(5, Duration::ZERO));
+        assert_eq!(queues.total, 3);
+        assert_eq!(queues.pop_back(&"b").unwrap().eof_poller, 3);
+        assert!(queues.pop_back(&"b").is_none());
+    }
+
+    #[test]
+    fn evict_empty() {
+        let config = pool_config(4, 16);
+        let mut queues = IdleQueues::new();
+
+        queues.push(&config, "a", idle_conn(1, Duration::ZERO));
+        assert!(queues.pop_back(&"a").is_some());
+        assert!(queues.queues.is_empty());
+        assert_eq!(queues.total, 0);
+
+        queues.push(&config, "b", idle_conn(2, config.idle_timeout()));
+        queues.push(&config, "c", idle_conn(3, Duration::ZERO));
+        assert!(!queues.queues.contains_key("b"));
+        assert_eq!(queues.queues.len(), 1);
+        assert_eq!(queues.total, 1);
+    }
+
+    #[test]
+    fn tls_key() {
+        let config = pool_config(4, 16);
+        let mut queues = IdleQueues::new();
+
+        let peer = UpstreamAddr::from_host_str_and_port("proxy.example.net", 3128).unwrap();
+        let upstream = UpstreamAddr::from_host_str_and_port("www.example.net", 443).unwrap();
+        let tls_name = Host::from_str("www.example.net").unwrap();
+        let other_name = Host::from_str("example.net").unwrap();
+
+        let tls_config = OpensslClientConfigBuilder::with_cache_for_one_site()
+            .build()
+            .unwrap();
+        let same_config = tls_config.clone();
+        let other_config = OpensslClientConfigBuilder::with_cache_for_one_site()
+            .build()
+            .unwrap();
+
+        let tls = HttpForwardTlsKey::new(&tls_config, &tls_name);
+        let key = HttpForwardPoolKey::new(peer.clone(), &upstream, Some(&tls));
+        queues.push(&config, key.clone(), idle_conn(1, Duration::ZERO));
+
+        // plain http connection to the same peer
+        let plain_key = HttpForwardPoolKey::new(peer.clone(), &upstream, None);
+        assert!(queues.pop_back(&plain_key).is_none());
+
+        // different tls config
+        let other_tls = HttpForwardTlsKey::new(&other_config, &tls_name);
+        let other_key = HttpForwardPoolKey::new(peer.clone(), &upstream, Some(&other_tls));
+        assert_ne!(key, other_key);
+        assert!(queues.pop_back(&other_key).is_none());
+
+        // different tls name
+        let other_tls = HttpForwardTlsKey::new(&tls_config, &other_name);
+        let other_key = HttpForwardPoolKey::new(peer.clone(), &upstream, Some(&other_tls));
+        assert_ne!(key, other_key);
+        assert!(queues.pop_back(&other_key).is_none());
+
+        // cloned tls config
+        let same_tls = HttpForwardTlsKey::new(&same_config, &tls_name);
+        let same_key = HttpForwardPoolKey::new(peer, &upstream, Some(&same_tls));
+        assert_eq!(queues.pop_back(&same_key).unwrap().eof_poller, 1);
+        assert_eq!(queues.total, 0);
+    }
+}
@@ -258,6 +258,9 @@ impl HttpProxyClientResponse {
                 version,
                 true,
             ),
+            TcpConnectError::ResolveFailed(e) if e.is_denied() => {
+                HttpProxyClientResponse::from_standard(StatusCode::FORBIDDEN, version, close)
+            }
             TcpConnectError::ResolveFailed(_) => HttpProxyClientResponse::from_standard(
                 StatusCode::from_u16(CustomStatusCode::ORIGIN_DNS_ERROR).unwrap(),
                 version,
@@ -288,7 +291,8 @@ impl HttpProxyClientResponse {
             TcpConnectError::ProxyProtocolWriteFailed(_)
             | TcpConnectError::NegotiationReadFailed(_)
             | TcpConnectError::NegotiationWriteFailed(_)
-            | TcpConnectError::NegotiationRejected(_) => {
+            | TcpConnectError::NegotiationRejected(_)
+            | TcpConnectError::NegotiationAuthFailed => {
                 HttpProxyClientResponse::from_standard(StatusCode::BAD_GATEWAY, version, true)
             }
             TcpConnectError::NegotiationPeerTimeout => {
@@ -401,11 +405,13 @@ impl HttpProxyClientResponse {
             ServerTaskError::CanceledAsUserBlocked => {
                 HttpProxyClientResponse::from_standard(StatusCode::FORBIDDEN, version, true)
             }
-            ServerTaskError::CanceledAsServerQuit => HttpProxyClientResponse::from_standard(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                version,
-                true,
-            ),
+            ServerTaskError::CanceledAsServerQuit | ServerTaskError::CanceledAsKilled => {
+                HttpProxyClientResponse::from_standard(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    version,
+                    true,
+                )
+            }
             ServerTaskError::ClientTcpReadFailed(_)
             | ServerTaskError::ClientTcpWriteFailed(_)
             | ServerTaskError::ClientUdpRecvFailed(_)
@@ -559,6 +565,7 @@ impl HttpProxyClientResponse {
         version: Version,
         writer: &mut W,
         realm: &AsciiStr,
+        negotiate: bool,
         close: bool,
     ) -> io::Result<()>
     where
@@ -569,6 +576,9 @@ impl HttpProxyClientResponse {
             version,
             close,
         );
+        if negotiate {
+            response.add_extra_header(g3_http::header::proxy_authenticate_negotiate());
+        }
         let auth_header = g3_http::header::proxy_authenticate_basic(realm.as_str());
         response.add_extra_header(auth_header);
         response.reply_err(writer).await
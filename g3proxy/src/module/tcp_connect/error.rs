@@ -56,6 +56,8 @@ pub(crate) enum TcpConnectError {
     NegotiationWriteFailed(io::Error),
     #[error("negotiation rejected: {0}")]
     NegotiationRejected(String),
+    #[error("negotiation auth failed with remote proxy")]
+    NegotiationAuthFailed,
     #[error("negotiation timeout")]
     NegotiationPeerTimeout,
     #[error("negotiation protocol error")]
@@ -91,6 +93,7 @@ impl TcpConnectError {
             TcpConnectError::NegotiationReadFailed(_) => "NegotiationReadFailed",
             TcpConnectError::NegotiationWriteFailed(_) => "NegotiationWriteFailed",
             TcpConnectError::NegotiationRejected(_) => "NegotiationRejected",
+            TcpConnectError::NegotiationAuthFailed => "NegotiationAuthFailed",
             TcpConnectError::NegotiationPeerTimeout => "NegotiationPeerTimeout",
             TcpConnectError::NegotiationProtocolErr => "NegotiationProtocolErr",
             TcpConnectError::InternalServerError(_) => "InternalServerError",
@@ -101,6 +104,23 @@ impl TcpConnectError {
             TcpConnectError::UpstreamTlsHandshakeFailed(_) => "UpstreamTlsHandshakeFailed",
         }
     }
+
+    /// check if the error is caused by the next proxy peer itself
+    pub(crate) fn is_peer_failure(&self) -> bool {
+        matches!(
+            self,
+            TcpConnectError::ConnectFailed(_)
+                | TcpConnectError::TimeoutByRule
+                | TcpConnectError::NoAddressConnected
+                | TcpConnectError::ProxyProtocolWriteFailed(_)
+                | TcpConnectError::NegotiationReadFailed(_)
+                | TcpConnectError::NegotiationWriteFailed(_)
+                | TcpConnectError::NegotiationPeerTimeout
+                | TcpConnectError::NegotiationProtocolErr
+                | TcpConnectError::PeerTlsHandshakeTimeout
+                | TcpConnectError::PeerTlsHandshakeFailed(_)
+        )
+    }
 }
 
 impl From<TcpConnectError> for ServerTaskError {
@@ -129,6 +149,9 @@ impl From<TcpConnectError> for ServerTaskError {
             TcpConnectError::NegotiationReadFailed(e) => ServerTaskError::UpstreamReadFailed(e),
             TcpConnectError::NegotiationWriteFailed(e) => ServerTaskError::UpstreamWriteFailed(e),
             TcpConnectError::NegotiationRejected(e) => ServerTaskError::UpstreamNotNegotiated(e),
+            TcpConnectError::NegotiationAuthFailed => {
+                ServerTaskError::UpstreamNotNegotiated("auth failed with remote proxy".to_string())
+            }
             TcpConnectError::NegotiationPeerTimeout => {
                 ServerTaskError::UpstreamAppTimeout("negotiation peer timeout")
             }
@@ -164,9 +187,7 @@ impl From<SocksConnectError> for TcpConnectError {
             SocksConnectError::UnsupportedAuthVersion => TcpConnectError::NegotiationRejected(
                 "auth protocol mismatch with remote proxy".to_string(),
             ),
-            SocksConnectError::AuthFailed => {
-                TcpConnectError::NegotiationRejected("auth failed with remote proxy".to_string())
-            }
+            SocksConnectError::AuthFailed => TcpConnectError::NegotiationAuthFailed,
             SocksConnectError::InvalidProtocol(_) => TcpConnectError::NegotiationProtocolErr,
             SocksConnectError::PeerTimeout => TcpConnectError::NegotiationPeerTimeout,
             SocksConnectError::RequestFailed(s) => TcpConnectError::NegotiationRejected(s),
@@ -180,6 +201,7 @@ impl From<&TcpConnectError> for Socks5Reply {
             TcpConnectError::MethodUnavailable
             | TcpConnectError::ForbiddenAddressFamily
             | TcpConnectError::ForbiddenRemoteAddress => Socks5Reply::ForbiddenByRule,
+            TcpConnectError::ResolveFailed(e) if e.is_denied() => Socks5Reply::ForbiddenByRule,
             TcpConnectError::ConnectFailed(e) => match e {
                 ConnectError::ConnectionRefused | ConnectError::ConnectionReset => {
                     Socks5Reply::ConnectionRefused
@@ -200,7 +222,9 @@ impl From<&TcpConnectError> for Socks5Reply {
             TcpConnectError::ProxyProtocolWriteFailed(_)
             | TcpConnectError::NegotiationReadFailed(_)
             | TcpConnectError::NegotiationWriteFailed(_) => Socks5Reply::GeneralServerFailure,
-            TcpConnectError::NegotiationRejected(_) => Socks5Reply::ConnectionRefused,
+            TcpConnectError::NegotiationRejected(_) | TcpConnectError::NegotiationAuthFailed => {
+                Socks5Reply::ConnectionRefused
+            }
             TcpConnectError::NegotiationPeerTimeout => Socks5Reply::ConnectionTimedOut,
             TcpConnectError::InternalServerError(_)
             | TcpConnectError::InternalTlsClientError(_) => Socks5Reply::GeneralServerFailure,
@@ -221,6 +245,9 @@ impl From<HttpConnectError> for TcpConnectError {
             HttpConnectError::ReadFailed(e) => TcpConnectError::NegotiationReadFailed(e),
             HttpConnectError::WriteFailed(e) => TcpConnectError::NegotiationWriteFailed(e),
             HttpConnectError::InvalidResponse(_) => TcpConnectError::NegotiationProtocolErr,
+            HttpConnectError::UnexpectedStatusCode(407, _) => {
+                TcpConnectError::NegotiationAuthFailed
+            }
             HttpConnectError::UnexpectedStatusCode(code, reason) => {
                 TcpConnectError::NegotiationRejected(format!(
                     "rejected by remote proxy with response {code} {reason}"
@@ -42,8 +42,12 @@ pub(crate) struct TcpConnectTaskNotes {
     pub(crate) escaper: MetricsName,
     pub(crate) bind: Option<IpAddr>,
     pub(crate) next: Option<SocketAddr>,
+    /// the next proxy node that has been connected to, only set by proxy escapers
+    pub(crate) next_peer: Option<UpstreamAddr>,
     pub(crate) tries: usize,
     pub(crate) local: Option<SocketAddr>,
+    /// the effective mss, only set if mss clamping is enabled
+    pub(crate) mss: Option<u32>,
     pub(crate) expire: Option<DateTime<Utc>>,
     pub(crate) egress: Option<EgressInfo>,
     pub(crate) chained: TcpConnectChainedNotes,
@@ -57,8 +61,10 @@ impl TcpConnectTaskNotes {
             escaper: MetricsName::default(),
             bind: None,
             next: None,
+            next_peer: None,
             tries: 0,
             local: None,
+            mss: None,
             expire: None,
             egress: None,
             chained: Default::default(),
@@ -79,8 +85,10 @@ impl TcpConnectTaskNotes {
         self.escaper.clear();
         self.bind = None;
         self.next = None;
+        self.next_peer = None;
         self.tries = 0;
         self.local = None;
+        self.mss = None;
         self.expire = None;
         self.egress = None;
         self.chained.reset();
@@ -91,8 +99,10 @@ impl TcpConnectTaskNotes {
         self.escaper.clone_from(&other.escaper);
         self.bind = other.bind;
         self.next = other.next;
+        self.next_peer.clone_from(&other.next_peer);
         self.tries = other.tries;
         self.local = other.local;
+        self.mss = other.mss;
         self.expire = other.expire;
         self.egress = other.egress.clone();
         self.chained.clone_from(&other.chained);
@@ -29,6 +29,9 @@ const ARGS_COMPLETION: &str = "completion";
 const ARGS_VERSION: &str = "version";
 const ARGS_VERIFY_PANIC: &str = "verify-panic";
 const ARGS_DEP_GRAPH: &str = "dep-graph";
+const ARGS_CHECK_CONFIG: &str = "check-config";
+const ARGS_UPGRADE: &str = "upgrade";
+const ARGS_ENV_INTERPOLATION: &str = "env-interpolation";
 const ARGS_GROUP_NAME: &str = "group-name";
 const ARGS_CONFIG_FILE: &str = "config-file";
 const ARGS_CONTROL_DIR: &str = "control-dir";
@@ -45,6 +48,8 @@ pub struct ProcArgs {
     pub output_graphviz_graph: bool,
     pub output_mermaid_graph: bool,
     pub output_plantuml_graph: bool,
+    pub check_config: bool,
+    pub upgrade: bool,
 }
 
 impl Default for ProcArgs {
@@ -54,6 +59,8 @@ impl Default for ProcArgs {
             output_graphviz_graph: false,
             output_mermaid_graph: false,
             output_plantuml_graph: false,
+            check_config: false,
+            upgrade: false,
         }
     }
 }
@@ -94,6 +101,24 @@ fn build_cli_args() -> Command {
                 .value_parser([DEP_GRAPH_GRAPHVIZ, DEP_GRAPH_MERMAID, DEP_GRAPH_PLANTUML])
                 .default_missing_value(DEP_GRAPH_GRAPHVIZ),
         )
+        .arg(
+            Arg::new(ARGS_CHECK_CONFIG)
+                .help("Load and validate the config, including the cross references, then exit")
+                .action(ArgAction::SetTrue)
+                .long("check-config"),
+        )
+        .arg(
+            Arg::new(ARGS_UPGRADE)
+                .help("Take over the listen sockets from the running daemon of the same group")
+                .action(ArgAction::SetTrue)
+                .long("upgrade"),
+        )
+        .arg(
+            Arg::new(ARGS_ENV_INTERPOLATION)
+                .help("Expand environment variables in config values")
+                .action(ArgAction::SetTrue)
+                .long("env-interpolation"),
+        )
         .arg(
             Arg::new(ARGS_GROUP_NAME)
                 .help("Group name")
@@ -148,6 +173,15 @@ pub fn parse_clap() -> anyhow::Result<Option<ProcArgs>> {
     if args.get_flag(ARGS_VERIFY_PANIC) {
         panic!("panic as requested")
     }
+    if args.get_flag(ARGS_CHECK_CONFIG) {
+        proc_args.check_config = true;
+    }
+    if args.get_flag(ARGS_UPGRADE) {
+        proc_args.upgrade = true;
+    }
+    if args.get_flag(ARGS_ENV_INTERPOLATION) {
+        g3_yaml::enable_env_interpolation();
+    }
     if let Some(g) = args.get_one::<String>(ARGS_DEP_GRAPH) {
         match g.as_str() {
             DEP_GRAPH_GRAPHVIZ => proc_args.output_graphviz_graph = true,
@@ -22,7 +22,9 @@ use std::time::Duration;
 
 use g3_resolver::{ResolveError, ResolvedRecordSource};
 use g3_types::metrics::MetricsName;
-use g3_types::resolve::{QueryStrategy, ResolveRedirectionValue, ResolveStrategy};
+use g3_types::resolve::{
+    PickStrategy, QueryStrategy, ResolvePickState, ResolveRedirectionValue, ResolveStrategy,
+};
 
 pub(crate) trait LoggedResolveJob {
     fn log_error(&self, _e: &ResolveError, _source: ResolvedRecordSource) {}
@@ -54,6 +56,22 @@ pub(crate) trait IntegratedResolverHandle {
     fn query_v4(&self, domain: String) -> Result<BoxLoggedResolveJob, ResolveError>;
     fn query_v6(&self, domain: String) -> Result<BoxLoggedResolveJob, ResolveError>;
 
+    /// query with the client subnet option if enabled, the client ip will be ignored by default
+    fn query_v4_for_client(
+        &self,
+        domain: String,
+        _client_ip: IpAddr,
+    ) -> Result<BoxLoggedResolveJob, ResolveError> {
+        self.query_v4(domain)
+    }
+    fn query_v6_for_client(
+        &self,
+        domain: String,
+        _client_ip: IpAddr,
+    ) -> Result<BoxLoggedResolveJob, ResolveError> {
+        self.query_v6(domain)
+    }
+
     fn clone_inner(&self) -> Option<g3_resolver::ResolverHandle>;
 }
 
@@ -96,16 +114,18 @@ pub(crate) struct HappyEyeballsResolveJob {
     h2_done: bool,
     r2_block: bool,
     strategy: ResolveStrategy,
+    pick_state: Arc<ResolvePickState>,
 }
 
 impl HappyEyeballsResolveJob {
     pub(crate) fn new_redirected(
         s: ResolveStrategy,
+        pick_state: &Arc<ResolvePickState>,
         h: &ArcIntegratedResolverHandle,
         v: ResolveRedirectionValue,
     ) -> Result<Self, ResolveError> {
         match v {
-            ResolveRedirectionValue::Domain(d) => Self::new_dyn(s, h, &d),
+            ResolveRedirectionValue::Domain(d) => Self::new_dyn(s, pick_state, h, &d),
             ResolveRedirectionValue::Ip((ip4, ip6)) => {
                 let mut job = HappyEyeballsResolveJob {
                     r1: None,
@@ -116,8 +136,9 @@ impl HappyEyeballsResolveJob {
                     h2_done: true,
                     r2_block: false,
                     strategy: s,
+                    pick_state: pick_state.clone(),
                 };
-                match s.query {
+                match s.weighted_query() {
                     QueryStrategy::Ipv4Only => {
                         job.r1 = Some(ip4);
                         job.r2 = Some(Vec::new());
@@ -152,15 +173,35 @@ impl HappyEyeballsResolveJob {
 
     pub(crate) fn new_dyn(
         s: ResolveStrategy,
+        pick_state: &Arc<ResolvePickState>,
+        h: &ArcIntegratedResolverHandle,
+        domain: &str,
+    ) -> Result<Self, ResolveError> {
+        Self::new_dyn_for_client(s, pick_state, h, domain, None)
+    }
+
+    /// the client ip will be used to set the EDNS Client Subnet option if enabled on the resolver
+    pub(crate) fn new_dyn_for_client(
+        s: ResolveStrategy,
+        pick_state: &Arc<ResolvePickState>,
         h: &ArcIntegratedResolverHandle,
         domain: &str,
+        client_ip: Option<IpAddr>,
     ) -> Result<Self, ResolveError> {
         if domain.is_empty() {
             return Err(ResolveError::EmptyDomain);
         }
-        match s.query {
+        let query_v4 = || match client_ip {
+            Some(ip) => h.query_v4_for_client(domain.to_string(), ip),
+            None => h.query_v4(domain.to_string()),
+        };
+        let query_v6 = || match client_ip {
+            Some(ip) => h.query_v6_for_client(domain.to_string(), ip),
+            None => h.query_v6(domain.to_string()),
+        };
+        match s.weighted_query() {
             QueryStrategy::Ipv4Only => {
-                let h1 = h.query_v4(domain.to_string())?;
+                let h1 = query_v4()?;
                 let h2 = Box::new(NeverResolveJob {});
                 Ok(HappyEyeballsResolveJob {
                     r1: None,
@@ -171,11 +212,12 @@ impl HappyEyeballsResolveJob {
                     h2_done: true,
                     r2_block: false,
                     strategy: s,
+                    pick_state: pick_state.clone(),
                 })
             }
             QueryStrategy::Ipv4First => {
-                let h1 = h.query_v4(domain.to_string())?;
-                let h2 = h.query_v6(domain.to_string())?;
+                let h1 = query_v4()?;
+                let h2 = query_v6()?;
                 Ok(HappyEyeballsResolveJob {
                     r1: None,
                     r2: None,
@@ -185,10 +227,11 @@ impl HappyEyeballsResolveJob {
                     h2_done: false,
                     r2_block: false,
                     strategy: s,
+                    pick_state: pick_state.clone(),
                 })
             }
             QueryStrategy::Ipv6Only => {
-                let h1 = h.query_v6(domain.to_string())?;
+                let h1 = query_v6()?;
                 let h2 = Box::new(NeverResolveJob {});
                 Ok(HappyEyeballsResolveJob {
                     r1: None,
@@ -199,11 +242,12 @@ impl HappyEyeballsResolveJob {
                     h2_done: true,
                     r2_block: false,
                     strategy: s,
+                    pick_state: pick_state.clone(),
                 })
             }
             QueryStrategy::Ipv6First => {
-                let h1 = h.query_v6(domain.to_string())?;
-                let h2 = h.query_v4(domain.to_string())?;
+                let h1 = query_v6()?;
+                let h2 = query_v4()?;
                 Ok(HappyEyeballsResolveJob {
                     r1: None,
                     r2: None,
@@ -213,6 +257,7 @@ impl HappyEyeballsResolveJob {
                     h2_done: false,
                     r2_block: false,
                     strategy: s,
+                    pick_state: pick_state.clone(),
                 })
             }
         }
@@ -223,7 +268,7 @@ impl HappyEyeballsResolveJob {
             Ok(r1) => {
                 self.h1_done = true;
                 self.h1 = Box::new(NeverResolveJob {});
-                Ok(self.strategy.pick_many(r1, max_count))
+                Ok(self.strategy.pick_many(r1, max_count, &self.pick_state))
             }
             Err(e) => {
                 self.h1_done = true;
@@ -238,7 +283,7 @@ impl HappyEyeballsResolveJob {
             Ok(r2) => {
                 self.h2_done = true;
                 self.h2 = Box::new(NeverResolveJob {});
-                Ok(self.strategy.pick_many(r2, max_count))
+                Ok(self.strategy.pick_many(r2, max_count, &self.pick_state))
             }
             Err(e) => {
                 self.h2_done = true;
@@ -248,6 +293,19 @@ impl HappyEyeballsResolveJob {
         }
     }
 
+    /// get the state to record the connect rtt to the resolved addresses, if needed
+    pub(crate) fn connect_rtt_state(&self) -> Option<Arc<ResolvePickState>> {
+        if self.strategy.pick == PickStrategy::LowestLatency {
+            Some(self.pick_state.clone())
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn pick_best(&self, ips: Vec<IpAddr>) -> Option<IpAddr> {
+        self.strategy.pick_best(ips, &self.pick_state)
+    }
+
     pub(crate) async fn get_r1_or_first(
         &mut self,
         resolution_delay: Duration,
@@ -256,7 +314,7 @@ impl HappyEyeballsResolveJob {
         if let Some(r1) = self.r1.take() {
             assert!(self.h1_done);
             // h1 should be a never job
-            return Ok(self.strategy.pick_many(r1, max_count));
+            return Ok(self.strategy.pick_many(r1, max_count, &self.pick_state));
         }
 
         if self.h2_done {
@@ -271,7 +329,7 @@ impl HappyEyeballsResolveJob {
                     Ok(r1) => {
                         self.h1_done = true;
                         self.h1 = Box::new(NeverResolveJob {});
-                        Ok(self.strategy.pick_many(r1, max_count))
+                        Ok(self.strategy.pick_many(r1, max_count, &self.pick_state))
                     }
                     Err(e) => {
                         self.h1 = Box::new(ErrorResolveJob::with_error(e));
@@ -296,13 +354,15 @@ impl HappyEyeballsResolveJob {
                                     self.r2 = Some(r2);
                                     self.h1_done = true;
                                     self.h1 = Box::new(NeverResolveJob {});
-                                    Ok(self.strategy.pick_many(r1, max_count))
+                                    Ok(self.strategy.pick_many(r1, max_count, &self.pick_state))
                                 }
                                 Ok(Err(e)) => {
                                     self.h1 = Box::new(ErrorResolveJob::with_error(e));
-                                    Ok(self.strategy.pick_many(r2, max_count))
+                                    Ok(self.strategy.pick_many(r2, max_count, &self.pick_state))
+                                }
+                                Err(_) => {
+                                    Ok(self.strategy.pick_many(r2, max_count, &self.pick_state))
                                 }
-                                Err(_) => Ok(self.strategy.pick_many(r2, max_count)),
                             }
                         }
                     }
@@ -326,18 +386,18 @@ impl HappyEyeballsResolveJob {
 
         if let Some(r2) = self.r2.take() {
             self.r2_block = true;
-            return Ok(self.strategy.pick_many(r2, max_count));
+            return Ok(self.strategy.pick_many(r2, max_count, &self.pick_state));
         }
 
         // there must be at most 1 query at r2 stage
         let r = if !self.h2_done {
             poll_fn(|cx| self.h2.poll_query(cx))
                 .await
-                .map(|r2| self.strategy.pick_many(r2, max_count))
+                .map(|r2| self.strategy.pick_many(r2, max_count, &self.pick_state))
         } else if !self.h1_done {
             poll_fn(|cx| self.h1.poll_query(cx))
                 .await
-                .map(|r1| self.strategy.pick_many(r1, max_count))
+                .map(|r1| self.strategy.pick_many(r1, max_count, &self.pick_state))
         } else {
             // if all done, return empty record to make caller know it
             Ok(Vec::new())
@@ -354,6 +414,7 @@ enum ArriveFirstResolveJobInner {
 
 pub(crate) struct ArriveFirstResolveJob {
     strategy: ResolveStrategy,
+    pick_state: Arc<ResolvePickState>,
     inner: Option<ArriveFirstResolveJobInner>,
 }
 
@@ -361,12 +422,13 @@ impl ArriveFirstResolveJob {
     pub(crate) fn new(
         handle: &ArcIntegratedResolverHandle,
         strategy: ResolveStrategy,
+        pick_state: &Arc<ResolvePickState>,
         domain: &str,
     ) -> Result<Self, ResolveError> {
         if domain.is_empty() {
             return Err(ResolveError::EmptyDomain);
         }
-        let inner = match strategy.query {
+        let inner = match strategy.weighted_query() {
             QueryStrategy::Ipv4Only => {
                 ArriveFirstResolveJobInner::OnlyOne(handle.query_v4(domain.to_string())?)
             }
@@ -384,6 +446,7 @@ impl ArriveFirstResolveJob {
         };
         Ok(ArriveFirstResolveJob {
             strategy,
+            pick_state: pick_state.clone(),
             inner: Some(inner),
         })
     }
@@ -445,11 +508,14 @@ impl ArriveFirstResolveJob {
         cx: &mut Context<'_>,
     ) -> Poll<Result<IpAddr, ResolveError>> {
         let ips = ready!(self.poll_all_addrs(cx))?;
-        let ip = self.strategy.pick_best(ips).ok_or_else(|| {
-            ResolveError::UnexpectedError(
-                "resolver job return ok but with no ip can be selected".to_string(),
-            )
-        })?;
+        let ip = self
+            .strategy
+            .pick_best(ips, &self.pick_state)
+            .ok_or_else(|| {
+                ResolveError::UnexpectedError(
+                    "resolver job return ok but with no ip can be selected".to_string(),
+                )
+            })?;
         Poll::Ready(Ok(ip))
     }
 }
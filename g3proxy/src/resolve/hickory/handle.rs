@@ -21,7 +21,7 @@ use std::task::{ready, Context, Poll};
 use slog::{slog_info, Logger};
 use tokio::time::Instant;
 
-use g3_resolver::{ResolveError, ResolveQueryType, ResolvedRecordSource};
+use g3_resolver::{ClientSubnet, ResolveError, ResolveQueryType, ResolvedRecordSource};
 use g3_slog_types::{LtDuration, LtIpAddr};
 use g3_types::metrics::MetricsName;
 
@@ -47,6 +47,26 @@ impl HickoryResolverHandle {
             logger: Arc::clone(logger),
         }
     }
+
+    fn client_subnet(&self, client_ip: IpAddr) -> Option<ClientSubnet> {
+        self.config.get_client_subnet()?.subnet(client_ip)
+    }
+
+    fn new_job(
+        &self,
+        domain: String,
+        query_type: ResolveQueryType,
+        inner: g3_resolver::ResolveJob,
+    ) -> BoxLoggedResolveJob {
+        Box::new(HickoryResolverJob {
+            config: Arc::clone(&self.config),
+            domain,
+            query_type,
+            inner,
+            logger: Arc::clone(&self.logger),
+            create_ins: Instant::now(),
+        })
+    }
 }
 
 impl IntegratedResolverHandle for HickoryResolverHandle {
@@ -60,26 +80,32 @@ impl IntegratedResolverHandle for HickoryResolverHandle {
 
     fn query_v4(&self, domain: String) -> Result<BoxLoggedResolveJob, ResolveError> {
         let job = self.inner.get_v4(domain.clone())?;
-        Ok(Box::new(HickoryResolverJob {
-            config: Arc::clone(&self.config),
-            domain,
-            query_type: ResolveQueryType::A,
-            inner: job,
-            logger: Arc::clone(&self.logger),
-            create_ins: Instant::now(),
-        }))
+        Ok(self.new_job(domain, ResolveQueryType::A, job))
+    }
+
+    fn query_v4_for_client(
+        &self,
+        domain: String,
+        client_ip: IpAddr,
+    ) -> Result<BoxLoggedResolveJob, ResolveError> {
+        let subnet = self.client_subnet(client_ip);
+        let job = self.inner.get_v4_with_subnet(domain.clone(), subnet)?;
+        Ok(self.new_job(domain, ResolveQueryType::A, job))
     }
 
     fn query_v6(&self, domain: String) -> Result<BoxLoggedResolveJob, ResolveError> {
         let job = self.inner.get_v6(domain.clone())?;
-        Ok(Box::new(HickoryResolverJob {
-            config: Arc::clone(&self.config),
-            domain,
-            query_type: ResolveQueryType::Aaaa,
-            inner: job,
-            logger: Arc::clone(&self.logger),
-            create_ins: Instant::now(),
-        }))
+        Ok(self.new_job(domain, ResolveQueryType::Aaaa, job))
+    }
+
+    fn query_v6_for_client(
+        &self,
+        domain: String,
+        client_ip: IpAddr,
+    ) -> Result<BoxLoggedResolveJob, ResolveError> {
+        let subnet = self.client_subnet(client_ip);
+        let job = self.inner.get_v6_with_subnet(domain.clone(), subnet)?;
+        Ok(self.new_job(domain, ResolveQueryType::Aaaa, job))
     }
 
     fn clone_inner(&self) -> Option<g3_resolver::ResolverHandle> {
@@ -44,6 +44,7 @@ mod hickory;
 
 mod deny_all;
 mod fail_over;
+mod split;
 
 mod ops;
 pub(crate) use ops::reload;
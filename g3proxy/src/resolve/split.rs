@@ -0,0 +1,321 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use arc_swap::ArcSwap;
+use async_trait::async_trait;
+use radix_trie::Trie;
+
+use g3_resolver::ResolveError;
+use g3_types::metrics::MetricsName;
+
+use super::{
+    ArcIntegratedResolverHandle, BoxLoggedResolveJob, IntegratedResolverHandle, Resolver,
+    ResolverInternal,
+};
+use crate::config::resolver::split::SplitResolverConfig;
+use crate::config::resolver::{AnyResolverConfig, ResolverConfig};
+use crate::resolve::{BoxResolver, ResolverStats};
+
+struct SplitResolveRoute {
+    child_match_domain: Trie<String, ArcIntegratedResolverHandle>,
+    default_next: ArcIntegratedResolverHandle,
+}
+
+impl SplitResolveRoute {
+    fn new(
+        config: &SplitResolverConfig,
+        next_table: &BTreeMap<MetricsName, ArcIntegratedResolverHandle>,
+    ) -> anyhow::Result<Self> {
+        let get_next = |name: &MetricsName| {
+            next_table
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("no next resolver {name} found"))
+        };
+
+        let mut child_match_domain = Trie::new();
+        for (next, domains) in &config.child_match_domain {
+            let handle = get_next(next)?;
+            for domain in domains {
+                let reversed = g3_types::resolve::reverse_idna_domain(domain);
+                child_match_domain.insert(reversed, Arc::clone(&handle));
+            }
+        }
+
+        Ok(SplitResolveRoute {
+            child_match_domain,
+            default_next: get_next(&config.default_next)?,
+        })
+    }
+
+    fn select(&self, domain: &str) -> &ArcIntegratedResolverHandle {
+        let key = g3_types::resolve::reverse_idna_domain(domain);
+        self.child_match_domain
+            .get_ancestor_value(&key)
+            .unwrap_or(&self.default_next)
+    }
+}
+
+pub(super) struct SplitResolver {
+    config: Arc<SplitResolverConfig>,
+    next_table: BTreeMap<MetricsName, ArcIntegratedResolverHandle>,
+    route: Arc<ArcSwap<SplitResolveRoute>>,
+    stats: Arc<ResolverStats>,
+}
+
+impl SplitResolver {
+    pub(super) fn new_obj(config: SplitResolverConfig) -> anyhow::Result<BoxResolver> {
+        let mut next_table = BTreeMap::new();
+        if let Some(set) = config.dependent_resolver() {
+            for name in set {
+                let handle = crate::resolve::get_handle(&name)
+                    .context(format!("failed to get next resolver {name} handle"))?;
+                next_table.insert(name, handle);
+            }
+        }
+        let route = SplitResolveRoute::new(&config, &next_table)?;
+
+        let stats = g3_resolver::ResolverStats::default();
+        let stats = ResolverStats::new(config.name(), Arc::new(stats));
+        Ok(Box::new(SplitResolver {
+            config: Arc::new(config),
+            next_table,
+            route: Arc::new(ArcSwap::from_pointee(route)),
+            stats: Arc::new(stats),
+        }))
+    }
+}
+
+#[async_trait]
+impl ResolverInternal for SplitResolver {
+    fn _dependent_resolver(&self) -> Option<BTreeSet<MetricsName>> {
+        self.config.dependent_resolver()
+    }
+
+    fn _clone_config(&self) -> AnyResolverConfig {
+        AnyResolverConfig::Split(self.config.as_ref().clone())
+    }
+
+    fn _update_config(
+        &mut self,
+        config: AnyResolverConfig,
+        dep_table: BTreeMap<MetricsName, ArcIntegratedResolverHandle>,
+    ) -> anyhow::Result<()> {
+        if let AnyResolverConfig::Split(config) = config {
+            let route = SplitResolveRoute::new(&config, &dep_table)?;
+            self.route.store(Arc::new(route));
+            self.next_table = dep_table;
+            self.config = Arc::new(config);
+            Ok(())
+        } else {
+            Err(anyhow!("invalid config type for SplitResolver"))
+        }
+    }
+
+    fn _update_dependent_handle(
+        &mut self,
+        target: &MetricsName,
+        handle: ArcIntegratedResolverHandle,
+    ) -> anyhow::Result<()> {
+        let Some(next) = self.next_table.get_mut(target) else {
+            return Err(anyhow!(
+                "resolver {} doesn't depend on resolver {}",
+                self.config.name(),
+                target
+            ));
+        };
+        *next = handle;
+
+        let route = SplitResolveRoute::new(&self.config, &self.next_table)?;
+        self.route.store(Arc::new(route));
+        Ok(())
+    }
+
+    async fn _shutdown(&mut self) {}
+}
+
+impl Resolver for SplitResolver {
+    fn get_handle(&self) -> ArcIntegratedResolverHandle {
+        Arc::new(SplitResolverHandle {
+            name: self.config.name().clone(),
+            route: Arc::clone(&self.route),
+        })
+    }
+
+    fn get_stats(&self) -> Arc<ResolverStats> {
+        Arc::clone(&self.stats)
+    }
+}
+
+struct SplitResolverHandle {
+    name: MetricsName,
+    route: Arc<ArcSwap<SplitResolveRoute>>,
+}
+
+impl IntegratedResolverHandle for SplitResolverHandle {
+    fn name(&self) -> &MetricsName {
+        &self.name
+    }
+
+    fn is_closed(&self) -> bool {
+        // the next resolver handles will be updated in place
+        false
+    }
+
+    fn query_v4(&self, domain: String) -> Result<BoxLoggedResolveJob, ResolveError> {
+        let route = self.route.load();
+        route.select(&domain).query_v4(domain)
+    }
+
+    fn query_v6(&self, domain: String) -> Result<BoxLoggedResolveJob, ResolveError> {
+        let route = self.route.load();
+        route.select(&domain).query_v6(domain)
+    }
+
+    fn query_v4_for_client(
+        &self,
+        domain: String,
+        client_ip: IpAddr,
+    ) -> Result<BoxLoggedResolveJob, ResolveError> {
+        let route = self.route.load();
+        route.select(&domain).query_v4_for_client(domain, client_ip)
+    }
+
+    fn query_v6_for_client(
+        &self,
+        domain: String,
+        client_ip: IpAddr,
+    ) -> Result<BoxLoggedResolveJob, ResolveError> {
+        let route = self.route.load();
+        route.select(&domain).query_v6_for_client(domain, client_ip)
+    }
+
+    fn clone_inner(&self) -> Option<g3_resolver::ResolverHandle> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use g3_resolver::ResolveLocalError;
+
+    struct NamedResolverHandle {
+        name: MetricsName,
+    }
+
+    impl IntegratedResolverHandle for NamedResolverHandle {
+        fn name(&self) -> &MetricsName {
+            &self.name
+        }
+
+        fn is_closed(&self) -> bool {
+            false
+        }
+
+        fn query_v4(&self, _domain: String) -> Result<BoxLoggedResolveJob, ResolveError> {
+            Err(ResolveLocalError::NoResolverRunning.into())
+        }
+
+        fn query_v6(&self, _domain: String) -> Result<BoxLoggedResolveJob, ResolveError> {
+            Err(ResolveLocalError::NoResolverRunning.into())
+        }
+
+        fn clone_inner(&self) -> Option<g3_resolver::ResolverHandle> {
+            None
+        }
+    }
+
+    fn build_route(yaml: &str) -> SplitResolveRoute {
+        let docs = yaml_rust::YamlLoader::load_from_str(yaml).unwrap();
+        let map = docs[0].as_hash().unwrap();
+        let config = SplitResolverConfig::parse(map, None).unwrap();
+
+        let mut next_table = BTreeMap::new();
+        for name in config.dependent_resolver().unwrap() {
+            let handle: ArcIntegratedResolverHandle =
+                Arc::new(NamedResolverHandle { name: name.clone() });
+            next_table.insert(name, handle);
+        }
+        SplitResolveRoute::new(&config, &next_table).unwrap()
+    }
+
+    fn selected<'a>(route: &'a SplitResolveRoute, domain: &str) -> &'a str {
+        route.select(domain).name().as_str()
+    }
+
+    const CONFIG: &str = r#"
+        name: split
+        default_next: default
+        rules:
+          - next: example
+            suffix: example.com
+          - next: internal
+            suffixes:
+              - internal.example.com
+              - example.net
+        "#;
+
+    #[test]
+    fn exact_match() {
+        let route = build_route(CONFIG);
+        assert_eq!(selected(&route, "example.com"), "example");
+        assert_eq!(selected(&route, "example.net"), "internal");
+        assert_eq!(selected(&route, "internal.example.com"), "internal");
+    }
+
+    #[test]
+    fn subdomain_match() {
+        let route = build_route(CONFIG);
+        assert_eq!(selected(&route, "www.example.com"), "example");
+        assert_eq!(selected(&route, "a.b.example.com"), "example");
+        assert_eq!(selected(&route, "www.example.net"), "internal");
+    }
+
+    #[test]
+    fn label_boundary() {
+        let route = build_route(CONFIG);
+        assert_eq!(selected(&route, "badexample.com"), "default");
+        assert_eq!(selected(&route, "www.badexample.com"), "default");
+        assert_eq!(selected(&route, "example.com.cn"), "default");
+        assert_eq!(selected(&route, "xinternal.example.com"), "example");
+    }
+
+    #[test]
+    fn longest_suffix() {
+        let route = build_route(CONFIG);
+        assert_eq!(selected(&route, "internal.example.com"), "internal");
+        assert_eq!(selected(&route, "api.internal.example.com"), "internal");
+        assert_eq!(selected(&route, "api.external.example.com"), "example");
+    }
+
+    #[test]
+    fn default_next() {
+        let route = build_route(CONFIG);
+        assert_eq!(selected(&route, "example.org"), "default");
+        assert_eq!(selected(&route, "com"), "default");
+        assert_eq!(selected(&route, "localhost"), "default");
+
+        let route = build_route("{name: split, default_next: default}");
+        assert_eq!(selected(&route, "www.example.com"), "default");
+    }
+}
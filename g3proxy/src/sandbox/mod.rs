@@ -0,0 +1,126 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use log::{info, warn};
+use nix::libc;
+use nix::unistd;
+
+use crate::config::sandbox::SandboxConfig;
+
+mod landlock;
+mod seccomp;
+
+/// system paths that may be read by the libraries we use, such as the resolver and the tls stack
+const SYSTEM_READ_PATHS: &[&str] = &["/etc", "/usr", "/proc", "/sys", "/dev"];
+
+fn set_no_new_privs() -> anyhow::Result<()> {
+    let r = unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) };
+    if r != 0 {
+        return Err(anyhow!(
+            "failed to set no_new_privs: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+fn apply_landlock(config: &SandboxConfig) -> anyhow::Result<()> {
+    let control_dir = g3_daemon::opts::control_dir();
+
+    let mut rules = landlock::PathRules::default();
+    for path in SYSTEM_READ_PATHS {
+        rules.allow_read(Path::new(path));
+    }
+    if let Some(dir) = g3_daemon::opts::config_dir() {
+        rules.allow_read(dir);
+    }
+    for path in &config.read_paths {
+        rules.allow_read(path);
+    }
+    // the control sockets will be created and removed here
+    rules.allow_write(&control_dir);
+    for path in &config.write_paths {
+        rules.allow_write(path);
+    }
+
+    landlock::restrict_self(&rules)
+}
+
+fn drop_privileges(config: &SandboxConfig) -> anyhow::Result<()> {
+    let gid = match (&config.group, &config.user) {
+        (Some(group), _) => group.gid,
+        (None, Some(user)) => user.gid,
+        (None, None) => return Ok(()),
+    };
+    // the supplementary groups and the gid should be changed before the uid
+    unistd::setgroups(&[gid]).map_err(|e| anyhow!("failed to set groups: {e}"))?;
+    unistd::setgid(gid).map_err(|e| anyhow!("failed to set gid to {gid}: {e}"))?;
+    if let Some(user) = &config.user {
+        unistd::setuid(user.uid).map_err(|e| anyhow!("failed to set uid to {}: {e}", user.uid))?;
+    }
+    Ok(())
+}
+
+/// Enforce the landlock rules if configured.
+///
+/// This should be called after the config loaded and the daemon mode entered, and before any
+/// other threads spawned, as the landlock rules will only be inherited by the new threads.
+/// The listening sockets can still be bound after this, as only file paths are restricted.
+pub fn restrict_paths() -> anyhow::Result<()> {
+    let Some(config) = crate::config::sandbox::get_global_config() else {
+        return Ok(());
+    };
+    if !config.landlock {
+        return Ok(());
+    }
+
+    set_no_new_privs()?;
+    match apply_landlock(config) {
+        Ok(_) => info!("sandbox: landlock rules enforced"),
+        // the kernel may not support it, so do not fail here
+        Err(e) => warn!("sandbox: landlock not enforced: {e:?}"),
+    }
+    Ok(())
+}
+
+/// Drop the privileges and install the seccomp filter if configured.
+///
+/// This should be called after all the listening sockets bound, so the privileged ports can
+/// be used. The seccomp filter is synced to all existing threads, including the process log
+/// thread, and will be inherited by the new threads.
+pub fn apply() -> anyhow::Result<()> {
+    let Some(config) = crate::config::sandbox::get_global_config() else {
+        return Ok(());
+    };
+
+    drop_privileges(config).context("sandbox: failed to drop privileges")?;
+    if let Some(user) = &config.user {
+        info!("sandbox: privileges dropped to user {}", user.name);
+    }
+
+    if config.seccomp {
+        set_no_new_privs()?;
+        // the pid won't change after the daemon mode entered
+        seccomp::install(config.seccomp_deny_action, std::process::id())
+            .context("sandbox: seccomp failed")?;
+        info!("sandbox: seccomp filter installed");
+    }
+
+    Ok(())
+}
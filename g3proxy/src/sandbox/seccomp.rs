@@ -0,0 +1,468 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+
+use anyhow::anyhow;
+use nix::libc;
+
+use crate::config::sandbox::SeccompDenyAction;
+
+const SECCOMP_SET_MODE_FILTER: libc::c_ulong = 1;
+const SECCOMP_FILTER_FLAG_TSYNC: libc::c_ulong = 1;
+
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+const SECCOMP_RET_LOG: u32 = 0x7ffc_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+const BPF_LD_W_ABS: u16 = 0x20; // BPF_LD | BPF_W | BPF_ABS
+const BPF_JMP_JEQ_K: u16 = 0x15; // BPF_JMP | BPF_JEQ | BPF_K
+const BPF_RET_K: u16 = 0x06; // BPF_RET | BPF_K
+
+/// offsets in struct seccomp_data
+const SECCOMP_DATA_NR_OFFSET: u32 = 0;
+const SECCOMP_DATA_ARCH_OFFSET: u32 = 4;
+const SECCOMP_DATA_ARGS_OFFSET: u32 = 16;
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH_CURRENT: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH_CURRENT: u32 = 0xc000_00b7;
+
+/// syscalls used by the runtime after startup, including reload and the spawn of blocking threads
+const ALLOWED_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_read,
+    libc::SYS_write,
+    libc::SYS_readv,
+    libc::SYS_writev,
+    libc::SYS_pread64,
+    libc::SYS_pwrite64,
+    libc::SYS_preadv,
+    libc::SYS_pwritev,
+    libc::SYS_openat,
+    libc::SYS_close,
+    libc::SYS_fstat,
+    libc::SYS_newfstatat,
+    libc::SYS_statx,
+    libc::SYS_statfs,
+    libc::SYS_fstatfs,
+    libc::SYS_lseek,
+    libc::SYS_getdents64,
+    libc::SYS_readlinkat,
+    libc::SYS_faccessat,
+    libc::SYS_faccessat2,
+    libc::SYS_mkdirat,
+    libc::SYS_unlinkat,
+    libc::SYS_renameat,
+    libc::SYS_renameat2,
+    libc::SYS_fsync,
+    libc::SYS_fdatasync,
+    libc::SYS_ftruncate,
+    libc::SYS_fallocate,
+    libc::SYS_flock,
+    libc::SYS_fcntl,
+    libc::SYS_dup,
+    libc::SYS_dup3,
+    libc::SYS_pipe2,
+    libc::SYS_splice,
+    libc::SYS_sendfile,
+    libc::SYS_getcwd,
+    libc::SYS_mmap,
+    libc::SYS_munmap,
+    libc::SYS_mprotect,
+    libc::SYS_madvise,
+    libc::SYS_mremap,
+    libc::SYS_msync,
+    libc::SYS_brk,
+    libc::SYS_memfd_create,
+    libc::SYS_rt_sigaction,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_rt_sigtimedwait,
+    libc::SYS_restart_syscall,
+    libc::SYS_sigaltstack,
+    libc::SYS_futex,
+    libc::SYS_set_robust_list,
+    libc::SYS_get_robust_list,
+    libc::SYS_rseq,
+    libc::SYS_set_tid_address,
+    libc::SYS_clone,
+    libc::SYS_clone3,
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+    libc::SYS_wait4,
+    libc::SYS_sched_yield,
+    libc::SYS_sched_getaffinity,
+    libc::SYS_sched_setaffinity,
+    libc::SYS_getpid,
+    libc::SYS_getppid,
+    libc::SYS_gettid,
+    libc::SYS_getuid,
+    libc::SYS_geteuid,
+    libc::SYS_getgid,
+    libc::SYS_getegid,
+    libc::SYS_getresuid,
+    libc::SYS_getresgid,
+    libc::SYS_getrlimit,
+    libc::SYS_prlimit64,
+    libc::SYS_getrusage,
+    libc::SYS_sysinfo,
+    libc::SYS_uname,
+    libc::SYS_getrandom,
+    libc::SYS_clock_gettime,
+    libc::SYS_clock_getres,
+    libc::SYS_clock_nanosleep,
+    libc::SYS_nanosleep,
+    libc::SYS_gettimeofday,
+    libc::SYS_epoll_create1,
+    libc::SYS_epoll_ctl,
+    libc::SYS_epoll_pwait,
+    libc::SYS_eventfd2,
+    libc::SYS_timerfd_create,
+    libc::SYS_timerfd_settime,
+    libc::SYS_timerfd_gettime,
+    libc::SYS_inotify_init1,
+    libc::SYS_inotify_add_watch,
+    libc::SYS_inotify_rm_watch,
+    libc::SYS_connect,
+    libc::SYS_accept,
+    libc::SYS_accept4,
+    libc::SYS_bind,
+    libc::SYS_listen,
+    libc::SYS_getsockname,
+    libc::SYS_getpeername,
+    libc::SYS_setsockopt,
+    libc::SYS_getsockopt,
+    libc::SYS_sendto,
+    libc::SYS_recvfrom,
+    libc::SYS_sendmsg,
+    libc::SYS_recvmsg,
+    libc::SYS_sendmmsg,
+    libc::SYS_recvmmsg,
+    libc::SYS_shutdown,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_open,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_stat,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_lstat,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_access,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_readlink,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_mkdir,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_unlink,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_rename,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_getdents,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_pipe,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_dup2,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_poll,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_epoll_wait,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_arch_prctl,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_time,
+    #[cfg(target_arch = "aarch64")]
+    libc::SYS_ppoll,
+];
+
+/// the socket domains used by the listeners, the escapers and the resolvers
+const ALLOWED_SOCKET_DOMAINS: &[u32] = &[
+    libc::AF_UNIX as u32,
+    libc::AF_INET as u32,
+    libc::AF_INET6 as u32,
+    libc::AF_NETLINK as u32,
+];
+
+/// the ioctl requests used by std and the log backends
+const ALLOWED_IOCTL_REQUESTS: &[u32] = &[
+    libc::FIONBIO as u32,
+    libc::FIOCLEX as u32,
+    libc::FIONCLEX as u32,
+    libc::FIONREAD as u32,
+    libc::TCGETS as u32,
+    libc::TIOCGWINSZ as u32,
+];
+
+/// the prctl options used to name the threads
+const ALLOWED_PRCTL_OPTIONS: &[u32] = &[libc::PR_SET_NAME as u32, libc::PR_GET_NAME as u32];
+
+/// syscalls that are only allowed if the arg at the index is one of the values
+type ArgRule<'a> = (libc::c_long, u32, &'a [u32]);
+
+fn arg_rules(pid: &[u32]) -> [ArgRule<'_>; 6] {
+    [
+        (libc::SYS_socket, 0, ALLOWED_SOCKET_DOMAINS),
+        (libc::SYS_socketpair, 0, &[libc::AF_UNIX as u32]),
+        (libc::SYS_ioctl, 1, ALLOWED_IOCTL_REQUESTS),
+        (libc::SYS_prctl, 0, ALLOWED_PRCTL_OPTIONS),
+        // signals can only be sent to this process
+        (libc::SYS_kill, 0, pid),
+        (libc::SYS_tgkill, 0, pid),
+    ]
+}
+
+/// offset of the low 32 bits of the arg, both x86_64 and aarch64 are little endian
+fn arg_offset(index: u32) -> u32 {
+    SECCOMP_DATA_ARGS_OFFSET + index * 8
+}
+
+fn bpf_stmt(code: u16, k: u32) -> libc::sock_filter {
+    libc::sock_filter {
+        code,
+        jt: 0,
+        jf: 0,
+        k,
+    }
+}
+
+fn bpf_jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+    libc::sock_filter { code, jt, jf, k }
+}
+
+fn build_filter(deny_action: SeccompDenyAction, pid: u32) -> Vec<libc::sock_filter> {
+    let deny = match deny_action {
+        SeccompDenyAction::Errno => SECCOMP_RET_ERRNO | (libc::EPERM as u32),
+        SeccompDenyAction::Kill => SECCOMP_RET_KILL_PROCESS,
+        SeccompDenyAction::Log => SECCOMP_RET_LOG,
+    };
+
+    let mut filter = Vec::with_capacity(ALLOWED_SYSCALLS.len() * 2 + 5);
+    // kill the process if the arch is not the one we are built for
+    filter.push(bpf_stmt(BPF_LD_W_ABS, SECCOMP_DATA_ARCH_OFFSET));
+    filter.push(bpf_jump(BPF_JMP_JEQ_K, AUDIT_ARCH_CURRENT, 1, 0));
+    filter.push(bpf_stmt(BPF_RET_K, SECCOMP_RET_KILL_PROCESS));
+
+    filter.push(bpf_stmt(BPF_LD_W_ABS, SECCOMP_DATA_NR_OFFSET));
+    for nr in ALLOWED_SYSCALLS {
+        filter.push(bpf_jump(BPF_JMP_JEQ_K, *nr as u32, 0, 1));
+        filter.push(bpf_stmt(BPF_RET_K, SECCOMP_RET_ALLOW));
+    }
+
+    let pid = [pid];
+    for (nr, index, values) in arg_rules(&pid) {
+        // skip the whole block if not matched, the accumulator will still be the syscall nr
+        let block_len = values.len() * 2 + 5;
+        filter.push(bpf_jump(BPF_JMP_JEQ_K, nr as u32, 0, block_len as u8));
+        // the high 32 bits should be zero
+        filter.push(bpf_stmt(BPF_LD_W_ABS, arg_offset(index) + 4));
+        filter.push(bpf_jump(BPF_JMP_JEQ_K, 0, 1, 0));
+        filter.push(bpf_stmt(BPF_RET_K, deny));
+        filter.push(bpf_stmt(BPF_LD_W_ABS, arg_offset(index)));
+        for v in values {
+            filter.push(bpf_jump(BPF_JMP_JEQ_K, *v, 0, 1));
+            filter.push(bpf_stmt(BPF_RET_K, SECCOMP_RET_ALLOW));
+        }
+        filter.push(bpf_stmt(BPF_RET_K, deny));
+    }
+
+    filter.push(bpf_stmt(BPF_RET_K, deny));
+    filter
+}
+
+/// install the seccomp filter for all threads of this process
+pub(super) fn install(deny_action: SeccompDenyAction, pid: u32) -> anyhow::Result<()> {
+    let mut filter = build_filter(deny_action, pid);
+    let prog = libc::sock_fprog {
+        len: filter.len() as u16,
+        filter: filter.as_mut_ptr(),
+    };
+
+    let r = unsafe {
+        libc::syscall(
+            libc::SYS_seccomp,
+            SECCOMP_SET_MODE_FILTER,
+            SECCOMP_FILTER_FLAG_TSYNC,
+            &prog as *const libc::sock_fprog,
+        )
+    };
+    if r != 0 {
+        return Err(anyhow!(
+            "failed to install seccomp filter: {}",
+            io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BPF_JMP_JA: u16 = 0x05; // BPF_JMP | BPF_JA
+
+    const PID: u32 = 1234;
+
+    /// run the filter the way the kernel does, and return the action
+    fn run_filter(filter: &[libc::sock_filter], arch: u32, nr: u32, args: &[u64]) -> u32 {
+        let mut acc = 0u32;
+        let mut pc = 0usize;
+        loop {
+            let insn = &filter[pc];
+            pc += 1;
+            match insn.code {
+                BPF_LD_W_ABS => {
+                    acc = match insn.k {
+                        SECCOMP_DATA_NR_OFFSET => nr,
+                        SECCOMP_DATA_ARCH_OFFSET => arch,
+                        k if k >= SECCOMP_DATA_ARGS_OFFSET && k < SECCOMP_DATA_ARGS_OFFSET + 48 => {
+                            let offset = k - SECCOMP_DATA_ARGS_OFFSET;
+                            let arg = args.get((offset / 8) as usize).copied().unwrap_or(0);
+                            if offset % 8 == 0 {
+                                arg as u32
+                            } else {
+                                (arg >> 32) as u32
+                            }
+                        }
+                        k => panic!("unexpected load offset {k}"),
+                    };
+                }
+                BPF_JMP_JEQ_K => {
+                    let offset = if acc == insn.k { insn.jt } else { insn.jf };
+                    pc += offset as usize;
+                }
+                BPF_JMP_JA => pc += insn.k as usize,
+                BPF_RET_K => return insn.k,
+                code => panic!("unexpected instruction code {code:#x}"),
+            }
+            assert!(pc < filter.len(), "jump out of the filter");
+        }
+    }
+
+    fn run_syscall(filter: &[libc::sock_filter], nr: libc::c_long, args: &[u64]) -> u32 {
+        run_filter(filter, AUDIT_ARCH_CURRENT, nr as u32, args)
+    }
+
+    #[test]
+    fn filter_jumps() {
+        let deny = SECCOMP_RET_ERRNO | (libc::EPERM as u32);
+        let filter = build_filter(SeccompDenyAction::Errno, PID);
+        assert!(filter.len() <= u16::MAX as usize);
+
+        for nr in ALLOWED_SYSCALLS {
+            assert_eq!(
+                run_syscall(&filter, *nr, &[]),
+                SECCOMP_RET_ALLOW,
+                "syscall {nr} should be allowed"
+            );
+        }
+        assert_eq!(run_syscall(&filter, libc::SYS_execve, &[]), deny);
+        assert_eq!(run_syscall(&filter, libc::SYS_ptrace, &[]), deny);
+        assert_eq!(
+            run_filter(&filter, 0x4000_0003, libc::SYS_read as u32, &[]),
+            SECCOMP_RET_KILL_PROCESS
+        );
+
+        let filter = build_filter(SeccompDenyAction::Kill, PID);
+        assert_eq!(
+            run_syscall(&filter, libc::SYS_execve, &[]),
+            SECCOMP_RET_KILL_PROCESS
+        );
+        let filter = build_filter(SeccompDenyAction::Log, PID);
+        assert_eq!(run_syscall(&filter, libc::SYS_execve, &[]), SECCOMP_RET_LOG);
+    }
+
+    #[test]
+    fn filter_args() {
+        let deny = SECCOMP_RET_ERRNO | (libc::EPERM as u32);
+        let filter = build_filter(SeccompDenyAction::Errno, PID);
+
+        let pid = [PID];
+        for (nr, index, values) in arg_rules(&pid) {
+            for v in values {
+                let mut args = [0u64; 6];
+                args[index as usize] = *v as u64;
+                assert_eq!(
+                    run_syscall(&filter, nr, &args),
+                    SECCOMP_RET_ALLOW,
+                    "syscall {nr} with arg {v} should be allowed"
+                );
+                // the high 32 bits should not be ignored
+                args[index as usize] |= 1 << 32;
+                assert_eq!(run_syscall(&filter, nr, &args), deny);
+            }
+        }
+
+        let socket = |domain: libc::c_int| {
+            run_syscall(
+                &filter,
+                libc::SYS_socket,
+                &[domain as u64, libc::SOCK_STREAM as u64],
+            )
+        };
+        assert_eq!(socket(libc::AF_INET6), SECCOMP_RET_ALLOW);
+        assert_eq!(socket(libc::AF_PACKET), deny);
+        assert_eq!(socket(libc::AF_VSOCK), deny);
+        assert_eq!(
+            run_syscall(&filter, libc::SYS_socketpair, &[libc::AF_INET as u64]),
+            deny
+        );
+
+        let ioctl = |request: u64| run_syscall(&filter, libc::SYS_ioctl, &[3, request]);
+        assert_eq!(ioctl(libc::FIONBIO as u64), SECCOMP_RET_ALLOW);
+        assert_eq!(ioctl(libc::TIOCSTI as u64), deny);
+        assert_eq!(ioctl(libc::SIOCSIFFLAGS as u64), deny);
+
+        let prctl = |option: libc::c_int| run_syscall(&filter, libc::SYS_prctl, &[option as u64]);
+        assert_eq!(prctl(libc::PR_SET_NAME), SECCOMP_RET_ALLOW);
+        assert_eq!(prctl(libc::PR_SET_DUMPABLE), deny);
+        assert_eq!(prctl(libc::PR_SET_SECCOMP), deny);
+
+        let kill =
+            |pid: u32| run_syscall(&filter, libc::SYS_kill, &[pid as u64, libc::SIGTERM as u64]);
+        assert_eq!(kill(PID), SECCOMP_RET_ALLOW);
+        assert_eq!(kill(PID + 1), deny);
+        assert_eq!(kill(1), deny);
+        assert_eq!(
+            run_syscall(&filter, libc::SYS_tgkill, &[PID as u64 + 1, 7, 9]),
+            deny
+        );
+    }
+
+    #[test]
+    fn allowed_syscalls() {
+        for nr in [
+            libc::SYS_restart_syscall,
+            libc::SYS_faccessat2,
+            libc::SYS_futex,
+            libc::SYS_epoll_pwait,
+        ] {
+            assert!(ALLOWED_SYSCALLS.contains(&nr), "syscall {nr} not allowed");
+        }
+        assert!(!ALLOWED_SYSCALLS.contains(&libc::SYS_execve));
+        // these should only be allowed with restricted args
+        for (nr, ..) in arg_rules(&[PID]) {
+            assert!(
+                !ALLOWED_SYSCALLS.contains(&nr),
+                "syscall {nr} not restricted"
+            );
+        }
+
+        let mut sorted = ALLOWED_SYSCALLS.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted.len(), ALLOWED_SYSCALLS.len(), "duplicated syscalls");
+    }
+}
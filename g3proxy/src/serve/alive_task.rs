@@ -0,0 +1,365 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use ahash::RandomState;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use g3_daemon::stat::task::TcpStreamTaskStats;
+use g3_types::metrics::MetricsName;
+use g3_types::net::UpstreamAddr;
+
+use super::{ServerTaskError, ServerTaskResult};
+
+const REGISTRY_SHARD_COUNT: usize = 64;
+
+static ALIVE_TASK_REGISTRY: Lazy<AliveTaskRegistry> = Lazy::new(AliveTaskRegistry::default);
+
+pub(crate) trait AliveTaskIoStats {
+    fn client_read_bytes(&self) -> u64;
+    fn client_write_bytes(&self) -> u64;
+}
+
+pub(crate) type ArcAliveTaskIoStats = Arc<dyn AliveTaskIoStats + Send + Sync>;
+
+impl AliveTaskIoStats for TcpStreamTaskStats {
+    fn client_read_bytes(&self) -> u64 {
+        self.clt.read.get_bytes()
+    }
+
+    fn client_write_bytes(&self) -> u64 {
+        self.clt.write.get_bytes()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct AliveTaskFilter {
+    pub(crate) server: Option<MetricsName>,
+    pub(crate) user_group: Option<MetricsName>,
+    pub(crate) user: Option<String>,
+}
+
+impl AliveTaskFilter {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.server.is_none() && self.user_group.is_none() && self.user.is_none()
+    }
+
+    fn check(&self, task: &AliveTaskHandle) -> bool {
+        if let Some(server) = &self.server {
+            if task.server.ne(server) {
+                return false;
+            }
+        }
+        if let Some(user_group) = &self.user_group {
+            if task.user_group.as_ref() != Some(user_group) {
+                return false;
+            }
+        }
+        if let Some(user) = &self.user {
+            if task.user.as_ref().map(|u| u.ne(user)).unwrap_or(true) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub(crate) struct AliveTaskHandle {
+    pub(crate) id: Uuid,
+    pub(crate) server: MetricsName,
+    pub(crate) user_group: Option<MetricsName>,
+    pub(crate) user: Option<String>,
+    pub(crate) upstream: UpstreamAddr,
+    pub(crate) start_at: DateTime<Utc>,
+    create_ins: Instant,
+    client_addr: SocketAddr,
+    io_stats: OnceLock<ArcAliveTaskIoStats>,
+    kill_token: CancellationToken,
+}
+
+impl AliveTaskHandle {
+    #[inline]
+    pub(crate) fn client_addr(&self) -> SocketAddr {
+        self.client_addr
+    }
+
+    #[inline]
+    pub(crate) fn time_elapsed(&self) -> Duration {
+        self.create_ins.elapsed()
+    }
+
+    pub(crate) fn io_stats(&self) -> Option<&ArcAliveTaskIoStats> {
+        self.io_stats.get()
+    }
+
+    pub(crate) fn set_io_stats(&self, stats: ArcAliveTaskIoStats) {
+        let _ = self.io_stats.set(stats);
+    }
+
+    pub(crate) fn kill_token(&self) -> CancellationToken {
+        self.kill_token.clone()
+    }
+
+    /// signal the task to quit, the task itself will close its connections
+    fn kill(&self) {
+        self.kill_token.cancel();
+    }
+}
+
+/// Run the task future until it finishes or get killed by ctl commands.
+///
+/// The future will be dropped if the task is killed, so all sockets owned by it will be closed.
+pub(crate) async fn run_killable<F, T>(kill_token: CancellationToken, fut: F) -> ServerTaskResult<T>
+where
+    F: Future<Output = ServerTaskResult<T>>,
+{
+    tokio::select! {
+        biased;
+
+        _ = kill_token.cancelled() => Err(ServerTaskError::CanceledAsKilled),
+        r = fut => r,
+    }
+}
+
+struct AliveTaskRegistry {
+    hash_builder: RandomState,
+    shards: [Mutex<HashMap<Uuid, Arc<AliveTaskHandle>>>; REGISTRY_SHARD_COUNT],
+}
+
+impl Default for AliveTaskRegistry {
+    fn default() -> Self {
+        AliveTaskRegistry {
+            hash_builder: RandomState::new(),
+            shards: std::array::from_fn(|_| Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl AliveTaskRegistry {
+    fn shard(&self, id: &Uuid) -> &Mutex<HashMap<Uuid, Arc<AliveTaskHandle>>> {
+        let i = self.hash_builder.hash_one(id) as usize % REGISTRY_SHARD_COUNT;
+        &self.shards[i]
+    }
+
+    fn insert(&self, handle: Arc<AliveTaskHandle>) {
+        let mut ht = self.shard(&handle.id).lock().unwrap();
+        ht.insert(handle.id, handle);
+    }
+
+    fn remove(&self, id: &Uuid) {
+        let mut ht = self.shard(id).lock().unwrap();
+        ht.remove(id);
+    }
+
+    fn foreach<F>(&self, mut f: F)
+    where
+        F: FnMut(&Arc<AliveTaskHandle>),
+    {
+        for shard in &self.shards {
+            let ht = shard.lock().unwrap();
+            ht.values().for_each(&mut f);
+        }
+    }
+
+    fn list(&self, filter: &AliveTaskFilter) -> Vec<Arc<AliveTaskHandle>> {
+        let mut tasks = Vec::new();
+        self.foreach(|t| {
+            if filter.check(t) {
+                tasks.push(t.clone());
+            }
+        });
+        tasks.sort_by_key(|t| t.create_ins);
+        tasks
+    }
+
+    fn kill(&self, id: &Uuid) -> io::Result<()> {
+        let ht = self.shard(id).lock().unwrap();
+        let Some(task) = ht.get(id) else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no such task"));
+        };
+        task.kill();
+        Ok(())
+    }
+
+    fn kill_matched(&self, filter: &AliveTaskFilter) -> usize {
+        let mut count = 0;
+        self.foreach(|t| {
+            if filter.check(t) {
+                t.kill();
+                count += 1;
+            }
+        });
+        count
+    }
+}
+
+pub(super) fn register(
+    id: Uuid,
+    server: &MetricsName,
+    user: Option<(&MetricsName, &str)>,
+    upstream: &UpstreamAddr,
+    start_at: DateTime<Utc>,
+    create_ins: Instant,
+    client_addr: SocketAddr,
+) -> Arc<AliveTaskHandle> {
+    let handle = Arc::new(AliveTaskHandle {
+        id,
+        server: server.clone(),
+        user_group: user.map(|(group, _)| group.clone()),
+        user: user.map(|(_, name)| name.to_string()),
+        upstream: upstream.clone(),
+        start_at,
+        create_ins,
+        client_addr,
+        io_stats: OnceLock::new(),
+        kill_token: CancellationToken::new(),
+    });
+    ALIVE_TASK_REGISTRY.insert(handle.clone());
+    handle
+}
+
+pub(super) fn unregister(id: &Uuid) {
+    ALIVE_TASK_REGISTRY.remove(id);
+}
+
+pub(crate) fn list(filter: &AliveTaskFilter) -> Vec<Arc<AliveTaskHandle>> {
+    ALIVE_TASK_REGISTRY.list(filter)
+}
+
+pub(crate) fn kill(id: &Uuid) -> io::Result<()> {
+    ALIVE_TASK_REGISTRY.kill(id)
+}
+
+/// kill all matched tasks, and return the count of killed ones
+pub(crate) fn kill_matched(filter: &AliveTaskFilter) -> usize {
+    ALIVE_TASK_REGISTRY.kill_matched(filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn new_handle(server: &str, user: Option<(&str, &str)>) -> Arc<AliveTaskHandle> {
+        let server = MetricsName::from_str(server).unwrap();
+        let group = user.map(|(g, _)| MetricsName::from_str(g).unwrap());
+        Arc::new(AliveTaskHandle {
+            id: Uuid::new_v4(),
+            server,
+            user_group: group,
+            user: user.map(|(_, u)| u.to_string()),
+            upstream: UpstreamAddr::from_str("www.example.com:443").unwrap(),
+            start_at: Utc::now(),
+            create_ins: Instant::now(),
+            client_addr: SocketAddr::from_str("127.0.0.1:10000").unwrap(),
+            io_stats: OnceLock::new(),
+            kill_token: CancellationToken::new(),
+        })
+    }
+
+    #[test]
+    fn register_and_list() {
+        let registry = AliveTaskRegistry::default();
+        registry.insert(new_handle("s1", Some(("g1", "u1"))));
+        registry.insert(new_handle("s1", None));
+        let t3 = new_handle("s2", Some(("g1", "u2")));
+        registry.insert(t3.clone());
+
+        let all = registry.list(&AliveTaskFilter::default());
+        assert_eq!(all.len(), 3);
+
+        let filter = AliveTaskFilter {
+            server: Some(MetricsName::from_str("s1").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(registry.list(&filter).len(), 2);
+
+        let filter = AliveTaskFilter {
+            user_group: Some(MetricsName::from_str("g1").unwrap()),
+            user: Some("u2".to_string()),
+            ..Default::default()
+        };
+        let matched = registry.list(&filter);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].id, t3.id);
+
+        registry.remove(&t3.id);
+        assert!(registry.list(&filter).is_empty());
+        assert_eq!(registry.list(&AliveTaskFilter::default()).len(), 2);
+    }
+
+    #[test]
+    fn kill_by_id() {
+        let registry = AliveTaskRegistry::default();
+        let t1 = new_handle("s1", None);
+        let t2 = new_handle("s1", None);
+        registry.insert(t1.clone());
+        registry.insert(t2.clone());
+
+        registry.kill(&t1.id).unwrap();
+        assert!(t1.kill_token.is_cancelled());
+        assert!(!t2.kill_token.is_cancelled());
+
+        let e = registry.kill(&Uuid::new_v4()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn kill_by_filter() {
+        let registry = AliveTaskRegistry::default();
+        let t1 = new_handle("s1", Some(("g1", "u1")));
+        let t2 = new_handle("s1", Some(("g1", "u2")));
+        let t3 = new_handle("s2", Some(("g1", "u1")));
+        registry.insert(t1.clone());
+        registry.insert(t2.clone());
+        registry.insert(t3.clone());
+
+        let filter = AliveTaskFilter {
+            user: Some("u1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(registry.kill_matched(&filter), 2);
+        assert!(t1.kill_token.is_cancelled());
+        assert!(!t2.kill_token.is_cancelled());
+        assert!(t3.kill_token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn killable_future() {
+        let token = CancellationToken::new();
+        let r = run_killable(token.clone(), async { Ok(1) }).await;
+        assert_eq!(r.unwrap(), 1);
+
+        let t = token.clone();
+        let r: ServerTaskResult<()> = run_killable(token, async move {
+            t.cancel();
+            std::future::pending().await
+        })
+        .await;
+        assert!(matches!(r, Err(ServerTaskError::CanceledAsKilled)));
+    }
+}
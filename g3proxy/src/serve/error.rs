@@ -59,6 +59,20 @@ pub(crate) enum ServerTaskForbiddenError {
     UaBlocked,
     #[error("user blocked")]
     UserBlocked,
+    #[error("out of allowed time window")]
+    OutOfTimeWindow,
+    #[error("traffic quota exceeded")]
+    QuotaExceeded,
+    #[error("too many alive connections")]
+    ConnectionLimited,
+    #[error("content blocked")]
+    ContentBlocked,
+    #[error("body too large")]
+    BodyTooLarge,
+    #[error("tls fingerprint blocked")]
+    TlsFingerprintBlocked,
+    #[error("protocol blocked")]
+    ProtocolBlocked,
 }
 
 #[derive(Error, Debug)]
@@ -125,6 +139,8 @@ pub(crate) enum ServerTaskError {
     CanceledAsUserBlocked,
     #[error("canceled as server quit")]
     CanceledAsServerQuit,
+    #[error("canceled as killed by admin")]
+    CanceledAsKilled,
     #[error("idle after {0:?} x {1}")]
     Idle(Duration, i32),
     #[error("{0} interception error: {1}")]
@@ -169,6 +185,7 @@ impl ServerTaskError {
             ServerTaskError::ClosedEarlyByClient => "ClosedEarlyByClient",
             ServerTaskError::CanceledAsUserBlocked => "CanceledAsUserBlocked",
             ServerTaskError::CanceledAsServerQuit => "CanceledAsServerQuit",
+            ServerTaskError::CanceledAsKilled => "CanceledAsKilled",
             ServerTaskError::Idle(_, _) => "Idle",
             ServerTaskError::InterceptionError(_, _) => "InterceptionError",
             ServerTaskError::Finished => "Finished",
@@ -181,7 +198,9 @@ pub(crate) type ServerTaskResult<T> = Result<T, ServerTaskError>;
 
 impl From<ResolveError> for ServerTaskError {
     fn from(e: ResolveError) -> Self {
-        if matches!(e, ResolveError::FromServer(_)) {
+        if e.is_denied() {
+            ServerTaskError::ForbiddenByRule(ServerTaskForbiddenError::DestDenied)
+        } else if matches!(e, ResolveError::FromServer(_)) {
             ServerTaskError::UpstreamNotResolved(e)
         } else {
             ServerTaskError::InternalResolverError(e)
@@ -22,6 +22,7 @@ use anyhow::{anyhow, Context};
 use arc_swap::{ArcSwap, ArcSwapOption};
 use async_trait::async_trait;
 use log::debug;
+use openssl::x509::X509;
 #[cfg(feature = "quic")]
 use quinn::Connection;
 use slog::Logger;
@@ -110,6 +111,9 @@ impl HttpProxyServer {
 
         // always update extra metrics tags
         server_stats.set_extra_tags(config.extra_metrics_tags.clone());
+        server_stats
+            .duration
+            .set_config(config.duration_stats.as_ref());
 
         let escaper = Arc::new(crate::escape::get_or_insert_default(config.escaper()));
         let user_group = config.get_user_group();
@@ -194,8 +198,12 @@ impl HttpProxyServer {
         false
     }
 
-    async fn spawn_stream_task<T>(&self, stream: T, cc_info: ClientConnectionInfo)
-    where
+    async fn spawn_stream_task<T>(
+        &self,
+        stream: T,
+        cc_info: ClientConnectionInfo,
+        client_cert: Option<X509>,
+    ) where
         T: AsyncRead + AsyncWrite + Send + Sync + 'static,
     {
         let ctx = self.get_common_task_context(cc_info);
@@ -204,15 +212,25 @@ impl HttpProxyServer {
 
         // NOTE tls underlying traffic is not counted in (server/task/user) stats
 
+        let user_group = self.user_group.load_full();
+        let cert_user = client_cert.and_then(|cert| {
+            user_group
+                .as_ref()
+                .and_then(|group| group.get_user_by_client_cert(&cert))
+        });
+
         let (clt_r, clt_w) = tokio::io::split(stream);
         let r_task = HttpProxyPipelineReaderTask::new(&ctx, task_sender, clt_r, &pipeline_stats);
-        let w_task = HttpProxyPipelineWriterTask::new(
+        let mut w_task = HttpProxyPipelineWriterTask::new(
             &ctx,
-            self.user_group.load_full(),
+            user_group,
             task_receiver,
             clt_w,
             &pipeline_stats,
         );
+        if let Some(user) = cert_user {
+            w_task.set_client_cert_user(user);
+        }
 
         tokio::spawn(r_task.into_running());
         w_task.into_running().await
@@ -358,7 +376,11 @@ impl AcceptTcpServer for HttpProxyServer {
 
         if let Some(tls_acceptor) = &self.tls_acceptor {
             match tokio::time::timeout(self.tls_accept_timeout, tls_acceptor.accept(stream)).await {
-                Ok(Ok(tls_stream)) => self.spawn_stream_task(tls_stream, cc_info).await,
+                Ok(Ok(tls_stream)) => {
+                    let client_cert = rustls_client_cert(&tls_stream);
+                    self.spawn_stream_task(tls_stream, cc_info, client_cert)
+                        .await
+                }
                 Ok(Err(e)) => {
                     self.listen_stats.add_failed();
                     debug!(
@@ -451,7 +473,8 @@ impl Server for HttpProxyServer {
             return;
         }
 
-        self.spawn_stream_task(stream, cc_info).await;
+        let client_cert = rustls_client_cert(&stream);
+        self.spawn_stream_task(stream, cc_info, client_cert).await;
     }
 
     async fn run_openssl_task(&self, stream: SslStream<TcpStream>, cc_info: ClientConnectionInfo) {
@@ -461,6 +484,13 @@ impl Server for HttpProxyServer {
             return;
         }
 
-        self.spawn_stream_task(stream, cc_info).await;
+        let client_cert = stream.ssl().peer_certificate();
+        self.spawn_stream_task(stream, cc_info, client_cert).await;
     }
 }
+
+fn rustls_client_cert(stream: &TlsStream<TcpStream>) -> Option<X509> {
+    let (_, conn) = stream.get_ref();
+    let cert = conn.peer_certificates()?.first()?;
+    X509::from_der(cert.as_ref()).ok()
+}
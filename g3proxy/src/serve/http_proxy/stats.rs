@@ -24,7 +24,8 @@ use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::stats::{StatId, TcpIoSnapshot, TcpIoStats};
 
 use crate::serve::{
-    ServerForbiddenSnapshot, ServerForbiddenStats, ServerPerTaskStats, ServerStats,
+    ServerDurationStats, ServerForbiddenSnapshot, ServerForbiddenStats, ServerPerTaskStats,
+    ServerStats, ServerTaskDurationStats,
 };
 use crate::stat::types::UntrustedTaskStatsSnapshot;
 
@@ -38,6 +39,7 @@ pub(crate) struct HttpProxyServerStats {
     conn_total: AtomicU64,
 
     pub forbidden: ServerForbiddenStats,
+    pub duration: ServerDurationStats,
 
     pub task_http_untrusted: ServerPerTaskStats,
     pub task_http_connect: ServerPerTaskStats,
@@ -58,6 +60,7 @@ impl HttpProxyServerStats {
             online: AtomicIsize::new(0),
             conn_total: AtomicU64::new(0),
             forbidden: Default::default(),
+            duration: Default::default(),
             task_http_untrusted: Default::default(),
             task_http_connect: Default::default(),
             task_http_forward: Default::default(),
@@ -146,4 +149,8 @@ impl ServerStats for HttpProxyServerStats {
             in_bytes: self.io_untrusted.get_in_bytes(),
         })
     }
+
+    fn duration_stats(&self) -> Option<Arc<ServerTaskDurationStats>> {
+        self.duration.stats()
+    }
 }
@@ -48,6 +48,22 @@ pub(crate) struct CommonTaskContext {
 }
 
 impl CommonTaskContext {
+    pub(crate) fn task_escaper(&self, task_notes: &ServerTaskNotes) -> ArcEscaper {
+        task_notes
+            .user_escaper()
+            .unwrap_or_else(|| Arc::clone(&self.escaper))
+    }
+
+    pub(crate) fn task_audit_handle(
+        &self,
+        task_notes: &ServerTaskNotes,
+    ) -> Option<Arc<AuditHandle>> {
+        match task_notes.user_ctx() {
+            Some(user_ctx) => user_ctx.audit_handle(self.audit_handle.as_ref()),
+            None => self.audit_handle.clone(),
+        }
+    }
+
     #[inline]
     pub(crate) fn client_addr(&self) -> SocketAddr {
         self.cc_info.client_addr()
@@ -23,10 +23,12 @@ use g3_io_ext::{
 
 use super::HttpProxyServerStats;
 use crate::auth::UserTrafficStats;
+use crate::stat::types::{TrafficProtocolCell, TrafficProtocolHint};
 
 trait TcpConnectTaskCltStatsWrapper {
     fn add_read_bytes(&self, size: u64);
     fn add_write_bytes(&self, size: u64);
+    fn add_protocol_bytes(&self, in_bytes: u64, out_bytes: u64, protocol: TrafficProtocolHint);
 }
 
 type ArcTcpConnectTaskCltStatsWrapper = Arc<dyn TcpConnectTaskCltStatsWrapper + Send + Sync>;
@@ -39,20 +41,35 @@ impl TcpConnectTaskCltStatsWrapper for UserTrafficStats {
     fn add_write_bytes(&self, size: u64) {
         self.io.http_connect.add_out_bytes(size);
     }
+
+    fn add_protocol_bytes(&self, in_bytes: u64, out_bytes: u64, protocol: TrafficProtocolHint) {
+        let stats = self.io.protocol.get(protocol);
+        if in_bytes > 0 {
+            stats.add_in_bytes(in_bytes);
+        }
+        if out_bytes > 0 {
+            stats.add_out_bytes(out_bytes);
+        }
+    }
 }
 
-#[derive(Clone)]
 pub(crate) struct TcpConnectTaskCltWrapperStats {
     server: Arc<HttpProxyServerStats>,
     task: Arc<TcpStreamTaskStats>,
+    protocol: Arc<TrafficProtocolCell>,
     others: Vec<ArcTcpConnectTaskCltStatsWrapper>,
 }
 
 impl TcpConnectTaskCltWrapperStats {
-    pub(crate) fn new(server: &Arc<HttpProxyServerStats>, task: &Arc<TcpStreamTaskStats>) -> Self {
+    pub(crate) fn new(
+        server: &Arc<HttpProxyServerStats>,
+        task: &Arc<TcpStreamTaskStats>,
+        protocol: &Arc<TrafficProtocolCell>,
+    ) -> Self {
         TcpConnectTaskCltWrapperStats {
             server: Arc::clone(server),
             task: Arc::clone(task),
+            protocol: Arc::clone(protocol),
             others: Vec::with_capacity(2),
         }
     }
@@ -67,6 +84,36 @@ impl TcpConnectTaskCltWrapperStats {
         let s = Arc::new(self);
         (Arc::clone(&s) as _, s as _)
     }
+
+    fn add_protocol_bytes(&self, in_bytes: u64, out_bytes: u64) {
+        if self.others.is_empty() {
+            return;
+        }
+        let Some(protocol) = self.protocol.get() else {
+            // wait until the protocol inspection is done
+            self.protocol.add_pending_in_bytes(in_bytes);
+            self.protocol.add_pending_out_bytes(out_bytes);
+            return;
+        };
+        let (pending_in, pending_out) = self.protocol.take_pending();
+        let in_bytes = in_bytes.wrapping_add(pending_in);
+        let out_bytes = out_bytes.wrapping_add(pending_out);
+        self.others
+            .iter()
+            .for_each(|s| s.add_protocol_bytes(in_bytes, out_bytes, protocol));
+    }
+}
+
+impl Drop for TcpConnectTaskCltWrapperStats {
+    fn drop(&mut self) {
+        let (in_bytes, out_bytes) = self.protocol.take_pending();
+        if in_bytes > 0 || out_bytes > 0 {
+            let protocol = self.protocol.get().unwrap_or(TrafficProtocolHint::Other);
+            self.others
+                .iter()
+                .for_each(|s| s.add_protocol_bytes(in_bytes, out_bytes, protocol));
+        }
+    }
 }
 
 impl LimitedReaderStats for TcpConnectTaskCltWrapperStats {
@@ -75,6 +122,7 @@ impl LimitedReaderStats for TcpConnectTaskCltWrapperStats {
         self.task.clt.read.add_bytes(size);
         self.server.io_connect.add_in_bytes(size);
         self.others.iter().for_each(|s| s.add_read_bytes(size));
+        self.add_protocol_bytes(size, 0);
     }
 }
 
@@ -84,5 +132,6 @@ impl LimitedWriterStats for TcpConnectTaskCltWrapperStats {
         self.task.clt.write.add_bytes(size);
         self.server.io_connect.add_out_bytes(size);
         self.others.iter().for_each(|s| s.add_write_bytes(size));
+        self.add_protocol_bytes(0, size);
     }
 }
@@ -19,6 +19,9 @@ use super::{protocol, CommonTaskContext, HttpProxyServerStats};
 mod task;
 pub(super) use task::HttpProxyForwardTask;
 
+mod retry;
+use retry::{StaleRetry, StaleRetryAction};
+
 mod stats;
 use stats::{
     HttpForwardTaskCltWrapperStats, HttpForwardTaskStats, HttpsForwardTaskCltWrapperStats,
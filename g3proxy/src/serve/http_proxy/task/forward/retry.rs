@@ -0,0 +1,122 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use http::Method;
+
+#[derive(Debug, Eq, PartialEq)]
+pub(super) enum StaleRetryAction {
+    /// the error should be returned to the client
+    Abort,
+    /// try the next reused connection
+    TryReused,
+    /// try a new connection, and this is the last try
+    TryNew,
+}
+
+/// Limit the number of reused connections that will be tried for a single request
+pub(super) struct StaleRetry {
+    remaining: usize,
+}
+
+impl StaleRetry {
+    pub(super) fn new(count: usize) -> Self {
+        StaleRetry { remaining: count }
+    }
+
+    /// check if the reused connection should be checked for stale before receiving response,
+    /// only idempotent requests can be retried after the request has been sent
+    pub(super) fn check_stale(&self, method: &Method) -> bool {
+        self.remaining > 0 && method.is_idempotent()
+    }
+
+    /// Decide what to do if the request failed on a reused connection.
+    /// `req_sent` should be true if any request data may have been consumed by the upstream,
+    /// such as the request body has been sent, or there is no stale check.
+    /// Requests of all methods will be retried if nothing has been sent yet.
+    pub(super) fn on_failure(&mut self, req_sent: bool) -> StaleRetryAction {
+        if req_sent {
+            return StaleRetryAction::Abort;
+        }
+        self.remaining = self.remaining.saturating_sub(1);
+        if self.remaining > 0 {
+            StaleRetryAction::TryReused
+        } else {
+            // the last retry should always use a new connection
+            StaleRetryAction::TryNew
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idempotent_only() {
+        for method in [
+            Method::GET,
+            Method::HEAD,
+            Method::OPTIONS,
+            Method::PUT,
+            Method::DELETE,
+        ] {
+            let retry = StaleRetry::new(2);
+            assert!(retry.check_stale(&method));
+        }
+
+        for method in [Method::POST, Method::PATCH, Method::CONNECT] {
+            let retry = StaleRetry::new(2);
+            assert!(!retry.check_stale(&method));
+        }
+    }
+
+    #[test]
+    fn retry_before_sent() {
+        // non-idempotent requests are not stale checked after sent, so the failure is always
+        // before sent for them, and it's safe to retry
+        let mut retry = StaleRetry::new(2);
+        assert!(!retry.check_stale(&Method::POST));
+        assert_eq!(retry.on_failure(false), StaleRetryAction::TryReused);
+        assert_eq!(retry.on_failure(false), StaleRetryAction::TryNew);
+    }
+
+    #[test]
+    fn no_retry_after_sent() {
+        let mut retry = StaleRetry::new(2);
+        assert_eq!(retry.on_failure(true), StaleRetryAction::Abort);
+        assert_eq!(retry.on_failure(true), StaleRetryAction::Abort);
+        // the count is not consumed
+        assert_eq!(retry.on_failure(false), StaleRetryAction::TryReused);
+    }
+
+    #[test]
+    fn retry_limit() {
+        let mut retry = StaleRetry::new(3);
+        assert_eq!(retry.on_failure(false), StaleRetryAction::TryReused);
+        assert_eq!(retry.on_failure(false), StaleRetryAction::TryReused);
+        assert!(retry.check_stale(&Method::GET));
+        assert_eq!(retry.on_failure(false), StaleRetryAction::TryNew);
+        assert!(!retry.check_stale(&Method::GET));
+
+        let mut retry = StaleRetry::new(1);
+        assert_eq!(retry.on_failure(false), StaleRetryAction::TryNew);
+
+        // the first reused connection will always be tried
+        let mut retry = StaleRetry::new(0);
+        assert!(!retry.check_stale(&Method::GET));
+        assert_eq!(retry.on_failure(false), StaleRetryAction::TryNew);
+    }
+}
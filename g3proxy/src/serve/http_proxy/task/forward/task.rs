@@ -34,13 +34,14 @@ use g3_icap_client::respmod::h1::{
 };
 use g3_io_ext::{LimitedBufReadExt, LimitedCopy, LimitedCopyError};
 use g3_types::acl::AclAction;
-use g3_types::net::{HttpHeaderMap, ProxyRequestType};
+use g3_types::net::{Host, HttpHeaderMap, ProxyRequestType};
 
 use super::protocol::{HttpClientReader, HttpClientWriter, HttpProxyRequest};
 use super::{
     CommonTaskContext, HttpForwardTaskCltWrapperStats, HttpForwardTaskStats,
-    HttpsForwardTaskCltWrapperStats,
+    HttpsForwardTaskCltWrapperStats, StaleRetry, StaleRetryAction,
 };
+use crate::audit::{AuditHandle, AuditorStats};
 use crate::config::server::ServerConfig;
 use crate::log::task::http_forward::TaskLogForHttpForward;
 use crate::module::http_forward::{
@@ -64,7 +65,9 @@ pub(crate) struct HttpProxyForwardTask<'a> {
     http_notes: HttpForwardTaskNotes,
     tcp_notes: TcpConnectTaskNotes,
     task_stats: Arc<HttpForwardTaskStats>,
+    audit_handle: Option<Arc<AuditHandle>>,
     do_application_audit: bool,
+    stale_retry: StaleRetry,
 }
 
 impl<'a> HttpProxyForwardTask<'a> {
@@ -76,18 +79,19 @@ impl<'a> HttpProxyForwardTask<'a> {
     ) -> Self {
         let mut uri_log_max_chars = ctx.server_config.log_uri_max_chars;
         let mut do_application_audit = false;
+        let audit_handle = ctx.task_audit_handle(&task_notes);
         if let Some(user_ctx) = task_notes.user_ctx() {
             let user_config = &user_ctx.user_config();
             if let Some(max_chars) = user_config.log_uri_max_chars {
                 uri_log_max_chars = max_chars; // overwrite
             }
-            if let Some(audit_handle) = &ctx.audit_handle {
+            if let Some(audit_handle) = &audit_handle {
                 do_application_audit = user_config
                     .audit
                     .do_application_audit()
                     .unwrap_or_else(|| audit_handle.do_application_audit());
             }
-        } else if let Some(audit_handle) = &ctx.audit_handle {
+        } else if let Some(audit_handle) = &audit_handle {
             do_application_audit = audit_handle.do_application_audit();
         }
         let http_notes = HttpForwardTaskNotes::new(
@@ -107,7 +111,9 @@ impl<'a> HttpProxyForwardTask<'a> {
             http_notes,
             tcp_notes: TcpConnectTaskNotes::new(req.upstream.clone()),
             task_stats: Arc::new(HttpForwardTaskStats::default()),
+            audit_handle,
             do_application_audit,
+            stale_retry: StaleRetry::new(ctx.server_config.http_forward_stale_retry),
         }
     }
 
@@ -455,19 +461,16 @@ impl<'a> HttpProxyForwardTask<'a> {
                 );
                 for s in &user_io_stats {
                     s.io.https_forward.add_in_bytes(origin_header_size);
+                    s.io.protocol.http.add_in_bytes(origin_header_size);
                 }
                 wrapper_stats.push_user_io_stats(user_io_stats);
 
-                let user_config = user_ctx.user_config();
-                if user_config
-                    .tcp_sock_speed_limit
-                    .eq(&self.ctx.server_config.tcp_sock_speed_limit)
-                {
+                let user_limit = user_ctx.tcp_sock_speed_limit();
+                if user_limit.eq(&self.ctx.server_config.tcp_sock_speed_limit) {
                     None
                 } else {
-                    let limit_config = user_config
-                        .tcp_sock_speed_limit
-                        .shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
+                    let limit_config =
+                        user_limit.shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
                     Some(limit_config)
                 }
             } else {
@@ -487,19 +490,16 @@ impl<'a> HttpProxyForwardTask<'a> {
                 );
                 for s in &user_io_stats {
                     s.io.http_forward.add_in_bytes(origin_header_size);
+                    s.io.protocol.http.add_in_bytes(origin_header_size);
                 }
                 wrapper_stats.push_user_io_stats(user_io_stats);
 
-                let user_config = user_ctx.user_config();
-                if user_config
-                    .tcp_sock_speed_limit
-                    .eq(&self.ctx.server_config.tcp_sock_speed_limit)
-                {
+                let user_limit = user_ctx.tcp_sock_speed_limit();
+                if user_limit.eq(&self.ctx.server_config.tcp_sock_speed_limit) {
                     None
                 } else {
-                    let limit_config = user_config
-                        .tcp_sock_speed_limit
-                        .shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
+                    let limit_config =
+                        user_limit.shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
                     Some(limit_config)
                 }
             } else {
@@ -515,12 +515,15 @@ impl<'a> HttpProxyForwardTask<'a> {
             clt_w.reset_stats(clt_w_stats);
             if let Some(limit_config) = &limit_config {
                 br.reset_limit(limit_config.shift_millis, limit_config.max_north);
+                br.set_burst_limit(limit_config.burst_north);
                 clt_w.reset_limit(limit_config.shift_millis, limit_config.max_south);
+                clt_w.set_burst_limit(limit_config.burst_south);
             }
         } else {
             clt_w.reset_stats(clt_w_stats);
             if let Some(limit_config) = &limit_config {
                 clt_w.reset_limit(limit_config.shift_millis, limit_config.max_south);
+                clt_w.set_burst_limit(limit_config.burst_south);
             }
         }
     }
@@ -544,6 +547,20 @@ impl<'a> HttpProxyForwardTask<'a> {
             let action = user_ctx.check_client_addr(self.task_notes.client_addr());
             self.handle_user_client_acl_action(action, clt_w).await?;
 
+            if user_ctx.check_time_window().is_err() {
+                self.reply_forbidden(clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::OutOfTimeWindow,
+                ));
+            }
+
+            if user_ctx.check_traffic_quota().is_err() {
+                self.reply_forbidden(clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
             if user_ctx.check_rate_limit().is_err() {
                 self.reply_too_many_requests(clt_w).await;
                 return Err(ServerTaskError::ForbiddenByRule(
@@ -599,8 +616,11 @@ impl<'a> HttpProxyForwardTask<'a> {
         self.setup_clt_limit_and_stats(clt_r, clt_w);
 
         fwd_ctx.prepare_connection(&self.tcp_notes.upstream, self.is_https);
+        if self.is_https {
+            fwd_ctx.prepare_tls_connection(&self.ctx.tls_client_config, self.tls_name());
+        }
 
-        if let Some(connection) = fwd_ctx
+        while let Some(connection) = fwd_ctx
             .get_alive_connection(
                 &self.task_notes,
                 self.task_stats.clone() as _,
@@ -627,18 +647,26 @@ impl<'a> HttpProxyForwardTask<'a> {
                     return Ok(());
                 }
                 Err(e) => {
-                    if self.http_notes.retry_new_connection {
-                        // continue to make new connection
-                        if let Some(user_ctx) = self.task_notes.user_ctx() {
-                            user_ctx
-                                .foreach_req_stats(|s| s.req_renew.add_http_forward(self.is_https));
+                    let req_sent = !self.http_notes.retry_new_connection;
+                    match self.stale_retry.on_failure(req_sent) {
+                        StaleRetryAction::Abort => {
+                            self.should_close = true;
+                            if self.send_error_response {
+                                self.reply_task_err(&e, clt_w).await;
+                            }
+                            return Err(e);
                         }
-                    } else {
-                        self.should_close = true;
-                        if self.send_error_response {
-                            self.reply_task_err(&e, clt_w).await;
+                        action => {
+                            // continue to make new connection
+                            if let Some(user_ctx) = self.task_notes.user_ctx() {
+                                user_ctx.foreach_req_stats(|s| {
+                                    s.req_renew.add_http_forward(self.is_https)
+                                });
+                            }
+                            if action == StaleRetryAction::TryNew {
+                                break;
+                            }
                         }
-                        return Err(e);
                     }
                 }
             }
@@ -680,31 +708,35 @@ impl<'a> HttpProxyForwardTask<'a> {
         }
     }
 
+    fn tls_name(&self) -> &Host {
+        self.req
+            .host
+            .as_ref()
+            .unwrap_or(&self.tcp_notes.upstream)
+            .host()
+    }
+
     async fn make_new_connection(
         &self,
         fwd_ctx: &mut BoxHttpForwardContext,
     ) -> Result<BoxHttpForwardConnection, TcpConnectError> {
-        if self.is_https {
-            let tls_name = self
-                .req
-                .host
-                .as_ref()
-                .unwrap_or(&self.tcp_notes.upstream)
-                .host();
-
+        let connect_start = Instant::now();
+        let r = if self.is_https {
             fwd_ctx
                 .make_new_https_connection(
                     &self.task_notes,
                     self.task_stats.clone() as _,
                     &self.ctx.tls_client_config,
-                    tls_name,
+                    self.tls_name(),
                 )
                 .await
         } else {
             fwd_ctx
                 .make_new_http_connection(&self.task_notes, self.task_stats.clone() as _)
                 .await
-        }
+        };
+        self.task_notes.trace_span("connect", connect_start);
+        r
     }
 
     fn mark_relaying(&mut self) {
@@ -738,8 +770,21 @@ impl<'a> HttpProxyForwardTask<'a> {
             .prepare_new(&self.task_notes, &self.tcp_notes.upstream);
 
         if self.do_application_audit {
-            if let Some(audit_handle) = &self.ctx.audit_handle {
-                if let Some(reqmod) = audit_handle.icap_reqmod_client() {
+            if let Some(audit_handle) = &self.audit_handle {
+                let content_length = match self.req.body_type() {
+                    Some(HttpBodyType::ContentLength(size)) => Some(size),
+                    _ => None,
+                };
+                let reqmod_client = audit_handle
+                    .icap_reqmod_client()
+                    .filter(|_| !audit_handle.icap_reqmod_bypassed(content_length));
+                if reqmod_client.is_some() && audit_handle.icap_reqmod_body_blocked(content_length)
+                {
+                    return Err(ServerTaskError::ForbiddenByRule(
+                        ServerTaskForbiddenError::BodyTooLarge,
+                    ));
+                }
+                if let Some(reqmod) = reqmod_client {
                     match reqmod
                         .h1_adapter(
                             self.ctx.server_config.tcp_copy,
@@ -764,6 +809,7 @@ impl<'a> HttpProxyForwardTask<'a> {
                                     ups_c,
                                     adapter,
                                     &mut adaptation_state,
+                                    audit_handle.stats().clone(),
                                 )
                                 .await;
                             if let Some(dur) = adaptation_state.dur_ups_send_header {
@@ -776,9 +822,12 @@ impl<'a> HttpProxyForwardTask<'a> {
                             return r;
                         }
                         Err(e) => {
+                            let icap_stats = audit_handle.stats().icap_reqmod();
                             if !reqmod.bypass() {
+                                icap_stats.add_error();
                                 return Err(ServerTaskError::InternalAdapterError(e));
                             }
+                            icap_stats.add_bypassed();
                         }
                     }
                 }
@@ -819,6 +868,7 @@ impl<'a> HttpProxyForwardTask<'a> {
         mut ups_c: BoxHttpForwardConnection,
         icap_adapter: HttpRequestAdapter<ServerIdleChecker>,
         adaptation_state: &'f mut ReqmodAdaptationRunState,
+        audit_stats: Arc<AuditorStats>,
     ) -> ServerTaskResult<Option<BoxHttpForwardConnection>>
     where
         CDR: AsyncRead + Send + Unpin,
@@ -826,6 +876,7 @@ impl<'a> HttpProxyForwardTask<'a> {
     {
         use crate::module::http_forward::HttpForwardWriterForAdaptation;
 
+        let adaptation_start = Instant::now();
         let ups_w = &mut ups_c.0;
         let ups_r = &mut ups_c.1;
 
@@ -862,15 +913,20 @@ impl<'a> HttpProxyForwardTask<'a> {
                     }
                 }
                 r = &mut adaptation_fut => {
+                    self.task_notes.trace_span("adaptation", adaptation_start);
+                    let icap_stats = audit_stats.icap_reqmod();
                     match r {
                         Ok(ReqmodAdaptationEndState::OriginalTransferred) => {
+                            icap_stats.add_no_modification();
                             break;
                         }
                         Ok(ReqmodAdaptationEndState::AdaptedTransferred(_r)) => {
                             // TODO add log for adapted request?
+                            icap_stats.add_adapted();
                             break;
                         }
                         Ok(ReqmodAdaptationEndState::HttpErrResponse(rsp, rsp_recv_body)) => {
+                            icap_stats.add_blocked();
                             self.send_adaptation_error_response(clt_w, rsp, rsp_recv_body).await?;
                             return Ok(None);
                         }
@@ -880,7 +936,11 @@ impl<'a> HttpProxyForwardTask<'a> {
                                 // not all client data read in, drop the client connection
                                 self.should_close = true;
                             }
-                            return Err(e.into());
+                            let e = ServerTaskError::from(e);
+                            if matches!(e, ServerTaskError::InternalAdapterError(_)) {
+                                icap_stats.add_error();
+                            }
+                            return Err(e);
                         }
                     }
                 }
@@ -921,6 +981,8 @@ impl<'a> HttpProxyForwardTask<'a> {
             }
         };
         self.http_notes.mark_rsp_recv_hdr();
+        self.task_notes
+            .record_first_byte(self.http_notes.dur_rsp_recv_hdr);
 
         self.send_response(
             clt_w,
@@ -996,10 +1058,12 @@ impl<'a> HttpProxyForwardTask<'a> {
         self.http_notes.mark_req_send_hdr();
         self.http_notes.mark_req_no_body();
         self.http_notes.retry_new_connection = false;
+        let check_stale =
+            self.http_notes.reuse_connection && self.stale_retry.check_stale(&self.req.method);
 
         let mut rsp_header = match tokio::time::timeout(
             self.ctx.server_config.timeout.recv_rsp_header,
-            self.recv_response_header(ups_r),
+            self.recv_first_response_header(ups_r, check_stale),
         )
         .await
         {
@@ -1012,6 +1076,8 @@ impl<'a> HttpProxyForwardTask<'a> {
             }
         };
         self.http_notes.mark_rsp_recv_hdr();
+        self.task_notes
+            .record_first_byte(self.http_notes.dur_rsp_recv_hdr);
 
         self.send_response(clt_w, ups_r, &mut rsp_header, None)
             .await?;
@@ -1167,6 +1233,8 @@ impl<'a> HttpProxyForwardTask<'a> {
             }
         };
         self.http_notes.mark_rsp_recv_hdr();
+        self.task_notes
+            .record_first_byte(self.http_notes.dur_rsp_recv_hdr);
 
         self.send_response(clt_w, ups_r, &mut rsp_header, None)
             .await?;
@@ -1215,6 +1283,27 @@ impl<'a> HttpProxyForwardTask<'a> {
         Ok(())
     }
 
+    async fn recv_first_response_header(
+        &mut self,
+        ups_r: &mut BoxHttpForwardReader,
+        check_stale: bool,
+    ) -> ServerTaskResult<HttpForwardRemoteResponse> {
+        if check_stale {
+            // the reused connection may be closed by upstream right before we send the request,
+            // and it's safe to retry as no response data has been received
+            let r = match ups_r.fill_wait_data().await {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(ServerTaskError::ClosedByUpstream),
+                Err(e) => Err(ServerTaskError::UpstreamReadFailed(e)),
+            };
+            if let Err(e) = r {
+                self.http_notes.retry_new_connection = true;
+                return Err(e);
+            }
+        }
+        self.recv_response_header(ups_r).await
+    }
+
     async fn recv_response_header(
         &mut self,
         ups_r: &mut BoxHttpForwardReader,
@@ -1252,8 +1341,28 @@ impl<'a> HttpProxyForwardTask<'a> {
         self.update_response_header(rsp_header);
 
         if self.do_application_audit {
-            if let Some(audit_handle) = &self.ctx.audit_handle {
-                if let Some(respmod) = audit_handle.icap_respmod_client() {
+            if let Some(audit_handle) = &self.audit_handle {
+                let content_type = rsp_header
+                    .end_to_end_headers
+                    .get(http::header::CONTENT_TYPE)
+                    .map(|v| v.to_str());
+                let content_length = match rsp_header.body_type(&self.req.method) {
+                    Some(HttpBodyType::ContentLength(size)) => Some(size),
+                    _ => None,
+                };
+                let respmod_client = audit_handle
+                    .icap_respmod_client()
+                    .filter(|_| !audit_handle.icap_respmod_bypassed(content_type, content_length));
+                if respmod_client.is_some()
+                    && audit_handle.icap_respmod_body_blocked(content_length)
+                {
+                    // the response body will not be read
+                    self.should_close = true;
+                    return Err(ServerTaskError::ForbiddenByRule(
+                        ServerTaskForbiddenError::BodyTooLarge,
+                    ));
+                }
+                if let Some(respmod) = respmod_client {
                     match respmod
                         .h1_adapter(
                             self.ctx.server_config.tcp_copy,
@@ -1279,6 +1388,7 @@ impl<'a> HttpProxyForwardTask<'a> {
                                     rsp_header,
                                     adapter,
                                     &mut adaptation_state,
+                                    audit_handle.stats().clone(),
                                 )
                                 .await;
                             if !adaptation_state.clt_write_finished
@@ -1293,9 +1403,12 @@ impl<'a> HttpProxyForwardTask<'a> {
                             return r;
                         }
                         Err(e) => {
+                            let icap_stats = audit_handle.stats().icap_respmod();
                             if !respmod.bypass() {
+                                icap_stats.add_error();
                                 return Err(ServerTaskError::InternalAdapterError(e));
                             }
+                            icap_stats.add_bypassed();
                         }
                     }
                 }
@@ -1313,24 +1426,34 @@ impl<'a> HttpProxyForwardTask<'a> {
         rsp_header: &HttpForwardRemoteResponse,
         icap_adapter: HttpResponseAdapter<ServerIdleChecker>,
         adaptation_state: &mut RespmodAdaptationRunState,
+        audit_stats: Arc<AuditorStats>,
     ) -> ServerTaskResult<()>
     where
         R: AsyncBufRead + Unpin,
         W: AsyncWrite + Send + Unpin,
     {
+        let icap_stats = audit_stats.icap_respmod();
         match icap_adapter
             .xfer(adaptation_state, self.req, rsp_header, ups_r, clt_w)
             .await
         {
             Ok(RespmodAdaptationEndState::OriginalTransferred) => {
+                icap_stats.add_no_modification();
                 self.http_notes.rsp_status = rsp_header.code;
                 Ok(())
             }
             Ok(RespmodAdaptationEndState::AdaptedTransferred(adapted_rsp)) => {
+                icap_stats.add_adapted();
                 self.http_notes.rsp_status = adapted_rsp.code;
                 Ok(())
             }
-            Err(e) => Err(e.into()),
+            Err(e) => {
+                let e = ServerTaskError::from(e);
+                if matches!(e, ServerTaskError::InternalAdapterError(_)) {
+                    icap_stats.add_error();
+                }
+                Err(e)
+            }
         }
     }
 
@@ -33,10 +33,12 @@ type ArcFtpOverHttpTaskCltStatsWrapper = Arc<dyn FtpOverHttpTaskCltStatsWrapper
 impl FtpOverHttpTaskCltStatsWrapper for UserTrafficStats {
     fn add_read_bytes(&self, size: u64) {
         self.io.ftp_over_http.add_in_bytes(size);
+        self.io.protocol.http.add_in_bytes(size);
     }
 
     fn add_write_bytes(&self, size: u64) {
         self.io.ftp_over_http.add_out_bytes(size);
+        self.io.protocol.http.add_out_bytes(size);
     }
 }
 
@@ -352,19 +352,16 @@ impl<'a> FtpOverHttpTask<'a> {
             );
             for s in &user_io_stats {
                 s.io.ftp_over_http.add_in_bytes(origin_header_size);
+                s.io.protocol.http.add_in_bytes(origin_header_size);
             }
             wrapper_stats.push_user_io_stats(user_io_stats);
 
-            let user_config = user_ctx.user_config();
-            if user_config
-                .tcp_sock_speed_limit
-                .eq(&self.ctx.server_config.tcp_sock_speed_limit)
-            {
+            let user_limit = user_ctx.tcp_sock_speed_limit();
+            if user_limit.eq(&self.ctx.server_config.tcp_sock_speed_limit) {
                 None
             } else {
-                let limit_config = user_config
-                    .tcp_sock_speed_limit
-                    .shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
+                let limit_config =
+                    user_limit.shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
                 Some(limit_config)
             }
         } else {
@@ -377,7 +374,9 @@ impl<'a> FtpOverHttpTask<'a> {
         clt_r.reset_buffer_stats(clt_r_stats);
         if let Some(limit_config) = &limit_config {
             clt_w.reset_limit(limit_config.shift_millis, limit_config.max_south);
+            clt_w.set_burst_limit(limit_config.burst_south);
             clt_r.reset_limit(limit_config.shift_millis, limit_config.max_north);
+            clt_r.set_burst_limit(limit_config.burst_north);
         }
     }
 
@@ -399,6 +398,20 @@ impl<'a> FtpOverHttpTask<'a> {
             let action = user_ctx.check_client_addr(self.task_notes.client_addr());
             self.handle_user_client_acl_action(action, clt_w).await?;
 
+            if user_ctx.check_time_window().is_err() {
+                self.reply_forbidden(clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::OutOfTimeWindow,
+                ));
+            }
+
+            if user_ctx.check_traffic_quota().is_err() {
+                self.reply_forbidden(clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
             if user_ctx.check_rate_limit().is_err() {
                 self.reply_too_many_requests(clt_w).await;
                 return Err(ServerTaskError::ForbiddenByRule(
@@ -632,11 +645,10 @@ impl<'a> FtpOverHttpTask<'a> {
     where
         W: AsyncWrite + Unpin,
     {
-        let escaper_connect_context = self
-            .ctx
-            .escaper
+        let escaper = self.ctx.task_escaper(&self.task_notes);
+        let escaper_connect_context = escaper
             .new_ftp_connect_context(
-                Arc::clone(&self.ctx.escaper),
+                Arc::clone(&escaper),
                 &self.task_notes,
                 self.ftp_notes.upstream(),
             )
@@ -24,6 +24,8 @@ use tokio::sync::mpsc;
 
 use g3_io_ext::{ArcLimitedWriterStats, LimitedWriter};
 use g3_types::auth::UserAuthError;
+use g3_types::limit::GaugeSemaphorePermit;
+use g3_types::metrics::MetricsName;
 use g3_types::net::{HttpAuth, HttpBasicAuth, HttpHeaderMap};
 use g3_types::route::EgressPathSelection;
 
@@ -32,7 +34,7 @@ use super::{
     CommonTaskContext, FtpOverHttpTask, HttpProxyCltWrapperStats, HttpProxyConnectTask,
     HttpProxyForwardTask, HttpProxyPipelineStats, HttpProxyUntrustedTask,
 };
-use crate::auth::{UserContext, UserGroup, UserRequestStats};
+use crate::auth::{User, UserContext, UserGroup, UserRequestStats, UserType};
 use crate::config::server::ServerConfig;
 use crate::module::http_forward::{BoxHttpForwardContext, HttpProxyClientResponse};
 use crate::serve::{ServerStats, ServerTaskNotes};
@@ -41,6 +43,7 @@ struct UserData {
     req_stats: Arc<UserRequestStats>,
     site_req_stats: Option<Arc<UserRequestStats>>,
     count: usize,
+    _conn_alive_permit: GaugeSemaphorePermit,
 }
 
 impl Drop for UserData {
@@ -78,9 +81,13 @@ pub(crate) struct HttpProxyPipelineWriterTask<CDR, CDW> {
     task_queue: mpsc::Receiver<Result<HttpProxyRequest<CDR>, HttpProxyClientResponse>>,
     stream_writer: Option<HttpClientWriter<CDW>>,
     forward_context: BoxHttpForwardContext,
+    /// the user escaper used by the forward context, or none if it's the server one
+    forward_user_escaper: Option<MetricsName>,
     wrapper_stats: ArcLimitedWriterStats,
     pipeline_stats: Arc<HttpProxyPipelineStats>,
     req_count: RequestCount,
+    /// Negotiate and client cert auth are connection based, so save the user for later requests
+    conn_auth_user: Option<(Arc<User>, UserType, Option<String>)>,
 }
 
 enum LoopAction {
@@ -105,32 +112,49 @@ where
             .new_http_forward_context(Arc::clone(&ctx.escaper));
         let clt_w_stats = HttpProxyCltWrapperStats::new_for_writer(&ctx.server_stats);
         let limit_config = &ctx.server_config.tcp_sock_speed_limit;
-        let clt_w = LimitedWriter::new(
+        let mut clt_w = LimitedWriter::new(
             write_half,
             limit_config.shift_millis,
             limit_config.max_south,
             Arc::clone(&clt_w_stats),
         );
+        clt_w.set_burst_limit(limit_config.burst_south);
         HttpProxyPipelineWriterTask {
             ctx: Arc::clone(ctx),
             user_group,
             task_queue: task_receiver,
             stream_writer: Some(clt_w),
             forward_context,
+            forward_user_escaper: None,
             wrapper_stats: clt_w_stats,
             pipeline_stats: Arc::clone(pipeline_stats),
             req_count: RequestCount::default(),
+            conn_auth_user: None,
         }
     }
 
-    fn do_auth(
+    pub(crate) fn set_client_cert_user(&mut self, user: (Arc<User>, UserType, Option<String>)) {
+        self.conn_auth_user = Some(user);
+    }
+
+    async fn do_auth(
         &mut self,
         req: &HttpProxyRequest<CDR>,
     ) -> Result<Option<UserContext>, UserAuthError> {
         if let Some(user_group) = &self.user_group {
             let mut user_ctx = match &req.inner.auth_info {
                 HttpAuth::None => {
-                    if let Some((user, user_type)) = user_group.get_anonymous_user() {
+                    if let Some((user, user_type, username)) = &self.conn_auth_user {
+                        let user_ctx = UserContext::new(
+                            username.clone(),
+                            Arc::clone(user),
+                            *user_type,
+                            self.ctx.server_config.name(),
+                            self.ctx.server_stats.share_extra_tags(),
+                        );
+                        user_ctx.check_auth_status()?;
+                        user_ctx
+                    } else if let Some((user, user_type)) = user_group.get_anonymous_user() {
                         UserContext::new(
                             None,
                             user,
@@ -153,11 +177,46 @@ where
                             self.ctx.server_config.name(),
                             self.ctx.server_stats.share_extra_tags(),
                         );
-                        user_ctx.check_password(password.as_original())?;
+                        user_group
+                            .check_password(&user_ctx, password.as_original())
+                            .await?;
                         user_ctx
                     }
                     None => return Err(UserAuthError::NoSuchUser),
                 },
+                HttpAuth::Bearer(bearer) => {
+                    let (user, user_type, username) =
+                        user_group.get_user_by_token(bearer.token())?;
+                    let has_username = username.is_some();
+                    let user_ctx = UserContext::new(
+                        username,
+                        user,
+                        user_type,
+                        self.ctx.server_config.name(),
+                        self.ctx.server_stats.share_extra_tags(),
+                    );
+                    if has_username {
+                        user_ctx.check_auth_status()?;
+                    }
+                    user_ctx
+                }
+                HttpAuth::Negotiate(negotiate) => {
+                    let (user, user_type, username) =
+                        user_group.get_user_by_negotiate(negotiate.token())?;
+                    let has_username = username.is_some();
+                    let user_ctx = UserContext::new(
+                        username.clone(),
+                        Arc::clone(&user),
+                        user_type,
+                        self.ctx.server_config.name(),
+                        self.ctx.server_stats.share_extra_tags(),
+                    );
+                    if has_username {
+                        user_ctx.check_auth_status()?;
+                        self.conn_auth_user = Some((user, user_type, username));
+                    }
+                    user_ctx
+                }
             };
 
             user_ctx.check_in_site(
@@ -165,30 +224,33 @@ where
                 self.ctx.server_stats.share_extra_tags(),
                 &req.upstream,
             );
-            self.req_count
-                .passed_users
-                .entry(user_ctx.user_name().to_string())
-                .and_modify(|e| {
-                    user_ctx.mark_reused_client_connection();
-                    e.count += 1;
-                })
-                .or_insert_with(|| {
-                    let req_stats = user_ctx.req_stats().clone();
-                    req_stats.conn_total.add_http();
-                    req_stats.l7_conn_alive.inc_http();
-                    let site_req_stats = if let Some(site_req_stats) = user_ctx.site_req_stats() {
-                        site_req_stats.conn_total.add_http();
-                        site_req_stats.l7_conn_alive.inc_http();
-                        Some(Arc::clone(site_req_stats))
-                    } else {
-                        None
-                    };
+            if let Some(e) = self.req_count.passed_users.get_mut(user_ctx.user_name()) {
+                user_ctx.mark_reused_client_connection();
+                e.count += 1;
+            } else {
+                let conn_alive_permit = user_ctx
+                    .acquire_connection_semaphore()
+                    .map_err(|_| UserAuthError::ConnectionLimited)?;
+                let req_stats = user_ctx.req_stats().clone();
+                req_stats.conn_total.add_http();
+                req_stats.l7_conn_alive.inc_http();
+                let site_req_stats = if let Some(site_req_stats) = user_ctx.site_req_stats() {
+                    site_req_stats.conn_total.add_http();
+                    site_req_stats.l7_conn_alive.inc_http();
+                    Some(Arc::clone(site_req_stats))
+                } else {
+                    None
+                };
+                self.req_count.passed_users.insert(
+                    user_ctx.user_name().to_string(),
                     UserData {
                         req_stats,
                         site_req_stats,
                         count: 1,
-                    }
-                });
+                        _conn_alive_permit: conn_alive_permit,
+                    },
+                );
+            }
             Ok(Some(user_ctx))
         } else {
             self.req_count.anonymous += 1;
@@ -200,11 +262,12 @@ where
         loop {
             let res = match self.task_queue.recv().await {
                 Some(Ok(req)) => {
-                    let res = match self.do_auth(&req) {
+                    let res = match self.do_auth(&req).await {
                         Ok(user_ctx) => {
                             self.req_count.consequent_auth_failed = 0;
                             self.run(req, user_ctx).await
                         }
+                        Err(UserAuthError::ConnectionLimited) => self.run_conn_limited(req).await,
                         Err(e) => {
                             self.req_count.consequent_auth_failed += 1;
                             self.req_count.auth_failed += 1;
@@ -254,6 +317,21 @@ where
             .unwrap_or_default()
     }
 
+    fn update_forward_context(&mut self, task_notes: &ServerTaskNotes) {
+        let user_escaper = task_notes
+            .user_ctx()
+            .and_then(|ctx| ctx.user_config().escaper.as_ref());
+        if self.forward_user_escaper.as_ref() == user_escaper {
+            return;
+        }
+
+        let escaper = task_notes
+            .user_escaper()
+            .unwrap_or_else(|| Arc::clone(&self.ctx.escaper));
+        self.forward_context = escaper.new_http_forward_context(Arc::clone(&escaper));
+        self.forward_user_escaper = user_escaper.cloned();
+    }
+
     async fn run(
         &mut self,
         mut req: HttpProxyRequest<CDR>,
@@ -261,12 +339,39 @@ where
     ) -> LoopAction {
         let path_selection =
             self.get_egress_path_selection(&mut req.inner.end_to_end_headers, user_ctx.as_ref());
-        let task_notes = ServerTaskNotes::with_path_selection(
+        let mut task_notes = ServerTaskNotes::with_path_selection(
             self.ctx.cc_info.clone(),
             user_ctx,
             req.time_accepted.elapsed(),
             path_selection,
         );
+        task_notes.set_duration_recorder(self.ctx.server_stats.duration.recorder());
+        task_notes.register_alive(self.ctx.server_config.name(), &req.upstream);
+        task_notes.trace_http_request(&mut req.inner.end_to_end_headers);
+
+        let kill_token = task_notes.alive_kill_token();
+        let action = tokio::select! {
+            biased;
+
+            _ = kill_token.cancelled() => None,
+            r = self.run_registered(req, task_notes) => Some(r),
+        };
+        match action {
+            Some(action) => action,
+            None => {
+                // the client writer has been dropped along with the task
+                self.notify_reader_to_close();
+                LoopAction::Break
+            }
+        }
+    }
+
+    async fn run_registered(
+        &mut self,
+        mut req: HttpProxyRequest<CDR>,
+        task_notes: ServerTaskNotes,
+    ) -> LoopAction {
+        self.update_forward_context(&task_notes);
 
         let forward_capability = self
             .forward_context
@@ -359,14 +464,32 @@ where
         stream_w.reset_stats(Arc::clone(&self.wrapper_stats));
         let limit_config = &self.ctx.server_config.tcp_sock_speed_limit;
         stream_w.reset_limit(limit_config.shift_millis, limit_config.max_south);
+        stream_w.set_burst_limit(limit_config.burst_south);
         self.stream_writer = Some(stream_w);
     }
 
+    async fn run_conn_limited(&mut self, req: HttpProxyRequest<CDR>) -> LoopAction {
+        // the user has too many alive connections, always close the connection
+        if !self.ctx.server_config.no_early_error_reply {
+            if let Some(clt_w) = &mut self.stream_writer {
+                let rsp = HttpProxyClientResponse::too_many_requests(req.inner.version);
+                let _ = rsp.reply_err_to_request(clt_w).await;
+            }
+        }
+
+        self.notify_reader_to_close();
+        LoopAction::Break
+    }
+
     async fn run_untrusted(
         &mut self,
         mut req: HttpProxyRequest<CDR>,
         blocked_delay: Option<Duration>,
     ) -> LoopAction {
+        let allow_negotiate = self
+            .user_group
+            .as_ref()
+            .is_some_and(|g| g.allow_negotiate());
         if self.ctx.server_config.no_early_error_reply {
             if let Some(duration) = blocked_delay {
                 self.ctx.server_stats.forbidden.add_user_blocked();
@@ -404,6 +527,7 @@ where
                     req.inner.version,
                     clt_w,
                     &self.ctx.server_config.auth_realm,
+                    allow_negotiate,
                     true,
                 )
                 .await;
@@ -416,7 +540,8 @@ where
 
             match req.body_reader.take() {
                 Some(stream_r) => {
-                    let mut untrusted_task = HttpProxyUntrustedTask::new(&self.ctx, &req);
+                    let mut untrusted_task =
+                        HttpProxyUntrustedTask::new(&self.ctx, &req, allow_negotiate);
                     let mut clt_r = Some(stream_r);
                     untrusted_task.run(&mut clt_r, clt_w).await;
                     if untrusted_task.should_close() {
@@ -434,7 +559,8 @@ where
                     }
                 }
                 None => {
-                    let mut untrusted_task = HttpProxyUntrustedTask::new(&self.ctx, &req);
+                    let mut untrusted_task =
+                        HttpProxyUntrustedTask::new(&self.ctx, &req, allow_negotiate);
                     let mut clt_r = None;
                     untrusted_task.run::<CDR, CDW>(&mut clt_r, clt_w).await;
                     if untrusted_task.should_close() {
@@ -33,6 +33,7 @@ use crate::serve::{ServerTaskError, ServerTaskResult};
 pub(crate) struct HttpProxyUntrustedTask<'a> {
     ctx: Arc<CommonTaskContext>,
     req: &'a HttpProxyClientRequest,
+    allow_negotiate: bool,
     should_close: bool,
 }
 
@@ -40,10 +41,12 @@ impl<'a> HttpProxyUntrustedTask<'a> {
     pub(crate) fn new(
         ctx: &Arc<CommonTaskContext>,
         req: &'a HttpProxyRequest<impl AsyncRead>,
+        allow_negotiate: bool,
     ) -> Self {
         HttpProxyUntrustedTask {
             ctx: Arc::clone(ctx),
             req: &req.inner,
+            allow_negotiate,
             should_close: !req.inner.keep_alive(),
         }
     }
@@ -92,6 +95,7 @@ impl<'a> HttpProxyUntrustedTask<'a> {
                     self.pre_start();
 
                     br.reset_limit(limit_config.shift_millis, limit_config.max_north);
+                    br.set_burst_limit(limit_config.burst_north);
                     let buffer_stats =
                         UntrustedCltReadWrapperStats::new_obj(&self.ctx.server_stats);
                     br.reset_buffer_stats(buffer_stats);
@@ -116,6 +120,7 @@ impl<'a> HttpProxyUntrustedTask<'a> {
             self.req.version,
             clt_w,
             &self.ctx.server_config.auth_realm,
+            self.allow_negotiate,
             self.should_close,
         )
         .await;
@@ -97,6 +97,9 @@ impl HttpRProxyServer {
 
         // always update extra metrics tags
         server_stats.set_extra_tags(config.extra_metrics_tags.clone());
+        server_stats
+            .duration
+            .set_config(config.duration_stats.as_ref());
 
         let escaper = Arc::new(crate::escape::get_or_insert_default(config.escaper()));
         let user_group = config.get_user_group();
@@ -35,18 +35,22 @@ type ArcHttpForwardTaskCltStatsWrapper = Arc<dyn HttpForwardTaskCltStatsWrapper
 impl HttpForwardTaskCltStatsWrapper for UserTrafficStats {
     fn add_http_read_bytes(&self, size: u64) {
         self.io.http_forward.add_in_bytes(size);
+        self.io.protocol.http.add_in_bytes(size);
     }
 
     fn add_http_write_bytes(&self, size: u64) {
         self.io.http_forward.add_out_bytes(size);
+        self.io.protocol.http.add_out_bytes(size);
     }
 
     fn add_https_read_bytes(&self, size: u64) {
         self.io.https_forward.add_in_bytes(size);
+        self.io.protocol.http.add_in_bytes(size);
     }
 
     fn add_https_write_bytes(&self, size: u64) {
         self.io.https_forward.add_out_bytes(size);
+        self.io.protocol.http.add_out_bytes(size);
     }
 }
 
@@ -364,19 +364,16 @@ impl<'a> HttpRProxyForwardTask<'a> {
                 );
                 for s in &user_io_stats {
                     s.io.https_forward.add_in_bytes(origin_header_size);
+                    s.io.protocol.http.add_in_bytes(origin_header_size);
                 }
                 wrapper_stats.push_user_io_stats(user_io_stats);
 
-                let user_config = user_ctx.user_config();
-                if user_config
-                    .tcp_sock_speed_limit
-                    .eq(&self.ctx.server_config.tcp_sock_speed_limit)
-                {
+                let user_limit = user_ctx.tcp_sock_speed_limit();
+                if user_limit.eq(&self.ctx.server_config.tcp_sock_speed_limit) {
                     None
                 } else {
-                    let limit_config = user_config
-                        .tcp_sock_speed_limit
-                        .shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
+                    let limit_config =
+                        user_limit.shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
                     Some(limit_config)
                 }
             } else {
@@ -396,19 +393,16 @@ impl<'a> HttpRProxyForwardTask<'a> {
                 );
                 for s in &user_io_stats {
                     s.io.http_forward.add_in_bytes(origin_header_size);
+                    s.io.protocol.http.add_in_bytes(origin_header_size);
                 }
                 wrapper_stats.push_user_io_stats(user_io_stats);
 
-                let user_config = user_ctx.user_config();
-                if user_config
-                    .tcp_sock_speed_limit
-                    .eq(&self.ctx.server_config.tcp_sock_speed_limit)
-                {
+                let user_limit = user_ctx.tcp_sock_speed_limit();
+                if user_limit.eq(&self.ctx.server_config.tcp_sock_speed_limit) {
                     None
                 } else {
-                    let limit_config = user_config
-                        .tcp_sock_speed_limit
-                        .shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
+                    let limit_config =
+                        user_limit.shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
                     Some(limit_config)
                 }
             } else {
@@ -424,12 +418,15 @@ impl<'a> HttpRProxyForwardTask<'a> {
             clt_w.reset_stats(clt_w_stats);
             if let Some(limit_config) = &limit_config {
                 br.reset_limit(limit_config.shift_millis, limit_config.max_north);
+                br.set_burst_limit(limit_config.burst_north);
                 clt_w.reset_limit(limit_config.shift_millis, limit_config.max_south);
+                clt_w.set_burst_limit(limit_config.burst_south);
             }
         } else {
             clt_w.reset_stats(clt_w_stats);
             if let Some(limit_config) = &limit_config {
                 clt_w.reset_limit(limit_config.shift_millis, limit_config.max_south);
+                clt_w.set_burst_limit(limit_config.burst_south);
             }
         }
     }
@@ -453,6 +450,20 @@ impl<'a> HttpRProxyForwardTask<'a> {
             let action = user_ctx.check_client_addr(self.task_notes.client_addr());
             self.handle_user_client_acl_action(action, clt_w).await?;
 
+            if user_ctx.check_time_window().is_err() {
+                self.reply_forbidden(clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::OutOfTimeWindow,
+                ));
+            }
+
+            if user_ctx.check_traffic_quota().is_err() {
+                self.reply_forbidden(clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
             if user_ctx.check_rate_limit().is_err() {
                 self.reply_too_many_requests(clt_w).await;
                 return Err(ServerTaskError::ForbiddenByRule(
@@ -495,6 +506,9 @@ impl<'a> HttpRProxyForwardTask<'a> {
         self.setup_clt_limit_and_stats(clt_r, clt_w);
 
         fwd_ctx.prepare_connection(&self.tcp_notes.upstream, self.is_https);
+        if let Some(tls_client) = &self.host.tls_client {
+            fwd_ctx.prepare_tls_connection(tls_client, &self.host.config.tls_name);
+        }
 
         if let Some(connection) = fwd_ctx
             .get_alive_connection(
@@ -674,6 +688,8 @@ impl<'a> HttpRProxyForwardTask<'a> {
             }
         };
         self.http_notes.mark_rsp_recv_hdr();
+        self.task_notes
+            .record_first_byte(self.http_notes.dur_rsp_recv_hdr);
 
         self.update_response_header(&mut rsp_header);
         self.send_response(clt_w, ups_r, &rsp_header).await?;
@@ -834,6 +850,8 @@ impl<'a> HttpRProxyForwardTask<'a> {
             }
         };
         self.http_notes.mark_rsp_recv_hdr();
+        self.task_notes
+            .record_first_byte(self.http_notes.dur_rsp_recv_hdr);
 
         self.update_response_header(&mut rsp_header);
         self.send_response(clt_w, ups_r, &rsp_header).await?;
@@ -47,13 +47,14 @@ where
     ) -> Self {
         let clt_r_stats = HttpRProxyCltWrapperStats::new_for_reader(&ctx.server_stats);
         let limit_config = &ctx.server_config.tcp_sock_speed_limit;
-        let clt_r = LimitedBufReader::new(
+        let mut clt_r = LimitedBufReader::new(
             read_half,
             limit_config.shift_millis,
             limit_config.max_north,
             clt_r_stats,
             Arc::new(NilLimitedReaderStats::default()),
         );
+        clt_r.set_burst_limit(limit_config.burst_north);
         HttpRProxyPipelineReaderTask {
             ctx: Arc::clone(ctx),
             task_queue: task_sender,
@@ -191,6 +192,7 @@ where
                         reader.reset_buffer_stats(Arc::new(NilLimitedReaderStats::default()));
                         let limit_config = &self.ctx.server_config.tcp_sock_speed_limit;
                         reader.reset_limit(limit_config.shift_millis, limit_config.max_north);
+                        reader.set_burst_limit(limit_config.burst_north);
                         self.stream_reader = Some(reader);
                     }
                     None => {
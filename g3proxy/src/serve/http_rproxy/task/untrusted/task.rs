@@ -92,6 +92,7 @@ impl<'a> HttpRProxyUntrustedTask<'a> {
                     self.pre_start();
 
                     br.reset_limit(limit_config.shift_millis, limit_config.max_north);
+                    br.set_burst_limit(limit_config.burst_north);
                     let buffer_stats =
                         UntrustedCltReadWrapperStats::new_obj(&self.ctx.server_stats);
                     br.reset_buffer_stats(buffer_stats);
@@ -60,9 +60,13 @@ mod tcp_stream;
 mod tcp_tproxy;
 mod tls_stream;
 
+mod test_echo;
+
 mod error;
 mod task;
 
+pub(crate) mod alive_task;
+
 pub(crate) use error::{ServerTaskError, ServerTaskForbiddenError, ServerTaskResult};
 pub(crate) use task::{ServerTaskNotes, ServerTaskStage};
 
@@ -76,7 +80,8 @@ pub use ops::{spawn_all, spawn_offline_clean};
 
 mod stats;
 pub(crate) use stats::{
-    ArcServerStats, ServerForbiddenSnapshot, ServerForbiddenStats, ServerPerTaskStats, ServerStats,
+    ArcServerStats, ServerDurationStats, ServerForbiddenSnapshot, ServerForbiddenStats,
+    ServerPerTaskStats, ServerStats, ServerTaskDurationRecorder, ServerTaskDurationStats,
 };
 
 pub(crate) trait ServerInternal {
@@ -51,6 +51,8 @@ use super::tcp_stream::TcpStreamServer;
 use super::tcp_tproxy::TcpTProxyServer;
 use super::tls_stream::TlsStreamServer;
 
+use super::test_echo::TestEchoServer;
+
 static SERVER_OPS_LOCK: Mutex<()> = Mutex::const_new(());
 
 pub fn spawn_offline_clean() {
@@ -313,6 +315,7 @@ fn spawn_new_unlocked(config: AnyServerConfig) -> anyhow::Result<()> {
         AnyServerConfig::SocksProxy(c) => SocksProxyServer::prepare_initial(*c)?,
         AnyServerConfig::HttpProxy(c) => HttpProxyServer::prepare_initial(*c)?,
         AnyServerConfig::HttpRProxy(c) => HttpRProxyServer::prepare_initial(*c)?,
+        AnyServerConfig::TestEcho(c) => TestEchoServer::prepare_initial(c)?,
     };
     registry::add(name.clone(), server)?;
     update_dependency_to_server_unlocked(&name, "spawned");
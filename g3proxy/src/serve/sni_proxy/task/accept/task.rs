@@ -23,7 +23,7 @@ use tokio::net::TcpStream;
 use tokio::time::Instant;
 
 use g3_daemon::stat::task::TcpStreamConnectionStats;
-use g3_dpi::{Protocol, ProtocolInspector};
+use g3_dpi::{Protocol, ProtocolInspector, TlsClientFingerprint, TlsClientHello};
 use g3_io_ext::{LimitedReader, LimitedWriter};
 use g3_types::net::UpstreamAddr;
 
@@ -53,18 +53,20 @@ impl ClientHelloAcceptTask {
             SniProxyCltWrapperStats::new_pair(&self.ctx.server_stats, &self.pre_handshake_stats);
         let limit_config = &self.ctx.server_config.tcp_sock_speed_limit;
         let (clt_r, clt_w) = stream.into_split();
-        let clt_r = LimitedReader::new(
+        let mut clt_r = LimitedReader::new(
             clt_r,
             limit_config.shift_millis,
             limit_config.max_north,
             clt_r_stats,
         );
-        let clt_w = LimitedWriter::new(
+        clt_r.set_burst_limit(limit_config.burst_north);
+        let mut clt_w = LimitedWriter::new(
             clt_w,
             limit_config.shift_millis,
             limit_config.max_south,
             clt_w_stats,
         );
+        clt_w.set_burst_limit(limit_config.burst_south);
 
         let client_addr = self.ctx.client_addr();
         if let Err(e) = self.run(clt_r, clt_w).await {
@@ -123,6 +125,25 @@ impl ClientHelloAcceptTask {
             ServerTaskError::ClientAppTimeout("timeout to receive full client request")
         })??;
 
+        let tls_fingerprint = if protocol == Protocol::TlsModern {
+            // the full client hello msg has been read into the buffer
+            TlsClientHello::parse_records(clt_r_buf.chunk())
+                .ok()
+                .map(|hello| TlsClientFingerprint::new(&hello))
+        } else {
+            None
+        };
+        if let Some(fingerprint) = &tls_fingerprint {
+            if let Some(audit_handle) = &self.ctx.audit_handle {
+                if audit_handle.tls_fingerprint_blocked(fingerprint) {
+                    // just close the connection
+                    return Err(ServerTaskError::ForbiddenByRule(
+                        ServerTaskForbiddenError::TlsFingerprintBlocked,
+                    ));
+                }
+            }
+        }
+
         if let Some(allowed_sites) = &self.ctx.server_config.allowed_sites {
             if let Some(site) = allowed_sites.get(upstream.host()) {
                 let final_upstream = site.redirect(&upstream);
@@ -132,6 +153,7 @@ impl ClientHelloAcceptTask {
                     final_upstream,
                     self.time_accepted.elapsed(),
                     self.pre_handshake_stats.as_ref().clone(),
+                    tls_fingerprint,
                 )
                 .into_running(clt_r, clt_r_buf, clt_w)
                 .await;
@@ -149,6 +171,7 @@ impl ClientHelloAcceptTask {
                 upstream,
                 self.time_accepted.elapsed(),
                 self.pre_handshake_stats.as_ref().clone(),
+                tls_fingerprint,
             )
             .into_running(clt_r, clt_r_buf, clt_w)
             .await;
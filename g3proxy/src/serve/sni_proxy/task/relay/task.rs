@@ -22,7 +22,7 @@ use log::debug;
 use tokio::io::{AsyncRead, AsyncWrite};
 
 use g3_daemon::stat::task::{TcpStreamConnectionStats, TcpStreamTaskStats};
-use g3_dpi::Protocol;
+use g3_dpi::{Protocol, TlsClientFingerprint};
 use g3_io_ext::{FlexBufReader, LimitedCopy, LimitedReader, LimitedWriter, OnceBufReader};
 use g3_types::net::UpstreamAddr;
 
@@ -32,7 +32,9 @@ use crate::inspect::{StreamInspectContext, StreamInspection};
 use crate::log::task::tcp_connect::TaskLogForTcpConnect;
 use crate::module::tcp_connect::TcpConnectTaskNotes;
 use crate::serve::tcp_stream::TcpStreamTaskCltWrapperStats;
-use crate::serve::{ServerTaskError, ServerTaskNotes, ServerTaskResult, ServerTaskStage};
+use crate::serve::{
+    alive_task, ServerTaskError, ServerTaskNotes, ServerTaskResult, ServerTaskStage,
+};
 
 pub(crate) struct TcpStreamTask {
     ctx: CommonTaskContext,
@@ -49,8 +51,12 @@ impl TcpStreamTask {
         upstream: UpstreamAddr,
         wait_time: Duration,
         pre_handshake_stats: TcpStreamConnectionStats,
+        tls_fingerprint: Option<TlsClientFingerprint>,
     ) -> Self {
-        let task_notes = ServerTaskNotes::new(ctx.cc_info.clone(), None, wait_time);
+        let mut task_notes = ServerTaskNotes::new(ctx.cc_info.clone(), None, wait_time);
+        task_notes.client_tls_fingerprint = tls_fingerprint;
+        task_notes.set_duration_recorder(ctx.server_stats.duration.recorder());
+        task_notes.register_alive(ctx.server_config.name(), &upstream);
         TcpStreamTask {
             ctx,
             protocol,
@@ -82,7 +88,8 @@ impl TcpStreamTask {
         W: AsyncWrite + Send + Sync + Unpin + 'static,
     {
         self.pre_start();
-        match self.run(clt_r, clt_r_buf, clt_w).await {
+        let kill_token = self.task_notes.alive_kill_token();
+        match alive_task::run_killable(kill_token, self.run(clt_r, clt_r_buf, clt_w)).await {
             Ok(_) => self
                 .get_log_context()
                 .log(&self.ctx.task_logger, &ServerTaskError::Finished),
@@ -101,6 +108,7 @@ impl TcpStreamTask {
         );
         self.ctx.server_stats.add_task();
         self.ctx.server_stats.inc_alive_task();
+        self.task_notes.set_alive_io_stats(self.task_stats.clone());
     }
 
     fn pre_stop(&self) {
@@ -180,7 +188,12 @@ impl TcpStreamTask {
         clt_r.reset_stats(clt_r_stats);
         clt_w.reset_stats(clt_w_stats);
 
-        if let Some(audit_handle) = self.ctx.audit_handle.take() {
+        if let Some(audit_handle) = self
+            .ctx
+            .audit_handle
+            .take()
+            .filter(|h| h.do_application_audit())
+        {
             let ctx = StreamInspectContext::new(
                 audit_handle,
                 self.ctx.server_config.clone(),
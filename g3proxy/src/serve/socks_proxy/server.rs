@@ -20,6 +20,7 @@ use std::sync::Arc;
 use anyhow::anyhow;
 use arc_swap::{ArcSwap, ArcSwapOption};
 use async_trait::async_trait;
+use ip_network_table::IpNetworkTable;
 #[cfg(feature = "quic")]
 use quinn::Connection;
 use slog::Logger;
@@ -50,6 +51,7 @@ pub(crate) struct SocksProxyServer {
     server_stats: Arc<SocksProxyServerStats>,
     listen_stats: Arc<ListenStats>,
     ingress_net_filter: Option<Arc<AclNetworkRule>>,
+    auth_free_networks: Option<Arc<IpNetworkTable<()>>>,
     dst_host_filter: Option<Arc<AclDstHostRuleSet>>,
     reload_sender: broadcast::Sender<ServerReloadCommand>,
     task_logger: Logger,
@@ -75,6 +77,16 @@ impl SocksProxyServer {
             .as_ref()
             .map(|builder| Arc::new(builder.build()));
 
+        let auth_free_networks = if config.auth_free_networks.is_empty() {
+            None
+        } else {
+            let mut table = IpNetworkTable::new();
+            for net in &config.auth_free_networks {
+                table.insert(*net, ());
+            }
+            Some(Arc::new(table))
+        };
+
         let dst_host_filter = config
             .dst_host_filter
             .as_ref()
@@ -83,6 +95,9 @@ impl SocksProxyServer {
         let task_logger = config.get_task_logger();
 
         server_stats.set_extra_tags(config.extra_metrics_tags.clone());
+        server_stats
+            .duration
+            .set_config(config.duration_stats.as_ref());
 
         let escaper = Arc::new(crate::escape::get_or_insert_default(config.escaper()));
         let user_group = config.get_user_group();
@@ -93,6 +108,7 @@ impl SocksProxyServer {
             server_stats,
             listen_stats,
             ingress_net_filter,
+            auth_free_networks,
             dst_host_filter,
             reload_sender,
             task_logger,
@@ -158,6 +174,7 @@ impl SocksProxyServer {
             escaper: self.escaper.load().as_ref().clone(),
             audit_handle: self.audit_handle.load_full(),
             ingress_net_filter: self.ingress_net_filter.clone(),
+            auth_free_networks: self.auth_free_networks.clone(),
             dst_host_filter: self.dst_host_filter.clone(),
             cc_info,
             task_logger: self.task_logger.clone(),
@@ -24,7 +24,8 @@ use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::stats::{StatId, TcpIoSnapshot, TcpIoStats, UdpIoSnapshot, UdpIoStats};
 
 use crate::serve::{
-    ServerForbiddenSnapshot, ServerForbiddenStats, ServerPerTaskStats, ServerStats,
+    ServerDurationStats, ServerForbiddenSnapshot, ServerForbiddenStats, ServerPerTaskStats,
+    ServerStats, ServerTaskDurationStats,
 };
 
 pub(crate) struct SocksProxyServerStats {
@@ -37,6 +38,7 @@ pub(crate) struct SocksProxyServerStats {
     conn_total: AtomicU64,
 
     pub(crate) forbidden: ServerForbiddenStats,
+    pub(crate) duration: ServerDurationStats,
 
     pub(crate) task_tcp_connect: ServerPerTaskStats,
     pub(crate) task_udp_associate: ServerPerTaskStats,
@@ -55,6 +57,7 @@ impl SocksProxyServerStats {
             online: AtomicIsize::new(0),
             conn_total: AtomicU64::new(0),
             forbidden: Default::default(),
+            duration: Default::default(),
             task_tcp_connect: Default::default(),
             task_udp_associate: Default::default(),
             task_udp_connect: Default::default(),
@@ -135,4 +138,8 @@ impl ServerStats for SocksProxyServerStats {
     fn forbidden_stats(&self) -> ServerForbiddenSnapshot {
         self.forbidden.snapshot()
     }
+
+    fn duration_stats(&self) -> Option<Arc<ServerTaskDurationStats>> {
+        self.duration.stats()
+    }
 }
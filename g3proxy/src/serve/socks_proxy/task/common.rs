@@ -17,6 +17,7 @@
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
+use ip_network_table::IpNetworkTable;
 use slog::Logger;
 use tokio::net::UdpSocket;
 
@@ -38,17 +39,41 @@ pub(crate) struct CommonTaskContext {
     pub(crate) escaper: ArcEscaper,
     pub(crate) audit_handle: Option<Arc<AuditHandle>>,
     pub(crate) ingress_net_filter: Option<Arc<AclNetworkRule>>,
+    pub(crate) auth_free_networks: Option<Arc<IpNetworkTable<()>>>,
     pub(crate) dst_host_filter: Option<Arc<AclDstHostRuleSet>>,
     pub(crate) cc_info: ClientConnectionInfo,
     pub(crate) task_logger: Logger,
 }
 
 impl CommonTaskContext {
+    pub(crate) fn task_escaper(&self, task_notes: &ServerTaskNotes) -> ArcEscaper {
+        task_notes
+            .user_escaper()
+            .unwrap_or_else(|| Arc::clone(&self.escaper))
+    }
+
+    pub(crate) fn task_audit_handle(
+        &self,
+        task_notes: &ServerTaskNotes,
+    ) -> Option<Arc<AuditHandle>> {
+        match task_notes.user_ctx() {
+            Some(user_ctx) => user_ctx.audit_handle(self.audit_handle.as_ref()),
+            None => self.audit_handle.clone(),
+        }
+    }
+
     #[inline]
     pub(super) fn client_addr(&self) -> SocketAddr {
         self.cc_info.client_addr()
     }
 
+    pub(super) fn is_auth_free_client(&self) -> bool {
+        self.auth_free_networks
+            .as_ref()
+            .map(|table| table.longest_match(self.client_addr().ip()).is_some())
+            .unwrap_or(false)
+    }
+
     #[inline]
     pub(super) fn server_addr(&self) -> SocketAddr {
         self.cc_info.server_addr()
@@ -86,6 +111,24 @@ impl CommonTaskContext {
         default_action
     }
 
+    /// Get the TLS server name in the QUIC Initial packet sent by the client,
+    /// so it can be checked by the same dst host filter as the target address
+    #[cfg(feature = "quic")]
+    pub(super) fn quic_sni_upstream(&self, payload: &[u8], port: u16) -> Option<UpstreamAddr> {
+        if !self.server_config.udp_quic_sni_check {
+            return None;
+        }
+        g3_dpi::check_quic_client_initial(payload)?;
+        let packet = g3_dpi::QuicInitialPacket::parse(payload).ok()?;
+        let domain = packet.server_name()?;
+        UpstreamAddr::from_host_str_and_port(domain, port).ok()
+    }
+
+    #[cfg(not(feature = "quic"))]
+    pub(super) fn quic_sni_upstream(&self, _payload: &[u8], _port: u16) -> Option<UpstreamAddr> {
+        None
+    }
+
     fn select_bind_ip(&self, ref_ip: IpAddr) -> Option<IpAddr> {
         match ref_ip {
             IpAddr::V4(_) => fastrand::choice(&self.server_config.udp_bind4).copied(),
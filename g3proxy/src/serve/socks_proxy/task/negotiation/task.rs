@@ -16,7 +16,7 @@
 
 use std::sync::Arc;
 
-use log::debug;
+use log::{debug, warn};
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, BufReader};
 use tokio::net::TcpStream;
 use tokio::time::Instant;
@@ -29,7 +29,7 @@ use super::tcp_connect::SocksProxyTcpConnectTask;
 use super::udp_associate::SocksProxyUdpAssociateTask;
 use super::udp_connect::SocksProxyUdpConnectTask;
 use super::{CommonTaskContext, SocksProxyCltWrapperStats};
-use crate::auth::{UserContext, UserGroup};
+use crate::auth::{User, UserContext, UserGroup, UserType};
 use crate::config::server::ServerConfig;
 use crate::serve::{
     ServerStats, ServerTaskError, ServerTaskForbiddenError, ServerTaskNotes, ServerTaskResult,
@@ -57,18 +57,20 @@ impl SocksProxyNegotiationTask {
             SocksProxyCltWrapperStats::new_pair(&self.ctx.server_stats);
         let limit_config = &self.ctx.server_config.tcp_sock_speed_limit;
         let (clt_r, clt_w) = stream.into_split();
-        let clt_r = LimitedReader::new(
+        let mut clt_r = LimitedReader::new(
             clt_r,
             limit_config.shift_millis,
             limit_config.max_north,
             clt_r_stats,
         );
-        let clt_w = LimitedWriter::new(
+        clt_r.set_burst_limit(limit_config.burst_north);
+        let mut clt_w = LimitedWriter::new(
             clt_w,
             limit_config.shift_millis,
             limit_config.max_south,
             clt_w_stats,
         );
+        clt_w.set_burst_limit(limit_config.burst_south);
 
         let client_addr = self.ctx.client_addr();
         if let Err(e) = self.run(BufReader::new(clt_r), clt_w).await {
@@ -125,6 +127,32 @@ impl SocksProxyNegotiationTask {
             .unwrap_or_default()
     }
 
+    /// get the auth free user for clients from auth free networks, they will be denied if the
+    /// configured user is not found, instead of falling back to the anonymous user
+    fn get_auth_free_user(
+        &self,
+        user_group: &UserGroup,
+    ) -> ServerTaskResult<Option<(String, Arc<User>, UserType)>> {
+        if !self.ctx.is_auth_free_client() {
+            return Ok(None);
+        }
+        let Some(name) = &self.ctx.server_config.auth_free_user else {
+            return Ok(None);
+        };
+        match user_group.get_named_user(name) {
+            Some((user, user_type)) => Ok(Some((name.to_string(), user, user_type))),
+            None => {
+                warn!(
+                    "server {}: auth free user {name} is not found in user-group {}",
+                    self.ctx.server_config.name(),
+                    self.ctx.server_config.user_group()
+                );
+                self.ctx.server_stats.forbidden.add_auth_failed();
+                Err(ServerTaskError::ClientAuthFailed)
+            }
+        }
+    }
+
     async fn run_v4<CDR, CDW>(
         self,
         mut clt_r: BufReader<LimitedReader<CDR>>,
@@ -134,18 +162,64 @@ impl SocksProxyNegotiationTask {
         CDR: AsyncRead + Send + Sync + Unpin + 'static,
         CDW: AsyncWrite + Send + Sync + Unpin + 'static,
     {
-        if self.user_group.is_some() {
-            // socks4(a) doesn't support auth
-            self.ctx.server_stats.forbidden.add_auth_failed();
-            return Err(ServerTaskError::InvalidClientProtocol(
-                "socks4 does not support auth",
-            ));
-        }
+        let user_ctx = if let Some(user_group) = &self.user_group {
+            // socks4(a) doesn't support auth, so only the auth free user or the anonymous user can be used
+            let user_ctx =
+                if let Some((username, user, user_type)) = self.get_auth_free_user(user_group)? {
+                    UserContext::new(
+                        Some(username),
+                        user,
+                        user_type,
+                        self.ctx.server_config.name(),
+                        self.ctx.server_stats.share_extra_tags(),
+                    )
+                } else if let Some((user, user_type)) = user_group.get_anonymous_user() {
+                    UserContext::new(
+                        None,
+                        user,
+                        user_type,
+                        self.ctx.server_config.name(),
+                        self.ctx.server_stats.share_extra_tags(),
+                    )
+                } else {
+                    self.ctx.server_stats.forbidden.add_auth_failed();
+                    return Err(ServerTaskError::InvalidClientProtocol(
+                        "socks4 does not support auth",
+                    ));
+                };
+            user_ctx.req_stats().conn_total.add_socks();
+            Some(user_ctx)
+        } else {
+            None
+        };
 
         let req = v4a::SocksV4aRequest::recv(&mut clt_r).await?;
 
-        let task_notes =
-            ServerTaskNotes::new(self.ctx.cc_info.clone(), None, self.time_accepted.elapsed());
+        let conn_alive_permit = match &user_ctx {
+            Some(user_ctx) => match user_ctx.acquire_connection_semaphore() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    let _ = v4a::SocksV4Reply::RequestRejectedOrFailed
+                        .send(&mut clt_w)
+                        .await;
+                    return Err(ServerTaskError::ForbiddenByRule(
+                        ServerTaskForbiddenError::ConnectionLimited,
+                    ));
+                }
+            },
+            None => None,
+        };
+
+        let path_selection = self.get_egress_path_selection(user_ctx.as_ref());
+        let mut task_notes = ServerTaskNotes::with_path_selection(
+            self.ctx.cc_info.clone(),
+            user_ctx,
+            self.time_accepted.elapsed(),
+            path_selection,
+        );
+        task_notes.user_conn_alive_permit = conn_alive_permit;
+        task_notes.set_duration_recorder(self.ctx.server_stats.duration.recorder());
+        task_notes.register_alive(self.ctx.server_config.name(), &req.upstream);
         match req.command {
             SocksCommand::TcpConnect => {
                 let task = SocksProxyTcpConnectTask::new(
@@ -179,8 +253,24 @@ impl SocksProxyNegotiationTask {
         CDW: AsyncWrite + Send + Sync + Unpin + 'static,
     {
         let client_methods = v5::auth::recv_methods_from_client(&mut clt_r).await?;
+        let mut auth_free_user = None;
         let auth_method = if let Some(user_group) = &self.user_group {
-            if client_methods.contains(&SocksAuthMethod::User) {
+            if client_methods.contains(&SocksAuthMethod::None) {
+                auth_free_user = match self.get_auth_free_user(user_group) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        let _ = v5::auth::send_method_to_client(
+                            &mut clt_w,
+                            &SocksAuthMethod::NoAcceptable,
+                        )
+                        .await;
+                        return Err(e);
+                    }
+                };
+            }
+            if auth_free_user.is_some() {
+                SocksAuthMethod::None
+            } else if client_methods.contains(&SocksAuthMethod::User) {
                 SocksAuthMethod::User
             } else if user_group.allow_anonymous() {
                 SocksAuthMethod::None
@@ -203,7 +293,17 @@ impl SocksProxyNegotiationTask {
 
         let user_ctx = match auth_method {
             SocksAuthMethod::None => {
-                if let Some(user_group) = &self.user_group {
+                if let Some((username, user, user_type)) = auth_free_user {
+                    let user_ctx = UserContext::new(
+                        Some(username),
+                        user,
+                        user_type,
+                        self.ctx.server_config.name(),
+                        self.ctx.server_stats.share_extra_tags(),
+                    );
+                    user_ctx.req_stats().conn_total.add_socks();
+                    Some(user_ctx)
+                } else if let Some(user_group) = &self.user_group {
                     if let Some((user, user_type)) = user_group.get_anonymous_user() {
                         let user_ctx = UserContext::new(
                             None,
@@ -232,7 +332,10 @@ impl SocksProxyNegotiationTask {
                             self.ctx.server_config.name(),
                             self.ctx.server_stats.share_extra_tags(),
                         );
-                        match user_ctx.check_password(password.as_original()) {
+                        match user_group
+                            .check_password(&user_ctx, password.as_original())
+                            .await
+                        {
                             Ok(_) => {
                                 user_ctx.req_stats().conn_total.add_socks();
                                 v5::auth::send_user_auth_success(&mut clt_w)
@@ -269,13 +372,29 @@ impl SocksProxyNegotiationTask {
 
         let req = v5::Socks5Request::recv(&mut clt_r).await?;
 
+        let conn_alive_permit = match &user_ctx {
+            Some(user_ctx) => match user_ctx.acquire_connection_semaphore() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    let _ = v5::Socks5Reply::ForbiddenByRule.send(&mut clt_w).await;
+                    return Err(ServerTaskError::ForbiddenByRule(
+                        ServerTaskForbiddenError::ConnectionLimited,
+                    ));
+                }
+            },
+            None => None,
+        };
+
         let path_selection = self.get_egress_path_selection(user_ctx.as_ref());
-        let task_notes = ServerTaskNotes::with_path_selection(
+        let mut task_notes = ServerTaskNotes::with_path_selection(
             self.ctx.cc_info.clone(),
             user_ctx,
             self.time_accepted.elapsed(),
             path_selection,
         );
+        task_notes.user_conn_alive_permit = conn_alive_permit;
+        task_notes.set_duration_recorder(self.ctx.server_stats.duration.recorder());
+        task_notes.register_alive(self.ctx.server_config.name(), &req.upstream);
         match req.command {
             SocksCommand::TcpConnect => {
                 let task = SocksProxyTcpConnectTask::new(
@@ -19,22 +19,25 @@ use std::sync::Arc;
 
 use log::debug;
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
 
 use g3_daemon::stat::task::TcpStreamTaskStats;
-use g3_io_ext::{LimitedReader, LimitedWriter};
+use g3_io_ext::{DelayedReader, LimitedReader, LimitedWriter};
 use g3_socks::{v4a, v5, SocksVersion};
 use g3_types::acl::AclAction;
 use g3_types::net::{ProxyRequestType, UpstreamAddr};
 
 use super::{CommonTaskContext, TcpConnectTaskCltWrapperStats};
+use crate::audit::AuditHandle;
 use crate::config::server::ServerConfig;
 use crate::inspect::StreamInspectContext;
 use crate::log::task::tcp_connect::TaskLogForTcpConnect;
 use crate::module::tcp_connect::TcpConnectTaskNotes;
 use crate::serve::{
-    ServerStats, ServerTaskError, ServerTaskForbiddenError, ServerTaskNotes, ServerTaskResult,
-    ServerTaskStage,
+    alive_task, ServerStats, ServerTaskError, ServerTaskForbiddenError, ServerTaskNotes,
+    ServerTaskResult, ServerTaskStage,
 };
+use crate::stat::types::{TrafficProtocolCell, TrafficProtocolHint};
 
 pub(crate) struct SocksProxyTcpConnectTask {
     socks_version: SocksVersion,
@@ -89,7 +92,8 @@ impl SocksProxyTcpConnectTask {
     {
         tokio::spawn(async move {
             self.pre_start();
-            match self.run(clt_r, clt_w).await {
+            let kill_token = self.task_notes.alive_kill_token();
+            match alive_task::run_killable(kill_token, self.run(clt_r, clt_w)).await {
                 Ok(_) => self
                     .get_log_context()
                     .log(&self.ctx.task_logger, &ServerTaskError::Finished),
@@ -109,6 +113,7 @@ impl SocksProxyTcpConnectTask {
         );
         self.ctx.server_stats.task_tcp_connect.add_task();
         self.ctx.server_stats.task_tcp_connect.inc_alive_task();
+        self.task_notes.set_alive_io_stats(self.task_stats.clone());
 
         if let Some(user_ctx) = self.task_notes.user_ctx() {
             user_ctx.foreach_req_stats(|s| {
@@ -228,6 +233,20 @@ impl SocksProxyTcpConnectTask {
             self.handle_user_acl_action(action, &mut clt_w, ServerTaskForbiddenError::SrcBlocked)
                 .await?;
 
+            if user_ctx.check_time_window().is_err() {
+                self.reply_forbidden(&mut clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::OutOfTimeWindow,
+                ));
+            }
+
+            if user_ctx.check_traffic_quota().is_err() {
+                self.reply_forbidden(&mut clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
             if user_ctx.check_rate_limit().is_err() {
                 self.reply_forbidden(&mut clt_w).await;
                 return Err(ServerTaskError::ForbiddenByRule(
@@ -272,16 +291,17 @@ impl SocksProxyTcpConnectTask {
             })?;
 
         self.task_notes.stage = ServerTaskStage::Connecting;
-        match self
-            .ctx
-            .escaper
+        let escaper = self.ctx.task_escaper(&self.task_notes);
+        let connect_start = Instant::now();
+        let r = escaper
             .tcp_setup_connection(
                 &mut self.tcp_notes,
                 &self.task_notes,
                 self.task_stats.clone() as _,
             )
-            .await
-        {
+            .await;
+        self.task_notes.trace_span("connect", connect_start);
+        match r {
             Ok((ups_r, ups_w)) => {
                 self.task_notes.stage = ServerTaskStage::Connected;
                 self.run_connected(clt_r, clt_w, ups_r, ups_w).await
@@ -365,40 +385,30 @@ impl SocksProxyTcpConnectTask {
         UR: AsyncRead + Send + Sync + Unpin + 'static,
         UW: AsyncWrite + Send + Sync + Unpin + 'static,
     {
-        self.update_clt(&mut clt_r, &mut clt_w);
-
-        if let Some(audit_handle) = &self.ctx.audit_handle {
-            let do_protocol_inspection = self
-                .task_notes
-                .user_ctx()
-                .map(|ctx| {
-                    let user_config = &ctx.user_config().audit;
-                    user_config.enable_protocol_inspection
-                        && user_config
-                            .do_application_audit()
-                            .unwrap_or_else(|| audit_handle.do_application_audit())
-                })
-                .unwrap_or_else(|| audit_handle.do_application_audit());
-
-            if do_protocol_inspection {
-                let ctx = StreamInspectContext::new(
-                    audit_handle.clone(),
-                    self.ctx.server_config.clone(),
-                    self.ctx.server_stats.clone(),
-                    self.ctx.server_quit_policy.clone(),
-                    &self.task_notes,
-                );
-                return crate::inspect::stream::transit_with_inspection(
-                    clt_r,
-                    clt_w,
-                    ups_r,
-                    ups_w,
-                    ctx,
-                    self.tcp_notes.upstream.clone(),
-                    None,
-                )
-                .await;
-            }
+        let inspect_audit_handle = self.protocol_inspection_audit_handle();
+        let traffic_protocol = self.new_traffic_protocol(inspect_audit_handle.is_some());
+        self.update_clt(&mut clt_r, &mut clt_w, &traffic_protocol);
+        let (clt_r, ups_r) = self.add_stream_delay(clt_r, ups_r);
+
+        if let Some(audit_handle) = inspect_audit_handle {
+            let mut ctx = StreamInspectContext::new(
+                audit_handle,
+                self.ctx.server_config.clone(),
+                self.ctx.server_stats.clone(),
+                self.ctx.server_quit_policy.clone(),
+                &self.task_notes,
+            );
+            ctx.set_traffic_protocol(traffic_protocol);
+            return crate::inspect::stream::transit_with_inspection(
+                clt_r,
+                clt_w,
+                ups_r,
+                ups_w,
+                ctx,
+                self.tcp_notes.upstream.clone(),
+                None,
+            )
+            .await;
         }
 
         crate::inspect::stream::transit_transparent(
@@ -413,13 +423,63 @@ impl SocksProxyTcpConnectTask {
         .await
     }
 
-    fn update_clt<CR, CW>(&mut self, clt_r: &mut LimitedReader<CR>, clt_w: &mut LimitedWriter<CW>)
-    where
+    fn protocol_inspection_audit_handle(&self) -> Option<Arc<AuditHandle>> {
+        let audit_handle = self.ctx.task_audit_handle(&self.task_notes)?;
+        let do_protocol_inspection = self
+            .task_notes
+            .user_ctx()
+            .map(|ctx| {
+                let user_config = &ctx.user_config().audit;
+                user_config.enable_protocol_inspection
+                    && user_config
+                        .do_application_audit()
+                        .unwrap_or_else(|| audit_handle.do_application_audit())
+            })
+            .unwrap_or_else(|| audit_handle.do_application_audit());
+        do_protocol_inspection.then_some(audit_handle)
+    }
+
+    /// the protocol will be set by the protocol inspection if enabled,
+    /// or else it's guessed by the upstream port
+    fn new_traffic_protocol(&self, do_inspection: bool) -> Arc<TrafficProtocolCell> {
+        if do_inspection {
+            Arc::new(TrafficProtocolCell::pending())
+        } else {
+            let protocol = TrafficProtocolHint::from_tcp_port(self.tcp_notes.upstream.port());
+            Arc::new(TrafficProtocolCell::new(protocol))
+        }
+    }
+
+    fn add_stream_delay<CR, UR>(
+        &self,
+        clt_r: CR,
+        ups_r: UR,
+    ) -> (DelayedReader<CR>, DelayedReader<UR>) {
+        let delay = self
+            .task_notes
+            .user_ctx()
+            .and_then(|ctx| ctx.tcp_stream_delay())
+            .unwrap_or_default();
+        (
+            DelayedReader::new(clt_r, delay),
+            DelayedReader::new(ups_r, delay),
+        )
+    }
+
+    fn update_clt<CR, CW>(
+        &mut self,
+        clt_r: &mut LimitedReader<CR>,
+        clt_w: &mut LimitedWriter<CW>,
+        traffic_protocol: &Arc<TrafficProtocolCell>,
+    ) where
         CR: AsyncRead + Unpin,
         CW: AsyncWrite + Unpin,
     {
-        let mut wrapper_stats =
-            TcpConnectTaskCltWrapperStats::new(&self.ctx.server_stats, &self.task_stats);
+        let mut wrapper_stats = TcpConnectTaskCltWrapperStats::new(
+            &self.ctx.server_stats,
+            &self.task_stats,
+            traffic_protocol,
+        );
 
         if let Some(user_ctx) = self.task_notes.user_ctx() {
             wrapper_stats.push_user_io_stats(user_ctx.fetch_traffic_stats(
@@ -427,16 +487,14 @@ impl SocksProxyTcpConnectTask {
                 self.ctx.server_stats.share_extra_tags(),
             ));
 
-            let user_config = user_ctx.user_config();
-            if !user_config
-                .tcp_sock_speed_limit
-                .eq(&self.ctx.server_config.tcp_sock_speed_limit)
-            {
-                let limit_config = user_config
-                    .tcp_sock_speed_limit
-                    .shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
+            let user_limit = user_ctx.tcp_sock_speed_limit();
+            if !user_limit.eq(&self.ctx.server_config.tcp_sock_speed_limit) {
+                let limit_config =
+                    user_limit.shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
                 clt_r.reset_limit(limit_config.shift_millis, limit_config.max_north);
+                clt_r.set_burst_limit(limit_config.burst_north);
                 clt_w.reset_limit(limit_config.shift_millis, limit_config.max_south);
+                clt_w.set_burst_limit(limit_config.burst_south);
             }
         }
         let (clt_r_stats, clt_w_stats) = wrapper_stats.split();
@@ -19,10 +19,12 @@ use super::{CommonTaskContext, SocksProxyServerStats};
 mod task;
 pub(super) use task::SocksProxyUdpAssociateTask;
 
+mod protocol;
 mod recv;
 mod send;
 mod stats;
 
+use protocol::UdpAssociateProtocolStats;
 use recv::Socks5UdpAssociateClientRecv;
 use send::Socks5UdpAssociateClientSend;
 use stats::{UdpAssociateTaskCltWrapperStats, UdpAssociateTaskStats};
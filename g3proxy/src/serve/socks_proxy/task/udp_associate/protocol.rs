@@ -0,0 +1,91 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::{Arc, Mutex};
+
+use ahash::AHashMap;
+
+use g3_types::net::UpstreamAddr;
+
+use crate::auth::UserTrafficStats;
+use crate::stat::types::TrafficProtocolHint;
+
+/// the max number of remote peers to remember the detected protocol for
+const MAX_TRACKED_PEERS: usize = 1024;
+
+/// The per protocol user traffic stats for udp associate tasks.
+///
+/// The protocol is detected from each packet sent by the client, and will be remembered
+/// for the remote peer, so the following packets from or to the same peer will use it.
+pub(super) struct UdpAssociateProtocolStats {
+    peers: Mutex<AHashMap<UpstreamAddr, TrafficProtocolHint>>,
+    user_stats: Vec<Arc<UserTrafficStats>>,
+}
+
+impl UdpAssociateProtocolStats {
+    pub(super) fn new(user_stats: Vec<Arc<UserTrafficStats>>) -> Self {
+        UdpAssociateProtocolStats {
+            peers: Mutex::new(AHashMap::new()),
+            user_stats,
+        }
+    }
+
+    fn detect(&self, upstream: &UpstreamAddr, payload: &[u8]) -> TrafficProtocolHint {
+        let protocol = TrafficProtocolHint::from_udp_client_packet(payload);
+        let mut peers = self.peers.lock().unwrap();
+        if protocol == TrafficProtocolHint::Other {
+            peers
+                .get(upstream)
+                .copied()
+                .unwrap_or(TrafficProtocolHint::Other)
+        } else {
+            if peers.len() < MAX_TRACKED_PEERS || peers.contains_key(upstream) {
+                peers.insert(upstream.clone(), protocol);
+            }
+            protocol
+        }
+    }
+
+    pub(super) fn add_client_packet(&self, upstream: &UpstreamAddr, payload: &[u8], size: usize) {
+        if self.user_stats.is_empty() {
+            return;
+        }
+        let protocol = self.detect(upstream, payload);
+        for s in &self.user_stats {
+            let stats = s.io.protocol.get(protocol);
+            stats.add_in_bytes(size as u64);
+            stats.add_in_packet();
+        }
+    }
+
+    pub(super) fn add_remote_packet(&self, upstream: &UpstreamAddr, size: usize) {
+        if self.user_stats.is_empty() {
+            return;
+        }
+        let protocol = self
+            .peers
+            .lock()
+            .unwrap()
+            .get(upstream)
+            .copied()
+            .unwrap_or(TrafficProtocolHint::Other);
+        for s in &self.user_stats {
+            let stats = s.io.protocol.get(protocol);
+            stats.add_out_bytes(size as u64);
+            stats.add_out_packet();
+        }
+    }
+}
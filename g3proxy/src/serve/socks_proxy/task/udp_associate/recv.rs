@@ -32,7 +32,7 @@ use g3_socks::v5::UdpInput;
 use g3_types::acl::{AclAction, AclNetworkRule};
 use g3_types::net::UpstreamAddr;
 
-use super::CommonTaskContext;
+use super::{CommonTaskContext, UdpAssociateProtocolStats};
 use crate::auth::UserContext;
 
 pub(super) struct Socks5UdpAssociateClientRecv<T> {
@@ -40,6 +40,7 @@ pub(super) struct Socks5UdpAssociateClientRecv<T> {
     client_addr: SocketAddr,
     ctx: Arc<CommonTaskContext>,
     user_ctx: Option<UserContext>,
+    protocol_stats: Option<Arc<UdpAssociateProtocolStats>>,
 }
 
 impl<T> Socks5UdpAssociateClientRecv<T>
@@ -59,9 +60,14 @@ where
             client_addr,
             ctx: Arc::clone(ctx),
             user_ctx: user_ctx.cloned(),
+            protocol_stats: None,
         }
     }
 
+    pub(super) fn set_protocol_stats(&mut self, stats: Arc<UdpAssociateProtocolStats>) {
+        self.protocol_stats = Some(stats);
+    }
+
     pub(super) fn inner(&self) -> &T {
         &self.inner
     }
@@ -134,6 +140,18 @@ where
         Ok(())
     }
 
+    fn check_packet(
+        &self,
+        upstream: &UpstreamAddr,
+        payload: &[u8],
+    ) -> Result<(), UdpRelayClientError> {
+        self.check_upstream(upstream)?;
+        if let Some(sni_upstream) = self.ctx.quic_sni_upstream(payload, upstream.port()) {
+            self.check_upstream(&sni_upstream)?;
+        }
+        Ok(())
+    }
+
     fn poll_recv(
         &mut self,
         cx: &mut Context<'_>,
@@ -143,7 +161,10 @@ where
 
         let (off, upstream) = UdpInput::parse_header(buf)
             .map_err(|e| UdpRelayClientError::InvalidPacket(e.to_string()))?;
-        self.check_upstream(&upstream)?;
+        self.check_packet(&upstream, &buf[off..nr])?;
+        if let Some(stats) = &self.protocol_stats {
+            stats.add_client_packet(&upstream, &buf[off..nr], nr);
+        }
         Poll::Ready(Ok((off, nr, upstream)))
     }
 
@@ -194,7 +215,7 @@ where
         let (off, upstream) = UdpInput::parse_header(buf)
             .map_err(|e| UdpRelayClientError::InvalidPacket(e.to_string()))?;
         *initial_peer = upstream;
-        self.check_upstream(initial_peer)?;
+        self.check_packet(initial_peer, &buf[off..nr])?;
         Poll::Ready(Ok((off, nr)))
     }
 
@@ -258,6 +279,10 @@ where
         for (p, m) in packets.iter_mut().take(count).zip(meta) {
             let (off, ups) = UdpInput::parse_header(&p.buf()[0..m.len])
                 .map_err(|e| UdpRelayClientError::InvalidPacket(e.to_string()))?;
+            self.check_packet(&ups, &p.buf()[off..m.len])?;
+            if let Some(stats) = &self.protocol_stats {
+                stats.add_client_packet(&ups, &p.buf()[off..m.len], m.len);
+            }
 
             p.set_offset(off);
             p.set_length(m.len);
@@ -16,6 +16,7 @@
 
 use std::io::{self, IoSlice};
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::task::{ready, Context, Poll};
 
 use g3_io_ext::{AsyncUdpSend, UdpRelayClientError, UdpRelayClientSend};
@@ -30,21 +31,29 @@ use g3_io_ext::{SendMsgHdr, UdpRelayPacket};
 use g3_socks::v5::SocksUdpHeader;
 use g3_types::net::UpstreamAddr;
 
+use super::UdpAssociateProtocolStats;
+
 pub(super) struct Socks5UdpAssociateClientSend<T> {
     inner: T,
     client: SocketAddr,
     socks_headers: Vec<SocksUdpHeader>,
+    protocol_stats: Option<Arc<UdpAssociateProtocolStats>>,
 }
 
 impl<T> Socks5UdpAssociateClientSend<T>
 where
     T: AsyncUdpSend,
 {
-    pub(super) fn new(inner: T, client: SocketAddr) -> Self {
+    pub(super) fn new(
+        inner: T,
+        client: SocketAddr,
+        protocol_stats: Option<Arc<UdpAssociateProtocolStats>>,
+    ) -> Self {
         Socks5UdpAssociateClientSend {
             inner,
             client,
             socks_headers: vec![SocksUdpHeader::default(); 4],
+            protocol_stats,
         }
     }
 }
@@ -72,6 +81,9 @@ where
                 "write zero byte into sender",
             ))))
         } else {
+            if let Some(stats) = &self.protocol_stats {
+                stats.add_remote_packet(from, nw);
+            }
             Poll::Ready(Ok(nw))
         }
     }
@@ -110,6 +122,11 @@ where
                 "write zero packet into sender",
             ))))
         } else {
+            if let Some(stats) = &self.protocol_stats {
+                for (p, m) in packets.iter().zip(msgs.iter()).take(count) {
+                    stats.add_remote_packet(p.upstream(), m.iov[0].len() + m.iov[1].len());
+                }
+            }
             Poll::Ready(Ok(count))
         }
     }
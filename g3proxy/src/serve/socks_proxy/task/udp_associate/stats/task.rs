@@ -15,8 +15,10 @@
  */
 
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use g3_daemon::stat::task::UdpConnectHalfConnectionStats;
+use g3_io_ext::UdpRelayPacingStats;
 
 use crate::module::udp_relay::UdpRelayTaskRemoteStats;
 
@@ -24,6 +26,7 @@ use crate::module::udp_relay::UdpRelayTaskRemoteStats;
 pub(crate) struct UdpAssociateClientSideStats {
     pub(crate) recv: UdpConnectHalfConnectionStats,
     pub(crate) send: UdpConnectHalfConnectionStats,
+    pub(crate) send_pacing: Arc<UdpRelayPacingStats>,
 }
 
 #[derive(Default)]
@@ -35,15 +35,15 @@ use g3_types::net::ProxyRequestType;
 
 use super::{
     CommonTaskContext, Socks5UdpAssociateClientRecv, Socks5UdpAssociateClientSend,
-    UdpAssociateTaskCltWrapperStats, UdpAssociateTaskStats,
+    UdpAssociateProtocolStats, UdpAssociateTaskCltWrapperStats, UdpAssociateTaskStats,
 };
 use crate::config::server::ServerConfig;
 use crate::log::escape::udp_sendto::EscapeLogForUdpRelaySendto;
 use crate::log::task::udp_associate::TaskLogForUdpAssociate;
 use crate::module::udp_relay::UdpRelayTaskNotes;
 use crate::serve::{
-    ServerStats, ServerTaskError, ServerTaskForbiddenError, ServerTaskNotes, ServerTaskResult,
-    ServerTaskStage,
+    alive_task, ServerStats, ServerTaskError, ServerTaskForbiddenError, ServerTaskNotes,
+    ServerTaskResult, ServerTaskStage,
 };
 
 pub(crate) struct SocksProxyUdpAssociateTask {
@@ -89,6 +89,10 @@ impl SocksProxyUdpAssociateTask {
             remote_rd_packets: self.task_stats.ups.recv.get_packets(),
             remote_wr_bytes: self.task_stats.ups.send.get_bytes(),
             remote_wr_packets: self.task_stats.ups.send.get_packets(),
+            client_wr_pacing: self.ctx.server_config.udp_relay_pacing.map(|_| {
+                let stats = &self.task_stats.clt.send_pacing;
+                (stats.get_dropped_packets(), stats.get_max_queued_packets())
+            }),
         }
     }
 
@@ -99,7 +103,8 @@ impl SocksProxyUdpAssociateTask {
     {
         tokio::spawn(async move {
             self.pre_start();
-            match self.run(clt_r, clt_w).await {
+            let kill_token = self.task_notes.alive_kill_token();
+            match alive_task::run_killable(kill_token, self.run(clt_r, clt_w)).await {
                 Ok(_) => self
                     .get_log_context()
                     .log(&self.ctx.task_logger, &ServerTaskError::ClosedByClient),
@@ -194,6 +199,20 @@ impl SocksProxyUdpAssociateTask {
             )
             .await?;
 
+            if user_ctx.check_time_window().is_err() {
+                self.reply_forbidden(&mut clt_tcp_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::OutOfTimeWindow,
+                ));
+            }
+
+            if user_ctx.check_traffic_quota().is_err() {
+                self.reply_forbidden(&mut clt_tcp_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
             if user_ctx.check_rate_limit().is_err() {
                 self.reply_forbidden(&mut clt_tcp_w).await;
                 return Err(ServerTaskError::ForbiddenByRule(
@@ -281,6 +300,9 @@ impl SocksProxyUdpAssociateTask {
             UdpRelayClientToRemote::new(&mut *clt_r, &mut *ups_w, self.ctx.server_config.udp_relay);
         let mut r_to_c =
             UdpRelayRemoteToClient::new(&mut *clt_w, &mut *ups_r, self.ctx.server_config.udp_relay);
+        if let Some(pacing) = &self.ctx.server_config.udp_relay_pacing {
+            r_to_c.set_pacing(pacing, self.task_stats.clt.send_pacing.clone());
+        }
 
         let idle_duration = self.ctx.server_config.task_idle_check_duration;
         let mut idle_interval =
@@ -388,8 +410,7 @@ impl SocksProxyUdpAssociateTask {
 
         let limit_config = if let Some(user_ctx) = self.task_notes.user_ctx() {
             user_ctx
-                .user_config()
-                .udp_sock_speed_limit
+                .udp_sock_speed_limit()
                 .shrink_as_smaller(&self.ctx.server_config.udp_sock_speed_limit)
         } else {
             self.ctx.server_config.udp_sock_speed_limit
@@ -397,13 +418,17 @@ impl SocksProxyUdpAssociateTask {
         let (clt_r_stats, mut clt_w_stats) =
             UdpAssociateTaskCltWrapperStats::new(&self.ctx.server_stats, &self.task_stats).split();
 
-        let clt_r = LimitedUdpRecv::new(
+        let mut clt_r = LimitedUdpRecv::new(
             clt_r,
             limit_config.shift_millis,
             limit_config.max_north_packets,
             limit_config.max_north_bytes,
             clt_r_stats,
         );
+        clt_r.set_burst_limit(
+            limit_config.burst_north_packets,
+            limit_config.burst_north_bytes,
+        );
 
         let mut clt_r = Socks5UdpAssociateClientRecv::new(
             clt_r,
@@ -420,6 +445,7 @@ impl SocksProxyUdpAssociateTask {
             .await?;
         self.udp_client_addr = Some(udp_client_addr);
 
+        let mut protocol_stats = None;
         if let Some(user_ctx) = self.task_notes.user_ctx_mut() {
             // set user site by using the upstream address of the first packet
             user_ctx.check_in_site(
@@ -446,6 +472,15 @@ impl SocksProxyUdpAssociateTask {
                 s.io.socks_udp_associate.add_in_bytes(p1_size as u64);
                 s.io.socks_udp_associate.add_in_packet();
             }
+            let user_protocol_stats =
+                Arc::new(UdpAssociateProtocolStats::new(user_io_stats.clone()));
+            user_protocol_stats.add_client_packet(
+                &self.udp_notes.initial_peer,
+                &buf[buf_off..buf_nr],
+                p1_size,
+            );
+            clt_r.set_protocol_stats(user_protocol_stats.clone());
+            protocol_stats = Some(user_protocol_stats);
 
             wrapper_stats.push_user_io_stats(user_io_stats);
             let (clt_r_stats, new_clt_w_stats) = wrapper_stats.split();
@@ -461,18 +496,21 @@ impl SocksProxyUdpAssociateTask {
             .map_err(|_| {
                 ServerTaskError::InternalServerError("unable to connect the client side udp socket")
             })?;
-        let clt_w = LimitedUdpSend::new(
+        let mut clt_w = LimitedUdpSend::new(
             clt_w,
             limit_config.shift_millis,
             limit_config.max_south_packets,
             limit_config.max_south_bytes,
             clt_w_stats,
         );
+        clt_w.set_burst_limit(
+            limit_config.burst_south_packets,
+            limit_config.burst_south_bytes,
+        );
 
         self.task_notes.stage = ServerTaskStage::Connecting;
-        let (ups_r, mut ups_w, logger) = self
-            .ctx
-            .escaper
+        let escaper = self.ctx.task_escaper(&self.task_notes);
+        let (ups_r, mut ups_w, logger) = escaper
             .udp_setup_relay(
                 &mut self.udp_notes,
                 &self.task_notes,
@@ -486,7 +524,7 @@ impl SocksProxyUdpAssociateTask {
         })
         .await?;
 
-        let clt_w = Socks5UdpAssociateClientSend::new(clt_w, udp_client_addr);
+        let clt_w = Socks5UdpAssociateClientSend::new(clt_w, udp_client_addr, protocol_stats);
 
         Ok((clt_r, clt_w, ups_r, ups_w, logger))
     }
@@ -20,37 +20,42 @@ use g3_io_ext::{ArcLimitedRecvStats, ArcLimitedSendStats, LimitedRecvStats, Limi
 
 use super::{SocksProxyServerStats, UdpConnectTaskStats};
 use crate::auth::UserTrafficStats;
+use crate::stat::types::TrafficProtocolHint;
 
 trait UdpConnectTaskCltStatsWrapper {
-    fn add_recv_bytes(&self, size: u64);
-    fn add_recv_packet(&self) {
-        self.add_recv_packets(1);
+    fn add_recv_bytes(&self, size: u64, protocol: TrafficProtocolHint);
+    fn add_recv_packet(&self, protocol: TrafficProtocolHint) {
+        self.add_recv_packets(1, protocol);
     }
-    fn add_recv_packets(&self, n: usize);
-    fn add_send_bytes(&self, size: u64);
-    fn add_send_packet(&self) {
-        self.add_send_packets(1);
+    fn add_recv_packets(&self, n: usize, protocol: TrafficProtocolHint);
+    fn add_send_bytes(&self, size: u64, protocol: TrafficProtocolHint);
+    fn add_send_packet(&self, protocol: TrafficProtocolHint) {
+        self.add_send_packets(1, protocol);
     }
-    fn add_send_packets(&self, n: usize);
+    fn add_send_packets(&self, n: usize, protocol: TrafficProtocolHint);
 }
 
 type ArcUdpConnectTaskCltStatsWrapper = Arc<dyn UdpConnectTaskCltStatsWrapper + Send + Sync>;
 
 impl UdpConnectTaskCltStatsWrapper for UserTrafficStats {
-    fn add_recv_bytes(&self, size: u64) {
+    fn add_recv_bytes(&self, size: u64, protocol: TrafficProtocolHint) {
         self.io.socks_udp_connect.add_in_bytes(size);
+        self.io.protocol.get(protocol).add_in_bytes(size);
     }
 
-    fn add_recv_packets(&self, n: usize) {
+    fn add_recv_packets(&self, n: usize, protocol: TrafficProtocolHint) {
         self.io.socks_udp_connect.add_in_packets(n);
+        self.io.protocol.get(protocol).add_in_packets(n);
     }
 
-    fn add_send_bytes(&self, size: u64) {
+    fn add_send_bytes(&self, size: u64, protocol: TrafficProtocolHint) {
         self.io.socks_udp_connect.add_out_bytes(size);
+        self.io.protocol.get(protocol).add_out_bytes(size);
     }
 
-    fn add_send_packets(&self, n: usize) {
+    fn add_send_packets(&self, n: usize, protocol: TrafficProtocolHint) {
         self.io.socks_udp_connect.add_out_packets(n);
+        self.io.protocol.get(protocol).add_out_packets(n);
     }
 }
 
@@ -58,6 +63,7 @@ impl UdpConnectTaskCltStatsWrapper for UserTrafficStats {
 pub(crate) struct UdpConnectTaskCltWrapperStats {
     server: Arc<SocksProxyServerStats>,
     task: Arc<UdpConnectTaskStats>,
+    protocol: TrafficProtocolHint,
     others: Vec<ArcUdpConnectTaskCltStatsWrapper>,
 }
 
@@ -69,10 +75,15 @@ impl UdpConnectTaskCltWrapperStats {
         UdpConnectTaskCltWrapperStats {
             server: Arc::clone(server),
             task: Arc::clone(task),
+            protocol: TrafficProtocolHint::Other,
             others: Vec::with_capacity(2),
         }
     }
 
+    pub(crate) fn set_traffic_protocol_hint(&mut self, protocol: TrafficProtocolHint) {
+        self.protocol = protocol;
+    }
+
     pub(crate) fn push_user_io_stats(&mut self, all: Vec<Arc<UserTrafficStats>>) {
         for s in all {
             self.others.push(s as _);
@@ -90,13 +101,17 @@ impl LimitedRecvStats for UdpConnectTaskCltWrapperStats {
         let size = size as u64;
         self.server.io_udp.add_in_bytes(size);
         self.task.clt.recv.add_bytes(size);
-        self.others.iter().for_each(|s| s.add_recv_bytes(size));
+        self.others
+            .iter()
+            .for_each(|s| s.add_recv_bytes(size, self.protocol));
     }
 
     fn add_recv_packets(&self, n: usize) {
         self.server.io_udp.add_in_packets(n);
         self.task.clt.recv.add_packets(n);
-        self.others.iter().for_each(|s| s.add_recv_packets(n));
+        self.others
+            .iter()
+            .for_each(|s| s.add_recv_packets(n, self.protocol));
     }
 }
 
@@ -105,12 +120,16 @@ impl LimitedSendStats for UdpConnectTaskCltWrapperStats {
         let size = size as u64;
         self.server.io_udp.add_out_bytes(size);
         self.task.clt.send.add_bytes(size);
-        self.others.iter().for_each(|s| s.add_send_bytes(size));
+        self.others
+            .iter()
+            .for_each(|s| s.add_send_bytes(size, self.protocol));
     }
 
     fn add_send_packets(&self, n: usize) {
         self.server.io_udp.add_out_packets(n);
         self.task.clt.send.add_packets(n);
-        self.others.iter().for_each(|s| s.add_send_packets(n));
+        self.others
+            .iter()
+            .for_each(|s| s.add_send_packets(n, self.protocol));
     }
 }
@@ -42,9 +42,10 @@ use crate::log::escape::udp_sendto::EscapeLogForUdpConnectSendTo;
 use crate::log::task::udp_connect::TaskLogForUdpConnect;
 use crate::module::udp_connect::UdpConnectTaskNotes;
 use crate::serve::{
-    ServerStats, ServerTaskError, ServerTaskForbiddenError, ServerTaskNotes, ServerTaskResult,
-    ServerTaskStage,
+    alive_task, ServerStats, ServerTaskError, ServerTaskForbiddenError, ServerTaskNotes,
+    ServerTaskResult, ServerTaskStage,
 };
+use crate::stat::types::TrafficProtocolHint;
 
 pub(crate) struct SocksProxyUdpConnectTask {
     ctx: CommonTaskContext,
@@ -99,7 +100,8 @@ impl SocksProxyUdpConnectTask {
     {
         tokio::spawn(async move {
             self.pre_start();
-            match self.run(clt_r, clt_w).await {
+            let kill_token = self.task_notes.alive_kill_token();
+            match alive_task::run_killable(kill_token, self.run(clt_r, clt_w)).await {
                 Ok(_) => self
                     .get_log_context()
                     .log(&self.ctx.task_logger, &ServerTaskError::ClosedByClient),
@@ -244,6 +246,20 @@ impl SocksProxyUdpConnectTask {
             )
             .await?;
 
+            if user_ctx.check_time_window().is_err() {
+                self.reply_forbidden(&mut clt_tcp_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::OutOfTimeWindow,
+                ));
+            }
+
+            if user_ctx.check_traffic_quota().is_err() {
+                self.reply_forbidden(&mut clt_tcp_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
             if user_ctx.check_rate_limit().is_err() {
                 self.reply_forbidden(&mut clt_tcp_w).await;
                 return Err(ServerTaskError::ForbiddenByRule(
@@ -436,8 +452,7 @@ impl SocksProxyUdpConnectTask {
 
         let limit_config = if let Some(user_ctx) = self.task_notes.user_ctx() {
             user_ctx
-                .user_config()
-                .udp_sock_speed_limit
+                .udp_sock_speed_limit()
                 .shrink_as_smaller(&self.ctx.server_config.udp_sock_speed_limit)
         } else {
             self.ctx.server_config.udp_sock_speed_limit
@@ -445,13 +460,17 @@ impl SocksProxyUdpConnectTask {
         let (clt_r_stats, mut clt_w_stats) =
             UdpConnectTaskCltWrapperStats::new(&self.ctx.server_stats, &self.task_stats).split();
 
-        let clt_r = LimitedUdpRecv::new(
+        let mut clt_r = LimitedUdpRecv::new(
             clt_r,
             limit_config.shift_millis,
             limit_config.max_north_packets,
             limit_config.max_north_bytes,
             clt_r_stats,
         );
+        clt_r.set_burst_limit(
+            limit_config.burst_north_packets,
+            limit_config.burst_north_bytes,
+        );
 
         let mut clt_r = Socks5UdpConnectClientRecv::new(clt_r, self.udp_client_addr);
 
@@ -480,6 +499,8 @@ impl SocksProxyUdpConnectTask {
 
             let mut wrapper_stats =
                 UdpConnectTaskCltWrapperStats::new(&self.ctx.server_stats, &self.task_stats);
+            let protocol = TrafficProtocolHint::from_udp_client_packet(&buf[buf_off..buf_nr]);
+            wrapper_stats.set_traffic_protocol_hint(protocol);
             let user_io_stats = user_ctx.fetch_traffic_stats(
                 self.ctx.server_config.name(),
                 self.ctx.server_stats.share_extra_tags(),
@@ -489,6 +510,8 @@ impl SocksProxyUdpConnectTask {
             for s in &user_io_stats {
                 s.io.socks_udp_connect.add_in_bytes(p1_size as u64);
                 s.io.socks_udp_connect.add_in_packet();
+                s.io.protocol.get(protocol).add_in_bytes(p1_size as u64);
+                s.io.protocol.get(protocol).add_in_packet();
             }
 
             wrapper_stats.push_user_io_stats(user_io_stats);
@@ -503,6 +526,17 @@ impl SocksProxyUdpConnectTask {
         }
         let action = self.ctx.check_upstream(&upstream);
         self.handle_server_upstream_acl_action(action)?;
+        if let Some(sni_upstream) = self
+            .ctx
+            .quic_sni_upstream(&buf[buf_off..buf_nr], upstream.port())
+        {
+            if let Some(user_ctx) = self.task_notes.user_ctx() {
+                let action = user_ctx.check_upstream(&sni_upstream);
+                self.handle_user_upstream_acl_action(action)?;
+            }
+            let action = self.ctx.check_upstream(&sni_upstream);
+            self.handle_server_upstream_acl_action(action)?;
+        }
 
         clt_r
             .inner()
@@ -512,18 +546,21 @@ impl SocksProxyUdpConnectTask {
             .map_err(|_| {
                 ServerTaskError::InternalServerError("unable to connect the client side udp socket")
             })?;
-        let clt_w = LimitedUdpSend::new(
+        let mut clt_w = LimitedUdpSend::new(
             clt_w,
             limit_config.shift_millis,
             limit_config.max_south_packets,
             limit_config.max_south_bytes,
             clt_w_stats,
         );
+        clt_w.set_burst_limit(
+            limit_config.burst_south_packets,
+            limit_config.burst_south_bytes,
+        );
 
         self.task_notes.stage = ServerTaskStage::Connecting;
-        let (ups_r, mut ups_w, logger) = self
-            .ctx
-            .escaper
+        let escaper = self.ctx.task_escaper(&self.task_notes);
+        let (ups_r, mut ups_w, logger) = escaper
             .udp_setup_connection(
                 &mut self.udp_notes,
                 &self.task_notes,
@@ -16,9 +16,12 @@
 
 use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use arc_swap::ArcSwapOption;
 
+use g3_histogram::{HistogramMetricsConfig, HistogramRecorder, HistogramStats};
+use g3_types::ext::DurationExt;
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::stats::{StatId, TcpIoSnapshot, UdpIoSnapshot};
 
@@ -51,6 +54,10 @@ pub(crate) trait ServerStats {
     fn untrusted_snapshot(&self) -> Option<UntrustedTaskStatsSnapshot> {
         None
     }
+
+    fn duration_stats(&self) -> Option<Arc<ServerTaskDurationStats>> {
+        None
+    }
 }
 
 pub(crate) type ArcServerStats = Arc<dyn ServerStats + Send + Sync>;
@@ -118,3 +125,72 @@ impl ServerPerTaskStats {
         self.alive_count.load(Ordering::Relaxed)
     }
 }
+
+pub(crate) struct ServerTaskDurationRecorder {
+    task_ready: HistogramRecorder<u64>,
+    task_total: HistogramRecorder<u64>,
+    first_byte: HistogramRecorder<u64>,
+}
+
+impl ServerTaskDurationRecorder {
+    pub(crate) fn record_task_ready(&self, dur: Duration) {
+        let _ = self.task_ready.record(dur.as_nanos_u64());
+    }
+
+    pub(crate) fn record_task_total(&self, dur: Duration) {
+        let _ = self.task_total.record(dur.as_nanos_u64());
+    }
+
+    pub(crate) fn record_first_byte(&self, dur: Duration) {
+        let _ = self.first_byte.record(dur.as_nanos_u64());
+    }
+}
+
+pub(crate) struct ServerTaskDurationStats {
+    pub(crate) task_ready: Arc<HistogramStats>,
+    pub(crate) task_total: Arc<HistogramStats>,
+    pub(crate) first_byte: Arc<HistogramStats>,
+}
+
+#[derive(Default)]
+pub(crate) struct ServerDurationStats {
+    recorder: ArcSwapOption<ServerTaskDurationRecorder>,
+    stats: ArcSwapOption<ServerTaskDurationStats>,
+}
+
+impl ServerDurationStats {
+    pub(crate) fn set_config(&self, config: Option<&HistogramMetricsConfig>) {
+        let Some(config) = config else {
+            self.recorder.store(None);
+            self.stats.store(None);
+            return;
+        };
+
+        let handle = g3_daemon::runtime::main_handle().cloned();
+        let (task_ready_r, task_ready_s) = config.build_spawned(handle.clone());
+        let (task_total_r, task_total_s) = config.build_spawned(handle.clone());
+        let (first_byte_r, first_byte_s) = config.build_spawned(handle);
+        let recorder = ServerTaskDurationRecorder {
+            task_ready: task_ready_r,
+            task_total: task_total_r,
+            first_byte: first_byte_r,
+        };
+        let stats = ServerTaskDurationStats {
+            task_ready: task_ready_s,
+            task_total: task_total_s,
+            first_byte: first_byte_s,
+        };
+        self.recorder.store(Some(Arc::new(recorder)));
+        self.stats.store(Some(Arc::new(stats)));
+    }
+
+    #[inline]
+    pub(crate) fn recorder(&self) -> Option<Arc<ServerTaskDurationRecorder>> {
+        self.recorder.load_full()
+    }
+
+    #[inline]
+    pub(crate) fn stats(&self) -> Option<Arc<ServerTaskDurationStats>> {
+        self.stats.load_full()
+    }
+}
@@ -20,13 +20,21 @@ use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use g3_daemon::server::ClientConnectionInfo;
+use g3_dpi::TlsClientFingerprint;
 use g3_types::limit::GaugeSemaphorePermit;
+use g3_types::metrics::MetricsName;
+use g3_types::net::{HttpHeaderMap, UpstreamAddr};
 use g3_types::route::EgressPathSelection;
 
 use crate::auth::UserContext;
+use crate::escape::ArcEscaper;
+use crate::serve::alive_task::{self, AliveTaskHandle, ArcAliveTaskIoStats};
+use crate::serve::ServerTaskDurationRecorder;
+use crate::trace::TaskTrace;
 
 static DEFAULT_PATH_SELECTION: OnceLock<Arc<EgressPathSelection>> = OnceLock::new();
 
@@ -70,8 +78,13 @@ pub(crate) struct ServerTaskNotes {
     pub(crate) wait_time: Duration,
     pub(crate) ready_time: Duration,
     pub(crate) egress_path_selection: Arc<EgressPathSelection>,
+    pub(crate) client_tls_fingerprint: Option<TlsClientFingerprint>,
+    trace: Option<TaskTrace>,
+    duration_recorder: Option<Arc<ServerTaskDurationRecorder>>,
+    alive_handle: Option<Arc<AliveTaskHandle>>,
     /// the following fields should not be cloned
     pub(crate) user_req_alive_permit: Option<GaugeSemaphorePermit>,
+    pub(crate) user_conn_alive_permit: Option<GaugeSemaphorePermit>,
 }
 
 impl ServerTaskNotes {
@@ -93,17 +106,23 @@ impl ServerTaskNotes {
     ) -> Self {
         let started = Utc::now();
         let uuid = g3_daemon::server::task::generate_uuid(&started);
+        let create_ins = Instant::now();
         ServerTaskNotes {
             cc_info,
             stage: ServerTaskStage::Created,
             start_at: started,
-            create_ins: Instant::now(),
+            create_ins,
             id: uuid,
             user_ctx,
             wait_time,
             ready_time: Duration::default(),
             egress_path_selection,
+            client_tls_fingerprint: None,
+            trace: TaskTrace::new(create_ins.into_std()),
+            duration_recorder: None,
+            alive_handle: None,
             user_req_alive_permit: None,
+            user_conn_alive_permit: None,
         }
     }
 
@@ -132,6 +151,12 @@ impl ServerTaskNotes {
         self.user_ctx.as_ref()
     }
 
+    /// get the escaper set in user config, which should take precedence over the server one
+    pub(crate) fn user_escaper(&self) -> Option<ArcEscaper> {
+        let name = self.user_ctx()?.user_config().escaper.as_ref()?;
+        Some(crate::escape::get_or_insert_default(name))
+    }
+
     #[inline]
     pub(crate) fn user_ctx_mut(&mut self) -> Option<&mut UserContext> {
         self.user_ctx.as_mut()
@@ -151,11 +176,101 @@ impl ServerTaskNotes {
         self.create_ins.elapsed()
     }
 
+    /// set the recorder for server level duration histograms
+    #[inline]
+    pub(crate) fn set_duration_recorder(
+        &mut self,
+        recorder: Option<Arc<ServerTaskDurationRecorder>>,
+    ) {
+        self.duration_recorder = recorder;
+    }
+
+    /// register to the alive task list, so it can be listed and killed by ctl commands
+    pub(crate) fn register_alive(&mut self, server: &MetricsName, upstream: &UpstreamAddr) {
+        let handle = alive_task::register(
+            self.id,
+            server,
+            self.user_ctx
+                .as_ref()
+                .map(|ctx| (ctx.user().group(), ctx.user_name())),
+            upstream,
+            self.start_at,
+            self.create_ins,
+            self.cc_info.client_addr(),
+        );
+        self.alive_handle = Some(handle);
+    }
+
+    /// get the token that will be cancelled if the task is killed by ctl commands
+    pub(crate) fn alive_kill_token(&self) -> CancellationToken {
+        self.alive_handle
+            .as_ref()
+            .map(|h| h.kill_token())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn set_alive_io_stats(&self, stats: ArcAliveTaskIoStats) {
+        if let Some(handle) = &self.alive_handle {
+            handle.set_io_stats(stats);
+        }
+    }
+
     pub(crate) fn mark_relaying(&mut self) {
         self.stage = ServerTaskStage::Relaying;
         self.ready_time = self.create_ins.elapsed();
         if let Some(user_ctx) = &self.user_ctx {
             user_ctx.record_task_ready(self.ready_time);
         }
+        if let Some(recorder) = &self.duration_recorder {
+            recorder.record_task_ready(self.ready_time);
+        }
+    }
+
+    /// record the time to first byte of the upstream response
+    pub(crate) fn record_first_byte(&self, dur: Duration) {
+        if let Some(recorder) = &self.duration_recorder {
+            recorder.record_first_byte(dur);
+        }
+    }
+
+    /// record a trace span which starts at `start` and ends now
+    pub(crate) fn trace_span(&self, name: &'static str, start: Instant) {
+        if let Some(trace) = &self.trace {
+            trace.add_span(name, start.into_std());
+        }
+    }
+
+    /// join the trace context in the http request headers, and propagate it to the upstream
+    pub(crate) fn trace_http_request(&mut self, headers: &mut HttpHeaderMap) {
+        if let Some(trace) = &mut self.trace {
+            trace.trace_http_request(headers);
+        }
+    }
+}
+
+impl Drop for ServerTaskNotes {
+    fn drop(&mut self) {
+        if self.alive_handle.take().is_some() {
+            alive_task::unregister(&self.id);
+        }
+
+        if let Some(recorder) = self.duration_recorder.take() {
+            recorder.record_task_total(self.create_ins.elapsed());
+        }
+
+        let Some(trace) = self.trace.take() else {
+            return;
+        };
+
+        let mut attributes = vec![
+            ("task.id", self.id.to_string()),
+            ("task.stage", self.stage.brief().to_string()),
+            ("client.address", self.client_addr().to_string()),
+            ("server.address", self.server_addr().to_string()),
+        ];
+        if let Some(user) = self.raw_user_name() {
+            attributes.push(("user", user.to_string()));
+        }
+        trace.finish(self.wait_time, self.ready_time, attributes);
     }
 }
@@ -97,6 +97,9 @@ impl TcpStreamServer {
         let task_logger = config.get_task_logger();
 
         server_stats.set_extra_tags(config.extra_metrics_tags.clone());
+        server_stats
+            .duration
+            .set_config(config.duration_stats.as_ref());
 
         let escaper = Arc::new(crate::escape::get_or_insert_default(config.escaper()));
         let audit_handle = config.get_audit_handle()?;
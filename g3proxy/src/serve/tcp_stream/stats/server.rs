@@ -23,7 +23,10 @@ use arc_swap::ArcSwapOption;
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::stats::{StatId, TcpIoSnapshot, TcpIoStats};
 
-use crate::serve::{ServerForbiddenSnapshot, ServerForbiddenStats, ServerStats};
+use crate::serve::{
+    ServerDurationStats, ServerForbiddenSnapshot, ServerForbiddenStats, ServerStats,
+    ServerTaskDurationStats,
+};
 
 pub(crate) struct TcpStreamServerStats {
     name: MetricsName,
@@ -39,6 +42,7 @@ pub(crate) struct TcpStreamServerStats {
 
     tcp: TcpIoStats,
     pub(crate) forbidden: ServerForbiddenStats,
+    pub(crate) duration: ServerDurationStats,
 }
 
 impl TcpStreamServerStats {
@@ -53,6 +57,7 @@ impl TcpStreamServerStats {
             task_alive_count: AtomicI32::new(0),
             tcp: Default::default(),
             forbidden: Default::default(),
+            duration: Default::default(),
         }
     }
 
@@ -140,4 +145,8 @@ impl ServerStats for TcpStreamServerStats {
     fn forbidden_stats(&self) -> ServerForbiddenSnapshot {
         self.forbidden.snapshot()
     }
+
+    fn duration_stats(&self) -> Option<Arc<ServerTaskDurationStats>> {
+        self.duration.stats()
+    }
 }
@@ -31,7 +31,9 @@ use crate::inspect::StreamInspectContext;
 use crate::log::task::tcp_connect::TaskLogForTcpConnect;
 use crate::module::tcp_connect::TcpConnectTaskNotes;
 use crate::serve::tcp_stream::TcpStreamTaskCltWrapperStats;
-use crate::serve::{ServerTaskError, ServerTaskNotes, ServerTaskResult, ServerTaskStage};
+use crate::serve::{
+    alive_task, ServerTaskError, ServerTaskNotes, ServerTaskResult, ServerTaskStage,
+};
 
 pub(super) struct TProxyStreamTask {
     ctx: CommonTaskContext,
@@ -42,11 +44,13 @@ pub(super) struct TProxyStreamTask {
 
 impl TProxyStreamTask {
     pub(super) fn new(ctx: CommonTaskContext) -> Self {
-        let target = ctx.target_addr();
-        let task_notes = ServerTaskNotes::new(ctx.cc_info.clone(), None, Duration::ZERO);
+        let upstream = UpstreamAddr::from(ctx.target_addr());
+        let mut task_notes = ServerTaskNotes::new(ctx.cc_info.clone(), None, Duration::ZERO);
+        task_notes.set_duration_recorder(ctx.server_stats.duration.recorder());
+        task_notes.register_alive(ctx.server_config.name(), &upstream);
         TProxyStreamTask {
             ctx,
-            tcp_notes: TcpConnectTaskNotes::new(UpstreamAddr::from(target)),
+            tcp_notes: TcpConnectTaskNotes::new(upstream),
             task_notes,
             task_stats: Arc::new(TcpStreamTaskStats::default()),
         }
@@ -66,7 +70,8 @@ impl TProxyStreamTask {
 
     pub(super) async fn into_running(mut self, stream: TcpStream) {
         self.pre_start();
-        match self.run(stream).await {
+        let kill_token = self.task_notes.alive_kill_token();
+        match alive_task::run_killable(kill_token, self.run(stream)).await {
             Ok(_) => self
                 .get_log_context()
                 .log(&self.ctx.task_logger, &ServerTaskError::Finished),
@@ -85,6 +90,7 @@ impl TProxyStreamTask {
         );
         self.ctx.server_stats.add_task();
         self.ctx.server_stats.inc_alive_task();
+        self.task_notes.set_alive_io_stats(self.task_stats.clone());
     }
 
     fn pre_stop(&self) {
@@ -141,7 +147,12 @@ impl TProxyStreamTask {
     {
         let (clt_r, clt_w) = self.split_clt(clt_stream);
 
-        if let Some(audit_handle) = self.ctx.audit_handle.take() {
+        if let Some(audit_handle) = self
+            .ctx
+            .audit_handle
+            .take()
+            .filter(|h| h.do_application_audit())
+        {
             let ctx = StreamInspectContext::new(
                 audit_handle,
                 self.ctx.server_config.clone(),
@@ -186,18 +197,20 @@ impl TProxyStreamTask {
             TcpStreamTaskCltWrapperStats::new_pair(&self.ctx.server_stats, &self.task_stats);
         let clt_speed_limit = &self.ctx.server_config.tcp_sock_speed_limit;
 
-        let clt_r = LimitedReader::new(
+        let mut clt_r = LimitedReader::new(
             clt_r,
             clt_speed_limit.shift_millis,
             clt_speed_limit.max_north,
             clt_r_stats,
         );
-        let clt_w = LimitedWriter::new(
+        clt_r.set_burst_limit(clt_speed_limit.burst_north);
+        let mut clt_w = LimitedWriter::new(
             clt_w,
             clt_speed_limit.shift_millis,
             clt_speed_limit.max_south,
             clt_w_stats,
         );
+        clt_w.set_burst_limit(clt_speed_limit.burst_south);
 
         (clt_r, clt_w)
     }
@@ -0,0 +1,258 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use log::warn;
+#[cfg(feature = "quic")]
+use quinn::Connection;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::broadcast;
+use tokio_rustls::server::TlsStream;
+
+use g3_daemon::listen::{AcceptQuicServer, AcceptTcpServer, ListenStats, ListenTcpRuntime};
+use g3_daemon::server::{BaseServer, ClientConnectionInfo, ServerReloadCommand};
+use g3_openssl::SslStream;
+use g3_types::metrics::MetricsName;
+use g3_types::net::UdpListenConfig;
+
+use super::task;
+use crate::config::server::test_echo::{TestEchoMode, TestEchoServerConfig};
+use crate::config::server::{AnyServerConfig, ServerConfig};
+use crate::serve::{ArcServer, Server, ServerInternal, ServerQuitPolicy, WrapArcServer};
+
+pub(crate) struct TestEchoServer {
+    config: TestEchoServerConfig,
+    listen_stats: Arc<ListenStats>,
+    reload_sender: broadcast::Sender<ServerReloadCommand>,
+
+    alive_count: AtomicI32,
+    quit_policy: Arc<ServerQuitPolicy>,
+}
+
+impl TestEchoServer {
+    fn new(config: TestEchoServerConfig, listen_stats: Arc<ListenStats>) -> Self {
+        let reload_sender = crate::serve::new_reload_notify_channel();
+
+        TestEchoServer {
+            config,
+            listen_stats,
+            reload_sender,
+            alive_count: AtomicI32::new(0),
+            quit_policy: Arc::new(ServerQuitPolicy::default()),
+        }
+    }
+
+    pub(crate) fn prepare_initial(config: TestEchoServerConfig) -> anyhow::Result<ArcServer> {
+        let listen_stats = Arc::new(ListenStats::new(config.name()));
+
+        let server = TestEchoServer::new(config, listen_stats);
+        Ok(Arc::new(server))
+    }
+
+    fn prepare_reload(&self, config: AnyServerConfig) -> anyhow::Result<TestEchoServer> {
+        if let AnyServerConfig::TestEcho(config) = config {
+            let listen_stats = Arc::clone(&self.listen_stats);
+
+            let server = TestEchoServer::new(config, listen_stats);
+            Ok(server)
+        } else {
+            Err(anyhow!(
+                "config type mismatch: expect {}, actual {}",
+                self.config.server_type(),
+                config.server_type()
+            ))
+        }
+    }
+
+    fn spawn_udp_echo(&self, config: &UdpListenConfig) -> anyhow::Result<()> {
+        let socket = g3_socket::udp::new_std_bind_listen(config)
+            .map_err(|e| anyhow!("failed to create udp listen socket: {e}"))?;
+        let socket = UdpSocket::from_std(socket).context("failed to setup udp listen socket")?;
+
+        let server_name = self.config.name().clone();
+        let mut reload_receiver = self.reload_sender.subscribe();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; u16::MAX as usize];
+            loop {
+                tokio::select! {
+                    biased;
+
+                    ev = reload_receiver.recv() => {
+                        match ev {
+                            Ok(ServerReloadCommand::ReloadVersion(_)) => {}
+                            Ok(ServerReloadCommand::QuitRuntime) => break,
+                            Err(broadcast::error::RecvError::Closed) => break,
+                            Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        }
+                    }
+                    r = socket.recv_from(&mut buf) => {
+                        match r {
+                            Ok((nr, peer)) => {
+                                let _ = socket.send_to(&buf[..nr], peer).await;
+                            }
+                            Err(e) => {
+                                warn!("server {server_name} udp echo recv error: {e}");
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn run_task<S>(&self, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        self.alive_count.fetch_add(1, Ordering::Relaxed);
+        match self.config.mode {
+            TestEchoMode::Echo => task::run_echo(stream).await,
+            TestEchoMode::Http => task::run_http(stream, &self.config).await,
+        }
+        self.alive_count.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl ServerInternal for TestEchoServer {
+    fn _clone_config(&self) -> AnyServerConfig {
+        AnyServerConfig::TestEcho(self.config.clone())
+    }
+
+    fn _update_config_in_place(&self, _flags: u64, _config: AnyServerConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn _depend_on_server(&self, _name: &MetricsName) -> bool {
+        false
+    }
+
+    // TestEcho server do not support reload with old runtime/notifier
+    fn _reload_config_notify_runtime(&self) {}
+
+    fn _update_next_servers_in_place(&self) {}
+
+    fn _update_escaper_in_place(&self) {}
+
+    fn _update_user_group_in_place(&self) {}
+
+    fn _update_audit_handle_in_place(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn _reload_with_old_notifier(&self, config: AnyServerConfig) -> anyhow::Result<ArcServer> {
+        Err(anyhow!(
+            "this {} server doesn't support reload with old notifier",
+            config.server_type()
+        ))
+    }
+
+    fn _reload_with_new_notifier(&self, config: AnyServerConfig) -> anyhow::Result<ArcServer> {
+        let server = self.prepare_reload(config)?;
+        Ok(Arc::new(server))
+    }
+
+    fn _start_runtime(&self, server: &ArcServer) -> anyhow::Result<()> {
+        if let Some(udp_listen) = &self.config.udp_listen {
+            self.spawn_udp_echo(udp_listen)?;
+        }
+        let Some(listen_config) = &self.config.listen else {
+            return Ok(());
+        };
+        let runtime =
+            ListenTcpRuntime::new(WrapArcServer(server.clone()), server.get_listen_stats());
+        runtime.run_all_instances(
+            listen_config,
+            self.config.listen_in_worker,
+            &self.reload_sender,
+        )
+    }
+
+    fn _abort_runtime(&self) {
+        let _ = self.reload_sender.send(ServerReloadCommand::QuitRuntime);
+    }
+}
+
+impl BaseServer for TestEchoServer {
+    #[inline]
+    fn name(&self) -> &MetricsName {
+        self.config.name()
+    }
+
+    #[inline]
+    fn server_type(&self) -> &'static str {
+        self.config.server_type()
+    }
+
+    #[inline]
+    fn version(&self) -> usize {
+        0
+    }
+}
+
+#[async_trait]
+impl AcceptTcpServer for TestEchoServer {
+    async fn run_tcp_task(&self, stream: TcpStream, _cc_info: ClientConnectionInfo) {
+        self.run_task(stream).await
+    }
+}
+
+#[async_trait]
+impl AcceptQuicServer for TestEchoServer {
+    #[cfg(feature = "quic")]
+    async fn run_quic_task(&self, _connection: Connection, _cc_info: ClientConnectionInfo) {}
+}
+
+#[async_trait]
+impl Server for TestEchoServer {
+    fn escaper(&self) -> &MetricsName {
+        Default::default()
+    }
+
+    fn user_group(&self) -> &MetricsName {
+        Default::default()
+    }
+
+    fn auditor(&self) -> &MetricsName {
+        Default::default()
+    }
+
+    fn get_listen_stats(&self) -> Arc<ListenStats> {
+        Arc::clone(&self.listen_stats)
+    }
+
+    fn alive_count(&self) -> i32 {
+        self.alive_count.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn quit_policy(&self) -> &Arc<ServerQuitPolicy> {
+        &self.quit_policy
+    }
+
+    async fn run_rustls_task(&self, stream: TlsStream<TcpStream>, _cc_info: ClientConnectionInfo) {
+        self.run_task(stream).await
+    }
+
+    async fn run_openssl_task(&self, stream: SslStream<TcpStream>, _cc_info: ClientConnectionInfo) {
+        self.run_task(stream).await
+    }
+}
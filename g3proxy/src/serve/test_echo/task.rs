@@ -0,0 +1,170 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::Write;
+
+use bytes::Bytes;
+use http::Method;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use g3_http::{HttpBodyReader, HttpTransparentRequest};
+
+use crate::config::server::test_echo::TestEchoServerConfig;
+
+pub(super) async fn run_echo<S>(stream: S)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut clt_r, mut clt_w) = tokio::io::split(stream);
+    let _ = tokio::io::copy(&mut clt_r, &mut clt_w).await;
+    let _ = clt_w.shutdown().await;
+}
+
+pub(super) async fn run_http<S>(stream: S, config: &TestEchoServerConfig)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (clt_r, mut clt_w) = tokio::io::split(stream);
+    let mut clt_r = BufReader::new(clt_r);
+
+    loop {
+        let Ok((req, head_bytes)) =
+            HttpTransparentRequest::parse(&mut clt_r, config.http_req_header_max_size).await
+        else {
+            break;
+        };
+
+        if let Some(body_type) = req.body_type() {
+            let mut body_reader = HttpBodyReader::new(&mut clt_r, body_type, 1024);
+            let mut sink = tokio::io::sink();
+            if tokio::io::copy(&mut body_reader, &mut sink).await.is_err() {
+                break;
+            }
+        }
+
+        // echo back the request header if no fixed body is set
+        let body = match &config.http_response_body {
+            Some(s) => Bytes::from(s.clone()),
+            None => head_bytes,
+        };
+
+        let mut rsp_head = Vec::with_capacity(128);
+        let _ = write!(
+            rsp_head,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n",
+            body.len()
+        );
+        if !req.keep_alive() {
+            rsp_head.extend_from_slice(b"Connection: close\r\n");
+        }
+        rsp_head.extend_from_slice(b"\r\n");
+
+        if clt_w.write_all(&rsp_head).await.is_err() {
+            break;
+        }
+        if req.method != Method::HEAD && clt_w.write_all(&body).await.is_err() {
+            break;
+        }
+        if clt_w.flush().await.is_err() {
+            break;
+        }
+
+        if !req.keep_alive() {
+            break;
+        }
+    }
+
+    let _ = clt_w.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    fn http_config(body: Option<&str>) -> TestEchoServerConfig {
+        let doc = match body {
+            Some(body) => format!(
+                "name: echo\nlisten: 127.0.0.1:9080\nmode: http\nhttp_response_body: {body}\n"
+            ),
+            None => "name: echo\nlisten: 127.0.0.1:9080\nmode: http\n".to_string(),
+        };
+        let docs = yaml_rust::YamlLoader::load_from_str(&doc).unwrap();
+        TestEchoServerConfig::parse(docs[0].as_hash().unwrap(), None).unwrap()
+    }
+
+    #[tokio::test]
+    async fn echo() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let task = tokio::spawn(run_echo(server));
+
+        client.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+
+        client.shutdown().await.unwrap();
+        let mut rest = Vec::new();
+        client.read_to_end(&mut rest).await.unwrap();
+        assert!(rest.is_empty());
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn http_echo_header() {
+        let config = http_config(None);
+        let (mut client, server) = tokio::io::duplex(4096);
+        let task = tokio::spawn(async move { run_http(server, &config).await });
+
+        let req = b"GET /a HTTP/1.1\r\nHost: example.net\r\nConnection: close\r\n\r\n";
+        client.write_all(req).await.unwrap();
+        let mut rsp = Vec::new();
+        client.read_to_end(&mut rsp).await.unwrap();
+        task.await.unwrap();
+
+        let rsp = String::from_utf8(rsp).unwrap();
+        assert!(rsp.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(rsp.contains("Connection: close\r\n"));
+        let (head, body) = rsp.split_once("\r\n\r\n").unwrap();
+        assert!(head.contains(&format!("Content-Length: {}\r\n", body.len())));
+        assert!(body.starts_with("GET /a HTTP/1.1\r\n"));
+    }
+
+    #[tokio::test]
+    async fn http_keep_alive() {
+        let config = http_config(Some("ok"));
+        let (mut client, server) = tokio::io::duplex(4096);
+        let task = tokio::spawn(async move { run_http(server, &config).await });
+
+        let rsp_1 = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\nok";
+        let req = b"POST / HTTP/1.1\r\nHost: example.net\r\nContent-Length: 4\r\n\r\ntest";
+        client.write_all(req).await.unwrap();
+        let mut buf = vec![0u8; rsp_1.len()];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, rsp_1.as_bytes());
+
+        // no body should be sent for HEAD requests
+        let req = b"HEAD / HTTP/1.1\r\nHost: example.net\r\nConnection: close\r\n\r\n";
+        client.write_all(req).await.unwrap();
+        let mut rsp = Vec::new();
+        client.read_to_end(&mut rsp).await.unwrap();
+        task.await.unwrap();
+        assert_eq!(
+            rsp,
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\nConnection: close\r\n\r\n"
+        );
+    }
+}
@@ -40,7 +40,7 @@ fn call_reload(_: u32) -> SigResult {
     SigResult::Continue
 }
 
-async fn do_reload() {
+pub(crate) async fn do_reload() {
     let _guard = RELOAD_MUTEX.lock().await;
     info!("reloading config");
 
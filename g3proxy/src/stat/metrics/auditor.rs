@@ -0,0 +1,246 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::{Arc, Mutex};
+
+use ahash::AHashMap;
+use once_cell::sync::Lazy;
+
+use g3_daemon::metrics::TAG_KEY_STAT_ID;
+use g3_statsd_client::{StatsdClient, StatsdTagGroup};
+use g3_types::metrics::MetricsName;
+use g3_types::stats::StatId;
+
+use crate::audit::{
+    AuditIcapSnapshot, AuditInspectSnapshot, AuditInterceptionSnapshot, AuditProtocolSnapshot,
+    AuditorStats,
+};
+
+const TAG_KEY_AUDITOR: &str = "auditor";
+const TAG_KEY_PROTOCOL: &str = "protocol";
+const TAG_KEY_ICAP_METHOD: &str = "icap_method";
+
+const METRIC_NAME_PROTOCOL_DETECTED: &str = "auditor.protocol.detected";
+const METRIC_NAME_PROTOCOL_BLOCKED: &str = "auditor.protocol.blocked";
+const METRIC_NAME_INSPECT_TIMEOUT: &str = "auditor.inspect.timeout";
+const METRIC_NAME_INSPECT_OVERSIZE: &str = "auditor.inspect.oversize";
+const METRIC_NAME_ICAP_ADAPTED: &str = "auditor.icap.adapted";
+const METRIC_NAME_ICAP_NO_MODIFICATION: &str = "auditor.icap.no_modification";
+const METRIC_NAME_ICAP_BLOCKED: &str = "auditor.icap.blocked";
+const METRIC_NAME_ICAP_ERROR: &str = "auditor.icap.error";
+const METRIC_NAME_ICAP_BYPASSED: &str = "auditor.icap.bypassed";
+const METRIC_NAME_TLS_INTERCEPTION_FAILED: &str = "auditor.tls_interception.failed";
+const METRIC_NAME_TLS_INTERCEPTION_BYPASSED: &str = "auditor.tls_interception.bypassed";
+const METRIC_NAME_TLS_INTERCEPTION_UPSTREAM_HANDSHAKE: &str =
+    "auditor.tls_interception.upstream.handshake";
+const METRIC_NAME_TLS_INTERCEPTION_UPSTREAM_SESSION_RESUMED: &str =
+    "auditor.tls_interception.upstream.session_resumed";
+const METRIC_NAME_HAR_EXPORT_DROPPED: &str = "auditor.har_export.dropped";
+
+type AuditorStatsValue = (Arc<AuditorStats>, AuditorSnapshot);
+
+static AUDITOR_STATS_MAP: Lazy<Mutex<AHashMap<StatId, AuditorStatsValue>>> =
+    Lazy::new(|| Mutex::new(AHashMap::new()));
+
+#[derive(Default)]
+struct AuditorSnapshot {
+    protocol: AHashMap<&'static str, AuditProtocolSnapshot>,
+    inspect: AuditInspectSnapshot,
+    icap_reqmod: AuditIcapSnapshot,
+    icap_respmod: AuditIcapSnapshot,
+    tls_interception: AuditInterceptionSnapshot,
+    har_export_dropped: u64,
+}
+
+trait AuditorMetricExt {
+    fn add_auditor_tags(&mut self, auditor: &MetricsName, stat_id: StatId);
+}
+
+impl AuditorMetricExt for StatsdTagGroup {
+    fn add_auditor_tags(&mut self, auditor: &MetricsName, stat_id: StatId) {
+        let mut buffer = itoa::Buffer::new();
+        let stat_id = buffer.format(stat_id.as_u64());
+        self.add_tag(TAG_KEY_AUDITOR, auditor);
+        self.add_tag(TAG_KEY_STAT_ID, stat_id);
+    }
+}
+
+pub(in crate::stat) fn sync_stats() {
+    let mut stats_map = AUDITOR_STATS_MAP.lock().unwrap();
+    crate::audit::foreach_auditor(|_, auditor| {
+        let stats = auditor.stats();
+        let stat_id = stats.stat_id();
+        stats_map
+            .entry(stat_id)
+            .or_insert_with(|| (stats.clone(), AuditorSnapshot::default()));
+    });
+}
+
+pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
+    let mut stats_map = AUDITOR_STATS_MAP.lock().unwrap();
+    stats_map.retain(|_, (stats, snap)| {
+        emit_stats_to_statsd(client, stats, snap);
+        // use Arc instead of Weak here, as we should emit the final metrics before drop it
+        Arc::strong_count(stats) > 1
+    });
+}
+
+fn emit_stats_to_statsd(
+    client: &mut StatsdClient,
+    stats: &AuditorStats,
+    snap: &mut AuditorSnapshot,
+) {
+    let mut common_tags = StatsdTagGroup::default();
+    common_tags.add_auditor_tags(stats.name(), stats.stat_id());
+
+    emit_protocol_stats_to_statsd(client, stats, &common_tags, &mut snap.protocol);
+    emit_inspect_stats_to_statsd(client, stats, &common_tags, &mut snap.inspect);
+
+    let new = stats.icap_reqmod().snapshot();
+    emit_icap_stats_to_statsd(client, "reqmod", new, &common_tags, &mut snap.icap_reqmod);
+    let new = stats.icap_respmod().snapshot();
+    emit_icap_stats_to_statsd(client, "respmod", new, &common_tags, &mut snap.icap_respmod);
+
+    emit_tls_interception_stats_to_statsd(client, stats, &common_tags, &mut snap.tls_interception);
+
+    let new = stats.har_export_dropped();
+    if new != 0 || snap.har_export_dropped != 0 {
+        let diff_value = new.wrapping_sub(snap.har_export_dropped);
+        client
+            .count_with_tags(METRIC_NAME_HAR_EXPORT_DROPPED, diff_value, &common_tags)
+            .send();
+        snap.har_export_dropped = new;
+    }
+}
+
+fn emit_protocol_stats_to_statsd(
+    client: &mut StatsdClient,
+    stats: &AuditorStats,
+    common_tags: &StatsdTagGroup,
+    snap: &mut AHashMap<&'static str, AuditProtocolSnapshot>,
+) {
+    for (protocol, new) in stats.snapshot() {
+        let old = snap.entry(protocol).or_default();
+
+        let diff_value = new.detected.wrapping_sub(old.detected);
+        client
+            .count_with_tags(METRIC_NAME_PROTOCOL_DETECTED, diff_value, common_tags)
+            .with_tag(TAG_KEY_PROTOCOL, protocol)
+            .send();
+
+        if new.blocked != 0 || old.blocked != 0 {
+            let diff_value = new.blocked.wrapping_sub(old.blocked);
+            client
+                .count_with_tags(METRIC_NAME_PROTOCOL_BLOCKED, diff_value, common_tags)
+                .with_tag(TAG_KEY_PROTOCOL, protocol)
+                .send();
+        }
+
+        *old = new;
+    }
+}
+
+fn emit_inspect_stats_to_statsd(
+    client: &mut StatsdClient,
+    stats: &AuditorStats,
+    common_tags: &StatsdTagGroup,
+    snap: &mut AuditInspectSnapshot,
+) {
+    let new = stats.inspect_snapshot();
+
+    let diff_value = new.timeout.wrapping_sub(snap.timeout);
+    client
+        .count_with_tags(METRIC_NAME_INSPECT_TIMEOUT, diff_value, common_tags)
+        .send();
+
+    let diff_value = new.oversize.wrapping_sub(snap.oversize);
+    client
+        .count_with_tags(METRIC_NAME_INSPECT_OVERSIZE, diff_value, common_tags)
+        .send();
+
+    *snap = new;
+}
+
+fn emit_icap_stats_to_statsd(
+    client: &mut StatsdClient,
+    method: &str,
+    new: AuditIcapSnapshot,
+    common_tags: &StatsdTagGroup,
+    snap: &mut AuditIcapSnapshot,
+) {
+    macro_rules! emit_field {
+        ($field:ident, $name:expr) => {
+            let diff_value = new.$field.wrapping_sub(snap.$field);
+            client
+                .count_with_tags($name, diff_value, common_tags)
+                .with_tag(TAG_KEY_ICAP_METHOD, method)
+                .send();
+        };
+    }
+
+    emit_field!(adapted, METRIC_NAME_ICAP_ADAPTED);
+    emit_field!(no_modification, METRIC_NAME_ICAP_NO_MODIFICATION);
+    emit_field!(blocked, METRIC_NAME_ICAP_BLOCKED);
+    emit_field!(error, METRIC_NAME_ICAP_ERROR);
+    emit_field!(bypassed, METRIC_NAME_ICAP_BYPASSED);
+
+    *snap = new;
+}
+
+fn emit_tls_interception_stats_to_statsd(
+    client: &mut StatsdClient,
+    stats: &AuditorStats,
+    common_tags: &StatsdTagGroup,
+    snap: &mut AuditInterceptionSnapshot,
+) {
+    let new = stats.tls_interception_snapshot();
+
+    let diff_value = new.failed.wrapping_sub(snap.failed);
+    client
+        .count_with_tags(METRIC_NAME_TLS_INTERCEPTION_FAILED, diff_value, common_tags)
+        .send();
+
+    let diff_value = new.bypassed.wrapping_sub(snap.bypassed);
+    client
+        .count_with_tags(
+            METRIC_NAME_TLS_INTERCEPTION_BYPASSED,
+            diff_value,
+            common_tags,
+        )
+        .send();
+
+    let diff_value = new.upstream_handshake.wrapping_sub(snap.upstream_handshake);
+    client
+        .count_with_tags(
+            METRIC_NAME_TLS_INTERCEPTION_UPSTREAM_HANDSHAKE,
+            diff_value,
+            common_tags,
+        )
+        .send();
+
+    let diff_value = new
+        .upstream_session_resumed
+        .wrapping_sub(snap.upstream_session_resumed);
+    client
+        .count_with_tags(
+            METRIC_NAME_TLS_INTERCEPTION_UPSTREAM_SESSION_RESUMED,
+            diff_value,
+            common_tags,
+        )
+        .send();
+
+    *snap = new;
+}
@@ -20,7 +20,7 @@ use ahash::AHashMap;
 use once_cell::sync::Lazy;
 
 use g3_daemon::metrics::{
-    TAG_KEY_STAT_ID, TAG_KEY_TRANSPORT, TRANSPORT_TYPE_TCP, TRANSPORT_TYPE_UDP,
+    TAG_KEY_QUANTILE, TAG_KEY_STAT_ID, TAG_KEY_TRANSPORT, TRANSPORT_TYPE_TCP, TRANSPORT_TYPE_UDP,
 };
 use g3_statsd_client::{StatsdClient, StatsdTagGroup};
 use g3_types::metrics::MetricsName;
@@ -28,17 +28,27 @@ use g3_types::stats::{StatId, TcpIoSnapshot, UdpIoSnapshot};
 
 use super::TAG_KEY_ESCAPER;
 use crate::escape::{
-    ArcEscaperStats, EscaperForbiddenSnapshot, RouteEscaperSnapshot, RouteEscaperStats,
+    ArcEscaperStats, EscaperForbiddenSnapshot, EscaperPeerQuarantineSnapshot,
+    EscaperTcpConnectErrorSnapshot, EscaperTlsSessionSnapshot, RouteEscaperSnapshot,
+    RouteEscaperStats,
 };
 
 const METRIC_NAME_ESCAPER_TASK_TOTAL: &str = "escaper.task.total";
 const METRIC_NAME_ESCAPER_CONN_ATTEMPT: &str = "escaper.connection.attempt";
 const METRIC_NAME_ESCAPER_CONN_ESTABLISH: &str = "escaper.connection.establish";
+const METRIC_NAME_ESCAPER_TCP_CONNECT_DURATION: &str = "escaper.tcp.connect.duration";
+const METRIC_NAME_ESCAPER_TCP_CONNECT_ERROR: &str = "escaper.tcp.connect.error";
 const METRIC_NAME_ESCAPER_IO_IN_BYTES: &str = "escaper.traffic.in.bytes";
 const METRIC_NAME_ESCAPER_IO_IN_PACKETS: &str = "escaper.traffic.in.packets";
 const METRIC_NAME_ESCAPER_IO_OUT_BYTES: &str = "escaper.traffic.out.bytes";
 const METRIC_NAME_ESCAPER_IO_OUT_PACKETS: &str = "escaper.traffic.out.packets";
 const METRIC_NAME_ESCAPER_FORBIDDEN_IP_BLOCKED: &str = "escaper.forbidden.ip_blocked";
+const METRIC_NAME_ESCAPER_TLS_HANDSHAKE: &str = "escaper.tls.handshake";
+const METRIC_NAME_ESCAPER_TLS_SESSION_RESUMED: &str = "escaper.tls.session.resumed";
+const METRIC_NAME_ESCAPER_PEER_QUARANTINE_TOTAL: &str = "escaper.peer.quarantine.total";
+const METRIC_NAME_ESCAPER_PEER_QUARANTINE_CURRENT: &str = "escaper.peer.quarantine.current";
+
+const TAG_KEY_ERROR_TYPE: &str = "error_type";
 
 const METRIC_NAME_ROUTE_REQUEST_PASSED: &str = "route.request.passed";
 const METRIC_NAME_ROUTE_REQUEST_FAILED: &str = "route.request.failed";
@@ -72,6 +82,9 @@ struct EscaperSnapshotStats {
     tcp: TcpIoSnapshot,
     udp: UdpIoSnapshot,
     forbidden: EscaperForbiddenSnapshot,
+    tls_session: EscaperTlsSessionSnapshot,
+    tcp_connect_error: EscaperTcpConnectErrorSnapshot,
+    peer_quarantine: EscaperPeerQuarantineSnapshot,
 }
 
 pub(in crate::stat) fn sync_stats() {
@@ -152,6 +165,33 @@ fn emit_escaper_stats(
         emit_forbidden_stats(client, forbidden_stats, &mut snap.forbidden, &common_tags);
     }
 
+    if let Some(tls_session_stats) = stats.tls_session_snapshot() {
+        emit_tls_session_stats(
+            client,
+            tls_session_stats,
+            &mut snap.tls_session,
+            &common_tags,
+        );
+    }
+
+    if let Some(tcp_connect_error_stats) = stats.tcp_connect_error_snapshot() {
+        emit_tcp_connect_error_stats(
+            client,
+            tcp_connect_error_stats,
+            &mut snap.tcp_connect_error,
+            &common_tags,
+        );
+    }
+
+    if let Some(peer_quarantine_stats) = stats.peer_quarantine_snapshot() {
+        emit_peer_quarantine_stats(
+            client,
+            peer_quarantine_stats,
+            &mut snap.peer_quarantine,
+            &common_tags,
+        );
+    }
+
     if let Some(tcp_io_stats) = stats.tcp_io_snapshot() {
         emit_tcp_io_to_statsd(client, tcp_io_stats, &mut snap.tcp, &common_tags);
     }
@@ -159,6 +199,15 @@ fn emit_escaper_stats(
     if let Some(udp_io_stats) = stats.udp_io_snapshot() {
         emit_udp_io_to_statsd(client, udp_io_stats, &mut snap.udp, &common_tags);
     }
+
+    if let Some(connect_duration_stats) = stats.tcp_connect_duration_stats() {
+        connect_duration_stats.foreach_stat(|_, quantile, v| {
+            client
+                .gauge_float_with_tags(METRIC_NAME_ESCAPER_TCP_CONNECT_DURATION, v, &common_tags)
+                .with_tag(TAG_KEY_QUANTILE, quantile)
+                .send();
+        });
+    }
 }
 
 fn emit_forbidden_stats(
@@ -181,6 +230,93 @@ fn emit_forbidden_stats(
     }
 }
 
+fn emit_tls_session_stats(
+    client: &mut StatsdClient,
+    stats: EscaperTlsSessionSnapshot,
+    snap: &mut EscaperTlsSessionSnapshot,
+    common_tags: &StatsdTagGroup,
+) {
+    if stats.handshake == 0 && snap.handshake == 0 {
+        return;
+    }
+
+    let new_value = stats.handshake;
+    let diff_value = new_value.wrapping_sub(snap.handshake);
+    client
+        .count_with_tags(METRIC_NAME_ESCAPER_TLS_HANDSHAKE, diff_value, common_tags)
+        .send();
+    snap.handshake = new_value;
+
+    let new_value = stats.resumed;
+    let diff_value = new_value.wrapping_sub(snap.resumed);
+    client
+        .count_with_tags(
+            METRIC_NAME_ESCAPER_TLS_SESSION_RESUMED,
+            diff_value,
+            common_tags,
+        )
+        .send();
+    snap.resumed = new_value;
+}
+
+fn emit_tcp_connect_error_stats(
+    client: &mut StatsdClient,
+    stats: EscaperTcpConnectErrorSnapshot,
+    snap: &mut EscaperTcpConnectErrorSnapshot,
+    common_tags: &StatsdTagGroup,
+) {
+    macro_rules! emit_field {
+        ($field:ident) => {
+            let new_value = stats.$field;
+            if new_value != 0 || snap.$field != 0 {
+                let diff_value = new_value.wrapping_sub(snap.$field);
+                client
+                    .count_with_tags(
+                        METRIC_NAME_ESCAPER_TCP_CONNECT_ERROR,
+                        diff_value,
+                        common_tags,
+                    )
+                    .with_tag(TAG_KEY_ERROR_TYPE, stringify!($field))
+                    .send();
+                snap.$field = new_value;
+            }
+        };
+    }
+
+    emit_field!(resolve_failed);
+    emit_field!(connect_timeout);
+    emit_field!(connect_refused);
+    emit_field!(connect_failed);
+    emit_field!(peer_auth_failed);
+    emit_field!(tls_handshake_failed);
+}
+
+fn emit_peer_quarantine_stats(
+    client: &mut StatsdClient,
+    stats: EscaperPeerQuarantineSnapshot,
+    snap: &mut EscaperPeerQuarantineSnapshot,
+    common_tags: &StatsdTagGroup,
+) {
+    client
+        .gauge_with_tags(
+            METRIC_NAME_ESCAPER_PEER_QUARANTINE_CURRENT,
+            stats.current,
+            common_tags,
+        )
+        .send();
+
+    let new_value = stats.total;
+    let diff_value = new_value.wrapping_sub(snap.total);
+    client
+        .count_with_tags(
+            METRIC_NAME_ESCAPER_PEER_QUARANTINE_TOTAL,
+            diff_value,
+            common_tags,
+        )
+        .send();
+    snap.total = new_value;
+}
+
 fn emit_tcp_io_to_statsd(
     client: &mut StatsdClient,
     stats: TcpIoSnapshot,
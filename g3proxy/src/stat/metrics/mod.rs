@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+pub(super) mod auditor;
 pub(super) mod escaper;
 pub(super) mod resolver;
 pub(super) mod server;
@@ -31,17 +31,23 @@ use crate::resolve::ResolverStats;
 
 const TAG_KEY_RESOLVER: &str = "resolver";
 const TAG_KEY_RR_TYPE: &str = "rr_type";
+const TAG_KEY_RCODE: &str = "rcode";
 
 const METRIC_NAME_QUERY_TOTAL: &str = "resolver.query.total";
 const METRIC_NAME_QUERY_CACHED: &str = "resolver.query.cached";
+const METRIC_NAME_QUERY_CACHE_HIT_RATIO: &str = "resolver.query.cache_hit_ratio";
 const METRIC_NAME_QUERY_DRIVER: &str = "resolver.query.driver.total";
+const METRIC_NAME_QUERY_DRIVER_PREFETCH: &str = "resolver.query.driver.prefetch";
 const METRIC_NAME_QUERY_DRIVER_TIMEOUT: &str = "resolver.query.driver.timeout";
 const METRIC_NAME_QUERY_DRIVER_REFUSED: &str = "resolver.query.driver.refused";
 const METRIC_NAME_QUERY_DRIVER_MALFORMED: &str = "resolver.query.driver.malformed";
+const METRIC_NAME_QUERY_SERVER_NO_ERROR: &str = "resolver.query.server.no_error";
 const METRIC_NAME_QUERY_SERVER_REFUSED: &str = "resolver.query.server.refused";
 const METRIC_NAME_QUERY_SERVER_MALFORMED: &str = "resolver.query.server.malformed";
 const METRIC_NAME_QUERY_SERVER_NOT_FOUND: &str = "resolver.query.server.not_found";
 const METRIC_NAME_QUERY_SERVER_SERV_FAIL: &str = "resolver.query.server.serv_fail";
+const METRIC_NAME_QUERY_SERVER_NOT_IMP: &str = "resolver.query.server.not_imp";
+const METRIC_NAME_QUERY_RCODE: &str = "resolver.query.rcode";
 const METRIC_NAME_MEMORY_CACHE_CAPACITY: &str = "resolver.memory.cache.capacity";
 const METRIC_NAME_MEMORY_CACHE_LENGTH: &str = "resolver.memory.cache.length";
 const METRIC_NAME_MEMORY_DOING_CAPACITY: &str = "resolver.memory.doing.capacity";
@@ -141,6 +147,15 @@ fn emit_query_stats_to_statsd(
         .count_with_tags(METRIC_NAME_QUERY_TOTAL, diff_value, common_tags)
         .with_tag(TAG_KEY_RR_TYPE, rr_type)
         .send();
+
+    if diff_value > 0 {
+        let diff_cached = stats.cached.wrapping_sub(snap.cached);
+        let ratio = diff_cached as f64 / diff_value as f64;
+        client
+            .gauge_float_with_tags(METRIC_NAME_QUERY_CACHE_HIT_RATIO, ratio, common_tags)
+            .with_tag(TAG_KEY_RR_TYPE, rr_type)
+            .send();
+    }
     snap.total = new_value;
 
     macro_rules! emit_query_stats_u64 {
@@ -155,17 +170,52 @@ fn emit_query_stats_to_statsd(
                 snap.$id = new_value;
             }
         };
+        ($id:ident, $name:expr, $rcode:expr) => {
+            let new_value = stats.$id;
+            if new_value != 0 || snap.$id != 0 {
+                let diff_value = new_value.wrapping_sub(snap.$id);
+                client
+                    .count_with_tags($name, diff_value, common_tags)
+                    .with_tag(TAG_KEY_RR_TYPE, rr_type)
+                    .send();
+                client
+                    .count_with_tags(METRIC_NAME_QUERY_RCODE, diff_value, common_tags)
+                    .with_tag(TAG_KEY_RR_TYPE, rr_type)
+                    .with_tag(TAG_KEY_RCODE, $rcode)
+                    .send();
+                snap.$id = new_value;
+            }
+        };
     }
 
     emit_query_stats_u64!(cached, METRIC_NAME_QUERY_CACHED);
     emit_query_stats_u64!(driver, METRIC_NAME_QUERY_DRIVER);
-    emit_query_stats_u64!(driver_timeout, METRIC_NAME_QUERY_DRIVER_TIMEOUT);
+    emit_query_stats_u64!(prefetch, METRIC_NAME_QUERY_DRIVER_PREFETCH);
+    emit_query_stats_u64!(driver_timeout, METRIC_NAME_QUERY_DRIVER_TIMEOUT, "TIMEOUT");
     emit_query_stats_u64!(driver_refused, METRIC_NAME_QUERY_DRIVER_REFUSED);
     emit_query_stats_u64!(driver_malformed, METRIC_NAME_QUERY_DRIVER_MALFORMED);
-    emit_query_stats_u64!(server_refused, METRIC_NAME_QUERY_SERVER_REFUSED);
-    emit_query_stats_u64!(server_malformed, METRIC_NAME_QUERY_SERVER_MALFORMED);
-    emit_query_stats_u64!(server_not_found, METRIC_NAME_QUERY_SERVER_NOT_FOUND);
-    emit_query_stats_u64!(server_serv_fail, METRIC_NAME_QUERY_SERVER_SERV_FAIL);
+    emit_query_stats_u64!(
+        server_no_error,
+        METRIC_NAME_QUERY_SERVER_NO_ERROR,
+        "NOERROR"
+    );
+    emit_query_stats_u64!(server_refused, METRIC_NAME_QUERY_SERVER_REFUSED, "REFUSED");
+    emit_query_stats_u64!(
+        server_malformed,
+        METRIC_NAME_QUERY_SERVER_MALFORMED,
+        "FORMERR"
+    );
+    emit_query_stats_u64!(
+        server_not_found,
+        METRIC_NAME_QUERY_SERVER_NOT_FOUND,
+        "NXDOMAIN"
+    );
+    emit_query_stats_u64!(
+        server_serv_fail,
+        METRIC_NAME_QUERY_SERVER_SERV_FAIL,
+        "SERVFAIL"
+    );
+    emit_query_stats_u64!(server_not_imp, METRIC_NAME_QUERY_SERVER_NOT_IMP, "NOTIMP");
 }
 
 fn emit_memory_stats_to_statsd(
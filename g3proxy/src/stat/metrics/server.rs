@@ -21,12 +21,12 @@ use once_cell::sync::Lazy;
 
 use g3_daemon::listen::{ListenSnapshot, ListenStats};
 use g3_daemon::metrics::{
-    ServerMetricExt, TAG_KEY_TRANSPORT, TRANSPORT_TYPE_TCP, TRANSPORT_TYPE_UDP,
+    ServerMetricExt, TAG_KEY_QUANTILE, TAG_KEY_TRANSPORT, TRANSPORT_TYPE_TCP, TRANSPORT_TYPE_UDP,
 };
 use g3_statsd_client::{StatsdClient, StatsdTagGroup};
 use g3_types::stats::{StatId, TcpIoSnapshot, UdpIoSnapshot};
 
-use crate::serve::{ArcServerStats, ServerForbiddenSnapshot};
+use crate::serve::{ArcServerStats, ServerForbiddenSnapshot, ServerTaskDurationStats};
 use crate::stat::types::UntrustedTaskStatsSnapshot;
 
 const METRIC_NAME_SERVER_CONN_TOTAL: &str = "server.connection.total";
@@ -42,6 +42,9 @@ const METRIC_NAME_SERVER_IO_OUT_PACKETS: &str = "server.traffic.out.packets";
 const METRIC_NAME_SERVER_UNTRUSTED_TASK_TOTAL: &str = "server.task.untrusted_total";
 const METRIC_NAME_SERVER_UNTRUSTED_TASK_ALIVE: &str = "server.task.untrusted_alive";
 const METRIC_NAME_SERVER_IO_UNTRUSTED_IN_BYTES: &str = "server.traffic.untrusted_in.bytes";
+const METRIC_NAME_SERVER_TASK_READY_DURATION: &str = "server.task.ready.duration";
+const METRIC_NAME_SERVER_TASK_TOTAL_DURATION: &str = "server.task.duration";
+const METRIC_NAME_SERVER_TASK_FIRST_BYTE_DURATION: &str = "server.task.first_byte.duration";
 
 type ServerStatsValue = (ArcServerStats, ServerSnapshot);
 type ListenStatsValue = (Arc<ListenStats>, ListenSnapshot);
@@ -148,6 +151,10 @@ fn emit_server_stats(client: &mut StatsdClient, stats: &ArcServerStats, snap: &m
     if let Some(untrusted_stats) = stats.untrusted_snapshot() {
         emit_untrusted_stats(client, untrusted_stats, &mut snap.untrusted, &common_tags);
     }
+
+    if let Some(duration_stats) = stats.duration_stats() {
+        emit_duration_stats(client, &duration_stats, &common_tags);
+    }
 }
 
 fn emit_forbidden_stats(
@@ -268,3 +275,28 @@ fn emit_untrusted_stats(
         .send();
     snap.in_bytes = new_value;
 }
+
+fn emit_duration_stats(
+    client: &mut StatsdClient,
+    stats: &ServerTaskDurationStats,
+    common_tags: &StatsdTagGroup,
+) {
+    stats.task_ready.foreach_stat(|_, quantile, v| {
+        client
+            .gauge_float_with_tags(METRIC_NAME_SERVER_TASK_READY_DURATION, v, common_tags)
+            .with_tag(TAG_KEY_QUANTILE, quantile)
+            .send();
+    });
+    stats.task_total.foreach_stat(|_, quantile, v| {
+        client
+            .gauge_float_with_tags(METRIC_NAME_SERVER_TASK_TOTAL_DURATION, v, common_tags)
+            .with_tag(TAG_KEY_QUANTILE, quantile)
+            .send();
+    });
+    stats.first_byte.foreach_stat(|_, quantile, v| {
+        client
+            .gauge_float_with_tags(METRIC_NAME_SERVER_TASK_FIRST_BYTE_DURATION, v, common_tags)
+            .with_tag(TAG_KEY_QUANTILE, quantile)
+            .send();
+    });
+}
@@ -35,13 +35,15 @@ use crate::auth::{
 };
 use crate::stat::types::{
     ConnectionSnapshot, ConnectionStats, KeepaliveRequestSnapshot, KeepaliveRequestStats,
-    L7ConnectionAliveStats, RequestAliveStats, RequestSnapshot, RequestStats, TrafficSnapshot,
-    TrafficStats, UpstreamTrafficSnapshot, UpstreamTrafficStats,
+    L7ConnectionAliveStats, ProtocolTrafficSnapshot, ProtocolTrafficStats, RequestAliveStats,
+    RequestSnapshot, RequestStats, TrafficProtocolHint, TrafficSnapshot, TrafficStats,
+    UpstreamTrafficSnapshot, UpstreamTrafficStats,
 };
 
-const TAG_KEY_USER_GROUP: &str = "user_group";
+pub(super) const TAG_KEY_USER_GROUP: &str = "user_group";
 const TAG_KEY_USER: &str = "user";
 const TAG_KEY_USER_TYPE: &str = "user_type";
+const TAG_KEY_PROTOCOL_HINT: &str = "protocol_hint";
 
 const METRIC_NAME_FORBIDDEN_AUTH_FAILED: &str = "user.forbidden.auth_failed";
 const METRIC_NAME_FORBIDDEN_USER_EXPIRED: &str = "user.forbidden.user_expired";
@@ -54,6 +56,9 @@ const METRIC_NAME_FORBIDDEN_DEST_DENIED: &str = "user.forbidden.dest_denied";
 const METRIC_NAME_FORBIDDEN_IP_BLOCKED: &str = "user.forbidden.ip_blocked";
 const METRIC_NAME_FORBIDDEN_LOG_SKIPPED: &str = "user.forbidden.log_skipped";
 const METRIC_NAME_FORBIDDEN_UA_BLOCKED: &str = "user.forbidden.ua_blocked";
+const METRIC_NAME_FORBIDDEN_TIME_BLOCKED: &str = "user.forbidden.time_blocked";
+const METRIC_NAME_FORBIDDEN_QUOTA_EXCEEDED: &str = "user.forbidden.quota_exceeded";
+const METRIC_NAME_FORBIDDEN_CONN_LIMITED: &str = "user.forbidden.conn_limited";
 
 pub(super) struct RequestStatsNamesRef<'a> {
     pub(super) connection_total: &'a str,
@@ -89,6 +94,13 @@ const TRAFFIC_STATS_NAMES: TrafficStatsNamesRef<'static> = TrafficStatsNamesRef
     out_packets: "user.traffic.out.packets",
 };
 
+const PROTOCOL_TRAFFIC_STATS_NAMES: TrafficStatsNamesRef<'static> = TrafficStatsNamesRef {
+    in_bytes: "user.traffic.protocol_hint.in.bytes",
+    in_packets: "user.traffic.protocol_hint.in.packets",
+    out_bytes: "user.traffic.protocol_hint.out.bytes",
+    out_packets: "user.traffic.protocol_hint.out.packets",
+};
+
 const UPSTREAM_TRAFFIC_STATS_NAMES: TrafficStatsNamesRef<'static> = TrafficStatsNamesRef {
     in_bytes: "user.upstream.traffic.in.bytes",
     in_packets: "user.upstream.traffic.in.packets",
@@ -301,82 +313,172 @@ fn emit_user_forbidden_stats(
     emit_forbid_stats_u64!(dest_denied, METRIC_NAME_FORBIDDEN_DEST_DENIED);
     emit_forbid_stats_u64!(ip_blocked, METRIC_NAME_FORBIDDEN_IP_BLOCKED);
     emit_forbid_stats_u64!(ua_blocked, METRIC_NAME_FORBIDDEN_UA_BLOCKED);
+    emit_forbid_stats_u64!(time_blocked, METRIC_NAME_FORBIDDEN_TIME_BLOCKED);
+    emit_forbid_stats_u64!(quota_exceeded, METRIC_NAME_FORBIDDEN_QUOTA_EXCEEDED);
+    emit_forbid_stats_u64!(conn_limited, METRIC_NAME_FORBIDDEN_CONN_LIMITED);
     emit_forbid_stats_u64!(log_skipped, METRIC_NAME_FORBIDDEN_LOG_SKIPPED);
 }
 
-pub(super) fn emit_user_request_stats<'a>(
-    client: &'a mut StatsdClient,
-    stats: &'a UserRequestStats,
-    snap: &'a mut UserRequestSnapshot,
-    names: &'a RequestStatsNamesRef<'a>,
-) {
-    let mut common_tags = StatsdTagGroup::default();
-    common_tags.add_user_request_tags(
-        stats.user_group(),
-        stats.user(),
-        stats.user_type(),
-        stats.server(),
-        stats.stat_id(),
-    );
-    if let Some(server_extra_tags) = stats.server_extra_tags() {
-        common_tags.add_static_tags(&server_extra_tags);
+#[derive(Clone, Copy)]
+pub(super) enum UserMetricValue {
+    Count(u64),
+    Gauge(i32),
+}
+
+impl UserMetricValue {
+    pub(super) fn send(
+        self,
+        client: &mut StatsdClient,
+        name: &str,
+        common_tags: &StatsdTagGroup,
+        tag_key: &str,
+        tag_value: &str,
+    ) {
+        match self {
+            UserMetricValue::Count(v) => client
+                .count_with_tags(name, v, common_tags)
+                .with_tag(tag_key, tag_value)
+                .send(),
+            UserMetricValue::Gauge(v) => client
+                .gauge_with_tags(name, v, common_tags)
+                .with_tag(tag_key, tag_value)
+                .send(),
+        }
     }
+}
 
+pub(super) fn visit_user_request_stats<'a, F>(
+    stats: &UserRequestStats,
+    snap: &mut UserRequestSnapshot,
+    names: &RequestStatsNamesRef<'a>,
+    mut visit: F,
+) where
+    F: FnMut(&'a str, UserMetricValue, &'static str, &'static str),
+{
     find_conn_stat(
         &stats.conn_total,
         &mut snap.conn_total,
         |value, conn_type| {
-            client
-                .count_with_tags(names.connection_total, value, &common_tags)
-                .with_tag(TAG_KEY_CONNECTION, conn_type)
-                .send();
+            visit(
+                names.connection_total,
+                UserMetricValue::Count(value),
+                TAG_KEY_CONNECTION,
+                conn_type.as_str(),
+            );
         },
     );
 
     find_l7conn_alive_stat(&stats.l7_conn_alive, |value, conn_type| {
-        client
-            .gauge_with_tags(names.l7_connection_alive, value, &common_tags)
-            .with_tag(TAG_KEY_CONNECTION, conn_type)
-            .send();
+        visit(
+            names.l7_connection_alive,
+            UserMetricValue::Gauge(value),
+            TAG_KEY_CONNECTION,
+            conn_type.as_str(),
+        );
     });
 
     find_req_stat(&stats.req_total, &mut snap.req_total, |value, req_type| {
-        client
-            .count_with_tags(names.request_total, value, &common_tags)
-            .with_tag(TAG_KEY_REQUEST, req_type)
-            .send();
+        visit(
+            names.request_total,
+            UserMetricValue::Count(value),
+            TAG_KEY_REQUEST,
+            req_type.as_str(),
+        );
     });
 
     find_req_alive_stat(&stats.req_alive, |value, req_type| {
-        client
-            .gauge_with_tags(names.request_alive, value, &common_tags)
-            .with_tag(TAG_KEY_REQUEST, req_type)
-            .send();
+        visit(
+            names.request_alive,
+            UserMetricValue::Gauge(value),
+            TAG_KEY_REQUEST,
+            req_type.as_str(),
+        );
     });
 
     find_req_stat(&stats.req_ready, &mut snap.req_ready, |value, req_type| {
-        client
-            .count_with_tags(names.request_ready, value, &common_tags)
-            .with_tag(TAG_KEY_REQUEST, req_type)
-            .send();
+        visit(
+            names.request_ready,
+            UserMetricValue::Count(value),
+            TAG_KEY_REQUEST,
+            req_type.as_str(),
+        );
     });
 
     find_keepalive_req_stat(&stats.req_reuse, &mut snap.req_reuse, |value, req_type| {
-        client
-            .count_with_tags(names.request_reuse, value, &common_tags)
-            .with_tag(TAG_KEY_REQUEST, req_type)
-            .send();
+        visit(
+            names.request_reuse,
+            UserMetricValue::Count(value),
+            TAG_KEY_REQUEST,
+            req_type.as_str(),
+        );
     });
 
     find_keepalive_req_stat(&stats.req_renew, &mut snap.req_renew, |value, req_type| {
-        client
-            .count_with_tags(names.request_renew, value, &common_tags)
-            .with_tag(TAG_KEY_REQUEST, req_type)
-            .send();
+        visit(
+            names.request_renew,
+            UserMetricValue::Count(value),
+            TAG_KEY_REQUEST,
+            req_type.as_str(),
+        );
+    });
+}
+
+fn emit_user_request_stats<'a>(
+    client: &'a mut StatsdClient,
+    stats: &'a UserRequestStats,
+    snap: &'a mut UserRequestSnapshot,
+    names: &'a RequestStatsNamesRef<'a>,
+) {
+    let mut common_tags = StatsdTagGroup::default();
+    common_tags.add_user_request_tags(
+        stats.user_group(),
+        stats.user(),
+        stats.user_type(),
+        stats.server(),
+        stats.stat_id(),
+    );
+    if let Some(server_extra_tags) = stats.server_extra_tags() {
+        common_tags.add_static_tags(&server_extra_tags);
+    }
+
+    visit_user_request_stats(stats, snap, names, |name, value, tag_key, tag_value| {
+        value.send(client, name, &common_tags, tag_key, tag_value);
     });
 }
 
-pub(super) fn emit_user_traffic_stats<'a>(
+pub(super) fn visit_user_traffic_stats<'a, F>(
+    stats: &'a UserTrafficStats,
+    snap: &'a mut UserTrafficSnapshot,
+    names: &'a TrafficStatsNamesRef<'a>,
+    mut visit: F,
+) where
+    F: FnMut(&'a str, UserMetricValue, &'static str, &'static str),
+{
+    find_io_stat(&stats.io, &mut snap.io, names, |key, value, req_type| {
+        visit(
+            key,
+            UserMetricValue::Count(value),
+            TAG_KEY_REQUEST,
+            req_type.as_str(),
+        );
+    });
+
+    find_protocol_io_stat(
+        &stats.io.protocol,
+        &mut snap.io.protocol,
+        &PROTOCOL_TRAFFIC_STATS_NAMES,
+        |key, value, protocol| {
+            visit(
+                key,
+                UserMetricValue::Count(value),
+                TAG_KEY_PROTOCOL_HINT,
+                protocol.as_str(),
+            );
+        },
+    );
+}
+
+fn emit_user_traffic_stats<'a>(
     client: &'a mut StatsdClient,
     stats: &'a UserTrafficStats,
     snap: &'a mut UserTrafficSnapshot,
@@ -394,15 +496,30 @@ pub(super) fn emit_user_traffic_stats<'a>(
         common_tags.add_static_tags(&server_extra_tags);
     }
 
-    find_io_stat(&stats.io, &mut snap.io, names, |key, value, req_type| {
-        client
-            .count_with_tags(key, value, &common_tags)
-            .with_tag(TAG_KEY_REQUEST, req_type)
-            .send();
+    visit_user_traffic_stats(stats, snap, names, |name, value, tag_key, tag_value| {
+        value.send(client, name, &common_tags, tag_key, tag_value);
     });
 }
 
-pub(super) fn emit_user_upstream_traffic_stats<'a>(
+pub(super) fn visit_user_upstream_traffic_stats<'a, F>(
+    stats: &'a UserUpstreamTrafficStats,
+    snap: &'a mut UserUpstreamTrafficSnapshot,
+    names: &'a TrafficStatsNamesRef<'a>,
+    mut visit: F,
+) where
+    F: FnMut(&'a str, UserMetricValue, &'static str, &'static str),
+{
+    find_ups_io_stat(&stats.io, &mut snap.io, names, |key, value, trans_type| {
+        visit(
+            key,
+            UserMetricValue::Count(value),
+            TAG_KEY_TRANSPORT,
+            trans_type.as_str(),
+        );
+    });
+}
+
+fn emit_user_upstream_traffic_stats<'a>(
     client: &'a mut StatsdClient,
     stats: &'a UserUpstreamTrafficStats,
     snap: &'a mut UserUpstreamTrafficSnapshot,
@@ -420,11 +537,8 @@ pub(super) fn emit_user_upstream_traffic_stats<'a>(
         common_tags.add_static_tags(&escaper_extra_tags);
     }
 
-    find_ups_io_stat(&stats.io, &mut snap.io, names, |key, value, trans_type| {
-        client
-            .count_with_tags(key, value, &common_tags)
-            .with_tag(TAG_KEY_TRANSPORT, trans_type)
-            .send();
+    visit_user_upstream_traffic_stats(stats, snap, names, |name, value, tag_key, tag_value| {
+        value.send(client, name, &common_tags, tag_key, tag_value);
     });
 }
 
@@ -624,6 +738,46 @@ fn find_udp_io_stat<'a, F>(
     emit_field!(out_bytes);
 }
 
+fn find_protocol_io_stat<'a, F>(
+    stats: &'a ProtocolTrafficStats,
+    snap: &'a mut ProtocolTrafficSnapshot,
+    names: &'a TrafficStatsNamesRef<'a>,
+    mut emit: F,
+) where
+    F: FnMut(&'a str, u64, TrafficProtocolHint),
+{
+    macro_rules! emit_field {
+        ($new:ident, $old:ident, $field:ident, $protocol:expr) => {
+            let new_value = $new.$field;
+            let diff_value = new_value.wrapping_sub($old.$field);
+            emit(names.$field, diff_value, $protocol);
+            $old.$field = new_value;
+        };
+    }
+
+    macro_rules! emit_protocol {
+        ($field:ident, $protocol:expr) => {
+            let new = stats.$field.snapshot();
+            let old = &mut snap.$field;
+            if new.in_bytes != 0 || old.in_bytes != 0 {
+                emit_field!(new, old, in_bytes, $protocol);
+                emit_field!(new, old, out_bytes, $protocol);
+                // packets are only counted for udp traffic
+                if new.in_packets != 0 || old.in_packets != 0 {
+                    emit_field!(new, old, in_packets, $protocol);
+                    emit_field!(new, old, out_packets, $protocol);
+                }
+            }
+        };
+    }
+
+    emit_protocol!(http, TrafficProtocolHint::Http);
+    emit_protocol!(tls, TrafficProtocolHint::Tls);
+    emit_protocol!(dns, TrafficProtocolHint::Dns);
+    emit_protocol!(quic, TrafficProtocolHint::Quic);
+    emit_protocol!(other, TrafficProtocolHint::Other);
+}
+
 fn find_ups_io_stat<'a, F>(
     stats: &UpstreamTrafficStats,
     snap: &'a mut UpstreamTrafficSnapshot,
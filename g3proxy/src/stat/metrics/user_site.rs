@@ -19,17 +19,22 @@ use std::sync::{Arc, Mutex};
 use ahash::AHashMap;
 use once_cell::sync::Lazy;
 
-use g3_daemon::metrics::TAG_KEY_QUANTILE;
+use g3_daemon::metrics::{TAG_KEY_QUANTILE, TAG_KEY_SERVER};
 use g3_statsd_client::{StatsdClient, StatsdTagGroup};
 use g3_types::metrics::MetricsName;
 use g3_types::stats::StatId;
 
-use super::{RequestStatsNamesRef, TrafficStatsNamesRef, UserMetricExt};
+use super::user::{UserMetricValue, TAG_KEY_USER_GROUP};
+use super::{RequestStatsNamesRef, TrafficStatsNamesRef, UserMetricExt, TAG_KEY_ESCAPER};
 use crate::auth::{
-    UserRequestSnapshot, UserRequestStats, UserSiteDurationStats, UserTrafficSnapshot,
-    UserTrafficStats, UserUpstreamTrafficSnapshot, UserUpstreamTrafficStats,
+    UserRequestSnapshot, UserRequestStats, UserSiteDurationStats, UserSiteEmitControl,
+    UserTrafficSnapshot, UserTrafficStats, UserUpstreamTrafficSnapshot, UserUpstreamTrafficStats,
 };
 
+const TAG_KEY_ROLLUP: &str = "rollup";
+const ROLLUP_LEVEL_SITE: &str = "site";
+const ROLLUP_LEVEL_USER_GROUP: &str = "user_group";
+
 static STORE_REQUEST_STATS_MAP: Lazy<Mutex<AHashMap<StatId, RequestStatsValue>>> =
     Lazy::new(|| Mutex::new(AHashMap::new()));
 static STORE_TRAFFIC_STATS_MAP: Lazy<Mutex<AHashMap<StatId, TrafficStatsValue>>> =
@@ -116,14 +121,20 @@ struct RequestStatsValue {
     stats: Arc<UserRequestStats>,
     snap: UserRequestSnapshot,
     names: RequestStatsNames,
+    control: Arc<UserSiteEmitControl>,
 }
 
 impl RequestStatsValue {
-    fn new(stats: Arc<UserRequestStats>, site_id: &MetricsName) -> Self {
+    fn new(
+        stats: Arc<UserRequestStats>,
+        site_id: &MetricsName,
+        control: &Arc<UserSiteEmitControl>,
+    ) -> Self {
         RequestStatsValue {
             stats,
             snap: Default::default(),
             names: RequestStatsNames::new(site_id),
+            control: control.clone(),
         }
     }
 }
@@ -132,14 +143,20 @@ struct TrafficStatsValue {
     stats: Arc<UserTrafficStats>,
     snap: UserTrafficSnapshot,
     names: TrafficStatsNames,
+    control: Arc<UserSiteEmitControl>,
 }
 
 impl TrafficStatsValue {
-    fn new(stats: Arc<UserTrafficStats>, site_id: &MetricsName) -> Self {
+    fn new(
+        stats: Arc<UserTrafficStats>,
+        site_id: &MetricsName,
+        control: &Arc<UserSiteEmitControl>,
+    ) -> Self {
         TrafficStatsValue {
             stats,
             snap: Default::default(),
             names: TrafficStatsNames::new_for_client(site_id),
+            control: control.clone(),
         }
     }
 }
@@ -147,13 +164,19 @@ impl TrafficStatsValue {
 struct DurationStatsValue {
     stats: Arc<UserSiteDurationStats>,
     names: DurationStatsNames,
+    control: Arc<UserSiteEmitControl>,
 }
 
 impl DurationStatsValue {
-    fn new(stats: Arc<UserSiteDurationStats>, site_id: &MetricsName) -> Self {
+    fn new(
+        stats: Arc<UserSiteDurationStats>,
+        site_id: &MetricsName,
+        control: &Arc<UserSiteEmitControl>,
+    ) -> Self {
         DurationStatsValue {
             stats,
             names: DurationStatsNames::new_for_client(site_id),
+            control: control.clone(),
         }
     }
 }
@@ -162,35 +185,143 @@ struct UpstreamTrafficStatsValue {
     stats: Arc<UserUpstreamTrafficStats>,
     snap: UserUpstreamTrafficSnapshot,
     tags: TrafficStatsNames,
+    control: Arc<UserSiteEmitControl>,
 }
 
 impl UpstreamTrafficStatsValue {
-    fn new(stats: Arc<UserUpstreamTrafficStats>, site_id: &MetricsName) -> Self {
+    fn new(
+        stats: Arc<UserUpstreamTrafficStats>,
+        site_id: &MetricsName,
+        control: &Arc<UserSiteEmitControl>,
+    ) -> Self {
         UpstreamTrafficStatsValue {
             stats,
             snap: Default::default(),
             tags: TrafficStatsNames::new_for_upstream(site_id),
+            control: control.clone(),
         }
     }
 }
 
-pub(crate) fn push_request_stats(stats: Arc<UserRequestStats>, site_id: &MetricsName) {
+#[derive(PartialEq, Eq, Hash)]
+struct RollupKey {
+    name: String,
+    level: &'static str,
+    user_group: Option<MetricsName>,
+    scope_key: &'static str,
+    scope: MetricsName,
+    tag_key: &'static str,
+    tag_value: &'static str,
+}
+
+/// the user site stats aggregated at emit time
+#[derive(Default)]
+struct RollupStats {
+    values: AHashMap<RollupKey, UserMetricValue>,
+}
+
+impl RollupStats {
+    #[allow(clippy::too_many_arguments)]
+    fn add(
+        &mut self,
+        control: &UserSiteEmitControl,
+        user_group: &MetricsName,
+        scope_key: &'static str,
+        scope: &MetricsName,
+        name: &str,
+        value: UserMetricValue,
+        tag_key: &'static str,
+        tag_value: &'static str,
+    ) {
+        if control.rollup_site() {
+            self.merge(
+                RollupKey {
+                    name: name.to_string(),
+                    level: ROLLUP_LEVEL_SITE,
+                    user_group: None,
+                    scope_key,
+                    scope: scope.clone(),
+                    tag_key,
+                    tag_value,
+                },
+                value,
+            );
+        }
+        if control.rollup_user_group() {
+            self.merge(
+                RollupKey {
+                    name: name.to_string(),
+                    level: ROLLUP_LEVEL_USER_GROUP,
+                    user_group: Some(user_group.clone()),
+                    scope_key,
+                    scope: scope.clone(),
+                    tag_key,
+                    tag_value,
+                },
+                value,
+            );
+        }
+    }
+
+    fn merge(&mut self, key: RollupKey, value: UserMetricValue) {
+        self.values
+            .entry(key)
+            .and_modify(|v| {
+                *v = match (*v, value) {
+                    (UserMetricValue::Count(a), UserMetricValue::Count(b)) => {
+                        UserMetricValue::Count(a.wrapping_add(b))
+                    }
+                    (UserMetricValue::Gauge(a), UserMetricValue::Gauge(b)) => {
+                        UserMetricValue::Gauge(a.saturating_add(b))
+                    }
+                    (v, _) => v,
+                };
+            })
+            .or_insert(value);
+    }
+
+    fn emit(self, client: &mut StatsdClient) {
+        for (key, value) in self.values {
+            let mut common_tags = StatsdTagGroup::default();
+            common_tags.add_tag(TAG_KEY_ROLLUP, key.level);
+            if let Some(user_group) = &key.user_group {
+                common_tags.add_tag(TAG_KEY_USER_GROUP, user_group);
+            }
+            common_tags.add_tag(key.scope_key, &key.scope);
+            value.send(client, &key.name, &common_tags, key.tag_key, key.tag_value);
+        }
+    }
+}
+
+pub(crate) fn push_request_stats(
+    stats: Arc<UserRequestStats>,
+    site_id: &MetricsName,
+    control: &Arc<UserSiteEmitControl>,
+) {
     let k = stats.stat_id();
-    let v = RequestStatsValue::new(stats, site_id);
+    let v = RequestStatsValue::new(stats, site_id, control);
     let mut ht = STORE_REQUEST_STATS_MAP.lock().unwrap();
     ht.insert(k, v);
 }
 
-pub(crate) fn push_traffic_stats(stats: Arc<UserTrafficStats>, site_id: &MetricsName) {
+pub(crate) fn push_traffic_stats(
+    stats: Arc<UserTrafficStats>,
+    site_id: &MetricsName,
+    control: &Arc<UserSiteEmitControl>,
+) {
     let k = stats.stat_id();
-    let v = TrafficStatsValue::new(stats, site_id);
+    let v = TrafficStatsValue::new(stats, site_id, control);
     let mut ht = STORE_TRAFFIC_STATS_MAP.lock().unwrap();
     ht.insert(k, v);
 }
 
-pub(crate) fn push_duration_stats(stats: Arc<UserSiteDurationStats>, site_id: &MetricsName) {
+pub(crate) fn push_duration_stats(
+    stats: Arc<UserSiteDurationStats>,
+    site_id: &MetricsName,
+    control: &Arc<UserSiteEmitControl>,
+) {
     let k = stats.stat_id();
-    let v = DurationStatsValue::new(stats, site_id);
+    let v = DurationStatsValue::new(stats, site_id, control);
     let mut ht = STORE_DURATION_STATS_MAP.lock().unwrap();
     ht.insert(k, v);
 }
@@ -198,9 +329,10 @@ pub(crate) fn push_duration_stats(stats: Arc<UserSiteDurationStats>, site_id: &M
 pub(crate) fn push_upstream_traffic_stats(
     stats: Arc<UserUpstreamTrafficStats>,
     site_id: &MetricsName,
+    control: &Arc<UserSiteEmitControl>,
 ) {
     let k = stats.stat_id();
-    let v = UpstreamTrafficStatsValue::new(stats, site_id);
+    let v = UpstreamTrafficStatsValue::new(stats, site_id, control);
     let mut ht = STORE_UPSTREAM_TRAFFIC_STATS_MAP.lock().unwrap();
     ht.insert(k, v);
 }
@@ -218,6 +350,8 @@ pub(in crate::stat) fn sync_stats() {
 }
 
 pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
+    let mut rollup = RollupStats::default();
+
     let mut req_stats_map = USER_SITE_REQUEST_STATS_MAP.lock().unwrap();
     req_stats_map.retain(|_, v| {
         let names = RequestStatsNamesRef {
@@ -229,7 +363,43 @@ pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
             request_renew: &v.names.request_renew,
             l7_connection_alive: &v.names.l7_connection_alive,
         };
-        super::user::emit_user_request_stats(client, &v.stats, &mut v.snap, &names);
+        let stats = &v.stats;
+        let control = &v.control;
+        let mut common_tags = None;
+        if control.emit_stats() {
+            let mut tags = StatsdTagGroup::default();
+            tags.add_user_request_tags(
+                stats.user_group(),
+                stats.user(),
+                stats.user_type(),
+                stats.server(),
+                stats.stat_id(),
+            );
+            if let Some(server_extra_tags) = stats.server_extra_tags() {
+                tags.add_static_tags(&server_extra_tags);
+            }
+            common_tags = Some(tags);
+        }
+        super::user::visit_user_request_stats(
+            stats,
+            &mut v.snap,
+            &names,
+            |name, value, tag_key, tag_value| {
+                if let Some(common_tags) = &common_tags {
+                    value.send(client, name, common_tags, tag_key, tag_value);
+                }
+                rollup.add(
+                    control,
+                    stats.user_group(),
+                    TAG_KEY_SERVER,
+                    stats.server(),
+                    name,
+                    value,
+                    tag_key,
+                    tag_value,
+                );
+            },
+        );
         // use Arc instead of Weak here, as we should emit the final metrics before drop it
         Arc::strong_count(&v.stats) > 1
     });
@@ -243,7 +413,43 @@ pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
             out_bytes: &v.names.out_bytes,
             out_packets: &v.names.out_packets,
         };
-        super::user::emit_user_traffic_stats(client, &v.stats, &mut v.snap, &names);
+        let stats = &v.stats;
+        let control = &v.control;
+        let mut common_tags = None;
+        if control.emit_stats() {
+            let mut tags = StatsdTagGroup::default();
+            tags.add_user_request_tags(
+                stats.user_group(),
+                stats.user(),
+                stats.user_type(),
+                stats.server(),
+                stats.stat_id(),
+            );
+            if let Some(server_extra_tags) = stats.server_extra_tags() {
+                tags.add_static_tags(&server_extra_tags);
+            }
+            common_tags = Some(tags);
+        }
+        super::user::visit_user_traffic_stats(
+            stats,
+            &mut v.snap,
+            &names,
+            |name, value, tag_key, tag_value| {
+                if let Some(common_tags) = &common_tags {
+                    value.send(client, name, common_tags, tag_key, tag_value);
+                }
+                rollup.add(
+                    control,
+                    stats.user_group(),
+                    TAG_KEY_SERVER,
+                    stats.server(),
+                    name,
+                    value,
+                    tag_key,
+                    tag_value,
+                );
+            },
+        );
         // use Arc instead of Weak here, as we should emit the final metrics before drop it
         Arc::strong_count(&v.stats) > 1
     });
@@ -251,7 +457,10 @@ pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
 
     let mut dur_stats_map = USER_SITE_DURATION_STATS_MAP.lock().unwrap();
     dur_stats_map.retain(|_, v| {
-        emit_site_duration_stats(client, &v.stats, &v.names);
+        // histogram stats can not be aggregated, so they are only emitted at user level
+        if v.control.emit_stats() {
+            emit_site_duration_stats(client, &v.stats, &v.names);
+        }
         // use Arc instead of Weak here, as we should emit the final metrics before drop it
         Arc::strong_count(&v.stats) > 1
     });
@@ -265,11 +474,49 @@ pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
             out_bytes: &v.tags.out_bytes,
             out_packets: &v.tags.out_packets,
         };
-        super::user::emit_user_upstream_traffic_stats(client, &v.stats, &mut v.snap, &names);
+        let stats = &v.stats;
+        let control = &v.control;
+        let mut common_tags = None;
+        if control.emit_stats() {
+            let mut tags = StatsdTagGroup::default();
+            tags.add_user_upstream_traffic_tags(
+                stats.user_group(),
+                stats.user(),
+                stats.user_type(),
+                stats.escaper(),
+                stats.stat_id(),
+            );
+            if let Some(escaper_extra_tags) = stats.escaper_extra_tags() {
+                tags.add_static_tags(&escaper_extra_tags);
+            }
+            common_tags = Some(tags);
+        }
+        super::user::visit_user_upstream_traffic_stats(
+            stats,
+            &mut v.snap,
+            &names,
+            |name, value, tag_key, tag_value| {
+                if let Some(common_tags) = &common_tags {
+                    value.send(client, name, common_tags, tag_key, tag_value);
+                }
+                rollup.add(
+                    control,
+                    stats.user_group(),
+                    TAG_KEY_ESCAPER,
+                    stats.escaper(),
+                    name,
+                    value,
+                    tag_key,
+                    tag_value,
+                );
+            },
+        );
         // use Arc instead of Weak here, as we should emit the final metrics before drop it
         Arc::strong_count(&v.stats) > 1
     });
     drop(upstream_io_stats_map);
+
+    rollup.emit(client);
 }
 
 fn emit_site_duration_stats<'a>(
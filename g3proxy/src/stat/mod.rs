@@ -52,13 +52,16 @@ fn spawn_main_thread(config: &StatsdClientConfig) -> anyhow::Result<JoinHandle<(
             metrics::escaper::sync_stats();
             metrics::resolver::sync_stats();
             metrics::user::sync_stats();
+            metrics::auditor::sync_stats();
             g3_daemon::log::metrics::sync_stats();
 
             metrics::server::emit_stats(&mut client);
             metrics::escaper::emit_stats(&mut client);
             metrics::resolver::emit_stats(&mut client);
             metrics::user::emit_stats(&mut client);
+            metrics::auditor::emit_stats(&mut client);
             g3_daemon::log::metrics::emit_stats(&mut client);
+            g3_daemon::metrics::emit_process_stats(&mut client);
 
             client.flush_sink();
 
@@ -103,6 +106,12 @@ pub fn spawn_working_threads(config: StatsdClientConfig) -> anyhow::Result<Vec<J
     let user_site_handle =
         spawn_user_site_thread(&config).context("failed to spawn user site stats thread")?;
     handlers.push(user_site_handle);
+    if let Some(exporter) = config.prometheus() {
+        let prometheus_handle =
+            g3_daemon::stat::prometheus::spawn_http_thread(exporter.clone(), &QUIT_STAT_THREAD)
+                .context("failed to spawn prometheus metrics thread")?;
+        handlers.push(prometheus_handle);
+    }
     Ok(handlers)
 }
 
@@ -25,6 +25,7 @@ pub(crate) use request::{
 
 mod traffic;
 pub(crate) use traffic::{
+    ProtocolTrafficSnapshot, ProtocolTrafficStats, TrafficProtocolCell, TrafficProtocolHint,
     TrafficSnapshot, TrafficStats, UpstreamTrafficSnapshot, UpstreamTrafficStats,
 };
 
@@ -14,6 +14,9 @@
  * limitations under the License.
  */
 
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use g3_dpi::Protocol;
 use g3_types::stats::{TcpIoSnapshot, TcpIoStats, UdpIoSnapshot, UdpIoStats};
 
 #[derive(Default)]
@@ -25,6 +28,41 @@ pub(crate) struct TrafficStats {
     pub(crate) socks_tcp_connect: TcpIoStats,
     pub(crate) socks_udp_connect: UdpIoStats,
     pub(crate) socks_udp_associate: UdpIoStats,
+    pub(crate) protocol: ProtocolTrafficStats,
+}
+
+impl TrafficStats {
+    /// total bytes in both directions, the protocol stats are not counted
+    pub(crate) fn total_bytes(&self) -> u64 {
+        let (in_bytes, out_bytes) = self.io_bytes();
+        in_bytes.wrapping_add(out_bytes)
+    }
+
+    /// (in_bytes, out_bytes), the protocol stats are not counted
+    pub(crate) fn io_bytes(&self) -> (u64, u64) {
+        let tcp = [
+            &self.http_forward,
+            &self.https_forward,
+            &self.http_connect,
+            &self.ftp_over_http,
+            &self.socks_tcp_connect,
+        ]
+        .into_iter()
+        .map(|s| {
+            let snap = s.snapshot();
+            (snap.in_bytes, snap.out_bytes)
+        });
+        let udp = [&self.socks_udp_connect, &self.socks_udp_associate]
+            .into_iter()
+            .map(|s| {
+                let snap = s.snapshot();
+                (snap.in_bytes, snap.out_bytes)
+            });
+        tcp.chain(udp)
+            .fold((0u64, 0u64), |(i, o), (in_bytes, out_bytes)| {
+                (i.wrapping_add(in_bytes), o.wrapping_add(out_bytes))
+            })
+    }
 }
 
 #[derive(Default)]
@@ -36,6 +74,185 @@ pub(crate) struct TrafficSnapshot {
     pub(crate) socks_tcp_connect: TcpIoSnapshot,
     pub(crate) socks_udp_connect: UdpIoSnapshot,
     pub(crate) socks_udp_associate: UdpIoSnapshot,
+    pub(crate) protocol: ProtocolTrafficSnapshot,
+}
+
+/// The L7 protocol hint of the relayed traffic.
+///
+/// It's the real protocol for forward requests. For tunnels it's the protocol detected by
+/// protocol inspection, or a guess by the well known upstream port if not inspected.
+/// For udp relay it's detected from the client packets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum TrafficProtocolHint {
+    Http,
+    Tls,
+    Dns,
+    Quic,
+    Other,
+}
+
+impl TrafficProtocolHint {
+    fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(TrafficProtocolHint::Http),
+            1 => Some(TrafficProtocolHint::Tls),
+            2 => Some(TrafficProtocolHint::Dns),
+            3 => Some(TrafficProtocolHint::Quic),
+            4 => Some(TrafficProtocolHint::Other),
+            _ => None,
+        }
+    }
+
+    /// the protocol detected by the protocol inspection of tcp streams
+    pub(crate) fn from_inspection(protocol: Protocol) -> Self {
+        match protocol {
+            Protocol::Http1 | Protocol::Http2 | Protocol::Websocket | Protocol::RtmpOverHttp => {
+                TrafficProtocolHint::Http
+            }
+            Protocol::SslLegacy | Protocol::TlsLegacy | Protocol::TlsModern | Protocol::TlsTlcp => {
+                TrafficProtocolHint::Tls
+            }
+            Protocol::Dns => TrafficProtocolHint::Dns,
+            Protocol::Http3 => TrafficProtocolHint::Quic,
+            _ => TrafficProtocolHint::Other,
+        }
+    }
+
+    /// detect the protocol of the udp packet sent by the client
+    pub(crate) fn from_udp_client_packet(data: &[u8]) -> Self {
+        if g3_dpi::check_quic_client_initial(data).is_some() {
+            TrafficProtocolHint::Quic
+        } else if g3_dpi::check_dns_udp_request(data) {
+            TrafficProtocolHint::Dns
+        } else {
+            TrafficProtocolHint::Other
+        }
+    }
+
+    /// guess the protocol of tcp tunnel traffic by the well known upstream port
+    pub(crate) fn from_tcp_port(port: u16) -> Self {
+        match port {
+            80 | 8080 => TrafficProtocolHint::Http,
+            443 | 465 | 563 | 636 | 853 | 989 | 990 | 992 | 993 | 994 | 995 | 8443 => {
+                TrafficProtocolHint::Tls
+            }
+            53 => TrafficProtocolHint::Dns,
+            _ => TrafficProtocolHint::Other,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficProtocolHint::Http => "http",
+            TrafficProtocolHint::Tls => "tls",
+            TrafficProtocolHint::Dns => "dns",
+            TrafficProtocolHint::Quic => "quic",
+            TrafficProtocolHint::Other => "other",
+        }
+    }
+}
+
+impl AsRef<str> for TrafficProtocolHint {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+const TRAFFIC_PROTOCOL_PENDING: u8 = u8::MAX;
+
+/// The protocol hint of a tcp tunnel, which may be set later by the protocol inspection.
+///
+/// The traffic before the protocol is known will be kept as pending,
+/// and should be added to the protocol stats after that.
+pub(crate) struct TrafficProtocolCell {
+    protocol: AtomicU8,
+    pending_in_bytes: AtomicU64,
+    pending_out_bytes: AtomicU64,
+}
+
+impl TrafficProtocolCell {
+    pub(crate) fn new(protocol: TrafficProtocolHint) -> Self {
+        TrafficProtocolCell {
+            protocol: AtomicU8::new(protocol as u8),
+            pending_in_bytes: AtomicU64::new(0),
+            pending_out_bytes: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn pending() -> Self {
+        TrafficProtocolCell {
+            protocol: AtomicU8::new(TRAFFIC_PROTOCOL_PENDING),
+            pending_in_bytes: AtomicU64::new(0),
+            pending_out_bytes: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get(&self) -> Option<TrafficProtocolHint> {
+        TrafficProtocolHint::from_u8(self.protocol.load(Ordering::Acquire))
+    }
+
+    /// set the protocol if it's still pending, the first one wins
+    pub(crate) fn resolve(&self, protocol: TrafficProtocolHint) {
+        let _ = self.protocol.compare_exchange(
+            TRAFFIC_PROTOCOL_PENDING,
+            protocol as u8,
+            Ordering::AcqRel,
+            Ordering::Relaxed,
+        );
+    }
+
+    pub(crate) fn add_pending_in_bytes(&self, size: u64) {
+        self.pending_in_bytes.fetch_add(size, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_pending_out_bytes(&self, size: u64) {
+        self.pending_out_bytes.fetch_add(size, Ordering::Relaxed);
+    }
+
+    /// take the (in_bytes, out_bytes) added while the protocol is still pending
+    pub(crate) fn take_pending(&self) -> (u64, u64) {
+        let take = |v: &AtomicU64| {
+            if v.load(Ordering::Relaxed) == 0 {
+                0
+            } else {
+                v.swap(0, Ordering::Relaxed)
+            }
+        };
+        (take(&self.pending_in_bytes), take(&self.pending_out_bytes))
+    }
+}
+
+/// The packet fields will only be updated for udp traffic
+#[derive(Default)]
+pub(crate) struct ProtocolTrafficStats {
+    pub(crate) http: UdpIoStats,
+    pub(crate) tls: UdpIoStats,
+    pub(crate) dns: UdpIoStats,
+    pub(crate) quic: UdpIoStats,
+    pub(crate) other: UdpIoStats,
+}
+
+impl ProtocolTrafficStats {
+    pub(crate) fn get(&self, protocol: TrafficProtocolHint) -> &UdpIoStats {
+        match protocol {
+            TrafficProtocolHint::Http => &self.http,
+            TrafficProtocolHint::Tls => &self.tls,
+            TrafficProtocolHint::Dns => &self.dns,
+            TrafficProtocolHint::Quic => &self.quic,
+            TrafficProtocolHint::Other => &self.other,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ProtocolTrafficSnapshot {
+    pub(crate) http: UdpIoSnapshot,
+    pub(crate) tls: UdpIoSnapshot,
+    pub(crate) dns: UdpIoSnapshot,
+    pub(crate) quic: UdpIoSnapshot,
+    pub(crate) other: UdpIoSnapshot,
 }
 
 #[derive(Default)]
@@ -49,3 +266,81 @@ pub(crate) struct UpstreamTrafficSnapshot {
     pub(crate) tcp: TcpIoSnapshot,
     pub(crate) udp: UdpIoSnapshot,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inspection() {
+        assert_eq!(
+            TrafficProtocolHint::from_inspection(Protocol::Http1),
+            TrafficProtocolHint::Http
+        );
+        assert_eq!(
+            TrafficProtocolHint::from_inspection(Protocol::TlsModern),
+            TrafficProtocolHint::Tls
+        );
+        assert_eq!(
+            TrafficProtocolHint::from_inspection(Protocol::Dns),
+            TrafficProtocolHint::Dns
+        );
+        assert_eq!(
+            TrafficProtocolHint::from_inspection(Protocol::Ssh),
+            TrafficProtocolHint::Other
+        );
+        assert_eq!(
+            TrafficProtocolHint::from_inspection(Protocol::Unknown),
+            TrafficProtocolHint::Other
+        );
+    }
+
+    #[test]
+    fn udp_client_packet() {
+        let mut quic = vec![0xc0, 0x00, 0x00, 0x00, 0x01, 0x08];
+        quic.extend_from_slice(&[0x83, 0x94, 0xc8, 0xf0, 0x3e, 0x51, 0x57, 0x08]);
+        quic.extend_from_slice(&[0x00, 0x00, 0x44, 0xd0]);
+        quic.resize(1250, 0);
+        assert_eq!(
+            TrafficProtocolHint::from_udp_client_packet(&quic),
+            TrafficProtocolHint::Quic
+        );
+        // not padded to the minimum initial datagram size
+        assert_eq!(
+            TrafficProtocolHint::from_udp_client_packet(&quic[..1000]),
+            TrafficProtocolHint::Other
+        );
+
+        let dns = [
+            0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x01,
+        ];
+        assert_eq!(
+            TrafficProtocolHint::from_udp_client_packet(&dns),
+            TrafficProtocolHint::Dns
+        );
+
+        assert_eq!(
+            TrafficProtocolHint::from_udp_client_packet(b"hello world"),
+            TrafficProtocolHint::Other
+        );
+    }
+
+    #[test]
+    fn cell_resolve() {
+        let cell = TrafficProtocolCell::pending();
+        assert!(cell.get().is_none());
+        cell.add_pending_in_bytes(10);
+        cell.add_pending_out_bytes(20);
+
+        cell.resolve(TrafficProtocolHint::Tls);
+        cell.resolve(TrafficProtocolHint::Http);
+        assert_eq!(cell.get(), Some(TrafficProtocolHint::Tls));
+        assert_eq!(cell.take_pending(), (10, 20));
+        assert_eq!(cell.take_pending(), (0, 0));
+
+        let cell = TrafficProtocolCell::new(TrafficProtocolHint::Dns);
+        cell.resolve(TrafficProtocolHint::Http);
+        assert_eq!(cell.get(), Some(TrafficProtocolHint::Dns));
+    }
+}
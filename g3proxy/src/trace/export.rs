@@ -0,0 +1,184 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::sync::OnceLock;
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+use anyhow::{anyhow, Context};
+use log::warn;
+use serde_json::{json, Value};
+
+use super::SpanData;
+use crate::config::trace::TraceExportConfig;
+
+static QUIT_EXPORT_THREAD: AtomicBool = AtomicBool::new(false);
+static SPAN_SENDER: OnceLock<SyncSender<Vec<SpanData>>> = OnceLock::new();
+
+pub(super) fn send(spans: Vec<SpanData>) {
+    if let Some(sender) = SPAN_SENDER.get() {
+        // drop the spans if the queue is full
+        let _ = sender.try_send(spans);
+    }
+}
+
+fn span_to_json(span: SpanData) -> Value {
+    let attributes: Vec<Value> = span
+        .attributes
+        .into_iter()
+        .map(|(k, v)| json!({"key": k, "value": {"stringValue": v}}))
+        .collect();
+    let mut v = json!({
+        "traceId": hex::encode(span.trace_id),
+        "spanId": hex::encode(span.span_id),
+        "name": span.name,
+        "kind": 2,
+        "startTimeUnixNano": span.start_unix_nanos.to_string(),
+        "endTimeUnixNano": span.end_unix_nanos.to_string(),
+        "attributes": attributes,
+    });
+    if let Some(parent) = span.parent_span_id {
+        v["parentSpanId"] = Value::String(hex::encode(parent));
+    }
+    v
+}
+
+fn build_body(config: &TraceExportConfig, spans: Vec<SpanData>) -> Vec<u8> {
+    let spans: Vec<Value> = spans.into_iter().map(span_to_json).collect();
+    let doc = json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    {"key": "service.name", "value": {"stringValue": config.service_name}},
+                    {"key": "daemon_group", "value": {"stringValue": crate::opts::daemon_group()}},
+                ],
+            },
+            "scopeSpans": [{
+                "scope": {"name": crate::build::PKG_NAME, "version": crate::build::VERSION},
+                "spans": spans,
+            }],
+        }],
+    });
+    serde_json::to_vec(&doc).unwrap_or_default()
+}
+
+fn post(config: &TraceExportConfig, body: &[u8]) -> anyhow::Result<()> {
+    let url = &config.endpoint;
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addr = (host, port)
+        .to_socket_addrs()
+        .context(format!("failed to resolve {host}"))?
+        .next()
+        .ok_or_else(|| anyhow!("no address found for {host}"))?;
+
+    let mut stream = TcpStream::connect_timeout(&addr, config.timeout)
+        .context(format!("failed to connect to {addr}"))?;
+    stream.set_read_timeout(Some(config.timeout))?;
+    stream.set_write_timeout(Some(config.timeout))?;
+
+    let header = format!(
+        "POST {} HTTP/1.1\r\nHost: {host}:{port}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n",
+        url.path(),
+        body.len()
+    );
+    stream.write_all(header.as_bytes())?;
+    stream.write_all(body)?;
+
+    let mut buf = [0u8; 32];
+    let mut len = 0;
+    while len < 12 {
+        let nr = stream.read(&mut buf[len..])?;
+        if nr == 0 {
+            return Err(anyhow!("connection closed before response received"));
+        }
+        len += nr;
+    }
+    // "HTTP/1.1 200"
+    match buf.get(9) {
+        Some(b'2') => Ok(()),
+        _ => Err(anyhow!(
+            "unexpected response: {}",
+            String::from_utf8_lossy(&buf[..len])
+        )),
+    }
+}
+
+fn flush(config: &TraceExportConfig, batch: &mut Vec<SpanData>) {
+    if batch.is_empty() {
+        return;
+    }
+    let body = build_body(config, std::mem::take(batch));
+    if let Err(e) = post(config, &body) {
+        warn!("failed to export trace spans to {}: {e:?}", config.endpoint);
+    }
+}
+
+fn run(config: &'static TraceExportConfig, receiver: Receiver<Vec<SpanData>>) {
+    let mut batch = Vec::with_capacity(config.batch_size);
+    let mut last_flush = Instant::now();
+    loop {
+        let timeout = config.flush_interval.saturating_sub(last_flush.elapsed());
+        match receiver.recv_timeout(timeout) {
+            Ok(spans) => {
+                batch.extend(spans);
+                if batch.len() < config.batch_size && last_flush.elapsed() < config.flush_interval {
+                    continue;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        flush(config, &mut batch);
+        last_flush = Instant::now();
+
+        if QUIT_EXPORT_THREAD.load(Ordering::Relaxed) {
+            break;
+        }
+    }
+
+    while let Ok(spans) = receiver.try_recv() {
+        batch.extend(spans);
+    }
+    flush(config, &mut batch);
+}
+
+pub fn spawn_export_thread() -> anyhow::Result<Option<JoinHandle<()>>> {
+    let Some(config) = crate::config::trace::get_global_config() else {
+        return Ok(None);
+    };
+
+    let (sender, receiver) = mpsc::sync_channel(config.queue_size);
+    SPAN_SENDER
+        .set(sender)
+        .map_err(|_| anyhow!("trace export thread has already been spawned"))?;
+
+    let handle = std::thread::Builder::new()
+        .name("trace-export".to_string())
+        .spawn(move || run(config, receiver))
+        .map_err(|e| anyhow!("failed to spawn thread: {e:?}"))?;
+    Ok(Some(handle))
+}
+
+pub fn stop_export_thread() {
+    QUIT_EXPORT_THREAD.store(true, Ordering::Relaxed);
+}
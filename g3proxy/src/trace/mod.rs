@@ -0,0 +1,326 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use http::HeaderName;
+
+use g3_types::net::{HttpHeaderMap, HttpHeaderValue};
+
+mod export;
+pub use export::{spawn_export_thread, stop_export_thread};
+
+const TRACEPARENT_HEADER: &str = "traceparent";
+
+struct SpanData {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_span_id: Option<[u8; 8]>,
+    name: &'static str,
+    start_unix_nanos: u64,
+    end_unix_nanos: u64,
+    attributes: Vec<(&'static str, String)>,
+}
+
+fn new_trace_id() -> [u8; 16] {
+    loop {
+        let id = rand::random::<[u8; 16]>();
+        if id != [0u8; 16] {
+            return id;
+        }
+    }
+}
+
+fn new_span_id() -> [u8; 8] {
+    loop {
+        let id = rand::random::<[u8; 8]>();
+        if id != [0u8; 8] {
+            return id;
+        }
+    }
+}
+
+/// decode a lowercase hex field of the traceparent header
+fn decode_field<const N: usize>(s: &str) -> Option<[u8; N]> {
+    if s.bytes().any(|b| b.is_ascii_uppercase()) {
+        return None;
+    }
+    let mut buf = [0u8; N];
+    hex::decode_to_slice(s, &mut buf).ok()?;
+    Some(buf)
+}
+
+/// parse the W3C traceparent header value, return the trace id, parent id and the sampled flag
+fn parse_traceparent(value: &str) -> Option<([u8; 16], [u8; 8], bool)> {
+    let mut parts = value.trim().split('-');
+    let version = decode_field::<1>(parts.next()?)?;
+    if version[0] == 0xff {
+        return None;
+    }
+    let trace_id = decode_field::<16>(parts.next()?)?;
+    let parent_id = decode_field::<8>(parts.next()?)?;
+    let flags = decode_field::<1>(parts.next()?)?;
+    // future versions may append more fields
+    if version[0] == 0 && parts.next().is_some() {
+        return None;
+    }
+    if trace_id == [0u8; 16] || parent_id == [0u8; 8] {
+        return None;
+    }
+    Some((trace_id, parent_id, flags[0] & 0x01 != 0))
+}
+
+/// The trace context of a server task, all spans will be exported when the task finished
+pub(crate) struct TaskTrace {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_span_id: Option<[u8; 8]>,
+    sampled: bool,
+    create_ins: Instant,
+    create_unix_nanos: u64,
+    spans: Mutex<Vec<SpanData>>,
+}
+
+impl TaskTrace {
+    pub(crate) fn new(create_ins: Instant) -> Option<Self> {
+        crate::config::trace::get_global_config()?;
+        let create_unix_nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Some(TaskTrace {
+            trace_id: new_trace_id(),
+            span_id: new_span_id(),
+            parent_span_id: None,
+            sampled: true,
+            create_ins,
+            create_unix_nanos,
+            spans: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn unix_nanos(&self, ins: Instant) -> u64 {
+        match ins.checked_duration_since(self.create_ins) {
+            Some(d) => self.create_unix_nanos + d.as_nanos() as u64,
+            None => self
+                .create_unix_nanos
+                .saturating_sub((self.create_ins - ins).as_nanos() as u64),
+        }
+    }
+
+    fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            hex::encode(self.trace_id),
+            hex::encode(self.span_id),
+            u8::from(self.sampled)
+        )
+    }
+
+    /// join the trace of the client request, and propagate the context to the upstream
+    pub(crate) fn trace_http_request(&mut self, headers: &mut HttpHeaderMap) {
+        if let Some(v) = headers.get(TRACEPARENT_HEADER) {
+            if let Some((trace_id, parent_id, sampled)) = parse_traceparent(v.to_str()) {
+                self.trace_id = trace_id;
+                self.parent_span_id = Some(parent_id);
+                self.sampled = sampled;
+            }
+        }
+
+        let propagate = crate::config::trace::get_global_config()
+            .map(|c| c.propagate)
+            .unwrap_or(false);
+        if propagate {
+            if let Ok(v) = self.traceparent().parse::<HttpHeaderValue>() {
+                headers.insert(HeaderName::from_static(TRACEPARENT_HEADER), v);
+            }
+        }
+    }
+
+    /// record a child span which starts at `start` and ends now
+    pub(crate) fn add_span(&self, name: &'static str, start: Instant) {
+        self.add_span_with_end(name, start, Instant::now());
+    }
+
+    fn add_span_with_end(&self, name: &'static str, start: Instant, end: Instant) {
+        if !self.sampled {
+            return;
+        }
+        let span = SpanData {
+            trace_id: self.trace_id,
+            span_id: new_span_id(),
+            parent_span_id: Some(self.span_id),
+            name,
+            start_unix_nanos: self.unix_nanos(start),
+            end_unix_nanos: self.unix_nanos(end),
+            attributes: Vec::new(),
+        };
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(span);
+    }
+
+    /// finish the task span and send all spans to the export thread
+    pub(crate) fn finish(
+        self,
+        wait_time: Duration,
+        ready_time: Duration,
+        attributes: Vec<(&'static str, String)>,
+    ) {
+        if !self.sampled {
+            return;
+        }
+
+        let now = Instant::now();
+        let accept_start = self
+            .create_ins
+            .checked_sub(wait_time)
+            .unwrap_or(self.create_ins);
+        self.add_span_with_end("accept", accept_start, self.create_ins);
+        if !ready_time.is_zero() {
+            let ready_ins = self.create_ins + ready_time;
+            self.add_span_with_end("ready", self.create_ins, ready_ins);
+            self.add_span_with_end("relay", ready_ins, now);
+        }
+
+        let root = SpanData {
+            trace_id: self.trace_id,
+            span_id: self.span_id,
+            parent_span_id: self.parent_span_id,
+            name: "task",
+            start_unix_nanos: self.unix_nanos(accept_start),
+            end_unix_nanos: self.unix_nanos(now),
+            attributes,
+        };
+        let mut spans = self.spans.into_inner().unwrap();
+        spans.push(root);
+        export::send(spans);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACE_ID: [u8; 16] = [
+        0x4b, 0xf9, 0x2f, 0x35, 0x77, 0xb3, 0x4d, 0xa6, 0xa3, 0xce, 0x92, 0x9d, 0x0e, 0x0e, 0x47,
+        0x36,
+    ];
+    const PARENT_ID: [u8; 8] = [0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7];
+
+    #[test]
+    fn parse_valid() {
+        let (trace_id, parent_id, sampled) =
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        assert_eq!(trace_id, TRACE_ID);
+        assert_eq!(parent_id, PARENT_ID);
+        assert!(sampled);
+
+        let (_, _, sampled) =
+            parse_traceparent(" 00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00 ").unwrap();
+        assert!(!sampled);
+
+        // unknown flags bits should be ignored
+        let (_, _, sampled) =
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-03").unwrap();
+        assert!(sampled);
+    }
+
+    #[test]
+    fn parse_version() {
+        assert!(
+            parse_traceparent("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(
+            parse_traceparent("0-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(
+            parse_traceparent("zz-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none()
+        );
+        // version 00 should have exactly 4 fields
+        assert!(
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra")
+                .is_none()
+        );
+        // but a future version may have more
+        assert!(
+            parse_traceparent("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra")
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn parse_zero_id() {
+        assert!(
+            parse_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01").is_none()
+        );
+    }
+
+    #[test]
+    fn parse_uppercase() {
+        assert!(
+            parse_traceparent("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00F067AA0BA902B7-01").is_none()
+        );
+    }
+
+    #[test]
+    fn parse_bad_length() {
+        assert!(
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e47360-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b-01").is_none()
+        );
+        assert!(
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-1").is_none()
+        );
+        assert!(
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7").is_none()
+        );
+        assert!(parse_traceparent("").is_none());
+    }
+
+    #[test]
+    fn encode() {
+        let mut trace = TaskTrace {
+            trace_id: TRACE_ID,
+            span_id: PARENT_ID,
+            parent_span_id: None,
+            sampled: true,
+            create_ins: Instant::now(),
+            create_unix_nanos: 0,
+            spans: Mutex::new(Vec::new()),
+        };
+        assert_eq!(
+            trace.traceparent(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+        trace.sampled = false;
+        assert_eq!(
+            trace.traceparent(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use clap::{value_parser, Arg, ArgMatches, Command};
+
+use g3_ctl::CommandResult;
+
+use g3proxy_proto::bandwidth_capnp::bandwidth_rate;
+use g3proxy_proto::proc_capnp::proc_control;
+
+pub const COMMAND: &str = "bandwidth";
+
+const SUBCOMMAND_USER: &str = "user";
+const SUBCOMMAND_ESCAPER: &str = "escaper";
+
+const ARG_WINDOW: &str = "window";
+const ARG_TOP: &str = "top";
+
+fn sample_args(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new(ARG_WINDOW)
+            .help("The sample window in seconds, at most 10")
+            .value_name("SECONDS")
+            .num_args(1)
+            .value_parser(value_parser!(u32).range(1..=10))
+            .default_value("1")
+            .long("window")
+            .short('w'),
+    )
+    .arg(
+        Arg::new(ARG_TOP)
+            .help("Only show the top N entries, ordered by total rate")
+            .value_name("N")
+            .num_args(1)
+            .value_parser(value_parser!(usize))
+            .default_value("10")
+            .long("top")
+            .short('n'),
+    )
+}
+
+pub fn command() -> Command {
+    Command::new(COMMAND)
+        .about("Show the live bandwidth usage")
+        .subcommand_required(true)
+        .subcommand(sample_args(
+            Command::new(SUBCOMMAND_USER).about("Show the bandwidth usage of users"),
+        ))
+        .subcommand(sample_args(
+            Command::new(SUBCOMMAND_ESCAPER).about("Show the bandwidth usage of escapers"),
+        ))
+}
+
+fn print_rates(
+    list: capnp::struct_list::Reader<'_, bandwidth_rate::Owned>,
+    args: &ArgMatches,
+) -> CommandResult<()> {
+    let mut rates = Vec::with_capacity(list.len() as usize);
+    for rate in list.iter() {
+        let group = rate.get_group()?.to_str()?;
+        let name = rate.get_name()?.to_str()?;
+        let name = if group.is_empty() {
+            name.to_string()
+        } else {
+            format!("{group}/{name}")
+        };
+        rates.push((
+            name,
+            rate.get_in_bytes_per_sec(),
+            rate.get_out_bytes_per_sec(),
+        ));
+    }
+    rates.sort_by_key(|(_, i, o)| std::cmp::Reverse(i.saturating_add(*o)));
+
+    let top = args.get_one::<usize>(ARG_TOP).copied().unwrap_or(10);
+    for (name, in_rate, out_rate) in rates.into_iter().take(top) {
+        println!("{name} in={in_rate}B/s out={out_rate}B/s");
+    }
+    Ok(())
+}
+
+fn get_window(args: &ArgMatches) -> u32 {
+    args.get_one::<u32>(ARG_WINDOW).copied().unwrap_or(1)
+}
+
+async fn user(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let mut req = client.query_user_bandwidth_request();
+    req.get().set_window(get_window(args));
+    let rsp = req.send().promise.await?;
+    print_rates(rsp.get()?.get_result()?, args)
+}
+
+async fn escaper(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let mut req = client.query_escaper_bandwidth_request();
+    req.get().set_window(get_window(args));
+    let rsp = req.send().promise.await?;
+    print_rates(rsp.get()?.get_result()?, args)
+}
+
+pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let (subcommand, args) = args.subcommand().unwrap();
+    match subcommand {
+        SUBCOMMAND_USER => user(client, args).await,
+        SUBCOMMAND_ESCAPER => escaper(client, args).await,
+        _ => unreachable!(),
+    }
+}
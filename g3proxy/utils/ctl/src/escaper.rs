@@ -35,6 +35,7 @@ const COMMAND_ARG_NAME: &str = "name";
 const SUBCOMMAND_PUBLISH: &str = "publish";
 const SUBCOMMAND_PUBLISH_ARG_FILE: &str = "file";
 const SUBCOMMAND_PUBLISH_ARG_DATA: &str = "data";
+const SUBCOMMAND_LIST_QUARANTINED_PEERS: &str = "list-quarantined-peers";
 
 pub fn command() -> Command {
     Command::new(COMMAND)
@@ -61,6 +62,10 @@ pub fn command() -> Command {
                         .conflicts_with(SUBCOMMAND_PUBLISH_ARG_FILE),
                 ),
         )
+        .subcommand(
+            Command::new(SUBCOMMAND_LIST_QUARANTINED_PEERS)
+                .about("List next proxy peers that are in quarantine"),
+        )
 }
 
 async fn publish(client: &escaper_control::Client, args: &ArgMatches) -> CommandResult<()> {
@@ -89,6 +94,12 @@ async fn publish(client: &escaper_control::Client, args: &ArgMatches) -> Command
     parse_operation_result(rsp.get()?.get_result()?)
 }
 
+async fn list_quarantined_peers(client: &escaper_control::Client) -> CommandResult<()> {
+    let req = client.list_quarantined_peers_request();
+    let rsp = req.send().promise.await?;
+    g3_ctl::print_result_list(rsp.get()?.get_result()?)
+}
+
 pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
     let name = args.get_one::<String>(COMMAND_ARG_NAME).unwrap();
 
@@ -99,6 +110,11 @@ pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandRes
                 .and_then(|escaper| async move { publish(&escaper, args).await })
                 .await
         }
+        SUBCOMMAND_LIST_QUARANTINED_PEERS => {
+            super::proc::get_escaper(client, name)
+                .and_then(|escaper| async move { list_quarantined_peers(&escaper).await })
+                .await
+        }
         _ => unreachable!(),
     }
 }
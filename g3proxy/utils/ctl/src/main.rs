@@ -31,9 +31,11 @@ use g3proxy_proto::proc_capnp::proc_control;
 mod common;
 mod proc;
 
+mod bandwidth;
 mod escaper;
 mod resolver;
 mod server;
+mod task;
 mod user_group;
 
 const DEFAULT_SYS_CONTROL_DIR: &str = "/run/g3proxy";
@@ -134,15 +136,19 @@ fn build_cli_args() -> Command {
         .subcommand(proc::commands::force_quit())
         .subcommand(proc::commands::force_quit_all())
         .subcommand(proc::commands::list())
+        .subcommand(proc::commands::check_config())
         .subcommand(proc::commands::reload_user_group())
         .subcommand(proc::commands::reload_resolver())
         .subcommand(proc::commands::reload_auditor())
         .subcommand(proc::commands::reload_escaper())
         .subcommand(proc::commands::reload_server())
+        .subcommand(proc::commands::partial_reload())
         .subcommand(user_group::command())
         .subcommand(resolver::command())
         .subcommand(escaper::command())
         .subcommand(server::command())
+        .subcommand(task::command())
+        .subcommand(bandwidth::command())
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -185,6 +191,7 @@ async fn main() -> anyhow::Result<()> {
                 proc::COMMAND_FORCE_QUIT => proc::force_quit(&proc_control, args).await,
                 proc::COMMAND_FORCE_QUIT_ALL => proc::force_quit_all(&proc_control).await,
                 proc::COMMAND_LIST => proc::list(&proc_control, args).await,
+                proc::COMMAND_CHECK_CONFIG => proc::check_config(&proc_control, args).await,
                 proc::COMMAND_RELOAD_USER_GROUP => {
                     proc::reload_user_group(&proc_control, args).await
                 }
@@ -192,10 +199,13 @@ async fn main() -> anyhow::Result<()> {
                 proc::COMMAND_RELOAD_AUDITOR => proc::reload_auditor(&proc_control, args).await,
                 proc::COMMAND_RELOAD_ESCAPER => proc::reload_escaper(&proc_control, args).await,
                 proc::COMMAND_RELOAD_SERVER => proc::reload_server(&proc_control, args).await,
+                proc::COMMAND_PARTIAL_RELOAD => proc::partial_reload(&proc_control, args).await,
                 user_group::COMMAND => user_group::run(&proc_control, args).await,
                 resolver::COMMAND => resolver::run(&proc_control, args).await,
                 escaper::COMMAND => escaper::run(&proc_control, args).await,
                 server::COMMAND => server::run(&proc_control, args).await,
+                task::COMMAND => task::run(&proc_control, args).await,
+                bandwidth::COMMAND => bandwidth::run(&proc_control, args).await,
                 _ => unreachable!(),
             }
         })
@@ -14,9 +14,12 @@
  * limitations under the License.
  */
 
+use std::path::PathBuf;
+
+use anyhow::anyhow;
 use clap::ArgMatches;
 
-use g3_ctl::CommandResult;
+use g3_ctl::{CommandError, CommandResult};
 
 use g3proxy_proto::escaper_capnp::escaper_control;
 use g3proxy_proto::proc_capnp::proc_control;
@@ -34,6 +37,9 @@ pub const COMMAND_FORCE_QUIT_ALL: &str = "force-quit-all";
 
 pub const COMMAND_LIST: &str = "list";
 
+pub const COMMAND_CHECK_CONFIG: &str = "check-config";
+const COMMAND_CHECK_CONFIG_ARG_FILE: &str = "file";
+
 const COMMAND_LIST_ARG_RESOURCE: &str = "resource";
 const RESOURCE_VALUE_USER_GROUP: &str = "user-group";
 const RESOURCE_VALUE_RESOLVER: &str = "resolver";
@@ -47,11 +53,14 @@ pub const COMMAND_RELOAD_AUDITOR: &str = "reload-auditor";
 pub const COMMAND_RELOAD_ESCAPER: &str = "reload-escaper";
 pub const COMMAND_RELOAD_SERVER: &str = "reload-server";
 
+pub const COMMAND_PARTIAL_RELOAD: &str = "partial-reload";
+const COMMAND_PARTIAL_RELOAD_ARG_RESOURCE: &str = "resource";
+
 const SUBCOMMAND_ARG_NAME: &str = "name";
 
 pub mod commands {
     use super::*;
-    use clap::{Arg, Command};
+    use clap::{value_parser, Arg, Command, ValueHint};
 
     pub fn version() -> Command {
         Command::new(COMMAND_VERSION)
@@ -87,6 +96,17 @@ pub mod commands {
         )
     }
 
+    pub fn check_config() -> Command {
+        Command::new(COMMAND_CHECK_CONFIG)
+            .about("Validate the config file without applying it, default to the current one")
+            .arg(
+                Arg::new(COMMAND_CHECK_CONFIG_ARG_FILE)
+                    .num_args(1)
+                    .value_parser(value_parser!(PathBuf))
+                    .value_hint(ValueHint::FilePath),
+            )
+    }
+
     pub fn reload_user_group() -> Command {
         Command::new(COMMAND_RELOAD_USER_GROUP)
             .arg(Arg::new(SUBCOMMAND_ARG_NAME).required(true).num_args(1))
@@ -111,6 +131,25 @@ pub mod commands {
         Command::new(COMMAND_RELOAD_SERVER)
             .arg(Arg::new(SUBCOMMAND_ARG_NAME).required(true).num_args(1))
     }
+
+    pub fn partial_reload() -> Command {
+        Command::new(COMMAND_PARTIAL_RELOAD)
+            .about("Reload exactly one entry from the on-disk config and show the changed keys")
+            .arg(
+                Arg::new(COMMAND_PARTIAL_RELOAD_ARG_RESOURCE)
+                    .required(true)
+                    .num_args(1)
+                    .value_parser([
+                        RESOURCE_VALUE_USER_GROUP,
+                        RESOURCE_VALUE_RESOLVER,
+                        RESOURCE_VALUE_AUDITOR,
+                        RESOURCE_VALUE_ESCAPER,
+                        RESOURCE_VALUE_SERVER,
+                    ])
+                    .ignore_case(true),
+            )
+            .arg(Arg::new(SUBCOMMAND_ARG_NAME).required(true).num_args(1))
+    }
 }
 
 pub async fn version(client: &proc_control::Client) -> CommandResult<()> {
@@ -133,6 +172,19 @@ pub async fn force_quit(client: &proc_control::Client, args: &ArgMatches) -> Com
     parse_operation_result(rsp.get()?.get_result()?)
 }
 
+pub async fn check_config(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let mut req = client.check_config_request();
+    if let Some(file) = args.get_one::<PathBuf>(COMMAND_CHECK_CONFIG_ARG_FILE) {
+        // the path should be resolved here as the daemon may have a different working dir
+        let file = std::fs::canonicalize(file).map_err(|e| {
+            CommandError::Cli(anyhow!("invalid config file {}: {e}", file.display()))
+        })?;
+        req.get().set_file(file.to_string_lossy().as_ref());
+    }
+    let rsp = req.send().promise.await?;
+    parse_operation_result(rsp.get()?.get_result()?)
+}
+
 pub async fn force_quit_all(client: &proc_control::Client) -> CommandResult<()> {
     let req = client.force_quit_offline_servers_request();
     let rsp = req.send().promise.await?;
@@ -230,6 +282,18 @@ pub async fn reload_server(client: &proc_control::Client, args: &ArgMatches) ->
     parse_operation_result(rsp.get()?.get_result()?)
 }
 
+pub async fn partial_reload(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let resource = args
+        .get_one::<String>(COMMAND_PARTIAL_RELOAD_ARG_RESOURCE)
+        .unwrap();
+    let name = args.get_one::<String>(SUBCOMMAND_ARG_NAME).unwrap();
+    let mut req = client.partial_reload_request();
+    req.get().set_kind(resource);
+    req.get().set_name(name);
+    let rsp = req.send().promise.await?;
+    parse_operation_result(rsp.get()?.get_result()?)
+}
+
 pub(crate) async fn get_user_group(
     client: &proc_control::Client,
     name: &str,
@@ -25,9 +25,11 @@ use g3_types::resolve::QueryStrategy as ResolverQueryStrategy;
 
 use g3proxy_proto::proc_capnp::proc_control;
 use g3proxy_proto::resolver_capnp::{
-    query_result, resolver_control, QueryStrategy as RpcQueryStrategy,
+    cache_entry, query_result, resolver_control, QueryStrategy as RpcQueryStrategy,
 };
 
+use crate::common::parse_operation_result;
+
 pub const COMMAND: &str = "resolver";
 
 const COMMAND_ARG_NAME: &str = "name";
@@ -37,6 +39,10 @@ const SUBCOMMAND_QUERY_ARG_DOMAIN: &str = "domain";
 const SUBCOMMAND_QUERY_ARG_STRATEGY: &str = "strategy";
 const SUBCOMMAND_QUERY_ARG_RESOLUTION_DELAY: &str = "resolution-delay";
 
+const SUBCOMMAND_DUMP_CACHE: &str = "dump-cache";
+const SUBCOMMAND_FLUSH_CACHE: &str = "flush-cache";
+const SUBCOMMAND_FLUSH_CACHE_ARG_DOMAIN: &str = "domain";
+
 pub fn command() -> Command {
     Command::new(COMMAND)
         .arg(Arg::new(COMMAND_ARG_NAME).required(true).num_args(1))
@@ -59,6 +65,12 @@ pub fn command() -> Command {
                         .default_value("50"),
                 ),
         )
+        .subcommand(Command::new(SUBCOMMAND_DUMP_CACHE).about("Dump all records in the cache"))
+        .subcommand(
+            Command::new(SUBCOMMAND_FLUSH_CACHE)
+                .about("Flush the cached records of the domains, or all if no domain is given")
+                .arg(Arg::new(SUBCOMMAND_FLUSH_CACHE_ARG_DOMAIN).num_args(1..)),
+        )
 }
 
 async fn query_domain(client: &resolver_control::Client, args: &ArgMatches) -> CommandResult<()> {
@@ -94,6 +106,57 @@ async fn query_domain(client: &resolver_control::Client, args: &ArgMatches) -> C
     }
 }
 
+fn print_cache_entry(entry: cache_entry::Reader<'_>) -> CommandResult<()> {
+    let result = match entry.which().unwrap() {
+        cache_entry::Which::Ip(ips) => {
+            let ips = ips?;
+            let mut v = Vec::with_capacity(ips.len() as usize);
+            for ip in ips.iter() {
+                v.push(ip?.to_str()?.to_string());
+            }
+            v.join(",")
+        }
+        cache_entry::Which::Err(reason) => format!("err: {}", reason?.to_str()?),
+    };
+    let subnet = if entry.has_client_subnet() {
+        format!(" subnet={}", entry.get_client_subnet()?.to_str()?)
+    } else {
+        String::new()
+    };
+    println!(
+        "{} {}{subnet} ttl={}s hits={}{} {result}",
+        entry.get_domain()?.to_str()?,
+        entry.get_query_type()?.to_str()?,
+        entry.get_ttl_remaining(),
+        entry.get_hits(),
+        if entry.get_stale() { " stale" } else { "" },
+    );
+    Ok(())
+}
+
+async fn dump_cache(client: &resolver_control::Client) -> CommandResult<()> {
+    let req = client.dump_cache_request();
+    let rsp = req.send().promise.await?;
+    for entry in rsp.get()?.get_result()?.iter() {
+        print_cache_entry(entry)?;
+    }
+    Ok(())
+}
+
+async fn flush_cache(client: &resolver_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let domains: Vec<&String> = args
+        .get_many::<String>(SUBCOMMAND_FLUSH_CACHE_ARG_DOMAIN)
+        .map(|v| v.collect())
+        .unwrap_or_default();
+    let mut req = client.flush_cache_request();
+    let mut builder = req.get().init_domains(domains.len() as u32);
+    for (i, domain) in domains.iter().enumerate() {
+        builder.set(i as u32, domain.as_str());
+    }
+    let rsp = req.send().promise.await?;
+    parse_operation_result(rsp.get()?.get_result()?)
+}
+
 pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
     let name = args.get_one::<String>(COMMAND_ARG_NAME).unwrap();
 
@@ -104,6 +167,16 @@ pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandRes
                 .and_then(|resolver| async move { query_domain(&resolver, args).await })
                 .await
         }
+        SUBCOMMAND_DUMP_CACHE => {
+            super::proc::get_resolver(client, name)
+                .and_then(|resolver| async move { dump_cache(&resolver).await })
+                .await
+        }
+        SUBCOMMAND_FLUSH_CACHE => {
+            super::proc::get_resolver(client, name)
+                .and_then(|resolver| async move { flush_cache(&resolver, args).await })
+                .await
+        }
         _ => unreachable!(),
     }
 }
@@ -0,0 +1,143 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use clap::{Arg, ArgGroup, ArgMatches, Command};
+
+use g3_ctl::CommandResult;
+
+use g3proxy_proto::proc_capnp::proc_control;
+use g3proxy_proto::task_capnp::{task_filter, task_info};
+
+use crate::common::parse_operation_result;
+
+pub const COMMAND: &str = "task";
+
+const SUBCOMMAND_LIST: &str = "list";
+const SUBCOMMAND_KILL: &str = "kill";
+const SUBCOMMAND_KILL_ALL: &str = "kill-all";
+
+const ARG_SERVER: &str = "server";
+const ARG_USER: &str = "user";
+const ARG_ID: &str = "id";
+
+fn filter_args(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new(ARG_SERVER)
+            .help("Only match tasks of this server")
+            .value_name("SERVER NAME")
+            .num_args(1)
+            .long("server"),
+    )
+    .arg(
+        Arg::new(ARG_USER)
+            .help("Only match tasks of this user")
+            .value_name("USER NAME")
+            .num_args(1)
+            .long("user"),
+    )
+}
+
+pub fn command() -> Command {
+    Command::new(COMMAND)
+        .subcommand_required(true)
+        .subcommand(filter_args(
+            Command::new(SUBCOMMAND_LIST).about("List alive tasks"),
+        ))
+        .subcommand(
+            Command::new(SUBCOMMAND_KILL)
+                .about("Force close the task by closing its client connection")
+                .arg(Arg::new(ARG_ID).required(true).num_args(1)),
+        )
+        .subcommand(
+            filter_args(Command::new(SUBCOMMAND_KILL_ALL).about("Force close all matched tasks"))
+                .group(
+                    ArgGroup::new("filter")
+                        .args([ARG_SERVER, ARG_USER])
+                        .required(true)
+                        .multiple(true),
+                ),
+        )
+}
+
+fn set_filter(mut builder: task_filter::Builder<'_>, args: &ArgMatches) {
+    if let Some(server) = args.get_one::<String>(ARG_SERVER) {
+        builder.set_server(server);
+    }
+    if let Some(user) = args.get_one::<String>(ARG_USER) {
+        builder.set_user(user);
+    }
+}
+
+fn print_task(task: task_info::Reader<'_>) -> CommandResult<()> {
+    let user = task.get_user()?.to_str()?;
+    let io = if task.has_io() {
+        let io = task.get_io()?;
+        format!(
+            "{}/{}",
+            io.get_client_read_bytes(),
+            io.get_client_write_bytes()
+        )
+    } else {
+        "-".to_string()
+    };
+    println!(
+        "{} server={} user={} client={} upstream={} start={} alive={}ms client_rw_bytes={}",
+        task.get_id()?.to_str()?,
+        task.get_server()?.to_str()?,
+        if user.is_empty() { "-" } else { user },
+        task.get_client_addr()?.to_str()?,
+        task.get_upstream()?.to_str()?,
+        task.get_start_at()?.to_str()?,
+        task.get_alive_millis(),
+        io,
+    );
+    Ok(())
+}
+
+async fn list(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let mut req = client.list_task_request();
+    set_filter(req.get().init_filter(), args);
+    let rsp = req.send().promise.await?;
+    for task in rsp.get()?.get_result()?.iter() {
+        print_task(task)?;
+    }
+    Ok(())
+}
+
+async fn kill(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let id = args.get_one::<String>(ARG_ID).unwrap();
+    let mut req = client.kill_task_request();
+    req.get().set_id(id);
+    let rsp = req.send().promise.await?;
+    parse_operation_result(rsp.get()?.get_result()?)
+}
+
+async fn kill_all(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let mut req = client.kill_tasks_request();
+    set_filter(req.get().init_filter(), args);
+    let rsp = req.send().promise.await?;
+    parse_operation_result(rsp.get()?.get_result()?)
+}
+
+pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let (subcommand, args) = args.subcommand().unwrap();
+    match subcommand {
+        SUBCOMMAND_LIST => list(client, args).await,
+        SUBCOMMAND_KILL => kill(client, args).await,
+        SUBCOMMAND_KILL_ALL => kill_all(client, args).await,
+        _ => unreachable!(),
+    }
+}
@@ -23,6 +23,7 @@ use clap::{value_parser, Arg, ArgMatches, Command, ValueHint};
 use g3_ctl::{CommandError, CommandResult};
 
 use g3proxy_proto::proc_capnp::proc_control;
+use g3proxy_proto::types_capnp::operation_result;
 use g3proxy_proto::user_group_capnp::user_group_control;
 
 use super::common::parse_operation_result;
@@ -31,10 +32,18 @@ pub const COMMAND: &str = "user-group";
 
 const COMMAND_ARG_NAME: &str = "name";
 const COMMAND_ARG_FILE: &str = "file";
+const COMMAND_ARG_USER: &str = "user";
 
 const SUBCOMMAND_LIST_STATIC_USER: &str = "list-static-user";
 const SUBCOMMAND_LIST_DYNAMIC_USER: &str = "list-dynamic-user";
 const SUBCOMMAND_PUBLISH_USER: &str = "publish-user";
+const SUBCOMMAND_QUERY_QUOTA: &str = "query-quota";
+const SUBCOMMAND_QUERY_PASSWORD_CACHE: &str = "query-password-cache";
+const SUBCOMMAND_FLUSH_PASSWORD_CACHE: &str = "flush-password-cache";
+const SUBCOMMAND_BLOCK_USER: &str = "block-user";
+const SUBCOMMAND_UNBLOCK_USER: &str = "unblock-user";
+const SUBCOMMAND_RESET_QUOTA: &str = "reset-quota";
+const SUBCOMMAND_EXPIRE_USER_SESSIONS: &str = "expire-user-sessions";
 
 pub fn command() -> Command {
     Command::new(COMMAND)
@@ -54,6 +63,42 @@ pub fn command() -> Command {
                         .value_hint(ValueHint::FilePath),
                 ),
         )
+        .subcommand(
+            Command::new(SUBCOMMAND_QUERY_QUOTA)
+                .about("Query the traffic quota usage of a user")
+                .visible_alias("query-traffic-quota")
+                .arg(Arg::new(COMMAND_ARG_USER).required(true).num_args(1)),
+        )
+        .subcommand(
+            Command::new(SUBCOMMAND_QUERY_PASSWORD_CACHE)
+                .about("Query the hit stats of the password cache"),
+        )
+        .subcommand(
+            Command::new(SUBCOMMAND_FLUSH_PASSWORD_CACHE)
+                .about("Flush the password cache, for all users if no user is given")
+                .arg(Arg::new(COMMAND_ARG_USER).num_args(1)),
+        )
+        .subcommand(
+            Command::new(SUBCOMMAND_BLOCK_USER)
+                .about("Block a user at runtime, until unblocked or the daemon restarted")
+                .arg(Arg::new(COMMAND_ARG_USER).required(true).num_args(1)),
+        )
+        .subcommand(
+            Command::new(SUBCOMMAND_UNBLOCK_USER)
+                .about("Unblock a user that is blocked at runtime")
+                .arg(Arg::new(COMMAND_ARG_USER).required(true).num_args(1)),
+        )
+        .subcommand(
+            Command::new(SUBCOMMAND_RESET_QUOTA)
+                .about("Reset the traffic quota usage of a user")
+                .visible_alias("reset-traffic-quota")
+                .arg(Arg::new(COMMAND_ARG_USER).required(true).num_args(1)),
+        )
+        .subcommand(
+            Command::new(SUBCOMMAND_EXPIRE_USER_SESSIONS)
+                .about("Flush cached credentials and close all alive tasks of a user")
+                .arg(Arg::new(COMMAND_ARG_USER).required(true).num_args(1)),
+        )
 }
 
 pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
@@ -66,6 +111,13 @@ pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandRes
         SUBCOMMAND_LIST_STATIC_USER => list_static_user(&user_group).await,
         SUBCOMMAND_LIST_DYNAMIC_USER => list_dynamic_user(&user_group).await,
         SUBCOMMAND_PUBLISH_USER => publish_dynamic_user(&user_group, args).await,
+        SUBCOMMAND_QUERY_QUOTA => query_traffic_quota(&user_group, args).await,
+        SUBCOMMAND_QUERY_PASSWORD_CACHE => query_password_cache(&user_group).await,
+        SUBCOMMAND_FLUSH_PASSWORD_CACHE => flush_password_cache(&user_group, args).await,
+        SUBCOMMAND_BLOCK_USER => block_user(&user_group, args, true).await,
+        SUBCOMMAND_UNBLOCK_USER => block_user(&user_group, args, false).await,
+        SUBCOMMAND_RESET_QUOTA => reset_traffic_quota(&user_group, args).await,
+        SUBCOMMAND_EXPIRE_USER_SESSIONS => expire_user_sessions(&user_group, args).await,
         _ => unreachable!(),
     }
 }
@@ -108,3 +160,89 @@ async fn publish_dynamic_user(
     let rsp = req.send().promise.await?;
     parse_operation_result(rsp.get()?.get_result()?)
 }
+
+async fn query_traffic_quota(
+    client: &user_group_control::Client,
+    args: &ArgMatches,
+) -> CommandResult<()> {
+    let user = args.get_one::<String>(COMMAND_ARG_USER).unwrap();
+
+    let mut req = client.query_traffic_quota_request();
+    req.get().set_user(user.as_str());
+    let rsp = req.send().promise.await?;
+    match rsp.get()?.get_result()?.which().unwrap() {
+        operation_result::Which::Ok(status) => g3_ctl::print_text("status", status?),
+        operation_result::Which::Err(err) => {
+            let e = err?;
+            Err(CommandError::api_error(e.get_code(), e.get_reason()?))
+        }
+    }
+}
+
+async fn query_password_cache(client: &user_group_control::Client) -> CommandResult<()> {
+    let req = client.query_password_cache_request();
+    let rsp = req.send().promise.await?;
+    match rsp.get()?.get_result()?.which().unwrap() {
+        operation_result::Which::Ok(status) => g3_ctl::print_text("status", status?),
+        operation_result::Which::Err(err) => {
+            let e = err?;
+            Err(CommandError::api_error(e.get_code(), e.get_reason()?))
+        }
+    }
+}
+
+async fn flush_password_cache(
+    client: &user_group_control::Client,
+    args: &ArgMatches,
+) -> CommandResult<()> {
+    let mut req = client.flush_password_cache_request();
+    if let Some(user) = args.get_one::<String>(COMMAND_ARG_USER) {
+        req.get().set_user(user.as_str());
+    }
+    let rsp = req.send().promise.await?;
+    parse_operation_result(rsp.get()?.get_result()?)
+}
+
+async fn block_user(
+    client: &user_group_control::Client,
+    args: &ArgMatches,
+    block: bool,
+) -> CommandResult<()> {
+    let user = args.get_one::<String>(COMMAND_ARG_USER).unwrap();
+
+    if block {
+        let mut req = client.block_user_request();
+        req.get().set_user(user.as_str());
+        let rsp = req.send().promise.await?;
+        parse_operation_result(rsp.get()?.get_result()?)
+    } else {
+        let mut req = client.unblock_user_request();
+        req.get().set_user(user.as_str());
+        let rsp = req.send().promise.await?;
+        parse_operation_result(rsp.get()?.get_result()?)
+    }
+}
+
+async fn reset_traffic_quota(
+    client: &user_group_control::Client,
+    args: &ArgMatches,
+) -> CommandResult<()> {
+    let user = args.get_one::<String>(COMMAND_ARG_USER).unwrap();
+
+    let mut req = client.reset_traffic_quota_request();
+    req.get().set_user(user.as_str());
+    let rsp = req.send().promise.await?;
+    parse_operation_result(rsp.get()?.get_result()?)
+}
+
+async fn expire_user_sessions(
+    client: &user_group_control::Client,
+    args: &ArgMatches,
+) -> CommandResult<()> {
+    let user = args.get_one::<String>(COMMAND_ARG_USER).unwrap();
+
+    let mut req = client.expire_user_sessions_request();
+    req.get().set_user(user.as_str());
+    let rsp = req.send().promise.await?;
+    parse_operation_result(rsp.get()?.get_result()?)
+}
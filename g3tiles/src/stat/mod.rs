@@ -53,6 +53,7 @@ fn spawn_main_thread(config: &StatsdClientConfig) -> anyhow::Result<JoinHandle<(
             metrics::backend::emit_stats(&mut client);
             metrics::server::emit_stats(&mut client);
             g3_daemon::log::metrics::emit_stats(&mut client);
+            g3_daemon::metrics::emit_process_stats(&mut client);
 
             client.flush_sink();
 
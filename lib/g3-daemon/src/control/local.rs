@@ -144,7 +144,7 @@ impl LocalController {
         LocalController::abort(&UNIQUE_CONTROLLER_ABORT_HANDLER);
     }
 
-    pub fn create_daemon(daemon_group: &str) -> anyhow::Result<Self> {
+    pub fn daemon_listen_path(daemon_group: &str) -> PathBuf {
         let socket_name = if daemon_group.is_empty() {
             "_.sock".to_string()
         } else {
@@ -152,6 +152,11 @@ impl LocalController {
         };
         let mut listen_path = crate::opts::control_dir();
         listen_path.push(Path::new(&socket_name));
+        listen_path
+    }
+
+    pub fn create_daemon(daemon_group: &str) -> anyhow::Result<Self> {
+        let listen_path = LocalController::daemon_listen_path(daemon_group);
         check_then_finalize_path(&listen_path)?;
 
         debug!("setting up daemon controller {}", listen_path.display());
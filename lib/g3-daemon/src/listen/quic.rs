@@ -115,6 +115,19 @@ where
         self.listen_stats.del_running_runtime();
     }
 
+    fn task_name(&self) -> String {
+        let name = format!(
+            "SRT[{}_v{}#{}]",
+            self.server.name(),
+            self.server_version,
+            self.instance_id
+        );
+        match self.worker_id {
+            Some(id) => format!("{name}@worker#{id}"),
+            None => name,
+        }
+    }
+
     async fn run<C>(
         mut self,
         listener: Endpoint,
@@ -407,7 +420,8 @@ where
         C: ListenQuicConf + Clone + Send + Sync + 'static,
     {
         let handle = self.get_rt_handle(listen_in_worker);
-        handle.spawn(async move {
+        let task_name = self.task_name();
+        let listen_task = async move {
             let sock_raw_fd = socket.as_raw_fd();
             // make sure the listen socket associated with the correct reactor
             match Endpoint::new(
@@ -436,7 +450,8 @@ where
                     );
                 }
             }
-        });
+        };
+        crate::runtime::spawn_named_on(&task_name, listen_task, &handle);
     }
 
     pub fn run_all_instances<C>(
@@ -457,6 +472,16 @@ where
             }
         }
 
+        if crate::upgrade::is_taking_over() {
+            // udp listen sockets are not handed over on upgrade
+            warn!(
+                "SRT[{}_v{}] binding new udp socket to {} as it won't be inherited",
+                self.server.name(),
+                self.server_version,
+                self.listen_config.address()
+            );
+        }
+
         for i in 0..instance_count {
             let mut runtime = self.clone();
             runtime.instance_id = i;
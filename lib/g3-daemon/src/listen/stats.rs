@@ -34,6 +34,7 @@ pub struct ListenStats {
     id: StatId,
 
     runtime_count: AtomicIsize,
+    backlog: AtomicIsize,
     accepted: AtomicU64,
     dropped: AtomicU64,
     timeout: AtomicU64,
@@ -46,6 +47,7 @@ impl ListenStats {
             name: name.clone(),
             id: StatId::new(),
             runtime_count: AtomicIsize::new(0),
+            backlog: AtomicIsize::new(0),
             accepted: AtomicU64::new(0),
             dropped: AtomicU64::new(0),
             timeout: AtomicU64::new(0),
@@ -77,6 +79,16 @@ impl ListenStats {
         self.get_running_runtime_count() > 0
     }
 
+    pub fn update_backlog(&self, old: u32, new: u32) {
+        let diff = new as isize - old as isize;
+        if diff != 0 {
+            self.backlog.fetch_add(diff, Ordering::Relaxed);
+        }
+    }
+    pub fn get_backlog(&self) -> isize {
+        self.backlog.load(Ordering::Relaxed)
+    }
+
     pub fn add_accepted(&self) {
         self.accepted.fetch_add(1, Ordering::Relaxed);
     }
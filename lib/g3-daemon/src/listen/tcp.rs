@@ -15,8 +15,9 @@
  */
 
 use std::net::SocketAddr;
-use std::os::fd::AsRawFd;
+use std::os::fd::{AsRawFd, RawFd};
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use log::{info, warn};
@@ -30,6 +31,9 @@ use g3_types::net::TcpListenConfig;
 
 use crate::listen::ListenStats;
 use crate::server::{BaseServer, ClientConnectionInfo, ServerReloadCommand};
+use crate::upgrade::ListenFdGuard;
+
+const BACKLOG_CHECK_INTERVAL: Duration = Duration::from_secs(1);
 
 #[async_trait]
 pub trait AcceptTcpServer: BaseServer {
@@ -99,13 +103,31 @@ where
         self.listen_stats.del_running_runtime();
     }
 
+    fn task_name(&self) -> String {
+        let name = format!(
+            "SRT[{}_v{}#{}]",
+            self.server.name(),
+            self.server_version,
+            self.instance_id
+        );
+        match self.worker_id {
+            Some(id) => format!("{name}@worker#{id}"),
+            None => name,
+        }
+    }
+
     async fn run(
         mut self,
         mut listener: LimitedTcpListener,
+        listen_fd: RawFd,
+        fd_guard: Option<ListenFdGuard>,
         mut server_reload_channel: broadcast::Receiver<ServerReloadCommand>,
     ) {
         use broadcast::error::RecvError;
 
+        let mut backlog_interval = tokio::time::interval(BACKLOG_CHECK_INTERVAL);
+        let mut backlog: u32 = 0;
+
         loop {
             tokio::select! {
                 biased;
@@ -141,6 +163,12 @@ where
                         break;
                     }
                 }
+                _ = backlog_interval.tick() => {
+                    if let Ok(new_backlog) = g3_socket::tcp::get_raw_listen_backlog(listen_fd) {
+                        self.listen_stats.update_backlog(backlog, new_backlog);
+                        backlog = new_backlog;
+                    }
+                }
                 result = listener.accept() => {
                     if listener.accept_current_available(result, |result| {
                         match result {
@@ -171,7 +199,10 @@ where
                 }
             }
         }
+        self.listen_stats.update_backlog(backlog, 0);
         self.post_stop();
+        // unregister before the listen socket closed
+        drop(fd_guard);
     }
 
     fn run_task(&self, stream: TcpStream, peer_addr: SocketAddr, local_addr: SocketAddr) {
@@ -213,13 +244,24 @@ where
         server_reload_channel: broadcast::Receiver<ServerReloadCommand>,
     ) {
         let handle = self.get_rt_handle(listen_in_worker);
-        handle.spawn(async move {
+        let task_name = self.task_name();
+        let listen_task = async move {
+            let listen_fd = listener.as_raw_fd();
+            let fd_guard = listener
+                .local_addr()
+                .ok()
+                .map(|addr| ListenFdGuard::new_tcp(&listener, addr));
             // make sure the listen socket associated with the correct reactor
             match tokio::net::TcpListener::from_std(listener) {
                 Ok(listener) => {
                     self.pre_start();
-                    self.run(LimitedTcpListener::new(listener), server_reload_channel)
-                        .await;
+                    self.run(
+                        LimitedTcpListener::new(listener),
+                        listen_fd,
+                        fd_guard,
+                        server_reload_channel,
+                    )
+                    .await;
                 }
                 Err(e) => {
                     warn!(
@@ -230,7 +272,8 @@ where
                     );
                 }
             }
-        });
+        };
+        crate::runtime::spawn_named_on(&task_name, listen_task, &handle);
     }
 
     pub fn run_all_instances(
@@ -251,7 +294,10 @@ where
             let mut runtime = self.clone();
             runtime.instance_id = i;
 
-            let listener = g3_socket::tcp::new_std_listener(listen_config)?;
+            let listener = match crate::upgrade::take_tcp_listener(listen_config.address()) {
+                Some(listener) => listener,
+                None => g3_socket::tcp::new_std_listener(listen_config)?,
+            };
             runtime.into_running(listener, listen_in_worker, server_reload_sender.subscribe());
         }
         Ok(())
@@ -18,12 +18,16 @@ use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::{anyhow, Context};
+use rand::distributions::Bernoulli;
 use yaml_rust::Yaml;
 
+use g3_filelog::FileLogConfig;
 use g3_fluentd::FluentdClientConfig;
 #[cfg(target_os = "linux")]
 use g3_journal::JournalConfig;
+use g3_kafka::KafkaProducerConfig;
 use g3_syslog::SyslogBuilder;
+use g3_types::limit::RateLimitQuotaConfig;
 
 const DEFAULT_CHANNEL_SIZE: usize = 4096;
 const IO_ERROR_SAMPLING_OFFSET_MAX: usize = 16;
@@ -36,6 +40,8 @@ pub enum LogConfigDriver {
     Journal(JournalConfig),
     Syslog(SyslogBuilder),
     Fluentd(Arc<FluentdClientConfig>),
+    Kafka(Arc<KafkaProducerConfig>),
+    File(Arc<FileLogConfig>),
 }
 
 #[derive(Clone)]
@@ -44,6 +50,8 @@ pub struct LogConfig {
     pub(crate) async_channel_size: usize,
     pub(crate) async_thread_number: usize,
     pub(crate) io_err_sampling_mask: usize,
+    pub(crate) sample_ratio: Option<Bernoulli>,
+    pub(crate) rate_limit: Option<RateLimitQuotaConfig>,
     pub(crate) program_name: &'static str,
 }
 
@@ -54,6 +62,8 @@ impl LogConfig {
             async_channel_size: DEFAULT_CHANNEL_SIZE,
             async_thread_number: 1,
             io_err_sampling_mask: (1 << IO_ERROR_SAMPLING_OFFSET_DEFAULT) - 1,
+            sample_ratio: None,
+            rate_limit: None,
             program_name,
         }
     }
@@ -84,6 +94,12 @@ impl LogConfig {
         )
     }
 
+    pub fn default_kafka(program_name: &'static str) -> Self {
+        let mut config = KafkaProducerConfig::default();
+        config.set_client_id(program_name.to_string());
+        Self::with_driver(LogConfigDriver::Kafka(Arc::new(config)), program_name)
+    }
+
     pub fn parse(
         v: &Yaml,
         conf_dir: &Path,
@@ -96,6 +112,7 @@ impl LogConfig {
                 "journal" => Ok(LogConfig::default_journal(program_name)),
                 "syslog" => Ok(LogConfig::default_syslog(program_name)),
                 "fluentd" => Ok(LogConfig::default_fluentd(program_name)),
+                "kafka" => Ok(LogConfig::default_kafka(program_name)),
                 _ => Err(anyhow!("invalid log config")),
             },
             Yaml::Hash(map) => {
@@ -108,8 +125,9 @@ impl LogConfig {
                         Ok(())
                     }
                     "syslog" => {
-                        let builder = g3_yaml::value::as_syslog_builder(v, program_name)
-                            .context("invalid syslog config")?;
+                        let builder =
+                            g3_yaml::value::as_syslog_builder(v, program_name, Some(conf_dir))
+                                .context("invalid syslog config")?;
                         config.driver = LogConfigDriver::Syslog(builder);
                         Ok(())
                     }
@@ -119,6 +137,18 @@ impl LogConfig {
                         config.driver = LogConfigDriver::Fluentd(Arc::new(client));
                         Ok(())
                     }
+                    "kafka" => {
+                        let producer = g3_yaml::value::as_kafka_producer_config(v, program_name)
+                            .context("invalid kafka config")?;
+                        config.driver = LogConfigDriver::Kafka(Arc::new(producer));
+                        Ok(())
+                    }
+                    "file" => {
+                        let file = g3_yaml::value::as_file_log_config(v)
+                            .context("invalid file log config")?;
+                        config.driver = LogConfigDriver::File(Arc::new(file));
+                        Ok(())
+                    }
                     "async_channel_size" | "channel_size" => {
                         let channel_size = g3_yaml::value::as_usize(v)
                             .context(format!("invalid usize value for key {k}"))?;
@@ -143,6 +173,18 @@ impl LogConfig {
                             Ok(())
                         }
                     }
+                    "sample_ratio" | "sampling_ratio" => {
+                        let ratio = g3_yaml::value::as_random_ratio(v)
+                            .context(format!("invalid random ratio value for key {k}"))?;
+                        config.sample_ratio = Some(ratio);
+                        Ok(())
+                    }
+                    "rate_limit" | "max_records_per_second" => {
+                        let quota = g3_yaml::value::as_rate_limit_quota(v)
+                            .context(format!("invalid rate limit quota value for key {k}"))?;
+                        config.rate_limit = Some(quota);
+                        Ok(())
+                    }
                     _ => Err(anyhow!("invalid key {k}")),
                 })?;
                 Ok(config)
@@ -0,0 +1,74 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use governor::{clock::DefaultClock, state::InMemoryState, state::NotKeyed, RateLimiter};
+use rand::distributions::{Bernoulli, Distribution};
+use slog::{Drain, Level, OwnedKVList, Record};
+
+use g3_types::log::LogStats;
+
+use super::LogConfig;
+
+/// sample and rate limit log records before sending them to the real drain
+pub struct LimitLogRecords<D: Drain<Err = slog::Error, Ok = ()>> {
+    sample_ratio: Option<Bernoulli>,
+    rate_limit: Option<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
+    stats: Arc<LogStats>,
+    inner: D,
+}
+
+impl<D: Drain<Err = slog::Error, Ok = ()>> LimitLogRecords<D> {
+    pub fn new(drain: D, config: &LogConfig, stats: Arc<LogStats>) -> Self {
+        LimitLogRecords {
+            sample_ratio: config.sample_ratio,
+            rate_limit: config
+                .rate_limit
+                .as_ref()
+                .map(|quota| RateLimiter::direct(quota.get_inner())),
+            stats,
+            inner: drain,
+        }
+    }
+}
+
+impl<D: Drain<Err = slog::Error, Ok = ()>> Drain for LimitLogRecords<D> {
+    type Ok = ();
+    type Err = slog::Error;
+
+    fn log(&self, record: &Record, logger_values: &OwnedKVList) -> Result<(), slog::Error> {
+        if let Some(ratio) = &self.sample_ratio {
+            let mut rng = rand::thread_rng();
+            if !ratio.sample(&mut rng) {
+                return Ok(());
+            }
+        }
+        if let Some(limit) = &self.rate_limit {
+            if limit.check().is_err() {
+                self.stats.io.add_total();
+                self.stats.drop.add_rate_limited();
+                return Ok(());
+            }
+        }
+        self.inner.log(record, logger_values)
+    }
+
+    #[inline]
+    fn is_enabled(&self, level: Level) -> bool {
+        self.inner.is_enabled(level)
+    }
+}
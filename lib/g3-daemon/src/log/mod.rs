@@ -19,6 +19,9 @@ pub mod process;
 mod report;
 pub use report::ReportLogIoError;
 
+mod limit;
+pub use limit::LimitLogRecords;
+
 mod stats;
 pub(crate) use stats::LoggerStats;
 
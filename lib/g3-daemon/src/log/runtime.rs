@@ -20,7 +20,7 @@ use slog::{slog_o, Logger, OwnedKV, SendSyncRefUnwindSafeKV};
 
 use g3_types::log::AsyncLogConfig;
 
-use super::{LogConfig, LogConfigDriver, LoggerStats, ReportLogIoError};
+use super::{LimitLogRecords, LogConfig, LogConfigDriver, LoggerStats, ReportLogIoError};
 
 pub fn create_shared_logger(
     logger_name: String,
@@ -58,8 +58,10 @@ where
                 thread_name: logger_name.clone(),
             };
             let drain = g3_journal::new_async_logger(&async_conf, journal_conf);
-            let logger_stats = LoggerStats::new(&logger_name, drain.get_stats());
+            let stats = drain.get_stats();
+            let logger_stats = LoggerStats::new(&logger_name, Arc::clone(&stats));
             super::registry::add(logger_name.clone(), Arc::new(logger_stats));
+            let drain = LimitLogRecords::new(drain, config, stats);
             let drain = ReportLogIoError::new(drain, &logger_name, config.io_err_sampling_mask);
             Logger::root(drain, common_values)
         }
@@ -70,8 +72,10 @@ where
                 thread_name: logger_name.clone(),
             };
             let drain = builder.start_async(&async_conf);
-            let logger_stats = LoggerStats::new(&logger_name, drain.get_stats());
+            let stats = drain.get_stats();
+            let logger_stats = LoggerStats::new(&logger_name, Arc::clone(&stats));
             super::registry::add(logger_name.clone(), Arc::new(logger_stats));
+            let drain = LimitLogRecords::new(drain, config, stats);
             let drain = ReportLogIoError::new(drain, &logger_name, config.io_err_sampling_mask);
             Logger::root(drain, common_values)
         }
@@ -86,8 +90,42 @@ where
                 &fluentd_conf,
                 format!("{}.{log_type}", config.program_name),
             );
-            let logger_stats = LoggerStats::new(&logger_name, drain.get_stats());
+            let stats = drain.get_stats();
+            let logger_stats = LoggerStats::new(&logger_name, Arc::clone(&stats));
             super::registry::add(logger_name.clone(), Arc::new(logger_stats));
+            let drain = LimitLogRecords::new(drain, config, stats);
+            let drain = ReportLogIoError::new(drain, &logger_name, config.io_err_sampling_mask);
+            Logger::root(drain, common_values)
+        }
+        LogConfigDriver::Kafka(kafka_conf) => {
+            let async_conf = AsyncLogConfig {
+                channel_capacity: config.async_channel_size,
+                thread_number: config.async_thread_number,
+                thread_name: logger_name.clone(),
+            };
+            let topic = kafka_conf
+                .topic()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| format!("{}.{log_type}", config.program_name));
+            let drain = g3_kafka::new_async_logger(&async_conf, &kafka_conf, topic);
+            let stats = drain.get_stats();
+            let logger_stats = LoggerStats::new(&logger_name, Arc::clone(&stats));
+            super::registry::add(logger_name.clone(), Arc::new(logger_stats));
+            let drain = LimitLogRecords::new(drain, config, stats);
+            let drain = ReportLogIoError::new(drain, &logger_name, config.io_err_sampling_mask);
+            Logger::root(drain, common_values)
+        }
+        LogConfigDriver::File(file_conf) => {
+            let async_conf = AsyncLogConfig {
+                channel_capacity: config.async_channel_size,
+                thread_number: 1,
+                thread_name: logger_name.clone(),
+            };
+            let drain = g3_filelog::new_async_logger(&async_conf, &file_conf, config.program_name);
+            let stats = drain.get_stats();
+            let logger_stats = LoggerStats::new(&logger_name, Arc::clone(&stats));
+            super::registry::add(logger_name.clone(), Arc::new(logger_stats));
+            let drain = LimitLogRecords::new(drain, config, stats);
             let drain = ReportLogIoError::new(drain, &logger_name, config.io_err_sampling_mask);
             Logger::root(drain, common_values)
         }
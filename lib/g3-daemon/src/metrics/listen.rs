@@ -22,6 +22,7 @@ use super::ServerMetricExt;
 use crate::listen::{ListenSnapshot, ListenStats};
 
 const METRIC_NAME_LISTEN_INSTANCE_COUNT: &str = "listen.instance.count";
+const METRIC_NAME_LISTEN_BACKLOG: &str = "listen.backlog";
 const METRIC_NAME_LISTEN_ACCEPTED: &str = "listen.accepted";
 const METRIC_NAME_LISTEN_DROPPED: &str = "listen.dropped";
 const METRIC_NAME_LISTEN_TIMEOUT: &str = "listen.timeout";
@@ -42,6 +43,13 @@ pub fn emit_listen_stats(
             &common_tags,
         )
         .send();
+    client
+        .gauge_with_tags(
+            METRIC_NAME_LISTEN_BACKLOG,
+            stats.get_backlog(),
+            &common_tags,
+        )
+        .send();
 
     macro_rules! emit_field {
         ($field:ident, $name:expr) => {
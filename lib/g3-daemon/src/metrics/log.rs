@@ -88,4 +88,5 @@ pub(crate) fn emit_log_drop_stats(
     emit_field!(channel_closed, LogDropType::ChannelClosed);
     emit_field!(channel_overflow, LogDropType::ChannelOverflow);
     emit_field!(peer_unreachable, LogDropType::PeerUnreachable);
+    emit_field!(rate_limited, LogDropType::RateLimited);
 }
@@ -20,6 +20,9 @@ pub use listen::emit_listen_stats;
 mod log;
 pub(crate) use log::{emit_log_drop_stats, emit_log_io_stats, LoggerMetricExt};
 
+mod process;
+pub use process::emit_process_stats;
+
 mod server;
 pub use server::{ServerMetricExt, TAG_KEY_ONLINE, TAG_KEY_SERVER};
 
@@ -0,0 +1,90 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use g3_statsd_client::StatsdClient;
+
+const METRIC_NAME_PROCESS_MEMORY_RSS: &str = "process.memory.rss";
+const METRIC_NAME_PROCESS_FD_OPEN: &str = "process.fd.open";
+const METRIC_NAME_PROCESS_FD_LIMIT: &str = "process.fd.limit";
+const METRIC_NAME_RUNTIME_MAIN_WORKER_COUNT: &str = "runtime.main.worker.count";
+const METRIC_NAME_RUNTIME_UNAIDED_WORKER_COUNT: &str = "runtime.unaided.worker.count";
+
+pub fn emit_process_stats(client: &mut StatsdClient) {
+    if let Some(rss) = get_memory_rss() {
+        client.gauge(METRIC_NAME_PROCESS_MEMORY_RSS, rss).send();
+    }
+    if let Some(count) = get_fd_open_count() {
+        client.gauge(METRIC_NAME_PROCESS_FD_OPEN, count).send();
+    }
+    if let Some(limit) = get_fd_limit() {
+        client.gauge(METRIC_NAME_PROCESS_FD_LIMIT, limit).send();
+    }
+
+    let main_worker_count = crate::runtime::config::get_runtime_config().intended_thread_number();
+    client
+        .gauge(METRIC_NAME_RUNTIME_MAIN_WORKER_COUNT, main_worker_count)
+        .send();
+    client
+        .gauge(
+            METRIC_NAME_RUNTIME_UNAIDED_WORKER_COUNT,
+            crate::runtime::worker::worker_count(),
+        )
+        .send();
+}
+
+#[cfg(target_os = "linux")]
+fn get_memory_rss() -> Option<u64> {
+    // the second field of statm is the resident set size in pages
+    let content = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages = content.split_ascii_whitespace().nth(1)?;
+    let pages = pages.parse::<u64>().ok()?;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if page_size <= 0 {
+        return None;
+    }
+    Some(pages * page_size as u64)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn get_memory_rss() -> Option<u64> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn get_fd_open_count() -> Option<u64> {
+    let dir = std::fs::read_dir("/proc/self/fd").ok()?;
+    // the fd used to read the dir itself is also counted
+    let count = dir.count() as u64;
+    Some(count.saturating_sub(1))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn get_fd_open_count() -> Option<u64> {
+    None
+}
+
+fn get_fd_limit() -> Option<u64> {
+    let mut rlimit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    let ret = unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut rlimit) };
+    if ret != 0 || rlimit.rlim_cur == libc::RLIM_INFINITY {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Some(rlimit.rlim_cur as u64)
+}
@@ -19,6 +19,8 @@ use tokio::runtime::Handle;
 pub mod config;
 pub mod worker;
 
+pub use g3_runtime::task::{spawn_named, spawn_named_on};
+
 static mut MAIN_HANDLE: Option<Handle> = None;
 
 pub fn main_handle() -> Option<&'static Handle> {
@@ -29,3 +31,14 @@ pub fn set_main_handle() {
     let handle = Handle::current();
     unsafe { MAIN_HANDLE = Some(handle) }
 }
+
+/// Init the tokio-console subscriber, which should be called before any runtime started.
+///
+/// The server can be configured by the `TOKIO_CONSOLE_*` environment variables,
+/// see the doc of console-subscriber for all supported variables.
+#[cfg(feature = "tokio-console")]
+pub fn init_console() {
+    console_subscriber::ConsoleLayer::builder()
+        .with_default_env()
+        .init();
+}
@@ -0,0 +1,122 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use log::debug;
+
+use g3_statsd_client::PrometheusExporter;
+
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_REQUEST_HEADER_SIZE: usize = 8192;
+
+const RSP_NOT_FOUND: &[u8] =
+    b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+const RSP_BAD_REQUEST: &[u8] =
+    b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+fn read_request_path(stream: &mut TcpStream) -> io::Result<Option<String>> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut tmp = [0u8; 1024];
+    loop {
+        let nr = stream.read(&mut tmp)?;
+        if nr == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&tmp[..nr]);
+        if buf.windows(4).any(|w| w == b"\r\n\r\n") {
+            break;
+        }
+        if buf.len() > MAX_REQUEST_HEADER_SIZE {
+            return Ok(None);
+        }
+    }
+
+    let head = String::from_utf8_lossy(&buf);
+    let mut parts = head.split_ascii_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(path)) => Ok(Some(path.to_string())),
+        _ => Ok(None),
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, exporter: &PrometheusExporter) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+    stream.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+
+    match read_request_path(&mut stream)?.as_deref() {
+        Some("/metrics") | Some("/") => {
+            let body = exporter.render();
+            let header = format!(
+                "HTTP/1.1 200 OK\r\n\
+                 Content-Type: text/plain; version=0.0.4\r\n\
+                 Content-Length: {}\r\n\
+                 Connection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(header.as_bytes())?;
+            stream.write_all(body.as_bytes())?;
+        }
+        Some(_) => stream.write_all(RSP_NOT_FOUND)?,
+        None => stream.write_all(RSP_BAD_REQUEST)?,
+    }
+    stream.flush()
+}
+
+/// spawn a thread to serve the collected metrics at http path /metrics
+pub fn spawn_http_thread(
+    exporter: PrometheusExporter,
+    quit: &'static AtomicBool,
+) -> anyhow::Result<JoinHandle<()>> {
+    let listen_addr = exporter.listen_addr();
+    let listener =
+        TcpListener::bind(listen_addr).context(format!("failed to listen to {listen_addr}"))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| anyhow!("failed to set listen socket to non-blocking: {e}"))?;
+
+    let handle = std::thread::Builder::new()
+        .name("stat-prometheus".to_string())
+        .spawn(move || loop {
+            if quit.load(Ordering::Relaxed) {
+                break;
+            }
+
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    if let Err(e) = handle_connection(stream, &exporter) {
+                        debug!("failed to serve prometheus metrics to {peer}: {e}");
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                }
+                Err(e) => {
+                    debug!("failed to accept prometheus metrics connection: {e}");
+                    std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                }
+            }
+        })
+        .map_err(|e| anyhow!("failed to spawn thread: {e:?}"))?;
+    Ok(handle)
+}
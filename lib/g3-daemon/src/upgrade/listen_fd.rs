@@ -0,0 +1,90 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::os::fd::{AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+static LISTEN_FD_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// listen sockets that are running in the current process, which can be handed over on upgrade
+static ACTIVE_TCP_LISTENERS: Lazy<Mutex<HashMap<usize, (SocketAddr, RawFd)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// listen sockets inherited from the old process
+static INHERITED_TCP_LISTENERS: Lazy<Mutex<HashMap<SocketAddr, Vec<OwnedFd>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Keep the listen socket registered for handover, it should be dropped before the listen socket
+pub struct ListenFdGuard {
+    id: usize,
+}
+
+impl ListenFdGuard {
+    pub fn new_tcp<T: AsRawFd>(listener: &T, addr: SocketAddr) -> Self {
+        let id = LISTEN_FD_ID.fetch_add(1, Ordering::Relaxed);
+        let mut ht = ACTIVE_TCP_LISTENERS.lock().unwrap();
+        ht.insert(id, (addr, listener.as_raw_fd()));
+        ListenFdGuard { id }
+    }
+}
+
+impl Drop for ListenFdGuard {
+    fn drop(&mut self) {
+        let mut ht = ACTIVE_TCP_LISTENERS.lock().unwrap();
+        ht.remove(&self.id);
+    }
+}
+
+/// duplicate all the active tcp listen sockets, so they can be sent without holding the lock
+pub(super) fn dup_active_tcp() -> Vec<(SocketAddr, OwnedFd)> {
+    let ht = ACTIVE_TCP_LISTENERS.lock().unwrap();
+    ht.values()
+        .filter_map(|(addr, fd)| {
+            // the fd is valid as the guard is still registered
+            let fd = unsafe { BorrowedFd::borrow_raw(*fd) };
+            fd.try_clone_to_owned().ok().map(|fd| (*addr, fd))
+        })
+        .collect()
+}
+
+pub(super) fn add_inherited_tcp(addr: SocketAddr, fd: OwnedFd) {
+    let mut ht = INHERITED_TCP_LISTENERS.lock().unwrap();
+    ht.entry(addr).or_default().push(fd);
+}
+
+/// take an inherited tcp listen socket with the same listen address
+pub fn take_tcp_listener(addr: SocketAddr) -> Option<std::net::TcpListener> {
+    let mut ht = INHERITED_TCP_LISTENERS.lock().unwrap();
+    let fds = ht.get_mut(&addr)?;
+    let fd = fds.pop()?;
+    if fds.is_empty() {
+        ht.remove(&addr);
+    }
+    Some(std::net::TcpListener::from(fd))
+}
+
+/// close all the inherited listen sockets that are not used
+pub fn release_inherited() -> usize {
+    let mut ht = INHERITED_TCP_LISTENERS.lock().unwrap();
+    let count = ht.values().map(|v| v.len()).sum();
+    ht.clear();
+    count
+}
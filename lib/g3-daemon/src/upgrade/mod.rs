@@ -0,0 +1,435 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fs::Permissions;
+use std::io::{self, IoSlice, IoSliceMut, Read, Write};
+use std::net::SocketAddr;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use log::{debug, info, warn};
+use nix::sys::socket::{recvmsg, sendmsg, ControlMessage, ControlMessageOwned, MsgFlags};
+use tokio::net::UnixListener;
+
+mod listen_fd;
+pub use listen_fd::{release_inherited, take_tcp_listener, ListenFdGuard};
+
+const HANDOVER_TIMEOUT: Duration = Duration::from_secs(60);
+const RELEASE_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+const RECORD_SIZE: usize = 64;
+const RECORD_KIND_END: u8 = 0;
+const RECORD_KIND_TCP: u8 = 1;
+
+const COMMAND_UPGRADE: &str = "upgrade";
+const COMMAND_DONE: &str = "done";
+
+static TAKING_OVER: AtomicBool = AtomicBool::new(false);
+
+/// Check if we are taking over from an old process, and the listen sockets are not all spawned
+pub fn is_taking_over() -> bool {
+    TAKING_OVER.load(Ordering::Relaxed)
+}
+
+fn socket_path(daemon_group: &str) -> PathBuf {
+    let socket_name = if daemon_group.is_empty() {
+        "_upgrade.sock".to_string()
+    } else {
+        format!("{daemon_group}_upgrade.sock")
+    };
+    let mut path = crate::opts::control_dir();
+    path.push(Path::new(&socket_name));
+    path
+}
+
+/// remove the socket file left by a dead process, as no one will be listening on it
+fn remove_stale_socket(path: &Path) -> anyhow::Result<()> {
+    match UnixStream::connect(path) {
+        Ok(_) => Err(anyhow!(
+            "upgrade socket {} is in use by another process",
+            path.display()
+        )),
+        Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+            warn!("removing stale upgrade socket {}", path.display());
+            std::fs::remove_file(path)
+                .map_err(|e| anyhow!("failed to remove stale socket {}: {e}", path.display()))
+        }
+        Err(e) => Err(anyhow!(
+            "upgrade socket path {} already exists: {e}",
+            path.display()
+        )),
+    }
+}
+
+/// only the same user or root is allowed to take over the listen sockets
+fn check_peer_uid(peer_uid: u32, euid: u32) -> anyhow::Result<()> {
+    if peer_uid == euid || peer_uid == 0 {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "peer uid {peer_uid} is neither root nor our euid {euid}"
+        ))
+    }
+}
+
+fn read_line(stream: &mut UnixStream) -> anyhow::Result<String> {
+    let mut line = Vec::with_capacity(16);
+    let mut b = [0u8; 1];
+    loop {
+        let n = stream.read(&mut b)?;
+        if n == 0 {
+            return Err(anyhow!("connection closed by peer"));
+        }
+        if b[0] == b'\n' {
+            break;
+        }
+        if line.len() >= 64 {
+            return Err(anyhow!("too long line"));
+        }
+        line.push(b[0]);
+    }
+    String::from_utf8(line).map_err(|e| anyhow!("invalid utf-8 line: {e}"))
+}
+
+fn send_record(
+    stream: &UnixStream,
+    kind: u8,
+    addr: Option<SocketAddr>,
+    fd: Option<RawFd>,
+) -> anyhow::Result<()> {
+    let mut record = [0u8; RECORD_SIZE];
+    record[0] = kind;
+    if let Some(addr) = addr {
+        let s = addr.to_string();
+        let len = s.len().min(RECORD_SIZE - 1);
+        record[1..=len].copy_from_slice(&s.as_bytes()[..len]);
+    }
+
+    let iov = [IoSlice::new(&record)];
+    let fds: Vec<RawFd> = fd.into_iter().collect();
+    let cmsg = [ControlMessage::ScmRights(&fds)];
+    let cmsg: &[ControlMessage] = if fds.is_empty() { &[] } else { &cmsg };
+    let n = sendmsg::<()>(stream.as_raw_fd(), &iov, cmsg, MsgFlags::empty(), None)
+        .map_err(|e| anyhow!("sendmsg failed: {e}"))?;
+    if n != RECORD_SIZE {
+        return Err(anyhow!("only {n} bytes of the record sent"));
+    }
+    Ok(())
+}
+
+fn recv_record(stream: &UnixStream) -> anyhow::Result<(u8, Option<SocketAddr>, Option<OwnedFd>)> {
+    let mut record = [0u8; RECORD_SIZE];
+    let mut cmsg_buf = nix::cmsg_space!([RawFd; 1]);
+    let mut iov = [IoSliceMut::new(&mut record)];
+    let msg = recvmsg::<()>(
+        stream.as_raw_fd(),
+        &mut iov,
+        Some(&mut cmsg_buf),
+        MsgFlags::MSG_WAITALL | MsgFlags::MSG_CMSG_CLOEXEC,
+    )
+    .map_err(|e| anyhow!("recvmsg failed: {e}"))?;
+
+    let mut fd = None;
+    for cmsg in msg.cmsgs() {
+        if let ControlMessageOwned::ScmRights(fds) = cmsg {
+            for raw_fd in fds {
+                // we are the only owner of the received fd
+                let owned = unsafe { OwnedFd::from_raw_fd(raw_fd) };
+                if fd.is_none() {
+                    fd = Some(owned);
+                }
+            }
+        }
+    }
+    if msg.bytes != RECORD_SIZE {
+        return Err(anyhow!("only {} bytes of the record received", msg.bytes));
+    }
+
+    let kind = record[0];
+    let end = record[1..]
+        .iter()
+        .position(|b| *b == 0)
+        .map(|p| p + 1)
+        .unwrap_or(RECORD_SIZE);
+    let addr = if end > 1 {
+        let s = std::str::from_utf8(&record[1..end])
+            .map_err(|e| anyhow!("invalid listen address: {e}"))?;
+        Some(SocketAddr::from_str(s).map_err(|e| anyhow!("invalid listen address {s}: {e}"))?)
+    } else {
+        None
+    };
+    Ok((kind, addr, fd))
+}
+
+/// The upgrade socket of the running process, which will be used by the new process to take over
+pub struct UpgradeListener {
+    listen_path: PathBuf,
+    listener: UnixListener,
+}
+
+impl UpgradeListener {
+    pub fn create(daemon_group: &str) -> anyhow::Result<Self> {
+        let listen_path = socket_path(daemon_group);
+        if listen_path.exists() {
+            remove_stale_socket(&listen_path)?;
+        }
+        if let Some(parent) = listen_path.parent() {
+            std::fs::DirBuilder::new().recursive(true).create(parent)?;
+        }
+        let listener = UnixListener::bind(&listen_path)
+            .map_err(|e| anyhow!("failed to bind to {}: {e}", listen_path.display()))?;
+        std::fs::set_permissions(&listen_path, Permissions::from_mode(0o600))
+            .map_err(|e| anyhow!("failed to set permission of {}: {e}", listen_path.display()))?;
+        debug!("upgrade listener created at {}", listen_path.display());
+        Ok(UpgradeListener {
+            listen_path,
+            listener,
+        })
+    }
+
+    /// wait until a new process has taken over all the listen sockets,
+    /// the app state returned by `state` will also be sent to it
+    pub async fn serve<F>(&self, state: F)
+    where
+        F: Fn() -> Vec<u8>,
+    {
+        loop {
+            let stream = match self.listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!(
+                        "upgrade listener {} accept: {e}",
+                        self.listen_path.display()
+                    );
+                    continue;
+                }
+            };
+
+            let peer_uid = match stream.peer_cred() {
+                Ok(ucred) => ucred.uid(),
+                Err(e) => {
+                    warn!("upgrade: failed to get peer credential: {e}");
+                    continue;
+                }
+            };
+            let euid = unsafe { libc::geteuid() };
+            if let Err(e) = check_peer_uid(peer_uid, euid) {
+                warn!("upgrade: rejected client: {e}");
+                continue;
+            }
+
+            let state = state();
+            let r = tokio::task::spawn_blocking(move || {
+                let stream = stream.into_std()?;
+                stream.set_nonblocking(false)?;
+                handover(stream, state)
+            })
+            .await;
+            match r {
+                Ok(Ok(_)) => return,
+                Ok(Err(e)) => warn!("upgrade handover failed: {e:?}"),
+                Err(e) => warn!("failed to join upgrade handover task: {e}"),
+            }
+        }
+    }
+}
+
+impl Drop for UpgradeListener {
+    fn drop(&mut self) {
+        if self.listen_path.exists() {
+            debug!("unlink socket file {}", self.listen_path.display());
+            let _ = std::fs::remove_file(&self.listen_path);
+        }
+    }
+}
+
+fn handover(mut stream: UnixStream, state: Vec<u8>) -> anyhow::Result<()> {
+    stream.set_read_timeout(Some(HANDOVER_TIMEOUT))?;
+    stream.set_write_timeout(Some(HANDOVER_TIMEOUT))?;
+
+    let cmd = read_line(&mut stream).context("failed to read upgrade command")?;
+    if cmd != COMMAND_UPGRADE {
+        return Err(anyhow!("unexpected command {cmd}"));
+    }
+
+    let listeners = listen_fd::dup_active_tcp();
+    for (addr, fd) in &listeners {
+        send_record(&stream, RECORD_KIND_TCP, Some(*addr), Some(fd.as_raw_fd()))
+            .context(format!("failed to send listen socket {addr}"))?;
+    }
+    send_record(&stream, RECORD_KIND_END, None, None)?;
+    stream.write_all(&(state.len() as u32).to_be_bytes())?;
+    stream.write_all(&state)?;
+    stream.flush()?;
+    info!(
+        "sent {} tcp listen sockets to the new process",
+        listeners.len()
+    );
+    info!("udp listen sockets are not handed over, they will be bound again by the new process");
+    drop(listeners);
+
+    let cmd = read_line(&mut stream).context("failed to wait the new process to be ready")?;
+    if cmd != COMMAND_DONE {
+        return Err(anyhow!("unexpected command {cmd}"));
+    }
+    Ok(())
+}
+
+/// The new process side of the upgrade
+pub struct UpgradeSession {
+    daemon_group: String,
+    stream: UnixStream,
+    state: Vec<u8>,
+}
+
+impl UpgradeSession {
+    /// connect to the running process of the same daemon group, and inherit all its listen sockets
+    pub fn take_over(daemon_group: &str) -> anyhow::Result<Self> {
+        let path = socket_path(daemon_group);
+        let mut stream = UnixStream::connect(&path)
+            .map_err(|e| anyhow!("failed to connect to {}: {e}", path.display()))?;
+        stream.set_read_timeout(Some(HANDOVER_TIMEOUT))?;
+        stream.set_write_timeout(Some(HANDOVER_TIMEOUT))?;
+
+        stream.write_all(format!("{COMMAND_UPGRADE}\n").as_bytes())?;
+        TAKING_OVER.store(true, Ordering::Relaxed);
+
+        let mut count = 0usize;
+        loop {
+            match recv_record(&stream)? {
+                (RECORD_KIND_END, _, _) => break,
+                (RECORD_KIND_TCP, Some(addr), Some(fd)) => {
+                    listen_fd::add_inherited_tcp(addr, fd);
+                    count += 1;
+                }
+                (kind, _, _) => return Err(anyhow!("invalid record with kind {kind}")),
+            }
+        }
+
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len)?;
+        let mut state = vec![0u8; u32::from_be_bytes(len) as usize];
+        stream.read_exact(&mut state)?;
+        info!("inherited {count} listen sockets from the old process");
+
+        Ok(UpgradeSession {
+            daemon_group: daemon_group.to_string(),
+            stream,
+            state,
+        })
+    }
+
+    pub fn state(&self) -> &[u8] {
+        &self.state
+    }
+
+    /// notify the old process that we are ready, and wait it to release the control sockets
+    pub async fn finish(mut self) -> anyhow::Result<()> {
+        TAKING_OVER.store(false, Ordering::Relaxed);
+        self.stream
+            .write_all(format!("{COMMAND_DONE}\n").as_bytes())
+            .map_err(|e| anyhow!("failed to notify the old process: {e}"))?;
+
+        let upgrade_path = socket_path(&self.daemon_group);
+        let daemon_path = crate::control::LocalController::daemon_listen_path(&self.daemon_group);
+        let wait = async {
+            while upgrade_path.exists() || daemon_path.exists() {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        };
+        tokio::time::timeout(RELEASE_WAIT_TIMEOUT, wait)
+            .await
+            .map_err(|_| anyhow!("timed out to wait the old process to release control sockets"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener as StdUnixListener;
+
+    fn temp_socket_path(name: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("g3-daemon-{}-{name}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn stale_socket() {
+        let path = temp_socket_path("stale");
+        let listener = StdUnixListener::bind(&path).unwrap();
+        drop(listener);
+        assert!(path.exists());
+
+        remove_stale_socket(&path).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn peer_uid() {
+        assert!(check_peer_uid(1000, 1000).is_ok());
+        assert!(check_peer_uid(0, 1000).is_ok());
+        assert!(check_peer_uid(0, 0).is_ok());
+        assert!(check_peer_uid(1001, 1000).is_err());
+        assert!(check_peer_uid(1000, 0).is_err());
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let (s1, s2) = UnixStream::pair().unwrap();
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        send_record(&s1, RECORD_KIND_TCP, Some(addr), Some(listener.as_raw_fd())).unwrap();
+        send_record(&s1, RECORD_KIND_END, None, None).unwrap();
+
+        let (kind, r_addr, fd) = recv_record(&s2).unwrap();
+        assert_eq!(kind, RECORD_KIND_TCP);
+        assert_eq!(r_addr, Some(addr));
+        let fd = fd.unwrap();
+        assert_ne!(fd.as_raw_fd(), listener.as_raw_fd());
+
+        // the received fd should refer to the same listen socket
+        let r_listener = std::net::TcpListener::from(fd);
+        assert_eq!(r_listener.local_addr().unwrap(), addr);
+        drop(listener);
+        let _client = std::net::TcpStream::connect(addr).unwrap();
+        let (_stream, peer) = r_listener.accept().unwrap();
+        assert_eq!(peer.ip(), addr.ip());
+
+        let (kind, r_addr, fd) = recv_record(&s2).unwrap();
+        assert_eq!(kind, RECORD_KIND_END);
+        assert!(r_addr.is_none());
+        assert!(fd.is_none());
+    }
+
+    #[test]
+    fn live_socket() {
+        let path = temp_socket_path("live");
+        let _listener = StdUnixListener::bind(&path).unwrap();
+
+        assert!(remove_stale_socket(&path).is_err());
+        assert!(path.exists());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,36 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsInterceptionConfig {
+    pub idle_timeout: Duration,
+    pub message_read_timeout: Duration,
+    pub log_queries: bool,
+    pub blocked_domains: Vec<String>,
+}
+
+impl Default for DnsInterceptionConfig {
+    fn default() -> Self {
+        DnsInterceptionConfig {
+            idle_timeout: Duration::from_secs(60),
+            message_read_timeout: Duration::from_secs(10),
+            log_queries: true,
+            blocked_domains: Vec::new(),
+        }
+    }
+}
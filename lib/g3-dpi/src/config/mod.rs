@@ -23,10 +23,29 @@ pub use size_limit::ProtocolInspectionSizeLimit;
 mod http;
 pub use http::{H1InterceptionConfig, H2InterceptionConfig};
 
+mod smtp;
+pub use smtp::SmtpInterceptionConfig;
+
+mod imap;
+pub use imap::ImapInterceptionConfig;
+
+mod pop3;
+pub use pop3::Pop3InterceptionConfig;
+
+mod ftp;
+pub use ftp::FtpInterceptionConfig;
+
+mod dns;
+pub use dns::DnsInterceptionConfig;
+
+mod websocket;
+pub use websocket::WebSocketInterceptionConfig;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ProtocolInspectionConfig {
     inspect_max_depth: usize,
     data0_buffer_size: usize,
+    data0_max_buffer_size: usize,
     data0_wait_timeout: Duration,
     data0_read_timeout: Duration,
     data0_size_limit: ProtocolInspectionSizeLimit,
@@ -37,6 +56,7 @@ impl Default for ProtocolInspectionConfig {
         ProtocolInspectionConfig {
             inspect_max_depth: 4,
             data0_buffer_size: 4096,
+            data0_max_buffer_size: 16384,
             data0_wait_timeout: Duration::from_secs(60),
             data0_read_timeout: Duration::from_secs(4),
             data0_size_limit: Default::default(),
@@ -63,6 +83,17 @@ impl ProtocolInspectionConfig {
         self.data0_buffer_size
     }
 
+    pub fn set_data0_max_buffer_size(&mut self, size: usize) {
+        self.data0_max_buffer_size = size;
+    }
+
+    /// the max size of the data that can be buffered for protocol inspection,
+    /// and it won't be less than the initial buffer size
+    #[inline]
+    pub fn data0_max_buffer_size(&self) -> usize {
+        self.data0_max_buffer_size.max(self.data0_buffer_size)
+    }
+
     #[inline]
     pub fn set_data0_wait_timeout(&mut self, value: Duration) {
         self.data0_wait_timeout = value;
@@ -0,0 +1,41 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pop3InterceptionConfig {
+    pub greeting_timeout: Duration,
+    pub command_wait_timeout: Duration,
+    pub response_wait_timeout: Duration,
+    pub command_line_max_size: usize,
+    pub response_line_max_size: usize,
+    pub data_line_max_size: usize,
+}
+
+impl Default for Pop3InterceptionConfig {
+    fn default() -> Self {
+        Pop3InterceptionConfig {
+            greeting_timeout: Duration::from_secs(300),
+            // the autologout timer should be at least 10 minutes, see RFC 1939 section 3
+            command_wait_timeout: Duration::from_secs(600),
+            response_wait_timeout: Duration::from_secs(300),
+            command_line_max_size: 512,
+            response_line_max_size: 512,
+            data_line_max_size: 65536,
+        }
+    }
+}
@@ -0,0 +1,47 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmtpInterceptionConfig {
+    pub greeting_timeout: Duration,
+    pub command_wait_timeout: Duration,
+    pub response_wait_timeout: Duration,
+    pub data_initiation_timeout: Duration,
+    pub data_termination_timeout: Duration,
+    pub command_line_max_size: usize,
+    pub response_line_max_size: usize,
+    pub data_line_max_size: usize,
+    pub adaptation_message_max_size: usize,
+}
+
+impl Default for SmtpInterceptionConfig {
+    fn default() -> Self {
+        // the timeout values are taken from RFC 5321 section 4.5.3.2
+        SmtpInterceptionConfig {
+            greeting_timeout: Duration::from_secs(300),
+            command_wait_timeout: Duration::from_secs(300),
+            response_wait_timeout: Duration::from_secs(300),
+            data_initiation_timeout: Duration::from_secs(120),
+            data_termination_timeout: Duration::from_secs(600),
+            command_line_max_size: 4096,
+            response_line_max_size: 2048,
+            data_line_max_size: 65536,
+            adaptation_message_max_size: 32 * 1024 * 1024, // 32MB
+        }
+    }
+}
@@ -19,11 +19,21 @@ pub use source::Source;
 
 mod protocol;
 pub use protocol::{
-    MaybeProtocol, Protocol, ProtocolInspector, ProtocolPortMap, ProtocolPortMapValue,
+    check_dns_udp_request, MaybeProtocol, Protocol, ProtocolInspector, ProtocolPortMap,
+    ProtocolPortMapValue,
 };
 
+mod tls;
+pub use tls::{TlsClientFingerprint, TlsClientHello, TlsClientHelloParseError};
+
+mod quic;
+#[cfg(feature = "quic")]
+pub use quic::QuicInitialPacket;
+pub use quic::{check_quic_client_initial, QuicInitialParseError, QUIC_VERSION_1, QUIC_VERSION_2};
+
 mod config;
 pub use config::{
-    H1InterceptionConfig, H2InterceptionConfig, ProtocolInspectionConfig,
-    ProtocolInspectionSizeLimit,
+    DnsInterceptionConfig, FtpInterceptionConfig, H1InterceptionConfig, H2InterceptionConfig,
+    ImapInterceptionConfig, Pop3InterceptionConfig, ProtocolInspectionConfig,
+    ProtocolInspectionSizeLimit, SmtpInterceptionConfig, WebSocketInterceptionConfig,
 };
@@ -39,25 +39,41 @@ impl ProtocolInspectState {
             return Ok(None);
         }
 
-        if self.check_dns_request_message_header(&data[2..]).is_err() {
+        if check_dns_request_message_header(&data[2..]).is_err() {
             self.exclude_current();
             return Ok(None);
         }
 
         Ok(Some(Protocol::Dns))
     }
+}
 
-    fn check_dns_request_message_header(&mut self, hdr: &[u8]) -> Result<(), ()> {
-        if hdr[2] & 0b1000_0000 != 0 {
-            // QR bit is not query
-            return Err(());
-        }
+fn check_dns_request_message_header(hdr: &[u8]) -> Result<(), ()> {
+    if hdr[2] & 0b1000_0000 != 0 {
+        // QR bit is not query
+        return Err(());
+    }
 
-        if hdr[6..DNS_MESSAGE_HEADER_LEN] != [0x00, 0x00, 0x00, 0x00, 0x00, 0x00] {
-            // there should be no any an / ns / ar count
-            return Err(());
-        }
+    if hdr[6..10] != [0x00, 0x00, 0x00, 0x00] {
+        // there should be no any an / ns count, the ar count may be set for EDNS
+        return Err(());
+    }
 
-        Ok(())
+    Ok(())
+}
+
+/// Check if the UDP datagram sent by the client looks like a DNS request message.
+///
+/// There is no length prefix for DNS over UDP, so the question count is also checked,
+/// which should always be 1 in practice.
+pub fn check_dns_udp_request(data: &[u8]) -> bool {
+    if data.len() < DNS_MESSAGE_HEADER_LEN {
+        return false;
     }
+
+    if data[4..6] != [0x00, 0x01] {
+        return false;
+    }
+
+    check_dns_request_message_header(data).is_ok()
 }
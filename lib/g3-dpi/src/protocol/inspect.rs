@@ -30,12 +30,17 @@ const GUESS_PROTOCOL_FOR_CLIENT_INITIAL_DATA: &[MaybeProtocol] = &[
     MaybeProtocol::Ssh,
     MaybeProtocol::Smpp,
     MaybeProtocol::BitTorrent,
+    MaybeProtocol::Mqtt,
+    MaybeProtocol::Rdp,
+    MaybeProtocol::Postgres,
+    MaybeProtocol::Redis,
 ];
 const GUESS_PROTOCOL_FOR_SERVER_INITIAL_DATA: &[MaybeProtocol] = &[
     MaybeProtocol::Ssh,
     MaybeProtocol::Ftp,
     MaybeProtocol::Nats,
     MaybeProtocol::BitTorrent,
+    MaybeProtocol::Mysql,
 ];
 
 #[derive(Debug)]
@@ -108,13 +113,17 @@ impl ProtocolInspectState {
             MaybeProtocol::Smpp => self.check_smpp_session_request(data),
             MaybeProtocol::Rtmp => self.check_rtmp_tcp_client_handshake(data),
             MaybeProtocol::BitTorrent => self.check_bittorrent_tcp_handshake(data),
+            MaybeProtocol::Rdp => self.check_rdp_client_connection_request(data),
+            MaybeProtocol::Postgres => self.check_postgres_client_startup(data),
+            MaybeProtocol::Redis => self.check_redis_client_command(data),
             MaybeProtocol::Ftp
             | MaybeProtocol::Smtp
             | MaybeProtocol::Pop3
             | MaybeProtocol::Nntp
             | MaybeProtocol::Nnsp
             | MaybeProtocol::Imap
-            | MaybeProtocol::Nats => {
+            | MaybeProtocol::Nats
+            | MaybeProtocol::Mysql => {
                 self.exclude_current();
                 Ok(None)
             }
@@ -152,6 +161,7 @@ impl ProtocolInspectState {
             MaybeProtocol::Imap => self.check_imap_server_greeting(data, size_limit),
             MaybeProtocol::Nats => self.check_nats_server_info_msg(data, size_limit),
             MaybeProtocol::BitTorrent => self.check_bittorrent_tcp_handshake(data),
+            MaybeProtocol::Mysql => self.check_mysql_server_greeting(data),
             MaybeProtocol::Dns
             | MaybeProtocol::Ssl
             | MaybeProtocol::Http
@@ -159,7 +169,10 @@ impl ProtocolInspectState {
             | MaybeProtocol::Mqtt
             | MaybeProtocol::Stomp
             | MaybeProtocol::Smpp
-            | MaybeProtocol::Rtmp => {
+            | MaybeProtocol::Rtmp
+            | MaybeProtocol::Rdp
+            | MaybeProtocol::Postgres
+            | MaybeProtocol::Redis => {
                 self.exclude_current();
                 Ok(None)
             }
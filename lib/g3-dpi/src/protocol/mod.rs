@@ -45,6 +45,10 @@ pub enum MaybeProtocol {
     Rtmp,
     Nats,
     BitTorrent,
+    Rdp,
+    Mysql,
+    Postgres,
+    Redis,
 
     Https,
     Pop3s,
@@ -99,6 +103,10 @@ impl FromStr for MaybeProtocol {
             "rtmp" => Ok(MaybeProtocol::Rtmp),
             "nats" => Ok(MaybeProtocol::Nats),
             "bittorrent" | "bt" => Ok(MaybeProtocol::BitTorrent),
+            "rdp" => Ok(MaybeProtocol::Rdp),
+            "mysql" => Ok(MaybeProtocol::Mysql),
+            "postgres" | "postgresql" | "pgsql" => Ok(MaybeProtocol::Postgres),
+            "redis" => Ok(MaybeProtocol::Redis),
             "https" | "http+tls" => Ok(MaybeProtocol::Https),
             "pop3s" | "pop3+tls" => Ok(MaybeProtocol::Pop3s),
             "nntps" | "nntp+tls" | "snntp" => Ok(MaybeProtocol::Nntps),
@@ -171,6 +179,10 @@ pub enum Protocol {
     Nats,
     BitTorrentOverTcp,
     BitTorrentOverUtp,
+    Rdp,
+    Mysql,
+    Postgres,
+    Redis,
     Websocket,
     Dns,
 }
@@ -201,6 +213,10 @@ impl Protocol {
             Protocol::RtmpOverTcp | Protocol::RtmpOverHttp => "rtmp",
             Protocol::Nats => "nats",
             Protocol::BitTorrentOverTcp | Protocol::BitTorrentOverUtp => "bittorrent",
+            Protocol::Rdp => "rdp",
+            Protocol::Mysql => "mysql",
+            Protocol::Postgres => "postgres",
+            Protocol::Redis => "redis",
             Protocol::Websocket => "websocket",
             Protocol::Dns => "dns",
         }
@@ -229,6 +245,10 @@ impl Protocol {
             Protocol::Nats => "nats", // not officially supported
             Protocol::BitTorrentOverTcp => "bittorrent.tcp",
             Protocol::BitTorrentOverUtp => "bittorrent.utp",
+            Protocol::Rdp => "rdp",
+            Protocol::Mysql => "mysql",
+            Protocol::Postgres => "pgsql",
+            Protocol::Redis => "resp",
             Protocol::Websocket => "websocket",
             Protocol::Dns => "dns",
         }
@@ -255,12 +275,59 @@ impl Protocol {
             Protocol::RtmpOverTcp | Protocol::RtmpOverHttp => "rtmpt",
             Protocol::Nats => "nats", // not officially supported
             Protocol::BitTorrentOverTcp | Protocol::BitTorrentOverUtp => "bittorrent",
+            Protocol::Rdp => "rdp",
+            Protocol::Mysql => "mysql",
+            Protocol::Postgres => "pgsql",
+            Protocol::Redis => "resp",
             Protocol::Websocket => "websocket",
             Protocol::Dns => "dns",
         }
     }
 }
 
+impl FromStr for Protocol {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "unknown" => Ok(Protocol::Unknown),
+            "ssl_legacy" => Ok(Protocol::SslLegacy),
+            "tls_legacy" => Ok(Protocol::TlsLegacy),
+            "tls_modern" | "tls" => Ok(Protocol::TlsModern),
+            "tls_tlcp" | "tlcp" => Ok(Protocol::TlsTlcp),
+            "http_1" | "http1" | "http" => Ok(Protocol::Http1),
+            "http_2" | "http2" | "h2" => Ok(Protocol::Http2),
+            "http_3" | "http3" | "h3" => Ok(Protocol::Http3),
+            "smtp" => Ok(Protocol::Smtp),
+            "ssh_legacy" => Ok(Protocol::SshLegacy),
+            "ssh" => Ok(Protocol::Ssh),
+            "ftp_control" | "ftp" => Ok(Protocol::FtpControl),
+            "pop3" => Ok(Protocol::Pop3),
+            "nntp" => Ok(Protocol::Nntp),
+            "nnsp" => Ok(Protocol::Nnsp),
+            "imap" => Ok(Protocol::Imap),
+            "rtsp" => Ok(Protocol::Rtsp),
+            "mqtt" => Ok(Protocol::Mqtt),
+            "stomp" => Ok(Protocol::Stomp),
+            "smpp" => Ok(Protocol::Smpp),
+            "rtmp" => Ok(Protocol::RtmpOverTcp),
+            "nats" => Ok(Protocol::Nats),
+            "bittorrent" | "bt" => Ok(Protocol::BitTorrentOverTcp),
+            "rdp" => Ok(Protocol::Rdp),
+            "mysql" => Ok(Protocol::Mysql),
+            "postgres" | "postgresql" => Ok(Protocol::Postgres),
+            "redis" => Ok(Protocol::Redis),
+            "rdp" => Ok(Protocol::Rdp),
+            "mysql" => Ok(Protocol::Mysql),
+            "postgres" | "postgresql" | "pgsql" => Ok(Protocol::Postgres),
+            "redis" => Ok(Protocol::Redis),
+            "websocket" => Ok(Protocol::Websocket),
+            "dns" => Ok(Protocol::Dns),
+            _ => Err(()),
+        }
+    }
+}
+
 impl From<AlpnProtocol> for Protocol {
     fn from(p: AlpnProtocol) -> Self {
         match p {
@@ -291,9 +358,13 @@ mod ftp;
 mod http;
 mod imap;
 mod mqtt;
+mod mysql;
 mod nats;
 mod nntp;
 mod pop3;
+mod postgres;
+mod rdp;
+mod redis;
 mod rtmp;
 mod rtsp;
 mod smpp;
@@ -301,3 +372,5 @@ mod smtp;
 mod ssh;
 mod ssl;
 mod stomp;
+
+pub use dns::check_dns_udp_request;
@@ -25,17 +25,16 @@ impl ProtocolInspectState {
         const MINIMUM_DATA_LEN: usize = 12;
 
         let data_len = data.len();
+        if data_len > 0 && data[0] != 0x10 {
+            self.exclude_current();
+            return Ok(None);
+        }
         if data_len < MINIMUM_DATA_LEN {
             return Err(ProtocolInspectError::NeedMoreData(
                 MINIMUM_DATA_LEN - data_len,
             ));
         }
 
-        if data[0] != 0x10 {
-            self.exclude_current();
-            return Ok(None);
-        }
-
         // exclude impossible protocols
         self.exclude_other(MaybeProtocol::Ssl);
         self.exclude_other(MaybeProtocol::Ssh);
@@ -45,26 +44,55 @@ impl ProtocolInspectState {
         self.exclude_other(MaybeProtocol::Smpp);
         self.exclude_other(MaybeProtocol::Rtmp);
         self.exclude_other(MaybeProtocol::BitTorrent);
+        self.exclude_other(MaybeProtocol::Postgres);
+        self.exclude_other(MaybeProtocol::Redis);
+        self.exclude_other(MaybeProtocol::Rdp);
 
-        let remaining_len = data[1] as usize;
-        if remaining_len + 2 < MINIMUM_DATA_LEN {
+        // the Remaining Length field is encoded in 1 to 4 bytes
+        let mut remaining_len: usize = 0;
+        let mut offset = 1;
+        loop {
+            if offset > 4 {
+                self.exclude_current();
+                return Ok(None);
+            }
+            let b = data[offset];
+            remaining_len |= ((b & 0x7F) as usize) << (7 * (offset - 1));
+            offset += 1;
+            if b & 0x80 == 0 {
+                break;
+            }
+        }
+        let header_len = offset;
+        if remaining_len + header_len < MINIMUM_DATA_LEN {
             self.exclude_current();
             return Ok(None);
         }
 
-        if &data[2..8] != b"\x00\x04MQTT" {
+        let left = &data[header_len..];
+        let (expected_level, left) = if let Some(left) = left.strip_prefix(b"\x00\x04MQTT") {
+            (&[0x04, 0x05][..], left)
+        } else if let Some(left) = left.strip_prefix(b"\x00\x06MQIsdp") {
+            // MQTT v3.1
+            (&[0x03][..], left)
+        } else if left.len() < 8 && b"\x00\x06MQIsdp".starts_with(left) {
+            return Err(ProtocolInspectError::NeedMoreData(8 - left.len()));
+        } else {
             self.exclude_current();
             return Ok(None);
-        }
+        };
 
-        let protocol_level = data[8];
-        match protocol_level {
-            0x04 => {}
-            0x05 => {}
-            _ => {
-                self.exclude_current();
-                return Ok(None);
-            }
+        if left.len() < 2 {
+            return Err(ProtocolInspectError::NeedMoreData(2 - left.len()));
+        }
+        if !expected_level.contains(&left[0]) {
+            self.exclude_current();
+            return Ok(None);
+        }
+        // the reserved bit in Connect Flags must be 0
+        if left[1] & 0x01 != 0 {
+            self.exclude_current();
+            return Ok(None);
         }
 
         Ok(Some(Protocol::Mqtt))
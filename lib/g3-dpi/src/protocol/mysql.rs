@@ -0,0 +1,85 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use super::{MaybeProtocol, Protocol, ProtocolInspectError, ProtocolInspectState};
+
+const MYSQL_PROTOCOL_VERSION_10: u8 = 0x0a;
+const MYSQL_GREETING_MAX_PAYLOAD: usize = 1024;
+
+impl ProtocolInspectState {
+    pub(crate) fn check_mysql_server_greeting(
+        &mut self,
+        data: &[u8],
+    ) -> Result<Option<Protocol>, ProtocolInspectError> {
+        // Packet Header (4 bytes), Protocol Version (1 byte) and at least 1 byte Server Version
+        const MINIMUM_DATA_LEN: usize = 6;
+        // Protocol Version, Server Version (at least 1 + 1), Thread ID, Auth Plugin Data Part 1 and Filler
+        const MINIMUM_PAYLOAD_LEN: usize = 1 + 2 + 4 + 8 + 1;
+
+        let data_len = data.len();
+        if data_len < MINIMUM_DATA_LEN {
+            return Err(ProtocolInspectError::NeedMoreData(
+                MINIMUM_DATA_LEN - data_len,
+            ));
+        }
+
+        // the sequence id should be 0 for the initial handshake packet
+        if data[3] != 0x00 || data[4] != MYSQL_PROTOCOL_VERSION_10 {
+            self.exclude_current();
+            return Ok(None);
+        }
+
+        // exclude impossible protocols
+        self.exclude_other(MaybeProtocol::Ssh);
+        self.exclude_other(MaybeProtocol::Ftp);
+        self.exclude_other(MaybeProtocol::Smtp);
+        self.exclude_other(MaybeProtocol::Pop3);
+        self.exclude_other(MaybeProtocol::Nntp);
+        self.exclude_other(MaybeProtocol::Nnsp);
+        self.exclude_other(MaybeProtocol::Imap);
+        self.exclude_other(MaybeProtocol::Nats);
+        self.exclude_other(MaybeProtocol::BitTorrent);
+
+        let payload_len = u32::from_le_bytes([data[0], data[1], data[2], 0]) as usize;
+        if !(MINIMUM_PAYLOAD_LEN..=MYSQL_GREETING_MAX_PAYLOAD).contains(&payload_len) {
+            self.exclude_current();
+            return Ok(None);
+        }
+
+        let packet_len = 4 + payload_len;
+        if data_len < packet_len {
+            return Err(ProtocolInspectError::NeedMoreData(packet_len - data_len));
+        }
+        let payload = &data[5..packet_len];
+
+        let Some(p) = memchr::memchr(b'\0', payload) else {
+            self.exclude_current();
+            return Ok(None);
+        };
+        if p == 0 || !payload[..p].iter().all(|c| c.is_ascii_graphic()) {
+            self.exclude_current();
+            return Ok(None);
+        }
+
+        // Thread ID (4 bytes), Auth Plugin Data Part 1 (8 bytes), and then the Filler
+        let filler_offset = p + 1 + 4 + 8;
+        if payload.len() <= filler_offset || payload[filler_offset] != 0x00 {
+            self.exclude_current();
+            return Ok(None);
+        }
+
+        Ok(Some(Protocol::Mysql))
+    }
+}
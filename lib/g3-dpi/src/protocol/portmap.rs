@@ -81,6 +81,10 @@ impl ProtocolPortMapValue {
                 self.check_ssl = true;
                 MaybeProtocol::Rtmp
             }
+            MaybeProtocol::DnsOverTls => {
+                self.check_ssl = true;
+                MaybeProtocol::Dns
+            }
             p => p,
         };
         if !self.protocols.contains(&p) {
@@ -135,8 +139,12 @@ impl ProtocolPortMap {
         map.insert(1883, MaybeProtocol::Mqtt);
         map.insert(1935, MaybeProtocol::Rtmp);
         map.insert(2775, MaybeProtocol::Smpp);
+        map.insert(3306, MaybeProtocol::Mysql);
+        map.insert(3389, MaybeProtocol::Rdp);
         map.insert(3550, MaybeProtocol::Ssmpp);
         map.insert(4222, MaybeProtocol::Nats);
+        map.insert(5432, MaybeProtocol::Postgres);
+        map.insert(6379, MaybeProtocol::Redis);
         map.insert(6881, MaybeProtocol::BitTorrent);
         map.insert(8080, MaybeProtocol::Http);
         map.insert(8554, MaybeProtocol::Rtsp);
@@ -0,0 +1,90 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use super::{MaybeProtocol, Protocol, ProtocolInspectError, ProtocolInspectState};
+
+const PG_PROTOCOL_V3: u32 = 0x0003_0000;
+const PG_CANCEL_REQUEST_CODE: u32 = 80877102;
+const PG_SSL_REQUEST_CODE: u32 = 80877103;
+const PG_GSSENC_REQUEST_CODE: u32 = 80877104;
+
+const PG_MAX_STARTUP_PACKET_LENGTH: usize = 10000;
+
+impl ProtocolInspectState {
+    pub(crate) fn check_postgres_client_startup(
+        &mut self,
+        data: &[u8],
+    ) -> Result<Option<Protocol>, ProtocolInspectError> {
+        // Length (4 bytes) and Protocol Version or Request Code (4 bytes)
+        const MINIMUM_DATA_LEN: usize = 8;
+
+        let data_len = data.len();
+        if data_len > 0 && data[0] != 0x00 {
+            // the startup packet is too small so the first byte should always be 0
+            self.exclude_current();
+            return Ok(None);
+        }
+        if data_len < MINIMUM_DATA_LEN {
+            return Err(ProtocolInspectError::NeedMoreData(
+                MINIMUM_DATA_LEN - data_len,
+            ));
+        }
+
+        let len = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let code = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+        match code {
+            PG_SSL_REQUEST_CODE | PG_GSSENC_REQUEST_CODE => {
+                // the client is asking for a TLS or GSSAPI encrypted session
+                if len != 8 {
+                    self.exclude_current();
+                    return Ok(None);
+                }
+            }
+            PG_CANCEL_REQUEST_CODE => {
+                // Process ID and Secret Key follows
+                if len != 16 {
+                    self.exclude_current();
+                    return Ok(None);
+                }
+            }
+            PG_PROTOCOL_V3 => {
+                // at least the terminator of the parameter list
+                if len <= 8 || len > PG_MAX_STARTUP_PACKET_LENGTH {
+                    self.exclude_current();
+                    return Ok(None);
+                }
+            }
+            _ => {
+                self.exclude_current();
+                return Ok(None);
+            }
+        }
+
+        // exclude impossible protocols
+        self.exclude_other(MaybeProtocol::Ssl);
+        self.exclude_other(MaybeProtocol::Ssh);
+        self.exclude_other(MaybeProtocol::Http);
+        self.exclude_other(MaybeProtocol::Rtsp);
+        self.exclude_other(MaybeProtocol::Mqtt);
+        self.exclude_other(MaybeProtocol::Stomp);
+        self.exclude_other(MaybeProtocol::Smpp);
+        self.exclude_other(MaybeProtocol::Rtmp);
+        self.exclude_other(MaybeProtocol::BitTorrent);
+        self.exclude_other(MaybeProtocol::Rdp);
+        self.exclude_other(MaybeProtocol::Redis);
+
+        Ok(Some(Protocol::Postgres))
+    }
+}
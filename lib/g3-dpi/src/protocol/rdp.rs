@@ -0,0 +1,82 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use super::{MaybeProtocol, Protocol, ProtocolInspectError, ProtocolInspectState};
+
+impl ProtocolInspectState {
+    pub(crate) fn check_rdp_client_connection_request(
+        &mut self,
+        data: &[u8],
+    ) -> Result<Option<Protocol>, ProtocolInspectError> {
+        // TPKT Header (4 bytes) and X.224 Connection Request TPDU fixed part (7 bytes)
+        const MINIMUM_DATA_LEN: usize = 11;
+
+        let data_len = data.len();
+        if data_len > 0 && data[0] != 0x03 {
+            self.exclude_current();
+            return Ok(None);
+        }
+        if data_len < MINIMUM_DATA_LEN {
+            return Err(ProtocolInspectError::NeedMoreData(
+                MINIMUM_DATA_LEN - data_len,
+            ));
+        }
+
+        if data[1] != 0x00 {
+            self.exclude_current();
+            return Ok(None);
+        }
+
+        // exclude impossible protocols
+        self.exclude_other(MaybeProtocol::Ssl);
+        self.exclude_other(MaybeProtocol::Ssh);
+        self.exclude_other(MaybeProtocol::Http);
+        self.exclude_other(MaybeProtocol::Rtsp);
+        self.exclude_other(MaybeProtocol::Mqtt);
+        self.exclude_other(MaybeProtocol::Stomp);
+        self.exclude_other(MaybeProtocol::Smpp);
+        self.exclude_other(MaybeProtocol::Rtmp);
+        self.exclude_other(MaybeProtocol::BitTorrent);
+        self.exclude_other(MaybeProtocol::Postgres);
+        self.exclude_other(MaybeProtocol::Redis);
+
+        let tpkt_len = u16::from_be_bytes([data[2], data[3]]) as usize;
+        if tpkt_len < MINIMUM_DATA_LEN {
+            self.exclude_current();
+            return Ok(None);
+        }
+
+        // the length indicator doesn't include itself
+        let x224_li = data[4] as usize;
+        if x224_li + 5 != tpkt_len {
+            self.exclude_current();
+            return Ok(None);
+        }
+
+        // CR CDT, with the lower 4 bits set to 0 for class 0
+        if data[5] != 0xE0 {
+            self.exclude_current();
+            return Ok(None);
+        }
+
+        // DST-REF should be 0, and the class option should be 0
+        if data[6] != 0x00 || data[7] != 0x00 || data[10] & 0xF0 != 0x00 {
+            self.exclude_current();
+            return Ok(None);
+        }
+
+        Ok(Some(Protocol::Rdp))
+    }
+}
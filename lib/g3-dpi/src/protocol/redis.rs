@@ -0,0 +1,131 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use super::{MaybeProtocol, Protocol, ProtocolInspectError, ProtocolInspectState};
+
+const REDIS_ARRAY_MAX_COUNT_DIGITS: usize = 7;
+const REDIS_COMMAND_MAX_LENGTH: usize = 32;
+
+/// parse a RESP decimal number ended with CRLF,
+/// return the number and the consumed length if valid
+fn parse_resp_number(
+    data: &[u8],
+    max_digits: usize,
+) -> Result<Option<(usize, usize)>, ProtocolInspectError> {
+    let mut value: usize = 0;
+    for (i, c) in data.iter().enumerate() {
+        match c {
+            b'0'..=b'9' => {
+                if i >= max_digits {
+                    return Ok(None);
+                }
+                value = value * 10 + (*c - b'0') as usize;
+            }
+            b'\r' => {
+                if i == 0 {
+                    return Ok(None);
+                }
+                return match data.get(i + 1) {
+                    Some(b'\n') => Ok(Some((value, i + 2))),
+                    Some(_) => Ok(None),
+                    None => Err(ProtocolInspectError::NeedMoreData(1)),
+                };
+            }
+            _ => return Ok(None),
+        }
+    }
+    Err(ProtocolInspectError::NeedMoreData(1))
+}
+
+impl ProtocolInspectState {
+    pub(crate) fn check_redis_client_command(
+        &mut self,
+        data: &[u8],
+    ) -> Result<Option<Protocol>, ProtocolInspectError> {
+        // at least *1<CR><LF>
+        const MINIMUM_DATA_LEN: usize = 4;
+
+        let data_len = data.len();
+        if data_len > 0 && data[0] != b'*' {
+            self.exclude_current();
+            return Ok(None);
+        }
+        if data_len < MINIMUM_DATA_LEN {
+            return Err(ProtocolInspectError::NeedMoreData(
+                MINIMUM_DATA_LEN - data_len,
+            ));
+        }
+
+        // exclude impossible protocols
+        self.exclude_other(MaybeProtocol::Ssl);
+        self.exclude_other(MaybeProtocol::Ssh);
+        self.exclude_other(MaybeProtocol::Http);
+        self.exclude_other(MaybeProtocol::Rtsp);
+        self.exclude_other(MaybeProtocol::Mqtt);
+        self.exclude_other(MaybeProtocol::Stomp);
+        self.exclude_other(MaybeProtocol::Smpp);
+        self.exclude_other(MaybeProtocol::Rtmp);
+        self.exclude_other(MaybeProtocol::BitTorrent);
+        self.exclude_other(MaybeProtocol::Rdp);
+        self.exclude_other(MaybeProtocol::Postgres);
+
+        // the command should be sent as an array of bulk strings
+        let Some((count, len)) = parse_resp_number(&data[1..], REDIS_ARRAY_MAX_COUNT_DIGITS)?
+        else {
+            self.exclude_current();
+            return Ok(None);
+        };
+        if count == 0 {
+            self.exclude_current();
+            return Ok(None);
+        }
+        let mut offset = 1 + len;
+
+        match data.get(offset) {
+            Some(b'$') => offset += 1,
+            Some(_) => {
+                self.exclude_current();
+                return Ok(None);
+            }
+            None => return Err(ProtocolInspectError::NeedMoreData(1)),
+        }
+
+        // the first bulk string is the command name
+        let Some((cmd_len, len)) = parse_resp_number(&data[offset..], 2)? else {
+            self.exclude_current();
+            return Ok(None);
+        };
+        if cmd_len == 0 || cmd_len > REDIS_COMMAND_MAX_LENGTH {
+            self.exclude_current();
+            return Ok(None);
+        }
+        offset += len;
+
+        let cmd_end = offset + cmd_len;
+        if data_len < cmd_end + 2 {
+            return Err(ProtocolInspectError::NeedMoreData(cmd_end + 2 - data_len));
+        }
+        if !data[offset..cmd_end]
+            .iter()
+            .all(|c| c.is_ascii_alphabetic())
+            || &data[cmd_end..cmd_end + 2] != b"\r\n"
+        {
+            self.exclude_current();
+            return Ok(None);
+        }
+
+        Ok(Some(Protocol::Redis))
+    }
+}
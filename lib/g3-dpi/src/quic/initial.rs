@@ -0,0 +1,259 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use ring::aead::quic::{HeaderProtectionKey, AES_128};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_128_GCM};
+use ring::hkdf;
+
+use super::{QuicInitialHeader, QuicInitialParseError, Reader, QUIC_VERSION_1, QUIC_VERSION_2};
+use crate::{TlsClientHello, TlsClientHelloParseError};
+
+const INITIAL_SALT_V1: [u8; 20] = [
+    0x38, 0x76, 0x2c, 0xf7, 0xf5, 0x59, 0x34, 0xb3, 0x4d, 0x17, 0x9a, 0xe6, 0xa4, 0xc8, 0x0c, 0xad,
+    0xcc, 0xbb, 0x7f, 0x0a,
+];
+const INITIAL_SALT_V2: [u8; 20] = [
+    0x0d, 0xed, 0xe3, 0xde, 0xf7, 0x00, 0xa6, 0xdb, 0x81, 0x93, 0x81, 0xbe, 0x6e, 0x26, 0x9d, 0xcb,
+    0xf9, 0xbd, 0x2e, 0xd9,
+];
+
+const SAMPLE_LEN: usize = 16;
+const TAG_LEN: usize = 16;
+const HANDSHAKE_HEADER_LEN: usize = 4;
+const HANDSHAKE_TYPE_CLIENT_HELLO: u8 = 1;
+
+const FRAME_PADDING: u64 = 0x00;
+const FRAME_PING: u64 = 0x01;
+const FRAME_ACK: u64 = 0x02;
+const FRAME_ACK_ECN: u64 = 0x03;
+const FRAME_CRYPTO: u64 = 0x06;
+const FRAME_CONNECTION_CLOSE: u64 = 0x1c;
+
+struct HkdfLen(usize);
+
+impl hkdf::KeyType for HkdfLen {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+/// HKDF-Expand-Label with empty context, see RFC 8446 Section 7.1
+fn hkdf_expand_label(
+    prk: &hkdf::Prk,
+    label: &[u8],
+    out: &mut [u8],
+) -> Result<(), QuicInitialParseError> {
+    let out_len = (out.len() as u16).to_be_bytes();
+    let label_len = [(b"tls13 ".len() + label.len()) as u8];
+    let info: [&[u8]; 5] = [&out_len, &label_len, b"tls13 ", label, &[0]];
+    prk.expand(&info, HkdfLen(out.len()))
+        .and_then(|okm| okm.fill(out))
+        .map_err(|_| QuicInitialParseError::DecryptFailed)
+}
+
+/// The client Initial keys, see RFC 9001 Section 5.2
+struct InitialKeys {
+    key: LessSafeKey,
+    iv: [u8; 12],
+    hp: HeaderProtectionKey,
+}
+
+impl InitialKeys {
+    fn new(version: u32, dcid: &[u8]) -> Result<Self, QuicInitialParseError> {
+        let (salt, key_label, iv_label, hp_label): (&[u8], &[u8], &[u8], &[u8]) = match version {
+            QUIC_VERSION_1 => (&INITIAL_SALT_V1, b"quic key", b"quic iv", b"quic hp"),
+            QUIC_VERSION_2 => (&INITIAL_SALT_V2, b"quicv2 key", b"quicv2 iv", b"quicv2 hp"),
+            v => return Err(QuicInitialParseError::UnsupportedVersion(v)),
+        };
+
+        let initial_secret = hkdf::Salt::new(hkdf::HKDF_SHA256, salt).extract(dcid);
+        let mut client_secret = [0u8; 32];
+        hkdf_expand_label(&initial_secret, b"client in", &mut client_secret)?;
+        let client_secret = hkdf::Prk::new_less_safe(hkdf::HKDF_SHA256, &client_secret);
+
+        let mut key = [0u8; 16];
+        hkdf_expand_label(&client_secret, key_label, &mut key)?;
+        let mut iv = [0u8; 12];
+        hkdf_expand_label(&client_secret, iv_label, &mut iv)?;
+        let mut hp = [0u8; 16];
+        hkdf_expand_label(&client_secret, hp_label, &mut hp)?;
+
+        let key = UnboundKey::new(&AES_128_GCM, &key)
+            .map_err(|_| QuicInitialParseError::DecryptFailed)?;
+        let hp = HeaderProtectionKey::new(&AES_128, &hp)
+            .map_err(|_| QuicInitialParseError::DecryptFailed)?;
+        Ok(InitialKeys {
+            key: LessSafeKey::new(key),
+            iv,
+            hp,
+        })
+    }
+}
+
+/// The QUIC Initial packet sent by the client, with the payload decrypted
+#[derive(Debug)]
+pub struct QuicInitialPacket {
+    pub version: u32,
+    pub dst_cid: Vec<u8>,
+    pub src_cid: Vec<u8>,
+    pub token: Vec<u8>,
+    pub packet_number: u64,
+    /// the CRYPTO stream data that is continuous from offset 0
+    pub crypto_data: Vec<u8>,
+    /// will be None if the ClientHello message is not complete in this packet
+    pub client_hello: Option<TlsClientHello>,
+}
+
+impl QuicInitialPacket {
+    /// Parse and decrypt the first QUIC Initial packet in the UDP datagram sent by the client
+    pub fn parse(data: &[u8]) -> Result<Self, QuicInitialParseError> {
+        let header = QuicInitialHeader::parse(data)?;
+        let keys = InitialKeys::new(header.version, header.dcid)?;
+
+        // remove header protection, see RFC 9001 Section 5.4
+        let pn_offset = header.pn_offset;
+        let sample_offset = pn_offset + 4;
+        let Some(sample) = data.get(sample_offset..sample_offset + SAMPLE_LEN) else {
+            return Err(QuicInitialParseError::InvalidPacket);
+        };
+        let mask = keys
+            .hp
+            .new_mask(sample)
+            .map_err(|_| QuicInitialParseError::DecryptFailed)?;
+
+        let mut packet = data[..pn_offset + header.length].to_vec();
+        packet[0] ^= mask[0] & 0x0f;
+        let pn_len = (packet[0] & 0x03) as usize + 1;
+        if header.length < pn_len + TAG_LEN {
+            return Err(QuicInitialParseError::InvalidPacket);
+        }
+        let mut packet_number = 0u64;
+        for i in 0..pn_len {
+            packet[pn_offset + i] ^= mask[1 + i];
+            packet_number = (packet_number << 8) | packet[pn_offset + i] as u64;
+        }
+
+        let mut nonce = keys.iv;
+        for (i, b) in packet_number.to_be_bytes().iter().enumerate() {
+            nonce[4 + i] ^= b;
+        }
+        let (header_bytes, payload) = packet.split_at_mut(pn_offset + pn_len);
+        let plain = keys
+            .key
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(&*header_bytes),
+                payload,
+            )
+            .map_err(|_| QuicInitialParseError::DecryptFailed)?;
+
+        let crypto_data = collect_crypto_data(plain)?;
+        let client_hello = parse_client_hello(&crypto_data)?;
+        Ok(QuicInitialPacket {
+            version: header.version,
+            dst_cid: header.dcid.to_vec(),
+            src_cid: header.scid.to_vec(),
+            token: header.token.to_vec(),
+            packet_number,
+            crypto_data,
+            client_hello,
+        })
+    }
+
+    pub fn server_name(&self) -> Option<&str> {
+        self.client_hello
+            .as_ref()
+            .and_then(|v| v.server_name.as_deref())
+    }
+}
+
+fn collect_crypto_data(payload: &[u8]) -> Result<Vec<u8>, QuicInitialParseError> {
+    let mut frames: Vec<(u64, &[u8])> = Vec::new();
+
+    let mut r = Reader::new(payload);
+    while !r.is_empty() {
+        let frame_type = r.varint().ok_or(QuicInitialParseError::InvalidFrame)?;
+        match frame_type {
+            FRAME_PADDING | FRAME_PING => {}
+            FRAME_ACK | FRAME_ACK_ECN => {
+                r.varint().ok_or(QuicInitialParseError::InvalidFrame)?; // largest acknowledged
+                r.varint().ok_or(QuicInitialParseError::InvalidFrame)?; // ack delay
+                let range_count = r.varint().ok_or(QuicInitialParseError::InvalidFrame)?;
+                r.varint().ok_or(QuicInitialParseError::InvalidFrame)?; // first ack range
+                for _ in 0..range_count {
+                    r.varint().ok_or(QuicInitialParseError::InvalidFrame)?; // gap
+                    r.varint().ok_or(QuicInitialParseError::InvalidFrame)?; // ack range length
+                }
+                if frame_type == FRAME_ACK_ECN {
+                    for _ in 0..3 {
+                        r.varint().ok_or(QuicInitialParseError::InvalidFrame)?;
+                    }
+                }
+            }
+            FRAME_CRYPTO => {
+                let offset = r.varint().ok_or(QuicInitialParseError::InvalidFrame)?;
+                let len = r.varint().ok_or(QuicInitialParseError::InvalidFrame)?;
+                let data = r
+                    .bytes(len as usize)
+                    .ok_or(QuicInitialParseError::InvalidFrame)?;
+                frames.push((offset, data));
+            }
+            FRAME_CONNECTION_CLOSE => {
+                r.varint().ok_or(QuicInitialParseError::InvalidFrame)?; // error code
+                r.varint().ok_or(QuicInitialParseError::InvalidFrame)?; // frame type
+                let len = r.varint().ok_or(QuicInitialParseError::InvalidFrame)?;
+                r.bytes(len as usize)
+                    .ok_or(QuicInitialParseError::InvalidFrame)?;
+            }
+            _ => return Err(QuicInitialParseError::InvalidFrame),
+        }
+    }
+
+    // the CRYPTO frames may be out of order, and may overlap
+    frames.sort_by_key(|(offset, _)| *offset);
+    let mut buf = Vec::new();
+    for (offset, data) in frames {
+        let offset = offset as usize;
+        if offset > buf.len() {
+            // the missing data should be in other packets
+            break;
+        }
+        let end = offset + data.len();
+        if end > buf.len() {
+            buf.extend_from_slice(&data[buf.len() - offset..]);
+        }
+    }
+    Ok(buf)
+}
+
+fn parse_client_hello(data: &[u8]) -> Result<Option<TlsClientHello>, QuicInitialParseError> {
+    if data.len() < HANDSHAKE_HEADER_LEN {
+        return Ok(None);
+    }
+    if data[0] != HANDSHAKE_TYPE_CLIENT_HELLO {
+        return Err(QuicInitialParseError::InvalidClientHello(
+            TlsClientHelloParseError::InvalidHandshakeType,
+        ));
+    }
+    let msg_len = u32::from_be_bytes([0, data[1], data[2], data[3]]) as usize;
+    let msg_end = HANDSHAKE_HEADER_LEN + msg_len;
+    if data.len() < msg_end {
+        return Ok(None);
+    }
+    TlsClientHello::parse_message(&data[HANDSHAKE_HEADER_LEN..msg_end])
+        .map(Some)
+        .map_err(QuicInitialParseError::InvalidClientHello)
+}
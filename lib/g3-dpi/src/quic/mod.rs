@@ -0,0 +1,166 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::TlsClientHelloParseError;
+
+#[cfg(feature = "quic")]
+mod initial;
+#[cfg(feature = "quic")]
+pub use initial::QuicInitialPacket;
+
+pub const QUIC_VERSION_1: u32 = 0x0000_0001;
+pub const QUIC_VERSION_2: u32 = 0x6b33_43cf;
+
+const LONG_HEADER_FORM: u8 = 0x80;
+const FIXED_BIT: u8 = 0x40;
+const MAX_CID_LEN: usize = 20;
+/// the minimum size of UDP datagrams that carry client Initial packets, see RFC 9000 Section 14.1
+const MIN_INITIAL_DATAGRAM_SIZE: usize = 1200;
+
+#[derive(Debug)]
+pub enum QuicInitialParseError {
+    NotLongHeader,
+    NotInitialPacket,
+    UnsupportedVersion(u32),
+    InvalidPacket,
+    DecryptFailed,
+    InvalidFrame,
+    InvalidClientHello(TlsClientHelloParseError),
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Reader { buf, offset: 0 }
+    }
+
+    #[cfg(feature = "quic")]
+    fn is_empty(&self) -> bool {
+        self.offset >= self.buf.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.offset.checked_add(len)?;
+        let v = self.buf.get(self.offset..end)?;
+        self.offset = end;
+        Some(v)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|v| v[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4)
+            .map(|v| u32::from_be_bytes([v[0], v[1], v[2], v[3]]))
+    }
+
+    /// variable-length integer encoding, see RFC 9000 Section 16
+    fn varint(&mut self) -> Option<u64> {
+        let first = self.u8()?;
+        let len = 1usize << (first >> 6);
+        let mut v = (first & 0x3f) as u64;
+        for b in self.bytes(len - 1)? {
+            v = (v << 8) | (*b as u64);
+        }
+        Some(v)
+    }
+}
+
+/// The long header fields of the QUIC Initial packet, before header protection is removed
+#[cfg_attr(not(feature = "quic"), allow(dead_code))]
+struct QuicInitialHeader<'a> {
+    version: u32,
+    dcid: &'a [u8],
+    scid: &'a [u8],
+    token: &'a [u8],
+    /// the offset of the packet number field
+    pn_offset: usize,
+    /// the length of the packet number and the protected payload
+    length: usize,
+}
+
+impl<'a> QuicInitialHeader<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self, QuicInitialParseError> {
+        let mut r = Reader::new(data);
+        let first = r.u8().ok_or(QuicInitialParseError::InvalidPacket)?;
+        if first & LONG_HEADER_FORM == 0 {
+            return Err(QuicInitialParseError::NotLongHeader);
+        }
+        if first & FIXED_BIT == 0 {
+            return Err(QuicInitialParseError::InvalidPacket);
+        }
+        let version = r.u32().ok_or(QuicInitialParseError::InvalidPacket)?;
+        let packet_type = (first & 0x30) >> 4;
+        let is_initial = match version {
+            QUIC_VERSION_1 => packet_type == 0b00,
+            QUIC_VERSION_2 => packet_type == 0b01,
+            0 => return Err(QuicInitialParseError::NotInitialPacket), // version negotiation
+            v => return Err(QuicInitialParseError::UnsupportedVersion(v)),
+        };
+        if !is_initial {
+            return Err(QuicInitialParseError::NotInitialPacket);
+        }
+
+        let dcid_len = r.u8().ok_or(QuicInitialParseError::InvalidPacket)? as usize;
+        if dcid_len > MAX_CID_LEN {
+            return Err(QuicInitialParseError::InvalidPacket);
+        }
+        let dcid = r
+            .bytes(dcid_len)
+            .ok_or(QuicInitialParseError::InvalidPacket)?;
+        let scid_len = r.u8().ok_or(QuicInitialParseError::InvalidPacket)? as usize;
+        if scid_len > MAX_CID_LEN {
+            return Err(QuicInitialParseError::InvalidPacket);
+        }
+        let scid = r
+            .bytes(scid_len)
+            .ok_or(QuicInitialParseError::InvalidPacket)?;
+        let token_len = r.varint().ok_or(QuicInitialParseError::InvalidPacket)? as usize;
+        let token = r
+            .bytes(token_len)
+            .ok_or(QuicInitialParseError::InvalidPacket)?;
+        let length = r.varint().ok_or(QuicInitialParseError::InvalidPacket)? as usize;
+        let pn_offset = r.offset;
+        if data.len() - pn_offset < length {
+            return Err(QuicInitialParseError::InvalidPacket);
+        }
+
+        Ok(QuicInitialHeader {
+            version,
+            dcid,
+            scid,
+            token,
+            pn_offset,
+            length,
+        })
+    }
+}
+
+/// Check if the UDP datagram sent by the client looks like a QUIC Initial packet,
+/// and return the QUIC version if it is.
+///
+/// The packet payload will not be decrypted.
+pub fn check_quic_client_initial(data: &[u8]) -> Option<u32> {
+    if data.len() < MIN_INITIAL_DATAGRAM_SIZE {
+        return None;
+    }
+    QuicInitialHeader::parse(data).ok().map(|h| h.version)
+}
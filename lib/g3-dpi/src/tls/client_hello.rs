@@ -0,0 +1,224 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+const RECORD_HEADER_LEN: usize = 5;
+const HANDSHAKE_HEADER_LEN: usize = 4;
+const CONTENT_TYPE_HANDSHAKE: u8 = 22;
+const HANDSHAKE_TYPE_CLIENT_HELLO: u8 = 1;
+
+const EXT_SERVER_NAME: u16 = 0;
+const EXT_SUPPORTED_GROUPS: u16 = 10;
+const EXT_EC_POINT_FORMATS: u16 = 11;
+const EXT_SIGNATURE_ALGORITHMS: u16 = 13;
+const EXT_ALPN: u16 = 16;
+const EXT_SUPPORTED_VERSIONS: u16 = 43;
+const EXT_ENCRYPTED_CLIENT_HELLO: u16 = 0xfe0d;
+
+#[derive(Debug)]
+pub enum TlsClientHelloParseError {
+    NeedMoreData,
+    InvalidRecord,
+    InvalidHandshakeType,
+    InvalidMessage,
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Reader { buf }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], TlsClientHelloParseError> {
+        if self.buf.len() < len {
+            return Err(TlsClientHelloParseError::InvalidMessage);
+        }
+        let (v, left) = self.buf.split_at(len);
+        self.buf = left;
+        Ok(v)
+    }
+
+    fn u8(&mut self) -> Result<u8, TlsClientHelloParseError> {
+        self.bytes(1).map(|v| v[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, TlsClientHelloParseError> {
+        self.bytes(2).map(|v| u16::from_be_bytes([v[0], v[1]]))
+    }
+
+    fn vec_u8(&mut self) -> Result<&'a [u8], TlsClientHelloParseError> {
+        let len = self.u8()? as usize;
+        self.bytes(len)
+    }
+
+    fn vec_u16(&mut self) -> Result<&'a [u8], TlsClientHelloParseError> {
+        let len = self.u16()? as usize;
+        self.bytes(len)
+    }
+
+    fn u16_list(data: &[u8]) -> Result<Vec<u16>, TlsClientHelloParseError> {
+        if data.len() % 2 != 0 {
+            return Err(TlsClientHelloParseError::InvalidMessage);
+        }
+        Ok(data
+            .chunks_exact(2)
+            .map(|v| u16::from_be_bytes([v[0], v[1]]))
+            .collect())
+    }
+}
+
+/// The fields in the TLS ClientHello message that are useful for fingerprinting
+#[derive(Debug, Default)]
+pub struct TlsClientHello {
+    pub legacy_version: u16,
+    pub cipher_suites: Vec<u16>,
+    /// extension types in the order they appear
+    pub extensions: Vec<u16>,
+    pub server_name: Option<String>,
+    pub supported_groups: Vec<u16>,
+    pub ec_point_formats: Vec<u8>,
+    pub signature_algorithms: Vec<u16>,
+    pub alpn_protocols: Vec<Vec<u8>>,
+    pub supported_versions: Vec<u16>,
+}
+
+impl TlsClientHello {
+    /// Parse the ClientHello message from the leading TLS records sent by the client.
+    ///
+    /// The message may be fragmented into many records,
+    /// and `NeedMoreData` will be returned if it is not complete.
+    pub fn parse_records(data: &[u8]) -> Result<Self, TlsClientHelloParseError> {
+        let mut msg = Vec::new();
+        let mut offset = 0;
+        loop {
+            let left = &data[offset..];
+            if left.len() < RECORD_HEADER_LEN {
+                return Err(TlsClientHelloParseError::NeedMoreData);
+            }
+            if left[0] != CONTENT_TYPE_HANDSHAKE {
+                return Err(TlsClientHelloParseError::InvalidRecord);
+            }
+            let record_len = u16::from_be_bytes([left[3], left[4]]) as usize;
+            if record_len == 0 {
+                return Err(TlsClientHelloParseError::InvalidRecord);
+            }
+            let record_end = RECORD_HEADER_LEN + record_len;
+            if left.len() < record_end {
+                return Err(TlsClientHelloParseError::NeedMoreData);
+            }
+            msg.extend_from_slice(&left[RECORD_HEADER_LEN..record_end]);
+            offset += record_end;
+
+            if msg.len() < HANDSHAKE_HEADER_LEN {
+                continue;
+            }
+            if msg[0] != HANDSHAKE_TYPE_CLIENT_HELLO {
+                return Err(TlsClientHelloParseError::InvalidHandshakeType);
+            }
+            let msg_len = u32::from_be_bytes([0, msg[1], msg[2], msg[3]]) as usize;
+            let msg_end = HANDSHAKE_HEADER_LEN + msg_len;
+            if msg.len() >= msg_end {
+                return TlsClientHello::parse_message(&msg[HANDSHAKE_HEADER_LEN..msg_end]);
+            }
+        }
+    }
+
+    /// Parse the ClientHello message body, without the handshake header
+    pub fn parse_message(data: &[u8]) -> Result<Self, TlsClientHelloParseError> {
+        let mut r = Reader::new(data);
+        let mut hello = TlsClientHello {
+            legacy_version: r.u16()?,
+            ..Default::default()
+        };
+        let _random = r.bytes(32)?;
+        let _session_id = r.vec_u8()?;
+        hello.cipher_suites = Reader::u16_list(r.vec_u16()?)?;
+        let _compression_methods = r.vec_u8()?;
+        if r.is_empty() {
+            return Ok(hello);
+        }
+
+        let mut ext_r = Reader::new(r.vec_u16()?);
+        while !ext_r.is_empty() {
+            let ext_type = ext_r.u16()?;
+            let ext_data = ext_r.vec_u16()?;
+            hello.extensions.push(ext_type);
+            hello.parse_extension(ext_type, ext_data)?;
+        }
+        Ok(hello)
+    }
+
+    fn parse_extension(
+        &mut self,
+        ext_type: u16,
+        data: &[u8],
+    ) -> Result<(), TlsClientHelloParseError> {
+        let mut r = Reader::new(data);
+        match ext_type {
+            EXT_SERVER_NAME => {
+                if data.is_empty() {
+                    // only allowed in ServerHello, just ignore it
+                    return Ok(());
+                }
+                let mut list_r = Reader::new(r.vec_u16()?);
+                while !list_r.is_empty() {
+                    let name_type = list_r.u8()?;
+                    let name = list_r.vec_u16()?;
+                    if name_type == 0 && self.server_name.is_none() {
+                        let name = std::str::from_utf8(name)
+                            .map_err(|_| TlsClientHelloParseError::InvalidMessage)?;
+                        self.server_name = Some(name.to_string());
+                    }
+                }
+            }
+            EXT_SUPPORTED_GROUPS => self.supported_groups = Reader::u16_list(r.vec_u16()?)?,
+            EXT_EC_POINT_FORMATS => self.ec_point_formats = r.vec_u8()?.to_vec(),
+            EXT_SIGNATURE_ALGORITHMS => self.signature_algorithms = Reader::u16_list(r.vec_u16()?)?,
+            EXT_ALPN => {
+                let mut list_r = Reader::new(r.vec_u16()?);
+                while !list_r.is_empty() {
+                    self.alpn_protocols.push(list_r.vec_u8()?.to_vec());
+                }
+            }
+            EXT_SUPPORTED_VERSIONS => {
+                self.supported_versions = Reader::u16_list(r.vec_u8()?)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Get the max supported TLS version, ignoring GREASE values
+    pub fn max_version(&self) -> u16 {
+        self.supported_versions
+            .iter()
+            .copied()
+            .filter(|v| !super::is_grease(*v))
+            .max()
+            .unwrap_or(self.legacy_version)
+    }
+
+    /// Check if the Encrypted ClientHello extension is present
+    pub fn has_ech(&self) -> bool {
+        self.extensions.contains(&EXT_ENCRYPTED_CLIENT_HELLO)
+    }
+}
@@ -0,0 +1,211 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt::{Display, Write};
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use super::{is_grease, TlsClientHello};
+
+const EXT_SERVER_NAME: u16 = 0;
+const EXT_ALPN: u16 = 16;
+const JA4_EMPTY_HASH: &str = "000000000000";
+
+/// The JA3 and JA4 fingerprints of a TLS client
+///
+/// See <https://github.com/salesforce/ja3> and <https://github.com/FoxIO-LLC/ja4>
+#[derive(Clone, Debug)]
+pub struct TlsClientFingerprint {
+    ja3: String,
+    ja4: String,
+}
+
+impl TlsClientFingerprint {
+    pub fn new(hello: &TlsClientHello) -> Self {
+        let ja3_string = ja3_string(hello);
+        TlsClientFingerprint {
+            ja3: hex::encode(Md5::digest(ja3_string.as_bytes())),
+            ja4: ja4(hello),
+        }
+    }
+
+    /// the JA3 hash
+    #[inline]
+    pub fn ja3(&self) -> &str {
+        &self.ja3
+    }
+
+    #[inline]
+    pub fn ja4(&self) -> &str {
+        &self.ja4
+    }
+}
+
+fn push_joined<T, I>(s: &mut String, values: I, sep: char)
+where
+    T: Display,
+    I: IntoIterator<Item = T>,
+{
+    let mut first = true;
+    for v in values {
+        if first {
+            first = false;
+        } else {
+            s.push(sep);
+        }
+        let _ = write!(s, "{v}");
+    }
+}
+
+fn ja3_string(hello: &TlsClientHello) -> String {
+    let mut s = String::with_capacity(256);
+    let _ = write!(s, "{},", hello.legacy_version);
+    push_joined(
+        &mut s,
+        hello.cipher_suites.iter().filter(|v| !is_grease(**v)),
+        '-',
+    );
+    s.push(',');
+    push_joined(
+        &mut s,
+        hello.extensions.iter().filter(|v| !is_grease(**v)),
+        '-',
+    );
+    s.push(',');
+    push_joined(
+        &mut s,
+        hello.supported_groups.iter().filter(|v| !is_grease(**v)),
+        '-',
+    );
+    s.push(',');
+    push_joined(&mut s, hello.ec_point_formats.iter(), '-');
+    s
+}
+
+fn ja4_version(version: u16) -> &'static str {
+    match version {
+        0x0304 => "13",
+        0x0303 => "12",
+        0x0302 => "11",
+        0x0301 => "10",
+        0x0300 => "s3",
+        0x0200 => "s2",
+        0xfeff => "d1",
+        0xfefd => "d2",
+        0xfefc => "d3",
+        _ => "00",
+    }
+}
+
+fn ja4_alpn(s: &mut String, hello: &TlsClientHello) {
+    let Some(alpn) = hello.alpn_protocols.first().filter(|v| !v.is_empty()) else {
+        s.push_str("00");
+        return;
+    };
+    let first = alpn[0];
+    let last = alpn[alpn.len() - 1];
+    if first.is_ascii_alphanumeric() && last.is_ascii_alphanumeric() {
+        s.push(first as char);
+        s.push(last as char);
+    } else {
+        let first = format!("{first:02x}");
+        let last = format!("{last:02x}");
+        s.push_str(&first[..1]);
+        s.push_str(&last[1..]);
+    }
+}
+
+fn ja4_hash(s: &mut String, data: &str) {
+    let hash = hex::encode(Sha256::digest(data.as_bytes()));
+    s.push_str(&hash[..12]);
+}
+
+fn ja4(hello: &TlsClientHello) -> String {
+    let mut cipher_suites: Vec<u16> = hello
+        .cipher_suites
+        .iter()
+        .copied()
+        .filter(|v| !is_grease(*v))
+        .collect();
+    let extensions: Vec<u16> = hello
+        .extensions
+        .iter()
+        .copied()
+        .filter(|v| !is_grease(*v))
+        .collect();
+
+    let mut s = String::with_capacity(40);
+    s.push('t');
+    s.push_str(ja4_version(hello.max_version()));
+    s.push(if hello.server_name.is_some() {
+        'd'
+    } else {
+        'i'
+    });
+    let _ = write!(
+        s,
+        "{:02}{:02}",
+        cipher_suites.len().min(99),
+        extensions.len().min(99)
+    );
+    ja4_alpn(&mut s, hello);
+    s.push('_');
+
+    if cipher_suites.is_empty() {
+        s.push_str(JA4_EMPTY_HASH);
+    } else {
+        cipher_suites.sort_unstable();
+        let mut data = String::with_capacity(cipher_suites.len() * 5);
+        push_joined(
+            &mut data,
+            cipher_suites.iter().map(|v| format!("{v:04x}")),
+            ',',
+        );
+        ja4_hash(&mut s, &data);
+    }
+    s.push('_');
+
+    let mut sorted_extensions: Vec<u16> = extensions
+        .into_iter()
+        .filter(|v| *v != EXT_SERVER_NAME && *v != EXT_ALPN)
+        .collect();
+    if sorted_extensions.is_empty() {
+        s.push_str(JA4_EMPTY_HASH);
+    } else {
+        sorted_extensions.sort_unstable();
+        let mut data = String::with_capacity(sorted_extensions.len() * 5 + 64);
+        push_joined(
+            &mut data,
+            sorted_extensions.iter().map(|v| format!("{v:04x}")),
+            ',',
+        );
+        if !hello.signature_algorithms.is_empty() {
+            data.push('_');
+            push_joined(
+                &mut data,
+                hello
+                    .signature_algorithms
+                    .iter()
+                    .filter(|v| !is_grease(**v))
+                    .map(|v| format!("{v:04x}")),
+                ',',
+            );
+        }
+        ja4_hash(&mut s, &data);
+    }
+    s
+}
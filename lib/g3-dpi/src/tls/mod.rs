@@ -0,0 +1,27 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+mod client_hello;
+pub use client_hello::{TlsClientHello, TlsClientHelloParseError};
+
+mod fingerprint;
+pub use fingerprint::TlsClientFingerprint;
+
+#[inline]
+fn is_grease(v: u16) -> bool {
+    // see https://datatracker.ietf.org/doc/html/rfc8701
+    (v & 0x0f0f) == 0x0a0a && (v >> 8) == (v & 0xff)
+}
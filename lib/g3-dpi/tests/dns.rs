@@ -0,0 +1,70 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use g3_dpi::{check_dns_udp_request, Protocol, ProtocolInspectionConfig, ProtocolInspector};
+
+/// a standard query for example.com A with EDNS
+const QUERY: &[u8] = &[
+    0x12, 0x34, 0x01, 0x20, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x07, b'e', b'x', b'a',
+    b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x29,
+    0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn tcp_query() {
+    let mut inspector = ProtocolInspector::default();
+    let config = ProtocolInspectionConfig::default();
+
+    let mut data = Vec::with_capacity(QUERY.len() + 2);
+    data.extend_from_slice(&(QUERY.len() as u16).to_be_bytes());
+    data.extend_from_slice(QUERY);
+
+    let protocol = inspector
+        .check_client_initial_data(&config, 53, &data)
+        .unwrap();
+    assert_eq!(protocol, Protocol::Dns);
+}
+
+#[test]
+fn udp_query() {
+    assert!(check_dns_udp_request(QUERY));
+}
+
+#[test]
+fn udp_response() {
+    let mut data = QUERY.to_vec();
+    data[2] |= 0x80;
+    assert!(!check_dns_udp_request(&data));
+}
+
+#[test]
+fn udp_with_answer() {
+    let mut data = QUERY.to_vec();
+    data[7] = 0x01;
+    assert!(!check_dns_udp_request(&data));
+}
+
+#[test]
+fn udp_no_question() {
+    let mut data = QUERY.to_vec();
+    data[5] = 0x00;
+    assert!(!check_dns_udp_request(&data));
+}
+
+#[test]
+fn udp_too_short() {
+    assert!(!check_dns_udp_request(&QUERY[..11]));
+}
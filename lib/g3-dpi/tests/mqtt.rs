@@ -0,0 +1,43 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use g3_dpi::{Protocol, ProtocolInspectionConfig, ProtocolInspector};
+
+#[test]
+fn port1883_v311_connect() {
+    let mut inspector = ProtocolInspector::default();
+    let config = ProtocolInspectionConfig::default();
+
+    const DATA: &[u8] = b"\x10\x10\x00\x04MQTT\x04\x02\x00\x3c\x00\x04test";
+
+    let protocol = inspector
+        .check_client_initial_data(&config, 1883, DATA)
+        .unwrap();
+    assert_eq!(protocol, Protocol::Mqtt);
+}
+
+#[test]
+fn guess_v31_connect() {
+    let mut inspector = ProtocolInspector::default();
+    let config = ProtocolInspectionConfig::default();
+
+    const DATA: &[u8] = b"\x10\x12\x00\x06MQIsdp\x03\x02\x00\x3c\x00\x04test";
+
+    let protocol = inspector
+        .check_client_initial_data(&config, 11883, DATA)
+        .unwrap();
+    assert_eq!(protocol, Protocol::Mqtt);
+}
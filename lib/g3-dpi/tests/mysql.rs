@@ -0,0 +1,37 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use g3_dpi::{Protocol, ProtocolInspectionConfig, ProtocolInspector};
+
+#[test]
+fn port3306_handshake_v10() {
+    let mut inspector = ProtocolInspector::default();
+    let config = ProtocolInspectionConfig::default();
+
+    const DATA: &[u8] = b"\x4a\x00\x00\x00\
+        \x0a8.0.36\x00\
+        \x0b\x00\x00\x00\
+        \x15\x3c\x4e\x24\x5f\x01\x6e\x0f\x00\
+        \xff\xff\xff\x02\x00\xff\xdf\x15\
+        \x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        \x1d\x5b\x3a\x6d\x6a\x0a\x27\x45\x4f\x5e\x2f\x1e\x00\
+        caching_sha2_password\x00";
+
+    let protocol = inspector
+        .check_server_initial_data(&config, 3306, DATA)
+        .unwrap();
+    assert_eq!(protocol, Protocol::Mysql);
+}
@@ -0,0 +1,43 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use g3_dpi::{Protocol, ProtocolInspectionConfig, ProtocolInspector};
+
+#[test]
+fn port5432_ssl_request() {
+    let mut inspector = ProtocolInspector::default();
+    let config = ProtocolInspectionConfig::default();
+
+    const DATA: &[u8] = b"\x00\x00\x00\x08\x04\xd2\x16\x2f";
+
+    let protocol = inspector
+        .check_client_initial_data(&config, 5432, DATA)
+        .unwrap();
+    assert_eq!(protocol, Protocol::Postgres);
+}
+
+#[test]
+fn guess_startup_message() {
+    let mut inspector = ProtocolInspector::default();
+    let config = ProtocolInspectionConfig::default();
+
+    const DATA: &[u8] = b"\x00\x00\x00\x17\x00\x03\x00\x00user\x00postgres\x00\x00";
+
+    let protocol = inspector
+        .check_client_initial_data(&config, 15432, DATA)
+        .unwrap();
+    assert_eq!(protocol, Protocol::Postgres);
+}
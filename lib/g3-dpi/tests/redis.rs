@@ -0,0 +1,43 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use g3_dpi::{Protocol, ProtocolInspectionConfig, ProtocolInspector};
+
+#[test]
+fn port6379_ping() {
+    let mut inspector = ProtocolInspector::default();
+    let config = ProtocolInspectionConfig::default();
+
+    const DATA: &[u8] = b"*1\r\n$4\r\nPING\r\n";
+
+    let protocol = inspector
+        .check_client_initial_data(&config, 6379, DATA)
+        .unwrap();
+    assert_eq!(protocol, Protocol::Redis);
+}
+
+#[test]
+fn guess_get() {
+    let mut inspector = ProtocolInspector::default();
+    let config = ProtocolInspectionConfig::default();
+
+    const DATA: &[u8] = b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n";
+
+    let protocol = inspector
+        .check_client_initial_data(&config, 16379, DATA)
+        .unwrap();
+    assert_eq!(protocol, Protocol::Redis);
+}
@@ -0,0 +1,230 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use g3_dpi::{TlsClientFingerprint, TlsClientHello, TlsClientHelloParseError};
+
+/// the ClientHello message in RFC 8448 Section 3
+const RFC8448_CLIENT_HELLO: &str = "\
+010000c00303cb34ecb1e78163ba1c38c6dacb196a6dffa21a8d9912ec18a2ef\
+6283024dece7000006130113031302010000910000000b000900000673657276\
+6572ff01000100000a00140012001d0017001800190100010101020103010400\
+230000003300260024001d002099381de560e4bd43d23d8e435a7dbafeb3c06e\
+51c13cae4d5413691e529aaf2c002b0003020304000d0020001e040305030603\
+020308040805080604010501060102010402050206020202002d00020101001c\
+00024001";
+
+/// the ClientHello message in RFC 9001 Appendix A.2
+const RFC9001_CLIENT_HELLO: &str = "\
+010000ed0303ebf8fa56f12939b9584a3896472ec40bb863cfd3e86804fe3a47\
+f06a2b69484c00000413011302010000c000000010000e00000b6578616d706c\
+652e636f6dff01000100000a00080006001d0017001800100007000504616c70\
+6e000500050100000000003300260024001d00209370b2c9caa47fbabaf4559f\
+edba753de171fa71f50f1ce15d43e994ec74d748002b0003020304000d001000\
+0e0403050306030203080408050806002d00020101001c000240010039003204\
+08ffffffffffffffff05048000ffff07048000ffff0801100104800075300901\
+100f088394c8f03e51570806048000ffff";
+
+fn to_records(msg: &[u8], fragment_size: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(msg.len() + 64);
+    for chunk in msg.chunks(fragment_size) {
+        data.extend_from_slice(&[0x16, 0x03, 0x01]);
+        data.extend_from_slice(&(chunk.len() as u16).to_be_bytes());
+        data.extend_from_slice(chunk);
+    }
+    data
+}
+
+#[test]
+fn parse_rfc8448() {
+    let msg = hex::decode(RFC8448_CLIENT_HELLO).unwrap();
+    let data = to_records(&msg, msg.len());
+    let hello = TlsClientHello::parse_records(&data).unwrap();
+
+    assert_eq!(hello.legacy_version, 0x0303);
+    assert_eq!(hello.cipher_suites, [0x1301, 0x1303, 0x1302]);
+    assert_eq!(
+        hello.extensions,
+        [0x0000, 0xff01, 0x000a, 0x0023, 0x0033, 0x002b, 0x000d, 0x002d, 0x001c]
+    );
+    assert_eq!(hello.server_name.as_deref(), Some("server"));
+    assert_eq!(
+        hello.supported_groups,
+        [0x001d, 0x0017, 0x0018, 0x0019, 0x0100, 0x0101, 0x0102, 0x0103, 0x0104]
+    );
+    assert!(hello.ec_point_formats.is_empty());
+    assert_eq!(
+        hello.signature_algorithms,
+        [
+            0x0403, 0x0503, 0x0603, 0x0203, 0x0804, 0x0805, 0x0806, 0x0401, 0x0501, 0x0601, 0x0201,
+            0x0402, 0x0502, 0x0602, 0x0202
+        ]
+    );
+    assert!(hello.alpn_protocols.is_empty());
+    assert_eq!(hello.supported_versions, [0x0304]);
+    assert_eq!(hello.max_version(), 0x0304);
+    assert!(!hello.has_ech());
+}
+
+#[test]
+fn parse_fragmented() {
+    let msg = hex::decode(RFC8448_CLIENT_HELLO).unwrap();
+    let data = to_records(&msg, 3);
+    let hello = TlsClientHello::parse_records(&data).unwrap();
+    assert_eq!(hello.server_name.as_deref(), Some("server"));
+    assert_eq!(hello.cipher_suites, [0x1301, 0x1303, 0x1302]);
+
+    for len in [0, 4, data.len() / 2, data.len() - 1] {
+        assert!(matches!(
+            TlsClientHello::parse_records(&data[..len]),
+            Err(TlsClientHelloParseError::NeedMoreData)
+        ));
+    }
+}
+
+#[test]
+fn parse_invalid() {
+    let msg = hex::decode(RFC8448_CLIENT_HELLO).unwrap();
+
+    let mut data = to_records(&msg, msg.len());
+    data[0] = 0x17;
+    assert!(matches!(
+        TlsClientHello::parse_records(&data),
+        Err(TlsClientHelloParseError::InvalidRecord)
+    ));
+
+    let mut data = to_records(&msg, msg.len());
+    data[5] = 0x02; // ServerHello
+    assert!(matches!(
+        TlsClientHello::parse_records(&data),
+        Err(TlsClientHelloParseError::InvalidHandshakeType)
+    ));
+
+    // truncate the extensions but keep the message length
+    let body = &msg[4..msg.len() - 1];
+    assert!(matches!(
+        TlsClientHello::parse_message(body),
+        Err(TlsClientHelloParseError::InvalidMessage)
+    ));
+}
+
+#[test]
+fn fingerprint_rfc8448() {
+    let msg = hex::decode(RFC8448_CLIENT_HELLO).unwrap();
+    let hello = TlsClientHello::parse_message(&msg[4..]).unwrap();
+    let fingerprint = TlsClientFingerprint::new(&hello);
+    assert_eq!(fingerprint.ja3(), "da4dea34fe6d4ce5f0725df3f2682fa0");
+    assert_eq!(fingerprint.ja4(), "t13d030900_55b375c5d22e_59cd3dafc54d");
+}
+
+#[test]
+fn fingerprint_rfc9001() {
+    let msg = hex::decode(RFC9001_CLIENT_HELLO).unwrap();
+    let hello = TlsClientHello::parse_message(&msg[4..]).unwrap();
+    assert_eq!(hello.alpn_protocols, [b"alpn".to_vec()]);
+    let fingerprint = TlsClientFingerprint::new(&hello);
+    assert_eq!(fingerprint.ja3(), "41bc9ae914d6cb3bd0bd0a5453ab7d7f");
+    assert_eq!(fingerprint.ja4(), "t13d0211an_62ed6f6ca7ad_4d634acda6c0");
+}
+
+#[test]
+fn ja3_reference() {
+    // the examples in https://github.com/salesforce/ja3, with GREASE values added
+    let hello = TlsClientHello {
+        legacy_version: 769,
+        cipher_suites: vec![
+            0x0a0a, 47, 53, 5, 10, 49161, 49162, 49171, 49172, 50, 56, 19, 4,
+        ],
+        extensions: vec![0x1a1a, 0, 10, 11, 0xfafa],
+        server_name: Some("example.com".to_string()),
+        supported_groups: vec![0x2a2a, 23, 24, 25],
+        ec_point_formats: vec![0],
+        ..Default::default()
+    };
+    let fingerprint = TlsClientFingerprint::new(&hello);
+    assert_eq!(fingerprint.ja3(), "ada70206e40642a3e4461f35503241d5");
+
+    let hello = TlsClientHello {
+        legacy_version: 769,
+        cipher_suites: vec![4, 5, 10, 9, 100, 98, 3, 6, 19, 18, 99],
+        ..Default::default()
+    };
+    let fingerprint = TlsClientFingerprint::new(&hello);
+    assert_eq!(fingerprint.ja3(), "de350869b8c85de67a350c8d186f11e6");
+}
+
+fn chrome_client_hello() -> TlsClientHello {
+    TlsClientHello {
+        legacy_version: 0x0303,
+        cipher_suites: vec![
+            0x3a3a, 0x1301, 0x1302, 0x1303, 0xc02b, 0xc02f, 0xc02c, 0xc030, 0xcca9, 0xcca8, 0xc013,
+            0xc014, 0x009c, 0x009d, 0x002f, 0x0035,
+        ],
+        extensions: vec![
+            0x8a8a, 0x0000, 0x0017, 0xff01, 0x000a, 0x000b, 0x0023, 0x0010, 0x0005, 0x000d, 0x0012,
+            0x0033, 0x002d, 0x002b, 0x001b, 0x4469, 0xfe0d, 0x9a9a,
+        ],
+        server_name: Some("www.example.com".to_string()),
+        supported_groups: vec![0xbaba, 0x001d, 0x0017, 0x0018],
+        ec_point_formats: vec![0],
+        signature_algorithms: vec![
+            0x0403, 0x0804, 0x0401, 0x0503, 0x0805, 0x0501, 0x0806, 0x0601,
+        ],
+        alpn_protocols: vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+        supported_versions: vec![0x4a4a, 0x0304, 0x0303],
+    }
+}
+
+#[test]
+fn ja4_reference() {
+    // the example in https://github.com/FoxIO-LLC/ja4/blob/main/technical_details/JA4.md
+    let hello = chrome_client_hello();
+    assert_eq!(hello.max_version(), 0x0304);
+    assert!(hello.has_ech());
+    let fingerprint = TlsClientFingerprint::new(&hello);
+    assert_eq!(fingerprint.ja4(), "t13d1516h2_8daaf6152771_02713d6af862");
+}
+
+#[test]
+fn ja4_without_sni_alpn() {
+    // SNI and ALPN are counted, but not included in the extension hash
+    let mut hello = chrome_client_hello();
+    hello.extensions.retain(|v| *v != 0x0000 && *v != 0x0010);
+    hello.server_name = None;
+    hello.alpn_protocols.clear();
+    let fingerprint = TlsClientFingerprint::new(&hello);
+    assert_eq!(fingerprint.ja4(), "t13i151400_8daaf6152771_02713d6af862");
+}
+
+#[test]
+fn ja4_alpn() {
+    let mut hello = chrome_client_hello();
+    let mut check = |alpn: &[u8], expected: &str| {
+        hello.alpn_protocols = vec![alpn.to_vec()];
+        let fingerprint = TlsClientFingerprint::new(&hello);
+        assert_eq!(&fingerprint.ja4()[8..10], expected, "alpn {alpn:?}");
+    };
+
+    check(b"http/1.1", "h1");
+    check(b"h3", "h3");
+    check(b"\x30\x31", "01");
+    // use the hex chars if the first or last char is non-alphanumeric
+    check(b"\xab", "ab");
+    check(b"\xab\xcd", "ad");
+    check(b"\x30\xab", "3b");
+    check(b"\x30\x31\xab\xcd", "3d");
+    check(b"\x30\xab\x31", "01");
+    check(b"", "00");
+}
@@ -0,0 +1,172 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+const DEFAULT_FIELDS: &[&str] = &[
+    "date",
+    "time",
+    "task_type",
+    "task_id",
+    "stage",
+    "user",
+    "client_addr",
+    "server_addr",
+    "upstream",
+    "escaper",
+    "next_peer_addr",
+    "reason",
+    "total_time",
+    "c_rd_bytes",
+    "c_wr_bytes",
+    "r_rd_bytes",
+    "r_wr_bytes",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum W3cFieldValue {
+    Date,
+    Time,
+    Message,
+    Key(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct W3cField {
+    pub(crate) name: String,
+    pub(crate) value: W3cFieldValue,
+}
+
+impl W3cField {
+    /// create a field which use the same name as the log key
+    pub fn new(name: &str) -> Self {
+        W3cField::with_key(name, name)
+    }
+
+    /// create a field with custom name, the value will be taken from the log key
+    pub fn with_key(name: &str, key: &str) -> Self {
+        let value = match key {
+            "date" => W3cFieldValue::Date,
+            "time" => W3cFieldValue::Time,
+            "msg" | "message" => W3cFieldValue::Message,
+            _ => W3cFieldValue::Key(key.to_string()),
+        };
+        W3cField {
+            name: name.to_string(),
+            value,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FileLogFormat {
+    #[default]
+    Json,
+    W3c,
+}
+
+impl FromStr for FileLogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(FileLogFormat::Json),
+            "w3c" | "elf" => Ok(FileLogFormat::W3c),
+            _ => Err(anyhow!("unsupported file log format {s}")),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FileLogConfig {
+    pub(crate) path: PathBuf,
+    pub(crate) format: FileLogFormat,
+    pub(crate) rotate_interval: Option<Duration>,
+    pub(crate) rotate_size: Option<u64>,
+    pub(crate) rotate_keep: Option<usize>,
+    pub(crate) rotate_max_age: Option<Duration>,
+    pub(crate) compress: bool,
+    pub(crate) fields: Vec<W3cField>,
+}
+
+impl FileLogConfig {
+    pub fn new(path: &Path) -> Self {
+        FileLogConfig {
+            path: path.to_path_buf(),
+            format: FileLogFormat::Json,
+            rotate_interval: None,
+            rotate_size: None,
+            rotate_keep: None,
+            rotate_max_age: None,
+            compress: false,
+            fields: DEFAULT_FIELDS.iter().copied().map(W3cField::new).collect(),
+        }
+    }
+
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.path.file_name().is_none() {
+            return Err(anyhow!("invalid log file path {}", self.path.display()));
+        }
+        if self.format == FileLogFormat::W3c {
+            if self.fields.is_empty() {
+                return Err(anyhow!("no w3c field set"));
+            }
+            for field in &self.fields {
+                if field.name.is_empty() || field.name.contains(char::is_whitespace) {
+                    return Err(anyhow!("invalid w3c field name '{}'", field.name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn has_retention(&self) -> bool {
+        self.rotate_keep.is_some() || self.rotate_max_age.is_some()
+    }
+
+    pub fn set_format(&mut self, format: FileLogFormat) {
+        self.format = format;
+    }
+
+    pub fn set_rotate_interval(&mut self, interval: Duration) {
+        self.rotate_interval = Some(interval);
+    }
+
+    pub fn set_rotate_size(&mut self, size: u64) {
+        self.rotate_size = Some(size);
+    }
+
+    pub fn set_rotate_keep(&mut self, count: usize) {
+        self.rotate_keep = Some(count);
+    }
+
+    pub fn set_rotate_max_age(&mut self, age: Duration) {
+        self.rotate_max_age = Some(age);
+    }
+
+    pub fn set_compress(&mut self, enable: bool) {
+        self.compress = enable;
+    }
+
+    pub fn set_fields(&mut self, fields: Vec<W3cField>) {
+        self.fields = fields;
+    }
+}
@@ -0,0 +1,62 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use slog::{OwnedKVList, Record};
+
+use g3_types::log::AsyncLogFormatter;
+
+use super::json::JsonFormatter;
+use super::w3c::W3cFormatter;
+use super::{FileLogContext, FileLogFormat, FileLogRecord};
+
+enum FormatterKind {
+    Json(JsonFormatter),
+    W3c(W3cFormatter),
+}
+
+pub struct FileLogFormatter {
+    context: Arc<FileLogContext>,
+    kind: FormatterKind,
+}
+
+impl FileLogFormatter {
+    pub(crate) fn new(context: Arc<FileLogContext>) -> Self {
+        let kind = match context.config.format {
+            FileLogFormat::Json => FormatterKind::Json(JsonFormatter::new()),
+            FileLogFormat::W3c => FormatterKind::W3c(W3cFormatter::new(&context.config.fields)),
+        };
+        FileLogFormatter { context, kind }
+    }
+}
+
+impl AsyncLogFormatter<FileLogRecord> for FileLogFormatter {
+    fn format_slog(
+        &self,
+        record: &Record,
+        logger_values: &OwnedKVList,
+    ) -> Result<FileLogRecord, slog::Error> {
+        let data = match &self.kind {
+            FormatterKind::Json(f) => f.format_slog(record, logger_values)?,
+            FormatterKind::W3c(f) => f.format_slog(record, logger_values)?,
+        };
+        Ok(FileLogRecord {
+            context: Arc::clone(&self.context),
+            data,
+        })
+    }
+}
@@ -0,0 +1,277 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use flume::{Receiver, Sender, WeakSender};
+use log::warn;
+
+use g3_types::log::{AsyncLogConfig, AsyncLogger, LogStats};
+
+mod config;
+pub use config::{FileLogConfig, FileLogFormat, W3cField};
+
+mod json;
+mod w3c;
+
+mod format;
+pub use format::FileLogFormatter;
+
+mod rotate;
+use rotate::RotatedFile;
+
+/// all loggers that write to the same file will share the same io thread
+static FILE_WRITERS: Mutex<Vec<(PathBuf, WeakSender<FileLogRecord>)>> = Mutex::new(Vec::new());
+
+struct FileLogContext {
+    config: Arc<FileLogConfig>,
+    stats: Arc<LogStats>,
+}
+
+pub struct FileLogRecord {
+    context: Arc<FileLogContext>,
+    data: Vec<u8>,
+}
+
+pub fn new_async_logger(
+    async_conf: &AsyncLogConfig,
+    config: &Arc<FileLogConfig>,
+    software: &str,
+) -> AsyncLogger<FileLogRecord, FileLogFormatter> {
+    let sender = get_file_writer(async_conf, &config.path, software);
+
+    let stats = Arc::new(LogStats::default());
+    let context = FileLogContext {
+        config: Arc::clone(config),
+        stats: Arc::clone(&stats),
+    };
+
+    AsyncLogger::new(sender, FileLogFormatter::new(Arc::new(context)), stats)
+}
+
+fn get_file_writer(
+    async_conf: &AsyncLogConfig,
+    path: &Path,
+    software: &str,
+) -> Sender<FileLogRecord> {
+    let mut writers = FILE_WRITERS.lock().unwrap();
+    writers.retain(|(_, s)| s.upgrade().is_some());
+    if let Some(sender) = writers
+        .iter()
+        .find(|(p, _)| p == path)
+        .and_then(|(_, s)| s.upgrade())
+    {
+        return sender;
+    }
+
+    let (sender, receiver) = flume::bounded::<FileLogRecord>(async_conf.channel_capacity);
+
+    let io_thread = AsyncIoThread {
+        path: path.to_path_buf(),
+        thread_name: async_conf.thread_name.clone(),
+        software: software.to_string(),
+        receiver,
+        file: None,
+        open_failed_at: None,
+        rotate_worker: None,
+    };
+
+    let _detached_thread = std::thread::Builder::new()
+        .name(async_conf.thread_name.clone())
+        .spawn(move || {
+            io_thread.run_to_end();
+        });
+
+    writers.push((path.to_path_buf(), sender.downgrade()));
+    sender
+}
+
+struct LogFile {
+    writer: BufWriter<File>,
+    open_time: Instant,
+    size: u64,
+    header_config: Arc<FileLogConfig>,
+}
+
+impl LogFile {
+    fn write_header(&mut self, config: &Arc<FileLogConfig>, software: &str) -> io::Result<()> {
+        let mut header = Vec::with_capacity(256);
+        w3c::format_header(&mut header, &config.fields, software, &Utc::now());
+        self.writer.write_all(&header)?;
+        self.size += header.len() as u64;
+        self.header_config = Arc::clone(config);
+        Ok(())
+    }
+}
+
+struct AsyncIoThread {
+    path: PathBuf,
+    thread_name: String,
+    software: String,
+    receiver: Receiver<FileLogRecord>,
+    file: Option<LogFile>,
+    open_failed_at: Option<Instant>,
+    rotate_worker: Option<mpsc::Sender<RotatedFile>>,
+}
+
+impl AsyncIoThread {
+    fn run_to_end(mut self) {
+        while let Ok(record) = self.receiver.recv() {
+            self.write_record(record);
+            while let Ok(record) = self.receiver.try_recv() {
+                self.write_record(record);
+            }
+            if let Some(file) = &mut self.file {
+                if let Err(e) = file.writer.flush() {
+                    warn!("failed to flush log file {}: {e}", self.path.display());
+                    self.file = None;
+                }
+            }
+        }
+    }
+
+    fn write_record(&mut self, record: FileLogRecord) {
+        let config = &record.context.config;
+        let stats = &record.context.stats;
+
+        if self.need_rotate(config) {
+            if let Err(e) = self.rotate(config) {
+                warn!("failed to rotate log file {}: {e}", self.path.display());
+            }
+        }
+        if self.file.is_none() {
+            if let Some(failed_at) = self.open_failed_at {
+                // hard coded 4s for a minimal reopen interval
+                if failed_at.elapsed() < Duration::from_secs(4) {
+                    stats.drop.add_peer_unreachable();
+                    return;
+                }
+            }
+            match self.open_file(config) {
+                Ok(file) => {
+                    self.file = Some(file);
+                    self.open_failed_at = None;
+                }
+                Err(e) => {
+                    warn!("failed to open log file {}: {e}", self.path.display());
+                    stats.drop.add_peer_unreachable();
+                    self.open_failed_at = Some(Instant::now());
+                    return;
+                }
+            }
+        }
+
+        let Some(file) = &mut self.file else {
+            return;
+        };
+        let r = if config.format == FileLogFormat::W3c
+            && !Arc::ptr_eq(&file.header_config, config)
+            && (file.header_config.format != FileLogFormat::W3c
+                || file.header_config.fields != config.fields)
+        {
+            // the field list changed, emit the directives again
+            file.write_header(config, &self.software)
+        } else {
+            Ok(())
+        };
+        match r.and_then(|_| file.writer.write_all(&record.data)) {
+            Ok(_) => {
+                file.size += record.data.len() as u64;
+                stats.io.add_passed();
+                stats.io.add_size(record.data.len());
+            }
+            Err(e) => {
+                warn!("failed to write log file {}: {e}", self.path.display());
+                stats.drop.add_peer_unreachable();
+                self.file = None;
+            }
+        }
+    }
+
+    fn need_rotate(&self, config: &FileLogConfig) -> bool {
+        let Some(file) = &self.file else {
+            return false;
+        };
+        if let Some(interval) = config.rotate_interval {
+            if file.open_time.elapsed() >= interval {
+                return true;
+            }
+        }
+        if let Some(size) = config.rotate_size {
+            if file.size >= size {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn rotate(&mut self, config: &Arc<FileLogConfig>) -> io::Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.writer.flush()?;
+        }
+
+        let mut base = self.path.clone().into_os_string();
+        base.push(Utc::now().format(".%Y%m%d-%H%M%S").to_string());
+        let mut rotated = PathBuf::from(&base);
+        let mut i = 0;
+        while rotated.exists() {
+            i += 1;
+            let mut name = base.clone();
+            name.push(format!(".{i}"));
+            rotated = PathBuf::from(name);
+        }
+        std::fs::rename(&self.path, &rotated)?;
+
+        if config.compress || config.has_retention() {
+            if self.rotate_worker.is_none() {
+                let worker =
+                    rotate::spawn_worker(&self.path, format!("{}-rotate", self.thread_name))?;
+                self.rotate_worker = Some(worker);
+            }
+            if let Some(worker) = &self.rotate_worker {
+                let _ = worker.send(RotatedFile {
+                    path: rotated,
+                    config: Arc::clone(config),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn open_file(&self, config: &Arc<FileLogConfig>) -> io::Result<LogFile> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let size = file.metadata()?.len();
+
+        let mut log_file = LogFile {
+            writer: BufWriter::new(file),
+            open_time: Instant::now(),
+            size,
+            header_config: Arc::clone(config),
+        };
+        if config.format == FileLogFormat::W3c {
+            log_file.write_header(config, &self.software)?;
+        }
+        Ok(log_file)
+    }
+}
@@ -0,0 +1,268 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::warn;
+
+use super::FileLogConfig;
+
+pub(crate) struct RotatedFile {
+    pub(crate) path: PathBuf,
+    pub(crate) config: Arc<FileLogConfig>,
+}
+
+/// compress and clean up rotated files in a separate thread, so the logging won't be blocked
+pub(crate) fn spawn_worker(
+    log_path: &Path,
+    thread_name: String,
+) -> io::Result<mpsc::Sender<RotatedFile>> {
+    let (sender, receiver) = mpsc::channel::<RotatedFile>();
+    let log_path = log_path.to_path_buf();
+    std::thread::Builder::new()
+        .name(thread_name)
+        .spawn(move || {
+            while let Ok(rotated) = receiver.recv() {
+                if rotated.config.compress {
+                    if let Err(e) = compress(&rotated.path) {
+                        warn!(
+                            "failed to compress log file {}: {e}",
+                            rotated.path.display()
+                        );
+                    }
+                }
+                if rotated.config.has_retention() {
+                    if let Err(e) = clean_up(&log_path, &rotated.config) {
+                        warn!(
+                            "failed to clean up rotated files for {}: {e}",
+                            log_path.display()
+                        );
+                    }
+                }
+            }
+        })?;
+    Ok(sender)
+}
+
+fn compress(path: &Path) -> io::Result<()> {
+    let mut gz_path = path.as_os_str().to_os_string();
+    gz_path.push(".gz");
+
+    let mut src = File::open(path)?;
+    let dst = File::create(&gz_path)?;
+    let mut encoder = GzEncoder::new(BufWriter::new(dst), Compression::default());
+    io::copy(&mut src, &mut encoder)?;
+    encoder.finish()?.flush()?;
+    fs::remove_file(path)
+}
+
+fn clean_up(log_path: &Path, config: &FileLogConfig) -> io::Result<()> {
+    let Some(file_name) = log_path.file_name() else {
+        return Ok(());
+    };
+    let dir = match log_path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let prefix = format!("{}.", file_name.to_string_lossy());
+
+    let mut rotated_files: Vec<(SystemTime, PathBuf)> = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let Some(suffix) = name.strip_prefix(prefix.as_str()) else {
+            continue;
+        };
+        // all rotated files have a timestamp suffix
+        if !suffix.starts_with(|c: char| c.is_ascii_digit()) {
+            continue;
+        }
+        let modified = entry.metadata()?.modified()?;
+        rotated_files.push((modified, entry.path()));
+    }
+    // newest first
+    rotated_files.sort_by(|a, b| b.0.cmp(&a.0));
+
+    for (i, (modified, path)) in rotated_files.into_iter().enumerate() {
+        let mut expired = false;
+        if let Some(keep) = config.rotate_keep {
+            expired = i >= keep;
+        }
+        if let Some(max_age) = config.rotate_max_age {
+            if let Ok(age) = modified.elapsed() {
+                expired |= age > max_age;
+            }
+        }
+        if expired {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    struct TestDir {
+        path: PathBuf,
+    }
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("g3-filelog-rotate-{}-{name}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TestDir { path }
+        }
+
+        fn create(&self, name: &str, age: Duration) {
+            let file = File::create(self.path.join(name)).unwrap();
+            file.set_modified(SystemTime::now() - age).unwrap();
+        }
+
+        fn files(&self) -> Vec<String> {
+            let mut files: Vec<String> = fs::read_dir(&self.path)
+                .unwrap()
+                .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+                .collect();
+            files.sort();
+            files
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    fn create_siblings(dir: &TestDir) {
+        dir.create("foo.log", Duration::ZERO);
+        dir.create("foo.log.bak", 100 * HOUR);
+        dir.create("foo.logger.1", 100 * HOUR);
+        dir.create("bar.log.20240101-000000", 100 * HOUR);
+    }
+
+    #[test]
+    fn keep_newest() {
+        let dir = TestDir::new("keep");
+        create_siblings(&dir);
+        dir.create("foo.log.20240101-000000", 5 * HOUR);
+        dir.create("foo.log.20240101-010000", 4 * HOUR);
+        dir.create("foo.log.20240101-020000", 3 * HOUR);
+        dir.create("foo.log.20240101-020000.1", 2 * HOUR);
+        dir.create("foo.log.20240101-030000", HOUR);
+
+        let log_path = dir.path.join("foo.log");
+        let mut config = FileLogConfig::new(&log_path);
+        config.set_rotate_keep(2);
+        clean_up(&log_path, &config).unwrap();
+
+        assert_eq!(
+            dir.files(),
+            [
+                "bar.log.20240101-000000",
+                "foo.log",
+                "foo.log.20240101-020000.1",
+                "foo.log.20240101-030000",
+                "foo.log.bak",
+                "foo.logger.1",
+            ]
+        );
+
+        config.set_rotate_keep(0);
+        clean_up(&log_path, &config).unwrap();
+        assert_eq!(
+            dir.files(),
+            [
+                "bar.log.20240101-000000",
+                "foo.log",
+                "foo.log.bak",
+                "foo.logger.1"
+            ]
+        );
+    }
+
+    #[test]
+    fn max_age() {
+        let dir = TestDir::new("age");
+        create_siblings(&dir);
+        dir.create("foo.log.20240101-000000", 72 * HOUR);
+        dir.create("foo.log.20240102-000000", 48 * HOUR);
+        dir.create("foo.log.20240103-000000", HOUR);
+
+        let log_path = dir.path.join("foo.log");
+        let mut config = FileLogConfig::new(&log_path);
+        config.set_rotate_max_age(24 * HOUR);
+        clean_up(&log_path, &config).unwrap();
+
+        assert_eq!(
+            dir.files(),
+            [
+                "bar.log.20240101-000000",
+                "foo.log",
+                "foo.log.20240103-000000",
+                "foo.log.bak",
+                "foo.logger.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn compressed() {
+        let dir = TestDir::new("gz");
+        create_siblings(&dir);
+        dir.create("foo.log.20240101-000000.gz", 3 * HOUR);
+        dir.create("foo.log.20240101-010000.1.gz", 2 * HOUR);
+        dir.create("foo.log.20240101-020000", HOUR);
+
+        let rotated = dir.path.join("foo.log.20240101-020000");
+        fs::write(&rotated, b"test log").unwrap();
+        compress(&rotated).unwrap();
+        assert!(!rotated.exists());
+        assert!(dir.path.join("foo.log.20240101-020000.gz").exists());
+
+        let log_path = dir.path.join("foo.log");
+        let mut config = FileLogConfig::new(&log_path);
+        config.set_rotate_keep(2);
+        clean_up(&log_path, &config).unwrap();
+
+        assert_eq!(
+            dir.files(),
+            [
+                "bar.log.20240101-000000",
+                "foo.log",
+                "foo.log.20240101-010000.1.gz",
+                "foo.log.20240101-020000.gz",
+                "foo.log.bak",
+                "foo.logger.1",
+            ]
+        );
+    }
+}
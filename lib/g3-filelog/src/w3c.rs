@@ -0,0 +1,160 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::fmt::Arguments;
+use std::io::Write;
+
+use chrono::{DateTime, Utc};
+use slog::{OwnedKVList, Record, Serializer, KV};
+
+use crate::config::{W3cField, W3cFieldValue};
+
+pub(crate) struct W3cFormatter {
+    fields: Vec<W3cField>,
+    key_index: HashMap<String, Vec<usize>>,
+}
+
+impl W3cFormatter {
+    pub(crate) fn new(fields: &[W3cField]) -> Self {
+        let mut key_index: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, field) in fields.iter().enumerate() {
+            if let W3cFieldValue::Key(key) = &field.value {
+                key_index.entry(key.to_string()).or_default().push(i);
+            }
+        }
+        W3cFormatter {
+            fields: fields.to_vec(),
+            key_index,
+        }
+    }
+
+    pub(crate) fn format_slog(
+        &self,
+        record: &Record,
+        logger_values: &OwnedKVList,
+    ) -> Result<Vec<u8>, slog::Error> {
+        let mut values: Vec<Option<String>> = vec![None; self.fields.len()];
+        let mut collector = FieldCollector {
+            key_index: &self.key_index,
+            values: &mut values,
+        };
+        logger_values.serialize(record, &mut collector)?;
+        record.kv().serialize(record, &mut collector)?;
+
+        let datetime_now = Utc::now();
+        let mut buf = Vec::<u8>::with_capacity(256);
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                buf.push(b' ');
+            }
+            match &field.value {
+                W3cFieldValue::Date => write!(buf, "{}", datetime_now.format("%Y-%m-%d"))?,
+                W3cFieldValue::Time => write!(buf, "{}", datetime_now.format("%H:%M:%S"))?,
+                W3cFieldValue::Message => push_value(&mut buf, &record.msg().to_string()),
+                W3cFieldValue::Key(_) => match &values[i] {
+                    Some(v) => push_value(&mut buf, v),
+                    None => buf.push(b'-'),
+                },
+            }
+        }
+        buf.push(b'\n');
+        Ok(buf)
+    }
+}
+
+/// the header directives that should be present at the start of each log file
+pub(crate) fn format_header(
+    buf: &mut Vec<u8>,
+    fields: &[W3cField],
+    software: &str,
+    datetime: &DateTime<Utc>,
+) {
+    let datetime = datetime.format("%Y-%m-%d %H:%M:%S");
+    let _ = write!(
+        buf,
+        "#Version: 1.0\n#Software: {software}\n#Start-Date: {datetime}\n#Date: {datetime}\n#Fields:"
+    );
+    for field in fields {
+        buf.push(b' ');
+        buf.extend_from_slice(field.name.as_bytes());
+    }
+    buf.push(b'\n');
+}
+
+fn push_value(buf: &mut Vec<u8>, value: &str) {
+    if value.is_empty() {
+        buf.push(b'-');
+        return;
+    }
+    if !value
+        .chars()
+        .any(|c| c.is_whitespace() || c.is_control() || c == '"')
+    {
+        buf.extend_from_slice(value.as_bytes());
+        return;
+    }
+
+    buf.push(b'"');
+    for c in value.chars() {
+        match c {
+            '"' => buf.extend_from_slice(b"\"\""),
+            c if c.is_control() => buf.push(b' '),
+            c => buf.extend_from_slice(c.encode_utf8(&mut [0u8; 4]).as_bytes()),
+        }
+    }
+    buf.push(b'"');
+}
+
+struct FieldCollector<'a> {
+    key_index: &'a HashMap<String, Vec<usize>>,
+    values: &'a mut [Option<String>],
+}
+
+impl<'a> Serializer for FieldCollector<'a> {
+    fn emit_none(&mut self, _key: slog::Key) -> slog::Result {
+        Ok(())
+    }
+
+    fn emit_arguments(&mut self, key: slog::Key, value: &Arguments) -> slog::Result {
+        if let Some(indexes) = self.key_index.get(key) {
+            let value = value.to_string();
+            for i in indexes {
+                self.values[*i] = Some(value.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_value() {
+        let mut buf = Vec::new();
+        push_value(&mut buf, "1.2.3.4:80");
+        buf.push(b' ');
+        push_value(&mut buf, "");
+        buf.push(b' ');
+        push_value(&mut buf, "a \"b\"\nc");
+        assert_eq!(
+            std::str::from_utf8(&buf).unwrap(),
+            "1.2.3.4:80 - \"a \"\"b\"\" c\""
+        );
+    }
+}
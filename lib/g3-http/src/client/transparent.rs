@@ -115,6 +115,18 @@ impl HttpTransparentResponse {
         self.keep_alive = false;
     }
 
+    /// Remove the Content-Encoding header and set the new Content-Length,
+    /// should be called only if the body has been decoded
+    pub fn set_identity_content(&mut self, content_length: u64) {
+        self.end_to_end_headers
+            .remove(http::header::CONTENT_ENCODING);
+        self.content_length = content_length;
+        self.has_content_length = true;
+        let value = unsafe { HttpHeaderValue::from_string_unchecked(content_length.to_string()) };
+        self.end_to_end_headers
+            .insert(http::header::CONTENT_LENGTH, value);
+    }
+
     fn expect_no_body(&self, method: &Method) -> bool {
         self.code < 200 || self.code == 204 || self.code == 304 || method.eq(&Method::HEAD)
     }
@@ -29,6 +29,18 @@ pub fn proxy_authorization_basic(username: &Username, password: &Password) -> St
     )
 }
 
+pub fn proxy_authorization_bearer(token: &str) -> String {
+    format!("Proxy-Authorization: Bearer {token}\r\n")
+}
+
+pub fn proxy_authorization_negotiate(encoded_token: &str) -> String {
+    format!("Proxy-Authorization: Negotiate {encoded_token}\r\n")
+}
+
+pub fn proxy_authenticate_negotiate() -> String {
+    "Proxy-Authenticate: Negotiate\r\n".to_string()
+}
+
 pub fn proxy_authenticate_basic(realm: &str) -> String {
     format!("Proxy-Authenticate: Basic realm=\"{realm}\"\r\n")
 }
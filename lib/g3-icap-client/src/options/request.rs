@@ -59,6 +59,7 @@ impl<'a> IcapOptionsRequest<'a> {
         if !self.config.icap_206_enable {
             options.support_206 = false;
         }
+        options.limit_ttl(self.config.options_refresh_interval);
         Ok(options)
     }
 }
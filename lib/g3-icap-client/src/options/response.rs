@@ -37,6 +37,7 @@ pub struct IcapServiceOptions {
     pub(crate) support_204: bool,
     pub(crate) support_206: bool,
     pub(crate) preview_size: Option<usize>,
+    pub(crate) keep_alive: bool,
 }
 
 impl IcapServiceOptions {
@@ -51,6 +52,7 @@ impl IcapServiceOptions {
             support_204: false,
             support_206: false,
             preview_size: None,
+            keep_alive: true,
         }
     }
 
@@ -65,6 +67,21 @@ impl IcapServiceOptions {
             support_204: false,
             support_206: false,
             preview_size: None,
+            keep_alive: true,
+        }
+    }
+
+    /// check if this is a valid response from the ICAP server, not the initial placeholder one
+    pub(crate) fn loaded(&self) -> bool {
+        !self.service_tag.is_empty()
+    }
+
+    /// make sure the options will be refreshed in at most `max_ttl`
+    pub(crate) fn limit_ttl(&mut self, max_ttl: Duration) {
+        let max_expire = Instant::now().add(max_ttl);
+        match self.expire {
+            Some(expire) if expire <= max_expire => {}
+            _ => self.expire = Some(max_expire),
         }
     }
 
@@ -171,6 +188,13 @@ impl IcapServiceOptions {
                 }
                 return Err(IcapOptionsParseError::MethodNotMatch);
             }
+            "connection" => {
+                for v in header.value.split(',') {
+                    if v.trim().eq_ignore_ascii_case("close") {
+                        self.keep_alive = false;
+                    }
+                }
+            }
             "service" => self.server = Some(header.value.to_string()),
             "istag" => self.service_tag = header.value.to_string(),
             "encapsulated" => {
@@ -57,8 +57,7 @@ impl IcapReqmodClient {
         http_req_add_no_via_header: bool,
         idle_checker: I,
     ) -> anyhow::Result<H2RequestAdapter<I>> {
-        let icap_client = self.inner.clone();
-        let (icap_connection, icap_options) = icap_client.fetch_connection().await?;
+        let (icap_client, icap_connection, icap_options) = self.inner.fetch_connection().await?;
         Ok(H2RequestAdapter {
             icap_client,
             icap_connection,
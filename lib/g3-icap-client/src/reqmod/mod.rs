@@ -16,7 +16,7 @@
 
 use std::sync::Arc;
 
-use crate::IcapServiceClient;
+use crate::IcapServiceGroup;
 
 mod error;
 pub use error::IcapReqmodParseError;
@@ -28,18 +28,20 @@ mod response;
 
 pub mod h1;
 pub mod h2;
+pub mod imap;
+pub mod smtp;
 
 #[derive(Clone)]
 pub struct IcapReqmodClient {
-    inner: Arc<IcapServiceClient>,
+    inner: Arc<IcapServiceGroup>,
 }
 
 impl IcapReqmodClient {
-    pub fn new(inner: Arc<IcapServiceClient>) -> IcapReqmodClient {
+    pub fn new(inner: Arc<IcapServiceGroup>) -> IcapReqmodClient {
         IcapReqmodClient { inner }
     }
 
     pub fn bypass(&self) -> bool {
-        self.inner.config.bypass
+        self.inner.bypass()
     }
 }
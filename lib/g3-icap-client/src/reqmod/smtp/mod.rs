@@ -0,0 +1,308 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{IoSlice, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bytes::BufMut;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use g3_http::{HttpBodyReader, HttpBodyType};
+use g3_io_ext::LimitedWriteExt;
+use g3_types::net::UpstreamAddr;
+
+use super::h1::HttpAdapterErrorResponse;
+use super::response::ReqmodResponse;
+use super::{IcapReqmodClient, IcapReqmodResponsePayload};
+use crate::{IcapClientConnection, IcapServiceClient, IcapServiceOptions};
+
+mod error;
+pub use error::SmtpAdaptationError;
+
+impl IcapReqmodClient {
+    pub async fn smtp_message_adapter(
+        &self,
+        body_line_max_size: usize,
+    ) -> anyhow::Result<SmtpMessageAdapter> {
+        let (icap_client, icap_connection, icap_options) = self.inner.fetch_connection().await?;
+        Ok(SmtpMessageAdapter {
+            icap_client,
+            icap_connection,
+            icap_options,
+            body_line_max_size,
+            client_addr: None,
+            client_username: None,
+        })
+    }
+}
+
+/// Adapter for the whole mail message received by DATA or BDAT commands.
+///
+/// The message will be sent to the ICAP server as the body of an encapsulated
+/// `PUT` HTTP request, with content type `message/rfc822`.
+/// If the ICAP server advertises a preview size in its OPTIONS response,
+/// only the preview part will be sent first, and the rest of the message
+/// will be sent only if the server replies with `100 Continue`.
+pub struct SmtpMessageAdapter {
+    icap_client: Arc<IcapServiceClient>,
+    icap_connection: IcapClientConnection,
+    icap_options: Arc<IcapServiceOptions>,
+    body_line_max_size: usize,
+    client_addr: Option<SocketAddr>,
+    client_username: Option<String>,
+}
+
+pub enum SmtpAdaptationEndState {
+    OriginalMessage,
+    AdaptedMessage(Vec<u8>),
+    Blocked(HttpAdapterErrorResponse),
+}
+
+pub struct SmtpMessageEnvelope<'a> {
+    pub upstream: &'a UpstreamAddr,
+    pub mail_from: &'a str,
+    pub rcpt_to: &'a [String],
+}
+
+impl SmtpMessageAdapter {
+    pub fn set_client_addr(&mut self, addr: SocketAddr) {
+        self.client_addr = Some(addr);
+    }
+
+    pub fn set_client_username(&mut self, user: &str) {
+        self.client_username = Some(user.to_string());
+    }
+
+    fn build_http_header(envelope: &SmtpMessageEnvelope<'_>, message_len: usize) -> Vec<u8> {
+        let mut header = Vec::with_capacity(256);
+        header.put_slice(b"PUT / HTTP/1.1\r\n");
+        let _ = write!(header, "Host: {}\r\n", envelope.upstream);
+        header.put_slice(b"Content-Type: message/rfc822\r\n");
+        let _ = write!(header, "Content-Length: {message_len}\r\n");
+        let _ = write!(header, "X-Mail-From: <{}>\r\n", envelope.mail_from);
+        for rcpt in envelope.rcpt_to {
+            let _ = write!(header, "X-Rcpt-To: <{rcpt}>\r\n");
+        }
+        header.put_slice(b"\r\n");
+        header
+    }
+
+    fn build_icap_request(&self, http_header_len: usize, preview_size: Option<usize>) -> Vec<u8> {
+        let mut header = Vec::with_capacity(self.icap_client.partial_request_header.len() + 128);
+        header.extend_from_slice(&self.icap_client.partial_request_header);
+        if let Some(addr) = self.client_addr {
+            crate::serialize::add_client_addr(&mut header, addr);
+        }
+        if let Some(user) = &self.client_username {
+            crate::serialize::add_client_username(&mut header, user);
+        }
+        if self.icap_options.support_204 {
+            header.put_slice(b"Allow: 204\r\n");
+        }
+        let _ = write!(
+            header,
+            "Encapsulated: req-hdr=0, req-body={http_header_len}\r\n",
+        );
+        if let Some(preview_size) = preview_size {
+            let _ = write!(header, "Preview: {preview_size}\r\n");
+        }
+        header.put_slice(b"\r\n");
+        header
+    }
+
+    async fn send_to_icap(&mut self, bufs: &[IoSlice<'_>]) -> Result<(), SmtpAdaptationError> {
+        let icap_w = &mut self.icap_connection.0;
+        icap_w
+            .write_all_vectored(bufs)
+            .await
+            .map_err(SmtpAdaptationError::IcapServerWriteFailed)?;
+        icap_w
+            .flush()
+            .await
+            .map_err(SmtpAdaptationError::IcapServerWriteFailed)
+    }
+
+    async fn recv_icap_response(&mut self) -> Result<ReqmodResponse, SmtpAdaptationError> {
+        let rsp = ReqmodResponse::parse(
+            &mut self.icap_connection.1,
+            self.icap_client.config.icap_max_header_size,
+            &self.icap_client.config.respond_shared_names,
+        )
+        .await?;
+        Ok(rsp)
+    }
+
+    async fn xfer_without_preview(
+        &mut self,
+        http_header: &[u8],
+        message: &[u8],
+    ) -> Result<ReqmodResponse, SmtpAdaptationError> {
+        let icap_header = self.build_icap_request(http_header.len(), None);
+        let chunk_header = format!("{:x}\r\n", message.len());
+        let chunk_end: &[u8] = if message.is_empty() {
+            b"\r\n"
+        } else {
+            b"\r\n0\r\n\r\n"
+        };
+
+        self.send_to_icap(&[
+            IoSlice::new(&icap_header),
+            IoSlice::new(http_header),
+            IoSlice::new(chunk_header.as_bytes()),
+            IoSlice::new(message),
+            IoSlice::new(chunk_end),
+        ])
+        .await?;
+        self.recv_icap_response().await
+    }
+
+    async fn xfer_with_preview(
+        &mut self,
+        http_header: &[u8],
+        message: &[u8],
+        preview_size: usize,
+    ) -> Result<ReqmodResponse, SmtpAdaptationError> {
+        let preview_size = preview_size.min(message.len());
+        let preview_eof = preview_size == message.len();
+        let icap_header = self.build_icap_request(http_header.len(), Some(preview_size));
+        let chunk_header = if preview_size > 0 {
+            format!("{preview_size:x}\r\n")
+        } else {
+            String::new()
+        };
+        let chunk_end: &[u8] = match (preview_size > 0, preview_eof) {
+            (true, true) => b"\r\n0; ieof\r\n\r\n",
+            (true, false) => b"\r\n0\r\n\r\n",
+            (false, true) => b"0; ieof\r\n\r\n",
+            (false, false) => b"0\r\n\r\n",
+        };
+
+        self.send_to_icap(&[
+            IoSlice::new(&icap_header),
+            IoSlice::new(http_header),
+            IoSlice::new(chunk_header.as_bytes()),
+            IoSlice::new(&message[..preview_size]),
+            IoSlice::new(chunk_end),
+        ])
+        .await?;
+        let rsp = self.recv_icap_response().await?;
+        if rsp.code != 100 {
+            return Ok(rsp);
+        }
+        if preview_eof {
+            // no more data to send after ieof
+            return Err(SmtpAdaptationError::IcapServerErrorResponse(
+                rsp.code, rsp.reason,
+            ));
+        }
+
+        let left = &message[preview_size..];
+        let chunk_header = format!("{:x}\r\n", left.len());
+        self.send_to_icap(&[
+            IoSlice::new(chunk_header.as_bytes()),
+            IoSlice::new(left),
+            IoSlice::new(b"\r\n0\r\n\r\n"),
+        ])
+        .await?;
+        self.recv_icap_response().await
+    }
+
+    pub async fn xfer(
+        mut self,
+        envelope: &SmtpMessageEnvelope<'_>,
+        message: &[u8],
+    ) -> Result<SmtpAdaptationEndState, SmtpAdaptationError> {
+        let http_header = Self::build_http_header(envelope, message.len());
+        let rsp = if let Some(preview_size) = self.icap_options.preview_size {
+            self.xfer_with_preview(&http_header, message, preview_size)
+                .await?
+        } else {
+            self.xfer_without_preview(&http_header, message).await?
+        };
+
+        match rsp.code {
+            204 => {
+                if rsp.keep_alive && rsp.payload == IcapReqmodResponsePayload::NoPayload {
+                    self.icap_client.save_connection(self.icap_connection).await;
+                }
+                Ok(SmtpAdaptationEndState::OriginalMessage)
+            }
+            n if (200..300).contains(&n) => match rsp.payload {
+                IcapReqmodResponsePayload::NoPayload => {
+                    if rsp.keep_alive {
+                        self.icap_client.save_connection(self.icap_connection).await;
+                    }
+                    // there should be a payload
+                    Err(SmtpAdaptationError::IcapServerErrorResponse(
+                        rsp.code, rsp.reason,
+                    ))
+                }
+                IcapReqmodResponsePayload::HttpRequestWithoutBody(_) => {
+                    Err(SmtpAdaptationError::NoAdaptedMessageBody)
+                }
+                IcapReqmodResponsePayload::HttpRequestWithBody(header_size) => {
+                    // the adapted http request header is not used
+                    let mut header = vec![0u8; header_size];
+                    self.icap_connection
+                        .1
+                        .read_exact(&mut header)
+                        .await
+                        .map_err(SmtpAdaptationError::IcapServerReadFailed)?;
+
+                    let mut adapted = Vec::with_capacity(message.len());
+                    let mut body_reader = HttpBodyReader::new(
+                        &mut self.icap_connection.1,
+                        HttpBodyType::ChunkedWithoutTrailer,
+                        self.body_line_max_size,
+                    );
+                    body_reader
+                        .read_to_end(&mut adapted)
+                        .await
+                        .map_err(SmtpAdaptationError::IcapServerReadFailed)?;
+                    if rsp.keep_alive && body_reader.finished() {
+                        self.icap_client.save_connection(self.icap_connection).await;
+                    }
+                    Ok(SmtpAdaptationEndState::AdaptedMessage(adapted))
+                }
+                IcapReqmodResponsePayload::HttpResponseWithoutBody(header_size) => {
+                    let http_rsp =
+                        HttpAdapterErrorResponse::parse(&mut self.icap_connection.1, header_size)
+                            .await?;
+                    if rsp.keep_alive {
+                        self.icap_client.save_connection(self.icap_connection).await;
+                    }
+                    Ok(SmtpAdaptationEndState::Blocked(http_rsp))
+                }
+                IcapReqmodResponsePayload::HttpResponseWithBody(header_size) => {
+                    // the response body is dropped along with the icap connection
+                    let http_rsp =
+                        HttpAdapterErrorResponse::parse(&mut self.icap_connection.1, header_size)
+                            .await?;
+                    Ok(SmtpAdaptationEndState::Blocked(http_rsp))
+                }
+            },
+            _ => {
+                if rsp.keep_alive && rsp.payload == IcapReqmodResponsePayload::NoPayload {
+                    self.icap_client.save_connection(self.icap_connection).await;
+                }
+                Err(SmtpAdaptationError::IcapServerErrorResponse(
+                    rsp.code, rsp.reason,
+                ))
+            }
+        }
+    }
+}
@@ -65,8 +65,7 @@ impl IcapRespmodClient {
         http_body_line_max_size: usize,
         idle_checker: I,
     ) -> anyhow::Result<HttpResponseAdapter<I>> {
-        let icap_client = self.inner.clone();
-        let (icap_connection, icap_options) = icap_client.fetch_connection().await?;
+        let (icap_client, icap_connection, icap_options) = self.inner.fetch_connection().await?;
         Ok(HttpResponseAdapter {
             icap_client,
             icap_connection,
@@ -60,8 +60,7 @@ impl IcapRespmodClient {
         http_trailer_max_size: usize,
         idle_checker: I,
     ) -> anyhow::Result<H2ResponseAdapter<I>> {
-        let icap_client = self.inner.clone();
-        let (icap_connection, icap_options) = icap_client.fetch_connection().await?;
+        let (icap_client, icap_connection, icap_options) = self.inner.fetch_connection().await?;
         Ok(H2ResponseAdapter {
             icap_client,
             icap_connection,
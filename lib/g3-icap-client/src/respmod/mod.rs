@@ -16,7 +16,7 @@
 
 use std::sync::Arc;
 
-use crate::IcapServiceClient;
+use crate::IcapServiceGroup;
 
 mod error;
 pub use error::IcapRespmodParseError;
@@ -31,15 +31,15 @@ pub mod h2;
 
 #[derive(Clone)]
 pub struct IcapRespmodClient {
-    inner: Arc<IcapServiceClient>,
+    inner: Arc<IcapServiceGroup>,
 }
 
 impl IcapRespmodClient {
-    pub fn new(inner: Arc<IcapServiceClient>) -> IcapRespmodClient {
+    pub fn new(inner: Arc<IcapServiceGroup>) -> IcapRespmodClient {
         IcapRespmodClient { inner }
     }
 
     pub fn bypass(&self) -> bool {
-        self.inner.config.bypass
+        self.inner.bypass()
     }
 }
@@ -17,6 +17,7 @@
 use std::sync::Arc;
 
 use anyhow::anyhow;
+use arc_swap::ArcSwap;
 use tokio::sync::oneshot;
 
 use super::{
@@ -30,13 +31,22 @@ pub struct IcapServiceClient {
     pub(crate) partial_request_header: Vec<u8>,
     cmd_sender: flume::Sender<IcapServiceClientCommand>,
     conn_creator: Arc<IcapConnectionCreator>,
+    options: Arc<ArcSwap<IcapServiceOptions>>,
 }
 
 impl IcapServiceClient {
     pub fn new(config: Arc<IcapServiceConfig>) -> Self {
         let (cmd_sender, cmd_receiver) = flume::unbounded();
         let conn_creator = Arc::new(IcapConnectionCreator::new(config.clone()));
-        let pool = IcapServicePool::new(config.clone(), cmd_receiver, conn_creator.clone());
+        let options = Arc::new(ArcSwap::from_pointee(IcapServiceOptions::new_expired(
+            config.method,
+        )));
+        let pool = IcapServicePool::new(
+            config.clone(),
+            cmd_receiver,
+            conn_creator.clone(),
+            options.clone(),
+        );
         tokio::spawn(pool.into_running());
         let partial_request_header = config.build_request_header();
         IcapServiceClient {
@@ -44,6 +54,7 @@ impl IcapServiceClient {
             partial_request_header,
             cmd_sender,
             conn_creator,
+            options,
         }
     }
 
@@ -64,17 +75,31 @@ impl IcapServiceClient {
             return Ok(conn);
         }
 
-        let mut conn = self
-            .conn_creator
-            .create()
-            .await
-            .map_err(|e| anyhow!("create new connection failed: {e:?}"))?;
+        let mut conn = self.create_connection().await?;
+        let options = self.options.load_full();
+        if options.loaded() {
+            // the options will be refreshed in the pool task
+            return Ok((conn, options));
+        }
+
         let options_req = IcapOptionsRequest::new(self.config.as_ref());
         let options = options_req
             .get_options(&mut conn, self.config.icap_max_header_size)
             .await
             .map_err(|e| anyhow!("failed to get icap service options: {e}"))?;
-        Ok((conn, Arc::new(options)))
+        let options = Arc::new(options);
+        self.options.store(options.clone());
+        if !options.keep_alive {
+            conn = self.create_connection().await?;
+        }
+        Ok((conn, options))
+    }
+
+    async fn create_connection(&self) -> anyhow::Result<IcapClientConnection> {
+        self.conn_creator
+            .create()
+            .await
+            .map_err(|e| anyhow!("create new connection failed: {e:?}"))
     }
 
     pub async fn save_connection(&self, conn: IcapClientConnection) {
@@ -18,14 +18,19 @@ use std::collections::BTreeSet;
 use std::io::Write;
 use std::time::Duration;
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use http::HeaderName;
+use tokio_rustls::rustls::ServerName;
 use url::Url;
 
-use g3_types::net::{HttpAuth, TcpKeepAliveConfig, UpstreamAddr};
+use g3_types::net::{
+    HttpAuth, RustlsClientConfig, RustlsClientConfigBuilder, TcpKeepAliveConfig, UpstreamAddr,
+};
 
 use super::IcapMethod;
 
+const ICAPS_DEFAULT_PORT: u16 = 11344;
+
 pub struct IcapConnectionPoolConfig {
     pub(crate) check_interval: Duration,
     pub(crate) max_idle_count: usize,
@@ -67,9 +72,12 @@ pub struct IcapServiceConfig {
     pub(crate) upstream: UpstreamAddr,
     pub connection_pool: IcapConnectionPoolConfig,
     pub(crate) tcp_keepalive: TcpKeepAliveConfig,
+    pub(crate) tls_client: Option<RustlsClientConfig>,
+    pub(crate) tls_name: Option<ServerName>,
     pub(crate) icap_206_enable: bool,
     pub(crate) icap_max_header_size: usize,
     pub(crate) preview_data_read_timeout: Duration,
+    pub(crate) options_refresh_interval: Duration,
     pub(crate) respond_shared_names: BTreeSet<String>,
     pub(crate) bypass: bool,
 }
@@ -84,8 +92,23 @@ impl IcapServiceConfig {
             .map_err(|_| anyhow!("failed to clear username in url"))?;
         url.set_password(None)
             .map_err(|_| anyhow!("failed to clear password in url"))?;
-        let upstream = UpstreamAddr::try_from(&url)
+        let use_tls = url.scheme().eq_ignore_ascii_case("icaps");
+        let mut upstream_url = url.clone();
+        if use_tls && url.port().is_none() {
+            upstream_url
+                .set_port(Some(ICAPS_DEFAULT_PORT))
+                .map_err(|_| anyhow!("failed to set default icaps port"))?;
+        }
+        let upstream = UpstreamAddr::try_from(&upstream_url)
             .map_err(|e| anyhow!("failed to get upstream address from url: {e}"))?;
+        let tls_client = if use_tls {
+            let tls_client = RustlsClientConfigBuilder::default()
+                .build()
+                .context("failed to build default tls client config")?;
+            Some(tls_client)
+        } else {
+            None
+        };
         Ok(IcapServiceConfig {
             method,
             url,
@@ -94,9 +117,12 @@ impl IcapServiceConfig {
             upstream,
             connection_pool: IcapConnectionPoolConfig::default(),
             tcp_keepalive: TcpKeepAliveConfig::default_enabled(),
+            tls_client,
+            tls_name: None,
             icap_206_enable: false,
             icap_max_header_size: 8192,
             preview_data_read_timeout: Duration::from_secs(4),
+            options_refresh_interval: Duration::from_secs(60),
             respond_shared_names: BTreeSet::new(),
             bypass: false,
         })
@@ -106,6 +132,19 @@ impl IcapServiceConfig {
         self.tcp_keepalive = config;
     }
 
+    /// Set the tls client config, this will enable tls even if the url scheme is not `icaps`
+    pub fn set_tls_client(&mut self, tls_config: RustlsClientConfigBuilder) -> anyhow::Result<()> {
+        let tls_client = tls_config
+            .build()
+            .context("failed to build tls client config")?;
+        self.tls_client = Some(tls_client);
+        Ok(())
+    }
+
+    pub fn set_tls_name(&mut self, tls_name: ServerName) {
+        self.tls_name = Some(tls_name);
+    }
+
     pub fn set_icap_max_header_size(&mut self, max_size: usize) {
         self.icap_max_header_size = max_size;
     }
@@ -114,6 +153,10 @@ impl IcapServiceConfig {
         self.preview_data_read_timeout = time;
     }
 
+    pub fn set_options_refresh_interval(&mut self, interval: Duration) {
+        self.options_refresh_interval = interval;
+    }
+
     pub fn set_bypass(&mut self, bypass: bool) {
         self.bypass = bypass;
     }
@@ -151,6 +194,16 @@ impl IcapServiceConfig {
                     basic_auth.encoded_value()
                 );
             }
+            HttpAuth::Bearer(bearer_auth) => {
+                let _ = write!(header, "Authorization: Bearer {}\r\n", bearer_auth.token());
+            }
+            HttpAuth::Negotiate(negotiate_auth) => {
+                let _ = write!(
+                    header,
+                    "Authorization: Negotiate {}\r\n",
+                    negotiate_auth.encoded_value()
+                );
+            }
         }
     }
 }
@@ -18,9 +18,10 @@ use std::io;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-use tokio::io::BufReader;
-use tokio::net::tcp;
+use tokio::io::{AsyncRead, AsyncWrite, BufReader};
 use tokio::sync::oneshot;
+use tokio_rustls::rustls::ServerName;
+use tokio_rustls::TlsConnector;
 
 use crate::IcapServiceOptions;
 use g3_io_ext::LimitedBufReadExt;
@@ -28,8 +29,8 @@ use g3_types::net::Host;
 
 use super::IcapServiceConfig;
 
-pub type IcapClientWriter = tcp::OwnedWriteHalf;
-pub type IcapClientReader = BufReader<tcp::OwnedReadHalf>;
+pub type IcapClientWriter = Box<dyn AsyncWrite + Send + Sync + Unpin>;
+pub type IcapClientReader = BufReader<Box<dyn AsyncRead + Send + Sync + Unpin>>;
 pub type IcapClientConnection = (IcapClientWriter, IcapClientReader);
 
 pub(super) struct IcapConnectionCreator {
@@ -64,8 +65,35 @@ impl IcapConnectionCreator {
             true,
         )?;
         let stream = socket.connect(peer).await?;
-        let (r, w) = stream.into_split();
-        Ok((w, BufReader::new(r)))
+
+        if let Some(tls_client) = &self.config.tls_client {
+            let tls_name = match &self.config.tls_name {
+                Some(name) => name.clone(),
+                None => ServerName::try_from(self.config.upstream.host())?,
+            };
+            let tls_connect =
+                TlsConnector::from(tls_client.driver.clone()).connect(tls_name, stream);
+            let tls_stream =
+                match tokio::time::timeout(tls_client.handshake_timeout, tls_connect).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => {
+                        return Err(io::Error::other(format!(
+                            "failed to tls connect to icap server: {e}"
+                        )))
+                    }
+                    Err(_) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "tls connect to icap server timed out",
+                        ))
+                    }
+                };
+            let (r, w) = tokio::io::split(tls_stream);
+            Ok((Box::new(w), BufReader::new(Box::new(r))))
+        } else {
+            let (r, w) = stream.into_split();
+            Ok((Box::new(w), BufReader::new(Box::new(r))))
+        }
     }
 }
 
@@ -0,0 +1,204 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use tokio::time::Instant;
+
+use super::{IcapClientConnection, IcapServiceClient, IcapServiceConfig};
+use crate::IcapServiceOptions;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IcapServiceSelectPolicy {
+    RoundRobin,
+    LeastLoad,
+}
+
+impl FromStr for IcapServiceSelectPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "roundrobin" | "rr" | "round_robin" => Ok(IcapServiceSelectPolicy::RoundRobin),
+            "leastload" | "least_load" | "least_conn" => Ok(IcapServiceSelectPolicy::LeastLoad),
+            _ => Err(()),
+        }
+    }
+}
+
+pub struct IcapServiceGroupConfig {
+    services: Vec<Arc<IcapServiceConfig>>,
+    select_policy: IcapServiceSelectPolicy,
+    unhealthy_duration: Duration,
+    bypass: Option<bool>,
+}
+
+impl IcapServiceGroupConfig {
+    pub fn new(services: Vec<IcapServiceConfig>) -> anyhow::Result<Self> {
+        if services.is_empty() {
+            return Err(anyhow!("no icap service set"));
+        }
+        let method = services[0].method;
+        if services.iter().any(|s| s.method != method) {
+            return Err(anyhow!("all icap services should use the same method"));
+        }
+        Ok(IcapServiceGroupConfig {
+            services: services.into_iter().map(Arc::new).collect(),
+            select_policy: IcapServiceSelectPolicy::RoundRobin,
+            unhealthy_duration: Duration::from_secs(30),
+            bypass: None,
+        })
+    }
+
+    pub fn set_select_policy(&mut self, policy: IcapServiceSelectPolicy) {
+        self.select_policy = policy;
+    }
+
+    pub fn set_unhealthy_duration(&mut self, duration: Duration) {
+        self.unhealthy_duration = duration;
+    }
+
+    pub fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = Some(bypass);
+    }
+
+    /// bypass if all services are unavailable, the value set to the group will take precedence
+    pub fn bypass(&self) -> bool {
+        self.bypass
+            .unwrap_or_else(|| self.services.iter().all(|s| s.bypass))
+    }
+}
+
+impl From<IcapServiceConfig> for IcapServiceGroupConfig {
+    fn from(value: IcapServiceConfig) -> Self {
+        IcapServiceGroupConfig {
+            services: vec![Arc::new(value)],
+            select_policy: IcapServiceSelectPolicy::RoundRobin,
+            unhealthy_duration: Duration::from_secs(30),
+            bypass: None,
+        }
+    }
+}
+
+struct IcapServiceMember {
+    client: Arc<IcapServiceClient>,
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl IcapServiceMember {
+    fn new(config: Arc<IcapServiceConfig>) -> Self {
+        IcapServiceMember {
+            client: Arc::new(IcapServiceClient::new(config)),
+            unhealthy_until: Mutex::new(None),
+        }
+    }
+
+    fn is_healthy(&self, now: Instant) -> bool {
+        let unhealthy_until = self.unhealthy_until.lock().unwrap();
+        match *unhealthy_until {
+            Some(until) => now >= until,
+            None => true,
+        }
+    }
+
+    fn mark_healthy(&self) {
+        let mut unhealthy_until = self.unhealthy_until.lock().unwrap();
+        *unhealthy_until = None;
+    }
+
+    fn mark_unhealthy(&self, duration: Duration) {
+        let mut unhealthy_until = self.unhealthy_until.lock().unwrap();
+        *unhealthy_until = Some(Instant::now() + duration);
+    }
+
+    /// the count of adapters that are using this service
+    fn load(&self) -> usize {
+        // each adapter holds a reference to the client until it's dropped
+        Arc::strong_count(&self.client) - 1
+    }
+}
+
+pub struct IcapServiceGroup {
+    config: Arc<IcapServiceGroupConfig>,
+    members: Vec<IcapServiceMember>,
+    next_index: AtomicUsize,
+}
+
+impl IcapServiceGroup {
+    pub fn new(config: Arc<IcapServiceGroupConfig>) -> Self {
+        let members = config
+            .services
+            .iter()
+            .map(|c| IcapServiceMember::new(c.clone()))
+            .collect();
+        IcapServiceGroup {
+            config,
+            members,
+            next_index: AtomicUsize::new(0),
+        }
+    }
+
+    #[inline]
+    pub fn bypass(&self) -> bool {
+        self.config.bypass()
+    }
+
+    fn select_order(&self) -> Vec<&IcapServiceMember> {
+        let now = Instant::now();
+        let start = self.next_index.fetch_add(1, Ordering::Relaxed) % self.members.len();
+        let rotated = self.members[start..]
+            .iter()
+            .chain(self.members[..start].iter());
+
+        let mut order: Vec<&IcapServiceMember> =
+            rotated.clone().filter(|m| m.is_healthy(now)).collect();
+        if order.is_empty() {
+            // all services are unhealthy, try all of them anyway
+            order = rotated.collect();
+        }
+        if self.config.select_policy == IcapServiceSelectPolicy::LeastLoad {
+            order.sort_by_key(|m| m.load());
+        }
+        order
+    }
+
+    pub(crate) async fn fetch_connection(
+        &self,
+    ) -> anyhow::Result<(
+        Arc<IcapServiceClient>,
+        IcapClientConnection,
+        Arc<IcapServiceOptions>,
+    )> {
+        let mut last_err = None;
+        for member in self.select_order() {
+            match member.client.fetch_connection().await {
+                Ok((conn, options)) => {
+                    member.mark_healthy();
+                    return Ok((member.client.clone(), conn, options));
+                }
+                Err(e) => {
+                    member.mark_unhealthy(self.config.unhealthy_duration);
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| anyhow!("no icap service available")))
+    }
+}
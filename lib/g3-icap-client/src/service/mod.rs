@@ -24,6 +24,9 @@ use connection::{IcapConnectionCreator, IcapConnectionEofPoller, IcapConnectionP
 mod client;
 pub use client::IcapServiceClient;
 
+mod group;
+pub use group::{IcapServiceGroup, IcapServiceGroupConfig, IcapServiceSelectPolicy};
+
 mod pool;
 use pool::{IcapServiceClientCommand, IcapServicePool};
 
@@ -14,9 +14,10 @@
  * limitations under the License.
  */
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use arc_swap::ArcSwap;
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::Interval;
 
@@ -32,13 +33,13 @@ pub(super) enum IcapServiceClientCommand {
 }
 
 enum IcapServicePoolCommand {
-    UpdateOptions(IcapServiceOptions),
     SaveConnection(IcapClientConnection),
 }
 
 pub(super) struct IcapServicePool {
     config: Arc<IcapServiceConfig>,
-    options: Arc<IcapServiceOptions>,
+    options: Arc<ArcSwap<IcapServiceOptions>>,
+    options_refreshing: Arc<AtomicBool>,
     conn_creator: Arc<IcapConnectionCreator>,
     check_interval: Interval,
     client_cmd_receiver: flume::Receiver<IcapServiceClientCommand>,
@@ -54,8 +55,8 @@ impl IcapServicePool {
         config: Arc<IcapServiceConfig>,
         client_cmd_receiver: flume::Receiver<IcapServiceClientCommand>,
         conn_creator: Arc<IcapConnectionCreator>,
+        options: Arc<ArcSwap<IcapServiceOptions>>,
     ) -> Self {
-        let options = Arc::new(IcapServiceOptions::new_expired(config.method));
         let check_interval = tokio::time::interval(config.connection_pool.check_interval);
         let (pool_cmd_sender, pool_cmd_receiver) =
             mpsc::channel(config.connection_pool.max_idle_count);
@@ -64,6 +65,7 @@ impl IcapServicePool {
         IcapServicePool {
             config,
             options,
+            options_refreshing: Arc::new(AtomicBool::new(false)),
             conn_creator,
             check_interval,
             client_cmd_receiver,
@@ -104,10 +106,12 @@ impl IcapServicePool {
     }
 
     fn check(&mut self) {
-        if self.options.expired() {
+        if self.options.load().expired() && !self.options_refreshing.swap(true, Ordering::AcqRel) {
             let pool_sender = self.pool_cmd_sender.clone();
             let conn_creator = self.conn_creator.clone();
             let config = self.config.clone();
+            let options_holder = self.options.clone();
+            let options_refreshing = self.options_refreshing.clone();
             tokio::spawn(async move {
                 if let Ok(mut conn) = conn_creator.create().await {
                     let req = IcapOptionsRequest::new(config.as_ref());
@@ -115,17 +119,16 @@ impl IcapServicePool {
                         .get_options(&mut conn, config.icap_max_header_size)
                         .await
                     {
-                        if pool_sender
-                            .send(IcapServicePoolCommand::UpdateOptions(options))
-                            .await
-                            .is_ok()
-                        {
+                        let keep_alive = options.keep_alive;
+                        options_holder.store(Arc::new(options));
+                        if keep_alive {
                             let _ = pool_sender
                                 .send(IcapServicePoolCommand::SaveConnection(conn))
                                 .await;
                         }
                     }
                 }
+                options_refreshing.store(false, Ordering::Release);
             });
         }
 
@@ -148,10 +151,14 @@ impl IcapServicePool {
     fn handle_client_cmd(&mut self, cmd: IcapServiceClientCommand) {
         match cmd {
             IcapServiceClientCommand::FetchConnection(sender) => {
+                let options = self.options.load_full();
+                if !options.loaded() {
+                    // drop the sender and let the client fetch the options by itself
+                    return;
+                }
                 if self.idle_conn_count() > 0 {
                     // there maybe race condition, so we have fallback at client side
                     let req_sender = self.conn_req_sender.clone();
-                    let options = self.options.clone();
                     tokio::spawn(async move {
                         let _ = req_sender
                             .send_async(IcapConnectionPollRequest::new(sender, options))
@@ -159,7 +166,6 @@ impl IcapServicePool {
                     });
                 } else {
                     let conn_creator = self.conn_creator.clone();
-                    let options = self.options.clone();
                     tokio::spawn(async move {
                         if let Ok(conn) = conn_creator.create().await {
                             let _ = sender.send((conn, options));
@@ -174,7 +180,6 @@ impl IcapServicePool {
     fn handle_pool_cmd(&mut self, cmd: IcapServicePoolCommand) {
         match cmd {
             IcapServicePoolCommand::SaveConnection(conn) => self.save_connection(conn),
-            IcapServicePoolCommand::UpdateOptions(options) => self.options = Arc::new(options),
         }
     }
 
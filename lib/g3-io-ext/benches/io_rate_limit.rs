@@ -36,6 +36,16 @@ fn test_fixed_window_3(limiter: &mut StreamLimitInfo, start: &Instant) {
     let _ = limiter.check(ts, 3);
 }
 
+fn test_token_bucket(limiter: &mut StreamLimitInfo, start: &Instant) {
+    let ts = start.elapsed().as_millis() as u64;
+    let _ = limiter.check(ts, 1);
+}
+
+fn test_token_bucket_3(limiter: &mut StreamLimitInfo, start: &Instant) {
+    let ts = start.elapsed().as_millis() as u64;
+    let _ = limiter.check(ts, 3);
+}
+
 fn test_leaky_bucket(limiter: &RateLimiter<NotKeyed, InMemoryState, DefaultClock>) {
     let _ = limiter.check();
 }
@@ -65,6 +75,20 @@ fn fixed_window_empty(b: &mut Bencher) {
     b.iter(|| test_fixed_window(&mut limiter, &start));
 }
 
+#[bench]
+fn token_bucket_ok1(b: &mut Bencher) {
+    let start = Instant::now();
+    let mut limiter = StreamLimitInfo::new_token_bucket(1024 * 1024 * 1024, 1024 * 1024);
+    b.iter(|| test_token_bucket(&mut limiter, &start));
+}
+
+#[bench]
+fn token_bucket_ok3(b: &mut Bencher) {
+    let start = Instant::now();
+    let mut limiter = StreamLimitInfo::new_token_bucket(1024 * 1024 * 1024, 1024 * 1024);
+    b.iter(|| test_token_bucket_3(&mut limiter, &start));
+}
+
 #[bench]
 fn leaky_bucket_ok1(b: &mut Bencher) {
     let quota = Quota::per_second(unsafe { NonZeroU32::new_unchecked(1024 * 1024 * 1024) });
@@ -17,6 +17,7 @@
 use std::io;
 use std::io::IoSlice;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{ready, Context, Poll};
 
 use pin_project::pin_project;
@@ -24,6 +25,7 @@ use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, ReadBuf};
 
 use super::DEFAULT_BUF_SIZE;
 use crate::io::{ArcLimitedReaderStats, LimitedReader};
+use crate::limit::GlobalStreamLimiter;
 
 #[pin_project]
 pub struct LimitedBufReader<R> {
@@ -134,6 +136,14 @@ where
         self.inner.reset_limit(shift_millis, max_bytes);
     }
 
+    pub fn set_burst_limit(&mut self, burst: usize) {
+        self.inner.set_burst_limit(burst);
+    }
+
+    pub fn set_global_limit(&mut self, limiter: Arc<GlobalStreamLimiter>) {
+        self.inner.set_global_limit(limiter);
+    }
+
     pub fn into_inner(self) -> R {
         self.inner.into_inner()
     }
@@ -0,0 +1,226 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::VecDeque;
+use std::io::{self, IoSlice};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::{Buf, Bytes};
+use futures_util::FutureExt;
+use pin_project::pin_project;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::time::{Instant, Sleep};
+
+use g3_types::net::StreamDelayConfig;
+
+const READ_CHUNK_SIZE: usize = 16 * 1024;
+const MAX_BUFFERED_SIZE: usize = 256 * 1024;
+
+enum DelayedData {
+    Data(Bytes),
+    Eof,
+    Error(io::Error),
+}
+
+/// A reader that releases the data read from the inner reader only after the
+/// configured latency (and a random jitter) has passed.
+///
+/// The inner reader will be polled ahead of time, so the throughput won't be
+/// affected as long as the buffered data is less than the max buffered size.
+#[pin_project]
+pub struct DelayedReader<R> {
+    #[pin]
+    inner: R,
+    config: StreamDelayConfig,
+    read_buf: Box<[u8]>,
+    queue: VecDeque<(Instant, DelayedData)>,
+    buffered: usize,
+    read_end: bool,
+    last_deadline: Instant,
+    sleep: Pin<Box<Sleep>>,
+}
+
+impl<R> DelayedReader<R> {
+    pub fn new(inner: R, config: StreamDelayConfig) -> Self {
+        let read_buf = if config.is_set() {
+            vec![0u8; READ_CHUNK_SIZE].into_boxed_slice()
+        } else {
+            Box::default()
+        };
+        DelayedReader {
+            inner,
+            config,
+            read_buf,
+            queue: VecDeque::new(),
+            buffered: 0,
+            read_end: false,
+            last_deadline: Instant::now(),
+            sleep: Box::pin(tokio::time::sleep(Duration::ZERO)),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R> AsyncRead for DelayedReader<R>
+where
+    R: AsyncRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let mut this = self.project();
+        if !this.config.is_set() {
+            return this.inner.poll_read(cx, buf);
+        }
+
+        // read ahead and queue all data that is available now
+        while !*this.read_end && *this.buffered < MAX_BUFFERED_SIZE {
+            let mut read_buf = ReadBuf::new(&mut this.read_buf[..]);
+            let data = match this.inner.as_mut().poll_read(cx, &mut read_buf) {
+                Poll::Pending => break,
+                Poll::Ready(Ok(_)) => {
+                    let filled = read_buf.filled();
+                    if filled.is_empty() {
+                        *this.read_end = true;
+                        DelayedData::Eof
+                    } else {
+                        *this.buffered += filled.len();
+                        DelayedData::Data(Bytes::copy_from_slice(filled))
+                    }
+                }
+                Poll::Ready(Err(e)) => {
+                    *this.read_end = true;
+                    DelayedData::Error(e)
+                }
+            };
+
+            let mut deadline = Instant::now() + this.config.latency();
+            let jitter = this.config.jitter();
+            if !jitter.is_zero() {
+                deadline += Duration::from_nanos(fastrand::u64(0..=jitter.as_nanos() as u64));
+            }
+            // keep the data in order
+            if deadline < *this.last_deadline {
+                deadline = *this.last_deadline;
+            }
+            *this.last_deadline = deadline;
+            this.queue.push_back((deadline, data));
+        }
+
+        let Some((deadline, _)) = this.queue.front() else {
+            return if *this.read_end {
+                Poll::Ready(Ok(()))
+            } else {
+                Poll::Pending
+            };
+        };
+        if *deadline > Instant::now() {
+            this.sleep.as_mut().reset(*deadline);
+            if this.sleep.poll_unpin(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+
+        let (_, data) = this.queue.front_mut().unwrap();
+        match data {
+            DelayedData::Data(b) => {
+                let len = b.len().min(buf.remaining());
+                buf.put_slice(&b[..len]);
+                b.advance(len);
+                *this.buffered -= len;
+                if b.is_empty() {
+                    this.queue.pop_front();
+                }
+                Poll::Ready(Ok(()))
+            }
+            DelayedData::Eof => Poll::Ready(Ok(())),
+            DelayedData::Error(_) => {
+                let Some((_, DelayedData::Error(e))) = this.queue.pop_front() else {
+                    unreachable!()
+                };
+                Poll::Ready(Err(e))
+            }
+        }
+    }
+}
+
+impl<R: AsyncRead + AsyncWrite> AsyncWrite for DelayedReader<R> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn delayed_read() {
+        let data = b"hello world";
+        let config = StreamDelayConfig::new(Duration::from_millis(20), Duration::ZERO);
+        let mut reader = DelayedReader::new(data.as_slice(), config);
+
+        let start = Instant::now();
+        let mut buf = Vec::new();
+        let len = reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(len, data.len());
+        assert_eq!(buf.as_slice(), data);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn not_delayed() {
+        let data = b"hello world";
+        let mut reader = DelayedReader::new(data.as_slice(), StreamDelayConfig::default());
+
+        let mut buf = Vec::new();
+        let len = reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(len, data.len());
+        assert_eq!(buf.as_slice(), data);
+    }
+}
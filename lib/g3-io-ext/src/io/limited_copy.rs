@@ -14,8 +14,9 @@
  * limitations under the License.
  */
 
+use std::collections::VecDeque;
 use std::future::Future;
-use std::io;
+use std::io::{self, IoSlice};
 use std::pin::Pin;
 use std::task::{ready, Context, Poll};
 
@@ -26,11 +27,14 @@ const DEFAULT_COPY_BUFFER_SIZE: usize = 16 * 1024; // 16KB
 const MINIMAL_COPY_BUFFER_SIZE: usize = 4 * 1024; // 4KB
 const DEFAULT_COPY_YIELD_SIZE: usize = 1024 * 1024; // 1MB
 const MINIMAL_COPY_YIELD_SIZE: usize = 256 * 1024; // 256KB
+const DEFAULT_COPY_PIPELINE_DEPTH: usize = 1;
+const MAXIMUM_COPY_PIPELINE_DEPTH: usize = 16;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct LimitedCopyConfig {
     buffer_size: usize,
     yield_size: usize,
+    pipeline_depth: usize,
 }
 
 impl Default for LimitedCopyConfig {
@@ -38,6 +42,7 @@ impl Default for LimitedCopyConfig {
         LimitedCopyConfig {
             buffer_size: DEFAULT_COPY_BUFFER_SIZE,
             yield_size: DEFAULT_COPY_YIELD_SIZE,
+            pipeline_depth: DEFAULT_COPY_PIPELINE_DEPTH,
         }
     }
 }
@@ -60,6 +65,19 @@ impl LimitedCopyConfig {
     pub fn yield_size(&self) -> usize {
         self.yield_size
     }
+
+    /// Set how many buffers can be in flight at the same time.
+    ///
+    /// With a depth larger than 1, we will continue to read into the next buffer
+    /// while the write of the previous buffers is still pending.
+    pub fn set_pipeline_depth(&mut self, depth: usize) {
+        self.pipeline_depth = depth.clamp(1, MAXIMUM_COPY_PIPELINE_DEPTH);
+    }
+
+    #[inline]
+    pub fn pipeline_depth(&self) -> usize {
+        self.pipeline_depth
+    }
 }
 
 #[derive(Error, Debug)]
@@ -71,12 +89,67 @@ pub enum LimitedCopyError {
 }
 
 #[derive(Debug)]
-struct LimitedCopyBuffer {
-    read_done: bool,
+struct LimitedCopyChunk {
     buf: Box<[u8]>,
-    yield_size: usize,
     r_off: usize,
     w_off: usize,
+}
+
+impl LimitedCopyChunk {
+    fn new(size: usize) -> Self {
+        LimitedCopyChunk {
+            buf: vec![0; size].into_boxed_slice(),
+            r_off: 0,
+            w_off: 0,
+        }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.w_off == self.r_off
+    }
+
+    #[inline]
+    fn is_full(&self) -> bool {
+        self.r_off == self.buf.len()
+    }
+
+    #[inline]
+    fn data(&self) -> &[u8] {
+        &self.buf[self.w_off..self.r_off]
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        self.w_off = 0;
+        self.r_off = 0;
+    }
+
+    fn compact(&mut self) {
+        let left = self.r_off - self.w_off;
+        if left < self.w_off {
+            // copy small data to the begin of the buffer, so we can read more data
+            unsafe {
+                let ptr = self.buf.as_mut_ptr();
+                let src_ptr = ptr.add(self.w_off);
+                std::ptr::copy_nonoverlapping(src_ptr, ptr, left);
+            }
+            self.w_off = 0;
+            self.r_off = left;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LimitedCopyBuffer {
+    read_done: bool,
+    /// the chunks in flight, which should never be empty.
+    /// we read into the back one and write from the front one
+    chunks: VecDeque<LimitedCopyChunk>,
+    spare_chunks: Vec<LimitedCopyChunk>,
+    chunk_size: usize,
+    pipeline_depth: usize,
+    yield_size: usize,
     total: u64,
     need_flush: bool,
     active: bool,
@@ -84,12 +157,15 @@ struct LimitedCopyBuffer {
 
 impl LimitedCopyBuffer {
     fn new(config: &LimitedCopyConfig) -> Self {
+        let mut chunks = VecDeque::with_capacity(config.pipeline_depth);
+        chunks.push_back(LimitedCopyChunk::new(config.buffer_size));
         LimitedCopyBuffer {
             read_done: false,
-            buf: vec![0; config.buffer_size].into_boxed_slice(),
+            chunks,
+            spare_chunks: Vec::new(),
+            chunk_size: config.buffer_size,
+            pipeline_depth: config.pipeline_depth,
             yield_size: config.yield_size,
-            r_off: 0,
-            w_off: 0,
             total: 0,
             need_flush: false,
             active: false,
@@ -103,18 +179,67 @@ impl LimitedCopyBuffer {
         } else {
             buf.resize(buf.capacity(), 0);
         }
-        LimitedCopyBuffer {
-            read_done: false,
+        let mut chunks = VecDeque::with_capacity(config.pipeline_depth);
+        chunks.push_back(LimitedCopyChunk {
             buf: buf.into_boxed_slice(),
-            yield_size: config.yield_size,
             r_off,
             w_off: 0,
+        });
+        LimitedCopyBuffer {
+            read_done: false,
+            chunks,
+            spare_chunks: Vec::new(),
+            chunk_size: config.buffer_size,
+            pipeline_depth: config.pipeline_depth,
+            yield_size: config.yield_size,
             total: 0,
             need_flush: false,
             active: true, // as we have data
         }
     }
 
+    fn is_empty(&self) -> bool {
+        self.chunks.iter().all(|c| c.is_empty())
+    }
+
+    fn can_read(&self) -> bool {
+        if self.read_done {
+            return false;
+        }
+        match self.chunks.back() {
+            Some(c) => !c.is_full() || self.chunks.len() < self.pipeline_depth,
+            None => true,
+        }
+    }
+
+    /// drop all fully written chunks from the front, except for the last one
+    fn pop_written(&mut self) {
+        while self.chunks.len() > 1 && self.chunks[0].is_empty() {
+            if let Some(mut c) = self.chunks.pop_front() {
+                c.reset();
+                self.spare_chunks.push(c);
+            }
+        }
+        if let Some(c) = self.chunks.front_mut() {
+            if c.is_empty() {
+                // if empty, reset
+                c.reset();
+            }
+        }
+    }
+
+    fn advance_written(&mut self, mut n: usize) {
+        for c in self.chunks.iter_mut() {
+            let left = c.r_off - c.w_off;
+            if n <= left {
+                c.w_off += n;
+                return;
+            }
+            c.w_off = c.r_off;
+            n -= left;
+        }
+    }
+
     fn poll_fill_buf<R>(
         &mut self,
         cx: &mut Context<'_>,
@@ -123,49 +248,84 @@ impl LimitedCopyBuffer {
     where
         R: AsyncRead + ?Sized,
     {
-        let mut buf = ReadBuf::new(&mut self.buf);
-        buf.set_filled(self.r_off);
+        let need_new_chunk = match self.chunks.back() {
+            Some(c) => c.is_full(),
+            None => true,
+        };
+        if need_new_chunk {
+            let chunk = self
+                .spare_chunks
+                .pop()
+                .unwrap_or_else(|| LimitedCopyChunk::new(self.chunk_size));
+            self.chunks.push_back(chunk);
+        }
+        let Some(chunk) = self.chunks.back_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+
+        let mut buf = ReadBuf::new(&mut chunk.buf);
+        buf.set_filled(chunk.r_off);
 
         let res = reader.poll_read(cx, &mut buf);
         if let Poll::Ready(Ok(_)) = res {
             let filled_len = buf.filled().len();
-            if self.r_off == filled_len {
+            if chunk.r_off == filled_len {
                 self.read_done = true;
             } else {
-                self.r_off = filled_len;
+                chunk.r_off = filled_len;
                 self.active = true;
             }
         }
         res
     }
 
+    fn poll_write_chunks<W>(
+        &mut self,
+        cx: &mut Context<'_>,
+        writer: Pin<&mut W>,
+    ) -> Poll<io::Result<usize>>
+    where
+        W: AsyncWrite + ?Sized,
+    {
+        if self.chunks.len() > 1 && writer.is_write_vectored() {
+            let mut slices = [IoSlice::new(&[]); MAXIMUM_COPY_PIPELINE_DEPTH];
+            let mut count = 0;
+            for c in self.chunks.iter().filter(|c| !c.is_empty()) {
+                slices[count] = IoSlice::new(c.data());
+                count += 1;
+            }
+            writer.poll_write_vectored(cx, &slices[..count])
+        } else {
+            match self.chunks.front() {
+                Some(c) => writer.poll_write(cx, c.data()),
+                None => Poll::Ready(Ok(0)),
+            }
+        }
+    }
+
     fn poll_write_buf<R, W>(
         &mut self,
         cx: &mut Context<'_>,
-        reader: Pin<&mut R>,
+        mut reader: Pin<&mut R>,
         writer: Pin<&mut W>,
     ) -> Poll<Result<usize, LimitedCopyError>>
     where
         R: AsyncRead + ?Sized,
         W: AsyncWrite + ?Sized,
     {
-        match writer.poll_write(cx, &self.buf[self.w_off..self.r_off]) {
+        self.pop_written();
+        match self.poll_write_chunks(cx, writer) {
             Poll::Pending => {
-                // Top up the buffer towards full if we can read a bit more
+                // Top up the buffers towards full if we can read a bit more
                 // data - this should improve the chances of a large write
-                if !self.read_done && self.r_off < self.buf.len() {
-                    let left = self.r_off - self.w_off;
-                    if left < self.w_off {
-                        // copy small data to the begin of the buffer, so we can read more data
-                        unsafe {
-                            let ptr = self.buf.as_mut_ptr();
-                            let src_ptr = ptr.add(self.w_off);
-                            std::ptr::copy_nonoverlapping(src_ptr, ptr, left);
-                        }
-                        self.w_off = 0;
-                        self.r_off = left;
+                if self.can_read() && self.chunks.len() == 1 {
+                    if let Some(c) = self.chunks.front_mut() {
+                        c.compact();
                     }
-                    ready!(self.poll_fill_buf(cx, reader)).map_err(LimitedCopyError::ReadFailed)?;
+                }
+                while self.can_read() {
+                    ready!(self.poll_fill_buf(cx, reader.as_mut()))
+                        .map_err(LimitedCopyError::ReadFailed)?;
                 }
                 Poll::Pending
             }
@@ -175,7 +335,7 @@ impl LimitedCopyBuffer {
                 "write zero byte into writer",
             )))),
             Poll::Ready(Ok(n)) => {
-                self.w_off += n;
+                self.advance_written(n);
                 self.total += n as u64;
                 self.need_flush = true;
                 self.active = true;
@@ -197,13 +357,9 @@ impl LimitedCopyBuffer {
         let mut copy_this_round = 0usize;
         loop {
             if !self.read_done {
-                if self.w_off == self.r_off {
-                    // if empty, reset
-                    self.w_off = 0;
-                    self.r_off = 0;
-                }
+                self.pop_written();
 
-                if self.r_off < self.buf.len() {
+                if self.can_read() {
                     // read first
                     match self.poll_fill_buf(cx, reader.as_mut()) {
                         Poll::Ready(Ok(_)) => {}
@@ -211,7 +367,7 @@ impl LimitedCopyBuffer {
                             return Poll::Ready(Err(LimitedCopyError::ReadFailed(e)));
                         }
                         Poll::Pending => {
-                            if self.w_off >= self.r_off {
+                            if self.is_empty() {
                                 // no data to write
                                 if self.need_flush {
                                     ready!(writer.as_mut().poll_flush(cx))
@@ -227,7 +383,7 @@ impl LimitedCopyBuffer {
             }
 
             // If our buffer has some data, let's write it out!
-            while self.w_off < self.r_off {
+            while !self.is_empty() {
                 // return if write blocked. no need to try flush
                 let i = ready!(self.poll_write_buf(cx, reader.as_mut(), writer.as_mut()))?;
                 copy_this_round += i;
@@ -241,7 +397,7 @@ impl LimitedCopyBuffer {
 
             // If we've seen EOF and written all the data, flush out the
             // data and finish the transfer.
-            if self.read_done && self.w_off == self.r_off {
+            if self.read_done && self.is_empty() {
                 if self.need_flush {
                     ready!(writer.as_mut().poll_flush(cx))
                         .map_err(LimitedCopyError::WriteFailed)?;
@@ -255,18 +411,21 @@ impl LimitedCopyBuffer {
     where
         W: AsyncWrite + Unpin + ?Sized,
     {
-        if self.w_off < self.r_off {
-            writer
-                .write_all(&self.buf[self.w_off..self.r_off])
-                .await
-                .map_err(LimitedCopyError::WriteFailed)?;
-            self.total += (self.r_off - self.w_off) as u64;
-            self.w_off = self.r_off;
+        if self.is_empty() {
+            return Ok(());
+        }
+        for c in self.chunks.iter_mut().filter(|c| !c.is_empty()) {
             writer
-                .flush()
+                .write_all(c.data())
                 .await
                 .map_err(LimitedCopyError::WriteFailed)?;
+            self.total += (c.r_off - c.w_off) as u64;
+            c.w_off = c.r_off;
         }
+        writer
+            .flush()
+            .await
+            .map_err(LimitedCopyError::WriteFailed)?;
         Ok(())
     }
 }
@@ -306,7 +465,7 @@ where
 
     #[inline]
     pub fn no_cached_data(&self) -> bool {
-        self.buf.r_off == self.buf.w_off
+        self.buf.is_empty()
     }
 
     #[inline]
@@ -376,7 +535,7 @@ where
 
     #[inline]
     pub fn no_cached_data(&self) -> bool {
-        self.buf.r_off == self.buf.w_off
+        self.buf.is_empty()
     }
 
     #[inline]
@@ -427,3 +586,37 @@ where
             .poll_copy(cx, Pin::new(&mut me.reader), Pin::new(&mut *me.writer))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn copy_with_pipeline() {
+        let data: Vec<u8> = (0..100 * 1024).map(|i| (i % 251) as u8).collect();
+
+        let mut config = LimitedCopyConfig::default();
+        config.set_buffer_size(4096);
+        config.set_pipeline_depth(4);
+
+        let mut reader = data.as_slice();
+        let mut writer = Vec::new();
+        let copy = LimitedCopy::new(&mut reader, &mut writer, &config);
+        let n = copy.await.unwrap();
+        assert_eq!(n, data.len() as u64);
+        assert_eq!(writer, data);
+    }
+
+    #[tokio::test]
+    async fn copy_with_data() {
+        let mut config = LimitedCopyConfig::default();
+        config.set_pipeline_depth(2);
+
+        let mut reader: &[u8] = b"world";
+        let mut writer = Vec::new();
+        let copy = LimitedCopy::with_data(&mut reader, &mut writer, &config, b"hello ".to_vec());
+        let n = copy.await.unwrap();
+        assert_eq!(n, 11);
+        assert_eq!(writer.as_slice(), b"hello world");
+    }
+}
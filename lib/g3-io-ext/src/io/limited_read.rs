@@ -26,7 +26,7 @@ use pin_project::pin_project;
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio::time::{Instant, Sleep};
 
-use crate::limit::{StreamLimitInfo, StreamLimitResult};
+use crate::limit::{GlobalStreamLimiter, StreamLimitInfo, StreamLimitResult};
 
 pub trait LimitedReaderStats {
     fn add_read_bytes(&self, size: usize);
@@ -44,6 +44,7 @@ pub(crate) struct LimitedReaderState {
     delay: Pin<Box<Sleep>>,
     started: Instant,
     limit: StreamLimitInfo,
+    global_limit: Option<Arc<GlobalStreamLimiter>>,
     stats: ArcLimitedReaderStats,
 }
 
@@ -53,6 +54,7 @@ impl LimitedReaderState {
             delay: Box::pin(tokio::time::sleep(Duration::from_millis(0))),
             started: Instant::now(),
             limit: StreamLimitInfo::new(shift_millis, max_bytes),
+            global_limit: None,
             stats,
         }
     }
@@ -62,6 +64,7 @@ impl LimitedReaderState {
             delay: Box::pin(tokio::time::sleep(Duration::from_millis(0))),
             started: Instant::now(),
             limit: StreamLimitInfo::default(),
+            global_limit: None,
             stats,
         }
     }
@@ -75,6 +78,20 @@ impl LimitedReaderState {
         self.limit.reset(shift_millis, max_bytes, dur_millis);
     }
 
+    pub(crate) fn reset_token_bucket_limit(&mut self, rate: usize, burst: usize) {
+        let dur_millis = self.started.elapsed().as_millis() as u64;
+        self.limit.reset_token_bucket(rate, burst, dur_millis);
+    }
+
+    pub(crate) fn set_burst_limit(&mut self, burst: usize) {
+        let dur_millis = self.started.elapsed().as_millis() as u64;
+        self.limit.set_burst(burst, dur_millis);
+    }
+
+    pub(crate) fn set_global_limit(&mut self, limiter: Arc<GlobalStreamLimiter>) {
+        self.global_limit = Some(limiter);
+    }
+
     pub(crate) fn poll_read<R>(
         &mut self,
         reader: Pin<&mut R>,
@@ -84,32 +101,52 @@ impl LimitedReaderState {
     where
         R: AsyncRead,
     {
+        if !self.limit.is_set() && self.global_limit.is_none() {
+            let old_filled_len = buf.filled().len();
+            ready!(reader.poll_read(cx, buf))?;
+            let nr = buf.filled().len() - old_filled_len;
+            self.stats.add_read_bytes(nr);
+            return Poll::Ready(Ok(()));
+        }
+
+        let mut to_read = buf.remaining();
         if self.limit.is_set() {
             let dur_millis = self.started.elapsed().as_millis() as u64;
-            match self.limit.check(dur_millis, buf.remaining()) {
-                StreamLimitResult::AdvanceBy(len) => {
-                    let mut limited_buf = ReadBuf::new(buf.initialize_unfilled_to(len));
-                    ready!(reader.poll_read(cx, &mut limited_buf))?;
-                    let nr = limited_buf.filled().len();
-                    self.limit.set_advance(nr);
-                    buf.advance(nr);
-                    self.stats.add_read_bytes(nr);
-                    Poll::Ready(Ok(()))
-                }
+            match self.limit.check(dur_millis, to_read) {
+                StreamLimitResult::AdvanceBy(len) => to_read = len,
                 StreamLimitResult::DelayFor(ms) => {
                     self.delay
                         .as_mut()
                         .reset(self.started + Duration::from_millis(dur_millis + ms));
-                    self.delay.poll_unpin(cx).map(|_| Ok(()))
+                    return self.delay.poll_unpin(cx).map(|_| Ok(()));
+                }
+            }
+        }
+        if let Some(global) = &self.global_limit {
+            match global.check(to_read) {
+                StreamLimitResult::AdvanceBy(len) => to_read = len,
+                StreamLimitResult::DelayFor(ms) => {
+                    self.delay
+                        .as_mut()
+                        .reset(Instant::now() + Duration::from_millis(ms));
+                    return self.delay.poll_unpin(cx).map(|_| Ok(()));
                 }
             }
-        } else {
-            let old_filled_len = buf.filled().len();
-            ready!(reader.poll_read(cx, buf))?;
-            let nr = buf.filled().len() - old_filled_len;
-            self.stats.add_read_bytes(nr);
-            Poll::Ready(Ok(()))
         }
+
+        let mut limited_buf = ReadBuf::new(buf.initialize_unfilled_to(to_read));
+        let r = reader.poll_read(cx, &mut limited_buf);
+        let nr = limited_buf.filled().len();
+        if let Some(global) = &self.global_limit {
+            global.release(to_read - nr);
+        }
+        ready!(r)?;
+        if self.limit.is_set() {
+            self.limit.set_advance(nr);
+        }
+        buf.advance(nr);
+        self.stats.add_read_bytes(nr);
+        Poll::Ready(Ok(()))
     }
 }
 
@@ -145,6 +182,24 @@ impl<R> LimitedReader<R> {
         self.state.reset_limit(shift_millis, max_bytes);
     }
 
+    /// Use token bucket limit, with `rate` bytes per second and a burst size of `burst` bytes
+    #[inline]
+    pub fn reset_token_bucket_limit(&mut self, rate: usize, burst: usize) {
+        self.state.reset_token_bucket_limit(rate, burst);
+    }
+
+    /// Allow a burst of `burst` bytes for the fixed window limit, 0 means no burst
+    #[inline]
+    pub fn set_burst_limit(&mut self, burst: usize) {
+        self.state.set_burst_limit(burst);
+    }
+
+    /// Add a limiter that is shared with other streams
+    #[inline]
+    pub fn set_global_limit(&mut self, limiter: Arc<GlobalStreamLimiter>) {
+        self.state.set_global_limit(limiter);
+    }
+
     pub fn into_inner(self) -> R {
         self.inner
     }
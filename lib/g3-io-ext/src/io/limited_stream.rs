@@ -76,6 +76,26 @@ impl<S> LimitedStream<S> {
         self.writer_state.reset_limit(shift_millis, write_max_bytes);
     }
 
+    /// Use token bucket limit, the rates are bytes per second and the bursts are in bytes
+    pub fn reset_token_bucket_limit(
+        &mut self,
+        read_rate: usize,
+        read_burst: usize,
+        write_rate: usize,
+        write_burst: usize,
+    ) {
+        self.reader_state
+            .reset_token_bucket_limit(read_rate, read_burst);
+        self.writer_state
+            .reset_token_bucket_limit(write_rate, write_burst);
+    }
+
+    /// Allow bursts for the fixed window limit, 0 means no burst
+    pub fn set_burst_limit(&mut self, read_burst: usize, write_burst: usize) {
+        self.reader_state.set_burst_limit(read_burst);
+        self.writer_state.set_burst_limit(write_burst);
+    }
+
     pub fn into_inner(self) -> S {
         self.inner
     }
@@ -26,7 +26,7 @@ use pin_project::pin_project;
 use tokio::io::AsyncWrite;
 use tokio::time::{Instant, Sleep};
 
-use crate::limit::{StreamLimitInfo, StreamLimitResult};
+use crate::limit::{GlobalStreamLimiter, StreamLimitInfo, StreamLimitResult};
 
 pub trait LimitedWriterStats {
     fn add_write_bytes(&self, size: usize);
@@ -44,6 +44,7 @@ pub(crate) struct LimitedWriterState {
     delay: Pin<Box<Sleep>>,
     started: Instant,
     limit: StreamLimitInfo,
+    global_limit: Option<Arc<GlobalStreamLimiter>>,
     stats: ArcLimitedWriterStats,
 }
 
@@ -53,6 +54,7 @@ impl LimitedWriterState {
             delay: Box::pin(tokio::time::sleep(Duration::from_millis(0))),
             started: Instant::now(),
             limit: StreamLimitInfo::new(shift_millis, max_bytes),
+            global_limit: None,
             stats,
         }
     }
@@ -62,6 +64,7 @@ impl LimitedWriterState {
             delay: Box::pin(tokio::time::sleep(Duration::from_millis(0))),
             started: Instant::now(),
             limit: StreamLimitInfo::default(),
+            global_limit: None,
             stats,
         }
     }
@@ -75,9 +78,23 @@ impl LimitedWriterState {
         self.limit.reset(shift_millis, max_bytes, dur_millis);
     }
 
+    pub(crate) fn reset_token_bucket_limit(&mut self, rate: usize, burst: usize) {
+        let dur_millis = self.started.elapsed().as_millis() as u64;
+        self.limit.reset_token_bucket(rate, burst, dur_millis);
+    }
+
+    pub(crate) fn set_burst_limit(&mut self, burst: usize) {
+        let dur_millis = self.started.elapsed().as_millis() as u64;
+        self.limit.set_burst(burst, dur_millis);
+    }
+
+    pub(crate) fn set_global_limit(&mut self, limiter: Arc<GlobalStreamLimiter>) {
+        self.global_limit = Some(limiter);
+    }
+
     #[inline]
     pub(crate) fn limit_is_set(&self) -> bool {
-        self.limit.is_set()
+        self.limit.is_set() || self.global_limit.is_some()
     }
 
     pub(crate) fn poll_write<W>(
@@ -89,27 +106,51 @@ impl LimitedWriterState {
     where
         W: AsyncWrite,
     {
+        if !self.limit_is_set() {
+            let nw = ready!(writer.poll_write(cx, buf))?;
+            self.stats.add_write_bytes(nw);
+            return Poll::Ready(Ok(nw));
+        }
+
+        let mut to_write = buf.len();
         if self.limit.is_set() {
             let dur_millis = self.started.elapsed().as_millis() as u64;
-            match self.limit.check(dur_millis, buf.len()) {
-                StreamLimitResult::AdvanceBy(len) => {
-                    let nw = ready!(writer.poll_write(cx, &buf[..len]))?;
-                    self.limit.set_advance(nw);
-                    self.stats.add_write_bytes(nw);
-                    Poll::Ready(Ok(nw))
-                }
+            match self.limit.check(dur_millis, to_write) {
+                StreamLimitResult::AdvanceBy(len) => to_write = len,
                 StreamLimitResult::DelayFor(ms) => {
                     self.delay
                         .as_mut()
                         .reset(self.started + Duration::from_millis(dur_millis + ms));
-                    self.delay.poll_unpin(cx).map(|_| Ok(0))
+                    return self.delay.poll_unpin(cx).map(|_| Ok(0));
                 }
             }
-        } else {
-            let nw = ready!(writer.poll_write(cx, buf))?;
-            self.stats.add_write_bytes(nw);
-            Poll::Ready(Ok(nw))
         }
+        if let Some(global) = &self.global_limit {
+            match global.check(to_write) {
+                StreamLimitResult::AdvanceBy(len) => to_write = len,
+                StreamLimitResult::DelayFor(ms) => {
+                    self.delay
+                        .as_mut()
+                        .reset(Instant::now() + Duration::from_millis(ms));
+                    return self.delay.poll_unpin(cx).map(|_| Ok(0));
+                }
+            }
+        }
+
+        let r = writer.poll_write(cx, &buf[..to_write]);
+        if let Some(global) = &self.global_limit {
+            let nw = match &r {
+                Poll::Ready(Ok(nw)) => *nw,
+                _ => 0,
+            };
+            global.release(to_write - nw);
+        }
+        let nw = ready!(r)?;
+        if self.limit.is_set() {
+            self.limit.set_advance(nw);
+        }
+        self.stats.add_write_bytes(nw);
+        Poll::Ready(Ok(nw))
     }
 }
 
@@ -145,6 +186,24 @@ impl<W: AsyncWrite> LimitedWriter<W> {
         self.state.reset_limit(shift_millis, max_bytes)
     }
 
+    /// Use token bucket limit, with `rate` bytes per second and a burst size of `burst` bytes
+    #[inline]
+    pub fn reset_token_bucket_limit(&mut self, rate: usize, burst: usize) {
+        self.state.reset_token_bucket_limit(rate, burst)
+    }
+
+    /// Allow a burst of `burst` bytes for the fixed window limit, 0 means no burst
+    #[inline]
+    pub fn set_burst_limit(&mut self, burst: usize) {
+        self.state.set_burst_limit(burst)
+    }
+
+    /// Add a limiter that is shared with other streams
+    #[inline]
+    pub fn set_global_limit(&mut self, limiter: Arc<GlobalStreamLimiter>) {
+        self.state.set_global_limit(limiter)
+    }
+
     pub fn into_inner(self) -> W {
         self.inner
     }
@@ -15,12 +15,14 @@
  */
 
 mod aggregate;
+mod delayed_read;
 mod limited_copy;
 mod limited_read;
 mod limited_stream;
 mod limited_write;
 
 pub use aggregate::AggregatedIo;
+pub use delayed_read::DelayedReader;
 pub use limited_copy::{LimitedCopy, LimitedCopyConfig, LimitedCopyError, ROwnedLimitedCopy};
 pub use limited_read::{
     ArcLimitedReaderStats, LimitedReader, LimitedReaderStats, NilLimitedReaderStats, SizedReader,
@@ -26,8 +26,8 @@ pub use cache::{
 };
 pub use io::*;
 pub use limit::{
-    DatagramLimitInfo, DatagramLimitResult, StreamLimitInfo, StreamLimitResult,
-    ThreadedCountLimitInfo,
+    DatagramLimitInfo, DatagramLimitResult, DatagramTokenBucket, GlobalStreamLimiter,
+    StreamLimitInfo, StreamLimitResult, StreamTokenBucket, ThreadedCountLimitInfo,
 };
 pub use listen::{LimitedTcpListener, LimitedTlsListener};
 pub use udp::*;
@@ -17,6 +17,7 @@
 use std::io::{IoSlice, IoSliceMut};
 
 use super::FixedWindow;
+use crate::limit::DatagramTokenBucket;
 use crate::RecvMsgBuf;
 
 pub trait HasPacketSize {
@@ -48,6 +49,7 @@ impl HasPacketSize for quinn::udp::Transmit {
     }
 }
 
+#[derive(Debug, Eq, PartialEq)]
 pub enum DatagramLimitResult {
     Advance(usize),
     DelayFor(u64),
@@ -56,6 +58,8 @@ pub enum DatagramLimitResult {
 #[derive(Default)]
 pub struct DatagramLimitInfo {
     window: FixedWindow,
+    // use token bucket instead of fixed window if set
+    bucket: Option<DatagramTokenBucket>,
 
     // direct conf entry
     max_packets: usize,
@@ -71,6 +75,7 @@ impl DatagramLimitInfo {
     pub fn new(shift_millis: u8, max_packets: usize, max_bytes: usize) -> Self {
         DatagramLimitInfo {
             window: FixedWindow::new(shift_millis, None),
+            bucket: None,
             max_packets,
             max_bytes,
             time_slice_id: 0,
@@ -87,6 +92,7 @@ impl DatagramLimitInfo {
         cur_millis: u64,
     ) {
         self.window = FixedWindow::new(shift_millis, Some(cur_millis));
+        self.bucket = None;
         self.max_packets = max_packets;
         self.max_bytes = max_bytes;
         self.time_slice_id = self.window.slice_id(cur_millis);
@@ -94,12 +100,56 @@ impl DatagramLimitInfo {
         self.cur_bytes = 0;
     }
 
+    /// Switch to token bucket, the rates are per second, and 0 means no limit
+    pub fn reset_token_bucket(
+        &mut self,
+        packet_rate: usize,
+        packet_burst: usize,
+        byte_rate: usize,
+        byte_burst: usize,
+        cur_millis: u64,
+    ) {
+        self.window = FixedWindow::default();
+        self.bucket = Some(DatagramTokenBucket::new(
+            packet_rate,
+            packet_burst,
+            byte_rate,
+            byte_burst,
+            cur_millis,
+        ));
+        self.cur_packets = 0;
+        self.cur_bytes = 0;
+    }
+
+    /// Switch the fixed window limit to a token bucket with the same average rates,
+    /// which allows a burst of `burst_packets` and `burst_bytes`. They won't be smaller than
+    /// the max values in one time slice. Nothing will be changed if both bursts are 0 or no
+    /// limit is set.
+    pub fn set_burst(&mut self, burst_packets: usize, burst_bytes: usize, cur_millis: u64) {
+        if (burst_packets == 0 && burst_bytes == 0) || !self.window.enabled() {
+            return;
+        }
+        let packet_rate = self.window.rate_per_second(self.max_packets);
+        let byte_rate = self.window.rate_per_second(self.max_bytes);
+        self.reset_token_bucket(
+            packet_rate,
+            burst_packets.max(self.max_packets),
+            byte_rate,
+            burst_bytes.max(self.max_bytes),
+            cur_millis,
+        );
+    }
+
     #[inline]
     pub fn is_set(&self) -> bool {
-        self.window.enabled()
+        self.bucket.is_some() || self.window.enabled()
     }
 
     pub fn check_packet(&mut self, cur_millis: u64, buf_size: usize) -> DatagramLimitResult {
+        if let Some(bucket) = &mut self.bucket {
+            return bucket.check_packet(cur_millis, buf_size);
+        }
+
         let time_slice_id = self.window.slice_id(cur_millis);
         if self.time_slice_id != time_slice_id {
             self.cur_bytes = 0;
@@ -125,6 +175,10 @@ impl DatagramLimitInfo {
     where
         P: HasPacketSize,
     {
+        if let Some(bucket) = &mut self.bucket {
+            return bucket.check_packets(cur_millis, packets);
+        }
+
         let time_slice_id = self.window.slice_id(cur_millis);
         if self.time_slice_id != time_slice_id {
             self.cur_bytes = 0;
@@ -167,7 +221,11 @@ impl DatagramLimitInfo {
 
     #[inline]
     pub fn set_advance(&mut self, packets: usize, size: usize) {
-        self.cur_packets += packets;
-        self.cur_bytes += size;
+        if let Some(bucket) = &mut self.bucket {
+            bucket.set_advance(packets, size);
+        } else {
+            self.cur_packets += packets;
+            self.cur_bytes += size;
+        }
     }
 }
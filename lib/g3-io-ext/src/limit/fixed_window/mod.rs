@@ -22,7 +22,7 @@ pub use count::ThreadedCountLimitInfo;
 mod datagram;
 mod stream;
 
-pub use datagram::{DatagramLimitInfo, DatagramLimitResult};
+pub use datagram::{DatagramLimitInfo, DatagramLimitResult, HasPacketSize};
 pub use stream::{StreamLimitInfo, StreamLimitResult};
 
 #[derive(Clone, Copy)]
@@ -79,4 +79,13 @@ impl FixedWindow {
     fn delay(&self, cur_millis: u64) -> u64 {
         self.max_delay_millis - (self.time_value_mask & cur_millis)
     }
+
+    /// get the average rate per second if `max` is allowed in each time slice
+    fn rate_per_second(&self, max: usize) -> usize {
+        if max == 0 {
+            return 0;
+        }
+        let rate = max.saturating_mul(1000) / self.max_delay_millis as usize;
+        rate.max(1)
+    }
 }
@@ -15,6 +15,7 @@
  */
 
 use super::FixedWindow;
+use crate::limit::StreamTokenBucket;
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum StreamLimitResult {
@@ -25,6 +26,8 @@ pub enum StreamLimitResult {
 #[derive(Default)]
 pub struct StreamLimitInfo {
     window: FixedWindow,
+    // use token bucket instead of fixed window if set
+    bucket: Option<StreamTokenBucket>,
 
     // direct conf entry
     max_bytes: usize,
@@ -38,25 +41,56 @@ impl StreamLimitInfo {
     pub fn new(shift_millis: u8, max_bytes: usize) -> Self {
         StreamLimitInfo {
             window: FixedWindow::new(shift_millis, None),
+            bucket: None,
             max_bytes,
             time_slice_id: 0,
             cur_bytes: 0,
         }
     }
 
+    /// Create a token bucket limiter, with `rate` bytes per second and `burst` bytes
+    pub fn new_token_bucket(rate: usize, burst: usize) -> Self {
+        StreamLimitInfo {
+            bucket: Some(StreamTokenBucket::new(rate, burst, 0)),
+            ..Default::default()
+        }
+    }
+
     pub fn reset(&mut self, shift_millis: u8, max_bytes: usize, cur_millis: u64) {
         self.window = FixedWindow::new(shift_millis, Some(cur_millis));
+        self.bucket = None;
         self.max_bytes = max_bytes;
         self.time_slice_id = self.window.slice_id(cur_millis);
         self.cur_bytes = 0;
     }
 
+    pub fn reset_token_bucket(&mut self, rate: usize, burst: usize, cur_millis: u64) {
+        self.window = FixedWindow::default();
+        self.bucket = Some(StreamTokenBucket::new(rate, burst, cur_millis));
+        self.cur_bytes = 0;
+    }
+
+    /// Switch the fixed window limit to a token bucket with the same average rate,
+    /// which allows a burst of `burst` bytes. It won't be smaller than the max bytes
+    /// in one time slice. Nothing will be changed if `burst` is 0 or no limit is set.
+    pub fn set_burst(&mut self, burst: usize, cur_millis: u64) {
+        if burst == 0 || !self.window.enabled() {
+            return;
+        }
+        let rate = self.window.rate_per_second(self.max_bytes);
+        self.reset_token_bucket(rate, burst.max(self.max_bytes), cur_millis);
+    }
+
     #[inline]
     pub fn is_set(&self) -> bool {
-        self.window.enabled()
+        self.bucket.is_some() || self.window.enabled()
     }
 
     pub fn check(&mut self, cur_millis: u64, to_advance: usize) -> StreamLimitResult {
+        if let Some(bucket) = &mut self.bucket {
+            return bucket.check(cur_millis, to_advance);
+        }
+
         let time_slice_id = self.window.slice_id(cur_millis);
         if self.time_slice_id != time_slice_id {
             self.cur_bytes = 0;
@@ -74,7 +108,11 @@ impl StreamLimitInfo {
 
     #[inline]
     pub fn set_advance(&mut self, size: usize) {
-        self.cur_bytes += size;
+        if let Some(bucket) = &mut self.bucket {
+            bucket.set_advance(size);
+        } else {
+            self.cur_bytes += size;
+        }
     }
 }
 
@@ -107,5 +145,31 @@ mod tests {
         limit.set_advance(900);
     }
 
+    #[test]
+    fn burst() {
+        // 1024 bytes in each 1024ms, which is 1000 bytes per second
+        let mut limit = StreamLimitInfo::new(10, 1024);
+        limit.set_burst(4096, 0);
+        assert_eq!(limit.check(0, 5000), StreamLimitResult::AdvanceBy(4096));
+        limit.set_advance(4096);
+        assert_eq!(limit.check(0, 100), StreamLimitResult::DelayFor(1));
+        assert_eq!(limit.check(100, 200), StreamLimitResult::AdvanceBy(100));
+
+        // the burst should not be smaller than the limit in one time slice
+        let mut limit = StreamLimitInfo::new(10, 1024);
+        limit.set_burst(100, 0);
+        assert_eq!(limit.check(0, 2000), StreamLimitResult::AdvanceBy(1024));
+
+        // no burst set, still use the fixed window
+        let mut limit = StreamLimitInfo::new(10, 1024);
+        limit.set_burst(0, 0);
+        assert_eq!(limit.check(0, 2000), StreamLimitResult::AdvanceBy(1024));
+        limit.set_advance(1024);
+        assert!(matches!(
+            limit.check(10, 100),
+            StreamLimitResult::DelayFor(_)
+        ));
+    }
+
     // TODO add reset test case
 }
@@ -0,0 +1,156 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Mutex;
+use std::time::Instant;
+
+use g3_types::net::GlobalStreamSpeedLimitConfig;
+
+use super::{StreamLimitResult, TokenBucket};
+
+struct LimiterState {
+    config: GlobalStreamSpeedLimitConfig,
+    bucket: TokenBucket,
+}
+
+/// A token bucket limiter that can be shared by many streams
+pub struct GlobalStreamLimiter {
+    started: Instant,
+    state: Mutex<LimiterState>,
+}
+
+impl GlobalStreamLimiter {
+    pub fn new(config: GlobalStreamSpeedLimitConfig) -> Self {
+        GlobalStreamLimiter {
+            started: Instant::now(),
+            state: Mutex::new(LimiterState {
+                config,
+                bucket: TokenBucket::new(
+                    config.replenish_bytes() as usize,
+                    config.max_burst_bytes() as usize,
+                    0,
+                ),
+            }),
+        }
+    }
+
+    pub fn config(&self) -> GlobalStreamSpeedLimitConfig {
+        self.state.lock().unwrap().config
+    }
+
+    /// Update the config in place, so the streams that are already using this
+    /// limiter will share the same bucket with the new ones
+    pub fn reload(&self, config: GlobalStreamSpeedLimitConfig) {
+        let mut state = self.state.lock().unwrap();
+        if state.config == config {
+            return;
+        }
+        state.bucket.set_rate(
+            config.replenish_bytes() as usize,
+            config.max_burst_bytes() as usize,
+        );
+        state.config = config;
+    }
+
+    /// Take tokens for at most `to_advance` bytes, the unused ones should be
+    /// returned by calling `release`
+    pub fn check(&self, to_advance: usize) -> StreamLimitResult {
+        let cur_millis = self.started.elapsed().as_millis() as u64;
+        self.check_at(cur_millis, to_advance)
+    }
+
+    fn check_at(&self, cur_millis: u64, to_advance: usize) -> StreamLimitResult {
+        let mut state = self.state.lock().unwrap();
+        let bucket = &mut state.bucket;
+        bucket.refill(cur_millis);
+
+        let tokens = bucket.available();
+        if tokens == 0 {
+            StreamLimitResult::DelayFor(bucket.delay().max(1))
+        } else {
+            let len = tokens.min(to_advance);
+            bucket.consume(len);
+            StreamLimitResult::AdvanceBy(len)
+        }
+    }
+
+    pub fn release(&self, size: usize) {
+        if size == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.bucket.release(size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn burst_and_release() {
+        let limiter = GlobalStreamLimiter::new(GlobalStreamSpeedLimitConfig::per_second(1000));
+        assert_eq!(limiter.check_at(0, 600), StreamLimitResult::AdvanceBy(600));
+        assert_eq!(limiter.check_at(0, 600), StreamLimitResult::AdvanceBy(400));
+        assert!(matches!(
+            limiter.check_at(0, 1),
+            StreamLimitResult::DelayFor(_)
+        ));
+        limiter.release(100);
+        assert_eq!(limiter.check_at(0, 600), StreamLimitResult::AdvanceBy(100));
+    }
+
+    #[test]
+    fn replenish() {
+        let limiter = GlobalStreamLimiter::new(GlobalStreamSpeedLimitConfig::per_second(1000));
+        assert_eq!(
+            limiter.check_at(0, 1000),
+            StreamLimitResult::AdvanceBy(1000)
+        );
+        assert_eq!(limiter.check_at(0, 1), StreamLimitResult::DelayFor(1));
+        assert_eq!(
+            limiter.check_at(100, 1000),
+            StreamLimitResult::AdvanceBy(100)
+        );
+        assert_eq!(
+            limiter.check_at(2000, 2000),
+            StreamLimitResult::AdvanceBy(1000)
+        );
+    }
+
+    #[test]
+    fn reload() {
+        let limiter = GlobalStreamLimiter::new(GlobalStreamSpeedLimitConfig::per_second(1000));
+        assert_eq!(limiter.check_at(0, 600), StreamLimitResult::AdvanceBy(600));
+
+        // the remaining tokens are kept
+        limiter.reload(GlobalStreamSpeedLimitConfig::per_second(2000));
+        assert_eq!(limiter.config().replenish_bytes(), 2000);
+        assert_eq!(limiter.check_at(0, 1000), StreamLimitResult::AdvanceBy(400));
+        assert_eq!(
+            limiter.check_at(100, 1000),
+            StreamLimitResult::AdvanceBy(200)
+        );
+
+        // the remaining tokens are capped by the new burst size
+        assert_eq!(limiter.check_at(2000, 0), StreamLimitResult::AdvanceBy(0));
+        limiter.reload(GlobalStreamSpeedLimitConfig::per_second(500));
+        assert_eq!(
+            limiter.check_at(2000, 1000),
+            StreamLimitResult::AdvanceBy(500)
+        );
+    }
+}
@@ -15,7 +15,12 @@
  */
 
 mod fixed_window;
+mod global;
+mod token_bucket;
 pub use fixed_window::{
     DatagramLimitInfo, DatagramLimitResult, StreamLimitInfo, StreamLimitResult,
     ThreadedCountLimitInfo,
 };
+pub use global::GlobalStreamLimiter;
+pub(crate) use token_bucket::TokenBucket;
+pub use token_bucket::{DatagramTokenBucket, StreamTokenBucket};
@@ -0,0 +1,212 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::TokenBucket;
+use crate::limit::fixed_window::HasPacketSize;
+use crate::limit::DatagramLimitResult;
+
+/// Token bucket limiter for datagrams.
+///
+/// The packet and byte limits are optional, and a value of 0 means no limit. A single packet
+/// larger than the remaining byte tokens will still be allowed if there are tokens left, and
+/// the debt will be paid back before the next packet.
+#[derive(Clone, Copy)]
+pub struct DatagramTokenBucket {
+    packets: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+impl DatagramTokenBucket {
+    pub fn new(
+        packet_rate: usize,
+        packet_burst: usize,
+        byte_rate: usize,
+        byte_burst: usize,
+        cur_millis: u64,
+    ) -> Self {
+        let packets =
+            (packet_rate > 0).then(|| TokenBucket::new(packet_rate, packet_burst, cur_millis));
+        let bytes = (byte_rate > 0).then(|| TokenBucket::new(byte_rate, byte_burst, cur_millis));
+        DatagramTokenBucket { packets, bytes }
+    }
+
+    fn refill(&mut self, cur_millis: u64) -> Option<u64> {
+        let mut delay = 0;
+        if let Some(bucket) = &mut self.packets {
+            bucket.refill(cur_millis);
+            delay = bucket.delay();
+        }
+        if let Some(bucket) = &mut self.bytes {
+            bucket.refill(cur_millis);
+            delay = delay.max(bucket.delay());
+        }
+        if delay > 0 {
+            Some(delay)
+        } else {
+            None
+        }
+    }
+
+    pub fn check_packet(&mut self, cur_millis: u64, _buf_size: usize) -> DatagramLimitResult {
+        if let Some(delay) = self.refill(cur_millis) {
+            return DatagramLimitResult::DelayFor(delay);
+        }
+        // the real advance size should be set via set_advance_size() method by caller
+
+        DatagramLimitResult::Advance(1)
+    }
+
+    pub fn check_packets<P>(&mut self, cur_millis: u64, packets: &[P]) -> DatagramLimitResult
+    where
+        P: HasPacketSize,
+    {
+        if let Some(delay) = self.refill(cur_millis) {
+            return DatagramLimitResult::DelayFor(delay);
+        }
+
+        let mut pkt_count = packets.len();
+        if let Some(bucket) = &self.packets {
+            pkt_count = pkt_count.min(bucket.available());
+        }
+
+        if let Some(bucket) = &self.bytes {
+            let mut total_size = 0usize;
+            for (i, p) in packets.iter().enumerate().take(pkt_count) {
+                // the packet is allowed as long as there are tokens left before it
+                if !bucket.allows(total_size) {
+                    return DatagramLimitResult::Advance(i);
+                }
+                total_size += p.packet_size();
+            }
+        }
+        // the real advance size should be set via set_advance_size() method by caller
+
+        DatagramLimitResult::Advance(pkt_count)
+    }
+
+    #[inline]
+    pub fn set_advance(&mut self, packets: usize, size: usize) {
+        if let Some(bucket) = &mut self.packets {
+            bucket.consume(packets);
+        }
+        if let Some(bucket) = &mut self.bytes {
+            bucket.consume(size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::IoSlice;
+
+    #[test]
+    fn packet_limit() {
+        // 10 packets per second, 1 packet every 100 milliseconds
+        let mut limit = DatagramTokenBucket::new(10, 5, 0, 0, 0);
+        let buf = [0u8; 100];
+        let packets = [IoSlice::new(&buf); 8];
+        assert_eq!(
+            limit.check_packets(0, &packets),
+            DatagramLimitResult::Advance(5)
+        );
+        limit.set_advance(5, 500);
+        assert_eq!(
+            limit.check_packet(0, 100),
+            DatagramLimitResult::DelayFor(100)
+        );
+        assert_eq!(
+            limit.check_packet(50, 100),
+            DatagramLimitResult::DelayFor(50)
+        );
+        assert_eq!(
+            limit.check_packets(100, &packets),
+            DatagramLimitResult::Advance(1)
+        );
+        limit.set_advance(1, 100);
+        // idle for a long time, the burst should be capped
+        assert_eq!(
+            limit.check_packets(10_000, &packets),
+            DatagramLimitResult::Advance(5)
+        );
+    }
+
+    #[test]
+    fn byte_limit() {
+        // 1000 bytes per second, 1 byte every millisecond
+        let mut limit = DatagramTokenBucket::new(0, 0, 1000, 1000, 0);
+        let buf = [0u8; 400];
+        let packets = [IoSlice::new(&buf); 4];
+        // the 3rd packet is allowed as there are tokens left before it
+        assert_eq!(
+            limit.check_packets(0, &packets),
+            DatagramLimitResult::Advance(3)
+        );
+        limit.set_advance(3, 1200);
+        // the debt should be paid back first
+        assert_eq!(
+            limit.check_packet(0, 400),
+            DatagramLimitResult::DelayFor(201)
+        );
+        assert_eq!(
+            limit.check_packets(100, &packets),
+            DatagramLimitResult::DelayFor(101)
+        );
+        assert_eq!(
+            limit.check_packets(201, &packets),
+            DatagramLimitResult::Advance(1)
+        );
+        limit.set_advance(1, 400);
+        assert_eq!(
+            limit.check_packets(700, &packets),
+            DatagramLimitResult::Advance(1)
+        );
+    }
+
+    #[test]
+    fn packet_and_byte_limit() {
+        let mut limit = DatagramTokenBucket::new(100, 10, 1000, 1000, 0);
+        let buf = [0u8; 50];
+        let packets = [IoSlice::new(&buf); 20];
+        assert_eq!(
+            limit.check_packets(0, &packets),
+            DatagramLimitResult::Advance(10)
+        );
+        limit.set_advance(10, 500);
+        // packet tokens exhausted, byte tokens left
+        assert_eq!(
+            limit.check_packets(0, &packets),
+            DatagramLimitResult::DelayFor(10)
+        );
+        assert_eq!(
+            limit.check_packets(10, &packets),
+            DatagramLimitResult::Advance(1)
+        );
+    }
+
+    #[test]
+    fn no_limit() {
+        let mut limit = DatagramTokenBucket::new(0, 0, 0, 0, 0);
+        let buf = [0u8; 1000];
+        let packets = [IoSlice::new(&buf); 100];
+        assert_eq!(
+            limit.check_packets(0, &packets),
+            DatagramLimitResult::Advance(100)
+        );
+        limit.set_advance(100, 100_000);
+        assert_eq!(limit.check_packet(0, 1000), DatagramLimitResult::Advance(1));
+    }
+}
@@ -0,0 +1,121 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+mod datagram;
+mod stream;
+
+pub use datagram::DatagramTokenBucket;
+pub use stream::StreamTokenBucket;
+
+/// tokens are recorded in milli units, so the refill for each millisecond is exactly the rate
+const TOKEN_SCALE: i64 = 1000;
+
+#[derive(Clone, Copy)]
+pub(crate) struct TokenBucket {
+    rate: i64,  // tokens per second, also milli tokens per millisecond
+    burst: i64, // in milli tokens
+    tokens: i64,
+    last_millis: u64,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(rate: usize, burst: usize, cur_millis: u64) -> Self {
+        let rate = Self::scaled_rate(rate);
+        let burst = Self::scaled_burst(burst);
+        TokenBucket {
+            rate,
+            burst,
+            tokens: burst, // start with a full bucket
+            last_millis: cur_millis,
+        }
+    }
+
+    fn scaled_rate(rate: usize) -> i64 {
+        i64::try_from(rate).unwrap_or(i64::MAX / TOKEN_SCALE).max(1)
+    }
+
+    fn scaled_burst(burst: usize) -> i64 {
+        i64::try_from(burst)
+            .unwrap_or(i64::MAX)
+            .clamp(1, i64::MAX / TOKEN_SCALE)
+            .saturating_mul(TOKEN_SCALE)
+    }
+
+    /// change the rate and burst size, the remaining tokens (or debt) will be kept
+    pub(crate) fn set_rate(&mut self, rate: usize, burst: usize) {
+        self.rate = Self::scaled_rate(rate);
+        self.burst = Self::scaled_burst(burst);
+        self.tokens = self.tokens.min(self.burst);
+    }
+
+    pub(crate) fn refill(&mut self, cur_millis: u64) {
+        if cur_millis <= self.last_millis {
+            return;
+        }
+        let elapsed = i64::try_from(cur_millis - self.last_millis).unwrap_or(i64::MAX);
+        self.tokens = self
+            .tokens
+            .saturating_add(elapsed.saturating_mul(self.rate))
+            .min(self.burst);
+        self.last_millis = cur_millis;
+    }
+
+    /// get the count of whole tokens, it will be 0 if in debt
+    pub(crate) fn available(&self) -> usize {
+        usize::try_from(self.tokens / TOKEN_SCALE).unwrap_or(0)
+    }
+
+    /// check if the remaining tokens are still positive after `used` tokens taken
+    fn allows(&self, used: usize) -> bool {
+        let used = i64::try_from(used).unwrap_or(i64::MAX);
+        self.tokens > used.saturating_mul(TOKEN_SCALE)
+    }
+
+    /// take tokens out, the bucket may go into debt, which will be paid back by later refills
+    pub(crate) fn consume(&mut self, count: usize) {
+        let count = i64::try_from(count).unwrap_or(i64::MAX);
+        self.tokens = self
+            .tokens
+            .saturating_sub(count.saturating_mul(TOKEN_SCALE));
+    }
+
+    /// put unused tokens back, the bucket won't be filled over the burst size
+    pub(crate) fn release(&mut self, count: usize) {
+        let count = i64::try_from(count).unwrap_or(i64::MAX);
+        self.tokens = self
+            .tokens
+            .saturating_add(count.saturating_mul(TOKEN_SCALE))
+            .min(self.burst);
+    }
+
+    /// get the milliseconds to wait until at least one whole token is available
+    pub(crate) fn delay(&self) -> u64 {
+        self.delay_for(1)
+    }
+
+    /// get the milliseconds to wait until at least `count` whole tokens are available
+    pub(crate) fn delay_for(&self, count: usize) -> u64 {
+        let count = i64::try_from(count).unwrap_or(i64::MAX);
+        let need = count
+            .saturating_mul(TOKEN_SCALE)
+            .saturating_sub(self.tokens);
+        if need <= 0 {
+            return 0;
+        }
+        let millis = need.saturating_add(self.rate - 1) / self.rate;
+        millis as u64
+    }
+}
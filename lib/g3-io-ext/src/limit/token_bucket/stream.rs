@@ -0,0 +1,87 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::TokenBucket;
+use crate::limit::StreamLimitResult;
+
+/// Token bucket limiter for streams.
+///
+/// The bytes can be sent at the `rate` per second, and a burst up to `burst` bytes is allowed
+/// after the stream has been idle for a while.
+#[derive(Clone, Copy)]
+pub struct StreamTokenBucket {
+    bucket: TokenBucket,
+}
+
+impl StreamTokenBucket {
+    pub fn new(rate: usize, burst: usize, cur_millis: u64) -> Self {
+        StreamTokenBucket {
+            bucket: TokenBucket::new(rate, burst, cur_millis),
+        }
+    }
+
+    pub fn check(&mut self, cur_millis: u64, to_advance: usize) -> StreamLimitResult {
+        self.bucket.refill(cur_millis);
+        let max = self.bucket.available();
+        if max == 0 {
+            StreamLimitResult::DelayFor(self.bucket.delay())
+        } else {
+            StreamLimitResult::AdvanceBy(to_advance.min(max))
+        }
+    }
+
+    #[inline]
+    pub fn set_advance(&mut self, size: usize) {
+        self.bucket.consume(size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_routine() {
+        // 1000 bytes per second, with burst 500
+        let mut limit = StreamTokenBucket::new(1000, 500, 0);
+        // the bucket is full at start
+        assert_eq!(limit.check(0, 600), StreamLimitResult::AdvanceBy(500));
+        limit.set_advance(500);
+        // empty now, 1 byte per millisecond
+        assert_eq!(limit.check(0, 20), StreamLimitResult::DelayFor(1));
+        assert_eq!(limit.check(10, 20), StreamLimitResult::AdvanceBy(10));
+        limit.set_advance(10);
+        // only 5 really sent
+        assert_eq!(limit.check(20, 20), StreamLimitResult::AdvanceBy(10));
+        limit.set_advance(5);
+        assert_eq!(limit.check(20, 20), StreamLimitResult::AdvanceBy(5));
+        limit.set_advance(5);
+        // idle for a long time, the burst should be capped
+        assert_eq!(limit.check(10_000, 1000), StreamLimitResult::AdvanceBy(500));
+    }
+
+    #[test]
+    fn slow_rate() {
+        // 100 bytes per second, 1 byte every 10 milliseconds
+        let mut limit = StreamTokenBucket::new(100, 100, 0);
+        assert_eq!(limit.check(0, 200), StreamLimitResult::AdvanceBy(100));
+        limit.set_advance(100);
+        assert_eq!(limit.check(1, 10), StreamLimitResult::DelayFor(9));
+        assert_eq!(limit.check(10, 10), StreamLimitResult::AdvanceBy(1));
+        limit.set_advance(1);
+        assert_eq!(limit.check(15, 10), StreamLimitResult::DelayFor(5));
+    }
+}
@@ -31,7 +31,9 @@ pub use relay::{
     UdpRelayClientError, UdpRelayClientRecv, UdpRelayClientSend, UdpRelayPacket,
     UdpRelayRemoteError, UdpRelayRemoteRecv, UdpRelayRemoteSend,
 };
-pub use relay::{UdpRelayClientToRemote, UdpRelayError, UdpRelayRemoteToClient};
+pub use relay::{
+    UdpRelayClientToRemote, UdpRelayError, UdpRelayPacingStats, UdpRelayRemoteToClient,
+};
 
 mod copy;
 pub use copy::{
@@ -90,6 +90,12 @@ impl<T: AsyncUdpRecv> LimitedUdpRecv<T> {
     pub fn reset_stats(&mut self, stats: ArcLimitedRecvStats) {
         self.stats = stats;
     }
+
+    /// Allow bursts for the fixed window limit, 0 means no burst
+    pub fn set_burst_limit(&mut self, burst_packets: usize, burst_bytes: usize) {
+        let dur_millis = self.started.elapsed().as_millis() as u64;
+        self.limit.set_burst(burst_packets, burst_bytes, dur_millis);
+    }
 }
 
 impl<T> AsyncUdpRecv for LimitedUdpRecv<T>
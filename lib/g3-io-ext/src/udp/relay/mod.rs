@@ -16,11 +16,12 @@
 
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{ready, Context, Poll};
 
 use thiserror::Error;
 
-use g3_types::net::UpstreamAddr;
+use g3_types::net::{UdpPacingConfig, UpstreamAddr};
 
 use super::LimitedUdpRelayConfig;
 
@@ -30,6 +31,10 @@ mod remote;
 pub use client::{UdpRelayClientError, UdpRelayClientRecv, UdpRelayClientSend};
 pub use remote::{UdpRelayRemoteError, UdpRelayRemoteRecv, UdpRelayRemoteSend};
 
+mod pacing;
+use pacing::UdpRelayPacer;
+pub use pacing::UdpRelayPacingStats;
+
 #[derive(Clone)]
 pub struct UdpRelayPacket {
     buf: Box<[u8]>,
@@ -289,12 +294,14 @@ impl<'a, T: UdpRelayRemoteSend + ?Sized> UdpRelaySend for RemoteSend<'a, T> {
 
 struct UdpRelayBuffer {
     config: LimitedUdpRelayConfig,
+    max_hdr_size: usize,
     packets: Vec<UdpRelayPacket>,
     send_start: usize,
     send_end: usize,
     recv_done: bool,
     total: u64,
     active: bool,
+    pacer: Option<(UdpRelayPacer, UdpRelayPacket)>,
 }
 
 impl UdpRelayBuffer {
@@ -303,12 +310,125 @@ impl UdpRelayBuffer {
             vec![UdpRelayPacket::new(max_hdr_size, config.packet_size); config.batch_size];
         UdpRelayBuffer {
             config,
+            max_hdr_size,
             packets,
             send_start: 0,
             send_end: 0,
             recv_done: false,
             total: 0,
             active: false,
+            pacer: None,
+        }
+    }
+
+    fn set_pacing(&mut self, pacing: &UdpPacingConfig, stats: Arc<UdpRelayPacingStats>) {
+        let pacer = UdpRelayPacer::new(pacing, self.config.packet_size, stats);
+        let queue_size = pacer.queue_size().max(self.config.batch_size);
+        if self.packets.len() < queue_size {
+            self.packets.resize(
+                queue_size,
+                UdpRelayPacket::new(self.max_hdr_size, self.config.packet_size),
+            );
+        }
+        // the packet buffer used to receive packets that will be dropped
+        let drop_packet = UdpRelayPacket::new(self.max_hdr_size, self.config.packet_size);
+        self.pacer = Some((pacer, drop_packet));
+    }
+
+    fn poll_relay<R, S>(
+        &mut self,
+        cx: &mut Context<'_>,
+        receiver: R,
+        sender: S,
+    ) -> Poll<Result<u64, UdpRelayError>>
+    where
+        R: UdpRelayRecv,
+        S: UdpRelaySend,
+    {
+        if self.pacer.is_some() {
+            self.poll_paced_relay(cx, receiver, sender)
+        } else {
+            self.poll_batch_relay(cx, receiver, sender)
+        }
+    }
+
+    fn poll_paced_relay<R, S>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut receiver: R,
+        mut sender: S,
+    ) -> Poll<Result<u64, UdpRelayError>>
+    where
+        R: UdpRelayRecv,
+        S: UdpRelaySend,
+    {
+        let Some((pacer, drop_packet)) = &mut self.pacer else {
+            return Poll::Ready(Ok(self.total));
+        };
+
+        let mut copy_this_round = 0usize;
+        loop {
+            if self.send_start > 0 {
+                // move the queued packets to the front
+                self.packets[..self.send_end].rotate_left(self.send_start);
+                self.send_end -= self.send_start;
+                self.send_start = 0;
+            }
+
+            // queue all the packets we can receive
+            let mut recv_blocked = false;
+            while !self.recv_done {
+                let r = if self.send_end < self.packets.len() {
+                    receiver.poll_recv_packets(cx, &mut self.packets[self.send_end..])
+                } else {
+                    // the queue is full, drop the new packets
+                    receiver.poll_recv_packets(cx, std::slice::from_mut(drop_packet))
+                };
+                match r {
+                    Poll::Ready(Ok(0)) => self.recv_done = true,
+                    Poll::Ready(Ok(count)) => {
+                        if self.send_end < self.packets.len() {
+                            self.send_end += count;
+                            pacer.update_queued(self.send_end);
+                        } else {
+                            pacer.add_dropped(count);
+                        }
+                    }
+                    Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                    Poll::Pending => {
+                        recv_blocked = true;
+                        break;
+                    }
+                }
+            }
+
+            while self.send_end > self.send_start {
+                let packets = &self.packets[self.send_start..self.send_end];
+                let allowed = ready!(pacer.poll_allow(cx, packets));
+                let packets = &packets[..allowed];
+                let count = ready!(sender.poll_send_packets(cx, packets))?;
+                pacer.consume(&packets[..count]);
+                copy_this_round += packets
+                    .iter()
+                    .take(count)
+                    .map(|p| p.buf_data_end - p.buf_data_off)
+                    .sum::<usize>();
+                self.send_start += count;
+            }
+            self.send_start = 0;
+            self.send_end = 0;
+
+            if copy_this_round >= self.config.yield_size {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            if self.recv_done {
+                return Poll::Ready(Ok(self.total));
+            }
+            if recv_blocked {
+                return Poll::Pending;
+            }
         }
     }
 
@@ -415,7 +535,7 @@ where
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let me = &mut *self;
         me.buffer
-            .poll_batch_relay(cx, ClientRecv(me.client), RemoteSend(me.remote))
+            .poll_relay(cx, ClientRecv(me.client), RemoteSend(me.remote))
     }
 }
 
@@ -439,6 +559,11 @@ where
         }
     }
 
+    /// Enable packet pacing on the client send side
+    pub fn set_pacing(&mut self, pacing: &UdpPacingConfig, stats: Arc<UdpRelayPacingStats>) {
+        self.buffer.set_pacing(pacing, stats);
+    }
+
     #[inline]
     pub fn is_idle(&self) -> bool {
         self.buffer.is_idle()
@@ -460,6 +585,6 @@ where
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let me = &mut *self;
         me.buffer
-            .poll_batch_relay(cx, RemoteRecv(me.remote), ClientSend(me.client))
+            .poll_relay(cx, RemoteRecv(me.remote), ClientSend(me.client))
     }
 }
@@ -0,0 +1,240 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio::time::{Instant, Sleep};
+
+use g3_types::net::UdpPacingConfig;
+
+use super::UdpRelayPacket;
+use crate::limit::TokenBucket;
+
+#[derive(Default)]
+pub struct UdpRelayPacingStats {
+    dropped_packets: AtomicU64,
+    max_queued_packets: AtomicUsize,
+}
+
+impl UdpRelayPacingStats {
+    pub fn get_dropped_packets(&self) -> u64 {
+        self.dropped_packets.load(Ordering::Relaxed)
+    }
+
+    pub fn get_max_queued_packets(&self) -> usize {
+        self.max_queued_packets.load(Ordering::Relaxed)
+    }
+
+    fn add_dropped_packets(&self, n: usize) {
+        self.dropped_packets.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    fn update_queued_packets(&self, n: usize) {
+        self.max_queued_packets.fetch_max(n, Ordering::Relaxed);
+    }
+}
+
+pub(super) struct UdpRelayPacer {
+    bucket: TokenBucket,
+    capacity: usize,
+    started: Instant,
+    delay: Pin<Box<Sleep>>,
+    queue_size: usize,
+    stats: Arc<UdpRelayPacingStats>,
+}
+
+impl UdpRelayPacer {
+    pub(super) fn new(
+        config: &UdpPacingConfig,
+        packet_size: usize,
+        stats: Arc<UdpRelayPacingStats>,
+    ) -> Self {
+        // at least one max sized packet should be allowed
+        let capacity = (config.burst_bytes() as usize).max(packet_size);
+        UdpRelayPacer {
+            bucket: TokenBucket::new(config.rate_bytes() as usize, capacity, 0),
+            capacity,
+            started: Instant::now(),
+            delay: Box::pin(tokio::time::sleep(Duration::ZERO)),
+            queue_size: config.queue_size(),
+            stats,
+        }
+    }
+
+    #[inline]
+    pub(super) fn queue_size(&self) -> usize {
+        self.queue_size
+    }
+
+    pub(super) fn add_dropped(&self, n: usize) {
+        self.stats.add_dropped_packets(n);
+    }
+
+    pub(super) fn update_queued(&self, n: usize) {
+        self.stats.update_queued_packets(n);
+    }
+
+    /// return how many packets at the front can be sent now
+    pub(super) fn poll_allow(
+        &mut self,
+        cx: &mut Context<'_>,
+        packets: &[UdpRelayPacket],
+    ) -> Poll<usize> {
+        let Some(first) = packets.first() else {
+            return Poll::Ready(0);
+        };
+        let first_size = first.payload().len().min(self.capacity);
+
+        loop {
+            let dur_millis = self.started.elapsed().as_millis() as u64;
+            self.bucket.refill(dur_millis);
+
+            let tokens = self.bucket.available();
+            if tokens >= first_size {
+                let mut tokens = tokens - first_size;
+                let mut count = 1;
+                for p in &packets[1..] {
+                    let size = p.payload().len();
+                    if size > tokens {
+                        break;
+                    }
+                    tokens -= size;
+                    count += 1;
+                }
+                return Poll::Ready(count);
+            }
+
+            let wait_millis = self.bucket.delay_for(first_size).max(1);
+            self.delay
+                .as_mut()
+                .reset(self.started + Duration::from_millis(dur_millis + wait_millis));
+            if self.delay.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    pub(super) fn consume(&mut self, packets: &[UdpRelayPacket]) {
+        let size = packets.iter().map(|p| p.payload().len()).sum::<usize>();
+        self.bucket.consume(size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::task::noop_waker_ref;
+
+    fn packets(sizes: &[usize]) -> Vec<UdpRelayPacket> {
+        sizes
+            .iter()
+            .map(|size| {
+                let mut p = UdpRelayPacket::new(0, *size);
+                p.set_length(*size);
+                p
+            })
+            .collect()
+    }
+
+    fn poll(pacer: &mut UdpRelayPacer, packets: &[UdpRelayPacket]) -> Poll<usize> {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        pacer.poll_allow(&mut cx, packets)
+    }
+
+    fn new_pacer(rate: u64, burst: u64, packet_size: usize) -> UdpRelayPacer {
+        let mut config = UdpPacingConfig::per_second(rate);
+        config.set_burst_bytes(burst);
+        UdpRelayPacer::new(
+            &config,
+            packet_size,
+            Arc::new(UdpRelayPacingStats::default()),
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn burst() {
+        let mut pacer = new_pacer(1000, 3000, 1000);
+        let packets = packets(&[1000, 1000, 1000, 1000]);
+
+        // the bucket is full at start
+        assert_eq!(poll(&mut pacer, &packets), Poll::Ready(3));
+        pacer.consume(&packets[..3]);
+        assert!(poll(&mut pacer, &packets[3..]).is_pending());
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert!(poll(&mut pacer, &packets[3..]).is_pending());
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert_eq!(poll(&mut pacer, &packets[3..]), Poll::Ready(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refill_rate() {
+        let mut pacer = new_pacer(2000, 0, 1000);
+        let packets = packets(&[1000]);
+
+        assert_eq!(poll(&mut pacer, &packets), Poll::Ready(1));
+        pacer.consume(&packets);
+
+        tokio::time::advance(Duration::from_millis(250)).await;
+        assert!(poll(&mut pacer, &packets).is_pending());
+        tokio::time::advance(Duration::from_millis(250)).await;
+        assert_eq!(poll(&mut pacer, &packets), Poll::Ready(1));
+        pacer.consume(&packets);
+
+        // the refill is capped by the burst size, which is at least one max sized packet
+        tokio::time::advance(Duration::from_secs(10)).await;
+        let packets = self::packets(&[600, 600]);
+        assert_eq!(poll(&mut pacer, &packets), Poll::Ready(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn wake_after_delay() {
+        let mut pacer = new_pacer(1000, 0, 1000);
+        let packets = packets(&[1000]);
+
+        pacer.consume(&packets);
+        let start = Instant::now();
+        let n = std::future::poll_fn(|cx| pacer.poll_allow(cx, &packets)).await;
+        assert_eq!(n, 1);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn zero_rate() {
+        // the rate will be at least 1 byte per second
+        let mut pacer = new_pacer(0, 0, 100);
+        let packets = packets(&[100]);
+
+        assert_eq!(poll(&mut pacer, &packets), Poll::Ready(1));
+        pacer.consume(&packets);
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(poll(&mut pacer, &packets).is_pending());
+        tokio::time::advance(Duration::from_secs(99)).await;
+        assert_eq!(poll(&mut pacer, &packets), Poll::Ready(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn empty() {
+        let mut pacer = new_pacer(0, 0, 100);
+        assert_eq!(poll(&mut pacer, &[]), Poll::Ready(0));
+    }
+}
@@ -35,6 +35,21 @@ pub fn as_udp_misc_sock_opts(v: &Value) -> anyhow::Result<UdpMiscSockOpts> {
                         crate::value::as_u8(v).context(format!("invalid u8 value for key {k}"))?;
                     config.type_of_service = Some(tos);
                 }
+                "traffic_class" | "tclass" => {
+                    let tclass =
+                        crate::value::as_u8(v).context(format!("invalid u8 value for key {k}"))?;
+                    config.traffic_class = Some(tclass);
+                }
+                "dscp" => {
+                    let dscp =
+                        crate::value::as_u8(v).context(format!("invalid u8 value for key {k}"))?;
+                    if dscp > 63 {
+                        return Err(anyhow!(
+                            "invalid dscp value {dscp}, should be in range 0-63"
+                        ));
+                    }
+                    config.set_dscp(dscp);
+                }
                 "netfilter_mark" | "mark" => {
                     let mark = crate::value::as_u32(v)
                         .context(format!("invalid u32 value for key {k}"))?;
@@ -271,6 +271,11 @@ fn set_openssl_tls_client_config_builder(
                         .context(format!("invalid usize value for key {k}"))?;
                     builder.set_session_cache_each_capacity(cap);
                 }
+                "session_cache_ttl" => {
+                    let ttl = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    builder.set_session_cache_ttl(ttl);
+                }
                 "supported_groups" => {
                     let groups = crate::value::as_string(v)?;
                     builder.set_supported_groups(groups);
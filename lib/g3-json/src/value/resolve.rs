@@ -44,6 +44,22 @@ pub fn as_pick_strategy(v: &Value) -> anyhow::Result<PickStrategy> {
     }
 }
 
+fn as_family_weight(v: &Value) -> anyhow::Result<(u8, u8)> {
+    if let Value::Object(map) = v {
+        let mut weight = (0, 0);
+        for (k, v) in map {
+            match crate::key::normalize(k).as_str() {
+                "ipv4" | "v4" => weight.0 = crate::value::as_u8(v)?,
+                "ipv6" | "v6" => weight.1 = crate::value::as_u8(v)?,
+                _ => return Err(anyhow!("invalid key {k}")),
+            }
+        }
+        Ok(weight)
+    } else {
+        Err(anyhow!("invalid json value type for family weight"))
+    }
+}
+
 pub fn as_resolve_strategy(v: &Value) -> anyhow::Result<ResolveStrategy> {
     let mut config = ResolveStrategy::default();
 
@@ -61,6 +77,12 @@ pub fn as_resolve_strategy(v: &Value) -> anyhow::Result<ResolveStrategy> {
                     "pick" => {
                         config.pick = as_pick_strategy(v)?;
                     }
+                    "family_weight" => {
+                        config.family_weight = Some(
+                            as_family_weight(v)
+                                .context(format!("invalid family weight value for key {k}"))?,
+                        );
+                    }
                     _ => return Err(anyhow!("invalid key {k}")),
                 };
             }
@@ -17,7 +17,7 @@
 use anyhow::{anyhow, Context};
 use serde_json::Value;
 
-use g3_types::net::{TcpSockSpeedLimitConfig, UdpSockSpeedLimitConfig};
+use g3_types::net::{StreamDelayConfig, TcpSockSpeedLimitConfig, UdpSockSpeedLimitConfig};
 
 pub fn as_tcp_sock_speed_limit(v: &Value) -> anyhow::Result<TcpSockSpeedLimitConfig> {
     let mut config = TcpSockSpeedLimitConfig::default();
@@ -43,6 +43,20 @@ pub fn as_tcp_sock_speed_limit(v: &Value) -> anyhow::Result<TcpSockSpeedLimitCon
                         config.max_south = crate::humanize::as_usize(v)
                             .context(format!("invalid humanize usize value for key {k}"))?;
                     }
+                    "upload_burst" | "north_burst" => {
+                        config.burst_north = crate::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?;
+                    }
+                    "download_burst" | "south_burst" => {
+                        config.burst_south = crate::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?;
+                    }
+                    "burst" => {
+                        let burst = crate::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?;
+                        config.burst_north = burst;
+                        config.burst_south = burst;
+                    }
                     _ => return Err(anyhow!("invalid key {k}")),
                 }
             }
@@ -85,6 +99,22 @@ pub fn as_udp_sock_speed_limit(v: &Value) -> anyhow::Result<UdpSockSpeedLimitCon
                         config.max_south_bytes = crate::humanize::as_usize(v)
                             .context(format!("invalid humanize usize value for key {k}"))?;
                     }
+                    "upload_burst_packets" | "north_burst_packets" => {
+                        config.burst_north_packets = crate::value::as_usize(v)
+                            .context(format!("invalid usize value for key {k}"))?;
+                    }
+                    "download_burst_packets" | "south_burst_packets" => {
+                        config.burst_south_packets = crate::value::as_usize(v)
+                            .context(format!("invalid usize value for key {k}"))?;
+                    }
+                    "upload_burst_bytes" | "north_burst_bytes" => {
+                        config.burst_north_bytes = crate::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?;
+                    }
+                    "download_burst_bytes" | "south_burst_bytes" => {
+                        config.burst_south_bytes = crate::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?;
+                    }
                     _ => return Err(anyhow!("invalid key {k}")),
                 }
             }
@@ -94,3 +124,32 @@ pub fn as_udp_sock_speed_limit(v: &Value) -> anyhow::Result<UdpSockSpeedLimitCon
     config.validate()?;
     Ok(config)
 }
+
+pub fn as_stream_delay_config(v: &Value) -> anyhow::Result<StreamDelayConfig> {
+    let mut config = StreamDelayConfig::default();
+    match v {
+        Value::String(_) | Value::Number(_) => {
+            let latency = crate::humanize::as_duration(v).context("invalid humanize duration")?;
+            config.set_latency(latency);
+        }
+        Value::Object(map) => {
+            for (k, v) in map {
+                match crate::key::normalize(k).as_str() {
+                    "latency" | "delay" => {
+                        let latency = crate::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        config.set_latency(latency);
+                    }
+                    "jitter" => {
+                        let jitter = crate::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        config.set_jitter(jitter);
+                    }
+                    _ => return Err(anyhow!("invalid key {k}")),
+                }
+            }
+        }
+        _ => return Err(anyhow!("invalid json value type")),
+    }
+    Ok(config)
+}
@@ -0,0 +1,168 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+use g3_types::net::UpstreamAddr;
+
+pub const KAFKA_DEFAULT_PORT: u16 = 9092;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KafkaCompression {
+    #[default]
+    None,
+    Gzip,
+}
+
+impl KafkaCompression {
+    pub(crate) fn codec(&self) -> i16 {
+        match self {
+            KafkaCompression::None => 0,
+            KafkaCompression::Gzip => 1,
+        }
+    }
+}
+
+impl FromStr for KafkaCompression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(KafkaCompression::None),
+            "gzip" => Ok(KafkaCompression::Gzip),
+            _ => Err(anyhow!("unsupported compression type {s}")),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct KafkaProducerConfig {
+    pub(crate) bootstrap_servers: Vec<UpstreamAddr>,
+    pub(crate) client_id: String,
+    topic: Option<String>,
+    pub(crate) acks: i16,
+    pub(crate) compression: KafkaCompression,
+    pub(crate) batch_size: usize,
+    pub(crate) batch_bytes: usize,
+    pub(crate) linger: Duration,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) request_timeout: Duration,
+    pub(crate) retry_delay: Duration,
+    pub(crate) retry_queue_len: usize,
+    pub(crate) metadata_refresh_interval: Duration,
+}
+
+impl Default for KafkaProducerConfig {
+    fn default() -> Self {
+        KafkaProducerConfig::new(vec![UpstreamAddr::from_ip_and_port(
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+            KAFKA_DEFAULT_PORT,
+        )])
+    }
+}
+
+impl KafkaProducerConfig {
+    pub fn new(bootstrap_servers: Vec<UpstreamAddr>) -> Self {
+        KafkaProducerConfig {
+            bootstrap_servers,
+            client_id: String::new(),
+            topic: None,
+            acks: 1,
+            compression: KafkaCompression::None,
+            batch_size: 1000,
+            batch_bytes: 1 << 20,
+            linger: Duration::from_millis(100),
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(5),
+            retry_delay: Duration::from_secs(1),
+            retry_queue_len: 10,
+            metadata_refresh_interval: Duration::from_secs(300),
+        }
+    }
+
+    pub fn check(&mut self) -> anyhow::Result<()> {
+        if self.bootstrap_servers.is_empty() {
+            return Err(anyhow!("no bootstrap server set"));
+        }
+        if !matches!(self.acks, -1 | 0 | 1) {
+            return Err(anyhow!("invalid acks value {}", self.acks));
+        }
+        if self.batch_size == 0 {
+            self.batch_size = 1;
+        }
+        Ok(())
+    }
+
+    pub fn set_bootstrap_servers(&mut self, servers: Vec<UpstreamAddr>) {
+        self.bootstrap_servers = servers;
+    }
+
+    pub fn set_client_id(&mut self, client_id: String) {
+        self.client_id = client_id;
+    }
+
+    pub fn set_topic(&mut self, topic: String) {
+        self.topic = Some(topic);
+    }
+
+    pub fn topic(&self) -> Option<&str> {
+        self.topic.as_deref()
+    }
+
+    pub fn set_acks(&mut self, acks: i16) {
+        self.acks = acks;
+    }
+
+    pub fn set_compression(&mut self, compression: KafkaCompression) {
+        self.compression = compression;
+    }
+
+    pub fn set_batch_size(&mut self, size: usize) {
+        self.batch_size = size;
+    }
+
+    pub fn set_batch_bytes(&mut self, size: usize) {
+        self.batch_bytes = size;
+    }
+
+    pub fn set_linger(&mut self, linger: Duration) {
+        self.linger = linger;
+    }
+
+    pub fn set_connect_timeout(&mut self, timeout: Duration) {
+        self.connect_timeout = timeout;
+    }
+
+    pub fn set_request_timeout(&mut self, timeout: Duration) {
+        self.request_timeout = timeout;
+    }
+
+    pub fn set_retry_delay(&mut self, delay: Duration) {
+        self.retry_delay = delay;
+    }
+
+    pub fn set_retry_queue_len(&mut self, len: usize) {
+        self.retry_queue_len = len;
+    }
+
+    pub fn set_metadata_refresh_interval(&mut self, interval: Duration) {
+        self.metadata_refresh_interval = interval;
+    }
+}
@@ -0,0 +1,206 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use chrono::Utc;
+use flume::Receiver;
+use log::warn;
+use tokio::time::Instant;
+
+use g3_types::log::{AsyncLogConfig, AsyncLogger, LogStats};
+
+mod config;
+pub use config::{KafkaCompression, KafkaProducerConfig, KAFKA_DEFAULT_PORT};
+
+mod format;
+pub use format::KafkaFormatter;
+
+mod protocol;
+use protocol::RecordBatchEncoder;
+
+mod producer;
+use producer::KafkaProducer;
+
+pub fn new_async_logger(
+    async_conf: &AsyncLogConfig,
+    kafka_conf: &Arc<KafkaProducerConfig>,
+    topic: String,
+) -> AsyncLogger<Vec<u8>, KafkaFormatter> {
+    let (sender, receiver) = flume::bounded::<Vec<u8>>(async_conf.channel_capacity);
+
+    let stats = Arc::new(LogStats::default());
+
+    for i in 0..async_conf.thread_number {
+        let io_thread = AsyncIoThread {
+            config: Arc::clone(kafka_conf),
+            receiver: receiver.clone(),
+            stats: Arc::clone(&stats),
+            producer: KafkaProducer::new(Arc::clone(kafka_conf), topic.clone()),
+            retry_queue: VecDeque::with_capacity(kafka_conf.retry_queue_len),
+            retry_after: None,
+        };
+
+        let _detached_thread = std::thread::Builder::new()
+            .name(format!("{}#{i}", async_conf.thread_name))
+            .spawn(move || {
+                let rt = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                rt.block_on(io_thread.run_to_end());
+            });
+    }
+
+    AsyncLogger::new(sender, KafkaFormatter::new(), stats)
+}
+
+struct PendingBatch {
+    count: usize,
+    data: Vec<u8>,
+}
+
+struct AsyncIoThread {
+    config: Arc<KafkaProducerConfig>,
+    receiver: Receiver<Vec<u8>>,
+    stats: Arc<LogStats>,
+    producer: KafkaProducer,
+    retry_queue: VecDeque<PendingBatch>,
+    retry_after: Option<Instant>,
+}
+
+impl AsyncIoThread {
+    async fn run_to_end(mut self) {
+        let mut encoder = RecordBatchEncoder::default();
+        let mut linger_deadline = Instant::now();
+
+        loop {
+            let r = if encoder.count() == 0 {
+                let r = if self.retry_queue.is_empty() {
+                    self.receiver.recv_async().await.ok()
+                } else {
+                    match tokio::time::timeout(self.config.retry_delay, self.receiver.recv_async())
+                        .await
+                    {
+                        Ok(r) => r.ok(),
+                        Err(_) => {
+                            self.send_retry_queue().await;
+                            continue;
+                        }
+                    }
+                };
+                linger_deadline = Instant::now() + self.config.linger;
+                r
+            } else {
+                match tokio::time::timeout_at(linger_deadline, self.receiver.recv_async()).await {
+                    Ok(r) => r.ok(),
+                    Err(_) => {
+                        // linger timeout
+                        let batch = std::mem::take(&mut encoder);
+                        self.send_batch(batch).await;
+                        continue;
+                    }
+                }
+            };
+
+            let Some(data) = r else {
+                // channel closed
+                break;
+            };
+            encoder.push(&data, Utc::now().timestamp_millis());
+            if encoder.count() >= self.config.batch_size
+                || encoder.size() >= self.config.batch_bytes
+            {
+                let batch = std::mem::take(&mut encoder);
+                self.send_batch(batch).await;
+            }
+        }
+
+        if encoder.count() > 0 {
+            self.send_batch(encoder).await;
+        }
+        self.retry_after = None;
+        self.send_retry_queue().await;
+    }
+
+    async fn send_batch(&mut self, encoder: RecordBatchEncoder) {
+        let count = encoder.count();
+        let batch = match encoder.finish(self.config.compression) {
+            Ok(data) => PendingBatch { count, data },
+            Err(e) => {
+                warn!("failed to encode kafka record batch: {e}");
+                for _ in 0..count {
+                    self.stats.drop.add_format_failed();
+                }
+                return;
+            }
+        };
+
+        self.send_retry_queue().await;
+        if !self.retry_queue.is_empty() {
+            self.push_to_retry(batch);
+            return;
+        }
+        if let Err(batch) = self.send_pending(batch).await {
+            self.push_to_retry(batch);
+        }
+    }
+
+    async fn send_retry_queue(&mut self) {
+        while let Some(batch) = self.retry_queue.pop_front() {
+            if let Err(batch) = self.send_pending(batch).await {
+                self.retry_queue.push_front(batch);
+                return;
+            }
+        }
+    }
+
+    async fn send_pending(&mut self, batch: PendingBatch) -> Result<(), PendingBatch> {
+        if let Some(retry_after) = self.retry_after {
+            if Instant::now() < retry_after {
+                return Err(batch);
+            }
+        }
+
+        match self.producer.produce(&batch.data).await {
+            Ok(_) => {
+                self.retry_after = None;
+                for _ in 0..batch.count {
+                    self.stats.io.add_passed();
+                }
+                self.stats.io.add_size(batch.data.len());
+                Ok(())
+            }
+            Err(e) => {
+                warn!("failed to send logs to kafka: {e:?}");
+                self.retry_after = Some(Instant::now() + self.config.retry_delay);
+                Err(batch)
+            }
+        }
+    }
+
+    fn push_to_retry(&mut self, batch: PendingBatch) {
+        self.retry_queue.push_back(batch);
+        if self.retry_queue.len() > self.config.retry_queue_len {
+            if let Some(dropped) = self.retry_queue.pop_front() {
+                for _ in 0..dropped.count {
+                    self.stats.drop.add_peer_unreachable();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::{anyhow, Context};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::protocol::{MetadataRequest, MetadataResponse, ProduceRequest, ProduceResponse};
+use crate::KafkaProducerConfig;
+
+struct TopicMetadata {
+    brokers: HashMap<i32, (String, u16)>,
+    partitions: Vec<(i32, i32)>,
+    create_time: Instant,
+}
+
+pub(crate) struct KafkaProducer {
+    config: Arc<KafkaProducerConfig>,
+    topic: String,
+    correlation_id: i32,
+    metadata: Option<TopicMetadata>,
+    next_partition: usize,
+    connections: HashMap<i32, TcpStream>,
+}
+
+impl KafkaProducer {
+    pub(crate) fn new(config: Arc<KafkaProducerConfig>, topic: String) -> Self {
+        KafkaProducer {
+            config,
+            topic,
+            correlation_id: 0,
+            metadata: None,
+            next_partition: 0,
+            connections: HashMap::new(),
+        }
+    }
+
+    fn next_correlation_id(&mut self) -> i32 {
+        self.correlation_id = self.correlation_id.wrapping_add(1) & i32::MAX;
+        self.correlation_id
+    }
+
+    async fn connect(&self, host: &str, port: u16) -> anyhow::Result<TcpStream> {
+        match tokio::time::timeout(
+            self.config.connect_timeout,
+            TcpStream::connect((host, port)),
+        )
+        .await
+        {
+            Ok(Ok(stream)) => {
+                let _ = stream.set_nodelay(true);
+                Ok(stream)
+            }
+            Ok(Err(e)) => Err(anyhow!("failed to connect to {host}:{port}: {e}")),
+            Err(_) => Err(anyhow!("timed out to connect to {host}:{port}")),
+        }
+    }
+
+    async fn send_request(
+        &self,
+        stream: &mut TcpStream,
+        req: &[u8],
+        correlation_id: i32,
+        expect_response: bool,
+    ) -> anyhow::Result<Vec<u8>> {
+        let fut = async {
+            stream
+                .write_all(req)
+                .await
+                .map_err(|e| anyhow!("failed to send request: {e}"))?;
+            if !expect_response {
+                return Ok(Vec::new());
+            }
+
+            let size = stream
+                .read_i32()
+                .await
+                .map_err(|e| anyhow!("failed to read response size: {e}"))?;
+            if size < 4 {
+                return Err(anyhow!("invalid response size {size}"));
+            }
+            let mut buf = vec![0u8; size as usize];
+            stream
+                .read_exact(&mut buf)
+                .await
+                .map_err(|e| anyhow!("failed to read response: {e}"))?;
+            let rsp_correlation_id = i32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
+            if rsp_correlation_id != correlation_id {
+                return Err(anyhow!(
+                    "correlation id mismatch: expected {correlation_id}, got {rsp_correlation_id}"
+                ));
+            }
+            buf.drain(0..4);
+            Ok(buf)
+        };
+        match tokio::time::timeout(self.config.request_timeout, fut).await {
+            Ok(r) => r,
+            Err(_) => Err(anyhow!("request timed out")),
+        }
+    }
+
+    async fn fetch_metadata_from(&mut self, host: &str, port: u16) -> anyhow::Result<()> {
+        let mut stream = self.connect(host, port).await?;
+        let correlation_id = self.next_correlation_id();
+        let req =
+            MetadataRequest { topic: &self.topic }.encode(correlation_id, &self.config.client_id);
+        let rsp = self
+            .send_request(&mut stream, &req, correlation_id, true)
+            .await?;
+        let rsp = MetadataResponse::decode(&rsp, &self.topic)?;
+
+        let brokers = rsp
+            .brokers
+            .into_iter()
+            .map(|b| (b.node_id, (b.host, b.port)))
+            .collect();
+        let partitions = rsp
+            .partitions
+            .into_iter()
+            .map(|p| (p.index, p.leader_id))
+            .collect();
+        self.metadata = Some(TopicMetadata {
+            brokers,
+            partitions,
+            create_time: Instant::now(),
+        });
+        self.connections.clear();
+        Ok(())
+    }
+
+    async fn refresh_metadata(&mut self) -> anyhow::Result<()> {
+        let config = self.config.clone();
+        let mut last_err = anyhow!("no bootstrap server available");
+        for server in &config.bootstrap_servers {
+            let host = server.host_str();
+            match self.fetch_metadata_from(&host, server.port()).await {
+                Ok(_) => return Ok(()),
+                Err(e) => last_err = e.context(format!("failed to fetch metadata from {server}")),
+            }
+        }
+        Err(last_err)
+    }
+
+    fn invalidate(&mut self, leader_id: i32) {
+        self.connections.remove(&leader_id);
+        self.metadata = None;
+    }
+
+    pub(crate) async fn produce(&mut self, record_batch: &[u8]) -> anyhow::Result<()> {
+        let need_refresh = match &self.metadata {
+            Some(m) => m.create_time.elapsed() > self.config.metadata_refresh_interval,
+            None => true,
+        };
+        if need_refresh {
+            self.refresh_metadata().await?;
+        }
+        let Some(metadata) = &self.metadata else {
+            return Err(anyhow!("no metadata available"));
+        };
+
+        // select the partition in round robin way for each batch
+        let (partition, leader_id) =
+            metadata.partitions[self.next_partition % metadata.partitions.len()];
+        self.next_partition = self.next_partition.wrapping_add(1);
+
+        let mut stream = match self.connections.remove(&leader_id) {
+            Some(stream) => stream,
+            None => {
+                let Some((host, port)) = metadata.brokers.get(&leader_id).cloned() else {
+                    self.metadata = None;
+                    return Err(anyhow!("no broker found for leader id {leader_id}"));
+                };
+                self.connect(&host, port).await?
+            }
+        };
+
+        let correlation_id = self.next_correlation_id();
+        let req = ProduceRequest {
+            acks: self.config.acks,
+            timeout_ms: self.config.request_timeout.as_millis() as i32,
+            topic: &self.topic,
+            partition,
+            record_batch,
+        }
+        .encode(correlation_id, &self.config.client_id);
+        let rsp = match self
+            .send_request(&mut stream, &req, correlation_id, self.config.acks != 0)
+            .await
+        {
+            Ok(rsp) => rsp,
+            Err(e) => {
+                self.invalidate(leader_id);
+                return Err(e).context(format!("produce to partition {partition} failed"));
+            }
+        };
+        self.connections.insert(leader_id, stream);
+
+        if self.config.acks != 0 {
+            let rsp = ProduceResponse::decode(&rsp, partition)?;
+            if !rsp.error_code.is_ok() {
+                if rsp.error_code.need_refresh_metadata() {
+                    self.invalidate(leader_id);
+                }
+                return Err(anyhow!(
+                    "produce to partition {partition} failed with error code {}",
+                    rsp.error_code.0
+                ));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+
+pub(crate) struct ResponseDecoder<'a> {
+    buf: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ResponseDecoder<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        ResponseDecoder { buf, offset: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        let end = self.offset + len;
+        if end > self.buf.len() {
+            return Err(anyhow!("response message is too short"));
+        }
+        let s = &self.buf[self.offset..end];
+        self.offset = end;
+        Ok(s)
+    }
+
+    pub(crate) fn get_i8(&mut self) -> anyhow::Result<i8> {
+        let s = self.take(1)?;
+        Ok(s[0] as i8)
+    }
+
+    pub(crate) fn get_i16(&mut self) -> anyhow::Result<i16> {
+        let s = self.take(2)?;
+        Ok(i16::from_be_bytes([s[0], s[1]]))
+    }
+
+    pub(crate) fn get_i32(&mut self) -> anyhow::Result<i32> {
+        let s = self.take(4)?;
+        Ok(i32::from_be_bytes([s[0], s[1], s[2], s[3]]))
+    }
+
+    pub(crate) fn get_i64(&mut self) -> anyhow::Result<i64> {
+        let s = self.take(8)?;
+        let mut b = [0u8; 8];
+        b.copy_from_slice(s);
+        Ok(i64::from_be_bytes(b))
+    }
+
+    pub(crate) fn get_string(&mut self) -> anyhow::Result<String> {
+        let len = self.get_i16()?;
+        if len < 0 {
+            return Err(anyhow!("unexpected null string"));
+        }
+        let s = self.take(len as usize)?;
+        std::str::from_utf8(s)
+            .map(|s| s.to_string())
+            .map_err(|_| anyhow!("invalid utf-8 string"))
+    }
+
+    pub(crate) fn skip_nullable_string(&mut self) -> anyhow::Result<()> {
+        let len = self.get_i16()?;
+        if len > 0 {
+            self.take(len as usize)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get_array_len(&mut self) -> anyhow::Result<usize> {
+        let len = self.get_i32()?;
+        Ok(len.max(0) as usize)
+    }
+
+    pub(crate) fn skip_i32_array(&mut self) -> anyhow::Result<()> {
+        let len = self.get_array_len()?;
+        self.take(len * 4)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+use bytes::BufMut;
+
+use super::{ErrorCode, ResponseDecoder};
+
+const API_VERSION: i16 = 1;
+
+pub(crate) struct MetadataRequest<'a> {
+    pub(crate) topic: &'a str,
+}
+
+impl<'a> MetadataRequest<'a> {
+    pub(crate) fn encode(&self, correlation_id: i32, client_id: &str) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(64);
+        super::encode_request_header(
+            &mut buf,
+            super::API_KEY_METADATA,
+            API_VERSION,
+            correlation_id,
+            client_id,
+        );
+        buf.put_i32(1);
+        super::put_string(&mut buf, self.topic);
+        super::finish_request(buf)
+    }
+}
+
+pub(crate) struct BrokerMetadata {
+    pub(crate) node_id: i32,
+    pub(crate) host: String,
+    pub(crate) port: u16,
+}
+
+pub(crate) struct PartitionMetadata {
+    pub(crate) index: i32,
+    pub(crate) leader_id: i32,
+}
+
+pub(crate) struct MetadataResponse {
+    pub(crate) brokers: Vec<BrokerMetadata>,
+    pub(crate) partitions: Vec<PartitionMetadata>,
+}
+
+impl MetadataResponse {
+    pub(crate) fn decode(buf: &[u8], topic: &str) -> anyhow::Result<Self> {
+        let mut d = ResponseDecoder::new(buf);
+
+        let broker_count = d.get_array_len()?;
+        let mut brokers = Vec::with_capacity(broker_count);
+        for _ in 0..broker_count {
+            let node_id = d.get_i32()?;
+            let host = d.get_string()?;
+            let port = d.get_i32()?;
+            d.skip_nullable_string()?; // rack
+            let port = u16::try_from(port).map_err(|_| anyhow!("invalid broker port {port}"))?;
+            brokers.push(BrokerMetadata {
+                node_id,
+                host,
+                port,
+            });
+        }
+
+        let _controller_id = d.get_i32()?;
+
+        let mut partitions = Vec::new();
+        let topic_count = d.get_array_len()?;
+        for _ in 0..topic_count {
+            let error_code = ErrorCode(d.get_i16()?);
+            let name = d.get_string()?;
+            let _is_internal = d.get_i8()?;
+            let partition_count = d.get_array_len()?;
+            let mut topic_partitions = Vec::with_capacity(partition_count);
+            for _ in 0..partition_count {
+                let p_error_code = ErrorCode(d.get_i16()?);
+                let index = d.get_i32()?;
+                let leader_id = d.get_i32()?;
+                d.skip_i32_array()?; // replica nodes
+                d.skip_i32_array()?; // isr nodes
+                if p_error_code.is_ok() && leader_id >= 0 {
+                    topic_partitions.push(PartitionMetadata { index, leader_id });
+                }
+            }
+            if name != topic {
+                continue;
+            }
+            if !error_code.is_ok() {
+                return Err(anyhow!("topic {topic} error: {}", error_code.0));
+            }
+            partitions = topic_partitions;
+        }
+
+        if partitions.is_empty() {
+            return Err(anyhow!("no available partition found for topic {topic}"));
+        }
+        Ok(MetadataResponse {
+            brokers,
+            partitions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_request() {
+        let req = MetadataRequest { topic: "logs" };
+        let buf = req.encode(1, "g3");
+        let expected: &[u8] = &[
+            0x00, 0x00, 0x00, 0x16, // size
+            0x00, 0x03, // api key
+            0x00, 0x01, // api version
+            0x00, 0x00, 0x00, 0x01, // correlation id
+            0x00, 0x02, b'g', b'3', // client id
+            0x00, 0x00, 0x00, 0x01, // topics count
+            0x00, 0x04, b'l', b'o', b'g', b's', // topic name
+        ];
+        assert_eq!(buf.as_slice(), expected);
+    }
+
+    /// the offset of the error code of topic logs in RESPONSE
+    const LOGS_ERROR_OFFSET: usize = 74;
+
+    const RESPONSE: &[u8] = &[
+        0x00, 0x00, 0x00, 0x02, // brokers count
+        0x00, 0x00, 0x00, 0x01, // node id
+        0x00, 0x02, b'k', b'1', // host
+        0x00, 0x00, 0x23, 0x84, // port
+        0xff, 0xff, // null rack
+        0x00, 0x00, 0x00, 0x02, // node id
+        0x00, 0x02, b'k', b'2', // host
+        0x00, 0x00, 0x23, 0x85, // port
+        0x00, 0x02, b'r', b'1', // rack
+        0x00, 0x00, 0x00, 0x01, // controller id
+        0x00, 0x00, 0x00, 0x02, // topics count
+        // topic 0
+        0x00, 0x00, // error code
+        0x00, 0x05, b'o', b't', b'h', b'e', b'r', // name
+        0x00, // is internal
+        0x00, 0x00, 0x00, 0x01, // partitions count
+        0x00, 0x00, // error code
+        0x00, 0x00, 0x00, 0x03, // partition index
+        0x00, 0x00, 0x00, 0x02, // leader id
+        0x00, 0x00, 0x00, 0x00, // replica nodes
+        0x00, 0x00, 0x00, 0x00, // isr nodes
+        // topic 1
+        0x00, 0x00, // error code
+        0x00, 0x04, b'l', b'o', b'g', b's', // name
+        0x00, // is internal
+        0x00, 0x00, 0x00, 0x02, // partitions count
+        0x00, 0x00, // error code
+        0x00, 0x00, 0x00, 0x00, // partition index
+        0x00, 0x00, 0x00, 0x01, // leader id
+        0x00, 0x00, 0x00, 0x02, // replica nodes count
+        0x00, 0x00, 0x00, 0x01, // replica node
+        0x00, 0x00, 0x00, 0x02, // replica node
+        0x00, 0x00, 0x00, 0x01, // isr nodes count
+        0x00, 0x00, 0x00, 0x01, // isr node
+        0x00, 0x05, // error code
+        0x00, 0x00, 0x00, 0x01, // partition index
+        0xff, 0xff, 0xff, 0xff, // leader id
+        0x00, 0x00, 0x00, 0x00, // replica nodes
+        0x00, 0x00, 0x00, 0x00, // isr nodes
+    ];
+
+    #[test]
+    fn decode_response() {
+        let rsp = MetadataResponse::decode(RESPONSE, "logs").unwrap();
+        assert_eq!(rsp.brokers.len(), 2);
+        assert_eq!(rsp.brokers[0].node_id, 1);
+        assert_eq!(rsp.brokers[0].host, "k1");
+        assert_eq!(rsp.brokers[0].port, 9092);
+        assert_eq!(rsp.brokers[1].node_id, 2);
+        assert_eq!(rsp.brokers[1].host, "k2");
+        assert_eq!(rsp.brokers[1].port, 9093);
+        assert_eq!(rsp.partitions.len(), 1);
+        assert_eq!(rsp.partitions[0].index, 0);
+        assert_eq!(rsp.partitions[0].leader_id, 1);
+
+        let rsp = MetadataResponse::decode(RESPONSE, "other").unwrap();
+        assert_eq!(rsp.partitions.len(), 1);
+        assert_eq!(rsp.partitions[0].index, 3);
+        assert_eq!(rsp.partitions[0].leader_id, 2);
+
+        assert!(MetadataResponse::decode(RESPONSE, "unknown").is_err());
+    }
+
+    #[test]
+    fn decode_topic_error() {
+        assert_eq!(
+            &RESPONSE[LOGS_ERROR_OFFSET + 2..LOGS_ERROR_OFFSET + 4],
+            &[0x00, 0x04]
+        );
+        let mut buf = RESPONSE.to_vec();
+        buf[LOGS_ERROR_OFFSET + 1] = 3; // UNKNOWN_TOPIC_OR_PARTITION
+        assert!(MetadataResponse::decode(&buf, "logs").is_err());
+        assert!(MetadataResponse::decode(&buf, "other").is_ok());
+    }
+
+    #[test]
+    fn decode_truncated() {
+        let buf = &RESPONSE[..RESPONSE.len() - 1];
+        assert!(MetadataResponse::decode(buf, "logs").is_err());
+    }
+}
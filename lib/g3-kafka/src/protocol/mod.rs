@@ -0,0 +1,106 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use bytes::BufMut;
+
+mod crc32c;
+
+mod decode;
+pub(crate) use decode::ResponseDecoder;
+
+mod metadata;
+pub(crate) use metadata::{MetadataRequest, MetadataResponse};
+
+mod produce;
+pub(crate) use produce::{ProduceRequest, ProduceResponse};
+
+mod record;
+pub(crate) use record::RecordBatchEncoder;
+
+const API_KEY_PRODUCE: i16 = 0;
+const API_KEY_METADATA: i16 = 3;
+
+/// encode the request header v1, with the leading message size field reserved
+fn encode_request_header(
+    buf: &mut Vec<u8>,
+    api_key: i16,
+    api_version: i16,
+    correlation_id: i32,
+    client_id: &str,
+) {
+    buf.put_i32(0); // size, will be set later
+    buf.put_i16(api_key);
+    buf.put_i16(api_version);
+    buf.put_i32(correlation_id);
+    put_string(buf, client_id);
+}
+
+fn finish_request(mut buf: Vec<u8>) -> Vec<u8> {
+    let size = (buf.len() - 4) as i32;
+    buf[0..4].copy_from_slice(&size.to_be_bytes());
+    buf
+}
+
+fn put_string(buf: &mut Vec<u8>, s: &str) {
+    buf.put_i16(s.len() as i16);
+    buf.put_slice(s.as_bytes());
+}
+
+fn put_varint(buf: &mut Vec<u8>, v: i64) {
+    let mut v = ((v << 1) ^ (v >> 63)) as u64;
+    while v >= 0x80 {
+        buf.put_u8((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.put_u8(v as u8);
+}
+
+#[derive(Debug)]
+pub(crate) struct ErrorCode(pub(crate) i16);
+
+impl ErrorCode {
+    pub(crate) fn is_ok(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// the errors that need a metadata refresh before retry
+    pub(crate) fn need_refresh_metadata(&self) -> bool {
+        matches!(
+            self.0,
+            3 // UNKNOWN_TOPIC_OR_PARTITION
+            | 5 // LEADER_NOT_AVAILABLE
+            | 6 // NOT_LEADER_OR_FOLLOWER
+            | 7 // REQUEST_TIMED_OUT
+            | 19 // NOT_ENOUGH_REPLICAS
+            | 20 // NOT_ENOUGH_REPLICAS_AFTER_APPEND
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varint() {
+        let mut buf = Vec::new();
+        put_varint(&mut buf, 0);
+        put_varint(&mut buf, -1);
+        put_varint(&mut buf, 1);
+        put_varint(&mut buf, 300);
+        assert_eq!(buf.as_slice(), &[0x00, 0x01, 0x02, 0xd8, 0x04]);
+    }
+}
@@ -0,0 +1,143 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use bytes::BufMut;
+
+use super::{ErrorCode, ResponseDecoder};
+
+const API_VERSION: i16 = 3;
+
+pub(crate) struct ProduceRequest<'a> {
+    pub(crate) acks: i16,
+    pub(crate) timeout_ms: i32,
+    pub(crate) topic: &'a str,
+    pub(crate) partition: i32,
+    pub(crate) record_batch: &'a [u8],
+}
+
+impl<'a> ProduceRequest<'a> {
+    pub(crate) fn encode(&self, correlation_id: i32, client_id: &str) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(64 + self.topic.len() + self.record_batch.len());
+        super::encode_request_header(
+            &mut buf,
+            super::API_KEY_PRODUCE,
+            API_VERSION,
+            correlation_id,
+            client_id,
+        );
+        buf.put_i16(-1); // null transactional id
+        buf.put_i16(self.acks);
+        buf.put_i32(self.timeout_ms);
+        buf.put_i32(1);
+        super::put_string(&mut buf, self.topic);
+        buf.put_i32(1);
+        buf.put_i32(self.partition);
+        buf.put_i32(self.record_batch.len() as i32);
+        buf.put_slice(self.record_batch);
+        super::finish_request(buf)
+    }
+}
+
+pub(crate) struct ProduceResponse {
+    pub(crate) error_code: ErrorCode,
+}
+
+impl ProduceResponse {
+    pub(crate) fn decode(buf: &[u8], partition: i32) -> anyhow::Result<Self> {
+        let mut d = ResponseDecoder::new(buf);
+
+        let mut error_code = ErrorCode(0);
+        let topic_count = d.get_array_len()?;
+        for _ in 0..topic_count {
+            let _name = d.get_string()?;
+            let partition_count = d.get_array_len()?;
+            for _ in 0..partition_count {
+                let index = d.get_i32()?;
+                let code = d.get_i16()?;
+                let _base_offset = d.get_i64()?;
+                let _log_append_time = d.get_i64()?;
+                if index == partition {
+                    error_code = ErrorCode(code);
+                }
+            }
+        }
+        Ok(ProduceResponse { error_code })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_request() {
+        let req = ProduceRequest {
+            acks: 1,
+            timeout_ms: 1500,
+            topic: "logs",
+            partition: 2,
+            record_batch: &[0x01, 0x02, 0x03],
+        };
+        let buf = req.encode(7, "g3");
+        let expected: &[u8] = &[
+            0x00, 0x00, 0x00, 0x2d, // size
+            0x00, 0x00, // api key
+            0x00, 0x03, // api version
+            0x00, 0x00, 0x00, 0x07, // correlation id
+            0x00, 0x02, b'g', b'3', // client id
+            0xff, 0xff, // transactional id
+            0x00, 0x01, // acks
+            0x00, 0x00, 0x05, 0xdc, // timeout ms
+            0x00, 0x00, 0x00, 0x01, // topic data count
+            0x00, 0x04, b'l', b'o', b'g', b's', // topic name
+            0x00, 0x00, 0x00, 0x01, // partition data count
+            0x00, 0x00, 0x00, 0x02, // partition index
+            0x00, 0x00, 0x00, 0x03, // records size
+            0x01, 0x02, 0x03, // records
+        ];
+        assert_eq!(buf.as_slice(), expected);
+    }
+
+    const RESPONSE: &[u8] = &[
+        0x00, 0x00, 0x00, 0x01, // responses count
+        0x00, 0x04, b'l', b'o', b'g', b's', // topic name
+        0x00, 0x00, 0x00, 0x02, // partition responses count
+        0x00, 0x00, 0x00, 0x00, // partition index
+        0x00, 0x00, // error code
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, // base offset
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // log append time
+        0x00, 0x00, 0x00, 0x02, // partition index
+        0x00, 0x06, // error code
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // base offset
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // log append time
+        0x00, 0x00, 0x00, 0x00, // throttle time ms
+    ];
+
+    #[test]
+    fn decode_response() {
+        let rsp = ProduceResponse::decode(RESPONSE, 0).unwrap();
+        assert!(rsp.error_code.is_ok());
+
+        let rsp = ProduceResponse::decode(RESPONSE, 2).unwrap();
+        assert_eq!(rsp.error_code.0, 6);
+        assert!(rsp.error_code.need_refresh_metadata());
+    }
+
+    #[test]
+    fn decode_truncated() {
+        assert!(ProduceResponse::decode(&RESPONSE[..30], 0).is_err());
+    }
+}
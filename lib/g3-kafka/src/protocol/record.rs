@@ -0,0 +1,174 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{self, Write};
+
+use bytes::BufMut;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::KafkaCompression;
+
+const RECORD_BATCH_MAGIC: i8 = 2;
+const RECORD_BATCH_HEADER_SIZE: usize = 61;
+const CRC_OFFSET: usize = 17;
+const ATTRIBUTES_OFFSET: usize = 21;
+
+/// Encode log values as records in a v2 record batch
+#[derive(Default)]
+pub(crate) struct RecordBatchEncoder {
+    records: Vec<u8>,
+    count: i32,
+    first_timestamp: i64,
+    max_timestamp: i64,
+}
+
+impl RecordBatchEncoder {
+    #[inline]
+    pub(crate) fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    #[inline]
+    pub(crate) fn size(&self) -> usize {
+        self.records.len()
+    }
+
+    pub(crate) fn push(&mut self, value: &[u8], timestamp: i64) {
+        if self.count == 0 {
+            self.first_timestamp = timestamp;
+        }
+        self.max_timestamp = self.max_timestamp.max(timestamp);
+
+        let mut body = Vec::with_capacity(value.len() + 16);
+        body.put_i8(0); // attributes
+        super::put_varint(&mut body, timestamp - self.first_timestamp);
+        super::put_varint(&mut body, self.count as i64);
+        super::put_varint(&mut body, -1); // null key
+        super::put_varint(&mut body, value.len() as i64);
+        body.put_slice(value);
+        super::put_varint(&mut body, 0); // no headers
+
+        super::put_varint(&mut self.records, body.len() as i64);
+        self.records.put_slice(&body);
+        self.count += 1;
+    }
+
+    pub(crate) fn finish(self, compression: KafkaCompression) -> io::Result<Vec<u8>> {
+        let records = match compression {
+            KafkaCompression::None => self.records,
+            KafkaCompression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&self.records)?;
+                encoder.finish()?
+            }
+        };
+
+        let mut buf = Vec::with_capacity(RECORD_BATCH_HEADER_SIZE + records.len());
+        buf.put_i64(0); // base offset
+        buf.put_i32(0); // batch length, will be set later
+        buf.put_i32(-1); // partition leader epoch
+        buf.put_i8(RECORD_BATCH_MAGIC);
+        buf.put_u32(0); // crc, will be set later
+        buf.put_i16(compression.codec());
+        buf.put_i32(self.count - 1); // last offset delta
+        buf.put_i64(self.first_timestamp);
+        buf.put_i64(self.max_timestamp);
+        buf.put_i64(-1); // producer id
+        buf.put_i16(-1); // producer epoch
+        buf.put_i32(-1); // base sequence
+        buf.put_i32(self.count);
+        buf.put_slice(&records);
+
+        let batch_len = (buf.len() - 12) as i32;
+        buf[8..12].copy_from_slice(&batch_len.to_be_bytes());
+        let crc = super::crc32c::checksum(&buf[ATTRIBUTES_OFFSET..]);
+        buf[CRC_OFFSET..ATTRIBUTES_OFFSET].copy_from_slice(&crc.to_be_bytes());
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_batch() {
+        let mut encoder = RecordBatchEncoder::default();
+        encoder.push(b"a", 1000);
+        encoder.push(b"bc", 1005);
+        assert_eq!(encoder.count(), 2);
+        assert_eq!(encoder.size(), 17);
+
+        let buf = encoder.finish(KafkaCompression::None).unwrap();
+        let expected: &[u8] = &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // base offset
+            0x00, 0x00, 0x00, 0x42, // batch length
+            0xff, 0xff, 0xff, 0xff, // partition leader epoch
+            0x02, // magic
+            0xdf, 0xd8, 0x82, 0x42, // crc
+            0x00, 0x00, // attributes
+            0x00, 0x00, 0x00, 0x01, // last offset delta
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xe8, // first timestamp
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xed, // max timestamp
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // producer id
+            0xff, 0xff, // producer epoch
+            0xff, 0xff, 0xff, 0xff, // base sequence
+            0x00, 0x00, 0x00, 0x02, // records count
+            // record 0
+            0x0e, // length
+            0x00, // attributes
+            0x00, // timestamp delta
+            0x00, // offset delta
+            0x01, // null key
+            0x02, b'a', // value
+            0x00, // headers count
+            // record 1
+            0x10, // length
+            0x00, // attributes
+            0x0a, // timestamp delta
+            0x02, // offset delta
+            0x01, // null key
+            0x04, b'b', b'c', // value
+            0x00, // headers count
+        ];
+        assert_eq!(buf.as_slice(), expected);
+    }
+
+    #[test]
+    fn encode_gzip_batch() {
+        let mut encoder = RecordBatchEncoder::default();
+        encoder.push(b"a", 1000);
+
+        let buf = encoder.finish(KafkaCompression::Gzip).unwrap();
+        assert_eq!(
+            &buf[ATTRIBUTES_OFFSET..ATTRIBUTES_OFFSET + 2],
+            &[0x00, 0x01]
+        );
+        let batch_len = i32::from_be_bytes([buf[8], buf[9], buf[10], buf[11]]);
+        assert_eq!(batch_len as usize, buf.len() - 12);
+        let crc = u32::from_be_bytes([buf[17], buf[18], buf[19], buf[20]]);
+        assert_eq!(
+            crc,
+            super::super::crc32c::checksum(&buf[ATTRIBUTES_OFFSET..])
+        );
+        // gzip magic
+        assert_eq!(
+            &buf[RECORD_BATCH_HEADER_SIZE..RECORD_BATCH_HEADER_SIZE + 2],
+            &[0x1f, 0x8b]
+        );
+    }
+}
@@ -16,7 +16,7 @@
 
 use std::time::Duration;
 
-use super::AnyResolveDriverConfig;
+use super::{AnyResolveDriverConfig, ResolverDenyListConfig, ResolverStaticHostsConfig};
 
 pub(crate) const RESOLVER_MINIMUM_CACHE_TTL: u32 = 30;
 #[cfg(any(feature = "c-ares", feature = "hickory"))]
@@ -26,6 +26,7 @@ const RESOLVER_CACHE_INITIAL_CAPACITY: usize = 10;
 const RESOLVER_BATCH_REQUEST_COUNT: usize = 10;
 const RESOLVER_PROTECTIVE_QUERY_TIMEOUT: Duration = Duration::from_secs(60);
 const RESOLVER_GRACEFUL_STOP_WAIT: Duration = Duration::from_secs(30);
+const RESOLVER_PREFETCH_AHEAD: Duration = Duration::from_secs(5);
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ResolverRuntimeConfig {
@@ -33,6 +34,13 @@ pub struct ResolverRuntimeConfig {
     pub batch_request_count: usize,
     pub protective_query_timeout: Duration,
     pub graceful_stop_wait: Duration,
+    pub static_hosts: ResolverStaticHostsConfig,
+    pub deny_list: ResolverDenyListConfig,
+    pub nxdomain_negative_ttl: Option<u32>,
+    pub servfail_negative_ttl: Option<u32>,
+    pub serve_stale: Option<Duration>,
+    pub prefetch_min_hits: Option<u32>,
+    pub prefetch_ahead: Duration,
 }
 
 impl Default for ResolverRuntimeConfig {
@@ -42,6 +50,13 @@ impl Default for ResolverRuntimeConfig {
             batch_request_count: RESOLVER_BATCH_REQUEST_COUNT,
             protective_query_timeout: RESOLVER_PROTECTIVE_QUERY_TIMEOUT,
             graceful_stop_wait: RESOLVER_GRACEFUL_STOP_WAIT,
+            static_hosts: ResolverStaticHostsConfig::default(),
+            deny_list: ResolverDenyListConfig::default(),
+            nxdomain_negative_ttl: None,
+            servfail_negative_ttl: None,
+            serve_stale: None,
+            prefetch_min_hits: None,
+            prefetch_ahead: RESOLVER_PREFETCH_AHEAD,
         }
     }
 }
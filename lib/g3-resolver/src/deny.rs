@@ -0,0 +1,85 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeSet;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ResolverDenyListConfig {
+    exact: BTreeSet<String>,
+    child: BTreeSet<String>,
+}
+
+impl ResolverDenyListConfig {
+    /// deny the domain itself only
+    pub fn add_exact(&mut self, domain: &str) {
+        self.exact.insert(normalize_domain(domain));
+    }
+
+    /// deny all the sub domains of the domain
+    pub fn add_child(&mut self, domain: &str) {
+        self.child.insert(normalize_domain(domain));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.child.is_empty()
+    }
+
+    pub(crate) fn is_denied(&self, domain: &str) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let domain = normalize_domain(domain);
+        if self.exact.contains(&domain) {
+            return true;
+        }
+        if self.child.is_empty() {
+            return false;
+        }
+        let mut s = domain.as_str();
+        while let Some((_, parent)) = s.split_once('.') {
+            if self.child.contains(parent) {
+                return true;
+            }
+            s = parent;
+        }
+        false
+    }
+}
+
+fn normalize_domain(domain: &str) -> String {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    domain.to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deny() {
+        let mut config = ResolverDenyListConfig::default();
+        assert!(!config.is_denied("www.example.net"));
+
+        config.add_exact("Example.net");
+        config.add_child("bad.example.org.");
+        assert!(config.is_denied("example.net"));
+        assert!(!config.is_denied("www.example.net"));
+        assert!(!config.is_denied("bad.example.org"));
+        assert!(config.is_denied("www.Bad.example.org"));
+        assert!(!config.is_denied("notbad.example.org"));
+        assert!(!config.is_denied("example.org"));
+    }
+}
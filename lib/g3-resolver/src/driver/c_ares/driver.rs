@@ -25,7 +25,7 @@ use tokio::time::Instant;
 
 use crate::config::ResolverRuntimeConfig;
 use crate::message::ResolveDriverResponse;
-use crate::{ResolveDriver, ResolveError, ResolvedRecord};
+use crate::{ClientSubnet, ResolveDriver, ResolveError, ResolvedRecord};
 
 pub(super) struct CAresResolver {
     pub(super) inner: FutureResolver,
@@ -143,6 +143,7 @@ impl ResolveDriver for CAresResolver {
     fn query_v4(
         &self,
         domain: String,
+        _subnet: Option<ClientSubnet>,
         config: &ResolverRuntimeConfig,
         sender: mpsc::UnboundedSender<ResolveDriverResponse>,
     ) {
@@ -151,13 +152,14 @@ impl ResolveDriver for CAresResolver {
         tokio::spawn(async move {
             let record = resolve_protective(query, domain, job_config).await;
 
-            let _ = sender.send(ResolveDriverResponse::V4(record)); // TODO log error
+            let _ = sender.send(ResolveDriverResponse::V4(record, None)); // TODO log error
         });
     }
 
     fn query_v6(
         &self,
         domain: String,
+        _subnet: Option<ClientSubnet>,
         config: &ResolverRuntimeConfig,
         sender: mpsc::UnboundedSender<ResolveDriverResponse>,
     ) {
@@ -166,7 +168,7 @@ impl ResolveDriver for CAresResolver {
         tokio::spawn(async move {
             let record = resolve_protective(query, domain, job_config).await;
 
-            let _ = sender.send(ResolveDriverResponse::V6(record)); // TODO log error
+            let _ = sender.send(ResolveDriverResponse::V6(record, None)); // TODO log error
         });
     }
 }
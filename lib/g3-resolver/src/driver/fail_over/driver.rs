@@ -22,8 +22,8 @@ use super::FailOverDriverStaticConfig;
 use crate::config::ResolverRuntimeConfig;
 use crate::message::ResolveDriverResponse;
 use crate::{
-    ResolveDriver, ResolveJob, ResolveJobRecvResult, ResolveLocalError, ResolvedRecord,
-    ResolverHandle,
+    ClientSubnet, ResolveDriver, ResolveJob, ResolveJobRecvResult, ResolveLocalError,
+    ResolvedRecord, ResolverHandle,
 };
 
 pub(super) struct FailOverResolver {
@@ -133,18 +133,29 @@ impl ResolveDriver for FailOverResolver {
     fn query_v4(
         &self,
         domain: String,
+        subnet: Option<ClientSubnet>,
         config: &ResolverRuntimeConfig,
         sender: mpsc::UnboundedSender<ResolveDriverResponse>,
     ) {
         let job_primary = self
             .primary
             .as_ref()
-            .map(|handle| handle.get_v4(domain.clone()).map(Some).unwrap_or(None))
+            .map(|handle| {
+                handle
+                    .get_v4_with_subnet(domain.clone(), subnet)
+                    .map(Some)
+                    .unwrap_or(None)
+            })
             .unwrap_or(None);
         let job_standby = self
             .standby
             .as_ref()
-            .map(|handle| handle.get_v4(domain.clone()).map(Some).unwrap_or(None))
+            .map(|handle| {
+                handle
+                    .get_v4_with_subnet(domain.clone(), subnet)
+                    .map(Some)
+                    .unwrap_or(None)
+            })
             .unwrap_or(None);
         let job = FailOverResolverJob {
             primary: job_primary,
@@ -154,25 +165,36 @@ impl ResolveDriver for FailOverResolver {
         };
         tokio::spawn(async move {
             let record = job.resolve_protective(domain).await;
-            let _ = sender.send(ResolveDriverResponse::V4(record)); // TODO log error
+            let _ = sender.send(ResolveDriverResponse::V4(record, subnet)); // TODO log error
         });
     }
 
     fn query_v6(
         &self,
         domain: String,
+        subnet: Option<ClientSubnet>,
         config: &ResolverRuntimeConfig,
         sender: mpsc::UnboundedSender<ResolveDriverResponse>,
     ) {
         let job_primary = self
             .primary
             .as_ref()
-            .map(|handle| handle.get_v6(domain.clone()).map(Some).unwrap_or(None))
+            .map(|handle| {
+                handle
+                    .get_v6_with_subnet(domain.clone(), subnet)
+                    .map(Some)
+                    .unwrap_or(None)
+            })
             .unwrap_or(None);
         let job_standby = self
             .standby
             .as_ref()
-            .map(|handle| handle.get_v6(domain.clone()).map(Some).unwrap_or(None))
+            .map(|handle| {
+                handle
+                    .get_v6_with_subnet(domain.clone(), subnet)
+                    .map(Some)
+                    .unwrap_or(None)
+            })
             .unwrap_or(None);
         let job = FailOverResolverJob {
             primary: job_primary,
@@ -182,7 +204,7 @@ impl ResolveDriver for FailOverResolver {
         };
         tokio::spawn(async move {
             let record = job.resolve_protective(domain).await;
-            let _ = sender.send(ResolveDriverResponse::V6(record)); // TODO log error
+            let _ = sender.send(ResolveDriverResponse::V6(record, subnet)); // TODO log error
         });
     }
 }
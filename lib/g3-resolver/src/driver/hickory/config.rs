@@ -24,10 +24,14 @@ use hickory_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOp
 use hickory_resolver::TokioAsyncResolver;
 use rustls::ServerName;
 
+#[cfg(feature = "quic")]
+use g3_types::net::{AlpnProtocol, RustlsClientConfigBuilder};
 use g3_types::net::{DnsEncryptionConfigBuilder, DnsEncryptionProtocol};
 
-use super::HickoryResolver;
-use crate::BoxResolverDriver;
+use super::{ClientSubnetQuery, HickoryResolver, ResponseConverter};
+#[cfg(feature = "quic")]
+use super::{QuicQuery, QuicResolver};
+use crate::{BoxResolverDriver, ClientSubnetConfig};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct HickoryDriverConfig {
@@ -41,6 +45,7 @@ pub struct HickoryDriverConfig {
     server_port: Option<u16>,
     bind_ip: Option<IpAddr>,
     encryption: Option<DnsEncryptionConfigBuilder>,
+    client_subnet: Option<ClientSubnetConfig>,
 }
 
 impl Default for HickoryDriverConfig {
@@ -56,6 +61,7 @@ impl Default for HickoryDriverConfig {
             server_port: None,
             bind_ip: None,
             encryption: None,
+            client_subnet: None,
         }
     }
 }
@@ -164,6 +170,15 @@ impl HickoryDriverConfig {
         self.encryption.as_ref()
     }
 
+    pub fn set_client_subnet(&mut self, config: ClientSubnetConfig) {
+        self.client_subnet = Some(config);
+    }
+
+    #[inline]
+    pub fn get_client_subnet(&self) -> Option<&ClientSubnetConfig> {
+        self.client_subnet.as_ref()
+    }
+
     pub fn set_each_timeout(&mut self, timeout: Duration) {
         self.each_timeout = timeout;
     }
@@ -201,7 +216,90 @@ impl HickoryDriverConfig {
         self.servers.is_empty()
     }
 
+    fn build_subnet_query(&self) -> anyhow::Result<Option<ClientSubnetQuery>> {
+        if self.client_subnet.is_none() {
+            return Ok(None);
+        }
+        if self.encryption.is_some() {
+            return Err(anyhow!(
+                "client subnet is not supported together with encryption"
+            ));
+        }
+        let port = self.server_port.unwrap_or(53);
+        Ok(Some(ClientSubnetQuery {
+            servers: self
+                .servers
+                .iter()
+                .map(|ip| SocketAddr::new(*ip, port))
+                .collect(),
+            bind_ip: self.bind_ip,
+            each_timeout: self.each_timeout,
+            retry_attempts: self.retry_attempts,
+            converter: self.response_converter(),
+        }))
+    }
+
+    fn response_converter(&self) -> ResponseConverter {
+        ResponseConverter {
+            positive_min_ttl: self.positive_min_ttl,
+            positive_max_ttl: self.positive_max_ttl,
+            negative_min_ttl: self.negative_min_ttl,
+            negative_max_ttl: self.negative_max_ttl,
+        }
+    }
+
+    #[cfg(feature = "quic")]
+    fn build_quic_query(&self, ec: &DnsEncryptionConfigBuilder) -> anyhow::Result<QuicQuery> {
+        let tls_name = match ec.tls_name() {
+            ServerName::DnsName(n) => n.as_ref().to_string(),
+            ServerName::IpAddress(ip) => ip.to_string(),
+            v => return Err(anyhow!("unsupported tls server name: {v:?}")),
+        };
+
+        let tls_config = match ec
+            .build_tls_client_config()
+            .context("unable to build tls client config")?
+        {
+            Some(config) => config,
+            None => RustlsClientConfigBuilder::default()
+                .build()
+                .context("unable to build default tls client config")?,
+        };
+        let mut tls_config = (*tls_config.driver).clone();
+        tls_config.alpn_protocols = vec![AlpnProtocol::DnsOverQuic.to_identification_sequence()];
+        // send queries in early data if the session is resumed
+        tls_config.enable_early_data = true;
+
+        let port = self.server_port.unwrap_or(853);
+        QuicQuery::new(
+            self.servers
+                .iter()
+                .map(|ip| SocketAddr::new(*ip, port))
+                .collect(),
+            self.bind_ip,
+            tls_config,
+            tls_name,
+            self.each_timeout,
+            self.retry_attempts,
+            self.response_converter(),
+        )
+        .context("failed to create quic endpoint")
+    }
+
     pub(crate) fn spawn_resolver_driver(&self) -> anyhow::Result<BoxResolverDriver> {
+        let subnet_query = self.build_subnet_query()?;
+
+        #[cfg(feature = "quic")]
+        if let Some(ec) = &self.encryption {
+            if ec.protocol() == DnsEncryptionProtocol::Quic {
+                let resolver = QuicResolver {
+                    inner: Arc::new(self.build_quic_query(ec)?),
+                    protective_cache_ttl: self.negative_min_ttl,
+                };
+                return Ok(Box::new(resolver));
+            }
+        }
+
         let name_servers = NameServerConfigGroup::try_from(self)?;
         let d_config = ResolverConfig::from_parts(None, vec![], name_servers);
         let d_opts = ResolverOpts::from(self);
@@ -211,6 +309,7 @@ impl HickoryDriverConfig {
         let resolver = HickoryResolver {
             inner: Arc::new(d_resolver),
             protective_cache_ttl: self.negative_min_ttl,
+            subnet_query: subnet_query.map(Arc::new),
         };
         Ok(Box::new(resolver))
     }
@@ -19,18 +19,21 @@ use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
+use hickory_proto::rr::RecordType;
 use hickory_resolver::lookup::{Ipv4Lookup, Ipv6Lookup};
 use hickory_resolver::TokioAsyncResolver;
 use tokio::sync::mpsc;
 use tokio::time::Instant;
 
+use super::subnet::ClientSubnetQuery;
 use crate::config::ResolverRuntimeConfig;
 use crate::message::ResolveDriverResponse;
-use crate::{ResolveDriver, ResolvedRecord};
+use crate::{ClientSubnet, ResolveDriver, ResolvedRecord};
 
 pub(super) struct HickoryResolver {
     pub(super) inner: Arc<TokioAsyncResolver>,
     pub(super) protective_cache_ttl: u32,
+    pub(super) subnet_query: Option<Arc<ClientSubnetQuery>>,
 }
 
 struct JobConfig {
@@ -39,6 +42,15 @@ struct JobConfig {
 }
 
 impl HickoryResolver {
+    /// the client subnet will be dropped if not enabled in config
+    fn subnet_query(
+        &self,
+        subnet: Option<ClientSubnet>,
+    ) -> Option<(Arc<ClientSubnetQuery>, ClientSubnet)> {
+        let q = self.subnet_query.as_ref()?;
+        Some((Arc::clone(q), subnet?))
+    }
+
     fn build_job_config(&self, rc: &ResolverRuntimeConfig) -> JobConfig {
         JobConfig {
             timeout: rc.protective_query_timeout,
@@ -101,32 +113,66 @@ impl ResolveDriver for HickoryResolver {
     fn query_v4(
         &self,
         domain: String,
+        subnet: Option<ClientSubnet>,
         config: &ResolverRuntimeConfig,
         sender: mpsc::UnboundedSender<ResolveDriverResponse>,
     ) {
-        let resolver = Arc::clone(&self.inner);
         let job_config = self.build_job_config(config);
+        if let Some((q, s)) = self.subnet_query(subnet) {
+            tokio::spawn(async move {
+                let record = tokio::time::timeout(
+                    job_config.timeout,
+                    q.query(domain.clone(), RecordType::A, s),
+                )
+                .await
+                .unwrap_or_else(|_| {
+                    ResolvedRecord::timed_out(domain, job_config.protective_cache_ttl)
+                });
+
+                let _ = sender.send(ResolveDriverResponse::V4(record, Some(s)));
+            });
+            return;
+        }
+
+        let resolver = Arc::clone(&self.inner);
         tokio::spawn(async move {
             let query = resolver.ipv4_lookup(format!("{domain}.")); // add trailing '.' to avoid search
             let record = resolve_protective(query, domain, job_config).await;
 
-            let _ = sender.send(ResolveDriverResponse::V4(record)); // TODO log error
+            let _ = sender.send(ResolveDriverResponse::V4(record, None)); // TODO log error
         });
     }
 
     fn query_v6(
         &self,
         domain: String,
+        subnet: Option<ClientSubnet>,
         config: &ResolverRuntimeConfig,
         sender: mpsc::UnboundedSender<ResolveDriverResponse>,
     ) {
-        let resolver = Arc::clone(&self.inner);
         let job_config = self.build_job_config(config);
+        if let Some((q, s)) = self.subnet_query(subnet) {
+            tokio::spawn(async move {
+                let record = tokio::time::timeout(
+                    job_config.timeout,
+                    q.query(domain.clone(), RecordType::AAAA, s),
+                )
+                .await
+                .unwrap_or_else(|_| {
+                    ResolvedRecord::timed_out(domain, job_config.protective_cache_ttl)
+                });
+
+                let _ = sender.send(ResolveDriverResponse::V6(record, Some(s)));
+            });
+            return;
+        }
+
+        let resolver = Arc::clone(&self.inner);
         tokio::spawn(async move {
             let query = resolver.ipv6_lookup(format!("{domain}.")); // add trailing '.' to avoid search
             let record = resolve_protective(query, domain, job_config).await;
 
-            let _ = sender.send(ResolveDriverResponse::V4(record)); // TODO log error
+            let _ = sender.send(ResolveDriverResponse::V6(record, None)); // TODO log error
         });
     }
 }
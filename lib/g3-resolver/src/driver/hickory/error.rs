@@ -19,18 +19,24 @@ use hickory_resolver::error::ResolveErrorKind;
 
 use crate::error::{ResolveDriverError, ResolveError, ResolveServerError};
 
+pub(super) fn response_code_error(code: ResponseCode) -> ResolveError {
+    match code {
+        ResponseCode::FormErr => ResolveServerError::FormErr.into(),
+        ResponseCode::ServFail => ResolveServerError::ServFail.into(),
+        ResponseCode::NXDomain => ResolveServerError::NotFound.into(),
+        ResponseCode::NotImp => ResolveServerError::NotImp.into(),
+        ResponseCode::Refused => ResolveServerError::Refused.into(),
+        ResponseCode::BADNAME => ResolveDriverError::BadName.into(),
+        _ => ResolveDriverError::BadResp.into(),
+    }
+}
+
 impl From<hickory_resolver::error::ResolveError> for ResolveError {
     fn from(e: hickory_resolver::error::ResolveError) -> Self {
         match e.kind() {
-            ResolveErrorKind::NoRecordsFound { response_code, .. } => match response_code {
-                ResponseCode::FormErr => ResolveServerError::FormErr.into(),
-                ResponseCode::ServFail => ResolveServerError::ServFail.into(),
-                ResponseCode::NXDomain => ResolveServerError::NotFound.into(),
-                ResponseCode::NotImp => ResolveServerError::NotImp.into(),
-                ResponseCode::Refused => ResolveServerError::Refused.into(),
-                ResponseCode::BADNAME => ResolveDriverError::BadName.into(),
-                _ => ResolveDriverError::BadResp.into(),
-            },
+            ResolveErrorKind::NoRecordsFound { response_code, .. } => {
+                response_code_error(*response_code)
+            }
             ResolveErrorKind::Timeout => ResolveDriverError::Timeout.into(),
             _ => ResolveDriverError::Internal(e.to_string()).into(),
         }
@@ -19,5 +19,16 @@ use driver::HickoryResolver;
 
 mod error;
 
+mod response;
+use response::ResponseConverter;
+
+mod subnet;
+use subnet::ClientSubnetQuery;
+
+#[cfg(feature = "quic")]
+mod quic;
+#[cfg(feature = "quic")]
+use quic::{QuicQuery, QuicResolver};
+
 mod config;
 pub use config::HickoryDriverConfig;
@@ -0,0 +1,311 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hickory_proto::op::{Message, MessageType, OpCode, Query};
+use hickory_proto::rr::{Name, RecordType};
+use quinn::{ClientConfig, Connection, Endpoint};
+use tokio::sync::mpsc;
+
+use super::ResponseConverter;
+use crate::config::ResolverRuntimeConfig;
+use crate::message::ResolveDriverResponse;
+use crate::{ClientSubnet, ResolveDriver, ResolveDriverError, ResolveError, ResolvedRecord};
+
+/// the max size of the length prefixed dns message
+const DOQ_MAX_MESSAGE_SIZE: usize = u16::MAX as usize + 2;
+
+struct QuicServer {
+    addr: SocketAddr,
+    endpoint: Endpoint,
+    connection: Mutex<Option<Connection>>,
+}
+
+impl QuicServer {
+    fn new(addr: SocketAddr, bind_ip: Option<IpAddr>) -> io::Result<Self> {
+        let ip = bind_ip.unwrap_or(match addr {
+            SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        });
+        let endpoint = Endpoint::client(SocketAddr::new(ip, 0))?;
+        Ok(QuicServer {
+            addr,
+            endpoint,
+            connection: Mutex::new(None),
+        })
+    }
+
+    fn alive_connection(&self) -> Option<Connection> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .as_ref()
+            .filter(|c| c.close_reason().is_none())
+            .cloned()
+    }
+
+    fn save_connection(&self, connection: Connection) {
+        let mut old = self.connection.lock().unwrap();
+        *old = Some(connection);
+    }
+}
+
+/// Send DNS over QUIC (RFC 9250) queries directly to the servers.
+/// Each query is sent in a new bidirectional stream on the shared connection to the server,
+/// and early data (0-RTT) will be used when reconnecting with a resumed TLS session.
+pub(super) struct QuicQuery {
+    servers: Vec<QuicServer>,
+    client_config: ClientConfig,
+    tls_name: String,
+    each_timeout: Duration,
+    retry_attempts: usize,
+    converter: ResponseConverter,
+}
+
+fn build_request(domain: &str, rtype: RecordType) -> Result<Message, ResolveError> {
+    let name = Name::from_ascii(format!("{domain}."))
+        .map_err(|_| ResolveError::FromDriver(ResolveDriverError::BadName))?;
+
+    let mut msg = Message::new();
+    // the message id should be 0, see RFC 9250 Section 4.2.1
+    msg.set_id(0)
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true)
+        .add_query(Query::query(name, rtype));
+    Ok(msg)
+}
+
+fn encode_request(req: &Message) -> Result<Vec<u8>, ResolveError> {
+    let buf = req
+        .to_vec()
+        .map_err(|_| ResolveError::FromDriver(ResolveDriverError::BadQuery))?;
+    let len = u16::try_from(buf.len())
+        .map_err(|_| ResolveError::FromDriver(ResolveDriverError::BadQuery))?;
+    let mut req_buf = Vec::with_capacity(buf.len() + 2);
+    req_buf.extend_from_slice(&len.to_be_bytes());
+    req_buf.extend_from_slice(&buf);
+    Ok(req_buf)
+}
+
+fn decode_response(buf: &[u8]) -> io::Result<Message> {
+    let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+    if buf.len() < 2 {
+        return Err(invalid("too short response"));
+    }
+    let len = u16::from_be_bytes([buf[0], buf[1]]) as usize;
+    if buf.len() != len + 2 {
+        return Err(invalid("response length mismatch"));
+    }
+    let rsp = Message::from_vec(&buf[2..]).map_err(|_| invalid("malformed response"))?;
+    if rsp.id() != 0 || rsp.message_type() != MessageType::Response {
+        return Err(invalid("invalid response"));
+    }
+    Ok(rsp)
+}
+
+async fn send_query(connection: &Connection, buf: &[u8]) -> io::Result<Message> {
+    let (mut send, mut recv) = connection.open_bi().await.map_err(io::Error::other)?;
+    send.write_all(buf).await.map_err(io::Error::other)?;
+    send.finish().await.map_err(io::Error::other)?;
+    let rsp = recv
+        .read_to_end(DOQ_MAX_MESSAGE_SIZE)
+        .await
+        .map_err(io::Error::other)?;
+    decode_response(&rsp)
+}
+
+impl QuicQuery {
+    pub(super) fn new(
+        servers: Vec<SocketAddr>,
+        bind_ip: Option<IpAddr>,
+        tls_config: rustls::ClientConfig,
+        tls_name: String,
+        each_timeout: Duration,
+        retry_attempts: usize,
+        converter: ResponseConverter,
+    ) -> io::Result<Self> {
+        let servers = servers
+            .into_iter()
+            .map(|addr| QuicServer::new(addr, bind_ip))
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(QuicQuery {
+            servers,
+            client_config: ClientConfig::new(Arc::new(tls_config)),
+            tls_name,
+            each_timeout,
+            retry_attempts,
+            converter,
+        })
+    }
+
+    async fn exchange(&self, server: &QuicServer, buf: &[u8]) -> io::Result<Message> {
+        if let Some(connection) = server.alive_connection() {
+            match send_query(&connection, buf).await {
+                Ok(rsp) => return Ok(rsp),
+                // the connection may be closed by the server, use a new one
+                Err(_) if connection.close_reason().is_some() => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let connecting = server
+            .endpoint
+            .connect_with(self.client_config.clone(), server.addr, &self.tls_name)
+            .map_err(io::Error::other)?;
+        match connecting.into_0rtt() {
+            Ok((connection, zero_rtt_accepted)) => {
+                server.save_connection(connection.clone());
+                match send_query(&connection, buf).await {
+                    Ok(rsp) => Ok(rsp),
+                    Err(e) => {
+                        if zero_rtt_accepted.await {
+                            Err(e)
+                        } else {
+                            // the early data is rejected, resend after the handshake completed
+                            send_query(&connection, buf).await
+                        }
+                    }
+                }
+            }
+            Err(connecting) => {
+                let connection = connecting.await?;
+                server.save_connection(connection.clone());
+                send_query(&connection, buf).await
+            }
+        }
+    }
+
+    pub(super) async fn query(&self, domain: String, rtype: RecordType) -> ResolvedRecord {
+        let buf = match build_request(&domain, rtype).and_then(|req| encode_request(&req)) {
+            Ok(buf) => buf,
+            Err(e) => return self.converter.failed(domain, e),
+        };
+
+        let mut last_err = ResolveDriverError::Timeout;
+        for _ in 0..self.retry_attempts.max(1) {
+            for server in &self.servers {
+                match tokio::time::timeout(self.each_timeout, self.exchange(server, &buf)).await {
+                    Ok(Ok(rsp)) => return self.converter.convert(domain, rtype, rsp),
+                    Ok(Err(e)) => {
+                        last_err = if e.kind() == io::ErrorKind::ConnectionRefused {
+                            ResolveDriverError::ConnRefused
+                        } else {
+                            ResolveDriverError::Internal(e.to_string())
+                        };
+                    }
+                    Err(_) => last_err = ResolveDriverError::Timeout,
+                }
+            }
+        }
+        self.converter.failed(domain, last_err.into())
+    }
+}
+
+pub(super) struct QuicResolver {
+    pub(super) inner: Arc<QuicQuery>,
+    pub(super) protective_cache_ttl: u32,
+}
+
+impl ResolveDriver for QuicResolver {
+    fn query_v4(
+        &self,
+        domain: String,
+        _subnet: Option<ClientSubnet>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        let query = Arc::clone(&self.inner);
+        let timeout = config.protective_query_timeout;
+        let protective_cache_ttl = self.protective_cache_ttl;
+        tokio::spawn(async move {
+            let record = tokio::time::timeout(timeout, query.query(domain.clone(), RecordType::A))
+                .await
+                .unwrap_or_else(|_| ResolvedRecord::timed_out(domain, protective_cache_ttl));
+
+            let _ = sender.send(ResolveDriverResponse::V4(record, None));
+        });
+    }
+
+    fn query_v6(
+        &self,
+        domain: String,
+        _subnet: Option<ClientSubnet>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        let query = Arc::clone(&self.inner);
+        let timeout = config.protective_query_timeout;
+        let protective_cache_ttl = self.protective_cache_ttl;
+        tokio::spawn(async move {
+            let record =
+                tokio::time::timeout(timeout, query.query(domain.clone(), RecordType::AAAA))
+                    .await
+                    .unwrap_or_else(|_| ResolvedRecord::timed_out(domain, protective_cache_ttl));
+
+            let _ = sender.send(ResolveDriverResponse::V6(record, None));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request() {
+        let req = build_request("www.example.net", RecordType::AAAA).unwrap();
+        let buf = encode_request(&req).unwrap();
+        let len = u16::from_be_bytes([buf[0], buf[1]]) as usize;
+        assert_eq!(len + 2, buf.len());
+
+        let decoded = Message::from_vec(&buf[2..]).unwrap();
+        assert_eq!(decoded.id(), 0);
+        assert!(decoded.recursion_desired());
+        assert_eq!(decoded.queries()[0].query_type(), RecordType::AAAA);
+        assert_eq!(decoded.queries()[0].name().to_ascii(), "www.example.net.");
+
+        let long_label = "a".repeat(64);
+        assert!(build_request(&long_label, RecordType::A).is_err());
+    }
+
+    fn response(id: u16, message_type: MessageType) -> Vec<u8> {
+        let mut rsp = Message::new();
+        rsp.set_id(id).set_message_type(message_type);
+        let buf = rsp.to_vec().unwrap();
+        let mut rsp_buf = (buf.len() as u16).to_be_bytes().to_vec();
+        rsp_buf.extend_from_slice(&buf);
+        rsp_buf
+    }
+
+    #[test]
+    fn response_check() {
+        let buf = response(0, MessageType::Response);
+        assert!(decode_response(&buf).is_ok());
+        assert!(decode_response(&buf[..buf.len() - 1]).is_err());
+        assert!(decode_response(&buf[..1]).is_err());
+
+        let mut long = buf.clone();
+        long.push(0);
+        assert!(decode_response(&long).is_err());
+
+        assert!(decode_response(&response(1, MessageType::Response)).is_err());
+        assert!(decode_response(&response(0, MessageType::Query)).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use hickory_proto::op::{Message, ResponseCode};
+use hickory_proto::rr::{RData, RecordType};
+use tokio::time::Instant;
+
+use super::error::response_code_error;
+use crate::{ResolveError, ResolvedRecord};
+
+/// Convert the raw responses to records, for the queries not sent by the hickory resolver
+#[derive(Clone, Copy, Debug)]
+pub(super) struct ResponseConverter {
+    pub(super) positive_min_ttl: u32,
+    pub(super) positive_max_ttl: u32,
+    pub(super) negative_min_ttl: u32,
+    pub(super) negative_max_ttl: u32,
+}
+
+impl ResponseConverter {
+    pub(super) fn failed(&self, domain: String, e: ResolveError) -> ResolvedRecord {
+        ResolvedRecord::failed(domain, self.negative_min_ttl, e)
+    }
+
+    fn negative_ttl(&self, rsp: &Message) -> u32 {
+        rsp.name_servers()
+            .iter()
+            .find_map(|r| match r.data() {
+                Some(RData::SOA(soa)) => Some(soa.minimum().min(r.ttl())),
+                _ => None,
+            })
+            .unwrap_or(self.negative_min_ttl)
+            .max(self.negative_min_ttl)
+            .min(self.negative_max_ttl)
+    }
+
+    pub(super) fn convert(
+        &self,
+        domain: String,
+        rtype: RecordType,
+        rsp: Message,
+    ) -> ResolvedRecord {
+        let code = rsp.response_code();
+        if code != ResponseCode::NoError {
+            return ResolvedRecord::failed(
+                domain,
+                self.negative_ttl(&rsp),
+                response_code_error(code),
+            );
+        }
+
+        let mut addrs = Vec::new();
+        let mut ttl = u32::MAX;
+        for r in rsp.answers() {
+            match r.data() {
+                Some(RData::A(a)) if rtype == RecordType::A => addrs.push(IpAddr::V4(a.0)),
+                Some(RData::AAAA(a)) if rtype == RecordType::AAAA => addrs.push(IpAddr::V6(a.0)),
+                _ => continue,
+            }
+            ttl = ttl.min(r.ttl());
+        }
+        if addrs.is_empty() {
+            return ResolvedRecord::failed(
+                domain,
+                self.negative_ttl(&rsp),
+                response_code_error(code),
+            );
+        }
+
+        let ttl = ttl.max(self.positive_min_ttl).min(self.positive_max_ttl);
+        let created = Instant::now();
+        ResolvedRecord {
+            domain,
+            created,
+            expire: created.checked_add(Duration::from_secs(ttl as u64)),
+            result: Ok(addrs),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    use hickory_proto::rr::rdata::{A, SOA};
+    use hickory_proto::rr::{Name, Record};
+
+    use crate::ResolveServerError;
+
+    const CONVERTER: ResponseConverter = ResponseConverter {
+        positive_min_ttl: 30,
+        positive_max_ttl: 3600,
+        negative_min_ttl: 30,
+        negative_max_ttl: 600,
+    };
+
+    fn name() -> Name {
+        Name::from_str("www.example.net.").unwrap()
+    }
+
+    fn a_record(ip: &str, ttl: u32) -> Record {
+        let ip = Ipv4Addr::from_str(ip).unwrap();
+        Record::from_rdata(name(), ttl, RData::A(A(ip)))
+    }
+
+    fn soa_record(minimum: u32, ttl: u32) -> Record {
+        let zone = Name::from_str("example.net.").unwrap();
+        let soa = SOA::new(zone.clone(), zone.clone(), 1, 3600, 600, 86400, minimum);
+        Record::from_rdata(zone, ttl, RData::SOA(soa))
+    }
+
+    fn ttl(record: &ResolvedRecord) -> u64 {
+        record
+            .expire
+            .unwrap()
+            .duration_since(record.created)
+            .as_secs()
+    }
+
+    #[test]
+    fn positive() {
+        let mut rsp = Message::new();
+        rsp.add_answer(a_record("192.0.2.1", 100));
+        rsp.add_answer(a_record("192.0.2.2", 60));
+        let record = CONVERTER.convert("www.example.net".to_string(), RecordType::A, rsp);
+        let addrs = record.result.as_ref().unwrap();
+        assert_eq!(addrs.len(), 2);
+        assert_eq!(addrs[1], IpAddr::from_str("192.0.2.2").unwrap());
+        assert_eq!(ttl(&record), 60);
+
+        let mut rsp = Message::new();
+        rsp.add_answer(a_record("192.0.2.1", 10));
+        let record = CONVERTER.convert("www.example.net".to_string(), RecordType::A, rsp);
+        assert_eq!(ttl(&record), 30);
+
+        // the A records should be skipped for AAAA queries
+        let mut rsp = Message::new();
+        rsp.add_answer(a_record("192.0.2.1", 100));
+        let record = CONVERTER.convert("www.example.net".to_string(), RecordType::AAAA, rsp);
+        assert!(record.is_err());
+    }
+
+    #[test]
+    fn negative() {
+        let mut rsp = Message::new();
+        rsp.set_response_code(ResponseCode::NXDomain);
+        rsp.add_name_server(soa_record(300, 900));
+        let record = CONVERTER.convert("www.example.net".to_string(), RecordType::A, rsp);
+        assert!(matches!(
+            record.result,
+            Err(ResolveError::FromServer(ResolveServerError::NotFound))
+        ));
+        assert_eq!(ttl(&record), 300);
+
+        let mut rsp = Message::new();
+        rsp.set_response_code(ResponseCode::NXDomain);
+        rsp.add_name_server(soa_record(3600, 3600));
+        let record = CONVERTER.convert("www.example.net".to_string(), RecordType::A, rsp);
+        assert_eq!(ttl(&record), 600);
+
+        let mut rsp = Message::new();
+        rsp.set_response_code(ResponseCode::ServFail);
+        let record = CONVERTER.convert("www.example.net".to_string(), RecordType::A, rsp);
+        assert!(matches!(
+            record.result,
+            Err(ResolveError::FromServer(ResolveServerError::ServFail))
+        ));
+        assert_eq!(ttl(&record), 30);
+    }
+}
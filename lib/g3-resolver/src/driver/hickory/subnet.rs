@@ -0,0 +1,210 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use hickory_proto::op::{Edns, Message, MessageType, OpCode, Query};
+use hickory_proto::rr::rdata::opt::EdnsOption;
+use hickory_proto::rr::{Name, RecordType};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpSocket, UdpSocket};
+
+use super::ResponseConverter;
+use crate::{
+    ClientSubnet, ResolveDriverError, ResolveError, ResolvedRecord, EDNS_OPTION_CODE_CLIENT_SUBNET,
+};
+
+const EDNS_MAX_PAYLOAD: u16 = 1232;
+const UDP_RECV_BUFFER_SIZE: usize = 4096;
+
+/// Send queries with the EDNS Client Subnet option directly to the servers,
+/// as the hickory resolver has no per query EDNS option support.
+pub(super) struct ClientSubnetQuery {
+    pub(super) servers: Vec<SocketAddr>,
+    pub(super) bind_ip: Option<IpAddr>,
+    pub(super) each_timeout: Duration,
+    pub(super) retry_attempts: usize,
+    pub(super) converter: ResponseConverter,
+}
+
+fn build_request(
+    domain: &str,
+    rtype: RecordType,
+    subnet: &ClientSubnet,
+) -> Result<Message, ResolveError> {
+    let name = Name::from_ascii(format!("{domain}."))
+        .map_err(|_| ResolveError::FromDriver(ResolveDriverError::BadName))?;
+
+    let mut edns = Edns::new();
+    edns.set_max_payload(EDNS_MAX_PAYLOAD);
+    edns.options_mut().insert(EdnsOption::Unknown(
+        EDNS_OPTION_CODE_CLIENT_SUBNET,
+        subnet.encode_option_data(),
+    ));
+
+    let mut msg = Message::new();
+    msg.set_id(fastrand::u16(..))
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true)
+        .add_query(Query::query(name, rtype))
+        .set_edns(edns);
+    Ok(msg)
+}
+
+fn is_valid_response(req: &Message, buf: &[u8]) -> Option<Message> {
+    let rsp = Message::from_vec(buf).ok()?;
+    if rsp.id() != req.id() || rsp.message_type() != MessageType::Response {
+        return None;
+    }
+    Some(rsp)
+}
+
+impl ClientSubnetQuery {
+    fn bind_addr(&self, server: &SocketAddr) -> SocketAddr {
+        let ip = self.bind_ip.unwrap_or(match server {
+            SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        });
+        SocketAddr::new(ip, 0)
+    }
+
+    async fn query_udp(
+        &self,
+        server: SocketAddr,
+        req: &Message,
+        buf: &[u8],
+    ) -> io::Result<Message> {
+        let socket = UdpSocket::bind(self.bind_addr(&server)).await?;
+        socket.connect(server).await?;
+        socket.send(buf).await?;
+
+        let mut rsp_buf = vec![0u8; UDP_RECV_BUFFER_SIZE];
+        loop {
+            let len = socket.recv(&mut rsp_buf).await?;
+            // skip the unmatched ones, which may be forged
+            if let Some(rsp) = is_valid_response(req, &rsp_buf[..len]) {
+                return Ok(rsp);
+            }
+        }
+    }
+
+    async fn query_tcp(
+        &self,
+        server: SocketAddr,
+        req: &Message,
+        buf: &[u8],
+    ) -> io::Result<Message> {
+        let socket = match server {
+            SocketAddr::V4(_) => TcpSocket::new_v4()?,
+            SocketAddr::V6(_) => TcpSocket::new_v6()?,
+        };
+        if self.bind_ip.is_some() {
+            socket.bind(self.bind_addr(&server))?;
+        }
+        let mut stream = socket.connect(server).await?;
+
+        let len = u16::try_from(buf.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too large request"))?;
+        let mut req_buf = Vec::with_capacity(buf.len() + 2);
+        req_buf.extend_from_slice(&len.to_be_bytes());
+        req_buf.extend_from_slice(buf);
+        stream.write_all(&req_buf).await?;
+
+        let len = stream.read_u16().await?;
+        let mut rsp_buf = vec![0u8; len as usize];
+        stream.read_exact(&mut rsp_buf).await?;
+        is_valid_response(req, &rsp_buf)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid response"))
+    }
+
+    async fn exchange(&self, server: SocketAddr, req: &Message, buf: &[u8]) -> io::Result<Message> {
+        let rsp = self.query_udp(server, req, buf).await?;
+        if rsp.truncated() {
+            self.query_tcp(server, req, buf).await
+        } else {
+            Ok(rsp)
+        }
+    }
+
+    pub(super) async fn query(
+        &self,
+        domain: String,
+        rtype: RecordType,
+        subnet: ClientSubnet,
+    ) -> ResolvedRecord {
+        let req = match build_request(&domain, rtype, &subnet) {
+            Ok(req) => req,
+            Err(e) => return self.converter.failed(domain, e),
+        };
+        let buf = match req.to_vec() {
+            Ok(buf) => buf,
+            Err(_) => {
+                let e = ResolveError::FromDriver(ResolveDriverError::BadQuery);
+                return self.converter.failed(domain, e);
+            }
+        };
+
+        let mut last_err = ResolveDriverError::Timeout;
+        for _ in 0..self.retry_attempts.max(1) {
+            for server in &self.servers {
+                match tokio::time::timeout(self.each_timeout, self.exchange(*server, &req, &buf))
+                    .await
+                {
+                    Ok(Ok(rsp)) => return self.converter.convert(domain, rtype, rsp),
+                    Ok(Err(e)) => {
+                        last_err = if e.kind() == io::ErrorKind::ConnectionRefused {
+                            ResolveDriverError::ConnRefused
+                        } else {
+                            ResolveDriverError::Internal(e.to_string())
+                        };
+                    }
+                    Err(_) => last_err = ResolveDriverError::Timeout,
+                }
+            }
+        }
+        self.converter.failed(domain, last_err.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn request() {
+        let subnet = ClientSubnet::new(IpAddr::from_str("203.0.113.77").unwrap(), 24);
+        let req = build_request("www.example.net", RecordType::A, &subnet).unwrap();
+        let buf = req.to_vec().unwrap();
+
+        let decoded = Message::from_vec(&buf).unwrap();
+        assert_eq!(decoded.id(), req.id());
+        assert!(decoded.recursion_desired());
+        assert_eq!(decoded.queries()[0].query_type(), RecordType::A);
+        let edns = decoded.extensions().as_ref().unwrap();
+        assert_eq!(edns.max_payload(), EDNS_MAX_PAYLOAD);
+
+        // the OPT rdata should be: code 8, len 7, family 1, source 24, scope 0, 203.0.113
+        let opt_data = [0u8, 8, 0, 7, 0, 1, 24, 0, 203, 0, 113];
+        assert!(buf.windows(opt_data.len()).any(|w| w == opt_data));
+
+        let long_label = "a".repeat(64);
+        assert!(build_request(&long_label, RecordType::A, &subnet).is_err());
+    }
+}
@@ -18,6 +18,7 @@ use tokio::sync::mpsc;
 
 use crate::config::ResolverRuntimeConfig;
 use crate::message::ResolveDriverResponse;
+use crate::ClientSubnet;
 
 pub mod fail_over;
 
@@ -52,12 +53,14 @@ pub(crate) trait ResolveDriver {
     fn query_v4(
         &self,
         domain: String,
+        subnet: Option<ClientSubnet>,
         config: &ResolverRuntimeConfig,
         sender: mpsc::UnboundedSender<ResolveDriverResponse>,
     );
     fn query_v6(
         &self,
         domain: String,
+        subnet: Option<ClientSubnet>,
         config: &ResolverRuntimeConfig,
         sender: mpsc::UnboundedSender<ResolveDriverResponse>,
     );
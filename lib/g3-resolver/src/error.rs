@@ -82,6 +82,8 @@ pub enum ResolveLocalError {
     NoResolverRunning,
     #[error("driver timed out")]
     DriverTimedOut,
+    #[error("domain denied by resolver")]
+    DomainDenied,
 }
 
 impl ResolveLocalError {
@@ -90,6 +92,7 @@ impl ResolveLocalError {
             ResolveLocalError::NoResolverSet => "NoResolverSet",
             ResolveLocalError::NoResolverRunning => "NoResolverRunning",
             ResolveLocalError::DriverTimedOut => "DriverTimedOut",
+            ResolveLocalError::DomainDenied => "DomainDenied",
         }
     }
 }
@@ -109,6 +112,14 @@ pub enum ResolveError {
 }
 
 impl ResolveError {
+    /// the query is denied by the deny list of the resolver
+    pub fn is_denied(&self) -> bool {
+        matches!(
+            self,
+            ResolveError::FromLocal(ResolveLocalError::DomainDenied)
+        )
+    }
+
     pub fn get_type(&self) -> &str {
         match self {
             ResolveError::EmptyDomain => "EmptyDomain",
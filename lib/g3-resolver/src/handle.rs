@@ -20,7 +20,9 @@ use std::task::{Context, Poll};
 
 use tokio::sync::{mpsc, oneshot};
 
-use super::{ArcResolvedRecord, ResolveLocalError, ResolvedRecordSource};
+use super::{
+    ArcResolvedRecord, CachedRecordSnapshot, ClientSubnet, ResolveLocalError, ResolvedRecordSource,
+};
 use crate::message::ResolveDriverRequest;
 
 #[derive(Clone, Debug)]
@@ -44,8 +46,17 @@ impl ResolverHandle {
     }
 
     pub fn get_v4(&self, domain: String) -> Result<ResolveJob, ResolveLocalError> {
+        self.get_v4_with_subnet(domain, None)
+    }
+
+    /// query with the EDNS Client Subnet option, the result will be cached per subnet
+    pub fn get_v4_with_subnet(
+        &self,
+        domain: String,
+        subnet: Option<ClientSubnet>,
+    ) -> Result<ResolveJob, ResolveLocalError> {
         let (sender, receiver) = oneshot::channel();
-        let req = ResolveDriverRequest::GetV4(domain, sender);
+        let req = ResolveDriverRequest::GetV4(domain, subnet, sender);
         let sender = self.req_sender.clone();
         match sender.send(req) {
             Ok(_) => Ok(ResolveJob { receiver }),
@@ -54,14 +65,46 @@ impl ResolverHandle {
     }
 
     pub fn get_v6(&self, domain: String) -> Result<ResolveJob, ResolveLocalError> {
+        self.get_v6_with_subnet(domain, None)
+    }
+
+    /// query with the EDNS Client Subnet option, the result will be cached per subnet
+    pub fn get_v6_with_subnet(
+        &self,
+        domain: String,
+        subnet: Option<ClientSubnet>,
+    ) -> Result<ResolveJob, ResolveLocalError> {
         let (sender, receiver) = oneshot::channel();
-        let req = ResolveDriverRequest::GetV6(domain, sender);
+        let req = ResolveDriverRequest::GetV6(domain, subnet, sender);
         let sender = self.req_sender.clone();
         match sender.send(req) {
             Ok(_) => Ok(ResolveJob { receiver }),
             Err(_) => Err(ResolveLocalError::NoResolverRunning),
         }
     }
+
+    pub async fn dump_cache(&self) -> Result<Vec<CachedRecordSnapshot>, ResolveLocalError> {
+        let (sender, receiver) = oneshot::channel();
+        let req = ResolveDriverRequest::DumpCache(sender);
+        self.req_sender
+            .send(req)
+            .map_err(|_| ResolveLocalError::NoResolverRunning)?;
+        receiver
+            .await
+            .map_err(|_| ResolveLocalError::NoResolverRunning)
+    }
+
+    /// flush the cached records for the domains, or all if the domain list is empty
+    pub async fn flush_cache(&self, domains: Vec<String>) -> Result<usize, ResolveLocalError> {
+        let (sender, receiver) = oneshot::channel();
+        let req = ResolveDriverRequest::FlushCache(domains, sender);
+        self.req_sender
+            .send(req)
+            .map_err(|_| ResolveLocalError::NoResolverRunning)?;
+        receiver
+            .await
+            .map_err(|_| ResolveLocalError::NoResolverRunning)
+    }
 }
 
 pub struct ResolveJob {
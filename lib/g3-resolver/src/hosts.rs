@@ -0,0 +1,301 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use ahash::AHashMap;
+use anyhow::{anyhow, Context};
+use log::warn;
+use tokio::time::Instant;
+
+use super::{ArcResolvedRecord, ResolvedRecord};
+
+const DEFAULT_HOSTS_FILE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResolverStaticHostsConfig {
+    records: BTreeMap<String, Vec<IpAddr>>,
+    hosts_file: Option<PathBuf>,
+    file_check_interval: Duration,
+}
+
+impl Default for ResolverStaticHostsConfig {
+    fn default() -> Self {
+        ResolverStaticHostsConfig {
+            records: BTreeMap::new(),
+            hosts_file: None,
+            file_check_interval: DEFAULT_HOSTS_FILE_CHECK_INTERVAL,
+        }
+    }
+}
+
+impl ResolverStaticHostsConfig {
+    pub fn add_record(&mut self, domain: &str, ip: IpAddr) {
+        let domain = normalize_domain(domain);
+        let ips = self.records.entry(domain).or_default();
+        if !ips.contains(&ip) {
+            ips.push(ip);
+        }
+    }
+
+    pub fn set_hosts_file(&mut self, path: PathBuf) {
+        self.hosts_file = Some(path);
+    }
+
+    #[inline]
+    pub fn hosts_file(&self) -> Option<&Path> {
+        self.hosts_file.as_deref()
+    }
+
+    pub fn set_file_check_interval(&mut self, interval: Duration) {
+        self.file_check_interval = interval;
+    }
+
+    #[inline]
+    pub(crate) fn file_check_interval(&self) -> Duration {
+        self.file_check_interval
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty() && self.hosts_file.is_none()
+    }
+
+    pub fn check(&self) -> anyhow::Result<()> {
+        if let Some(path) = &self.hosts_file {
+            load_hosts_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+fn normalize_domain(domain: &str) -> String {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    domain.to_ascii_lowercase()
+}
+
+fn load_hosts_file(path: &Path) -> anyhow::Result<BTreeMap<String, Vec<IpAddr>>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("failed to read hosts file {}: {e}", path.display()))?;
+    parse_hosts(&content).context(format!("invalid hosts file {}", path.display()))
+}
+
+/// parse the content in /etc/hosts format
+fn parse_hosts(content: &str) -> anyhow::Result<BTreeMap<String, Vec<IpAddr>>> {
+    let mut records = BTreeMap::<String, Vec<IpAddr>>::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = match line.split_once('#') {
+            Some((s, _)) => s,
+            None => line,
+        };
+        let mut fields = line.split_ascii_whitespace();
+        let Some(ip) = fields.next() else {
+            continue;
+        };
+        let ip = ip
+            .parse::<IpAddr>()
+            .map_err(|e| anyhow!("invalid ip address at line {}: {e}", i + 1))?;
+        for name in fields {
+            let ips = records.entry(normalize_domain(name)).or_default();
+            if !ips.contains(&ip) {
+                ips.push(ip);
+            }
+        }
+    }
+    Ok(records)
+}
+
+pub(crate) struct StaticHosts {
+    hosts_file: Option<PathBuf>,
+    file_modified: Option<SystemTime>,
+    config_records: BTreeMap<String, Vec<IpAddr>>,
+    v4: AHashMap<String, ArcResolvedRecord>,
+    v6: AHashMap<String, ArcResolvedRecord>,
+    child_v4: AHashMap<String, ArcResolvedRecord>,
+    child_v6: AHashMap<String, ArcResolvedRecord>,
+}
+
+impl StaticHosts {
+    pub(crate) fn new(config: &ResolverStaticHostsConfig) -> Self {
+        let mut hosts = StaticHosts {
+            hosts_file: config.hosts_file.clone(),
+            file_modified: None,
+            config_records: config.records.clone(),
+            v4: AHashMap::new(),
+            v6: AHashMap::new(),
+            child_v4: AHashMap::new(),
+            child_v6: AHashMap::new(),
+        };
+        hosts.reload(true);
+        hosts
+    }
+
+    /// reload the hosts file if it has been changed
+    pub(crate) fn reload(&mut self, force: bool) {
+        let mut file_records = None;
+        if let Some(path) = &self.hosts_file {
+            let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+            if !force && modified.is_some() && modified == self.file_modified {
+                return;
+            }
+            match load_hosts_file(path) {
+                Ok(records) => {
+                    self.file_modified = modified;
+                    file_records = Some(records);
+                }
+                Err(e) => {
+                    warn!("{e:?}");
+                    if !force {
+                        // keep the records loaded previously
+                        return;
+                    }
+                }
+            }
+        } else if !force {
+            return;
+        }
+
+        let created = Instant::now();
+        let mut v4 = AHashMap::new();
+        let mut v6 = AHashMap::new();
+        let mut child_v4 = AHashMap::new();
+        let mut child_v6 = AHashMap::new();
+        let mut add_records = |domain: &String, ips: &[IpAddr]| {
+            let (ip4, ip6): (Vec<IpAddr>, Vec<IpAddr>) = ips.iter().partition(|ip| ip.is_ipv4());
+            let (v4, v6) = if domain.starts_with("*.") {
+                (&mut child_v4, &mut child_v6)
+            } else {
+                (&mut v4, &mut v6)
+            };
+            let key = domain.strip_prefix("*.").unwrap_or(domain);
+            if !ip4.is_empty() {
+                let record = ResolvedRecord {
+                    domain: domain.to_string(),
+                    created,
+                    expire: None,
+                    result: Ok(ip4),
+                };
+                v4.insert(key.to_string(), Arc::new(record));
+            }
+            if !ip6.is_empty() {
+                let record = ResolvedRecord {
+                    domain: domain.to_string(),
+                    created,
+                    expire: None,
+                    result: Ok(ip6),
+                };
+                v6.insert(key.to_string(), Arc::new(record));
+            }
+        };
+        // records in the hosts file will be overridden by those in config
+        if let Some(records) = &file_records {
+            for (domain, ips) in records {
+                add_records(domain, ips);
+            }
+        }
+        for (domain, ips) in &self.config_records {
+            add_records(domain, ips);
+        }
+        self.v4 = v4;
+        self.v6 = v6;
+        self.child_v4 = child_v4;
+        self.child_v6 = child_v6;
+    }
+
+    fn get(
+        exact: &AHashMap<String, ArcResolvedRecord>,
+        child: &AHashMap<String, ArcResolvedRecord>,
+        domain: &str,
+    ) -> Option<ArcResolvedRecord> {
+        if exact.is_empty() && child.is_empty() {
+            return None;
+        }
+        let domain = normalize_domain(domain);
+        if let Some(r) = exact.get(&domain) {
+            return Some(r.clone());
+        }
+        // wildcard records only match the sub domains
+        let mut s = domain.as_str();
+        while let Some((_, parent)) = s.split_once('.') {
+            if let Some(r) = child.get(parent) {
+                return Some(r.clone());
+            }
+            s = parent;
+        }
+        None
+    }
+
+    pub(crate) fn get_v4(&self, domain: &str) -> Option<ArcResolvedRecord> {
+        Self::get(&self.v4, &self.child_v4, domain)
+    }
+
+    pub(crate) fn get_v6(&self, domain: &str) -> Option<ArcResolvedRecord> {
+        Self::get(&self.v6, &self.child_v6, domain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let content = "# comment line\n\
+                       127.0.0.1 localhost\n\
+                       ::1 localhost ip6-localhost # inline comment\n\
+                       \n\
+                       192.168.1.10\tTest.Example.Net. test\n";
+        let records = parse_hosts(content).unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(
+            records.get("localhost").unwrap(),
+            &vec![
+                IpAddr::from([127, 0, 0, 1]),
+                IpAddr::from([0, 0, 0, 0, 0, 0, 0, 1])
+            ]
+        );
+        assert_eq!(
+            records.get("test.example.net").unwrap(),
+            &vec![IpAddr::from([192, 168, 1, 10])]
+        );
+
+        assert!(parse_hosts("localhost 127.0.0.1").is_err());
+    }
+
+    #[test]
+    fn wildcard() {
+        let mut config = ResolverStaticHostsConfig::default();
+        config.add_record("*.example.net", IpAddr::from([192, 168, 1, 10]));
+        config.add_record("test.example.net", IpAddr::from([192, 168, 1, 11]));
+        let hosts = StaticHosts::new(&config);
+
+        assert!(hosts.get_v4("example.net").is_none());
+        assert!(hosts.get_v6("www.example.net").is_none());
+        let r = hosts.get_v4("www.Example.net").unwrap();
+        assert_eq!(
+            r.result.as_ref().unwrap(),
+            &vec![IpAddr::from([192, 168, 1, 10])]
+        );
+        let r = hosts.get_v4("test.example.net").unwrap();
+        assert_eq!(
+            r.result.as_ref().unwrap(),
+            &vec![IpAddr::from([192, 168, 1, 11])]
+        );
+    }
+}
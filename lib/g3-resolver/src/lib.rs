@@ -20,19 +20,25 @@ pub use driver::AnyResolveDriverConfig;
 pub(crate) use driver::{BoxResolverDriver, ResolveDriver};
 
 mod config;
+mod deny;
 mod error;
 mod handle;
+mod hosts;
 mod message;
 mod query;
 mod record;
 mod resolver;
 mod runtime;
 mod stats;
+mod subnet;
 
 pub use config::{ResolverConfig, ResolverRuntimeConfig};
+pub use deny::ResolverDenyListConfig;
 pub use error::{ResolveDriverError, ResolveError, ResolveLocalError, ResolveServerError};
 pub use handle::{ResolveJob, ResolveJobRecvResult, ResolverHandle};
+pub use hosts::ResolverStaticHostsConfig;
 pub use query::ResolveQueryType;
-pub use record::{ArcResolvedRecord, ResolvedRecord, ResolvedRecordSource};
+pub use record::{ArcResolvedRecord, CachedRecordSnapshot, ResolvedRecord, ResolvedRecordSource};
 pub use resolver::{Resolver, ResolverBuilder};
 pub use stats::{ResolverMemorySnapshot, ResolverQuerySnapshot, ResolverSnapshot, ResolverStats};
+pub use subnet::{ClientSubnet, ClientSubnetConfig, EDNS_OPTION_CODE_CLIENT_SUBNET};
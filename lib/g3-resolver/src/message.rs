@@ -16,7 +16,10 @@
 
 use tokio::sync::oneshot;
 
-use super::{ArcResolvedRecord, ResolvedRecord, ResolvedRecordSource, ResolverConfig};
+use super::{
+    ArcResolvedRecord, CachedRecordSnapshot, ClientSubnet, ResolvedRecord, ResolvedRecordSource,
+    ResolverConfig,
+};
 
 #[derive(Clone, Debug)]
 pub(crate) enum ResolverCommand {
@@ -27,15 +30,20 @@ pub(crate) enum ResolverCommand {
 pub(crate) enum ResolveDriverRequest {
     GetV4(
         String,
+        Option<ClientSubnet>,
         oneshot::Sender<(ArcResolvedRecord, ResolvedRecordSource)>,
     ),
     GetV6(
         String,
+        Option<ClientSubnet>,
         oneshot::Sender<(ArcResolvedRecord, ResolvedRecordSource)>,
     ),
+    DumpCache(oneshot::Sender<Vec<CachedRecordSnapshot>>),
+    /// flush the cache for the domains, or all if empty, and reply with the count of removed records
+    FlushCache(Vec<String>, oneshot::Sender<usize>),
 }
 
 pub(crate) enum ResolveDriverResponse {
-    V4(ResolvedRecord),
-    V6(ResolvedRecord),
+    V4(ResolvedRecord, Option<ClientSubnet>),
+    V6(ResolvedRecord, Option<ClientSubnet>),
 }
@@ -16,7 +16,7 @@
 
 use std::fmt;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum ResolveQueryType {
     A,
     Aaaa,
@@ -21,13 +21,14 @@ use std::time::Duration;
 
 use tokio::time::Instant;
 
-use super::ResolveError;
+use super::{ClientSubnet, ResolveError, ResolveQueryType};
 use crate::ResolveLocalError;
 
 #[derive(Clone, Copy, Debug)]
 pub enum ResolvedRecordSource {
     Cache,
     Query,
+    Static,
 }
 
 impl ResolvedRecordSource {
@@ -35,6 +36,7 @@ impl ResolvedRecordSource {
         match self {
             ResolvedRecordSource::Cache => "cache",
             ResolvedRecordSource::Query => "query",
+            ResolvedRecordSource::Static => "static",
         }
     }
 }
@@ -87,3 +89,15 @@ impl ResolvedRecord {
         }
     }
 }
+
+/// A snapshot of the record in the resolver cache
+#[derive(Clone, Debug)]
+pub struct CachedRecordSnapshot {
+    pub query_type: ResolveQueryType,
+    /// the client subnet sent in the query, if any
+    pub subnet: Option<ClientSubnet>,
+    pub record: ArcResolvedRecord,
+    pub ttl_remaining: Duration,
+    pub stale: bool,
+    pub hits: u32,
+}
@@ -68,14 +68,31 @@ impl ResolverBuilder {
                 .enable_all()
                 .build()
                 .unwrap();
-            basic_rt.block_on(async move {
+            let resolver_task = async move {
                 let resolver_name = config.name.to_owned();
                 let resolver_runtime =
                     ResolverRuntime::new(config, req_receiver, ctl_receiver, stats_a);
+                #[cfg(feature = "tokio-console")]
+                let resolver_runtime = {
+                    // run as a named task, so it can be found in tokio-console
+                    let task = tokio::task::Builder::new()
+                        .name(&format!("resolver {resolver_name}"))
+                        .spawn_local(resolver_runtime)
+                        .expect("failed to spawn resolver runtime task");
+                    async move {
+                        task.await.unwrap_or_else(|e| {
+                            Err(anyhow::anyhow!("failed to join resolver runtime task: {e}"))
+                        })
+                    }
+                };
                 if let Err(e) = resolver_runtime.await {
                     warn!("resolver {resolver_name} runtime exited with error: {e}",);
                 }
-            });
+            };
+            #[cfg(feature = "tokio-console")]
+            basic_rt.block_on(tokio::task::LocalSet::new().run_until(resolver_task));
+            #[cfg(not(feature = "tokio-console"))]
+            basic_rt.block_on(resolver_task);
         })?;
 
         Ok(Resolver {
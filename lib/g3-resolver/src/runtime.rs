@@ -19,21 +19,45 @@ use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use ahash::AHashMap;
 use log::{trace, warn};
 use tokio::sync::{mpsc, oneshot};
-use tokio::time::Instant;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
 use tokio_util::time::{delay_queue, DelayQueue};
 
+use super::hosts::StaticHosts;
 use super::stats::{ResolverMemoryStats, ResolverStats};
-use super::{ArcResolvedRecord, BoxResolverDriver, ResolvedRecordSource, ResolverConfig};
+use super::{
+    ArcResolvedRecord, BoxResolverDriver, CachedRecordSnapshot, ClientSubnet, ResolveError,
+    ResolveLocalError, ResolveQueryType, ResolveServerError, ResolvedRecord, ResolvedRecordSource,
+    ResolverConfig, ResolverRuntimeConfig,
+};
 use crate::message::{ResolveDriverRequest, ResolveDriverResponse, ResolverCommand};
 
+/// records queried with different client subnets are cached separately
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct CacheKey {
+    domain: String,
+    subnet: Option<ClientSubnet>,
+}
+
+impl CacheKey {
+    fn new(domain: String, subnet: Option<ClientSubnet>) -> Self {
+        CacheKey { domain, subnet }
+    }
+}
+
+type DoingSenders = Vec<oneshot::Sender<(ArcResolvedRecord, ResolvedRecordSource)>>;
+
 struct CachedRecord {
     inner: ArcResolvedRecord,
     expire_at: Instant,
     expire_key: Option<delay_queue::Key>,
+    stale: bool,
+    refresh_retry_at: Option<Instant>,
+    hits: u32,
 }
 
 pub(crate) struct ResolverRuntime {
@@ -43,13 +67,15 @@ pub(crate) struct ResolverRuntime {
     ctl_receiver: mpsc::UnboundedReceiver<ResolverCommand>,
     rsp_receiver: mpsc::UnboundedReceiver<ResolveDriverResponse>,
     rsp_sender: mpsc::UnboundedSender<ResolveDriverResponse>,
-    expired_v4: DelayQueue<String>,
-    expired_v6: DelayQueue<String>,
-    cache_v4: AHashMap<String, CachedRecord>,
-    cache_v6: AHashMap<String, CachedRecord>,
-    doing_v4: AHashMap<String, Vec<oneshot::Sender<(ArcResolvedRecord, ResolvedRecordSource)>>>,
-    doing_v6: AHashMap<String, Vec<oneshot::Sender<(ArcResolvedRecord, ResolvedRecordSource)>>>,
+    expired_v4: DelayQueue<CacheKey>,
+    expired_v6: DelayQueue<CacheKey>,
+    cache_v4: AHashMap<CacheKey, CachedRecord>,
+    cache_v6: AHashMap<CacheKey, CachedRecord>,
+    doing_v4: AHashMap<CacheKey, DoingSenders>,
+    doing_v6: AHashMap<CacheKey, DoingSenders>,
     driver: Option<BoxResolverDriver>,
+    static_hosts: StaticHosts,
+    hosts_check_interval: Option<Interval>,
 }
 
 impl Drop for ResolverRuntime {
@@ -68,6 +94,8 @@ impl ResolverRuntime {
     ) -> Self {
         let initial_cache_capacity = config.runtime.initial_cache_capacity;
         let (rsp_sender, rsp_receiver) = mpsc::unbounded_channel();
+        let static_hosts = StaticHosts::new(&config.runtime.static_hosts);
+        let hosts_check_interval = Self::new_hosts_check_interval(&config);
         ResolverRuntime {
             config,
             stats,
@@ -82,14 +110,29 @@ impl ResolverRuntime {
             doing_v4: AHashMap::with_capacity(initial_cache_capacity),
             doing_v6: AHashMap::with_capacity(initial_cache_capacity),
             driver: None,
+            static_hosts,
+            hosts_check_interval,
         }
     }
 
+    fn new_hosts_check_interval(config: &ResolverConfig) -> Option<Interval> {
+        let static_hosts = &config.runtime.static_hosts;
+        static_hosts.hosts_file()?;
+        let period = static_hosts.file_check_interval();
+        let mut interval = tokio::time::interval_at(Instant::now() + period, period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Some(interval)
+    }
+
     fn handle_cmd(&mut self, cmd: ResolverCommand) {
         match cmd {
             ResolverCommand::Update(config) => match config.driver.spawn_resolver_driver() {
                 Ok(driver) => {
                     self.driver = Some(driver);
+                    if self.config.runtime.static_hosts != config.runtime.static_hosts {
+                        self.static_hosts = StaticHosts::new(&config.runtime.static_hosts);
+                        self.hosts_check_interval = Self::new_hosts_check_interval(&config);
+                    }
                     self.config = *config;
                 }
                 Err(e) => {
@@ -101,42 +144,103 @@ impl ResolverRuntime {
     }
 
     fn update_cache(
-        cache: &mut AHashMap<String, CachedRecord>,
-        expire_queue: &mut DelayQueue<String>,
+        cache: &mut AHashMap<CacheKey, CachedRecord>,
+        expire_queue: &mut DelayQueue<CacheKey>,
+        key: CacheKey,
         record: ArcResolvedRecord,
         expire_at: Instant,
     ) {
-        match cache.entry(record.domain.to_owned()) {
+        match cache.entry(key) {
             hash_map::Entry::Occupied(mut o) => {
-                let v = o.get_mut();
-                let expire_key = match v.expire_key.take() {
+                let expire_key = match o.get_mut().expire_key.take() {
                     Some(expire_key) => {
                         expire_queue.reset_at(&expire_key, expire_at);
                         expire_key
                     }
-                    None => expire_queue.insert_at(record.domain.to_owned(), expire_at),
+                    None => expire_queue.insert_at(o.key().clone(), expire_at),
                 };
+                let v = o.get_mut();
                 v.inner = record;
                 v.expire_at = expire_at;
                 v.expire_key = Some(expire_key);
+                v.stale = false;
+                v.refresh_retry_at = None;
+                v.hits = 0;
             }
             hash_map::Entry::Vacant(v) => {
-                let expire_key = expire_queue.insert_at(record.domain.to_owned(), expire_at);
+                let expire_key = expire_queue.insert_at(v.key().clone(), expire_at);
                 v.insert(CachedRecord {
                     inner: record,
                     expire_at,
                     expire_key: Some(expire_key),
+                    stale: false,
+                    refresh_retry_at: None,
+                    hits: 0,
                 });
             }
         }
     }
 
+    fn set_negative_expire(runtime: &ResolverRuntimeConfig, record: &mut ResolvedRecord) {
+        let ttl = match &record.result {
+            // NODATA is cached in the same way as NXDOMAIN, see RFC 2308
+            Ok(addrs) if addrs.is_empty() => runtime.nxdomain_negative_ttl,
+            Err(ResolveError::FromServer(ResolveServerError::NotFound)) => {
+                runtime.nxdomain_negative_ttl
+            }
+            Err(ResolveError::FromServer(ResolveServerError::ServFail)) => {
+                runtime.servfail_negative_ttl
+            }
+            _ => None,
+        };
+        if let Some(ttl) = ttl {
+            record.expire = record.created.checked_add(Duration::from_secs(ttl as u64));
+        }
+    }
+
+    /// reply with the cached record if the refresh query failed, which may be a stale record,
+    /// or a record that is still valid when the prefetch query failed
+    fn serve_stale(
+        cache: &mut AHashMap<CacheKey, CachedRecord>,
+        doing: &mut AHashMap<CacheKey, DoingSenders>,
+        key: &CacheKey,
+        record: &ResolvedRecord,
+    ) -> Option<usize> {
+        if record.is_usable() {
+            return None;
+        }
+        let cached = cache.get_mut(key)?;
+        if cached.stale {
+            // do not retry the refresh query until the negative ttl expires
+            cached.refresh_retry_at = Some(record.expire.unwrap_or_else(Instant::now));
+        } else if cached.inner.is_usable() && cached.expire_at > Instant::now() {
+            // keep the valid record, and wait for enough hits before the next prefetch
+            cached.hits = 0;
+        } else {
+            return None;
+        }
+        let vec = doing.remove(key).unwrap_or_default();
+        let count = vec.len();
+        for sender in vec.into_iter() {
+            let _ = sender.send((Arc::clone(&cached.inner), ResolvedRecordSource::Cache));
+        }
+        Some(count)
+    }
+
     fn handle_rsp(&mut self, rsp: ResolveDriverResponse) {
         match rsp {
-            ResolveDriverResponse::V4(record) => {
+            ResolveDriverResponse::V4(mut record, subnet) => {
                 self.stats.query_a.add_record(&record);
+                Self::set_negative_expire(&self.config.runtime, &mut record);
+                let key = CacheKey::new(record.domain.clone(), subnet);
+                if let Some(n) =
+                    Self::serve_stale(&mut self.cache_v4, &mut self.doing_v4, &key, &record)
+                {
+                    self.stats.query_a.add_query_cached_n(n);
+                    return;
+                }
                 let record = Arc::new(record);
-                if let Some(mut vec) = self.doing_v4.remove(&record.domain) {
+                if let Some(mut vec) = self.doing_v4.remove(&key) {
                     if let Some(sender) = vec.pop() {
                         let _ = sender.send((Arc::clone(&record), ResolvedRecordSource::Query));
                         self.stats.query_a.add_query_cached_n(vec.len());
@@ -146,13 +250,27 @@ impl ResolverRuntime {
                     }
                 }
                 if let Some(expire_at) = record.expire {
-                    Self::update_cache(&mut self.cache_v4, &mut self.expired_v4, record, expire_at);
+                    Self::update_cache(
+                        &mut self.cache_v4,
+                        &mut self.expired_v4,
+                        key,
+                        record,
+                        expire_at,
+                    );
                 }
             }
-            ResolveDriverResponse::V6(record) => {
+            ResolveDriverResponse::V6(mut record, subnet) => {
                 self.stats.query_aaaa.add_record(&record);
+                Self::set_negative_expire(&self.config.runtime, &mut record);
+                let key = CacheKey::new(record.domain.clone(), subnet);
+                if let Some(n) =
+                    Self::serve_stale(&mut self.cache_v6, &mut self.doing_v6, &key, &record)
+                {
+                    self.stats.query_aaaa.add_query_cached_n(n);
+                    return;
+                }
                 let record = Arc::new(record);
-                if let Some(mut vec) = self.doing_v6.remove(&record.domain) {
+                if let Some(mut vec) = self.doing_v6.remove(&key) {
                     if let Some(sender) = vec.pop() {
                         let _ = sender.send((Arc::clone(&record), ResolvedRecordSource::Query));
                         self.stats.query_aaaa.add_query_cached_n(vec.len());
@@ -162,41 +280,223 @@ impl ResolverRuntime {
                     }
                 }
                 if let Some(expire_at) = record.expire {
-                    Self::update_cache(&mut self.cache_v6, &mut self.expired_v6, record, expire_at);
+                    Self::update_cache(
+                        &mut self.cache_v6,
+                        &mut self.expired_v6,
+                        key,
+                        record,
+                        expire_at,
+                    );
                 }
             }
         }
     }
 
-    fn handle_expired_v4(&mut self, domain: &str) {
-        trace!("clean expired v4 for domain {domain}");
-        self.cache_v4.remove(domain);
+    fn handle_expired(
+        cache: &mut AHashMap<CacheKey, CachedRecord>,
+        expire_queue: &mut DelayQueue<CacheKey>,
+        key: &CacheKey,
+        serve_stale: Option<Duration>,
+    ) {
+        if let Some(stale_time) = serve_stale {
+            if let Some(r) = cache.get_mut(key) {
+                if !r.stale && r.inner.is_usable() {
+                    // keep the expired record for use when the refresh query failed
+                    let expire_at = Instant::now() + stale_time;
+                    r.expire_at = expire_at;
+                    r.expire_key = Some(expire_queue.insert_at(key.clone(), expire_at));
+                    r.stale = true;
+                    return;
+                }
+            }
+        }
+        cache.remove(key);
     }
-    fn handle_expired_v6(&mut self, domain: &str) {
-        trace!("clean expired v6 for domain {domain}");
-        self.cache_v6.remove(domain);
+
+    fn handle_expired_v4(&mut self, key: &CacheKey) {
+        trace!("clean expired v4 for domain {}", key.domain);
+        Self::handle_expired(
+            &mut self.cache_v4,
+            &mut self.expired_v4,
+            key,
+            self.config.runtime.serve_stale,
+        );
+    }
+    fn handle_expired_v6(&mut self, key: &CacheKey) {
+        trace!("clean expired v6 for domain {}", key.domain);
+        Self::handle_expired(
+            &mut self.cache_v6,
+            &mut self.expired_v6,
+            key,
+            self.config.runtime.serve_stale,
+        );
+    }
+
+    fn need_prefetch(runtime: &ResolverRuntimeConfig, r: &CachedRecord) -> bool {
+        let Some(min_hits) = runtime.prefetch_min_hits else {
+            return false;
+        };
+        r.hits >= min_hits
+            && r.inner.is_usable()
+            && r.expire_at.saturating_duration_since(Instant::now()) <= runtime.prefetch_ahead
+    }
+
+    /// send a query in the background if there is no one running
+    fn refresh_v4(&mut self, key: CacheKey) -> bool {
+        let hash_map::Entry::Vacant(v) = self.doing_v4.entry(key.clone()) else {
+            return false;
+        };
+        let Some(driver) = &self.driver else {
+            return false;
+        };
+        v.insert(Vec::new());
+        self.stats.query_a.add_query_driver();
+        driver.query_v4(
+            key.domain,
+            key.subnet,
+            &self.config.runtime,
+            self.rsp_sender.clone(),
+        );
+        true
+    }
+
+    /// send a query in the background if there is no one running
+    fn refresh_v6(&mut self, key: CacheKey) -> bool {
+        let hash_map::Entry::Vacant(v) = self.doing_v6.entry(key.clone()) else {
+            return false;
+        };
+        let Some(driver) = &self.driver else {
+            return false;
+        };
+        v.insert(Vec::new());
+        self.stats.query_aaaa.add_query_driver();
+        driver.query_v6(
+            key.domain,
+            key.subnet,
+            &self.config.runtime,
+            self.rsp_sender.clone(),
+        );
+        true
+    }
+
+    fn denied_record(domain: String) -> ArcResolvedRecord {
+        let e = ResolveError::FromLocal(ResolveLocalError::DomainDenied);
+        Arc::new(ResolvedRecord::failed(domain, 0, e))
+    }
+
+    fn dump_cache(
+        cache: &AHashMap<CacheKey, CachedRecord>,
+        query_type: ResolveQueryType,
+        records: &mut Vec<CachedRecordSnapshot>,
+    ) {
+        let now = Instant::now();
+        for (k, r) in cache.iter() {
+            records.push(CachedRecordSnapshot {
+                query_type,
+                subnet: k.subnet,
+                record: Arc::clone(&r.inner),
+                ttl_remaining: r.expire_at.saturating_duration_since(now),
+                stale: r.stale,
+                hits: r.hits,
+            });
+        }
+    }
+
+    /// remove the records for all client subnets of the domain
+    fn flush_cache(
+        cache: &mut AHashMap<CacheKey, CachedRecord>,
+        expire_queue: &mut DelayQueue<CacheKey>,
+        domain: &str,
+    ) -> usize {
+        let mut count = 0;
+        cache.retain(|k, r| {
+            if k.domain != domain {
+                return true;
+            }
+            if let Some(expire_key) = r.expire_key.take() {
+                expire_queue.remove(&expire_key);
+            }
+            count += 1;
+            false
+        });
+        count
+    }
+
+    fn handle_flush_cache(&mut self, domains: Vec<String>) -> usize {
+        let count = if domains.is_empty() {
+            let count = self.cache_v4.len() + self.cache_v6.len();
+            self.cache_v4.clear();
+            self.expired_v4.clear();
+            self.cache_v6.clear();
+            self.expired_v6.clear();
+            count
+        } else {
+            let mut count = 0;
+            for domain in &domains {
+                count += Self::flush_cache(&mut self.cache_v4, &mut self.expired_v4, domain);
+                count += Self::flush_cache(&mut self.cache_v6, &mut self.expired_v6, domain);
+            }
+            count
+        };
+        self.update_mem_stats();
+        count
     }
 
     fn handle_req(&mut self, req: ResolveDriverRequest) {
         match req {
-            ResolveDriverRequest::GetV4(domain, sender) => {
+            ResolveDriverRequest::DumpCache(sender) => {
+                let mut records = Vec::with_capacity(self.cache_v4.len() + self.cache_v6.len());
+                Self::dump_cache(&self.cache_v4, ResolveQueryType::A, &mut records);
+                Self::dump_cache(&self.cache_v6, ResolveQueryType::Aaaa, &mut records);
+                let _ = sender.send(records);
+            }
+            ResolveDriverRequest::FlushCache(domains, sender) => {
+                let count = self.handle_flush_cache(domains);
+                let _ = sender.send(count);
+            }
+            ResolveDriverRequest::GetV4(domain, subnet, sender) => {
                 self.stats.query_a.add_query_total();
-                match self.cache_v4.get(&domain) {
-                    Some(r) => {
+                if self.config.runtime.deny_list.is_denied(&domain) {
+                    let _ =
+                        sender.send((Self::denied_record(domain), ResolvedRecordSource::Static));
+                    return;
+                }
+                if let Some(record) = self.static_hosts.get_v4(&domain) {
+                    self.stats.query_a.add_query_cached();
+                    let _ = sender.send((record, ResolvedRecordSource::Static));
+                    return;
+                }
+                let key = CacheKey::new(domain, subnet);
+                match self.cache_v4.get_mut(&key) {
+                    Some(r) if !r.stale => {
                         self.stats.query_a.add_query_cached();
                         let _ = sender.send((Arc::clone(&r.inner), ResolvedRecordSource::Cache));
+                        r.hits = r.hits.saturating_add(1);
+                        if Self::need_prefetch(&self.config.runtime, r) && self.refresh_v4(key) {
+                            self.stats.query_a.add_query_prefetch();
+                        }
+                    }
+                    Some(r) if r.refresh_retry_at.is_some() => {
+                        // the upstream is failing, reply with the stale record directly
+                        self.stats.query_a.add_query_cached();
+                        let _ = sender.send((Arc::clone(&r.inner), ResolvedRecordSource::Cache));
+                        if r.refresh_retry_at.is_some_and(|t| t <= Instant::now()) {
+                            self.refresh_v4(key);
+                        }
                     }
-                    None => match self.doing_v4.entry(domain.to_owned()) {
+                    _ => match self.doing_v4.entry(key) {
                         hash_map::Entry::Occupied(mut o) => {
                             // there is a query already
                             o.get_mut().push(sender);
                         }
                         hash_map::Entry::Vacant(v) => {
+                            let key = v.key().clone();
                             v.insert(vec![sender]);
                             if let Some(driver) = &self.driver {
                                 self.stats.query_a.add_query_driver();
                                 driver.query_v4(
-                                    domain,
+                                    key.domain,
+                                    key.subnet,
                                     &self.config.runtime,
                                     self.rsp_sender.clone(),
                                 );
@@ -207,24 +507,49 @@ impl ResolverRuntime {
                     },
                 }
             }
-            ResolveDriverRequest::GetV6(domain, sender) => {
+            ResolveDriverRequest::GetV6(domain, subnet, sender) => {
                 self.stats.query_aaaa.add_query_total();
-                match self.cache_v6.get(&domain) {
-                    Some(r) => {
+                if self.config.runtime.deny_list.is_denied(&domain) {
+                    let _ =
+                        sender.send((Self::denied_record(domain), ResolvedRecordSource::Static));
+                    return;
+                }
+                if let Some(record) = self.static_hosts.get_v6(&domain) {
+                    self.stats.query_aaaa.add_query_cached();
+                    let _ = sender.send((record, ResolvedRecordSource::Static));
+                    return;
+                }
+                let key = CacheKey::new(domain, subnet);
+                match self.cache_v6.get_mut(&key) {
+                    Some(r) if !r.stale => {
                         self.stats.query_aaaa.add_query_cached();
                         let _ = sender.send((Arc::clone(&r.inner), ResolvedRecordSource::Cache));
+                        r.hits = r.hits.saturating_add(1);
+                        if Self::need_prefetch(&self.config.runtime, r) && self.refresh_v6(key) {
+                            self.stats.query_aaaa.add_query_prefetch();
+                        }
                     }
-                    None => match self.doing_v6.entry(domain.to_owned()) {
+                    Some(r) if r.refresh_retry_at.is_some() => {
+                        // the upstream is failing, reply with the stale record directly
+                        self.stats.query_aaaa.add_query_cached();
+                        let _ = sender.send((Arc::clone(&r.inner), ResolvedRecordSource::Cache));
+                        if r.refresh_retry_at.is_some_and(|t| t <= Instant::now()) {
+                            self.refresh_v6(key);
+                        }
+                    }
+                    _ => match self.doing_v6.entry(key) {
                         hash_map::Entry::Occupied(mut o) => {
                             // there is a query already
                             o.get_mut().push(sender);
                         }
                         hash_map::Entry::Vacant(v) => {
+                            let key = v.key().clone();
                             v.insert(vec![sender]);
                             if let Some(driver) = &self.driver {
                                 self.stats.query_aaaa.add_query_driver();
                                 driver.query_v6(
-                                    domain,
+                                    key.domain,
+                                    key.subnet,
                                     &self.config.runtime,
                                     self.rsp_sender.clone(),
                                 );
@@ -274,6 +599,13 @@ impl ResolverRuntime {
                 }
             }
 
+            // reload the hosts file if changed
+            if let Some(interval) = &mut self.hosts_check_interval {
+                while interval.poll_tick(cx).is_ready() {
+                    self.static_hosts.reload(false);
+                }
+            }
+
             let mut update_mem_stats = false;
 
             // handle response
@@ -338,3 +670,293 @@ impl Future for ResolverRuntime {
         (*self).poll_loop(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::IpAddr;
+    use std::sync::Mutex;
+
+    use tokio::task::LocalSet;
+
+    use crate::driver::fail_over::FailOverDriverConfig;
+    use crate::{AnyResolveDriverConfig, ResolveDriver, ResolveServerError};
+
+    struct FakeState {
+        result: Result<Vec<IpAddr>, ResolveError>,
+        ttl: u32,
+        queries: usize,
+    }
+
+    #[derive(Clone)]
+    struct FakeDriver {
+        state: Arc<Mutex<FakeState>>,
+    }
+
+    impl FakeDriver {
+        fn new(result: Result<Vec<IpAddr>, ResolveError>, ttl: u32) -> Self {
+            FakeDriver {
+                state: Arc::new(Mutex::new(FakeState {
+                    result,
+                    ttl,
+                    queries: 0,
+                })),
+            }
+        }
+
+        fn set(&self, result: Result<Vec<IpAddr>, ResolveError>, ttl: u32) {
+            let mut state = self.state.lock().unwrap();
+            state.result = result;
+            state.ttl = ttl;
+        }
+
+        fn queries(&self) -> usize {
+            self.state.lock().unwrap().queries
+        }
+
+        fn record(&self, domain: String) -> ResolvedRecord {
+            let mut state = self.state.lock().unwrap();
+            state.queries += 1;
+            match &state.result {
+                Ok(addrs) => {
+                    let created = Instant::now();
+                    ResolvedRecord {
+                        domain,
+                        created,
+                        expire: created.checked_add(Duration::from_secs(state.ttl as u64)),
+                        result: Ok(addrs.clone()),
+                    }
+                }
+                Err(e) => ResolvedRecord::failed(domain, state.ttl, e.clone()),
+            }
+        }
+    }
+
+    impl ResolveDriver for FakeDriver {
+        fn query_v4(
+            &self,
+            domain: String,
+            subnet: Option<ClientSubnet>,
+            _config: &ResolverRuntimeConfig,
+            sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+        ) {
+            let _ = sender.send(ResolveDriverResponse::V4(self.record(domain), subnet));
+        }
+
+        fn query_v6(
+            &self,
+            domain: String,
+            subnet: Option<ClientSubnet>,
+            _config: &ResolverRuntimeConfig,
+            sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+        ) {
+            let _ = sender.send(ResolveDriverResponse::V6(self.record(domain), subnet));
+        }
+    }
+
+    struct TestResolver {
+        req_sender: mpsc::UnboundedSender<ResolveDriverRequest>,
+        _ctl_sender: mpsc::UnboundedSender<ResolverCommand>,
+    }
+
+    impl TestResolver {
+        fn spawn(local: &LocalSet, runtime: ResolverRuntimeConfig, driver: &FakeDriver) -> Self {
+            let config = ResolverConfig {
+                name: "test".to_string(),
+                driver: AnyResolveDriverConfig::FailOver(FailOverDriverConfig::default()),
+                runtime,
+            };
+            let (req_sender, req_receiver) = mpsc::unbounded_channel();
+            let (ctl_sender, ctl_receiver) = mpsc::unbounded_channel();
+            let stats = Arc::new(ResolverStats::default());
+            let mut runtime = ResolverRuntime::new(config, req_receiver, ctl_receiver, stats);
+            runtime.driver = Some(Box::new(driver.clone()));
+            local.spawn_local(runtime);
+            TestResolver {
+                req_sender,
+                _ctl_sender: ctl_sender,
+            }
+        }
+
+        async fn query_v4(&self, domain: &str) -> (ArcResolvedRecord, ResolvedRecordSource) {
+            let (sender, receiver) = oneshot::channel();
+            let req = ResolveDriverRequest::GetV4(domain.to_string(), None, sender);
+            if self.req_sender.send(req).is_err() {
+                panic!("the resolver runtime has quit");
+            }
+            receiver.await.unwrap()
+        }
+    }
+
+    fn addrs() -> Vec<IpAddr> {
+        vec!["192.0.2.1".parse().unwrap()]
+    }
+
+    fn servfail() -> ResolveError {
+        ResolveError::FromServer(ResolveServerError::ServFail)
+    }
+
+    fn nxdomain() -> ResolveError {
+        ResolveError::FromServer(ResolveServerError::NotFound)
+    }
+
+    fn assert_usable(r: &(ArcResolvedRecord, ResolvedRecordSource), from_cache: bool) {
+        assert_eq!(r.0.result.as_ref().unwrap(), &addrs());
+        assert_eq!(matches!(r.1, ResolvedRecordSource::Cache), from_cache);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn prefetch_failed() {
+        let runtime = ResolverRuntimeConfig {
+            prefetch_min_hits: Some(2),
+            prefetch_ahead: Duration::from_secs(5),
+            ..Default::default()
+        };
+        let driver = FakeDriver::new(Ok(addrs()), 10);
+        let local = LocalSet::new();
+        let resolver = TestResolver::spawn(&local, runtime, &driver);
+
+        local
+            .run_until(async move {
+                assert_usable(&resolver.query_v4("example.net").await, false);
+
+                tokio::time::sleep(Duration::from_secs(6)).await;
+                driver.set(Err(servfail()), 30);
+                assert_usable(&resolver.query_v4("example.net").await, true);
+                assert_eq!(driver.queries(), 1);
+                // the second hit will trigger the prefetch, which will fail
+                assert_usable(&resolver.query_v4("example.net").await, true);
+                assert_eq!(driver.queries(), 2);
+
+                // the valid record is kept, and the hits are reset
+                assert_usable(&resolver.query_v4("example.net").await, true);
+                assert_eq!(driver.queries(), 2);
+
+                // the failure will be returned after the record expired
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                let (record, source) = resolver.query_v4("example.net").await;
+                assert!(record.is_err());
+                assert!(matches!(source, ResolvedRecordSource::Query));
+                assert_eq!(driver.queries(), 3);
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn serve_stale() {
+        let runtime = ResolverRuntimeConfig {
+            serve_stale: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let driver = FakeDriver::new(Ok(addrs()), 10);
+        let local = LocalSet::new();
+        let resolver = TestResolver::spawn(&local, runtime, &driver);
+
+        local
+            .run_until(async move {
+                assert_usable(&resolver.query_v4("example.net").await, false);
+
+                // the expired record is refreshed, and served as the refresh failed
+                tokio::time::sleep(Duration::from_secs(11)).await;
+                driver.set(Err(servfail()), 5);
+                assert_usable(&resolver.query_v4("example.net").await, true);
+                assert_eq!(driver.queries(), 2);
+
+                // no refresh until the negative ttl of the failed refresh expires
+                assert_usable(&resolver.query_v4("example.net").await, true);
+                assert_eq!(driver.queries(), 2);
+                tokio::time::sleep(Duration::from_secs(6)).await;
+                assert_usable(&resolver.query_v4("example.net").await, true);
+                assert_eq!(driver.queries(), 3);
+
+                // the successful refresh will replace the stale record
+                tokio::time::sleep(Duration::from_secs(6)).await;
+                driver.set(Ok(vec!["192.0.2.2".parse().unwrap()]), 10);
+                assert_usable(&resolver.query_v4("example.net").await, true);
+                assert_eq!(driver.queries(), 4);
+                let (record, source) = resolver.query_v4("example.net").await;
+                assert_eq!(record.result.as_ref().unwrap()[0].to_string(), "192.0.2.2");
+                assert!(matches!(source, ResolvedRecordSource::Cache));
+                assert_eq!(driver.queries(), 4);
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn serve_stale_limit() {
+        let runtime = ResolverRuntimeConfig {
+            serve_stale: Some(Duration::from_secs(20)),
+            ..Default::default()
+        };
+        let driver = FakeDriver::new(Ok(addrs()), 10);
+        let local = LocalSet::new();
+        let resolver = TestResolver::spawn(&local, runtime, &driver);
+
+        local
+            .run_until(async move {
+                assert_usable(&resolver.query_v4("example.net").await, false);
+
+                tokio::time::sleep(Duration::from_secs(11)).await;
+                driver.set(Err(servfail()), 60);
+                assert_usable(&resolver.query_v4("example.net").await, true);
+
+                // the stale record is removed after the stale time
+                tokio::time::sleep(Duration::from_secs(20)).await;
+                let (record, source) = resolver.query_v4("example.net").await;
+                assert!(record.is_err());
+                assert!(matches!(source, ResolvedRecordSource::Query));
+                assert_eq!(driver.queries(), 3);
+            })
+            .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn negative_ttl() {
+        let runtime = ResolverRuntimeConfig {
+            nxdomain_negative_ttl: Some(60),
+            servfail_negative_ttl: Some(20),
+            serve_stale: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        // the protective ttl set by the driver is 5s
+        let driver = FakeDriver::new(Err(nxdomain()), 5);
+        let local = LocalSet::new();
+        let resolver = TestResolver::spawn(&local, runtime, &driver);
+
+        local
+            .run_until(async move {
+                for (domain, result) in [
+                    ("nxdomain.example.net", Err(nxdomain())),
+                    ("nodata.example.net", Ok(Vec::new())),
+                ] {
+                    driver.set(result, 5);
+                    let queries = driver.queries();
+                    let (record, source) = resolver.query_v4(domain).await;
+                    assert!(!record.is_usable());
+                    assert!(matches!(source, ResolvedRecordSource::Query));
+
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    let (record, source) = resolver.query_v4(domain).await;
+                    assert!(!record.is_usable());
+                    assert!(matches!(source, ResolvedRecordSource::Cache));
+                    assert_eq!(driver.queries(), queries + 1);
+
+                    // negative records are not served stale
+                    tokio::time::sleep(Duration::from_secs(51)).await;
+                    let (_, source) = resolver.query_v4(domain).await;
+                    assert!(matches!(source, ResolvedRecordSource::Query));
+                    assert_eq!(driver.queries(), queries + 2);
+                }
+
+                driver.set(Err(servfail()), 5);
+                let _ = resolver.query_v4("servfail.example.net").await;
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                let (_, source) = resolver.query_v4("servfail.example.net").await;
+                assert!(matches!(source, ResolvedRecordSource::Cache));
+                tokio::time::sleep(Duration::from_secs(11)).await;
+                let (_, source) = resolver.query_v4("servfail.example.net").await;
+                assert!(matches!(source, ResolvedRecordSource::Query));
+            })
+            .await;
+    }
+}
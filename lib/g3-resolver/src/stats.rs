@@ -25,13 +25,16 @@ pub struct ResolverQueryStats {
     query_total: AtomicU64,
     query_cached: AtomicU64,
     query_driver: AtomicU64,
+    query_prefetch: AtomicU64,
     driver_timeout: AtomicU64,
     driver_refused: AtomicU64,
     driver_malformed: AtomicU64,
+    server_no_error: AtomicU64,
     server_refused: AtomicU64,
     server_malformed: AtomicU64,
     server_not_found: AtomicU64,
     server_serv_fail: AtomicU64,
+    server_not_imp: AtomicU64,
 }
 
 #[derive(Default)]
@@ -39,13 +42,16 @@ pub struct ResolverQuerySnapshot {
     pub total: u64,
     pub cached: u64,
     pub driver: u64,
+    pub prefetch: u64,
     pub driver_timeout: u64,
     pub driver_refused: u64,
     pub driver_malformed: u64,
+    pub server_no_error: u64,
     pub server_refused: u64,
     pub server_malformed: u64,
     pub server_not_found: u64,
     pub server_serv_fail: u64,
+    pub server_not_imp: u64,
 }
 
 impl ResolverQueryStats {
@@ -54,13 +60,16 @@ impl ResolverQueryStats {
             total: self.query_total.load(Ordering::Relaxed),
             cached: self.query_cached.load(Ordering::Relaxed),
             driver: self.query_driver.load(Ordering::Relaxed),
+            prefetch: self.query_prefetch.load(Ordering::Relaxed),
             driver_timeout: self.driver_timeout.load(Ordering::Relaxed),
             driver_refused: self.driver_refused.load(Ordering::Relaxed),
             driver_malformed: self.driver_malformed.load(Ordering::Relaxed),
+            server_no_error: self.server_no_error.load(Ordering::Relaxed),
             server_refused: self.server_refused.load(Ordering::Relaxed),
             server_malformed: self.server_malformed.load(Ordering::Relaxed),
             server_not_found: self.server_not_found.load(Ordering::Relaxed),
             server_serv_fail: self.server_serv_fail.load(Ordering::Relaxed),
+            server_not_imp: self.server_not_imp.load(Ordering::Relaxed),
         }
     }
 
@@ -82,6 +91,10 @@ impl ResolverQueryStats {
         self.query_driver.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub(crate) fn add_query_prefetch(&self) {
+        self.query_prefetch.fetch_add(1, Ordering::Relaxed);
+    }
+
     #[inline]
     fn add_driver_timeout(&self) {
         self.driver_timeout.fetch_add(1, Ordering::Relaxed);
@@ -97,6 +110,11 @@ impl ResolverQueryStats {
         self.driver_malformed.fetch_add(1, Ordering::Relaxed);
     }
 
+    #[inline]
+    fn add_server_no_error(&self) {
+        self.server_no_error.fetch_add(1, Ordering::Relaxed);
+    }
+
     #[inline]
     fn add_server_refused(&self) {
         self.server_refused.fetch_add(1, Ordering::Relaxed);
@@ -117,9 +135,15 @@ impl ResolverQueryStats {
         self.server_serv_fail.fetch_add(1, Ordering::Relaxed);
     }
 
+    #[inline]
+    fn add_server_not_imp(&self) {
+        self.server_not_imp.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub(crate) fn add_record(&self, record: &ResolvedRecord) {
-        if let Err(e) = &record.result {
-            self.add_error(e);
+        match &record.result {
+            Ok(_) => self.add_server_no_error(),
+            Err(e) => self.add_error(e),
         }
     }
 
@@ -129,7 +153,7 @@ impl ResolverQueryStats {
             ResolveServerError::FormErr => self.add_server_malformed(),
             ResolveServerError::NotFound => self.add_server_not_found(),
             ResolveServerError::ServFail => self.add_server_serv_fail(),
-            _ => {}
+            ResolveServerError::NotImp => self.add_server_not_imp(),
         }
     }
 
@@ -223,3 +247,62 @@ pub struct ResolverSnapshot {
     pub memory_a: ResolverMemorySnapshot,
     pub memory_aaaa: ResolverMemorySnapshot,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn record(result: Result<Vec<std::net::IpAddr>, ResolveError>) -> ResolvedRecord {
+        ResolvedRecord {
+            domain: "www.example.net".to_string(),
+            created: Instant::now(),
+            expire: None,
+            result,
+        }
+    }
+
+    #[test]
+    fn rcode() {
+        let stats = ResolverQueryStats::default();
+        stats.add_record(&record(Ok(vec!["192.0.2.1".parse().unwrap()])));
+        stats.add_record(&record(Ok(Vec::new())));
+        for e in [
+            ResolveServerError::NotFound,
+            ResolveServerError::NotFound,
+            ResolveServerError::ServFail,
+            ResolveServerError::Refused,
+            ResolveServerError::FormErr,
+            ResolveServerError::NotImp,
+        ] {
+            stats.add_record(&record(Err(ResolveError::FromServer(e))));
+        }
+        let snap = stats.snapshot();
+        assert_eq!(snap.server_no_error, 2);
+        assert_eq!(snap.server_not_found, 2);
+        assert_eq!(snap.server_serv_fail, 1);
+        assert_eq!(snap.server_refused, 1);
+        assert_eq!(snap.server_malformed, 1);
+        assert_eq!(snap.server_not_imp, 1);
+        assert_eq!(snap.driver_timeout, 0);
+    }
+
+    #[test]
+    fn timeout() {
+        let stats = ResolverQueryStats::default();
+        stats.add_error(&ResolveError::FromDriver(ResolveDriverError::Timeout));
+        stats.add_record(&ResolvedRecord::timed_out(
+            "www.example.net".to_string(),
+            30,
+        ));
+        stats.add_error(&ResolveError::FromDriver(ResolveDriverError::ConnRefused));
+        stats.add_error(&ResolveError::FromDriver(ResolveDriverError::BadResp));
+
+        let snap = stats.snapshot();
+        assert_eq!(snap.driver_timeout, 2);
+        assert_eq!(snap.driver_refused, 1);
+        assert_eq!(snap.driver_malformed, 1);
+        assert_eq!(snap.server_no_error, 0);
+        assert_eq!(snap.server_refused, 0);
+    }
+}
@@ -0,0 +1,263 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use anyhow::anyhow;
+
+/// the EDNS option code of Client Subnet, see RFC 7871
+pub const EDNS_OPTION_CODE_CLIENT_SUBNET: u16 = 8;
+
+const DEFAULT_IPV4_PREFIX: u8 = 24;
+const DEFAULT_IPV6_PREFIX: u8 = 56;
+
+/// A masked client subnet to be sent in the EDNS Client Subnet option
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct ClientSubnet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl ClientSubnet {
+    pub fn new(ip: IpAddr, prefix: u8) -> Self {
+        match ip {
+            IpAddr::V4(ip4) => {
+                let prefix = prefix.min(32);
+                let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                let addr = Ipv4Addr::from(u32::from(ip4) & mask);
+                ClientSubnet {
+                    addr: IpAddr::V4(addr),
+                    prefix,
+                }
+            }
+            IpAddr::V6(ip6) => {
+                let prefix = prefix.min(128);
+                let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                let addr = Ipv6Addr::from(u128::from(ip6) & mask);
+                ClientSubnet {
+                    addr: IpAddr::V6(addr),
+                    prefix,
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    #[inline]
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// encode the option data, the scope prefix length is always 0 in queries
+    pub fn encode_option_data(&self) -> Vec<u8> {
+        let addr_len = (self.prefix as usize).div_ceil(8);
+        let mut buf = Vec::with_capacity(4 + addr_len);
+        match self.addr {
+            IpAddr::V4(ip4) => {
+                buf.extend_from_slice(&1u16.to_be_bytes());
+                buf.push(self.prefix);
+                buf.push(0);
+                buf.extend_from_slice(&ip4.octets()[..addr_len]);
+            }
+            IpAddr::V6(ip6) => {
+                buf.extend_from_slice(&2u16.to_be_bytes());
+                buf.push(self.prefix);
+                buf.push(0);
+                buf.extend_from_slice(&ip6.octets()[..addr_len]);
+            }
+        }
+        buf
+    }
+}
+
+impl fmt::Display for ClientSubnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ClientSubnetConfig {
+    ipv4_prefix: u8,
+    ipv6_prefix: u8,
+}
+
+impl Default for ClientSubnetConfig {
+    fn default() -> Self {
+        ClientSubnetConfig {
+            ipv4_prefix: DEFAULT_IPV4_PREFIX,
+            ipv6_prefix: DEFAULT_IPV6_PREFIX,
+        }
+    }
+}
+
+impl ClientSubnetConfig {
+    /// set the source prefix length for ipv4 clients, 0 to disable
+    pub fn set_ipv4_prefix(&mut self, prefix: u8) -> anyhow::Result<()> {
+        if prefix > 32 {
+            return Err(anyhow!("invalid ipv4 prefix length {prefix}"));
+        }
+        self.ipv4_prefix = prefix;
+        Ok(())
+    }
+
+    /// set the source prefix length for ipv6 clients, 0 to disable
+    pub fn set_ipv6_prefix(&mut self, prefix: u8) -> anyhow::Result<()> {
+        if prefix > 128 {
+            return Err(anyhow!("invalid ipv6 prefix length {prefix}"));
+        }
+        self.ipv6_prefix = prefix;
+        Ok(())
+    }
+
+    pub fn subnet(&self, client_ip: IpAddr) -> Option<ClientSubnet> {
+        let client_ip = match client_ip {
+            IpAddr::V6(ip6) => ip6
+                .to_ipv4_mapped()
+                .map(IpAddr::V4)
+                .unwrap_or(IpAddr::V6(ip6)),
+            ip => ip,
+        };
+        let prefix = match client_ip {
+            IpAddr::V4(ip4) => {
+                if ip4.is_loopback() || ip4.is_private() || ip4.is_link_local() {
+                    return None;
+                }
+                self.ipv4_prefix
+            }
+            IpAddr::V6(ip6) => {
+                if ip6.is_loopback()
+                    || ip6.is_unspecified()
+                    || is_unique_local(&ip6)
+                    || is_unicast_link_local(&ip6)
+                {
+                    return None;
+                }
+                self.ipv6_prefix
+            }
+        };
+        if prefix == 0 {
+            None
+        } else {
+            Some(ClientSubnet::new(client_ip, prefix))
+        }
+    }
+}
+
+/// fc00::/7, see RFC 4193
+fn is_unique_local(ip6: &Ipv6Addr) -> bool {
+    (ip6.segments()[0] & 0xfe00) == 0xfc00
+}
+
+/// fe80::/10, see RFC 4291
+fn is_unicast_link_local(ip6: &Ipv6Addr) -> bool {
+    (ip6.segments()[0] & 0xffc0) == 0xfe80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn mask() {
+        let s = ClientSubnet::new(IpAddr::from_str("203.0.113.77").unwrap(), 24);
+        assert_eq!(s.addr(), IpAddr::from_str("203.0.113.0").unwrap());
+        assert_eq!(s.encode_option_data(), [0, 1, 24, 0, 203, 0, 113]);
+        assert_eq!(s.to_string(), "203.0.113.0/24");
+
+        let s = ClientSubnet::new(IpAddr::from_str("203.0.113.77").unwrap(), 20);
+        assert_eq!(s.addr(), IpAddr::from_str("203.0.112.0").unwrap());
+        assert_eq!(s.encode_option_data(), [0, 1, 20, 0, 203, 0, 112]);
+
+        let s = ClientSubnet::new(IpAddr::from_str("2001:db8:1:2:3::1").unwrap(), 56);
+        assert_eq!(s.addr(), IpAddr::from_str("2001:db8:1::").unwrap());
+        assert_eq!(
+            s.encode_option_data(),
+            [0, 2, 56, 0, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x01, 0x00]
+        );
+    }
+
+    #[test]
+    fn config() {
+        let mut config = ClientSubnetConfig::default();
+        let ip = IpAddr::from_str("198.51.100.1").unwrap();
+        let s = config.subnet(ip).unwrap();
+        assert_eq!(s.prefix(), 24);
+
+        let mapped = IpAddr::from_str("::ffff:198.51.100.1").unwrap();
+        assert_eq!(config.subnet(mapped), Some(s));
+
+        assert!(config
+            .subnet(IpAddr::from_str("10.0.0.1").unwrap())
+            .is_none());
+        assert!(config.subnet(IpAddr::from_str("::1").unwrap()).is_none());
+
+        let ip6 = IpAddr::from_str("2001:db8:1:2:3::1").unwrap();
+        let s = config.subnet(ip6).unwrap();
+        assert_eq!(s.prefix(), 56);
+
+        config.set_ipv4_prefix(0).unwrap();
+        assert!(config.subnet(ip).is_none());
+        assert!(config.set_ipv6_prefix(129).is_err());
+    }
+
+    #[test]
+    fn excluded_ipv4() {
+        let config = ClientSubnetConfig::default();
+        for ip in [
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.0.1",
+            "172.31.255.254",
+            "192.168.1.1",
+            "169.254.0.1",
+        ] {
+            let ip = IpAddr::from_str(ip).unwrap();
+            assert!(config.subnet(ip).is_none(), "{ip} should be excluded");
+        }
+        assert!(config
+            .subnet(IpAddr::from_str("172.32.0.1").unwrap())
+            .is_some());
+    }
+
+    #[test]
+    fn excluded_ipv6() {
+        let config = ClientSubnetConfig::default();
+        for ip in [
+            "::1",
+            "::",
+            "fc00::1",
+            "fd12:3456:789a::1",
+            "fe80::1",
+            "febf:ffff::1",
+            "::ffff:10.0.0.1",
+            "::ffff:169.254.0.1",
+        ] {
+            let ip = IpAddr::from_str(ip).unwrap();
+            assert!(config.subnet(ip).is_none(), "{ip} should be excluded");
+        }
+        for ip in ["fbff::1", "fec0::1", "2001:db8::1"] {
+            let ip = IpAddr::from_str(ip).unwrap();
+            assert!(config.subnet(ip).is_some(), "{ip} should not be excluded");
+        }
+    }
+}
@@ -15,4 +15,5 @@
  */
 
 pub mod blended;
+pub mod task;
 pub mod unaided;
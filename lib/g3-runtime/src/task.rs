@@ -0,0 +1,74 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Spawn helpers for long-lived tasks.
+//!
+//! With the `tokio-console` feature enabled, the tasks will be spawned with the given name, which
+//! will be shown in tokio-console. The build should also have `--cfg tokio_unstable` set in
+//! RUSTFLAGS. Otherwise the name will be ignored.
+
+use std::future::Future;
+
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+
+/// Spawn a named task on the current runtime
+#[cfg(feature = "tokio-console")]
+pub fn spawn_named<F>(name: &str, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::task::Builder::new()
+        .name(name)
+        .spawn(future)
+        .expect("failed to spawn named task")
+}
+
+/// Spawn a named task on the current runtime
+#[cfg(not(feature = "tokio-console"))]
+#[inline]
+pub fn spawn_named<F>(_name: &str, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(future)
+}
+
+/// Spawn a named task on the runtime of the given handle
+#[cfg(feature = "tokio-console")]
+pub fn spawn_named_on<F>(name: &str, future: F, handle: &Handle) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::task::Builder::new()
+        .name(name)
+        .spawn_on(future, handle)
+        .expect("failed to spawn named task")
+}
+
+/// Spawn a named task on the runtime of the given handle
+#[cfg(not(feature = "tokio-console"))]
+#[inline]
+pub fn spawn_named_on<F>(_name: &str, future: F, handle: &Handle) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    handle.spawn(future)
+}
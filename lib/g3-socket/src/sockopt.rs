@@ -37,6 +37,20 @@ where
     Ok(())
 }
 
+#[cfg(target_os = "linux")]
+unsafe fn getsockopt<T>(fd: c_int, opt: c_int, val: c_int) -> io::Result<T>
+where
+    T: Copy,
+{
+    let mut payload: mem::MaybeUninit<T> = mem::MaybeUninit::uninit();
+    let mut len = mem::size_of::<T>() as libc::socklen_t;
+    let ret = libc::getsockopt(fd, opt, val, payload.as_mut_ptr().cast(), &mut len);
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(payload.assume_init())
+}
+
 pub(crate) fn set_only_ipv6(fd: c_int, only_ipv6: bool) -> io::Result<()> {
     unsafe {
         setsockopt(
@@ -49,6 +63,13 @@ pub(crate) fn set_only_ipv6(fd: c_int, only_ipv6: bool) -> io::Result<()> {
     }
 }
 
+pub(crate) fn set_ipv6_tclass(fd: c_int, tclass: u8) -> io::Result<()> {
+    unsafe {
+        setsockopt(fd, libc::IPPROTO_IPV6, libc::IPV6_TCLASS, tclass as c_int)?;
+        Ok(())
+    }
+}
+
 #[cfg(target_os = "linux")]
 pub(crate) fn set_bind_address_no_port(fd: c_int, enable: bool) -> io::Result<()> {
     unsafe {
@@ -66,3 +87,20 @@ pub(crate) fn set_bind_address_no_port(fd: c_int, enable: bool) -> io::Result<()
 pub(crate) fn set_bind_address_no_port(_fd: c_int, _enable: bool) -> io::Result<()> {
     Ok(())
 }
+
+#[cfg(target_os = "linux")]
+pub(crate) fn get_listen_backlog(fd: c_int) -> io::Result<u32> {
+    unsafe {
+        // for listen sockets, tcpi_unacked is the current length of the accept queue
+        let info: libc::tcp_info = getsockopt(fd, libc::IPPROTO_TCP, libc::TCP_INFO)?;
+        Ok(info.tcpi_unacked)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn get_listen_backlog(_fd: c_int) -> io::Result<u32> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "listen backlog is not supported on this platform",
+    ))
+}
@@ -15,15 +15,19 @@
  */
 
 use std::io;
-use std::net::{IpAddr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
 
 use socket2::{Domain, SockAddr, Socket, TcpKeepalive, Type};
 use tokio::net::{TcpListener, TcpSocket};
 
-use g3_types::net::{TcpKeepAliveConfig, TcpListenConfig, TcpMiscSockOpts};
+use g3_types::net::{
+    PortRange, TcpBindConfig, TcpKeepAliveConfig, TcpListenConfig, TcpMiscSockOpts,
+};
 
-use super::sockopt::{set_bind_address_no_port, set_only_ipv6};
+use super::sockopt::{
+    get_listen_backlog, set_bind_address_no_port, set_ipv6_tclass, set_only_ipv6,
+};
 use super::util::AddressFamily;
 
 pub fn new_std_listener(config: &TcpListenConfig) -> io::Result<std::net::TcpListener> {
@@ -51,6 +55,24 @@ pub fn new_std_socket_to(
     keepalive: &TcpKeepAliveConfig,
     misc_opts: &TcpMiscSockOpts,
     default_set_nodelay: bool,
+) -> io::Result<std::net::TcpStream> {
+    new_std_socket_to_with_bind(
+        peer_ip,
+        bind_ip,
+        &TcpBindConfig::default(),
+        keepalive,
+        misc_opts,
+        default_set_nodelay,
+    )
+}
+
+pub fn new_std_socket_to_with_bind(
+    peer_ip: IpAddr,
+    bind_ip: Option<IpAddr>,
+    bind_config: &TcpBindConfig,
+    keepalive: &TcpKeepAliveConfig,
+    misc_opts: &TcpMiscSockOpts,
+    default_set_nodelay: bool,
 ) -> io::Result<std::net::TcpStream> {
     let peer_family = AddressFamily::from(&peer_ip);
     let socket = new_tcp_socket(peer_family)?;
@@ -61,7 +83,15 @@ pub fn new_std_socket_to(
                 format!("peer_ip {peer_ip} and bind_ip {ip} should be of the same family",),
             ));
         }
-        set_bind_address_no_port(socket.as_raw_fd(), true)?;
+    }
+    if let Some(port_range) = bind_config.port_range() {
+        let ip = bind_ip.unwrap_or_else(|| match peer_family {
+            AddressFamily::Ipv4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            AddressFamily::Ipv6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        });
+        bind_in_range(&socket, ip, port_range)?;
+    } else if let Some(ip) = bind_ip {
+        set_bind_address_no_port(socket.as_raw_fd(), bind_config.bind_no_port())?;
         let addr: SockAddr = SocketAddr::new(ip, 0).into();
         socket.bind(&addr)?;
     }
@@ -80,6 +110,30 @@ pub fn new_std_socket_to(
     Ok(std::net::TcpStream::from(socket))
 }
 
+const BIND_IN_RANGE_MAX_TRIES: u16 = 16;
+
+/// Try a bounded number of ports in sequence from a random one within the range.
+///
+/// The last EADDRINUSE error will be returned if all tried ports are in use.
+fn bind_in_range(socket: &Socket, ip: IpAddr, port: PortRange) -> io::Result<()> {
+    let port_start = port.start();
+    let port_count = port.count();
+
+    let offset = fastrand::u16(0..port_count);
+    let tries = port_count.min(BIND_IN_RANGE_MAX_TRIES);
+    let mut last_err = io::Error::from(io::ErrorKind::AddrInUse);
+    for i in 0..tries {
+        let port = port_start + ((offset as u32 + i as u32) % port_count as u32) as u16;
+        let bind_addr: SockAddr = SocketAddr::new(ip, port).into();
+        match socket.bind(&bind_addr) {
+            Ok(_) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse => last_err = e,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_err)
+}
+
 pub fn set_raw_opts(
     fd: RawFd,
     misc_opts: &TcpMiscSockOpts,
@@ -91,6 +145,18 @@ pub fn set_raw_opts(
     Ok(())
 }
 
+pub fn get_raw_mss(fd: RawFd) -> io::Result<u32> {
+    let socket = unsafe { Socket::from_raw_fd(fd) };
+    let r = socket.mss();
+    let _ = socket.into_raw_fd();
+    r
+}
+
+/// Get the number of established connections that are waiting in the accept queue
+pub fn get_raw_listen_backlog(fd: RawFd) -> io::Result<u32> {
+    get_listen_backlog(fd)
+}
+
 fn set_misc_opts(
     socket: &Socket,
     misc_opts: &TcpMiscSockOpts,
@@ -110,10 +176,19 @@ fn set_misc_opts(
     if let Some(tos) = misc_opts.type_of_service {
         socket.set_tos(tos as u32)?;
     }
+    if let Some(tclass) = misc_opts.traffic_class {
+        if socket.local_addr()?.is_ipv6() {
+            set_ipv6_tclass(socket.as_raw_fd(), tclass)?;
+        }
+    }
     #[cfg(target_os = "linux")]
     if let Some(mark) = misc_opts.netfilter_mark {
         socket.set_mark(mark)?;
     }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if let Some(timeout) = misc_opts.user_timeout {
+        socket.set_tcp_user_timeout(Some(timeout))?;
+    }
     Ok(())
 }
 
@@ -161,3 +236,50 @@ pub fn new_socket_to(
     let socket = new_std_socket_to(peer_ip, bind_ip, keepalive, misc_opts, default_set_nodelay)?;
     Ok(TcpSocket::from_std_stream(socket))
 }
+
+pub fn new_socket_to_with_bind(
+    peer_ip: IpAddr,
+    bind_ip: Option<IpAddr>,
+    bind_config: &TcpBindConfig,
+    keepalive: &TcpKeepAliveConfig,
+    misc_opts: &TcpMiscSockOpts,
+    default_set_nodelay: bool,
+) -> io::Result<TcpSocket> {
+    let socket = new_std_socket_to_with_bind(
+        peer_ip,
+        bind_ip,
+        bind_config,
+        keepalive,
+        misc_opts,
+        default_set_nodelay,
+    )?;
+    Ok(TcpSocket::from_std_stream(socket))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bind_in_range_ok() {
+        let range = PortRange::new(61000, 65000);
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        for _i in 0..100 {
+            let socket = new_tcp_socket(AddressFamily::Ipv4).unwrap();
+            bind_in_range(&socket, ip, range).unwrap();
+            let port = socket.local_addr().unwrap().as_socket().unwrap().port();
+            assert!((61000..=65000).contains(&port));
+        }
+    }
+
+    #[test]
+    fn bind_in_range_in_use() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let range = PortRange::new(port, port);
+
+        let socket = new_tcp_socket(AddressFamily::Ipv4).unwrap();
+        let e = bind_in_range(&socket, IpAddr::V4(Ipv4Addr::LOCALHOST), range).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::AddrInUse);
+    }
+}
@@ -22,7 +22,7 @@ use socket2::{Domain, SockAddr, Socket, Type};
 
 use g3_types::net::{PortRange, SocketBufferConfig, UdpListenConfig, UdpMiscSockOpts};
 
-use super::sockopt::set_bind_address_no_port;
+use super::sockopt::{set_bind_address_no_port, set_ipv6_tclass};
 use super::util::AddressFamily;
 
 pub fn new_std_socket_to(
@@ -178,6 +178,11 @@ fn set_misc_opts(socket: &Socket, misc_opts: UdpMiscSockOpts) -> io::Result<()>
     if let Some(tos) = misc_opts.type_of_service {
         socket.set_tos(tos as u32)?;
     }
+    if let Some(tclass) = misc_opts.traffic_class {
+        if socket.local_addr()?.is_ipv6() {
+            set_ipv6_tclass(socket.as_raw_fd(), tclass)?;
+        }
+    }
     #[cfg(target_os = "linux")]
     if let Some(mark) = misc_opts.netfilter_mark {
         socket.set_mark(mark)?;
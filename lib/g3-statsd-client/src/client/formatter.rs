@@ -19,7 +19,7 @@ use ryu::Float;
 use smallvec::SmallVec;
 
 use super::StatsdClient;
-use crate::StatsdTagGroup;
+use crate::{StatsdTagFormat, StatsdTagGroup};
 
 enum MetricType {
     Count,
@@ -173,22 +173,31 @@ impl<'a> MetricFormatter<'a> {
                 self.msg_len += 2 + self.local_tags.len() // |#<tags>
             }
         }
+        let tag_format = self.client.tag_format;
         if let Err(e) = self.client.sink.emit(self.msg_len, |buf| {
             if !self.client.prefix.is_empty() {
                 buf.extend_from_slice(self.client.prefix.as_bytes());
                 buf.push(b'.');
             }
             buf.extend_from_slice(self.name.as_bytes());
+
+            if tag_format == StatsdTagFormat::Graphite && self.has_tags {
+                push_graphite_tags(buf, self.client.tags.as_bytes());
+                if let Some(common_tags) = self.common_tags {
+                    push_graphite_tags(buf, common_tags.as_bytes());
+                }
+                push_graphite_tags(buf, self.local_tags.as_bytes());
+            }
+
             buf.push(b':');
             buf.extend_from_slice(self.value.as_slice());
             buf.push(b'|');
             buf.extend_from_slice(self.metric_type.as_str().as_bytes());
 
-            if self.has_tags {
-                buf.extend_from_slice(b"|#");
-            } else {
+            if tag_format != StatsdTagFormat::DogStatsd || !self.has_tags {
                 return;
             }
+            buf.extend_from_slice(b"|#");
 
             let mut append_tags = false;
             if self.client.tags.len() > 0 {
@@ -217,3 +226,19 @@ impl<'a> MetricFormatter<'a> {
         }
     }
 }
+
+/// convert `k:v,k:v` to `;k=v;k=v`, tags without key are dropped as they are not supported
+fn push_graphite_tags(buf: &mut Vec<u8>, tags: &[u8]) {
+    if tags.is_empty() {
+        return;
+    }
+    for tag in tags.split(|c| *c == b',') {
+        let Some(p) = tag.iter().position(|c| *c == b':') else {
+            continue;
+        };
+        buf.push(b';');
+        buf.extend_from_slice(&tag[..p]);
+        buf.push(b'=');
+        buf.extend_from_slice(&tag[p + 1..]);
+    }
+}
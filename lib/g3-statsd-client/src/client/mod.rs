@@ -19,15 +19,16 @@ use std::time::Instant;
 
 use log::warn;
 
-use g3_types::metrics::MetricsName;
+use g3_types::metrics::{MetricsName, StaticMetricsTags};
 
-use crate::{StatsdMetricsSink, StatsdTagGroup};
+use crate::{StatsdMetricsSink, StatsdTagFormat, StatsdTagGroup};
 
 mod formatter;
 
 pub struct StatsdClient {
     prefix: MetricsName,
     sink: StatsdMetricsSink,
+    tag_format: StatsdTagFormat,
     tags: StatsdTagGroup,
 
     create_instant: Instant,
@@ -39,12 +40,21 @@ impl StatsdClient {
         StatsdClient {
             prefix,
             sink,
+            tag_format: StatsdTagFormat::default(),
             tags: Default::default(),
             create_instant: Instant::now(),
             last_error_report: 0,
         }
     }
 
+    pub(crate) fn set_tag_format(&mut self, format: StatsdTagFormat) {
+        self.tag_format = format;
+    }
+
+    pub(crate) fn add_static_tags(&mut self, tags: &StaticMetricsTags) {
+        self.tags.add_static_tags(tags);
+    }
+
     pub fn with_tag<T: AsRef<str>>(mut self, key: &str, value: T) -> Self {
         self.tags.add_tag(key, value);
         self
@@ -127,6 +137,24 @@ mod tests {
         assert_eq!(buf.as_slice(), b"test.count:20|c|#tag1:1234,tag2:a");
     }
 
+    #[test]
+    fn count_with_graphite_tags() {
+        let buf = Rc::new(Mutex::new(Vec::default()));
+        let sink = StatsdMetricsSink::buf_with_capacity(buf.clone(), 64);
+        let prefix = unsafe { MetricsName::from_str_unchecked("test") };
+        let mut client = StatsdClient::new(prefix, sink).with_tag("tag1", "1234");
+        client.set_tag_format(StatsdTagFormat::Graphite);
+        client
+            .count("count", 20)
+            .with_tag("tag2", "a")
+            .with_tag_value("v")
+            .send();
+        client.flush_sink();
+
+        let buf = buf.lock().unwrap();
+        assert_eq!(buf.as_slice(), b"test.count;tag1=1234;tag2=a:20|c");
+    }
+
     #[test]
     fn count_multiple_simple() {
         let buf = Rc::new(Mutex::new(Vec::default()));
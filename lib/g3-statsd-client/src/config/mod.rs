@@ -18,11 +18,12 @@ use std::io;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
 use std::os::unix::net::UnixDatagram;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::Duration;
 
-use g3_types::metrics::MetricsName;
+use g3_types::metrics::{MetricsName, StaticMetricsTags};
 
-use crate::{StatsdClient, StatsdMetricsSink};
+use crate::{PrometheusExporter, StatsdClient, StatsdMetricsSink};
 
 const UDP_DEFAULT_PORT: u16 = 8125;
 
@@ -39,10 +40,35 @@ impl Default for StatsdBackend {
     }
 }
 
+/// how the tags are encoded in the statsd message
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatsdTagFormat {
+    /// `<name>:<value>|<type>|#<k>:<v>,<k>:<v>`
+    #[default]
+    DogStatsd,
+    /// `<name>;<k>=<v>;<k>=<v>:<value>|<type>`
+    Graphite,
+}
+
+impl FromStr for StatsdTagFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dogstatsd" | "datadog" => Ok(StatsdTagFormat::DogStatsd),
+            "graphite" => Ok(StatsdTagFormat::Graphite),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct StatsdClientConfig {
     backend: StatsdBackend,
     prefix: MetricsName,
+    tag_format: StatsdTagFormat,
+    global_tags: StaticMetricsTags,
+    prometheus: Option<PrometheusExporter>,
     pub emit_duration: Duration,
 }
 
@@ -57,6 +83,9 @@ impl StatsdClientConfig {
         StatsdClientConfig {
             backend: StatsdBackend::default(),
             prefix,
+            tag_format: StatsdTagFormat::default(),
+            global_tags: StaticMetricsTags::new(),
+            prometheus: None,
             emit_duration: Duration::from_millis(200),
         }
     }
@@ -69,8 +98,25 @@ impl StatsdClientConfig {
         self.prefix = prefix;
     }
 
+    pub fn set_tag_format(&mut self, format: StatsdTagFormat) {
+        self.tag_format = format;
+    }
+
+    pub fn set_global_tags(&mut self, tags: StaticMetricsTags) {
+        self.global_tags = tags;
+    }
+
+    pub fn set_prometheus_listen(&mut self, addr: SocketAddr) {
+        self.prometheus = Some(PrometheusExporter::new(addr));
+    }
+
+    #[inline]
+    pub fn prometheus(&self) -> Option<&PrometheusExporter> {
+        self.prometheus.as_ref()
+    }
+
     pub fn build(&self) -> io::Result<StatsdClient> {
-        let sink = match &self.backend {
+        let mut sink = match &self.backend {
             StatsdBackend::Udp(addr, bind) => {
                 let bind_ip = bind.unwrap_or_else(|| match addr {
                     SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
@@ -84,7 +130,13 @@ impl StatsdClientConfig {
                 StatsdMetricsSink::unix_with_capacity(path.clone(), socket, 4096)
             }
         };
+        if let Some(exporter) = &self.prometheus {
+            sink.set_prometheus(exporter.clone());
+        }
 
-        Ok(StatsdClient::new(self.prefix.clone(), sink))
+        let mut client = StatsdClient::new(self.prefix.clone(), sink);
+        client.set_tag_format(self.tag_format);
+        client.add_static_tags(&self.global_tags);
+        Ok(client)
     }
 }
@@ -24,4 +24,7 @@ mod tag;
 pub use tag::StatsdTagGroup;
 
 mod config;
-pub use config::{StatsdBackend, StatsdClientConfig};
+pub use config::{StatsdBackend, StatsdClientConfig, StatsdTagFormat};
+
+mod prometheus;
+pub use prometheus::PrometheusExporter;
@@ -0,0 +1,199 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SampleType {
+    Counter,
+    Gauge,
+}
+
+impl SampleType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SampleType::Counter => "counter",
+            SampleType::Gauge => "gauge",
+        }
+    }
+}
+
+struct Sample {
+    sample_type: SampleType,
+    value: f64,
+}
+
+/// Collect the emitted statsd metrics, and expose them in prometheus text format
+#[derive(Clone)]
+pub struct PrometheusExporter {
+    listen: SocketAddr,
+    samples: Arc<Mutex<BTreeMap<(String, String), Sample>>>,
+}
+
+impl fmt::Debug for PrometheusExporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrometheusExporter")
+            .field("listen", &self.listen)
+            .finish()
+    }
+}
+
+fn push_name(buf: &mut String, name: &str, allow_colon: bool) {
+    for (i, c) in name.chars().enumerate() {
+        if i == 0 && c.is_ascii_digit() {
+            buf.push('_');
+        }
+        if c.is_ascii_alphanumeric() || c == '_' || (allow_colon && c == ':') {
+            buf.push(c);
+        } else {
+            buf.push('_');
+        }
+    }
+}
+
+fn push_label_value(buf: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '\\' => buf.push_str("\\\\"),
+            '"' => buf.push_str("\\\""),
+            '\n' => buf.push_str("\\n"),
+            _ => buf.push(c),
+        }
+    }
+}
+
+impl PrometheusExporter {
+    pub fn new(listen: SocketAddr) -> Self {
+        PrometheusExporter {
+            listen,
+            samples: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    #[inline]
+    pub fn listen_addr(&self) -> SocketAddr {
+        self.listen
+    }
+
+    /// parse the statsd messages in `buf`, which should be separated by '\n'
+    pub(crate) fn collect(&self, buf: &[u8]) {
+        let Ok(s) = std::str::from_utf8(buf) else {
+            return;
+        };
+
+        let mut samples = self.samples.lock().unwrap();
+        for line in s.split('\n') {
+            let Some((name, line)) = line.split_once(':') else {
+                continue;
+            };
+            let mut parts = line.split('|');
+            let Some(value) = parts.next().and_then(|v| v.parse::<f64>().ok()) else {
+                continue;
+            };
+            let sample_type = match parts.next() {
+                Some("c") => SampleType::Counter,
+                Some("g") => SampleType::Gauge,
+                _ => continue,
+            };
+
+            // graphite style tags are appended to the name
+            let mut name_parts = name.split(';');
+            let name = name_parts.next().unwrap_or_default();
+            let mut metric_name = String::with_capacity(name.len());
+            push_name(&mut metric_name, name, true);
+
+            let mut labels = String::new();
+            let mut push_label = |k: &str, v: &str| {
+                if !labels.is_empty() {
+                    labels.push(',');
+                }
+                push_name(&mut labels, k, false);
+                labels.push_str("=\"");
+                push_label_value(&mut labels, v);
+                labels.push('"');
+            };
+            for tag in name_parts {
+                if let Some((k, v)) = tag.split_once('=') {
+                    push_label(k, v);
+                }
+            }
+            if let Some(tags) = parts.next().and_then(|t| t.strip_prefix('#')) {
+                for tag in tags.split(',') {
+                    // tags without key are ignored
+                    if let Some((k, v)) = tag.split_once(':') {
+                        push_label(k, v);
+                    }
+                }
+            }
+
+            let sample = samples.entry((metric_name, labels)).or_insert(Sample {
+                sample_type,
+                value: 0.0,
+            });
+            match sample_type {
+                SampleType::Counter => sample.value += value,
+                SampleType::Gauge => sample.value = value,
+            }
+            sample.sample_type = sample_type;
+        }
+    }
+
+    /// render all collected metrics in prometheus text exposition format
+    pub fn render(&self) -> String {
+        let samples = self.samples.lock().unwrap();
+        let mut buf = String::with_capacity(samples.len() * 128);
+        let mut last_name = "";
+        for ((name, labels), sample) in samples.iter() {
+            if name != last_name {
+                let _ = writeln!(buf, "# TYPE {name} {}", sample.sample_type.as_str());
+                last_name = name;
+            }
+            if labels.is_empty() {
+                let _ = writeln!(buf, "{name} {}", sample.value);
+            } else {
+                let _ = writeln!(buf, "{name}{{{labels}}} {}", sample.value);
+            }
+        }
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[test]
+    fn collect_and_render() {
+        let exporter = PrometheusExporter::new(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0));
+        exporter.collect(
+            b"test.count:20|c|#c1:v1,c2:a\"b\ntest.gauge:1.5|g\ntest.count:30|c|#c1:v1,c2:a\"b",
+        );
+        exporter.collect(b"test.gauge:2|g|#value-only");
+
+        assert_eq!(
+            exporter.render(),
+            "# TYPE test_count counter\n\
+             test_count{c1=\"v1\",c2=\"a\\\"b\"} 50\n\
+             # TYPE test_gauge gauge\n\
+             test_gauge 2\n"
+        );
+    }
+}
@@ -23,6 +23,8 @@ use std::rc::Rc;
 #[cfg(test)]
 use std::sync::Mutex;
 
+use crate::PrometheusExporter;
+
 #[cfg(test)]
 mod buf;
 #[cfg(test)]
@@ -56,6 +58,7 @@ pub(crate) struct StatsdMetricsSink {
     cache_size: usize,
     buf: Vec<u8>,
     io: MetricsSinkIo,
+    prometheus: Option<PrometheusExporter>,
 }
 
 impl StatsdMetricsSink {
@@ -65,6 +68,7 @@ impl StatsdMetricsSink {
             cache_size,
             buf: Vec::with_capacity(cache_size),
             io: MetricsSinkIo::Buf(BufMetricsSink::new(buf)),
+            prometheus: None,
         }
     }
 
@@ -77,6 +81,7 @@ impl StatsdMetricsSink {
             cache_size,
             buf: Vec::with_capacity(cache_size),
             io: MetricsSinkIo::Udp(UdpMetricsSink::new(addr, socket)),
+            prometheus: None,
         }
     }
 
@@ -89,6 +94,7 @@ impl StatsdMetricsSink {
             cache_size,
             buf: Vec::with_capacity(cache_size),
             io: MetricsSinkIo::Unix(UnixMetricsSink::new(path, socket)),
+            prometheus: None,
         }
     }
 
@@ -115,7 +121,14 @@ impl StatsdMetricsSink {
         self.flush_buf()
     }
 
+    pub(crate) fn set_prometheus(&mut self, exporter: PrometheusExporter) {
+        self.prometheus = Some(exporter);
+    }
+
     fn flush_buf(&mut self) -> io::Result<()> {
+        if let Some(exporter) = &self.prometheus {
+            exporter.collect(&self.buf);
+        }
         self.io.send_msg(&self.buf)?;
         self.buf.clear();
         Ok(())
@@ -15,9 +15,9 @@
  */
 
 use std::cell::RefCell;
-use std::io::{self, Write};
+use std::io;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 use flume::{Receiver, Sender, TrySendError};
 use log::warn;
@@ -119,6 +119,7 @@ impl AsyncIoThread {
     fn run_to_end(self) {
         let mut backend_container: Option<SyslogBackend> = self.build_backend();
         let mut failed_instant = Instant::now();
+        let reconnect_delay = self.backend_builder.reconnect_delay();
         while let Ok(s) = self.receiver.recv() {
             if let Some(mut backend) = backend_container.take() {
                 if self.send_data(s, &mut backend).is_err() {
@@ -132,14 +133,14 @@ impl AsyncIoThread {
                     backend_container = Some(backend);
                 }
             } else {
-                if failed_instant.elapsed() > Duration::from_secs(4) {
-                    // hard coded 4s for a minimal reconnect interval
+                if failed_instant.elapsed() > reconnect_delay {
                     if let Some(mut backend) = self.build_backend() {
                         if self.send_data(s, &mut backend).is_ok() {
                             backend_container = Some(backend);
                             continue;
                         }
                     }
+                    failed_instant = Instant::now();
                 }
                 self.stats.drop.add_peer_unreachable();
             }
@@ -158,8 +159,7 @@ impl AsyncIoThread {
 
     fn send_data(&self, data: String, backend: &mut SyslogBackend) -> io::Result<()> {
         let size = data.len();
-        backend.write_all(data.as_bytes())?;
-        backend.flush()?;
+        backend.send_msg(data.as_bytes())?;
         self.stats.io.add_passed();
         self.stats.io.add_size(size);
         Ok(())
@@ -18,32 +18,33 @@ use std::io;
 use std::net::{IpAddr, SocketAddr, UdpSocket};
 use std::os::unix::net::UnixDatagram;
 use std::path::PathBuf;
+use std::time::Duration;
 
+mod tcp;
 mod udp;
 mod unix_datagram;
 
+pub use tcp::SyslogTcpConfig;
+use tcp::TcpSyslog;
+
 pub(super) enum SyslogBackend {
     Udp(UdpSocket),
     Unix(UnixDatagram),
+    Tcp(TcpSyslog),
 }
 
 impl SyslogBackend {
     pub(super) fn need_reconnect(&self) -> bool {
-        false
+        matches!(self, SyslogBackend::Tcp(_))
     }
-}
 
-impl io::Write for SyslogBackend {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    pub(super) fn send_msg(&mut self, msg: &[u8]) -> io::Result<()> {
         match self {
-            SyslogBackend::Udp(s) => s.send(buf),
-            SyslogBackend::Unix(s) => s.send(buf),
+            SyslogBackend::Udp(s) => s.send(msg).map(|_| ()),
+            SyslogBackend::Unix(s) => s.send(msg).map(|_| ()),
+            SyslogBackend::Tcp(s) => s.send_msg(msg),
         }
     }
-
-    fn flush(&mut self) -> io::Result<()> {
-        Ok(())
-    }
 }
 
 #[derive(Clone, Debug)]
@@ -53,6 +54,8 @@ pub enum SyslogBackendBuilder {
     Unix(PathBuf),
     /// udp socket with optional bind ip and remote address
     Udp(Option<IpAddr>, SocketAddr),
+    /// tcp stream with optional tls
+    Tcp(SyslogTcpConfig),
 }
 
 impl SyslogBackendBuilder {
@@ -70,6 +73,17 @@ impl SyslogBackendBuilder {
                 let socket = udp::udp(*bind_ip, *server)?;
                 Ok(SyslogBackend::Udp(socket))
             }
+            SyslogBackendBuilder::Tcp(config) => {
+                let stream = config.connect()?;
+                Ok(SyslogBackend::Tcp(stream))
+            }
+        }
+    }
+
+    pub(super) fn reconnect_delay(&self) -> Duration {
+        match self {
+            SyslogBackendBuilder::Tcp(config) => config.reconnect_delay,
+            _ => Duration::from_secs(4),
         }
     }
 }
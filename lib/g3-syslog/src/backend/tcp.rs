@@ -0,0 +1,200 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt;
+use std::io::{self, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+use rustls::{ClientConnection, ServerName, StreamOwned};
+use socket2::{Domain, SockAddr, Socket, Type};
+
+use g3_types::net::RustlsClientConfig;
+
+#[derive(Clone)]
+pub struct SyslogTcpConfig {
+    server: SocketAddr,
+    bind_ip: Option<IpAddr>,
+    tls_client: Option<RustlsClientConfig>,
+    tls_name: Option<ServerName>,
+    connect_timeout: Duration,
+    write_timeout: Duration,
+    pub(crate) reconnect_delay: Duration,
+    octet_counting: bool,
+}
+
+impl fmt::Debug for SyslogTcpConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyslogTcpConfig")
+            .field("server", &self.server)
+            .field("bind_ip", &self.bind_ip)
+            .field("tls", &self.tls_client.is_some())
+            .field("tls_name", &self.tls_name)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("write_timeout", &self.write_timeout)
+            .field("reconnect_delay", &self.reconnect_delay)
+            .field("octet_counting", &self.octet_counting)
+            .finish()
+    }
+}
+
+impl SyslogTcpConfig {
+    pub fn new(server: SocketAddr) -> Self {
+        SyslogTcpConfig {
+            server,
+            bind_ip: None,
+            tls_client: None,
+            tls_name: None,
+            connect_timeout: Duration::from_secs(10),
+            write_timeout: Duration::from_secs(5),
+            reconnect_delay: Duration::from_secs(4),
+            octet_counting: true,
+        }
+    }
+
+    pub fn set_server(&mut self, server: SocketAddr) {
+        self.server = server;
+    }
+
+    pub fn set_bind_ip(&mut self, ip: IpAddr) {
+        self.bind_ip = Some(ip);
+    }
+
+    pub fn set_tls_client(&mut self, tls_client: RustlsClientConfig) {
+        self.tls_client = Some(tls_client);
+    }
+
+    pub fn set_tls_name(&mut self, tls_name: ServerName) {
+        self.tls_name = Some(tls_name);
+    }
+
+    pub fn set_connect_timeout(&mut self, timeout: Duration) {
+        self.connect_timeout = timeout;
+    }
+
+    pub fn set_write_timeout(&mut self, timeout: Duration) {
+        self.write_timeout = timeout;
+    }
+
+    pub fn set_reconnect_delay(&mut self, delay: Duration) {
+        self.reconnect_delay = delay;
+    }
+
+    pub fn set_octet_counting(&mut self, enable: bool) {
+        self.octet_counting = enable;
+    }
+
+    fn tcp_connect(&self) -> io::Result<TcpStream> {
+        let socket = Socket::new(Domain::for_address(self.server), Type::STREAM, None)?;
+        if let Some(ip) = self.bind_ip {
+            let bind_addr: SockAddr = SocketAddr::new(ip, 0).into();
+            socket.bind(&bind_addr)?;
+        }
+        let peer_addr: SockAddr = self.server.into();
+        socket.connect_timeout(&peer_addr, self.connect_timeout)?;
+        let stream = TcpStream::from(socket);
+        stream.set_nodelay(true)?;
+        Ok(stream)
+    }
+
+    fn tls_handshake(
+        &self,
+        tls_client: &RustlsClientConfig,
+        mut stream: TcpStream,
+    ) -> io::Result<StreamOwned<ClientConnection, TcpStream>> {
+        let tls_name = self
+            .tls_name
+            .clone()
+            .unwrap_or(ServerName::IpAddress(self.server.ip()));
+        let mut conn =
+            ClientConnection::new(tls_client.driver.clone(), tls_name).map_err(io::Error::other)?;
+
+        let time_start = Instant::now();
+        stream.set_read_timeout(Some(tls_client.handshake_timeout))?;
+        stream.set_write_timeout(Some(tls_client.handshake_timeout))?;
+        while conn.is_handshaking() {
+            if time_start.elapsed() > tls_client.handshake_timeout {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "tls handshake timed out",
+                ));
+            }
+            conn.complete_io(&mut stream)?;
+        }
+        stream.set_read_timeout(None)?;
+        Ok(StreamOwned::new(conn, stream))
+    }
+
+    pub(super) fn connect(&self) -> io::Result<TcpSyslog> {
+        let stream = self.tcp_connect()?;
+        let stream = match &self.tls_client {
+            Some(tls_client) => {
+                let tls_stream = self.tls_handshake(tls_client, stream)?;
+                tls_stream
+                    .sock
+                    .set_write_timeout(Some(self.write_timeout))?;
+                SyslogStream::Tls(Box::new(tls_stream))
+            }
+            None => {
+                stream.set_write_timeout(Some(self.write_timeout))?;
+                SyslogStream::Tcp(stream)
+            }
+        };
+        Ok(TcpSyslog {
+            stream,
+            octet_counting: self.octet_counting,
+            buf: Vec::with_capacity(1024),
+        })
+    }
+}
+
+enum SyslogStream {
+    Tcp(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+pub(crate) struct TcpSyslog {
+    stream: SyslogStream,
+    octet_counting: bool,
+    buf: Vec<u8>,
+}
+
+impl TcpSyslog {
+    /// send one message with the framing described in rfc6587
+    pub(super) fn send_msg(&mut self, msg: &[u8]) -> io::Result<()> {
+        self.buf.clear();
+        if self.octet_counting {
+            let mut buffer = itoa::Buffer::new();
+            self.buf
+                .extend_from_slice(buffer.format(msg.len()).as_bytes());
+            self.buf.push(b' ');
+            self.buf.extend_from_slice(msg);
+        } else {
+            self.buf.extend_from_slice(msg);
+            if !msg.ends_with(b"\n") {
+                self.buf.push(b'\n');
+            }
+        }
+
+        match &mut self.stream {
+            SyslogStream::Tcp(s) => s.write_all(&self.buf),
+            SyslogStream::Tls(s) => {
+                s.write_all(&self.buf)?;
+                s.flush()
+            }
+        }
+    }
+}
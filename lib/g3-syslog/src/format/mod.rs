@@ -31,6 +31,7 @@ mod rfc5424;
 pub(super) use cee::{FormatterRfc3164Cee, FormatterRfc5424Cee, CEE_EVENT_FLAG};
 pub(super) use rfc3164::FormatterRfc3164;
 pub(super) use rfc5424::FormatterRfc5424;
+pub use rfc5424::Rfc5424SdConfig;
 
 pub trait SyslogFormatter {
     fn append_report_ts(&mut self, enable: bool);
@@ -51,8 +52,8 @@ pub enum SyslogFormatterKind {
     Rfc3164,
     /// rfc3164 cee formatter with event flag
     Rfc3164Cee(String),
-    /// rfc5424 formatter with enterprise id and optional message id
-    Rfc5424(i32, Option<String>),
+    /// rfc5424 formatter with enterprise id, optional message id and structured data config
+    Rfc5424(i32, Option<String>, Rfc5424SdConfig),
     /// rfc5424 cee formatter with optional message id and event flag
     Rfc5424Cee(Option<String>, String),
 }
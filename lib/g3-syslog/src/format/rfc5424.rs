@@ -15,6 +15,7 @@
  */
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt::{Arguments, Write};
 use std::io;
 
@@ -29,22 +30,125 @@ use crate::util::{encode_priority, level_to_severity};
 thread_local! {
     static TL_BUF: RefCell<String> = RefCell::new(String::with_capacity(128));
     static TL_VBUF: RefCell<Vec<u8>> = RefCell::new(Vec::with_capacity(128));
+    static TL_SD_BUF: RefCell<Vec<Vec<u8>>> = RefCell::new(Vec::new());
+}
+
+const DEFAULT_SD_ID: &str = "g3proxy";
+
+/// Config for the rfc5424 structured data.
+///
+/// All fields will be put into the default SD-ELEMENT, except those been
+/// listed in the field groups, which will have their own SD-ELEMENT.
+#[derive(Clone, Debug, Default)]
+pub struct Rfc5424SdConfig {
+    default_id: Option<String>,
+    groups: Vec<(String, Vec<String>)>,
+}
+
+impl Rfc5424SdConfig {
+    pub fn set_default_id(&mut self, id: String) {
+        self.default_id = Some(id);
+    }
+
+    pub fn add_group(&mut self, id: String, fields: Vec<String>) {
+        self.groups.push((id, fields));
+    }
+
+    fn full_id(id: &str, enterprise_id: i32) -> String {
+        if id.contains('@') {
+            id.to_string()
+        } else {
+            format!("{id}@{enterprise_id}")
+        }
+    }
 }
 
 pub(crate) struct FormatterRfc5424 {
-    enterprise_id: i32,
     message_id: Option<String>,
+    sd_ids: Vec<String>,
+    sd_fields: HashMap<String, usize>,
     append_report_ts: bool,
 }
 
 impl FormatterRfc5424 {
-    pub(crate) fn new(enterprise_id: i32, message_id: Option<String>) -> Self {
+    pub(crate) fn new(
+        enterprise_id: i32,
+        message_id: Option<String>,
+        sd_config: Rfc5424SdConfig,
+    ) -> Self {
+        let default_id = sd_config.default_id.as_deref().unwrap_or(DEFAULT_SD_ID);
+        let mut sd_ids = vec![Rfc5424SdConfig::full_id(default_id, enterprise_id)];
+        let mut sd_fields = HashMap::new();
+        for (id, fields) in sd_config.groups {
+            let index = sd_ids.len();
+            sd_ids.push(Rfc5424SdConfig::full_id(&id, enterprise_id));
+            for field in fields {
+                sd_fields.entry(field).or_insert(index);
+            }
+        }
         FormatterRfc5424 {
-            enterprise_id,
             message_id,
+            sd_ids,
+            sd_fields,
             append_report_ts: false,
         }
     }
+
+    fn format_content_as_sd(
+        &self,
+        w: &mut Vec<u8>,
+        record: &Record,
+        logger_values: &OwnedKVList,
+        report_ts: Option<i64>,
+    ) -> Result<(), slog::Error> {
+        if self.sd_fields.is_empty() {
+            w.push(b'[');
+            w.extend_from_slice(self.sd_ids[0].as_bytes());
+
+            let mut kv_formatter = FormatterKv(w);
+            logger_values.serialize(record, &mut kv_formatter)?;
+            record.kv().serialize(record, &mut kv_formatter)?;
+            if let Some(ts) = report_ts {
+                kv_formatter.append_report_ts(ts);
+            }
+            w.push(b']');
+        } else {
+            TL_SD_BUF.with_borrow_mut(|bufs| {
+                bufs.resize_with(self.sd_ids.len(), Vec::new);
+                bufs.iter_mut().for_each(|b| b.clear());
+
+                let mut sd_formatter = FormatterSd {
+                    fields: &self.sd_fields,
+                    bufs: bufs.as_mut_slice(),
+                };
+                logger_values.serialize(record, &mut sd_formatter)?;
+                record.kv().serialize(record, &mut sd_formatter)?;
+                if let Some(ts) = report_ts {
+                    FormatterKv(&mut bufs[0]).append_report_ts(ts);
+                }
+
+                for (i, (id, buf)) in self.sd_ids.iter().zip(bufs.iter()).enumerate() {
+                    // the default SD-ELEMENT is always present
+                    if i > 0 && buf.is_empty() {
+                        continue;
+                    }
+                    w.push(b'[');
+                    w.extend_from_slice(id.as_bytes());
+                    w.extend_from_slice(buf);
+                    w.push(b']');
+                }
+                Ok::<(), slog::Error>(())
+            })?;
+        }
+
+        // write msg
+        w.push(b' ');
+        let msg = record.msg().to_string();
+        let mut f = FormatterKv(w);
+        f.push_str_value(&msg);
+
+        Ok(())
+    }
 }
 
 impl SyslogFormatter for FormatterRfc5424 {
@@ -68,7 +172,7 @@ impl SyslogFormatter for FormatterRfc5424 {
         } else {
             None
         };
-        format_content_as_sd(w, self.enterprise_id, record, logger_values, report_ts)
+        self.format_content_as_sd(w, record, logger_values, report_ts)
     }
 }
 
@@ -117,35 +221,6 @@ pub(super) fn format_rfc5424_header(
     Ok(())
 }
 
-fn format_content_as_sd(
-    w: &mut Vec<u8>,
-    enterprise_id: i32,
-    record: &Record,
-    logger_values: &OwnedKVList,
-    report_ts: Option<i64>,
-) -> Result<(), slog::Error> {
-    w.extend_from_slice(b"[g3proxy@");
-    let mut buffer = itoa::Buffer::new();
-    let eid_s = buffer.format(enterprise_id);
-    w.extend_from_slice(eid_s.as_bytes());
-
-    let mut kv_formatter = FormatterKv(w);
-    logger_values.serialize(record, &mut kv_formatter)?;
-    record.kv().serialize(record, &mut kv_formatter)?;
-    if let Some(ts) = report_ts {
-        kv_formatter.append_report_ts(ts);
-    }
-    w.push(b']');
-
-    // write msg
-    w.push(b' ');
-    let msg = record.msg().to_string();
-    let mut f = FormatterKv(w);
-    f.push_str_value(&msg);
-
-    Ok(())
-}
-
 struct FormatterKv<'a>(&'a mut Vec<u8>);
 
 impl<'a> FormatterKv<'a> {
@@ -305,6 +380,55 @@ impl<'a> Serializer for FormatterKv<'a> {
     impl_serde_with_tls! {}
 }
 
+/// Dispatch each field to the buffer of the SD-ELEMENT it belongs to
+struct FormatterSd<'a> {
+    fields: &'a HashMap<String, usize>,
+    bufs: &'a mut [Vec<u8>],
+}
+
+impl<'a> FormatterSd<'a> {
+    fn kv(&mut self, key: &str) -> FormatterKv<'_> {
+        let index = self.fields.get(key).copied().unwrap_or(0);
+        FormatterKv(&mut self.bufs[index])
+    }
+}
+
+macro_rules! impl_sd_dispatch {
+    ($($f:ident: $t:ty),+ $(,)?) => {
+        $(
+            fn $f(&mut self, key: slog::Key, val: $t) -> slog::Result {
+                self.kv(key).$f(key, val)
+            }
+        )+
+    };
+}
+
+impl<'a> Serializer for FormatterSd<'a> {
+    impl_sd_dispatch! {
+        emit_usize: usize,
+        emit_isize: isize,
+        emit_u8: u8,
+        emit_i8: i8,
+        emit_u16: u16,
+        emit_i16: i16,
+        emit_u32: u32,
+        emit_i32: i32,
+        emit_f32: f32,
+        emit_u64: u64,
+        emit_i64: i64,
+        emit_f64: f64,
+        emit_bool: bool,
+        emit_char: char,
+        emit_str: &str,
+        emit_arguments: &Arguments,
+        emit_serde: &dyn slog::SerdeValue,
+    }
+
+    fn emit_none(&mut self, _key: slog::Key) -> slog::Result {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -403,4 +527,25 @@ mod tests {
             .unwrap();
         assert_eq!(std::str::from_utf8(&vec).unwrap(), " a-key=\"a-value\"");
     }
+
+    #[test]
+    fn dispatch_sd_group() {
+        let mut fields = HashMap::new();
+        fields.insert("client_ip".to_string(), 1);
+        let mut bufs = vec![Vec::new(), Vec::new()];
+        let mut sd_formatter = FormatterSd {
+            fields: &fields,
+            bufs: bufs.as_mut_slice(),
+        };
+        sd_formatter.emit_str("client_ip", "1.2.3.4").unwrap();
+        sd_formatter.emit_u16("server_port", 80).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&bufs[0]).unwrap(),
+            " server_port=\"80\""
+        );
+        assert_eq!(
+            std::str::from_utf8(&bufs[1]).unwrap(),
+            " client_ip=\"1.2.3.4\""
+        );
+    }
 }
@@ -17,21 +17,19 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use rustls::{Certificate, PrivateKey};
-
 use g3_io_ext::EffectiveCacheHandle;
 
-use super::CacheQueryKey;
+use super::{CacheQueryKey, FakeCertData};
 
 #[derive(Clone)]
 pub struct CertAgentHandle {
-    inner: EffectiveCacheHandle<CacheQueryKey, (Vec<Certificate>, PrivateKey)>,
+    inner: EffectiveCacheHandle<CacheQueryKey, FakeCertData>,
     request_timeout: Duration,
 }
 
 impl CertAgentHandle {
     pub(crate) fn new(
-        inner: EffectiveCacheHandle<CacheQueryKey, (Vec<Certificate>, PrivateKey)>,
+        inner: EffectiveCacheHandle<CacheQueryKey, FakeCertData>,
         request_timeout: Duration,
     ) -> Self {
         CertAgentHandle {
@@ -40,7 +38,7 @@ impl CertAgentHandle {
         }
     }
 
-    pub async fn fetch(&self, host: String) -> Option<(Vec<Certificate>, PrivateKey)> {
+    pub async fn fetch(&self, host: String) -> Option<FakeCertData> {
         let query_key = CacheQueryKey { host };
 
         self.inner
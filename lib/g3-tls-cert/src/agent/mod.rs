@@ -14,6 +14,8 @@
  * limitations under the License.
  */
 
+use rustls::{Certificate, PrivateKey};
+
 mod query;
 use query::QueryRuntime;
 
@@ -27,3 +29,11 @@ pub use handle::CertAgentHandle;
 pub(crate) struct CacheQueryKey {
     pub(crate) host: String,
 }
+
+#[derive(Clone)]
+pub struct FakeCertData {
+    pub certs: Vec<Certificate>,
+    pub key: PrivateKey,
+    /// the DER encoded OCSP response to staple, may be empty
+    pub ocsp: Vec<u8>,
+}
@@ -24,17 +24,17 @@ use std::time::Duration;
 
 use anyhow::anyhow;
 use log::warn;
-use rustls::{Certificate, PrivateKey};
+use rustls::PrivateKey;
 use tokio::io::ReadBuf;
 use tokio::net::UdpSocket;
 
 use g3_io_ext::{EffectiveCacheData, EffectiveQueryHandle};
 
-use super::{CacheQueryKey, CertAgentConfig};
+use super::{CacheQueryKey, CertAgentConfig, FakeCertData};
 
 pub(super) struct QueryRuntime {
     socket: UdpSocket,
-    query_handle: EffectiveQueryHandle<CacheQueryKey, (Vec<Certificate>, PrivateKey)>,
+    query_handle: EffectiveQueryHandle<CacheQueryKey, FakeCertData>,
     read_buffer: Box<[u8]>,
     write_queue: VecDeque<(Arc<CacheQueryKey>, Vec<u8>)>,
     protective_ttl: u32,
@@ -47,7 +47,7 @@ impl QueryRuntime {
     pub(super) fn new(
         config: &CertAgentConfig,
         socket: UdpSocket,
-        query_handle: EffectiveQueryHandle<CacheQueryKey, (Vec<Certificate>, PrivateKey)>,
+        query_handle: EffectiveQueryHandle<CacheQueryKey, FakeCertData>,
     ) -> Self {
         QueryRuntime {
             socket,
@@ -89,12 +89,13 @@ impl QueryRuntime {
 
     fn parse_rsp(
         map: Vec<(rmpv::ValueRef, rmpv::ValueRef)>,
-    ) -> anyhow::Result<(Arc<CacheQueryKey>, Vec<Certificate>, PrivateKey, u32)> {
+    ) -> anyhow::Result<(Arc<CacheQueryKey>, FakeCertData, u32)> {
         use anyhow::Context;
 
         let mut host = String::new();
         let mut cert = Vec::new();
         let mut pkey = PrivateKey(Vec::new());
+        let mut ocsp = Vec::new();
         let mut ttl: u32 = 0;
 
         for (k, v) in map {
@@ -112,6 +113,13 @@ impl QueryRuntime {
                     pkey = g3_msgpack::value::as_private_key(&v)
                         .context(format!("invalid tls private key value for key {key}"))?;
                 }
+                "ocsp" => {
+                    if let rmpv::ValueRef::Binary(b) = v {
+                        ocsp = b.to_vec();
+                    } else {
+                        return Err(anyhow!("invalid binary value for key {key}"));
+                    }
+                }
                 "ttl" => {
                     ttl = g3_msgpack::value::as_u32(&v)
                         .context(format!("invalid u32 value for key {key}"))?;
@@ -130,7 +138,12 @@ impl QueryRuntime {
             return Err(anyhow!("no required pkey key found"));
         }
 
-        Ok((Arc::new(CacheQueryKey { host }), cert, pkey, ttl))
+        let data = FakeCertData {
+            certs: cert,
+            key: pkey,
+            ocsp,
+        };
+        Ok((Arc::new(CacheQueryKey { host }), data, ttl))
     }
 
     fn handle_rsp(&mut self, len: usize) {
@@ -139,14 +152,14 @@ impl QueryRuntime {
         let mut buf = &self.read_buffer[..len];
         if let Ok(ValueRef::Map(map)) = rmpv::decode::read_value_ref(&mut buf) {
             match Self::parse_rsp(map) {
-                Ok((req_key, cert, key, mut ttl)) => {
+                Ok((req_key, data, mut ttl)) => {
                     if ttl == 0 {
                         ttl = self.protective_ttl;
                     } else if ttl > self.maximum_ttl {
                         ttl = self.maximum_ttl;
                     }
 
-                    let result = EffectiveCacheData::new((cert, key), ttl, self.vanish_wait);
+                    let result = EffectiveCacheData::new(data, ttl, self.vanish_wait);
                     self.query_handle.send_rsp_data(req_key, result, false);
                 }
                 Err(e) => {
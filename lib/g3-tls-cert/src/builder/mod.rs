@@ -38,3 +38,8 @@ pub use root::RootCertBuilder;
 
 mod intermediate;
 pub use intermediate::IntermediateCertBuilder;
+
+#[cfg(not(any(feature = "aws-lc", feature = "boringssl")))]
+mod ocsp;
+#[cfg(not(any(feature = "aws-lc", feature = "boringssl")))]
+pub use ocsp::OcspResponseBuilder;
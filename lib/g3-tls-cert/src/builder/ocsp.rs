@@ -0,0 +1,79 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use anyhow::{anyhow, Context};
+use chrono::{Days, Utc};
+use openssl::hash::MessageDigest;
+use openssl::ocsp::{OcspBasicResponse, OcspCertId, OcspResponse, OcspResponseStatus};
+use openssl::pkey::{PKey, Private};
+use openssl::x509::X509Ref;
+
+use super::asn1_time_from_chrono;
+use crate::ext::OcspBasicResponseExt;
+
+pub struct OcspResponseBuilder {
+    validity_days: u64,
+}
+
+impl Default for OcspResponseBuilder {
+    fn default() -> Self {
+        OcspResponseBuilder { validity_days: 7 }
+    }
+}
+
+impl OcspResponseBuilder {
+    pub fn set_validity_days(&mut self, days: u64) {
+        self.validity_days = days;
+    }
+
+    /// Build a DER encoded OCSP response with status *good* for the certificate,
+    /// which is signed directly by the issuer CA
+    pub fn build_good(
+        &self,
+        cert: &X509Ref,
+        issuer_cert: &X509Ref,
+        issuer_key: &PKey<Private>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let cert_id = OcspCertId::from_cert(MessageDigest::sha1(), cert, issuer_cert)
+            .map_err(|e| anyhow!("failed to create ocsp cert id: {e}"))?;
+
+        let time_now = Utc::now();
+        let this_update = time_now
+            .checked_sub_days(Days::new(1))
+            .ok_or(anyhow!("unable to get this update date"))?;
+        let next_update = time_now
+            .checked_add_days(Days::new(self.validity_days))
+            .ok_or(anyhow!("unable to get next update date"))?;
+        let this_update =
+            asn1_time_from_chrono(&this_update).context("failed to set thisUpdate time")?;
+        let next_update =
+            asn1_time_from_chrono(&next_update).context("failed to set nextUpdate time")?;
+
+        let mut basic = OcspBasicResponse::new_empty()
+            .map_err(|e| anyhow!("failed to create ocsp basic response: {e}"))?;
+        basic
+            .add_good_status(&cert_id, &this_update, &next_update)
+            .map_err(|e| anyhow!("failed to add cert status: {e}"))?;
+        basic
+            .sign_by_key_hash(issuer_cert, issuer_key, MessageDigest::sha256())
+            .map_err(|e| anyhow!("failed to sign ocsp basic response: {e}"))?;
+
+        let response = OcspResponse::create(OcspResponseStatus::SUCCESSFUL, Some(&basic))
+            .map_err(|e| anyhow!("failed to create ocsp response: {e}"))?;
+        response
+            .to_der()
+            .map_err(|e| anyhow!("failed to encode ocsp response: {e}"))
+    }
+}
@@ -15,7 +15,11 @@
  */
 
 use libc::{c_int, c_uchar, c_uint};
+#[cfg(not(any(feature = "aws-lc", feature = "boringssl")))]
+use libc::{c_ulong, c_void};
 use openssl_sys::RSA;
+#[cfg(not(any(feature = "aws-lc", feature = "boringssl")))]
+use openssl_sys::{stack_st_X509, ASN1_TIME, EVP_MD, EVP_PKEY, OCSP_BASICRESP, OCSP_CERTID, X509};
 
 extern "C" {
 
@@ -28,3 +32,28 @@ extern "C" {
         rsa: *mut RSA,
     ) -> c_int;
 }
+
+#[cfg(not(any(feature = "aws-lc", feature = "boringssl")))]
+extern "C" {
+
+    pub fn OCSP_BASICRESP_new() -> *mut OCSP_BASICRESP;
+
+    pub fn OCSP_basic_add1_status(
+        rsp: *mut OCSP_BASICRESP,
+        cid: *mut OCSP_CERTID,
+        status: c_int,
+        reason: c_int,
+        revtime: *mut ASN1_TIME,
+        thisupd: *mut ASN1_TIME,
+        nextupd: *mut ASN1_TIME,
+    ) -> *mut c_void;
+
+    pub fn OCSP_basic_sign(
+        brsp: *mut OCSP_BASICRESP,
+        signer: *mut X509,
+        key: *mut EVP_PKEY,
+        dgst: *const EVP_MD,
+        certs: *mut stack_st_X509,
+        flags: c_ulong,
+    ) -> c_int;
+}
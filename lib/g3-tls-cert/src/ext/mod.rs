@@ -24,3 +24,8 @@ pub use rsa::RsaExt;
 
 mod pkey;
 pub use pkey::PublicKeyExt;
+
+#[cfg(not(any(feature = "aws-lc", feature = "boringssl")))]
+mod ocsp;
+#[cfg(not(any(feature = "aws-lc", feature = "boringssl")))]
+pub use ocsp::OcspBasicResponseExt;
@@ -0,0 +1,110 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::ptr;
+
+use libc::{c_int, c_ulong};
+use openssl::asn1::Asn1TimeRef;
+use openssl::error::ErrorStack;
+use openssl::foreign_types::{ForeignType, ForeignTypeRef};
+use openssl::hash::MessageDigest;
+use openssl::ocsp::{OcspBasicResponse, OcspCertIdRef};
+use openssl::pkey::{HasPrivate, PKeyRef};
+use openssl::x509::X509Ref;
+
+use super::ffi;
+
+const V_OCSP_CERTSTATUS_GOOD: c_int = 0;
+const OCSP_NOCERTS: c_ulong = 0x1;
+const OCSP_RESPID_KEY: c_ulong = 0x400;
+
+pub trait OcspBasicResponseExt: Sized {
+    fn new_empty() -> Result<Self, ErrorStack>;
+
+    fn add_good_status(
+        &mut self,
+        cid: &OcspCertIdRef,
+        this_update: &Asn1TimeRef,
+        next_update: &Asn1TimeRef,
+    ) -> Result<(), ErrorStack>;
+
+    /// Sign the response, the responder id will be set to the key hash of the signer
+    fn sign_by_key_hash<T: HasPrivate>(
+        &mut self,
+        signer: &X509Ref,
+        key: &PKeyRef<T>,
+        digest: MessageDigest,
+    ) -> Result<(), ErrorStack>;
+}
+
+impl OcspBasicResponseExt for OcspBasicResponse {
+    fn new_empty() -> Result<Self, ErrorStack> {
+        unsafe {
+            let p = ffi::OCSP_BASICRESP_new();
+            if p.is_null() {
+                Err(ErrorStack::get())
+            } else {
+                Ok(OcspBasicResponse::from_ptr(p))
+            }
+        }
+    }
+
+    fn add_good_status(
+        &mut self,
+        cid: &OcspCertIdRef,
+        this_update: &Asn1TimeRef,
+        next_update: &Asn1TimeRef,
+    ) -> Result<(), ErrorStack> {
+        unsafe {
+            let r = ffi::OCSP_basic_add1_status(
+                self.as_ptr(),
+                cid.as_ptr(),
+                V_OCSP_CERTSTATUS_GOOD,
+                0,
+                ptr::null_mut(),
+                this_update.as_ptr(),
+                next_update.as_ptr(),
+            );
+            if r.is_null() {
+                Err(ErrorStack::get())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    fn sign_by_key_hash<T: HasPrivate>(
+        &mut self,
+        signer: &X509Ref,
+        key: &PKeyRef<T>,
+        digest: MessageDigest,
+    ) -> Result<(), ErrorStack> {
+        unsafe {
+            let r = ffi::OCSP_basic_sign(
+                self.as_ptr(),
+                signer.as_ptr(),
+                key.as_ptr(),
+                digest.as_ptr(),
+                ptr::null_mut(),
+                OCSP_NOCERTS | OCSP_RESPID_KEY,
+            );
+            if r != 1 {
+                Err(ErrorStack::get())
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
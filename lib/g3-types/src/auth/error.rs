@@ -30,6 +30,8 @@ pub enum UserAuthError {
     ExpiredUser,
     #[error("user has been blocked")]
     BlockedUser(Duration),
+    #[error("too many alive connections")]
+    ConnectionLimited,
 }
 
 impl UserAuthError {
@@ -60,4 +62,6 @@ pub enum AuthParseError {
     InvalidPassword,
     #[error("no delimiter found")]
     NoDelimiterFound,
+    #[error("invalid token")]
+    InvalidToken,
 }
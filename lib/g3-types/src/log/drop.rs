@@ -19,6 +19,7 @@ pub enum LogDropType {
     ChannelClosed,
     ChannelOverflow,
     PeerUnreachable,
+    RateLimited,
 }
 
 impl LogDropType {
@@ -28,6 +29,7 @@ impl LogDropType {
             LogDropType::ChannelClosed => "ChannelClosed",
             LogDropType::ChannelOverflow => "ChannelOverflow",
             LogDropType::PeerUnreachable => "PeerUnreachable",
+            LogDropType::RateLimited => "RateLimited",
         }
     }
 }
@@ -35,6 +35,7 @@ pub struct LogDropSnapshot {
     pub channel_closed: u64,
     pub channel_overflow: u64,
     pub peer_unreachable: u64,
+    pub rate_limited: u64,
 }
 
 #[derive(Default)]
@@ -87,6 +88,7 @@ pub struct LogDropStats {
     channel_closed: AtomicU64,
     channel_overflow: AtomicU64,
     peer_unreachable: AtomicU64,
+    rate_limited: AtomicU64,
 }
 
 impl LogDropStats {
@@ -96,6 +98,7 @@ impl LogDropStats {
             channel_closed: self.channel_closed.load(Ordering::Relaxed),
             channel_overflow: self.channel_overflow.load(Ordering::Relaxed),
             peer_unreachable: self.peer_unreachable.load(Ordering::Relaxed),
+            rate_limited: self.rate_limited.load(Ordering::Relaxed),
         }
     }
 
@@ -114,6 +117,10 @@ impl LogDropStats {
     pub fn add_peer_unreachable(&self) {
         self.peer_unreachable.fetch_add(1, Ordering::Relaxed);
     }
+
+    pub fn add_rate_limited(&self) {
+        self.rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
 }
 
 #[cfg(test)]
@@ -127,13 +134,15 @@ mod tests {
         stats.add_channel_closed();
         stats.add_channel_overflow();
         stats.add_peer_unreachable();
+        stats.add_rate_limited();
         assert_eq!(
             stats.snapshot(),
             LogDropSnapshot {
                 format_failed: 1,
                 channel_closed: 1,
                 channel_overflow: 1,
-                peer_unreachable: 1
+                peer_unreachable: 1,
+                rate_limited: 1,
             }
         )
     }
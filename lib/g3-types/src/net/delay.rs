@@ -0,0 +1,53 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+/// Artificial delay added to each direction of a relayed stream
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StreamDelayConfig {
+    latency: Duration,
+    jitter: Duration,
+}
+
+impl StreamDelayConfig {
+    pub fn new(latency: Duration, jitter: Duration) -> Self {
+        StreamDelayConfig { latency, jitter }
+    }
+
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        !self.latency.is_zero() || !self.jitter.is_zero()
+    }
+
+    #[inline]
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    #[inline]
+    pub fn jitter(&self) -> Duration {
+        self.jitter
+    }
+
+    pub fn set_latency(&mut self, latency: Duration) {
+        self.latency = latency;
+    }
+
+    pub fn set_jitter(&mut self, jitter: Duration) {
+        self.jitter = jitter;
+    }
+}
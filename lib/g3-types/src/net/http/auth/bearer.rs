@@ -0,0 +1,58 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+use crate::auth::AuthParseError;
+
+pub struct HttpBearerAuth {
+    token: String,
+}
+
+impl HttpBearerAuth {
+    #[inline]
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl FromStr for HttpBearerAuth {
+    type Err = AuthParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let token = s.trim(); // allow more space than spec
+        if token.is_empty() {
+            return Err(AuthParseError::InvalidToken);
+        }
+
+        // token68, see RFC 7235 section 2.1
+        let mut padding = false;
+        for c in token.bytes() {
+            if c == b'=' {
+                padding = true;
+            } else if padding
+                || !(c.is_ascii_alphanumeric()
+                    || matches!(c, b'-' | b'.' | b'_' | b'~' | b'+' | b'/'))
+            {
+                return Err(AuthParseError::InvalidToken);
+            }
+        }
+
+        Ok(HttpBearerAuth {
+            token: token.to_string(),
+        })
+    }
+}
@@ -24,9 +24,17 @@ use crate::net::HttpHeaderValue;
 mod basic;
 pub use basic::HttpBasicAuth;
 
+mod bearer;
+pub use bearer::HttpBearerAuth;
+
+mod negotiate;
+pub use negotiate::HttpNegotiateAuth;
+
 pub enum HttpAuth {
     None,
     Basic(HttpBasicAuth),
+    Bearer(HttpBearerAuth),
+    Negotiate(HttpNegotiateAuth),
 }
 
 impl HttpAuth {
@@ -37,6 +45,14 @@ impl HttpAuth {
                     let basic = HttpBasicAuth::from_str(&value[i + 1..])?;
                     Ok(HttpAuth::Basic(basic))
                 }
+                "bearer" => {
+                    let bearer = HttpBearerAuth::from_str(&value[i + 1..])?;
+                    Ok(HttpAuth::Bearer(bearer))
+                }
+                "negotiate" => {
+                    let negotiate = HttpNegotiateAuth::from_str(&value[i + 1..])?;
+                    Ok(HttpAuth::Negotiate(negotiate))
+                }
                 _ => Ok(HttpAuth::None),
             },
             None => Err(AuthParseError::UnsupportedAuthType),
@@ -98,10 +114,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_bearer() {
+        let value = "Bearer eyJhbGciOiJIUzI1NiJ9.e30.ZRrHA1JJJW8opsbCGfG_HACGpVUMN_a9IV7pAx_Zmeo";
+        let info = HttpAuth::from_authorization(value).unwrap();
+        let HttpAuth::Bearer(bearer) = info else {
+            panic!("not bearer auth");
+        };
+        assert_eq!(
+            bearer.token(),
+            "eyJhbGciOiJIUzI1NiJ9.e30.ZRrHA1JJJW8opsbCGfG_HACGpVUMN_a9IV7pAx_Zmeo"
+        );
+
+        let value = "Bearer a=b";
+        assert!(HttpAuth::from_authorization(value).is_err());
+    }
+
     #[test]
     fn parse_scheme_only() {
         let value = "Basic ";
         let result = HttpAuth::from_authorization(value);
         assert!(result.is_err());
+
+        let value = "Bearer ";
+        let result = HttpAuth::from_authorization(value);
+        assert!(result.is_err());
+
+        let value = "Negotiate ";
+        let result = HttpAuth::from_authorization(value);
+        assert!(result.is_err());
     }
 }
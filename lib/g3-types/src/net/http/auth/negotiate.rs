@@ -0,0 +1,58 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+use base64::prelude::*;
+
+use crate::auth::AuthParseError;
+
+pub struct HttpNegotiateAuth {
+    token: Vec<u8>,
+    encoded_value: String,
+}
+
+impl HttpNegotiateAuth {
+    #[inline]
+    pub fn token(&self) -> &[u8] {
+        &self.token
+    }
+
+    #[inline]
+    pub fn encoded_value(&self) -> &str {
+        &self.encoded_value
+    }
+}
+
+impl FromStr for HttpNegotiateAuth {
+    type Err = AuthParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let encoded_value = s.trim(); // allow more space than spec
+
+        let token = BASE64_STANDARD
+            .decode(encoded_value)
+            .map_err(|_| AuthParseError::InvalidBase64Encoding)?;
+        if token.is_empty() {
+            return Err(AuthParseError::InvalidToken);
+        }
+
+        Ok(HttpNegotiateAuth {
+            token,
+            encoded_value: encoded_value.to_string(),
+        })
+    }
+}
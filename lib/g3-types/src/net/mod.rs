@@ -15,11 +15,14 @@
  */
 
 mod buf;
+mod delay;
 mod dns;
 mod egress;
 mod error;
 mod haproxy;
 mod host;
+mod nat64;
+mod pool;
 mod port;
 mod proxy;
 mod rate_limit;
@@ -41,16 +44,19 @@ mod rustls;
 mod openssl;
 
 pub use buf::SocketBufferConfig;
+pub use delay::StreamDelayConfig;
 pub use dns::*;
 pub use egress::{EgressArea, EgressInfo};
 pub use error::ConnectError;
 pub use haproxy::{ProxyProtocolEncodeError, ProxyProtocolEncoder, ProxyProtocolVersion};
 pub use host::Host;
+pub use nat64::Nat64Prefix;
+pub use pool::ConnectionPoolConfig;
 pub use port::{PortRange, Ports};
 pub use proxy::{Proxy, ProxyParseError, ProxyRequestType, Socks4Proxy, Socks5Proxy};
 pub use rate_limit::{
-    TcpSockSpeedLimitConfig, UdpSockSpeedLimitConfig, RATE_LIMIT_SHIFT_MILLIS_DEFAULT,
-    RATE_LIMIT_SHIFT_MILLIS_MAX,
+    GlobalStreamSpeedLimitConfig, TcpSockSpeedLimitConfig, UdpPacingConfig,
+    UdpSockSpeedLimitConfig, RATE_LIMIT_SHIFT_MILLIS_DEFAULT, RATE_LIMIT_SHIFT_MILLIS_MAX,
 };
 pub use socks::SocksAuth;
 pub use tcp::*;
@@ -0,0 +1,171 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+const WELL_KNOWN_PREFIX: Ipv6Addr = Ipv6Addr::new(0x64, 0xff9b, 0, 0, 0, 0, 0, 0);
+
+/// NAT64 prefix used to synthesize IPv4-embedded IPv6 addresses, see RFC 6052
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Nat64Prefix {
+    prefix: [u8; 16],
+    len: u8,
+}
+
+impl Default for Nat64Prefix {
+    fn default() -> Self {
+        Nat64Prefix {
+            prefix: WELL_KNOWN_PREFIX.octets(),
+            len: 96,
+        }
+    }
+}
+
+impl Nat64Prefix {
+    pub fn new(prefix: Ipv6Addr, len: u8) -> anyhow::Result<Self> {
+        match len {
+            32 | 40 | 48 | 56 | 64 | 96 => {}
+            _ => return Err(anyhow!("unsupported nat64 prefix length {len}")),
+        }
+        let octets = prefix.octets();
+        let pos = (len / 8) as usize;
+        if octets[pos..].iter().any(|b| *b != 0) {
+            return Err(anyhow!("host bits are set in nat64 prefix {prefix}/{len}"));
+        }
+        Ok(Nat64Prefix {
+            prefix: octets,
+            len,
+        })
+    }
+
+    #[inline]
+    pub fn prefix(&self) -> Ipv6Addr {
+        Ipv6Addr::from(self.prefix)
+    }
+
+    #[inline]
+    pub fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    pub fn synthesize(&self, ip4: Ipv4Addr) -> Ipv6Addr {
+        let mut octets = self.prefix;
+        let mut pos = (self.len / 8) as usize;
+        for b in ip4.octets() {
+            if pos == 8 {
+                // skip the reserved u octet
+                pos += 1;
+            }
+            octets[pos] = b;
+            pos += 1;
+        }
+        Ipv6Addr::from(octets)
+    }
+
+    /// Check whether the IPv4 addresses in the resolved results should be synthesized.
+    ///
+    /// Like DNS64 (RFC 6147, Section 5.1.6), synthesis is only done if no IPv6 address found,
+    /// or the IPv4 addresses will be removed, and false will be returned.
+    pub fn prepare_resolved(ips: &mut Vec<IpAddr>) -> bool {
+        if ips.iter().any(|ip| ip.is_ipv6()) {
+            ips.retain(|ip| ip.is_ipv6());
+            false
+        } else {
+            true
+        }
+    }
+}
+
+impl FromStr for Nat64Prefix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((prefix, len)) = s.split_once('/') else {
+            return Err(anyhow!("no prefix length found"));
+        };
+        let prefix =
+            Ipv6Addr::from_str(prefix).map_err(|e| anyhow!("invalid ipv6 prefix {prefix}: {e}"))?;
+        let len = u8::from_str(len).map_err(|e| anyhow!("invalid prefix length {len}: {e}"))?;
+        Nat64Prefix::new(prefix, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc6052_examples() {
+        let ip4 = Ipv4Addr::new(192, 0, 2, 33);
+
+        let cases = [
+            ("2001:db8::/32", "2001:db8:c000:221::"),
+            ("2001:db8:100::/40", "2001:db8:1c0:2:21::"),
+            ("2001:db8:122::/48", "2001:db8:122:c000:2:2100::"),
+            ("2001:db8:122:300::/56", "2001:db8:122:3c0:0:221::"),
+            ("2001:db8:122:344::/64", "2001:db8:122:344:c0:2:2100:0"),
+            ("2001:db8:122:344::/96", "2001:db8:122:344::192.0.2.33"),
+        ];
+        for (prefix, expected) in cases {
+            let prefix = Nat64Prefix::from_str(prefix).unwrap();
+            assert_eq!(
+                prefix.synthesize(ip4),
+                Ipv6Addr::from_str(expected).unwrap()
+            );
+        }
+
+        let prefix = Nat64Prefix::default();
+        assert_eq!(
+            prefix.synthesize(ip4),
+            Ipv6Addr::from_str("64:ff9b::192.0.2.33").unwrap()
+        );
+    }
+
+    #[test]
+    fn prepare_resolved() {
+        let ip4 = IpAddr::from_str("192.0.2.33").unwrap();
+        let ip6 = IpAddr::from_str("2001:db8::1").unwrap();
+
+        // only A records
+        let mut ips = vec![ip4];
+        assert!(Nat64Prefix::prepare_resolved(&mut ips));
+        assert_eq!(ips, vec![ip4]);
+
+        // AAAA records found
+        let mut ips = vec![ip4, ip6];
+        assert!(!Nat64Prefix::prepare_resolved(&mut ips));
+        assert_eq!(ips, vec![ip6]);
+
+        let mut ips = vec![ip6];
+        assert!(!Nat64Prefix::prepare_resolved(&mut ips));
+        assert_eq!(ips, vec![ip6]);
+
+        // no records
+        let mut ips = Vec::new();
+        assert!(Nat64Prefix::prepare_resolved(&mut ips));
+        assert!(ips.is_empty());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Nat64Prefix::from_str("2001:db8::/33").is_err());
+        assert!(Nat64Prefix::from_str("2001:db8::1/96").is_err());
+        assert!(Nat64Prefix::from_str("2001:db8::").is_err());
+    }
+}
@@ -146,6 +146,11 @@ impl OpensslInterceptionClientConfigBuilder {
         self.session_cache.set_each_capacity(cap);
     }
 
+    #[inline]
+    pub fn set_session_cache_ttl(&mut self, ttl: Duration) {
+        self.session_cache.set_ttl(ttl);
+    }
+
     #[inline]
     pub fn set_supported_groups(&mut self, groups: String) {
         self.supported_groups = groups;
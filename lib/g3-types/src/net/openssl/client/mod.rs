@@ -20,7 +20,8 @@ use anyhow::anyhow;
 #[cfg(any(feature = "aws-lc", feature = "boringssl", feature = "tongsuo"))]
 use openssl::ssl::CertCompressionAlgorithm;
 use openssl::ssl::{
-    Ssl, SslConnector, SslConnectorBuilder, SslContext, SslMethod, SslVerifyMode, SslVersion,
+    Ssl, SslConnector, SslConnectorBuilder, SslContext, SslContextRef, SslMethod, SslVerifyMode,
+    SslVersion,
 };
 #[cfg(not(any(feature = "aws-lc", feature = "boringssl")))]
 use openssl::ssl::{SslCtValidationMode, StatusType};
@@ -77,6 +78,14 @@ impl OpensslClientConfig {
         }
         Ok(ssl)
     }
+
+    /// Check if the two configs are cloned from the same one, so the connections
+    /// created by one of them can also be used by the other
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        let a: &SslContextRef = &self.ssl_context;
+        let b: &SslContextRef = &other.ssl_context;
+        std::ptr::eq(a, b) && self.disable_sni == other.disable_sni
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -223,6 +232,11 @@ impl OpensslClientConfigBuilder {
         self.session_cache.set_each_capacity(cap);
     }
 
+    #[inline]
+    pub fn set_session_cache_ttl(&mut self, ttl: Duration) {
+        self.session_cache.set_ttl(ttl);
+    }
+
     #[inline]
     pub fn set_supported_groups(&mut self, groups: String) {
         self.supported_groups = groups;
@@ -17,6 +17,7 @@
 use std::collections::VecDeque;
 use std::num::NonZeroUsize;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use anyhow::anyhow;
 use lru::LruCache;
@@ -42,6 +43,7 @@ pub(in crate::net::openssl) struct OpensslSessionCacheConfig {
     method: OpensslSessionCacheMethod,
     sites_count: NonZeroUsize,
     each_capacity: NonZeroUsize,
+    ttl: Option<Duration>,
 }
 
 impl Default for OpensslSessionCacheConfig {
@@ -50,6 +52,7 @@ impl Default for OpensslSessionCacheConfig {
             method: OpensslSessionCacheMethod::Builtin,
             sites_count: SESSION_CACHE_DEFAULT_SITES_COUNT,
             each_capacity: SESSION_CACHE_DEFAULT_EACH_CAPACITY,
+            ttl: None,
         }
     }
 }
@@ -89,6 +92,14 @@ impl OpensslSessionCacheConfig {
         }
     }
 
+    pub(in crate::net::openssl) fn set_ttl(&mut self, ttl: Duration) {
+        if ttl.is_zero() {
+            self.ttl = None;
+        } else {
+            self.ttl = Some(ttl);
+        }
+    }
+
     pub(in crate::net::openssl) fn set_for_client(
         &self,
         ctx_builder: &mut SslContextBuilder,
@@ -96,13 +107,14 @@ impl OpensslSessionCacheConfig {
         match self.method {
             OpensslSessionCacheMethod::ForMany => {
                 let session_cache = OpensslClientSessionCache::new()?;
-                let caches = SessionCaches::for_many(self.sites_count, self.each_capacity.get());
+                let caches =
+                    SessionCaches::for_many(self.sites_count, self.each_capacity.get(), self.ttl);
                 session_cache.add_to_context(ctx_builder, caches);
                 Ok(Some(session_cache))
             }
             OpensslSessionCacheMethod::ForOne => {
                 let session_cache = OpensslClientSessionCache::new()?;
-                let caches = SessionCaches::for_one(self.each_capacity.get());
+                let caches = SessionCaches::for_one(self.each_capacity.get(), self.ttl);
                 session_cache.add_to_context(ctx_builder, caches);
                 Ok(Some(session_cache))
             }
@@ -118,50 +130,70 @@ impl OpensslSessionCacheConfig {
     }
 }
 
-struct ToOneCaches {
+struct ToOneCaches<T = SslSession> {
     capacity: usize,
-    queue: VecDeque<SslSession>,
+    ttl: Option<Duration>,
+    queue: VecDeque<(Instant, T)>,
 }
 
-impl ToOneCaches {
-    fn new(capacity: usize) -> Self {
+impl<T> ToOneCaches<T> {
+    fn new(capacity: usize, ttl: Option<Duration>) -> Self {
         ToOneCaches {
             capacity,
+            ttl,
             queue: VecDeque::new(),
         }
     }
 
-    fn pop(&mut self) -> Option<SslSession> {
-        self.queue.pop_front()
+    fn pop(&mut self) -> Option<T> {
+        self.pop_at(Instant::now())
+    }
+
+    fn pop_at(&mut self, now: Instant) -> Option<T> {
+        let (created, s) = self.queue.pop_front()?;
+        if let Some(ttl) = self.ttl {
+            if now.saturating_duration_since(created) >= ttl {
+                // the front one is the newest, so all others are expired too
+                self.queue.clear();
+                return None;
+            }
+        }
+        Some(s)
     }
 
-    fn push(&mut self, s: SslSession) {
-        self.queue.push_front(s);
+    fn push(&mut self, s: T) {
+        self.push_at(Instant::now(), s)
+    }
+
+    fn push_at(&mut self, now: Instant, s: T) {
+        self.queue.push_front((now, s));
         if self.queue.len() > self.capacity {
             self.queue.pop_back();
         }
     }
 }
 
-struct ToManyCaches {
-    lru: LruCache<String, ToOneCaches, ahash::RandomState>,
+struct ToManyCaches<T = SslSession> {
+    lru: LruCache<String, ToOneCaches<T>, ahash::RandomState>,
     each_capacity: usize,
+    ttl: Option<Duration>,
 }
 
-impl ToManyCaches {
-    fn new(site_capacity: NonZeroUsize, each_capacity: usize) -> Self {
+impl<T> ToManyCaches<T> {
+    fn new(site_capacity: NonZeroUsize, each_capacity: usize, ttl: Option<Duration>) -> Self {
         ToManyCaches {
             lru: LruCache::with_hasher(site_capacity, ahash::RandomState::new()),
             each_capacity,
+            ttl,
         }
     }
 
-    fn get_or_insert_mut(&mut self, key: String) -> &mut ToOneCaches {
+    fn get_or_insert_mut(&mut self, key: String) -> &mut ToOneCaches<T> {
         self.lru
-            .get_or_insert_mut(key, || ToOneCaches::new(self.each_capacity))
+            .get_or_insert_mut(key, || ToOneCaches::new(self.each_capacity, self.ttl))
     }
 
-    fn peek_mut(&mut self, key: &str) -> Option<&mut ToOneCaches> {
+    fn peek_mut(&mut self, key: &str) -> Option<&mut ToOneCaches<T>> {
         self.lru.peek_mut(key)
     }
 }
@@ -172,12 +204,16 @@ enum SessionCaches {
 }
 
 impl SessionCaches {
-    fn for_many(sites_count: NonZeroUsize, each_capacity: usize) -> Self {
-        SessionCaches::Many(Mutex::new(ToManyCaches::new(sites_count, each_capacity)))
+    fn for_many(sites_count: NonZeroUsize, each_capacity: usize, ttl: Option<Duration>) -> Self {
+        SessionCaches::Many(Mutex::new(ToManyCaches::new(
+            sites_count,
+            each_capacity,
+            ttl,
+        )))
     }
 
-    fn for_one(capacity: usize) -> Self {
-        SessionCaches::One(Mutex::new(ToOneCaches::new(capacity)))
+    fn for_one(capacity: usize, ttl: Option<Duration>) -> Self {
+        SessionCaches::One(Mutex::new(ToOneCaches::new(capacity, ttl)))
     }
 }
 
@@ -252,3 +288,102 @@ impl OpensslClientSessionCache {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_capacity() {
+        let mut caches = ToOneCaches::new(2, None);
+        let now = Instant::now();
+        caches.push_at(now, 1);
+        caches.push_at(now, 2);
+        caches.push_at(now, 3);
+        assert_eq!(caches.queue.len(), 2);
+        // the newest one comes first, and the oldest one has been dropped
+        assert_eq!(caches.pop_at(now), Some(3));
+        assert_eq!(caches.pop_at(now), Some(2));
+        assert_eq!(caches.pop_at(now), None);
+    }
+
+    #[test]
+    fn one_no_ttl() {
+        let mut caches = ToOneCaches::new(4, None);
+        let now = Instant::now();
+        caches.push_at(now, 1);
+        let later = now + Duration::from_secs(86400);
+        assert_eq!(caches.pop_at(later), Some(1));
+    }
+
+    #[test]
+    fn one_ttl_expire() {
+        let ttl = Duration::from_secs(60);
+        let mut caches = ToOneCaches::new(4, Some(ttl));
+        let now = Instant::now();
+        caches.push_at(now, 1);
+        caches.push_at(now + Duration::from_secs(10), 2);
+
+        let check = now + Duration::from_secs(65);
+        assert_eq!(caches.pop_at(check), Some(2));
+        // expired, and all older ones should be cleared
+        assert_eq!(caches.pop_at(check), None);
+        assert!(caches.queue.is_empty());
+    }
+
+    #[test]
+    fn one_ttl_clear_all() {
+        let ttl = Duration::from_secs(60);
+        let mut caches = ToOneCaches::new(4, Some(ttl));
+        let now = Instant::now();
+        caches.push_at(now, 1);
+        caches.push_at(now, 2);
+        caches.push_at(now, 3);
+
+        assert_eq!(caches.pop_at(now + ttl), None);
+        assert!(caches.queue.is_empty());
+
+        caches.push_at(now + ttl, 4);
+        assert_eq!(caches.pop_at(now + ttl), Some(4));
+    }
+
+    #[test]
+    fn many_sites_count() {
+        let mut caches = ToManyCaches::new(NonZeroUsize::new(2).unwrap(), 2, None);
+        let now = Instant::now();
+        caches
+            .get_or_insert_mut("[a]:443".to_string())
+            .push_at(now, 1);
+        caches
+            .get_or_insert_mut("[b]:443".to_string())
+            .push_at(now, 2);
+        caches
+            .get_or_insert_mut("[c]:443".to_string())
+            .push_at(now, 3);
+
+        // the least recently used site is evicted
+        assert!(caches.peek_mut("[a]:443").is_none());
+        assert_eq!(
+            caches.peek_mut("[b]:443").and_then(|c| c.pop_at(now)),
+            Some(2)
+        );
+        assert_eq!(
+            caches.peek_mut("[c]:443").and_then(|c| c.pop_at(now)),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn many_each_capacity_ttl() {
+        let ttl = Duration::from_secs(60);
+        let mut caches = ToManyCaches::new(NonZeroUsize::new(2).unwrap(), 1, Some(ttl));
+        let now = Instant::now();
+        let site = caches.get_or_insert_mut("[a]:443".to_string());
+        site.push_at(now, 1);
+        site.push_at(now, 2);
+        assert_eq!(site.queue.len(), 1);
+
+        let site = caches.peek_mut("[a]:443").unwrap();
+        assert_eq!(site.pop_at(now + ttl), None);
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+const DEFAULT_MAX_IDLE_COUNT: usize = 16;
+const DEFAULT_MAX_TOTAL_IDLE_COUNT: usize = 1024;
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_MAX_LIFETIME: Duration = Duration::from_secs(600);
+
+/// Config for pools of idle connections to the same peer
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ConnectionPoolConfig {
+    max_idle_count: usize,
+    max_total_idle_count: usize,
+    idle_timeout: Duration,
+    max_lifetime: Duration,
+}
+
+impl Default for ConnectionPoolConfig {
+    fn default() -> Self {
+        ConnectionPoolConfig {
+            max_idle_count: DEFAULT_MAX_IDLE_COUNT,
+            max_total_idle_count: DEFAULT_MAX_TOTAL_IDLE_COUNT,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            max_lifetime: DEFAULT_MAX_LIFETIME,
+        }
+    }
+}
+
+impl ConnectionPoolConfig {
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.max_idle_count == 0 {
+            return Err(anyhow!("max idle count should not be 0"));
+        }
+        if self.max_total_idle_count < self.max_idle_count {
+            return Err(anyhow!(
+                "max total idle count should not be less than max idle count"
+            ));
+        }
+        if self.idle_timeout.is_zero() {
+            return Err(anyhow!("idle timeout should not be 0"));
+        }
+        Ok(())
+    }
+
+    /// The max idle connections for each peer
+    #[inline]
+    pub fn max_idle_count(&self) -> usize {
+        self.max_idle_count
+    }
+
+    #[inline]
+    pub fn set_max_idle_count(&mut self, count: usize) {
+        self.max_idle_count = count;
+    }
+
+    /// The max idle connections for all peers
+    #[inline]
+    pub fn max_total_idle_count(&self) -> usize {
+        self.max_total_idle_count
+    }
+
+    #[inline]
+    pub fn set_max_total_idle_count(&mut self, count: usize) {
+        self.max_total_idle_count = count;
+    }
+
+    #[inline]
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    #[inline]
+    pub fn set_idle_timeout(&mut self, timeout: Duration) {
+        self.idle_timeout = timeout;
+    }
+
+    /// The max lifetime of each connection, zero means no limit
+    #[inline]
+    pub fn max_lifetime(&self) -> Duration {
+        self.max_lifetime
+    }
+
+    #[inline]
+    pub fn set_max_lifetime(&mut self, lifetime: Duration) {
+        self.max_lifetime = lifetime;
+    }
+
+    pub fn is_alive(&self, created: Duration, idle: Duration) -> bool {
+        if idle >= self.idle_timeout {
+            return false;
+        }
+        self.max_lifetime.is_zero() || created < self.max_lifetime
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check() {
+        let mut config = ConnectionPoolConfig::default();
+        assert!(config.check().is_ok());
+
+        config.set_max_total_idle_count(config.max_idle_count() - 1);
+        assert!(config.check().is_err());
+
+        config.set_max_total_idle_count(config.max_idle_count());
+        assert!(config.check().is_ok());
+
+        config.set_max_idle_count(0);
+        assert!(config.check().is_err());
+    }
+}
@@ -14,12 +14,16 @@
  * limitations under the License.
  */
 
+mod global;
+mod pacing;
 mod tcp;
 mod udp;
 
 pub const RATE_LIMIT_SHIFT_MILLIS_MAX: u8 = 12; // about 4s
 pub const RATE_LIMIT_SHIFT_MILLIS_DEFAULT: u8 = 10;
 
+pub use global::GlobalStreamSpeedLimitConfig;
+pub use pacing::UdpPacingConfig;
 pub use tcp::TcpSockSpeedLimitConfig;
 pub use udp::UdpSockSpeedLimitConfig;
 
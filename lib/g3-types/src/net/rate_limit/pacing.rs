@@ -0,0 +1,75 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+
+const DEFAULT_QUEUE_SIZE: usize = 64;
+
+/// Packet pacing config, in bytes per second, with a burst tolerance
+/// and a max count of packets that can be queued
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct UdpPacingConfig {
+    rate_bytes: u64,
+    burst_bytes: u64,
+    queue_size: usize,
+}
+
+impl UdpPacingConfig {
+    pub fn per_second(size: u64) -> Self {
+        UdpPacingConfig {
+            rate_bytes: size,
+            burst_bytes: 0,
+            queue_size: DEFAULT_QUEUE_SIZE,
+        }
+    }
+
+    #[inline]
+    pub fn rate_bytes(&self) -> u64 {
+        self.rate_bytes
+    }
+
+    #[inline]
+    pub fn burst_bytes(&self) -> u64 {
+        self.burst_bytes
+    }
+
+    #[inline]
+    pub fn queue_size(&self) -> usize {
+        self.queue_size
+    }
+
+    pub fn set_rate_bytes(&mut self, size: u64) {
+        self.rate_bytes = size;
+    }
+
+    pub fn set_burst_bytes(&mut self, size: u64) {
+        self.burst_bytes = size;
+    }
+
+    pub fn set_queue_size(&mut self, size: usize) {
+        self.queue_size = size;
+    }
+
+    pub fn check(&mut self) -> anyhow::Result<()> {
+        if self.rate_bytes == 0 {
+            return Err(anyhow!("pacing rate should not be 0"));
+        }
+        if self.queue_size == 0 {
+            return Err(anyhow!("queue size should not be 0"));
+        }
+        Ok(())
+    }
+}
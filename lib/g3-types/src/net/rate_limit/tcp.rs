@@ -25,6 +25,9 @@ pub struct TcpSockSpeedLimitConfig {
     pub shift_millis: u8,
     pub max_north: usize, // upload
     pub max_south: usize, // download
+    // use token bucket with the same average rate if set
+    pub burst_north: usize,
+    pub burst_south: usize,
 }
 
 impl TcpSockSpeedLimitConfig {
@@ -75,6 +78,9 @@ impl TcpSockSpeedLimitConfig {
             shift_millis,
             max_north: get_nonzero_smaller(self.max_north, other_north),
             max_south: get_nonzero_smaller(self.max_south, other_south),
+            // 0 means no burst, which is the smaller one
+            burst_north: self.burst_north.min(other.burst_north),
+            burst_south: self.burst_south.min(other.burst_south),
         }
     }
 }
@@ -89,16 +95,19 @@ mod tests {
             shift_millis: 10,
             max_north: 102400,
             max_south: 409600,
+            ..Default::default()
         };
         let b = TcpSockSpeedLimitConfig {
             shift_millis: 8,
             max_north: 12800,
             max_south: 204800,
+            ..Default::default()
         };
         let r = TcpSockSpeedLimitConfig {
             shift_millis: 10,
             max_north: 51200,
             max_south: 409600,
+            ..Default::default()
         };
         assert_eq!(a.shrink_as_smaller(&b), r);
     }
@@ -109,17 +118,47 @@ mod tests {
             shift_millis: 10,
             max_north: 102400,
             max_south: 409600,
+            ..Default::default()
         };
         let b = TcpSockSpeedLimitConfig {
             shift_millis: 8,
             max_north: 12800,
             max_south: 204800,
+            ..Default::default()
         };
         let r = TcpSockSpeedLimitConfig {
             shift_millis: 8,
             max_north: 12800,
             max_south: 102400,
+            ..Default::default()
         };
         assert_eq!(b.shrink_as_smaller(&a), r);
     }
+
+    #[test]
+    fn tcp_sock_limit_shrink_burst() {
+        let a = TcpSockSpeedLimitConfig {
+            shift_millis: 10,
+            max_north: 102400,
+            max_south: 409600,
+            burst_north: 1048576,
+            burst_south: 0,
+        };
+        let b = TcpSockSpeedLimitConfig {
+            shift_millis: 10,
+            max_north: 102400,
+            max_south: 409600,
+            burst_north: 524288,
+            burst_south: 4194304,
+        };
+        let r = TcpSockSpeedLimitConfig {
+            shift_millis: 10,
+            max_north: 102400,
+            max_south: 409600,
+            burst_north: 524288,
+            burst_south: 0,
+        };
+        assert_eq!(a.shrink_as_smaller(&b), r);
+        assert_eq!(b.shrink_as_smaller(&a), r);
+    }
 }
@@ -27,6 +27,11 @@ pub struct UdpSockSpeedLimitConfig {
     pub max_south_packets: usize, // download
     pub max_north_bytes: usize,   // upload
     pub max_south_bytes: usize,   // download
+    // use token bucket with the same average rate if set
+    pub burst_north_packets: usize,
+    pub burst_south_packets: usize,
+    pub burst_north_bytes: usize,
+    pub burst_south_bytes: usize,
 }
 
 impl UdpSockSpeedLimitConfig {
@@ -83,6 +88,11 @@ impl UdpSockSpeedLimitConfig {
             max_north_bytes: get_nonzero_smaller(self.max_north_bytes, other_north_bytes),
             max_south_packets: get_nonzero_smaller(self.max_south_packets, other_south_packets),
             max_south_bytes: get_nonzero_smaller(self.max_south_bytes, other_south_bytes),
+            // 0 means no burst, which is the smaller one
+            burst_north_packets: self.burst_north_packets.min(other.burst_north_packets),
+            burst_south_packets: self.burst_south_packets.min(other.burst_south_packets),
+            burst_north_bytes: self.burst_north_bytes.min(other.burst_north_bytes),
+            burst_south_bytes: self.burst_south_bytes.min(other.burst_south_bytes),
         }
     }
 }
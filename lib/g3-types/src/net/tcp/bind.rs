@@ -0,0 +1,58 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::net::PortRange;
+
+/// Local bind config for outgoing tcp sockets
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TcpBindConfig {
+    bind_no_port: bool,
+    port_range: Option<PortRange>,
+}
+
+impl Default for TcpBindConfig {
+    fn default() -> Self {
+        TcpBindConfig {
+            bind_no_port: true,
+            port_range: None,
+        }
+    }
+}
+
+impl TcpBindConfig {
+    /// Whether to set IP_BIND_ADDRESS_NO_PORT when bind to an ip without port.
+    ///
+    /// This has no effect if the port range is set.
+    #[inline]
+    pub fn bind_no_port(&self) -> bool {
+        self.bind_no_port
+    }
+
+    #[inline]
+    pub fn set_bind_no_port(&mut self, enable: bool) {
+        self.bind_no_port = enable;
+    }
+
+    #[inline]
+    pub fn port_range(&self) -> Option<PortRange> {
+        self.port_range
+    }
+
+    #[inline]
+    pub fn set_port_range(&mut self, range: PortRange) {
+        self.port_range = Some(range);
+    }
+}
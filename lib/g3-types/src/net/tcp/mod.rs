@@ -14,11 +14,13 @@
  * limitations under the License.
  */
 
+mod bind;
 mod connect;
 mod keepalive;
 mod listen;
 mod sockopt;
 
+pub use bind::TcpBindConfig;
 pub use connect::{HappyEyeballsConfig, TcpConnectConfig};
 pub use listen::TcpListenConfig;
 
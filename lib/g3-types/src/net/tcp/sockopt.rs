@@ -14,6 +14,8 @@
  * limitations under the License.
  */
 
+use std::time::Duration;
+
 use crate::ext::OptionExt;
 
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
@@ -22,10 +24,19 @@ pub struct TcpMiscSockOpts {
     pub max_segment_size: Option<u32>,
     pub time_to_live: Option<u32>,
     pub type_of_service: Option<u8>,
+    pub traffic_class: Option<u8>,
     pub netfilter_mark: Option<u32>,
+    pub user_timeout: Option<Duration>,
 }
 
 impl TcpMiscSockOpts {
+    /// set the DSCP value for both IPv4 TOS and IPv6 Traffic Class, the ECN bits will be cleared
+    pub fn set_dscp(&mut self, dscp: u8) {
+        let v = dscp << 2;
+        self.type_of_service = Some(v);
+        self.traffic_class = Some(v);
+    }
+
     #[must_use]
     pub fn adjust_to(self, other: &Self) -> Self {
         let no_delay = match (self.no_delay, other.no_delay) {
@@ -36,16 +47,66 @@ impl TcpMiscSockOpts {
 
         let max_segment_size = self.max_segment_size.existed_min(other.max_segment_size);
         let time_to_live = self.time_to_live.existed_min(other.time_to_live);
+        let user_timeout = self.user_timeout.existed_min(other.user_timeout);
 
-        let type_of_service = other.type_of_service.or(self.type_of_service);
-        let netfilter_mark = other.netfilter_mark.or(self.netfilter_mark);
+        let type_of_service = self.type_of_service.or(other.type_of_service);
+        let traffic_class = self.traffic_class.or(other.traffic_class);
+        let netfilter_mark = self.netfilter_mark.or(other.netfilter_mark);
 
         TcpMiscSockOpts {
             no_delay,
             max_segment_size,
             time_to_live,
             type_of_service,
+            traffic_class,
             netfilter_mark,
+            user_timeout,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dscp() {
+        let mut opts = TcpMiscSockOpts::default();
+        opts.set_dscp(46);
+        assert_eq!(opts.type_of_service, Some(0xb8));
+        assert_eq!(opts.traffic_class, Some(0xb8));
+
+        opts.set_dscp(63);
+        assert_eq!(opts.type_of_service, Some(0xfc));
+        opts.set_dscp(0);
+        assert_eq!(opts.traffic_class, Some(0));
+    }
+
+    #[test]
+    fn adjust_to() {
+        let mut server = TcpMiscSockOpts {
+            max_segment_size: Some(1400),
+            netfilter_mark: Some(1),
+            ..Default::default()
+        };
+        server.set_dscp(10);
+
+        let mut user = TcpMiscSockOpts {
+            max_segment_size: Some(1460),
+            netfilter_mark: Some(2),
+            ..Default::default()
+        };
+        user.set_dscp(46);
+
+        let opts = user.adjust_to(&server);
+        assert_eq!(opts.type_of_service, Some(46 << 2));
+        assert_eq!(opts.traffic_class, Some(46 << 2));
+        assert_eq!(opts.netfilter_mark, Some(2));
+        assert_eq!(opts.max_segment_size, Some(1400));
+
+        let opts = TcpMiscSockOpts::default().adjust_to(&server);
+        assert_eq!(opts.type_of_service, Some(10 << 2));
+        assert_eq!(opts.traffic_class, Some(10 << 2));
+        assert_eq!(opts.netfilter_mark, Some(1));
+    }
+}
@@ -20,21 +20,66 @@ use crate::ext::OptionExt;
 pub struct UdpMiscSockOpts {
     pub time_to_live: Option<u32>,
     pub type_of_service: Option<u8>,
+    pub traffic_class: Option<u8>,
     pub netfilter_mark: Option<u32>,
 }
 
 impl UdpMiscSockOpts {
+    /// set the DSCP value for both IPv4 TOS and IPv6 Traffic Class, the ECN bits will be cleared
+    pub fn set_dscp(&mut self, dscp: u8) {
+        let v = dscp << 2;
+        self.type_of_service = Some(v);
+        self.traffic_class = Some(v);
+    }
+
     #[must_use]
     pub fn adjust_to(self, other: &Self) -> Self {
         let time_to_live = self.time_to_live.existed_min(other.time_to_live);
 
-        let type_of_service = other.type_of_service.or(self.type_of_service);
-        let netfilter_mark = other.netfilter_mark.or(self.netfilter_mark);
+        let type_of_service = self.type_of_service.or(other.type_of_service);
+        let traffic_class = self.traffic_class.or(other.traffic_class);
+        let netfilter_mark = self.netfilter_mark.or(other.netfilter_mark);
 
         UdpMiscSockOpts {
             time_to_live,
             type_of_service,
+            traffic_class,
             netfilter_mark,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dscp() {
+        let mut opts = UdpMiscSockOpts::default();
+        opts.set_dscp(46);
+        assert_eq!(opts.type_of_service, Some(0xb8));
+        assert_eq!(opts.traffic_class, Some(0xb8));
+    }
+
+    #[test]
+    fn adjust_to() {
+        let mut server = UdpMiscSockOpts {
+            time_to_live: Some(32),
+            netfilter_mark: Some(1),
+            ..Default::default()
+        };
+        server.set_dscp(10);
+
+        let user = UdpMiscSockOpts {
+            time_to_live: Some(64),
+            traffic_class: Some(46 << 2),
+            ..Default::default()
+        };
+
+        let opts = user.adjust_to(&server);
+        assert_eq!(opts.type_of_service, Some(10 << 2));
+        assert_eq!(opts.traffic_class, Some(46 << 2));
+        assert_eq!(opts.netfilter_mark, Some(1));
+        assert_eq!(opts.time_to_live, Some(32));
+    }
+}
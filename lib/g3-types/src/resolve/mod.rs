@@ -15,9 +15,11 @@
  */
 
 mod redirect;
+mod state;
 mod strategy;
 
 pub use redirect::{ResolveRedirection, ResolveRedirectionBuilder, ResolveRedirectionValue};
+pub use state::ResolvePickState;
 pub use strategy::{PickStrategy, QueryStrategy, ResolveStrategy};
 
 /// the input domain should be valid IDNA domain
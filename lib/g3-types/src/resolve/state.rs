@@ -0,0 +1,182 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use ahash::AHashMap;
+
+const MAX_RECORD_COUNT: usize = 4096;
+const RECORD_EXPIRE_TIME: Duration = Duration::from_secs(300);
+
+struct RttRecord {
+    rtt: Duration,
+    updated: Instant,
+}
+
+impl RttRecord {
+    fn is_expired(&self, now: Instant) -> bool {
+        now.duration_since(self.updated) >= RECORD_EXPIRE_TIME
+    }
+}
+
+/// The state used by the round robin and lowest latency pick strategies.
+///
+/// It should be owned by the user of the resolve strategy, like an escaper, so the round robin
+/// offset and the connect RTT records won't be shared with others.
+#[derive(Default)]
+pub struct ResolvePickState {
+    round_robin: AtomicUsize,
+    connect_rtt: Mutex<AHashMap<IpAddr, RttRecord>>,
+}
+
+impl ResolvePickState {
+    pub(super) fn next_round_robin(&self) -> usize {
+        self.round_robin.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Record the connect RTT to a remote ip address, which will be used by the lowest latency
+    /// pick strategy. The value will be smoothed with the old record.
+    ///
+    /// The expired records will be removed if the table is full, and the new record will be
+    /// ignored if there is still no room for it.
+    pub fn record_connect_rtt(&self, ip: IpAddr, rtt: Duration) {
+        self.record_connect_rtt_at(ip, rtt, Instant::now())
+    }
+
+    fn record_connect_rtt_at(&self, ip: IpAddr, rtt: Duration, now: Instant) {
+        let mut ht = self.connect_rtt.lock().unwrap();
+        if let Some(r) = ht.get_mut(&ip) {
+            if r.is_expired(now) {
+                r.rtt = rtt;
+            } else {
+                r.rtt = (r.rtt * 7 + rtt) / 8;
+            }
+            r.updated = now;
+            return;
+        }
+
+        if ht.len() >= MAX_RECORD_COUNT {
+            ht.retain(|_, r| !r.is_expired(now));
+            if ht.len() >= MAX_RECORD_COUNT {
+                return;
+            }
+        }
+        ht.insert(ip, RttRecord { rtt, updated: now });
+    }
+
+    /// Sort the ip addresses by the recent connect RTT, those without a recent record come first
+    pub(super) fn sort_by_connect_rtt(&self, ips: &mut [IpAddr]) {
+        self.sort_by_connect_rtt_at(ips, Instant::now())
+    }
+
+    fn sort_by_connect_rtt_at(&self, ips: &mut [IpAddr], now: Instant) {
+        let ht = self.connect_rtt.lock().unwrap();
+        ips.sort_by_cached_key(|ip| {
+            ht.get(ip)
+                .filter(|r| !r.is_expired(now))
+                .map(|r| r.rtt)
+                .unwrap_or_default()
+        });
+    }
+
+    #[cfg(test)]
+    fn record_count(&self) -> usize {
+        self.connect_rtt.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort() {
+        let state = ResolvePickState::default();
+        let ip1 = IpAddr::from([127, 0, 0, 1]);
+        let ip2 = IpAddr::from([127, 0, 0, 2]);
+        let ip3 = IpAddr::from([127, 0, 0, 3]);
+        state.record_connect_rtt(ip1, Duration::from_millis(20));
+        state.record_connect_rtt(ip2, Duration::from_millis(10));
+
+        let mut ips = [ip1, ip2, ip3];
+        state.sort_by_connect_rtt(&mut ips);
+        assert_eq!(ips, [ip3, ip2, ip1]);
+
+        // the other state is not affected
+        let other = ResolvePickState::default();
+        let mut ips = [ip1, ip2, ip3];
+        other.sort_by_connect_rtt(&mut ips);
+        assert_eq!(ips, [ip1, ip2, ip3]);
+    }
+
+    #[test]
+    fn smooth() {
+        let state = ResolvePickState::default();
+        let ip1 = IpAddr::from([127, 0, 0, 1]);
+        let ip2 = IpAddr::from([127, 0, 0, 2]);
+        state.record_connect_rtt(ip1, Duration::from_millis(8));
+        state.record_connect_rtt(ip2, Duration::from_millis(16));
+        state.record_connect_rtt(ip1, Duration::from_millis(80));
+
+        // ip1 is smoothed to 17ms
+        let mut ips = [ip1, ip2];
+        state.sort_by_connect_rtt(&mut ips);
+        assert_eq!(ips, [ip2, ip1]);
+    }
+
+    #[test]
+    fn expire() {
+        let state = ResolvePickState::default();
+        let start = Instant::now();
+        let ip1 = IpAddr::from([127, 0, 0, 1]);
+        let ip2 = IpAddr::from([127, 0, 0, 2]);
+        state.record_connect_rtt_at(ip1, Duration::from_millis(20), start);
+        state.record_connect_rtt_at(ip2, Duration::from_millis(10), start);
+
+        let mut ips = [ip1, ip2];
+        state.sort_by_connect_rtt_at(&mut ips, start);
+        assert_eq!(ips, [ip2, ip1]);
+
+        // expired records are treated as unknown
+        let later = start + RECORD_EXPIRE_TIME;
+        state.record_connect_rtt_at(ip1, Duration::from_millis(20), later);
+        let mut ips = [ip1, ip2];
+        state.sort_by_connect_rtt_at(&mut ips, later);
+        assert_eq!(ips, [ip2, ip1]);
+    }
+
+    #[test]
+    fn bounded() {
+        let state = ResolvePickState::default();
+        let start = Instant::now();
+        for i in 0..MAX_RECORD_COUNT as u32 {
+            state.record_connect_rtt_at(IpAddr::from(i.to_be_bytes()), Duration::ZERO, start);
+        }
+        assert_eq!(state.record_count(), MAX_RECORD_COUNT);
+
+        // no room for new records if all are fresh
+        let ip = IpAddr::from([255, 255, 255, 255]);
+        state.record_connect_rtt_at(ip, Duration::ZERO, start);
+        assert_eq!(state.record_count(), MAX_RECORD_COUNT);
+
+        // expired records will be removed
+        state.record_connect_rtt_at(ip, Duration::ZERO, start + RECORD_EXPIRE_TIME);
+        assert_eq!(state.record_count(), 1);
+    }
+}
@@ -14,10 +14,13 @@
  * limitations under the License.
  */
 
+use std::net::IpAddr;
 use std::str::FromStr;
 
 use anyhow::anyhow;
 
+use super::ResolvePickState;
+
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub enum QueryStrategy {
     Ipv4Only,
@@ -56,6 +59,8 @@ pub enum PickStrategy {
     #[default]
     Random,
     Serial,
+    RoundRobin,
+    LowestLatency,
 }
 
 impl FromStr for PickStrategy {
@@ -65,6 +70,8 @@ impl FromStr for PickStrategy {
         match s.to_lowercase().replace('-', "_").as_str() {
             "random" => Ok(PickStrategy::Random),
             "serial" | "first" => Ok(PickStrategy::Serial),
+            "round_robin" | "roundrobin" | "rr" => Ok(PickStrategy::RoundRobin),
+            "lowest_latency" | "lowestlatency" | "latency" => Ok(PickStrategy::LowestLatency),
             _ => Err(()),
         }
     }
@@ -74,6 +81,8 @@ impl FromStr for PickStrategy {
 pub struct ResolveStrategy {
     pub query: QueryStrategy,
     pub pick: PickStrategy,
+    /// the weight of ipv4 and ipv6 to be used as the first address family
+    pub family_weight: Option<(u8, u8)>,
 }
 
 impl ResolveStrategy {
@@ -83,6 +92,27 @@ impl ResolveStrategy {
         ResolveStrategy {
             query,
             pick: other.pick,
+            family_weight: other.family_weight,
+        }
+    }
+
+    /// get the query strategy to use, the first address family may be changed by weight
+    pub fn weighted_query(&self) -> QueryStrategy {
+        match self.query {
+            QueryStrategy::Ipv4First | QueryStrategy::Ipv6First => {
+                let Some((w4, w6)) = self.family_weight else {
+                    return self.query;
+                };
+                let total = w4 as u32 + w6 as u32;
+                if total == 0 {
+                    self.query
+                } else if fastrand::u32(0..total) < w4 as u32 {
+                    QueryStrategy::Ipv4First
+                } else {
+                    QueryStrategy::Ipv6First
+                }
+            }
+            q => q,
         }
     }
 
@@ -128,26 +158,40 @@ impl ResolveStrategy {
         Ok(())
     }
 
-    pub fn pick_many<T: Copy>(&self, mut all: Vec<T>, count: usize) -> Vec<T> {
+    pub fn pick_many(
+        &self,
+        mut all: Vec<IpAddr>,
+        count: usize,
+        state: &ResolvePickState,
+    ) -> Vec<IpAddr> {
         if all.len() > 1 {
             match self.pick {
-                PickStrategy::Serial => {
-                    all.truncate(count);
-                }
-                PickStrategy::Random => {
-                    fastrand::shuffle(&mut all);
-                    all.truncate(count);
+                PickStrategy::Serial => {}
+                PickStrategy::Random => fastrand::shuffle(&mut all),
+                PickStrategy::RoundRobin => {
+                    let offset = state.next_round_robin() % all.len();
+                    all.rotate_left(offset);
                 }
+                PickStrategy::LowestLatency => state.sort_by_connect_rtt(&mut all),
             }
+            all.truncate(count);
         }
         all
     }
 
-    pub fn pick_best<T: Copy>(&self, mut all: Vec<T>) -> Option<T> {
+    pub fn pick_best(&self, mut all: Vec<IpAddr>, state: &ResolvePickState) -> Option<IpAddr> {
         if all.len() > 1 {
             match self.pick {
                 PickStrategy::Serial => all.into_iter().next(),
                 PickStrategy::Random => fastrand::choice(all),
+                PickStrategy::RoundRobin => {
+                    let offset = state.next_round_robin() % all.len();
+                    Some(all.swap_remove(offset))
+                }
+                PickStrategy::LowestLatency => {
+                    state.sort_by_connect_rtt(&mut all);
+                    all.into_iter().next()
+                }
             }
         } else {
             all.pop()
@@ -211,7 +255,48 @@ mod tests {
             pick: PickStrategy::Serial,
             ..Default::default()
         };
-        assert_eq!(s.pick_best(vec![1, 2]), Some(1));
-        assert_eq!(s.pick_many(vec![1, 2, 3], 2), vec![1, 2]);
+        let ip1 = IpAddr::from([127, 0, 0, 1]);
+        let ip2 = IpAddr::from([127, 0, 0, 2]);
+        let ip3 = IpAddr::from([127, 0, 0, 3]);
+        let state = ResolvePickState::default();
+        assert_eq!(s.pick_best(vec![ip1, ip2], &state), Some(ip1));
+        assert_eq!(s.pick_many(vec![ip1, ip2, ip3], 2, &state), vec![ip1, ip2]);
+
+        let s = ResolveStrategy {
+            pick: PickStrategy::RoundRobin,
+            ..Default::default()
+        };
+        let first = s.pick_best(vec![ip1, ip2], &state).unwrap();
+        let second = s.pick_best(vec![ip1, ip2], &state).unwrap();
+        assert_ne!(first, second);
+
+        // the round robin offset is not shared between states
+        let other = ResolvePickState::default();
+        assert_eq!(s.pick_best(vec![ip1, ip2], &other), Some(ip1));
+
+        let s = ResolveStrategy {
+            pick: PickStrategy::LowestLatency,
+            ..Default::default()
+        };
+        state.record_connect_rtt(ip1, std::time::Duration::from_millis(20));
+        state.record_connect_rtt(ip2, std::time::Duration::from_millis(10));
+        assert_eq!(s.pick_best(vec![ip1, ip2], &state), Some(ip2));
+        assert_eq!(s.pick_best(vec![ip1, ip2], &other), Some(ip1));
+    }
+
+    #[test]
+    fn t_weighted_query() {
+        let s = ResolveStrategy {
+            family_weight: Some((0, 1)),
+            ..Default::default()
+        };
+        assert_eq!(s.weighted_query(), QueryStrategy::Ipv6First);
+
+        let s = ResolveStrategy {
+            query: QueryStrategy::Ipv4Only,
+            family_weight: Some((0, 1)),
+            ..Default::default()
+        };
+        assert_eq!(s.weighted_query(), QueryStrategy::Ipv4Only);
     }
 }
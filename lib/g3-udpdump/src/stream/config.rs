@@ -15,15 +15,17 @@
  */
 
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 
 use g3_types::net::{SocketBufferConfig, UdpMiscSockOpts};
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct StreamDumpConfig {
     pub peer: SocketAddr,
     pub buffer: SocketBufferConfig,
     pub opts: UdpMiscSockOpts,
     pub packet_size: usize,
+    pub file: Option<PathBuf>,
 }
 
 impl Default for StreamDumpConfig {
@@ -33,6 +35,7 @@ impl Default for StreamDumpConfig {
             buffer: SocketBufferConfig::default(),
             opts: UdpMiscSockOpts::default(),
             packet_size: 1480,
+            file: None,
         }
     }
 }
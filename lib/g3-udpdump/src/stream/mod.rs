@@ -16,6 +16,7 @@
 
 use std::io;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use tokio::io::AsyncWrite;
 use tokio::net::UdpSocket;
@@ -30,6 +31,9 @@ pub use config::StreamDumpConfig;
 mod sink;
 use sink::Sinker;
 
+mod pcapng;
+use pcapng::PcapngFileSinker;
+
 mod header;
 use header::PduHeader;
 pub use header::{ToClientPduHeader, ToRemotePduHeader};
@@ -37,13 +41,30 @@ pub use header::{ToClientPduHeader, ToRemotePduHeader};
 mod write;
 pub use write::{StreamDumpWriter, ToClientStreamDumpWriter, ToRemoteStreamDumpWriter};
 
+pub(crate) struct StreamDumpPacket {
+    data: Vec<u8>,
+    comment: Option<Arc<str>>,
+}
+
 pub struct StreamDumper {
-    config: StreamDumpConfig,
-    sender: mpsc::UnboundedSender<Vec<u8>>,
+    packet_size: usize,
+    sender: mpsc::UnboundedSender<StreamDumpPacket>,
 }
 
 impl StreamDumper {
-    pub fn new(config: StreamDumpConfig, runtime: &Handle) -> io::Result<Self> {
+    pub fn new(config: &StreamDumpConfig, runtime: &Handle) -> io::Result<Self> {
+        if let Some(path) = &config.file {
+            let sinker = PcapngFileSinker::create(path)?;
+            let (sender, receiver) = mpsc::unbounded_channel();
+            std::thread::Builder::new()
+                .name("stream-dump".to_string())
+                .spawn(move || sinker.into_running(receiver))?;
+            return Ok(StreamDumper {
+                packet_size: config.packet_size,
+                sender,
+            });
+        }
+
         let socket =
             g3_socket::udp::new_std_socket_to(config.peer, None, config.buffer, config.opts)?;
         socket.connect(config.peer)?;
@@ -55,7 +76,10 @@ impl StreamDumper {
             Sinker::new(receiver, socket).into_running().await;
         });
 
-        Ok(StreamDumper { config, sender })
+        Ok(StreamDumper {
+            packet_size: config.packet_size,
+            sender,
+        })
     }
 
     pub fn wrap_io<CW, RW>(
@@ -63,6 +87,7 @@ impl StreamDumper {
         client_addr: SocketAddr,
         remote_addr: SocketAddr,
         dissector_hint: ExportedPduDissectorHint,
+        comment: Option<String>,
         client_writer: CW,
         remote_writer: RW,
     ) -> (ToClientStreamDumpWriter<CW>, ToRemoteStreamDumpWriter<RW>)
@@ -71,17 +96,20 @@ impl StreamDumper {
         RW: AsyncWrite,
     {
         let (to_c, to_r) = header::new_pair(client_addr, remote_addr, dissector_hint);
+        let comment: Option<Arc<str>> = comment.map(Arc::from);
         let cw = StreamDumpWriter::new(
             client_writer,
             to_c,
             self.sender.clone(),
-            self.config.packet_size,
+            comment.clone(),
+            self.packet_size,
         );
         let rw = StreamDumpWriter::new(
             remote_writer,
             to_r,
             self.sender.clone(),
-            self.config.packet_size,
+            comment,
+            self.packet_size,
         );
         (cw, rw)
     }
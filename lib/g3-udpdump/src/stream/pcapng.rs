@@ -0,0 +1,133 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+use log::debug;
+use tokio::sync::mpsc;
+
+use super::StreamDumpPacket;
+
+const BLOCK_TYPE_SECTION_HEADER: u32 = 0x0A0D_0D0A;
+const BLOCK_TYPE_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const BLOCK_TYPE_ENHANCED_PACKET: u32 = 0x0000_0006;
+
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+const LINKTYPE_WIRESHARK_UPPER_PDU: u16 = 252;
+
+const OPTION_END_OF_OPT: u16 = 0;
+const OPTION_COMMENT: u16 = 1;
+
+pub(super) struct PcapngFileSinker {
+    writer: BufWriter<File>,
+}
+
+impl PcapngFileSinker {
+    pub(super) fn create(path: &Path) -> io::Result<Self> {
+        // append a new section if the file already exists
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut sinker = PcapngFileSinker {
+            writer: BufWriter::new(file),
+        };
+        sinker.write_section_header()?;
+        sinker.write_interface_description()?;
+        sinker.writer.flush()?;
+        Ok(sinker)
+    }
+
+    pub(super) fn into_running(mut self, mut receiver: mpsc::UnboundedReceiver<StreamDumpPacket>) {
+        while let Some(pkt) = receiver.blocking_recv() {
+            if let Err(e) = self.write_packet(&pkt) {
+                debug!("stream dump pcapng write error: {e}");
+                continue;
+            }
+            while let Ok(pkt) = receiver.try_recv() {
+                if let Err(e) = self.write_packet(&pkt) {
+                    debug!("stream dump pcapng write error: {e}");
+                }
+            }
+            if let Err(e) = self.writer.flush() {
+                debug!("stream dump pcapng flush error: {e}");
+            }
+        }
+    }
+
+    fn write_section_header(&mut self) -> io::Result<()> {
+        let block_len: u32 = 28;
+        let w = &mut self.writer;
+        w.write_all(&BLOCK_TYPE_SECTION_HEADER.to_le_bytes())?;
+        w.write_all(&block_len.to_le_bytes())?;
+        w.write_all(&BYTE_ORDER_MAGIC.to_le_bytes())?;
+        w.write_all(&1u16.to_le_bytes())?; // major version
+        w.write_all(&0u16.to_le_bytes())?; // minor version
+        w.write_all(&(-1i64).to_le_bytes())?; // section length not specified
+        w.write_all(&block_len.to_le_bytes())
+    }
+
+    fn write_interface_description(&mut self) -> io::Result<()> {
+        let block_len: u32 = 20;
+        let w = &mut self.writer;
+        w.write_all(&BLOCK_TYPE_INTERFACE_DESCRIPTION.to_le_bytes())?;
+        w.write_all(&block_len.to_le_bytes())?;
+        w.write_all(&LINKTYPE_WIRESHARK_UPPER_PDU.to_le_bytes())?;
+        w.write_all(&0u16.to_le_bytes())?; // reserved
+        w.write_all(&0u32.to_le_bytes())?; // no snap length limit
+        w.write_all(&block_len.to_le_bytes())
+    }
+
+    fn write_packet(&mut self, pkt: &StreamDumpPacket) -> io::Result<()> {
+        let data_len = pkt.data.len();
+        let data_pad = padding_len(data_len);
+        let options_len = match &pkt.comment {
+            Some(s) => 4 + s.len() + padding_len(s.len()) + 4,
+            None => 0,
+        };
+        let block_len = u32::try_from(32 + data_len + data_pad + options_len)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too large packet"))?;
+
+        let ts = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or_default();
+
+        let w = &mut self.writer;
+        w.write_all(&BLOCK_TYPE_ENHANCED_PACKET.to_le_bytes())?;
+        w.write_all(&block_len.to_le_bytes())?;
+        w.write_all(&0u32.to_le_bytes())?; // interface id
+        w.write_all(&((ts >> 32) as u32).to_le_bytes())?;
+        w.write_all(&(ts as u32).to_le_bytes())?;
+        w.write_all(&(data_len as u32).to_le_bytes())?; // captured length
+        w.write_all(&(data_len as u32).to_le_bytes())?; // original length
+        w.write_all(&pkt.data)?;
+        w.write_all(&[0u8; 3][..data_pad])?;
+        if let Some(s) = &pkt.comment {
+            w.write_all(&OPTION_COMMENT.to_le_bytes())?;
+            w.write_all(&(s.len() as u16).to_le_bytes())?;
+            w.write_all(s.as_bytes())?;
+            w.write_all(&[0u8; 3][..padding_len(s.len())])?;
+            w.write_all(&OPTION_END_OF_OPT.to_le_bytes())?;
+            w.write_all(&0u16.to_le_bytes())?;
+        }
+        w.write_all(&block_len.to_le_bytes())
+    }
+}
+
+fn padding_len(len: usize) -> usize {
+    (4 - (len % 4)) % 4
+}
@@ -20,15 +20,20 @@ use log::debug;
 use tokio::net::UdpSocket;
 use tokio::sync::mpsc;
 
+use super::StreamDumpPacket;
+
 const UDP_BATCH_SEND_SIZE: usize = 8;
 
 pub(super) struct Sinker {
-    receiver: mpsc::UnboundedReceiver<Vec<u8>>,
+    receiver: mpsc::UnboundedReceiver<StreamDumpPacket>,
     socket: UdpSocket,
 }
 
 impl Sinker {
-    pub(super) fn new(receiver: mpsc::UnboundedReceiver<Vec<u8>>, socket: UdpSocket) -> Self {
+    pub(super) fn new(
+        receiver: mpsc::UnboundedReceiver<StreamDumpPacket>,
+        socket: UdpSocket,
+    ) -> Self {
         Sinker { receiver, socket }
     }
 
@@ -54,7 +59,7 @@ impl Sinker {
         target_os = "netbsd",
         target_os = "openbsd",
     ))]
-    async fn send_udp(&self, packets: &[Vec<u8>]) -> io::Result<()> {
+    async fn send_udp(&self, packets: &[StreamDumpPacket]) -> io::Result<()> {
         use g3_io_ext::{SendMsgHdr, UdpSocketExt};
         use std::future::poll_fn;
         use std::io::IoSlice;
@@ -62,7 +67,7 @@ impl Sinker {
         let msgs: Vec<_> = packets
             .iter()
             .map(|v| SendMsgHdr {
-                iov: [IoSlice::new(v.as_slice())],
+                iov: [IoSlice::new(v.data.as_slice())],
                 addr: None,
             })
             .collect();
@@ -74,9 +79,9 @@ impl Sinker {
     }
 
     #[cfg(any(target_os = "macos", target_os = "dragonfly"))]
-    async fn send_udp(&self, packets: &[Vec<u8>]) -> io::Result<()> {
+    async fn send_udp(&self, packets: &[StreamDumpPacket]) -> io::Result<()> {
         for pkt in packets {
-            self.socket.send(pkt.as_slice()).await?;
+            self.socket.send(pkt.data.as_slice()).await?;
         }
         Ok(())
     }
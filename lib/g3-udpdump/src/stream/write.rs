@@ -17,12 +17,13 @@
 use std::io::{self, IoSlice};
 use std::mem;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{ready, Context, Poll};
 
 use tokio::io::AsyncWrite;
 use tokio::sync::mpsc;
 
-use super::{PduHeader, ToClientPduHeader, ToRemotePduHeader};
+use super::{PduHeader, StreamDumpPacket, ToClientPduHeader, ToRemotePduHeader};
 
 pub type ToClientStreamDumpWriter<W> = StreamDumpWriter<W, ToClientPduHeader>;
 pub type ToRemoteStreamDumpWriter<W> = StreamDumpWriter<W, ToRemotePduHeader>;
@@ -30,7 +31,8 @@ pub type ToRemoteStreamDumpWriter<W> = StreamDumpWriter<W, ToRemotePduHeader>;
 pub struct StreamDumpWriter<W, H> {
     writer: W,
     header: H,
-    sender: mpsc::UnboundedSender<Vec<u8>>,
+    sender: mpsc::UnboundedSender<StreamDumpPacket>,
+    comment: Option<Arc<str>>,
     buf: Vec<u8>,
     pkt_size: usize,
     hdr_len: usize,
@@ -40,7 +42,8 @@ impl<W: AsyncWrite, H: PduHeader> StreamDumpWriter<W, H> {
     pub(super) fn new(
         writer: W,
         mut header: H,
-        sender: mpsc::UnboundedSender<Vec<u8>>,
+        sender: mpsc::UnboundedSender<StreamDumpPacket>,
+        comment: Option<Arc<str>>,
         mut pkt_size: usize,
     ) -> Self {
         pkt_size = pkt_size.max(1200);
@@ -50,6 +53,7 @@ impl<W: AsyncWrite, H: PduHeader> StreamDumpWriter<W, H> {
             writer,
             header,
             sender,
+            comment,
             buf,
             pkt_size,
             hdr_len,
@@ -78,7 +82,10 @@ impl<W: AsyncWrite, H: PduHeader> StreamDumpWriter<W, H> {
         let mut buf = mem::replace(&mut self.buf, new_buf);
         let data_len = buf.len() - self.hdr_len;
         self.header.update_tcp_dissector_data(&mut buf, data_len);
-        let _ = self.sender.send(buf);
+        let _ = self.sender.send(StreamDumpPacket {
+            data: buf,
+            comment: self.comment.take(),
+        });
         self.header.record_written_data(data_len);
     }
 
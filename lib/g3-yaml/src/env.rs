@@ -0,0 +1,128 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::anyhow;
+use yaml_rust::Yaml;
+
+static ENV_INTERPOLATION: AtomicBool = AtomicBool::new(false);
+
+/// Enable environment variable interpolation for all yaml docs loaded later.
+///
+/// It's disabled by default, as existing str values may contain literal `${`.
+pub fn enable_env_interpolation() {
+    ENV_INTERPOLATION.store(true, Ordering::Relaxed);
+}
+
+pub fn env_interpolation_enabled() -> bool {
+    ENV_INTERPOLATION.load(Ordering::Relaxed)
+}
+
+/// Replace `${NAME}` and `${NAME:-default}` in all string values with the value of the
+/// environment variable, the default value will be used if it's not set or empty.
+/// Use `$${` to get a literal `${`.
+pub(crate) fn interpolate(doc: &mut Yaml) -> anyhow::Result<()> {
+    match doc {
+        Yaml::String(s) => {
+            if s.contains("${") {
+                *s = interpolate_str(s)?;
+            }
+            Ok(())
+        }
+        Yaml::Array(seq) => {
+            for v in seq.iter_mut() {
+                interpolate(v)?;
+            }
+            Ok(())
+        }
+        Yaml::Hash(map) => {
+            for (_, v) in map.iter_mut() {
+                interpolate(v)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn interpolate_str(s: &str) -> anyhow::Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut left = s;
+    while let Some(p) = left.find('$') {
+        out.push_str(&left[..p]);
+        let rest = &left[p..];
+        if let Some(r) = rest.strip_prefix("$${") {
+            out.push_str("${");
+            left = r;
+        } else if let Some(r) = rest.strip_prefix("${") {
+            let Some(end) = r.find('}') else {
+                return Err(anyhow!("no closing brace found for variable in {s}"));
+            };
+            let expr = &r[..end];
+            let (name, default) = match expr.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (expr, None),
+            };
+            if name.is_empty() {
+                return Err(anyhow!("empty variable name found in {s}"));
+            }
+            match (std::env::var(name), default) {
+                (Ok(v), Some(d)) if v.is_empty() => out.push_str(d),
+                (Ok(v), _) => out.push_str(&v),
+                (Err(_), Some(d)) => out.push_str(d),
+                (Err(e), None) => {
+                    return Err(anyhow!("failed to get environment variable {name}: {e}"))
+                }
+            }
+            left = &r[end + 1..];
+        } else {
+            out.push('$');
+            left = &rest[1..];
+        }
+    }
+    out.push_str(left);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_interpolate() {
+        std::env::set_var("G3_YAML_TEST_SET", "abc");
+        std::env::set_var("G3_YAML_TEST_EMPTY", "");
+        std::env::remove_var("G3_YAML_TEST_UNSET");
+
+        assert_eq!(interpolate_str("a$b").unwrap(), "a$b");
+        assert_eq!(interpolate_str("${G3_YAML_TEST_SET}").unwrap(), "abc");
+        assert_eq!(
+            interpolate_str("x-${G3_YAML_TEST_SET}-y").unwrap(),
+            "x-abc-y"
+        );
+        assert_eq!(interpolate_str("${G3_YAML_TEST_UNSET:-d}").unwrap(), "d");
+        assert_eq!(interpolate_str("${G3_YAML_TEST_EMPTY:-d}").unwrap(), "d");
+        assert_eq!(interpolate_str("${G3_YAML_TEST_EMPTY}").unwrap(), "");
+        assert_eq!(
+            interpolate_str("$${G3_YAML_TEST_SET}").unwrap(),
+            "${G3_YAML_TEST_SET}"
+        );
+        assert!(interpolate_str("${G3_YAML_TEST_UNSET}").is_err());
+        assert!(interpolate_str("${G3_YAML_TEST_SET").is_err());
+        assert!(interpolate_str("${}").is_err());
+    }
+}
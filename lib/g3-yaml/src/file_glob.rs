@@ -0,0 +1,191 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use glob::{MatchOptions, Pattern};
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: true,
+};
+
+pub(crate) fn has_wildcard(s: &str) -> bool {
+    s.contains(['*', '?', '['])
+}
+
+/// Resolve the path to a list of files. The path will be joined with the conf dir if relative.
+///
+/// Unix shell style patterns are supported, see the doc of [`glob::Pattern`] for the syntax.
+/// Hidden files will only be matched if the pattern also starts with `.`.
+/// The matched files will be sorted by path.
+pub fn resolve_files(conf_dir: &Path, path: &str) -> anyhow::Result<Vec<PathBuf>> {
+    if !has_wildcard(path) {
+        let path = conf_dir.join(path);
+        return if path.is_file() {
+            Ok(vec![path])
+        } else {
+            Err(anyhow!("path {} should be a file", path.display()))
+        };
+    }
+
+    let pattern = if Path::new(path).is_absolute() {
+        path.to_string()
+    } else {
+        let conf_dir = conf_dir
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid conf dir {}", conf_dir.display()))?;
+        // the conf dir may contain special chars
+        let conf_dir = Pattern::escape(conf_dir);
+        format!("{}/{path}", conf_dir.trim_end_matches('/'))
+    };
+
+    let paths = glob::glob_with(&pattern, MATCH_OPTIONS)
+        .map_err(|e| anyhow!("invalid pattern {pattern}: {e}"))?;
+    let mut files = Vec::new();
+    for r in paths {
+        let file = r.map_err(|e| anyhow!("failed to read path {}: {e}", e.path().display()))?;
+        // NOTE symlink is followed
+        if file.is_file() {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "g3-yaml-test-{name}-{}-{}",
+                std::process::id(),
+                rand::random::<u32>()
+            ));
+            for file in [
+                "a.conf",
+                "b.conf",
+                "x1.conf",
+                "x2.conf",
+                "x3.yaml",
+                ".hidden.conf",
+                "sub/c.conf",
+                "sub/deep/d.conf",
+                ".hidden_dir/e.conf",
+            ] {
+                let path = dir.join(file);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, "").unwrap();
+            }
+            fs::create_dir_all(dir.join("dir.conf")).unwrap();
+            TestDir(dir)
+        }
+
+        fn resolve(&self, pattern: &str) -> Vec<String> {
+            resolve_files(&self.0, pattern)
+                .unwrap()
+                .into_iter()
+                .map(|p| {
+                    p.strip_prefix(&self.0)
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .to_string()
+                })
+                .collect()
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn plain_path() {
+        let dir = TestDir::new("plain");
+        assert_eq!(dir.resolve("a.conf"), ["a.conf"]);
+        assert_eq!(dir.resolve(".hidden.conf"), [".hidden.conf"]);
+        assert!(resolve_files(&dir.0, "not-existed.conf").is_err());
+        assert!(resolve_files(&dir.0, "sub").is_err());
+    }
+
+    #[test]
+    fn star() {
+        let dir = TestDir::new("star");
+        assert_eq!(
+            dir.resolve("*.conf"),
+            ["a.conf", "b.conf", "x1.conf", "x2.conf"]
+        );
+        assert_eq!(dir.resolve("sub/*.conf"), ["sub/c.conf"]);
+        assert_eq!(dir.resolve("*/*.conf"), ["sub/c.conf"]);
+        assert!(dir.resolve("*.yml").is_empty());
+    }
+
+    #[test]
+    fn recursive() {
+        let dir = TestDir::new("recursive");
+        assert_eq!(
+            dir.resolve("**/*.conf"),
+            [
+                "a.conf",
+                "b.conf",
+                "sub/c.conf",
+                "sub/deep/d.conf",
+                "x1.conf",
+                "x2.conf"
+            ]
+        );
+        assert_eq!(dir.resolve("sub/**/d.conf"), ["sub/deep/d.conf"]);
+    }
+
+    #[test]
+    fn question_mark_and_class() {
+        let dir = TestDir::new("class");
+        assert_eq!(dir.resolve("?.conf"), ["a.conf", "b.conf"]);
+        assert_eq!(dir.resolve("x[12].conf"), ["x1.conf", "x2.conf"]);
+        assert_eq!(dir.resolve("x[!1].*"), ["x2.conf", "x3.yaml"]);
+        assert_eq!(dir.resolve("[a-b].conf"), ["a.conf", "b.conf"]);
+        assert!(resolve_files(&dir.0, "x[.conf").is_err());
+    }
+
+    #[test]
+    fn hidden() {
+        let dir = TestDir::new("hidden");
+        assert_eq!(dir.resolve(".*.conf"), [".hidden.conf"]);
+        assert_eq!(dir.resolve(".*/*.conf"), [".hidden_dir/e.conf"]);
+        assert!(!dir
+            .resolve("**/*.conf")
+            .contains(&".hidden_dir/e.conf".to_string()));
+    }
+
+    #[test]
+    fn absolute() {
+        let dir = TestDir::new("absolute");
+        let pattern = format!("{}/sub/*.conf", dir.0.display());
+        let files = resolve_files(Path::new("/not-existed"), &pattern).unwrap();
+        assert_eq!(files, [dir.0.join("sub/c.conf")]);
+    }
+}
@@ -83,6 +83,14 @@ impl HybridParser {
     where
         F: Fn(&yaml::Hash, Option<YamlDocPosition>) -> anyhow::Result<()>,
     {
+        if crate::file_glob::has_wildcard(path) {
+            for file in crate::resolve_files(&self.conf_dir, path)? {
+                self.load_file(&file, f)
+                    .context(format!("failed to load conf from file {}", file.display()))?;
+            }
+            return Ok(());
+        }
+
         let path = self.get_final_path(path)?;
         if path.is_dir() {
             // NOTE symlink is followed
@@ -107,9 +115,10 @@ impl HybridParser {
     where
         F: Fn(&yaml::Hash, Option<YamlDocPosition>) -> anyhow::Result<()>,
     {
-        for d_entry in std::fs::read_dir(path)? {
-            let d_entry = d_entry?;
-
+        // sort the entries so the load order is deterministic
+        let mut d_entries = std::fs::read_dir(path)?.collect::<Result<Vec<_>, _>>()?;
+        d_entries.sort_by_key(|d_entry| d_entry.file_name());
+        for d_entry in d_entries {
             let file_name = d_entry.path();
             if let Some(conf_extension) = &self.conf_extension {
                 let extension = match file_name.extension() {
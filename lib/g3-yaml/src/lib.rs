@@ -15,6 +15,8 @@
  */
 
 mod callback;
+mod env;
+mod file_glob;
 mod hash;
 mod hybrid;
 mod util;
@@ -24,6 +26,8 @@ pub mod key;
 pub mod value;
 
 pub use callback::YamlMapCallback;
+pub use env::{enable_env_interpolation, env_interpolation_enabled};
+pub use file_glob::resolve_files;
 pub use hash::{
     foreach_kv, get_required as hash_get_required, get_required_str as hash_get_required_str,
 };
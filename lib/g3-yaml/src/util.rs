@@ -20,7 +20,7 @@ use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use yaml_rust::{Yaml, YamlLoader};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -88,7 +88,11 @@ pub fn load_doc(position: &YamlDocPosition) -> anyhow::Result<Yaml> {
 
     let mut yaml_docs = YamlLoader::load_from_str(&conf)?;
     if yaml_docs.get(position.index).is_some() {
-        Ok(yaml_docs.remove(position.index))
+        let mut doc = yaml_docs.remove(position.index);
+        if crate::env::env_interpolation_enabled() {
+            crate::env::interpolate(&mut doc)?;
+        }
+        Ok(doc)
     } else {
         Err(anyhow!("no doc found in {position}"))
     }
@@ -101,8 +105,12 @@ where
     let mut conf = String::new();
     File::open(path)?.read_to_string(&mut conf)?;
 
-    let yaml_docs = YamlLoader::load_from_str(&conf)?;
-    for (i, doc) in yaml_docs.iter().enumerate() {
+    let mut yaml_docs = YamlLoader::load_from_str(&conf)?;
+    let interpolate = crate::env::env_interpolation_enabled();
+    for (i, doc) in yaml_docs.iter_mut().enumerate() {
+        if interpolate {
+            crate::env::interpolate(doc).context(format!("failed to interpolate doc {i}"))?;
+        }
         f(i, doc)?;
     }
     Ok(())
@@ -14,13 +14,17 @@
  * limitations under the License.
  */
 
+use std::path::Path;
 use std::str::FromStr;
 
 use anyhow::{anyhow, Context};
 use url::Url;
 use yaml_rust::{yaml, Yaml};
 
-use g3_icap_client::{IcapConnectionPoolConfig, IcapMethod, IcapServiceConfig};
+use g3_icap_client::{
+    IcapConnectionPoolConfig, IcapMethod, IcapServiceConfig, IcapServiceGroupConfig,
+    IcapServiceSelectPolicy,
+};
 
 fn set_icap_connection_pool_config(
     config: &mut IcapConnectionPoolConfig,
@@ -56,6 +60,7 @@ fn set_icap_connection_pool_config(
 fn as_icap_service_config(
     map: &yaml::Hash,
     method: IcapMethod,
+    lookup_dir: Option<&Path>,
 ) -> anyhow::Result<IcapServiceConfig> {
     const KEY_URL: &str = "url";
     let url = crate::hash_get_required(map, KEY_URL)?;
@@ -71,6 +76,20 @@ fn as_icap_service_config(
             config.set_tcp_keepalive(keepalive);
             Ok(())
         }
+        "tls_client" => {
+            let tls_config = crate::value::as_rustls_client_config_builder(v, lookup_dir).context(
+                format!("invalid rustls tls client config value for key {k}"),
+            )?;
+            config
+                .set_tls_client(tls_config)
+                .context("failed to set tls client config")
+        }
+        "tls_name" => {
+            let tls_name = crate::value::as_rustls_server_name(v)
+                .context(format!("invalid rustls server name value for key {k}"))?;
+            config.set_tls_name(tls_name);
+            Ok(())
+        }
         "icap_connection_pool" | "connection_pool" | "pool" => {
             set_icap_connection_pool_config(&mut config.connection_pool, v).context(format!(
                 "invalid icap connection pool config value for key {k}"
@@ -88,6 +107,12 @@ fn as_icap_service_config(
             config.set_preview_data_read_timeout(time);
             Ok(())
         }
+        "options_refresh_interval" => {
+            let interval = crate::humanize::as_duration(v)
+                .context(format!("invalid humanize duration value for key {k}"))?;
+            config.set_options_refresh_interval(interval);
+            Ok(())
+        }
         "respond_shared_names" => {
             if let Yaml::Array(seq) = v {
                 for (i, v) in seq.iter().enumerate() {
@@ -113,12 +138,16 @@ fn as_icap_service_config(
     Ok(config)
 }
 
-pub fn as_icap_reqmod_service_config(value: &Yaml) -> anyhow::Result<IcapServiceConfig> {
+fn as_icap_method_service_config(
+    value: &Yaml,
+    method: IcapMethod,
+    lookup_dir: Option<&Path>,
+) -> anyhow::Result<IcapServiceConfig> {
     match value {
-        Yaml::Hash(map) => as_icap_service_config(map, IcapMethod::Reqmod),
+        Yaml::Hash(map) => as_icap_service_config(map, method, lookup_dir),
         Yaml::String(s) => {
             let url = Url::from_str(s).map_err(|e| anyhow!("invalid url string: {e}"))?;
-            IcapServiceConfig::new(IcapMethod::Reqmod, url)
+            IcapServiceConfig::new(method, url)
         }
         _ => Err(anyhow!(
             "yaml value type for 'icap service config' should be 'map' or 'url str'"
@@ -126,15 +155,97 @@ pub fn as_icap_reqmod_service_config(value: &Yaml) -> anyhow::Result<IcapService
     }
 }
 
-pub fn as_icap_respmod_service_config(value: &Yaml) -> anyhow::Result<IcapServiceConfig> {
+fn as_icap_service_list(
+    value: &Yaml,
+    method: IcapMethod,
+    lookup_dir: Option<&Path>,
+) -> anyhow::Result<Vec<IcapServiceConfig>> {
+    if let Yaml::Array(seq) = value {
+        let mut services = Vec::with_capacity(seq.len());
+        for (i, v) in seq.iter().enumerate() {
+            let service = as_icap_method_service_config(v, method, lookup_dir)
+                .context(format!("invalid icap service config value for #{i}"))?;
+            services.push(service);
+        }
+        Ok(services)
+    } else {
+        let service = as_icap_method_service_config(value, method, lookup_dir)?;
+        Ok(vec![service])
+    }
+}
+
+fn as_icap_service_group_config(
+    value: &Yaml,
+    method: IcapMethod,
+    lookup_dir: Option<&Path>,
+) -> anyhow::Result<IcapServiceGroupConfig> {
     match value {
-        Yaml::Hash(map) => as_icap_service_config(map, IcapMethod::Respmod),
-        Yaml::String(s) => {
-            let url = Url::from_str(s).map_err(|e| anyhow!("invalid url string: {e}"))?;
-            IcapServiceConfig::new(IcapMethod::Respmod, url)
+        Yaml::Hash(map) if map.contains_key(&Yaml::String("services".to_string())) => {
+            let v = crate::hash_get_required(map, "services")?;
+            let services = as_icap_service_list(v, method, lookup_dir)
+                .context("invalid icap service list value for key services")?;
+            let mut config = IcapServiceGroupConfig::new(services)?;
+
+            crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+                "services" => Ok(()),
+                "select_policy" | "pick_policy" => {
+                    let s = crate::value::as_string(v)?;
+                    let policy = IcapServiceSelectPolicy::from_str(&s)
+                        .map_err(|_| anyhow!("invalid icap service select policy {s}"))?;
+                    config.set_select_policy(policy);
+                    Ok(())
+                }
+                "unhealthy_duration" => {
+                    let duration = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_unhealthy_duration(duration);
+                    Ok(())
+                }
+                "bypass" => {
+                    let bypass = crate::value::as_bool(v)?;
+                    config.set_bypass(bypass);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+
+            Ok(config)
+        }
+        Yaml::Array(_) => {
+            let services = as_icap_service_list(value, method, lookup_dir)?;
+            IcapServiceGroupConfig::new(services)
+        }
+        _ => {
+            let service = as_icap_method_service_config(value, method, lookup_dir)?;
+            Ok(IcapServiceGroupConfig::from(service))
         }
-        _ => Err(anyhow!(
-            "yaml value type for 'icap service config' should be 'map' or 'url str'"
-        )),
     }
 }
+
+pub fn as_icap_reqmod_service_config(
+    value: &Yaml,
+    lookup_dir: Option<&Path>,
+) -> anyhow::Result<IcapServiceConfig> {
+    as_icap_method_service_config(value, IcapMethod::Reqmod, lookup_dir)
+}
+
+pub fn as_icap_respmod_service_config(
+    value: &Yaml,
+    lookup_dir: Option<&Path>,
+) -> anyhow::Result<IcapServiceConfig> {
+    as_icap_method_service_config(value, IcapMethod::Respmod, lookup_dir)
+}
+
+pub fn as_icap_reqmod_service_group_config(
+    value: &Yaml,
+    lookup_dir: Option<&Path>,
+) -> anyhow::Result<IcapServiceGroupConfig> {
+    as_icap_service_group_config(value, IcapMethod::Reqmod, lookup_dir)
+}
+
+pub fn as_icap_respmod_service_group_config(
+    value: &Yaml,
+    lookup_dir: Option<&Path>,
+) -> anyhow::Result<IcapServiceGroupConfig> {
+    as_icap_service_group_config(value, IcapMethod::Respmod, lookup_dir)
+}
@@ -15,4 +15,7 @@
  */
 
 mod icap;
-pub use icap::{as_icap_reqmod_service_config, as_icap_respmod_service_config};
+pub use icap::{
+    as_icap_reqmod_service_config, as_icap_reqmod_service_group_config,
+    as_icap_respmod_service_config, as_icap_respmod_service_group_config,
+};
@@ -0,0 +1,56 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use g3_dpi::DnsInterceptionConfig;
+
+pub fn as_dns_interception_config(value: &Yaml) -> anyhow::Result<DnsInterceptionConfig> {
+    if let Yaml::Hash(map) = value {
+        let mut config = DnsInterceptionConfig::default();
+
+        crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+            "idle_timeout" => {
+                config.idle_timeout = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "message_read_timeout" => {
+                config.message_read_timeout = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "log_queries" => {
+                config.log_queries =
+                    crate::value::as_bool(v).context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
+            "blocked_domains" | "block_domains" => {
+                config.blocked_domains = crate::value::as_list(v, crate::value::as_domain)
+                    .context(format!("invalid domain list value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        Ok(config)
+    } else {
+        Err(anyhow!(
+            "yaml value type for 'dns interception config' should be 'map'"
+        ))
+    }
+}
@@ -43,6 +43,12 @@ pub fn as_stream_dump_config(value: &Yaml) -> anyhow::Result<StreamDumpConfig> {
                     config.packet_size = crate::value::as_usize(v)?;
                     Ok(())
                 }
+                "file" | "pcapng_file" => {
+                    let path = crate::value::as_absolute_path(v)
+                        .context(format!("invalid absolute path value for key {k}"))?;
+                    config.file = Some(path);
+                    Ok(())
+                }
                 _ => Err(anyhow!("invalid key {k}")),
             })?;
 
@@ -75,6 +75,12 @@ pub fn as_protocol_inspection_config(value: &Yaml) -> anyhow::Result<ProtocolIns
                 config.set_data0_buffer_size(size);
                 Ok(())
             }
+            "data0_max_buffer_size" => {
+                let size = crate::humanize::as_usize(v)
+                    .context(format!("invalid humanize usize value for key {k}"))?;
+                config.set_data0_max_buffer_size(size);
+                Ok(())
+            }
             "inspect_max_depth" => {
                 let depth = crate::value::as_usize(v)?;
                 config.set_max_depth(depth);
@@ -0,0 +1,48 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use g3_dpi::WebSocketInterceptionConfig;
+
+pub fn as_websocket_interception_config(
+    value: &Yaml,
+) -> anyhow::Result<WebSocketInterceptionConfig> {
+    if let Yaml::Hash(map) = value {
+        let mut config = WebSocketInterceptionConfig::default();
+
+        crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+            "log_messages" | "log_message" => {
+                config.log_messages = crate::value::as_bool(v)?;
+                Ok(())
+            }
+            "max_message_size" => {
+                let size = crate::humanize::as_usize(v)
+                    .context(format!("invalid humanize usize value for key {k}"))?;
+                config.max_message_size = if size > 0 { Some(size) } else { None };
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        Ok(config)
+    } else {
+        Err(anyhow!(
+            "yaml value type for 'websocket interception config' should be 'map'"
+        ))
+    }
+}
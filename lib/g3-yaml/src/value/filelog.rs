@@ -0,0 +1,107 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use g3_filelog::{FileLogConfig, FileLogFormat, W3cField};
+
+fn as_w3c_field(value: &Yaml) -> anyhow::Result<W3cField> {
+    match value {
+        Yaml::String(s) => Ok(W3cField::new(s)),
+        Yaml::Hash(map) if map.len() == 1 => {
+            let (k, v) = map.iter().next().unwrap();
+            let name = crate::value::as_string(k).context("invalid w3c field name")?;
+            let key = crate::value::as_string(v)
+                .context(format!("invalid log key value for w3c field {name}"))?;
+            Ok(W3cField::with_key(&name, &key))
+        }
+        _ => Err(anyhow!(
+            "the yaml value type for 'w3c field' should be 'string' or a single entry 'map'"
+        )),
+    }
+}
+
+pub fn as_file_log_config(value: &Yaml) -> anyhow::Result<FileLogConfig> {
+    let config = match value {
+        Yaml::Hash(map) => {
+            let v = crate::hash_get_required(map, "path")?;
+            let path = crate::value::as_absolute_path(v).context("invalid value for key path")?;
+            let mut config = FileLogConfig::new(&path);
+
+            crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+                "path" => Ok(()),
+                "rotate_interval" => {
+                    let interval = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_rotate_interval(interval);
+                    Ok(())
+                }
+                "rotate_size" => {
+                    let size = crate::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                    config.set_rotate_size(size as u64);
+                    Ok(())
+                }
+                "format" => {
+                    let s = crate::value::as_string(v)?;
+                    let format = FileLogFormat::from_str(&s)
+                        .map_err(|_| anyhow!("invalid file log format {s}"))?;
+                    config.set_format(format);
+                    Ok(())
+                }
+                "rotate_keep" | "keep" => {
+                    let keep = crate::value::as_usize(v)?;
+                    config.set_rotate_keep(keep);
+                    Ok(())
+                }
+                "rotate_max_age" | "max_age" => {
+                    let age = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_rotate_max_age(age);
+                    Ok(())
+                }
+                "compress" => {
+                    let compress = crate::value::as_bool(v)?;
+                    config.set_compress(compress);
+                    Ok(())
+                }
+                "fields" | "w3c_fields" => {
+                    let fields = crate::value::as_list(v, as_w3c_field)
+                        .context(format!("invalid w3c field list value for key {k}"))?;
+                    config.set_fields(fields);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+            config
+        }
+        Yaml::String(_) => {
+            let path = crate::value::as_absolute_path(value)?;
+            FileLogConfig::new(&path)
+        }
+        _ => {
+            return Err(anyhow!(
+                "yaml value type for 'FileLogConfig' should be 'map' or 'string'"
+            ))
+        }
+    };
+
+    config.check()?;
+    Ok(config)
+}
@@ -0,0 +1,153 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use g3_kafka::{KafkaCompression, KafkaProducerConfig, KAFKA_DEFAULT_PORT};
+
+fn as_kafka_compression(v: &Yaml) -> anyhow::Result<KafkaCompression> {
+    if let Yaml::String(s) = v {
+        KafkaCompression::from_str(s)
+    } else {
+        Err(anyhow!(
+            "yaml value type for 'kafka compression' should be 'string'"
+        ))
+    }
+}
+
+pub fn as_kafka_producer_config(
+    value: &Yaml,
+    client_id: &str,
+) -> anyhow::Result<KafkaProducerConfig> {
+    let mut config = match value {
+        Yaml::Hash(map) => {
+            let mut config = KafkaProducerConfig::default();
+            config.set_client_id(client_id.to_string());
+
+            crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+                "bootstrap_servers" | "brokers" => {
+                    let servers = crate::value::as_list(v, |v| {
+                        crate::value::as_upstream_addr(v, KAFKA_DEFAULT_PORT)
+                    })
+                    .context(format!("invalid upstream addr list value for key {k}"))?;
+                    config.set_bootstrap_servers(servers);
+                    Ok(())
+                }
+                "client_id" => {
+                    let id = crate::value::as_string(v)
+                        .context(format!("invalid string value for key {k}"))?;
+                    config.set_client_id(id);
+                    Ok(())
+                }
+                "topic" => {
+                    let topic = crate::value::as_string(v)
+                        .context(format!("invalid string value for key {k}"))?;
+                    config.set_topic(topic);
+                    Ok(())
+                }
+                "acks" => {
+                    let acks = crate::value::as_i32(v)
+                        .context(format!("invalid i32 value for key {k}"))?;
+                    let acks =
+                        i16::try_from(acks).map_err(|_| anyhow!("out of range acks value"))?;
+                    config.set_acks(acks);
+                    Ok(())
+                }
+                "compression" => {
+                    let compression = as_kafka_compression(v)
+                        .context(format!("invalid kafka compression value for key {k}"))?;
+                    config.set_compression(compression);
+                    Ok(())
+                }
+                "batch_size" => {
+                    let size = crate::value::as_usize(v)
+                        .context(format!("invalid usize value for key {k}"))?;
+                    config.set_batch_size(size);
+                    Ok(())
+                }
+                "batch_bytes" => {
+                    let size = crate::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                    config.set_batch_bytes(size);
+                    Ok(())
+                }
+                "linger" | "flush_interval" => {
+                    let interval = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_linger(interval);
+                    Ok(())
+                }
+                "connect_timeout" => {
+                    let timeout = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_connect_timeout(timeout);
+                    Ok(())
+                }
+                "request_timeout" => {
+                    let timeout = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_request_timeout(timeout);
+                    Ok(())
+                }
+                "retry_delay" => {
+                    let delay = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_retry_delay(delay);
+                    Ok(())
+                }
+                "retry_queue_len" => {
+                    let len = crate::value::as_usize(v)
+                        .context(format!("invalid usize value for key {k}"))?;
+                    config.set_retry_queue_len(len);
+                    Ok(())
+                }
+                "metadata_refresh_interval" => {
+                    let interval = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_metadata_refresh_interval(interval);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+
+            config
+        }
+        Yaml::String(_) | Yaml::Array(_) => {
+            let servers = crate::value::as_list(value, |v| {
+                crate::value::as_upstream_addr(v, KAFKA_DEFAULT_PORT)
+            })?;
+            let mut config = KafkaProducerConfig::new(servers);
+            config.set_client_id(client_id.to_string());
+            config
+        }
+        Yaml::Null => {
+            let mut config = KafkaProducerConfig::default();
+            config.set_client_id(client_id.to_string());
+            config
+        }
+        _ => {
+            return Err(anyhow!(
+                "yaml value type for 'KafkaProducerConfig' should be 'map'"
+            ))
+        }
+    };
+
+    config.check()?;
+    Ok(config)
+}
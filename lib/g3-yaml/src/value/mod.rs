@@ -38,12 +38,18 @@ pub use primary::{
 };
 pub use random::as_random_ratio;
 pub use rate_limit::as_rate_limit_quota;
-pub use speed_limit::{as_tcp_sock_speed_limit, as_udp_sock_speed_limit};
+pub use speed_limit::{
+    as_global_stream_speed_limit, as_stream_delay_config, as_tcp_sock_speed_limit,
+    as_udp_pacing_config, as_udp_sock_speed_limit,
+};
 
 #[cfg(feature = "audit")]
 mod audit;
 #[cfg(feature = "audit")]
-pub use audit::{as_icap_reqmod_service_config, as_icap_respmod_service_config};
+pub use audit::{
+    as_icap_reqmod_service_config, as_icap_reqmod_service_group_config,
+    as_icap_respmod_service_config, as_icap_respmod_service_group_config,
+};
 
 #[cfg(feature = "acl-rule")]
 pub mod acl;
@@ -60,6 +66,16 @@ mod fluentd;
 #[cfg(feature = "fluentd")]
 pub use fluentd::as_fluentd_client_config;
 
+#[cfg(feature = "kafka")]
+mod kafka;
+#[cfg(feature = "kafka")]
+pub use kafka::as_kafka_producer_config;
+
+#[cfg(feature = "filelog")]
+mod filelog;
+#[cfg(feature = "filelog")]
+pub use filelog::as_file_log_config;
+
 #[cfg(feature = "statsd")]
 mod statsd;
 #[cfg(feature = "statsd")]
@@ -25,7 +25,7 @@ use yaml_rust::Yaml;
 use ip_network::IpNetwork;
 
 use g3_types::collection::WeightedValue;
-use g3_types::net::{Host, UpstreamAddr, WeightedUpstreamAddr};
+use g3_types::net::{Host, Nat64Prefix, UpstreamAddr, WeightedUpstreamAddr};
 
 pub fn as_env_sockaddr(value: &Yaml) -> anyhow::Result<SocketAddr> {
     if let Yaml::String(s) = value {
@@ -107,6 +107,16 @@ pub fn as_ipv6addr(value: &Yaml) -> anyhow::Result<Ipv6Addr> {
     }
 }
 
+pub fn as_nat64_prefix(value: &Yaml) -> anyhow::Result<Nat64Prefix> {
+    match value {
+        Yaml::String(s) => Nat64Prefix::from_str(s),
+        Yaml::Boolean(true) => Ok(Nat64Prefix::default()),
+        _ => Err(anyhow!(
+            "yaml value type for 'Nat64Prefix' should be 'string' or 'true'"
+        )),
+    }
+}
+
 #[cfg(feature = "acl-rule")]
 pub fn as_ip_network(value: &Yaml) -> anyhow::Result<IpNetwork> {
     if let Yaml::String(s) = value {
@@ -17,6 +17,7 @@
 mod base;
 mod buf;
 mod haproxy;
+mod pool;
 mod port;
 mod proxy;
 mod tcp;
@@ -32,11 +33,12 @@ mod ftp;
 mod dns;
 
 pub use base::{
-    as_domain, as_env_sockaddr, as_host, as_ipaddr, as_ipv4addr, as_ipv6addr, as_sockaddr,
-    as_upstream_addr, as_url, as_weighted_sockaddr, as_weighted_upstream_addr,
+    as_domain, as_env_sockaddr, as_host, as_ipaddr, as_ipv4addr, as_ipv6addr, as_nat64_prefix,
+    as_sockaddr, as_upstream_addr, as_url, as_weighted_sockaddr, as_weighted_upstream_addr,
 };
 pub use buf::as_socket_buffer_config;
 pub use haproxy::as_proxy_protocol_version;
+pub use pool::as_connection_pool_config;
 pub use port::{as_port_range, as_ports};
 pub use proxy::as_proxy_request_type;
 pub use tcp::{
@@ -0,0 +1,61 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use g3_types::net::ConnectionPoolConfig;
+
+pub fn as_connection_pool_config(value: &Yaml) -> anyhow::Result<ConnectionPoolConfig> {
+    if let Yaml::Hash(map) = value {
+        let mut config = ConnectionPoolConfig::default();
+
+        crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+            "max_idle_count" | "max_idle" | "pool_size" => {
+                let count = crate::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                config.set_max_idle_count(count);
+                Ok(())
+            }
+            "max_total_idle_count" | "max_total_idle" => {
+                let count = crate::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                config.set_max_total_idle_count(count);
+                Ok(())
+            }
+            "idle_timeout" | "idle_expire" => {
+                let timeout = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                config.set_idle_timeout(timeout);
+                Ok(())
+            }
+            "max_lifetime" => {
+                let lifetime = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                config.set_max_lifetime(lifetime);
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        config.check()?;
+        Ok(config)
+    } else {
+        Err(anyhow!(
+            "yaml value type for 'connection pool config' should be 'map'"
+        ))
+    }
+}
@@ -256,12 +256,35 @@ pub fn as_tcp_misc_sock_opts(v: &Yaml) -> anyhow::Result<TcpMiscSockOpts> {
                 config.type_of_service = Some(tos);
                 Ok(())
             }
+            "traffic_class" | "tclass" => {
+                let tclass =
+                    crate::value::as_u8(v).context(format!("invalid u8 value for key {k}"))?;
+                config.traffic_class = Some(tclass);
+                Ok(())
+            }
+            "dscp" => {
+                let dscp =
+                    crate::value::as_u8(v).context(format!("invalid u8 value for key {k}"))?;
+                if dscp > 63 {
+                    return Err(anyhow!(
+                        "invalid dscp value {dscp}, should be in range 0-63"
+                    ));
+                }
+                config.set_dscp(dscp);
+                Ok(())
+            }
             "netfilter_mark" | "mark" => {
                 let mark =
                     crate::value::as_u32(v).context(format!("invalid u32 value for key {k}"))?;
                 config.netfilter_mark = Some(mark);
                 Ok(())
             }
+            "user_timeout" => {
+                let timeout = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                config.user_timeout = Some(timeout);
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         })?;
 
@@ -272,3 +295,47 @@ pub fn as_tcp_misc_sock_opts(v: &Yaml) -> anyhow::Result<TcpMiscSockOpts> {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse_dscp(value: &str) -> anyhow::Result<TcpMiscSockOpts> {
+        let docs = YamlLoader::load_from_str(&format!("dscp: {value}")).unwrap();
+        as_tcp_misc_sock_opts(&docs[0])
+    }
+
+    #[test]
+    fn misc_sock_opts_dscp() {
+        let opts = parse_dscp("0").unwrap();
+        assert_eq!(opts.type_of_service, Some(0));
+        assert_eq!(opts.traffic_class, Some(0));
+
+        let opts = parse_dscp("46").unwrap();
+        assert_eq!(opts.type_of_service, Some(46 << 2));
+        assert_eq!(opts.traffic_class, Some(46 << 2));
+
+        let opts = parse_dscp("\"63\"").unwrap();
+        assert_eq!(opts.type_of_service, Some(63 << 2));
+
+        assert!(parse_dscp("64").is_err());
+        assert!(parse_dscp("256").is_err());
+        assert!(parse_dscp("-1").is_err());
+        assert!(parse_dscp("4.5").is_err());
+        assert!(parse_dscp("ef").is_err());
+        assert!(parse_dscp("true").is_err());
+    }
+
+    #[test]
+    fn misc_sock_opts_dscp_override() {
+        let docs = YamlLoader::load_from_str("{tos: 4, dscp: 10}").unwrap();
+        let opts = as_tcp_misc_sock_opts(&docs[0]).unwrap();
+        assert_eq!(opts.type_of_service, Some(10 << 2));
+
+        let docs = YamlLoader::load_from_str("{dscp: 10, tclass: 4}").unwrap();
+        let opts = as_tcp_misc_sock_opts(&docs[0]).unwrap();
+        assert_eq!(opts.type_of_service, Some(10 << 2));
+        assert_eq!(opts.traffic_class, Some(4));
+    }
+}
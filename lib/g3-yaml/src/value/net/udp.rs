@@ -39,6 +39,23 @@ pub fn as_udp_misc_sock_opts(v: &Yaml) -> anyhow::Result<UdpMiscSockOpts> {
                 config.type_of_service = Some(tos);
                 Ok(())
             }
+            "traffic_class" | "tclass" => {
+                let tclass =
+                    crate::value::as_u8(v).context(format!("invalid u8 value for key {k}"))?;
+                config.traffic_class = Some(tclass);
+                Ok(())
+            }
+            "dscp" => {
+                let dscp =
+                    crate::value::as_u8(v).context(format!("invalid u8 value for key {k}"))?;
+                if dscp > 63 {
+                    return Err(anyhow!(
+                        "invalid dscp value {dscp}, should be in range 0-63"
+                    ));
+                }
+                config.set_dscp(dscp);
+                Ok(())
+            }
             "netfilter_mark" | "mark" => {
                 let mark =
                     crate::value::as_u32(v).context(format!("invalid u32 value for key {k}"))?;
@@ -158,3 +175,23 @@ pub fn as_udp_listen_config(value: &Yaml) -> anyhow::Result<UdpListenConfig> {
     config.check()?;
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn misc_sock_opts_dscp() {
+        let docs = YamlLoader::load_from_str("dscp: 46").unwrap();
+        let opts = as_udp_misc_sock_opts(&docs[0]).unwrap();
+        assert_eq!(opts.type_of_service, Some(46 << 2));
+        assert_eq!(opts.traffic_class, Some(46 << 2));
+
+        let docs = YamlLoader::load_from_str("dscp: 64").unwrap();
+        assert!(as_udp_misc_sock_opts(&docs[0]).is_err());
+
+        let docs = YamlLoader::load_from_str("dscp: af41").unwrap();
+        assert!(as_udp_misc_sock_opts(&docs[0]).is_err());
+    }
+}
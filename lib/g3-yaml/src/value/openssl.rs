@@ -331,6 +331,12 @@ fn set_openssl_tls_client_config_builder(
                 builder.set_session_cache_each_capacity(cap);
                 Ok(())
             }
+            "session_cache_ttl" => {
+                let ttl = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                builder.set_session_cache_ttl(ttl);
+                Ok(())
+            }
             "supported_groups" => {
                 let groups = crate::value::as_string(v)?;
                 builder.set_supported_groups(groups);
@@ -433,6 +439,12 @@ pub fn as_tls_interception_client_config_builder(
                 builder.set_session_cache_each_capacity(cap);
                 Ok(())
             }
+            "session_cache_ttl" => {
+                let ttl = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                builder.set_session_cache_ttl(ttl);
+                Ok(())
+            }
             "supported_groups" => {
                 let groups = crate::value::as_string(v)?;
                 builder.set_supported_groups(groups);
@@ -44,6 +44,26 @@ pub fn as_pick_strategy(v: &Yaml) -> anyhow::Result<PickStrategy> {
     }
 }
 
+fn as_family_weight(v: &Yaml) -> anyhow::Result<(u8, u8)> {
+    if let Yaml::Hash(map) = v {
+        let mut weight = (0, 0);
+        crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+            "ipv4" | "v4" => {
+                weight.0 = crate::value::as_u8(v)?;
+                Ok(())
+            }
+            "ipv6" | "v6" => {
+                weight.1 = crate::value::as_u8(v)?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+        Ok(weight)
+    } else {
+        Err(anyhow!("invalid yaml value type for family weight"))
+    }
+}
+
 pub fn as_resolve_strategy(v: &Yaml) -> anyhow::Result<ResolveStrategy> {
     let mut config = ResolveStrategy::default();
 
@@ -62,6 +82,13 @@ pub fn as_resolve_strategy(v: &Yaml) -> anyhow::Result<ResolveStrategy> {
                     config.pick = as_pick_strategy(v)?;
                     Ok(())
                 }
+                "family_weight" => {
+                    config.family_weight = Some(
+                        as_family_weight(v)
+                            .context(format!("invalid family weight value for key {k}"))?,
+                    );
+                    Ok(())
+                }
                 _ => Err(anyhow!("invalid key {k}")),
             })?;
             Ok(config)
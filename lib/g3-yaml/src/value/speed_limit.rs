@@ -17,7 +17,10 @@
 use anyhow::{anyhow, Context};
 use yaml_rust::Yaml;
 
-use g3_types::net::{TcpSockSpeedLimitConfig, UdpSockSpeedLimitConfig};
+use g3_types::net::{
+    GlobalStreamSpeedLimitConfig, StreamDelayConfig, TcpSockSpeedLimitConfig, UdpPacingConfig,
+    UdpSockSpeedLimitConfig,
+};
 
 pub fn as_tcp_sock_speed_limit(v: &Yaml) -> anyhow::Result<TcpSockSpeedLimitConfig> {
     let mut config = TcpSockSpeedLimitConfig::default();
@@ -45,6 +48,23 @@ pub fn as_tcp_sock_speed_limit(v: &Yaml) -> anyhow::Result<TcpSockSpeedLimitConf
                         .context(format!("invalid humanize usize value for key {k}"))?;
                     Ok(())
                 }
+                "upload_burst" | "north_burst" => {
+                    config.burst_north = crate::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                    Ok(())
+                }
+                "download_burst" | "south_burst" => {
+                    config.burst_south = crate::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                    Ok(())
+                }
+                "burst" => {
+                    let burst = crate::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                    config.burst_north = burst;
+                    config.burst_south = burst;
+                    Ok(())
+                }
                 _ => Err(anyhow!("invalid key {k}")),
             })?;
         }
@@ -90,6 +110,26 @@ pub fn as_udp_sock_speed_limit(v: &Yaml) -> anyhow::Result<UdpSockSpeedLimitConf
                         .context(format!("invalid humanize usize value for key {k}"))?;
                     Ok(())
                 }
+                "upload_burst_packets" | "north_burst_packets" => {
+                    config.burst_north_packets = crate::value::as_usize(v)
+                        .context(format!("invalid usize value for key {k}"))?;
+                    Ok(())
+                }
+                "download_burst_packets" | "south_burst_packets" => {
+                    config.burst_south_packets = crate::value::as_usize(v)
+                        .context(format!("invalid usize value for key {k}"))?;
+                    Ok(())
+                }
+                "upload_burst_bytes" | "north_burst_bytes" => {
+                    config.burst_north_bytes = crate::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                    Ok(())
+                }
+                "download_burst_bytes" | "south_burst_bytes" => {
+                    config.burst_south_bytes = crate::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                    Ok(())
+                }
                 _ => Err(anyhow!("invalid key {k}")),
             })?;
         }
@@ -98,3 +138,100 @@ pub fn as_udp_sock_speed_limit(v: &Yaml) -> anyhow::Result<UdpSockSpeedLimitConf
     config.validate()?;
     Ok(config)
 }
+
+pub fn as_global_stream_speed_limit(v: &Yaml) -> anyhow::Result<GlobalStreamSpeedLimitConfig> {
+    let mut config = match v {
+        Yaml::String(_) | Yaml::Integer(_) => {
+            let limit = crate::humanize::as_usize(v).context("invalid humanize usize value")?;
+            GlobalStreamSpeedLimitConfig::per_second(limit as u64)
+        }
+        Yaml::Hash(map) => {
+            let mut config = GlobalStreamSpeedLimitConfig::per_second(0);
+            crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+                "replenish_bytes" | "rate" => {
+                    let size = crate::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                    config.set_replenish_bytes(size as u64);
+                    Ok(())
+                }
+                "max_burst_bytes" | "burst" => {
+                    let size = crate::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                    config.set_max_burst_bytes(size as u64);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+            config
+        }
+        _ => return Err(anyhow!("invalid yaml value type")),
+    };
+    config.check()?;
+    Ok(config)
+}
+
+pub fn as_udp_pacing_config(v: &Yaml) -> anyhow::Result<UdpPacingConfig> {
+    let mut config = match v {
+        Yaml::String(_) | Yaml::Integer(_) => {
+            let rate = crate::humanize::as_usize(v).context("invalid humanize usize value")?;
+            UdpPacingConfig::per_second(rate as u64)
+        }
+        Yaml::Hash(map) => {
+            let mut config = UdpPacingConfig::per_second(0);
+            crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+                "rate_bytes" | "rate" => {
+                    let size = crate::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                    config.set_rate_bytes(size as u64);
+                    Ok(())
+                }
+                "burst_bytes" | "burst" => {
+                    let size = crate::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                    config.set_burst_bytes(size as u64);
+                    Ok(())
+                }
+                "queue_size" | "queue" => {
+                    let size = crate::value::as_usize(v)
+                        .context(format!("invalid usize value for key {k}"))?;
+                    config.set_queue_size(size);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+            config
+        }
+        _ => return Err(anyhow!("invalid yaml value type")),
+    };
+    config.check()?;
+    Ok(config)
+}
+
+pub fn as_stream_delay_config(v: &Yaml) -> anyhow::Result<StreamDelayConfig> {
+    let mut config = StreamDelayConfig::default();
+    match v {
+        Yaml::String(_) | Yaml::Integer(_) | Yaml::Real(_) => {
+            let latency = crate::humanize::as_duration(v).context("invalid humanize duration")?;
+            config.set_latency(latency);
+        }
+        Yaml::Hash(map) => {
+            crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+                "latency" | "delay" => {
+                    let latency = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_latency(latency);
+                    Ok(())
+                }
+                "jitter" => {
+                    let jitter = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_jitter(jitter);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+        }
+        _ => return Err(anyhow!("invalid yaml value type")),
+    }
+    Ok(config)
+}
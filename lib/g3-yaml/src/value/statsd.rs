@@ -21,7 +21,7 @@ use std::str::FromStr;
 use anyhow::{anyhow, Context};
 use yaml_rust::Yaml;
 
-use g3_statsd_client::{StatsdBackend, StatsdClientConfig};
+use g3_statsd_client::{StatsdBackend, StatsdClientConfig, StatsdTagFormat};
 use g3_types::metrics::MetricsName;
 
 fn as_statsd_backend_udp(v: &Yaml) -> anyhow::Result<StatsdBackend> {
@@ -131,12 +131,31 @@ pub fn as_statsd_client_config(
                     Err(anyhow!("yaml value type for key {k} should be 'map'"))
                 }
             }
+            "prometheus" | "prometheus_listen" => {
+                let addr = crate::value::as_env_sockaddr(v)
+                    .context(format!("invalid socket address value for key {k}"))?;
+                config.set_prometheus_listen(addr);
+                Ok(())
+            }
             "prefix" => {
                 let prefix = crate::value::as_metrics_name(v)
                     .context(format!("invalid metrics name value for key {k}"))?;
                 config.set_prefix(prefix);
                 Ok(())
             }
+            "tag_format" => {
+                let s = crate::value::as_string(v)?;
+                let format = StatsdTagFormat::from_str(&s)
+                    .map_err(|_| anyhow!("invalid statsd tag format {s}"))?;
+                config.set_tag_format(format);
+                Ok(())
+            }
+            "global_tags" => {
+                let tags = crate::value::as_static_metrics_tags(v)
+                    .context(format!("invalid static metrics tags value for key {k}"))?;
+                config.set_global_tags(tags);
+                Ok(())
+            }
             "emit_duration" => {
                 config.emit_duration = crate::humanize::as_duration(v)
                     .context(format!("invalid humanize duration value for key {k}"))?;
@@ -16,17 +16,76 @@
 
 use std::convert::TryFrom;
 use std::net::{IpAddr, SocketAddr};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::{anyhow, Context};
 use yaml_rust::Yaml;
 
-use g3_syslog::{SyslogBackendBuilder, SyslogBuilder, SyslogFormatterKind};
+use g3_types::net::RustlsClientConfigBuilder;
+
+use g3_syslog::{
+    Rfc5424SdConfig, SyslogBackendBuilder, SyslogBuilder, SyslogFormatterKind, SyslogTcpConfig,
+};
+
+fn as_syslog_sd_name(value: &Yaml) -> anyhow::Result<String> {
+    let s = crate::value::as_string(value)?;
+    let name = match s.split_once('@') {
+        Some((name, _)) => name,
+        None => s.as_str(),
+    };
+    if name.is_empty() || name.len() > 32 {
+        return Err(anyhow!("the length of SD-NAME should be in range 1..=32"));
+    }
+    if let Some(c) = s
+        .chars()
+        .find(|c| !c.is_ascii_graphic() || matches!(c, '=' | ']' | '"'))
+    {
+        return Err(anyhow!("invalid char {c:?} found in SD-ID"));
+    }
+    Ok(s)
+}
+
+fn as_syslog_sd_config(value: &Yaml) -> anyhow::Result<Rfc5424SdConfig> {
+    let mut config = Rfc5424SdConfig::default();
+    match value {
+        Yaml::Hash(map) => {
+            crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+                "default_id" | "id" => {
+                    let id =
+                        as_syslog_sd_name(v).context(format!("invalid SD-ID value for key {k}"))?;
+                    config.set_default_id(id);
+                    Ok(())
+                }
+                "groups" | "field_groups" => {
+                    if let Yaml::Hash(map) = v {
+                        for (id, fields) in map.iter() {
+                            let id = as_syslog_sd_name(id).context("invalid SD-ID as group key")?;
+                            let fields = crate::value::as_list(fields, crate::value::as_string)
+                                .context(format!("invalid field list for SD-ID {id}"))?;
+                            config.add_group(id, fields);
+                        }
+                        Ok(())
+                    } else {
+                        Err(anyhow!("yaml value type for key {k} should be 'map'"))
+                    }
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+        }
+        Yaml::String(_) => {
+            let id = as_syslog_sd_name(value)?;
+            config.set_default_id(id);
+        }
+        _ => return Err(anyhow!("invalid yaml value for rfc5424 structured data")),
+    }
+    Ok(config)
+}
 
 fn as_syslog_format_rfc5424(value: &Yaml) -> anyhow::Result<SyslogFormatterKind> {
     let mut enterprise_id = 0i32;
     let mut message_id: Option<String> = None;
+    let mut sd_config = Rfc5424SdConfig::default();
 
     match value {
         Yaml::Hash(map) => {
@@ -42,17 +101,34 @@ fn as_syslog_format_rfc5424(value: &Yaml) -> anyhow::Result<SyslogFormatterKind>
                     );
                     Ok(())
                 }
+                "structured_data" | "sd" => {
+                    sd_config =
+                        as_syslog_sd_config(v).context(format!("invalid value for key {k}"))?;
+                    Ok(())
+                }
                 _ => Err(anyhow!("invalid key {k}")),
             })?;
-            Ok(SyslogFormatterKind::Rfc5424(enterprise_id, message_id))
+            Ok(SyslogFormatterKind::Rfc5424(
+                enterprise_id,
+                message_id,
+                sd_config,
+            ))
         }
         Yaml::Integer(i) => {
             enterprise_id = i32::try_from(*i).map_err(|e| anyhow!("invalid enterprise_id: {e}"))?;
-            Ok(SyslogFormatterKind::Rfc5424(enterprise_id, message_id))
+            Ok(SyslogFormatterKind::Rfc5424(
+                enterprise_id,
+                message_id,
+                sd_config,
+            ))
         }
         Yaml::String(s) => {
             message_id = Some(s.to_string());
-            Ok(SyslogFormatterKind::Rfc5424(enterprise_id, message_id))
+            Ok(SyslogFormatterKind::Rfc5424(
+                enterprise_id,
+                message_id,
+                sd_config,
+            ))
         }
         _ => Err(anyhow!("invalid yaml value for rfc5424 syslog format")),
     }
@@ -94,6 +170,101 @@ fn as_syslog_backend_udp(value: &Yaml) -> anyhow::Result<SyslogBackendBuilder> {
     }
 }
 
+fn as_syslog_backend_tcp(
+    value: &Yaml,
+    lookup_dir: Option<&Path>,
+    enable_tls: bool,
+) -> anyhow::Result<SyslogBackendBuilder> {
+    match value {
+        Yaml::Hash(map) => {
+            let mut addr: Option<SocketAddr> = None;
+            let mut tls_client: Option<RustlsClientConfigBuilder> = None;
+            let mut config = SyslogTcpConfig::new(SocketAddr::from(([127, 0, 0, 1], 0)));
+
+            crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+                "address" | "addr" => {
+                    addr = Some(crate::value::as_env_sockaddr(v).context(format!(
+                        "invalid syslog tcp peer socket address value for key {k}"
+                    ))?);
+                    Ok(())
+                }
+                "bind_ip" | "bind" => {
+                    let ip = crate::value::as_ipaddr(v)
+                        .context(format!("invalid ip address value for key {k}"))?;
+                    config.set_bind_ip(ip);
+                    Ok(())
+                }
+                "tls_client" => {
+                    let builder = crate::value::as_rustls_client_config_builder(v, lookup_dir)
+                        .context(format!(
+                            "invalid rustls tls client config value for key {k}"
+                        ))?;
+                    tls_client = Some(builder);
+                    Ok(())
+                }
+                "tls_name" => {
+                    let tls_name = crate::value::as_rustls_server_name(v)
+                        .context(format!("invalid rustls server name value for key {k}"))?;
+                    config.set_tls_name(tls_name);
+                    Ok(())
+                }
+                "connect_timeout" => {
+                    let timeout = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_connect_timeout(timeout);
+                    Ok(())
+                }
+                "write_timeout" => {
+                    let timeout = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_write_timeout(timeout);
+                    Ok(())
+                }
+                "reconnect_delay" | "connect_delay" => {
+                    let delay = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_reconnect_delay(delay);
+                    Ok(())
+                }
+                "octet_counting" => {
+                    let enable = crate::value::as_bool(v)
+                        .context(format!("invalid boolean value for key {k}"))?;
+                    config.set_octet_counting(enable);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+
+            let Some(addr) = addr else {
+                return Err(anyhow!("no target address has been set"));
+            };
+            config.set_server(addr);
+            if enable_tls && tls_client.is_none() {
+                tls_client = Some(RustlsClientConfigBuilder::default());
+            }
+            if let Some(builder) = tls_client {
+                let tls_client = builder
+                    .build()
+                    .context("failed to build tls client config")?;
+                config.set_tls_client(tls_client);
+            }
+            Ok(SyslogBackendBuilder::Tcp(config))
+        }
+        Yaml::String(s) => {
+            let addr = SocketAddr::from_str(s).map_err(|e| anyhow!("invalid SocketAddr: {e}"))?;
+            let mut config = SyslogTcpConfig::new(addr);
+            if enable_tls {
+                let tls_client = RustlsClientConfigBuilder::default()
+                    .build()
+                    .context("failed to build default tls client config")?;
+                config.set_tls_client(tls_client);
+            }
+            Ok(SyslogBackendBuilder::Tcp(config))
+        }
+        _ => Err(anyhow!("invalid yaml value for tcp syslog backend")),
+    }
+}
+
 fn as_syslog_backend_unix(value: &Yaml) -> anyhow::Result<SyslogBackendBuilder> {
     match value {
         Yaml::Hash(map) => {
@@ -123,7 +294,11 @@ fn as_syslog_backend_unix(value: &Yaml) -> anyhow::Result<SyslogBackendBuilder>
     }
 }
 
-pub fn as_syslog_builder(value: &Yaml, ident: &'static str) -> anyhow::Result<SyslogBuilder> {
+pub fn as_syslog_builder(
+    value: &Yaml,
+    ident: &'static str,
+    lookup_dir: Option<&Path>,
+) -> anyhow::Result<SyslogBuilder> {
     match value {
         Yaml::Hash(map) => {
             let mut builder = SyslogBuilder::with_ident(ident);
@@ -143,6 +318,18 @@ pub fn as_syslog_builder(value: &Yaml, ident: &'static str) -> anyhow::Result<Sy
                     builder.set_backend(backend);
                     Ok(())
                 }
+                "target_tcp" | "backend_tcp" => {
+                    let backend = as_syslog_backend_tcp(v, lookup_dir, false)
+                        .context(format!("invalid value for key {k}"))?;
+                    builder.set_backend(backend);
+                    Ok(())
+                }
+                "target_tls" | "backend_tls" => {
+                    let backend = as_syslog_backend_tcp(v, lookup_dir, true)
+                        .context(format!("invalid value for key {k}"))?;
+                    builder.set_backend(backend);
+                    Ok(())
+                }
                 "target" | "backend" => {
                     if let Yaml::Hash(map) = v {
                         crate::hash::foreach_kv(map, |k, v| {
@@ -159,6 +346,18 @@ pub fn as_syslog_builder(value: &Yaml, ident: &'static str) -> anyhow::Result<Sy
                                     builder.set_backend(backend);
                                     Ok(())
                                 }
+                                "tcp" => {
+                                    let backend = as_syslog_backend_tcp(v, lookup_dir, false)
+                                        .context(format!("invalid value for key {k}"))?;
+                                    builder.set_backend(backend);
+                                    Ok(())
+                                }
+                                "tls" => {
+                                    let backend = as_syslog_backend_tcp(v, lookup_dir, true)
+                                        .context(format!("invalid value for key {k}"))?;
+                                    builder.set_backend(backend);
+                                    Ok(())
+                                }
                                 _ => Err(anyhow!("invalid key {k}")),
                             }
                         })